@@ -0,0 +1,382 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism analyze` - corpus statistics ahead of a migration.
+//!
+//! Walks a directory and runs format detection plus a few byte-level checks
+//! on every file, without parsing or converting anything. The aggregate
+//! report (files per format and family, size distribution, encrypted and
+//! macro-bearing counts, files that could not be identified or read) helps
+//! size a migration before committing to a full conversion run.
+
+use anyhow::{Context, Result};
+use prism_core::format::{Format, FormatRegistry};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Upper bounds of the size buckets, with their labels
+const SIZE_BUCKETS: &[(u64, &str)] = &[
+    (10 * 1024, "< 10 KB"),
+    (100 * 1024, "10 KB - 100 KB"),
+    (1024 * 1024, "100 KB - 1 MB"),
+    (10 * 1024 * 1024, "1 MB - 10 MB"),
+    (100 * 1024 * 1024, "10 MB - 100 MB"),
+    (u64::MAX, ">= 100 MB"),
+];
+
+/// Number of failed paths listed in the report
+const MAX_LISTED_FAILURES: usize = 20;
+
+/// Aggregate statistics for a directory of documents
+#[derive(Debug, Default, Serialize)]
+pub struct AnalysisReport {
+    /// Directory that was analyzed
+    pub root: PathBuf,
+    /// Number of files found
+    pub files: usize,
+    /// Total size in bytes
+    pub total_bytes: u64,
+    /// Files per detected format name
+    pub formats: BTreeMap<String, FormatStats>,
+    /// Files per format family
+    pub families: BTreeMap<String, usize>,
+    /// Size distribution
+    pub sizes: SizeStats,
+    /// Files that are password-protected or encrypted
+    pub encrypted: usize,
+    /// Office files carrying VBA macros
+    pub macros: usize,
+    /// Files whose format could not be detected
+    pub unknown: usize,
+    /// Files that could not be read
+    pub unreadable: usize,
+    /// Share of files that are unknown or unreadable (0.0 - 1.0)
+    pub failure_rate: f64,
+    /// The first files that are unknown or unreadable, with the reason
+    pub failures: Vec<Failure>,
+}
+
+/// Count and volume of one format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FormatStats {
+    /// Number of files
+    pub count: usize,
+    /// Total size in bytes
+    pub bytes: u64,
+}
+
+/// File size distribution
+#[derive(Debug, Default, Serialize)]
+pub struct SizeStats {
+    /// Smallest file in bytes
+    pub min: u64,
+    /// Median file size in bytes
+    pub median: u64,
+    /// 95th percentile in bytes
+    pub p95: u64,
+    /// Largest file in bytes
+    pub max: u64,
+    /// Files per size bucket, smallest first
+    pub buckets: Vec<(String, usize)>,
+}
+
+/// A file that could not be analyzed
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    /// Path of the file
+    pub path: PathBuf,
+    /// What went wrong
+    pub reason: String,
+}
+
+/// What the byte-level checks found in one file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileTraits {
+    /// The file is encrypted
+    pub encrypted: bool,
+    /// The file contains a VBA project
+    pub macros: bool,
+}
+
+/// Analyze every file below `root`, detecting formats with `formats`
+///
+/// # Errors
+///
+/// Returns an error if `root` cannot be listed.
+pub fn analyze(root: &Path, formats: &FormatRegistry) -> Result<AnalysisReport> {
+    let mut paths = Vec::new();
+    collect_files(root, &mut paths)
+        .with_context(|| format!("Failed to read directory {}", root.display()))?;
+    paths.sort();
+
+    let mut report = AnalysisReport {
+        root: root.to_path_buf(),
+        ..AnalysisReport::default()
+    };
+    let mut sizes = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        report.files += 1;
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                report.unreadable += 1;
+                report.fail(path, e.to_string());
+                continue;
+            }
+        };
+        let size = u64::try_from(data.len()).unwrap_or(u64::MAX);
+        report.total_bytes += size;
+        sizes.push(size);
+
+        let filename = path.file_name().and_then(|name| name.to_str());
+        let Some(detection) = formats.detect(&data, filename) else {
+            report.unknown += 1;
+            report.fail(path, "unknown format".to_string());
+            continue;
+        };
+
+        let format = detection.format;
+        let stats = report.formats.entry(format.name.clone()).or_default();
+        stats.count += 1;
+        stats.bytes += size;
+        *report
+            .families
+            .entry(format.family.name().to_string())
+            .or_default() += 1;
+
+        let traits = inspect(&format, &data);
+        report.encrypted += usize::from(traits.encrypted);
+        report.macros += usize::from(traits.macros);
+    }
+
+    report.sizes = SizeStats::from_sizes(sizes);
+    report.failure_rate = ratio(report.unknown + report.unreadable, report.files);
+    Ok(report)
+}
+
+impl AnalysisReport {
+    fn fail(&mut self, path: PathBuf, reason: String) {
+        if self.failures.len() < MAX_LISTED_FAILURES {
+            self.failures.push(Failure { path, reason });
+        }
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "{}: {} files, {} bytes",
+            self.root.display(),
+            self.files,
+            self.total_bytes
+        );
+
+        let _ = writeln!(out, "Formats");
+        let mut formats: Vec<_> = self.formats.iter().collect();
+        formats.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+        for (name, stats) in formats {
+            let _ = writeln!(out, "  {name}: {} ({} bytes)", stats.count, stats.bytes);
+        }
+
+        let _ = writeln!(out, "Families");
+        for (name, count) in &self.families {
+            let _ = writeln!(out, "  {name}: {count}");
+        }
+
+        let _ = writeln!(
+            out,
+            "Sizes (min {}, median {}, p95 {}, max {} bytes)",
+            self.sizes.min, self.sizes.median, self.sizes.p95, self.sizes.max
+        );
+        for (label, count) in &self.sizes.buckets {
+            let _ = writeln!(out, "  {label}: {count}");
+        }
+
+        let _ = writeln!(out, "Encrypted: {}", self.encrypted);
+        let _ = writeln!(out, "With macros: {}", self.macros);
+        let _ = writeln!(
+            out,
+            "Unknown: {}, unreadable: {} ({:.1}% failure rate)",
+            self.unknown,
+            self.unreadable,
+            self.failure_rate * 100.0
+        );
+        for failure in &self.failures {
+            let _ = writeln!(out, "  {}: {}", failure.path.display(), failure.reason);
+        }
+
+        out
+    }
+}
+
+impl SizeStats {
+    fn from_sizes(mut sizes: Vec<u64>) -> Self {
+        sizes.sort_unstable();
+        let percentile = |p: usize| {
+            sizes
+                .get((sizes.len() * p).div_ceil(100).saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        };
+
+        let mut buckets: Vec<(String, usize)> = SIZE_BUCKETS
+            .iter()
+            .map(|(_, label)| ((*label).to_string(), 0))
+            .collect();
+        for &size in &sizes {
+            if let Some(index) = SIZE_BUCKETS.iter().position(|(limit, _)| size < *limit) {
+                buckets[index].1 += 1;
+            }
+        }
+
+        Self {
+            min: sizes.first().copied().unwrap_or_default(),
+            median: percentile(50),
+            p95: percentile(95),
+            max: sizes.last().copied().unwrap_or_default(),
+            buckets,
+        }
+    }
+}
+
+/// Cheap structural checks for encryption and macros
+///
+/// These look at raw bytes only, so a file counted here may still fail to
+/// convert for other reasons.
+#[must_use]
+pub fn inspect(format: &Format, data: &[u8]) -> FileTraits {
+    match format.extension.as_str() {
+        "pdf" => FileTraits {
+            encrypted: contains(data, b"/Encrypt"),
+            macros: false,
+        },
+        "zip" | "docx" | "xlsx" | "pptx" => FileTraits {
+            encrypted: zip_entries(data).any(|(flags, _)| flags & 1 != 0),
+            macros: zip_entries(data).any(|(_, name)| name.ends_with(b"vbaProject.bin")),
+        },
+        // OLE compound files: stream names are stored as UTF-16LE
+        _ if data.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) => FileTraits {
+            encrypted: contains(data, &utf16("EncryptedPackage")),
+            macros: contains(data, &utf16("_VBA_PROJECT")),
+        },
+        _ => FileTraits::default(),
+    }
+}
+
+/// General purpose flags and names of the ZIP local file headers in `data`
+fn zip_entries(data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    data.windows(4)
+        .enumerate()
+        .filter(|(_, window)| *window == b"PK\x03\x04")
+        .filter_map(move |(start, _)| {
+            let header = data.get(start..start + 30)?;
+            let flags = u16::from_le_bytes([header[6], header[7]]);
+            let name_len = usize::from(u16::from_le_bytes([header[26], header[27]]));
+            let name = data.get(start + 30..start + 30 + name_len)?;
+            Some((flags, name))
+        })
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Collect the files below `dir`, following neither symlinks nor
+/// special files
+pub(crate) fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            // A subdirectory that cannot be listed is skipped, not fatal
+            let _ = collect_files(&entry.path(), paths);
+        } else if file_type.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_cli::fixtures::{self, FixtureKind, FixtureSpec};
+
+    /// A ZIP local file header for `name` with the given flags
+    fn zip_header(flags: u16, name: &str) -> Vec<u8> {
+        let mut header = b"PK\x03\x04".to_vec();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.resize(26, 0);
+        header.extend_from_slice(&u16::try_from(name.len()).unwrap().to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header
+    }
+
+    #[test]
+    fn test_inspect() {
+        let docx = Format::docx();
+        let mut data = zip_header(0, "[Content_Types].xml");
+        data.extend(zip_header(0, "word/vbaProject.bin"));
+        assert_eq!(
+            inspect(&docx, &data),
+            FileTraits {
+                encrypted: false,
+                macros: true
+            }
+        );
+        assert!(inspect(&Format::zip(), &zip_header(1, "secret.txt")).encrypted);
+        assert!(inspect(&Format::pdf(), b"%PDF-1.7 trailer << /Encrypt 5 0 R >>").encrypted);
+
+        let mut ole = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        ole.extend(utf16("EncryptedPackage"));
+        assert!(inspect(&Format::doc(), &ole).encrypted);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        let docx = fixtures::generate(FixtureKind::Docx, &FixtureSpec::default()).unwrap();
+        std::fs::write(dir.path().join("a.docx"), &docx).unwrap();
+        std::fs::write(nested.join("b.docx"), &docx).unwrap();
+        std::fs::write(nested.join("c.pdf"), b"%PDF-1.4 /Encrypt").unwrap();
+        std::fs::write(dir.path().join("d.bin"), [0u8; 16]).unwrap();
+
+        let report = analyze(dir.path(), &FormatRegistry::default()).unwrap();
+        assert_eq!(report.files, 4);
+        assert_eq!(report.formats[&Format::docx().name].count, 2);
+        assert_eq!(report.families["Office"], 2);
+        assert_eq!(report.encrypted, 1);
+        assert_eq!(report.unknown, 1);
+        assert!((report.failure_rate - 0.25).abs() < f64::EPSILON);
+        assert!(report.failures[0].path.ends_with("d.bin"));
+        assert_eq!(report.sizes.min, 16);
+        assert_eq!(report.sizes.buckets[0].1, 4);
+
+        let text = report.render_text();
+        assert!(text.contains("Encrypted: 1"));
+        assert!(text.contains("(25.0% failure rate)"));
+    }
+}
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism bench` - parse and render timings over a corpus.
+//!
+//! Parses every file below a directory and renders it to HTML a number of
+//! times, keeping the median of each stage. A report saved as JSON can be
+//! passed back as the baseline of a later run, which then lists every file
+//! that got slower by more than a threshold, so performance regressions
+//! show up on the documents that matter to the user rather than on
+//! synthetic inputs only.
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use prism_core::parser::{ParseContext, ParseOptions};
+use prism_core::render::{RenderContext, RenderOptions, Renderer};
+use prism_parsers::ParserRegistry;
+use prism_render::html::HtmlRenderer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::analyze::collect_files;
+
+/// Timings smaller than this are noise and never count as a regression
+const MIN_COMPARED_MS: f64 = 1.0;
+
+/// Timings for a directory of documents
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Directory that was benchmarked
+    pub root: PathBuf,
+    /// Runs per file and stage
+    pub iterations: usize,
+    /// Files that were parsed and rendered, by path relative to the root
+    pub files: BTreeMap<String, FileTiming>,
+    /// Totals per format name
+    pub formats: BTreeMap<String, FormatTiming>,
+    /// Files that could not be benchmarked, with the reason
+    pub failures: BTreeMap<String, String>,
+}
+
+/// Median timings of one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTiming {
+    /// Detected format name
+    pub format: String,
+    /// File size in bytes
+    pub bytes: u64,
+    /// Number of pages parsed
+    pub pages: usize,
+    /// Median parse time in milliseconds
+    pub parse_ms: f64,
+    /// Median HTML render time in milliseconds
+    pub render_ms: f64,
+}
+
+/// Totals of one format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FormatTiming {
+    /// Number of files
+    pub files: usize,
+    /// Total size in bytes
+    pub bytes: u64,
+    /// Sum of the median parse times in milliseconds
+    pub parse_ms: f64,
+    /// Sum of the median render times in milliseconds
+    pub render_ms: f64,
+    /// Parse throughput in MB/s
+    pub parse_mb_per_s: f64,
+}
+
+/// A file whose timing changed between two reports
+#[derive(Debug, Serialize)]
+pub struct TimingChange {
+    /// Path relative to the root
+    pub file: String,
+    /// `parse` or `render`
+    pub stage: &'static str,
+    /// Median in the baseline, in milliseconds
+    pub baseline_ms: f64,
+    /// Median in this run, in milliseconds
+    pub current_ms: f64,
+    /// Relative change in percent; positive is slower
+    pub change_percent: f64,
+}
+
+/// A report compared against a baseline
+#[derive(Debug, Default, Serialize)]
+pub struct Comparison {
+    /// Threshold in percent above which a slowdown is a regression
+    pub threshold_percent: f64,
+    /// Slowdowns beyond the threshold, largest first
+    pub regressions: Vec<TimingChange>,
+    /// Speedups beyond the threshold, largest first
+    pub improvements: Vec<TimingChange>,
+    /// Files in only one of the reports
+    pub unmatched: Vec<String>,
+}
+
+/// Time parsing and HTML rendering of every file below `root`
+///
+/// # Errors
+///
+/// Returns an error if `root` cannot be listed.
+pub async fn bench(
+    registry: &ParserRegistry,
+    root: &Path,
+    iterations: usize,
+) -> Result<BenchReport> {
+    let mut paths = Vec::new();
+    collect_files(root, &mut paths)
+        .with_context(|| format!("Failed to read directory {}", root.display()))?;
+    paths.sort();
+
+    let iterations = iterations.max(1);
+    let mut report = BenchReport {
+        root: root.to_path_buf(),
+        iterations,
+        ..BenchReport::default()
+    };
+    for path in paths {
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        match bench_file(registry, &path, iterations).await {
+            Ok(timing) => {
+                let format = report.formats.entry(timing.format.clone()).or_default();
+                format.files += 1;
+                format.bytes += timing.bytes;
+                format.parse_ms += timing.parse_ms;
+                format.render_ms += timing.render_ms;
+                report.files.insert(name, timing);
+            }
+            Err(e) => {
+                report.failures.insert(name, format!("{e:#}"));
+            }
+        }
+    }
+    for format in report.formats.values_mut() {
+        format.parse_mb_per_s = throughput(format.bytes, format.parse_ms);
+    }
+    Ok(report)
+}
+
+/// Median parse and render times of one file
+async fn bench_file(
+    registry: &ParserRegistry,
+    path: &Path,
+    iterations: usize,
+) -> Result<FileTiming> {
+    let data = Bytes::from(std::fs::read(path)?);
+    let filename = path.file_name().and_then(|name| name.to_str());
+    let detection = registry.detect(&data, filename).context("unknown format")?;
+    let parser = registry
+        .get_parser_for_data(&detection.format, &data)
+        .with_context(|| format!("no parser for {}", detection.format.name))?;
+    let context = ParseContext {
+        format: detection.format.clone(),
+        filename: filename.map(str::to_string),
+        size: data.len(),
+        options: ParseOptions::default(),
+        progress: None,
+    };
+
+    let mut parse_times = Vec::with_capacity(iterations);
+    let mut document = None;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let parsed = parser.parse(data.clone(), context.clone()).await?;
+        parse_times.push(start.elapsed());
+        document = Some(parsed);
+    }
+    let document = document.context("no iterations")?;
+
+    let renderer = HtmlRenderer::new();
+    let mut render_times = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let context = RenderContext {
+            options: RenderOptions::default(),
+            filename: None,
+            progress: None,
+        };
+        let start = Instant::now();
+        renderer.render(&document, context).await?;
+        render_times.push(start.elapsed());
+    }
+
+    Ok(FileTiming {
+        format: detection.format.name,
+        bytes: u64::try_from(data.len()).unwrap_or(u64::MAX),
+        pages: document.pages.len(),
+        parse_ms: median_ms(parse_times),
+        render_ms: median_ms(render_times),
+    })
+}
+
+impl BenchReport {
+    /// Compare this run against `baseline`
+    ///
+    /// Only files present in both reports are compared, and only stages
+    /// taking at least a millisecond in one of them, since shorter timings
+    /// vary more than any threshold between runs.
+    #[must_use]
+    pub fn compare(&self, baseline: &BenchReport, threshold_percent: f64) -> Comparison {
+        let mut comparison = Comparison {
+            threshold_percent,
+            ..Comparison::default()
+        };
+        for (file, current) in &self.files {
+            let Some(before) = baseline.files.get(file) else {
+                comparison.unmatched.push(file.clone());
+                continue;
+            };
+            for (stage, baseline_ms, current_ms) in [
+                ("parse", before.parse_ms, current.parse_ms),
+                ("render", before.render_ms, current.render_ms),
+            ] {
+                if baseline_ms.max(current_ms) < MIN_COMPARED_MS || baseline_ms <= 0.0 {
+                    continue;
+                }
+                let change_percent = (current_ms - baseline_ms) / baseline_ms * 100.0;
+                let change = TimingChange {
+                    file: file.clone(),
+                    stage,
+                    baseline_ms,
+                    current_ms,
+                    change_percent,
+                };
+                if change_percent > threshold_percent {
+                    comparison.regressions.push(change);
+                } else if change_percent < -threshold_percent {
+                    comparison.improvements.push(change);
+                }
+            }
+        }
+        comparison.unmatched.extend(
+            baseline
+                .files
+                .keys()
+                .filter(|file| !self.files.contains_key(*file))
+                .cloned(),
+        );
+        comparison
+            .regressions
+            .sort_by(|a, b| b.change_percent.total_cmp(&a.change_percent));
+        comparison
+            .improvements
+            .sort_by(|a, b| a.change_percent.total_cmp(&b.change_percent));
+        comparison
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}: {} files, median of {} run(s)",
+            self.root.display(),
+            self.files.len(),
+            self.iterations
+        );
+
+        let _ = writeln!(out, "Formats");
+        for (name, format) in &self.formats {
+            let _ = writeln!(
+                out,
+                "  {name}: {} file(s), {} bytes, parse {:.1} ms ({:.1} MB/s), render {:.1} ms",
+                format.files,
+                format.bytes,
+                format.parse_ms,
+                format.parse_mb_per_s,
+                format.render_ms
+            );
+        }
+
+        let _ = writeln!(out, "Files");
+        for (name, file) in &self.files {
+            let _ = writeln!(
+                out,
+                "  {name}: {} page(s), parse {:.1} ms, render {:.1} ms",
+                file.pages, file.parse_ms, file.render_ms
+            );
+        }
+
+        if !self.failures.is_empty() {
+            let _ = writeln!(out, "Failed: {}", self.failures.len());
+            for (name, reason) in &self.failures {
+                let _ = writeln!(out, "  {name}: {reason}");
+            }
+        }
+        out
+    }
+}
+
+impl Comparison {
+    /// Render the comparison as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let mut section = |title: &str, changes: &[TimingChange]| {
+            let _ = writeln!(
+                out,
+                "{title} (beyond {:.0}%): {}",
+                self.threshold_percent,
+                changes.len()
+            );
+            for change in changes {
+                let _ = writeln!(
+                    out,
+                    "  {} {}: {:.1} ms -> {:.1} ms ({:+.1}%)",
+                    change.file,
+                    change.stage,
+                    change.baseline_ms,
+                    change.current_ms,
+                    change.change_percent
+                );
+            }
+        };
+        section("Regressions", &self.regressions);
+        section("Improvements", &self.improvements);
+        if !self.unmatched.is_empty() {
+            let _ = writeln!(out, "Not in both reports: {}", self.unmatched.len());
+        }
+        out
+    }
+}
+
+fn median_ms(mut times: Vec<Duration>) -> f64 {
+    times.sort_unstable();
+    times
+        .get(times.len() / 2)
+        .map_or(0.0, |time| time.as_secs_f64() * 1000.0)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn throughput(bytes: u64, ms: f64) -> f64 {
+    if ms > 0.0 {
+        bytes as f64 / 1_000_000.0 / (ms / 1000.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(parse_ms: f64, render_ms: f64) -> FileTiming {
+        FileTiming {
+            format: "PDF".to_string(),
+            bytes: 1000,
+            pages: 1,
+            parse_ms,
+            render_ms,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bench() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "Hello, world!\n").unwrap();
+        std::fs::write(dir.path().join("blob.bin"), [0u8, 159, 146, 150]).unwrap();
+
+        let registry = ParserRegistry::with_default_parsers();
+        let report = bench(&registry, dir.path(), 2).await.unwrap();
+        assert_eq!(report.iterations, 2);
+        assert_eq!(report.files["notes.txt"].pages, 1);
+        assert_eq!(report.formats.values().map(|f| f.files).sum::<usize>(), 1);
+        assert!(report.failures.contains_key("blob.bin"));
+        assert!(report.render_text().contains("notes.txt: 1 page(s)"));
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = BenchReport {
+            files: BTreeMap::from([
+                ("a.pdf".to_string(), timing(10.0, 20.0)),
+                ("b.pdf".to_string(), timing(0.2, 50.0)),
+                ("gone.pdf".to_string(), timing(5.0, 5.0)),
+            ]),
+            ..BenchReport::default()
+        };
+        let current = BenchReport {
+            files: BTreeMap::from([
+                ("a.pdf".to_string(), timing(15.0, 20.5)),
+                ("b.pdf".to_string(), timing(0.5, 30.0)),
+            ]),
+            ..BenchReport::default()
+        };
+
+        let comparison = current.compare(&baseline, 10.0);
+        assert_eq!(comparison.regressions.len(), 1);
+        assert_eq!(comparison.regressions[0].file, "a.pdf");
+        assert_eq!(comparison.regressions[0].stage, "parse");
+        assert!((comparison.regressions[0].change_percent - 50.0).abs() < 1e-9);
+        assert_eq!(comparison.improvements.len(), 1);
+        assert_eq!(comparison.improvements[0].stage, "render");
+        assert_eq!(comparison.unmatched, ["gone.pdf"]);
+        assert!(comparison
+            .render_text()
+            .contains("a.pdf parse: 10.0 ms -> 15.0 ms (+50.0%)"));
+    }
+}
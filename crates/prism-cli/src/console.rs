@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Quiet and strict modes.
+//!
+//! `--quiet` prints nothing but errors: command output, progress bars and
+//! log messages below the error level are all dropped, leaving the exit
+//! code to tell how the command went. `--strict` fails a command that
+//! logged a warning, recovered from a damaged part of a document or could
+//! not render something faithfully, with exit code 1 once it is done.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Turn quiet mode on or off
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether only errors may be printed
+#[must_use]
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Count `count` warnings that were reported other than through logging,
+/// such as recovered parse problems
+pub fn record_warnings(count: usize) {
+    WARNINGS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Number of warnings so far
+#[must_use]
+pub fn warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Logging layer counting warnings and errors, whether or not they are
+/// printed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountWarnings;
+
+impl<S: Subscriber> Layer<S> for CountWarnings {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        // More severe levels compare as smaller
+        if *event.metadata().level() <= Level::WARN {
+            record_warnings(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_count_warnings() {
+        let subscriber = tracing_subscriber::registry().with(CountWarnings);
+        let before = warnings();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("converted");
+            tracing::warn!("font substituted");
+            tracing::error!("image missing");
+        });
+        record_warnings(2);
+        // Other tests may log warnings at the same time
+        assert!(warnings() >= before + 4);
+    }
+}
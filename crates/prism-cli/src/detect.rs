@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism detect` - format detection of one file.
+//!
+//! Reports the format a file is detected as, how sure detection is and
+//! whether it went by content or by the file name alone.
+
+use prism_core::format::DetectionResult;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// Format of a file, or its absence
+#[derive(Debug, Serialize)]
+pub struct DetectReport {
+    /// File that was inspected
+    pub file: String,
+    /// Detected format, if any
+    pub format: Option<DetectedFormat>,
+}
+
+/// A detected format
+#[derive(Debug, Serialize)]
+pub struct DetectedFormat {
+    /// Format name
+    pub name: String,
+    /// MIME type
+    pub mime_type: String,
+    /// File extension
+    pub extension: String,
+    /// Confidence from 0 to 1
+    pub confidence: f64,
+    /// How the format was detected, e.g. `MagicBytes`
+    pub method: String,
+}
+
+impl DetectReport {
+    /// Build a report from the detection result for `file`
+    #[must_use]
+    pub fn new(file: String, result: Option<&DetectionResult>) -> Self {
+        Self {
+            file,
+            format: result.map(|result| DetectedFormat {
+                name: result.format.name.clone(),
+                mime_type: result.format.mime_type.clone(),
+                extension: result.format.extension.clone(),
+                confidence: result.confidence,
+                method: format!("{:?}", result.method),
+            }),
+        }
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Detecting format of: {}", self.file);
+        match &self.format {
+            Some(format) => {
+                let _ = writeln!(out, "Format: {}", format.name);
+                let _ = writeln!(out, "MIME type: {}", format.mime_type);
+                let _ = writeln!(out, "Extension: {}", format.extension);
+                let _ = writeln!(out, "Confidence: {:.2}%", format.confidence * 100.0);
+                let _ = writeln!(out, "Method: {}", format.method);
+            }
+            None => out.push_str("Could not detect format\n"),
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_parsers::ParserRegistry;
+
+    #[test]
+    fn test_detect_report() {
+        let registry = ParserRegistry::with_default_parsers();
+        let result = registry.detect(b"%PDF-1.7\n", None);
+        let report = DetectReport::new("a.pdf".to_string(), result.as_ref());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["format"]["mime_type"], "application/pdf");
+        assert_eq!(json["format"]["method"], "MagicBytes");
+        assert!(report.render_text().contains("MIME type: application/pdf"));
+
+        let report = DetectReport::new("blob".to_string(), None);
+        assert!(report.render_text().ends_with("Could not detect format\n"));
+    }
+}
@@ -0,0 +1,429 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism doctor` - installation self-test.
+//!
+//! Runs a series of environment checks (parsers, sandbox, fonts, external
+//! tools, writable directories) and a tiny end-to-end conversion per format
+//! family. The resulting report is meant to be pasted into support tickets.
+
+use prism_cli::fixtures::{self, FixtureKind, FixtureSpec};
+use prism_core::render::{RenderContext, RenderOptions, Renderer};
+use prism_parsers::ParserRegistry;
+use prism_render::html::HtmlRenderer;
+use prism_sandbox::SandboxManager;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Font file extensions counted by the font check
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "woff", "woff2"];
+
+/// Maximum directory depth searched for fonts
+const FONT_SEARCH_DEPTH: usize = 4;
+
+/// External tools Prism can bridge to when installed
+const EXTERNAL_TOOLS: &[(&str, &str)] = &[
+    ("tesseract", "OCR"),
+    ("soffice", "LibreOffice conversion fallback"),
+    ("gs", "Ghostscript PostScript/EPS rendering"),
+];
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Check passed
+    Ok,
+    /// Optional component missing or degraded
+    Warn,
+    /// Required component broken
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// A single diagnostic check
+#[derive(Debug, Serialize)]
+pub struct Check {
+    /// Section the check belongs to
+    pub section: &'static str,
+    /// What was checked
+    pub name: String,
+    /// Outcome
+    pub status: CheckStatus,
+    /// Human-readable details
+    pub detail: String,
+}
+
+/// Full diagnostic report
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    /// Prism version
+    pub version: &'static str,
+    /// Target OS
+    pub os: &'static str,
+    /// Target architecture
+    pub arch: &'static str,
+    /// Individual checks
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Whether any check failed
+    #[must_use]
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Prism doctor v{} ({}/{})",
+            self.version, self.os, self.arch
+        );
+
+        let mut section = "";
+        for check in &self.checks {
+            if check.section != section {
+                section = check.section;
+                let _ = writeln!(out, "\n{section}");
+            }
+            let _ = writeln!(
+                out,
+                "  [{:<4}] {}: {}",
+                check.status.label(),
+                check.name,
+                check.detail
+            );
+        }
+
+        let count = |status| self.checks.iter().filter(|c| c.status == status).count();
+        let _ = writeln!(
+            out,
+            "\n{} ok, {} warnings, {} failures",
+            count(CheckStatus::Ok),
+            count(CheckStatus::Warn),
+            count(CheckStatus::Fail)
+        );
+        out
+    }
+}
+
+/// Run all diagnostic checks
+pub async fn run(registry: &ParserRegistry) -> DoctorReport {
+    let mut checks = Vec::new();
+
+    check_parsers(registry, &mut checks);
+    check_sandbox(&mut checks);
+    check_fonts(&mut checks);
+    check_external_tools(&mut checks);
+    check_directories(&mut checks);
+    check_conversions(registry, &mut checks).await;
+
+    DoctorReport {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        checks,
+    }
+}
+
+fn check_parsers(registry: &ParserRegistry, checks: &mut Vec<Check>) {
+    let mut parsers: Vec<_> = registry
+        .all_parsers()
+        .iter()
+        .map(|p| format!("{} ({})", p.metadata().name, p.format().mime_type))
+        .collect();
+    parsers.sort();
+
+    checks.push(Check {
+        section: "Parsers",
+        name: "registered".to_string(),
+        status: if parsers.is_empty() {
+            CheckStatus::Fail
+        } else {
+            CheckStatus::Ok
+        },
+        detail: format!("{} parsers", parsers.len()),
+    });
+    for parser in parsers {
+        checks.push(Check {
+            section: "Parsers",
+            name: parser,
+            status: CheckStatus::Ok,
+            detail: "registered".to_string(),
+        });
+    }
+}
+
+fn check_sandbox(checks: &mut Vec<Check>) {
+    let manager = SandboxManager::default_config();
+    let config = manager.config();
+
+    checks.push(Check {
+        section: "Sandbox",
+        name: "WASM runtime".to_string(),
+        status: if manager.is_available() {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Warn
+        },
+        detail: if manager.is_available() {
+            "available".to_string()
+        } else {
+            "not compiled into this build; parsers run in-process".to_string()
+        },
+    });
+    checks.push(Check {
+        section: "Sandbox",
+        name: "limits".to_string(),
+        status: CheckStatus::Ok,
+        detail: format!(
+            "{} MB memory, {}s execution time",
+            config.max_memory / (1024 * 1024),
+            config.max_execution_time.as_secs()
+        ),
+    });
+}
+
+fn check_fonts(checks: &mut Vec<Check>) {
+    let mut total = 0;
+    for dir in font_dirs() {
+        if !dir.is_dir() {
+            continue;
+        }
+        let count = count_fonts(&dir, FONT_SEARCH_DEPTH);
+        total += count;
+        checks.push(Check {
+            section: "Fonts",
+            name: dir.display().to_string(),
+            status: CheckStatus::Ok,
+            detail: format!("{count} font files"),
+        });
+    }
+
+    if total == 0 {
+        checks.push(Check {
+            section: "Fonts",
+            name: "system fonts".to_string(),
+            status: CheckStatus::Warn,
+            detail: "no font files found; rendered output will use fallback fonts".to_string(),
+        });
+    }
+}
+
+/// Well-known system font directories for the current platform
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "windows") {
+        if let Ok(windir) = std::env::var("WINDIR") {
+            dirs.push(Path::new(&windir).join("Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+    } else {
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join(".fonts"));
+        dirs.push(home.join(".local/share/fonts"));
+    }
+    dirs
+}
+
+/// Count font files below `dir`, descending at most `depth` levels
+fn count_fonts(dir: &Path, depth: usize) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                count += count_fonts(&path, depth - 1);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| FONT_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn check_external_tools(checks: &mut Vec<Check>) {
+    for (tool, purpose) in EXTERNAL_TOOLS {
+        let (status, detail) = match find_in_path(tool) {
+            Some(path) => (CheckStatus::Ok, format!("{} ({purpose})", path.display())),
+            None => (
+                CheckStatus::Warn,
+                format!("not found ({purpose} unavailable)"),
+            ),
+        };
+        checks.push(Check {
+            section: "External tools",
+            name: (*tool).to_string(),
+            status,
+            detail,
+        });
+    }
+}
+
+/// Locate an executable on `PATH`
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        [name.to_string(), format!("{name}.exe")]
+            .into_iter()
+            .map(|candidate| dir.join(candidate))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn check_directories(checks: &mut Vec<Check>) {
+    let temp = std::env::temp_dir();
+    checks.push(directory_check("temp", &temp, CheckStatus::Fail));
+
+    if let Some(cache) = cache_dir() {
+        checks.push(directory_check("cache", &cache, CheckStatus::Warn));
+    }
+}
+
+/// Cache directory (`PRISM_CACHE_DIR`, else the XDG/home cache location)
+fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("PRISM_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CACHE_HOME") {
+        return Some(PathBuf::from(dir).join("prism"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache").join("prism"))
+}
+
+/// Check that `dir` exists (creating it if needed) and is writable
+fn directory_check(name: &str, dir: &Path, on_error: CheckStatus) -> Check {
+    let probe = dir.join(format!(".prism-doctor-{}", std::process::id()));
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b"prism"))
+        .and_then(|()| std::fs::remove_file(&probe));
+
+    let (status, detail) = match result {
+        Ok(()) => (CheckStatus::Ok, format!("{} is writable", dir.display())),
+        Err(e) => (on_error, format!("{} is not writable: {e}", dir.display())),
+    };
+    Check {
+        section: "Directories",
+        name: name.to_string(),
+        status,
+        detail,
+    }
+}
+
+/// Tiny built-in samples, one per format family
+fn samples() -> Vec<(&'static str, &'static str, Vec<u8>)> {
+    let spec = FixtureSpec::default();
+    let fixture = |kind| fixtures::generate(kind, &spec).unwrap_or_default();
+
+    vec![
+        ("Text", "sample.txt", b"Prism doctor sample\n".to_vec()),
+        (
+            "Text",
+            "sample.html",
+            b"<!DOCTYPE html><html><head><title>Doctor</title></head><body><p>Prism</p></body></html>"
+                .to_vec(),
+        ),
+        ("Document", "sample.pdf", fixture(FixtureKind::Pdf)),
+        ("Office", "sample.docx", fixture(FixtureKind::Docx)),
+        ("Office", "sample.xlsx", fixture(FixtureKind::Xlsx)),
+        ("Office", "sample.pptx", fixture(FixtureKind::Pptx)),
+        ("Image", "sample.png", fixtures::SAMPLE_PNG.to_vec()),
+        ("Email", "sample.eml", fixture(FixtureKind::Eml)),
+        (
+            "Contact",
+            "sample.vcf",
+            b"BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Prism Doctor\r\nEND:VCARD\r\n".to_vec(),
+        ),
+    ]
+}
+
+async fn check_conversions(registry: &ParserRegistry, checks: &mut Vec<Check>) {
+    let renderer = HtmlRenderer::new();
+
+    for (family, filename, data) in samples() {
+        let result = match crate::parse_bytes(registry, data, Some(filename)).await {
+            Ok(document) => {
+                let context = RenderContext {
+                    options: RenderOptions::default(),
+                    filename: None,
+                    progress: None,
+                };
+                renderer
+                    .render(&document, context)
+                    .await
+                    .map(|html| (document.page_count(), html.len()))
+                    .map_err(anyhow::Error::from)
+            }
+            Err(e) => Err(e),
+        };
+
+        let (status, detail) = match result {
+            Ok((pages, bytes)) => (
+                CheckStatus::Ok,
+                format!("{pages} page(s) -> {bytes} bytes of HTML"),
+            ),
+            Err(e) => (CheckStatus::Fail, e.to_string()),
+        };
+        checks.push(Check {
+            section: "Conversions",
+            name: format!("{family}: {filename}"),
+            status,
+            detail,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_check_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = directory_check("temp", dir.path(), CheckStatus::Fail);
+        assert_eq!(check.status, CheckStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_conversions_pass() {
+        let registry = ParserRegistry::with_default_parsers();
+        let mut checks = Vec::new();
+        check_conversions(&registry, &mut checks).await;
+
+        for check in &checks {
+            assert_eq!(
+                check.status,
+                CheckStatus::Ok,
+                "{}: {}",
+                check.name,
+                check.detail
+            );
+        }
+    }
+}
@@ -0,0 +1,714 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Synthetic test document generation.
+//!
+//! Builds small but structurally valid DOCX, XLSX, PPTX, PDF and EML files
+//! with controlled features, so tests and fuzzing corpora do not have to
+//! rely on proprietary sample documents. Every generator is deterministic:
+//! the same [`FixtureSpec`] always yields the same bytes.
+//!
+//! Not every feature applies to every format:
+//!
+//! | Feature        | DOCX | XLSX | PPTX | PDF | EML |
+//! |----------------|------|------|------|-----|-----|
+//! | pages          | page breaks | sheets | slides | pages | - |
+//! | merged cells   | yes  | yes  | yes  | -   | -   |
+//! | images         | yes  | -    | yes  | yes | attachments |
+//! | unicode text   | yes  | yes  | yes  | title | subject + body |
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use clap::ValueEnum;
+use prism_render::zip_writer::DeterministicZipWriter;
+use std::fmt::Write as _;
+
+/// Unicode sample mixing accented Latin, CJK, RTL script and an emoji
+pub const UNICODE_SAMPLE: &str = "Ünïcödé — 日本語のテキスト — العربية — 🚀";
+
+/// A 1x1 white RGB PNG
+pub const SAMPLE_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xde, 0x00, 0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xff, 0xff, 0x3f,
+    0x00, 0x05, 0xfe, 0x02, 0xfe, 0x0d, 0xef, 0x46, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+    0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// English Metric Units per point
+const EMU_PER_PT: u64 = 12_700;
+
+/// Relationship type URIs
+const REL_OFFICE_DOCUMENT: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+const REL_IMAGE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
+const REL_WORKSHEET: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet";
+const REL_SLIDE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide";
+
+/// Kind of fixture to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FixtureKind {
+    /// DOCX document
+    Docx,
+    /// XLSX workbook
+    Xlsx,
+    /// PPTX presentation
+    Pptx,
+    /// PDF document
+    Pdf,
+    /// RFC 822 email
+    Eml,
+}
+
+impl FixtureKind {
+    /// File extension for this kind
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            FixtureKind::Docx => "docx",
+            FixtureKind::Xlsx => "xlsx",
+            FixtureKind::Pptx => "pptx",
+            FixtureKind::Pdf => "pdf",
+            FixtureKind::Eml => "eml",
+        }
+    }
+}
+
+/// Features to include in a generated fixture
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    /// Number of pages (sheets for XLSX, slides for PPTX)
+    pub pages: usize,
+    /// Include a table with merged cells
+    pub merged_cells: bool,
+    /// Number of embedded images
+    pub images: usize,
+    /// Include non-ASCII text
+    pub unicode: bool,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            pages: 1,
+            merged_cells: false,
+            images: 0,
+            unicode: false,
+        }
+    }
+}
+
+/// Generate a fixture
+///
+/// # Errors
+///
+/// Returns an error if the container cannot be assembled.
+pub fn generate(kind: FixtureKind, spec: &FixtureSpec) -> Result<Vec<u8>> {
+    match kind {
+        FixtureKind::Docx => docx(spec),
+        FixtureKind::Xlsx => xlsx(spec),
+        FixtureKind::Pptx => pptx(spec),
+        FixtureKind::Pdf => Ok(pdf(spec)),
+        FixtureKind::Eml => Ok(eml(spec)),
+    }
+}
+
+/// Escape text for XML content and attribute values
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write a list of `(name, bytes)` entries to a ZIP archive.
+///
+/// Entries are sorted by name after `[Content_Types].xml`, so the output is
+/// byte-for-byte reproducible.
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut zip = DeterministicZipWriter::new().with_leading(["[Content_Types].xml"]);
+    for (name, data) in entries {
+        zip.add(name.as_str(), data.as_slice());
+    }
+    Ok(zip.finish()?)
+}
+
+/// Build a relationships part from `(id, type, target)` triples
+fn relationships(rels: &[(String, &str, String)]) -> Vec<u8> {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    );
+    for (id, rel_type, target) in rels {
+        let _ = write!(
+            xml,
+            r#"<Relationship Id="{id}" Type="{rel_type}" Target="{target}"/>"#
+        );
+    }
+    xml.push_str("</Relationships>");
+    xml.into_bytes()
+}
+
+/// Build `[Content_Types].xml` from `(part name, content type)` overrides
+fn content_types(overrides: &[(String, &str)]) -> Vec<u8> {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Default Extension="png" ContentType="image/png"/>"#,
+    );
+    for (part, content_type) in overrides {
+        let _ = write!(
+            xml,
+            r#"<Override PartName="{part}" ContentType="{content_type}"/>"#
+        );
+    }
+    xml.push_str("</Types>");
+    xml.into_bytes()
+}
+
+/// Sample paragraph text for page `page` (1-indexed)
+fn page_text(page: usize) -> String {
+    format!("Page {page}: The quick brown fox jumps over the lazy dog.")
+}
+
+fn docx(spec: &FixtureSpec) -> Result<Vec<u8>> {
+    let pages = spec.pages.max(1);
+    let mut body = String::new();
+
+    for page in 1..=pages {
+        let _ = write!(
+            body,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Heading {page}</w:t></w:r></w:p><w:p><w:r><w:t>{}</w:t></w:r></w:p>"#,
+            page_text(page)
+        );
+
+        if page == 1 {
+            if spec.unicode {
+                let _ = write!(
+                    body,
+                    r"<w:p><w:r><w:t>{}</w:t></w:r></w:p>",
+                    xml_escape(UNICODE_SAMPLE)
+                );
+            }
+            if spec.merged_cells {
+                body.push_str(&docx_merged_table());
+            }
+            for image in 1..=spec.images {
+                body.push_str(&docx_image(image));
+            }
+        }
+
+        if page < pages {
+            body.push_str(r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p>"#);
+        }
+    }
+
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><w:body>{body}<w:sectPr><w:pgSz w:w="12240" w:h="15840"/></w:sectPr></w:body></w:document>"#
+    );
+
+    let image_rels: Vec<_> = (1..=spec.images)
+        .map(|i| {
+            (
+                format!("rIdImg{i}"),
+                REL_IMAGE,
+                format!("media/image{i}.png"),
+            )
+        })
+        .collect();
+
+    let mut entries = vec![
+        (
+            "[Content_Types].xml".to_string(),
+            content_types(&[(
+                "/word/document.xml".to_string(),
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+            )]),
+        ),
+        (
+            "_rels/.rels".to_string(),
+            relationships(&[(
+                "rId1".to_string(),
+                REL_OFFICE_DOCUMENT,
+                "word/document.xml".to_string(),
+            )]),
+        ),
+        ("word/document.xml".to_string(), document.into_bytes()),
+        (
+            "word/_rels/document.xml.rels".to_string(),
+            relationships(&image_rels),
+        ),
+    ];
+    for i in 1..=spec.images {
+        entries.push((format!("word/media/image{i}.png"), SAMPLE_PNG.to_vec()));
+    }
+
+    write_zip(&entries)
+}
+
+/// 3x3 table whose first row spans two columns
+fn docx_merged_table() -> String {
+    let cell = |text: &str, span: Option<usize>| {
+        let grid_span = span
+            .map(|s| format!(r#"<w:tcPr><w:gridSpan w:val="{s}"/></w:tcPr>"#))
+            .unwrap_or_default();
+        format!(r"<w:tc>{grid_span}<w:p><w:r><w:t>{text}</w:t></w:r></w:p></w:tc>")
+    };
+
+    let mut table = String::from(
+        r#"<w:tbl><w:tblGrid><w:gridCol w:w="2000"/><w:gridCol w:w="2000"/><w:gridCol w:w="2000"/></w:tblGrid>"#,
+    );
+    let _ = write!(
+        table,
+        "<w:tr>{}{}</w:tr>",
+        cell("Merged", Some(2)),
+        cell("C1", None)
+    );
+    for row in 2..=3 {
+        let _ = write!(
+            table,
+            "<w:tr>{}{}{}</w:tr>",
+            cell(&format!("A{row}"), None),
+            cell(&format!("B{row}"), None),
+            cell(&format!("C{row}"), None)
+        );
+    }
+    table.push_str("</w:tbl>");
+    table
+}
+
+/// Inline picture paragraph referencing `media/image{index}.png`
+fn docx_image(index: usize) -> String {
+    let size = 72 * EMU_PER_PT;
+    format!(
+        r#"<w:p><w:r><w:drawing><wp:inline><wp:extent cx="{size}" cy="{size}"/><wp:docPr id="{index}" name="Image {index}"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{index}" name="image{index}.png"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="rIdImg{index}"/></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{size}" cy="{size}"/></a:xfrm><a:prstGeom prst="rect"/></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#
+    )
+}
+
+fn xlsx(spec: &FixtureSpec) -> Result<Vec<u8>> {
+    let sheets = spec.pages.max(1);
+
+    let mut workbook_sheets = String::new();
+    let mut workbook_rels = Vec::new();
+    let mut overrides = vec![(
+        "/xl/workbook.xml".to_string(),
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml",
+    )];
+    let mut entries = Vec::new();
+
+    for sheet in 1..=sheets {
+        let _ = write!(
+            workbook_sheets,
+            r#"<sheet name="Sheet{sheet}" sheetId="{sheet}" r:id="rId{sheet}"/>"#
+        );
+        workbook_rels.push((
+            format!("rId{sheet}"),
+            REL_WORKSHEET,
+            format!("worksheets/sheet{sheet}.xml"),
+        ));
+        overrides.push((
+            format!("/xl/worksheets/sheet{sheet}.xml"),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml",
+        ));
+        entries.push((
+            format!("xl/worksheets/sheet{sheet}.xml"),
+            xlsx_sheet(sheet, spec).into_bytes(),
+        ));
+    }
+
+    let workbook = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{workbook_sheets}</sheets></workbook>"#
+    );
+
+    let mut all = vec![
+        ("[Content_Types].xml".to_string(), content_types(&overrides)),
+        (
+            "_rels/.rels".to_string(),
+            relationships(&[(
+                "rId1".to_string(),
+                REL_OFFICE_DOCUMENT,
+                "xl/workbook.xml".to_string(),
+            )]),
+        ),
+        ("xl/workbook.xml".to_string(), workbook.into_bytes()),
+        (
+            "xl/_rels/workbook.xml.rels".to_string(),
+            relationships(&workbook_rels),
+        ),
+    ];
+    all.extend(entries);
+
+    write_zip(&all)
+}
+
+/// A small grid: a header row, three numeric rows and optional extras
+fn xlsx_sheet(sheet: usize, spec: &FixtureSpec) -> String {
+    let inline = |cell: &str, text: &str| {
+        format!(
+            r#"<c r="{cell}" t="inlineStr"><is><t>{}</t></is></c>"#,
+            xml_escape(text)
+        )
+    };
+
+    let mut rows = format!(
+        r#"<row r="1">{}{}{}</row>"#,
+        inline("A1", &format!("Sheet {sheet}")),
+        inline("B1", "Value"),
+        inline("C1", "Total")
+    );
+    for row in 2..=4 {
+        let _ = write!(
+            rows,
+            r#"<row r="{row}">{}<c r="B{row}"><v>{}</v></c><c r="C{row}"><v>{}</v></c></row>"#,
+            inline(&format!("A{row}"), &format!("Item {}", row - 1)),
+            row * 10,
+            row * 100
+        );
+    }
+    if spec.unicode {
+        let _ = write!(rows, r#"<row r="5">{}</row>"#, inline("A5", UNICODE_SAMPLE));
+    }
+
+    let merges = if spec.merged_cells {
+        r#"<mergeCells count="1"><mergeCell ref="A1:B1"/></mergeCells>"#
+    } else {
+        ""
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{rows}</sheetData>{merges}</worksheet>"#
+    )
+}
+
+fn pptx(spec: &FixtureSpec) -> Result<Vec<u8>> {
+    let slides = spec.pages.max(1);
+
+    let mut slide_ids = String::new();
+    let mut presentation_rels = Vec::new();
+    let mut overrides = vec![(
+        "/ppt/presentation.xml".to_string(),
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml",
+    )];
+    let mut entries = Vec::new();
+
+    for slide in 1..=slides {
+        let _ = write!(
+            slide_ids,
+            r#"<p:sldId id="{}" r:id="rId{slide}"/>"#,
+            255 + slide
+        );
+        presentation_rels.push((
+            format!("rId{slide}"),
+            REL_SLIDE,
+            format!("slides/slide{slide}.xml"),
+        ));
+        overrides.push((
+            format!("/ppt/slides/slide{slide}.xml"),
+            "application/vnd.openxmlformats-officedocument.presentationml.slide+xml",
+        ));
+
+        let images = if slide == 1 { spec.images } else { 0 };
+        entries.push((
+            format!("ppt/slides/slide{slide}.xml"),
+            pptx_slide(slide, images, spec).into_bytes(),
+        ));
+        let image_rels: Vec<_> = (1..=images)
+            .map(|i| {
+                (
+                    format!("rIdImg{i}"),
+                    REL_IMAGE,
+                    format!("../media/image{i}.png"),
+                )
+            })
+            .collect();
+        entries.push((
+            format!("ppt/slides/_rels/slide{slide}.xml.rels"),
+            relationships(&image_rels),
+        ));
+    }
+    for i in 1..=spec.images {
+        entries.push((format!("ppt/media/image{i}.png"), SAMPLE_PNG.to_vec()));
+    }
+
+    let presentation = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:presentation xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"><p:sldIdLst>{slide_ids}</p:sldIdLst><p:sldSz cx="12192000" cy="6858000"/><p:notesSz cx="6858000" cy="9144000"/></p:presentation>"#
+    );
+
+    let mut all = vec![
+        ("[Content_Types].xml".to_string(), content_types(&overrides)),
+        (
+            "_rels/.rels".to_string(),
+            relationships(&[(
+                "rId1".to_string(),
+                REL_OFFICE_DOCUMENT,
+                "ppt/presentation.xml".to_string(),
+            )]),
+        ),
+        (
+            "ppt/presentation.xml".to_string(),
+            presentation.into_bytes(),
+        ),
+        (
+            "ppt/_rels/presentation.xml.rels".to_string(),
+            relationships(&presentation_rels),
+        ),
+    ];
+    all.extend(entries);
+
+    write_zip(&all)
+}
+
+/// Slide with a title text box plus optional table, pictures and unicode text
+fn pptx_slide(slide: usize, images: usize, spec: &FixtureSpec) -> String {
+    let emu = |pt: u64| pt * EMU_PER_PT;
+    let text_box = |id: usize, y: u64, text: &str| {
+        format!(
+            r#"<p:sp><p:nvSpPr><p:cNvPr id="{id}" name="TextBox {id}"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr><p:spPr><a:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></a:xfrm><a:prstGeom prst="rect"/></p:spPr><p:txBody><a:bodyPr/><a:p><a:r><a:rPr lang="en-US" sz="2400"/><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp>"#,
+            emu(36),
+            emu(y),
+            emu(600),
+            emu(40),
+            xml_escape(text)
+        )
+    };
+
+    let mut shapes = text_box(2, 36, &format!("Slide {slide}"));
+    shapes.push_str(&text_box(3, 90, &page_text(slide)));
+    if spec.unicode && slide == 1 {
+        shapes.push_str(&text_box(4, 140, UNICODE_SAMPLE));
+    }
+
+    if spec.merged_cells && slide == 1 {
+        let cell = |text: &str, attrs: &str| {
+            format!(
+                r"<a:tc{attrs}><a:txBody><a:bodyPr/><a:p><a:r><a:t>{text}</a:t></a:r></a:p></a:txBody><a:tcPr/></a:tc>"
+            )
+        };
+        let _ = write!(
+            shapes,
+            r#"<p:graphicFrame><p:nvGraphicFramePr><p:cNvPr id="5" name="Table 5"/><p:cNvGraphicFramePr/><p:nvPr/></p:nvGraphicFramePr><p:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></p:xfrm><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/table"><a:tbl><a:tblGrid><a:gridCol w="{}"/><a:gridCol w="{}"/></a:tblGrid><a:tr h="{}">{}{}</a:tr><a:tr h="{}">{}{}</a:tr></a:tbl></a:graphicData></a:graphic></p:graphicFrame>"#,
+            emu(36),
+            emu(200),
+            emu(400),
+            emu(80),
+            emu(200),
+            emu(200),
+            emu(40),
+            cell("Merged", r#" gridSpan="2""#),
+            cell("", r#" hMerge="1""#),
+            emu(40),
+            cell("A2", ""),
+            cell("B2", "")
+        );
+    }
+
+    for i in 1..=images {
+        let x = 36 + 120 * (i as u64 - 1);
+        let _ = write!(
+            shapes,
+            r#"<p:pic><p:nvPicPr><p:cNvPr id="{}" name="Picture {i}"/><p:cNvPicPr/><p:nvPr/></p:nvPicPr><p:blipFill><a:blip r:embed="rIdImg{i}"/><a:stretch><a:fillRect/></a:stretch></p:blipFill><p:spPr><a:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></a:xfrm><a:prstGeom prst="rect"/></p:spPr></p:pic>"#,
+            10 + i,
+            emu(x),
+            emu(300),
+            emu(100),
+            emu(100)
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sld xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"><p:cSld><p:spTree><p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/>{shapes}</p:spTree></p:cSld></p:sld>"#
+    )
+}
+
+/// Build a PDF with one line of text per page and optional images.
+///
+/// Images are 1x1 uncompressed RGB image objects. Unicode text goes into the
+/// document title (as UTF-16BE), since the standard 14 fonts cannot show it.
+fn pdf(spec: &FixtureSpec) -> Vec<u8> {
+    let pages = spec.pages.max(1);
+
+    // Object layout: 1 catalog, 2 pages, 3 font, 4 info, then per page
+    // (page, content), then images
+    let first_page = 5;
+    let first_image = first_page + 2 * pages;
+
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..pages)
+        .map(|i| format!("{} 0 R", first_page + 2 * i))
+        .collect();
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {pages} >>",
+            kids.join(" ")
+        )
+        .into_bytes(),
+    );
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec());
+
+    let title = if spec.unicode {
+        let mut hex = String::from("FEFF");
+        for unit in UNICODE_SAMPLE.encode_utf16() {
+            let _ = write!(hex, "{unit:04X}");
+        }
+        format!("<{hex}>")
+    } else {
+        "(Prism fixture)".to_string()
+    };
+    objects.push(format!("<< /Title {title} /Producer (Prism gen-fixture) >>").into_bytes());
+
+    let xobjects: String = (0..spec.images)
+        .map(|i| format!("/Im{} {} 0 R", i + 1, first_image + i))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for page in 1..=pages {
+        let mut content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", page_text(page));
+        if page == 1 {
+            for i in 0..spec.images {
+                let _ = write!(
+                    content,
+                    "\nq 72 0 0 72 {} 560 cm /Im{} Do Q",
+                    72 + 90 * i,
+                    i + 1
+                );
+            }
+        }
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {} 0 R /Resources << /Font << /F1 3 0 R >> /XObject << {xobjects} >> >> >>",
+                first_page + 2 * (page - 1) + 1
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            )
+            .into_bytes(),
+        );
+    }
+
+    for _ in 0..spec.images {
+        let mut image = b"<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Length 3 >>\nstream\n".to_vec();
+        image.extend_from_slice(&[0xff, 0xff, 0xff]);
+        image.extend_from_slice(b"\nendstream");
+        objects.push(image);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(body);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 4 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+/// Build an email; images become base64 PNG attachments.
+fn eml(spec: &FixtureSpec) -> Vec<u8> {
+    const BOUNDARY: &str = "prism-fixture-boundary";
+
+    let subject = if spec.unicode {
+        // RFC 2047 encoded word
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(UNICODE_SAMPLE.as_bytes()))
+    } else {
+        "Prism fixture".to_string()
+    };
+
+    let mut body = page_text(1);
+    if spec.unicode {
+        let _ = write!(body, "\r\n{UNICODE_SAMPLE}");
+    }
+
+    let mut eml = format!(
+        "From: Prism Fixtures <fixtures@example.com>\r\nTo: Test Recipient <test@example.com>\r\nSubject: {subject}\r\nDate: Thu, 01 Jan 2026 00:00:00 +0000\r\nMessage-ID: <fixture@example.com>\r\nMIME-Version: 1.0\r\n"
+    );
+
+    if spec.images == 0 {
+        let _ = write!(
+            eml,
+            "Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{body}\r\n"
+        );
+        return eml.into_bytes();
+    }
+
+    let _ = write!(
+        eml,
+        "Content-Type: multipart/mixed; boundary=\"{BOUNDARY}\"\r\n\r\n--{BOUNDARY}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{body}\r\n"
+    );
+    for i in 1..=spec.images {
+        let _ = write!(
+            eml,
+            "--{BOUNDARY}\r\nContent-Type: image/png; name=\"image{i}.png\"\r\nContent-Disposition: attachment; filename=\"image{i}.png\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            STANDARD.encode(SAMPLE_PNG)
+        );
+    }
+    let _ = write!(eml, "--{BOUNDARY}--\r\n");
+    eml.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let spec = FixtureSpec {
+            pages: 2,
+            merged_cells: true,
+            images: 1,
+            unicode: true,
+        };
+        for kind in [
+            FixtureKind::Docx,
+            FixtureKind::Xlsx,
+            FixtureKind::Pptx,
+            FixtureKind::Pdf,
+            FixtureKind::Eml,
+        ] {
+            assert_eq!(
+                generate(kind, &spec).unwrap(),
+                generate(kind, &spec).unwrap(),
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pdf_xref_offsets() {
+        let pdf = pdf(&FixtureSpec {
+            pages: 3,
+            ..FixtureSpec::default()
+        });
+        let text = String::from_utf8_lossy(&pdf);
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|line| line.parse().ok())
+            .unwrap();
+        assert!(text[startxref..].starts_with("xref"));
+        assert!(text.contains("/Count 3"));
+    }
+}
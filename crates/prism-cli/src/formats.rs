@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism formats` - support matrix of this build.
+//!
+//! Lists every registered parser with its format, features, sandbox
+//! requirement and version, and the output formats the CLI can write.
+
+use clap::ValueEnum;
+use prism_parsers::{ParserRegistry, ParserSupport};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+use crate::output::OutputFormat;
+
+/// Inputs and outputs supported by this build
+#[derive(Debug, Serialize)]
+pub struct FormatsReport {
+    /// Registered parsers, ordered by MIME type
+    pub parsers: Vec<ParserSupport>,
+    /// Names of the output formats, as accepted by `--format`
+    pub outputs: Vec<String>,
+}
+
+impl FormatsReport {
+    /// Collect the support matrix of `registry`
+    #[must_use]
+    pub fn new(registry: &ParserRegistry) -> Self {
+        Self {
+            parsers: registry.support_matrix(),
+            outputs: OutputFormat::value_variants()
+                .iter()
+                .filter_map(|format| format.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect(),
+        }
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Input formats ({}):", self.parsers.len());
+        for support in &self.parsers {
+            let metadata = &support.metadata;
+            let _ = writeln!(
+                out,
+                "  {:<8} {:<60} {} v{}{}",
+                support.format.extension,
+                support.format.mime_type,
+                metadata.name,
+                metadata.version,
+                if metadata.requires_sandbox {
+                    " (sandboxed)"
+                } else {
+                    ""
+                }
+            );
+            if !metadata.features.is_empty() {
+                let features: Vec<String> = metadata
+                    .features
+                    .iter()
+                    .map(|feature| format!("{feature:?}"))
+                    .collect();
+                let _ = writeln!(out, "           features: {}", features.join(", "));
+            }
+        }
+        let _ = writeln!(out, "Output formats: {}", self.outputs.join(", "));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_report() {
+        let registry = ParserRegistry::with_default_parsers();
+        let report = FormatsReport::new(&registry);
+        assert_eq!(report.parsers.len(), registry.count());
+        assert!(report.outputs.contains(&"html".to_string()));
+
+        let text = report.render_text();
+        assert!(text.contains("application/pdf"));
+        assert!(text.contains("Output formats: html, text"));
+    }
+}
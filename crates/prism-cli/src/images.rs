@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Image extraction.
+//!
+//! Writes the embedded images of a parsed document to a directory together
+//! with a [`MANIFEST_FILE`] that maps every image resource to its file and
+//! to the pages and bounds it is placed at.
+//!
+//! Images are written in the encoding the document stores them in, so a
+//! JPEG stays a JPEG and nothing is re-encoded. Resources with identical
+//! data share one file, named after the first resource that uses it.
+//! Images that are only linked by URL appear in the manifest without a file.
+
+use anyhow::{Context, Result};
+use prism_core::document::{ContentBlock, Document, ImageResource, Rect};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Name of the manifest written next to the images
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The images written for a document
+#[derive(Debug, Default, Serialize)]
+pub struct ImageManifest {
+    /// Distinct image files, in the order they were written
+    pub files: Vec<ImageFile>,
+    /// Every image resource of the document by its ID
+    pub resources: BTreeMap<String, ImageEntry>,
+}
+
+/// One image file
+#[derive(Debug, Serialize)]
+pub struct ImageFile {
+    /// File name within the output directory
+    pub file: String,
+    /// SHA-256 of the image data
+    pub sha256: String,
+    /// MIME type of the image
+    pub mime_type: String,
+    /// Width in pixels, 0 when unknown
+    pub width: u32,
+    /// Height in pixels, 0 when unknown
+    pub height: u32,
+    /// Size in bytes
+    pub size: usize,
+}
+
+/// An image resource and where the document shows it
+#[derive(Debug, Default, Serialize)]
+pub struct ImageEntry {
+    /// File holding the image data, if it is embedded
+    pub file: Option<String>,
+    /// External URL, if the image is linked
+    pub url: Option<String>,
+    /// Places the image is shown at, in page order
+    pub placements: Vec<ImagePlacement>,
+}
+
+/// One use of an image on a page
+#[derive(Debug, Clone, Serialize)]
+pub struct ImagePlacement {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Bounds of the image on the page, in points
+    pub bounds: Rect,
+}
+
+/// Write every image of `document` to `dir`, followed by the manifest
+///
+/// # Errors
+///
+/// Returns an error if the directory or a file cannot be written.
+pub fn extract_images(document: &Document, dir: &Path) -> Result<ImageManifest> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut placements = placements(document);
+    let mut manifest = ImageManifest::default();
+    let mut by_hash: HashMap<String, String> = HashMap::new();
+    let mut used = HashSet::new();
+    for image in &document.resources.images {
+        let mut entry = ImageEntry {
+            url: image.url.clone(),
+            placements: placements.remove(image.id.as_str()).unwrap_or_default(),
+            ..ImageEntry::default()
+        };
+        if let Some(data) = image.data.as_deref().filter(|data| !data.is_empty()) {
+            let sha256 = format!("{:x}", Sha256::digest(data));
+            let file = if let Some(file) = by_hash.get(&sha256) {
+                file.clone()
+            } else {
+                let file = file_name(image, data, &mut used);
+                let path = dir.join(&file);
+                std::fs::write(&path, data)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                manifest.files.push(ImageFile {
+                    file: file.clone(),
+                    sha256: sha256.clone(),
+                    mime_type: image.mime_type.clone(),
+                    width: image.width,
+                    height: image.height,
+                    size: data.len(),
+                });
+                by_hash.insert(sha256, file.clone());
+                file
+            };
+            entry.file = Some(file);
+        }
+        manifest.resources.insert(image.id.clone(), entry);
+    }
+
+    let path = dir.join(MANIFEST_FILE);
+    let json = serde_json::to_string_pretty(&manifest)? + "\n";
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(manifest)
+}
+
+/// Placements of every image resource, including images nested in tables,
+/// lists and containers
+fn placements(document: &Document) -> HashMap<&str, Vec<ImagePlacement>> {
+    fn collect<'a>(
+        blocks: &'a [ContentBlock],
+        page: u32,
+        placements: &mut HashMap<&'a str, Vec<ImagePlacement>>,
+    ) {
+        for block in blocks {
+            match block {
+                ContentBlock::Image(image) => placements
+                    .entry(image.resource_id.as_str())
+                    .or_default()
+                    .push(ImagePlacement {
+                        page,
+                        bounds: image.bounds,
+                    }),
+                ContentBlock::Table(table) => {
+                    for cell in table.rows.iter().flat_map(|row| &row.cells) {
+                        collect(&cell.content, page, placements);
+                    }
+                }
+                ContentBlock::List(list) => {
+                    for item in &list.items {
+                        collect(&item.content, page, placements);
+                    }
+                }
+                ContentBlock::Container(container) => {
+                    collect(&container.children, page, placements);
+                }
+                ContentBlock::Text(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+            }
+        }
+    }
+
+    let mut placements = HashMap::new();
+    for page in &document.pages {
+        collect(&page.content, page.number, &mut placements);
+    }
+    placements
+}
+
+/// A file name for an image not written yet: its resource ID and the
+/// extension of its format
+fn file_name(image: &ImageResource, data: &[u8], used: &mut HashSet<String>) -> String {
+    let stem = sanitize(&image.id);
+    let ext = extension(&image.mime_type)
+        .or_else(|| sniff_extension(data))
+        .unwrap_or("bin");
+    let mut name = format!("{stem}.{ext}");
+    let mut counter = 1;
+    while !used.insert(name.clone()) {
+        counter += 1;
+        name = format!("{stem}-{counter}.{ext}");
+    }
+    name
+}
+
+/// File extension for an image MIME type
+fn extension(mime_type: &str) -> Option<&'static str> {
+    match mime_type.to_ascii_lowercase().as_str() {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/bmp" | "image/x-bmp" => Some("bmp"),
+        "image/tiff" => Some("tif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "image/x-emf" | "image/emf" => Some("emf"),
+        "image/x-wmf" | "image/wmf" => Some("wmf"),
+        "image/x-icon" | "image/vnd.microsoft.icon" => Some("ico"),
+        "image/jp2" => Some("jp2"),
+        _ => None,
+    }
+}
+
+/// File extension from the signature of the data, for resources with a
+/// generic MIME type
+fn sniff_extension(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "png"),
+        (b"\xff\xd8\xff", "jpg"),
+        (b"GIF8", "gif"),
+        (b"BM", "bmp"),
+        (b"II*\0", "tif"),
+        (b"MM\0*", "tif"),
+        (b"\xd7\xcd\xc6\x9a", "wmf"),
+    ];
+    if let Some((_, ext)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return Some(ext);
+    }
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        return Some("webp");
+    }
+    if data.get(40..44) == Some(b" EMF") {
+        return Some("emf");
+    }
+    let head = String::from_utf8_lossy(&data[..data.len().min(256)]);
+    head.contains("<svg").then_some("svg")
+}
+
+/// Keep a resource ID usable as a file name on every platform
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "image".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_PNG;
+    use prism_core::document::{
+        Dimensions, ImageBlock, Page, ShapeStyle, TableBlock, TableCell, TableRow,
+    };
+
+    fn image(id: &str, mime_type: &str, data: Option<&[u8]>) -> ImageResource {
+        ImageResource {
+            id: id.to_string(),
+            mime_type: mime_type.to_string(),
+            data: data.map(<[u8]>::to_vec),
+            url: None,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    fn block(resource_id: &str, x: f64) -> ContentBlock {
+        ContentBlock::Image(ImageBlock {
+            bounds: Rect {
+                x,
+                y: 10.0,
+                width: 20.0,
+                height: 20.0,
+            },
+            resource_id: resource_id.to_string(),
+            alt_text: None,
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        })
+    }
+
+    #[test]
+    fn test_extract_images() {
+        let mut document = Document::new();
+        document.resources.images = vec![
+            image("rId1", "image/png", Some(SAMPLE_PNG)),
+            image("rId2", "image/png", Some(SAMPLE_PNG)),
+            image(
+                "media/photo",
+                "application/octet-stream",
+                Some(b"\xff\xd8\xff\xe0"),
+            ),
+            ImageResource {
+                url: Some("https://example.com/logo.png".to_string()),
+                ..image("logo", "image/png", None)
+            },
+        ];
+        let mut first = Page::new(1, Dimensions::LETTER);
+        first.content = vec![block("rId1", 0.0), block("rId2", 50.0)];
+        let mut second = Page::new(2, Dimensions::LETTER);
+        second.content = vec![ContentBlock::Table(TableBlock {
+            bounds: Rect::default(),
+            rows: vec![TableRow {
+                cells: vec![TableCell {
+                    content: vec![block("rId1", 5.0)],
+                    col_span: 1,
+                    row_span: 1,
+                    background_color: None,
+                }],
+                height: None,
+            }],
+            column_count: 1,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+        })];
+        document.pages = vec![first, second];
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = extract_images(&document, dir.path()).unwrap();
+
+        let files: Vec<_> = manifest.files.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, ["rId1.png", "media_photo.jpg"]);
+        assert_eq!(
+            std::fs::read(dir.path().join("rId1.png")).unwrap(),
+            SAMPLE_PNG
+        );
+        assert_eq!(manifest.resources["rId2"].file.as_deref(), Some("rId1.png"));
+        let pages: Vec<_> = manifest.resources["rId1"]
+            .placements
+            .iter()
+            .map(|placement| placement.page)
+            .collect();
+        assert_eq!(pages, [1, 2]);
+        assert!(manifest.resources["logo"].file.is_none());
+        assert!(manifest.resources["media/photo"].placements.is_empty());
+
+        let json = std::fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap();
+        assert!(json.contains("\"url\": \"https://example.com/logo.png\""));
+    }
+}
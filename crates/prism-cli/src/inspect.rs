@@ -0,0 +1,436 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism inspect` - dump the structure of a parsed document.
+//!
+//! Builds a compact summary of the UDM (pages, blocks with bounds,
+//! resources, metadata, structure) that can be printed as a tree or
+//! serialized to JSON. Text and binary payloads are abbreviated so the
+//! output stays readable for large documents.
+
+use prism_core::document::{ContentBlock, Document, Rect};
+use prism_core::metadata::{Metadata, MetadataValue};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+use crate::verify::status_label;
+
+/// Maximum number of characters of text shown per block
+const PREVIEW_CHARS: usize = 40;
+
+/// Summary of a parsed document
+#[derive(Debug, Serialize)]
+pub struct InspectReport {
+    /// Document ID
+    pub id: String,
+    /// Source filename
+    pub filename: Option<String>,
+    /// Detected format name
+    pub format: Option<String>,
+    /// Source size in bytes
+    pub size: Option<u64>,
+    /// Document metadata
+    pub metadata: Metadata,
+    /// Per-page summaries
+    pub pages: Vec<PageSummary>,
+    /// Resource counts
+    pub resources: ResourceSummary,
+    /// Structure counts
+    pub structure: StructureSummary,
+    /// Number of attachments
+    pub attachments: usize,
+}
+
+/// Summary of a single page
+#[derive(Debug, Serialize)]
+pub struct PageSummary {
+    /// Page number (1-indexed)
+    pub number: u32,
+    /// Page width in points
+    pub width: f64,
+    /// Page height in points
+    pub height: f64,
+    /// Page label (e.g. sheet name)
+    pub label: Option<String>,
+    /// Page rotation in degrees
+    pub rotation: i32,
+    /// Number of annotations
+    pub annotations: usize,
+    /// Content blocks
+    pub blocks: Vec<BlockSummary>,
+}
+
+/// Summary of a content block
+#[derive(Debug, Serialize)]
+pub struct BlockSummary {
+    /// Block type ("text", "image", ...)
+    pub kind: &'static str,
+    /// Block bounds
+    pub bounds: Rect,
+    /// Short type-specific description
+    pub detail: String,
+    /// Nested blocks (containers and table cells)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BlockSummary>,
+}
+
+/// Resource counts
+#[derive(Debug, Serialize)]
+pub struct ResourceSummary {
+    /// Number of image resources
+    pub images: usize,
+    /// Images that carry embedded data
+    pub embedded_images: usize,
+    /// Number of font resources
+    pub fonts: usize,
+}
+
+/// Structure counts
+#[derive(Debug, Serialize)]
+pub struct StructureSummary {
+    /// Number of top-level outline items
+    pub outline: usize,
+    /// Number of TOC entries
+    pub toc: usize,
+    /// Number of headings
+    pub headings: usize,
+    /// Number of top-level sections
+    pub sections: usize,
+}
+
+impl InspectReport {
+    /// Build a report from a parsed document
+    #[must_use]
+    pub fn from_document(document: &Document) -> Self {
+        let pages = document
+            .pages
+            .iter()
+            .map(|page| PageSummary {
+                number: page.number,
+                width: page.dimensions.width,
+                height: page.dimensions.height,
+                label: page.metadata.label.clone(),
+                rotation: page.metadata.rotation,
+                annotations: page.annotations.len(),
+                blocks: page.content.iter().map(BlockSummary::from_block).collect(),
+            })
+            .collect();
+
+        Self {
+            id: document.id.to_string(),
+            filename: document.source.filename.clone(),
+            format: document.source.format.as_ref().map(|f| f.name.clone()),
+            size: document.source.size,
+            metadata: document.metadata.clone(),
+            pages,
+            resources: ResourceSummary {
+                images: document.resources.images.len(),
+                embedded_images: document
+                    .resources
+                    .images
+                    .iter()
+                    .filter(|img| img.data.is_some())
+                    .count(),
+                fonts: document.resources.fonts.len(),
+            },
+            structure: StructureSummary {
+                outline: document.structure.outline.len(),
+                toc: document.structure.toc.len(),
+                headings: document.structure.headings.len(),
+                sections: document.structure.sections.len(),
+            },
+            attachments: document.attachments.len(),
+        }
+    }
+
+    /// Render the report as an indented tree
+    #[must_use]
+    pub fn render_tree(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "Document {} ({}, {}, {})",
+            self.id,
+            self.filename.as_deref().unwrap_or("<unnamed>"),
+            self.format.as_deref().unwrap_or("unknown format"),
+            self.size
+                .map_or_else(|| "unknown size".to_string(), |s| format!("{s} bytes"))
+        );
+
+        let _ = writeln!(out, "├── Metadata");
+        let fields = metadata_fields(&self.metadata);
+        if fields.is_empty() {
+            let _ = writeln!(out, "│   └── (none)");
+        }
+        for (i, (key, value)) in fields.iter().enumerate() {
+            let branch = if i + 1 == fields.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            let _ = writeln!(out, "│   {branch} {key}: {value}");
+        }
+
+        let _ = writeln!(out, "├── Pages ({})", self.pages.len());
+        for (i, page) in self.pages.iter().enumerate() {
+            let last = i + 1 == self.pages.len();
+            let branch = if last { "└──" } else { "├──" };
+            let _ = write!(
+                out,
+                "│   {branch} Page {} {:.1}x{:.1}pt",
+                page.number, page.width, page.height
+            );
+            if let Some(label) = &page.label {
+                let _ = write!(out, " \"{label}\"");
+            }
+            if page.rotation != 0 {
+                let _ = write!(out, " rotated {}°", page.rotation);
+            }
+            let _ = writeln!(
+                out,
+                " [{} blocks, {} annotations]",
+                page.blocks.len(),
+                page.annotations
+            );
+
+            let prefix = if last { "│       " } else { "│   │   " };
+            write_blocks(&mut out, &page.blocks, prefix);
+        }
+
+        let _ = writeln!(
+            out,
+            "├── Resources: {} images ({} embedded), {} fonts",
+            self.resources.images, self.resources.embedded_images, self.resources.fonts
+        );
+        let _ = writeln!(
+            out,
+            "├── Structure: {} outline items, {} TOC entries, {} headings, {} sections",
+            self.structure.outline,
+            self.structure.toc,
+            self.structure.headings,
+            self.structure.sections
+        );
+        let _ = writeln!(out, "└── Attachments: {}", self.attachments);
+
+        out
+    }
+}
+
+impl BlockSummary {
+    /// Summarize a content block (recursively)
+    #[must_use]
+    pub fn from_block(block: &ContentBlock) -> Self {
+        match block {
+            ContentBlock::Text(text) => Self {
+                kind: "text",
+                bounds: text.bounds,
+                detail: format!(
+                    "{} runs \"{}\"",
+                    text.runs.len(),
+                    preview(&text.extract_text())
+                ),
+                children: Vec::new(),
+            },
+            ContentBlock::Image(image) => Self {
+                kind: "image",
+                bounds: image.bounds,
+                detail: format!(
+                    "resource={} format={}",
+                    image.resource_id,
+                    image.format.as_deref().unwrap_or("?")
+                ),
+                children: Vec::new(),
+            },
+            ContentBlock::Table(table) => Self {
+                kind: "table",
+                bounds: table.bounds,
+                detail: format!("{} rows x {} columns", table.rows.len(), table.column_count),
+                children: table
+                    .rows
+                    .iter()
+                    .flat_map(|row| row.cells.iter())
+                    .flat_map(|cell| cell.content.iter())
+                    .filter(|b| !matches!(b, ContentBlock::Text(_)))
+                    .map(Self::from_block)
+                    .collect(),
+            },
+            ContentBlock::List(list) => {
+                let ordered = list.items.iter().filter(|item| item.ordered).count();
+                let depth = list.items.iter().map(|item| item.level).max().unwrap_or(0);
+                Self {
+                    kind: "list",
+                    bounds: list.bounds,
+                    detail: format!(
+                        "{} items ({ordered} numbered), {} levels",
+                        list.items.len(),
+                        u32::from(depth) + 1
+                    ),
+                    children: list
+                        .items
+                        .iter()
+                        .flat_map(|item| item.content.iter())
+                        .filter(|b| !matches!(b, ContentBlock::Text(_)))
+                        .map(Self::from_block)
+                        .collect(),
+                }
+            }
+            ContentBlock::FormField(field) => Self {
+                kind: "form field",
+                bounds: field.bounds,
+                detail: format!(
+                    "{:?} name={} \"{}\"",
+                    field.field_type,
+                    field.name,
+                    preview(&field.extract_text())
+                ),
+                children: Vec::new(),
+            },
+            ContentBlock::Vector(vector) => Self {
+                kind: "vector",
+                bounds: vector.bounds,
+                detail: format!("{} paths", vector.paths.len()),
+                children: Vec::new(),
+            },
+            ContentBlock::Container(container) => Self {
+                kind: "container",
+                bounds: container.bounds,
+                detail: container
+                    .container_type
+                    .clone()
+                    .unwrap_or_else(|| "group".to_string()),
+                children: container.children.iter().map(Self::from_block).collect(),
+            },
+        }
+    }
+}
+
+/// Write a list of blocks as tree branches below `prefix`
+fn write_blocks(out: &mut String, blocks: &[BlockSummary], prefix: &str) {
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i + 1 == blocks.len();
+        let branch = if last { "└──" } else { "├──" };
+        let b = &block.bounds;
+        let _ = writeln!(
+            out,
+            "{prefix}{branch} {:<9} @ ({:.1}, {:.1}) {:.1}x{:.1}  {}",
+            block.kind, b.x, b.y, b.width, b.height, block.detail
+        );
+
+        if !block.children.is_empty() {
+            let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            write_blocks(out, &block.children, &child_prefix);
+        }
+    }
+}
+
+/// List the populated metadata fields as key/value strings
+pub(crate) fn metadata_fields(metadata: &Metadata) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let standard = [
+        ("title", &metadata.title),
+        ("author", &metadata.author),
+        ("subject", &metadata.subject),
+        ("creator", &metadata.creator),
+        ("producer", &metadata.producer),
+        ("language", &metadata.language),
+    ];
+    for (key, value) in standard {
+        if let Some(value) = value {
+            fields.push((key.to_string(), value.clone()));
+        }
+    }
+    if !metadata.keywords.is_empty() {
+        fields.push(("keywords".to_string(), metadata.keywords.join(", ")));
+    }
+    if let Some(created) = metadata.created {
+        fields.push(("created".to_string(), created.to_rfc3339()));
+    }
+    if let Some(modified) = metadata.modified {
+        fields.push(("modified".to_string(), modified.to_rfc3339()));
+    }
+    for signature in &metadata.signatures {
+        let mut value = format!(
+            "{} ({})",
+            signature.signer.as_deref().unwrap_or("unknown signer"),
+            status_label(signature.status)
+        );
+        if signature.modified_after_signing {
+            value.push_str(", modified after signing");
+        }
+        fields.push(("signature".to_string(), value));
+    }
+
+    let mut custom: Vec<_> = metadata.custom.iter().collect();
+    custom.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in custom {
+        let value = match value {
+            MetadataValue::String(s) => preview(s),
+            MetadataValue::Integer(i) => i.to_string(),
+            MetadataValue::Float(f) => f.to_string(),
+            MetadataValue::Boolean(b) => b.to_string(),
+            MetadataValue::DateTime(dt) => dt.to_rfc3339(),
+        };
+        fields.push((format!("custom.{key}"), value));
+    }
+
+    fields
+}
+
+/// Abbreviate text to a single line of at most `PREVIEW_CHARS` characters
+fn preview(text: &str) -> String {
+    let flat: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let flat = flat.trim();
+    if flat.chars().count() > PREVIEW_CHARS {
+        let cut: String = flat.chars().take(PREVIEW_CHARS).collect();
+        format!("{cut}…")
+    } else {
+        flat.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Dimensions, Page, TextBlock, TextRun};
+
+    fn sample_document() -> Document {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        let mut block = TextBlock::new(Rect::new(72.0, 72.0, 468.0, 14.0));
+        block.add_run(TextRun::new("Hello inspector"));
+        page.add_content(ContentBlock::Text(block));
+
+        let mut doc = Document::builder()
+            .metadata(Metadata::builder().title("Sample").build())
+            .page(page)
+            .build();
+        doc.metadata.add_custom("format", "TEST");
+        doc
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let report = InspectReport::from_document(&sample_document());
+        assert_eq!(report.pages.len(), 1);
+        assert_eq!(report.pages[0].blocks.len(), 1);
+        assert_eq!(report.pages[0].blocks[0].kind, "text");
+        assert_eq!(report.resources.images, 0);
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let tree = InspectReport::from_document(&sample_document()).render_tree();
+        assert!(tree.contains("title: Sample"));
+        assert!(tree.contains("custom.format: TEST"));
+        assert!(tree.contains("Page 1 612.0x792.0pt"));
+        assert!(tree.contains("\"Hello inspector\""));
+    }
+
+    #[test]
+    fn test_preview_truncates() {
+        let long = "x".repeat(100);
+        assert_eq!(preview(&long).chars().count(), PREVIEW_CHARS + 1);
+        assert_eq!(preview("a\nb"), "a b");
+    }
+}
@@ -8,5 +8,8 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod fixtures;
+pub mod images;
+
 /// Prism CLI version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -9,91 +9,1006 @@
 //! # Detect document format
 //! prism detect document.pdf
 //!
+//! # Print the results of any command as JSON for scripts
+//! prism --output json detect document.pdf
+//!
 //! # Convert document
-//! prism convert document.docx -o output.pdf
+//! prism convert document.docx -o output.html
+//!
+//! # Convert and list fonts substituted, images missing and content skipped,
+//! # and how long each parse stage took
+//! prism convert deck.pptx -o deck.docx --verbose
+//!
+//! # Convert a remote document without downloading it first
+//! prism convert https://example.com/report.pdf -o report.txt --allow-host example.com
 //!
 //! # Extract text
 //! prism extract-text document.pdf -o text.txt
 //!
-//! # Extract metadata
-//! prism metadata document.pdf
+//! # Extract embedded images with a manifest of where they are placed
+//! prism extract-images deck.pptx -o img
+//!
+//! # Extract metadata and dump embedded fonts
+//! prism metadata document.pdf --fonts-dir fonts
+//!
+//! # Count pages, words, images and tables of a document
+//! prism stats document.docx --json
+//!
+//! # Survey a corpus before migrating it
+//! prism analyze /archive --json
+//!
+//! # Time parsing and rendering, and compare against an earlier run
+//! prism bench corpus --save before.json
+//! prism bench corpus --baseline before.json --threshold 15
+//!
+//! # Export slide text and speaker notes as Markdown
+//! prism slides deck.pptx -o deck.md
+//!
+//! # Check the digital signatures of a signed contract
+//! prism verify contract.pdf
+//!
+//! # Rebuild a file without its macros, scripts, links and executables
+//! prism disarm invoice.docm -o invoice.html
+//!
+//! # Write one HTML file per email conversation of a mailbox and messages
+//! prism threads inbox.mbox reply.eml -o threads
+//!
+//! # Dump the parsed document structure
+//! prism inspect document.docx --json
+//!
+//! # Extract specific elements as JSON
+//! prism query report.docx "pages[3].tables[*].rows[0]"
+//!
+//! # Check the installation
+//! prism doctor
+//!
+//! # List the supported input and output formats
+//! prism formats --json
+//!
+//! # Generate a synthetic test document
+//! prism gen-fixture docx --pages 3 --merged-cells --images 2 -o sample.docx
+//!
+//! # Convert files as they appear in a directory
+//! prism watch inbox -o converted --format html
+//!
+//! # Export chunks as JSON Lines for a vector database
+//! prism watch inbox -o chunks --format jsonl
+//!
+//! # Reuse conversions across runs and output directories
+//! prism watch inbox -o converted --cache-dir ~/.cache/prism
+//!
+//! # Generate shell completion
+//! prism completions zsh > ~/.zfunc/_prism
+//!
+//! # Convert in a batch job: print nothing but errors, and fail on any
+//! # warning; the exit code tells unsupported formats (2), parse errors (3),
+//! # render errors (4) and resource limits (5) apart
+//! prism convert scan.pdf -o scan.html --quiet --strict
 //!
 //! # Get version
 //! prism version
 //! ```
 
-use anyhow::Result;
-use std::path::PathBuf;
-use tracing::Level;
+mod analyze;
+mod bench;
+mod console;
+mod detect;
+mod doctor;
+mod formats;
+mod inspect;
+mod metadata;
+mod output;
+mod progress;
+mod stats;
+mod verify;
+mod watch;
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use clap::{CommandFactory, Parser as ClapParser, Subcommand};
+use clap_complete::Shell;
+use output::{OutputFormat, ReportFormat};
+use prism_cli::fixtures::{self, FixtureKind, FixtureSpec};
+use prism_cli::images;
+use prism_core::cache::{CacheLimits, ConversionCache, DiskCache};
+use prism_core::document::Document;
+use prism_core::fetch::{fetch, FetchPolicy};
+use prism_core::license::{LicenseManager, LicenseStatus};
+use prism_core::pipeline::{Pipeline, PipelineOutput};
+use prism_core::progress::{ProgressEvent, ProgressSink};
+use prism_core::query::Query;
+use prism_parsers::email::thread_documents;
+use prism_parsers::security::Disarm;
+use prism_parsers::ParserRegistry;
+use prism_render::slides::SlideExport;
+use progress::ProgressBar;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{warn, Level};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Layer as _, SubscriberExt as _};
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+/// `print!` unless `--quiet` is given
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if !console::is_quiet() {
+            print!($($arg)*);
+        }
+    };
+}
+
+/// `println!` unless `--quiet` is given
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if !console::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// `eprintln!` unless `--quiet` is given, for notes beside the output
+macro_rules! note {
+    ($($arg:tt)*) => {
+        if !console::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
 
-/// CLI arguments (placeholder - would use clap in real implementation)
-#[derive(Debug)]
+/// Command-line arguments
+#[derive(Debug, ClapParser)]
+#[command(name = "prism", version, about = "Prism document processing")]
 struct Args {
+    /// Print results as text or as JSON, as `--json` does for a single
+    /// command; comes before the command
+    #[arg(long, value_enum, default_value = "text")]
+    output: ReportFormat,
+
+    /// Print nothing but errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Fail when a warning is logged, a damaged part of a document is
+    /// skipped or the output format cannot reproduce something faithfully
+    #[arg(long, global = true)]
+    strict: bool,
+
+    #[command(subcommand)]
     command: Command,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Subcommand)]
 enum Command {
-    Detect { file: PathBuf },
-    Convert { input: PathBuf, output: PathBuf },
-    ExtractText { input: PathBuf, output: PathBuf },
-    Metadata { file: PathBuf },
+    /// Detect the format of a file
+    Detect {
+        /// File to inspect
+        file: PathBuf,
+    },
+    /// Convert a document to another format
+    Convert {
+        /// Input document, or an HTTP(S) URL to download it from
+        input: PathBuf,
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format (defaults to the output file's extension)
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Host URLs may be downloaded from, subdomains included
+        /// (repeatable; any host if omitted)
+        #[arg(long)]
+        allow_host: Vec<String>,
+        /// Largest document downloaded, in megabytes
+        #[arg(long, default_value_t = 100)]
+        max_download_mb: usize,
+        /// Redirects followed when downloading
+        #[arg(long, default_value_t = 5)]
+        max_redirects: usize,
+        /// Seconds a download may take
+        #[arg(long, default_value_t = 30)]
+        fetch_timeout: u64,
+        /// List what the output format could not reproduce faithfully and
+        /// how long each parse stage took
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Extract plain text from a document
+    ExtractText {
+        /// Input document
+        input: PathBuf,
+        /// Output text file
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write the embedded images of a document and a JSON manifest
+    ExtractImages {
+        /// Input document
+        input: PathBuf,
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Print document metadata and fonts
+    Metadata {
+        /// Input document
+        file: PathBuf,
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+        /// Write embedded font files to this directory
+        #[arg(long)]
+        fonts_dir: Option<PathBuf>,
+    },
+    /// Count the pages, words, images, tables and annotations of a document
+    Stats {
+        /// Input document
+        file: PathBuf,
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Detect formats across a directory and print aggregate statistics
+    Analyze {
+        /// Directory to analyze
+        dir: PathBuf,
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Time parsing and HTML rendering of every file in a directory
+    Bench {
+        /// Directory of documents to benchmark
+        dir: PathBuf,
+        /// Runs per file; the median is reported
+        #[arg(short, long, default_value_t = 5)]
+        iterations: usize,
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+        /// Save the report as JSON, for use as a later baseline
+        #[arg(long)]
+        save: Option<PathBuf>,
+        /// Compare against a report saved with `--save`; fails if any
+        /// file got slower than the threshold allows
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Slowdown in percent that counts as a regression
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+    },
+    /// Export slide titles, text, speaker notes and image alt text
+    Slides {
+        /// Input presentation
+        file: PathBuf,
+        /// Emit JSON instead of Markdown
+        #[arg(long)]
+        json: bool,
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Parse a document and dump its structure
+    Inspect {
+        /// Input document
+        file: PathBuf,
+        /// Emit machine-readable JSON instead of a tree
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the digital signatures embedded in a PDF or OOXML file
+    Verify {
+        /// Signed document
+        file: PathBuf,
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rebuild a document without macros, scripts, external links or
+    /// embedded executables, and report what was removed
+    Disarm {
+        /// Input document
+        input: PathBuf,
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "html")]
+        format: OutputFormat,
+        /// Print the removed items as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Group emails and mailboxes into conversations, and write each
+    /// conversation as one document
+    Threads {
+        /// EML, MSG or MBOX files
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "html")]
+        format: OutputFormat,
+    },
+    /// Select elements of a parsed document with a path expression
+    Query {
+        /// Input document
+        file: PathBuf,
+        /// Path expression, e.g. `pages[0].tables[*].rows[0]`
+        path: String,
+    },
+    /// Check the installation and print a diagnostic report
+    Doctor {
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the formats this build can read and write
+    Formats {
+        /// Emit machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Watch a directory and convert new or changed files
+    Watch {
+        /// Directory to watch
+        dir: PathBuf,
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "html")]
+        format: OutputFormat,
+        /// Quiet period after the last change before converting (milliseconds)
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+        /// Glob pattern to ignore (repeatable)
+        #[arg(long)]
+        ignore: Vec<String>,
+        /// Directory of a conversion cache shared with other runs
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+    },
+    /// Generate a synthetic test document
+    GenFixture {
+        /// Kind of document to generate
+        #[arg(value_enum)]
+        kind: FixtureKind,
+        /// Output file (defaults to fixture.<ext>)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Number of pages (sheets for XLSX, slides for PPTX)
+        #[arg(long, default_value_t = 1)]
+        pages: usize,
+        /// Include a table with merged cells
+        #[arg(long)]
+        merged_cells: bool,
+        /// Number of embedded images
+        #[arg(long, default_value_t = 0)]
+        images: usize,
+        /// Include non-ASCII text
+        #[arg(long)]
+        unicode: bool,
+    },
+    /// Print a shell completion script to standard output
+    Completions {
+        /// Shell to complete in
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print version information
     Version,
 }
 
-fn parse_args() -> Result<Args> {
-    // Placeholder - would use clap
-    Ok(Args {
-        command: Command::Version,
-    })
+/// Detect, parse and return the document stored at `path`
+///
+/// Progress is shown on stderr when it is a terminal.
+async fn load_document(registry: &ParserRegistry, path: &Path) -> Result<Document> {
+    let bar = ProgressBar::stderr();
+    let mut pipeline = Pipeline::new(Arc::new(registry.clone()));
+    if let Some(bar) = &bar {
+        pipeline = pipeline.with_progress(bar.clone());
+    }
+    let result = load_with(&pipeline, path, bar.as_deref()).await;
+    if let Some(bar) = &bar {
+        bar.finish();
+    }
+    Ok(result?.document)
+}
+
+/// Read the file at `path` and run it through `pipeline`
+async fn load_with(
+    pipeline: &Pipeline,
+    path: &Path,
+    bar: Option<&ProgressBar>,
+) -> Result<PipelineOutput> {
+    let data = progress::read_file(path, bar.map(|bar| bar as &dyn ProgressSink))?;
+    let filename = path.file_name().and_then(|s| s.to_str());
+    let output = pipeline.run(Bytes::from(data), filename).await?;
+    console::record_warnings(output.document.diagnostics.len());
+    Ok(output)
+}
+
+/// The input as a URL, if it is an HTTP(S) URL rather than a path
+fn input_url(input: &Path) -> Option<&str> {
+    input
+        .to_str()
+        .filter(|input| input.starts_with("http://") || input.starts_with("https://"))
+}
+
+/// Download the document at `url` within `policy` and run it through
+/// `pipeline`
+async fn load_url(
+    pipeline: &Pipeline,
+    url: &str,
+    policy: &FetchPolicy,
+    bar: Option<&ProgressBar>,
+) -> Result<PipelineOutput> {
+    if let Some(bar) = bar {
+        bar.report(ProgressEvent::StageChanged {
+            stage: "download".to_string(),
+        });
+    }
+    let fetched = fetch(url, policy)
+        .await
+        .with_context(|| format!("Failed to fetch {url}"))?;
+    let output = pipeline
+        .run(fetched.data, fetched.filename.as_deref())
+        .await?;
+    console::record_warnings(output.document.diagnostics.len());
+    Ok(output)
+}
+
+/// Detect and parse an in-memory document
+async fn parse_bytes(
+    registry: &ParserRegistry,
+    data: Vec<u8>,
+    filename: Option<&str>,
+) -> Result<Document> {
+    let pipeline = Pipeline::new(Arc::new(registry.clone()));
+    let output = pipeline.run(Bytes::from(data), filename).await?;
+    Ok(output.document)
+}
+
+/// Exit code for a failed command
+///
+/// Document processing errors map through their [`ErrorCode`](prism_core::ErrorCode)
+/// (2 unsupported format, 3 parse error, 4 render error, 5 resource limit);
+/// anything else exits with 1.
+fn exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<prism_core::Error>())
+        .map_or(1, |error| error.code().exit_code())
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .with_target(false)
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    console::set_quiet(args.quiet);
+
+    // Initialize tracing; warnings are counted for --strict even when
+    // --quiet hides them
+    let level = if args.quiet {
+        Level::ERROR
+    } else {
+        Level::INFO
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(std::io::stderr)
+                .with_filter(LevelFilter::from_level(level)),
+        )
+        .with(console::CountWarnings.with_filter(LevelFilter::WARN))
         .init();
 
-    let args = parse_args()?;
+    let strict = args.strict;
+    let result = run(args).await.and_then(|()| {
+        let warnings = console::warnings();
+        if strict && warnings > 0 {
+            anyhow::bail!("{warnings} warning(s) with --strict");
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            ExitCode::from(exit_code(&error))
+        }
+    }
+}
 
+async fn run(args: Args) -> Result<()> {
+    // A broken license key should not stop the CLI; fall back to community
+    let license = LicenseManager::from_env().unwrap_or_else(|e| {
+        warn!("Ignoring license: {}", e);
+        LicenseManager::community()
+    });
+    if let LicenseStatus::GracePeriod { days_remaining } = license.status() {
+        warn!(
+            "License has expired; features will be disabled in {} day(s)",
+            days_remaining
+        );
+    }
+
+    // Per-command `--json` flags predate the global `--output json`
+    let json_output = args.output == ReportFormat::Json;
     match args.command {
         Command::Version => {
-            println!("Prism CLI v{}", env!("CARGO_PKG_VERSION"));
-            println!("  prism-core: v{}", prism_core::VERSION);
-            println!("  prism-parsers: v{}", prism_parsers::VERSION);
-            println!("  prism-render: v{}", prism_render::VERSION);
+            if json_output {
+                let versions = serde_json::json!({
+                    "cli": env!("CARGO_PKG_VERSION"),
+                    "core": prism_core::VERSION,
+                    "parsers": prism_parsers::VERSION,
+                    "render": prism_render::VERSION,
+                    "license": license.license_type(),
+                });
+                outln!("{}", serde_json::to_string_pretty(&versions)?);
+            } else {
+                outln!("Prism CLI v{}", env!("CARGO_PKG_VERSION"));
+                outln!("  prism-core: v{}", prism_core::VERSION);
+                outln!("  prism-parsers: v{}", prism_parsers::VERSION);
+                outln!("  prism-render: v{}", prism_render::VERSION);
+                outln!("License: {}", license.license_type());
+            }
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), "prism", &mut std::io::stdout());
         }
         Command::Detect { file } => {
-            println!("Detecting format of: {}", file.display());
-            let data = std::fs::read(&file)?;
-            match prism_core::format::detect_format(&data, file.file_name().and_then(|s| s.to_str())) {
-                Some(result) => {
-                    println!("Format: {}", result.format.name);
-                    println!("MIME type: {}", result.format.mime_type);
-                    println!("Extension: {}", result.format.extension);
-                    println!("Confidence: {:.2}%", result.confidence * 100.0);
-                    println!("Method: {:?}", result.method);
+            let data = std::fs::read(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let registry = ParserRegistry::with_default_parsers();
+            let result = registry.detect(&data, file.file_name().and_then(|s| s.to_str()));
+            let report = detect::DetectReport::new(file.display().to_string(), result.as_ref());
+
+            if json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+        }
+        Command::Convert {
+            input,
+            output,
+            format,
+            allow_host,
+            max_download_mb,
+            max_redirects,
+            fetch_timeout,
+            verbose,
+        } => {
+            let format = format
+                .or_else(|| {
+                    output
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .and_then(OutputFormat::from_extension)
+                })
+                .with_context(|| {
+                    format!(
+                        "Cannot tell the output format from {}; pass --format",
+                        output.display()
+                    )
+                })?;
+            let policy = FetchPolicy {
+                allowed_hosts: allow_host,
+                max_size: max_download_mb.saturating_mul(1024 * 1024),
+                max_redirects,
+                timeout: Duration::from_secs(fetch_timeout),
+                // Only the user's own requests go out from here
+                allow_private_hosts: true,
+            };
+
+            let registry = ParserRegistry::with_default_parsers();
+            let bar = ProgressBar::stderr();
+            let progress = bar.clone().map(|bar| bar as Arc<dyn ProgressSink>);
+            let mut pipeline = Pipeline::new(Arc::new(registry));
+            if let Some(progress) = &progress {
+                pipeline = pipeline.with_progress(progress.clone());
+            }
+            let loaded = match input_url(&input) {
+                Some(url) => load_url(&pipeline, url, &policy, bar.as_deref()).await,
+                None => load_with(&pipeline, &input, bar.as_deref()).await,
+            };
+            let result = match loaded {
+                Ok(loaded) => format
+                    .render_with_diagnostics(&loaded.document, progress)
+                    .await
+                    .map(|(rendered, diagnostics)| {
+                        (rendered, diagnostics, loaded.document.timings)
+                    }),
+                Err(e) => Err(e),
+            };
+            if let Some(bar) = &bar {
+                bar.finish();
+            }
+            let (rendered, diagnostics, timings) = result?;
+            console::record_warnings(diagnostics.lines().len());
+            std::fs::write(&output, &rendered)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            if json_output {
+                let written = serde_json::json!({
+                    "output": output,
+                    "bytes": rendered.len(),
+                    "diagnostics": diagnostics,
+                });
+                outln!("{}", serde_json::to_string_pretty(&written)?);
+            } else {
+                outln!("Wrote {}", output.display());
+            }
+            if verbose {
+                let lines = diagnostics.lines();
+                note!("{} fidelity note(s)", lines.len());
+                for line in lines {
+                    note!("  {line}");
+                }
+                if !timings.is_empty() {
+                    note!("{} parse stage(s)", timings.len());
                 }
-                None => {
-                    println!("Could not detect format");
+                for timing in timings {
+                    note!("  {}: {:?}", timing.stage, timing.elapsed);
                 }
             }
         }
-        Command::Convert { input, output } => {
-            println!("Converting {} -> {}", input.display(), output.display());
-            println!("(Not yet implemented)");
-        }
         Command::ExtractText { input, output } => {
-            println!("Extracting text from {} to {}", input.display(), output.display());
-            println!("(Not yet implemented)");
+            outln!(
+                "Extracting text from {} to {}",
+                input.display(),
+                output.display()
+            );
+            outln!("(Not yet implemented)");
+        }
+        Command::ExtractImages { input, output } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &input).await?;
+            let manifest = images::extract_images(&document, &output)?;
+            if json_output {
+                outln!("{}", serde_json::to_string_pretty(&manifest)?);
+            }
+            note!(
+                "Wrote {} image file(s) for {} resource(s) to {}",
+                manifest.files.len(),
+                manifest.resources.len(),
+                output.display()
+            );
+        }
+        Command::Metadata {
+            file,
+            json,
+            fonts_dir,
+        } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &file).await?;
+            let report = metadata::MetadataReport::from_document(&document);
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+            if let Some(dir) = fonts_dir {
+                let written = metadata::dump_fonts(&document, &dir)?;
+                note!("Wrote {} font file(s) to {}", written.len(), dir.display());
+            }
+        }
+        Command::Stats { file, json } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &file).await?;
+            let report = stats::StatsReport::from_document(&document);
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+        }
+        Command::Analyze { dir, json } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let report = analyze::analyze(&dir, registry.formats())?;
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+        }
+        Command::Bench {
+            dir,
+            iterations,
+            json,
+            save,
+            baseline,
+            threshold,
+        } => {
+            // Read the baseline first so a bad path fails before the run
+            let baseline: Option<bench::BenchReport> = baseline
+                .map(|path| -> Result<_> {
+                    let data = std::fs::read(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    serde_json::from_slice(&data)
+                        .with_context(|| format!("Invalid bench report {}", path.display()))
+                })
+                .transpose()?;
+
+            let registry = ParserRegistry::with_default_parsers();
+            let report = bench::bench(&registry, &dir, iterations).await?;
+            if let Some(path) = save {
+                std::fs::write(&path, serde_json::to_string_pretty(&report)? + "\n")
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            let comparison = baseline.map(|baseline| report.compare(&baseline, threshold));
+
+            if json || json_output {
+                let output = serde_json::json!({ "report": report, "comparison": comparison });
+                outln!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                out!("{}", report.render_text());
+                if let Some(comparison) = &comparison {
+                    out!("{}", comparison.render_text());
+                }
+            }
+
+            if let Some(comparison) = comparison.filter(|c| !c.regressions.is_empty()) {
+                anyhow::bail!(
+                    "{} timing(s) regressed by more than {threshold}%",
+                    comparison.regressions.len()
+                );
+            }
+        }
+        Command::Slides { file, json, output } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &file).await?;
+            let export = SlideExport::from_document(&document);
+
+            let content = if json || json_output {
+                serde_json::to_string_pretty(&export)? + "\n"
+            } else {
+                export.to_markdown()
+            };
+            match output {
+                Some(path) => std::fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => out!("{content}"),
+            }
         }
-        Command::Metadata { file } => {
-            println!("Extracting metadata from: {}", file.display());
-            println!("(Not yet implemented)");
+        Command::Inspect { file, json } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &file).await?;
+            let report = inspect::InspectReport::from_document(&document);
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_tree());
+            }
+        }
+        Command::Verify { file, json } => {
+            let data = std::fs::read(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let report = verify::VerifyReport::new(file.display().to_string(), &data);
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+
+            if report.signatures.is_empty() {
+                anyhow::bail!("{} is not signed", file.display());
+            }
+            let tampered = report.tampered();
+            if tampered > 0 {
+                anyhow::bail!("{tampered} signature(s) no longer match the signed content");
+            }
+        }
+        Command::Disarm {
+            input,
+            output,
+            format,
+            json,
+        } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let bar = ProgressBar::stderr();
+            let progress = bar.clone().map(|bar| bar as Arc<dyn ProgressSink>);
+            let mut pipeline = Pipeline::new(Arc::new(registry)).with_processor(Arc::new(Disarm));
+            if let Some(progress) = &progress {
+                pipeline = pipeline.with_progress(progress.clone());
+            }
+            let result = match load_with(&pipeline, &input, bar.as_deref()).await {
+                Ok(output) => format
+                    .render_with_progress(&output.document, progress)
+                    .await
+                    .map(|rendered| (output.document, rendered)),
+                Err(e) => Err(e),
+            };
+            if let Some(bar) = &bar {
+                bar.finish();
+            }
+            let (document, rendered) = result?;
+            std::fs::write(&output, rendered)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+
+            let removed = &document.metadata.disarmed;
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(removed)?);
+            } else {
+                outln!("Wrote {}", output.display());
+                if removed.is_empty() {
+                    outln!("Nothing to remove");
+                } else {
+                    outln!("Removed {} item(s):", removed.len());
+                    for item in removed {
+                        outln!("  {item}");
+                    }
+                }
+            }
+        }
+        Command::Threads {
+            inputs,
+            output,
+            format,
+        } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let mut documents = Vec::with_capacity(inputs.len());
+            for input in &inputs {
+                documents.push(load_document(&registry, input).await?);
+            }
+            let threads = thread_documents(&documents);
+            if threads.is_empty() {
+                anyhow::bail!("No email messages found");
+            }
+
+            std::fs::create_dir_all(&output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            let mut written = Vec::with_capacity(threads.len());
+            for (index, thread) in threads.iter().enumerate() {
+                let path = output.join(format!("thread-{:03}.{}", index + 1, format.extension()));
+                std::fs::write(&path, format.render(thread).await?)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                let title = thread.metadata.title.as_deref().unwrap_or_default();
+                if json_output {
+                    written.push(serde_json::json!({
+                        "output": path,
+                        "title": title,
+                        "messages": thread.pages.len(),
+                    }));
+                } else {
+                    outln!(
+                        "{}: {} ({} message(s))",
+                        path.display(),
+                        title,
+                        thread.pages.len()
+                    );
+                }
+            }
+            if json_output {
+                outln!("{}", serde_json::to_string_pretty(&written)?);
+            }
+        }
+        Command::Query { file, path } => {
+            // Reject a malformed path before spending time parsing the file
+            let query = Query::parse(&path)?;
+            let registry = ParserRegistry::with_default_parsers();
+            let document = load_document(&registry, &file).await?;
+            let matches = query.evaluate(&document)?;
+            outln!("{}", serde_json::to_string_pretty(&matches)?);
+        }
+        Command::Watch {
+            dir,
+            output,
+            format,
+            debounce_ms,
+            ignore,
+            cache_dir,
+        } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let cache = match cache_dir {
+                Some(dir) => Some(Arc::new(
+                    DiskCache::new(&dir, CacheLimits::default())
+                        .with_context(|| format!("Cannot open cache {}", dir.display()))?,
+                ) as Arc<dyn ConversionCache>),
+                None => None,
+            };
+            watch::run(
+                &registry,
+                watch::WatchOptions {
+                    input: dir,
+                    output,
+                    format,
+                    debounce: Duration::from_millis(debounce_ms),
+                    ignore,
+                    cache,
+                },
+            )
+            .await?;
+        }
+        Command::GenFixture {
+            kind,
+            output,
+            pages,
+            merged_cells,
+            images,
+            unicode,
+        } => {
+            let spec = FixtureSpec {
+                pages,
+                merged_cells,
+                images,
+                unicode,
+            };
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("fixture.{}", kind.extension())));
+            let data = fixtures::generate(kind, &spec)?;
+            std::fs::write(&output, &data)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            if json_output {
+                let written = serde_json::json!({ "output": output, "bytes": data.len() });
+                outln!("{}", serde_json::to_string_pretty(&written)?);
+            } else {
+                outln!("Wrote {} ({} bytes)", output.display(), data.len());
+            }
+        }
+        Command::Formats { json } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let report = formats::FormatsReport::new(&registry);
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+        }
+        Command::Doctor { json } => {
+            let registry = ParserRegistry::with_default_parsers();
+            let report = doctor::run(&registry).await;
+
+            if json || json_output {
+                outln!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                out!("{}", report.render_text());
+            }
+
+            if report.has_failures() {
+                std::process::exit(1);
+            }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        Args::command().debug_assert();
+
+        // The global `--output` and that of `convert` do not clash
+        let args = Args::try_parse_from([
+            "prism", "--output", "json", "convert", "a.docx", "--output", "a.html",
+        ])
+        .unwrap();
+        assert_eq!(args.output, ReportFormat::Json);
+        assert!(
+            matches!(args.command, Command::Convert { output, .. } if output == Path::new("a.html"))
+        );
+        assert_eq!(
+            Args::try_parse_from(["prism", "formats"]).unwrap().output,
+            ReportFormat::Text
+        );
+
+        // Quiet and strict are accepted after the command too
+        let args = Args::try_parse_from(["prism", "stats", "a.pdf", "-q", "--strict"]).unwrap();
+        assert!(args.quiet && args.strict);
+    }
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism metadata` - document metadata and font report.
+//!
+//! Lists the document properties together with every font the document
+//! references: whether it is embedded, whether it is a subset, its file
+//! format and the embedding permissions it declares. Embedded font files
+//! can be written to a directory for licensing audits or to debug
+//! rendering differences.
+
+use anyhow::{Context, Result};
+use prism_core::document::{Document, EmbeddingRights, FontResource};
+use prism_core::metadata::Metadata;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::inspect::metadata_fields;
+
+/// Metadata and fonts of a parsed document
+#[derive(Debug, Serialize)]
+pub struct MetadataReport {
+    /// Source filename
+    pub filename: Option<String>,
+    /// Detected format name
+    pub format: Option<String>,
+    /// Source size in bytes
+    pub size: Option<u64>,
+    /// SHA-256 of the source file
+    pub hash: Option<String>,
+    /// Document metadata
+    pub metadata: Metadata,
+    /// Fonts the document uses
+    pub fonts: Vec<FontSummary>,
+}
+
+/// One font of the document
+#[derive(Debug, Serialize)]
+pub struct FontSummary {
+    /// Family name
+    pub family: String,
+    /// Style name
+    pub style: String,
+    /// Whether the font program is embedded
+    pub embedded: bool,
+    /// Whether only a subset of the glyphs is embedded
+    pub subset: bool,
+    /// Font file format
+    pub font_type: Option<String>,
+    /// Embedding permissions
+    pub embedding_rights: EmbeddingRights,
+    /// Size of the embedded font file in bytes
+    pub size: Option<usize>,
+}
+
+impl MetadataReport {
+    /// Build a report from a parsed document
+    #[must_use]
+    pub fn from_document(document: &Document) -> Self {
+        Self {
+            filename: document.source.filename.clone(),
+            format: document.source.format.as_ref().map(|f| f.name.clone()),
+            size: document.source.size,
+            hash: document.source.hash.clone(),
+            metadata: document.metadata.clone(),
+            fonts: document
+                .resources
+                .fonts
+                .iter()
+                .map(|font| FontSummary {
+                    family: font.family.clone(),
+                    style: font.style.clone(),
+                    embedded: font.embedded,
+                    subset: font.subset,
+                    font_type: font.font_type.clone(),
+                    embedding_rights: font.embedding_rights,
+                    size: font.data.as_ref().map(Vec::len),
+                })
+                .collect(),
+        }
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "{} ({})",
+            self.filename.as_deref().unwrap_or("<unnamed>"),
+            self.format.as_deref().unwrap_or("unknown format")
+        );
+        if let Some(size) = self.size {
+            let _ = writeln!(out, "  size: {size} bytes");
+        }
+        if let Some(hash) = &self.hash {
+            let _ = writeln!(out, "  sha256: {hash}");
+        }
+        for (key, value) in metadata_fields(&self.metadata) {
+            let _ = writeln!(out, "  {key}: {value}");
+        }
+
+        let _ = writeln!(out, "Fonts ({})", self.fonts.len());
+        for font in &self.fonts {
+            let _ = write!(out, "  {} {}", font.family, font.style);
+            if font.embedded {
+                let _ = write!(
+                    out,
+                    " [embedded{}, {}, {:?}",
+                    if font.subset { " subset" } else { "" },
+                    font.font_type.as_deref().unwrap_or("unknown type"),
+                    font.embedding_rights
+                );
+                if let Some(size) = font.size {
+                    let _ = write!(out, ", {size} bytes");
+                }
+                let _ = write!(out, "]");
+            } else {
+                let _ = write!(out, " [not embedded]");
+            }
+            let _ = writeln!(out);
+        }
+
+        out
+    }
+}
+
+/// Write every embedded font file of `document` to `dir`
+///
+/// Files are named `<family>-<style>.<ext>`; returns the paths written.
+pub fn dump_fonts(document: &Document, dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut used = HashSet::new();
+    let mut written = Vec::new();
+    for font in &document.resources.fonts {
+        let Some(data) = font.data.as_deref().filter(|data| !data.is_empty()) else {
+            continue;
+        };
+        let stem = sanitize(&format!("{}-{}", font.family, font.style));
+        let ext = extension(font);
+        let mut name = format!("{stem}.{ext}");
+        let mut counter = 1;
+        while !used.insert(name.clone()) {
+            counter += 1;
+            name = format!("{stem}-{counter}.{ext}");
+        }
+
+        let path = dir.join(name);
+        std::fs::write(&path, data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// File extension for a font's format
+fn extension(font: &FontResource) -> &'static str {
+    match font.font_type.as_deref() {
+        Some("TrueType") => "ttf",
+        Some("TrueType Collection") => "ttc",
+        Some("OpenType") => "otf",
+        Some("WOFF") => "woff",
+        Some("WOFF2") => "woff2",
+        Some("Type1") => "pfa",
+        Some("CFF") => "cff",
+        Some("EOT") => "eot",
+        _ => "bin",
+    }
+}
+
+/// Keep a font name usable as a file name on every platform
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_document() -> Document {
+        let mut document = Document::new();
+        document.metadata.title = Some("Fonts".to_string());
+        document.resources.fonts = vec![
+            FontResource {
+                embedded: true,
+                subset: true,
+                font_type: Some("TrueType".to_string()),
+                embedding_rights: EmbeddingRights::PreviewAndPrint,
+                data: Some(vec![0, 1, 0, 0]),
+                ..FontResource::new("Brand Sans", "Bold")
+            },
+            FontResource {
+                embedded: true,
+                font_type: Some("TrueType".to_string()),
+                data: Some(vec![0, 1, 0, 0]),
+                ..FontResource::new("Brand Sans", "Bold")
+            },
+            FontResource::new("Helvetica", "Regular"),
+        ];
+        document
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = MetadataReport::from_document(&sample_document()).render_text();
+        assert!(text.contains("title: Fonts"));
+        assert!(text.contains("Fonts (3)"));
+        assert!(
+            text.contains("Brand Sans Bold [embedded subset, TrueType, PreviewAndPrint, 4 bytes]")
+        );
+        assert!(text.contains("Helvetica Regular [not embedded]"));
+    }
+
+    #[test]
+    fn test_dump_fonts() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = dump_fonts(&sample_document(), dir.path()).unwrap();
+        let names: Vec<_> = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["Brand_Sans-Bold.ttf", "Brand_Sans-Bold-2.ttf"]);
+    }
+}
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Output formats supported by the CLI.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use prism_core::document::{ContentBlock, Document};
+use prism_core::progress::ProgressSink;
+use prism_core::render::{RenderContext, RenderDiagnostics, RenderOptions, Renderer};
+use prism_render::docx::DocxRenderer;
+use prism_render::html::HtmlRenderer;
+use prism_render::jsonl::JsonlRenderer;
+use prism_render::xlsx::XlsxRenderer;
+use std::sync::Arc;
+
+/// How commands print their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// Machine-readable JSON
+    Json,
+}
+
+/// Output format for converted documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Standalone HTML page
+    Html,
+    /// Plain text
+    Text,
+    /// Unified Document Model as JSON
+    Json,
+    /// Text chunks with provenance as JSON Lines, for embedding
+    Jsonl,
+    /// Excel workbook of the document's tables
+    Xlsx,
+    /// Word document
+    Docx,
+}
+
+impl OutputFormat {
+    /// File extension for this format
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Html => "html",
+            OutputFormat::Text => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Docx => "docx",
+        }
+    }
+
+    /// Format written to files with extension `extension`
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Render a document in this format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering or serialization fails.
+    pub async fn render(self, document: &Document) -> Result<Vec<u8>> {
+        self.render_with_progress(document, None).await
+    }
+
+    /// Render a document in this format, reporting rendered pages to
+    /// `progress` where the renderer supports it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering or serialization fails.
+    pub async fn render_with_progress(
+        self,
+        document: &Document,
+        progress: Option<Arc<dyn ProgressSink>>,
+    ) -> Result<Vec<u8>> {
+        let (output, _) = self.render_with_diagnostics(document, progress).await?;
+        Ok(output)
+    }
+
+    /// Render a document in this format, along with what the format could
+    /// not reproduce faithfully
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering or serialization fails.
+    pub async fn render_with_diagnostics(
+        self,
+        document: &Document,
+        progress: Option<Arc<dyn ProgressSink>>,
+    ) -> Result<(Vec<u8>, RenderDiagnostics)> {
+        match self {
+            OutputFormat::Html => {
+                let context = RenderContext {
+                    options: RenderOptions::default(),
+                    filename: document.source.filename.clone(),
+                    progress,
+                };
+                let result = HtmlRenderer::new()
+                    .render_with_diagnostics(document, context)
+                    .await?;
+                Ok((result.output.to_vec(), result.diagnostics))
+            }
+            OutputFormat::Text => {
+                // Only blocks with extractable text make it into plain text
+                let diagnostics = RenderDiagnostics::check(document, |block| {
+                    matches!(
+                        block,
+                        ContentBlock::Text(_)
+                            | ContentBlock::Table(_)
+                            | ContentBlock::List(_)
+                            | ContentBlock::FormField(_)
+                    )
+                });
+                Ok((document.extract_text().into_bytes(), diagnostics))
+            }
+            OutputFormat::Json => Ok((
+                serde_json::to_vec_pretty(document)?,
+                RenderDiagnostics::default(),
+            )),
+            OutputFormat::Jsonl => {
+                let renderer = JsonlRenderer::new();
+                let output = renderer.render_jsonl(document)?.into_bytes();
+                Ok((output, checked(document, &renderer)))
+            }
+            OutputFormat::Xlsx => {
+                let renderer = XlsxRenderer::new();
+                let output = renderer.render_xlsx(document)?;
+                Ok((output, checked(document, &renderer)))
+            }
+            OutputFormat::Docx => {
+                let renderer = DocxRenderer::new();
+                let output = renderer.render_docx(document)?;
+                Ok((output, checked(document, &renderer)))
+            }
+        }
+    }
+}
+
+/// Diagnostics of rendering `document` with `renderer`
+fn checked(document: &Document, renderer: &impl Renderer) -> RenderDiagnostics {
+    RenderDiagnostics::check(document, |block| renderer.supports(block))
+}
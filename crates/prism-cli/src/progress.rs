@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Progress bar for long conversions.
+//!
+//! The bar is drawn on stderr, and only when stderr is a terminal, so
+//! redirected output and logs are left alone.
+
+use anyhow::{Context, Result};
+use prism_core::progress::{ProgressEvent, ProgressSink};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Width of the bar in characters
+const BAR_WIDTH: u64 = 30;
+
+/// Size of the chunks input files are read in
+const READ_CHUNK: usize = 1 << 20;
+
+/// A single-line progress bar on stderr
+#[derive(Debug, Default)]
+pub struct ProgressBar {
+    state: Mutex<BarState>,
+}
+
+impl ProgressBar {
+    /// A bar drawing on stderr, or `None` when stderr is not a terminal
+    /// or `--quiet` is given
+    #[must_use]
+    pub fn stderr() -> Option<Arc<Self>> {
+        (std::io::stderr().is_terminal() && !crate::console::is_quiet())
+            .then(|| Arc::new(Self::default()))
+    }
+
+    /// Erase the bar, leaving the line for the command's own output
+    pub fn finish(&self) {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.drawn {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+impl ProgressSink for ProgressBar {
+    fn report(&self, event: ProgressEvent) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.apply(&event) {
+            eprint!("\r\x1b[2K{}", state.line());
+            state.drawn = true;
+        }
+    }
+}
+
+/// What the bar shows
+#[derive(Debug, Default)]
+struct BarState {
+    stage: String,
+    done: u64,
+    total: Option<u64>,
+    unit: &'static str,
+    drawn: bool,
+}
+
+impl BarState {
+    /// Update the state from `event`; returns whether it needs redrawing
+    fn apply(&mut self, event: &ProgressEvent) -> bool {
+        let (done, total, unit) = match event {
+            ProgressEvent::StageChanged { stage } => {
+                self.stage.clone_from(stage);
+                (0, None, "")
+            }
+            ProgressEvent::BytesRead { read, total } => (*read, *total, "bytes"),
+            ProgressEvent::PageParsed { parsed, total } => {
+                (count(*parsed), total.map(count), "pages")
+            }
+            ProgressEvent::RenderStarted { pages } => (0, Some(count(*pages)), "pages"),
+            ProgressEvent::PageRendered { rendered, total } => {
+                (count(*rendered), total.map(count), "pages")
+            }
+            ProgressEvent::FormatDetected { .. }
+            | ProgressEvent::RenderFinished { .. }
+            | ProgressEvent::Warning { .. } => return false,
+        };
+        self.done = done;
+        self.total = total;
+        self.unit = unit;
+        true
+    }
+
+    /// The bar as one line of text
+    fn line(&self) -> String {
+        match self.total.filter(|total| *total > 0) {
+            Some(total) => {
+                let filled = (self.done.min(total) * BAR_WIDTH / total).min(BAR_WIDTH);
+                let bar: String = (0..BAR_WIDTH)
+                    .map(|i| if i < filled { '#' } else { '-' })
+                    .collect();
+                format!(
+                    "{:<16} [{bar}] {}/{} {}",
+                    self.stage, self.done, total, self.unit
+                )
+            }
+            None if self.unit.is_empty() => self.stage.clone(),
+            None => format!("{:<16} {} {}", self.stage, self.done, self.unit),
+        }
+    }
+}
+
+fn count(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// Read the file at `path`, reporting the bytes read to `progress`
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn read_file(path: &Path, progress: Option<&dyn ProgressSink>) -> Result<Vec<u8>> {
+    let Some(progress) = progress else {
+        return std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()));
+    };
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let total = file.metadata().ok().map(|metadata| metadata.len());
+    progress.report(ProgressEvent::StageChanged {
+        stage: "read".to_string(),
+    });
+
+    let mut data = Vec::new();
+    let mut chunk = vec![0; READ_CHUNK];
+    loop {
+        let read = file
+            .read(&mut chunk)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            return Ok(data);
+        }
+        data.extend_from_slice(&chunk[..read]);
+        progress.report(ProgressEvent::BytesRead {
+            read: count(data.len()),
+            total,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_line() {
+        let mut state = BarState::default();
+        assert!(state.apply(&ProgressEvent::StageChanged {
+            stage: "parse".to_string()
+        }));
+        assert_eq!(state.line(), "parse");
+
+        state.apply(&ProgressEvent::PageParsed {
+            parsed: 1,
+            total: Some(3),
+        });
+        assert_eq!(
+            state.line(),
+            format!(
+                "parse            [{}{}] 1/3 pages",
+                "#".repeat(10),
+                "-".repeat(20)
+            )
+        );
+
+        assert!(!state.apply(&ProgressEvent::RenderFinished { bytes: 1 }));
+        state.apply(&ProgressEvent::PageRendered {
+            rendered: 4,
+            total: None,
+        });
+        assert_eq!(state.line(), "parse            4 pages");
+    }
+}
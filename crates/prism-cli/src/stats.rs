@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism stats` - content statistics of one document.
+//!
+//! Counts pages, words, images, tables and annotations so that ingestion
+//! scripts can decide how to handle a file before converting it.
+
+use prism_core::document::Document;
+use prism_core::statistics::DocumentStatistics;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// Statistics of a parsed document
+#[derive(Debug, Serialize)]
+pub struct StatsReport {
+    /// Source filename
+    pub filename: Option<String>,
+    /// Detected format name
+    pub format: Option<String>,
+    /// Content counts
+    #[serde(flatten)]
+    pub statistics: DocumentStatistics,
+}
+
+impl StatsReport {
+    /// Build a report from a parsed document
+    #[must_use]
+    pub fn from_document(document: &Document) -> Self {
+        Self {
+            filename: document.source.filename.clone(),
+            format: document.source.format.as_ref().map(|f| f.name.clone()),
+            statistics: document.statistics(),
+        }
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let stats = &self.statistics;
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "{} ({})",
+            self.filename.as_deref().unwrap_or("<unnamed>"),
+            self.format.as_deref().unwrap_or("unknown format")
+        );
+        let _ = writeln!(out, "  pages: {}", stats.pages);
+        let _ = writeln!(out, "  words: {}", stats.words);
+        let _ = writeln!(out, "  characters: {}", stats.characters);
+        let _ = writeln!(
+            out,
+            "  images: {} ({} bytes)",
+            stats.images, stats.image_bytes
+        );
+        let _ = writeln!(
+            out,
+            "  tables: {} ({} cells)",
+            stats.tables, stats.table_cells
+        );
+        let _ = write!(out, "  annotations: {}", stats.annotation_count());
+        if !stats.annotations.is_empty() {
+            let counts: Vec<_> = stats
+                .annotations
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect();
+            let _ = write!(out, " ({})", counts.join(", "));
+        }
+        let _ = writeln!(out);
+        if let Some(language) = &stats.language {
+            let _ = writeln!(out, "  language: {language}");
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text() {
+        let mut document = Document::new();
+        document.source.filename = Some("report.docx".to_string());
+        let mut report = StatsReport::from_document(&document);
+        report.statistics.words = 12;
+        report
+            .statistics
+            .annotations
+            .insert("comment".to_string(), 2);
+        report.statistics.language = Some("en-US".to_string());
+
+        let text = report.render_text();
+        assert!(text.starts_with("report.docx (unknown format)\n"));
+        assert!(text.contains("  words: 12\n"));
+        assert!(text.contains("  annotations: 2 (comment 2)\n"));
+        assert!(text.contains("  language: en-US\n"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["words"], 12);
+        assert_eq!(json["annotations"]["comment"], 2);
+    }
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism verify` - digital signature report.
+//!
+//! Lists the signatures embedded in a PDF or OOXML file: who signed, when,
+//! and whether the signed content still matches. Only content digests are
+//! checked; signature values and signer certificates are not, so a
+//! matching digest does not rule out tampering.
+
+use prism_core::metadata::{DigitalSignature, SignatureKind, SignatureStatus};
+use prism_parsers::signatures;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// Signatures of one file
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    /// File name
+    pub filename: String,
+    /// Signatures in signing order
+    pub signatures: Vec<DigitalSignature>,
+}
+
+impl VerifyReport {
+    /// Find and check the signatures in `data`
+    #[must_use]
+    pub fn new(filename: impl Into<String>, data: &[u8]) -> Self {
+        Self {
+            filename: filename.into(),
+            signatures: signatures::verify(data),
+        }
+    }
+
+    /// Number of signatures whose signed content no longer matches
+    #[must_use]
+    pub fn tampered(&self) -> usize {
+        self.signatures
+            .iter()
+            .filter(|signature| signature.status == SignatureStatus::Tampered)
+            .count()
+    }
+
+    /// Render the report as plain text
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        if self.signatures.is_empty() {
+            let _ = writeln!(out, "{}: no digital signatures", self.filename);
+            return out;
+        }
+
+        let _ = writeln!(
+            out,
+            "{}: {} signature(s)",
+            self.filename,
+            self.signatures.len()
+        );
+        for (index, signature) in self.signatures.iter().enumerate() {
+            let kind = match signature.kind {
+                SignatureKind::Pdf => "PDF",
+                SignatureKind::Ooxml => "OOXML",
+            };
+            let _ = writeln!(
+                out,
+                "  {}. {kind} signature by {}",
+                index + 1,
+                signature.signer.as_deref().unwrap_or("unknown signer")
+            );
+            if let Some(signed_at) = signature.signed_at {
+                let _ = writeln!(out, "     signed: {}", signed_at.to_rfc3339());
+            }
+            if let Some(reason) = &signature.reason {
+                let _ = writeln!(out, "     reason: {reason}");
+            }
+            let _ = writeln!(out, "     status: {}", status_label(signature.status));
+            if signature.modified_after_signing {
+                let _ = writeln!(out, "     modified after signing");
+            }
+        }
+        if self
+            .signatures
+            .iter()
+            .any(|signature| signature.status == SignatureStatus::DigestMatches)
+        {
+            let _ = writeln!(
+                out,
+                "  Note: signature values are not verified; whoever edits the file can \
+                 replace the digest too, so a match does not prove it is unchanged"
+            );
+        }
+        out
+    }
+}
+
+/// Short description of a signature status
+pub(crate) fn status_label(status: SignatureStatus) -> &'static str {
+    match status {
+        SignatureStatus::DigestMatches => "content matches the signed digest",
+        SignatureStatus::Tampered => "content does not match signature",
+        SignatureStatus::Unverified => "not verified",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text() {
+        let mut report = VerifyReport::new("contract.pdf", b"%PDF-1.7\n");
+        assert!(report.signatures.is_empty());
+        assert_eq!(
+            report.render_text(),
+            "contract.pdf: no digital signatures\n"
+        );
+
+        report.signatures.push(DigitalSignature {
+            kind: SignatureKind::Pdf,
+            signer: Some("Jane Signer".to_string()),
+            signed_at: None,
+            reason: Some("Approved".to_string()),
+            status: SignatureStatus::Tampered,
+            modified_after_signing: true,
+        });
+        let text = report.render_text();
+        assert!(text.contains("1. PDF signature by Jane Signer"));
+        assert!(text.contains("reason: Approved"));
+        assert!(text.contains("status: content does not match signature"));
+        assert!(text.contains("modified after signing"));
+        assert!(!text.contains("Note:"));
+        assert_eq!(report.tampered(), 1);
+
+        report.signatures[0].status = SignatureStatus::DigestMatches;
+        let text = report.render_text();
+        assert!(text.contains("status: content matches the signed digest"));
+        assert!(text.contains("Note: signature values are not verified"));
+    }
+}
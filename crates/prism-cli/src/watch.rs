@@ -0,0 +1,438 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism watch` - continuous conversion of a directory.
+//!
+//! Watches an input directory for new or modified files and converts each
+//! one into the output directory. Bursts of filesystem events are debounced,
+//! and a state file in the output directory records the SHA-256 of every
+//! converted input so unchanged files are not reconverted across restarts.
+//! With a conversion cache, outputs are also reused across output
+//! directories and for inputs that were renamed or copied.
+
+use crate::output::OutputFormat;
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use notify::{EventKind, RecursiveMode, Watcher};
+use prism_core::cache::{CacheKey, CachedOutput, ConversionCache};
+use prism_parsers::ParserRegistry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Name of the state file kept in the output directory
+pub const STATE_FILE: &str = ".prism-watch.json";
+
+/// Patterns that are always ignored (hidden files, editor and Office temp files)
+const DEFAULT_IGNORES: &[&str] = &[".*", "*~", "*.tmp", "*.swp", "~$*"];
+
+/// How often pending events are checked against the debounce window
+const TICK: Duration = Duration::from_millis(100);
+
+/// Options for a watch session
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Directory to watch
+    pub input: PathBuf,
+    /// Directory receiving converted files
+    pub output: PathBuf,
+    /// Output format
+    pub format: OutputFormat,
+    /// Quiet period required after the last event before converting
+    pub debounce: Duration,
+    /// Additional glob patterns to ignore
+    pub ignore: Vec<String>,
+    /// Cache of converted outputs, keyed by input hash and format
+    pub cache: Option<Arc<dyn ConversionCache>>,
+}
+
+/// Hashes of converted inputs, keyed by path relative to the input directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    files: BTreeMap<String, String>,
+}
+
+impl WatchState {
+    /// Load state from disk; a missing or unreadable file starts fresh
+    #[must_use]
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write state to disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Run a watch session until interrupted with Ctrl-C
+///
+/// # Errors
+///
+/// Returns an error if the directories cannot be prepared or the
+/// filesystem watcher cannot be started.
+pub async fn run(registry: &ParserRegistry, options: WatchOptions) -> Result<()> {
+    std::fs::create_dir_all(&options.output)
+        .with_context(|| format!("Failed to create {}", options.output.display()))?;
+    let options = WatchOptions {
+        input: options
+            .input
+            .canonicalize()
+            .with_context(|| format!("Cannot watch {}", options.input.display()))?,
+        output: options.output.canonicalize()?,
+        ..options
+    };
+
+    let state_path = options.output.join(STATE_FILE);
+    let mut state = WatchState::load(&state_path);
+
+    // Catch up on anything that changed while we were not running
+    let mut converted = 0;
+    for path in list_files(&options.input) {
+        if convert_if_changed(registry, &options, &mut state, &path).await {
+            converted += 1;
+        }
+    }
+    if converted > 0 {
+        state.save(&state_path)?;
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(&options.input, RecursiveMode::Recursive)?;
+
+    info!(
+        "Watching {} -> {} ({} file(s) converted on startup)",
+        options.input.display(),
+        options.output.display(),
+        converted
+    );
+
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    let mut tick = tokio::time::interval(TICK);
+
+    loop {
+        tokio::select! {
+            Some(event) = rx.recv() => match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            pending.insert(path, Instant::now());
+                        }
+                    }
+                }
+                Err(e) => warn!("Watch error: {}", e),
+            },
+            _ = tick.tick() => {
+                let ready: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, last)| last.elapsed() >= options.debounce)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                if ready.is_empty() {
+                    continue;
+                }
+
+                let mut changed = false;
+                for path in ready {
+                    pending.remove(&path);
+                    changed |= convert_if_changed(registry, &options, &mut state, &path).await;
+                }
+                if changed {
+                    state.save(&state_path)?;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Stopping watch");
+                break;
+            }
+        }
+    }
+
+    state.save(&state_path)
+}
+
+/// Convert `path` unless it is ignored or unchanged since the last run.
+///
+/// Returns `true` if a conversion was written. Failures are logged and the
+/// file is left out of the state so it is retried on its next change.
+async fn convert_if_changed(
+    registry: &ParserRegistry,
+    options: &WatchOptions,
+    state: &mut WatchState,
+    path: &Path,
+) -> bool {
+    if !path.is_file() || path.starts_with(&options.output) {
+        return false;
+    }
+    let Ok(relative) = path.strip_prefix(&options.input) else {
+        return false;
+    };
+    if is_ignored(relative, &options.ignore) {
+        debug!("Ignoring {}", relative.display());
+        return false;
+    }
+
+    let key = relative_key(relative);
+    let target = output_path(&options.output, relative, options.format);
+
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            // Usually a file that vanished between the event and now
+            debug!("Skipping {}: {}", key, e);
+            return false;
+        }
+    };
+
+    let hash = format!("{:x}", Sha256::digest(&data));
+    if state.files.get(&key) == Some(&hash) && target.exists() {
+        debug!("Unchanged: {}", key);
+        return false;
+    }
+
+    match convert(registry, data, &hash, path, &target, options).await {
+        Ok(()) => {
+            info!("Converted {} -> {}", key, target.display());
+            state.files.insert(key, hash);
+            true
+        }
+        Err(e) => {
+            warn!("Failed to convert {}: {:#}", key, e);
+            state.files.remove(&key);
+            false
+        }
+    }
+}
+
+async fn convert(
+    registry: &ParserRegistry,
+    data: Vec<u8>,
+    hash: &str,
+    path: &Path,
+    target: &Path,
+    options: &WatchOptions,
+) -> Result<()> {
+    let filename = path.file_name().and_then(|s| s.to_str());
+    // The same bytes can parse as another format under another name
+    let detected = registry
+        .detect(&data, filename)
+        .map(|detection| detection.format.mime_type);
+    let key = CacheKey::new(hash, env!("CARGO_PKG_VERSION"), &(options.format, detected));
+    let cached = options.cache.as_ref().and_then(|cache| cache.get(&key));
+    let rendered = if let Some(cached) = cached {
+        debug!("Cache hit: {}", path.display());
+        cached.data
+    } else {
+        let document = crate::parse_bytes(registry, data, filename).await?;
+        let rendered = Bytes::from(options.format.render(&document).await?);
+        if let Some(cache) = &options.cache {
+            cache.put(&key, CachedOutput::new(rendered.clone()));
+        }
+        rendered
+    };
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, rendered)?;
+    Ok(())
+}
+
+/// Output location for an input; the original extension is kept so that
+/// `report.docx` and `report.pdf` do not overwrite each other.
+fn output_path(output: &Path, relative: &Path, format: OutputFormat) -> PathBuf {
+    let mut target = output.join(relative).into_os_string();
+    target.push(".");
+    target.push(format.extension());
+    PathBuf::from(target)
+}
+
+/// State key for a relative path (always `/`-separated)
+fn relative_key(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// List all files below `dir`
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Check a relative path against the default and user ignore patterns.
+///
+/// Patterns are matched against every path component as well as the whole
+/// `/`-separated path, so `.*` also skips files inside hidden directories.
+fn is_ignored(relative: &Path, patterns: &[String]) -> bool {
+    let key = relative_key(relative);
+    let patterns = DEFAULT_IGNORES
+        .iter()
+        .copied()
+        .chain(patterns.iter().map(String::as_str));
+
+    for pattern in patterns {
+        if glob_match(pattern, &key) {
+            return true;
+        }
+        let component_match = relative.components().any(|c| match c {
+            Component::Normal(name) => glob_match(pattern, &name.to_string_lossy()),
+            _ => false,
+        });
+        if component_match {
+            return true;
+        }
+    }
+    false
+}
+
+/// Minimal glob matching supporting `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.tmp", "report.tmp"));
+        assert!(glob_match("~$*", "~$report.docx"));
+        assert!(glob_match("drafts/*", "drafts/a.docx"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("*.tmp", "report.docx"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let user = vec!["*.log".to_string()];
+        assert!(is_ignored(Path::new(".hidden.docx"), &user));
+        assert!(is_ignored(Path::new(".git/config"), &user));
+        assert!(is_ignored(Path::new("logs/app.log"), &user));
+        assert!(!is_ignored(Path::new("docs/report.docx"), &user));
+    }
+
+    #[test]
+    fn test_output_path_keeps_extension() {
+        let target = output_path(
+            Path::new("/out"),
+            Path::new("sub/report.docx"),
+            OutputFormat::Html,
+        );
+        assert_eq!(target, PathBuf::from("/out/sub/report.docx.html"));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_files_are_skipped() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let file = input.path().join("notes.txt");
+        std::fs::write(&file, "hello watch").unwrap();
+
+        let options = WatchOptions {
+            input: input.path().to_path_buf(),
+            output: output.path().to_path_buf(),
+            format: OutputFormat::Text,
+            debounce: Duration::ZERO,
+            ignore: Vec::new(),
+            cache: None,
+        };
+        let registry = ParserRegistry::with_default_parsers();
+        let mut state = WatchState::default();
+
+        assert!(convert_if_changed(&registry, &options, &mut state, &file).await);
+        assert!(!convert_if_changed(&registry, &options, &mut state, &file).await);
+
+        std::fs::write(&file, "hello again").unwrap();
+        assert!(convert_if_changed(&registry, &options, &mut state, &file).await);
+
+        let converted = std::fs::read_to_string(output.path().join("notes.txt.txt")).unwrap();
+        assert!(converted.contains("hello again"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_covers_detected_format() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        for name in ["x.csv", "x.txt"] {
+            std::fs::write(input.path().join(name), "name,total\nwidgets,3\n").unwrap();
+        }
+
+        let options = WatchOptions {
+            input: input.path().to_path_buf(),
+            output: output.path().to_path_buf(),
+            format: OutputFormat::Json,
+            debounce: Duration::ZERO,
+            ignore: Vec::new(),
+            cache: Some(Arc::new(prism_core::cache::MemoryCache::new(
+                prism_core::cache::CacheLimits::default(),
+            ))),
+        };
+        let registry = ParserRegistry::with_default_parsers();
+        let mut state = WatchState::default();
+
+        for name in ["x.csv", "x.txt"] {
+            let file = input.path().join(name);
+            assert!(convert_if_changed(&registry, &options, &mut state, &file).await);
+        }
+
+        let csv = std::fs::read_to_string(output.path().join("x.csv.json")).unwrap();
+        let text = std::fs::read_to_string(output.path().join("x.txt.json")).unwrap();
+        assert!(csv.contains("text/csv"));
+        assert!(text.contains("text/plain"));
+    }
+}
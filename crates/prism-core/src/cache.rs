@@ -0,0 +1,542 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Conversion Cache
+//!
+//! Stores rendered output under a [`CacheKey`] made of everything that
+//! determines it: the SHA-256 of the input, the version of the code that
+//! converted it and the options it was converted with. Converting a file
+//! that was seen before - the same attachment arriving in many emails -
+//! is then a lookup instead of a parse.
+//!
+//! [`ConversionCache`] is the extension point. [`MemoryCache`] keeps
+//! entries in memory and evicts the least recently used; [`DiskCache`]
+//! keeps them in a directory that survives restarts and can be shared
+//! between processes. Both bound their total size and can expire entries
+//! after a time to live ([`CacheLimits`]).
+//!
+//! ## Example
+//!
+//! ```rust
+//! use prism_core::cache::{CacheKey, CacheLimits, CachedOutput, ConversionCache, MemoryCache};
+//! use prism_core::document::SourceInfo;
+//!
+//! let cache = MemoryCache::new(CacheLimits::default());
+//! let key = CacheKey::new(&SourceInfo::content_hash(b"input"), "1.0.0", &"html");
+//! cache.put(&key, CachedOutput::new("<html></html>"));
+//! assert!(cache.get(&key).is_some());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Extension of entry files in a [`DiskCache`]
+const ENTRY_EXTENSION: &str = "entry";
+
+/// Identity of a conversion
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Key for converting the input hashed as `input_hash` with code at
+    /// `version` and the given options
+    ///
+    /// Options are fingerprinted through their `Debug` output, which
+    /// covers every field, so any option that changes the output changes
+    /// the key.
+    #[must_use]
+    pub fn new(input_hash: &str, version: &str, options: &impl fmt::Debug) -> Self {
+        let fingerprint = format!("{input_hash}\n{version}\n{options:?}");
+        Self(format!("{:x}", Sha256::digest(fingerprint.as_bytes())))
+    }
+
+    /// The key as lowercase hex
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A cached conversion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachedOutput {
+    /// Rendered output
+    pub data: Bytes,
+    /// Small named values stored alongside, such as the content type or
+    /// the document ID
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl CachedOutput {
+    /// Output without attributes
+    #[must_use]
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            data: data.into(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Add an attribute
+    #[must_use]
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    /// Bytes counted against [`CacheLimits::max_bytes`]
+    fn size(&self) -> u64 {
+        let attributes: usize = self
+            .attributes
+            .iter()
+            .map(|(name, value)| name.len() + value.len())
+            .sum();
+        u64::try_from(self.data.len() + attributes).unwrap_or(u64::MAX)
+    }
+}
+
+/// How much a cache holds and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// Total size of the entries in bytes; the least recently used are
+    /// evicted beyond it, and larger outputs are not cached at all
+    pub max_bytes: u64,
+    /// How long an entry is served after it was stored (None = until
+    /// evicted)
+    pub ttl: Option<Duration>,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 256 * 1024 * 1024,
+            ttl: None,
+        }
+    }
+}
+
+/// What a cache currently holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Number of entries
+    pub entries: usize,
+    /// Total size of the entries in bytes
+    pub size_bytes: u64,
+}
+
+/// Store of conversion results
+///
+/// Caches are best effort: a failure to store or read an entry is logged
+/// and treated as a miss, never as a conversion error.
+pub trait ConversionCache: Send + Sync + fmt::Debug {
+    /// The output stored under `key`, unless it is missing or expired
+    fn get(&self, key: &CacheKey) -> Option<CachedOutput>;
+
+    /// Store `output` under `key`, evicting older entries as needed
+    fn put(&self, key: &CacheKey, output: CachedOutput);
+
+    /// Remove every entry and return how many there were
+    fn purge(&self) -> usize;
+
+    /// Number and size of the entries
+    fn stats(&self) -> CacheStats;
+}
+
+/// In-memory cache with least-recently-used eviction
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    limits: CacheLimits,
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    entries: HashMap<CacheKey, MemoryEntry>,
+    /// Keys by the tick of their last use, least recent first
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    size: u64,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    output: CachedOutput,
+    stored: Instant,
+    used: u64,
+}
+
+impl MemoryState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used);
+            self.size -= entry.output.size();
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Create an empty cache
+    #[must_use]
+    pub fn new(limits: CacheLimits) -> Self {
+        Self {
+            limits,
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ConversionCache for MemoryCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedOutput> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let expired = state
+            .entries
+            .get(key)
+            .map(|entry| is_expired(entry.stored.elapsed(), self.limits.ttl))?;
+        if expired {
+            state.remove(key);
+            return None;
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(key)?;
+        let previous = std::mem::replace(&mut entry.used, tick);
+        let output = entry.output.clone();
+        state.recency.remove(&previous);
+        state.recency.insert(tick, key.clone());
+        Some(output)
+    }
+
+    fn put(&self, key: &CacheKey, output: CachedOutput) {
+        let size = output.size();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.remove(key);
+        if size > self.limits.max_bytes {
+            return;
+        }
+        while state.size + size > self.limits.max_bytes {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.remove(&oldest);
+        }
+
+        state.tick += 1;
+        let used = state.tick;
+        state.size += size;
+        state.recency.insert(used, key.clone());
+        state.entries.insert(
+            key.clone(),
+            MemoryEntry {
+                output,
+                stored: Instant::now(),
+                used,
+            },
+        );
+    }
+
+    fn purge(&self) -> usize {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let purged = state.entries.len();
+        *state = MemoryState::default();
+        purged
+    }
+
+    fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        CacheStats {
+            entries: state.entries.len(),
+            size_bytes: state.size,
+        }
+    }
+}
+
+/// Cache keeping one file per entry in a directory
+///
+/// An entry is written to a temporary file and renamed into place, so
+/// concurrent readers never see a partial entry. Reading an entry updates
+/// its modification time, which orders eviction.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    limits: CacheLimits,
+}
+
+/// Header of an entry file, followed by the output
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskHeader {
+    /// Seconds since the Unix epoch when the entry was stored
+    stored: u64,
+    attributes: BTreeMap<String, String>,
+}
+
+impl DiskCache {
+    /// Use `dir` for entries, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn new(dir: impl Into<PathBuf>, limits: CacheLimits) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, limits })
+    }
+
+    /// Directory holding the entries
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{key}.{ENTRY_EXTENSION}"))
+    }
+
+    /// Entry files with their size and modification time
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != ENTRY_EXTENSION {
+                return None;
+            }
+            let metadata = fs::metadata(&path).ok()?;
+            Some((path, metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Option<CachedOutput>> {
+        let data = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed cache entry");
+        if data.len() < 4 {
+            return Err(invalid());
+        }
+        let (length, rest) = data.split_at(4);
+        let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]);
+        let length = usize::try_from(length).map_err(|_| invalid())?;
+        if rest.len() < length {
+            return Err(invalid());
+        }
+        let (header, output) = rest.split_at(length);
+        let header: DiskHeader = serde_json::from_slice(header)?;
+
+        let age = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH + Duration::from_secs(header.stored))
+            .unwrap_or_default();
+        if is_expired(age, self.limits.ttl) {
+            fs::remove_file(path)?;
+            return Ok(None);
+        }
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now())?;
+        Ok(Some(CachedOutput {
+            data: Bytes::copy_from_slice(output),
+            attributes: header.attributes,
+        }))
+    }
+
+    fn write(&self, key: &CacheKey, output: &CachedOutput) -> io::Result<()> {
+        let stored = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let header = serde_json::to_vec(&DiskHeader {
+            stored,
+            attributes: output.attributes.clone(),
+        })?;
+        let length = u32::try_from(header.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "attributes too large"))?;
+
+        let temporary = self.dir.join(format!(".{key}.{}.tmp", std::process::id()));
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(&length.to_le_bytes())?;
+        file.write_all(&header)?;
+        file.write_all(&output.data)?;
+        drop(file);
+        fs::rename(&temporary, self.entry_path(key))
+    }
+
+    /// Remove the least recently used entries other than `keep` until the
+    /// rest fit the limit
+    fn evict(&self, keep: &Path) {
+        let mut entries = self.entries();
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, len, _) in entries {
+            if size <= self.limits.max_bytes {
+                break;
+            }
+            if path != keep && fs::remove_file(&path).is_ok() {
+                size -= len;
+            }
+        }
+    }
+}
+
+impl ConversionCache for DiskCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedOutput> {
+        let path = self.entry_path(key);
+        match self.read(&path) {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!("Dropping unreadable cache entry {}: {}", path.display(), e);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: &CacheKey, output: CachedOutput) {
+        if output.size() > self.limits.max_bytes {
+            return;
+        }
+        if let Err(e) = self.write(key, &output) {
+            warn!("Failed to store cache entry {}: {}", key, e);
+            return;
+        }
+        self.evict(&self.entry_path(key));
+    }
+
+    fn purge(&self) -> usize {
+        self.entries()
+            .into_iter()
+            .filter(|(path, _, _)| fs::remove_file(path).is_ok())
+            .count()
+    }
+
+    fn stats(&self) -> CacheStats {
+        let entries = self.entries();
+        CacheStats {
+            entries: entries.len(),
+            size_bytes: entries.iter().map(|(_, len, _)| len).sum(),
+        }
+    }
+}
+
+/// Whether an entry of the given age is past the time to live
+fn is_expired(age: Duration, ttl: Option<Duration>) -> bool {
+    ttl.is_some_and(|ttl| age >= ttl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> CacheKey {
+        CacheKey::new(name, "1.0.0", &())
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let html = CacheKey::new("abc", "1.0.0", &"html");
+        assert_eq!(html, CacheKey::new("abc", "1.0.0", &"html"));
+        assert_ne!(html, CacheKey::new("abc", "1.0.0", &"jsonl"));
+        assert_ne!(html, CacheKey::new("abc", "1.0.1", &"html"));
+        assert_eq!(html.as_str().len(), 64);
+    }
+
+    #[test]
+    fn test_memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new(CacheLimits {
+            max_bytes: 10,
+            ttl: None,
+        });
+        cache.put(&key("a"), CachedOutput::new("aaaa"));
+        cache.put(&key("b"), CachedOutput::new("bbbb"));
+        assert!(cache.get(&key("a")).is_some());
+        cache.put(&key("c"), CachedOutput::new("cccc"));
+
+        assert!(cache.get(&key("b")).is_none());
+        assert_eq!(cache.get(&key("a")).unwrap().data, "aaaa");
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                entries: 2,
+                size_bytes: 8
+            }
+        );
+        cache.put(&key("d"), CachedOutput::new("too large to cache"));
+        assert!(cache.get(&key("d")).is_none());
+        assert_eq!(cache.purge(), 2);
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_memory_cache_ttl() {
+        let cache = MemoryCache::new(CacheLimits {
+            ttl: Some(Duration::ZERO),
+            ..CacheLimits::default()
+        });
+        cache.put(&key("a"), CachedOutput::new("aaaa"));
+        assert!(cache.get(&key("a")).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_disk_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"), CacheLimits::default()).unwrap();
+        let output = CachedOutput::new("<html></html>").with_attribute("content-type", "text/html");
+        cache.put(&key("a"), output.clone());
+        assert_eq!(cache.get(&key("a")), Some(output.clone()));
+        assert!(cache.get(&key("b")).is_none());
+
+        // A second handle on the same directory sees the entry
+        let shared = DiskCache::new(cache.dir(), CacheLimits::default()).unwrap();
+        assert_eq!(shared.get(&key("a")), Some(output));
+        assert_eq!(shared.stats().entries, 1);
+
+        std::fs::write(cache.entry_path(&key("c")), b"garbage").unwrap();
+        assert!(cache.get(&key("c")).is_none());
+        assert_eq!(cache.purge(), 1);
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_disk_cache_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(
+            dir.path(),
+            CacheLimits {
+                max_bytes: 100,
+                ttl: None,
+            },
+        )
+        .unwrap();
+        cache.put(&key("a"), CachedOutput::new(vec![b'a'; 60]));
+        cache.put(&key("b"), CachedOutput::new(vec![b'b'; 60]));
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.get(&key("b")).is_some());
+
+        let expiring = DiskCache::new(
+            dir.path(),
+            CacheLimits {
+                ttl: Some(Duration::ZERO),
+                ..CacheLimits::default()
+            },
+        )
+        .unwrap();
+        assert!(expiring.get(&key("b")).is_none());
+        assert_eq!(expiring.stats().entries, 0);
+    }
+}
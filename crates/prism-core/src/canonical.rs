@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Canonical text
+//!
+//! A comparison-safe rendition of a document's text, used for hashing,
+//! deduplication and deciding whether two files hold the "same document"
+//! even when they come from different formats (a DOCX and the PDF printed
+//! from it, say).
+//!
+//! The canonical form is defined as follows, and must stay stable across
+//! releases since hashes of it are persisted:
+//!
+//! 1. Pages are taken in order.
+//! 2. Within a page, blocks are read top to bottom, then left to right, when
+//!    every block is positioned; otherwise in source order. Containers are
+//!    read the same way, tables row by row and cell by cell, lists item by
+//!    item.
+//! 3. Only the text of text blocks counts; styles, list markers, images,
+//!    form fields and vector graphics are ignored.
+//! 4. Text is NFKC-normalized and invisible format characters (soft hyphen,
+//!    zero-width space, word joiner, byte order mark) are removed.
+//! 5. The result is the sequence of whitespace-separated words joined by
+//!    single spaces. Line, paragraph and page breaks are layout and are not
+//!    represented, so a paragraph wrapped differently still compares equal.
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::document::{ContentBlock, Document};
+
+/// Characters that carry no visible text and are dropped
+const INVISIBLE: &[char] = &['\u{00AD}', '\u{200B}', '\u{2060}', '\u{FEFF}'];
+
+impl Document {
+    /// Deterministic, layout- and style-independent text of the document
+    ///
+    /// See the [module documentation](crate::canonical) for the exact form.
+    #[must_use]
+    pub fn canonical_text(&self) -> String {
+        let mut canonical = String::new();
+        for page in &self.pages {
+            collect_text(&page.content, &mut canonical);
+        }
+        canonical
+    }
+}
+
+/// Canonical form of a single piece of text
+///
+/// Applies rules 4 and 5, so text obtained elsewhere (e.g. a search query)
+/// can be compared with [`Document::canonical_text`].
+#[must_use]
+pub fn canonicalize(text: &str) -> String {
+    let mut canonical = String::with_capacity(text.len());
+    append_words(text, &mut canonical);
+    canonical
+}
+
+fn append_words(text: &str, canonical: &mut String) {
+    let normalized: String = text.nfkc().filter(|c| !INVISIBLE.contains(c)).collect();
+    for word in normalized.split_whitespace() {
+        if !canonical.is_empty() {
+            canonical.push(' ');
+        }
+        canonical.push_str(word);
+    }
+}
+
+fn collect_text(blocks: &[ContentBlock], canonical: &mut String) {
+    for block in reading_order(blocks) {
+        match block {
+            // Runs are joined without a separator; a word split across runs
+            // stays one word
+            ContentBlock::Text(text) => append_words(&text.extract_text(), canonical),
+            ContentBlock::Table(table) => {
+                for cell in table.rows.iter().flat_map(|row| &row.cells) {
+                    collect_text(&cell.content, canonical);
+                }
+            }
+            ContentBlock::List(list) => {
+                for item in &list.items {
+                    collect_text(&item.content, canonical);
+                }
+            }
+            ContentBlock::Container(container) => collect_text(&container.children, canonical),
+            ContentBlock::Image(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+        }
+    }
+}
+
+/// Blocks in reading order (rule 2)
+fn reading_order(blocks: &[ContentBlock]) -> Vec<&ContentBlock> {
+    let mut ordered: Vec<&ContentBlock> = blocks.iter().collect();
+    let positions: Option<Vec<(f64, f64)>> = blocks.iter().map(position).collect();
+    if let Some(positions) = positions {
+        let mut indexed: Vec<usize> = (0..blocks.len()).collect();
+        // Stable, so blocks at the same position keep their source order
+        indexed.sort_by(|&a, &b| {
+            let ((ay, ax), (by, bx)) = (positions[a], positions[b]);
+            ay.total_cmp(&by).then(ax.total_cmp(&bx))
+        });
+        ordered = indexed.into_iter().map(|index| &blocks[index]).collect();
+    }
+    ordered
+}
+
+/// Top-left corner of a positioned block as `(y, x)`
+fn position(block: &ContentBlock) -> Option<(f64, f64)> {
+    let bounds = block.bounds();
+    (bounds.width > 0.0 && bounds.height > 0.0).then_some((bounds.y, bounds.x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Dimensions, Page, Rect, TextBlock, TextRun};
+
+    fn block(text: &str, bounds: Rect) -> ContentBlock {
+        let mut block = TextBlock::new(bounds);
+        for part in text.split('|') {
+            let mut run = TextRun::new(part);
+            run.style.bold = part.starts_with('B');
+            block.add_run(run);
+        }
+        ContentBlock::Text(block)
+    }
+
+    fn document(pages: Vec<Vec<ContentBlock>>) -> Document {
+        let mut document = Document::new();
+        for (index, content) in pages.into_iter().enumerate() {
+            let mut page = Page::new(u32::try_from(index + 1).unwrap(), Dimensions::LETTER);
+            page.content = content;
+            document.pages.push(page);
+        }
+        document
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        assert_eq!(
+            canonicalize("  ﬁnal\u{00A0}\tre\u{00AD}port \n"),
+            "final report"
+        );
+        assert_eq!(canonicalize("Ｆｕｌｌ width"), "Full width");
+        // Composed and decomposed forms compare equal
+        assert_eq!(canonicalize("cafe\u{0301}"), canonicalize("caf\u{00E9}"));
+        assert_eq!(canonicalize("\u{200B}\n"), "");
+    }
+
+    #[test]
+    fn test_same_text_across_layouts() {
+        // A flowing document, as parsed from a word processor file
+        let flow = document(vec![vec![
+            block("Quarterly ", Rect::default()),
+            block("Bold |results are in.", Rect::default()),
+            block("", Rect::default()),
+        ]]);
+        // The same text positioned out of order and split over two pages
+        let positioned = document(vec![
+            vec![
+                block("Bold results", Rect::new(72.0, 120.0, 200.0, 14.0)),
+                block("Quarterly", Rect::new(72.0, 90.0, 200.0, 14.0)),
+            ],
+            vec![block("are  in.", Rect::new(72.0, 72.0, 200.0, 14.0))],
+        ]);
+
+        assert_eq!(flow.canonical_text(), "Quarterly Bold results are in.");
+        assert_eq!(positioned.canonical_text(), flow.canonical_text());
+    }
+}
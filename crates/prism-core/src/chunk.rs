@@ -0,0 +1,526 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Chunking
+//!
+//! Splits a document into overlapping text chunks for retrieval-augmented
+//! generation and search indexing, instead of cutting
+//! [`Document::extract_text`] at arbitrary offsets.
+//!
+//! Chunks follow the document's logical structure:
+//!
+//! - A new section (a chapter, sheet or message, see
+//!   [`DocumentStructure::sections`](crate::document::DocumentStructure))
+//!   always starts a new chunk. Documents without sections are split at
+//!   their headings.
+//! - Paragraphs, list items and table rows are never split unless one alone
+//!   exceeds the budget, in which case it is split between words.
+//! - A block that fits in a chunk of its own is not spread over two: a
+//!   table that does not fit in the rest of a chunk starts the next.
+//!
+//! When a chunk is full, the next one repeats its last paragraphs or rows
+//! up to [`ChunkOptions::overlap`], so context carries over. Every chunk
+//! records where it came from: its pages, its bounds on each page with
+//! positioned content, and the titles of the sections it belongs to.
+
+use serde::Serialize;
+
+use crate::document::{ContentBlock, ContentPosition, Document, Rect, Section, TableBlock};
+
+/// Characters per token when sizes are given in tokens
+///
+/// Close to the average for English text with common tokenizers.
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// A chunk size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSize {
+    /// Number of characters
+    Characters(usize),
+    /// Estimated number of tokens, at [`CHARS_PER_TOKEN`]
+    Tokens(usize),
+}
+
+impl ChunkSize {
+    fn chars(self) -> usize {
+        match self {
+            Self::Characters(chars) => chars,
+            Self::Tokens(tokens) => tokens.saturating_mul(CHARS_PER_TOKEN),
+        }
+    }
+}
+
+/// How to split a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Largest chunk
+    pub max_size: ChunkSize,
+    /// How much of the end of a full chunk to repeat at the start of the
+    /// next; kept below half of `max_size`
+    pub overlap: ChunkSize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_size: ChunkSize::Tokens(512),
+            overlap: ChunkSize::Tokens(64),
+        }
+    }
+}
+
+/// A piece of the document's text with its provenance
+#[derive(Debug, Clone, Serialize)]
+pub struct Chunk {
+    /// Position of the chunk in the document, from 0
+    pub index: usize,
+    /// Text: paragraphs separated by blank lines, list items and table rows
+    /// by newlines, table cells by ` | `
+    pub text: String,
+    /// Pages the text comes from, in order
+    pub pages: Vec<u32>,
+    /// Area covered on each page with positioned content
+    pub regions: Vec<ChunkRegion>,
+    /// Titles of the sections holding the chunk, outermost first
+    pub heading_path: Vec<String>,
+    /// Position of the first top-level block
+    pub start: ContentPosition,
+    /// Position just past the last top-level block
+    pub end: ContentPosition,
+}
+
+/// The area a chunk covers on one page
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ChunkRegion {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Union of the bounds of the chunk's blocks on the page
+    pub bounds: Rect,
+}
+
+/// Split `document` into chunks
+#[must_use]
+pub fn chunk(document: &Document, options: &ChunkOptions) -> Vec<Chunk> {
+    split_document(document, Chunker::new(options), false)
+}
+
+/// One chunk per top-level block with text, whatever its size
+///
+/// For indexing blocks individually; chunks have the same provenance as
+/// those of [`chunk`].
+#[must_use]
+pub fn block_chunks(document: &Document) -> Vec<Chunk> {
+    let options = ChunkOptions {
+        max_size: ChunkSize::Characters(usize::MAX),
+        overlap: ChunkSize::Characters(0),
+    };
+    split_document(document, Chunker::new(&options), true)
+}
+
+fn split_document(document: &Document, mut chunker: Chunker, per_block: bool) -> Vec<Chunk> {
+    let derived;
+    let sections = if document.structure.sections.is_empty() {
+        derived = Section::from_headings(&document.pages);
+        &derived
+    } else {
+        &document.structure.sections
+    };
+
+    let mut current: Vec<&Section> = Vec::new();
+    for page in &document.pages {
+        for (index, block) in page.content.iter().enumerate() {
+            let position = ContentPosition::new(page.number, index);
+            let path = section_path(sections, position);
+            let same = path.len() == current.len()
+                && path.iter().zip(&current).all(|(a, b)| std::ptr::eq(*a, *b));
+            if !same {
+                chunker.finish(false);
+                chunker.heading_path = path.iter().map(|section| section.title.clone()).collect();
+                current = path;
+            }
+
+            let mut lines = Vec::new();
+            block_lines(block, "\n\n", &mut lines);
+            let bounds = Some(block.bounds()).filter(|b| b.width > 0.0 && b.height > 0.0);
+            chunker.block(&lines, position, bounds);
+            if per_block {
+                chunker.finish(false);
+            }
+        }
+    }
+    chunker.finish(false);
+    chunker.chunks
+}
+
+/// Sections holding `position`, outermost first
+fn section_path(sections: &[Section], position: ContentPosition) -> Vec<&Section> {
+    let mut path = Vec::new();
+    let mut level = sections;
+    while let Some(section) = level.iter().find(|section| section.contains(position)) {
+        path.push(section);
+        level = &section.children;
+    }
+    path
+}
+
+/// Text of a block as lines that chunks may be split between, each with
+/// the separator that goes before it
+fn block_lines(
+    block: &ContentBlock,
+    separator: &'static str,
+    lines: &mut Vec<(&'static str, String)>,
+) {
+    let mut push = |separator, text: String| {
+        let text = text.trim();
+        if !text.is_empty() {
+            lines.push((separator, text.to_string()));
+        }
+    };
+    match block {
+        ContentBlock::Text(text) => push(separator, text.extract_text()),
+        ContentBlock::Table(table) => {
+            for (index, row) in table_rows(table).into_iter().enumerate() {
+                push(if index == 0 { separator } else { "\n" }, row);
+            }
+        }
+        ContentBlock::List(list) => {
+            for (index, item) in list.extract_text().lines().enumerate() {
+                push(if index == 0 { separator } else { "\n" }, item.to_string());
+            }
+        }
+        ContentBlock::FormField(field) => {
+            let value = field.extract_text();
+            if !value.is_empty() {
+                let label = field.label.as_deref().unwrap_or(&field.name);
+                push(separator, format!("{label}: {value}"));
+            }
+        }
+        ContentBlock::Image(image) => push(separator, image.alt_text.clone().unwrap_or_default()),
+        ContentBlock::Container(container) => {
+            for child in &container.children {
+                let separator = if lines.is_empty() { separator } else { "\n\n" };
+                block_lines(child, separator, lines);
+            }
+        }
+        ContentBlock::Vector(_) => {}
+    }
+}
+
+fn table_rows(table: &TableBlock) -> Vec<String> {
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .map(|cell| {
+                    cell.extract_text()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+        .collect()
+}
+
+/// A line of text placed in a chunk
+#[derive(Debug, Clone)]
+struct Piece {
+    separator: &'static str,
+    text: String,
+    position: ContentPosition,
+    bounds: Option<Rect>,
+}
+
+impl Piece {
+    fn chars(&self) -> usize {
+        self.separator.chars().count() + self.text.chars().count()
+    }
+}
+
+/// Packs pieces into chunks
+struct Chunker {
+    max_chars: usize,
+    overlap_chars: usize,
+    heading_path: Vec<String>,
+    pieces: Vec<Piece>,
+    chars: usize,
+    chunks: Vec<Chunk>,
+}
+
+impl Chunker {
+    fn new(options: &ChunkOptions) -> Self {
+        let max_chars = options.max_size.chars().max(1);
+        Self {
+            max_chars,
+            overlap_chars: options.overlap.chars().min(max_chars / 2),
+            heading_path: Vec::new(),
+            pieces: Vec::new(),
+            chars: 0,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Add the lines of a block
+    fn block(
+        &mut self,
+        lines: &[(&'static str, String)],
+        position: ContentPosition,
+        bounds: Option<Rect>,
+    ) {
+        let total: usize = lines
+            .iter()
+            .map(|(separator, text)| separator.chars().count() + text.chars().count())
+            .sum();
+        // Keep a block that fits in a chunk of its own in one chunk
+        if total <= self.max_chars && self.chars.saturating_add(total) > self.max_chars {
+            self.finish(true);
+        }
+        for (separator, text) in lines {
+            for (index, part) in split(text, self.max_chars).into_iter().enumerate() {
+                self.push(Piece {
+                    separator: if index == 0 { separator } else { " " },
+                    text: part,
+                    position,
+                    bounds,
+                });
+            }
+        }
+    }
+
+    fn push(&mut self, piece: Piece) {
+        if !self.pieces.is_empty() && self.chars.saturating_add(piece.chars()) > self.max_chars {
+            self.finish(true);
+            // Drop overlap that leaves no room for the piece
+            while !self.pieces.is_empty()
+                && self.chars.saturating_add(piece.chars()) > self.max_chars
+            {
+                let dropped = self.pieces.remove(0);
+                self.chars -= dropped.chars();
+            }
+        }
+        self.chars += piece.chars();
+        self.pieces.push(piece);
+    }
+
+    /// Close the open chunk, starting the next with its overlap if `overlap`
+    fn finish(&mut self, overlap: bool) {
+        if self.pieces.is_empty() {
+            return;
+        }
+        let pieces = std::mem::take(&mut self.pieces);
+        self.chars = 0;
+        self.chunks.push(self.build(&pieces));
+
+        if overlap && self.overlap_chars > 0 {
+            let mut kept = 0;
+            let start = pieces
+                .iter()
+                .rposition(|piece| {
+                    kept += piece.chars();
+                    kept > self.overlap_chars
+                })
+                .map_or(0, |index| index + 1);
+            self.pieces = pieces[start..].to_vec();
+            self.chars = self.pieces.iter().map(Piece::chars).sum();
+        }
+    }
+
+    fn build(&self, pieces: &[Piece]) -> Chunk {
+        let mut text = String::new();
+        let mut pages: Vec<u32> = Vec::new();
+        let mut regions: Vec<ChunkRegion> = Vec::new();
+        for (index, piece) in pieces.iter().enumerate() {
+            if index > 0 {
+                text.push_str(piece.separator);
+            }
+            text.push_str(&piece.text);
+            let page = piece.position.page;
+            if pages.last() != Some(&page) {
+                pages.push(page);
+            }
+            if let Some(bounds) = piece.bounds {
+                match regions.iter_mut().find(|region| region.page == page) {
+                    Some(region) => region.bounds = region.bounds.union(&bounds),
+                    None => regions.push(ChunkRegion { page, bounds }),
+                }
+            }
+        }
+        let start = pieces
+            .first()
+            .map_or(ContentPosition::new(1, 0), |piece| piece.position);
+        let last = pieces.last().map_or(start, |piece| piece.position);
+        Chunk {
+            index: self.chunks.len(),
+            text,
+            pages,
+            regions,
+            heading_path: self.heading_path.clone(),
+            start,
+            end: ContentPosition::new(last.page, last.block + 1),
+        }
+    }
+}
+
+/// Split `text` between words into parts of at most `max_chars`, cutting
+/// words longer than that
+fn split(text: &str, max_chars: usize) -> Vec<String> {
+    if text.chars().count() <= max_chars {
+        return vec![text.to_string()];
+    }
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut part_chars = 0;
+    for word in text.split_whitespace() {
+        let word_chars = word.chars().count();
+        if part_chars > 0 && part_chars + 1 + word_chars > max_chars {
+            parts.push(std::mem::take(&mut part));
+            part_chars = 0;
+        }
+        if word_chars > max_chars {
+            let chars: Vec<char> = word.chars().collect();
+            for piece in chars.chunks(max_chars) {
+                parts.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if part_chars > 0 {
+            part.push(' ');
+            part_chars += 1;
+        }
+        part.push_str(word);
+        part_chars += word_chars;
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Dimensions, Page, TableCell, TableRow, TextBlock, TextRun};
+
+    fn paragraph(text: &str, bounds: Rect) -> ContentBlock {
+        let mut block = TextBlock::new(bounds);
+        block.add_run(TextRun::new(text));
+        ContentBlock::Text(block)
+    }
+
+    fn table(rows: &[[&str; 2]]) -> ContentBlock {
+        let mut table = TableBlock::new(Rect::default(), 2);
+        for row in rows {
+            table.add_row(TableRow {
+                cells: row
+                    .iter()
+                    .map(|text| TableCell {
+                        content: vec![paragraph(text, Rect::default())],
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    })
+                    .collect(),
+                height: None,
+            });
+        }
+        ContentBlock::Table(table)
+    }
+
+    fn options(max: usize, overlap: usize) -> ChunkOptions {
+        ChunkOptions {
+            max_size: ChunkSize::Characters(max),
+            overlap: ChunkSize::Characters(overlap),
+        }
+    }
+
+    #[test]
+    fn test_chunks_follow_sections() {
+        let mut document = Document::builder()
+            .add_text_page("Intro", "Short text.")
+            .add_text_page("Data", "Figures follow.")
+            .build();
+        document.pages[1].add_content(table(&[["Year", "Sales"], ["2024", "10"]]));
+
+        let chunks = chunk(&document, &ChunkOptions::default());
+        let summary: Vec<_> = chunks
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.heading_path.clone(),
+                    chunk.text.as_str(),
+                    chunk.pages.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (vec!["Intro".to_string()], "Intro\n\nShort text.", vec![1]),
+                (
+                    vec!["Data".to_string()],
+                    "Data\n\nFigures follow.\n\nYear | Sales\n2024 | 10",
+                    vec![2]
+                ),
+            ]
+        );
+        assert_eq!(chunks[1].start, ContentPosition::new(2, 0));
+        assert_eq!(chunks[1].end, ContentPosition::new(2, 3));
+    }
+
+    #[test]
+    fn test_budget_and_overlap() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(paragraph("one two three", Rect::new(0.0, 0.0, 100.0, 10.0)));
+        page.add_content(paragraph("four five", Rect::new(0.0, 20.0, 100.0, 10.0)));
+        page.add_content(paragraph(
+            "six seven eight",
+            Rect::new(0.0, 40.0, 100.0, 10.0),
+        ));
+        let document = Document::builder().page(page).build();
+
+        let chunks = chunk(&document, &options(30, 12));
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["one two three\n\nfour five", "four five\n\nsix seven eight"]
+        );
+        let bounds = chunks[1].regions[0].bounds;
+        assert!((bounds.y - 20.0).abs() < 1e-9 && (bounds.height - 30.0).abs() < 1e-9);
+        assert!(chunks[0].heading_path.is_empty());
+    }
+
+    #[test]
+    fn test_tables_start_a_chunk() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(paragraph("Some leading text", Rect::default()));
+        page.add_content(table(&[["a", "b"], ["c", "d"], ["e", "f"]]));
+        let document = Document::builder().page(page).build();
+
+        let texts: Vec<_> = chunk(&document, &options(30, 0))
+            .into_iter()
+            .map(|chunk| chunk.text)
+            .collect();
+        assert_eq!(texts, ["Some leading text", "a | b\nc | d\ne | f"]);
+    }
+
+    #[test]
+    fn test_block_chunks() {
+        let mut document = Document::builder()
+            .add_text_page("Intro", "First.\n\nSecond.")
+            .build();
+        document.pages[0].add_content(table(&[["a", "b"]]));
+
+        let chunks = block_chunks(&document);
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, ["Intro", "First.", "Second.", "a | b"]);
+        assert_eq!(chunks[3].start, ContentPosition::new(1, 3));
+        assert_eq!(chunks[3].heading_path, ["Intro"]);
+    }
+
+    #[test]
+    fn test_split_long_paragraph() {
+        assert_eq!(split("alpha beta gamma", 11), ["alpha beta", "gamma"]);
+        assert_eq!(split("abcdefgh", 3), ["abc", "def", "gh"]);
+    }
+}
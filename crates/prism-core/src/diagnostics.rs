@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Diagnostics
+//!
+//! Problems a parser worked around instead of failing. With
+//! [`ParseOptions::lenient`](crate::parser::ParseOptions::lenient) set,
+//! parsers recover what they can from damaged files (a truncated ZIP, one
+//! slide with malformed XML) and record what went wrong in
+//! [`Document::diagnostics`](crate::document::Document::diagnostics), so
+//! callers can tell a complete conversion from a partial one.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::{Error, ErrorCode, ErrorLocation};
+
+/// How much a problem affected the result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Content was recovered, possibly approximated
+    Warning,
+    /// Content was lost
+    Error,
+}
+
+/// A problem found while processing a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// How much the problem affected the result
+    pub severity: Severity,
+    /// Class of the problem
+    pub code: ErrorCode,
+    /// Human-readable description
+    pub message: String,
+    /// Where in the file the problem was found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<ErrorLocation>,
+}
+
+impl Diagnostic {
+    /// A problem that was worked around
+    pub fn warning(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// A problem that lost content
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..Self::warning(code, message)
+        }
+    }
+
+    /// Attach a location
+    #[must_use]
+    pub fn at(mut self, location: ErrorLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: error.code(),
+            message: error.to_string(),
+            location: error.location().cloned(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity} {}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let error = Error::corrupt("PPTX", "unexpected end of file")
+            .at(ErrorLocation::part("ppt/slides/slide2.xml"));
+        let diagnostic = Diagnostic::from(&error);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.code, ErrorCode::CorruptFile);
+        assert_eq!(
+            diagnostic.location.as_ref().and_then(|l| l.part.as_deref()),
+            Some("ppt/slides/slide2.xml")
+        );
+        assert!(diagnostic
+            .to_string()
+            .starts_with("error E1201: Corrupt PPTX file"));
+
+        let json = serde_json::to_value(Diagnostic::warning(ErrorCode::ParseError, "x")).unwrap();
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["code"], "parse_error");
+        assert!(json.get("location").is_none());
+    }
+}
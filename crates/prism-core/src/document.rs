@@ -23,19 +23,26 @@
 //! │   │   ├── Text (runs, styles, positions)
 //! │   │   ├── Images (embedded, linked)
 //! │   │   ├── Tables (rows, cols, cells)
+//! │   │   ├── Lists (items, nesting, markers)
+//! │   │   ├── Form fields (inputs, checkboxes, choices, signatures)
 //! │   │   └── Vectors (paths, shapes)
 //! │   └── Annotations
 //! ├── Styles (fonts, colors, paragraph styles)
 //! ├── Resources (fonts, images, embeddings)
-//! └── Structure (headings, TOC, bookmarks)
+//! └── Structure (headings, TOC, bookmarks, sections)
 //! ```
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::diagnostics::Diagnostic;
 use crate::format::Format;
 use crate::metadata::Metadata;
+use crate::timing::StageTiming;
+use crate::validate::ValidationErrors;
 
 /// A parsed document in the Unified Document Model format.
 ///
@@ -65,6 +72,18 @@ pub struct Document {
 
     /// Embedded files/attachments
     pub attachments: Vec<Attachment>,
+
+    /// Problems the parser recovered from (lenient parsing)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Tracked changes and comments, in document order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<Revision>,
+
+    /// How long each parse stage took, for parsers that time them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<StageTiming>,
 }
 
 impl Document {
@@ -80,7 +99,25 @@ impl Document {
             resources: ResourceStore::default(),
             structure: DocumentStructure::default(),
             attachments: Vec::new(),
+            diagnostics: Vec::new(),
+            revisions: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    /// Deterministic ID for content with the given [`SourceInfo::hash`]
+    ///
+    /// The same bytes always yield the same ID, so documents can be
+    /// deduplicated or cached by ID. Returns `None` if `hash` is not a
+    /// hex-encoded digest of at least 128 bits.
+    #[must_use]
+    pub fn content_id(hash: &str) -> Option<Uuid> {
+        let hex = hash.get(..32)?;
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
         }
+        Some(uuid::Builder::from_custom_bytes(bytes).into_uuid())
     }
 
     /// Create a document builder for fluent construction
@@ -114,6 +151,26 @@ impl Document {
             .join("\n\n")
     }
 
+    /// Top-level blocks of `section`, with their page numbers
+    pub fn section_blocks<'a>(
+        &'a self,
+        section: &'a Section,
+    ) -> impl Iterator<Item = (u32, &'a ContentBlock)> + 'a {
+        self.pages
+            .iter()
+            .filter(move |page| (section.start.page..=section.end.page).contains(&page.number))
+            .flat_map(move |page| {
+                page.content
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, block)| {
+                        section
+                            .contains(ContentPosition::new(page.number, index))
+                            .then_some((page.number, block))
+                    })
+            })
+    }
+
     /// Get the total word count
     #[must_use]
     pub fn word_count(&self) -> usize {
@@ -161,11 +218,94 @@ impl DocumentBuilder {
         self
     }
 
+    /// Add a page holding a `Heading 1` title followed by `text`
+    ///
+    /// Paragraphs in `text` are separated by blank lines. The page is
+    /// numbered after the pages added so far, and the title is listed as a
+    /// level-one heading.
+    #[must_use]
+    pub fn add_text_page(mut self, title: &str, text: &str) -> Self {
+        let number = u32::try_from(self.document.pages.len() + 1).unwrap_or(u32::MAX);
+        let mut page = Page::new(number, Dimensions::LETTER);
+
+        let mut heading = TextBlock::new(Rect::default());
+        heading.paragraph_style = Some("Heading 1".to_string());
+        heading.add_run(TextRun::new(title));
+        page.add_content(ContentBlock::Text(heading));
+
+        for paragraph in text.split("\n\n") {
+            let paragraph = paragraph.trim();
+            if paragraph.is_empty() {
+                continue;
+            }
+            let mut block = TextBlock::new(Rect::default());
+            block.add_run(TextRun::new(paragraph));
+            page.add_content(ContentBlock::Text(block));
+        }
+
+        self.document.pages.push(page);
+        self.heading(1, title, number)
+    }
+
+    /// Add an embedded file
+    #[must_use]
+    pub fn attach(mut self, file: Attachment) -> Self {
+        self.document.attachments.push(file);
+        self
+    }
+
+    /// Add an image resource for image blocks to reference
+    #[must_use]
+    pub fn resource(mut self, image: ImageResource) -> Self {
+        self.document.resources.images.push(image);
+        self
+    }
+
+    /// List a heading in the document structure
+    #[must_use]
+    pub fn heading(mut self, level: u8, text: &str, page: u32) -> Self {
+        self.document.structure.headings.push(Heading {
+            text: text.to_string(),
+            level,
+            page,
+            bounds: None,
+        });
+        self
+    }
+
+    /// List a logical section in the document structure
+    #[must_use]
+    pub fn section(mut self, section: Section) -> Self {
+        self.document.structure.sections.push(section);
+        self
+    }
+
+    /// Check the document assembled so far
+    ///
+    /// See [`validate`](crate::validate::validate) for the rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns every structural problem found.
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        crate::validate::validate(&self.document)
+    }
+
     /// Build the final document
     #[must_use]
     pub fn build(self) -> Document {
         self.document
     }
+
+    /// Validate and build the final document
+    ///
+    /// # Errors
+    ///
+    /// Returns every structural problem found.
+    pub fn try_build(self) -> std::result::Result<Document, ValidationErrors> {
+        self.validate()?;
+        Ok(self.document)
+    }
 }
 
 /// Information about the source file
@@ -180,13 +320,22 @@ pub struct SourceInfo {
     /// File size in bytes
     pub size: Option<u64>,
 
-    /// Hash of the original content (for verification)
+    /// Hash of the original content (for verification), as lowercase hex
+    /// SHA-256; see [`SourceInfo::content_hash`]
     pub hash: Option<String>,
 
     /// When the document was parsed
     pub parsed_at: Option<DateTime<Utc>>,
 }
 
+impl SourceInfo {
+    /// The hash recorded in [`SourceInfo::hash`] for `data`
+    #[must_use]
+    pub fn content_hash(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+}
+
 /// A single page in the document
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
@@ -219,14 +368,17 @@ impl Page {
         }
     }
 
-    /// Extract all text from this page
+    /// Extract all text from this page, in
+    /// [reading order](Page::reading_order)
     #[must_use]
     pub fn extract_text(&self) -> String {
-        self.content
-            .iter()
+        self.blocks_in_reading_order()
+            .into_iter()
             .filter_map(|block| match block {
                 ContentBlock::Text(text) => Some(text.extract_text()),
                 ContentBlock::Table(table) => Some(table.extract_text()),
+                ContentBlock::List(list) => Some(list.extract_text()),
+                ContentBlock::FormField(field) => Some(field.extract_text()),
                 _ => None,
             })
             .collect::<Vec<_>>()
@@ -240,7 +392,7 @@ impl Page {
 }
 
 /// Page dimensions
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Dimensions {
     /// Width in points (1 point = 1/72 inch)
     pub width: f64,
@@ -306,6 +458,12 @@ pub enum ContentBlock {
     /// Table content
     Table(TableBlock),
 
+    /// Bulleted or numbered list
+    List(ListBlock),
+
+    /// Interactive form field
+    FormField(FormFieldBlock),
+
     /// Vector graphics
     Vector(VectorBlock),
 
@@ -313,6 +471,36 @@ pub enum ContentBlock {
     Container(ContainerBlock),
 }
 
+impl ContentBlock {
+    /// Bounding box of the block on its page; empty for flowing content
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Self::Text(b) => b.bounds,
+            Self::Image(b) => b.bounds,
+            Self::Table(b) => b.bounds,
+            Self::List(b) => b.bounds,
+            Self::FormField(b) => b.bounds,
+            Self::Vector(b) => b.bounds,
+            Self::Container(b) => b.bounds,
+        }
+    }
+
+    /// Name of the block type, e.g. `text` or `form_field`
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "text",
+            Self::Image(_) => "image",
+            Self::Table(_) => "table",
+            Self::List(_) => "list",
+            Self::FormField(_) => "form_field",
+            Self::Vector(_) => "vector",
+            Self::Container(_) => "container",
+        }
+    }
+}
+
 /// Visual style for a shape or block
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ShapeStyle {
@@ -322,6 +510,57 @@ pub struct ShapeStyle {
     pub stroke_color: Option<String>,
     /// Stroke width in points
     pub stroke_width: Option<f64>,
+    /// Gradient, picture or pattern fill, drawn instead of `fill_color`
+    /// where supported; `fill_color` then holds a representative color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill: Option<Fill>,
+}
+
+/// A fill that is not a single color
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fill {
+    /// Blend between colors
+    Gradient(GradientFill),
+
+    /// An image resource
+    Picture {
+        /// Resource ID of the image
+        resource_id: String,
+        /// Whether the image is repeated rather than stretched
+        tile: bool,
+    },
+
+    /// A two-color pattern
+    Pattern {
+        /// Preset name, e.g. `dkDnDiag` or `smGrid`
+        preset: String,
+        /// Color of the lines or dots
+        foreground: String,
+        /// Color behind them
+        background: String,
+    },
+}
+
+/// A linear or radial gradient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradientFill {
+    /// Color stops, by position
+    pub stops: Vec<GradientStop>,
+    /// Direction of a linear gradient in degrees, clockwise from left to
+    /// right
+    pub angle: f64,
+    /// Whether the gradient spreads out from the center instead
+    pub radial: bool,
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position from 0.0 (start) to 1.0 (end)
+    pub position: f64,
+    /// Color at this position
+    pub color: String,
 }
 
 /// A block of text content
@@ -343,6 +582,10 @@ pub struct TextBlock {
     /// Rotation in degrees
     #[serde(default)]
     pub rotation: f64,
+
+    /// Base direction of the paragraph
+    #[serde(default)]
+    pub direction: TextDirection,
 }
 
 impl TextBlock {
@@ -355,6 +598,7 @@ impl TextBlock {
             paragraph_style: None,
             style: ShapeStyle::default(),
             rotation: 0.0,
+            direction: TextDirection::Auto,
         }
     }
 
@@ -368,17 +612,31 @@ impl TextBlock {
     pub fn extract_text(&self) -> String {
         self.runs
             .iter()
-            .map(|run| run.text.as_str())
+            .map(|run| &*run.text)
             .collect::<Vec<_>>()
             .join("")
     }
 }
 
+/// Base (paragraph) text direction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextDirection {
+    /// Not specified; taken from the first strongly directional character
+    #[default]
+    Auto,
+
+    /// Left to right (Latin, Cyrillic, CJK, ...)
+    Ltr,
+
+    /// Right to left (Arabic, Hebrew, ...)
+    Rtl,
+}
+
 /// A run of text with consistent styling
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextRun {
     /// The text content
-    pub text: String,
+    pub text: Arc<str>,
 
     /// Style for this run
     pub style: TextStyle,
@@ -388,37 +646,93 @@ pub struct TextRun {
 
     /// Individual character positions (for precise selection/highlighting)
     pub char_positions: Option<Vec<Point>>,
+
+    /// Hyperlink target, if the run is a link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<Link>,
+
+    /// Tracked change the run was inserted or deleted by, when changes are
+    /// kept as markup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<RevisionMark>,
 }
 
 impl TextRun {
     /// Create a new text run with default styling
     #[must_use]
-    pub fn new(text: impl Into<String>) -> Self {
+    pub fn new(text: impl Into<Arc<str>>) -> Self {
         Self {
             text: text.into(),
             style: TextStyle::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
 
     /// Create a text run with specific style
     #[must_use]
-    pub fn with_style(text: impl Into<String>, style: TextStyle) -> Self {
+    pub fn with_style(text: impl Into<Arc<str>>, style: TextStyle) -> Self {
         Self {
             text: text.into(),
             style,
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
+        }
+    }
+
+    /// Make the run a link to `link`
+    #[must_use]
+    pub fn with_link(mut self, link: Link) -> Self {
+        self.link = Some(link);
+        self
+    }
+}
+
+/// Target of a hyperlink
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Link {
+    /// An external URL
+    Url(String),
+    /// A named anchor in the document (a bookmark or element id)
+    Anchor(String),
+    /// A page of the document (1-based)
+    Page(u32),
+}
+
+impl Link {
+    /// The target of an `href`, the inverse of [`Link::href`]: `#page-N` is
+    /// a page, any other fragment an anchor and anything else a URL
+    #[must_use]
+    pub fn from_href(href: &str) -> Self {
+        let Some(anchor) = href.strip_prefix('#') else {
+            return Self::Url(href.to_string());
+        };
+        match anchor.strip_prefix("page-").and_then(|n| n.parse().ok()) {
+            Some(page) => Self::Page(page),
+            None => Self::Anchor(anchor.to_string()),
+        }
+    }
+
+    /// The link as an `href`: the URL, `#anchor` or `#page-N`
+    #[must_use]
+    pub fn href(&self) -> String {
+        match self {
+            Self::Url(url) => url.clone(),
+            Self::Anchor(anchor) => format!("#{anchor}"),
+            Self::Page(page) => format!("#page-{page}"),
         }
     }
 }
 
 /// Text styling properties
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TextStyle {
     /// Font family name
-    pub font_family: Option<String>,
+    pub font_family: Option<Arc<str>>,
 
     /// Font size in points
     pub font_size: Option<f64>,
@@ -436,10 +750,15 @@ pub struct TextStyle {
     pub strikethrough: bool,
 
     /// Text color (hex or named)
-    pub color: Option<String>,
+    pub color: Option<Arc<str>>,
 
     /// Background/highlight color
-    pub background_color: Option<String>,
+    pub background_color: Option<Arc<str>>,
+
+    /// Language of the text as a BCP 47 tag (e.g. `ar-SA`, `ja-JP`), which
+    /// selects script-specific glyphs and line breaking
+    #[serde(default)]
+    pub language: Option<Arc<str>>,
 }
 
 /// An image block
@@ -467,6 +786,44 @@ pub struct ImageBlock {
     /// Rotation in degrees
     #[serde(default)]
     pub rotation: f64,
+
+    /// Part of the image shown; `None` shows all of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<ImageCrop>,
+
+    /// Whether the image is mirrored left to right
+    #[serde(default)]
+    pub flip_horizontal: bool,
+
+    /// Whether the image is mirrored top to bottom
+    #[serde(default)]
+    pub flip_vertical: bool,
+}
+
+/// Edges cut off an image, as fractions of its width and height
+///
+/// Negative fractions add empty room around the image instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImageCrop {
+    /// Fraction cut off the left edge
+    pub left: f64,
+
+    /// Fraction cut off the top edge
+    pub top: f64,
+
+    /// Fraction cut off the right edge
+    pub right: f64,
+
+    /// Fraction cut off the bottom edge
+    pub bottom: f64,
+}
+
+impl ImageCrop {
+    /// Whether nothing is cut off
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.left == 0.0 && self.top == 0.0 && self.right == 0.0 && self.bottom == 0.0
+    }
 }
 
 /// A table block
@@ -566,6 +923,276 @@ impl TableCell {
     }
 }
 
+/// A bulleted or numbered list
+///
+/// Items are stored flat in reading order; nesting is given by each item's
+/// [`level`](ListItem::level), so a sub-list follows the item it belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListBlock {
+    /// Bounding box on the page
+    pub bounds: Rect,
+
+    /// List items
+    pub items: Vec<ListItem>,
+}
+
+impl ListBlock {
+    /// Create a new empty list
+    #[must_use]
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            items: Vec::new(),
+        }
+    }
+
+    /// Add an item to the list
+    pub fn add_item(&mut self, item: ListItem) {
+        self.items.push(item);
+    }
+
+    /// Extract text from the list, one item per line, each starting with its
+    /// marker
+    #[must_use]
+    pub fn extract_text(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| {
+                let text = item.extract_text();
+                if item.marker.is_empty() {
+                    text
+                } else {
+                    format!("{} {text}", item.marker)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An item of a [`ListBlock`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListItem {
+    /// Content of the item, usually a single text block, positioned
+    /// relative to the list
+    pub content: Vec<ContentBlock>,
+
+    /// Nesting depth, 0 for top-level items
+    pub level: u8,
+
+    /// Whether the item is numbered
+    pub ordered: bool,
+
+    /// How the marker is drawn
+    pub marker_style: ListMarker,
+
+    /// The marker as displayed, e.g. `•` or `3.`; empty for no marker
+    pub marker: String,
+}
+
+impl ListItem {
+    /// Create an item holding `content` with a bullet marker
+    #[must_use]
+    pub fn bullet(content: Vec<ContentBlock>, level: u8) -> Self {
+        Self {
+            content,
+            level,
+            ordered: false,
+            marker_style: ListMarker::Bullet,
+            marker: "•".to_string(),
+        }
+    }
+
+    /// Create the `number`th item of a list numbered in `style`
+    #[must_use]
+    pub fn numbered(content: Vec<ContentBlock>, level: u8, style: ListMarker, number: u32) -> Self {
+        Self {
+            content,
+            level,
+            ordered: true,
+            marker: format!("{}.", style.label(number)),
+            marker_style: style,
+        }
+    }
+
+    /// Extract text from the item
+    #[must_use]
+    pub fn extract_text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.extract_text()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Marker style of a list item
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListMarker {
+    /// A bullet glyph
+    #[default]
+    Bullet,
+    /// 1, 2, 3
+    Decimal,
+    /// a, b, c
+    LowerAlpha,
+    /// A, B, C
+    UpperAlpha,
+    /// i, ii, iii
+    LowerRoman,
+    /// I, II, III
+    UpperRoman,
+    /// No marker
+    None,
+}
+
+impl ListMarker {
+    /// The label of the `number`th item (1-based), without punctuation
+    ///
+    /// Empty for [`Bullet`](Self::Bullet) and [`None`](Self::None).
+    #[must_use]
+    pub fn label(self, number: u32) -> String {
+        match self {
+            Self::Bullet | Self::None => String::new(),
+            Self::Decimal => number.to_string(),
+            Self::LowerAlpha => alpha(number),
+            Self::UpperAlpha => alpha(number).to_uppercase(),
+            Self::LowerRoman => roman(number).to_lowercase(),
+            Self::UpperRoman => roman(number),
+        }
+    }
+
+    /// Whether items are numbered in this style
+    #[must_use]
+    pub fn is_ordered(self) -> bool {
+        !matches!(self, Self::Bullet | Self::None)
+    }
+}
+
+/// a, b, ..., z, aa, ab, ...
+fn alpha(mut number: u32) -> String {
+    let mut label = Vec::new();
+    while number > 0 {
+        number -= 1;
+        label.push(b'a' + u8::try_from(number % 26).unwrap_or(0));
+        number /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap_or_default()
+}
+
+fn roman(mut number: u32) -> String {
+    const NUMERALS: &[(u32, &str)] = &[
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut label = String::new();
+    for &(value, numeral) in NUMERALS {
+        while number >= value {
+            label.push_str(numeral);
+            number -= value;
+        }
+    }
+    label
+}
+
+/// An interactive form field: a PDF `AcroForm` field or a Word content
+/// control
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormFieldBlock {
+    /// Bounding box on the page
+    pub bounds: Rect,
+
+    /// Kind of field
+    pub field_type: FormFieldType,
+
+    /// Field name, e.g. `applicant.name`; empty if the field has none
+    pub name: String,
+
+    /// Label shown to the user (PDF tooltip, Word content control title)
+    pub label: Option<String>,
+
+    /// Entered text, selected option or date, if filled in
+    pub value: Option<String>,
+
+    /// Whether a checkbox or radio button is selected
+    #[serde(default)]
+    pub checked: bool,
+
+    /// Choices of a dropdown or list box, or the states of a radio group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+
+    /// Whether the field cannot be edited
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl FormFieldBlock {
+    /// Create an empty field
+    #[must_use]
+    pub fn new(field_type: FormFieldType, name: impl Into<String>, bounds: Rect) -> Self {
+        Self {
+            bounds,
+            field_type,
+            name: name.into(),
+            label: None,
+            value: None,
+            checked: false,
+            options: Vec::new(),
+            read_only: false,
+        }
+    }
+
+    /// The field's value as text: the value, or a ballot box for checkboxes
+    /// and radio buttons
+    #[must_use]
+    pub fn extract_text(&self) -> String {
+        match self.field_type {
+            FormFieldType::Checkbox | FormFieldType::Radio => {
+                if self.checked { "☒" } else { "☐" }.to_string()
+            }
+            _ => self.value.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Kind of form field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FormFieldType {
+    /// Single- or multi-line text input
+    #[default]
+    Text,
+    /// Checkbox
+    Checkbox,
+    /// Radio button group
+    Radio,
+    /// Dropdown (combo box)
+    Dropdown,
+    /// List box
+    ListBox,
+    /// Date picker
+    Date,
+    /// Digital signature
+    Signature,
+    /// Push button
+    Button,
+}
+
 /// Vector graphics block
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorBlock {
@@ -590,6 +1217,46 @@ pub struct VectorPath {
 
     /// Stroke width
     pub stroke_width: Option<f64>,
+
+    /// Gradient, picture or pattern fill, drawn instead of `fill` where
+    /// supported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_style: Option<Fill>,
+
+    /// Arrowhead at the first point of an open path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_arrow: Option<Arrowhead>,
+
+    /// Arrowhead at the last point of an open path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_arrow: Option<Arrowhead>,
+}
+
+/// Decoration at an end of a line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Arrowhead {
+    /// Shape of the arrowhead
+    pub kind: ArrowheadKind,
+    /// Width across the line, in multiples of the line width
+    pub width: f64,
+    /// Length along the line, in multiples of the line width
+    pub length: f64,
+}
+
+/// Shape of an arrowhead
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrowheadKind {
+    /// Filled triangle
+    Triangle,
+    /// Filled triangle with a notched back
+    Stealth,
+    /// Open V of two strokes
+    Open,
+    /// Filled diamond centered on the end
+    Diamond,
+    /// Filled oval centered on the end
+    Oval,
 }
 
 /// Path drawing commands
@@ -653,6 +1320,19 @@ impl Rect {
             && point.y >= self.y
             && point.y <= self.y + self.height
     }
+
+    /// Smallest rect containing both rects
+    #[must_use]
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(
+            x,
+            y,
+            (self.x + self.width).max(other.x + other.width) - x,
+            (self.y + self.height).max(other.y + other.height) - y,
+        )
+    }
 }
 
 /// A 2D point
@@ -697,6 +1377,82 @@ pub struct Annotation {
     pub color: Option<String>,
 }
 
+/// A tracked change or a comment
+///
+/// Office documents record who changed what while changes are tracked,
+/// and comments attached to a range of text, a slide or a cell. Runs of a
+/// change kept as markup point back here through [`TextRun::revision`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    /// Identifier of the change or comment within the document
+    pub id: String,
+
+    /// Whether text was inserted, deleted or commented on
+    pub kind: RevisionKind,
+
+    /// Who made the change or wrote the comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    /// When the change or comment was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<Utc>>,
+
+    /// Inserted or deleted text, or the text of the comment
+    pub text: String,
+
+    /// Text a comment refers to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
+
+    /// Number of the page (slide, sheet) the change or comment is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+
+    /// Cell a spreadsheet comment is attached to, such as `B2`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<String>,
+}
+
+impl Revision {
+    /// A revision of `kind` without author, date or location
+    #[must_use]
+    pub fn new(id: impl Into<String>, kind: RevisionKind, text: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            kind,
+            author: None,
+            date: None,
+            text: text.into(),
+            anchor: None,
+            page: None,
+            cell: None,
+        }
+    }
+}
+
+/// Kinds of [`Revision`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionKind {
+    /// Text inserted while changes were tracked
+    Insertion,
+    /// Text deleted while changes were tracked
+    Deletion,
+    /// A comment
+    Comment,
+}
+
+/// The tracked change a [`TextRun`] belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevisionMark {
+    /// Whether the run was inserted or deleted
+    pub kind: RevisionKind,
+
+    /// [`Revision::id`] of the change
+    pub id: String,
+}
+
 /// Types of annotations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotationType {
@@ -726,6 +1482,158 @@ pub struct PageMetadata {
 
     /// Rotation in degrees (0, 90, 180, 270)
     pub rotation: i32,
+
+    /// Speaker notes (presentations)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+
+    /// Headers of the email message on this page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<MessageHeaders>,
+
+    /// Layout of the worksheet on this page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<SheetLayout>,
+}
+
+/// Headers placing an email message in its conversation
+///
+/// Message IDs are kept without their angle brackets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageHeaders {
+    /// `Message-ID` of the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+
+    /// `In-Reply-To`: ID of the message this one answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<String>,
+
+    /// `References`: IDs of the earlier messages of the conversation,
+    /// oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+
+    /// Subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    /// Sender, as written in the `From` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// When the message was sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<Utc>>,
+
+    /// Recipients, in the order the message lists them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<Recipient>,
+}
+
+/// A recipient of an email message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recipient {
+    /// Display name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    /// Header the recipient is listed in
+    pub kind: RecipientKind,
+}
+
+impl Recipient {
+    /// The recipient as written in a header: `Name <email>`, or whichever
+    /// of the two is known
+    #[must_use]
+    pub fn display(&self) -> String {
+        match (&self.name, &self.email) {
+            (Some(name), Some(email)) if name != email => format!("{name} <{email}>"),
+            (Some(name), _) => name.clone(),
+            (None, Some(email)) => email.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Header a recipient is listed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipientKind {
+    /// `To`
+    #[default]
+    To,
+    /// `Cc`
+    Cc,
+    /// `Bcc`
+    Bcc,
+}
+
+/// How a worksheet is shown and printed
+///
+/// Rows and columns are counted from 0 in the table holding the sheet's
+/// cells, which starts at the first cell in use rather than at `A1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetLayout {
+    /// Whether the sheet is hidden in the workbook
+    #[serde(default)]
+    pub hidden: bool,
+
+    /// Rows hidden from view, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_rows: Vec<usize>,
+
+    /// Columns hidden from view, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_columns: Vec<usize>,
+
+    /// Rows and columns frozen in place while the rest scrolls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<FrozenPanes>,
+
+    /// Areas printed when the sheet is printed; all of it when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub print_areas: Vec<CellRange>,
+}
+
+/// Rows at the top and columns at the left of a sheet that stay in view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrozenPanes {
+    /// Frozen rows
+    pub rows: usize,
+
+    /// Frozen columns
+    pub columns: usize,
+}
+
+/// A rectangle of cells, with its first and last rows and columns
+/// included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellRange {
+    /// First row
+    pub first_row: usize,
+
+    /// First column
+    pub first_column: usize,
+
+    /// Last row
+    pub last_row: usize,
+
+    /// Last column
+    pub last_column: usize,
+}
+
+impl CellRange {
+    /// Whether the cell at `row` and `column` is in the range
+    #[must_use]
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        (self.first_row..=self.last_row).contains(&row)
+            && (self.first_column..=self.last_column).contains(&column)
+    }
 }
 
 /// Document stylesheet containing style definitions
@@ -771,6 +1679,10 @@ pub struct ParagraphStyle {
 
     /// Right indent (points)
     pub right_indent: Option<f64>,
+
+    /// Base text direction
+    #[serde(default)]
+    pub direction: TextDirection,
 }
 
 /// Text alignment options
@@ -829,9 +1741,75 @@ pub struct FontResource {
 
     /// Font data (if embedded)
     pub data: Option<Vec<u8>>,
+
+    /// Font program format (`TrueType`, `OpenType`, `Type1`, ...), if known
+    #[serde(default)]
+    pub font_type: Option<String>,
+
+    /// Whether the embedded data only covers the glyphs the document uses
+    #[serde(default)]
+    pub subset: bool,
+
+    /// Embedding permissions declared by the font
+    #[serde(default)]
+    pub embedding_rights: EmbeddingRights,
+}
+
+impl FontResource {
+    /// A referenced font without embedded data
+    #[must_use]
+    pub fn new(family: impl Into<String>, style: impl Into<String>) -> Self {
+        Self {
+            family: family.into(),
+            style: style.into(),
+            embedded: false,
+            data: None,
+            font_type: None,
+            subset: false,
+            embedding_rights: EmbeddingRights::Unknown,
+        }
+    }
 }
 
-/// Document structure (headings, bookmarks, TOC)
+/// Embedding permissions of a font (OpenType `OS/2.fsType`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbeddingRights {
+    /// Not declared, or the font is not embedded
+    #[default]
+    Unknown,
+
+    /// May be embedded and permanently installed
+    Installable,
+
+    /// May be embedded; documents may be edited
+    Editable,
+
+    /// May be embedded for viewing and printing only
+    PreviewAndPrint,
+
+    /// Must not be embedded without the owner's permission
+    Restricted,
+}
+
+impl EmbeddingRights {
+    /// Interpret an `fsType` value
+    ///
+    /// When several usage bits are set the least restrictive one applies.
+    #[must_use]
+    pub fn from_fs_type(fs_type: u16) -> Self {
+        if fs_type & 0x0008 != 0 {
+            Self::Editable
+        } else if fs_type & 0x0004 != 0 {
+            Self::PreviewAndPrint
+        } else if fs_type & 0x0002 != 0 {
+            Self::Restricted
+        } else {
+            Self::Installable
+        }
+    }
+}
+
+/// Document structure (headings, bookmarks, TOC, sections)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DocumentStructure {
     /// Document outline/bookmarks
@@ -842,6 +1820,10 @@ pub struct DocumentStructure {
 
     /// Heading structure
     pub headings: Vec<Heading>,
+
+    /// Logical sections grouping the content, in document order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<Section>,
 }
 
 /// An outline/bookmark item
@@ -889,6 +1871,164 @@ pub struct Heading {
     pub bounds: Option<Rect>,
 }
 
+/// A logical section of the document: a chapter, a sheet of a workbook or
+/// a message of a mail folder
+///
+/// A section spans the top-level blocks from `start` up to, not including,
+/// `end`, so it can begin or end partway down a page. Subsections nest in
+/// `children` and lie within their parent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    /// Section title: heading text, sheet name or message subject
+    pub title: String,
+
+    /// What the section is
+    pub kind: SectionKind,
+
+    /// Position of the first block
+    pub start: ContentPosition,
+
+    /// Position just past the last block
+    pub end: ContentPosition,
+
+    /// Subsections
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Section>,
+}
+
+impl Section {
+    /// Create a section without subsections
+    #[must_use]
+    pub fn new(
+        kind: SectionKind,
+        title: impl Into<String>,
+        start: ContentPosition,
+        end: ContentPosition,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            kind,
+            start,
+            end,
+            children: Vec::new(),
+        }
+    }
+
+    /// Create a section spanning every block of `page`
+    #[must_use]
+    pub fn page(kind: SectionKind, title: impl Into<String>, page: &Page) -> Self {
+        Self::new(
+            kind,
+            title,
+            ContentPosition::new(page.number, 0),
+            ContentPosition::new(page.number, page.content.len()),
+        )
+    }
+
+    /// Whether the block at `position` lies in the section
+    #[must_use]
+    pub fn contains(&self, position: ContentPosition) -> bool {
+        self.start <= position && position < self.end
+    }
+
+    /// Chapters delimited by the headings among the top-level blocks of
+    /// `pages`
+    ///
+    /// Headings are text blocks with a `Heading 1` to `Heading 6` paragraph
+    /// style (or `Heading1`, as Word names them). Each chapter runs to the
+    /// next heading of the same or a higher level and holds the chapters of
+    /// lower-level headings within it. Content before the first heading is
+    /// left out.
+    #[must_use]
+    pub fn from_headings(pages: &[Page]) -> Vec<Section> {
+        let end = pages.last().map_or(ContentPosition::new(1, 0), |page| {
+            ContentPosition::new(page.number, page.content.len())
+        });
+
+        // Open chapters, outermost first, with their levels
+        let mut open: Vec<(u8, Section)> = Vec::new();
+        let mut sections = Vec::new();
+        let close = |open: &mut Vec<(u8, Section)>, sections: &mut Vec<Section>, level, at| {
+            while open
+                .last()
+                .is_some_and(|(open_level, _)| *open_level >= level)
+            {
+                let Some((_, mut section)) = open.pop() else {
+                    break;
+                };
+                section.end = at;
+                match open.last_mut() {
+                    Some((_, parent)) => parent.children.push(section),
+                    None => sections.push(section),
+                }
+            }
+        };
+
+        for page in pages {
+            for (index, block) in page.content.iter().enumerate() {
+                let ContentBlock::Text(text) = block else {
+                    continue;
+                };
+                let Some(level) = text.paragraph_style.as_deref().and_then(heading_level) else {
+                    continue;
+                };
+                let at = ContentPosition::new(page.number, index);
+                close(&mut open, &mut sections, level, at);
+                let title = text.extract_text().trim().to_string();
+                open.push((level, Section::new(SectionKind::Chapter, title, at, at)));
+            }
+        }
+        close(&mut open, &mut sections, 0, end);
+        sections
+    }
+}
+
+/// Level of a `Heading N` or `HeadingN` paragraph style
+fn heading_level(style: &str) -> Option<u8> {
+    let level: u8 = style.strip_prefix("Heading")?.trim_start().parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// What a section of a document is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    /// A part of the text introduced by a heading
+    #[default]
+    Chapter,
+    /// A worksheet of a workbook
+    Sheet,
+    /// A message of a mail folder or thread
+    Message,
+    /// A conversation of a mail folder, holding its messages
+    Thread,
+    /// A directory of an archive, holding its files and subdirectories
+    Directory,
+    /// A file of an archive
+    File,
+}
+
+/// A position between the top-level blocks of a document: just before
+/// block `block` of page `page`
+///
+/// Positions order by page, then block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ContentPosition {
+    /// 1-indexed page number
+    pub page: u32,
+
+    /// 0-based index into the page's content
+    pub block: usize,
+}
+
+impl ContentPosition {
+    /// Create a position
+    #[must_use]
+    pub fn new(page: u32, block: usize) -> Self {
+        Self { page, block }
+    }
+}
+
 /// An embedded file/attachment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Attachment {
@@ -909,6 +2049,12 @@ pub struct Attachment {
 
     /// Modification date
     pub modified: Option<DateTime<Utc>>,
+
+    /// The file parsed as a document of its own, for embedded documents
+    /// parsed with
+    /// [`ParseOptions::parse_embedded`](crate::parser::ParseOptions::parse_embedded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<Box<Document>>,
 }
 
 #[cfg(test)]
@@ -960,6 +2106,28 @@ mod tests {
         assert!((from_inches.height - 792.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_list_extraction() {
+        let item = |text: &str| {
+            let mut block = TextBlock::new(Rect::default());
+            block.add_run(TextRun::new(text));
+            vec![ContentBlock::Text(block)]
+        };
+        let mut list = ListBlock::new(Rect::default());
+        list.add_item(ListItem::numbered(item("First"), 0, ListMarker::Decimal, 1));
+        list.add_item(ListItem::bullet(item("Nested"), 1));
+        list.add_item(ListItem::numbered(
+            item("Fourth"),
+            0,
+            ListMarker::LowerRoman,
+            4,
+        ));
+
+        assert_eq!(list.extract_text(), "1. First\n• Nested\niv. Fourth");
+        assert_eq!(ListMarker::UpperAlpha.label(28), "AB");
+        assert_eq!(ListMarker::UpperRoman.label(1994), "MCMXCIV");
+    }
+
     #[test]
     fn test_rect_contains() {
         let rect = Rect::new(10.0, 10.0, 100.0, 50.0);
@@ -969,4 +2137,55 @@ mod tests {
         assert!(!rect.contains(Point::new(5.0, 30.0)));
         assert!(!rect.contains(Point::new(50.0, 100.0)));
     }
+
+    #[test]
+    fn test_sections_from_headings() {
+        let mut document = Document::builder()
+            .add_text_page("Intro", "Welcome.")
+            .add_text_page("Usage", "Run it.")
+            .build();
+        let mut heading = TextBlock::new(Rect::default());
+        heading.paragraph_style = Some("Heading2".to_string());
+        heading.add_run(TextRun::new("Options"));
+        document.pages[1].add_content(ContentBlock::Text(heading));
+        let mut text = TextBlock::new(Rect::default());
+        text.add_run(TextRun::new("Pass --help."));
+        document.pages[1].add_content(ContentBlock::Text(text));
+
+        let sections = Section::from_headings(&document.pages);
+        let outline: Vec<_> = sections
+            .iter()
+            .map(|section| (section.title.as_str(), section.start, section.end))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                (
+                    "Intro",
+                    ContentPosition::new(1, 0),
+                    ContentPosition::new(2, 0)
+                ),
+                (
+                    "Usage",
+                    ContentPosition::new(2, 0),
+                    ContentPosition::new(2, 4)
+                ),
+            ]
+        );
+        let options = &sections[1].children[0];
+        assert_eq!(options.title, "Options");
+        assert_eq!(options.start, ContentPosition::new(2, 2));
+
+        let texts: Vec<_> = document
+            .section_blocks(options)
+            .filter_map(|(page, block)| match block {
+                ContentBlock::Text(text) => Some((page, text.extract_text())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [(2, "Options".to_string()), (2, "Pass --help.".to_string())]
+        );
+    }
 }
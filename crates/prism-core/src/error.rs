@@ -2,7 +2,14 @@
 //! # Error Handling
 //!
 //! Error types and result aliases for Prism operations.
+//!
+//! Every [`Error`] has a stable numeric [`ErrorCode`] that clients can match
+//! on instead of the message text. The code also decides the HTTP status the
+//! server responds with and the exit code of the CLI. Errors about a
+//! particular file can say where in it the problem is ([`ErrorLocation`]).
 
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io;
 use thiserror::Error;
 
@@ -36,10 +43,52 @@ pub enum Error {
     #[error("Document is encrypted: {0}")]
     Encrypted(String),
 
+    /// Document is encrypted, and how; a password is needed to open it
+    #[error("{format} file is encrypted ({method}); a password is required")]
+    EncryptedDocument {
+        /// Format name, e.g. "ZIP" or "DOCX"
+        format: String,
+        /// Encryption method, such as `ZipCrypto`, `AES-256` or `Agile (AES-256)`
+        method: String,
+    },
+
+    /// Document contains active content that policy refuses
+    #[error("Document contains active content: {0}")]
+    ActiveContent(String),
+
     /// Document is corrupted
     #[error("Document is corrupted: {0}")]
     Corrupted(String),
 
+    /// File is damaged or does not follow its format's specification
+    #[error("Corrupt {}: {message}", corrupt_subject(.format.as_deref(), .location.as_ref()))]
+    CorruptFile {
+        /// Format name, when known
+        format: Option<String>,
+        /// Where in the file the problem was found
+        location: Option<ErrorLocation>,
+        /// What is wrong
+        message: String,
+    },
+
+    /// File uses a feature of its format that Prism does not handle
+    #[error("Unsupported {format} feature: {feature}")]
+    UnsupportedFeature {
+        /// Format name
+        format: String,
+        /// The feature, e.g. "encrypted ZIP entries"
+        feature: String,
+    },
+
+    /// A processing limit other than memory or time was reached
+    #[error("Resource limit exceeded: {resource} is limited to {limit}")]
+    ResourceLimit {
+        /// What was limited, e.g. "pages" or "archive entries"
+        resource: String,
+        /// The limit
+        limit: u64,
+    },
+
     /// Resource not found
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
@@ -52,6 +101,20 @@ pub enum Error {
     #[error("Operation timed out after {0:?}")]
     Timeout(std::time::Duration),
 
+    /// A parse stage took longer than
+    /// [`ParseOptions::stage_budget`](crate::parser::ParseOptions::stage_budget)
+    #[error("Parse stage {stage} took {elapsed:?}, over its budget of {budget:?}")]
+    StageTimeout {
+        /// The stage, e.g. `sheet:Sales`
+        stage: String,
+        /// Time the stage took
+        elapsed: std::time::Duration,
+        /// Budget of every stage
+        budget: std::time::Duration,
+        /// Stages timed until then, this one included
+        timings: Vec<crate::timing::StageTiming>,
+    },
+
     /// Memory limit exceeded
     #[error("Memory limit exceeded: {used} bytes used, {limit} bytes allowed")]
     MemoryLimitExceeded {
@@ -74,13 +137,223 @@ pub enum Error {
     Internal(String),
 }
 
+/// Where in a file an error was found
+///
+/// All parts are optional; parsers fill in what they know.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorLocation {
+    /// Package part or archive entry, e.g. `word/document.xml`
+    pub part: Option<String>,
+    /// Byte offset, within `part` if given, otherwise within the file
+    pub offset: Option<u64>,
+    /// Path of the XML element, e.g. `/w:document/w:body/w:p[3]`
+    pub xml_path: Option<String>,
+}
+
+impl ErrorLocation {
+    /// Location given by a byte offset in the file
+    #[must_use]
+    pub fn offset(offset: u64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..Self::default()
+        }
+    }
+
+    /// Location given by a package part or archive entry
+    #[must_use]
+    pub fn part(part: impl Into<String>) -> Self {
+        Self {
+            part: Some(part.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Add a byte offset
+    #[must_use]
+    pub fn at_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Add an XML element path
+    #[must_use]
+    pub fn with_xml_path(mut self, path: impl Into<String>) -> Self {
+        self.xml_path = Some(path.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(part) = &self.part {
+            parts.push(format!("in {part}"));
+        }
+        if let Some(path) = &self.xml_path {
+            parts.push(format!("at {path}"));
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("at byte {offset}"));
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+/// "DOCX file (in word/document.xml)" for [`Error::CorruptFile`] messages
+fn corrupt_subject(format: Option<&str>, location: Option<&ErrorLocation>) -> String {
+    let subject = format!("{} file", format.unwrap_or("document"));
+    match location.map(ToString::to_string) {
+        Some(location) if !location.is_empty() => format!("{subject} ({location})"),
+        _ => subject,
+    }
+}
+
+/// Stable numeric identifier of an error class
+///
+/// Numbers are grouped by cause and never reused: 1xxx input and format
+/// problems, 2xxx rendering, 3xxx limits, 4xxx lookups, 5xxx environment
+/// and internal failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// Format could not be detected
+    DetectionFailed = 1000,
+    /// Format is known but not supported
+    UnsupportedFormat = 1001,
+    /// A feature of the format is not supported
+    UnsupportedFeature = 1002,
+    /// Request or argument is invalid
+    InvalidInput = 1100,
+    /// Parsing failed
+    ParseError = 1200,
+    /// File is damaged
+    CorruptFile = 1201,
+    /// File is encrypted
+    Encrypted = 1202,
+    /// File has active content and policy refuses it
+    ActiveContent = 1203,
+    /// Rendering failed
+    RenderError = 2000,
+    /// A processing limit was reached
+    ResourceLimit = 3000,
+    /// Memory limit was reached
+    MemoryLimit = 3001,
+    /// Operation timed out
+    Timeout = 3002,
+    /// A referenced resource does not exist
+    NotFound = 4000,
+    /// I/O failure
+    Io = 5000,
+    /// Sandbox failure
+    Sandbox = 5001,
+    /// Invalid configuration
+    Config = 5002,
+    /// Bug or unexpected state
+    Internal = 5999,
+}
+
+impl ErrorCode {
+    /// The numeric code
+    #[must_use]
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    /// HTTP status code for responses failing with this error
+    #[must_use]
+    pub fn http_status(self) -> u16 {
+        match self {
+            Self::InvalidInput => 400,
+            Self::NotFound => 404,
+            Self::ResourceLimit | Self::MemoryLimit => 413,
+            Self::DetectionFailed | Self::UnsupportedFormat => 415,
+            Self::UnsupportedFeature
+            | Self::ParseError
+            | Self::CorruptFile
+            | Self::Encrypted
+            | Self::ActiveContent => 422,
+            Self::Timeout => 503,
+            Self::RenderError | Self::Io | Self::Sandbox | Self::Config | Self::Internal => 500,
+        }
+    }
+
+    /// Process exit code for command-line tools failing with this error
+    ///
+    /// 2 unsupported format, 3 parse error, 4 render error, 5 resource
+    /// limit, 1 anything else.
+    #[must_use]
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::DetectionFailed | Self::UnsupportedFormat | Self::UnsupportedFeature => 2,
+            Self::ParseError
+            | Self::CorruptFile
+            | Self::Encrypted
+            | Self::ActiveContent
+            | Self::InvalidInput => 3,
+            Self::RenderError => 4,
+            Self::ResourceLimit | Self::MemoryLimit | Self::Timeout => 5,
+            Self::NotFound | Self::Io | Self::Sandbox | Self::Config | Self::Internal => 1,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{}", self.as_u16())
+    }
+}
+
 impl Error {
+    /// Stable code identifying the class of this error
+    #[must_use]
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::DetectionFailed(_) => ErrorCode::DetectionFailed,
+            Error::UnsupportedFormat(_) => ErrorCode::UnsupportedFormat,
+            Error::UnsupportedFeature { .. } => ErrorCode::UnsupportedFeature,
+            Error::ParseError(_) => ErrorCode::ParseError,
+            Error::Corrupted(_) | Error::CorruptFile { .. } => ErrorCode::CorruptFile,
+            Error::Encrypted(_) | Error::EncryptedDocument { .. } => ErrorCode::Encrypted,
+            Error::ActiveContent(_) => ErrorCode::ActiveContent,
+            Error::RenderError(_) => ErrorCode::RenderError,
+            Error::InvalidInput(_) => ErrorCode::InvalidInput,
+            Error::ResourceNotFound(_) => ErrorCode::NotFound,
+            Error::Io(_) => ErrorCode::Io,
+            Error::Timeout(_) | Error::StageTimeout { .. } => ErrorCode::Timeout,
+            Error::MemoryLimitExceeded { .. } => ErrorCode::MemoryLimit,
+            Error::ResourceLimit { .. } => ErrorCode::ResourceLimit,
+            Error::SandboxError(_) => ErrorCode::Sandbox,
+            Error::ConfigError(_) => ErrorCode::Config,
+            Error::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// Where in the file the error was found, if known
+    #[must_use]
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            Error::CorruptFile { location, .. } => location.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Encryption method of an encrypted document, if known
+    #[must_use]
+    pub fn encryption_method(&self) -> Option<&str> {
+        match self {
+            Error::EncryptedDocument { method, .. } => Some(method),
+            _ => None,
+        }
+    }
+
     /// Check if this error is recoverable
     #[must_use]
     pub fn is_recoverable(&self) -> bool {
         matches!(
             self,
-            Error::Timeout(_) | Error::MemoryLimitExceeded { .. }
+            Error::Timeout(_) | Error::StageTimeout { .. } | Error::MemoryLimitExceeded { .. }
         )
     }
 
@@ -91,11 +364,78 @@ impl Error {
             self,
             Error::InvalidInput(_)
                 | Error::Corrupted(_)
+                | Error::CorruptFile { .. }
                 | Error::UnsupportedFormat(_)
+                | Error::UnsupportedFeature { .. }
                 | Error::ParseError(_)
         )
     }
 
+    /// Create a corrupt-file error for `format`
+    ///
+    /// Add the position with [`Error::at`].
+    pub fn corrupt(format: impl Into<String>, message: impl Into<String>) -> Self {
+        Error::CorruptFile {
+            format: Some(format.into()),
+            location: None,
+            message: message.into(),
+        }
+    }
+
+    /// Create an encrypted-document error for `format`
+    pub fn encrypted(format: impl Into<String>, method: impl Into<String>) -> Self {
+        Error::EncryptedDocument {
+            format: format.into(),
+            method: method.into(),
+        }
+    }
+
+    /// Create an unsupported-feature error
+    pub fn unsupported_feature(format: impl Into<String>, feature: impl Into<String>) -> Self {
+        Error::UnsupportedFeature {
+            format: format.into(),
+            feature: feature.into(),
+        }
+    }
+
+    /// Set the package part of a corrupt-file error's location, keeping any
+    /// offset or XML path already recorded; other errors are returned
+    /// unchanged
+    #[must_use]
+    pub fn in_part(self, part: impl Into<String>) -> Self {
+        match self {
+            Error::CorruptFile {
+                format,
+                location,
+                message,
+            } => Error::CorruptFile {
+                format,
+                location: Some(ErrorLocation {
+                    part: Some(part.into()),
+                    ..location.unwrap_or_default()
+                }),
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// Attach a location to a corrupt-file error; other errors are returned
+    /// unchanged
+    #[must_use]
+    pub fn at(self, at: ErrorLocation) -> Self {
+        match self {
+            Error::CorruptFile {
+                format, message, ..
+            } => Error::CorruptFile {
+                format,
+                location: Some(at),
+                message,
+            },
+            other => other,
+        }
+    }
+
     /// Create a parse error with context
     pub fn parse<S: Into<String>>(msg: S) -> Self {
         Error::ParseError(msg.into())
@@ -144,4 +484,46 @@ mod tests {
         assert!(Error::Corrupted("test".to_string()).is_input_error());
         assert!(!Error::Io(io::Error::new(io::ErrorKind::NotFound, "test")).is_input_error());
     }
+
+    #[test]
+    fn test_error_codes() {
+        let err = Error::corrupt("DOCX", "unexpected end of element")
+            .at(ErrorLocation::part("word/document.xml").at_offset(1024));
+        assert_eq!(
+            err.to_string(),
+            "Corrupt DOCX file (in word/document.xml at byte 1024): unexpected end of element"
+        );
+        assert_eq!(err.code(), ErrorCode::CorruptFile);
+        assert_eq!(err.code().as_u16(), 1201);
+        assert_eq!(err.code().to_string(), "E1201");
+        assert_eq!(err.code().http_status(), 422);
+        assert_eq!(err.code().exit_code(), 3);
+        assert_eq!(err.location().and_then(|l| l.offset), Some(1024));
+
+        let limit = Error::ResourceLimit {
+            resource: "pages".to_string(),
+            limit: 1000,
+        };
+        assert_eq!(
+            (limit.code().http_status(), limit.code().exit_code()),
+            (413, 5)
+        );
+        assert_eq!(
+            Error::UnsupportedFormat("x".to_string()).code().exit_code(),
+            2
+        );
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::UnsupportedFeature).unwrap(),
+            "\"unsupported_feature\""
+        );
+
+        let encrypted = Error::encrypted("DOCX", "Agile (AES-256)");
+        assert_eq!(
+            encrypted.to_string(),
+            "DOCX file is encrypted (Agile (AES-256)); a password is required"
+        );
+        assert_eq!(encrypted.code(), ErrorCode::Encrypted);
+        assert_eq!(encrypted.code().http_status(), 422);
+        assert_eq!(encrypted.encryption_method(), Some("Agile (AES-256)"));
+    }
 }
@@ -0,0 +1,539 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Remote Input
+//!
+//! Downloads documents from HTTP(S) URLs so they can be converted without
+//! a download-then-upload round trip. Every download is held to a
+//! [`FetchPolicy`]: the hosts it may reach, the size it may have, the
+//! redirects it may follow and how long it may take.
+//!
+//! Redirects are followed one hop at a time so each target is checked
+//! against the policy, and hosts resolving to loopback, private or
+//! link-local addresses are refused unless the policy allows them, which
+//! keeps a server fetching on behalf of its clients from being pointed at
+//! its own network. Each host is resolved once and the connection pinned
+//! to the addresses checked; proxies configured in the environment are not
+//! used, since they would resolve the host again themselves.
+//!
+//! Only available with the `fetch` feature.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use prism_core::fetch::{fetch, FetchPolicy};
+//!
+//! # async fn example() -> Result<(), prism_core::fetch::FetchError> {
+//! let policy = FetchPolicy {
+//!     allowed_hosts: vec!["example.com".to_string()],
+//!     ..FetchPolicy::default()
+//! };
+//! let document = fetch("https://example.com/report.pdf", &policy).await?;
+//! assert_eq!(document.filename.as_deref(), Some("report.pdf"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE, LOCATION};
+use reqwest::redirect;
+use thiserror::Error;
+use tracing::debug;
+use url::{Host, Url};
+
+/// What a download may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchPolicy {
+    /// Hosts that may be fetched from; an entry also admits its
+    /// subdomains (every host if empty)
+    pub allowed_hosts: Vec<String>,
+    /// Largest document accepted, in bytes
+    pub max_size: usize,
+    /// Redirects followed before giving up (0 = none)
+    pub max_redirects: usize,
+    /// Time allowed for the whole download, redirects included
+    pub timeout: Duration,
+    /// Allow hosts on loopback, private and link-local addresses
+    pub allow_private_hosts: bool,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            max_size: 100 * 1024 * 1024,
+            max_redirects: 5,
+            timeout: Duration::from_secs(30),
+            allow_private_hosts: false,
+        }
+    }
+}
+
+impl FetchPolicy {
+    /// Check that `url` may be fetched, without resolving its host
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not HTTP(S), its host is not
+    /// allowed, or it names a private address the policy excludes.
+    pub fn check(&self, url: &Url) -> Result<(), FetchError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(FetchError::UnsupportedScheme(url.scheme().to_string()));
+        }
+        let host = url
+            .host()
+            .ok_or_else(|| FetchError::InvalidUrl(format!("{url} has no host")))?;
+        let name = match &host {
+            Host::Domain(domain) => domain.to_ascii_lowercase(),
+            Host::Ipv4(ip) => ip.to_string(),
+            Host::Ipv6(ip) => ip.to_string(),
+        };
+
+        if !self.allowed_hosts.is_empty()
+            && !self.allowed_hosts.iter().any(|entry| {
+                let entry = entry
+                    .trim_start_matches("*.")
+                    .trim_start_matches('.')
+                    .to_ascii_lowercase();
+                name == entry || name.ends_with(&format!(".{entry}"))
+            })
+        {
+            return Err(FetchError::HostNotAllowed(name));
+        }
+
+        if !self.allow_private_hosts {
+            let private = match host {
+                Host::Domain(domain) => {
+                    domain.eq_ignore_ascii_case("localhost")
+                        || domain.to_ascii_lowercase().ends_with(".localhost")
+                }
+                Host::Ipv4(ip) => is_private(IpAddr::V4(ip)),
+                Host::Ipv6(ip) => is_private(IpAddr::V6(ip)),
+            };
+            if private {
+                return Err(FetchError::HostNotAllowed(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the host of `url` once, checking that none of its addresses
+    /// is private
+    ///
+    /// The request is then pinned to the addresses returned, so a name
+    /// that resolves differently the second time (DNS rebinding) cannot
+    /// redirect it to an address that was never checked.
+    async fn resolve(&self, url: &Url) -> Result<Option<(String, Vec<SocketAddr>)>, FetchError> {
+        if self.allow_private_hosts {
+            return Ok(None);
+        }
+        let Some(Host::Domain(domain)) = url.host() else {
+            return Ok(None);
+        };
+        let port = url.port_or_known_default().unwrap_or(80);
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| FetchError::Request(format!("Failed to resolve {domain}: {e}")))?
+            .collect();
+        if addresses.is_empty() || addresses.iter().any(|address| is_private(address.ip())) {
+            return Err(FetchError::HostNotAllowed(domain.to_string()));
+        }
+        Ok(Some((domain.to_string(), addresses)))
+    }
+}
+
+/// A downloaded document
+#[derive(Debug, Clone)]
+pub struct FetchedDocument {
+    /// Document content
+    pub data: Bytes,
+    /// Filename from `Content-Disposition`, or else the last segment of
+    /// the URL path
+    pub filename: Option<String>,
+    /// `Content-Type` sent by the server
+    pub content_type: Option<String>,
+    /// URL the document was served from, after redirects
+    pub url: Url,
+}
+
+/// Why a download failed
+#[derive(Debug, Error)]
+pub enum FetchError {
+    /// The URL could not be parsed
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    /// The URL is not HTTP(S)
+    #[error("Unsupported URL scheme: {0}")]
+    UnsupportedScheme(String),
+
+    /// The host is not on the allow-list, or is a private address
+    #[error("Fetching from {0} is not allowed")]
+    HostNotAllowed(String),
+
+    /// The document is larger than the policy allows
+    #[error("Document exceeds the maximum size of {limit} bytes")]
+    TooLarge {
+        /// Size limit in bytes
+        limit: usize,
+    },
+
+    /// The redirect limit was reached
+    #[error("Too many redirects (limit {0})")]
+    TooManyRedirects(usize),
+
+    /// The download took longer than the policy allows
+    #[error("Download timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// The server answered with an error status
+    #[error("Server responded with status {0}")]
+    Status(u16),
+
+    /// The request failed
+    #[error("Request failed: {0}")]
+    Request(String),
+}
+
+/// Download the document at `url` within `policy`
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid or not allowed by the policy, a
+/// limit of the policy is exceeded, or the server cannot be reached or
+/// answers with an error.
+pub async fn fetch(url: &str, policy: &FetchPolicy) -> Result<FetchedDocument, FetchError> {
+    let url = Url::parse(url).map_err(|e| FetchError::InvalidUrl(format!("{url}: {e}")))?;
+    policy.check(&url)?;
+    tokio::time::timeout(policy.timeout, download(url, policy))
+        .await
+        .map_err(|_| FetchError::Timeout(policy.timeout))?
+}
+
+async fn download(mut url: Url, policy: &FetchPolicy) -> Result<FetchedDocument, FetchError> {
+    let mut redirects = 0;
+    let mut response = loop {
+        // A proxy would resolve the host itself, past the checks and the
+        // pinned addresses, so none is used
+        let mut client = reqwest::Client::builder()
+            .no_proxy()
+            .redirect(redirect::Policy::none())
+            .user_agent(concat!("prism/", env!("CARGO_PKG_VERSION")));
+        if let Some((domain, addresses)) = policy.resolve(&url).await? {
+            client = client.resolve_to_addrs(&domain, &addresses);
+        }
+        let client = client
+            .build()
+            .map_err(|e| FetchError::Request(e.to_string()))?;
+        let response = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| FetchError::Request(e.without_url().to_string()))?;
+        if !response.status().is_redirection() {
+            break response;
+        }
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or(FetchError::Status(response.status().as_u16()))?;
+        if redirects == policy.max_redirects {
+            return Err(FetchError::TooManyRedirects(policy.max_redirects));
+        }
+        redirects += 1;
+        let next = url
+            .join(location)
+            .map_err(|e| FetchError::InvalidUrl(format!("{location}: {e}")))?;
+        policy.check(&next)?;
+        debug!("Following redirect from {} to {}", url, next);
+        url = next;
+    };
+
+    if !response.status().is_success() {
+        return Err(FetchError::Status(response.status().as_u16()));
+    }
+    let limit = policy.max_size;
+    if response
+        .content_length()
+        .is_some_and(|length| length > u64::try_from(limit).unwrap_or(u64::MAX))
+    {
+        return Err(FetchError::TooLarge { limit });
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header(CONTENT_TYPE);
+    let filename = header(CONTENT_DISPOSITION)
+        .as_deref()
+        .and_then(disposition_filename)
+        .or_else(|| url_filename(&url));
+
+    let mut data = BytesMut::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| FetchError::Request(e.without_url().to_string()))?
+    {
+        if data.len() + chunk.len() > limit {
+            return Err(FetchError::TooLarge { limit });
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(FetchedDocument {
+        data: data.freeze(),
+        filename,
+        content_type,
+        url,
+    })
+}
+
+/// Whether `ip` is a loopback, private, link-local, shared, benchmarking,
+/// multicast, reserved or unspecified address
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                // Multicast (224.0.0.0/4), and reserved (240.0.0.0/4) with
+                // the broadcast address
+                || a >= 224
+                // "This network" (0.0.0.0/8)
+                || a == 0
+                // IETF protocol assignments (192.0.0.0/24)
+                || (a == 192 && b == 0 && c == 0)
+                // Carrier-grade NAT (100.64.0.0/10)
+                || (a == 100 && b & 0xc0 == 64)
+                // Benchmarking (198.18.0.0/15)
+                || (a == 198 && b & 0xfe == 18)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_private(IpAddr::V4(mapped));
+            }
+            let segments = ip.segments();
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local (fc00::/7) and link-local (fe80::/10)
+                || segments[0] & 0xfe00 == 0xfc00
+                || segments[0] & 0xffc0 == 0xfe80
+                // NAT64 (64:ff9b::/96), which reaches any IPv4 address
+                || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+        }
+    }
+}
+
+/// Filename from a `Content-Disposition` header
+fn disposition_filename(disposition: &str) -> Option<String> {
+    disposition.split(';').find_map(|part| {
+        let value = part.trim().strip_prefix("filename=")?;
+        let name = value.trim_matches('"').rsplit(['/', '\\']).next()?;
+        (!name.is_empty()).then(|| name.to_string())
+    })
+}
+
+/// Last segment of the URL path, if it looks like a filename
+fn url_filename(url: &Url) -> Option<String> {
+    url.path_segments()?
+        .next_back()
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn check(policy: &FetchPolicy, url: &str) -> Result<(), FetchError> {
+        policy.check(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let policy = FetchPolicy {
+            allowed_hosts: vec!["example.com".to_string()],
+            ..FetchPolicy::default()
+        };
+        assert!(check(&policy, "https://example.com/a.pdf").is_ok());
+        assert!(check(&policy, "https://files.EXAMPLE.com/a.pdf").is_ok());
+        assert!(matches!(
+            check(&policy, "https://badexample.com/a.pdf"),
+            Err(FetchError::HostNotAllowed(_))
+        ));
+        assert!(matches!(
+            check(&policy, "file:///etc/passwd"),
+            Err(FetchError::UnsupportedScheme(_))
+        ));
+
+        let open = FetchPolicy::default();
+        for url in [
+            "http://localhost/",
+            "http://127.0.0.1/",
+            "http://10.0.0.8/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[::ffff:192.168.0.1]/",
+            "http://0.1.2.3/",
+            "http://100.64.0.1/",
+            "http://100.127.255.254/",
+            "http://198.19.0.1/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+            "http://224.0.0.251/",
+            "http://239.255.255.250/",
+            "http://240.0.0.1/",
+            "http://255.255.255.255/",
+            "http://192.0.0.170/",
+            "http://[ff02::1]/",
+            "http://[ff0e::1]/",
+        ] {
+            assert!(check(&open, url).is_err(), "{url}");
+        }
+        assert!(check(&open, "http://93.184.216.34/").is_ok());
+        assert!(check(&open, "http://100.128.0.1/").is_ok());
+        assert!(check(&open, "http://198.20.0.1/").is_ok());
+        assert!(check(&open, "http://192.0.2.1/").is_ok());
+        assert!(check(&open, "http://223.255.255.254/").is_ok());
+    }
+
+    #[test]
+    fn test_filenames() {
+        assert_eq!(
+            disposition_filename(r#"attachment; filename="Q3 report.pdf""#).as_deref(),
+            Some("Q3 report.pdf")
+        );
+        assert_eq!(
+            disposition_filename("attachment; filename=../../etc/passwd").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(disposition_filename("inline"), None);
+        let url = Url::parse("https://example.com/files/a.docx?download=1").unwrap();
+        assert_eq!(url_filename(&url).as_deref(), Some("a.docx"));
+        assert_eq!(
+            url_filename(&Url::parse("https://example.com/").unwrap()),
+            None
+        );
+    }
+
+    /// Serve `responses` in turn to one connection each
+    async fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let base = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /files/notes.txt\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello".to_string(),
+        ])
+        .await;
+        let policy = FetchPolicy {
+            allow_private_hosts: true,
+            ..FetchPolicy::default()
+        };
+        let document = fetch(&format!("{base}/start"), &policy).await.unwrap();
+        assert_eq!(&document.data[..], b"hello");
+        assert_eq!(document.filename.as_deref(), Some("notes.txt"));
+        assert_eq!(document.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(document.url.path(), "/files/notes.txt");
+
+        // Private hosts are refused by default
+        assert!(matches!(
+            fetch(&format!("{base}/start"), &FetchPolicy::default()).await,
+            Err(FetchError::HostNotAllowed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_ignores_proxy() {
+        // A proxy that would fetch anything it is asked for
+        let proxy = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 7\r\nConnection: close\r\n\r\nproxied".to_string(),
+        ])
+        .await;
+        std::env::set_var("HTTP_PROXY", &proxy);
+        std::env::set_var("ALL_PROXY", &proxy);
+
+        let refused = fetch("http://127.0.0.1:9/", &FetchPolicy::default()).await;
+        assert!(matches!(refused, Err(FetchError::HostNotAllowed(_))));
+
+        let base = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\ndirect".to_string(),
+        ])
+        .await;
+        let policy = FetchPolicy {
+            allow_private_hosts: true,
+            ..FetchPolicy::default()
+        };
+        let document = fetch(&format!("{base}/a.txt"), &policy).await;
+        std::env::remove_var("HTTP_PROXY");
+        std::env::remove_var("ALL_PROXY");
+        assert_eq!(&document.unwrap().data[..], b"direct");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_limits() {
+        let redirect = "HTTP/1.1 301 Moved\r\nLocation: /next\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let base = serve(vec![redirect.to_string()]).await;
+        let policy = FetchPolicy {
+            allow_private_hosts: true,
+            max_redirects: 0,
+            ..FetchPolicy::default()
+        };
+        assert!(matches!(
+            fetch(&base, &policy).await,
+            Err(FetchError::TooManyRedirects(0))
+        ));
+
+        let base = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\n0123456789"
+                .to_string(),
+        ])
+        .await;
+        let policy = FetchPolicy {
+            allow_private_hosts: true,
+            max_size: 4,
+            ..FetchPolicy::default()
+        };
+        assert!(matches!(
+            fetch(&base, &policy).await,
+            Err(FetchError::TooLarge { limit: 4 })
+        ));
+
+        let base = serve(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ])
+        .await;
+        let policy = FetchPolicy {
+            allow_private_hosts: true,
+            ..FetchPolicy::default()
+        };
+        assert!(matches!(
+            fetch(&base, &policy).await,
+            Err(FetchError::Status(404))
+        ));
+    }
+}
@@ -4,9 +4,9 @@
 //! Utilities for detecting document formats from file content.
 //!
 //! Format detection uses multiple strategies:
-//! 1. Magic bytes / file signatures
-//! 2. File extension hints
-//! 3. Content analysis
+//! 1. Magic bytes / file signatures, with ZIP and OLE container inspection
+//! 2. Content analysis for text formats (JSON, HTML, XML, email, CSV, ...)
+//! 3. File extension hints, weighed against content analysis
 //!
 //! ## Example
 //!
@@ -23,6 +23,8 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, OnceLock};
 
 /// Detected file format
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -358,6 +360,138 @@ impl Format {
             is_container: false, // It's a compressor, but effectively behaves like single-file container
         }
     }
+
+    /// Create a new ISO disc image format instance (ISO 9660 or UDF)
+    #[must_use]
+    pub fn iso() -> Self {
+        Self {
+            mime_type: "application/x-iso9660-image".to_string(),
+            extension: "iso".to_string(),
+            family: FormatFamily::Archive,
+            name: "ISO Disc Image".to_string(),
+            is_container: true,
+        }
+    }
+
+    /// Create a new MSI format instance (Windows Installer package)
+    #[must_use]
+    pub fn msi() -> Self {
+        Self {
+            mime_type: "application/x-msi".to_string(),
+            extension: "msi".to_string(),
+            family: FormatFamily::Archive,
+            name: "Windows Installer Package".to_string(),
+            is_container: true,
+        }
+    }
+
+    /// Create a new XZ format instance
+    #[must_use]
+    pub fn xz() -> Self {
+        Self {
+            mime_type: "application/x-xz".to_string(),
+            extension: "xz".to_string(),
+            family: FormatFamily::Archive,
+            name: "XZ Compressed File".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new BZIP2 format instance
+    #[must_use]
+    pub fn bzip2() -> Self {
+        Self {
+            mime_type: "application/x-bzip2".to_string(),
+            extension: "bz2".to_string(),
+            family: FormatFamily::Archive,
+            name: "BZIP2 Compressed File".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new Zstandard format instance
+    #[must_use]
+    pub fn zstd() -> Self {
+        Self {
+            mime_type: "application/zstd".to_string(),
+            extension: "zst".to_string(),
+            family: FormatFamily::Archive,
+            name: "Zstandard Compressed File".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new MP3 format instance
+    #[must_use]
+    pub fn mp3() -> Self {
+        Self {
+            mime_type: "audio/mpeg".to_string(),
+            extension: "mp3".to_string(),
+            family: FormatFamily::Audio,
+            name: "MP3 Audio".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new MP4 format instance
+    #[must_use]
+    pub fn mp4() -> Self {
+        Self {
+            mime_type: "video/mp4".to_string(),
+            extension: "mp4".to_string(),
+            family: FormatFamily::Video,
+            name: "MPEG-4 Video".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new M4A format instance (MPEG-4 audio)
+    #[must_use]
+    pub fn m4a() -> Self {
+        Self {
+            mime_type: "audio/mp4".to_string(),
+            extension: "m4a".to_string(),
+            family: FormatFamily::Audio,
+            name: "MPEG-4 Audio".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new `QuickTime` movie format instance
+    #[must_use]
+    pub fn mov() -> Self {
+        Self {
+            mime_type: "video/quicktime".to_string(),
+            extension: "mov".to_string(),
+            family: FormatFamily::Video,
+            name: "QuickTime Movie".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new Matroska format instance
+    #[must_use]
+    pub fn mkv() -> Self {
+        Self {
+            mime_type: "video/x-matroska".to_string(),
+            extension: "mkv".to_string(),
+            family: FormatFamily::Video,
+            name: "Matroska Video".to_string(),
+            is_container: false,
+        }
+    }
+
+    /// Create a new `WebM` format instance
+    #[must_use]
+    pub fn webm() -> Self {
+        Self {
+            mime_type: "video/webm".to_string(),
+            extension: "webm".to_string(),
+            family: FormatFamily::Video,
+            name: "WebM Video".to_string(),
+            is_container: false,
+        }
+    }
 }
 
 /// Format families for categorization
@@ -447,8 +581,27 @@ pub enum DetectionMethod {
     ContentAnalysis,
     /// Detected via container inspection (e.g., ZIP containing Office files)
     ContainerInspection,
+    /// Declared by the caller, skipping detection
+    Declared,
 }
 
+impl DetectionMethod {
+    /// Precedence between results of equal confidence, lowest first
+    fn rank(self) -> u8 {
+        match self {
+            Self::Declared => 0,
+            Self::ContainerInspection => 1,
+            Self::MagicBytes => 2,
+            Self::Extension => 3,
+            Self::ContentAnalysis => 4,
+        }
+    }
+}
+
+/// Confidence of a container format whose content was identified, so that
+/// it ranks below the format it holds but stays available as a fallback
+const CONTAINER_FALLBACK_CONFIDENCE: f64 = 0.5;
+
 // =========================================
 // Format signatures database
 // =========================================
@@ -491,6 +644,36 @@ static SIGNATURES: &[FormatSignature] = &[
         offset: 0,
         format: Format::gzip,
     },
+    // ISO 9660, after the 32 KiB system area
+    FormatSignature {
+        bytes: b"CD001",
+        offset: 32769,
+        format: Format::iso,
+    },
+    // UDF without ISO 9660, starting its volume recognition sequence
+    FormatSignature {
+        bytes: b"BEA01",
+        offset: 32769,
+        format: Format::iso,
+    },
+    // XZ
+    FormatSignature {
+        bytes: &[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00],
+        offset: 0,
+        format: Format::xz,
+    },
+    // BZIP2
+    FormatSignature {
+        bytes: b"BZh",
+        offset: 0,
+        format: Format::bzip2,
+    },
+    // Zstandard
+    FormatSignature {
+        bytes: &[0x28, 0xB5, 0x2F, 0xFD],
+        offset: 0,
+        format: Format::zstd,
+    },
     // GIF
     FormatSignature {
         bytes: b"GIF87a",
@@ -562,6 +745,56 @@ static SIGNATURES: &[FormatSignature] = &[
             is_container: true,
         },
     },
+    // MP3 with an ID3v2 tag
+    FormatSignature {
+        bytes: b"ID3",
+        offset: 0,
+        format: Format::mp3,
+    },
+    // MP3 without a tag, starting with an MPEG-1 or MPEG-2 layer III frame
+    FormatSignature {
+        bytes: &[0xFF, 0xFB],
+        offset: 0,
+        format: Format::mp3,
+    },
+    FormatSignature {
+        bytes: &[0xFF, 0xF3],
+        offset: 0,
+        format: Format::mp3,
+    },
+    FormatSignature {
+        bytes: &[0xFF, 0xF2],
+        offset: 0,
+        format: Format::mp3,
+    },
+    // ISO base media file (MP4, M4A, QuickTime), by its file type box
+    FormatSignature {
+        bytes: b"ftyp",
+        offset: 4,
+        format: Format::mp4,
+    },
+    // QuickTime movie without a file type box
+    FormatSignature {
+        bytes: b"moov",
+        offset: 4,
+        format: Format::mov,
+    },
+    FormatSignature {
+        bytes: b"wide",
+        offset: 4,
+        format: Format::mov,
+    },
+    FormatSignature {
+        bytes: b"mdat",
+        offset: 4,
+        format: Format::mov,
+    },
+    // Matroska and WebM, by their EBML header
+    FormatSignature {
+        bytes: &[0x1A, 0x45, 0xDF, 0xA3],
+        offset: 0,
+        format: Format::mkv,
+    },
 ];
 
 /// Extension to format mapping
@@ -597,121 +830,424 @@ static EXTENSION_MAP: &[(&str, fn() -> Format)] = &[
     ("gz", Format::gzip),
     ("gzip", Format::gzip),
     ("tgz", Format::gzip), // Often treated as gzip then tar
+    ("iso", Format::iso),
+    ("udf", Format::iso),
+    ("msi", Format::msi),
+    ("xz", Format::xz),
+    ("txz", Format::xz),
+    ("bz2", Format::bzip2),
+    ("bzip2", Format::bzip2),
+    ("tbz2", Format::bzip2),
+    ("tbz", Format::bzip2),
+    ("zst", Format::zstd),
+    ("zstd", Format::zstd),
+    ("tzst", Format::zstd),
+    ("mp3", Format::mp3),
+    ("mp4", Format::mp4),
+    ("m4v", Format::mp4),
+    ("m4a", Format::m4a),
+    ("m4b", Format::m4a),
+    ("mov", Format::mov),
+    ("qt", Format::mov),
+    ("mkv", Format::mkv),
+    ("mka", Format::mkv),
+    ("mk3d", Format::mkv),
+    ("webm", Format::webm),
 ];
 
-/// Detect the format of a document from its content
-///
-/// # Arguments
-///
-/// * `data` - The document content (at least first 8KB recommended)
-/// * `filename` - Optional filename hint for extension-based detection
+// =========================================
+// Format registry
+// =========================================
+
+/// A custom detection step, for formats that magic bytes and extensions
+/// cannot identify (e.g. a ZIP-based format with its own manifest)
+pub type Detector = Arc<dyn Fn(&[u8], Option<&str>) -> Option<DetectionResult> + Send + Sync>;
+
+/// The formats detection knows about
 ///
-/// # Returns
+/// [`FormatRegistry::default`] holds Prism's built-in signatures and
+/// extensions; parsers and plugins add their own with the `register_*`
+/// methods, usually from [`Parser::register_formats`]. Entries registered
+/// later take precedence over earlier ones and over the defaults, so a
+/// plugin can claim an extension or a more specific signature.
 ///
-/// The detected format with confidence, or None if unknown
-#[must_use]
-pub fn detect_format(data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
-    // Try magic bytes first (highest confidence)
-    if let Some(result) = detect_by_magic(data) {
-        // If it's a ZIP, check if it's actually an Office document
-        if result.format.mime_type == "application/zip" {
-            if let Some(office_format) = detect_office_in_zip(data) {
-                return Some(DetectionResult {
-                    format: office_format,
-                    confidence: 0.95,
-                    method: DetectionMethod::ContainerInspection,
-                });
+/// [`Parser::register_formats`]: crate::parser::Parser::register_formats
+#[derive(Clone)]
+pub struct FormatRegistry {
+    signatures: Vec<FormatSignature>,
+    extensions: Vec<(String, Format)>,
+    detectors: Vec<Detector>,
+}
+
+impl FormatRegistry {
+    /// A registry without any formats
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            signatures: Vec::new(),
+            extensions: Vec::new(),
+            detectors: Vec::new(),
+        }
+    }
+
+    /// Recognise `signature.bytes` at `signature.offset`
+    pub fn register_signature(&mut self, signature: FormatSignature) {
+        self.signatures.push(signature);
+    }
+
+    /// Map a file extension (without the dot, case-insensitive) to a format
+    pub fn register_extension(&mut self, extension: &str, format: Format) {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.extensions.push((extension, format));
+    }
+
+    /// Run `detector` before the built-in detection steps
+    pub fn register_detector(
+        &mut self,
+        detector: impl Fn(&[u8], Option<&str>) -> Option<DetectionResult> + Send + Sync + 'static,
+    ) {
+        self.detectors.push(Arc::new(detector));
+    }
+
+    /// Detect the format of a document from its content
+    ///
+    /// Custom detectors run first, then magic bytes; ZIP and OLE containers
+    /// are inspected for the Office format they hold. Data without a
+    /// signature goes through content analysis for text formats, and the
+    /// result is weighed against the extension of `filename`: the more
+    /// confident one wins, and when both agree their confidence is
+    /// combined.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The document content (at least first 8KB recommended)
+    /// * `filename` - Optional filename hint for extension-based detection
+    ///
+    /// # Returns
+    ///
+    /// The detected format with confidence, or None if unknown
+    #[must_use]
+    pub fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        self.detect_all(data, filename).into_iter().next()
+    }
+
+    /// Every plausible format of a document, most likely first
+    ///
+    /// The first entry is what [`detect`](Self::detect) returns; the rest
+    /// are fallbacks for when no parser accepts the data as the first
+    /// format. Results of custom detectors come first in registration
+    /// order, latest first. The built-in candidates follow by confidence:
+    ///
+    /// - every matching signature, with a container that holds a known
+    ///   format (a DOCX inside a ZIP) ranked below the format it holds
+    /// - the content analysis result
+    /// - the format of the extension, which loses to a signature but is
+    ///   combined with content analysis when both agree
+    ///
+    /// On equal confidence, container inspection ranks above magic bytes,
+    /// magic bytes above the extension and the extension above content
+    /// analysis. Each format appears once.
+    #[must_use]
+    pub fn detect_all(&self, data: &[u8], filename: Option<&str>) -> Vec<DetectionResult> {
+        let mut custom: Vec<DetectionResult> = Vec::new();
+        for result in self
+            .detectors
+            .iter()
+            .rev()
+            .filter_map(|detector| detector(data, filename))
+        {
+            push_candidate(&mut custom, result);
+        }
+
+        let mut candidates = Vec::new();
+        for result in self.detect_by_magic(data) {
+            let inspected = match result.format.mime_type.as_str() {
+                // Is the ZIP actually an Office document?
+                "application/zip" => detect_office_in_zip(data),
+                // Is the OLE2/CFB file a legacy Office document?
+                "application/x-cfb" => {
+                    detect_office_in_ole(data, filename).map(|format| DetectionResult {
+                        format,
+                        confidence: 0.95,
+                        method: DetectionMethod::ContainerInspection,
+                    })
+                }
+                // Is the MP4 a QuickTime movie or MPEG-4 audio?
+                "video/mp4" => detect_brand_in_ftyp(data),
+                // Is the Matroska file a WebM file?
+                "video/x-matroska" => detect_webm(data),
+                _ => None,
+            };
+            match inspected {
+                Some(inspected) => {
+                    candidates.push(inspected);
+                    candidates.push(DetectionResult {
+                        confidence: CONTAINER_FALLBACK_CONFIDENCE,
+                        ..result
+                    });
+                }
+                None => candidates.push(result),
             }
         }
-        // If it's OLE2/CFB, check if it's a legacy Office document
-        if result.format.mime_type == "application/x-cfb" {
-            if let Some(office_format) = detect_office_in_ole(data, filename) {
-                return Some(DetectionResult {
-                    format: office_format,
-                    confidence: 0.95,
-                    method: DetectionMethod::ContainerInspection,
+
+        let content = detect_by_content(data);
+        let extension = filename.and_then(|filename| self.detect_by_extension(filename));
+        match (content, extension) {
+            (Some(content), Some(extension)) if content.format == extension.format => {
+                // Independent evidence for the same format
+                let confidence = 1.0 - (1.0 - content.confidence) * (1.0 - extension.confidence);
+                candidates.push(DetectionResult {
+                    confidence: confidence.min(0.98),
+                    ..content
                 });
             }
+            (content, extension) => candidates.extend(content.into_iter().chain(extension)),
         }
-        return Some(result);
-    }
 
-    // Try extension-based detection
-    if let Some(filename) = filename {
-        if let Some(result) = detect_by_extension(filename) {
-            return Some(result);
+        // Stable, so signatures keep their registration precedence
+        candidates.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.method.rank().cmp(&b.method.rank()))
+        });
+        for candidate in candidates {
+            push_candidate(&mut custom, candidate);
         }
+        custom
     }
 
-    None
-}
+    /// Get format information by extension
+    #[must_use]
+    pub fn format_by_extension(&self, extension: &str) -> Option<Format> {
+        let ext = extension.trim_start_matches('.').to_lowercase();
+        self.extensions
+            .iter()
+            .rev()
+            .find(|(e, _)| *e == ext)
+            .map(|(_, format)| format.clone())
+    }
 
-/// Detect format by magic bytes
-fn detect_by_magic(data: &[u8]) -> Option<DetectionResult> {
-    for sig in SIGNATURES {
-        if data.len() >= sig.offset + sig.bytes.len() {
-            let slice = &data[sig.offset..sig.offset + sig.bytes.len()];
-            if slice == sig.bytes {
-                return Some(DetectionResult {
+    /// Get format information by MIME type, ignoring case and parameters
+    /// such as `charset`
+    #[must_use]
+    pub fn format_by_mime(&self, mime_type: &str) -> Option<Format> {
+        let mime_type = mime_type.split(';').next().unwrap_or_default().trim();
+        let matches = |format: &Format| format.mime_type.eq_ignore_ascii_case(mime_type);
+        self.extensions
+            .iter()
+            .rev()
+            .map(|(_, format)| format.clone())
+            .find(|format| matches(format))
+            .or_else(|| {
+                self.signatures
+                    .iter()
+                    .rev()
+                    .map(|signature| (signature.format)())
+                    .find(|format| matches(format))
+            })
+    }
+
+    /// Every signature that matches, latest registration first
+    fn detect_by_magic(&self, data: &[u8]) -> Vec<DetectionResult> {
+        self.signatures
+            .iter()
+            .rev()
+            .filter_map(|sig| {
+                let slice = data.get(sig.offset..sig.offset + sig.bytes.len())?;
+                (slice == sig.bytes).then(|| DetectionResult {
                     format: (sig.format)(),
                     confidence: 0.99,
                     method: DetectionMethod::MagicBytes,
-                });
-            }
-        }
+                })
+            })
+            .collect()
+    }
+
+    /// Detect format by file extension
+    fn detect_by_extension(&self, filename: &str) -> Option<DetectionResult> {
+        let ext = filename.rsplit('.').next()?;
+        self.format_by_extension(ext).map(|format| DetectionResult {
+            format,
+            confidence: 0.7,
+            method: DetectionMethod::Extension,
+        })
     }
-    None
 }
 
-/// Detect format by file extension
-fn detect_by_extension(filename: &str) -> Option<DetectionResult> {
-    let ext = filename.rsplit('.').next()?.to_lowercase();
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        Self::with_default_formats()
+    }
+}
 
-    for (extension, format_fn) in EXTENSION_MAP {
-        if ext == *extension {
-            return Some(DetectionResult {
-                format: format_fn(),
-                confidence: 0.7,
-                method: DetectionMethod::Extension,
-            });
+impl FormatRegistry {
+    /// A registry with Prism's built-in formats
+    #[must_use]
+    pub fn with_default_formats() -> Self {
+        // Reversed so that earlier table entries keep precedence, as the
+        // registry searches from the most recent registration
+        Self {
+            signatures: SIGNATURES.iter().rev().cloned().collect(),
+            extensions: EXTENSION_MAP
+                .iter()
+                .rev()
+                .map(|(extension, format)| ((*extension).to_string(), format()))
+                .collect(),
+            detectors: Vec::new(),
         }
     }
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormatRegistry")
+            .field("signatures", &self.signatures.len())
+            .field("extensions", &self.extensions.len())
+            .field("detectors", &self.detectors.len())
+            .finish()
+    }
+}
 
-    None
+/// The built-in registry used by the free functions
+fn default_registry() -> &'static FormatRegistry {
+    static DEFAULT: OnceLock<FormatRegistry> = OnceLock::new();
+    DEFAULT.get_or_init(FormatRegistry::default)
 }
 
-/// Check if a ZIP file is actually an Office document
-fn detect_office_in_zip(data: &[u8]) -> Option<Format> {
-    // Simple check: look for "[Content_Types].xml" which is present in OOXML
-    // In a real implementation, you'd actually parse the ZIP
-
-    let content_types = b"[Content_Types].xml";
-    if data
-        .windows(content_types.len())
-        .any(|w| w == content_types)
+/// Detect the format of a document with the built-in formats
+///
+/// See [`FormatRegistry::detect`]; use a registry to include formats added
+/// by parsers and plugins.
+#[must_use]
+pub fn detect_format(data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+    default_registry().detect(data, filename)
+}
+
+/// Every plausible format of a document with the built-in formats, most
+/// likely first
+///
+/// See [`FormatRegistry::detect_all`].
+#[must_use]
+pub fn detect_format_all(data: &[u8], filename: Option<&str>) -> Vec<DetectionResult> {
+    default_registry().detect_all(data, filename)
+}
+
+/// Add `result` unless its format is already a candidate
+fn push_candidate(candidates: &mut Vec<DetectionResult>, result: DetectionResult) {
+    if !candidates
+        .iter()
+        .any(|candidate| candidate.format == result.format)
     {
-        // Check for specific document types - order matters, check most specific first
-        // Look for directory names which are more reliable
-        if data.windows(9).any(|w| w == b"ppt/slides") || data.windows(3).any(|w| w == b"ppt") {
-            return Some(Format::pptx());
-        }
-        if data.windows(9).any(|w| w == b"xl/workbook")
-            || data.windows(13).any(|w| w == b"xl/worksheets")
-        {
-            return Some(Format::xlsx());
+        candidates.push(result);
+    }
+}
+
+/// Check if a ZIP file is actually an Office document
+///
+/// The entry names are read from the central directory at the end of the
+/// file. When it is missing, because only the start of the file was passed
+/// or the file is truncated, the local file headers are scanned instead,
+/// with lower confidence since later entries are not seen.
+fn detect_office_in_zip(data: &[u8]) -> Option<DetectionResult> {
+    let (names, confidence) = match zip_central_directory(data) {
+        Some(names) => (names, 0.98),
+        None => (zip_local_names(data), 0.9),
+    };
+    if !names.iter().any(|name| *name == b"[Content_Types].xml") {
+        return None;
+    }
+
+    // The main part of each OOXML format lives in its own top-level folder
+    let format = [
+        (b"word/".as_slice(), Format::docx as fn() -> Format),
+        (b"xl/", Format::xlsx),
+        (b"ppt/", Format::pptx),
+    ]
+    .into_iter()
+    .find(|(folder, _)| names.iter().any(|name| name.starts_with(folder)))
+    .map(|(_, format)| format())?;
+
+    Some(DetectionResult {
+        format,
+        confidence,
+        method: DetectionMethod::ContainerInspection,
+    })
+}
+
+/// Signature of the ZIP end of central directory record
+const ZIP_END_OF_CENTRAL_DIRECTORY: &[u8] = b"PK\x05\x06";
+
+/// Signature of a ZIP central directory file header
+const ZIP_CENTRAL_HEADER: &[u8] = b"PK\x01\x02";
+
+/// Signature of a ZIP local file header
+const ZIP_LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+
+/// Entry names listed in the central directory of a ZIP file
+///
+/// Returns `None` if there is no readable central directory, including
+/// ZIP64 archives whose offsets do not fit the classic record.
+fn zip_central_directory(data: &[u8]) -> Option<Vec<&[u8]>> {
+    // The record is 22 bytes plus a comment of up to 65535 bytes
+    let search_start = data.len().saturating_sub(22 + usize::from(u16::MAX));
+    let end = search_start
+        + data[search_start..]
+            .windows(4)
+            .rposition(|window| window == ZIP_END_OF_CENTRAL_DIRECTORY)?;
+
+    let entries = read_le_u16(data, end + 10)?;
+    let mut offset = usize::try_from(read_le_u32(data, end + 16)?).ok()?;
+    let mut names = Vec::with_capacity(usize::from(entries));
+    for _ in 0..entries {
+        if data.get(offset..offset + 4)? != ZIP_CENTRAL_HEADER {
+            return None;
         }
-        if data.windows(10).any(|w| w == b"word/document") || data.windows(4).any(|w| w == b"word")
-        {
-            return Some(Format::docx());
+        let name_len = usize::from(read_le_u16(data, offset + 28)?);
+        let extra_len = usize::from(read_le_u16(data, offset + 30)?);
+        let comment_len = usize::from(read_le_u16(data, offset + 32)?);
+        names.push(data.get(offset + 46..offset + 46 + name_len)?);
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+    Some(names)
+}
+
+/// Entry names of the ZIP local file headers found in `data`
+fn zip_local_names(data: &[u8]) -> Vec<&[u8]> {
+    let mut names = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = data.get(offset..).and_then(|rest| {
+        rest.windows(4)
+            .position(|window| window == ZIP_LOCAL_HEADER)
+    }) {
+        let start = offset + found;
+        let name = read_le_u16(data, start + 26)
+            .and_then(|name_len| data.get(start + 30..start + 30 + usize::from(name_len)));
+        if let Some(name) = name {
+            names.push(name);
         }
+        offset = start + 4;
     }
+    names
+}
 
-    None
+fn read_le_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
 }
 
 /// Detect specific Office format in OLE2/CFB files (DOC, XLS, PPT, MSG)
 /// Note: This function should only be called if magic bytes already confirmed OLE2/CFB format
 fn detect_office_in_ole(data: &[u8], filename: Option<&str>) -> Option<Format> {
+    // Windows Installer packages are marked by the class of the root storage
+    if ole_root_clsid(data) == Some(&MSI_CLSID) {
+        return Some(Format::msi());
+    }
+
     // Look for stream names in the OLE2 structure
     // Word documents have "WordDocument" stream
     if data.windows(12).any(|w| w == b"WordDocument") {
@@ -737,43 +1273,383 @@ fn detect_office_in_ole(data: &[u8], filename: Option<&str>) -> Option<Format> {
         if ext == "msg" {
             return Some(Format::msg());
         }
+        if ext == "msi" {
+            return Some(Format::msi());
+        }
+
+        // An encrypted OOXML document is a compound file with an
+        // `EncryptedPackage` stream; only the extension tells which kind
+        let encrypted_package: Vec<u8> = "EncryptedPackage"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        if data
+            .windows(encrypted_package.len())
+            .any(|w| w == encrypted_package)
+        {
+            match ext.as_str() {
+                "docx" => return Some(Format::docx()),
+                "xlsx" => return Some(Format::xlsx()),
+                "pptx" => return Some(Format::pptx()),
+                _ => {}
+            }
+        }
     }
 
     None
 }
 
-/// Get format information by MIME type
-#[must_use]
-pub fn format_by_mime(mime_type: &str) -> Option<Format> {
-    match mime_type {
-        "application/pdf" => Some(Format::pdf()),
-        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
-            Some(Format::docx())
-        }
-        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => Some(Format::xlsx()),
-        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => {
-            Some(Format::pptx())
+/// The format named by the major brand of an ISO base media file, when it
+/// is not plain MP4
+fn detect_brand_in_ftyp(data: &[u8]) -> Option<DetectionResult> {
+    let format = match data.get(8..12)? {
+        b"qt  " => Format::mov(),
+        b"M4A " | b"M4B " | b"M4P " => Format::m4a(),
+        _ => return None,
+    };
+    Some(DetectionResult {
+        format,
+        confidence: 0.99,
+        method: DetectionMethod::ContainerInspection,
+    })
+}
+
+/// `WebM` if the document type of the EBML header of a Matroska file is
+/// `webm`
+fn detect_webm(data: &[u8]) -> Option<DetectionResult> {
+    // The DocType element, 0x4282, with its 4-byte size
+    let header = &data[..data.len().min(64)];
+    header
+        .windows(7)
+        .any(|window| window == b"\x42\x82\x84webm")
+        .then(|| DetectionResult {
+            format: Format::webm(),
+            confidence: 0.99,
+            method: DetectionMethod::ContainerInspection,
+        })
+}
+
+/// CLSID of the root storage of a Windows Installer package,
+/// {000C1084-0000-0000-C000-000000000046}, as stored
+const MSI_CLSID: [u8; 16] = [
+    0x84, 0x10, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+/// The CLSID of the root storage of a compound file, if the first
+/// directory sector is within `data`
+fn ole_root_clsid(data: &[u8]) -> Option<&[u8]> {
+    let shift = u16::from_le_bytes(data.get(30..32)?.try_into().ok()?);
+    if !(9..=12).contains(&shift) {
+        return None;
+    }
+    let first_directory_sector = u32::from_le_bytes(data.get(48..52)?.try_into().ok()?);
+    let root = usize::try_from(first_directory_sector)
+        .ok()?
+        .checked_add(1)?
+        .checked_shl(u32::from(shift))?;
+    data.get(root.checked_add(0x50)?..root.checked_add(0x60)?)
+}
+
+// =========================================
+// Content analysis
+// =========================================
+
+/// Bytes examined by content analysis
+const CONTENT_SAMPLE_LEN: usize = 8 * 1024;
+
+/// Lines examined by the line-based heuristics
+const CONTENT_SAMPLE_LINES: usize = 20;
+
+/// Header fields that commonly open an email message
+const EMAIL_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "subject",
+    "date",
+    "message-id",
+    "received",
+    "return-path",
+    "mime-version",
+    "content-type",
+    "delivered-to",
+    "reply-to",
+];
+
+/// Detect a text format from the content itself
+///
+/// The data must be text: it starts with a byte order mark, or it is valid
+/// UTF-8 without control characters. Confidence follows how distinctive the
+/// evidence is: data that parses as JSON is almost certainly JSON, while a
+/// consistent comma count only suggests CSV, so weak matches stay below the
+/// confidence of an extension match and do not override it.
+fn detect_by_content(data: &[u8]) -> Option<DetectionResult> {
+    let (text, has_bom) = decode_text(data)?;
+    let truncated = data.len() > CONTENT_SAMPLE_LEN;
+    let text = text.trim_start();
+
+    let (format, confidence) = analyze_text(text, truncated)
+        .or_else(|| (has_bom && !text.is_empty()).then(|| (Format::text(), 0.6)))?;
+    Some(DetectionResult {
+        format,
+        confidence,
+        method: DetectionMethod::ContentAnalysis,
+    })
+}
+
+/// Decode the start of `data` as text
+///
+/// Returns the text and whether it started with a byte order mark, or
+/// `None` if the data is not text.
+fn decode_text(data: &[u8]) -> Option<(String, bool)> {
+    let sample = &data[..data.len().min(CONTENT_SAMPLE_LEN)];
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        char::decode_utf16(bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])))
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<String>()
+    };
+
+    if let Some(rest) = sample.strip_prefix(b"\xEF\xBB\xBF") {
+        return Some((utf8_prefix(rest)?.to_string(), true));
+    }
+    if let Some(rest) = sample.strip_prefix(b"\xFF\xFE") {
+        return Some((utf16(rest, u16::from_le_bytes), true));
+    }
+    if let Some(rest) = sample.strip_prefix(b"\xFE\xFF") {
+        return Some((utf16(rest, u16::from_be_bytes), true));
+    }
+
+    let text = utf8_prefix(sample)?;
+    // Escape is allowed for coloured terminal logs
+    if text
+        .chars()
+        .any(|c| c.is_control() && !c.is_whitespace() && c != '\x1b')
+    {
+        return None;
+    }
+    Some((text.to_string(), false))
+}
+
+/// `bytes` as UTF-8, tolerating a character cut off at the end
+fn utf8_prefix(bytes: &[u8]) -> Option<&str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
+
+/// Match text against the text formats, most distinctive first
+///
+/// `truncated` is set when `text` is only the start of the data.
+fn analyze_text(text: &str, truncated: bool) -> Option<(Format, f64)> {
+    if text.is_empty() {
+        return None;
+    }
+    let head: String = text
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if head.starts_with('{') || head.starts_with('[') {
+        return json_confidence(text, truncated).map(|confidence| (Format::json(), confidence));
+    }
+    if head.starts_with('<') {
+        return markup_format(&head);
+    }
+    if head.starts_with("begin:vcard") {
+        return Some((Format::vcf(), 0.95));
+    }
+    if head.starts_with("begin:vcalendar") {
+        return Some((Format::ics(), 0.95));
+    }
+
+    let mut lines: Vec<&str> = text.lines().take(CONTENT_SAMPLE_LINES + 1).collect();
+    if truncated || lines.len() > CONTENT_SAMPLE_LINES {
+        // The last line may be cut off
+        lines.pop();
+    }
+
+    if let Some(rest) = lines.first().and_then(|line| line.strip_prefix("From ")) {
+        if !rest.is_empty() && lines.get(1).is_some_and(|line| header_name(line).is_some()) {
+            return Some((Format::mbox(), 0.85));
         }
-        "image/png" => Some(Format::png()),
-        "image/jpeg" => Some(Format::jpeg()),
-        "image/tiff" => Some(Format::tiff()),
-        "text/html" => Some(Format::html()),
-        _ => None,
     }
+    email_confidence(&lines)
+        .map(|confidence| (Format::eml(), confidence))
+        .or_else(|| markdown_confidence(&lines).map(|confidence| (Format::markdown(), confidence)))
+        .or_else(|| csv_confidence(&lines).map(|confidence| (Format::csv(), confidence)))
+        .or_else(|| log_confidence(&lines).map(|confidence| (Format::log(), confidence)))
 }
 
-/// Get format information by extension
-#[must_use]
-pub fn format_by_extension(extension: &str) -> Option<Format> {
-    let ext = extension.trim_start_matches('.').to_lowercase();
+/// JSON: a complete document that parses, a cut-off one that is valid as
+/// far as it goes, or JSON Lines
+fn json_confidence(text: &str, truncated: bool) -> Option<f64> {
+    match serde_json::from_str::<serde::de::IgnoredAny>(text) {
+        Ok(_) if !truncated => return Some(0.95),
+        Err(e) if truncated && e.is_eof() => return Some(0.9),
+        _ => {}
+    }
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let first_is_json = lines
+        .next()
+        .is_some_and(|line| serde_json::from_str::<serde::de::IgnoredAny>(line).is_ok());
+    first_is_json.then_some(0.7)
+}
 
-    for (e, format_fn) in EXTENSION_MAP {
-        if ext == *e {
-            return Some(format_fn());
+/// HTML or XML, from the lowercased start of the text
+fn markup_format(head: &str) -> Option<(Format, f64)> {
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        return Some((Format::html(), 0.95));
+    }
+    let has_html_element = ["<html", "<head", "<body"]
+        .iter()
+        .any(|tag| head.contains(tag));
+    if head.starts_with("<?xml") {
+        // XHTML keeps the XML declaration
+        return Some(if has_html_element {
+            (Format::html(), 0.9)
+        } else {
+            (Format::xml(), 0.9)
+        });
+    }
+    if has_html_element {
+        return Some((Format::html(), 0.8));
+    }
+    head[1..]
+        .starts_with(|c: char| c.is_ascii_alphabetic() || c == '!')
+        .then(|| (Format::xml(), 0.6))
+}
+
+/// Name of a `Name: value` header line, lowercased
+fn header_name(line: &str) -> Option<String> {
+    let (name, _) = line.split_once(':')?;
+    let valid = !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic() && b != b':');
+    valid.then(|| name.to_ascii_lowercase())
+}
+
+/// Email: the text opens with a block of header fields
+fn email_confidence(lines: &[&str]) -> Option<f64> {
+    let mut known = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            break;
+        }
+        // Folded continuation of the previous header
+        if index > 0 && line.starts_with([' ', '\t']) {
+            continue;
         }
+        let name = header_name(line)?;
+        known += usize::from(EMAIL_HEADERS.contains(&name.as_str()));
+    }
+    match known {
+        0 | 1 => None,
+        2 => Some(0.6),
+        _ => Some(0.85),
     }
+}
 
-    None
+/// Markdown: several kinds of Markdown syntax
+fn markdown_confidence(lines: &[&str]) -> Option<f64> {
+    let mut heading = false;
+    let mut list = false;
+    let mut fence = false;
+    let mut link = false;
+    let mut emphasis = false;
+    for line in lines {
+        let trimmed = line.trim_start();
+        let hashes = trimmed.bytes().take_while(|&b| b == b'#').count();
+        heading |= (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ');
+        list |= trimmed.starts_with("- ") || trimmed.starts_with("* ") || {
+            let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+            digits > 0 && trimmed[digits..].starts_with(". ")
+        };
+        fence |= trimmed.starts_with("```");
+        link |= line.contains("](");
+        emphasis |= line.contains("**") || line.contains("__");
+    }
+    match [heading, list, fence, link, emphasis]
+        .into_iter()
+        .filter(|&found| found)
+        .count()
+    {
+        0 | 1 => None,
+        2 => Some(0.55),
+        _ => Some(0.65),
+    }
+}
+
+/// CSV: every line has the same number of delimiters outside quotes
+fn csv_confidence(lines: &[&str]) -> Option<f64> {
+    if lines.len() < 2 {
+        return None;
+    }
+    let count = |line: &str, delimiter: char| {
+        let mut quoted = false;
+        line.chars()
+            .filter(|&c| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c == delimiter && !quoted
+            })
+            .count()
+    };
+    let consistent = [',', ';', '\t'].into_iter().any(|delimiter| {
+        let first = count(lines[0], delimiter);
+        first > 0 && lines.iter().all(|line| count(line, delimiter) == first)
+    });
+    match (consistent, lines.len()) {
+        (false, _) => None,
+        (true, 2..=4) => Some(0.5),
+        (true, _) => Some(0.65),
+    }
+}
+
+/// Logs: most lines start with a timestamp or a severity level
+fn log_confidence(lines: &[&str]) -> Option<f64> {
+    const LEVELS: &[&str] = &["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+    const MONTHS: &[&str] = &[
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim_start_matches('['))
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if lines.len() < 3 {
+        return None;
+    }
+    let is_entry = |line: &str| {
+        let bytes = line.as_bytes();
+        // 2024-01-31 or 2024/01/31
+        let iso_date = bytes.len() >= 10
+            && bytes[..4].iter().all(u8::is_ascii_digit)
+            && matches!(bytes[4], b'-' | b'/')
+            && bytes[5..7].iter().all(u8::is_ascii_digit)
+            && bytes[7] == bytes[4]
+            && bytes[8..10].iter().all(u8::is_ascii_digit);
+        // Syslog: Jan 31 12:00:00
+        let syslog =
+            MONTHS.iter().any(|month| line.starts_with(month)) && line.get(3..4) == Some(" ");
+        iso_date || syslog || LEVELS.iter().any(|level| line.starts_with(level))
+    };
+    let entries = lines.iter().filter(|line| is_entry(line)).count();
+    (entries * 10 >= lines.len() * 6).then_some(0.6)
+}
+
+/// Get format information by MIME type, among the built-in formats
+#[must_use]
+pub fn format_by_mime(mime_type: &str) -> Option<Format> {
+    default_registry().format_by_mime(mime_type)
+}
+
+/// Get format information by extension, among the built-in formats
+#[must_use]
+pub fn format_by_extension(extension: &str) -> Option<Format> {
+    default_registry().format_by_extension(extension)
 }
 
 #[cfg(test)]
@@ -802,6 +1678,77 @@ mod tests {
         assert_eq!(result.format.mime_type, "image/png");
     }
 
+    #[test]
+    fn test_detect_compressed() {
+        let xz = [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00, 0x00, 0x04];
+        assert_eq!(detect_format(&xz, None).unwrap().format, Format::xz());
+        let bzip2 = b"BZh91AY&SY";
+        assert_eq!(detect_format(bzip2, None).unwrap().format, Format::bzip2());
+        let zstd = [0x28, 0xB5, 0x2F, 0xFD, 0x24, 0x00];
+        assert_eq!(detect_format(&zstd, None).unwrap().format, Format::zstd());
+
+        let result = detect_format(b"unknown content", Some("logs.tar.zst")).unwrap();
+        assert_eq!(result.format, Format::zstd());
+        assert_eq!(result.method, DetectionMethod::Extension);
+    }
+
+    #[test]
+    fn test_detect_iso_and_msi() {
+        let mut iso = vec![0u8; 32 * 1024 + 6];
+        iso[32 * 1024..].copy_from_slice(b"\x01CD001");
+        assert_eq!(detect_format(&iso, None).unwrap().format, Format::iso());
+
+        // A compound file whose root storage has the installer class
+        let mut msi = vec![0u8; 1024];
+        msi[..8].copy_from_slice(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+        msi[30..32].copy_from_slice(&9u16.to_le_bytes());
+        msi[512 + 0x50..512 + 0x60].copy_from_slice(&MSI_CLSID);
+        assert_eq!(detect_format(&msi, None).unwrap().format, Format::msi());
+    }
+
+    #[test]
+    fn test_detect_media() {
+        assert_eq!(
+            detect_format(b"ID3\x04\x00", None).unwrap().format,
+            Format::mp3()
+        );
+        assert_eq!(
+            detect_format(&[0xFF, 0xFB, 0x90, 0x64], None)
+                .unwrap()
+                .format,
+            Format::mp3()
+        );
+        let ftyp = |brand: &[u8]| [b"\0\0\0\x18ftyp".as_slice(), brand, b"\0\0\0\0"].concat();
+        assert_eq!(
+            detect_format(&ftyp(b"isom"), None).unwrap().format,
+            Format::mp4()
+        );
+        assert_eq!(
+            detect_format(&ftyp(b"qt  "), None).unwrap().format,
+            Format::mov()
+        );
+        assert_eq!(
+            detect_format(&ftyp(b"M4A "), None).unwrap().format,
+            Format::m4a()
+        );
+
+        let ebml = |doc_type: &[u8]| {
+            let mut header = vec![0x1A, 0x45, 0xDF, 0xA3, 0x9F, 0x42, 0x82, 0x84];
+            header.extend_from_slice(doc_type);
+            header
+        };
+        assert_eq!(
+            detect_format(&ebml(b"matr"), None).unwrap().format,
+            Format::mkv()
+        );
+        assert_eq!(
+            detect_format(&ebml(b"webm"), None).unwrap().format,
+            Format::webm()
+        );
+        assert_eq!(Format::mkv().family, FormatFamily::Video);
+        assert_eq!(Format::mp3().family, FormatFamily::Audio);
+    }
+
     #[test]
     fn test_detect_by_extension() {
         let result = detect_format(b"unknown content", Some("document.pdf"));
@@ -813,6 +1760,187 @@ mod tests {
         assert!(result.confidence < 0.99); // Lower confidence for extension-based
     }
 
+    #[test]
+    fn test_format_by_mime() {
+        assert_eq!(
+            format_by_mime("application/vnd.ms-outlook"),
+            Some(Format::msg())
+        );
+        assert_eq!(
+            format_by_mime("Text/CSV; charset=utf-8"),
+            Some(Format::csv())
+        );
+        assert_eq!(format_by_mime("application/pdf"), Some(Format::pdf()));
+        assert_eq!(format_by_mime("application/x-unknown"), None);
+    }
+
+    /// A ZIP file of empty stored entries
+    fn zip(names: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut central = Vec::new();
+        for name in names {
+            let offset = u32::try_from(data.len()).unwrap();
+            let name_len = u16::try_from(name.len()).unwrap().to_le_bytes();
+            data.extend_from_slice(ZIP_LOCAL_HEADER);
+            data.extend_from_slice(&[0; 22]);
+            data.extend_from_slice(&name_len);
+            data.extend_from_slice(&[0; 2]);
+            data.extend_from_slice(name.as_bytes());
+
+            central.extend_from_slice(ZIP_CENTRAL_HEADER);
+            central.extend_from_slice(&[0; 24]);
+            central.extend_from_slice(&name_len);
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = u32::try_from(data.len()).unwrap();
+        let count = u16::try_from(names.len()).unwrap().to_le_bytes();
+        data.extend_from_slice(&central);
+        data.extend_from_slice(ZIP_END_OF_CENTRAL_DIRECTORY);
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&u32::try_from(central.len()).unwrap().to_le_bytes());
+        data.extend_from_slice(&central_offset.to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data
+    }
+
+    #[test]
+    fn test_detect_office_in_zip() {
+        let docx = zip(&["[Content_Types].xml", "_rels/.rels", "word/document.xml"]);
+        let result = detect_format(&docx, None).unwrap();
+        assert_eq!(result.format, Format::docx());
+        assert_eq!(result.method, DetectionMethod::ContainerInspection);
+        assert!(result.confidence > 0.95);
+
+        // Only the first bytes: found through the local headers
+        let result = detect_format(&docx[..docx.len() - 80], None).unwrap();
+        assert_eq!(result.format, Format::docx());
+        assert!(result.confidence < 0.95);
+
+        let xlsx = zip(&["[Content_Types].xml", "xl/workbook.xml"]);
+        assert_eq!(detect_format(&xlsx, None).unwrap().format, Format::xlsx());
+
+        // Ordinary archives that merely mention the folder names
+        let plain = zip(&["keyword/ppt-notes.txt", "xl.csv", "[Content_Types].xml.bak"]);
+        assert_eq!(detect_format(&plain, None).unwrap().format, Format::zip());
+    }
+
+    #[test]
+    fn test_detect_by_content() {
+        let detect = |data: &[u8]| detect_format(data, None).map(|result| result.format);
+
+        assert_eq!(
+            detect(br#"{"name": "Prism", "tags": [1, 2]}"#),
+            Some(Format::json())
+        );
+        assert_eq!(detect(b"{\"a\": 1}\n{\"a\": 2}\n"), Some(Format::json()));
+        assert_eq!(detect(b"{ not json"), None);
+        assert_eq!(
+            detect(b"<!DOCTYPE html><html><body>Hi</body></html>"),
+            Some(Format::html())
+        );
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?><html xmlns=\"http://www.w3.org/1999/xhtml\"/>"),
+            Some(Format::html())
+        );
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?><root/>"),
+            Some(Format::xml())
+        );
+        assert_eq!(
+            detect(b"From: a@example.com\nTo: b@example.com\nSubject: Hi\n\nBody"),
+            Some(Format::eml())
+        );
+        assert_eq!(
+            detect(b"BEGIN:VCARD\nVERSION:3.0\nFN:Ada\nEND:VCARD\n"),
+            Some(Format::vcf())
+        );
+        assert_eq!(
+            detect(b"# Title\n\nSome **bold** text with a [link](https://example.com)\n"),
+            Some(Format::markdown())
+        );
+        assert_eq!(
+            detect(b"id,name,city\n1,Ada,\"London, UK\"\n2,Alan,Wilmslow\n3,Grace,NYC\n4,Linus,Helsinki\n"),
+            Some(Format::csv())
+        );
+        assert_eq!(
+            detect(b"2024-01-31 12:00:00 INFO start\n2024-01-31 12:00:01 WARN slow\n2024-01-31 12:00:02 INFO done\n"),
+            Some(Format::log())
+        );
+
+        // A byte order mark is enough to call it text
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("hello".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(detect(&utf16), Some(Format::text()));
+        assert_eq!(detect(b"\xEF\xBB\xBFhello"), Some(Format::text()));
+        assert_eq!(detect(b"hello\x00\x01binary"), None);
+    }
+
+    #[test]
+    fn test_content_and_extension() {
+        // Agreement raises confidence above either source alone
+        let csv = b"a,b\n1,2\n";
+        let result = detect_format(csv, Some("data.csv")).unwrap();
+        assert_eq!(result.format, Format::csv());
+        assert_eq!(result.method, DetectionMethod::ContentAnalysis);
+        assert!(result.confidence > 0.7);
+
+        // Weak content evidence does not override the extension
+        let result = detect_format(csv, Some("notes.txt")).unwrap();
+        assert_eq!(result.format, Format::text());
+
+        // Strong content evidence does
+        let result = detect_format(b"<!DOCTYPE html><p>x</p>", Some("page.txt")).unwrap();
+        assert_eq!(result.format, Format::html());
+    }
+
+    #[test]
+    fn test_detect_all() {
+        let formats = |data: &[u8], filename| -> Vec<Format> {
+            detect_format_all(data, filename)
+                .into_iter()
+                .map(|result| result.format)
+                .collect()
+        };
+
+        // Signature first, the disagreeing extension as a fallback
+        assert_eq!(
+            formats(b"%PDF-1.7", Some("scan.png")),
+            [Format::pdf(), Format::png()]
+        );
+        // The container ranks below the format it holds
+        let docx = zip(&["[Content_Types].xml", "word/document.xml"]);
+        let results = detect_format_all(&docx, Some("report.docx"));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].format, Format::docx());
+        assert_eq!(results[1].format, Format::zip());
+        assert!(results[1].confidence < 0.7);
+
+        // Agreement is one candidate, disagreement two
+        assert_eq!(formats(b"a,b\n1,2\n", Some("data.csv")), [Format::csv()]);
+        assert_eq!(
+            formats(b"a,b\n1,2\n", Some("notes.txt")),
+            [Format::text(), Format::csv()]
+        );
+        assert!(formats(b"random bytes", None).is_empty());
+
+        // Custom detectors come first whatever their confidence
+        let mut registry = FormatRegistry::default();
+        registry.register_detector(|data, _| {
+            data.starts_with(b"%PDF").then(|| DetectionResult {
+                format: Format::text(),
+                confidence: 0.1,
+                method: DetectionMethod::ContentAnalysis,
+            })
+        });
+        let results = registry.detect_all(b"%PDF-1.7", None);
+        assert_eq!(results[0].format, Format::text());
+        assert_eq!(results[1].format, Format::pdf());
+    }
+
     #[test]
     fn test_unknown_format() {
         let result = detect_format(b"random bytes", None);
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! String interning for the Unified Document Model.
+//!
+//! Run text and style strings are `Arc<str>`, so equal values can share one
+//! allocation. Spreadsheets in particular repeat the same few strings across
+//! hundreds of thousands of cells; parsers route such strings through an
+//! [`Interner`] so each distinct value is stored once.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Deduplicates strings into shared `Arc<str>` values
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Create an empty interner
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `value`, allocating it on first use
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(value) {
+            return Arc::clone(existing);
+        }
+        let shared: Arc<str> = Arc::from(value);
+        self.strings.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Number of distinct strings
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether no string has been interned
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        let first = interner.intern("Paid");
+        let second = interner.intern(&String::from("Paid"));
+        let other = interner.intern("Open");
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(&*second, "Paid");
+        assert_eq!(interner.len(), 2);
+    }
+}
@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Layout Analysis
+//!
+//! Geometry of positioned blocks, for formats that place every block on
+//! the page (PDF, PPTX) rather than flowing them: their source order is
+//! drawing order, which is often not the order a person reads them in.
+//!
+//! - [`SpatialIndex`] is a grid over block bounds answering which blocks
+//!   cover a point or overlap an area.
+//! - [`Page::reading_order`] infers the reading order: running headers
+//!   first, then the body cut recursively into rows and columns along its
+//!   whitespace (an XY-cut, splitting at the widest gap each time), then
+//!   running footers.
+//!
+//! Pages with any unpositioned block are flow content and are read in
+//! source order.
+
+use std::collections::HashMap;
+
+use crate::document::{ContentBlock, Page, Point, Rect};
+
+/// Side of a grid cell of the spatial index, in points
+const CELL_SIZE: f64 = 72.0;
+
+/// Share of the page height at the top and bottom where blocks count as
+/// running headers and footers
+const MARGIN_BAND: f64 = 0.08;
+
+/// Narrowest whitespace, in points, that separates rows or columns
+const MIN_GAP: f64 = 1.0;
+
+/// Grid index over the bounds of a page's top-level blocks
+///
+/// Blocks without an area are not indexed.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    bounds: Vec<Rect>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialIndex {
+    /// Index `blocks` by their bounds
+    #[must_use]
+    pub fn new(blocks: &[ContentBlock]) -> Self {
+        let mut index = Self {
+            bounds: blocks.iter().map(ContentBlock::bounds).collect(),
+            cells: HashMap::new(),
+        };
+        for (block, bounds) in index.bounds.iter().enumerate() {
+            if !has_area(bounds) {
+                continue;
+            }
+            for cell in cells(bounds) {
+                index.cells.entry(cell).or_default().push(block);
+            }
+        }
+        index
+    }
+
+    /// Indices of the blocks overlapping `area`, in source order
+    #[must_use]
+    pub fn query(&self, area: &Rect) -> Vec<usize> {
+        let mut found: Vec<usize> = cells(area)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|&block| intersects(&self.bounds[block], area))
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Indices of the blocks covering `point`, in source order
+    #[must_use]
+    pub fn at(&self, point: Point) -> Vec<usize> {
+        self.query(&Rect::new(point.x, point.y, 0.0, 0.0))
+    }
+}
+
+/// Indices of a page's top-level blocks in reading order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadingOrder {
+    /// Running headers, left to right
+    pub headers: Vec<usize>,
+    /// The body
+    pub body: Vec<usize>,
+    /// Running footers, left to right
+    pub footers: Vec<usize>,
+}
+
+impl ReadingOrder {
+    /// Every block: headers, body, then footers
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.headers
+            .iter()
+            .chain(&self.body)
+            .chain(&self.footers)
+            .copied()
+    }
+}
+
+impl Page {
+    /// Infer the order the top-level blocks are read in
+    ///
+    /// See the [module documentation](crate::layout).
+    #[must_use]
+    pub fn reading_order(&self) -> ReadingOrder {
+        let bounds: Vec<Rect> = self.content.iter().map(ContentBlock::bounds).collect();
+        if !bounds.iter().all(has_area) {
+            return ReadingOrder {
+                body: (0..bounds.len()).collect(),
+                ..ReadingOrder::default()
+            };
+        }
+
+        // Margin bands only hold headers and footers when there is a body
+        // between them
+        let band = self.dimensions.height * MARGIN_BAND;
+        let bottom = self.dimensions.height - band;
+        let is_header = |rect: &Rect| rect.y + rect.height <= band;
+        let is_footer = |rect: &Rect| rect.y >= bottom;
+        let mut order = ReadingOrder::default();
+        let mut body = Vec::new();
+        for (index, rect) in bounds.iter().enumerate() {
+            if is_header(rect) {
+                order.headers.push(index);
+            } else if is_footer(rect) {
+                order.footers.push(index);
+            } else {
+                body.push(index);
+            }
+        }
+        if body.is_empty() {
+            body = (0..bounds.len()).collect();
+            order.headers.clear();
+            order.footers.clear();
+        }
+
+        let by_position = |a: &usize, b: &usize| {
+            let (a, b) = (&bounds[*a], &bounds[*b]);
+            a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+        };
+        order.headers.sort_by(by_position);
+        order.footers.sort_by(by_position);
+        xy_cut(body, &bounds, &mut order.body);
+        order
+    }
+
+    /// Top-level blocks in reading order
+    #[must_use]
+    pub fn blocks_in_reading_order(&self) -> Vec<&ContentBlock> {
+        self.reading_order()
+            .iter()
+            .map(|index| &self.content[index])
+            .collect()
+    }
+
+    /// Spatial index over the top-level blocks
+    #[must_use]
+    pub fn spatial_index(&self) -> SpatialIndex {
+        SpatialIndex::new(&self.content)
+    }
+}
+
+/// Append `blocks` to `order`, cutting them into rows or columns at the
+/// widest whitespace gap until no gap is left
+fn xy_cut(blocks: Vec<usize>, bounds: &[Rect], order: &mut Vec<usize>) {
+    if blocks.len() < 2 {
+        order.extend(blocks);
+        return;
+    }
+    let rows = split(&blocks, |block| {
+        let rect = &bounds[block];
+        (rect.y, rect.y + rect.height)
+    });
+    let columns = split(&blocks, |block| {
+        let rect = &bounds[block];
+        (rect.x, rect.x + rect.width)
+    });
+    let (first, second) = match (rows, columns) {
+        (Some(rows), Some(columns)) if columns.2 > rows.2 => (columns.0, columns.1),
+        (Some(rows), _) => (rows.0, rows.1),
+        (None, Some(columns)) => (columns.0, columns.1),
+        // Overlapping blocks: top to bottom, then left to right
+        (None, None) => {
+            let mut blocks = blocks;
+            blocks.sort_by(|&a, &b| {
+                let (a, b) = (&bounds[a], &bounds[b]);
+                a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+            });
+            order.extend(blocks);
+            return;
+        }
+    };
+    xy_cut(first, bounds, order);
+    xy_cut(second, bounds, order);
+}
+
+/// `blocks` split along one axis at the widest whitespace gap, in order,
+/// and the width of that gap; `None` if there is no gap
+fn split(
+    blocks: &[usize],
+    extent: impl Fn(usize) -> (f64, f64),
+) -> Option<(Vec<usize>, Vec<usize>, f64)> {
+    let mut sorted = blocks.to_vec();
+    // Stable, so blocks starting together keep their source order
+    sorted.sort_by(|&a, &b| extent(a).0.total_cmp(&extent(b).0));
+
+    let mut end = f64::NEG_INFINITY;
+    let mut widest: Option<(usize, f64)> = None;
+    for (position, &block) in sorted.iter().enumerate() {
+        let (start, stop) = extent(block);
+        let gap = start - end;
+        if position > 0 && gap >= MIN_GAP && widest.map_or(true, |(_, widest)| gap > widest) {
+            widest = Some((position, gap));
+        }
+        end = end.max(stop);
+    }
+    let (position, gap) = widest?;
+    let second = sorted.split_off(position);
+    Some((sorted, second, gap))
+}
+
+fn has_area(rect: &Rect) -> bool {
+    rect.width > 0.0 && rect.height > 0.0
+}
+
+fn intersects(a: &Rect, b: &Rect) -> bool {
+    a.x <= b.x + b.width && b.x <= a.x + a.width && a.y <= b.y + b.height && b.y <= a.y + a.height
+}
+
+/// Grid cells covered by `rect`
+#[allow(clippy::cast_possible_truncation)]
+fn cells(rect: &Rect) -> impl Iterator<Item = (i64, i64)> {
+    let cell = |value: f64| (value / CELL_SIZE).floor() as i64;
+    let (left, right) = (cell(rect.x), cell(rect.x + rect.width));
+    let (top, bottom) = (cell(rect.y), cell(rect.y + rect.height));
+    (left..=right).flat_map(move |x| (top..=bottom).map(move |y| (x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Dimensions, TextBlock, TextRun};
+
+    fn block(text: &str, x: f64, y: f64, width: f64, height: f64) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::new(x, y, width, height));
+        block.add_run(TextRun::new(text));
+        ContentBlock::Text(block)
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        // Drawn in an order unrelated to how the page reads
+        page.content = vec![
+            block("footer", 72.0, 750.0, 468.0, 20.0),
+            block("right 1", 320.0, 120.0, 220.0, 200.0),
+            block("left 2", 72.0, 330.0, 220.0, 200.0),
+            block("title", 72.0, 72.0, 468.0, 30.0),
+            block("left 1", 72.0, 120.0, 220.0, 200.0),
+            block("right 2", 320.0, 330.0, 220.0, 200.0),
+            block("header", 72.0, 20.0, 468.0, 20.0),
+        ];
+
+        let order = page.reading_order();
+        assert_eq!(order.headers, [6]);
+        assert_eq!(order.footers, [0]);
+        let text: Vec<String> = page
+            .blocks_in_reading_order()
+            .into_iter()
+            .map(|block| match block {
+                ContentBlock::Text(text) => text.extract_text(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(
+            text,
+            ["header", "title", "left 1", "left 2", "right 1", "right 2", "footer"]
+        );
+
+        // Flow content keeps its source order
+        page.content.push(block("flow", 0.0, 0.0, 0.0, 0.0));
+        let order = page.reading_order();
+        assert_eq!(order.iter().collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+
+        let index = page.spatial_index();
+        assert_eq!(index.at(Point::new(100.0, 150.0)), [4]);
+        assert_eq!(
+            index.query(&Rect::new(250.0, 300.0, 100.0, 50.0)),
+            [1, 2, 4, 5]
+        );
+    }
+}
@@ -28,20 +28,47 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod cache;
+pub mod canonical;
+pub mod chunk;
+pub mod diagnostics;
 pub mod document;
 pub mod error;
+#[cfg(feature = "fetch")]
+pub mod fetch;
 pub mod format;
+pub mod intern;
+pub mod layout;
 pub mod license;
 pub mod metadata;
 pub mod parser;
+pub mod pipeline;
+pub mod processor;
+pub mod progress;
+pub mod query;
 pub mod render;
+pub mod statistics;
+pub mod stream;
+pub mod table_detection;
+pub mod text_cleanup;
+pub mod timing;
+pub mod validate;
 
 // Re-exports for convenience
-pub use document::{ContentBlock, Document, ImageBlock, Page, TableBlock, TextBlock};
-pub use error::{Error, Result};
-pub use format::{detect_format, Format, FormatFamily, FormatSignature};
+pub use document::{
+    ContentBlock, Document, FormFieldBlock, ImageBlock, ListBlock, Page, Section, TableBlock,
+    TextBlock,
+};
+pub use error::{Error, ErrorCode, Result};
+pub use format::{
+    detect_format, detect_format_all, Format, FormatFamily, FormatRegistry, FormatSignature,
+};
 pub use metadata::Metadata;
 pub use parser::{ParseContext, ParseOptions, Parser};
+pub use pipeline::{Pipeline, PipelineOutput};
+pub use processor::Processor;
+pub use stream::{DocumentStream, StreamedPage};
+pub use validate::{ValidationError, ValidationErrors};
 
 /// Prism SDK version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
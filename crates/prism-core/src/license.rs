@@ -1,34 +1,519 @@
 // SPDX-License-Identifier: AGPL-3.0-only
+//! # Licensing
+//!
+//! License key parsing and feature gating.
+//!
+//! Prism is open-core: everything in this repository runs under the
+//! AGPL "community" license, which is what you get when no key is
+//! configured. Commercial keys unlock additional features (OCR, CAD, ...)
+//! and carry an optional expiry date with a short grace period.
+//!
+//! ## Key format
+//!
+//! Keys are `;`-separated `key=value` pairs prefixed with `prism1;`:
+//!
+//! ```text
+//! prism1;tier=commercial;licensee=Acme Corp;features=ocr,cad,server;expires=2027-01-31
+//! ```
+//!
+//! In the AGPL build keys are parsed but not cryptographically verified.
+//! The commercial build adds signature checking on top of this parser.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+
+/// Environment variable holding a license key
+pub const LICENSE_KEY_ENV: &str = "PRISM_LICENSE_KEY";
+
+/// Environment variable pointing at a file containing a license key
+pub const LICENSE_FILE_ENV: &str = "PRISM_LICENSE_FILE";
+
+/// Development key accepted by the AGPL build
+const DEV_KEY: &str = "commercial-dev-key-123";
+
+/// Prefix identifying version 1 license keys
+const KEY_PREFIX: &str = "prism1";
+
+/// Features every license grants, including none at all
+///
+/// The AGPL build ships the server, so it is always enabled.
+const COMMUNITY_FEATURES: [LicenseFeature; 1] = [LicenseFeature::Server];
+
+/// Number of days features stay enabled after a license expires
+pub const DEFAULT_GRACE_DAYS: i64 = 14;
+
+/// License tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseTier {
+    /// Open-source AGPL tier (default)
+    Community,
+    /// Paid commercial tier
+    Commercial,
+    /// Time-limited evaluation
+    Trial,
+}
+
+impl LicenseTier {
+    /// Human-readable tier name
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            LicenseTier::Community => "AGPL-3.0 (Community)",
+            LicenseTier::Commercial => "Commercial",
+            LicenseTier::Trial => "Trial",
+        }
+    }
+}
+
+impl FromStr for LicenseTier {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "community" => Ok(LicenseTier::Community),
+            "commercial" => Ok(LicenseTier::Commercial),
+            "trial" => Ok(LicenseTier::Trial),
+            other => Err(Error::ConfigError(format!("Unknown license tier: {other}"))),
+        }
+    }
+}
+
+/// Features that can be gated by a license
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseFeature {
+    /// Optical character recognition
+    Ocr,
+    /// CAD formats (DWG, DXF, ...)
+    Cad,
+    /// REST API server
+    Server,
+}
+
+impl LicenseFeature {
+    /// All known features
+    pub const ALL: [LicenseFeature; 3] = [
+        LicenseFeature::Ocr,
+        LicenseFeature::Cad,
+        LicenseFeature::Server,
+    ];
+
+    /// Identifier used in license keys
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LicenseFeature::Ocr => "ocr",
+            LicenseFeature::Cad => "cad",
+            LicenseFeature::Server => "server",
+        }
+    }
+}
+
+impl fmt::Display for LicenseFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LicenseFeature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ocr" => Ok(LicenseFeature::Ocr),
+            "cad" => Ok(LicenseFeature::Cad),
+            "server" => Ok(LicenseFeature::Server),
+            other => Err(Error::ConfigError(format!(
+                "Unknown license feature: {other}"
+            ))),
+        }
+    }
+}
+
+/// A parsed license
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct License {
+    /// License tier
+    pub tier: LicenseTier,
+
+    /// Name of the licensee
+    pub licensee: Option<String>,
+
+    /// Features granted by this license
+    pub features: Vec<LicenseFeature>,
+
+    /// Expiry date (`None` = perpetual)
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl License {
+    /// The open-core community license used when no key is configured.
+    #[must_use]
+    pub fn community() -> Self {
+        Self {
+            tier: LicenseTier::Community,
+            licensee: None,
+            features: COMMUNITY_FEATURES.to_vec(),
+            expires: None,
+        }
+    }
+
+    /// Parse a license key
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ConfigError` if the key is malformed.
+    pub fn parse(key: &str) -> Result<Self> {
+        let key = key.trim();
+
+        if key == DEV_KEY {
+            return Ok(Self {
+                tier: LicenseTier::Commercial,
+                licensee: Some("Development".to_string()),
+                features: LicenseFeature::ALL.to_vec(),
+                expires: None,
+            });
+        }
+
+        let mut parts = key.split(';');
+        if parts.next().map(str::trim) != Some(KEY_PREFIX) {
+            return Err(Error::ConfigError(
+                "License key must start with 'prism1;'".to_string(),
+            ));
+        }
+
+        let mut tier = None;
+        let mut licensee = None;
+        let mut features = Vec::new();
+        let mut expires = None;
+
+        for part in parts.map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| Error::ConfigError(format!("Malformed license field: {part}")))?;
+
+            match name.trim() {
+                "tier" => tier = Some(value.parse::<LicenseTier>()?),
+                "licensee" => licensee = Some(value.trim().to_string()),
+                "features" => {
+                    for name in value.split(',').filter(|f| !f.trim().is_empty()) {
+                        // Unknown features are skipped like unknown fields,
+                        // so a key naming a newer feature still loads
+                        match name.parse::<LicenseFeature>() {
+                            Ok(feature) if !features.contains(&feature) => features.push(feature),
+                            Ok(_) => {}
+                            Err(e) => warn!("Ignoring license feature: {e}"),
+                        }
+                    }
+                }
+                "expires" => expires = Some(parse_expiry(value)?),
+                // Unknown fields are ignored so newer keys keep working
+                _ => {}
+            }
+        }
+
+        let tier = tier.ok_or_else(|| {
+            Error::ConfigError("License key is missing the 'tier' field".to_string())
+        })?;
+
+        Ok(Self {
+            tier,
+            licensee,
+            features,
+            expires,
+        })
+    }
+
+    /// Check whether this license grants a feature (ignoring expiry)
+    ///
+    /// Community features are granted by every license, on top of those
+    /// the key lists.
+    #[must_use]
+    pub fn grants(&self, feature: LicenseFeature) -> bool {
+        self.features.contains(&feature) || COMMUNITY_FEATURES.contains(&feature)
+    }
+}
+
+impl Default for License {
+    fn default() -> Self {
+        Self::community()
+    }
+}
+
+/// Parse an expiry date (`YYYY-MM-DD` or RFC 3339)
+fn parse_expiry(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        // A license is valid through the end of its expiry day
+        if let Some(end_of_day) = date.and_hms_opt(23, 59, 59) {
+            return Ok(end_of_day.and_utc());
+        }
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| Error::ConfigError(format!("Invalid license expiry date: {value}")))
+}
+
+/// Validity of a license at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LicenseStatus {
+    /// License is valid
+    Valid,
+    /// License has expired but is within the grace period
+    GracePeriod {
+        /// Whole days left before features are disabled
+        days_remaining: i64,
+    },
+    /// License has expired and the grace period is over
+    Expired,
+}
 
 /// Commercial License Manager
 ///
-/// Handles validation of commercial license keys.
-/// In the AGPL version, this is a stub. In the commercial build,
-/// this would contain actual validation logic (e.g., verifying signature).
-pub struct LicenseManager;
+/// Holds the active license and answers feature-gating questions.
+/// Once a license has expired past its grace period, the manager falls
+/// back to the community feature set rather than failing outright.
+#[derive(Debug, Clone)]
+pub struct LicenseManager {
+    license: License,
+    grace_period: Duration,
+}
 
 impl LicenseManager {
+    /// Create a manager for the given license
+    #[must_use]
+    pub fn new(license: License) -> Self {
+        Self {
+            license,
+            grace_period: Duration::days(DEFAULT_GRACE_DAYS),
+        }
+    }
+
+    /// Create a manager running under the community license
+    #[must_use]
+    pub fn community() -> Self {
+        Self::new(License::community())
+    }
+
+    /// Create a manager from a license key
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ConfigError` if the key is malformed.
+    pub fn from_key(key: &str) -> Result<Self> {
+        License::parse(key).map(Self::new)
+    }
+
+    /// Load the license from the environment.
+    ///
+    /// Checks `PRISM_LICENSE_KEY` first, then the file named by
+    /// `PRISM_LICENSE_FILE`. Falls back to the community license when
+    /// neither is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the license file cannot be read or the key is malformed.
+    pub fn from_env() -> Result<Self> {
+        if let Ok(key) = std::env::var(LICENSE_KEY_ENV) {
+            if !key.trim().is_empty() {
+                return Self::from_key(&key);
+            }
+        }
+
+        if let Ok(path) = std::env::var(LICENSE_FILE_ENV) {
+            let key = std::fs::read_to_string(&path).map_err(|e| {
+                Error::ConfigError(format!("Failed to read license file {path}: {e}"))
+            })?;
+            return Self::from_key(&key);
+        }
+
+        Ok(Self::community())
+    }
+
+    /// Set the grace period applied after expiry
+    #[must_use]
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
     /// Validate a license key.
     ///
     /// # Arguments
     /// * `key` - The license string to validate.
     ///
     /// # Returns
-    /// * `true` if valid, `false` otherwise.
+    /// * `true` if the key parses and has not expired past its grace period, `false` otherwise.
+    #[must_use]
     pub fn validate(key: &str) -> bool {
-        // Placeholder validation logic
-        // In a real scenario, this might check a JWT signature or online endpoint.
-        // For the open source version, we might just return false or a default "community" state.
+        Self::from_key(key).is_ok_and(|manager| manager.status() != LicenseStatus::Expired)
+    }
+
+    /// The active license
+    #[must_use]
+    pub fn license(&self) -> &License {
+        &self.license
+    }
+
+    /// Get the current license type.
+    #[must_use]
+    pub fn license_type(&self) -> &'static str {
+        self.license.tier.name()
+    }
+
+    /// License status right now
+    #[must_use]
+    pub fn status(&self) -> LicenseStatus {
+        self.status_at(Utc::now())
+    }
+
+    /// License status at a given time
+    #[must_use]
+    pub fn status_at(&self, now: DateTime<Utc>) -> LicenseStatus {
+        let Some(expires) = self.license.expires else {
+            return LicenseStatus::Valid;
+        };
 
-        if key == "commercial-dev-key-123" {
-            return true;
+        if now <= expires {
+            LicenseStatus::Valid
+        } else if now <= expires + self.grace_period {
+            LicenseStatus::GracePeriod {
+                days_remaining: (expires + self.grace_period - now).num_days(),
+            }
+        } else {
+            LicenseStatus::Expired
         }
+    }
 
-        false
+    /// Check whether a feature is enabled right now
+    #[must_use]
+    pub fn is_enabled(&self, feature: LicenseFeature) -> bool {
+        self.is_enabled_at(feature, Utc::now())
     }
 
-    /// Get the current license type.
-    pub fn license_type() -> &'static str {
-        "AGPL-3.0 (Community)"
+    /// Check whether a feature is enabled at a given time
+    #[must_use]
+    pub fn is_enabled_at(&self, feature: LicenseFeature, now: DateTime<Utc>) -> bool {
+        match self.status_at(now) {
+            LicenseStatus::Valid | LicenseStatus::GracePeriod { .. } => {
+                self.license.grants(feature)
+            }
+            LicenseStatus::Expired => License::community().grants(feature),
+        }
+    }
+
+    /// Features enabled right now
+    #[must_use]
+    pub fn enabled_features(&self) -> Vec<LicenseFeature> {
+        let now = Utc::now();
+        LicenseFeature::ALL
+            .into_iter()
+            .filter(|f| self.is_enabled_at(*f, now))
+            .collect()
+    }
+
+    /// Fail unless a feature is enabled
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ConfigError` naming the missing feature.
+    pub fn require(&self, feature: LicenseFeature) -> Result<()> {
+        if self.is_enabled(feature) {
+            Ok(())
+        } else {
+            Err(Error::ConfigError(format!(
+                "Feature '{feature}' is not enabled by the {} license",
+                self.license_type()
+            )))
+        }
+    }
+}
+
+impl Default for LicenseManager {
+    fn default() -> Self {
+        Self::community()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_community_default() {
+        let manager = LicenseManager::default();
+        assert_eq!(manager.license_type(), "AGPL-3.0 (Community)");
+        assert!(manager.is_enabled(LicenseFeature::Server));
+        assert!(!manager.is_enabled(LicenseFeature::Ocr));
+        assert!(manager.require(LicenseFeature::Cad).is_err());
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let license = License::parse(
+            "prism1;tier=commercial;licensee=Acme Corp;features=ocr,server;expires=2030-06-30",
+        )
+        .unwrap();
+
+        assert_eq!(license.tier, LicenseTier::Commercial);
+        assert_eq!(license.licensee.as_deref(), Some("Acme Corp"));
+        assert!(license.grants(LicenseFeature::Ocr));
+        assert!(!license.grants(LicenseFeature::Cad));
+        assert!(license.expires.is_some());
+
+        assert!(License::parse("garbage").is_err());
+        assert!(License::parse("prism1;features=ocr").is_err());
+
+        let newer = License::parse("prism1;tier=commercial;features=teleport,ocr").unwrap();
+        assert_eq!(newer.features, vec![LicenseFeature::Ocr]);
+    }
+
+    #[test]
+    fn test_keys_keep_community_features() {
+        let manager = LicenseManager::from_key("prism1;tier=commercial;features=ocr").unwrap();
+        assert!(manager.is_enabled(LicenseFeature::Ocr));
+        assert!(manager.is_enabled(LicenseFeature::Server));
+        assert!(manager.require(LicenseFeature::Server).is_ok());
+        assert!(!manager.is_enabled(LicenseFeature::Cad));
+    }
+
+    #[test]
+    fn test_expiry_and_grace() {
+        let manager =
+            LicenseManager::from_key("prism1;tier=trial;features=ocr,cad;expires=2025-01-01")
+                .unwrap();
+        let expires = manager.license().expires.unwrap();
+
+        assert_eq!(manager.status_at(expires), LicenseStatus::Valid);
+
+        let in_grace = expires + Duration::days(3);
+        assert!(matches!(
+            manager.status_at(in_grace),
+            LicenseStatus::GracePeriod { days_remaining: 11 }
+        ));
+        assert!(manager.is_enabled_at(LicenseFeature::Ocr, in_grace));
+
+        let after_grace = expires + Duration::days(DEFAULT_GRACE_DAYS + 1);
+        assert_eq!(manager.status_at(after_grace), LicenseStatus::Expired);
+        assert!(!manager.is_enabled_at(LicenseFeature::Ocr, after_grace));
+        // Community features survive expiry
+        assert!(manager.is_enabled_at(LicenseFeature::Server, after_grace));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(LicenseManager::validate("commercial-dev-key-123"));
+        assert!(!LicenseManager::validate("not-a-key"));
+        assert!(!LicenseManager::validate(
+            "prism1;tier=commercial;features=ocr;expires=2000-01-01"
+        ));
     }
 }
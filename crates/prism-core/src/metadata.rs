@@ -39,6 +39,20 @@ pub struct Metadata {
 
     /// Custom metadata properties
     pub custom: HashMap<String, MetadataValue>,
+
+    /// Digital signatures embedded in the source file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<DigitalSignature>,
+
+    /// Macros, scripts and other content that can act when the source
+    /// file is opened in its native application
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active_content: Vec<ActiveContent>,
+
+    /// Active content and links removed by content disarm and
+    /// reconstruction
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disarmed: Vec<ActiveContent>,
 }
 
 impl Metadata {
@@ -156,6 +170,137 @@ impl MetadataBuilder {
     }
 }
 
+/// How a signature is embedded in its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureKind {
+    /// PDF signature field covering a byte range of the file
+    Pdf,
+    /// XML signature in the `_xmlsignatures` parts of an OOXML package
+    Ooxml,
+}
+
+/// Outcome of checking signed content against its signature
+///
+/// Only the digest of the signed content is checked; the signature value
+/// is not verified against the signer's key, and certificate chains are
+/// not validated. A matching digest therefore does not rule out tampering:
+/// whoever edits the file can replace the digest as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The signed content matches the digest stored in the signature
+    DigestMatches,
+    /// The signed content no longer matches its digest
+    Tampered,
+    /// The digest could not be checked, e.g. for an unsupported algorithm
+    Unverified,
+}
+
+/// A digital signature found in a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigitalSignature {
+    /// How the signature is embedded
+    pub kind: SignatureKind,
+
+    /// Signer name, from the signature or the signing certificate
+    pub signer: Option<String>,
+
+    /// Signing time claimed by the signer
+    pub signed_at: Option<DateTime<Utc>>,
+
+    /// Reason or comment given when signing
+    pub reason: Option<String>,
+
+    /// Result of the digest check
+    pub status: SignatureStatus,
+
+    /// Whether the file changed after signing, through content outside
+    /// the signed range or signed content that no longer matches
+    pub modified_after_signing: bool,
+}
+
+/// Kind of active content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveContentKind {
+    /// VBA project or Excel 4.0 macro sheet
+    Macro,
+    /// PDF JavaScript
+    Script,
+    /// Action run when the document or a page opens, such as `/OpenAction`
+    AutoAction,
+    /// PDF action starting an external program
+    Launch,
+    /// Executable file embedded in the document
+    EmbeddedExecutable,
+    /// Reference fetched from outside the file, such as a remote template
+    /// or linked workbook
+    ExternalReference,
+    /// Document embedded as-is rather than rebuilt, such as an attachment
+    /// with active content of its own
+    EmbeddedDocument,
+    /// Link to a resource outside the document; reported only when removed
+    Hyperlink,
+}
+
+impl std::fmt::Display for ActiveContentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Macro => "macro",
+            Self::Script => "script",
+            Self::AutoAction => "automatic action",
+            Self::Launch => "launch action",
+            Self::EmbeddedExecutable => "embedded executable",
+            Self::ExternalReference => "external reference",
+            Self::EmbeddedDocument => "embedded document",
+            Self::Hyperlink => "hyperlink",
+        })
+    }
+}
+
+/// An item of active content found in a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveContent {
+    /// What the content is
+    pub kind: ActiveContentKind,
+
+    /// Where it was found: a package part, OLE stream or PDF object
+    pub location: String,
+
+    /// The URL, file name or action involved, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ActiveContent {
+    /// Create an item found at `location`
+    pub fn new(kind: ActiveContentKind, location: impl Into<String>) -> Self {
+        Self {
+            kind,
+            location: location.into(),
+            detail: None,
+        }
+    }
+
+    /// Add the URL, file name or action involved
+    #[must_use]
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ActiveContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in {}", self.kind, self.location)?;
+        if let Some(detail) = &self.detail {
+            write!(f, " ({detail})")?;
+        }
+        Ok(())
+    }
+}
+
 /// A metadata value (can be string, number, bool, or date)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -5,13 +5,24 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::diagnostics::Diagnostic;
 use crate::document::Document;
-use crate::error::Result;
-use crate::format::Format;
+use crate::error::{Error, Result};
+use crate::format::{Format, FormatRegistry};
+use crate::progress::{ProgressEvent, ProgressSink};
+use crate::stream::DocumentStream;
 
 /// Options for parsing documents
 #[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct ParseOptions {
     /// Whether to extract images
     pub extract_images: bool,
@@ -28,8 +39,250 @@ pub struct ParseOptions {
     /// Timeout for parsing (in seconds)
     pub timeout: Option<u64>,
 
+    /// Longest any single parse stage may take, such as opening the
+    /// package or parsing one sheet
+    ///
+    /// A stage over budget fails with [`Error::StageTimeout`]; in lenient
+    /// mode a failed sheet or slide is left out like any other damaged
+    /// one. Stages are timed into
+    /// [`Document::timings`](crate::document::Document::timings) either way.
+    pub stage_budget: Option<Duration>,
+
     /// Password for encrypted documents
     pub password: Option<String>,
+
+    /// How the document ID is assigned
+    pub id_strategy: IdStrategy,
+
+    /// Recover what can be read from damaged files instead of failing,
+    /// recording the problems in [`Document::diagnostics`]
+    pub lenient: bool,
+
+    /// Directory that relative references to other files (images linked
+    /// from Markdown) are read from
+    ///
+    /// Files outside it are never read. When unset, such references are
+    /// kept as URLs without fetching them.
+    pub resource_dir: Option<PathBuf>,
+
+    /// Entries of log files to keep
+    pub log_filter: LogFilter,
+
+    /// Refuse files with macros, scripts, embedded executables or external
+    /// references, failing with [`Error::ActiveContent`]
+    ///
+    /// Either way, what is found is listed in
+    /// [`Metadata::active_content`](crate::metadata::Metadata::active_content).
+    pub reject_active_content: bool,
+
+    /// How tracked changes are applied to the content
+    ///
+    /// Either way, every change is listed in
+    /// [`Document::revisions`](crate::document::Document::revisions).
+    pub revisions: RevisionMode,
+
+    /// What happens to content hidden in the source, such as hidden
+    /// sheets, rows and columns of a workbook
+    pub hidden: HiddenContent,
+
+    /// Rebuild tables from aligned, positioned text after parsing, for
+    /// PDFs and scans that have none of their own
+    ///
+    /// See [`table_detection`](crate::table_detection).
+    pub detect_tables: bool,
+
+    /// Merge hyphenated and hard-wrapped lines, spell out ligatures and
+    /// normalize whitespace after parsing, for text extracted from PDFs
+    /// and scans
+    ///
+    /// See [`text_cleanup`](crate::text_cleanup).
+    pub clean_text: bool,
+
+    /// Parse the documents embedded in office documents, such as a
+    /// workbook in a Word document, into
+    /// [`Attachment::document`](crate::document::Attachment::document)
+    ///
+    /// Either way, the embedded files are kept in
+    /// [`Document::attachments`].
+    pub parse_embedded: bool,
+}
+
+impl ParseOptions {
+    /// Handle a recoverable parse error
+    ///
+    /// In lenient mode `error` is recorded in `diagnostics` and parsing
+    /// continues; otherwise it is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `error` unless [`lenient`](Self::lenient) is set.
+    pub fn recover(&self, error: Error, diagnostics: &mut Vec<Diagnostic>) -> Result<()> {
+        if self.lenient {
+            tracing::warn!("Recovered from parse error: {}", error);
+            diagnostics.push(Diagnostic::from(&error));
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+}
+
+/// How [`Document::id`] is assigned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// A fresh random ID for every parse
+    #[default]
+    Random,
+
+    /// Derived from the SHA-256 of the input bytes, so parsing the same
+    /// file twice yields the same ID (see [`Document::content_id`])
+    ContentHash,
+}
+
+/// How tracked changes are applied to the content of a document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RevisionMode {
+    /// The document as if every change were accepted: inserted text is
+    /// kept and deleted text dropped
+    #[default]
+    Accept,
+
+    /// The document as if every change were rejected: inserted text is
+    /// dropped and deleted text kept
+    Reject,
+
+    /// Both inserted and deleted text, marked with
+    /// [`TextRun::revision`](crate::document::TextRun::revision) so
+    /// renderers can show the changes
+    Markup,
+}
+
+impl FromStr for RevisionMode {
+    type Err = Error;
+
+    /// Parse `accept`, `reject` or `markup`, in any case
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "accept" => Ok(Self::Accept),
+            "reject" => Ok(Self::Reject),
+            "markup" => Ok(Self::Markup),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown revision mode '{other}'"
+            ))),
+        }
+    }
+}
+
+/// What happens to content the source document hides from view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HiddenContent {
+    /// Kept, and flagged as hidden in the page metadata, such as
+    /// [`SheetLayout`](crate::document::SheetLayout) for worksheets
+    #[default]
+    Flag,
+
+    /// Left out
+    Skip,
+}
+
+/// Which entries of a log file to keep
+///
+/// Entries without a timestamp pass the time bounds; entries without a
+/// severity are dropped once a minimum severity is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Drop entries logged before this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Drop entries logged after this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Drop entries less severe than this
+    pub min_level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    /// Whether an entry with this timestamp and severity is kept
+    #[must_use]
+    pub fn accepts(&self, timestamp: Option<DateTime<Utc>>, level: Option<LogLevel>) -> bool {
+        let in_range = timestamp.map_or(true, |time| {
+            self.since.map_or(true, |since| time >= since)
+                && self.until.map_or(true, |until| time <= until)
+        });
+        let severe_enough = self
+            .min_level
+            .map_or(true, |min| level.is_some_and(|level| level >= min));
+        in_range && severe_enough
+    }
+}
+
+/// Severity of a log entry, from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Tracing detail
+    Trace,
+    /// Debugging detail
+    Debug,
+    /// Normal operation, including notices
+    Info,
+    /// Something unexpected that was handled
+    Warn,
+    /// A failed operation
+    Error,
+    /// The program cannot continue (fatal, critical, alert, emergency)
+    Fatal,
+}
+
+impl LogLevel {
+    /// All levels, from least to most severe
+    pub const ALL: [Self; 6] = [
+        Self::Trace,
+        Self::Debug,
+        Self::Info,
+        Self::Warn,
+        Self::Error,
+        Self::Fatal,
+    ];
+
+    /// Lowercase name
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+            Self::Fatal => "fatal",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    /// Parse a level name, accepting the usual aliases (`warning`, `err`,
+    /// `critical`, `notice`, ...) in any case
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trace" | "finest" | "verbose" => Ok(Self::Trace),
+            "debug" | "dbg" | "fine" | "finer" => Ok(Self::Debug),
+            "info" | "information" | "notice" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" | "err" | "severe" => Ok(Self::Error),
+            "fatal" | "critical" | "crit" | "alert" | "emerg" | "emergency" | "panic" => {
+                Ok(Self::Fatal)
+            }
+            other => Err(Error::InvalidInput(format!("Unknown log level '{other}'"))),
+        }
+    }
 }
 
 /// Context provided to parsers during parsing
@@ -46,6 +299,18 @@ pub struct ParseContext {
 
     /// Parse options
     pub options: ParseOptions,
+
+    /// Receiver of progress events, if the caller wants them
+    pub progress: Option<Arc<dyn ProgressSink>>,
+}
+
+impl ParseContext {
+    /// Send `event` to the progress sink, if there is one
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event);
+        }
+    }
 }
 
 /// Trait for document parsers
@@ -74,14 +339,43 @@ pub trait Parser: Send + Sync {
     /// A parsed Document in the UDM format
     async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document>;
 
+    /// Parse a document page by page
+    ///
+    /// Parsers that can decode pages independently override this to yield
+    /// each page as soon as it is ready. The default parses the whole
+    /// document and then streams its pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document cannot be opened; errors in later
+    /// pages are yielded by the stream.
+    async fn parse_stream(&self, data: Bytes, context: ParseContext) -> Result<DocumentStream> {
+        Ok(DocumentStream::from_document(self.parse(data, context).await?))
+    }
+
     /// Get parser metadata (name, version, supported features)
     fn metadata(&self) -> ParserMetadata {
         ParserMetadata::default()
     }
+
+    /// Teach format detection about the formats this parser handles
+    ///
+    /// Called when the parser is registered. The default maps the
+    /// extension of [`Parser::format`] if detection does not know it yet;
+    /// parsers for formats with a signature or a container layout of their
+    /// own register those here.
+    fn register_formats(&self, formats: &mut FormatRegistry) {
+        let format = self.format();
+        if !format.extension.is_empty() && formats.format_by_extension(&format.extension).is_none()
+        {
+            let extension = format.extension.clone();
+            formats.register_extension(&extension, format);
+        }
+    }
 }
 
 /// Metadata about a parser
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ParserMetadata {
     /// Parser name
     pub name: String,
@@ -97,7 +391,8 @@ pub struct ParserMetadata {
 }
 
 /// Features that a parser may support
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ParserFeature {
     /// Can extract text
     TextExtraction,
@@ -130,6 +425,7 @@ mod tests {
         let opts = ParseOptions::default();
         assert!(!opts.extract_images);
         assert!(!opts.preserve_formatting);
+        assert_eq!(opts.id_strategy, IdStrategy::Random);
     }
 
     #[test]
@@ -139,9 +435,32 @@ mod tests {
             filename: Some("test.pdf".to_string()),
             size: 1024,
             options: ParseOptions::default(),
+            progress: None,
         };
 
         assert_eq!(context.size, 1024);
         assert_eq!(context.filename, Some("test.pdf".to_string()));
     }
+
+    #[test]
+    fn test_log_filter() {
+        let at = |hour| {
+            DateTime::parse_from_rfc3339(&format!("2024-01-31T{hour:02}:00:00Z"))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let filter = LogFilter {
+            since: Some(at(9)),
+            until: Some(at(17)),
+            min_level: Some("warning".parse().unwrap()),
+        };
+
+        assert!(filter.accepts(Some(at(12)), Some(LogLevel::Error)));
+        assert!(filter.accepts(None, Some(LogLevel::Warn)));
+        assert!(!filter.accepts(Some(at(8)), Some(LogLevel::Fatal)));
+        assert!(!filter.accepts(Some(at(12)), Some(LogLevel::Info)));
+        assert!(!filter.accepts(Some(at(12)), None));
+        assert!(LogFilter::default().accepts(None, None));
+        assert!("loud".parse::<LogLevel>().is_err());
+    }
 }
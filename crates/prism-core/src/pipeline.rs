@@ -0,0 +1,742 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Processing Pipeline
+//!
+//! Chains the stages every consumer needs - detect, parse, process and
+//! render - behind a single entry point:
+//!
+//! ```text
+//! bytes ──▶ Detect ──▶ Parse ──▶ Processor* ──▶ Render? ──▶ PipelineOutput
+//! ```
+//!
+//! Parsers are looked up through a [`ParserProvider`] (implemented by the
+//! parser registry), processors run in the order they were added, and the
+//! renderer is optional so the pipeline can also be used to just obtain a
+//! [`Document`]. [`PipelineHook`]s observe each stage for instrumentation,
+//! and a [`ProgressSink`] receives finer-grained progress of a single run.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use prism_core::pipeline::{ErrorPolicy, Pipeline, ParserProvider};
+//!
+//! # async fn example(parsers: Arc<dyn ParserProvider>, data: bytes::Bytes) -> prism_core::Result<()> {
+//! let pipeline = Pipeline::new(parsers).with_error_policy(ErrorPolicy::Collect);
+//!
+//! let output = pipeline.run(data, Some("report.docx")).await?;
+//! println!("{} pages", output.document.page_count());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use tracing::{debug, warn};
+
+use crate::document::{Document, SourceInfo};
+use crate::error::{Error, Result};
+use crate::format::{detect_format, detect_format_all, DetectionMethod, DetectionResult, Format};
+use crate::parser::{IdStrategy, ParseContext, ParseOptions, Parser};
+use crate::processor::Processor;
+use crate::progress::{ProgressEvent, ProgressSink};
+use crate::render::{RenderContext, RenderDiagnostics, RenderOptions, Renderer};
+use crate::table_detection::DetectTables;
+use crate::text_cleanup::CleanText;
+
+/// Source of parsers for detected formats
+pub trait ParserProvider: Send + Sync {
+    /// Find a parser for `format` that accepts `data`
+    fn parser_for(&self, format: &Format, data: &[u8]) -> Option<Arc<dyn Parser>>;
+
+    /// Detect the format of `data`
+    ///
+    /// The default knows only the built-in formats; providers that hold a
+    /// [`FormatRegistry`](crate::format::FormatRegistry) use it instead.
+    fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        detect_format(data, filename)
+    }
+
+    /// Every plausible format of `data`, most likely first
+    ///
+    /// Like [`detect`](Self::detect), the default knows only the built-in
+    /// formats.
+    fn detect_all(&self, data: &[u8], filename: Option<&str>) -> Vec<DetectionResult> {
+        detect_format_all(data, filename)
+    }
+}
+
+impl ParserProvider for Vec<Arc<dyn Parser>> {
+    fn parser_for(&self, format: &Format, data: &[u8]) -> Option<Arc<dyn Parser>> {
+        self.iter()
+            .find(|parser| parser.format().mime_type == format.mime_type && parser.can_parse(data))
+            .cloned()
+    }
+}
+
+/// A stage of the pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stage {
+    /// Format detection
+    Detect,
+    /// Parsing into the UDM
+    Parse,
+    /// A named processor
+    Process(String),
+    /// Rendering to the output format
+    Render,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Detect => write!(f, "detect"),
+            Stage::Parse => write!(f, "parse"),
+            Stage::Process(name) => write!(f, "process:{name}"),
+            Stage::Render => write!(f, "render"),
+        }
+    }
+}
+
+/// What to do when a processor fails
+///
+/// Detection, parsing and rendering failures always abort the run since
+/// there is nothing meaningful to continue with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Abort on the first error
+    #[default]
+    FailFast,
+    /// Record processor errors in [`PipelineOutput::errors`] and continue
+    Collect,
+}
+
+/// Typed configuration for a pipeline run
+#[derive(Debug, Clone, Default)]
+pub struct PipelineConfig {
+    /// Options passed to the parser
+    pub parse: ParseOptions,
+    /// Options passed to the renderer
+    pub render: RenderOptions,
+    /// Processor error handling
+    pub error_policy: ErrorPolicy,
+    /// Format to parse documents as, skipping detection; for files whose
+    /// extension is wrong or missing and whose content is ambiguous
+    pub format: Option<Format>,
+}
+
+/// Observer notified around every stage
+///
+/// Both methods default to no-ops so hooks only implement what they need.
+pub trait PipelineHook: Send + Sync {
+    /// Called before a stage starts
+    fn on_stage_start(&self, _stage: &Stage) {}
+
+    /// Called after a stage finishes, with the error if it failed
+    fn on_stage_end(&self, _stage: &Stage, _elapsed: Duration, _error: Option<&Error>) {}
+}
+
+/// An error recorded under [`ErrorPolicy::Collect`]
+#[derive(Debug)]
+pub struct StageError {
+    /// Stage that failed
+    pub stage: Stage,
+    /// The error it returned
+    pub error: Error,
+}
+
+/// Result of a pipeline run
+#[derive(Debug)]
+pub struct PipelineOutput {
+    /// Format detection result
+    pub detection: DetectionResult,
+    /// The parsed (and processed) document
+    pub document: Document,
+    /// Rendered output, if the pipeline has a renderer
+    pub rendered: Option<Bytes>,
+    /// What the renderer could not reproduce faithfully; empty without a
+    /// renderer
+    pub render_diagnostics: RenderDiagnostics,
+    /// Errors collected from processors
+    pub errors: Vec<StageError>,
+}
+
+impl PipelineOutput {
+    /// Whether every stage succeeded
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Detect → parse → process → render
+#[derive(Clone)]
+pub struct Pipeline {
+    parsers: Arc<dyn ParserProvider>,
+    processors: Vec<Arc<dyn Processor>>,
+    renderer: Option<Arc<dyn Renderer>>,
+    hooks: Vec<Arc<dyn PipelineHook>>,
+    progress: Option<Arc<dyn ProgressSink>>,
+    config: PipelineConfig,
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field(
+                "processors",
+                &self.processors.iter().map(|p| p.name()).collect::<Vec<_>>(),
+            )
+            .field(
+                "renderer",
+                &self.renderer.as_ref().map(|r| r.output_format().name),
+            )
+            .field("hooks", &self.hooks.len())
+            .field("progress", &self.progress.is_some())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pipeline {
+    /// Create a pipeline that detects and parses using `parsers`
+    #[must_use]
+    pub fn new(parsers: Arc<dyn ParserProvider>) -> Self {
+        Self {
+            parsers,
+            processors: Vec::new(),
+            renderer: None,
+            hooks: Vec::new(),
+            progress: None,
+            config: PipelineConfig::default(),
+        }
+    }
+
+    /// Append a processor
+    #[must_use]
+    pub fn with_processor(mut self, processor: Arc<dyn Processor>) -> Self {
+        self.processors.push(processor);
+        self
+    }
+
+    /// Render the processed document with `renderer`
+    #[must_use]
+    pub fn with_renderer(mut self, renderer: Arc<dyn Renderer>) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+
+    /// Stop rendering, so that runs end with the processed document
+    #[must_use]
+    pub fn without_renderer(mut self) -> Self {
+        self.renderer = None;
+        self
+    }
+
+    /// Register an instrumentation hook
+    #[must_use]
+    pub fn with_hook(mut self, hook: Arc<dyn PipelineHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Report the progress of runs to `progress`
+    ///
+    /// The sink is also handed to the parser, so pipelines running
+    /// unrelated conversions should each get their own.
+    #[must_use]
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressSink>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Replace the configuration
+    #[must_use]
+    pub fn with_config(mut self, config: PipelineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the processor error policy
+    #[must_use]
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.config.error_policy = policy;
+        self
+    }
+
+    /// The active configuration
+    #[must_use]
+    pub fn config(&self) -> &PipelineConfig {
+        &self.config
+    }
+
+    /// Detect the format of `data` with the parsers' format registry
+    #[must_use]
+    pub fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        self.parsers.detect(data, filename)
+    }
+
+    /// Every plausible format of `data`, most likely first
+    #[must_use]
+    pub fn detect_all(&self, data: &[u8], filename: Option<&str>) -> Vec<DetectionResult> {
+        self.parsers.detect_all(data, filename)
+    }
+
+    /// Run every stage on `data`
+    ///
+    /// The document is parsed as the most likely detected format that a
+    /// parser accepts, so a file whose signature and extension disagree
+    /// still reaches a parser that can read it. A format set in
+    /// [`PipelineConfig::format`] is used as is instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::DetectionFailed` if the format cannot be detected,
+    /// `Error::UnsupportedFormat` if no parser accepts the data, and
+    /// otherwise the first error from a stage that aborted the run.
+    pub async fn run(&self, data: Bytes, filename: Option<&str>) -> Result<PipelineOutput> {
+        let detection = match &self.config.format {
+            Some(format) => DetectionResult {
+                format: format.clone(),
+                confidence: 1.0,
+                method: DetectionMethod::Declared,
+            },
+            None => self.detect_parseable(&data, filename).await?,
+        };
+        debug!(
+            "Detected {} ({:.0}% via {:?})",
+            detection.format.name,
+            detection.confidence * 100.0,
+            detection.method
+        );
+        self.report(ProgressEvent::FormatDetected {
+            mime_type: detection.format.mime_type.clone(),
+            name: detection.format.name.clone(),
+            confidence: detection.confidence,
+        });
+
+        let mut document = self
+            .stage(Stage::Parse, self.parse(&detection.format, data, filename))
+            .await?;
+        for diagnostic in &document.diagnostics {
+            self.report(ProgressEvent::Warning {
+                diagnostic: diagnostic.clone(),
+            });
+        }
+
+        // Tables are rebuilt and text cleaned up before any processor sees
+        // the document
+        let mut builtin: Vec<Arc<dyn Processor>> = Vec::new();
+        if self.config.parse.detect_tables {
+            builtin.push(Arc::new(DetectTables));
+        }
+        if self.config.parse.clean_text {
+            builtin.push(Arc::new(CleanText));
+        }
+        let mut errors = Vec::new();
+        for processor in builtin.iter().chain(&self.processors) {
+            let stage = Stage::Process(processor.name().to_string());
+            let result = self
+                .stage(stage.clone(), processor.process(&mut document))
+                .await;
+            if let Err(error) = result {
+                match self.config.error_policy {
+                    ErrorPolicy::FailFast => return Err(error),
+                    ErrorPolicy::Collect => {
+                        warn!("Processor {} failed: {}", processor.name(), error);
+                        errors.push(StageError { stage, error });
+                    }
+                }
+            }
+        }
+
+        let mut render_diagnostics = RenderDiagnostics::default();
+        let rendered = match &self.renderer {
+            Some(renderer) => {
+                let context = RenderContext {
+                    options: self.config.render.clone(),
+                    filename: filename.map(str::to_string),
+                    progress: self.progress.clone(),
+                };
+                let render = async {
+                    self.report(ProgressEvent::RenderStarted {
+                        pages: document.page_count(),
+                    });
+                    renderer.render_within_limits(&document, context).await
+                };
+                let result = self.stage(Stage::Render, render).await?;
+                self.report(ProgressEvent::RenderFinished {
+                    bytes: result.output.len(),
+                });
+                render_diagnostics = result.diagnostics;
+                Some(result.output)
+            }
+            None => None,
+        };
+
+        Ok(PipelineOutput {
+            detection,
+            document,
+            rendered,
+            render_diagnostics,
+            errors,
+        })
+    }
+
+    /// The most likely detected format of `data` that a parser accepts
+    async fn detect_parseable(
+        &self,
+        data: &[u8],
+        filename: Option<&str>,
+    ) -> Result<DetectionResult> {
+        let candidates = self
+            .stage(Stage::Detect, async {
+                let candidates = self.detect_all(data, filename);
+                if candidates.is_empty() {
+                    return Err(Error::DetectionFailed(
+                        filename.unwrap_or("<input>").to_string(),
+                    ));
+                }
+                Ok(candidates)
+            })
+            .await?;
+        Ok(candidates
+            .iter()
+            .find(|candidate| self.parsers.parser_for(&candidate.format, data).is_some())
+            .unwrap_or(&candidates[0])
+            .clone())
+    }
+
+    async fn parse(
+        &self,
+        format: &Format,
+        data: Bytes,
+        filename: Option<&str>,
+    ) -> Result<Document> {
+        let parser = self
+            .parsers
+            .parser_for(format, &data)
+            .ok_or_else(|| Error::UnsupportedFormat(format.name.clone()))?;
+
+        let size = data.len();
+        let hash = SourceInfo::content_hash(&data);
+        let context = ParseContext {
+            format: format.clone(),
+            filename: filename.map(str::to_string),
+            size,
+            options: self.config.parse.clone(),
+            progress: self.progress.clone(),
+        };
+        let mut document = parser.parse(data, context).await?;
+
+        // Not every parser records where the document came from
+        let source = &mut document.source;
+        source.filename = source
+            .filename
+            .take()
+            .or_else(|| filename.map(str::to_string));
+        source.format = source.format.take().or_else(|| Some(format.clone()));
+        source.size = source.size.or(Some(size as u64));
+        source.hash = source.hash.take().or(Some(hash));
+
+        if self.config.parse.id_strategy == IdStrategy::ContentHash {
+            if let Some(id) = source.hash.as_deref().and_then(Document::content_id) {
+                document.id = id;
+            }
+        }
+
+        Ok(document)
+    }
+
+    fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event);
+        }
+    }
+
+    /// Run one stage, notifying hooks around it
+    async fn stage<T>(
+        &self,
+        stage: Stage,
+        work: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        self.report(ProgressEvent::StageChanged {
+            stage: stage.to_string(),
+        });
+        for hook in &self.hooks {
+            hook.on_stage_start(&stage);
+        }
+
+        let started = Instant::now();
+        let result = work.await;
+        let elapsed = started.elapsed();
+
+        for hook in &self.hooks {
+            hook.on_stage_end(&stage, elapsed, result.as_ref().err());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{ContentBlock, Dimensions, Page, Rect, TextBlock, TextRun};
+    use crate::format::DetectionMethod;
+    use crate::render::RenderOptions;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct TextParser;
+
+    #[async_trait]
+    impl Parser for TextParser {
+        fn format(&self) -> Format {
+            Format::text()
+        }
+
+        fn can_parse(&self, _data: &[u8]) -> bool {
+            true
+        }
+
+        async fn parse(&self, data: Bytes, _context: ParseContext) -> Result<Document> {
+            let mut block = TextBlock::new(Rect::default());
+            block.add_run(TextRun::new(String::from_utf8_lossy(&data)));
+            let mut page = Page::new(1, Dimensions::default());
+            page.content.push(ContentBlock::Text(block));
+            let mut document = Document::new();
+            document.pages.push(page);
+            Ok(document)
+        }
+    }
+
+    struct Upper;
+
+    #[async_trait]
+    impl Processor for Upper {
+        fn name(&self) -> &'static str {
+            "upper"
+        }
+
+        async fn process(&self, document: &mut Document) -> Result<()> {
+            document.metadata.title = Some(document.extract_text().to_uppercase());
+            Ok(())
+        }
+    }
+
+    struct Failing;
+
+    #[async_trait]
+    impl Processor for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn process(&self, _document: &mut Document) -> Result<()> {
+            Err(Error::internal("boom"))
+        }
+    }
+
+    struct TitleRenderer;
+
+    #[async_trait]
+    impl Renderer for TitleRenderer {
+        fn output_format(&self) -> Format {
+            Format::text()
+        }
+
+        async fn render(&self, document: &Document, _context: RenderContext) -> Result<Bytes> {
+            Ok(Bytes::from(
+                document.metadata.title.clone().unwrap_or_default(),
+            ))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl ProgressSink for Recorder {
+        fn report(&self, event: ProgressEvent) {
+            self.0.lock().unwrap().push(event.name().to_string());
+        }
+    }
+
+    impl PipelineHook for Recorder {
+        fn on_stage_end(&self, stage: &Stage, _elapsed: Duration, error: Option<&Error>) {
+            let outcome = if error.is_some() { "err" } else { "ok" };
+            self.0.lock().unwrap().push(format!("{stage}:{outcome}"));
+        }
+    }
+
+    fn pipeline() -> Pipeline {
+        let parsers: Vec<Arc<dyn Parser>> = vec![Arc::new(TextParser)];
+        Pipeline::new(Arc::new(parsers))
+    }
+
+    #[tokio::test]
+    async fn test_full_run() {
+        let recorder = Arc::new(Recorder::default());
+        let pipeline = pipeline()
+            .with_processor(Arc::new(Upper))
+            .with_renderer(Arc::new(TitleRenderer))
+            .with_hook(recorder.clone());
+
+        let output = pipeline
+            .run(Bytes::from_static(b"hello"), Some("a.txt"))
+            .await
+            .unwrap();
+
+        assert!(output.is_clean());
+        assert_eq!(output.rendered.unwrap(), Bytes::from_static(b"HELLO"));
+        assert_eq!(output.document.source.filename.as_deref(), Some("a.txt"));
+        assert_eq!(output.document.source.size, Some(5));
+        assert_eq!(
+            output.document.source.hash.as_deref(),
+            Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec!["detect:ok", "parse:ok", "process:upper:ok", "render:ok"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let progress = Arc::new(Recorder::default());
+        let pipeline = pipeline()
+            .with_renderer(Arc::new(TitleRenderer))
+            .with_progress(progress.clone());
+
+        pipeline
+            .run(Bytes::from_static(b"hello"), Some("a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(
+            *progress.0.lock().unwrap(),
+            vec![
+                "stage_changed",
+                "format_detected",
+                "stage_changed",
+                "stage_changed",
+                "render_started",
+                "render_finished"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detection_fallback() {
+        // Named like a PDF, but only the text parser is available
+        let output = pipeline()
+            .run(Bytes::from_static(b"\xEF\xBB\xBFhello"), Some("a.pdf"))
+            .await
+            .unwrap();
+        assert_eq!(output.detection.format, Format::text());
+        assert_eq!(output.detection.method, DetectionMethod::ContentAnalysis);
+
+        let result = pipeline()
+            .run(Bytes::from_static(b"\x00\x01"), Some("a.pdf"))
+            .await;
+        assert!(matches!(result, Err(Error::UnsupportedFormat(_))));
+    }
+
+    #[tokio::test]
+    async fn test_declared_format() {
+        // Detected as a PDF, but declared as text
+        let config = PipelineConfig {
+            format: Some(Format::text()),
+            ..Default::default()
+        };
+        let output = pipeline()
+            .with_config(config)
+            .run(Bytes::from_static(b"%PDF-1.7 plain"), None)
+            .await
+            .unwrap();
+        assert_eq!(output.detection.format, Format::text());
+        assert_eq!(output.detection.method, DetectionMethod::Declared);
+        assert_eq!(output.document.extract_text(), "%PDF-1.7 plain");
+
+        let config = PipelineConfig {
+            format: Some(Format::pdf()),
+            ..Default::default()
+        };
+        let result = pipeline()
+            .with_config(config)
+            .run(Bytes::from_static(b"hello"), Some("a.txt"))
+            .await;
+        assert!(matches!(result, Err(Error::UnsupportedFormat(_))));
+    }
+
+    #[tokio::test]
+    async fn test_content_hash_ids() {
+        let run = |pipeline: Pipeline| async move {
+            pipeline
+                .run(Bytes::from_static(b"hello"), Some("a.txt"))
+                .await
+                .unwrap()
+                .document
+                .id
+        };
+
+        assert_ne!(run(pipeline()).await, run(pipeline()).await);
+
+        let config = PipelineConfig {
+            parse: ParseOptions {
+                id_strategy: IdStrategy::ContentHash,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let first = run(pipeline().with_config(config.clone())).await;
+        assert_eq!(first, run(pipeline().with_config(config)).await);
+        assert_eq!(first.to_string(), "2cf24dba-5fb0-830e-a6e8-3b2ac5b9e29e");
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast() {
+        let pipeline = pipeline()
+            .with_processor(Arc::new(Failing))
+            .with_renderer(Arc::new(TitleRenderer));
+
+        let result = pipeline
+            .run(Bytes::from_static(b"hello"), Some("a.txt"))
+            .await;
+        assert!(matches!(result, Err(Error::Internal(_))));
+    }
+
+    #[tokio::test]
+    async fn test_collect_errors() {
+        let pipeline = pipeline()
+            .with_processor(Arc::new(Failing))
+            .with_processor(Arc::new(Upper))
+            .with_error_policy(ErrorPolicy::Collect);
+
+        let output = pipeline
+            .run(Bytes::from_static(b"hello"), Some("a.txt"))
+            .await
+            .unwrap();
+
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(
+            output.errors[0].stage,
+            Stage::Process("failing".to_string())
+        );
+        assert_eq!(output.document.metadata.title.as_deref(), Some("HELLO"));
+        assert!(output.rendered.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_format() {
+        let pipeline = pipeline().with_config(PipelineConfig {
+            render: RenderOptions::default(),
+            ..PipelineConfig::default()
+        });
+
+        let result = pipeline
+            .run(Bytes::from_static(b"%PDF-1.4\n"), Some("a.pdf"))
+            .await;
+        assert!(matches!(result, Err(Error::UnsupportedFormat(_))));
+    }
+}
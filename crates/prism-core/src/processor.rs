@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Processor Traits
+//!
+//! Core traits for processors that transform a parsed document before it is
+//! rendered (OCR, PII detection, redaction, ...).
+
+use async_trait::async_trait;
+
+use crate::document::Document;
+use crate::error::Result;
+
+/// Trait for document processors
+///
+/// Processors run in order between parsing and rendering, each receiving
+/// the document as left by the previous one.
+#[async_trait]
+pub trait Processor: Send + Sync {
+    /// Short, stable name used in logs and error reports
+    fn name(&self) -> &str;
+
+    /// Transform the document in place
+    async fn process(&self, document: &mut Document) -> Result<()>;
+}
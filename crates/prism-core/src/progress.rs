@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Progress Reporting
+//!
+//! Long conversions report what they are doing through a [`ProgressSink`]
+//! handed to parsers in [`ParseContext::progress`](crate::parser::ParseContext::progress),
+//! to renderers in [`RenderContext::progress`](crate::render::RenderContext::progress)
+//! and to the pipeline with
+//! [`Pipeline::with_progress`](crate::pipeline::Pipeline::with_progress).
+//! Sinks are called from whatever thread does the work, so they should
+//! only record or forward the event.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+
+/// A step of a running conversion
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// A pipeline stage started
+    StageChanged {
+        /// Name of the stage, such as `parse` or `process:disarm`
+        stage: String,
+    },
+    /// Part of the input was read
+    BytesRead {
+        /// Number of bytes read so far
+        read: u64,
+        /// Size of the input, when known up front
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<u64>,
+    },
+    /// The input format was detected
+    FormatDetected {
+        /// MIME type of the format
+        mime_type: String,
+        /// Human-readable format name
+        name: String,
+        /// Detection confidence (0.0 to 1.0)
+        confidence: f64,
+    },
+    /// A page (or slide, sheet or frame) was parsed
+    PageParsed {
+        /// Number of pages parsed so far
+        parsed: usize,
+        /// Total number of pages, when known up front
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+    },
+    /// Rendering started
+    RenderStarted {
+        /// Number of pages to render
+        pages: usize,
+    },
+    /// A page was rendered
+    PageRendered {
+        /// Number of pages rendered so far
+        rendered: usize,
+        /// Total number of pages, when known up front
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+    },
+    /// Rendering finished
+    RenderFinished {
+        /// Size of the output in bytes
+        bytes: usize,
+    },
+    /// A problem was recovered from
+    Warning {
+        /// What went wrong
+        diagnostic: Diagnostic,
+    },
+}
+
+impl ProgressEvent {
+    /// Short name of the event kind, as used in the serialized `event` tag
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::StageChanged { .. } => "stage_changed",
+            Self::BytesRead { .. } => "bytes_read",
+            Self::FormatDetected { .. } => "format_detected",
+            Self::PageParsed { .. } => "page_parsed",
+            Self::RenderStarted { .. } => "render_started",
+            Self::PageRendered { .. } => "page_rendered",
+            Self::RenderFinished { .. } => "render_finished",
+            Self::Warning { .. } => "warning",
+        }
+    }
+}
+
+/// Receiver of [`ProgressEvent`]s
+pub trait ProgressSink: Send + Sync + fmt::Debug {
+    /// Record one event
+    fn report(&self, event: ProgressEvent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let event = ProgressEvent::PageParsed {
+            parsed: 2,
+            total: None,
+        };
+        assert_eq!(event.name(), "page_parsed");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"page_parsed","parsed":2}"#
+        );
+    }
+}
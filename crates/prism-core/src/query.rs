@@ -0,0 +1,522 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Document queries
+//!
+//! Path expressions that pick elements out of a parsed document without
+//! writing Rust, for scripting targeted extractions. A query is evaluated
+//! over the JSON form of the [`Document`], so every field name is the one
+//! that appears in `prism inspect --json` output.
+//!
+//! The syntax is a subset of `JSONPath`:
+//!
+//! | Expression      | Selects                                          |
+//! |-----------------|--------------------------------------------------|
+//! | `$`             | The document (optional at the start)             |
+//! | `.name`         | A field of an object                             |
+//! | `['name']`      | A field whose name is not a plain identifier     |
+//! | `.*` or `[*]`   | Every element of an array or value of an object  |
+//! | `[2]`, `[-1]`   | An array element, counted from zero or the end   |
+//! | `[1:3]`         | A range of elements, end exclusive               |
+//! | `..name`        | The field at any depth                           |
+//!
+//! On top of the real fields, a few names are resolved against the document
+//! model:
+//!
+//! - `texts`, `images`, `tables`, `lists`, `form_fields`, `vectors` and
+//!   `containers` on a page, table cell, list item or container are its
+//!   content blocks of that type, so `pages[3].tables[0].rows[0]` is the first row of the
+//!   first table on the fourth page. `..tables` finds tables at any depth,
+//!   including tables nested in cells.
+//! - `text` on anything that has no `text` field of its own is the plain
+//!   text it contains: `pages[0].tables[0].rows[*].cells[1].text` is the
+//!   second column of a table.
+
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::document::Document;
+use crate::error::{Error, Result};
+
+/// Names that select content blocks by their `type` tag
+const BLOCK_TYPES: &[(&str, &str)] = &[
+    ("texts", "Text"),
+    ("images", "Image"),
+    ("tables", "Table"),
+    ("lists", "List"),
+    ("form_fields", "FormField"),
+    ("vectors", "Vector"),
+    ("containers", "Container"),
+];
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Field(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Descendants(String),
+}
+
+impl Query {
+    /// Parse a query expression
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if the expression is malformed.
+    pub fn parse(source: &str) -> Result<Self> {
+        let steps = QueryParser::new(source).parse()?;
+        Ok(Self {
+            source: source.to_string(),
+            steps,
+        })
+    }
+
+    /// Evaluate the query against a document
+    ///
+    /// Returns the matching elements in document order; a query that
+    /// matches nothing returns an empty list.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Internal`] if the document cannot be serialized.
+    pub fn evaluate(&self, document: &Document) -> Result<Vec<Value>> {
+        let root = serde_json::to_value(document)
+            .map_err(|e| Error::Internal(format!("Failed to serialize document: {e}")))?;
+        Ok(self.select(&root))
+    }
+
+    /// Evaluate the query against any JSON value
+    #[must_use]
+    pub fn select(&self, root: &Value) -> Vec<Value> {
+        let mut current = vec![root.clone()];
+        for step in &self.steps {
+            current = current.iter().flat_map(|value| step.apply(value)).collect();
+        }
+        current
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Parse `path` and evaluate it against `document`
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidInput`] if the expression is malformed.
+pub fn query(document: &Document, path: &str) -> Result<Vec<Value>> {
+    Query::parse(path)?.evaluate(document)
+}
+
+impl Step {
+    fn apply(&self, value: &Value) -> Vec<Value> {
+        match self {
+            Self::Field(name) => field(value, name).into_iter().collect(),
+            Self::Wildcard => match value {
+                Value::Array(items) => items.clone(),
+                Value::Object(map) => map.values().cloned().collect(),
+                _ => Vec::new(),
+            },
+            Self::Index(index) => match value {
+                Value::Array(items) => resolve_index(*index, items.len())
+                    .filter(|&i| i < items.len())
+                    .map(|i| items[i].clone())
+                    .into_iter()
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Self::Slice(start, end) => match value {
+                Value::Array(items) => {
+                    let len = items.len();
+                    let start = start.map_or(0, |i| resolve_index(i, len).unwrap_or(0).min(len));
+                    let end = end.map_or(len, |i| resolve_index(i, len).unwrap_or(0).min(len));
+                    items
+                        .get(start..end.max(start))
+                        .unwrap_or_default()
+                        .to_vec()
+                }
+                _ => Vec::new(),
+            },
+            Self::Descendants(name) => {
+                let mut found = Vec::new();
+                descendants(value, name, &mut found);
+                found
+            }
+        }
+    }
+}
+
+/// Position of `index` in an array of `len`, negative indices counting
+/// from the end
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    if index >= 0 {
+        usize::try_from(index).ok()
+    } else {
+        len.checked_sub(usize::try_from(index.unsigned_abs()).ok()?)
+    }
+}
+
+/// The field `name` of `value`, real or resolved against the document model
+fn field(value: &Value, name: &str) -> Option<Value> {
+    let map = value.as_object()?;
+    if let Some(found) = map.get(name) {
+        return Some(found.clone());
+    }
+    if let Some(blocks) = blocks_of_type(value, name) {
+        return Some(Value::Array(blocks.into_iter().cloned().collect()));
+    }
+    (name == "text").then(|| Value::String(text_of(value)))
+}
+
+/// Content blocks of `value` whose type is selected by `name`, if `value`
+/// holds blocks and `name` is one of [`BLOCK_TYPES`]
+fn blocks_of_type<'a>(value: &'a Value, name: &str) -> Option<Vec<&'a Value>> {
+    let (_, tag) = BLOCK_TYPES.iter().find(|(plural, _)| *plural == name)?;
+    let map = value.as_object()?;
+    let blocks = map
+        .get("content")
+        .or_else(|| map.get("children"))?
+        .as_array()?;
+    Some(
+        blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some(tag))
+            .collect(),
+    )
+}
+
+/// Collect the field `name` of `value` and of everything below it
+///
+/// Block type names yield the blocks themselves rather than one list per
+/// parent, so `..tables` is a flat list of every table.
+fn descendants(value: &Value, name: &str, found: &mut Vec<Value>) {
+    if let Some(blocks) = blocks_of_type(value, name) {
+        found.extend(blocks.into_iter().cloned());
+    } else if name == "*" {
+        if let Some(map) = value.as_object() {
+            found.extend(map.values().cloned());
+        }
+    } else if let Some(found_value) = value.as_object().and_then(|map| map.get(name)) {
+        found.push(found_value.clone());
+    }
+
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| descendants(item, name, found)),
+        Value::Object(map) => map.values().for_each(|item| descendants(item, name, found)),
+        _ => {}
+    }
+}
+
+/// Plain text contained in `value`
+///
+/// Runs are concatenated, table cells separated by tabs and everything else
+/// by line breaks.
+fn text_of(value: &Value) -> String {
+    let join = |items: &[Value], separator: &str| {
+        items
+            .iter()
+            .map(text_of)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(separator)
+    };
+
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => join(items, "\n"),
+        Value::Object(map) => {
+            if let Some(Value::Array(runs)) = map.get("runs") {
+                return runs
+                    .iter()
+                    .filter_map(|run| run.get("text").and_then(Value::as_str))
+                    .collect();
+            }
+            if let Some(Value::String(text)) = map.get("text") {
+                return text.clone();
+            }
+            if let Some(Value::Array(cells)) = map.get("cells") {
+                return join(cells, "\t");
+            }
+            ["pages", "content", "children", "rows", "items"]
+                .iter()
+                .find_map(|key| map.get(*key).and_then(Value::as_array))
+                .map(|items| join(items, "\n"))
+                .unwrap_or_default()
+        }
+        _ => String::new(),
+    }
+}
+
+struct QueryParser<'a> {
+    source: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().peekable(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Vec<Step>> {
+        let mut steps = Vec::new();
+        self.eat('$');
+        // The first field may omit its dot: `pages[0]`
+        if self.peek().is_some_and(is_name_char) {
+            steps.push(Step::Field(self.name()?));
+        } else if self.eat('*') {
+            steps.push(Step::Wildcard);
+        }
+
+        while let Some(c) = self.peek() {
+            match c {
+                '.' => {
+                    self.chars.next();
+                    if self.eat('.') {
+                        let name = if self.eat('*') {
+                            "*".to_string()
+                        } else {
+                            self.name()?
+                        };
+                        steps.push(Step::Descendants(name));
+                    } else if self.eat('*') {
+                        steps.push(Step::Wildcard);
+                    } else {
+                        steps.push(Step::Field(self.name()?));
+                    }
+                }
+                '[' => {
+                    self.chars.next();
+                    steps.push(self.bracket()?);
+                }
+                _ => return Err(self.error(&format!("unexpected '{c}'"))),
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Contents of `[...]`, after the opening bracket
+    fn bracket(&mut self) -> Result<Step> {
+        self.skip_whitespace();
+        let step = match self.peek() {
+            Some('*') => {
+                self.chars.next();
+                Step::Wildcard
+            }
+            Some(quote @ ('\'' | '"')) => {
+                self.chars.next();
+                let mut name = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some((_, c)) if c == quote => break,
+                        Some((_, c)) => name.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+                Step::Field(name)
+            }
+            _ => {
+                let start = self.integer()?;
+                self.skip_whitespace();
+                if self.eat(':') {
+                    self.skip_whitespace();
+                    Step::Slice(start, self.integer()?)
+                } else {
+                    Step::Index(start.ok_or_else(|| self.error("expected an index"))?)
+                }
+            }
+        };
+        self.skip_whitespace();
+        if !self.eat(']') {
+            return Err(self.error("expected ']'"));
+        }
+        Ok(step)
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|&c| is_name_char(c)) {
+            name.push(c);
+            self.chars.next();
+        }
+        if name.is_empty() {
+            return Err(self.error("expected a field name"));
+        }
+        Ok(name)
+    }
+
+    /// An optional, possibly negative integer
+    fn integer(&mut self) -> Result<Option<i64>> {
+        let mut digits = String::new();
+        if self.eat('-') {
+            digits.push('-');
+        }
+        while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+            digits.push(c);
+            self.chars.next();
+        }
+        match digits.as_str() {
+            "" => Ok(None),
+            "-" => Err(self.error("expected a number")),
+            _ => digits
+                .parse()
+                .map(Some)
+                .map_err(|_| self.error("index out of range")),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.chars.next();
+        }
+        matched
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.chars.next();
+        }
+    }
+
+    fn error(&mut self, message: &str) -> Error {
+        let offset = self.chars.peek().map_or(self.source.len(), |&(i, _)| i);
+        Error::InvalidInput(format!(
+            "Invalid query '{}' at offset {offset}: {message}",
+            self.source
+        ))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{
+        Dimensions, Page, Rect, ShapeStyle, TableBlock, TableCell, TableRow, TextBlock, TextRun,
+        TextStyle,
+    };
+    use crate::ContentBlock;
+    use serde_json::json;
+
+    fn text(content: &str) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::new(0.0, 0.0, 100.0, 20.0));
+        block.add_run(TextRun {
+            text: content.into(),
+            style: TextStyle::default(),
+            bounds: None,
+            char_positions: None,
+            link: None,
+            revision: None,
+        });
+        ContentBlock::Text(block)
+    }
+
+    fn table(rows: &[&[&str]]) -> ContentBlock {
+        ContentBlock::Table(TableBlock {
+            bounds: Rect::new(0.0, 0.0, 100.0, 100.0),
+            rows: rows
+                .iter()
+                .map(|cells| TableRow {
+                    cells: cells
+                        .iter()
+                        .map(|content| TableCell {
+                            content: vec![text(content)],
+                            col_span: 1,
+                            row_span: 1,
+                            background_color: None,
+                        })
+                        .collect(),
+                    height: None,
+                })
+                .collect(),
+            column_count: rows[0].len(),
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+        })
+    }
+
+    fn document() -> Document {
+        let mut first = Page::new(1, Dimensions::new(612.0, 792.0));
+        first.content = vec![text("Intro")];
+        let mut second = Page::new(2, Dimensions::new(612.0, 792.0));
+        second.content = vec![
+            text("Prices"),
+            table(&[&["Item", "Price"], &["Tea", "3"]]),
+            table(&[&["Total", "3"]]),
+        ];
+        let mut document = Document::new();
+        document.pages = vec![first, second];
+        document
+    }
+
+    #[test]
+    fn test_query_document() {
+        let document = document();
+        let select = |path: &str| query(&document, path).unwrap();
+
+        assert_eq!(select("pages[*].number"), [json!(1), json!(2)]);
+        assert_eq!(select("$.pages[-1].number"), [json!(2)]);
+        assert_eq!(select("pages[1].tables[0].rows[1].text"), [json!("Tea\t3")]);
+        assert_eq!(
+            select("pages[1].tables[0].rows[*].cells[1].text"),
+            [json!("Price"), json!("3")]
+        );
+        assert_eq!(select("pages[1].tables").len(), 1);
+        assert_eq!(select("pages[1].tables[*]").len(), 2);
+        assert_eq!(select("..tables").len(), 2);
+        assert_eq!(
+            select("pages[*].texts[0].text"),
+            [json!("Intro"), json!("Prices")]
+        );
+        assert_eq!(select("pages[0:1].number"), [json!(1)]);
+        assert_eq!(select("pages[5]"), Vec::<Value>::new());
+        assert_eq!(select("pages[0]['number']"), [json!(1)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for path in [
+            "pages[", "pages[x]", "pages.", "pages[0", "pages#", "['name",
+        ] {
+            assert!(
+                matches!(Query::parse(path), Err(Error::InvalidInput(_))),
+                "{path} should not parse"
+            );
+        }
+        assert_eq!(
+            Query::parse("$..rows[1:]").unwrap().steps,
+            [
+                Step::Descendants("rows".to_string()),
+                Step::Slice(Some(1), None)
+            ]
+        );
+    }
+}
@@ -5,10 +5,20 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::StreamExt;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::document::Document;
-use crate::error::Result;
+use crate::document::{
+    AnnotationType, ContentBlock, Dimensions, Document, Rect, TextBlock, TextRun,
+};
+use crate::error::{Error, Result};
 use crate::format::Format;
+use crate::progress::{ProgressEvent, ProgressSink};
+use crate::stream::{ByteStream, DocumentStream};
+use crate::validate::{validate, ValidationError};
 
 /// Options for rendering documents
 #[derive(Debug, Clone, Default)]
@@ -30,6 +40,527 @@ pub struct RenderOptions {
 
     /// Quality for lossy formats (0-100)
     pub quality: Option<u8>,
+
+    /// How multi-page output is split and loaded
+    pub pagination: Pagination,
+
+    /// How pages are arranged on printed sheets
+    pub imposition: Imposition,
+
+    /// Resize every page to a common size (None = keep source sizes)
+    pub page_size: Option<PageNormalization>,
+
+    /// Color handling, e.g. grayscale for printing
+    pub color_mode: ColorMode,
+
+    /// Categories of content to leave out, e.g. for text-only proofs
+    pub content: ContentFilter,
+
+    /// Which page annotations are drawn over the content
+    pub annotations: AnnotationOptions,
+
+    /// Text or image overlaid on pages, e.g. `DRAFT` or a logo
+    pub watermark: Option<Watermark>,
+
+    /// Sequential page numbers stamped for legal production
+    pub bates: Option<BatesNumbering>,
+
+    /// Caps on the size of the output, e.g. to keep a single HTML file
+    /// within what a browser can open
+    pub limits: OutputLimits,
+}
+
+/// A category of page content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    /// Text blocks
+    Text,
+
+    /// Raster images
+    Images,
+
+    /// Shapes, lines and other vector graphics
+    Vectors,
+
+    /// Comments, highlights, links, form fields and other annotations
+    Annotations,
+}
+
+/// Which categories of content are rendered
+///
+/// Tables and containers are not a category of their own: they keep
+/// whatever content passes the filter and disappear once they are empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentFilter {
+    excluded: Vec<ContentKind>,
+}
+
+impl ContentFilter {
+    /// Render everything
+    #[must_use]
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Text only, for lightweight proofs
+    #[must_use]
+    pub fn text_only() -> Self {
+        Self::all()
+            .without(ContentKind::Images)
+            .without(ContentKind::Vectors)
+            .without(ContentKind::Annotations)
+    }
+
+    /// Images only, for exhibit sets
+    #[must_use]
+    pub fn images_only() -> Self {
+        Self::all()
+            .without(ContentKind::Text)
+            .without(ContentKind::Vectors)
+            .without(ContentKind::Annotations)
+    }
+
+    /// Also leave out `kind`
+    #[must_use]
+    pub fn without(mut self, kind: ContentKind) -> Self {
+        if !self.excluded.contains(&kind) {
+            self.excluded.push(kind);
+        }
+        self
+    }
+
+    /// Whether content of `kind` is rendered
+    #[must_use]
+    pub fn includes(&self, kind: ContentKind) -> bool {
+        !self.excluded.contains(&kind)
+    }
+
+    /// Whether nothing is filtered out
+    #[must_use]
+    pub fn is_all(&self) -> bool {
+        self.excluded.is_empty()
+    }
+}
+
+/// Which types of page annotations are drawn
+///
+/// Everything is drawn by default. Leaving out every annotation is also
+/// possible with [`ContentKind::Annotations`], which additionally drops
+/// form fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct AnnotationOptions {
+    /// Highlights, underlines and strikeouts over text
+    pub highlights: bool,
+
+    /// Comments, shown as notes with their author and date
+    pub comments: bool,
+
+    /// Link areas, made clickable
+    pub links: bool,
+
+    /// Redactions, drawn as black boxes
+    pub redactions: bool,
+
+    /// Stamps such as `APPROVED`
+    pub stamps: bool,
+}
+
+impl Default for AnnotationOptions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl AnnotationOptions {
+    /// Draw every annotation
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            highlights: true,
+            comments: true,
+            links: true,
+            redactions: true,
+            stamps: true,
+        }
+    }
+
+    /// Draw no annotations
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            highlights: false,
+            comments: false,
+            links: false,
+            redactions: false,
+            stamps: false,
+        }
+    }
+
+    /// Whether annotations of type `annotation` are drawn
+    ///
+    /// Ink annotations carry no strokes in the document model, so they are
+    /// never drawn.
+    #[must_use]
+    pub fn includes(&self, annotation: &AnnotationType) -> bool {
+        match annotation {
+            AnnotationType::Highlight | AnnotationType::Underline | AnnotationType::Strikeout => {
+                self.highlights
+            }
+            AnnotationType::Comment => self.comments,
+            AnnotationType::Link { .. } => self.links,
+            AnnotationType::Redaction => self.redactions,
+            AnnotationType::Stamp => self.stamps,
+            AnnotationType::Ink => false,
+        }
+    }
+}
+
+impl FromStr for AnnotationOptions {
+    type Err = Error;
+
+    /// Parse `all`, `none` or a comma-separated list of `highlights`,
+    /// `comments`, `links`, `redactions` and `stamps`, in any case
+    fn from_str(s: &str) -> Result<Self> {
+        let mut options = Self::none();
+        for name in s.split(',').map(|name| name.trim().to_ascii_lowercase()) {
+            match name.as_str() {
+                "all" => options = Self::all(),
+                "none" | "" => {}
+                "highlights" => options.highlights = true,
+                "comments" => options.comments = true,
+                "links" => options.links = true,
+                "redactions" => options.redactions = true,
+                "stamps" => options.stamps = true,
+                other => {
+                    return Err(Error::InvalidInput(format!(
+                        "Unknown annotation type '{other}'"
+                    )))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Where on the page a stamp is placed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StampPosition {
+    /// Centered on the page
+    #[default]
+    Center,
+
+    /// Centered along the top edge
+    Top,
+
+    /// Centered along the bottom edge
+    Bottom,
+
+    /// Top-left corner
+    TopLeft,
+
+    /// Top-right corner
+    TopRight,
+
+    /// Bottom-left corner
+    BottomLeft,
+
+    /// Bottom-right corner
+    BottomRight,
+}
+
+/// Which pages a stamp is placed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StampPages {
+    /// Every page
+    #[default]
+    All,
+
+    /// The first page only, e.g. for a cover stamp
+    First,
+}
+
+impl StampPages {
+    /// Whether the stamp goes on the page at `index` (0-based) of the output
+    #[must_use]
+    pub fn includes(self, index: usize) -> bool {
+        match self {
+            Self::All => true,
+            Self::First => index == 0,
+        }
+    }
+}
+
+/// What a watermark shows
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkContent {
+    /// Text, such as `CONFIDENTIAL`
+    Text {
+        /// The text
+        text: String,
+        /// Font size in points
+        font_size: f64,
+        /// Text color (hex or named)
+        color: String,
+    },
+
+    /// An image, such as a logo
+    Image {
+        /// Encoded image data
+        data: Vec<u8>,
+        /// MIME type of `data`
+        mime_type: String,
+        /// Size on the page
+        size: Dimensions,
+    },
+}
+
+/// A text or image overlaid on rendered pages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    /// What is shown
+    pub content: WatermarkContent,
+
+    /// Opacity from 0.0 (invisible) to 1.0 (opaque)
+    pub opacity: f64,
+
+    /// Where on the page it is placed
+    pub position: StampPosition,
+
+    /// Rotation in degrees, counter-clockwise
+    pub rotation: f64,
+
+    /// Which pages it is placed on
+    pub pages: StampPages,
+}
+
+impl Watermark {
+    /// Large gray text across the middle of every page, rising at 45°
+    #[must_use]
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: WatermarkContent::Text {
+                text: text.into(),
+                font_size: 72.0,
+                color: "#808080".to_string(),
+            },
+            opacity: 0.3,
+            position: StampPosition::Center,
+            rotation: 45.0,
+            pages: StampPages::All,
+        }
+    }
+
+    /// An image of `size` in the middle of every page
+    #[must_use]
+    pub fn image(data: Vec<u8>, mime_type: impl Into<String>, size: Dimensions) -> Self {
+        Self {
+            content: WatermarkContent::Image {
+                data,
+                mime_type: mime_type.into(),
+                size,
+            },
+            opacity: 0.3,
+            position: StampPosition::Center,
+            rotation: 0.0,
+            pages: StampPages::All,
+        }
+    }
+
+    /// Use a different opacity, clamped to 0.0-1.0
+    #[must_use]
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Place the watermark elsewhere on the page
+    #[must_use]
+    pub fn with_position(mut self, position: StampPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Rotate by `degrees`, counter-clockwise
+    #[must_use]
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    /// Place the watermark on the first page only
+    #[must_use]
+    pub fn first_page_only(mut self) -> Self {
+        self.pages = StampPages::First;
+        self
+    }
+}
+
+/// Bates numbering: a unique, sequential identifier stamped on every page
+/// of a production, such as `ACME000001`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatesNumbering {
+    /// Text before the number
+    pub prefix: String,
+
+    /// Number of the first page
+    pub start: u64,
+
+    /// Minimum number of digits; shorter numbers are padded with zeros
+    pub digits: usize,
+
+    /// Where on the page the number is placed
+    pub position: StampPosition,
+
+    /// Font size in points
+    pub font_size: f64,
+}
+
+impl BatesNumbering {
+    /// Six-digit numbers from 1 after `prefix`, in the bottom-right corner
+    #[must_use]
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            start: 1,
+            digits: 6,
+            position: StampPosition::BottomRight,
+            font_size: 10.0,
+        }
+    }
+
+    /// Start numbering at `start`, e.g. to continue an earlier production
+    #[must_use]
+    pub fn with_start(mut self, start: u64) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Pad numbers to `digits` digits
+    #[must_use]
+    pub fn with_digits(mut self, digits: usize) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    /// Place the number elsewhere on the page
+    #[must_use]
+    pub fn with_position(mut self, position: StampPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// The label of the page at `index` (0-based) of the output
+    #[must_use]
+    pub fn label(&self, index: usize) -> String {
+        let number = self
+            .start
+            .saturating_add(u64::try_from(index).unwrap_or(u64::MAX));
+        format!("{}{number:0width$}", self.prefix, width = self.digits)
+    }
+}
+
+/// How colors are reproduced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colors as in the source document
+    #[default]
+    Color,
+
+    /// Every color converted to its gray equivalent
+    Grayscale,
+
+    /// Grayscale with full-page backgrounds lightened and light text
+    /// darkened to match, for printer-friendly slide decks
+    InkSaving,
+}
+
+/// Pagination mode for viewer-style output (e.g. HTML)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pagination {
+    /// Every page in a single document, rendered up front
+    #[default]
+    Continuous,
+
+    /// A single document whose pages are only rendered as they scroll
+    /// into view
+    Deferred,
+
+    /// One fragment per page plus an index shell with navigation,
+    /// packaged as a ZIP archive
+    Split,
+}
+
+impl FromStr for Pagination {
+    type Err = Error;
+
+    /// Parse `continuous`, `deferred` or `split`, in any case
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "continuous" => Ok(Self::Continuous),
+            "deferred" => Ok(Self::Deferred),
+            "split" => Ok(Self::Split),
+            other => Err(Error::InvalidInput(format!("Unknown pagination '{other}'"))),
+        }
+    }
+}
+
+/// Arrangement of pages on printed sheets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Imposition {
+    /// One page per sheet
+    #[default]
+    None,
+
+    /// Consecutive pages side by side, two per sheet
+    TwoUp,
+
+    /// Saddle-stitched booklet: two pages per sheet side, ordered so that
+    /// the printed stack reads in sequence once folded in half
+    Booklet,
+
+    /// Contact sheet: pages scaled down into a grid
+    NUp {
+        /// Pages across each sheet
+        columns: u32,
+        /// Pages down each sheet
+        rows: u32,
+    },
+}
+
+/// Target size that every page is scaled to
+#[derive(Debug, Clone, Copy)]
+pub struct PageNormalization {
+    /// Target page size
+    pub size: Dimensions,
+
+    /// How pages with a different aspect ratio are fitted
+    pub fit: PageFit,
+}
+
+impl PageNormalization {
+    /// Normalize to `size` using `fit`
+    #[must_use]
+    pub fn new(size: Dimensions, fit: PageFit) -> Self {
+        Self { size, fit }
+    }
+}
+
+/// How a page is scaled onto a target size; content is always scaled
+/// proportionally
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageFit {
+    /// Scale to fit inside the target; the page shrinks to the scaled size
+    /// so no space is added
+    Fit,
+
+    /// Scale to cover the target, centered, cropping whatever overflows
+    Fill,
+
+    /// Scale to fit inside the target, centered on a page of exactly the
+    /// target size with blank margins
+    #[default]
+    Letterbox,
 }
 
 /// A range of pages to render
@@ -45,6 +576,157 @@ pub enum PageRange {
     Range { start: u32, end: u32 },
 }
 
+impl PageRange {
+    /// Most pages a list such as `1,4,7-9` may name
+    const MAX_LISTED_PAGES: u32 = 10_000;
+
+    /// Whether `page` (1-indexed) is in the range
+    #[must_use]
+    pub fn includes(&self, page: u32) -> bool {
+        match self {
+            Self::All => true,
+            Self::Pages(pages) => pages.contains(&page),
+            Self::Range { start, end } => (*start..=*end).contains(&page),
+        }
+    }
+}
+
+impl FromStr for PageRange {
+    type Err = Error;
+
+    /// Parse `all`, a single range such as `3-7`, or a comma-separated list
+    /// of pages and ranges such as `1,4,7-9`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidInput(format!("Invalid page range '{s}'"));
+        let bounds = |part: &str| -> Result<(u32, u32)> {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+            let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+            if start == 0 || end < start {
+                return Err(invalid());
+            }
+            Ok((start, end))
+        };
+
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        if !s.contains(',') && s.contains('-') {
+            let (start, end) = bounds(s)?;
+            return Ok(Self::Range { start, end });
+        }
+        let mut pages = Vec::new();
+        for part in s.split(',') {
+            let (start, end) = bounds(part)?;
+            if pages.len() + (end - start) as usize >= Self::MAX_LISTED_PAGES as usize {
+                return Err(invalid());
+            }
+            pages.extend(start..=end);
+        }
+        Ok(Self::Pages(pages))
+    }
+}
+
+/// Caps on the size of rendered output
+///
+/// Output over a limit is cut at a page boundary and its last page ends
+/// with a marker saying so. [`Renderer::render_within_limits`] enforces
+/// them and records the cut as [`RenderDiagnostics::truncated`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Most pages rendered, counted after the page range is applied
+    pub max_pages: Option<usize>,
+
+    /// Most bytes of output
+    pub max_bytes: Option<usize>,
+}
+
+impl OutputLimits {
+    /// Whether no limit is set
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.max_pages.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// The limit that cut output short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationReason {
+    /// [`OutputLimits::max_pages`]
+    MaxPages,
+
+    /// [`OutputLimits::max_bytes`]
+    MaxBytes,
+}
+
+/// Output cut short by one of the [`OutputLimits`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Truncation {
+    /// The limit that was reached
+    pub reason: TruncationReason,
+
+    /// Pages in the output
+    pub pages_rendered: usize,
+
+    /// Pages there were to render
+    pub pages_total: usize,
+}
+
+impl Truncation {
+    /// The marker ending truncated output
+    #[must_use]
+    pub fn marker(&self) -> String {
+        format!(
+            "[Output truncated: {} of {} pages rendered]",
+            self.pages_rendered, self.pages_total
+        )
+    }
+
+    /// `document` with only its first [`pages_rendered`](Self::pages_rendered)
+    /// pages, the last of them ending with the [marker](Self::marker)
+    ///
+    /// The marker is placed along the bottom edge of a page whose blocks
+    /// are all positioned, and flows after the content otherwise.
+    #[must_use]
+    pub fn truncate(&self, document: &Document) -> Document {
+        let mut document = document.clone();
+        document.pages.truncate(self.pages_rendered);
+        if let Some(page) = document.pages.last_mut() {
+            let positioned = !page.content.is_empty()
+                && page.content.iter().all(|block| {
+                    let bounds = block.bounds();
+                    bounds.width > 0.0 && bounds.height > 0.0
+                });
+            let bounds = if positioned {
+                let size = page.dimensions;
+                Rect::new(36.0, size.height - 30.0, size.width - 72.0, 14.0)
+            } else {
+                Rect::default()
+            };
+            let mut marker = TextBlock::new(bounds);
+            marker.add_run(TextRun::new(self.marker()));
+            page.content.push(ContentBlock::Text(marker));
+        }
+        document
+    }
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.reason {
+            TruncationReason::MaxPages => "page",
+            TruncationReason::MaxBytes => "size",
+        };
+        write!(
+            f,
+            "output truncated at the {limit} limit: {} of {} pages rendered",
+            self.pages_rendered, self.pages_total
+        )
+    }
+}
+
 /// Context for rendering operations
 #[derive(Debug, Clone)]
 pub struct RenderContext {
@@ -53,6 +735,18 @@ pub struct RenderContext {
 
     /// Target filename (optional hint)
     pub filename: Option<String>,
+
+    /// Receiver of progress events, if the caller wants them
+    pub progress: Option<Arc<dyn ProgressSink>>,
+}
+
+impl RenderContext {
+    /// Send `event` to the progress sink, if there is one
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event);
+        }
+    }
 }
 
 /// Trait for document renderers
@@ -75,12 +769,387 @@ pub trait Renderer: Send + Sync {
     /// The rendered document as bytes
     async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes>;
 
+    /// Render a document while its pages are still being parsed
+    ///
+    /// Renderers whose output can be written page by page override this to
+    /// emit each page as it arrives. The default collects the whole
+    /// document and renders it in one chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream yields one before output starts or
+    /// rendering fails; later errors are yielded by the output stream.
+    async fn render_stream(
+        &self,
+        document: DocumentStream,
+        context: RenderContext,
+    ) -> Result<ByteStream> {
+        let document = document.collect().await?;
+        let output = self.render(&document, context).await?;
+        Ok(futures::stream::once(async move { Ok(output) }).boxed())
+    }
+
+    /// Render a document to bytes, noting what could not be rendered
+    /// faithfully
+    ///
+    /// The default checks the document for missing images, out-of-page
+    /// blocks and blocks [`supports`](Self::supports) rejects, then calls
+    /// [`render`](Self::render). Renderers that know more, such as which
+    /// fonts they substituted, override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails.
+    async fn render_with_diagnostics(
+        &self,
+        document: &Document,
+        context: RenderContext,
+    ) -> Result<RenderResult> {
+        let diagnostics = RenderDiagnostics::check(document, |block| self.supports(block));
+        let output = self.render(document, context).await?;
+        Ok(RenderResult {
+            output,
+            diagnostics,
+        })
+    }
+
+    /// Render a document to bytes with the [`OutputLimits`] of the
+    /// context enforced
+    ///
+    /// Pages past [`OutputLimits::max_pages`] are dropped. Output larger
+    /// than [`OutputLimits::max_bytes`] is rendered again with as many
+    /// pages as fit, found by bisection. Renderers are not expected to
+    /// override this: it renders through
+    /// [`render_with_diagnostics`](Self::render_with_diagnostics).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails, or [`Error::ResourceLimit`] if
+    /// not even the first page fits in `max_bytes`.
+    async fn render_within_limits(
+        &self,
+        document: &Document,
+        mut context: RenderContext,
+    ) -> Result<RenderResult> {
+        let limits = context.options.limits;
+        if limits.is_unlimited() {
+            return self.render_with_diagnostics(document, context).await;
+        }
+
+        // Pages are counted once the range has been applied
+        let selected;
+        let document = match context.options.page_range.take() {
+            Some(range) => {
+                let mut document = document.clone();
+                document.pages.retain(|page| range.includes(page.number));
+                selected = document;
+                &selected
+            }
+            None => document,
+        };
+        let total = document.pages.len();
+        let pages = limits.max_pages.map_or(total, |max| total.min(max.max(1)));
+        let reason = (pages < total).then_some(TruncationReason::MaxPages);
+        let result = render_pages(self, document, pages, reason, context.clone()).await?;
+        let Some(max_bytes) = limits.max_bytes.filter(|&max| result.output.len() > max) else {
+            return Ok(result);
+        };
+
+        // The largest number of pages whose output fits
+        let mut fitting = None;
+        let (mut low, mut high) = (1, pages.saturating_sub(1));
+        while low <= high {
+            let middle = low + (high - low) / 2;
+            let attempt = render_pages(
+                self,
+                document,
+                middle,
+                Some(TruncationReason::MaxBytes),
+                context.clone(),
+            )
+            .await?;
+            if attempt.output.len() <= max_bytes {
+                fitting = Some(attempt);
+                low = middle + 1;
+            } else {
+                high = middle - 1;
+            }
+        }
+        fitting.ok_or_else(|| Error::ResourceLimit {
+            resource: "output bytes".to_string(),
+            limit: max_bytes as u64,
+        })
+    }
+
+    /// Whether the output format can represent `block`
+    ///
+    /// Blocks it cannot are left out of the output and reported as
+    /// [`RenderDiagnostics::skipped_blocks`]. Containers are looked into,
+    /// so a renderer accepting them is asked about their children.
+    fn supports(&self, _block: &ContentBlock) -> bool {
+        true
+    }
+
     /// Get renderer metadata
     fn metadata(&self) -> RendererMetadata {
         RendererMetadata::default()
     }
 }
 
+/// Render the first `pages` pages of `document`, ending them with a
+/// truncation marker if `reason` is given
+async fn render_pages<R: Renderer + ?Sized>(
+    renderer: &R,
+    document: &Document,
+    pages: usize,
+    reason: Option<TruncationReason>,
+    context: RenderContext,
+) -> Result<RenderResult> {
+    let Some(reason) = reason else {
+        return renderer.render_with_diagnostics(document, context).await;
+    };
+    let truncation = Truncation {
+        reason,
+        pages_rendered: pages,
+        pages_total: document.pages.len(),
+    };
+    let mut result = renderer
+        .render_with_diagnostics(&truncation.truncate(document), context)
+        .await?;
+    result.diagnostics.truncated = Some(truncation);
+    Ok(result)
+}
+
+/// Rendered output together with its fidelity notes
+#[derive(Debug, Clone, Default)]
+pub struct RenderResult {
+    /// The rendered document
+    pub output: Bytes,
+
+    /// What could not be rendered faithfully
+    pub diagnostics: RenderDiagnostics,
+}
+
+/// Fidelity notes collected while rendering
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenderDiagnostics {
+    /// Fonts that were not available and were replaced
+    pub font_substitutions: Vec<FontSubstitution>,
+
+    /// Images whose resource is not in the document's resource store
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_images: Vec<MissingImage>,
+
+    /// Blocks left out because the output format cannot represent them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_blocks: Vec<SkippedBlocks>,
+
+    /// Blocks extending past the edges of their page
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub overflows: Vec<BoundsOverflow>,
+
+    /// Whether output was cut short by an [`OutputLimits`] limit, and
+    /// where
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<Truncation>,
+}
+
+impl RenderDiagnostics {
+    /// Slack in points before a block counts as leaving its page
+    const OVERFLOW_TOLERANCE: f64 = 1.0;
+
+    /// Check `document` for content a renderer cannot reproduce: images
+    /// without a resource, blocks outside their page, and blocks that
+    /// `supports` rejects
+    #[must_use]
+    pub fn check(document: &Document, supports: impl Fn(&ContentBlock) -> bool) -> Self {
+        let mut diagnostics = Self::default();
+        if let Err(errors) = validate(document) {
+            for error in errors.0 {
+                if let ValidationError::MissingResource { page, resource_id } = error {
+                    diagnostics
+                        .missing_images
+                        .push(MissingImage { page, resource_id });
+                }
+            }
+        }
+        for page in &document.pages {
+            diagnostics.check_support(&page.content, page.number, &supports);
+            for (index, block) in page.content.iter().enumerate() {
+                if overflows(block.bounds(), page.dimensions) {
+                    diagnostics.overflows.push(BoundsOverflow {
+                        page: page.number,
+                        index,
+                        kind: block.kind(),
+                    });
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Whether nothing was recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.font_substitutions.is_empty()
+            && self.missing_images.is_empty()
+            && self.skipped_blocks.is_empty()
+            && self.overflows.is_empty()
+            && self.truncated.is_none()
+    }
+
+    /// Every note as a line of text, for logs and terminal output
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        let fonts = self.font_substitutions.iter().map(ToString::to_string);
+        let images = self.missing_images.iter().map(ToString::to_string);
+        let skipped = self.skipped_blocks.iter().map(ToString::to_string);
+        let overflows = self.overflows.iter().map(ToString::to_string);
+        let truncated = self.truncated.iter().map(ToString::to_string);
+        fonts
+            .chain(images)
+            .chain(skipped)
+            .chain(overflows)
+            .chain(truncated)
+            .collect()
+    }
+
+    /// Record `block` as left out of page `page`
+    pub fn skip(&mut self, page: u32, block: &ContentBlock) {
+        let kind = block.kind();
+        match self.skipped_blocks.last_mut() {
+            Some(last) if last.page == page && last.kind == kind => last.count += 1,
+            _ => self.skipped_blocks.push(SkippedBlocks {
+                page,
+                kind,
+                count: 1,
+            }),
+        }
+    }
+
+    fn check_support(
+        &mut self,
+        blocks: &[ContentBlock],
+        page: u32,
+        supports: &impl Fn(&ContentBlock) -> bool,
+    ) {
+        for block in blocks {
+            if !supports(block) {
+                self.skip(page, block);
+            } else if let ContentBlock::Container(container) = block {
+                self.check_support(&container.children, page, supports);
+            }
+        }
+    }
+}
+
+/// Whether `bounds` reach past the edges of a page of size `page`; flowing
+/// content without bounds never does
+fn overflows(bounds: Rect, page: Dimensions) -> bool {
+    let slack = RenderDiagnostics::OVERFLOW_TOLERANCE;
+    if bounds.width <= 0.0 && bounds.height <= 0.0 {
+        return false;
+    }
+    bounds.x < -slack
+        || bounds.y < -slack
+        || bounds.x + bounds.width > page.width + slack
+        || bounds.y + bounds.height > page.height + slack
+}
+
+/// An image whose resource could not be found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingImage {
+    /// Page holding the image
+    pub page: u32,
+
+    /// The unresolved resource identifier
+    pub resource_id: String,
+}
+
+impl fmt::Display for MissingImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "page {}: image '{}' is missing from the resources",
+            self.page, self.resource_id
+        )
+    }
+}
+
+/// Consecutive blocks of one type left out of a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedBlocks {
+    /// Page holding the blocks
+    pub page: u32,
+
+    /// Block type, as given by [`ContentBlock::kind`]
+    pub kind: &'static str,
+
+    /// Number of blocks
+    pub count: usize,
+}
+
+impl fmt::Display for SkippedBlocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "page {}: skipped {} {} block(s)",
+            self.page, self.count, self.kind
+        )
+    }
+}
+
+/// A block reaching past the edges of its page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoundsOverflow {
+    /// Page holding the block
+    pub page: u32,
+
+    /// Position of the block in [`Page::content`](crate::document::Page::content)
+    pub index: usize,
+
+    /// Block type, as given by [`ContentBlock::kind`]
+    pub kind: &'static str,
+}
+
+impl fmt::Display for BoundsOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "page {}: {} block {} extends past the page",
+            self.page, self.kind, self.index
+        )
+    }
+}
+
+/// A font replaced during rendering
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FontSubstitution {
+    /// Family referenced by the document
+    pub requested: String,
+
+    /// Family used in its place
+    pub substitute: String,
+
+    /// Whether the substitute has the same glyph metrics, so line breaks
+    /// and pagination are preserved
+    pub metric_compatible: bool,
+}
+
+impl fmt::Display for FontSubstitution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "font '{}' replaced by '{}'",
+            self.requested, self.substitute
+        )?;
+        if !self.metric_compatible {
+            f.write_str(" (metrics differ)")?;
+        }
+        Ok(())
+    }
+}
+
 /// Metadata about a renderer
 #[derive(Debug, Clone, Default)]
 pub struct RendererMetadata {
@@ -114,22 +1183,257 @@ pub enum RenderFeature {
 
     /// Supports streaming output
     StreamingSupport,
+
+    /// Supports multi-page imposition
+    Imposition,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_bates_label() {
+        let bates = BatesNumbering::new("ACME").with_start(998);
+        assert_eq!(bates.label(0), "ACME000998");
+        assert_eq!(bates.label(3), "ACME001001");
+        assert_eq!(BatesNumbering::new("X-").with_digits(2).label(120), "X-121");
+        assert!(StampPages::First.includes(0) && !StampPages::First.includes(1));
+    }
+
     #[test]
     fn test_render_options_default() {
         let opts = RenderOptions::default();
         assert!(!opts.include_images);
         assert!(!opts.preserve_formatting);
+        assert_eq!(opts.pagination, Pagination::Continuous);
+        assert_eq!(opts.imposition, Imposition::None);
+        assert!(opts.page_size.is_none());
+        assert_eq!(opts.color_mode, ColorMode::Color);
+        assert!(opts.content.is_all());
+    }
+
+    #[test]
+    fn test_content_filter() {
+        let text = ContentFilter::text_only();
+        assert!(text.includes(ContentKind::Text));
+        assert!(!text.includes(ContentKind::Images));
+        assert!(!text.includes(ContentKind::Annotations));
+
+        let images = ContentFilter::images_only();
+        assert!(images.includes(ContentKind::Images));
+        assert!(!images.includes(ContentKind::Text));
+
+        let no_vectors = ContentFilter::all()
+            .without(ContentKind::Vectors)
+            .without(ContentKind::Vectors);
+        assert!(!no_vectors.is_all());
+        assert_eq!(no_vectors, ContentFilter::all().without(ContentKind::Vectors));
+    }
+
+    #[test]
+    fn test_annotation_options() {
+        let options: AnnotationOptions = "Comments, links".parse().unwrap();
+        assert!(options.includes(&AnnotationType::Comment));
+        assert!(options.includes(&AnnotationType::Link { url: String::new() }));
+        assert!(!options.includes(&AnnotationType::Redaction));
+        assert_eq!(
+            "all".parse::<AnnotationOptions>().unwrap(),
+            AnnotationOptions::default()
+        );
+        assert_eq!(
+            "none".parse::<AnnotationOptions>().unwrap(),
+            AnnotationOptions::none()
+        );
+        assert!("arrows".parse::<AnnotationOptions>().is_err());
+        assert!(!AnnotationOptions::all().includes(&AnnotationType::Ink));
     }
 
     #[test]
     fn test_page_range() {
         let range = PageRange::Range { start: 1, end: 10 };
         assert_eq!(range, PageRange::Range { start: 1, end: 10 });
+        assert!(range.includes(10) && !range.includes(11));
+
+        assert_eq!(
+            "3-7".parse::<PageRange>().unwrap(),
+            PageRange::Range { start: 3, end: 7 }
+        );
+        assert_eq!(
+            "1, 4,7-9".parse::<PageRange>().unwrap(),
+            PageRange::Pages(vec![1, 4, 7, 8, 9])
+        );
+        assert_eq!("ALL".parse::<PageRange>().unwrap(), PageRange::All);
+        for invalid in ["", "0", "5-2", "1,x", "1-100000,2"] {
+            assert!(invalid.parse::<PageRange>().is_err(), "{invalid}");
+        }
+        assert_eq!("Split".parse::<Pagination>().unwrap(), Pagination::Split);
+        assert!("paged".parse::<Pagination>().is_err());
+    }
+
+    #[test]
+    fn test_render_diagnostics_check() {
+        use crate::document::{ContainerBlock, ImageBlock, Page, ShapeStyle, TextBlock};
+
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(ContentBlock::Image(ImageBlock {
+            bounds: Rect {
+                x: 500.0,
+                y: 10.0,
+                width: 200.0,
+                height: 100.0,
+            },
+            resource_id: "img1".to_string(),
+            alt_text: None,
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        page.add_content(ContentBlock::Container(ContainerBlock {
+            bounds: Rect::default(),
+            children: vec![ContentBlock::Text(TextBlock::new(Rect::default()))],
+            container_type: None,
+        }));
+        page.add_content(ContentBlock::Text(TextBlock::new(Rect::default())));
+        let document = Document::builder().page(page).build();
+
+        let diagnostics =
+            RenderDiagnostics::check(&document, |block| !matches!(block, ContentBlock::Text(_)));
+        assert_eq!(
+            diagnostics.missing_images,
+            vec![MissingImage {
+                page: 1,
+                resource_id: "img1".to_string(),
+            }]
+        );
+        assert_eq!(
+            diagnostics.skipped_blocks,
+            vec![SkippedBlocks {
+                page: 1,
+                kind: "text",
+                count: 2,
+            }]
+        );
+        assert_eq!(
+            diagnostics.overflows,
+            vec![BoundsOverflow {
+                page: 1,
+                index: 0,
+                kind: "image",
+            }]
+        );
+        assert_eq!(
+            diagnostics.lines()[0],
+            "page 1: image 'img1' is missing from the resources"
+        );
+
+        assert!(RenderDiagnostics::check(&Document::new(), |_| true).is_empty());
+    }
+
+    struct TextRenderer;
+
+    #[async_trait]
+    impl Renderer for TextRenderer {
+        fn output_format(&self) -> Format {
+            Format::text()
+        }
+
+        async fn render(&self, document: &Document, _context: RenderContext) -> Result<Bytes> {
+            Ok(Bytes::from(document.extract_text()))
+        }
+    }
+
+    fn limited(limits: OutputLimits, page_range: Option<PageRange>) -> RenderContext {
+        RenderContext {
+            options: RenderOptions {
+                page_range,
+                limits,
+                ..RenderOptions::default()
+            },
+            filename: None,
+            progress: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_within_limits() {
+        let mut document = Document::new();
+        for number in 1..=10 {
+            let mut block = TextBlock::new(Rect::default());
+            block.add_run(TextRun::new(format!("page {number:02} {}", "x".repeat(92))));
+            let mut page = crate::document::Page::new(number, Dimensions::LETTER);
+            page.add_content(ContentBlock::Text(block));
+            document.pages.push(page);
+        }
+
+        let result = TextRenderer
+            .render_within_limits(&document, limited(OutputLimits::default(), None))
+            .await
+            .unwrap();
+        assert!(result.diagnostics.truncated.is_none());
+
+        let limits = OutputLimits {
+            max_pages: Some(2),
+            max_bytes: None,
+        };
+        let result = TextRenderer
+            .render_within_limits(
+                &document,
+                limited(limits, Some(PageRange::Range { start: 5, end: 10 })),
+            )
+            .await
+            .unwrap();
+        let text = String::from_utf8(result.output.to_vec()).unwrap();
+        assert!(text.starts_with("page 05"));
+        assert!(text.contains("page 06") && !text.contains("page 07"));
+        assert!(text.ends_with("[Output truncated: 2 of 6 pages rendered]"));
+        assert_eq!(
+            result.diagnostics.truncated,
+            Some(Truncation {
+                reason: TruncationReason::MaxPages,
+                pages_rendered: 2,
+                pages_total: 6,
+            })
+        );
+
+        let limits = OutputLimits {
+            max_pages: None,
+            max_bytes: Some(450),
+        };
+        let result = TextRenderer
+            .render_within_limits(&document, limited(limits, None))
+            .await
+            .unwrap();
+        assert!(result.output.len() <= 450);
+        let truncated = result.diagnostics.truncated.unwrap();
+        assert_eq!(truncated.reason, TruncationReason::MaxBytes);
+        // One more page would not fit
+        let more = TextRenderer
+            .render(
+                &Truncation {
+                    pages_rendered: truncated.pages_rendered + 1,
+                    ..truncated
+                }
+                .truncate(&document),
+                limited(OutputLimits::default(), None),
+            )
+            .await
+            .unwrap();
+        assert!(more.len() > 450);
+
+        let limits = OutputLimits {
+            max_pages: None,
+            max_bytes: Some(10),
+        };
+        assert!(matches!(
+            TextRenderer
+                .render_within_limits(&document, limited(limits, None))
+                .await,
+            Err(Error::ResourceLimit { .. })
+        ));
     }
 }
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Document Statistics
+//!
+//! A structured summary of what a parsed document holds: pages, words,
+//! images, tables and annotations.
+//!
+//! Ingestion pipelines use it to triage documents (route scanned PDFs to
+//! OCR, skip empty files, flag oversized spreadsheets) without rendering
+//! anything. Get one with [`Document::statistics`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::{AnnotationType, ContentBlock, Document, TextBlock};
+
+/// Counts describing a [`Document`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentStatistics {
+    /// Number of pages
+    pub pages: usize,
+
+    /// Number of whitespace-separated words in text blocks, including those
+    /// nested in tables, lists and containers
+    pub words: usize,
+
+    /// Number of characters in those words, whitespace excluded
+    pub characters: usize,
+
+    /// Number of image resources
+    pub images: usize,
+
+    /// Total size of the embedded image data in bytes
+    pub image_bytes: u64,
+
+    /// Number of tables, nested tables included
+    pub tables: usize,
+
+    /// Number of cells across all tables
+    pub table_cells: usize,
+
+    /// Number of annotations per type, e.g. `highlight` or `link`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, usize>,
+
+    /// Language from the metadata, or else the language tagged on most of
+    /// the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl DocumentStatistics {
+    /// Total number of annotations of any type
+    #[must_use]
+    pub fn annotation_count(&self) -> usize {
+        self.annotations.values().sum()
+    }
+}
+
+impl Document {
+    /// Summarize the content of the document
+    #[must_use]
+    pub fn statistics(&self) -> DocumentStatistics {
+        let mut counter = Counter::default();
+        for page in &self.pages {
+            counter.blocks(&page.content);
+            for annotation in &page.annotations {
+                *counter
+                    .statistics
+                    .annotations
+                    .entry(annotation_kind(&annotation.annotation_type).to_string())
+                    .or_default() += 1;
+            }
+        }
+
+        let images = &self.resources.images;
+        let language = self.metadata.language.clone().or_else(|| {
+            counter
+                .languages
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(language, _)| language.to_string())
+        });
+        DocumentStatistics {
+            pages: self.pages.len(),
+            images: images.len(),
+            image_bytes: images
+                .iter()
+                .filter_map(|image| image.data.as_ref())
+                .map(|data| data.len() as u64)
+                .sum(),
+            language,
+            ..counter.statistics
+        }
+    }
+}
+
+/// Accumulates block-level counts while walking the content tree
+#[derive(Default)]
+struct Counter<'a> {
+    statistics: DocumentStatistics,
+    /// Characters of text tagged with each language
+    languages: HashMap<&'a str, usize>,
+}
+
+impl<'a> Counter<'a> {
+    fn blocks(&mut self, blocks: &'a [ContentBlock]) {
+        for block in blocks {
+            match block {
+                ContentBlock::Text(text) => self.text(text),
+                ContentBlock::Table(table) => {
+                    self.statistics.tables += 1;
+                    for row in &table.rows {
+                        self.statistics.table_cells += row.cells.len();
+                        for cell in &row.cells {
+                            self.blocks(&cell.content);
+                        }
+                    }
+                }
+                ContentBlock::List(list) => {
+                    for item in &list.items {
+                        self.blocks(&item.content);
+                    }
+                }
+                ContentBlock::Container(container) => self.blocks(&container.children),
+                ContentBlock::Image(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+            }
+        }
+    }
+
+    fn text(&mut self, text: &'a TextBlock) {
+        let content = text.extract_text();
+        for word in content.split_whitespace() {
+            self.statistics.words += 1;
+            self.statistics.characters += word.chars().count();
+        }
+        for run in &text.runs {
+            if let Some(language) = &run.style.language {
+                let characters = run.text.chars().filter(|c| !c.is_whitespace()).count();
+                *self.languages.entry(language).or_default() += characters;
+            }
+        }
+    }
+}
+
+fn annotation_kind(annotation: &AnnotationType) -> &'static str {
+    match annotation {
+        AnnotationType::Highlight => "highlight",
+        AnnotationType::Underline => "underline",
+        AnnotationType::Strikeout => "strikeout",
+        AnnotationType::Comment => "comment",
+        AnnotationType::Redaction => "redaction",
+        AnnotationType::Stamp => "stamp",
+        AnnotationType::Ink => "ink",
+        AnnotationType::Link { .. } => "link",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{
+        Annotation, Dimensions, ImageResource, Page, Rect, ShapeStyle, TableBlock, TableCell,
+        TableRow, TextRun,
+    };
+    use crate::metadata::Metadata;
+
+    fn paragraph(text: &str, language: Option<&str>) -> TextBlock {
+        let mut run = TextRun::new(text);
+        run.style.language = language.map(Into::into);
+        let mut block = TextBlock::new(Rect::default());
+        block.runs.push(run);
+        block
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(ContentBlock::Text(paragraph(
+            "Bonjour le monde",
+            Some("fr"),
+        )));
+        page.add_content(ContentBlock::Table(TableBlock {
+            bounds: Rect::default(),
+            rows: vec![TableRow {
+                cells: vec![
+                    TableCell {
+                        content: vec![ContentBlock::Text(paragraph("Hi", Some("en")))],
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    },
+                    TableCell {
+                        content: Vec::new(),
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    },
+                ],
+                height: None,
+            }],
+            column_count: 2,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+        }));
+        page.annotations.push(Annotation {
+            id: uuid::Uuid::new_v4(),
+            annotation_type: AnnotationType::Link {
+                url: "https://example.com".to_string(),
+            },
+            bounds: Rect::default(),
+            content: None,
+            author: None,
+            created: None,
+            color: None,
+        });
+        let mut document = Document::builder().page(page).build();
+        document.resources.images.push(ImageResource {
+            id: "image1".to_string(),
+            mime_type: "image/png".to_string(),
+            data: Some(vec![0; 100]),
+            url: None,
+            width: 10,
+            height: 10,
+        });
+
+        let statistics = document.statistics();
+        assert_eq!(statistics.pages, 1);
+        assert_eq!(statistics.words, 4);
+        assert_eq!(statistics.characters, 16);
+        assert_eq!(statistics.images, 1);
+        assert_eq!(statistics.image_bytes, 100);
+        assert_eq!(statistics.tables, 1);
+        assert_eq!(statistics.table_cells, 2);
+        assert_eq!(statistics.annotations.get("link"), Some(&1));
+        assert_eq!(statistics.annotation_count(), 1);
+        assert_eq!(statistics.language.as_deref(), Some("fr"));
+
+        document.metadata = Metadata {
+            language: Some("de".to_string()),
+            ..Metadata::default()
+        };
+        assert_eq!(document.statistics().language.as_deref(), Some("de"));
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Document Streams
+//!
+//! Page-at-a-time access to a document.
+//!
+//! A [`DocumentStream`] holds everything about a document except its pages,
+//! which it yields one by one as the parser produces them. Each page comes
+//! with the image resources it introduces, so a consumer can render and drop
+//! a page before the next one is decoded. This keeps memory flat for
+//! multi-gigabyte scans and lets output start before parsing finishes.
+//!
+//! Parsers that cannot produce pages incrementally are wrapped with
+//! [`DocumentStream::from_document`], so every parser can be consumed as a
+//! stream and every stream can be collected back into a [`Document`].
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::document::{Document, ImageResource, Page};
+use crate::error::Result;
+
+/// Rendered output produced in chunks
+pub type ByteStream = BoxStream<'static, Result<Bytes>>;
+
+/// A page yielded by a [`DocumentStream`]
+#[derive(Debug, Clone)]
+pub struct StreamedPage {
+    /// The page
+    pub page: Page,
+
+    /// Image resources first referenced by this page
+    pub images: Vec<ImageResource>,
+}
+
+impl StreamedPage {
+    /// A page that brings no resources of its own
+    #[must_use]
+    pub fn new(page: Page) -> Self {
+        Self {
+            page,
+            images: Vec::new(),
+        }
+    }
+}
+
+/// A document whose pages are produced incrementally
+///
+/// Implements [`Stream`] over its pages. The [`header`](Self::header) holds
+/// metadata, styles, shared resources and structure; its `pages` are empty.
+pub struct DocumentStream {
+    header: Document,
+    pages: BoxStream<'static, Result<StreamedPage>>,
+}
+
+impl DocumentStream {
+    /// A stream of `pages` for the document described by `header`
+    ///
+    /// Any pages already in `header` are yielded first.
+    pub fn new(
+        mut header: Document,
+        pages: impl Stream<Item = Result<StreamedPage>> + Send + 'static,
+    ) -> Self {
+        let existing: Vec<_> = std::mem::take(&mut header.pages)
+            .into_iter()
+            .map(|page| Ok(StreamedPage::new(page)))
+            .collect();
+        Self {
+            header,
+            pages: stream::iter(existing).chain(pages).boxed(),
+        }
+    }
+
+    /// Stream the pages of an already parsed document
+    #[must_use]
+    pub fn from_document(document: Document) -> Self {
+        Self::new(document, stream::empty())
+    }
+
+    /// The document without its pages
+    #[must_use]
+    pub fn header(&self) -> &Document {
+        &self.header
+    }
+
+    /// Split into the header and the page stream
+    #[must_use]
+    pub fn into_parts(self) -> (Document, BoxStream<'static, Result<StreamedPage>>) {
+        (self.header, self.pages)
+    }
+
+    /// Read every page and assemble the complete document
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error the stream yields.
+    pub async fn collect(self) -> Result<Document> {
+        let (mut document, mut pages) = self.into_parts();
+        while let Some(streamed) = pages.next().await {
+            let streamed = streamed?;
+            document.resources.images.extend(streamed.images);
+            document.pages.push(streamed.page);
+        }
+        Ok(document)
+    }
+}
+
+impl Stream for DocumentStream {
+    type Item = Result<StreamedPage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.pages.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for DocumentStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DocumentStream")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Document> for DocumentStream {
+    fn from(document: Document) -> Self {
+        Self::from_document(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Dimensions;
+    use crate::error::Error;
+
+    fn image(id: &str) -> ImageResource {
+        ImageResource {
+            id: id.to_string(),
+            mime_type: "image/png".to_string(),
+            data: None,
+            url: None,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_pages() {
+        let mut header = Document::new();
+        header.pages.push(Page::new(1, Dimensions::LETTER));
+        let more = stream::iter((2..=3).map(|number| {
+            Ok(StreamedPage {
+                page: Page::new(number, Dimensions::LETTER),
+                images: vec![image(&format!("img{number}"))],
+            })
+        }));
+
+        let mut stream = DocumentStream::new(header, more);
+        assert!(stream.header().pages.is_empty());
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.page.number, 1);
+        assert!(first.images.is_empty());
+
+        let document = stream.collect().await.unwrap();
+        let numbers: Vec<_> = document.pages.iter().map(|page| page.number).collect();
+        assert_eq!(numbers, [2, 3]);
+        assert_eq!(document.resources.images.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error() {
+        let pages = stream::iter([
+            Ok(StreamedPage::new(Page::new(1, Dimensions::LETTER))),
+            Err(Error::ParseError("truncated".to_string())),
+        ]);
+        let result = DocumentStream::new(Document::new(), pages).collect().await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Table Detection
+//!
+//! PDFs and scanned pages have no tables, only text placed on the page. A
+//! financial statement comes out as a list of loose labels and figures
+//! unless the grid is rebuilt from the positions of its text.
+//!
+//! [`detect_tables`] rebuilds it from the positioned top-level text blocks
+//! of each page:
+//!
+//! 1. Blocks whose vertical centers fall within each other's extent form a
+//!    row.
+//! 2. Consecutive rows of at least two blocks, each no further below the
+//!    previous one than twice its own height, form a candidate region.
+//! 3. The horizontal extents of the region's blocks are merged; the gaps
+//!    left between them are the column boundaries.
+//! 4. A region of at least [`MIN_ROWS`] rows and two columns becomes a
+//!    [`TableBlock`] when it has three columns or more, or a column other
+//!    than the first where at least half the cells are numbers, so that two
+//!    columns of running text are left alone.
+//!
+//! Detection is off by default; set
+//! [`ParseOptions::detect_tables`](crate::parser::ParseOptions::detect_tables)
+//! to run it after parsing, or add [`DetectTables`] to a pipeline.
+
+use async_trait::async_trait;
+
+use crate::document::{ContentBlock, Document, Page, Rect, TableBlock, TableCell, TableRow};
+use crate::error::Result;
+use crate::processor::Processor;
+
+/// Fewest rows a detected table has
+pub const MIN_ROWS: usize = 3;
+
+/// Widest gap between two rows of a table, in row heights
+const MAX_ROW_GAP: f64 = 2.0;
+
+/// Processor applying [`detect_tables`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DetectTables;
+
+#[async_trait]
+impl Processor for DetectTables {
+    fn name(&self) -> &'static str {
+        "detect_tables"
+    }
+
+    async fn process(&self, document: &mut Document) -> Result<()> {
+        let tables = detect_tables(document);
+        tracing::debug!("Detected {} table(s)", tables);
+        Ok(())
+    }
+}
+
+/// Replace aligned text blocks with tables on every page and return the
+/// number of tables built
+///
+/// See the [module documentation](crate::table_detection).
+pub fn detect_tables(document: &mut Document) -> usize {
+    document.pages.iter_mut().map(detect_page_tables).sum()
+}
+
+/// A row of positioned text blocks, by index into the page content
+struct Row {
+    blocks: Vec<usize>,
+    top: f64,
+    bottom: f64,
+}
+
+fn detect_page_tables(page: &mut Page) -> usize {
+    let bounds: Vec<Option<Rect>> = page
+        .content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Text(text) if text.bounds.width > 0.0 && text.bounds.height > 0.0 => {
+                Some(text.bounds)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut tables = Vec::new();
+    let mut region: Vec<Row> = Vec::new();
+    for row in rows(&bounds) {
+        let gap = region.last().map(|last| row.top - last.bottom);
+        let height = row.bottom - row.top;
+        if row.blocks.len() < 2 || gap.is_some_and(|gap| gap > height * MAX_ROW_GAP) {
+            tables.extend(table(&page.content, &bounds, &region));
+            region.clear();
+        }
+        if row.blocks.len() >= 2 {
+            region.push(row);
+        }
+    }
+    tables.extend(table(&page.content, &bounds, &region));
+
+    let count = tables.len();
+    replace_blocks(page, tables);
+    count
+}
+
+/// Group the positioned blocks into rows, top to bottom, each left to
+/// right
+fn rows(bounds: &[Option<Rect>]) -> Vec<Row> {
+    let mut positioned: Vec<(usize, Rect)> = bounds
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rect)| rect.map(|rect| (index, rect)))
+        .collect();
+    positioned.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+
+    let mut rows: Vec<Row> = Vec::new();
+    for (index, rect) in positioned {
+        let center = rect.y + rect.height / 2.0;
+        match rows.last_mut() {
+            Some(row) if center <= row.bottom => {
+                row.blocks.push(index);
+                row.bottom = row.bottom.max(rect.y + rect.height);
+            }
+            _ => rows.push(Row {
+                blocks: vec![index],
+                top: rect.y,
+                bottom: rect.y + rect.height,
+            }),
+        }
+    }
+    for row in &mut rows {
+        row.blocks.sort_by(|&a, &b| {
+            let (a, b) = (bounds[a].unwrap_or_default(), bounds[b].unwrap_or_default());
+            a.x.total_cmp(&b.x)
+        });
+    }
+    rows
+}
+
+/// The table a region of rows forms, with the indices of the blocks it
+/// takes over, if it looks like one
+fn table(
+    content: &[ContentBlock],
+    bounds: &[Option<Rect>],
+    region: &[Row],
+) -> Option<(Vec<usize>, TableBlock)> {
+    if region.len() < MIN_ROWS {
+        return None;
+    }
+    let rect = |index: usize| bounds[index].unwrap_or_default();
+
+    // Column boundaries are the gaps between the merged horizontal extents
+    let mut extents: Vec<(f64, f64)> = region
+        .iter()
+        .flat_map(|row| &row.blocks)
+        .map(|&index| (rect(index).x, rect(index).x + rect(index).width))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in extents {
+        match columns.last_mut() {
+            Some(column) if start <= column.1 => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    if columns.len() < 2 {
+        return None;
+    }
+    let column_of = |index: usize| {
+        let x = rect(index).x;
+        columns
+            .iter()
+            .rposition(|column| column.0 <= x)
+            .unwrap_or_default()
+    };
+
+    let grid: Vec<Vec<Vec<usize>>> = region
+        .iter()
+        .map(|row| {
+            let mut cells = vec![Vec::new(); columns.len()];
+            for &index in &row.blocks {
+                cells[column_of(index)].push(index);
+            }
+            cells
+        })
+        .collect();
+    let has_figures = (1..columns.len()).any(|column| {
+        let cells: Vec<&Vec<usize>> = grid
+            .iter()
+            .map(|row| &row[column])
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        let numbers = cells
+            .iter()
+            .filter(|cell| cell.iter().all(|&index| is_number(&content[index])))
+            .count();
+        !cells.is_empty() && 2 * numbers >= cells.len()
+    });
+    if columns.len() < 3 && !has_figures {
+        return None;
+    }
+
+    let taken: Vec<usize> = region.iter().flat_map(|row| row.blocks.clone()).collect();
+    let table_bounds = taken
+        .iter()
+        .map(|&index| rect(index))
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_default();
+    let mut table = TableBlock::new(table_bounds, columns.len());
+    for (cells, row) in grid.into_iter().zip(region) {
+        table.add_row(TableRow {
+            cells: cells
+                .into_iter()
+                .map(|blocks| TableCell {
+                    content: blocks
+                        .into_iter()
+                        .map(|index| content[index].clone())
+                        .collect(),
+                    col_span: 1,
+                    row_span: 1,
+                    background_color: None,
+                })
+                .collect(),
+            height: Some(row.bottom - row.top),
+        });
+    }
+    Some((taken, table))
+}
+
+/// Put each table where its first block was and drop the blocks it took
+/// over
+fn replace_blocks(page: &mut Page, tables: Vec<(Vec<usize>, TableBlock)>) {
+    if tables.is_empty() {
+        return;
+    }
+    let mut taken = vec![None; page.content.len()];
+    let mut tables: Vec<Option<TableBlock>> = tables
+        .into_iter()
+        .enumerate()
+        .map(|(table, (blocks, block))| {
+            for index in blocks {
+                taken[index] = Some(table);
+            }
+            Some(block)
+        })
+        .collect();
+
+    let content = std::mem::take(&mut page.content);
+    for (index, block) in content.into_iter().enumerate() {
+        match taken[index] {
+            Some(table) => {
+                if let Some(table) = tables[table].take() {
+                    page.content.push(ContentBlock::Table(table));
+                }
+            }
+            None => page.content.push(block),
+        }
+    }
+}
+
+/// Whether a text block holds a single figure, such as `1,234.50`,
+/// `(12)`, `-3%` or `$ 40`
+fn is_number(block: &ContentBlock) -> bool {
+    let ContentBlock::Text(text) = block else {
+        return false;
+    };
+    let text = text.extract_text();
+    let digits: String = text
+        .trim()
+        .trim_matches(|c: char| {
+            matches!(c, '(' | ')' | '%' | '$' | '€' | '£' | '¥') || c.is_whitespace()
+        })
+        .chars()
+        .filter(|&c| c != ',')
+        .collect();
+    let digits = digits
+        .strip_prefix(['-', '+', '\u{2212}'])
+        .unwrap_or(&digits);
+    // A dash stands for zero
+    (!digits.is_empty() && digits.parse::<f64>().is_ok()) || digits == "-" || digits == "\u{2014}"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Dimensions, TextBlock, TextRun};
+
+    fn block(text: &str, x: f64, y: f64) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::new(x, y, 60.0, 12.0));
+        block.add_run(TextRun::new(text));
+        ContentBlock::Text(block)
+    }
+
+    #[test]
+    fn test_detect_tables() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.content = vec![
+            block("Income statement", 72.0, 40.0),
+            block("Revenue", 72.0, 100.0),
+            block("1,200", 300.0, 100.0),
+            block("Costs", 72.0, 116.0),
+            block("(800)", 300.0, 116.0),
+            block("Profit", 72.0, 132.0),
+            block("400", 300.0, 132.0),
+            block("Unaudited", 72.0, 300.0),
+        ];
+        let mut document = Document::builder().page(page).build();
+        assert_eq!(detect_tables(&mut document), 1);
+
+        let content = &document.pages[0].content;
+        assert_eq!(content.len(), 3);
+        let ContentBlock::Table(table) = &content[1] else {
+            panic!("expected a table, got {content:?}");
+        };
+        assert_eq!(table.column_count, 2);
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.extract_text().lines().next(), Some("Revenue\t1,200"));
+        assert!(matches!(content[2], ContentBlock::Text(_)));
+
+        // Two columns of words are running text, not a table
+        let mut page = Page::new(1, Dimensions::LETTER);
+        for (line, y) in [100.0, 116.0, 132.0].into_iter().enumerate() {
+            page.content.push(block(&format!("left {line}"), 72.0, y));
+            page.content.push(block(&format!("right {line}"), 300.0, y));
+        }
+        let mut document = Document::builder().page(page).build();
+        assert_eq!(detect_tables(&mut document), 0);
+        assert_eq!(document.pages[0].content.len(), 6);
+    }
+}
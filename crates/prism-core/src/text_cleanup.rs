@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Text Cleanup
+//!
+//! Text extracted from PDFs and scans keeps the line breaks of the page it
+//! was printed on: words hyphenated at the end of a line, paragraphs
+//! broken into hard-wrapped lines, ligature characters and stray
+//! whitespace. [`clean_text`] undoes that in every text block:
+//!
+//! - typographic ligatures (`ﬁ`, `ﬂ`, `ﬀ`, ...) are spelled out and soft
+//!   hyphens dropped
+//! - tabs and Unicode spaces become plain spaces, runs of them collapse
+//!   into one, and lines are trimmed
+//! - a word hyphenated across a line break is merged (`infor-`/`mation`
+//!   becomes `information`); the hyphen is kept when the next line starts
+//!   with a capital letter or a digit (`COVID-`/`19`), and in languages
+//!   that repeat the hyphen of a compound on the next line (Polish,
+//!   Portuguese, Czech, ...) the repeated one is dropped instead
+//! - the remaining single line breaks are joined with a space, or with
+//!   nothing between Chinese, Japanese, Korean or Thai text; blank lines
+//!   still separate paragraphs, and lines starting with a bullet or
+//!   number stay on their own
+//!
+//! The language of a run is its own or else the document's. Cleanup is
+//! off by default; set
+//! [`ParseOptions::clean_text`](crate::parser::ParseOptions::clean_text) to
+//! run it after parsing, or add [`CleanText`] to a pipeline. Explicit line
+//! breaks of flowing documents are joined too, so it is meant for
+//! extracted text rather than word processing files.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::document::{ContentBlock, Document, TextBlock};
+use crate::error::Result;
+use crate::processor::Processor;
+
+/// Languages that repeat the hyphen of a compound word broken at it on
+/// the next line
+const REPEATED_HYPHEN: &[&str] = &["cs", "hr", "pl", "pt", "sk", "sl", "sq", "sr"];
+
+/// Processor applying [`clean_text`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanText;
+
+#[async_trait]
+impl Processor for CleanText {
+    fn name(&self) -> &'static str {
+        "clean_text"
+    }
+
+    async fn process(&self, document: &mut Document) -> Result<()> {
+        let cleaned = clean_text(document);
+        tracing::debug!("Cleaned up the text of {} block(s)", cleaned);
+        Ok(())
+    }
+}
+
+/// Clean up the text of every text block and return the number of blocks
+/// changed
+///
+/// See the [module documentation](crate::text_cleanup).
+pub fn clean_text(document: &mut Document) -> usize {
+    let language = document.metadata.language.clone();
+    document
+        .pages
+        .iter_mut()
+        .map(|page| clean_blocks(&mut page.content, language.as_deref()))
+        .sum()
+}
+
+fn clean_blocks(blocks: &mut [ContentBlock], language: Option<&str>) -> usize {
+    blocks
+        .iter_mut()
+        .map(|block| match block {
+            ContentBlock::Text(text) => usize::from(clean_block(text, language)),
+            ContentBlock::Table(table) => table
+                .rows
+                .iter_mut()
+                .flat_map(|row| &mut row.cells)
+                .map(|cell| clean_blocks(&mut cell.content, language))
+                .sum(),
+            ContentBlock::List(list) => list
+                .items
+                .iter_mut()
+                .map(|item| clean_blocks(&mut item.content, language))
+                .sum(),
+            ContentBlock::Container(container) => clean_blocks(&mut container.children, language),
+            ContentBlock::Image(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => 0,
+        })
+        .sum()
+}
+
+/// Clean up the runs of `block` as one text, so that a word hyphenated
+/// across two runs is merged too; returns whether anything changed
+fn clean_block(block: &mut TextBlock, language: Option<&str>) -> bool {
+    // Every character is tagged with the run it belongs to
+    let chars: Vec<(char, usize)> = block
+        .runs
+        .iter()
+        .enumerate()
+        .flat_map(|(index, run)| run.text.chars().map(move |c| (c, index)))
+        .collect();
+    let language = block
+        .runs
+        .iter()
+        .find_map(|run| run.style.language.as_deref())
+        .or(language);
+    let cleaned = clean(&chars, language);
+    if cleaned == chars {
+        return false;
+    }
+
+    for (index, run) in block.runs.iter_mut().enumerate() {
+        let text: String = cleaned
+            .iter()
+            .filter(|(_, run)| *run == index)
+            .map(|(c, _)| c)
+            .collect();
+        if *run.text != *text {
+            run.text = Arc::from(text);
+            // Positions no longer match the characters
+            run.char_positions = None;
+        }
+    }
+    true
+}
+
+/// Clean up tagged characters; inserted characters take the tag of the
+/// one before
+fn clean(chars: &[(char, usize)], language: Option<&str>) -> Vec<(char, usize)> {
+    let repeats_hyphen = language.is_some_and(|language| {
+        let primary = language.split(['-', '_']).next().unwrap_or_default();
+        REPEATED_HYPHEN
+            .iter()
+            .any(|code| primary.eq_ignore_ascii_case(code))
+    });
+
+    let mut lines: Vec<Vec<(char, usize)>> = Vec::new();
+    let mut line = Vec::new();
+    let mut previous = None;
+    for &(c, tag) in chars {
+        match c {
+            '\u{00AD}' => {}
+            // `\r\n` is a single break
+            '\n' if previous == Some('\r') => {}
+            '\r' | '\n' | '\u{2028}' | '\u{2029}' => lines.push(std::mem::take(&mut line)),
+            // Spaces collapse, and none starts a line
+            c if c.is_whitespace() => {
+                if line.last().is_some_and(|(c, _)| *c != ' ') {
+                    line.push((' ', tag));
+                }
+            }
+            c => match ligature(c) {
+                Some(spelled) => line.extend(spelled.chars().map(|c| (c, tag))),
+                None => line.push((c, tag)),
+            },
+        }
+        previous = Some(c);
+    }
+    lines.push(line);
+
+    let mut out: Vec<(char, usize)> = Vec::new();
+    let mut blank = false;
+    for mut line in lines {
+        while line.last().is_some_and(|(c, _)| *c == ' ') {
+            line.pop();
+        }
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        let tag = out.last().map_or(line[0].1, |(_, tag)| *tag);
+        if blank {
+            out.extend([('\n', tag), ('\n', tag)]);
+            blank = false;
+        } else if let Some(&(last, _)) = out.last() {
+            let first = line[0].0;
+            let hyphenated = last == '-' && out.len() > 1 && out[out.len() - 2].0.is_alphabetic();
+            if hyphenated {
+                if first == '-' && repeats_hyphen {
+                    line.remove(0);
+                } else if first.is_lowercase() {
+                    out.pop();
+                }
+            } else if starts_item(&line) {
+                out.push(('\n', tag));
+            } else if !(is_unspaced(last) && is_unspaced(first)) {
+                out.push((' ', tag));
+            }
+        }
+        out.extend(line);
+    }
+    out
+}
+
+/// Whether a line starts a list item, such as `• `, `- `, `3. ` or `b) `
+fn starts_item(line: &[(char, usize)]) -> bool {
+    let text: String = line.iter().take(6).map(|(c, _)| c).collect();
+    if text.starts_with(['•', '◦', '▪', '‣', '–', '—', '*', '-']) {
+        return text.chars().nth(1).map_or(true, char::is_whitespace);
+    }
+    let marker: String = text
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    let rest = &text[marker.len()..];
+    !marker.is_empty()
+        && (marker.len() == 1 || marker.chars().all(|c| c.is_ascii_digit()))
+        && (rest.starts_with(". ") || rest.starts_with(") "))
+}
+
+/// Whether `c` belongs to a script written without spaces between words
+fn is_unspaced(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0E00}'..='\u{0E7F}'     // Thai
+            | '\u{3000}'..='\u{30FF}' // CJK punctuation, kana
+            | '\u{3400}'..='\u{4DBF}' // CJK extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+            | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+            | '\u{FF00}'..='\u{FFEF}' // Full-width forms
+    )
+}
+
+/// Letters a typographic ligature stands for
+fn ligature(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{FB00}' => "ff",
+        '\u{FB01}' => "fi",
+        '\u{FB02}' => "fl",
+        '\u{FB03}' => "ffi",
+        '\u{FB04}' => "ffl",
+        '\u{FB05}' | '\u{FB06}' => "st",
+        '\u{0132}' => "IJ",
+        '\u{0133}' => "ij",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Dimensions, Page, Rect, TextRun};
+
+    fn cleaned(text: &str, language: Option<&str>) -> String {
+        let chars: Vec<(char, usize)> = text.chars().map(|c| (c, 0)).collect();
+        clean(&chars, language)
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(
+            cleaned("The infor-\nmation was\r\nhard-wrapped  here.", None),
+            "The information was hard-wrapped here."
+        );
+        assert_eq!(
+            cleaned("\u{FB01}nal e\u{FB03}cient\u{00AD}ly", None),
+            "final efficiently"
+        );
+        assert_eq!(
+            cleaned("COVID-\n19 and Nord-\nAmerika", None),
+            "COVID-19 and Nord-Amerika"
+        );
+        assert_eq!(cleaned("guarda-\n-chuva", Some("pt-BR")), "guarda-chuva");
+        assert_eq!(
+            cleaned("First para\n\n\nSecond\tpara ", None),
+            "First para\n\nSecond para"
+        );
+        assert_eq!(
+            cleaned("Items:\n• one\n2. two", None),
+            "Items:\n• one\n2. two"
+        );
+        assert_eq!(cleaned("日本語の\n文章", Some("ja")), "日本語の文章");
+    }
+
+    #[test]
+    fn test_clean_text() {
+        let mut block = TextBlock::new(Rect::default());
+        block.add_run(TextRun::new("Extraction of infor-\n"));
+        let mut bold = TextRun::new("mation");
+        bold.style.bold = true;
+        bold.char_positions = Some(Vec::new());
+        block.add_run(bold);
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(ContentBlock::Text(block));
+        let mut document = Document::builder().page(page).build();
+
+        assert_eq!(clean_text(&mut document), 1);
+        let ContentBlock::Text(block) = &document.pages[0].content[0] else {
+            panic!("expected a text block");
+        };
+        assert_eq!(&*block.runs[0].text, "Extraction of infor");
+        assert_eq!(&*block.runs[1].text, "mation");
+        assert!(block.runs[1].char_positions.is_some());
+        assert_eq!(clean_text(&mut document), 0);
+    }
+}
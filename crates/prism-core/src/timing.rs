@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Stage Timing
+//!
+//! How long each stage of a parse took: opening the ZIP package, reading
+//! shared strings, parsing each sheet or slide, decoding images. When a
+//! file takes a minute and a half, [`Document::timings`] tells which stage
+//! dominated.
+//!
+//! Parsers run their stages through a [`StageTimer`], which also opens a
+//! `parse_stage` tracing span per stage and enforces
+//! [`ParseOptions::stage_budget`]. Stages cannot be interrupted, so a stage
+//! over budget fails once it is done, with [`Error::StageTimeout`] carrying
+//! the timings recorded until then.
+//!
+//! [`Document::timings`]: crate::document::Document::timings
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::parser::ParseOptions;
+
+/// How long one parse stage took
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageTiming {
+    /// Stage name, e.g. `zip_open` or `sheet:Sales`
+    pub stage: String,
+
+    /// Wall-clock time spent in the stage
+    #[serde(rename = "elapsed_ms", with = "millis")]
+    pub elapsed: Duration,
+}
+
+/// Times parse stages and enforces the per-stage budget
+///
+/// Stages may run on several threads at once, as sheets of a workbook do.
+#[derive(Debug, Default)]
+pub struct StageTimer {
+    budget: Option<Duration>,
+    timings: Mutex<Vec<StageTiming>>,
+}
+
+impl StageTimer {
+    /// A timer enforcing the stage budget of `options`
+    #[must_use]
+    pub fn new(options: &ParseOptions) -> Self {
+        Self {
+            budget: options.stage_budget,
+            timings: Mutex::default(),
+        }
+    }
+
+    /// Run `f` as the stage `stage` and record how long it took
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f`, or [`Error::StageTimeout`] if the stage
+    /// took longer than the budget.
+    pub fn time<T>(&self, stage: impl Into<String>, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let stage = stage.into();
+        let span = tracing::info_span!("parse_stage", stage = %stage);
+        let start = Instant::now();
+        let result = span.in_scope(f);
+        let elapsed = start.elapsed();
+        tracing::debug!("Parse stage {} took {:?}", stage, elapsed);
+
+        let mut timings = self.lock();
+        timings.push(StageTiming {
+            stage: stage.clone(),
+            elapsed,
+        });
+        match self.budget {
+            Some(budget) if elapsed > budget => Err(Error::StageTimeout {
+                stage,
+                elapsed,
+                budget,
+                timings: timings.clone(),
+            }),
+            _ => result,
+        }
+    }
+
+    /// Stages timed so far, in the order they finished
+    #[must_use]
+    pub fn into_timings(self) -> Vec<StageTiming> {
+        self.timings
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StageTiming>> {
+        // A stage that panicked still leaves usable timings behind
+        self.timings
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Durations as fractional milliseconds
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let millis = f64::deserialize(deserializer)?;
+        Ok(Duration::try_from_secs_f64(millis / 1000.0).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_timer() {
+        let timer = StageTimer::default();
+        assert_eq!(timer.time("zip_open", || Ok(1)).unwrap(), 1);
+        assert!(timer
+            .time("sheet:Sales", || Err::<(), _>(Error::corrupt(
+                "XLSX",
+                "bad sheet"
+            )))
+            .is_err());
+        let stages: Vec<_> = timer
+            .into_timings()
+            .into_iter()
+            .map(|timing| timing.stage)
+            .collect();
+        assert_eq!(stages, ["zip_open", "sheet:Sales"]);
+
+        let timer = StageTimer::new(&ParseOptions {
+            stage_budget: Some(Duration::ZERO),
+            ..ParseOptions::default()
+        });
+        let error = timer
+            .time("shared_strings", || {
+                std::thread::sleep(Duration::from_millis(1));
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::StageTimeout { stage, timings, .. }
+                if stage == "shared_strings" && timings.len() == 1
+        ));
+        assert!(error.is_recoverable());
+
+        let json = serde_json::to_value(StageTiming {
+            stage: "zip_open".to_string(),
+            elapsed: Duration::from_micros(1500),
+        })
+        .unwrap();
+        assert_eq!(json["elapsed_ms"], 1.5);
+    }
+}
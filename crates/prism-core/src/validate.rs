@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Document Validation
+//!
+//! Structural checks for documents assembled by hand.
+//!
+//! Parsers produce consistent documents, but a library user building one
+//! with [`DocumentBuilder`](crate::document::DocumentBuilder) can easily
+//! reference an image that was never added or number pages out of order,
+//! which renderers do not expect. [`validate`] reports every such problem at
+//! once instead of stopping at the first.
+
+use std::collections::HashSet;
+use std::fmt;
+use thiserror::Error;
+
+use crate::document::{ContentBlock, Document, Section, TableBlock};
+
+/// A structural problem in a document
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    /// An image block references a resource that is not in the store
+    #[error("page {page}: image references unknown resource '{resource_id}'")]
+    MissingResource {
+        /// Page holding the image
+        page: u32,
+        /// The unresolved reference
+        resource_id: String,
+    },
+
+    /// Two image resources share an identifier
+    #[error("duplicate image resource '{id}'")]
+    DuplicateResource {
+        /// The shared identifier
+        id: String,
+    },
+
+    /// Pages are not numbered 1, 2, 3, ...
+    #[error("page at position {index} is numbered {found}, expected {expected}")]
+    PageNumber {
+        /// Zero-based position in [`Document::pages`]
+        index: usize,
+        /// The number the page should have
+        expected: u32,
+        /// The number it has
+        found: u32,
+    },
+
+    /// A table cell spans no rows or columns
+    #[error("page {page}: table cell at row {row}, cell {cell} has an empty span")]
+    EmptySpan {
+        /// Page holding the table
+        page: u32,
+        /// Zero-based row index
+        row: usize,
+        /// Zero-based cell index within the row
+        cell: usize,
+    },
+
+    /// A table cell extends past the last column or row
+    #[error("page {page}: table cell at row {row}, cell {cell} extends past the table")]
+    SpanOverflow {
+        /// Page holding the table
+        page: u32,
+        /// Zero-based row index
+        row: usize,
+        /// Zero-based cell index within the row
+        cell: usize,
+    },
+
+    /// A heading, outline item, TOC entry or section points at a page that
+    /// does not exist
+    #[error("'{title}' points to page {page}, but the document has {page_count} pages")]
+    PageReference {
+        /// Heading, entry or section title
+        title: String,
+        /// The referenced page
+        page: u32,
+        /// Number of pages in the document
+        page_count: usize,
+    },
+
+    /// A section ends before it starts
+    #[error("section '{title}' ends before it starts")]
+    SectionOrder {
+        /// Section title
+        title: String,
+    },
+
+    /// A heading level outside 1-6
+    #[error("heading '{text}' has level {level}, expected 1 to 6")]
+    HeadingLevel {
+        /// Heading text
+        text: String,
+        /// The level
+        level: u8,
+    },
+}
+
+/// Every problem found by [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl ValidationErrors {
+    /// The individual problems
+    #[must_use]
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.0
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} validation error(s)", self.0.len())?;
+        for error in &self.0 {
+            write!(f, "; {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for crate::error::Error {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::InvalidInput(errors.to_string())
+    }
+}
+
+/// Check that `document` is structurally consistent
+///
+/// Image references must resolve to a resource, resource identifiers must be
+/// unique, pages must be numbered consecutively from 1, table cell spans must
+/// be at least 1 and stay within the table, and headings, outline items,
+/// TOC entries and sections must point at existing pages.
+///
+/// # Errors
+///
+/// Returns every problem found.
+pub fn validate(document: &Document) -> Result<(), ValidationErrors> {
+    let mut errors = Vec::new();
+
+    let mut ids = HashSet::new();
+    for image in &document.resources.images {
+        if !ids.insert(image.id.as_str()) {
+            errors.push(ValidationError::DuplicateResource {
+                id: image.id.clone(),
+            });
+        }
+    }
+
+    for (index, page) in document.pages.iter().enumerate() {
+        let expected = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        if page.number != expected {
+            errors.push(ValidationError::PageNumber {
+                index,
+                expected,
+                found: page.number,
+            });
+        }
+        check_blocks(&page.content, page.number, &ids, &mut errors);
+    }
+
+    check_references(document, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationErrors(errors))
+    }
+}
+
+fn check_blocks(
+    blocks: &[ContentBlock],
+    page: u32,
+    ids: &HashSet<&str>,
+    errors: &mut Vec<ValidationError>,
+) {
+    for block in blocks {
+        match block {
+            ContentBlock::Image(image) => {
+                if !ids.contains(image.resource_id.as_str()) {
+                    errors.push(ValidationError::MissingResource {
+                        page,
+                        resource_id: image.resource_id.clone(),
+                    });
+                }
+            }
+            ContentBlock::Table(table) => {
+                check_spans(table, page, errors);
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        check_blocks(&cell.content, page, ids, errors);
+                    }
+                }
+            }
+            ContentBlock::List(list) => {
+                for item in &list.items {
+                    check_blocks(&item.content, page, ids, errors);
+                }
+            }
+            ContentBlock::Container(container) => {
+                check_blocks(&container.children, page, ids, errors);
+            }
+            ContentBlock::Text(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+        }
+    }
+}
+
+fn check_spans(table: &TableBlock, page: u32, errors: &mut Vec<ValidationError>) {
+    let row_count = table.rows.len();
+    for (row, cells) in table.rows.iter().enumerate() {
+        let mut column = 0;
+        for (cell, span) in cells.cells.iter().enumerate() {
+            if span.col_span == 0 || span.row_span == 0 {
+                errors.push(ValidationError::EmptySpan { page, row, cell });
+                continue;
+            }
+            column += span.col_span;
+            if column > table.column_count || row + span.row_span > row_count {
+                errors.push(ValidationError::SpanOverflow { page, row, cell });
+            }
+        }
+    }
+}
+
+fn check_references(document: &Document, errors: &mut Vec<ValidationError>) {
+    let page_count = document.pages.len();
+    let exists = |page: u32| page >= 1 && usize::try_from(page).is_ok_and(|p| p <= page_count);
+    let mut reference = |title: &str, page: u32| {
+        if !exists(page) {
+            errors.push(ValidationError::PageReference {
+                title: title.to_string(),
+                page,
+                page_count,
+            });
+        }
+    };
+
+    let structure = &document.structure;
+    for heading in &structure.headings {
+        reference(&heading.text, heading.page);
+    }
+    for entry in &structure.toc {
+        reference(&entry.title, entry.page);
+    }
+    let mut outline: Vec<_> = structure.outline.iter().collect();
+    while let Some(item) = outline.pop() {
+        reference(&item.title, item.page);
+        outline.extend(&item.children);
+    }
+    let mut sections: Vec<_> = structure.sections.iter().collect();
+    while let Some(section) = sections.pop() {
+        reference(&section.title, section.start.page);
+        reference(&section.title, section.end.page);
+        sections.extend(&section.children);
+    }
+    for section in &structure.sections {
+        check_order(section, errors);
+    }
+
+    for heading in &structure.headings {
+        if !(1..=6).contains(&heading.level) {
+            errors.push(ValidationError::HeadingLevel {
+                text: heading.text.clone(),
+                level: heading.level,
+            });
+        }
+    }
+}
+
+fn check_order(section: &Section, errors: &mut Vec<ValidationError>) {
+    if section.end < section.start {
+        errors.push(ValidationError::SectionOrder {
+            title: section.title.clone(),
+        });
+    }
+    for child in &section.children {
+        check_order(child, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{
+        ContentPosition, Dimensions, ImageBlock, Page, Rect, SectionKind, ShapeStyle, TableCell,
+        TableRow,
+    };
+
+    fn image(resource_id: &str) -> ContentBlock {
+        ContentBlock::Image(ImageBlock {
+            bounds: Rect::default(),
+            resource_id: resource_id.to_string(),
+            alt_text: None,
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        })
+    }
+
+    fn cell(col_span: usize, row_span: usize) -> TableCell {
+        TableCell {
+            content: Vec::new(),
+            col_span,
+            row_span,
+            background_color: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let document = Document::builder()
+            .add_text_page("Intro", "First paragraph.\n\nSecond paragraph.")
+            .add_text_page("Details", "More text.")
+            .heading(2, "Background", 1)
+            .build();
+        assert_eq!(validate(&document), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut first = Page::new(1, Dimensions::LETTER);
+        first.add_content(image("missing"));
+        let mut table = TableBlock::new(Rect::default(), 2);
+        table.add_row(TableRow {
+            cells: vec![cell(2, 1), cell(1, 1)],
+            height: None,
+        });
+        table.add_row(TableRow {
+            cells: vec![cell(0, 1), cell(1, 2)],
+            height: None,
+        });
+        first.add_content(ContentBlock::Table(table));
+
+        let document = Document::builder()
+            .page(first)
+            .page(Page::new(3, Dimensions::LETTER))
+            .heading(7, "Deep", 5)
+            .section(Section::new(
+                SectionKind::Chapter,
+                "Backwards",
+                ContentPosition::new(2, 0),
+                ContentPosition::new(1, 1),
+            ))
+            .build();
+        let errors = validate(&document).unwrap_err();
+        assert_eq!(
+            errors.errors(),
+            [
+                ValidationError::MissingResource {
+                    page: 1,
+                    resource_id: "missing".to_string(),
+                },
+                ValidationError::SpanOverflow {
+                    page: 1,
+                    row: 0,
+                    cell: 1,
+                },
+                ValidationError::EmptySpan {
+                    page: 1,
+                    row: 1,
+                    cell: 0,
+                },
+                ValidationError::SpanOverflow {
+                    page: 1,
+                    row: 1,
+                    cell: 1,
+                },
+                ValidationError::PageNumber {
+                    index: 1,
+                    expected: 2,
+                    found: 3,
+                },
+                ValidationError::PageReference {
+                    title: "Deep".to_string(),
+                    page: 5,
+                    page_count: 2,
+                },
+                ValidationError::SectionOrder {
+                    title: "Backwards".to_string(),
+                },
+                ValidationError::HeadingLevel {
+                    text: "Deep".to_string(),
+                    level: 7,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,12 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Generates the gRPC service and messages from `proto/`.
+//!
+//! A vendored `protoc` is used unless `PROTOC` names another one.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_build::configure().compile_protos(&["proto/prism/v1/prism.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Conversions from core types to their protobuf messages
+
+use prism_core::diagnostics::{Diagnostic, Severity};
+use prism_core::format::{DetectionResult, Format};
+use prism_core::progress::ProgressEvent;
+use prism_core::Error;
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Status};
+
+use crate::proto::{self, diagnostic, progress};
+use crate::service::ERROR_CODE_METADATA;
+
+impl From<&Format> for proto::Format {
+    fn from(format: &Format) -> Self {
+        Self {
+            mime_type: format.mime_type.clone(),
+            extension: format.extension.clone(),
+            name: format.name.clone(),
+            family: format!("{:?}", format.family),
+            is_container: format.is_container,
+        }
+    }
+}
+
+impl From<&DetectionResult> for proto::DetectResponse {
+    fn from(detection: &DetectionResult) -> Self {
+        Self {
+            format: Some((&detection.format).into()),
+            confidence: detection.confidence,
+            method: format!("{:?}", detection.method),
+        }
+    }
+}
+
+impl From<&Diagnostic> for proto::Diagnostic {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        let severity = match diagnostic.severity {
+            Severity::Warning => diagnostic::Severity::Warning,
+            Severity::Error => diagnostic::Severity::Error,
+        };
+        Self {
+            severity: severity.into(),
+            code: diagnostic.code.to_string(),
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+impl From<&ProgressEvent> for proto::Progress {
+    fn from(event: &ProgressEvent) -> Self {
+        let kind = match event {
+            ProgressEvent::StageChanged { stage } => {
+                progress::Kind::StageChanged(proto::StageChanged {
+                    stage: stage.clone(),
+                })
+            }
+            ProgressEvent::BytesRead { read, total } => {
+                progress::Kind::BytesRead(proto::BytesRead {
+                    read: *read,
+                    total: *total,
+                })
+            }
+            ProgressEvent::FormatDetected {
+                mime_type,
+                name,
+                confidence,
+            } => progress::Kind::FormatDetected(proto::FormatDetected {
+                mime_type: mime_type.clone(),
+                name: name.clone(),
+                confidence: *confidence,
+            }),
+            ProgressEvent::PageParsed { parsed, total } => {
+                progress::Kind::PageParsed(proto::PageParsed {
+                    parsed: count(*parsed),
+                    total: total.map(count),
+                })
+            }
+            ProgressEvent::RenderStarted { pages } => {
+                progress::Kind::RenderStarted(proto::RenderStarted {
+                    pages: count(*pages),
+                })
+            }
+            ProgressEvent::PageRendered { rendered, total } => {
+                progress::Kind::PageRendered(proto::PageRendered {
+                    rendered: count(*rendered),
+                    total: total.map(count),
+                })
+            }
+            ProgressEvent::RenderFinished { bytes } => {
+                progress::Kind::RenderFinished(proto::RenderFinished {
+                    bytes: count(*bytes),
+                })
+            }
+            ProgressEvent::Warning { diagnostic } => progress::Kind::Warning(diagnostic.into()),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+/// Status reporting `error`, with its Prism error code in the
+/// [`ERROR_CODE_METADATA`] trailer
+pub(crate) fn error_status(error: &Error) -> Status {
+    use prism_core::ErrorCode;
+
+    let error_code = error.code();
+    let code = match error_code {
+        ErrorCode::DetectionFailed
+        | ErrorCode::InvalidInput
+        | ErrorCode::ParseError
+        | ErrorCode::CorruptFile => Code::InvalidArgument,
+        ErrorCode::UnsupportedFormat | ErrorCode::UnsupportedFeature => Code::Unimplemented,
+        ErrorCode::Encrypted | ErrorCode::ActiveContent => Code::FailedPrecondition,
+        ErrorCode::ResourceLimit | ErrorCode::MemoryLimit => Code::ResourceExhausted,
+        ErrorCode::Timeout => Code::DeadlineExceeded,
+        ErrorCode::NotFound => Code::NotFound,
+        ErrorCode::RenderError
+        | ErrorCode::Io
+        | ErrorCode::Sandbox
+        | ErrorCode::Config
+        | ErrorCode::Internal => Code::Internal,
+    };
+
+    let mut metadata = MetadataMap::new();
+    if let Ok(value) = error_code.to_string().parse() {
+        metadata.insert(ERROR_CODE_METADATA, value);
+    }
+    Status::with_metadata(code, error.to_string(), metadata)
+}
+
+fn count(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_status() {
+        let status = error_status(&Error::ParseError("bad xref table".to_string()));
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(status.message().contains("bad xref table"));
+        assert_eq!(status.metadata().get(ERROR_CODE_METADATA).unwrap(), "E1200");
+    }
+
+    #[test]
+    fn test_progress_conversion() {
+        let progress = proto::Progress::from(&ProgressEvent::PageParsed {
+            parsed: 2,
+            total: None,
+        });
+        assert_eq!(
+            progress.kind,
+            Some(progress::Kind::PageParsed(proto::PageParsed {
+                parsed: 2,
+                total: None,
+            }))
+        );
+    }
+}
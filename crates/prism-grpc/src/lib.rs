@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Prism gRPC
+//!
+//! gRPC API for Prism document processing, for services that would rather
+//! call a typed RPC with a deadline than post multipart forms. The service
+//! is defined in `proto/prism/v1/prism.proto`:
+//!
+//! - `Detect` identifies the format of a document
+//! - `Convert` renders a document as HTML, JSON Lines, text or JSON
+//! - `ExtractText` returns the plain text of a document
+//! - `ConvertJob` converts like `Convert`, streaming progress events
+//!   before the result
+//!
+//! Client deadlines (`grpc-timeout`) bound the work done for a call: once
+//! the deadline passes the conversion is dropped and the call fails with
+//! `DEADLINE_EXCEEDED`.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use prism_grpc::PrismService;
+//! use tonic::transport::Server;
+//!
+//! # async fn serve() -> Result<(), Box<dyn std::error::Error>> {
+//! Server::builder()
+//!     .add_service(PrismService::default().into_server())
+//!     .serve("127.0.0.1:50051".parse()?)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+// tonic::Status is large, and the generated service returns it
+#![allow(clippy::result_large_err)]
+
+mod convert;
+mod service;
+
+pub use service::{PrismService, DEFAULT_MAX_MESSAGE_SIZE, ERROR_CODE_METADATA};
+
+/// Messages and service stubs generated from `prism.proto`
+#[allow(missing_docs, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("prism.v1");
+}
+
+/// Prism gRPC version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
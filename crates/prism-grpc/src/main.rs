@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Prism gRPC Server
+//!
+//! Serves the `prism.v1.Prism` gRPC service on the address in
+//! `PRISM_GRPC_ADDR` (default `127.0.0.1:50051`).
+
+use anyhow::Context;
+use prism_core::license::{LicenseFeature, LicenseManager, LicenseStatus};
+use prism_grpc::PrismService;
+use std::net::SocketAddr;
+use tonic::transport::Server;
+use tracing::{info, warn, Level};
+
+/// Environment variable naming the address to listen on
+const ADDR_ENV: &str = "PRISM_GRPC_ADDR";
+
+/// Address listened on unless `PRISM_GRPC_ADDR` is set
+const DEFAULT_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 50051);
+
+/// Load the license and make sure it allows running a server
+fn load_license() -> anyhow::Result<LicenseManager> {
+    let license = LicenseManager::from_env()?;
+
+    info!("License: {}", license.license_type());
+    match license.status() {
+        LicenseStatus::Valid => {}
+        LicenseStatus::GracePeriod { days_remaining } => {
+            warn!(
+                "License has expired; features will be disabled in {} day(s)",
+                days_remaining
+            );
+        }
+        LicenseStatus::Expired => {
+            warn!("License has expired; falling back to community features");
+        }
+    }
+
+    license.require(LicenseFeature::Server)?;
+    Ok(license)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .with_target(false)
+        .init();
+
+    info!("Starting Prism gRPC Server v{}", prism_grpc::VERSION);
+    load_license()?;
+
+    let addr = match std::env::var(ADDR_ENV) {
+        Ok(addr) => addr
+            .parse()
+            .with_context(|| format!("Invalid {ADDR_ENV}: {addr}"))?,
+        Err(_) => SocketAddr::from(DEFAULT_ADDR),
+    };
+    info!("gRPC server listening on {}", addr);
+
+    Server::builder()
+        .add_service(PrismService::default().into_server())
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,410 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! The `prism.v1.Prism` service
+
+use bytes::Bytes;
+use prism_core::pipeline::{Pipeline, PipelineConfig};
+use prism_core::progress::{ProgressEvent, ProgressSink};
+use prism_parsers::ParserRegistry;
+use prism_render::html::HtmlRenderer;
+use prism_render::jsonl::{JsonlRenderer, JSONL_MIME_TYPE};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::convert::error_status;
+use crate::proto::prism_server::{Prism, PrismServer};
+use crate::proto::{
+    job_event, ConvertRequest, ConvertResponse, DetectRequest, DetectResponse, ExtractTextRequest,
+    ExtractTextResponse, JobEvent, OutputFormat,
+};
+
+/// Largest request or response message, in bytes, unless configured
+/// otherwise
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Trailer carrying the Prism error code of a failed call, such as
+/// `E1200`
+pub const ERROR_CODE_METADATA: &str = "prism-error-code";
+
+/// Request header in which clients send their deadline
+const TIMEOUT_METADATA: &str = "grpc-timeout";
+
+/// Progress events buffered per job; a client reading more slowly misses
+/// events, never the result
+const JOB_EVENT_BUFFER: usize = 64;
+
+/// Implementation of the `prism.v1.Prism` service
+#[derive(Debug, Clone)]
+pub struct PrismService {
+    /// Pipeline without a renderer; requests add the one they need
+    pipeline: Pipeline,
+    max_message_size: usize,
+}
+
+impl Default for PrismService {
+    fn default() -> Self {
+        Self::new(ParserRegistry::with_default_parsers())
+    }
+}
+
+impl PrismService {
+    /// Create a service converting with the parsers in `registry`
+    #[must_use]
+    pub fn new(registry: ParserRegistry) -> Self {
+        Self {
+            pipeline: Pipeline::new(Arc::new(registry)),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Set the pipeline configuration requests start from
+    #[must_use]
+    pub fn with_config(mut self, config: PipelineConfig) -> Self {
+        self.pipeline = self.pipeline.with_config(config);
+        self
+    }
+
+    /// Set the largest message accepted or sent, in bytes
+    #[must_use]
+    pub fn with_max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Wrap the service for adding to a tonic server
+    #[must_use]
+    pub fn into_server(self) -> PrismServer<Self> {
+        let size = self.max_message_size;
+        PrismServer::new(self)
+            .max_decoding_message_size(size)
+            .max_encoding_message_size(size)
+    }
+
+    /// Run a conversion, reporting its progress to `progress`
+    async fn run_convert(
+        &self,
+        request: ConvertRequest,
+        progress: Option<Arc<dyn ProgressSink>>,
+    ) -> Result<ConvertResponse, Status> {
+        let output = request.output();
+        let mut config = self.pipeline.config().clone();
+        if let Some(options) = &request.options {
+            if let Some(lenient) = options.lenient {
+                config.parse.lenient = lenient;
+            }
+            if let Some(pages) = &options.pages {
+                config.render.page_range = Some(pages.parse().map_err(|e| error_status(&e))?);
+            }
+        }
+
+        let mut pipeline = self.pipeline.clone().with_config(config);
+        pipeline = match output {
+            OutputFormat::Unspecified | OutputFormat::Html => {
+                pipeline.with_renderer(Arc::new(HtmlRenderer::new()))
+            }
+            OutputFormat::Jsonl => pipeline.with_renderer(Arc::new(JsonlRenderer::new())),
+            OutputFormat::Text | OutputFormat::Json => pipeline,
+        };
+        if let Some(progress) = progress {
+            pipeline = pipeline.with_progress(progress);
+        }
+
+        let result = pipeline
+            .run(Bytes::from(request.data), request.filename.as_deref())
+            .await
+            .map_err(|e| error_status(&e))?;
+        let document = &result.document;
+        let (content, content_type) = match output {
+            OutputFormat::Unspecified | OutputFormat::Html => (
+                result.rendered.clone().unwrap_or_default().to_vec(),
+                "text/html; charset=utf-8",
+            ),
+            OutputFormat::Jsonl => (
+                result.rendered.clone().unwrap_or_default().to_vec(),
+                JSONL_MIME_TYPE,
+            ),
+            OutputFormat::Text => (
+                document.extract_text().into_bytes(),
+                "text/plain; charset=utf-8",
+            ),
+            OutputFormat::Json => (
+                serde_json::to_vec(document).map_err(|e| Status::internal(e.to_string()))?,
+                "application/json",
+            ),
+        };
+
+        Ok(ConvertResponse {
+            content,
+            content_type: content_type.to_string(),
+            document_id: document.id.to_string(),
+            format: Some((&result.detection.format).into()),
+            diagnostics: document.diagnostics.iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Prism for PrismService {
+    async fn detect(
+        &self,
+        request: Request<DetectRequest>,
+    ) -> Result<Response<DetectResponse>, Status> {
+        let request = request.into_inner();
+        let detection = self
+            .pipeline
+            .detect(&request.data, request.filename.as_deref())
+            .ok_or_else(|| Status::invalid_argument("Could not detect the document format"))?;
+        Ok(Response::new((&detection).into()))
+    }
+
+    async fn convert(
+        &self,
+        request: Request<ConvertRequest>,
+    ) -> Result<Response<ConvertResponse>, Status> {
+        let deadline = deadline(&request);
+        let request = request.into_inner();
+        info!(
+            "Converting {:?}, {} bytes",
+            request.filename,
+            request.data.len()
+        );
+        within(deadline, self.run_convert(request, None))
+            .await
+            .map(Response::new)
+    }
+
+    async fn extract_text(
+        &self,
+        request: Request<ExtractTextRequest>,
+    ) -> Result<Response<ExtractTextResponse>, Status> {
+        let deadline = deadline(&request);
+        let request = request.into_inner();
+        let output = within(deadline, async {
+            self.pipeline
+                .run(Bytes::from(request.data), request.filename.as_deref())
+                .await
+                .map_err(|e| error_status(&e))
+        })
+        .await?;
+
+        let document = &output.document;
+        Ok(Response::new(ExtractTextResponse {
+            text: document.extract_text(),
+            page_count: u32::try_from(document.page_count()).unwrap_or(u32::MAX),
+            diagnostics: document.diagnostics.iter().map(Into::into).collect(),
+        }))
+    }
+
+    type ConvertJobStream = ReceiverStream<Result<JobEvent, Status>>;
+
+    async fn convert_job(
+        &self,
+        request: Request<ConvertRequest>,
+    ) -> Result<Response<Self::ConvertJobStream>, Status> {
+        let deadline = deadline(&request);
+        let request = request.into_inner();
+        let job_id = Uuid::new_v4().to_string();
+        let (sender, receiver) = mpsc::channel(JOB_EVENT_BUFFER);
+        info!("Job {} converting {:?}", job_id, request.filename);
+
+        let progress = Arc::new(JobProgress {
+            job_id: job_id.clone(),
+            sender: sender.clone(),
+        });
+        let service = self.clone();
+        tokio::spawn(async move {
+            let work = within(deadline, service.run_convert(request, Some(progress)));
+            let result = tokio::select! {
+                result = work => result,
+                // Nobody is waiting for the result any more
+                () = sender.closed() => {
+                    debug!("Job {} abandoned by its client", job_id);
+                    return;
+                }
+            };
+            let event = result.map(|response| JobEvent {
+                job_id,
+                event: Some(job_event::Event::Result(response)),
+            });
+            let _ = sender.send(event).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Forwards the progress of a job to its stream
+#[derive(Debug)]
+struct JobProgress {
+    job_id: String,
+    sender: mpsc::Sender<Result<JobEvent, Status>>,
+}
+
+impl ProgressSink for JobProgress {
+    fn report(&self, event: ProgressEvent) {
+        // Dropped when the client falls behind
+        let _ = self.sender.try_send(Ok(JobEvent {
+            job_id: self.job_id.clone(),
+            event: Some(job_event::Event::Progress((&event).into())),
+        }));
+    }
+}
+
+/// Time left until the deadline the client sent with `request`
+fn deadline<T>(request: &Request<T>) -> Option<Duration> {
+    let value = request.metadata().get(TIMEOUT_METADATA)?.to_str().ok()?;
+    let (amount, unit) = value.split_at(value.len().checked_sub(1)?);
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount.saturating_mul(3600)),
+        "M" => Duration::from_secs(amount.saturating_mul(60)),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+/// Run `work`, giving up once `deadline` has passed
+async fn within<T>(
+    deadline: Option<Duration>,
+    work: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, work)
+            .await
+            .unwrap_or_else(|_| Err(Status::deadline_exceeded("Deadline exceeded"))),
+        None => work.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::ConvertOptions;
+    use tokio_stream::StreamExt;
+    use tonic::Code;
+
+    fn convert_request(output: OutputFormat) -> ConvertRequest {
+        ConvertRequest {
+            data: b"Hello over gRPC".to_vec(),
+            filename: Some("hello.txt".to_string()),
+            output: output.into(),
+            options: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect() {
+        let service = PrismService::default();
+        let response = service
+            .detect(Request::new(DetectRequest {
+                data: b"%PDF-1.7\n".to_vec(),
+                filename: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.format.unwrap().mime_type, "application/pdf");
+        assert!(response.confidence > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_convert() {
+        let service = PrismService::default();
+        let response = service
+            .convert(Request::new(convert_request(OutputFormat::Text)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.content, b"Hello over gRPC");
+        assert_eq!(response.content_type, "text/plain; charset=utf-8");
+        assert_eq!(response.format.unwrap().mime_type, "text/plain");
+
+        let html = service
+            .convert(Request::new(convert_request(OutputFormat::Unspecified)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(String::from_utf8(html.content)
+            .unwrap()
+            .contains("Hello over gRPC"));
+
+        let mut request = convert_request(OutputFormat::Html);
+        request.options = Some(ConvertOptions {
+            pages: Some("3-1".to_string()),
+            lenient: None,
+        });
+        let status = service.convert(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_extract_text() {
+        let response = PrismService::default()
+            .extract_text(Request::new(ExtractTextRequest {
+                data: b"Plain words".to_vec(),
+                filename: Some("words.txt".to_string()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.text, "Plain words");
+        assert_eq!(response.page_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_convert_job() {
+        let stream = PrismService::default()
+            .convert_job(Request::new(convert_request(OutputFormat::Html)))
+            .await
+            .unwrap()
+            .into_inner();
+        let events: Vec<JobEvent> = stream.map(Result::unwrap).collect().await;
+
+        let (last, progress) = events.split_last().unwrap();
+        assert!(!progress.is_empty());
+        assert!(events.iter().all(|event| event.job_id == last.job_id));
+        assert!(progress
+            .iter()
+            .all(|event| matches!(event.event, Some(job_event::Event::Progress(_)))));
+        assert!(matches!(
+            &last.event,
+            Some(job_event::Event::Result(result)) if !result.content.is_empty()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let mut request = Request::new(());
+        assert_eq!(deadline(&request), None);
+        for (value, expected) in [
+            ("2S", Duration::from_secs(2)),
+            ("150m", Duration::from_millis(150)),
+            ("1H", Duration::from_secs(3600)),
+        ] {
+            request
+                .metadata_mut()
+                .insert(TIMEOUT_METADATA, value.parse().unwrap());
+            assert_eq!(deadline(&request), Some(expected));
+        }
+        request
+            .metadata_mut()
+            .insert(TIMEOUT_METADATA, "soon".parse().unwrap());
+        assert_eq!(deadline(&request), None);
+
+        let status = within(Some(Duration::from_millis(10)), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Parse throughput per format.
+//!
+//! Every input is generated here, in three sizes, so the numbers do not
+//! depend on sample files. Run with `cargo bench -p prism-parsers`; pass a
+//! filter such as `cargo bench -p prism-parsers -- xlsx/large` to run one
+//! case.
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use prism_core::format::Format;
+use prism_core::parser::{ParseContext, ParseOptions, Parser};
+use prism_parsers::{
+    CsvParser, DocxParser, EmlParser, HtmlParser, JsonParser, MarkdownParser, TextParser,
+    XlsxParser,
+};
+use std::fmt::Write as _;
+use std::io::{Cursor, Write as _};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Fixture sizes, as the number of paragraphs, rows or records
+const SIZES: &[(&str, usize)] = &[("small", 20), ("medium", 500), ("large", 5_000)];
+
+const SENTENCE: &str = "The quick brown fox jumps over the lazy dog & keeps running.";
+
+fn text(units: usize) -> Vec<u8> {
+    (0..units)
+        .map(|i| format!("Paragraph {i}: {SENTENCE}\n\n"))
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn csv(units: usize) -> Vec<u8> {
+    let mut out = String::from("id,name,amount,paid\n");
+    for i in 0..units {
+        let _ = writeln!(out, "{i},Item {i},{}.50,{}", i * 3, i % 2 == 0);
+    }
+    out.into_bytes()
+}
+
+fn markdown(units: usize) -> Vec<u8> {
+    let mut out = String::from("# Report\n\n");
+    for i in 0..units {
+        let _ = write!(
+            out,
+            "## Section {i}\n\n{SENTENCE} *Emphasis* and `code`.\n\n- first\n- second\n\n"
+        );
+    }
+    out.into_bytes()
+}
+
+fn html(units: usize) -> Vec<u8> {
+    let mut out = String::from("<!DOCTYPE html><html><head><title>Report</title></head><body>");
+    for i in 0..units {
+        let _ = write!(
+            out,
+            "<h2>Section {i}</h2><p>{SENTENCE} <b>Bold</b> <i>italic</i>.</p>\
+             <table><tr><td>{i}</td><td>Item</td></tr></table>"
+        );
+    }
+    out.push_str("</body></html>");
+    out.into_bytes()
+}
+
+fn json(units: usize) -> Vec<u8> {
+    let records: Vec<_> = (0..units)
+        .map(|i| {
+            serde_json::json!({
+                "id": i,
+                "name": format!("Item {i}"),
+                "tags": ["a", "b"],
+                "price": { "amount": i * 3, "currency": "EUR" },
+            })
+        })
+        .collect();
+    serde_json::to_vec_pretty(&serde_json::json!({ "records": records })).unwrap()
+}
+
+fn eml(units: usize) -> Vec<u8> {
+    let mut out = String::from(
+        "From: Alice <alice@example.com>\r\nTo: Bob <bob@example.com>\r\n\
+         Subject: Quarterly report\r\nDate: Wed, 31 Jan 2024 09:00:00 +0000\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+    );
+    for i in 0..units {
+        let _ = write!(out, "Line {i}: {SENTENCE}\r\n");
+    }
+    out.into_bytes()
+}
+
+fn zip(entries: &[(&str, String)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn docx(units: usize) -> Vec<u8> {
+    let mut body = String::new();
+    for i in 0..units {
+        let _ = write!(
+            body,
+            "<w:p><w:pPr><w:pStyle w:val=\"Normal\"/></w:pPr>\
+             <w:r><w:rPr><w:b/></w:rPr><w:t>Paragraph {i}: </w:t></w:r>\
+             <w:r><w:t>{}</w:t></w:r></w:p>",
+            SENTENCE.replace('&', "&amp;")
+        );
+    }
+    zip(&[
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#.to_string(),
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "word/document.xml",
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{body}</w:body></w:document>"#
+            ),
+        ),
+    ])
+}
+
+fn xlsx(units: usize) -> Vec<u8> {
+    let mut rows = String::new();
+    for row in 1..=units {
+        let _ = write!(
+            rows,
+            r#"<row r="{row}"><c r="A{row}" t="inlineStr"><is><t>Item {}</t></is></c><c r="B{row}" t="inlineStr"><is><t>Open</t></is></c><c r="C{row}"><v>{}</v></c><c r="D{row}"><v>{}.25</v></c></row>"#,
+            row % 50,
+            row * 10,
+            row * 3
+        );
+    }
+    zip(&[
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Data" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_string(),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/worksheets/sheet1.xml",
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{rows}</sheetData></worksheet>"#
+            ),
+        ),
+    ])
+}
+
+/// A parser under benchmark and its fixture generator
+struct Case {
+    name: &'static str,
+    parser: Box<dyn Parser>,
+    format: Format,
+    generate: fn(usize) -> Vec<u8>,
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case {
+            name: "text",
+            parser: Box::new(TextParser::new()),
+            format: Format::text(),
+            generate: text,
+        },
+        Case {
+            name: "csv",
+            parser: Box::new(CsvParser::new()),
+            format: Format::csv(),
+            generate: csv,
+        },
+        Case {
+            name: "markdown",
+            parser: Box::new(MarkdownParser::new()),
+            format: Format::markdown(),
+            generate: markdown,
+        },
+        Case {
+            name: "html",
+            parser: Box::new(HtmlParser::new()),
+            format: Format::html(),
+            generate: html,
+        },
+        Case {
+            name: "json",
+            parser: Box::new(JsonParser::new()),
+            format: Format::json(),
+            generate: json,
+        },
+        Case {
+            name: "eml",
+            parser: Box::new(EmlParser::new()),
+            format: Format::eml(),
+            generate: eml,
+        },
+        Case {
+            name: "docx",
+            parser: Box::new(DocxParser::new()),
+            format: Format::docx(),
+            generate: docx,
+        },
+        Case {
+            name: "xlsx",
+            parser: Box::new(XlsxParser::new()),
+            format: Format::xlsx(),
+            generate: xlsx,
+        },
+    ]
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    for case in cases() {
+        let mut group = c.benchmark_group(case.name);
+        for &(size, units) in SIZES {
+            let data = Bytes::from((case.generate)(units));
+            let context = ParseContext {
+                format: case.format.clone(),
+                filename: Some(format!("bench.{}", case.format.extension)),
+                size: data.len(),
+                options: ParseOptions::default(),
+                progress: None,
+            };
+            // Fail fast on a fixture the parser rejects
+            runtime
+                .block_on(case.parser.parse(data.clone(), context.clone()))
+                .unwrap_or_else(|e| panic!("{}/{size}: {e}", case.name));
+
+            group.throughput(Throughput::Bytes(data.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+                b.iter(|| {
+                    runtime
+                        .block_on(case.parser.parse(data.clone(), context.clone()))
+                        .unwrap()
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Microsoft cabinet files, as embedded in MSI packages
+//!
+//! Only what extracting one file takes is read: the folders, the file
+//! table and the data blocks of the folder holding the file. Blocks stored
+//! as is and MSZIP blocks are supported; Quantum and LZX compression are
+//! not, nor are files continued from or into another cabinet.
+
+use flate2::read::DeflateDecoder;
+use prism_core::error::{Error, Result};
+use std::io::Read;
+
+/// Size of the history MSZIP blocks refer back into
+const MSZIP_WINDOW: usize = 32 * 1024;
+
+/// Largest number of bytes a data block decompresses to
+const MAX_BLOCK_SIZE: usize = 32 * 1024 + 6144;
+
+/// A file of a cabinet
+pub(crate) struct CabinetFile {
+    pub name: String,
+    pub size: u32,
+    /// Offset within the uncompressed data of its folder
+    offset: u32,
+    folder: u16,
+}
+
+struct Folder {
+    /// Offset of the first data block
+    data: usize,
+    blocks: u16,
+    compression: u16,
+}
+
+/// A cabinet read into its folders and files
+pub(crate) struct Cabinet<'a> {
+    data: &'a [u8],
+    /// Reserved bytes at the start of each data block
+    block_reserve: usize,
+    folders: Vec<Folder>,
+    pub files: Vec<CabinetFile>,
+}
+
+impl<'a> Cabinet<'a> {
+    /// Read the header, folders and file table of a cabinet
+    pub fn open(data: &'a [u8]) -> Result<Self> {
+        if !data.starts_with(b"MSCF") || data.len() < 36 {
+            return Err(corrupt("not a cabinet file"));
+        }
+        let files_offset = usize::try_from(u32_le(data, 16)).unwrap_or(usize::MAX);
+        let folder_count = u16_le(data, 26);
+        let file_count = u16_le(data, 28);
+        let flags = u16_le(data, 30);
+
+        let mut position = 36;
+        let (mut folder_reserve, mut block_reserve) = (0, 0);
+        if flags & 0x0004 != 0 {
+            let header_reserve = usize::from(u16_le(data, 36));
+            folder_reserve = usize::from(*data.get(38).ok_or_else(|| corrupt("truncated header"))?);
+            block_reserve = usize::from(*data.get(39).ok_or_else(|| corrupt("truncated header"))?);
+            position = 40 + header_reserve;
+        }
+        // Names of the previous and next cabinet and disk
+        let strings = usize::from(flags & 0x0001 != 0) * 2 + usize::from(flags & 0x0002 != 0) * 2;
+        for _ in 0..strings {
+            position = c_string(data, position)?.1;
+        }
+
+        let mut folders = Vec::with_capacity(usize::from(folder_count));
+        for _ in 0..folder_count {
+            let folder = data
+                .get(position..position + 8)
+                .ok_or_else(|| corrupt("truncated folder table"))?;
+            folders.push(Folder {
+                data: usize::try_from(u32_le(folder, 0)).unwrap_or(usize::MAX),
+                blocks: u16_le(folder, 4),
+                compression: u16_le(folder, 6),
+            });
+            position += 8 + folder_reserve;
+        }
+
+        let mut files = Vec::with_capacity(usize::from(file_count));
+        let mut position = files_offset;
+        for _ in 0..file_count {
+            let file = data
+                .get(position..position + 16)
+                .ok_or_else(|| corrupt("truncated file table"))?;
+            let (name, next) = c_string(data, position + 16)?;
+            files.push(CabinetFile {
+                name,
+                size: u32_le(file, 0),
+                offset: u32_le(file, 4),
+                folder: u16_le(file, 8),
+            });
+            position = next;
+        }
+
+        Ok(Self {
+            data,
+            block_reserve,
+            folders,
+            files,
+        })
+    }
+
+    /// The contents of `file`, decompressing its folder up to its end
+    pub fn extract(&self, file: &CabinetFile) -> Result<Vec<u8>> {
+        let folder = self.folders.get(usize::from(file.folder)).ok_or_else(|| {
+            Error::UnsupportedFormat(format!("'{}' continues in another cabinet", file.name))
+        })?;
+        let start = usize::try_from(file.offset).unwrap_or(usize::MAX);
+        let end = start.saturating_add(usize::try_from(file.size).unwrap_or(usize::MAX));
+
+        let mut uncompressed = Vec::new();
+        let mut position = folder.data;
+        for _ in 0..folder.blocks {
+            if uncompressed.len() >= end {
+                break;
+            }
+            let header = self
+                .data
+                .get(position..position + 8)
+                .ok_or_else(|| corrupt("truncated data block"))?;
+            let compressed_size = usize::from(u16_le(header, 4));
+            let size = usize::from(u16_le(header, 6));
+            let start = position + 8 + self.block_reserve;
+            let block = self
+                .data
+                .get(start..start + compressed_size)
+                .ok_or_else(|| corrupt("truncated data block"))?;
+            position = start + compressed_size;
+
+            match folder.compression & 0x000F {
+                0 => uncompressed.extend_from_slice(block),
+                1 => {
+                    let inflated = mszip(block, &uncompressed, size)?;
+                    uncompressed.extend_from_slice(&inflated);
+                }
+                2 => return Err(unsupported("Quantum")),
+                3 => return Err(unsupported("LZX")),
+                other => return Err(corrupt(&format!("compression type {other}"))),
+            }
+        }
+        uncompressed
+            .get(start..end)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| corrupt(&format!("'{}' runs past the end of its folder", file.name)))
+    }
+}
+
+/// Inflate an MSZIP block, a `CK`-prefixed deflate stream that may refer
+/// back into the data of the blocks before it
+///
+/// The history is put in front of the block as stored deflate blocks,
+/// which end on a byte boundary where the block's own stream can begin.
+fn mszip(block: &[u8], history: &[u8], size: usize) -> Result<Vec<u8>> {
+    let stream = block
+        .strip_prefix(b"CK")
+        .ok_or_else(|| corrupt("MSZIP block without its signature"))?;
+    let history = &history[history.len().saturating_sub(MSZIP_WINDOW)..];
+    let mut input = Vec::with_capacity(history.len() + 5 + stream.len());
+    for chunk in history.chunks(usize::from(u16::MAX)) {
+        let length = u16::try_from(chunk.len()).unwrap_or(u16::MAX);
+        input.push(0);
+        input.extend_from_slice(&length.to_le_bytes());
+        input.extend_from_slice(&(!length).to_le_bytes());
+        input.extend_from_slice(chunk);
+    }
+    input.extend_from_slice(stream);
+
+    let limit = history.len() + size.min(MAX_BLOCK_SIZE);
+    let mut inflated = Vec::with_capacity(limit);
+    DeflateDecoder::new(input.as_slice())
+        .take(u64::try_from(limit).unwrap_or(u64::MAX))
+        .read_to_end(&mut inflated)
+        .map_err(|e| corrupt(&format!("MSZIP block: {e}")))?;
+    Ok(inflated.split_off(history.len().min(inflated.len())))
+}
+
+/// A NUL-terminated string at `start`, and the position after it
+fn c_string(data: &[u8], start: usize) -> Result<(String, usize)> {
+    let rest = data
+        .get(start..)
+        .ok_or_else(|| corrupt("truncated string"))?;
+    let length = rest
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| corrupt("unterminated string"))?;
+    Ok((
+        String::from_utf8_lossy(&rest[..length]).into_owned(),
+        start + length + 1,
+    ))
+}
+
+fn u16_le(data: &[u8], offset: usize) -> u16 {
+    data.get(offset..offset + 2)
+        .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_le(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |bytes| {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+}
+
+fn corrupt(message: &str) -> Error {
+    Error::corrupt("CAB", message)
+}
+
+fn unsupported(compression: &str) -> Error {
+    Error::UnsupportedFormat(format!(
+        "{compression}-compressed cabinets are not supported"
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A cabinet of `files` in one folder of MSZIP blocks, one block per
+    /// file
+    pub(crate) fn mszip_cabinet(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut blocks = Vec::new();
+        for (_, contents) in files {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(b"CK".to_vec(), flate2::Compression::default());
+            encoder.write_all(contents).unwrap();
+            let block = encoder.finish().unwrap();
+            blocks.extend_from_slice(&[0; 4]);
+            blocks.extend_from_slice(&u16::try_from(block.len()).unwrap().to_le_bytes());
+            blocks.extend_from_slice(&u16::try_from(contents.len()).unwrap().to_le_bytes());
+            blocks.extend_from_slice(&block);
+        }
+        let mut table = Vec::new();
+        let mut offset = 0u32;
+        for (name, contents) in files {
+            let size = u32::try_from(contents.len()).unwrap();
+            table.extend_from_slice(&size.to_le_bytes());
+            table.extend_from_slice(&offset.to_le_bytes());
+            table.extend_from_slice(&[0; 8]);
+            table.extend_from_slice(name.as_bytes());
+            table.push(0);
+            offset += size;
+        }
+
+        let files_offset = 36 + 8;
+        let data_offset = files_offset + table.len();
+        let mut cabinet = b"MSCF".to_vec();
+        cabinet.extend_from_slice(&[0; 4]);
+        cabinet.extend_from_slice(
+            &u32::try_from(data_offset + blocks.len())
+                .unwrap()
+                .to_le_bytes(),
+        );
+        cabinet.extend_from_slice(&[0; 4]);
+        cabinet.extend_from_slice(&u32::try_from(files_offset).unwrap().to_le_bytes());
+        cabinet.extend_from_slice(&[0, 0, 0, 0, 3, 1, 1, 0]);
+        cabinet.extend_from_slice(&u16::try_from(files.len()).unwrap().to_le_bytes());
+        cabinet.extend_from_slice(&[0; 6]);
+        cabinet.extend_from_slice(&u32::try_from(data_offset).unwrap().to_le_bytes());
+        cabinet.extend_from_slice(&u16::try_from(files.len()).unwrap().to_le_bytes());
+        cabinet.extend_from_slice(&1u16.to_le_bytes());
+        cabinet.extend_from_slice(&table);
+        cabinet.extend_from_slice(&blocks);
+        cabinet
+    }
+
+    #[test]
+    fn test_mszip_cabinet() {
+        let cabinet = mszip_cabinet(&[("first", b"Hello cabinet"), ("second", b"Second file")]);
+        let cabinet = Cabinet::open(&cabinet).unwrap();
+        let names: Vec<&str> = cabinet
+            .files
+            .iter()
+            .map(|file| file.name.as_str())
+            .collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(
+            cabinet.extract(&cabinet.files[0]).unwrap(),
+            b"Hello cabinet"
+        );
+        assert_eq!(cabinet.extract(&cabinet.files[1]).unwrap(), b"Second file");
+    }
+
+    #[test]
+    fn test_mszip_history() {
+        // The second block refers back into the first
+        let first = b"abcdefgh".repeat(8);
+        let mut stream = flate2::Compress::new(flate2::Compression::default(), false);
+        let mut output = Vec::with_capacity(1024);
+        stream
+            .compress_vec(&first, &mut output, flate2::FlushCompress::Sync)
+            .unwrap();
+        let split = output.len();
+        stream
+            .compress_vec(&first, &mut output, flate2::FlushCompress::Finish)
+            .unwrap();
+        let second = [b"CK".as_slice(), &output[split..]].concat();
+        assert_eq!(mszip(&second, &first, first.len()).unwrap(), first);
+    }
+}
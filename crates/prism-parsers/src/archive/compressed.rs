@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Single files compressed with GZIP, XZ, BZIP2 or Zstandard
+//!
+//! Each of these holds one stream of data, which is decompressed, unwrapped
+//! of any further compression layers and then detected and parsed as the
+//! file it is.
+
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use prism_core::{
+    document::{
+        ContentBlock, Dimensions, Document, Rect, TableBlock, TableCell, TableRow, TextBlock,
+        TextRun,
+    },
+    error::{Error, Result},
+    format::Format,
+    parser::ParseContext,
+};
+use std::io::Read;
+
+use super::tar;
+use super::{not_found, ArchiveEntry};
+use crate::registry::ParserRegistry;
+
+/// Largest size compressed data is decompressed to, unless
+/// [`ParseOptions::max_memory`](prism_core::parser::ParseOptions::max_memory)
+/// sets another limit
+pub const MAX_DECOMPRESSED_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Most compression layers unwrapped from one another, so data compressed
+/// over and over cannot keep the parser busy
+const MAX_LAYERS: usize = 8;
+
+/// A single-stream compression format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// GZIP (`.gz`)
+    Gzip,
+    /// XZ (`.xz`)
+    Xz,
+    /// BZIP2 (`.bz2`)
+    Bzip2,
+    /// Zstandard (`.zst`)
+    Zstd,
+}
+
+impl Compression {
+    /// The compression of `format`, if it is a compression format
+    #[must_use]
+    pub fn from_format(format: &Format) -> Option<Self> {
+        match format.mime_type.as_str() {
+            "application/gzip" => Some(Self::Gzip),
+            "application/x-xz" => Some(Self::Xz),
+            "application/x-bzip2" => Some(Self::Bzip2),
+            "application/zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The compression `data` starts with, judging by its magic bytes
+    #[must_use]
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else if data.len() >= 4 && data.starts_with(b"BZh") && data[3].is_ascii_digit() {
+            Some(Self::Bzip2)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Short name, as recorded in the `compression` custom metadata
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Xz => "xz",
+            Self::Bzip2 => "bzip2",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// The format of data in this compression
+    #[must_use]
+    pub fn format(self) -> Format {
+        match self {
+            Self::Gzip => Format::gzip(),
+            Self::Xz => Format::xz(),
+            Self::Bzip2 => Format::bzip2(),
+            Self::Zstd => Format::zstd(),
+        }
+    }
+
+    /// The name of the file compressed as `name`: `name` without the
+    /// extension of this compression, or with `.tar` for the shorthand
+    /// extensions of compressed TAR archives
+    fn strip_extension(self, name: &str) -> Option<String> {
+        let (stem, extension) = name.rsplit_once('.')?;
+        let extension = extension.to_ascii_lowercase();
+        let (plain, tar): (&[&str], &[&str]) = match self {
+            Self::Gzip => (&["gz", "gzip"], &["tgz"]),
+            Self::Xz => (&["xz"], &["txz"]),
+            Self::Bzip2 => (&["bz2", "bzip2"], &["tbz2", "tbz"]),
+            Self::Zstd => (&["zst", "zstd"], &["tzst"]),
+        };
+        if plain.contains(&extension.as_str()) {
+            Some(stem.to_string())
+        } else if tar.contains(&extension.as_str()) {
+            Some(format!("{stem}.tar"))
+        } else {
+            None
+        }
+    }
+
+    /// The original file name stored in the header (empty if there is
+    /// none; only GZIP stores one) and the decompressed data
+    ///
+    /// Fails with [`Error::ResourceLimit`] rather than decompress to more
+    /// than `limit` bytes.
+    fn decompress(self, data: &[u8], limit: u64) -> Result<(String, Vec<u8>)> {
+        let failed = |e: std::io::Error| {
+            Error::ParseError(format!("{} decompression failed: {e}", self.format().name))
+        };
+        let mut decompressed = Vec::new();
+        let mut name = String::new();
+        match self {
+            Self::Gzip => {
+                let mut decoder = GzDecoder::new(data);
+                read_limited(&mut decoder, &mut decompressed, limit).map_err(failed)?;
+                if let Some(filename) = decoder.header().and_then(|header| header.filename()) {
+                    name = String::from_utf8_lossy(filename).into_owned();
+                }
+            }
+            Self::Xz => {
+                let mut decoder = xz2::read::XzDecoder::new(data);
+                read_limited(&mut decoder, &mut decompressed, limit).map_err(failed)?;
+            }
+            Self::Bzip2 => {
+                let mut decoder = bzip2::read::BzDecoder::new(data);
+                read_limited(&mut decoder, &mut decompressed, limit).map_err(failed)?;
+            }
+            Self::Zstd => {
+                let mut decoder = zstd::stream::read::Decoder::new(data).map_err(failed)?;
+                read_limited(&mut decoder, &mut decompressed, limit).map_err(failed)?;
+            }
+        }
+        if u64::try_from(decompressed.len()).map_or(true, |size| size > limit) {
+            return Err(Error::ResourceLimit {
+                resource: "decompressed size in bytes".to_string(),
+                limit,
+            });
+        }
+        Ok((name, decompressed))
+    }
+}
+
+/// Read `reader` into `buffer`, stopping one byte past `limit` so that
+/// going over it shows
+fn read_limited(reader: &mut impl Read, buffer: &mut Vec<u8>, limit: u64) -> std::io::Result<()> {
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(buffer)
+        .map(drop)
+}
+
+/// Parse data compressed with `compression` as the file it compresses
+///
+/// A compressed TAR archive parses to the listing of its entries. Anything
+/// else is detected and parsed by the parser for its format, compression
+/// layers within the data (a GZIP file compressed again with XZ) being
+/// unwrapped first; a file in no supported format is described by a table
+/// of its sizes. Either way, the compression is recorded in the
+/// `compression` (outermost layer first), `compressed_size` and
+/// `decompressed_size` custom metadata.
+///
+/// # Errors
+///
+/// Returns [`Error::ResourceLimit`] if the data decompresses to more than
+/// the limit, a parse error if it is damaged, and any error parsing the
+/// decompressed file fails with.
+pub async fn parse(
+    compression: Compression,
+    context: ParseContext,
+    data: Bytes,
+) -> Result<Document> {
+    let limit = context
+        .options
+        .max_memory
+        .map_or(MAX_DECOMPRESSED_SIZE, |max| {
+            u64::try_from(max).unwrap_or(u64::MAX)
+        });
+    let (header_name, mut decompressed) = compression.decompress(&data, limit)?;
+    let mut name = inner_name(compression, header_name, context.filename.as_deref());
+    let mut layers = vec![compression.name()];
+    while layers.len() < MAX_LAYERS {
+        let Some(inner_compression) = Compression::detect(&decompressed) else {
+            break;
+        };
+        let (header_name, inner) = inner_compression.decompress(&decompressed, limit)?;
+        name = inner_name(inner_compression, header_name, name.as_deref());
+        decompressed = inner;
+        layers.push(inner_compression.name());
+    }
+    let decompressed_size = u64::try_from(decompressed.len()).unwrap_or(u64::MAX);
+
+    let mut document = if is_tar(&decompressed) {
+        tar::parse(context, Bytes::from(decompressed)).await?
+    } else {
+        match delegate(&context, name, decompressed).await {
+            Ok(document) => document,
+            Err(Some(error)) => return Err(error),
+            Err(None) => summary(
+                compression,
+                u64::try_from(data.len()).unwrap_or(u64::MAX),
+                decompressed_size,
+            ),
+        }
+    };
+    let metadata = &mut document.metadata;
+    metadata.add_custom("compression", layers.join(", "));
+    metadata.add_custom(
+        "compressed_size",
+        i64::try_from(data.len()).unwrap_or(i64::MAX),
+    );
+    metadata.add_custom(
+        "decompressed_size",
+        i64::try_from(decompressed_size).unwrap_or(i64::MAX),
+    );
+    Ok(document)
+}
+
+/// Parse `decompressed`, the file called `name`, with the parser for its
+/// format
+///
+/// Fails with `None` if no parser supports the file, or if it is yet more
+/// compressed data.
+async fn delegate(
+    context: &ParseContext,
+    name: Option<String>,
+    decompressed: Vec<u8>,
+) -> std::result::Result<Document, Option<Error>> {
+    let registry = ParserRegistry::with_default_parsers();
+    let detected = registry
+        .detect(&decompressed, name.as_deref())
+        .filter(|detected| Compression::from_format(&detected.format).is_none())
+        .ok_or(None)?;
+    let parser = registry
+        .get_parser_for_data(&detected.format, &decompressed)
+        .ok_or(None)?;
+    let inner = ParseContext {
+        format: detected.format,
+        filename: name,
+        size: decompressed.len(),
+        options: context.options.clone(),
+        progress: context.progress.clone(),
+    };
+    parser
+        .parse(Bytes::from(decompressed), inner)
+        .await
+        .map_err(Some)
+}
+
+/// Name of the file compressed with `compression`: the name stored in its
+/// header, or else `outer`, the name of the compressed data, without its
+/// extension
+fn inner_name(
+    compression: Compression,
+    header_name: String,
+    outer: Option<&str>,
+) -> Option<String> {
+    if !header_name.is_empty() {
+        return Some(header_name);
+    }
+    compression.strip_extension(outer?)
+}
+
+/// Table describing a compressed file in no supported format
+fn summary(compression: Compression, original_size: u64, decompressed_size: u64) -> Document {
+    let mut rows = Vec::new();
+
+    rows.push(TableRow {
+        cells: vec![
+            create_header_cell("Properties"),
+            create_header_cell("Value"),
+        ],
+        height: None,
+    });
+
+    rows.push(create_prop_row("Type", &compression.format().name));
+    rows.push(create_prop_row(
+        "Original Size",
+        &format_size(original_size),
+    ));
+    rows.push(create_prop_row(
+        "Decompressed Size",
+        &format_size(decompressed_size),
+    ));
+    rows.push(create_prop_row(
+        "Ratio",
+        &format!(
+            "{:.1}%",
+            (original_size as f64 / decompressed_size as f64) * 100.0
+        ),
+    ));
+
+    let mut document = Document::new();
+    let mut page = prism_core::document::Page::new(1, Dimensions::LETTER);
+
+    let table = TableBlock {
+        bounds: Rect::new(50.0, 50.0, 500.0, 200.0),
+        rows,
+        column_count: 2,
+        style: Default::default(),
+        rotation: 0.0,
+    };
+
+    page.add_content(ContentBlock::Table(table));
+    document.pages.push(page);
+    document
+}
+
+/// The entries of a compressed TAR archive, or the one compressed file
+pub(crate) fn entries(compression: Compression, data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let (name, decompressed) = compression.decompress(data, MAX_DECOMPRESSED_SIZE)?;
+    if is_tar(&decompressed) {
+        return tar::entries(&decompressed);
+    }
+    Ok(vec![ArchiveEntry {
+        name,
+        size: u64::try_from(decompressed.len()).unwrap_or(u64::MAX),
+        compressed_size: Some(u64::try_from(data.len()).unwrap_or(u64::MAX)),
+        modified: None,
+        crc32: None,
+        is_dir: false,
+        encryption: None,
+    }])
+}
+
+/// The contents of entry `name` of a compressed TAR archive, or of the
+/// compressed file if that is what `name` names
+pub(crate) fn extract(compression: Compression, data: &[u8], name: &str) -> Result<Bytes> {
+    let (file_name, decompressed) = compression.decompress(data, MAX_DECOMPRESSED_SIZE)?;
+    if is_tar(&decompressed) {
+        return tar::extract(&decompressed, name);
+    }
+    if name == file_name {
+        Ok(Bytes::from(decompressed))
+    } else {
+        Err(not_found(name))
+    }
+}
+
+fn is_tar(data: &[u8]) -> bool {
+    if data.len() < 512 {
+        return false;
+    }
+    // Check USTAR magic at offset 257 (5 bytes of "ustar" followed by NUL or space)
+    // "ustar\0" or "ustar "
+    let magic = &data[257..263]; // 6 bytes
+    magic == b"ustar\0" || magic == b"ustar "
+}
+
+fn create_header_cell(text: &str) -> TableCell {
+    let mut run = TextRun::new(text);
+    run.style.bold = true;
+
+    let block = TextBlock {
+        bounds: Default::default(),
+        runs: vec![run],
+        paragraph_style: None,
+        style: Default::default(),
+        rotation: 0.0,
+        direction: prism_core::document::TextDirection::Auto,
+    };
+
+    TableCell {
+        content: vec![ContentBlock::Text(block)],
+        col_span: 1,
+        row_span: 1,
+        background_color: Some("#CCCCCC".to_string()),
+    }
+}
+
+fn create_text_cell(text: &str) -> TableCell {
+    let run = TextRun::new(text);
+
+    let block = TextBlock {
+        bounds: Default::default(),
+        runs: vec![run],
+        paragraph_style: None,
+        style: Default::default(),
+        rotation: 0.0,
+        direction: prism_core::document::TextDirection::Auto,
+    };
+
+    TableCell {
+        content: vec![ContentBlock::Text(block)],
+        col_span: 1,
+        row_span: 1,
+        background_color: None,
+    }
+}
+
+fn create_prop_row(key: &str, value: &str) -> TableRow {
+    TableRow {
+        cells: vec![create_text_cell(key), create_text_cell(value)],
+        height: None,
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
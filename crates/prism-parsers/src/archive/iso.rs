@@ -0,0 +1,1085 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! ISO 9660 and UDF disc images
+//!
+//! An ISO 9660 image is read through its Joliet volume descriptor when it
+//! has one, for the Unicode long names, and through the primary one
+//! otherwise. Images with a UDF file system and no ISO 9660 one, as many
+//! DVD and Blu-ray images are, are read through UDF. Physical partitions
+//! and the metadata partitions of UDF 2.50 are supported; the virtual and
+//! sparable partitions of rewritable media are not.
+
+use bytes::Bytes;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use prism_core::{
+    document::Document,
+    error::{Error, Result},
+    parser::ParseContext,
+};
+use std::collections::HashSet;
+
+use super::{is_directory, listing, not_found, ArchiveEntry};
+
+/// Size of an ISO 9660 sector, and of the sectors UDF anchors are found by
+const SECTOR: usize = 2048;
+
+/// First sector of the volume descriptors, after the system area
+const FIRST_DESCRIPTOR: usize = 16;
+
+/// Most volume descriptors looked at before giving up on a terminator
+const MAX_DESCRIPTORS: usize = 64;
+
+/// Most files and directories listed, so a directory loop or a forged
+/// image cannot keep the parser busy
+const MAX_FILES: usize = 100_000;
+
+/// Deepest directory nesting followed
+const MAX_DEPTH: usize = 64;
+
+/// A disc image read into its files
+struct Image {
+    /// Which file system the files were read from
+    file_system: &'static str,
+    /// Volume identifier
+    label: Option<String>,
+    /// Publisher of the volume
+    publisher: Option<String>,
+    /// Application the volume was prepared with
+    application: Option<String>,
+    /// When the volume was created
+    created: Option<DateTime<Utc>>,
+    /// When the volume was last modified
+    modified: Option<DateTime<Utc>>,
+    files: Vec<File>,
+}
+
+/// A file or directory of a disc image
+struct File {
+    /// Path within the image, `/`-separated
+    name: String,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    is_dir: bool,
+    /// Where the contents are, in order
+    spans: Vec<Span>,
+}
+
+/// A stretch of the contents of a file
+enum Span {
+    /// `len` bytes of the image at `offset`
+    Stored { offset: u64, len: u64 },
+    /// `len` bytes allocated but never written, read as zeros
+    Zeros(u64),
+    /// Bytes kept within the file entry itself (UDF)
+    Embedded(Vec<u8>),
+}
+
+/// Parse a disc image to the listing of its files
+///
+/// The volume identifier becomes the title; the file system, publisher and
+/// application are recorded in the `file_system`, `publisher` and
+/// `application` custom metadata.
+///
+/// # Errors
+///
+/// Returns a parse error if the data holds no ISO 9660 or UDF file system,
+/// or if it is damaged.
+pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
+    let image = read(&data)?;
+    let mut document = listing(&entries_of(&image));
+    let metadata = &mut document.metadata;
+    metadata.title = image.label;
+    metadata.created = image.created;
+    metadata.modified = image.modified;
+    metadata.add_custom("file_system", image.file_system);
+    if let Some(publisher) = image.publisher {
+        metadata.add_custom("publisher", publisher);
+    }
+    if let Some(application) = image.application {
+        metadata.add_custom("application", application);
+    }
+    Ok(document)
+}
+
+/// The files and directories of a disc image
+///
+/// Disc images record neither a CRC nor a compressed size.
+pub(crate) fn entries(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    Ok(entries_of(&read(data)?))
+}
+
+/// The contents of file `name`
+pub(crate) fn extract(data: &[u8], name: &str) -> Result<Bytes> {
+    let image = read(data)?;
+    let wanted = name.trim_matches('/');
+    let file = image
+        .files
+        .iter()
+        .find(|file| file.name == wanted)
+        .ok_or_else(|| not_found(name))?;
+    if file.is_dir {
+        return Err(is_directory(name));
+    }
+    if file.size > u64::try_from(data.len()).unwrap_or(u64::MAX) {
+        return Err(corrupt(format!("'{name}' is larger than the image")));
+    }
+    Ok(Bytes::from(contents(data, &file.spans, file.size)?))
+}
+
+fn entries_of(image: &Image) -> Vec<ArchiveEntry> {
+    image
+        .files
+        .iter()
+        .map(|file| ArchiveEntry {
+            name: if file.is_dir {
+                format!("{}/", file.name)
+            } else {
+                file.name.clone()
+            },
+            size: file.size,
+            compressed_size: None,
+            modified: file.modified,
+            crc32: None,
+            is_dir: file.is_dir,
+            encryption: None,
+        })
+        .collect()
+}
+
+/// The first `size` bytes of `spans`
+fn contents(data: &[u8], spans: &[Span], size: u64) -> Result<Vec<u8>> {
+    let size = usize::try_from(size).map_err(|_| corrupt("file too large".to_string()))?;
+    let mut contents = Vec::with_capacity(size);
+    for span in spans {
+        if contents.len() >= size {
+            break;
+        }
+        let wanted = size - contents.len();
+        match span {
+            Span::Stored { offset, len } => {
+                let start = usize::try_from(*offset).unwrap_or(usize::MAX);
+                let len = usize::try_from(*len).unwrap_or(usize::MAX).min(wanted);
+                let bytes = start
+                    .checked_add(len)
+                    .and_then(|end| data.get(start..end))
+                    .ok_or_else(|| {
+                        corrupt("file extent beyond the end of the image".to_string())
+                    })?;
+                contents.extend_from_slice(bytes);
+            }
+            Span::Zeros(len) => {
+                let len = usize::try_from(*len).unwrap_or(usize::MAX).min(wanted);
+                contents.resize(contents.len() + len, 0);
+            }
+            Span::Embedded(bytes) => {
+                contents.extend_from_slice(&bytes[..bytes.len().min(wanted)]);
+            }
+        }
+    }
+    contents.resize(size, 0);
+    Ok(contents)
+}
+
+/// Read the file system of a disc image, ISO 9660 first
+fn read(data: &[u8]) -> Result<Image> {
+    let mut primary = None;
+    let mut joliet = None;
+    let mut udf = false;
+    for index in FIRST_DESCRIPTOR..FIRST_DESCRIPTOR + MAX_DESCRIPTORS {
+        let Some(descriptor) = sector(data, index) else {
+            break;
+        };
+        match &descriptor[1..6] {
+            b"CD001" => match descriptor[0] {
+                1 if primary.is_none() => primary = Some(descriptor),
+                2 if is_joliet(descriptor) && joliet.is_none() => joliet = Some(descriptor),
+                // Other descriptors, and the terminator that UDF follows
+                _ => {}
+            },
+            b"NSR02" | b"NSR03" => udf = true,
+            b"BEA01" | b"TEA01" | b"BOOT2" | b"CDW02" => {}
+            _ => break,
+        }
+    }
+
+    match (joliet.or(primary), udf) {
+        (Some(descriptor), _) => read_iso9660(data, descriptor, joliet.is_some()),
+        (None, true) => Udf::open(data)?.read(),
+        (None, false) => Err(corrupt("no ISO 9660 or UDF volume descriptor".to_string())),
+    }
+}
+
+fn sector(data: &[u8], index: usize) -> Option<&[u8]> {
+    data.get(index * SECTOR..(index + 1) * SECTOR)
+}
+
+/// Whether a supplementary volume descriptor declares UCS-2 names
+fn is_joliet(descriptor: &[u8]) -> bool {
+    matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E")
+}
+
+fn corrupt(message: String) -> Error {
+    Error::corrupt("ISO", message)
+}
+
+fn u16_le(data: &[u8], offset: usize) -> u16 {
+    data.get(offset..offset + 2)
+        .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_le(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |bytes| {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+}
+
+fn u64_le(data: &[u8], offset: usize) -> u64 {
+    data.get(offset..offset + 8).map_or(0, |bytes| {
+        let mut array = [0; 8];
+        array.copy_from_slice(bytes);
+        u64::from_le_bytes(array)
+    })
+}
+
+// =========================================
+// ISO 9660
+// =========================================
+
+/// Read the directory tree of an ISO 9660 volume descriptor
+fn read_iso9660(data: &[u8], descriptor: &[u8], joliet: bool) -> Result<Image> {
+    let text = |range: std::ops::Range<usize>| {
+        let field = &descriptor[range];
+        let text = if joliet {
+            ucs2_be(field)
+        } else {
+            String::from_utf8_lossy(field).into_owned()
+        };
+        let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+        (!text.is_empty()).then(|| text.to_string())
+    };
+
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    // Directories to read: extent, size and path
+    let mut pending = vec![(
+        u32_le(descriptor, 156 + 2),
+        u32_le(descriptor, 156 + 10),
+        String::new(),
+        0,
+    )];
+    while let Some((extent, size, path, depth)) = pending.pop() {
+        if depth > MAX_DEPTH || !visited.insert(extent) {
+            continue;
+        }
+        let start = usize::try_from(extent)
+            .unwrap_or(usize::MAX)
+            .saturating_mul(SECTOR);
+        let directory = start
+            .checked_add(usize::try_from(size).unwrap_or(usize::MAX))
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(|| corrupt(format!("directory '{path}' beyond the end of the image")))?;
+
+        let mut position = 0;
+        // Whether the last record continues in the next, for files in
+        // several extents
+        let mut continued = false;
+        while position < directory.len() {
+            let length = usize::from(directory[position]);
+            if length == 0 {
+                // Records do not cross sectors; the rest of this one is padding
+                position = (position / SECTOR + 1) * SECTOR;
+                continue;
+            }
+            let Some(record) = directory.get(position..position + length) else {
+                break;
+            };
+            position += length;
+            if record.len() < 34 {
+                break;
+            }
+            let name_length = usize::from(record[32]);
+            let Some(raw_name) = record.get(33..33 + name_length) else {
+                break;
+            };
+            if raw_name == [0] || raw_name == [1] {
+                // The directory itself and its parent
+                continue;
+            }
+            let flags = record[25];
+            let is_dir = flags & 0x02 != 0;
+            let name = record_name(raw_name, joliet, is_dir);
+            let full_name = if path.is_empty() {
+                name
+            } else {
+                format!("{path}/{name}")
+            };
+            let extent = u32_le(record, 2);
+            let length = u32_le(record, 10);
+            let span = Span::Stored {
+                offset: u64::from(extent) * SECTOR as u64,
+                len: u64::from(length),
+            };
+
+            match files.last_mut() {
+                Some(File {
+                    name, size, spans, ..
+                }) if continued && *name == full_name => {
+                    *size += u64::from(length);
+                    spans.push(span);
+                }
+                _ => {
+                    if files.len() >= MAX_FILES {
+                        return Err(too_many_files());
+                    }
+                    if is_dir {
+                        pending.push((extent, length, full_name.clone(), depth + 1));
+                    }
+                    files.push(File {
+                        name: full_name,
+                        size: if is_dir { 0 } else { u64::from(length) },
+                        modified: record_time(&record[18..25]),
+                        is_dir,
+                        spans: if is_dir { Vec::new() } else { vec![span] },
+                    });
+                }
+            }
+            continued = flags & 0x80 != 0;
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Image {
+        file_system: if joliet {
+            "ISO 9660 (Joliet)"
+        } else {
+            "ISO 9660"
+        },
+        label: text(40..72),
+        publisher: text(318..446),
+        application: text(574..702),
+        created: descriptor_time(&descriptor[813..830]),
+        modified: descriptor_time(&descriptor[830..847]),
+        files,
+    })
+}
+
+fn too_many_files() -> Error {
+    Error::ResourceLimit {
+        resource: "files in a disc image".to_string(),
+        limit: MAX_FILES as u64,
+    }
+}
+
+/// A file name of a directory record, without its `;1` version and the
+/// dot of a file without extension
+fn record_name(raw: &[u8], joliet: bool, is_dir: bool) -> String {
+    let name = if joliet {
+        ucs2_be(raw)
+    } else {
+        String::from_utf8_lossy(raw).into_owned()
+    };
+    let name = name.split(';').next().unwrap_or_default();
+    if is_dir {
+        name.to_string()
+    } else {
+        name.trim_end_matches('.').to_string()
+    }
+}
+
+fn ucs2_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// The 7-byte time of a directory record: years since 1900, month, day,
+/// hour, minute, second and the offset from GMT in 15 minute steps
+fn record_time(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let &[year, month, day, hour, minute, second, offset] = bytes else {
+        return None;
+    };
+    time(
+        1900 + i32::from(year),
+        u32::from(month),
+        u32::from(day),
+        (u32::from(hour), u32::from(minute), u32::from(second)),
+        i32::from(i8::from_le_bytes([offset])) * 15,
+    )
+}
+
+/// The 17-byte time of a volume descriptor: `YYYYMMDDHHMMSScc` as digits
+/// and the offset from GMT in 15 minute steps
+fn descriptor_time(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let digits = std::str::from_utf8(&bytes[..16]).ok()?;
+    let number = |range: std::ops::Range<usize>| digits.get(range)?.parse::<u32>().ok();
+    time(
+        i32::try_from(number(0..4)?).ok()?,
+        number(4..6)?,
+        number(6..8)?,
+        (number(8..10)?, number(10..12)?, number(12..14)?),
+        i32::from(i8::from_le_bytes([bytes[16]])) * 15,
+    )
+}
+
+fn time(
+    year: i32,
+    month: u32,
+    day: u32,
+    (hour, minute, second): (u32, u32, u32),
+    offset_minutes: i32,
+) -> Option<DateTime<Utc>> {
+    let local = NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, second)?;
+    let offset = FixedOffset::east_opt(offset_minutes * 60)?;
+    Some(
+        local
+            .and_local_timezone(offset)
+            .single()?
+            .with_timezone(&Utc),
+    )
+}
+
+// =========================================
+// UDF
+// =========================================
+
+/// Descriptor tag identifiers
+const TAG_PRIMARY_VOLUME: u16 = 1;
+const TAG_ANCHOR: u16 = 2;
+const TAG_PARTITION: u16 = 5;
+const TAG_LOGICAL_VOLUME: u16 = 6;
+const TAG_TERMINATING: u16 = 8;
+const TAG_FILE_SET: u16 = 256;
+const TAG_FILE_IDENTIFIER: u16 = 257;
+const TAG_ALLOCATION_EXTENT: u16 = 258;
+const TAG_FILE_ENTRY: u16 = 261;
+const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+/// Largest directory read into memory
+const MAX_DIRECTORY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Where a logical volume's partition reference numbers point
+enum Partition {
+    /// A partition of the image starting at a sector
+    Physical { start: u64 },
+    /// A UDF 2.50 metadata partition, whose blocks are the contents of a
+    /// metadata file in a physical partition
+    Metadata { spans: Vec<Span> },
+    /// A partition type that is not supported
+    Unsupported,
+}
+
+/// Address of a block: block number within a partition reference
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct BlockAddress {
+    block: u32,
+    partition: u16,
+}
+
+struct Udf<'a> {
+    data: &'a [u8],
+    block_size: u64,
+    label: Option<String>,
+    created: Option<DateTime<Utc>>,
+    partitions: Vec<Partition>,
+    file_set: BlockAddress,
+}
+
+/// What a file entry says about a file
+struct Node {
+    is_dir: bool,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    spans: Vec<Span>,
+}
+
+impl<'a> Udf<'a> {
+    /// Find the volume and its partitions from the anchor at sector 256
+    fn open(data: &'a [u8]) -> Result<Self> {
+        let anchor = sector(data, 256)
+            .filter(|anchor| tag_id(anchor) == Some(TAG_ANCHOR))
+            .ok_or_else(|| corrupt("no UDF anchor volume descriptor".to_string()))?;
+        let sequence_length = usize::try_from(u32_le(anchor, 16)).unwrap_or(usize::MAX);
+        let sequence_start = usize::try_from(u32_le(anchor, 20)).unwrap_or(usize::MAX);
+
+        let mut label = None;
+        let mut created = None;
+        let mut starts = Vec::new();
+        let mut logical_volume = None;
+        for index in 0..(sequence_length / SECTOR).min(MAX_DESCRIPTORS) {
+            let Some(descriptor) = sector(data, sequence_start.saturating_add(index)) else {
+                break;
+            };
+            match tag_id(descriptor) {
+                Some(TAG_PRIMARY_VOLUME) => {
+                    label = dstring(&descriptor[24..56]);
+                    created = timestamp(&descriptor[376..388]);
+                }
+                Some(TAG_PARTITION) => {
+                    starts.push((u16_le(descriptor, 22), u64::from(u32_le(descriptor, 188))));
+                }
+                Some(TAG_LOGICAL_VOLUME) => logical_volume = Some(descriptor),
+                Some(TAG_TERMINATING) | None => break,
+                Some(_) => {}
+            }
+        }
+        let logical_volume = logical_volume
+            .ok_or_else(|| corrupt("no UDF logical volume descriptor".to_string()))?;
+        let block_size = u64::from(u32_le(logical_volume, 212));
+        if !block_size.is_power_of_two() || !(512..=65536).contains(&block_size) {
+            return Err(corrupt(format!("UDF block size {block_size}")));
+        }
+        let label = dstring(&logical_volume[84..212]).or(label);
+        let file_set = long_ad_address(&logical_volume[248..264]);
+
+        let mut udf = Self {
+            data,
+            block_size,
+            label,
+            created,
+            partitions: Vec::new(),
+            file_set,
+        };
+        let start_of = |number: u16| {
+            starts
+                .iter()
+                .find(|(partition, _)| *partition == number)
+                .map(|(_, start)| *start)
+        };
+        let map_count = u32_le(logical_volume, 268);
+        let mut position = 440;
+        // Partition numbers of the physical references, and the metadata
+        // partitions to map once those are known
+        let mut physical = Vec::new();
+        let mut metadata = Vec::new();
+        for reference in 0..u16::try_from(map_count.min(64)).unwrap_or(64) {
+            let Some(&[kind, length]) = logical_volume.get(position..position + 2) else {
+                break;
+            };
+            let map = &logical_volume[position..(position + usize::from(length)).min(SECTOR)];
+            let partition = match kind {
+                1 => {
+                    let number = u16_le(map, 4);
+                    physical.push((number, reference));
+                    start_of(number).map_or(Partition::Unsupported, |start| Partition::Physical {
+                        start,
+                    })
+                }
+                2 if map.get(5..28) == Some(b"*UDF Metadata Partition".as_slice()) => {
+                    metadata.push((reference, u16_le(map, 38), u32_le(map, 40)));
+                    Partition::Unsupported
+                }
+                _ => Partition::Unsupported,
+            };
+            udf.partitions.push(partition);
+            if length == 0 {
+                break;
+            }
+            position += usize::from(length);
+        }
+
+        // The metadata file is in the physical partition of the same
+        // number, and its contents are the blocks of the metadata partition
+        for (reference, number, location) in metadata {
+            let Some(&(_, partition)) = physical.iter().find(|(found, _)| *found == number) else {
+                continue;
+            };
+            let file = udf.node(BlockAddress {
+                block: location,
+                partition,
+            })?;
+            udf.partitions[usize::from(reference)] = Partition::Metadata { spans: file.spans };
+        }
+        Ok(udf)
+    }
+
+    /// Walk the directory tree from the root of the file set
+    fn read(self) -> Result<Image> {
+        let file_set = self.block(self.file_set)?;
+        if tag_id(file_set) != Some(TAG_FILE_SET) {
+            return Err(corrupt("no UDF file set descriptor".to_string()));
+        }
+        let root = long_ad_address(&file_set[400..416]);
+
+        let mut files = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![(root, String::new(), 0)];
+        while let Some((address, path, depth)) = pending.pop() {
+            if depth > MAX_DEPTH || !visited.insert(address) {
+                continue;
+            }
+            let directory = self.node(address)?;
+            if directory.size > MAX_DIRECTORY_SIZE {
+                return Err(corrupt(format!("directory '{path}' is too large")));
+            }
+            let contents = contents(self.data, &directory.spans, directory.size)?;
+            for (name, child) in file_identifiers(&contents) {
+                if files.len() >= MAX_FILES {
+                    return Err(too_many_files());
+                }
+                let full_name = if path.is_empty() {
+                    name
+                } else {
+                    format!("{path}/{name}")
+                };
+                let node = self.node(child)?;
+                if node.is_dir {
+                    pending.push((child, full_name.clone(), depth + 1));
+                }
+                files.push(File {
+                    name: full_name,
+                    size: if node.is_dir { 0 } else { node.size },
+                    modified: node.modified,
+                    is_dir: node.is_dir,
+                    spans: node.spans,
+                });
+            }
+        }
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Image {
+            file_system: "UDF",
+            label: self.label,
+            publisher: None,
+            application: None,
+            created: self.created,
+            modified: None,
+            files,
+        })
+    }
+
+    /// Byte offset of a block within the image
+    fn offset(&self, address: BlockAddress) -> Result<u64> {
+        let unsupported = || {
+            Error::UnsupportedFormat(format!(
+                "UDF partition reference {} is of an unsupported type",
+                address.partition
+            ))
+        };
+        match self.partitions.get(usize::from(address.partition)) {
+            Some(Partition::Physical { start }) => {
+                Ok((start + u64::from(address.block)) * self.block_size)
+            }
+            Some(Partition::Metadata { spans }) => {
+                let mut position = u64::from(address.block) * self.block_size;
+                for span in spans {
+                    match span {
+                        Span::Stored { offset, len } if position < *len => {
+                            return Ok(offset + position);
+                        }
+                        Span::Stored { len, .. } | Span::Zeros(len) => {
+                            position = position.saturating_sub(*len);
+                        }
+                        Span::Embedded(_) => break,
+                    }
+                }
+                Err(corrupt(format!(
+                    "block {} beyond the UDF metadata partition",
+                    address.block
+                )))
+            }
+            Some(Partition::Unsupported) | None => Err(unsupported()),
+        }
+    }
+
+    fn block(&self, address: BlockAddress) -> Result<&'a [u8]> {
+        let start = usize::try_from(self.offset(address)?).unwrap_or(usize::MAX);
+        let size = usize::try_from(self.block_size).unwrap_or(usize::MAX);
+        start
+            .checked_add(size)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| {
+                corrupt(format!(
+                    "block {} beyond the end of the image",
+                    address.block
+                ))
+            })
+    }
+
+    /// Read the file entry at `address`
+    fn node(&self, address: BlockAddress) -> Result<Node> {
+        let entry = self.block(address)?;
+        let (time_offset, base) = match tag_id(entry) {
+            Some(TAG_FILE_ENTRY) => (84, 176),
+            Some(TAG_EXTENDED_FILE_ENTRY) => (92, 216),
+            _ => {
+                return Err(corrupt(format!(
+                    "no UDF file entry at block {}",
+                    address.block
+                )))
+            }
+        };
+        let file_type = entry[16 + 11];
+        let flags = u16_le(entry, 16 + 18);
+        let size = u64_le(entry, 56);
+        let extended_attributes = usize::try_from(u32_le(entry, base - 8)).unwrap_or(usize::MAX);
+        let descriptors_length = usize::try_from(u32_le(entry, base - 4)).unwrap_or(usize::MAX);
+        let start = base.saturating_add(extended_attributes);
+        let descriptors = start
+            .checked_add(descriptors_length)
+            .and_then(|end| entry.get(start..end))
+            .ok_or_else(|| {
+                corrupt("UDF allocation descriptors beyond the file entry".to_string())
+            })?;
+
+        let spans = match flags & 0x07 {
+            0 => self.allocation(descriptors, address.partition, false)?,
+            1 => self.allocation(descriptors, address.partition, true)?,
+            3 => vec![Span::Embedded(descriptors.to_vec())],
+            kind => {
+                return Err(Error::UnsupportedFormat(format!(
+                    "UDF allocation descriptors of type {kind}"
+                )))
+            }
+        };
+        Ok(Node {
+            is_dir: file_type == 4,
+            size,
+            modified: timestamp(&entry[time_offset..time_offset + 12]),
+            spans,
+        })
+    }
+
+    /// The spans of short (8-byte) or long (16-byte) allocation
+    /// descriptors, following allocation extent descriptors where the list
+    /// continues
+    fn allocation(&self, descriptors: &[u8], partition: u16, long: bool) -> Result<Vec<Span>> {
+        let size = if long { 16 } else { 8 };
+        let mut spans = Vec::new();
+        let mut descriptors = descriptors.to_vec();
+        let mut followed = 0;
+        'list: loop {
+            for descriptor in descriptors.chunks_exact(size) {
+                let length = u32_le(descriptor, 0);
+                let len = u64::from(length & 0x3FFF_FFFF);
+                if len == 0 {
+                    break 'list;
+                }
+                let address = if long {
+                    long_ad_address(descriptor)
+                } else {
+                    BlockAddress {
+                        block: u32_le(descriptor, 4),
+                        partition,
+                    }
+                };
+                match length >> 30 {
+                    0 => spans.push(Span::Stored {
+                        offset: self.offset(address)?,
+                        len,
+                    }),
+                    3 => {
+                        // The list goes on in an allocation extent descriptor
+                        followed += 1;
+                        let next = self.block(address)?;
+                        if followed > 1024 || tag_id(next) != Some(TAG_ALLOCATION_EXTENT) {
+                            return Err(corrupt("broken UDF allocation extent".to_string()));
+                        }
+                        let length = usize::try_from(u32_le(next, 20)).unwrap_or(usize::MAX);
+                        descriptors = next[24..].get(..length).unwrap_or(&next[24..]).to_vec();
+                        continue 'list;
+                    }
+                    _ => spans.push(Span::Zeros(len)),
+                }
+            }
+            break;
+        }
+        Ok(spans)
+    }
+}
+
+/// Names and file entries of the children listed in a directory, without
+/// the parent and deleted entries
+fn file_identifiers(directory: &[u8]) -> Vec<(String, BlockAddress)> {
+    let mut children = Vec::new();
+    let mut position = 0;
+    while let Some(descriptor) = directory.get(position..position + 38) {
+        if tag_id(descriptor) != Some(TAG_FILE_IDENTIFIER) {
+            break;
+        }
+        let characteristics = descriptor[18];
+        let name_length = usize::from(descriptor[19]);
+        let implementation_length = usize::from(u16_le(descriptor, 36));
+        let name_start = position + 38 + implementation_length;
+        let Some(name) = directory.get(name_start..name_start + name_length) else {
+            break;
+        };
+        if characteristics & 0x0C == 0 {
+            children.push((osta(name), long_ad_address(&descriptor[20..36])));
+        }
+        position = (name_start + name_length + 3) & !3;
+    }
+    children
+}
+
+/// Identifier of a descriptor tag, if its checksum holds
+fn tag_id(descriptor: &[u8]) -> Option<u16> {
+    let tag = descriptor.get(..16)?;
+    let checksum = tag
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 4)
+        .fold(0u8, |sum, (_, byte)| sum.wrapping_add(*byte));
+    (checksum == tag[4]).then(|| u16_le(tag, 0))
+}
+
+/// The block a long allocation descriptor points at
+fn long_ad_address(descriptor: &[u8]) -> BlockAddress {
+    BlockAddress {
+        block: u32_le(descriptor, 4),
+        partition: u16_le(descriptor, 8),
+    }
+}
+
+/// An OSTA compressed Unicode string: 8-bit or big-endian 16-bit
+/// characters after a byte giving which
+fn osta(bytes: &[u8]) -> String {
+    match bytes.split_first() {
+        Some((16, rest)) => ucs2_be(rest),
+        Some((_, rest)) => rest.iter().map(|&byte| char::from(byte)).collect(),
+        None => String::new(),
+    }
+}
+
+/// A fixed-size string field whose last byte is the length used
+fn dstring(field: &[u8]) -> Option<String> {
+    let (&length, field) = field.split_last()?;
+    let text = osta(field.get(..usize::from(length))?);
+    let text = text.trim_end_matches('\0').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A 12-byte UDF timestamp
+fn timestamp(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let type_and_zone = u16_le(bytes, 0);
+    // A 12-bit signed offset in minutes, -2047 when unspecified
+    let zone = (i32::from(type_and_zone & 0x0FFF) ^ 0x800) - 0x800;
+    let offset = if type_and_zone >> 12 == 1 && zone != -2047 {
+        zone
+    } else {
+        0
+    };
+    time(
+        i32::from(i16::from_le_bytes([bytes[2], bytes[3]])),
+        u32::from(bytes[4]),
+        u32::from(bytes[5]),
+        (
+            u32::from(bytes[6]),
+            u32::from(bytes[7]),
+            u32::from(bytes[8]),
+        ),
+        offset,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ISO 9660 image with `README.TXT;1` and `DOCS/NOTES.TXT;1`
+    fn iso_image() -> Vec<u8> {
+        let mut image = vec![0u8; 24 * SECTOR];
+        let record = |extent: u32, size: u32, flags: u8, name: &[u8]| {
+            let length = 33 + name.len() + (1 - name.len() % 2);
+            let mut record = vec![0u8; length];
+            record[0] = u8::try_from(length).unwrap();
+            record[2..6].copy_from_slice(&extent.to_le_bytes());
+            record[6..10].copy_from_slice(&extent.to_be_bytes());
+            record[10..14].copy_from_slice(&size.to_le_bytes());
+            record[14..18].copy_from_slice(&size.to_be_bytes());
+            record[18..25].copy_from_slice(&[124, 5, 17, 9, 30, 0, 8]);
+            record[25] = flags;
+            record[32] = u8::try_from(name.len()).unwrap();
+            record[33..33 + name.len()].copy_from_slice(name);
+            record
+        };
+        let write = |image: &mut Vec<u8>, offset: usize, bytes: &[u8]| {
+            image[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+
+        let primary = 16 * SECTOR;
+        image[primary] = 1;
+        write(&mut image, primary + 1, b"CD001");
+        write(
+            &mut image,
+            primary + 40,
+            b"PRISM_DISC                      ",
+        );
+        write(&mut image, primary + 156, &record(20, 2048, 2, &[0]));
+        write(&mut image, primary + 813, b"2024051709300000");
+        image[primary + 829] = 8;
+        image[17 * SECTOR] = 255;
+        write(&mut image, 17 * SECTOR + 1, b"CD001");
+
+        let mut root = record(20, 2048, 2, &[0]);
+        root.extend(record(20, 2048, 2, &[1]));
+        root.extend(record(22, 11, 0, b"README.TXT;1"));
+        root.extend(record(21, 2048, 2, b"DOCS"));
+        write(&mut image, 20 * SECTOR, &root);
+        let mut docs = record(21, 2048, 2, &[0]);
+        docs.extend(record(20, 2048, 2, &[1]));
+        docs.extend(record(23, 5, 0, b"NOTES.TXT;1"));
+        write(&mut image, 21 * SECTOR, &docs);
+        write(&mut image, 22 * SECTOR, b"Hello Prism");
+        write(&mut image, 23 * SECTOR, b"Notes");
+        image
+    }
+
+    #[tokio::test]
+    async fn test_iso9660_listing_and_extract() {
+        let image = iso_image();
+        let names: Vec<String> = entries(&image)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, ["DOCS/", "DOCS/NOTES.TXT", "README.TXT"]);
+        assert_eq!(&extract(&image, "README.TXT").unwrap()[..], b"Hello Prism");
+        assert_eq!(&extract(&image, "/DOCS/NOTES.TXT").unwrap()[..], b"Notes");
+        assert!(matches!(
+            extract(&image, "DOCS"),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            extract(&image, "MISSING"),
+            Err(Error::ResourceNotFound(_))
+        ));
+
+        let context = ParseContext {
+            format: prism_core::format::Format::iso(),
+            filename: Some("disc.iso".to_string()),
+            size: image.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let document = parse(context, Bytes::from(image)).await.unwrap();
+        assert_eq!(document.metadata.title.as_deref(), Some("PRISM_DISC"));
+        assert_eq!(
+            document.metadata.created.unwrap().to_rfc3339(),
+            "2024-05-17T07:30:00+00:00"
+        );
+        assert!(document.extract_text().contains("DOCS/NOTES.TXT"));
+    }
+
+    /// Give a descriptor tag its identifier, location and checksum
+    fn tag(block: &mut [u8], id: u16, location: u32) {
+        block[0..2].copy_from_slice(&id.to_le_bytes());
+        block[2] = 2;
+        block[12..16].copy_from_slice(&location.to_le_bytes());
+        block[4] = 0;
+        block[4] = block[..16]
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    }
+
+    /// A UDF image with `hello.txt` in directory `docs`
+    fn udf_image() -> Vec<u8> {
+        const PARTITION: u32 = 300;
+        let mut image = vec![0u8; 310 * SECTOR];
+        for (index, id) in [(16, b"BEA01"), (17, b"NSR02"), (18, b"TEA01")] {
+            image[index * SECTOR + 1..index * SECTOR + 6].copy_from_slice(id);
+        }
+        let block = |sector: u32| {
+            let start = usize::try_from(sector).unwrap() * SECTOR;
+            start..start + SECTOR
+        };
+
+        let range = block(256);
+        let anchor = &mut image[range];
+        anchor[16..20].copy_from_slice(&(4 * 2048u32).to_le_bytes());
+        anchor[20..24].copy_from_slice(&32u32.to_le_bytes());
+        tag(anchor, TAG_ANCHOR, 256);
+
+        let range = block(32);
+        let partition = &mut image[range];
+        partition[188..192].copy_from_slice(&PARTITION.to_le_bytes());
+        tag(partition, TAG_PARTITION, 32);
+
+        let range = block(33);
+        let volume = &mut image[range];
+        volume[84] = 8;
+        volume[85..90].copy_from_slice(b"PRISM");
+        volume[211] = 6;
+        volume[212..216].copy_from_slice(&2048u32.to_le_bytes());
+        volume[268..272].copy_from_slice(&1u32.to_le_bytes());
+        volume[440..442].copy_from_slice(&[1, 6]);
+        tag(volume, TAG_LOGICAL_VOLUME, 33);
+        let range = block(34);
+        tag(&mut image[range], TAG_TERMINATING, 34);
+
+        // Blocks of the partition: file set, root entry, root directory,
+        // docs entry, docs directory, file entry, file data
+        let range = block(PARTITION);
+        let file_set = &mut image[range];
+        file_set[404..408].copy_from_slice(&1u32.to_le_bytes());
+        tag(file_set, TAG_FILE_SET, 0);
+
+        let entry = |image: &mut [u8], number: u32, file_type: u8, size: u32, data: u32| {
+            let range = block(PARTITION + number);
+            let entry = &mut image[range];
+            entry[16 + 11] = file_type;
+            entry[56..60].copy_from_slice(&size.to_le_bytes());
+            entry[84..86].copy_from_slice(&0x1000u16.to_le_bytes());
+            entry[86..88].copy_from_slice(&2024u16.to_le_bytes());
+            entry[88..92].copy_from_slice(&[5, 17, 9, 30]);
+            entry[172..176].copy_from_slice(&8u32.to_le_bytes());
+            entry[176..180].copy_from_slice(&size.to_le_bytes());
+            entry[180..184].copy_from_slice(&data.to_le_bytes());
+            tag(entry, TAG_FILE_ENTRY, number);
+        };
+        let identifiers = |image: &mut [u8], number: u32, children: &[(&str, u32, u8)]| {
+            let range = block(PARTITION + number);
+            let directory = &mut image[range];
+            let mut position = 0;
+            for (name, child, characteristics) in children {
+                let descriptor = &mut directory[position..];
+                descriptor[18] = *characteristics;
+                descriptor[24..28].copy_from_slice(&child.to_le_bytes());
+                let mut length = 38;
+                if !name.is_empty() {
+                    descriptor[19] = u8::try_from(name.len() + 1).unwrap();
+                    descriptor[38] = 8;
+                    descriptor[39..39 + name.len()].copy_from_slice(name.as_bytes());
+                    length += name.len() + 1;
+                }
+                tag(descriptor, TAG_FILE_IDENTIFIER, number);
+                position += (length + 3) & !3;
+            }
+            u32::try_from(position).unwrap()
+        };
+        let root_size = identifiers(&mut image, 2, &[("", 1, 0x0A), ("docs", 3, 0x02)]);
+        entry(&mut image, 1, 4, root_size, 2);
+        let docs_size = identifiers(&mut image, 4, &[("", 1, 0x0A), ("hello.txt", 5, 0)]);
+        entry(&mut image, 3, 4, docs_size, 4);
+        entry(&mut image, 5, 5, 10, 6);
+        let range = block(PARTITION + 6);
+        image[range][..10].copy_from_slice(b"Hello, UDF");
+        image
+    }
+
+    #[test]
+    fn test_udf_listing_and_extract() {
+        let image = udf_image();
+        let entries = entries(&image).unwrap();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["docs/", "docs/hello.txt"]);
+        assert_eq!(entries[1].size, 10);
+        assert_eq!(
+            entries[1].modified.unwrap().to_rfc3339(),
+            "2024-05-17T09:30:00+00:00"
+        );
+        assert_eq!(
+            &extract(&image, "docs/hello.txt").unwrap()[..],
+            b"Hello, UDF"
+        );
+        assert_eq!(read(&image).unwrap().label.as_deref(), Some("PRISM"));
+    }
+
+    #[test]
+    fn test_not_a_disc_image() {
+        assert!(matches!(
+            entries(&vec![0u8; 40 * SECTOR]),
+            Err(Error::CorruptFile { .. })
+        ));
+    }
+}
@@ -1,27 +1,120 @@
 // SPDX-License-Identifier: AGPL-3.0-only
-pub mod gzip;
+//! ZIP and TAR archives, ISO disc images, MSI installer packages, and GZIP,
+//! XZ, BZIP2 and Zstandard compressed files
+//!
+//! An archive parses to a listing of its entries: one table with each
+//! entry's name, size, compressed size, modification time and CRC-32, and
+//! the directory tree as [`SectionKind::Directory`] and
+//! [`SectionKind::File`] sections of the document structure. Values an
+//! archive format does not record (TAR has no CRC) are left empty.
+//!
+//! The entries themselves are not parsed. [`ArchiveParser::extract`] reads
+//! a single entry, without unpacking the rest, so it can be detected and
+//! parsed as a document of its own. Compressed data holding anything but a
+//! TAR archive is the exception: it parses to the document it compresses
+//! (see [`compressed::parse`]).
+//!
+//! Names and sizes of encrypted ZIP entries are stored in the clear, so
+//! such archives are still listed, with a warning; extracting an encrypted
+//! entry fails with [`Error::EncryptedDocument`].
+
+mod cab;
+pub mod compressed;
+pub mod iso;
+pub mod msi;
 pub mod tar;
 pub mod zip;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use compressed::Compression;
 use prism_core::{
-    document::Document,
-    error::{Error, Result},
+    diagnostics::Diagnostic,
+    document::{
+        ContentBlock, ContentPosition, Dimensions, Document, Page, Rect, Section, SectionKind,
+        TableBlock, TableCell, TableRow, TextBlock, TextRun,
+    },
+    error::{Error, ErrorCode, Result},
     format::Format,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
 };
 
-/// Archive parser supporting ZIP, TAR, GZIP
+/// Archive parser supporting ZIP, TAR, ISO, MSI, GZIP, XZ, BZIP2 and
+/// Zstandard
 pub struct ArchiveParser {
     format: Format,
 }
 
+/// An entry of an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the entry within the archive, `/`-separated
+    pub name: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Size of the stored data, if the format compresses entries
+    pub compressed_size: Option<u64>,
+    /// Last modification time
+    pub modified: Option<DateTime<Utc>>,
+    /// CRC-32 of the uncompressed data, if the format records one
+    pub crc32: Option<u32>,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// Encryption method, if the entry is encrypted
+    pub encryption: Option<String>,
+}
+
 impl ArchiveParser {
     /// Create a new archive parser for the specified format
     pub fn new(format: Format) -> Self {
         Self { format }
     }
+
+    /// List the entries of `data`, in archive order
+    ///
+    /// # Errors
+    ///
+    /// Returns a parse error if the archive is damaged.
+    pub fn entries(&self, data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+        match self.format.mime_type.as_str() {
+            "application/zip" => zip::entries(data),
+            "application/x-tar" => tar::entries(data),
+            "application/x-iso9660-image" => iso::entries(data),
+            "application/x-msi" => msi::entries(data),
+            _ => match Compression::from_format(&self.format) {
+                Some(compression) => compressed::entries(compression, data),
+                None => Err(self.unsupported()),
+            },
+        }
+    }
+
+    /// Read the entry called `name`, to parse as a document of its own
+    ///
+    /// Only that entry is decompressed; the rest of the archive is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ResourceNotFound`] if there is no such entry,
+    /// [`Error::InvalidInput`] if it is a directory,
+    /// [`Error::EncryptedDocument`] if it is encrypted, and a parse error if
+    /// the archive is damaged.
+    pub fn extract(&self, data: &[u8], name: &str) -> Result<Bytes> {
+        match self.format.mime_type.as_str() {
+            "application/zip" => zip::extract(data, name),
+            "application/x-tar" => tar::extract(data, name),
+            "application/x-iso9660-image" => iso::extract(data, name),
+            "application/x-msi" => msi::extract(data, name),
+            _ => match Compression::from_format(&self.format) {
+                Some(compression) => compressed::extract(compression, data, name),
+                None => Err(self.unsupported()),
+            },
+        }
+    }
+
+    fn unsupported(&self) -> Error {
+        Error::UnsupportedFormat(format!("Unsupported archive format: {}", self.format.name))
+    }
 }
 
 #[async_trait]
@@ -40,14 +133,15 @@ impl Parser for ArchiveParser {
             return zip::parse(context, data).await;
         } else if self.format.mime_type == "application/x-tar" {
             return tar::parse(context, data).await;
-        } else if self.format.mime_type == "application/gzip" {
-            return gzip::parse(context, data).await;
+        } else if self.format.mime_type == "application/x-iso9660-image" {
+            return iso::parse(context, data).await;
+        } else if self.format.mime_type == "application/x-msi" {
+            return msi::parse(context, data).await;
+        } else if let Some(compression) = Compression::from_format(&self.format) {
+            return compressed::parse(compression, context, data).await;
         }
 
-        Err(Error::UnsupportedFormat(format!(
-            "Unsupported archive format: {}",
-            self.format.name
-        )))
+        Err(self.unsupported())
     }
 
     fn metadata(&self) -> ParserMetadata {
@@ -57,16 +151,168 @@ impl Parser for ArchiveParser {
             features: vec![
                 ParserFeature::MetadataExtraction,
                 ParserFeature::TableExtraction,
+                ParserFeature::StructureExtraction,
             ],
             requires_sandbox: false,
         }
     }
 }
 
+/// The listing of `entries`: a table of the entries and their directory
+/// tree
+fn listing(entries: &[ArchiveEntry]) -> Document {
+    let mut rows = vec![TableRow {
+        cells: ["Name", "Size", "Compressed", "Modified", "CRC-32"]
+            .into_iter()
+            .map(header_cell)
+            .collect(),
+        height: None,
+    }];
+    for entry in entries {
+        let cells = [
+            entry.name.clone(),
+            if entry.is_dir {
+                String::new()
+            } else {
+                entry.size.to_string()
+            },
+            entry
+                .compressed_size
+                .filter(|_| !entry.is_dir)
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            entry
+                .modified
+                .map(|modified| modified.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            entry
+                .crc32
+                .filter(|_| !entry.is_dir)
+                .map(|crc| format!("{crc:08x}"))
+                .unwrap_or_default(),
+        ];
+        rows.push(TableRow {
+            cells: cells.iter().map(|text| text_cell(text)).collect(),
+            height: None,
+        });
+    }
+
+    let height = 20.0 * f64::from(u32::try_from(rows.len()).unwrap_or(u32::MAX));
+    let mut table = TableBlock::new(Rect::new(50.0, 50.0, 500.0, height), 5);
+    table.rows = rows;
+
+    let mut page = Page::new(1, Dimensions::LETTER);
+    page.add_content(ContentBlock::Table(table));
+    let mut document = Document::new();
+    document.structure.sections = directory_tree(entries, &page);
+    let encrypted: Vec<&ArchiveEntry> = entries
+        .iter()
+        .filter(|entry| entry.encryption.is_some())
+        .collect();
+    if let Some(first) = encrypted.first() {
+        document.diagnostics.push(Diagnostic::warning(
+            ErrorCode::Encrypted,
+            format!(
+                "{} of {} entries are encrypted ({}); their contents cannot be read",
+                encrypted.len(),
+                entries.len(),
+                first.encryption.as_deref().unwrap_or_default()
+            ),
+        ));
+    }
+    document.pages.push(page);
+    document
+}
+
+/// Sections for the directories and files of `entries`, nested as in the
+/// archive, all spanning the listing on `page`
+///
+/// Directories without an entry of their own, implied by the paths of the
+/// files in them, are included too.
+fn directory_tree(entries: &[ArchiveEntry], page: &Page) -> Vec<Section> {
+    let mut tree = Vec::new();
+    for entry in entries {
+        let parts: Vec<&str> = entry
+            .name
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect();
+        let Some((last, parents)) = parts.split_last() else {
+            continue;
+        };
+        let mut level = &mut tree;
+        for part in parents {
+            level = &mut child(level, part, SectionKind::Directory, page).children;
+        }
+        let kind = if entry.is_dir {
+            SectionKind::Directory
+        } else {
+            SectionKind::File
+        };
+        child(level, last, kind, page);
+    }
+    tree
+}
+
+/// The section of `kind` titled `title` among `sections`, added if missing
+fn child<'a>(
+    sections: &'a mut Vec<Section>,
+    title: &str,
+    kind: SectionKind,
+    page: &Page,
+) -> &'a mut Section {
+    let index = sections
+        .iter()
+        .position(|section| section.kind == kind && section.title == title)
+        .unwrap_or_else(|| {
+            sections.push(Section::new(
+                kind,
+                title,
+                ContentPosition::new(page.number, 0),
+                ContentPosition::new(page.number, page.content.len()),
+            ));
+            sections.len() - 1
+        });
+    &mut sections[index]
+}
+
+fn header_cell(text: &str) -> TableCell {
+    let mut cell = text_cell(text);
+    if let Some(ContentBlock::Text(block)) = cell.content.first_mut() {
+        for run in &mut block.runs {
+            run.style.bold = true;
+        }
+    }
+    cell.background_color = Some("#CCCCCC".to_string());
+    cell
+}
+
+fn text_cell(text: &str) -> TableCell {
+    let mut block = TextBlock::new(Rect::default());
+    block.add_run(TextRun::new(text));
+    TableCell {
+        content: vec![ContentBlock::Text(block)],
+        col_span: 1,
+        row_span: 1,
+        background_color: None,
+    }
+}
+
+/// The error for a name that is not in the archive
+fn not_found(name: &str) -> Error {
+    Error::ResourceNotFound(format!("Archive entry '{name}'"))
+}
+
+/// The error for extracting a directory
+fn is_directory(name: &str) -> Error {
+    Error::InvalidInput(format!("Archive entry '{name}' is a directory"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use prism_core::format::Format;
+    use prism_core::metadata::MetadataValue;
     use prism_core::parser::ParseOptions;
     use std::io::Write;
 
@@ -94,6 +340,7 @@ mod tests {
             filename: Some("test.zip".to_string()),
             size: buf.len(),
             options: ParseOptions::default(),
+            progress: None,
         };
 
         let result = parser.parse(Bytes::from(buf), context).await;
@@ -123,6 +370,7 @@ mod tests {
             filename: Some("test.tar".to_string()),
             size: buf.len(),
             options: ParseOptions::default(),
+            progress: None,
         };
 
         let result = parser.parse(Bytes::from(buf), context).await;
@@ -148,6 +396,7 @@ mod tests {
             filename: Some("test.txt.gz".to_string()),
             size: buf.len(),
             options: ParseOptions::default(),
+            progress: None,
         };
 
         let result = parser.parse(Bytes::from(buf), context).await;
@@ -156,4 +405,231 @@ mod tests {
         assert!(!doc.pages.is_empty());
         assert!(!doc.pages[0].content.is_empty());
     }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut encoder =
+            flate2_crate::write::GzEncoder::new(&mut buf, flate2_crate::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap();
+        buf
+    }
+
+    async fn parse_gzip(data: Vec<u8>, filename: &str, options: ParseOptions) -> Result<Document> {
+        let context = ParseContext {
+            format: Format::gzip(),
+            filename: Some(filename.to_string()),
+            size: data.len(),
+            options,
+            progress: None,
+        };
+        ArchiveParser::new(Format::gzip())
+            .parse(Bytes::from(data), context)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_gzip_delegates_to_inner_format() {
+        let json = br#"{"name": "Prism", "tags": ["parser"]}"#;
+        let document = parse_gzip(gzip(json), "data.json.gz", ParseOptions::default())
+            .await
+            .unwrap();
+        assert!(document.extract_text().contains("Prism"));
+        assert!(!matches!(
+            document.pages[0].content.first(),
+            Some(ContentBlock::Table(_))
+        ));
+        let custom = &document.metadata.custom;
+        assert!(matches!(
+            custom.get("compression"),
+            Some(MetadataValue::String(layers)) if layers == "gzip"
+        ));
+        assert!(matches!(
+            custom.get("decompressed_size"),
+            Some(MetadataValue::Integer(size)) if *size == i64::try_from(json.len()).unwrap()
+        ));
+
+        // Layers within layers are unwrapped
+        let document = parse_gzip(
+            gzip(&gzip(json)),
+            "data.json.gz.gz",
+            ParseOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(document.extract_text().contains("Prism"));
+        assert!(matches!(
+            document.metadata.custom.get("compression"),
+            Some(MetadataValue::String(layers)) if layers == "gzip, gzip"
+        ));
+
+        let options = ParseOptions {
+            max_memory: Some(16),
+            ..ParseOptions::default()
+        };
+        assert!(matches!(
+            parse_gzip(gzip(json), "data.json.gz", options).await,
+            Err(Error::ResourceLimit { limit: 16, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_xz_bzip2_zstd_delegate_to_inner_format() {
+        let json = br#"{"name": "Prism", "tags": ["parser"]}"#;
+        let xz = {
+            let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+            encoder.write_all(json).unwrap();
+            encoder.finish().unwrap()
+        };
+        let bzip2 = {
+            let mut encoder =
+                bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+            encoder.write_all(json).unwrap();
+            encoder.finish().unwrap()
+        };
+        let zstd = zstd::encode_all(&json[..], 0).unwrap();
+
+        for (format, data, filename, layers) in [
+            (Format::xz(), xz, "data.json.xz", "xz"),
+            (Format::bzip2(), bzip2, "data.json.bz2", "bzip2"),
+            (Format::zstd(), zstd.clone(), "data.json.zst", "zstd"),
+            (
+                Format::gzip(),
+                gzip(&zstd),
+                "data.json.zst.gz",
+                "gzip, zstd",
+            ),
+        ] {
+            let context = ParseContext {
+                format: format.clone(),
+                filename: Some(filename.to_string()),
+                size: data.len(),
+                options: ParseOptions::default(),
+                progress: None,
+            };
+            let document = ArchiveParser::new(format)
+                .parse(Bytes::from(data), context)
+                .await
+                .unwrap();
+            assert!(document.extract_text().contains("Prism"), "{filename}");
+            assert!(matches!(
+                document.metadata.custom.get("compression"),
+                Some(MetadataValue::String(found)) if found == layers
+            ));
+        }
+    }
+
+    fn zip_archive() -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip_crate::ZipWriter::new(std::io::Cursor::new(&mut buf));
+            let options = zip_crate::write::FileOptions::default()
+                .compression_method(zip_crate::CompressionMethod::Deflated);
+            zip.add_directory("docs/", options).unwrap();
+            zip.start_file("docs/notes.txt", options).unwrap();
+            zip.write_all(&b"Hello World ".repeat(20)).unwrap();
+            zip.start_file("docs/img/logo.png", options).unwrap();
+            zip.write_all(b"not really a png").unwrap();
+            zip.start_file("README", options).unwrap();
+            zip.write_all(b"Read me").unwrap();
+            zip.finish().unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn test_zip_listing() {
+        let data = zip_archive();
+        let parser = ArchiveParser::new(Format::zip());
+        let entries = parser.entries(&data).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert!(entries[0].is_dir);
+        let notes = &entries[1];
+        assert_eq!(notes.name, "docs/notes.txt");
+        assert_eq!(notes.size, 240);
+        assert!(notes.compressed_size.unwrap() < 240);
+        assert!(notes.crc32.is_some());
+
+        let document = listing(&entries);
+        let ContentBlock::Table(table) = &document.pages[0].content[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.rows.len(), 5);
+        let row: Vec<String> = table.rows[2]
+            .cells
+            .iter()
+            .map(TableCell::extract_text)
+            .collect();
+        assert_eq!(row[0], "docs/notes.txt");
+        assert_eq!(row[1], "240");
+        assert_eq!(row[4], format!("{:08x}", notes.crc32.unwrap()));
+
+        let tree = &document.structure.sections;
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].title, "docs");
+        assert_eq!(tree[0].kind, SectionKind::Directory);
+        let children: Vec<(&str, SectionKind)> = tree[0]
+            .children
+            .iter()
+            .map(|section| (section.title.as_str(), section.kind))
+            .collect();
+        assert_eq!(
+            children,
+            [
+                ("notes.txt", SectionKind::File),
+                ("img", SectionKind::Directory)
+            ]
+        );
+        assert_eq!(tree[0].children[1].children[0].title, "logo.png");
+        assert_eq!(
+            (tree[1].title.as_str(), tree[1].kind),
+            ("README", SectionKind::File)
+        );
+    }
+
+    #[test]
+    fn test_extract_entry() {
+        let data = zip_archive();
+        let parser = ArchiveParser::new(Format::zip());
+        assert_eq!(&parser.extract(&data, "README").unwrap()[..], b"Read me");
+        assert!(matches!(
+            parser.extract(&data, "missing.txt"),
+            Err(Error::ResourceNotFound(_))
+        ));
+        assert!(matches!(
+            parser.extract(&data, "docs/"),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let mut tar_data = Vec::new();
+        {
+            let mut tar = tar_crate::Builder::new(&mut tar_data);
+            for (name, contents) in [("a.txt", &b"first"[..]), ("b/c.txt", b"second")] {
+                let mut header = tar_crate::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_cksum();
+                tar.append_data(&mut header, name, contents).unwrap();
+            }
+            tar.finish().unwrap();
+        }
+        let parser = ArchiveParser::new(Format::tar());
+        assert_eq!(
+            &parser.extract(&tar_data, "b/c.txt").unwrap()[..],
+            b"second"
+        );
+        assert!(parser.extract(&tar_data, "c.txt").is_err());
+
+        let mut gz_data = Vec::new();
+        {
+            let mut encoder = flate2_crate::write::GzEncoder::new(
+                &mut gz_data,
+                flate2_crate::Compression::default(),
+            );
+            encoder.write_all(&tar_data).unwrap();
+            encoder.finish().unwrap();
+        }
+        let parser = ArchiveParser::new(Format::gzip());
+        assert_eq!(parser.entries(&gz_data).unwrap().len(), 2);
+        assert_eq!(&parser.extract(&gz_data, "a.txt").unwrap()[..], b"first");
+    }
 }
@@ -0,0 +1,962 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Windows Installer packages (MSI)
+//!
+//! An MSI package is a compound file holding a relational database. Its
+//! files are the rows of the `File` table, placed by the `Component` and
+//! `Directory` tables: `ProgramFilesFolder/Prism/prism.exe`. Standard
+//! folders directly under the root keep the name of their property, since
+//! the installer decides where they are. The files themselves are in
+//! cabinets, listed in the `Media` table; those embedded in the package can
+//! be extracted, while external ones and uncompressed source files are
+//! beside the package, not in it.
+//!
+//! The summary information stream gives the title, author, subject,
+//! keywords and dates; the `Property` table gives the product name,
+//! version, manufacturer and codes.
+
+use bytes::Bytes;
+use cfb::CompoundFile;
+use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use prism_core::{
+    document::Document,
+    error::{Error, Result},
+    metadata::Metadata,
+    parser::ParseContext,
+};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use super::cab::Cabinet;
+use super::{listing, not_found, ArchiveEntry};
+
+/// Column type bits
+const TYPE_VALID: u16 = 0x0100;
+const TYPE_STRING: u16 = 0x0800;
+const TYPE_NULLABLE: u16 = 0x1000;
+const TYPE_TEMPORARY: u16 = 0x4000;
+
+/// Deepest directory nesting followed, so a loop in the `Directory` table
+/// ends
+const MAX_DEPTH: usize = 64;
+
+/// Properties of the `Property` table recorded as custom metadata
+const PRODUCT_PROPERTIES: [(&str, &str); 5] = [
+    ("ProductName", "product_name"),
+    ("ProductVersion", "product_version"),
+    ("Manufacturer", "manufacturer"),
+    ("ProductCode", "product_code"),
+    ("UpgradeCode", "upgrade_code"),
+];
+
+/// A file installed by a package
+struct File {
+    /// Install path, `/`-separated
+    path: String,
+    /// Key of the row, which is also its name in the cabinet
+    key: String,
+    size: u64,
+    /// Name of the cabinet it is in, `#`-prefixed if embedded
+    cabinet: Option<String>,
+}
+
+/// Parse an MSI package to the listing of the files it installs
+///
+/// # Errors
+///
+/// Returns a parse error if the data is not an MSI package or its database
+/// is damaged.
+pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
+    let mut package = Package::open(&data)?;
+    let files = package.files()?;
+    let mut document = listing(&entries_of(&files));
+    document.metadata = package.metadata()?;
+    Ok(document)
+}
+
+/// The files a package installs
+///
+/// MSI records only their install size.
+pub(crate) fn entries(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    Ok(entries_of(&Package::open(data)?.files()?))
+}
+
+/// The contents of the file installed as `name`, from the cabinet embedded
+/// in the package
+pub(crate) fn extract(data: &[u8], name: &str) -> Result<Bytes> {
+    let mut package = Package::open(data)?;
+    let files = package.files()?;
+    let wanted = name.trim_matches('/');
+    let file = files
+        .iter()
+        .find(|file| file.path == wanted)
+        .ok_or_else(|| not_found(name))?;
+    let outside = || {
+        Error::ResourceNotFound(format!(
+            "Archive entry '{name}' (stored outside the package)"
+        ))
+    };
+    let stream = file
+        .cabinet
+        .as_deref()
+        .and_then(|cabinet| cabinet.strip_prefix('#'))
+        .ok_or_else(outside)?;
+    let cabinet = package.stream(stream)?.ok_or_else(outside)?;
+    let cabinet = Cabinet::open(&cabinet)?;
+    let entry = cabinet
+        .files
+        .iter()
+        .find(|entry| entry.name == file.key)
+        .ok_or_else(|| not_found(name))?;
+    Ok(Bytes::from(cabinet.extract(entry)?))
+}
+
+fn entries_of(files: &[File]) -> Vec<ArchiveEntry> {
+    files
+        .iter()
+        .map(|file| ArchiveEntry {
+            name: file.path.clone(),
+            size: file.size,
+            compressed_size: None,
+            modified: None,
+            crc32: None,
+            is_dir: false,
+            encryption: None,
+        })
+        .collect()
+}
+
+fn corrupt(message: impl Into<String>) -> Error {
+    Error::corrupt("MSI", message)
+}
+
+/// A value of a table cell
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+impl Value {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// A table read into rows
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Cell `column` of `row`, `Null` if the table has no such column
+    fn get<'t>(&self, row: &'t [Value], column: &str) -> &'t Value {
+        self.columns
+            .iter()
+            .position(|name| name == column)
+            .and_then(|index| row.get(index))
+            .unwrap_or(&Value::Null)
+    }
+}
+
+/// An open package and its string pool
+struct Package {
+    comp: CompoundFile<Cursor<Vec<u8>>>,
+    /// Raw stream names by decoded name, tables `!`-prefixed
+    streams: HashMap<String, String>,
+    strings: Vec<String>,
+    /// Whether string references take 3 bytes rather than 2
+    long_references: bool,
+    /// Columns of each table, with their types, in order
+    columns: HashMap<String, Vec<(String, u16)>>,
+}
+
+impl Package {
+    fn open(data: &[u8]) -> Result<Self> {
+        let comp = CompoundFile::open(Cursor::new(data.to_vec()))
+            .map_err(|e| corrupt(format!("Failed to open OLE2 container: {e}")))?;
+        let streams = comp
+            .read_root_storage()
+            .filter(cfb::Entry::is_stream)
+            .map(|entry| (decode_name(entry.name()), entry.name().to_string()))
+            .collect();
+        let mut package = Self {
+            comp,
+            streams,
+            strings: Vec::new(),
+            long_references: false,
+            columns: HashMap::new(),
+        };
+
+        let pool = package
+            .stream("!_StringPool")?
+            .ok_or_else(|| corrupt("no string pool"))?;
+        let data = package.stream("!_StringData")?.unwrap_or_default();
+        let (strings, long_references) = string_pool(&pool, &data)?;
+        package.strings = strings;
+        package.long_references = long_references;
+
+        let schema = [
+            ("Table".to_string(), TYPE_VALID | TYPE_STRING | 64),
+            ("Number".to_string(), TYPE_VALID | 2),
+            ("Name".to_string(), TYPE_VALID | TYPE_STRING | 64),
+            ("Type".to_string(), TYPE_VALID | 2),
+        ];
+        let columns = package.read_table("_Columns", &schema)?;
+        let mut numbered: HashMap<String, Vec<(i64, String, u16)>> = HashMap::new();
+        for row in &columns.rows {
+            let (
+                Value::Text(table),
+                Value::Integer(number),
+                Value::Text(name),
+                Value::Integer(kind),
+            ) = (&row[0], &row[1], &row[2], &row[3])
+            else {
+                continue;
+            };
+            numbered.entry(table.clone()).or_default().push((
+                *number,
+                name.clone(),
+                u16::try_from(*kind & 0xFFFF).unwrap_or_default(),
+            ));
+        }
+        package.columns = numbered
+            .into_iter()
+            .map(|(table, mut columns)| {
+                columns.sort_by_key(|(number, _, _)| *number);
+                let columns = columns
+                    .into_iter()
+                    .map(|(_, name, kind)| (name, kind))
+                    .collect();
+                (table, columns)
+            })
+            .collect();
+        Ok(package)
+    }
+
+    /// The contents of the stream called `name` once decoded, if there is one
+    fn stream(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let Some(raw) = self.streams.get(name) else {
+            return Ok(None);
+        };
+        let mut stream = self
+            .comp
+            .open_stream(raw)
+            .map_err(|e| corrupt(format!("Failed to open stream '{name}': {e}")))?;
+        let mut data = Vec::new();
+        stream
+            .read_to_end(&mut data)
+            .map_err(|e| corrupt(format!("Failed to read stream '{name}': {e}")))?;
+        Ok(Some(data))
+    }
+
+    /// Table `name`, empty if the package does not have it
+    fn table(&mut self, name: &str) -> Result<Table> {
+        let columns = self.columns.get(name).cloned().unwrap_or_default();
+        self.read_table(name, &columns)
+    }
+
+    /// Table `name` read with `columns`
+    ///
+    /// Tables are stored column by column, each cell taking 2 or 4 bytes;
+    /// strings are references into the string pool and integers are
+    /// stored offset so that 0 is null.
+    fn read_table(&mut self, name: &str, columns: &[(String, u16)]) -> Result<Table> {
+        let columns: Vec<&(String, u16)> = columns
+            .iter()
+            .filter(|(_, kind)| kind & TYPE_TEMPORARY == 0)
+            .collect();
+        let widths: Vec<usize> = columns.iter().map(|(_, kind)| self.width(*kind)).collect();
+        let row_size: usize = widths.iter().sum();
+        let data = self.stream(&format!("!{name}"))?.unwrap_or_default();
+        let row_count = data.len().checked_div(row_size).unwrap_or(0);
+
+        let mut rows = vec![Vec::with_capacity(columns.len()); row_count];
+        let mut offset = 0;
+        for ((_, kind), width) in columns.iter().zip(&widths) {
+            for row in &mut rows {
+                let cell = &data[offset..offset + width];
+                offset += width;
+                let raw = cell
+                    .iter()
+                    .rev()
+                    .fold(0u32, |value, &byte| (value << 8) | u32::from(byte));
+                row.push(self.value(*kind, *width, raw));
+            }
+        }
+        Ok(Table {
+            columns: columns.iter().map(|(name, _)| name.clone()).collect(),
+            rows,
+        })
+    }
+
+    /// Bytes a cell of a column of type `kind` takes
+    fn width(&self, kind: u16) -> usize {
+        let is_stream = kind & !TYPE_NULLABLE == TYPE_STRING | TYPE_VALID;
+        if kind & TYPE_STRING != 0 && !is_stream {
+            if self.long_references {
+                3
+            } else {
+                2
+            }
+        } else if kind & 0xFF <= 2 || is_stream {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn value(&self, kind: u16, width: usize, raw: u32) -> Value {
+        if raw == 0 {
+            return Value::Null;
+        }
+        let is_stream = kind & !TYPE_NULLABLE == TYPE_STRING | TYPE_VALID;
+        if kind & TYPE_STRING != 0 && !is_stream {
+            return usize::try_from(raw)
+                .ok()
+                .and_then(|index| self.strings.get(index))
+                .map_or(Value::Null, |text| Value::Text(text.clone()));
+        }
+        if is_stream {
+            return Value::Null;
+        }
+        let value = if width == 2 {
+            i64::from(i16::from_le_bytes(
+                u16::try_from(raw ^ 0x8000)
+                    .unwrap_or_default()
+                    .to_le_bytes(),
+            ))
+        } else {
+            i64::from(i32::from_le_bytes((raw ^ 0x8000_0000).to_le_bytes()))
+        };
+        Value::Integer(value)
+    }
+
+    /// The files of the `File` table, in table order
+    fn files(&mut self) -> Result<Vec<File>> {
+        let directories = self.table("Directory")?;
+        let components = self.table("Component")?;
+        let media = self.table("Media")?;
+        let files = self.table("File")?;
+
+        // Key, parent and target name of each directory
+        let mut tree: HashMap<&str, (Option<&str>, &str)> = HashMap::new();
+        for row in &directories.rows {
+            if let Some(key) = directories.get(row, "Directory").text() {
+                let parent = directories
+                    .get(row, "Directory_Parent")
+                    .text()
+                    .filter(|parent| *parent != key);
+                let name = directories.get(row, "DefaultDir").text().unwrap_or(".");
+                tree.insert(key, (parent, name));
+            }
+        }
+        let component_directories: HashMap<&str, &str> = components
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some((
+                    components.get(row, "Component").text()?,
+                    components.get(row, "Directory_").text()?,
+                ))
+            })
+            .collect();
+        // Last sequence number and cabinet of each disk, in order
+        let mut disks: Vec<(i64, Option<&str>)> = media
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some((
+                    media.get(row, "LastSequence").integer()?,
+                    media.get(row, "Cabinet").text(),
+                ))
+            })
+            .collect();
+        disks.sort_by_key(|(last, _)| *last);
+
+        let mut result = Vec::with_capacity(files.rows.len());
+        for row in &files.rows {
+            let Some(key) = files.get(row, "File").text() else {
+                continue;
+            };
+            let name = long_name(files.get(row, "FileName").text().unwrap_or(key));
+            let directory = files
+                .get(row, "Component_")
+                .text()
+                .and_then(|component| component_directories.get(component))
+                .map(|directory| directory_path(&tree, directory))
+                .unwrap_or_default();
+            let sequence = files.get(row, "Sequence").integer().unwrap_or(0);
+            let cabinet = disks
+                .iter()
+                .find(|(last, _)| *last >= sequence)
+                .and_then(|(_, cabinet)| *cabinet)
+                .map(str::to_string);
+            result.push(File {
+                path: if directory.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{directory}/{name}")
+                },
+                key: key.to_string(),
+                size: u64::try_from(files.get(row, "FileSize").integer().unwrap_or(0)).unwrap_or(0),
+                cabinet,
+            });
+        }
+        Ok(result)
+    }
+
+    /// The summary information and product properties
+    fn metadata(&mut self) -> Result<Metadata> {
+        let mut metadata = Metadata::default();
+        if let Some(stream) = self.stream("\u{5}SummaryInformation")? {
+            summary_information(&stream, &mut metadata);
+        }
+        let properties = self.table("Property")?;
+        for row in &properties.rows {
+            let (Some(property), Some(value)) = (
+                properties.get(row, "Property").text(),
+                properties.get(row, "Value").text(),
+            ) else {
+                continue;
+            };
+            if let Some((_, key)) = PRODUCT_PROPERTIES
+                .iter()
+                .find(|(name, _)| *name == property)
+            {
+                metadata.add_custom(*key, value.to_string());
+                if property == "ProductName" && metadata.title.is_none() {
+                    metadata.title = Some(value.to_string());
+                }
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// The install path of a directory, from the root of the tree
+fn directory_path(tree: &HashMap<&str, (Option<&str>, &str)>, key: &str) -> String {
+    let mut parts = Vec::new();
+    let mut current = key;
+    for _ in 0..MAX_DEPTH {
+        let Some(&(parent, default_dir)) = tree.get(current) else {
+            break;
+        };
+        let Some(parent) = parent else {
+            // The root, TARGETDIR, is where the installer puts everything
+            break;
+        };
+        let is_top = tree
+            .get(parent)
+            .map_or(true, |(grandparent, _)| grandparent.is_none());
+        if is_top && current.ends_with("Folder") {
+            parts.push(current.to_string());
+        } else {
+            let target = default_dir.split(':').next().unwrap_or(default_dir);
+            let name = long_name(target);
+            if name != "." {
+                parts.push(name.to_string());
+            }
+        }
+        current = parent;
+    }
+    parts.reverse();
+    parts.join("/")
+}
+
+/// The long name of a `short|long` name pair
+fn long_name(name: &str) -> &str {
+    name.rsplit('|').next().unwrap_or(name)
+}
+
+/// Decode a stream name: characters from U+3800 pack two of the 64
+/// characters table names are made of, and U+4840 marks a table
+fn decode_name(name: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz._";
+    let mut decoded = String::with_capacity(name.len() * 2);
+    for c in name.chars() {
+        let code = u32::from(c);
+        let symbol = |value: u32| char::from(ALPHABET[(value & 0x3F) as usize]);
+        match code {
+            0x4840 => decoded.push('!'),
+            0x4800..=0x483F => decoded.push(symbol(code - 0x4800)),
+            0x3800..=0x47FF => {
+                decoded.push(symbol(code - 0x3800));
+                decoded.push(symbol((code - 0x3800) >> 6));
+            }
+            _ => decoded.push(c),
+        }
+    }
+    decoded
+}
+
+/// The strings of the string pool, indexed by their reference (0 is
+/// null), and whether references take 3 bytes
+///
+/// The pool stream starts with the code page and then has a length and
+/// reference count for each string, whose bytes follow each other in the
+/// data stream. A string over 64 KiB takes two entries: a zero length,
+/// then the low and high words of the length.
+fn string_pool(pool: &[u8], data: &[u8]) -> Result<(Vec<String>, bool)> {
+    let header = pool
+        .get(..4)
+        .ok_or_else(|| corrupt("truncated string pool"))?;
+    let codepage = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let long_references = codepage & 0x8000_0000 != 0;
+    let encoding = codepage_encoding(codepage & 0x7FFF_FFFF);
+
+    let entries: Vec<(u16, u16)> = pool[4..]
+        .chunks_exact(4)
+        .map(|entry| {
+            (
+                u16::from_le_bytes([entry[0], entry[1]]),
+                u16::from_le_bytes([entry[2], entry[3]]),
+            )
+        })
+        .collect();
+    let mut strings = vec![String::new()];
+    let mut offset = 0usize;
+    let mut index = 0;
+    while index < entries.len() {
+        let (length, references) = entries[index];
+        let length = if length != 0 {
+            index += 1;
+            usize::from(length)
+        } else if references == 0 {
+            index += 1;
+            strings.push(String::new());
+            continue;
+        } else {
+            let (low, high) = entries
+                .get(index + 1)
+                .copied()
+                .ok_or_else(|| corrupt("truncated string pool"))?;
+            index += 2;
+            (usize::from(high) << 16) | usize::from(low)
+        };
+        let bytes = data
+            .get(offset..offset + length)
+            .ok_or_else(|| corrupt("string pool beyond the string data"))?;
+        offset += length;
+        strings.push(encoding.decode_without_bom_handling(bytes).0.into_owned());
+    }
+    Ok((strings, long_references))
+}
+
+/// The encoding of a Windows code page, Windows-1252 for the neutral one
+/// and any unknown
+fn codepage_encoding(codepage: u32) -> &'static Encoding {
+    let label = match codepage {
+        65001 => return UTF_8,
+        874 | 1250..=1258 => format!("windows-{codepage}"),
+        932 => "shift_jis".to_string(),
+        936 => "gbk".to_string(),
+        949 => "euc-kr".to_string(),
+        950 => "big5".to_string(),
+        _ => return WINDOWS_1252,
+    };
+    Encoding::for_label(label.as_bytes()).unwrap_or(WINDOWS_1252)
+}
+
+/// Read the properties of a summary information property set into
+/// `metadata`
+fn summary_information(stream: &[u8], metadata: &mut Metadata) {
+    let u32_at = |offset: usize| {
+        stream
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let Some(section) = u32_at(44).and_then(|offset| usize::try_from(offset).ok()) else {
+        return;
+    };
+    let count = u32_at(section + 4).unwrap_or(0).min(256);
+    let mut encoding = WINDOWS_1252;
+    let mut properties = Vec::new();
+    for index in 0..usize::try_from(count).unwrap_or(0) {
+        let (Some(id), Some(offset)) = (
+            u32_at(section + 8 + index * 8),
+            u32_at(section + 12 + index * 8),
+        ) else {
+            break;
+        };
+        let position = section + usize::try_from(offset).unwrap_or(usize::MAX - section);
+        let Some(kind) = u32_at(position) else {
+            continue;
+        };
+        match kind {
+            // VT_I2, the code page when it is property 1
+            2 if id == 1 => {
+                let codepage = u32_at(position + 4).unwrap_or(0) & 0xFFFF;
+                encoding = codepage_encoding(codepage);
+            }
+            // VT_LPSTR
+            30 => {
+                let length = u32_at(position + 4)
+                    .and_then(|length| usize::try_from(length).ok())
+                    .unwrap_or(0);
+                if let Some(bytes) = stream.get(position + 8..position + 8 + length) {
+                    properties.push((id, Property::Text(bytes)));
+                }
+            }
+            // VT_FILETIME
+            64 => {
+                if let (Some(low), Some(high)) = (u32_at(position + 4), u32_at(position + 8)) {
+                    properties.push((id, Property::Time((u64::from(high) << 32) | u64::from(low))));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (id, property) in properties {
+        match property {
+            Property::Text(bytes) => {
+                let text = encoding.decode_without_bom_handling(bytes).0;
+                let text = text.trim_end_matches('\0').trim().to_string();
+                if text.is_empty() {
+                    continue;
+                }
+                match id {
+                    2 => metadata.title = Some(text),
+                    3 => metadata.subject = Some(text),
+                    4 => metadata.author = Some(text),
+                    5 => {
+                        metadata.keywords = text
+                            .split([';', ','])
+                            .map(str::trim)
+                            .filter(|keyword| !keyword.is_empty())
+                            .map(str::to_string)
+                            .collect();
+                    }
+                    6 => metadata.add_custom("comments", text),
+                    // Platform and languages, as "Intel;1033"
+                    7 => metadata.add_custom("platform", text),
+                    // The package code
+                    9 => metadata.add_custom("package_code", text),
+                    18 => metadata.creator = Some(text),
+                    _ => {}
+                }
+            }
+            Property::Time(filetime) => match id {
+                12 => metadata.created = filetime_to_datetime(filetime),
+                13 => metadata.modified = filetime_to_datetime(filetime),
+                _ => {}
+            },
+        }
+    }
+}
+
+enum Property<'a> {
+    Text(&'a [u8]),
+    Time(u64),
+}
+
+/// A FILETIME: 100-nanosecond intervals since 1601-01-01
+fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    const UNIX_EPOCH_AS_FILETIME: u64 = 116_444_736_000_000_000;
+    let since_epoch = filetime.checked_sub(UNIX_EPOCH_AS_FILETIME)?;
+    let seconds = i64::try_from(since_epoch / 10_000_000).ok()?;
+    let nanoseconds = u32::try_from((since_epoch % 10_000_000) * 100).ok()?;
+    DateTime::from_timestamp(seconds, nanoseconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::cab::tests::mszip_cabinet;
+    use std::io::Write;
+
+    /// Encode a stream name as MSI stores it
+    fn encode_name(name: &str, table: bool) -> String {
+        const ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz._";
+        let index = |c: char| ALPHABET.find(c).map(|i| u32::try_from(i).unwrap());
+        let mut encoded = String::new();
+        if table {
+            encoded.push('\u{4840}');
+        }
+        let chars: Vec<char> = name.chars().collect();
+        let mut position = 0;
+        while position < chars.len() {
+            let first = index(chars[position]).unwrap();
+            if let Some(second) = chars.get(position + 1).and_then(|&c| index(c)) {
+                encoded.push(char::from_u32(0x3800 + first + (second << 6)).unwrap());
+                position += 2;
+            } else {
+                encoded.push(char::from_u32(0x4800 + first).unwrap());
+                position += 1;
+            }
+        }
+        encoded
+    }
+
+    enum Cell {
+        Text(&'static str),
+        Short(i16),
+        Long(i32),
+    }
+
+    /// A table's name, columns with their types, and rows
+    type TestTable = (&'static str, Vec<(&'static str, u16)>, Vec<Vec<Cell>>);
+
+    /// A package with two files in a directory under `ProgramFilesFolder`,
+    /// both in an embedded cabinet
+    fn package() -> Vec<u8> {
+        const STRING: u16 = TYPE_VALID | TYPE_STRING | 0x48;
+        const SHORT: u16 = TYPE_VALID | 2;
+        const LONG: u16 = TYPE_VALID | 4;
+        let tables: Vec<TestTable> = vec![
+            (
+                "Directory",
+                vec![
+                    ("Directory", STRING),
+                    ("Directory_Parent", STRING),
+                    ("DefaultDir", STRING),
+                ],
+                vec![
+                    vec![
+                        Cell::Text("TARGETDIR"),
+                        Cell::Text(""),
+                        Cell::Text("SourceDir"),
+                    ],
+                    vec![
+                        Cell::Text("ProgramFilesFolder"),
+                        Cell::Text("TARGETDIR"),
+                        Cell::Text("PFiles"),
+                    ],
+                    vec![
+                        Cell::Text("INSTALLDIR"),
+                        Cell::Text("ProgramFilesFolder"),
+                        Cell::Text("PRISM|Prism App"),
+                    ],
+                ],
+            ),
+            (
+                "Component",
+                vec![("Component", STRING), ("Directory_", STRING)],
+                vec![vec![Cell::Text("Main"), Cell::Text("INSTALLDIR")]],
+            ),
+            (
+                "File",
+                vec![
+                    ("File", STRING),
+                    ("Component_", STRING),
+                    ("FileName", STRING),
+                    ("FileSize", LONG),
+                    ("Sequence", SHORT),
+                ],
+                vec![
+                    vec![
+                        Cell::Text("ReadmeFile"),
+                        Cell::Text("Main"),
+                        Cell::Text("README.TXT|readme.txt"),
+                        Cell::Long(14),
+                        Cell::Short(1),
+                    ],
+                    vec![
+                        Cell::Text("LicenseFile"),
+                        Cell::Text("Main"),
+                        Cell::Text("license.txt"),
+                        Cell::Long(11),
+                        Cell::Short(2),
+                    ],
+                ],
+            ),
+            (
+                "Media",
+                vec![
+                    ("DiskId", SHORT),
+                    ("LastSequence", SHORT),
+                    ("Cabinet", STRING),
+                ],
+                vec![vec![
+                    Cell::Short(1),
+                    Cell::Short(2),
+                    Cell::Text("#product.cab"),
+                ]],
+            ),
+            (
+                "Property",
+                vec![("Property", STRING), ("Value", STRING)],
+                vec![
+                    vec![Cell::Text("ProductName"), Cell::Text("Prism App")],
+                    vec![Cell::Text("ProductVersion"), Cell::Text("1.2.3")],
+                ],
+            ),
+        ];
+
+        let mut strings: Vec<&str> = Vec::new();
+        let reference = |text: &'static str, strings: &mut Vec<&'static str>| -> u16 {
+            if text.is_empty() {
+                return 0;
+            }
+            let index = strings.iter().position(|s| *s == text).unwrap_or_else(|| {
+                strings.push(text);
+                strings.len() - 1
+            });
+            u16::try_from(index + 1).unwrap()
+        };
+        let mut streams: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut columns: Vec<(u16, u16, u16, u16)> = Vec::new();
+        for (table, schema, rows) in &tables {
+            let table_ref = reference(table, &mut strings);
+            for (number, (name, kind)) in schema.iter().enumerate() {
+                columns.push((
+                    table_ref,
+                    u16::try_from(number + 1).unwrap() ^ 0x8000,
+                    reference(name, &mut strings),
+                    kind ^ 0x8000,
+                ));
+            }
+            let mut data = Vec::new();
+            for column in 0..schema.len() {
+                for row in rows {
+                    match &row[column] {
+                        Cell::Text(text) => {
+                            data.extend_from_slice(&reference(text, &mut strings).to_le_bytes());
+                        }
+                        Cell::Short(value) => {
+                            data.extend_from_slice(&value.to_le_bytes());
+                            let len = data.len();
+                            data[len - 1] ^= 0x80;
+                        }
+                        Cell::Long(value) => {
+                            data.extend_from_slice(&value.to_le_bytes());
+                            let len = data.len();
+                            data[len - 1] ^= 0x80;
+                        }
+                    }
+                }
+            }
+            streams.push((encode_name(table, true), data));
+        }
+        let mut column_data = Vec::new();
+        for part in 0..4 {
+            for column in &columns {
+                let value = [column.0, column.1, column.2, column.3][part];
+                column_data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        streams.push((encode_name("_Columns", true), column_data));
+
+        let mut pool = 1252u32.to_le_bytes().to_vec();
+        let mut string_data = Vec::new();
+        for text in &strings {
+            pool.extend_from_slice(&u16::try_from(text.len()).unwrap().to_le_bytes());
+            pool.extend_from_slice(&1u16.to_le_bytes());
+            string_data.extend_from_slice(text.as_bytes());
+        }
+        streams.push((encode_name("_StringPool", true), pool));
+        streams.push((encode_name("_StringData", true), string_data));
+        streams.push((
+            encode_name("product.cab", false),
+            mszip_cabinet(&[
+                ("ReadmeFile", b"Hello from MSI"),
+                ("LicenseFile", b"MIT License"),
+            ]),
+        ));
+
+        // Summary information with a title and a creation time
+        let title = b"Prism Installer\0";
+        let mut summary = vec![0xFE, 0xFF, 0, 0, 6, 2, 1, 0];
+        summary.extend_from_slice(&[0; 16]);
+        summary.extend_from_slice(&1u32.to_le_bytes());
+        summary.extend_from_slice(&[0; 16]);
+        summary.extend_from_slice(&48u32.to_le_bytes());
+        let properties_offset = 8 + 2 * 8;
+        let mut section = Vec::new();
+        section.extend_from_slice(&0u32.to_le_bytes());
+        section.extend_from_slice(&2u32.to_le_bytes());
+        section.extend_from_slice(&2u32.to_le_bytes());
+        section.extend_from_slice(&u32::try_from(properties_offset).unwrap().to_le_bytes());
+        section.extend_from_slice(&12u32.to_le_bytes());
+        section.extend_from_slice(&u32::try_from(properties_offset + 24).unwrap().to_le_bytes());
+        section.extend_from_slice(&30u32.to_le_bytes());
+        section.extend_from_slice(&u32::try_from(title.len()).unwrap().to_le_bytes());
+        section.extend_from_slice(title);
+        section.extend_from_slice(&64u32.to_le_bytes());
+        // 2024-05-17T09:30:00Z
+        let filetime = (1_715_938_200u64 * 10_000_000) + 116_444_736_000_000_000;
+        section.extend_from_slice(&filetime.to_le_bytes());
+        summary.extend_from_slice(&section);
+        streams.push(("\u{5}SummaryInformation".to_string(), summary));
+
+        let mut comp = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in &streams {
+            comp.create_stream(name).unwrap().write_all(data).unwrap();
+        }
+        comp.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_decode_name() {
+        assert_eq!(
+            decode_name(&encode_name("_StringPool", true)),
+            "!_StringPool"
+        );
+        assert_eq!(decode_name(&encode_name("Data1.cab", false)), "Data1.cab");
+        assert_eq!(
+            decode_name("\u{5}SummaryInformation"),
+            "\u{5}SummaryInformation"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_msi_listing_and_extract() {
+        let package = package();
+        let names: Vec<String> = entries(&package)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "ProgramFilesFolder/Prism App/readme.txt",
+                "ProgramFilesFolder/Prism App/license.txt"
+            ]
+        );
+        assert_eq!(
+            &extract(&package, "ProgramFilesFolder/Prism App/readme.txt").unwrap()[..],
+            b"Hello from MSI"
+        );
+        assert_eq!(
+            &extract(&package, "ProgramFilesFolder/Prism App/license.txt").unwrap()[..],
+            b"MIT License"
+        );
+        assert!(matches!(
+            extract(&package, "missing.txt"),
+            Err(Error::ResourceNotFound(_))
+        ));
+
+        let context = ParseContext {
+            format: prism_core::format::Format::msi(),
+            filename: Some("setup.msi".to_string()),
+            size: package.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let document = parse(context, Bytes::from(package)).await.unwrap();
+        assert_eq!(document.metadata.title.as_deref(), Some("Prism Installer"));
+        assert_eq!(
+            document.metadata.created.unwrap().to_rfc3339(),
+            "2024-05-17T09:30:00+00:00"
+        );
+        assert!(matches!(
+            document.metadata.custom.get("product_version"),
+            Some(prism_core::metadata::MetadataValue::String(version)) if version == "1.2.3"
+        ));
+        assert!(document.extract_text().contains("readme.txt"));
+    }
+}
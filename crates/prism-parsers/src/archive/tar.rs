@@ -1,129 +1,79 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 use bytes::Bytes;
 use prism_core::{
-    document::{
-        ContentBlock, Dimensions, Document, Rect, TableBlock, TableCell, TableRow, TextBlock,
-        TextRun,
-    },
+    document::Document,
     error::{Error, Result},
     parser::ParseContext,
 };
-use std::io::Cursor;
+use std::io::{Cursor, Read};
 use tar::Archive;
 
-pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
-    let reader = Cursor::new(data);
-    let mut archive = Archive::new(reader);
-
-    let mut rows = Vec::new();
+use super::{is_directory, listing, not_found, ArchiveEntry};
 
-    // Header row
-    rows.push(TableRow {
-        cells: vec![
-            create_header_cell("Path"),
-            create_header_cell("Size"),
-            create_header_cell("Modified"),
-        ],
-        height: None,
-    });
+pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
+    Ok(listing(&entries(&data)?))
+}
 
-    // tar::Archive::entries() returns an iterator over Result<Entry>
-    let entries = archive
+/// The entries of a TAR archive
+///
+/// TAR records neither a CRC nor a compressed size.
+pub(crate) fn entries(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = Archive::new(Cursor::new(data));
+    let mut entries = Vec::new();
+    for entry in archive
         .entries()
-        .map_err(|e| Error::ParseError(e.to_string()))?;
-
-    for entry in entries {
+        .map_err(|e| Error::ParseError(e.to_string()))?
+    {
         let entry = entry.map_err(|e| Error::ParseError(e.to_string()))?;
-
-        // Skip directories? Usually they appear as explicit entries in TAR.
-        // We can include them.
-
-        let path = entry
-            .path()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| "[Unknown]".to_string());
-        let size = entry.size();
-        let mtime = entry.header().mtime().unwrap_or(0);
-
-        // Simple date formatting (manual implementation to avoid extra deps if possible, or use chrono)
-        // Since we used chrono in zip.rs, we can use it here too if we interpret mtime as unix timestamp
-        let modified = match chrono::DateTime::from_timestamp(mtime as i64, 0) {
-            Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-            None => "-".to_string(),
-        };
-
-        rows.push(TableRow {
-            cells: vec![
-                create_text_cell(&path),
-                create_text_cell(&format_size(size)),
-                create_text_cell(&modified),
-            ],
-            height: None,
+        let header = entry.header();
+        let modified = header
+            .mtime()
+            .ok()
+            .and_then(|mtime| i64::try_from(mtime).ok())
+            .and_then(|mtime| chrono::DateTime::from_timestamp(mtime, 0));
+
+        entries.push(ArchiveEntry {
+            name: entry_name(&entry),
+            size: entry.size(),
+            compressed_size: None,
+            modified,
+            crc32: None,
+            is_dir: header.entry_type().is_dir(),
+            encryption: None,
         });
     }
-
-    let mut document = Document::new();
-    let mut page = prism_core::document::Page::new(1, Dimensions::LETTER);
-
-    let table = TableBlock {
-        bounds: Rect::new(50.0, 50.0, 500.0, rows.len() as f64 * 20.0),
-        rows,
-        column_count: 3,
-        style: Default::default(),
-        rotation: 0.0,
-    };
-
-    page.add_content(ContentBlock::Table(table));
-    document.pages.push(page);
-
-    Ok(document)
-}
-
-fn create_header_cell(text: &str) -> TableCell {
-    let mut run = TextRun::new(text);
-    run.style.bold = true;
-
-    let block = TextBlock {
-        bounds: Default::default(),
-        runs: vec![run],
-        paragraph_style: None,
-        style: Default::default(),
-        rotation: 0.0,
-    };
-
-    TableCell {
-        content: vec![ContentBlock::Text(block)],
-        col_span: 1,
-        row_span: 1,
-        background_color: Some("#CCCCCC".to_string()),
-    }
+    Ok(entries)
 }
 
-fn create_text_cell(text: &str) -> TableCell {
-    let run = TextRun::new(text);
-
-    let block = TextBlock {
-        bounds: Default::default(),
-        runs: vec![run],
-        paragraph_style: None,
-        style: Default::default(),
-        rotation: 0.0,
-    };
-
-    TableCell {
-        content: vec![ContentBlock::Text(block)],
-        col_span: 1,
-        row_span: 1,
-        background_color: None,
+/// The contents of entry `name`
+///
+/// Entries before it are skipped over, not read.
+pub(crate) fn extract(data: &[u8], name: &str) -> Result<Bytes> {
+    let wanted = name.trim_end_matches('/');
+    let mut archive = Archive::new(Cursor::new(data));
+    for entry in archive
+        .entries()
+        .map_err(|e| Error::ParseError(e.to_string()))?
+    {
+        let mut entry = entry.map_err(|e| Error::ParseError(e.to_string()))?;
+        if entry_name(&entry).trim_end_matches('/') != wanted {
+            continue;
+        }
+        if entry.header().entry_type().is_dir() {
+            return Err(is_directory(name));
+        }
+        let mut contents = Vec::with_capacity(usize::try_from(entry.size()).unwrap_or(0));
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| Error::ParseError(format!("Failed to read '{name}': {e}")))?;
+        return Ok(Bytes::from(contents));
     }
+    Err(not_found(name))
 }
 
-fn format_size(bytes: u64) -> String {
-    if bytes < 1024 {
-        format!("{} B", bytes)
-    } else if bytes < 1024 * 1024 {
-        format!("{:.1} KB", bytes as f64 / 1024.0)
-    } else {
-        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
-    }
+fn entry_name<R: Read>(entry: &tar::Entry<'_, R>) -> String {
+    entry.path().map_or_else(
+        |_| "[Unknown]".to_string(),
+        |path| path.to_string_lossy().into_owned(),
+    )
 }
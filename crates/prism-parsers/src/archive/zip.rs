@@ -1,125 +1,89 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 use bytes::Bytes;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, TimeZone, Utc};
 use prism_core::{
-    document::{
-        ContentBlock, Dimensions, Document, Rect, TableBlock, TableCell, TableRow, TextBlock,
-        TextRun,
-    },
+    document::Document,
     error::{Error, Result},
     parser::ParseContext,
 };
-use std::io::Cursor;
+use std::io::{Cursor, Read};
+use zip::result::ZipError;
 use zip::ZipArchive;
 
-pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
-    let reader = Cursor::new(data);
-    let mut archive = ZipArchive::new(reader).map_err(|e| Error::ParseError(e.to_string()))?;
-
-    let mut rows = Vec::new();
+use super::{is_directory, listing, not_found, ArchiveEntry};
+use crate::encryption;
 
-    // Header row
-    rows.push(TableRow {
-        cells: vec![
-            create_header_cell("Path"),
-            create_header_cell("Size"),
-            create_header_cell("Compressed"),
-            create_header_cell("Modified"),
-        ],
-        height: None,
-    });
+pub async fn parse(_context: ParseContext, data: Bytes) -> Result<Document> {
+    Ok(listing(&entries(&data)?))
+}
 
+/// The entries of a ZIP archive, read from its central directory
+pub(crate) fn entries(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = open(data)?;
+    let encrypted = encryption::encrypted_zip_entries(data);
+    let mut entries = Vec::with_capacity(archive.len());
     for i in 0..archive.len() {
+        // Raw access reads the header only, so entries that cannot be
+        // decompressed (or decrypted) are still listed
         let file = archive
-            .by_index(i)
+            .by_index_raw(i)
             .map_err(|e| Error::ParseError(e.to_string()))?;
 
-        // Format date
         let dt = file.last_modified();
-        // ZipDateTime to string
-        let modified = format!(
-            "{}-{}-{} {}:{}:{}",
-            dt.year(),
-            dt.month(),
-            dt.day(),
-            dt.hour(),
-            dt.minute(),
-            dt.second()
-        );
-
-        rows.push(TableRow {
-            cells: vec![
-                create_text_cell(file.name()),
-                create_text_cell(&format_size(file.size())),
-                create_text_cell(&format_size(file.compressed_size())),
-                create_text_cell(&modified),
-            ],
-            height: None,
+        let modified = NaiveDate::from_ymd_opt(
+            i32::from(dt.year()),
+            u32::from(dt.month()),
+            u32::from(dt.day()),
+        )
+        .and_then(|date| {
+            date.and_hms_opt(
+                u32::from(dt.hour()),
+                u32::from(dt.minute()),
+                u32::from(dt.second()),
+            )
+        })
+        .map(|modified| Utc.from_utc_datetime(&modified));
+
+        entries.push(ArchiveEntry {
+            name: file.name().to_string(),
+            size: file.size(),
+            compressed_size: Some(file.compressed_size()),
+            modified,
+            crc32: Some(file.crc32()),
+            is_dir: file.is_dir(),
+            encryption: encrypted
+                .iter()
+                .find(|(name, _)| name == file.name())
+                .map(|(_, method)| method.clone()),
         });
     }
-
-    let mut document = Document::new();
-    let mut page = prism_core::document::Page::new(1, Dimensions::LETTER);
-
-    let table = TableBlock {
-        bounds: Rect::new(50.0, 50.0, 500.0, rows.len() as f64 * 20.0), // Approximate
-        rows,
-        column_count: 4,
-        style: Default::default(),
-        rotation: 0.0,
-    };
-
-    page.add_content(ContentBlock::Table(table));
-    document.pages.push(page);
-
-    Ok(document)
+    Ok(entries)
 }
 
-fn create_header_cell(text: &str) -> TableCell {
-    let mut run = TextRun::new(text);
-    run.style.bold = true;
-
-    let block = TextBlock {
-        bounds: Default::default(),
-        runs: vec![run],
-        paragraph_style: None,
-        style: Default::default(),
-        rotation: 0.0,
-    };
-
-    TableCell {
-        content: vec![ContentBlock::Text(block)],
-        col_span: 1,
-        row_span: 1,
-        background_color: Some("#CCCCCC".to_string()),
+/// The contents of entry `name`
+pub(crate) fn extract(data: &[u8], name: &str) -> Result<Bytes> {
+    if let Some((_, method)) = encryption::encrypted_zip_entries(data)
+        .into_iter()
+        .find(|(entry, _)| entry == name)
+    {
+        return Err(Error::encrypted("ZIP", method));
     }
-}
-
-fn create_text_cell(text: &str) -> TableCell {
-    let run = TextRun::new(text);
-
-    let block = TextBlock {
-        bounds: Default::default(),
-        runs: vec![run],
-        paragraph_style: None,
-        style: Default::default(),
-        rotation: 0.0,
+    let mut archive = open(data)?;
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Err(not_found(name)),
+        Err(e) => return Err(Error::ParseError(e.to_string())),
     };
-
-    TableCell {
-        content: vec![ContentBlock::Text(block)],
-        col_span: 1,
-        row_span: 1,
-        background_color: None,
+    if file.is_dir() {
+        return Err(is_directory(name));
     }
+
+    let mut contents = Vec::with_capacity(usize::try_from(file.size()).unwrap_or(0));
+    file.read_to_end(&mut contents)
+        .map_err(|e| Error::ParseError(format!("Failed to read '{name}': {e}")))?;
+    Ok(Bytes::from(contents))
 }
 
-fn format_size(bytes: u64) -> String {
-    if bytes < 1024 {
-        format!("{} B", bytes)
-    } else if bytes < 1024 * 1024 {
-        format!("{:.1} KB", bytes as f64 / 1024.0)
-    } else {
-        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
-    }
+fn open(data: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>> {
+    ZipArchive::new(Cursor::new(data)).map_err(|e| Error::ParseError(e.to_string()))
 }
@@ -2,14 +2,17 @@
 //! EML (Email Message) parser
 //!
 //! Parses .EML files (RFC 822/MIME email messages) into the Unified Document Model.
+//! Attachments are kept, with those wrapped in a TNEF `winmail.dat`
+//! unpacked.
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::DateTime;
-use mail_parser::MessageParser;
+use mail_parser::{Message, MessageParser, MimeHeaders};
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, ShapeStyle, TextBlock, TextRun, TextStyle,
+        Attachment, ContentBlock, Dimensions, Document, Page, PageMetadata, ShapeStyle, TextBlock,
+        TextDirection, TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
@@ -18,6 +21,9 @@ use prism_core::{
 };
 use tracing::{debug, info};
 
+use super::thread::message_headers;
+use super::tnef;
+
 /// EML email parser
 #[derive(Debug, Clone)]
 pub struct EmlParser;
@@ -32,17 +38,42 @@ impl EmlParser {
     /// Format email headers as text content
     fn format_email_header(&self, label: &str, value: &str) -> TextRun {
         TextRun {
-            text: format!("{}: {}\n", label, value),
+            text: format!("{label}: {value}\n").into(),
             style: TextStyle {
                 bold: label == "From" || label == "To" || label == "Subject",
                 ..Default::default()
             },
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
 }
 
+/// Attachments of `message`, with TNEF streams left as they are
+fn attachments(message: &Message<'_>) -> Vec<Attachment> {
+    message
+        .attachments()
+        .map(|part| Attachment {
+            filename: part
+                .attachment_name()
+                .map_or_else(|| "attachment".to_string(), str::to_string),
+            mime_type: part
+                .content_type()
+                .map(|content_type| match content_type.subtype() {
+                    Some(subtype) => format!("{}/{subtype}", content_type.ctype()),
+                    None => content_type.ctype().to_string(),
+                }),
+            description: None,
+            data: part.contents().to_vec(),
+            created: None,
+            modified: None,
+            document: None,
+        })
+        .collect()
+}
+
 impl Default for EmlParser {
     fn default() -> Self {
         Self::new()
@@ -139,12 +170,18 @@ impl Parser for EmlParser {
 
         // Add empty line separator
         text_runs.push(TextRun {
-            text: "\n".to_string(),
+            text: "\n".into(),
             style: Default::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         });
 
+        // Outlook may send the body and attachments wrapped in winmail.dat
+        let mut attachments = attachments(&message);
+        let tnef_body = tnef::expand(&mut attachments);
+
         // Extract body text
         let body_text = if let Some(text_body) = message.body_text(0) {
             text_body.to_string()
@@ -152,14 +189,16 @@ impl Parser for EmlParser {
             // If only HTML body, strip tags (basic)
             html_body.to_string()
         } else {
-            String::from("[No message body]")
+            tnef_body.unwrap_or_else(|| String::from("[No message body]"))
         };
 
         text_runs.push(TextRun {
-            text: body_text,
+            text: body_text.into(),
             style: Default::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         });
 
         // Create text block with all runs
@@ -174,6 +213,7 @@ impl Parser for EmlParser {
             paragraph_style: None,
             style: ShapeStyle::default(),
             rotation: 0.0,
+            direction: TextDirection::Auto,
         };
 
         // Create page
@@ -181,7 +221,10 @@ impl Parser for EmlParser {
             number: 1,
             dimensions: Dimensions::LETTER,
             content: vec![ContentBlock::Text(text_block)],
-            metadata: Default::default(),
+            metadata: PageMetadata {
+                message: Some(message_headers(&message)),
+                ..PageMetadata::default()
+            },
             annotations: Vec::new(),
         };
 
@@ -208,6 +251,7 @@ impl Parser for EmlParser {
         let mut document = Document::new();
         document.pages = vec![page];
         document.metadata = metadata;
+        document.attachments = attachments;
 
         info!("Successfully parsed EML email");
 
@@ -239,6 +283,31 @@ mod tests {
         assert!(parser.can_parse(eml_data));
     }
 
+    #[tokio::test]
+    async fn test_tnef_attachment() {
+        let parser = EmlParser::new();
+        let data = Bytes::from_static(
+            b"From: ana@example.com\r\nTo: bo@example.com\r\nSubject: Budget\r\n\
+              MIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=b\r\n\r\n\
+              --b\r\nContent-Type: text/plain\r\n\r\nSee attached\r\n\
+              --b\r\nContent-Type: application/ms-tnef; name=winmail.dat\r\n\
+              Content-Transfer-Encoding: base64\r\n\r\n\
+              eJ8+IgEAAgKQBgAOAAAAAAAAAAAAAAAAAAAAAAAAAAIQgAEACwAAAGJ1ZGdldC5jc3YA9QMCD4AGAAYAAABxMSwxMAo5AQ==\r\n\
+              --b--\r\n",
+        );
+        let context = ParseContext {
+            format: parser.format(),
+            filename: None,
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let document = parser.parse(data, context).await.unwrap();
+        assert_eq!(document.attachments.len(), 1);
+        assert_eq!(document.attachments[0].filename, "budget.csv");
+        assert_eq!(document.attachments[0].data, b"q1,10\n");
+    }
+
     #[test]
     fn test_parser_metadata() {
         let parser = EmlParser::new();
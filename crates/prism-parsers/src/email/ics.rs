@@ -9,7 +9,8 @@ use ical::parser::ical::component::IcalCalendar;
 use ical::IcalParser;
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, Rect, ShapeStyle, TextBlock, TextRun, TextStyle,
+        ContentBlock, Dimensions, Document, Page, Rect, ShapeStyle, TextBlock, TextDirection,
+        TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
@@ -33,13 +34,15 @@ impl IcsParser {
     /// Format event field as text content
     fn format_field(&self, label: &str, value: &str, bold: bool) -> TextRun {
         TextRun {
-            text: format!("{}: {}\n", label, value),
+            text: format!("{label}: {value}\n").into(),
             style: TextStyle {
                 bold,
                 ..Default::default()
             },
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
 
@@ -74,19 +77,23 @@ impl IcsParser {
                     "DESCRIPTION" => {
                         if let Some(value) = prop.value.as_ref() {
                             text_runs.push(TextRun {
-                                text: "\nDescription:\n".to_string(),
+                                text: "\nDescription:\n".into(),
                                 style: TextStyle {
                                     bold: true,
                                     ..Default::default()
                                 },
                                 bounds: None,
                                 char_positions: None,
+                                link: None,
+                                revision: None,
                             });
                             text_runs.push(TextRun {
-                                text: format!("{}\n", value),
+                                text: format!("{value}\n").into(),
                                 style: Default::default(),
                                 bounds: None,
                                 char_positions: None,
+                                link: None,
+                                revision: None,
                             });
                         }
                     }
@@ -106,10 +113,12 @@ impl IcsParser {
 
             // Add separator between events
             text_runs.push(TextRun {
-                text: "\n---\n\n".to_string(),
+                text: "\n---\n\n".into(),
                 style: Default::default(),
                 bounds: None,
                 char_positions: None,
+                link: None,
+                revision: None,
             });
         }
 
@@ -187,6 +196,7 @@ impl Parser for IcsParser {
                 paragraph_style: None,
                 style: ShapeStyle::default(),
                 rotation: 0.0,
+                direction: TextDirection::Auto,
             };
 
             let page = Page {
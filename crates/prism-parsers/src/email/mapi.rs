@@ -0,0 +1,336 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MAPI properties of Outlook message files
+//!
+//! Every storage of an MSG file (the message itself, each recipient, each
+//! attachment) keeps its properties in two places, per MS-OXMSG:
+//!
+//! - variable-length values (strings, binary data) in streams of their own,
+//!   named `__substg1.0_` followed by the property tag in hex
+//! - fixed-length values (integers, booleans, floats, times) in the
+//!   `__properties_version1.0` stream: a header whose size depends on the
+//!   storage, then 16-byte entries of tag, flags and value
+
+use cfb::CompoundFile;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+/// Header size of the property stream of the top-level message
+pub const MESSAGE_HEADER: usize = 32;
+
+/// Header size of the property stream of an embedded message
+pub const EMBEDDED_MESSAGE_HEADER: usize = 24;
+
+/// Header size of the property stream of a recipient or an attachment
+pub const ENTRY_HEADER: usize = 8;
+
+/// Prefix of the storages holding recipients
+pub const RECIPIENT_PREFIX: &str = "__recip_version1.0_#";
+
+/// Prefix of the storages holding attachments
+pub const ATTACHMENT_PREFIX: &str = "__attach_version1.0_#";
+
+/// `PR_SUBJECT`
+pub const SUBJECT: u16 = 0x0037;
+/// `PR_CLIENT_SUBMIT_TIME`
+pub const CLIENT_SUBMIT_TIME: u16 = 0x0039;
+/// `PR_SENDER_NAME`
+pub const SENDER_NAME: u16 = 0x0C1A;
+/// `PR_SENDER_EMAIL_ADDRESS`
+pub const SENDER_EMAIL_ADDRESS: u16 = 0x0C1F;
+/// `PR_RECIPIENT_TYPE`
+pub const RECIPIENT_TYPE: u16 = 0x0C15;
+/// `PR_DISPLAY_BCC`
+pub const DISPLAY_BCC: u16 = 0x0E02;
+/// `PR_DISPLAY_CC`
+pub const DISPLAY_CC: u16 = 0x0E03;
+/// `PR_DISPLAY_TO`
+pub const DISPLAY_TO: u16 = 0x0E04;
+/// `PR_MESSAGE_DELIVERY_TIME`
+pub const MESSAGE_DELIVERY_TIME: u16 = 0x0E06;
+/// `PR_BODY`
+pub const BODY: u16 = 0x1000;
+/// `PR_RTF_COMPRESSED`
+pub const RTF_COMPRESSED: u16 = 0x1009;
+/// `PR_BODY_HTML`
+pub const BODY_HTML: u16 = 0x1013;
+/// `PR_INTERNET_MESSAGE_ID`
+pub const INTERNET_MESSAGE_ID: u16 = 0x1035;
+/// `PR_INTERNET_REFERENCES`
+pub const INTERNET_REFERENCES: u16 = 0x1039;
+/// `PR_IN_REPLY_TO_ID`
+pub const IN_REPLY_TO_ID: u16 = 0x1042;
+/// `PR_DISPLAY_NAME`
+pub const DISPLAY_NAME: u16 = 0x3001;
+/// `PR_ADDRTYPE`
+pub const ADDRTYPE: u16 = 0x3002;
+/// `PR_EMAIL_ADDRESS`
+pub const EMAIL_ADDRESS: u16 = 0x3003;
+/// `PR_CREATION_TIME`
+pub const CREATION_TIME: u16 = 0x3007;
+/// `PR_LAST_MODIFICATION_TIME`
+pub const LAST_MODIFICATION_TIME: u16 = 0x3008;
+/// `PR_ATTACH_DATA_BIN`
+pub const ATTACH_DATA: u16 = 0x3701;
+/// `PR_ATTACH_FILENAME`
+pub const ATTACH_FILENAME: u16 = 0x3704;
+/// `PR_ATTACH_LONG_FILENAME`
+pub const ATTACH_LONG_FILENAME: u16 = 0x3707;
+/// `PR_ATTACH_MIME_TAG`
+pub const ATTACH_MIME_TAG: u16 = 0x370E;
+/// `PR_SMTP_ADDRESS`
+pub const SMTP_ADDRESS: u16 = 0x39FE;
+/// `PR_SENDER_SMTP_ADDRESS`
+pub const SENDER_SMTP_ADDRESS: u16 = 0x5D01;
+
+/// A typed property value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `PtypInteger16`, `PtypInteger32` or `PtypInteger64`
+    Integer(i64),
+    /// `PtypFloating32`, `PtypFloating64` or `PtypCurrency`
+    Float(f64),
+    /// `PtypBoolean`
+    Bool(bool),
+    /// `PtypTime` or `PtypFloatingTime`
+    Time(DateTime<Utc>),
+    /// `PtypString` or `PtypString8`
+    String(String),
+    /// `PtypBinary`
+    Binary(Vec<u8>),
+}
+
+/// The properties of one storage, by property ID
+#[derive(Debug, Clone, Default)]
+pub struct Properties {
+    values: HashMap<u16, Value>,
+}
+
+impl Properties {
+    /// Read the properties of `storage`, whose property stream starts with
+    /// a header of `header` bytes
+    ///
+    /// Properties that cannot be read are left out.
+    pub fn read<F: Read + Seek>(comp: &mut CompoundFile<F>, storage: &str, header: usize) -> Self {
+        let mut values = HashMap::new();
+
+        let streams: Vec<(String, String)> = comp
+            .read_storage(storage)
+            .map(|entries| {
+                entries
+                    .filter(cfb::Entry::is_stream)
+                    .map(|entry| {
+                        let path = entry.path().to_string_lossy().into_owned();
+                        (entry.name().to_string(), path)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (name, path) in streams {
+            let Some(tag) = name
+                .strip_prefix("__substg1.0_")
+                .and_then(|tag| u32::from_str_radix(tag, 16).ok())
+            else {
+                continue;
+            };
+            let Some(data) = read_stream(comp, &path) else {
+                continue;
+            };
+            let (id, kind) = split_tag(tag);
+            let value = match kind {
+                0x001F => Value::String(utf16(&data)),
+                0x001E => Value::String(
+                    String::from_utf8_lossy(data.split(|&b| b == 0).next().unwrap_or_default())
+                        .into_owned(),
+                ),
+                0x0102 => Value::Binary(data),
+                _ => continue,
+            };
+            values.insert(id, value);
+        }
+
+        let path = format!("{}/__properties_version1.0", storage.trim_end_matches('/'));
+        if let Some(data) = read_stream(comp, &path) {
+            for entry in data.get(header..).unwrap_or_default().chunks_exact(16) {
+                let tag = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+                let mut value = [0; 8];
+                value.copy_from_slice(&entry[8..16]);
+                let (id, kind) = split_tag(tag);
+                if let Some(value) = fixed(kind, value) {
+                    values.entry(id).or_insert(value);
+                }
+            }
+        }
+
+        Self { values }
+    }
+
+    /// The value of property `id`
+    #[must_use]
+    pub fn get(&self, id: u16) -> Option<&Value> {
+        self.values.get(&id)
+    }
+
+    /// The string value of property `id`, if not blank
+    #[must_use]
+    pub fn string(&self, id: u16) -> Option<&str> {
+        match self.get(id)? {
+            Value::String(text) if !text.trim().is_empty() => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The binary value of property `id`
+    #[must_use]
+    pub fn binary(&self, id: u16) -> Option<&[u8]> {
+        match self.get(id)? {
+            Value::Binary(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The integer value of property `id`
+    #[must_use]
+    pub fn integer(&self, id: u16) -> Option<i64> {
+        match self.get(id)? {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The time value of property `id`
+    #[must_use]
+    pub fn time(&self, id: u16) -> Option<DateTime<Utc>> {
+        match self.get(id)? {
+            Value::Time(time) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+/// Paths of the storages directly under the root whose names start with
+/// `prefix`, in order
+pub fn storages<F>(comp: &CompoundFile<F>, prefix: &str) -> Vec<String> {
+    let mut storages: Vec<String> = comp
+        .read_root_storage()
+        .filter(|entry| entry.is_storage() && entry.name().starts_with(prefix))
+        .map(|entry| entry.path().to_string_lossy().into_owned())
+        .collect();
+    storages.sort();
+    storages
+}
+
+/// Property ID and type of a property tag
+fn split_tag(tag: u32) -> (u16, u16) {
+    let [a, b, c, d] = tag.to_be_bytes();
+    (u16::from_be_bytes([a, b]), u16::from_be_bytes([c, d]))
+}
+
+/// A fixed-length value of type `kind` from its 8 bytes
+fn fixed(kind: u16, value: [u8; 8]) -> Option<Value> {
+    let [a, b, c, d, ..] = value;
+    Some(match kind {
+        0x0002 => Value::Integer(i16::from_le_bytes([a, b]).into()),
+        0x0003 => Value::Integer(i32::from_le_bytes([a, b, c, d]).into()),
+        0x0014 => Value::Integer(i64::from_le_bytes(value)),
+        0x000B => Value::Bool(a != 0),
+        0x0004 => Value::Float(f32::from_le_bytes([a, b, c, d]).into()),
+        0x0005 => Value::Float(f64::from_le_bytes(value)),
+        // Currency: a fixed-point number with 4 decimal places
+        #[allow(clippy::cast_precision_loss)]
+        0x0006 => Value::Float(i64::from_le_bytes(value) as f64 / 10_000.0),
+        // Floating time: days since 1899-12-30
+        0x0007 => {
+            let days = f64::from_le_bytes(value);
+            if !days.is_finite() {
+                return None;
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let seconds = (days * 86_400.0).round() as i64;
+            let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+            Value::Time(
+                epoch
+                    .checked_add_signed(Duration::seconds(seconds))?
+                    .and_utc(),
+            )
+        }
+        // FILETIME: 100 ns intervals since 1601-01-01
+        0x0040 => {
+            let seconds = i64::try_from(u64::from_le_bytes(value) / 10_000_000).ok()?;
+            Value::Time(DateTime::from_timestamp(seconds - 11_644_473_600, 0)?)
+        }
+        _ => return None,
+    })
+}
+
+/// UTF-16LE text up to the first NUL
+fn utf16(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_stream<F: Read + Seek>(comp: &mut CompoundFile<F>, path: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    comp.open_stream(path).ok()?.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_read_properties() {
+        let mut comp = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        let subject: Vec<u8> = "Budget\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        comp.create_stream("/__substg1.0_0037001F")
+            .unwrap()
+            .write_all(&subject)
+            .unwrap();
+        let mut properties = vec![0; MESSAGE_HEADER];
+        // PR_CLIENT_SUBMIT_TIME, 2024-03-01T10:00:00Z
+        properties.extend(0x0039_0040_u32.to_le_bytes());
+        properties.extend(6_u32.to_le_bytes());
+        properties.extend(133_537_608_000_000_000_u64.to_le_bytes());
+        // PR_RECIPIENT_TYPE, Cc
+        properties.extend(0x0C15_0003_u32.to_le_bytes());
+        properties.extend(6_u32.to_le_bytes());
+        properties.extend(2_u64.to_le_bytes());
+        comp.create_stream("/__properties_version1.0")
+            .unwrap()
+            .write_all(&properties)
+            .unwrap();
+        comp.create_storage("/__recip_version1.0_#00000001")
+            .unwrap();
+        comp.create_storage("/__recip_version1.0_#00000000")
+            .unwrap();
+        comp.create_storage("/__attach_version1.0_#00000000")
+            .unwrap();
+
+        let properties = Properties::read(&mut comp, "/", MESSAGE_HEADER);
+        assert_eq!(properties.string(SUBJECT), Some("Budget"));
+        assert_eq!(
+            properties
+                .time(CLIENT_SUBMIT_TIME)
+                .map(|time| time.to_rfc3339()),
+            Some("2024-03-01T10:00:00+00:00".to_string())
+        );
+        assert_eq!(properties.integer(RECIPIENT_TYPE), Some(2));
+        assert_eq!(properties.string(BODY), None);
+        assert_eq!(
+            storages(&comp, RECIPIENT_PREFIX),
+            [
+                "/__recip_version1.0_#00000000",
+                "/__recip_version1.0_#00000001"
+            ]
+        );
+    }
+}
@@ -2,13 +2,15 @@
 //! MBOX (Email Mailbox) parser
 //!
 //! Parses .MBOX files (mailbox containing multiple emails) into the Unified Document Model.
+//! Each message becomes a page, listed as a message section titled with its subject.
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use mail_parser::MessageParser;
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, Rect, ShapeStyle, TextBlock, TextRun, TextStyle,
+        ContentBlock, Dimensions, Document, MessageHeaders, Page, PageMetadata, Rect, Section,
+        SectionKind, ShapeStyle, TextBlock, TextDirection, TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
@@ -17,6 +19,8 @@ use prism_core::{
 };
 use tracing::{debug, info};
 
+use super::thread::message_headers;
+
 /// MBOX mailbox parser
 #[derive(Debug, Clone)]
 pub struct MboxParser;
@@ -31,18 +35,21 @@ impl MboxParser {
     /// Format email headers as text content
     fn format_email_header(&self, label: &str, value: &str) -> TextRun {
         TextRun {
-            text: format!("{}: {}\n", label, value),
+            text: format!("{label}: {value}\n").into(),
             style: TextStyle {
                 bold: label == "From" || label == "To" || label == "Subject",
                 ..Default::default()
             },
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
 
-    /// Parse a single message from MBOX format
-    fn parse_message(&self, message_data: &[u8]) -> Result<Vec<TextRun>> {
+    /// Parse a single message from MBOX format, returning its subject, text
+    /// and threading headers
+    fn parse_message(&self, message_data: &[u8]) -> Result<(String, Vec<TextRun>, MessageHeaders)> {
         let message = MessageParser::default()
             .parse(message_data)
             .ok_or_else(|| Error::ParseError("Failed to parse message".to_string()))?;
@@ -114,10 +121,12 @@ impl MboxParser {
         text_runs.push(TextRun {
             text: "
 "
-            .to_string(),
+            .into(),
             style: Default::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         });
 
         // Extract body text
@@ -130,13 +139,16 @@ impl MboxParser {
         };
 
         text_runs.push(TextRun {
-            text: body_text,
+            text: body_text.into(),
             style: Default::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         });
 
-        Ok(text_runs)
+        let subject = message.subject().unwrap_or("(no subject)").to_string();
+        Ok((subject, text_runs, message_headers(&message)))
     }
 }
 
@@ -172,6 +184,7 @@ impl Parser for MboxParser {
         let content = String::from_utf8_lossy(&data);
         let mut pages = Vec::new();
         let mut page_number = 1;
+        let mut sections = Vec::new();
 
         // Split by "From " lines (MBOX delimiter)
         let messages: Vec<&str> = content
@@ -200,23 +213,28 @@ impl Parser for MboxParser {
                 let message_data = &message_text[msg_start + 1..];
 
                 match self.parse_message(message_data.as_bytes()) {
-                    Ok(text_runs) => {
+                    Ok((subject, text_runs, headers)) => {
                         let text_block = TextBlock {
                             bounds: Rect::new(0.0, 0.0, 0.0, 0.0), // No layout info in MBOX
                             runs: text_runs,
                             paragraph_style: None,
                             style: ShapeStyle::default(),
                             rotation: 0.0,
+                            direction: TextDirection::Auto,
                         };
 
                         let page = Page {
                             number: page_number,
                             dimensions: Dimensions::LETTER,
                             content: vec![ContentBlock::Text(text_block)],
-                            metadata: Default::default(),
+                            metadata: PageMetadata {
+                                message: Some(headers),
+                                ..PageMetadata::default()
+                            },
                             annotations: Vec::new(),
                         };
 
+                        sections.push(Section::page(SectionKind::Message, subject, &page));
                         pages.push(page);
                         page_number += 1;
                     }
@@ -246,6 +264,7 @@ impl Parser for MboxParser {
         let mut document = Document::new();
         document.pages = pages;
         document.metadata = metadata;
+        document.structure.sections = sections;
 
         info!(
             "Successfully parsed MBOX with {} message(s)",
@@ -287,4 +306,35 @@ mod tests {
         assert_eq!(metadata.name, "MBOX Parser");
         assert!(!metadata.requires_sandbox);
     }
+
+    #[tokio::test]
+    async fn test_message_sections() {
+        let parser = MboxParser::new();
+        let data = Bytes::from_static(
+            b"From a@example.com Mon Jan 01 00:00:00 2024\nFrom: a@example.com\nSubject: Hello\n\nFirst\n\
+              From b@example.com Mon Jan 01 00:00:00 2024\nFrom: b@example.com\n\nSecond\n",
+        );
+        let context = ParseContext {
+            format: parser.format(),
+            filename: None,
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let document = parser.parse(data, context).await.unwrap();
+
+        let sections: Vec<_> = document
+            .structure
+            .sections
+            .iter()
+            .map(|section| (section.kind, section.title.as_str(), section.start.page))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                (SectionKind::Message, "Hello", 1),
+                (SectionKind::Message, "(no subject)", 2),
+            ]
+        );
+    }
 }
@@ -6,15 +6,25 @@
 //! - MSG: Microsoft Outlook message format
 //! - MBOX: Unix mailbox format (multiple emails)
 //! - VCF: vCard contact format
+//!
+//! [`thread`] groups the parsed messages into conversations; [`tnef`]
+//! unpacks the `winmail.dat` attachments Outlook sends; [`mapi`] reads the
+//! typed properties MSG files store their message, recipients and
+//! attachments in.
 
 pub mod eml;
 pub mod ics;
+pub mod mapi;
 pub mod mbox;
 pub mod msg;
+pub mod rtf;
+pub mod thread;
+pub mod tnef;
 pub mod vcf;
 
 pub use eml::EmlParser;
 pub use ics::IcsParser;
 pub use mbox::MboxParser;
 pub use msg::MsgParser;
+pub use thread::{thread_documents, Threads};
 pub use vcf::VcfParser;
@@ -2,20 +2,35 @@
 //! MSG (Outlook Message) parser
 //!
 //! Parses .MSG files (Microsoft Outlook message format) into the Unified Document Model.
+//! Properties of the message, its recipients and its attachments are read
+//! with their types from their storages (see [`mapi`]), so that times are
+//! real timestamps and recipients keep their name, address and kind.
+//!
+//! HTML bodies, whether stored as HTML or as HTML encapsulated in the
+//! compressed RTF body, are parsed as HTML so that the message keeps its
+//! formatting.
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use cfb::CompoundFile;
+use chrono::{DateTime, Utc};
 use prism_core::{
-    document::{ContentBlock, Dimensions, Document, Page, TextBlock, TextRun, TextStyle},
+    document::{
+        Attachment, ContentBlock, Dimensions, Document, MessageHeaders, Page, PageMetadata,
+        Recipient, RecipientKind, TextBlock, TextRun, TextStyle,
+    },
     error::{Error, Result},
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
 };
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek};
 use tracing::{debug, info};
 
+use super::mapi::{self, Properties};
+use super::{rtf, tnef};
+use crate::text::HtmlParser;
+
 /// MSG Outlook message parser
 #[derive(Debug, Clone)]
 pub struct MsgParser;
@@ -30,121 +45,178 @@ impl MsgParser {
     /// Format email headers as text content
     fn format_email_header(&self, label: &str, value: &str) -> TextRun {
         TextRun {
-            text: format!("{}: {}\n", label, value),
+            text: format!("{label}: {value}\n").into(),
             style: TextStyle {
                 bold: label == "From" || label == "To" || label == "Subject",
                 ..Default::default()
             },
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
+}
 
-    /// Extract string property from MSG file
-    fn extract_string_property(
-        &self,
-        comp: &mut CompoundFile<Cursor<&[u8]>>,
-        prop_path: &str,
-    ) -> Option<String> {
-        comp.open_stream(prop_path).ok().and_then(|mut stream| {
-            use std::io::Read;
-            let mut buffer = Vec::new();
-            stream.read_to_end(&mut buffer).ok()?;
-
-            // MSG properties are often UTF-16LE encoded
-            if buffer.len() >= 2 && buffer.len() % 2 == 0 {
-                // Try UTF-16LE first
-                let utf16_chars: Vec<u16> = buffer
-                    .chunks_exact(2)
-                    .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                    .take_while(|&c| c != 0) // Stop at null terminator
-                    .collect();
-
-                if let Ok(s) = String::from_utf16(&utf16_chars) {
-                    return Some(s);
-                }
-            }
+/// The body of the message, preferring the formatted one
+///
+/// The HTML body (`PR_BODY_HTML`) is used as is. Messages written in HTML
+/// and stored only as compressed RTF (`PR_RTF_COMPRESSED`) get the HTML the
+/// RTF encapsulates back; other RTF bodies give their text when there is no
+/// plain text body (`PR_BODY`).
+fn body(message: &Properties) -> MessageBody {
+    let html = message
+        .binary(mapi::BODY_HTML)
+        .map(|html| String::from_utf8_lossy(html).into_owned())
+        .or_else(|| message.string(mapi::BODY_HTML).map(str::to_string))
+        .filter(|html| !html.trim().is_empty());
+    if let Some(html) = html {
+        return MessageBody::Html(html);
+    }
 
-            // Fallback to UTF-8
-            String::from_utf8(buffer.into_iter().take_while(|&b| b != 0).collect()).ok()
-        })
+    let rtf = message
+        .binary(mapi::RTF_COMPRESSED)
+        .and_then(|data| rtf::decompress(data).ok());
+    if let Some(html) = rtf.as_deref().and_then(rtf::to_html) {
+        return MessageBody::Html(html);
     }
+    message
+        .string(mapi::BODY)
+        .map(str::to_string)
+        .or_else(|| rtf.as_deref().map(rtf::to_text))
+        .filter(|body| !body.trim().is_empty())
+        .map_or_else(
+            || MessageBody::Text(String::from("[No message body]")),
+            MessageBody::Text,
+        )
+}
 
-    /// Extract attachments from MSG file
-    fn extract_attachments(
-        &self,
-        comp: &mut CompoundFile<Cursor<&[u8]>>,
-    ) -> Vec<prism_core::document::Attachment> {
-        let mut attachments = Vec::new();
-
-        // CFB crate doesn't easily list root entries matching a pattern in a way that gives us the list of attachments.
-        // We have to iterate through entries.
-        // Typically attachments are named "__attach_version1.0_#" where # is the index.
-        // We can try probing for indices 0..N until we fail.
-
-        for i in 0..100 {
-            // Limit to 100 attachments for sanity
-            let attach_storage_name = format!("__attach_version1.0_{:08}", i);
-
-            // Check if this storage exists (by trying to read a property inside it?)
-            // Or just check if valid directory.
-            if comp.is_storage(&attach_storage_name) {
-                // Open the storage path, but `cfb` crate access is usually absolute paths.
-                // We can extract properties relative to this storage.
-
-                let base = &attach_storage_name;
-
-                // Filename: 0x3707 (Long) or 0x3704 (Short)
-                let filename = self
-                    .extract_string_property(comp, &format!("{}/__substg1.0_3707001F", base))
-                    .or_else(|| {
-                        self.extract_string_property(
-                            comp,
-                            &format!("{}/__substg1.0_3704001F", base),
-                        )
-                    })
-                    .unwrap_or_else(|| format!("attachment_{}", i));
-
-                // Mime Type: 0x370E
-                let mime_type =
-                    self.extract_string_property(comp, &format!("{}/__substg1.0_370E001F", base));
-
-                // Data: 0x3701 (Binary - 0102)
-                let data_path = format!("{}/__substg1.0_37010102", base);
-                let data = if let Ok(mut stream) = comp.open_stream(&data_path) {
-                    use std::io::Read;
-                    let mut buf = Vec::new();
-                    if stream.read_to_end(&mut buf).is_ok() {
-                        buf
-                    } else {
-                        Vec::new()
-                    }
-                } else {
-                    Vec::new()
-                };
-
-                if !data.is_empty() {
-                    attachments.push(prism_core::document::Attachment {
-                        filename,
-                        mime_type,
-                        description: None,
-                        data,
-                        created: None,
-                        modified: None,
-                    });
-                }
-            } else {
-                // Attachments are sequential, if 0 exists but 1 doesn't, we are done?
-                // Mostly yes, but some implementations might be sparse. checking a few ahead?
-                // Standard is sequential.
-                break;
+/// Sender of the message: its display name, or else its address
+fn sender(message: &Properties) -> Option<String> {
+    [
+        mapi::SENDER_NAME,
+        mapi::SENDER_SMTP_ADDRESS,
+        mapi::SENDER_EMAIL_ADDRESS,
+    ]
+    .into_iter()
+    .find_map(|id| message.string(id))
+    .map(str::to_string)
+}
+
+/// Time the message was sent, or else received
+fn sent_time(message: &Properties) -> Option<DateTime<Utc>> {
+    message
+        .time(mapi::CLIENT_SUBMIT_TIME)
+        .or_else(|| message.time(mapi::MESSAGE_DELIVERY_TIME))
+}
+
+/// Recipients of the message, from its recipient storages
+///
+/// Exchange recipients carry an X.500 distinguished name as their address,
+/// so the SMTP address is preferred and other addresses are only kept when
+/// they look like one.
+fn recipients<F: Read + Seek>(comp: &mut CompoundFile<F>) -> Vec<Recipient> {
+    mapi::storages(comp, mapi::RECIPIENT_PREFIX)
+        .iter()
+        .map(|storage| {
+            let recipient = Properties::read(comp, storage, mapi::ENTRY_HEADER);
+            let email = recipient.string(mapi::SMTP_ADDRESS).or_else(|| {
+                recipient
+                    .string(mapi::EMAIL_ADDRESS)
+                    .filter(|email| email.contains('@'))
+            });
+            // The high bits flag recipients that were already sent to
+            let kind = match recipient
+                .integer(mapi::RECIPIENT_TYPE)
+                .map(|kind| kind & 0x0FFF_FFFF)
+            {
+                Some(2) => RecipientKind::Cc,
+                Some(3) => RecipientKind::Bcc,
+                _ => RecipientKind::To,
+            };
+            Recipient {
+                name: recipient.string(mapi::DISPLAY_NAME).map(str::to_string),
+                email: email.map(str::to_string),
+                kind,
             }
-        }
+        })
+        .filter(|recipient| recipient.name.is_some() || recipient.email.is_some())
+        .collect()
+}
 
-        attachments
+/// Threading headers of the message
+fn message_headers(message: &Properties, recipients: Vec<Recipient>) -> MessageHeaders {
+    let id = |value: &str| {
+        value
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string()
+    };
+    MessageHeaders {
+        message_id: message.string(mapi::INTERNET_MESSAGE_ID).map(id),
+        in_reply_to: message.string(mapi::IN_REPLY_TO_ID).map(id),
+        references: message
+            .string(mapi::INTERNET_REFERENCES)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(id)
+            .filter(|reference| !reference.is_empty())
+            .collect(),
+        subject: message.string(mapi::SUBJECT).map(str::to_string),
+        from: sender(message),
+        date: sent_time(message),
+        recipients,
     }
 }
 
+/// Attachments of the message, from its attachment storages
+fn attachments<F: Read + Seek>(comp: &mut CompoundFile<F>) -> Vec<Attachment> {
+    mapi::storages(comp, mapi::ATTACHMENT_PREFIX)
+        .iter()
+        .enumerate()
+        .filter_map(|(i, storage)| {
+            let attachment = Properties::read(comp, storage, mapi::ENTRY_HEADER);
+            // Embedded messages and OLE objects are storages, not data
+            let data = attachment
+                .binary(mapi::ATTACH_DATA)
+                .filter(|data| !data.is_empty())?;
+            Some(Attachment {
+                filename: attachment
+                    .string(mapi::ATTACH_LONG_FILENAME)
+                    .or_else(|| attachment.string(mapi::ATTACH_FILENAME))
+                    .map_or_else(|| format!("attachment_{i}"), str::to_string),
+                mime_type: attachment.string(mapi::ATTACH_MIME_TAG).map(str::to_string),
+                description: None,
+                data: data.to_vec(),
+                created: attachment.time(mapi::CREATION_TIME),
+                modified: attachment.time(mapi::LAST_MODIFICATION_TIME),
+                document: None,
+            })
+        })
+        .collect()
+}
+
+/// Body of a message
+enum MessageBody {
+    /// HTML markup
+    Html(String),
+    /// Plain text
+    Text(String),
+}
+
+/// Parse an HTML body into a document of one page
+async fn html_body(html: String, context: &ParseContext) -> Result<Document> {
+    let context = ParseContext {
+        format: Format::html(),
+        filename: None,
+        size: html.len(),
+        options: context.options.clone(),
+        progress: None,
+    };
+    HtmlParser::new().parse(Bytes::from(html), context).await
+}
+
 impl Default for MsgParser {
     fn default() -> Self {
         Self::new()
@@ -178,77 +250,72 @@ impl Parser for MsgParser {
         // Open as CFB file
         let cursor = Cursor::new(&data[..]);
         let mut comp = CompoundFile::open(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open MSG as CFB: {}", e)))?;
-
-        let mut text_runs = Vec::new();
+            .map_err(|e| Error::corrupt("MSG", format!("Failed to open OLE2 container: {e}")))?;
 
-        // Extract common MSG properties
-        // Property paths in MSG files follow the pattern: __substg1.0_XXXXYYYY
-        // where XXXX is the property tag and YYYY is the data type
-
-        // Sender name (0x0C1A - SENDER_NAME, 001F = Unicode string)
-        if let Some(sender_name) = self.extract_string_property(&mut comp, "__substg1.0_0C1A001F") {
-            text_runs.push(self.format_email_header("From", &sender_name));
-        } else if let Some(sender_email) =
-            self.extract_string_property(&mut comp, "__substg1.0_0C1F001F")
-        {
-            text_runs.push(self.format_email_header("From", &sender_email));
-        }
-
-        // Sent time (0x0039 - CLIENT_SUBMIT_TIME)
-        if let Some(sent_time) = self.extract_string_property(&mut comp, "__substg1.0_00390040") {
-            text_runs.push(self.format_email_header("Sent", &sent_time));
-        }
+        let message = Properties::read(&mut comp, "/", mapi::MESSAGE_HEADER);
+        let recipients = recipients(&mut comp);
 
-        // Recipient (0x0E04 - DISPLAY_TO, 001F = Unicode string)
-        if let Some(to) = self.extract_string_property(&mut comp, "__substg1.0_0E04001F") {
-            text_runs.push(self.format_email_header("To", &to));
+        let mut text_runs = Vec::new();
+        if let Some(sender) = sender(&message) {
+            text_runs.push(self.format_email_header("From", &sender));
         }
-
-        // CC (0x0E03 - DISPLAY_CC)
-        if let Some(cc) = self.extract_string_property(&mut comp, "__substg1.0_0E03001F") {
-            text_runs.push(self.format_email_header("Cc", &cc));
+        if let Some(sent) = sent_time(&message) {
+            text_runs.push(self.format_email_header("Sent", &sent.to_rfc3339()));
         }
 
-        // BCC (0x0E02 - DISPLAY_BCC)
-        if let Some(bcc) = self.extract_string_property(&mut comp, "__substg1.0_0E02001F") {
-            text_runs.push(self.format_email_header("Bcc", &bcc));
+        // Recipient lines list the recipients of each kind, or else the
+        // display lists Outlook keeps for messages without recipient
+        // storages
+        for (label, kind, display) in [
+            ("To", RecipientKind::To, mapi::DISPLAY_TO),
+            ("Cc", RecipientKind::Cc, mapi::DISPLAY_CC),
+            ("Bcc", RecipientKind::Bcc, mapi::DISPLAY_BCC),
+        ] {
+            let listed = recipients
+                .iter()
+                .filter(|recipient| recipient.kind == kind)
+                .map(Recipient::display)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if !listed.is_empty() {
+                text_runs.push(self.format_email_header(label, &listed));
+            } else if let Some(listed) = message.string(display) {
+                text_runs.push(self.format_email_header(label, listed));
+            }
         }
 
-        // Subject (0x0037 - SUBJECT, 001F = Unicode string)
-        if let Some(subject) = self.extract_string_property(&mut comp, "__substg1.0_0037001F") {
-            text_runs.push(self.format_email_header("Subject", &subject));
+        if let Some(subject) = message.string(mapi::SUBJECT) {
+            text_runs.push(self.format_email_header("Subject", subject));
         }
 
         // Add empty line separator
         text_runs.push(TextRun {
-            text: "\n".to_string(),
+            text: "\n".into(),
             style: Default::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         });
 
-        // Body (0x1000 - BODY, 001F = Unicode string)
-        let body_text = if let Some(body) =
-            self.extract_string_property(&mut comp, "__substg1.0_1000001F")
-        {
-            body
-        } else if let Some(body) = self.extract_string_property(&mut comp, "__substg1.0_10130102") {
-            // HTML body (0x1013, 0102 = binary) - simplified handling for now, raw string fallback
-            body
-        } else {
-            String::from("[No message body]")
-        };
-
-        text_runs.push(TextRun {
-            text: body_text.clone(),
-            style: Default::default(),
-            bounds: None,
-            char_positions: None,
-        });
+        // An HTML body is parsed as HTML and follows the headers; a text
+        // body goes on with them
+        let mut html = None;
+        match body(&message) {
+            MessageBody::Html(body) => html = Some(html_body(body, &context).await?),
+            MessageBody::Text(body) => text_runs.push(TextRun {
+                text: body.as_str().into(),
+                style: TextStyle::default(),
+                bounds: None,
+                char_positions: None,
+                link: None,
+                revision: None,
+            }),
+        }
 
-        // Extract Attachments
-        let attachments = self.extract_attachments(&mut comp);
+        // Extract Attachments, unpacking any TNEF stream among them
+        let mut attachments = attachments(&mut comp);
+        tnef::expand(&mut attachments);
 
         // Create text block
         let text_block = TextBlock {
@@ -257,26 +324,37 @@ impl Parser for MsgParser {
             paragraph_style: None,
             style: prism_core::document::ShapeStyle::default(),
             rotation: 0.0,
+            direction: prism_core::document::TextDirection::Auto,
         };
 
         // Create page
-        let page = Page {
+        let mut page = Page {
             number: 1,
             dimensions: Dimensions::LETTER,
             content: vec![ContentBlock::Text(text_block)],
-            metadata: Default::default(),
+            metadata: PageMetadata {
+                message: Some(message_headers(&message, recipients)),
+                ..PageMetadata::default()
+            },
             annotations: Vec::new(),
             // attachments can also be linked here? No, they are document level in UDM.
         };
+        let mut images = Vec::new();
+        if let Some(mut html) = html {
+            if let Some(body) = html.pages.pop() {
+                page.content.extend(body.content);
+                page.annotations.extend(body.annotations);
+            }
+            images = html.resources.images;
+        }
 
         // Create metadata
-        let mut metadata = Metadata::default();
-        if let Some(subject) = self.extract_string_property(&mut comp, "__substg1.0_0037001F") {
-            metadata.title = Some(subject);
-        }
-        if let Some(sender) = self.extract_string_property(&mut comp, "__substg1.0_0C1A001F") {
-            metadata.author = Some(sender);
-        }
+        let mut metadata = Metadata {
+            title: message.string(mapi::SUBJECT).map(str::to_string),
+            author: sender(&message),
+            created: sent_time(&message),
+            ..Metadata::default()
+        };
         metadata.add_custom("format", "MSG");
         metadata.add_custom("attachment_count", attachments.len() as i64);
 
@@ -285,6 +363,7 @@ impl Parser for MsgParser {
         document.pages = vec![page];
         document.metadata = metadata;
         document.attachments = attachments;
+        document.resources.images = images;
 
         info!("Successfully parsed MSG email");
 
@@ -315,6 +394,158 @@ mod tests {
         assert!(parser.can_parse(msg_header));
     }
 
+    /// An MSG file holding the given property streams, in storages of
+    /// their own when their paths say so
+    fn msg(streams: &[(&str, &[u8])]) -> Bytes {
+        let mut comp = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in streams {
+            use std::io::Write;
+            if let Some((storage, _)) = name.rsplit_once('/') {
+                if !comp.is_storage(storage) {
+                    comp.create_storage(storage).unwrap();
+                }
+            }
+            comp.create_stream(name).unwrap().write_all(data).unwrap();
+        }
+        Bytes::from(comp.into_inner().into_inner())
+    }
+
+    async fn parse(data: Bytes) -> Document {
+        let parser = MsgParser::new();
+        let context = ParseContext {
+            format: parser.format(),
+            filename: None,
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        parser.parse(data, context).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bodies() {
+        // The compressed RTF example of MS-OXRTFCP
+        let rtf = [
+            0x2d, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x4c, 0x5a, 0x46, 0x75, 0xf1, 0xc5,
+            0xc7, 0xa7, 0x03, 0x00, 0x0a, 0x00, 0x72, 0x63, 0x70, 0x67, 0x31, 0x32, 0x35, 0x42,
+            0x32, 0x0a, 0xf3, 0x20, 0x68, 0x65, 0x6c, 0x09, 0x00, 0x20, 0x62, 0x77, 0x05, 0xb0,
+            0x6c, 0x64, 0x7d, 0x0a, 0x80, 0x0f, 0xa0,
+        ];
+        let document = parse(msg(&[("__substg1.0_10090102", &rtf)])).await;
+        assert!(document.extract_text().ends_with("hello world"));
+
+        let html = b"<html><body><p>Quarterly <b>numbers</b></p></body></html>";
+        let document = parse(msg(&[
+            ("__substg1.0_10090102", &rtf),
+            ("__substg1.0_10130102", html),
+        ]))
+        .await;
+        let page = &document.pages[0];
+        assert_eq!(page.content.len(), 2);
+        let ContentBlock::Text(body) = &page.content[1] else {
+            panic!("expected the HTML body as text");
+        };
+        assert_eq!(body.extract_text(), "Quarterly numbers");
+        assert!(body
+            .runs
+            .iter()
+            .any(|run| run.style.bold && &*run.text == "numbers"));
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    /// A property stream of `header` bytes of header and one fixed-length
+    /// property
+    fn properties(header: usize, tag: u32, value: u64) -> Vec<u8> {
+        let mut data = vec![0; header];
+        data.extend(tag.to_le_bytes());
+        data.extend(6_u32.to_le_bytes());
+        data.extend(value.to_le_bytes());
+        data
+    }
+
+    #[tokio::test]
+    async fn test_recipients() {
+        // 2024-03-01T10:00:00Z as a FILETIME
+        let sent = properties(32, 0x0039_0040, 133_537_608_000_000_000);
+        let to = "__recip_version1.0_#00000000";
+        let cc = "__recip_version1.0_#00000001";
+        let attachment = "__attach_version1.0_#00000000";
+        let streams: Vec<(String, Vec<u8>)> = vec![
+            ("__properties_version1.0".into(), sent),
+            ("__substg1.0_0037001F".into(), utf16("Budget")),
+            ("__substg1.0_0C1A001F".into(), utf16("Ana")),
+            ("__substg1.0_0E04001F".into(), utf16("Bo")),
+            ("__substg1.0_1000001F".into(), utf16("See attached")),
+            (format!("{to}/__substg1.0_3001001F"), utf16("Bo")),
+            (
+                format!("{to}/__substg1.0_39FE001F"),
+                utf16("bo@example.com"),
+            ),
+            (
+                format!("{to}/__substg1.0_3003001F"),
+                utf16("/O=EXAMPLE/CN=BO"),
+            ),
+            (
+                format!("{to}/__properties_version1.0"),
+                properties(8, 0x0C15_0003, 1),
+            ),
+            (
+                format!("{cc}/__substg1.0_3003001E"),
+                b"cy@example.com".to_vec(),
+            ),
+            (
+                format!("{cc}/__properties_version1.0"),
+                properties(8, 0x0C15_0003, 2),
+            ),
+            (
+                format!("{attachment}/__substg1.0_3707001F"),
+                utf16("notes.txt"),
+            ),
+            (
+                format!("{attachment}/__substg1.0_37010102"),
+                b"Totals".to_vec(),
+            ),
+        ];
+        let streams: Vec<(&str, &[u8])> = streams
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let document = parse(msg(&streams)).await;
+
+        let text = document.extract_text();
+        assert!(text.contains("Sent: 2024-03-01T10:00:00+00:00"));
+        assert!(text.contains("To: Bo <bo@example.com>"));
+        assert!(text.contains("Cc: cy@example.com"));
+        assert_eq!(
+            document.metadata.created,
+            document.pages[0].metadata.message.as_ref().unwrap().date
+        );
+        let headers = document.pages[0].metadata.message.as_ref().unwrap();
+        assert_eq!(headers.from.as_deref(), Some("Ana"));
+        assert_eq!(headers.subject.as_deref(), Some("Budget"));
+        assert_eq!(
+            headers.recipients,
+            [
+                Recipient {
+                    name: Some("Bo".to_string()),
+                    email: Some("bo@example.com".to_string()),
+                    kind: RecipientKind::To,
+                },
+                Recipient {
+                    name: None,
+                    email: Some("cy@example.com".to_string()),
+                    kind: RecipientKind::Cc,
+                },
+            ]
+        );
+        assert_eq!(document.attachments.len(), 1);
+        assert_eq!(document.attachments[0].filename, "notes.txt");
+        assert_eq!(document.attachments[0].data, b"Totals");
+    }
+
     #[test]
     fn test_parser_metadata() {
         let parser = MsgParser::new();
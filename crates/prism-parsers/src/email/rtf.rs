@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! RTF message bodies
+//!
+//! Outlook keeps the formatted body of a message as compressed RTF
+//! (`PR_RTF_COMPRESSED`), both in MSG files and in the TNEF streams it
+//! attaches to outgoing mail. [`decompress`] undoes the `LZFu` compression of
+//! [MS-OXRTFCP]; [`to_text`] reads the text of the resulting RTF, dropping
+//! formatting, font and colour tables and other destinations that hold no
+//! text, and [`to_html`] recovers the HTML of messages written in HTML.
+//!
+//! [MS-OXRTFCP]: https://learn.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxrtfcp
+
+use prism_core::error::{Error, Result};
+
+/// Compression type of `LZFu`-compressed RTF
+const COMPRESSED: u32 = 0x7546_5A4C;
+
+/// Compression type of RTF stored as is
+const UNCOMPRESSED: u32 = 0x414C_454D;
+
+/// Initial content of the `LZFu` dictionary
+const DICTIONARY: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \
+\\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier\
+{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+
+/// Destinations whose content is not part of the text
+const SKIPPED: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "object",
+    "fldinst",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "themedata",
+    "colorschememapping",
+    "latentstyles",
+    "datastore",
+    "xmlnstbl",
+    "header",
+    "footer",
+];
+
+/// Decompress a `PR_RTF_COMPRESSED` value into RTF
+///
+/// # Errors
+///
+/// Returns an error if the header is truncated or names an unknown
+/// compression type.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let header = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or_else(|| Error::corrupt("RTF", "Truncated compressed RTF header"))
+    };
+    let raw_size = header(4)? as usize;
+    let body = &data[16.min(data.len())..];
+    match header(8)? {
+        UNCOMPRESSED => return Ok(body[..raw_size.min(body.len())].to_vec()),
+        COMPRESSED => {}
+        kind => {
+            return Err(Error::corrupt(
+                "RTF",
+                format!("Unknown RTF compression type {kind:#010x}"),
+            ))
+        }
+    }
+
+    let mut dictionary = [0_u8; 4096];
+    dictionary[..DICTIONARY.len()].copy_from_slice(DICTIONARY);
+    let mut write = DICTIONARY.len();
+    let mut out = Vec::with_capacity(raw_size.min(1 << 24));
+    let mut input = body.iter().copied();
+    'runs: while let Some(control) = input.next() {
+        for bit in 0..8 {
+            if control & (1 << bit) == 0 {
+                let Some(byte) = input.next() else {
+                    break 'runs;
+                };
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % dictionary.len();
+                continue;
+            }
+            // A reference: 12-bit dictionary offset, 4-bit length - 2
+            let (Some(high), Some(low)) = (input.next(), input.next()) else {
+                break 'runs;
+            };
+            let reference = u16::from_be_bytes([high, low]);
+            let mut offset = usize::from(reference >> 4);
+            if offset == write {
+                break 'runs;
+            }
+            for _ in 0..usize::from(reference & 0xF) + 2 {
+                let byte = dictionary[offset];
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % dictionary.len();
+                offset = (offset + 1) % dictionary.len();
+            }
+        }
+    }
+    out.truncate(raw_size);
+    Ok(out)
+}
+
+/// Group state of the RTF reader
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    /// Whether the group's content is left out
+    skip: bool,
+    /// Whether the group holds a tag of encapsulated HTML
+    tag: bool,
+    /// Whether `\htmlrtf` marks the content as RTF-only
+    rtf_only: bool,
+    /// Number of fallback characters following each `\u`
+    fallback: usize,
+}
+
+/// The text of an RTF document, one line per paragraph
+#[must_use]
+pub fn to_text(rtf: &[u8]) -> String {
+    read(rtf, false)
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// The HTML an RTF document encapsulates, or `None` if it was not converted
+/// from HTML
+///
+/// Outlook stores HTML mail as RTF marked `\fromhtml1`: the HTML tags are
+/// kept in `\*\htmltag` destinations and the RTF needed to display it
+/// without HTML is wrapped in `\htmlrtf` ... `\htmlrtf0`; see
+/// [MS-OXRTFEX](https://learn.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxrtfex).
+#[must_use]
+pub fn to_html(rtf: &[u8]) -> Option<String> {
+    let header = &rtf[..rtf.len().min(1024)];
+    header
+        .windows(10)
+        .any(|window| window == b"\\fromhtml1")
+        .then(|| read(rtf, true))
+}
+
+/// The text of `rtf`, or the HTML it encapsulates when `html` is set
+fn read(rtf: &[u8], html: bool) -> String {
+    let mut text = String::new();
+    let mut group = Group {
+        skip: false,
+        tag: false,
+        rtf_only: false,
+        fallback: 1,
+    };
+    let mut stack = Vec::new();
+    // Whether the next control word names an optional destination
+    let mut starred = false;
+    // Fallback characters still to drop after a `\u`
+    let mut pending = 0;
+    let mut i = 0;
+    while i < rtf.len() {
+        let byte = rtf[i];
+        i += 1;
+        let hidden = group.skip || (html && group.rtf_only && !group.tag);
+        match byte {
+            b'{' => stack.push(group),
+            b'}' => group = stack.pop().unwrap_or(group),
+            b'\r' | b'\n' => {}
+            b'\\' => {
+                let Some(&next) = rtf.get(i) else {
+                    break;
+                };
+                if next.is_ascii_alphabetic() {
+                    let (word, param, end) = control_word(rtf, i);
+                    i = end;
+                    if std::mem::take(&mut starred) {
+                        if html && word == "htmltag" {
+                            group.tag = true;
+                        } else {
+                            group.skip = true;
+                        }
+                    }
+                    if group.skip {
+                        continue;
+                    }
+                    if SKIPPED.contains(&word) {
+                        group.skip = true;
+                        continue;
+                    }
+                    match (word, param) {
+                        ("htmlrtf", param) => group.rtf_only = param != Some(0),
+                        ("uc", Some(count)) => group.fallback = usize::try_from(count).unwrap_or(0),
+                        ("u", Some(code)) => {
+                            // Values above 32767 are written as negative numbers
+                            let code = if code < 0 { code + 65536 } else { code };
+                            if let Some(c) = u32::try_from(code).ok().and_then(char::from_u32) {
+                                if !hidden {
+                                    text.push(c);
+                                }
+                            }
+                            pending = group.fallback;
+                        }
+                        _ => {
+                            if let Some(replacement) = control_text(word).filter(|_| !hidden) {
+                                text.push_str(replacement);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                i += 1;
+                match next {
+                    b'*' => starred = true,
+                    b'\'' => {
+                        let hex = rtf
+                            .get(i..i + 2)
+                            .and_then(|hex| std::str::from_utf8(hex).ok());
+                        i += 2;
+                        if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                            if pending > 0 {
+                                pending -= 1;
+                            } else if !hidden {
+                                text.push(windows_1252(byte));
+                            }
+                        }
+                    }
+                    b'\r' | b'\n' if !hidden => text.push('\n'),
+                    b'~' if !hidden => text.push('\u{a0}'),
+                    b'_' if !hidden => text.push('-'),
+                    b'\\' | b'{' | b'}' if !hidden => text.push(char::from(next)),
+                    _ => {}
+                }
+            }
+            _ if pending > 0 => pending -= 1,
+            _ if !hidden => text.push(windows_1252(byte)),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// The control word starting at `start`, its numeric parameter and the
+/// position just past it, including the space that may end it
+fn control_word(rtf: &[u8], start: usize) -> (&str, Option<i32>, usize) {
+    let mut end = start;
+    while rtf.get(end).is_some_and(u8::is_ascii_alphabetic) {
+        end += 1;
+    }
+    let word = std::str::from_utf8(&rtf[start..end]).unwrap_or_default();
+    let digits = end;
+    if rtf.get(end) == Some(&b'-') {
+        end += 1;
+    }
+    while rtf.get(end).is_some_and(u8::is_ascii_digit) {
+        end += 1;
+    }
+    let param = std::str::from_utf8(&rtf[digits..end])
+        .ok()
+        .and_then(|param| param.parse().ok());
+    if rtf.get(end) == Some(&b' ') {
+        end += 1;
+    }
+    (word, param, end)
+}
+
+/// Text written for a control word, if it stands for any
+fn control_text(word: &str) -> Option<&'static str> {
+    Some(match word {
+        "par" | "line" | "row" => "\n",
+        "tab" | "cell" => "\t",
+        "emdash" => "\u{2014}",
+        "endash" => "\u{2013}",
+        "bullet" => "\u{2022}",
+        "lquote" => "\u{2018}",
+        "rquote" => "\u{2019}",
+        "ldblquote" => "\u{201c}",
+        "rdblquote" => "\u{201d}",
+        _ => return None,
+    })
+}
+
+/// A byte of the Windows-1252 code page, which RTF bodies default to
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}',
+        '\u{8f}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        // The compressed example of MS-OXRTFCP
+        let data = [
+            0x2d, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x4c, 0x5a, 0x46, 0x75, 0xf1, 0xc5,
+            0xc7, 0xa7, 0x03, 0x00, 0x0a, 0x00, 0x72, 0x63, 0x70, 0x67, 0x31, 0x32, 0x35, 0x42,
+            0x32, 0x0a, 0xf3, 0x20, 0x68, 0x65, 0x6c, 0x09, 0x00, 0x20, 0x62, 0x77, 0x05, 0xb0,
+            0x6c, 0x64, 0x7d, 0x0a, 0x80, 0x0f, 0xa0,
+        ];
+        assert_eq!(DICTIONARY.len(), 207);
+        let rtf = decompress(&data).unwrap();
+        assert_eq!(rtf, b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n");
+        assert_eq!(to_text(&rtf), "hello world");
+    }
+
+    #[test]
+    fn test_to_text() {
+        let rtf = concat!(
+            r"{\rtf1\ansi{\fonttbl{\f0 Arial;}}{\*\generator Riched20;}\uc1",
+            "\r\n",
+            r"\pard Caf\'e9 \b costs\b0  \u8364? 5\par",
+            r"{\field{\*\fldinst HYPERLINK x}{\fldrslt link}}\tab end\emdash\par}",
+        );
+        assert_eq!(to_text(rtf.as_bytes()), "Café costs € 5\nlink\tend\u{2014}");
+        assert_eq!(to_html(rtf.as_bytes()), None);
+    }
+
+    #[test]
+    fn test_to_html() {
+        let rtf = concat!(
+            r"{\rtf1\ansi\fbidis\ansicpg1252\deff0\fromhtml1{\fonttbl{\f0\fswiss Arial;}}",
+            r"{\*\htmltag19 <html>}{\*\htmltag34 <head>}{\*\htmltag41 </head>}",
+            r"{\*\htmltag50 <body>}\htmlrtf \pard\plain\htmlrtf0 ",
+            r#"{\*\htmltag64 <p style="color:red">}\htmlrtf {\htmlrtf0 Red \'e9"#,
+            r"{\*\htmltag84 &nbsp;}\htmlrtf\'a0\htmlrtf0 text",
+            r"\htmlrtf\par}\htmlrtf0 {\*\htmltag72 </p>}",
+            r"{\*\htmltag58 </body>}{\*\htmltag27 </html>}}",
+        );
+        assert_eq!(
+            to_html(rtf.as_bytes()).as_deref(),
+            Some(
+                "<html><head></head><body><p style=\"color:red\">Red é&nbsp;text</p></body></html>"
+            )
+        );
+        assert_eq!(to_text(rtf.as_bytes()), "Red é\u{a0}text");
+    }
+}
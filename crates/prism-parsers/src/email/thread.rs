@@ -0,0 +1,439 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Email thread reconstruction
+//!
+//! The EML, MBOX and MSG parsers record the `Message-ID`, `In-Reply-To`
+//! and `References` headers of each message in
+//! [`PageMetadata::message`](prism_core::document::PageMetadata::message).
+//! Messages are put in the same conversation when one names the other,
+//! directly or through a message both refer to, so replies to a message
+//! missing from the input still end up together. Each conversation is
+//! ordered by date; messages without a date follow the dated ones in input
+//! order.
+//!
+//! [`thread_documents`] builds one document per conversation from any
+//! number of parsed emails and mailboxes, each message on a page of its
+//! own. [`Threads`] regroups the messages of a single document, such as a
+//! mailbox, into [`SectionKind::Thread`] sections.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mail_parser::{Address, Message};
+use prism_core::document::{
+    Attachment, ContentPosition, Document, MessageHeaders, Page, Recipient, RecipientKind, Section,
+    SectionKind,
+};
+use prism_core::error::Result;
+use prism_core::metadata::Metadata;
+use prism_core::processor::Processor;
+use std::collections::HashMap;
+
+/// Title of a message or conversation without a subject
+const NO_SUBJECT: &str = "(no subject)";
+
+/// Processor regrouping the messages of a document into conversations
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Threads;
+
+#[async_trait]
+impl Processor for Threads {
+    fn name(&self) -> &'static str {
+        "threads"
+    }
+
+    async fn process(&self, document: &mut Document) -> Result<()> {
+        regroup(document);
+        Ok(())
+    }
+}
+
+/// Reorder the pages of `document` conversation by conversation and list
+/// each conversation as a thread section holding its messages
+///
+/// Documents without message headers are left alone.
+pub fn regroup(document: &mut Document) {
+    let headers: Vec<&MessageHeaders> = document
+        .pages
+        .iter()
+        .filter_map(|page| page.metadata.message.as_ref())
+        .collect();
+    if headers.len() != document.pages.len() || headers.is_empty() {
+        return;
+    }
+
+    let order = conversations(&headers);
+    let mut pages: Vec<Option<Page>> = std::mem::take(&mut document.pages)
+        .into_iter()
+        .map(Some)
+        .collect();
+    let mut sections = Vec::with_capacity(order.len());
+    for thread in order {
+        let mut section = Section::new(
+            SectionKind::Thread,
+            String::new(),
+            ContentPosition::new(0, 0),
+            ContentPosition::new(0, 0),
+        );
+        for index in thread {
+            let Some(mut page) = pages[index].take() else {
+                continue;
+            };
+            page.number = u32::try_from(document.pages.len() + 1).unwrap_or(u32::MAX);
+            section.children.push(message_section(&page));
+            document.pages.push(page);
+        }
+        if let (Some(first), Some(last)) = (section.children.first(), section.children.last()) {
+            section.title = thread_subject(&first.title).to_string();
+            section.start = first.start;
+            section.end = last.end;
+        }
+        sections.push(section);
+    }
+    document.structure.sections = sections;
+}
+
+/// One document per conversation among the messages of `documents`
+///
+/// Pages without message headers are left out. The attachments of a
+/// document holding a single message, such as an EML or MSG file, follow
+/// that message into its conversation.
+#[must_use]
+pub fn thread_documents(documents: &[Document]) -> Vec<Document> {
+    // Each message with the index of the document it came from
+    let mut messages: Vec<(usize, &Page, &MessageHeaders)> = Vec::new();
+    for (index, document) in documents.iter().enumerate() {
+        for page in &document.pages {
+            if let Some(headers) = &page.metadata.message {
+                messages.push((index, page, headers));
+            }
+        }
+    }
+    let mut counts = vec![0_usize; documents.len()];
+    for (index, _, _) in &messages {
+        counts[*index] += 1;
+    }
+
+    let headers: Vec<&MessageHeaders> = messages.iter().map(|(_, _, headers)| *headers).collect();
+    conversations(&headers)
+        .into_iter()
+        .map(|thread| {
+            let mut document = Document::new();
+            let mut attachments: Vec<Attachment> = Vec::new();
+            for index in thread {
+                let (source, page, _) = messages[index];
+                let mut page = page.clone();
+                page.number = u32::try_from(document.pages.len() + 1).unwrap_or(u32::MAX);
+                document.structure.sections.push(message_section(&page));
+                document.pages.push(page);
+                if counts[source] == 1 {
+                    attachments.extend(documents[source].attachments.iter().cloned());
+                }
+            }
+            document.metadata = thread_metadata(&document.pages);
+            document.attachments = attachments;
+            document
+        })
+        .collect()
+}
+
+/// Indexes of `headers` grouped into conversations, each in date order,
+/// the conversations ordered by their first message
+fn conversations(headers: &[&MessageHeaders]) -> Vec<Vec<usize>> {
+    // Union-find over messages: each ID seen is owned by the first message
+    // naming it, and every later message naming it joins that message
+    let mut parent: Vec<usize> = (0..headers.len()).collect();
+    let mut owners: HashMap<&str, usize> = HashMap::new();
+    for (index, message) in headers.iter().enumerate() {
+        let ids = message
+            .message_id
+            .iter()
+            .chain(&message.in_reply_to)
+            .chain(&message.references);
+        for id in ids {
+            match owners.get(id.as_str()) {
+                Some(&owner) => union(&mut parent, owner, index),
+                None => {
+                    owners.insert(id, index);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..headers.len() {
+        groups
+            .entry(find(&mut parent, index))
+            .or_default()
+            .push(index);
+    }
+    let date_order = |index: &usize| {
+        let date = headers[*index].date;
+        (date.is_none(), date, *index)
+    };
+    let mut threads: Vec<Vec<usize>> = groups.into_values().collect();
+    for thread in &mut threads {
+        thread.sort_by_key(date_order);
+    }
+    threads.sort_by_key(|thread| date_order(&thread[0]));
+    threads
+}
+
+fn find(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    // The earlier message stays the root, keeping groups in input order
+    parent[a.max(b)] = a.min(b);
+}
+
+/// A message section spanning `page`, titled with the message's subject
+fn message_section(page: &Page) -> Section {
+    let subject = page
+        .metadata
+        .message
+        .as_ref()
+        .and_then(|headers| headers.subject.as_deref())
+        .filter(|subject| !subject.trim().is_empty())
+        .unwrap_or(NO_SUBJECT);
+    Section::page(SectionKind::Message, subject, page)
+}
+
+/// Metadata of a conversation made of the messages on `pages`: titled with
+/// the subject of the first message, written by its sender, created when it
+/// was sent and modified when the last message was
+fn thread_metadata(pages: &[Page]) -> Metadata {
+    let headers: Vec<&MessageHeaders> = pages
+        .iter()
+        .filter_map(|page| page.metadata.message.as_ref())
+        .collect();
+    let mut metadata = Metadata::default();
+    if let Some(first) = headers.first() {
+        metadata.title = Some(thread_subject(first.subject.as_deref().unwrap_or("")).to_string());
+        metadata.author.clone_from(&first.from);
+        metadata.created = first.date;
+        if let Some(id) = first.references.first().or(first.message_id.as_ref()) {
+            metadata.add_custom("thread_id", id.as_str());
+        }
+    }
+    metadata.modified = headers.iter().filter_map(|headers| headers.date).max();
+    metadata.add_custom("format", "Thread");
+    metadata.add_custom(
+        "message_count",
+        i64::try_from(headers.len()).unwrap_or(i64::MAX),
+    );
+    metadata
+}
+
+/// `subject` without its reply and forward prefixes, such as `Re: Fwd: `
+fn thread_subject(subject: &str) -> &str {
+    const PREFIXES: &[&str] = &["re:", "fw:", "fwd:", "aw:", "wg:", "sv:", "vs:"];
+    let mut subject = subject.trim();
+    while let Some(prefix) = PREFIXES.iter().find(|prefix| {
+        subject
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    }) {
+        subject = subject[prefix.len()..].trim_start();
+    }
+    if subject.is_empty() {
+        NO_SUBJECT
+    } else {
+        subject
+    }
+}
+
+/// Threading headers of a parsed RFC 822 message
+pub(crate) fn message_headers(message: &Message<'_>) -> MessageHeaders {
+    let ids = |value: &mail_parser::HeaderValue<'_>| -> Vec<String> {
+        value
+            .as_text_list()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| id.trim_matches(|c: char| c == '<' || c == '>' || c.is_whitespace()))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    MessageHeaders {
+        message_id: message.message_id().map(str::to_string),
+        in_reply_to: ids(message.in_reply_to()).pop(),
+        references: ids(message.references()),
+        subject: message.subject().map(str::to_string),
+        from: message.from().and_then(Address::first).map(|addr| {
+            let email = addr.address.as_deref().unwrap_or_default();
+            match &addr.name {
+                Some(name) => format!("{name} <{email}>"),
+                None => email.to_string(),
+            }
+        }),
+        date: message
+            .date()
+            .and_then(|date| DateTime::<Utc>::from_timestamp(date.to_timestamp(), 0)),
+        recipients: [
+            (message.to(), RecipientKind::To),
+            (message.cc(), RecipientKind::Cc),
+            (message.bcc(), RecipientKind::Bcc),
+        ]
+        .into_iter()
+        .filter_map(|(addresses, kind)| Some((addresses?, kind)))
+        .flat_map(|(addresses, kind)| {
+            addresses.iter().map(move |addr| Recipient {
+                name: addr.name.as_deref().map(str::to_string),
+                email: addr.address.as_deref().map(str::to_string),
+                kind,
+            })
+        })
+        .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use prism_core::metadata::MetadataValue;
+
+    fn message(id: &str, reply_to: Option<&str>, subject: &str, hour: Option<u32>) -> Page {
+        let mut page = Document::builder()
+            .add_text_page(subject, "Body")
+            .build()
+            .pages
+            .remove(0);
+        page.metadata.message = Some(MessageHeaders {
+            message_id: Some(id.to_string()),
+            in_reply_to: reply_to.map(str::to_string),
+            references: reply_to.map(str::to_string).into_iter().collect(),
+            subject: Some(subject.to_string()),
+            from: Some("ana@example.com".to_string()),
+            date: hour.map(|hour| Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()),
+            recipients: Vec::new(),
+        });
+        page
+    }
+
+    #[test]
+    fn test_thread_documents() {
+        // Two replies to a message missing from the input, out of order,
+        // and an unrelated message
+        let mut mailbox = Document::new();
+        mailbox.pages = vec![
+            message("b@x", Some("a@x"), "Re: Budget", Some(11)),
+            message("z@x", None, "Lunch", Some(9)),
+            message("c@x", Some("a@x"), "RE: Fwd: Budget", Some(10)),
+        ];
+        let mut single = Document::new();
+        single.pages = vec![message("d@x", Some("b@x"), "Re: Budget", None)];
+        single.attachments.push(Attachment {
+            filename: "plan.xlsx".to_string(),
+            mime_type: None,
+            description: None,
+            data: vec![1],
+            created: None,
+            modified: None,
+            document: None,
+        });
+
+        let threads = thread_documents(&[mailbox, single]);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].metadata.title.as_deref(), Some("Lunch"));
+        let budget = &threads[1];
+        assert_eq!(budget.metadata.title.as_deref(), Some("Budget"));
+        let ids: Vec<_> = budget
+            .pages
+            .iter()
+            .map(|page| {
+                page.metadata
+                    .message
+                    .as_ref()
+                    .unwrap()
+                    .message_id
+                    .as_deref()
+            })
+            .collect();
+        assert_eq!(ids, [Some("c@x"), Some("b@x"), Some("d@x")]);
+        let numbers: Vec<_> = budget.pages.iter().map(|page| page.number).collect();
+        assert_eq!(numbers, [1, 2, 3]);
+        assert_eq!(budget.structure.sections.len(), 3);
+        assert_eq!(budget.attachments.len(), 1);
+        assert!(matches!(
+            budget.metadata.get_custom("thread_id"),
+            Some(MetadataValue::String(id)) if id == "a@x"
+        ));
+    }
+
+    #[test]
+    fn test_message_headers() {
+        let message = mail_parser::MessageParser::default()
+            .parse(
+                b"From: Ana <ana@example.com>\r\nSubject: Re: Budget\r\n\
+                  To: Bo <bo@example.com>, cy@example.com\r\nCc: dee@example.com\r\n\
+                  Message-ID: <b@x>\r\nIn-Reply-To: <a@x>\r\n\
+                  References: <root@x> <a@x>\r\nDate: Fri, 1 Mar 2024 10:00:00 +0000\r\n\r\nOk",
+            )
+            .unwrap();
+        let headers = message_headers(&message);
+        assert_eq!(headers.message_id.as_deref(), Some("b@x"));
+        assert_eq!(headers.in_reply_to.as_deref(), Some("a@x"));
+        assert_eq!(headers.references, ["root@x", "a@x"]);
+        assert_eq!(headers.from.as_deref(), Some("Ana <ana@example.com>"));
+        assert_eq!(
+            headers.date,
+            Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).single()
+        );
+        let recipients: Vec<_> = headers
+            .recipients
+            .iter()
+            .map(|recipient| (recipient.display(), recipient.kind))
+            .collect();
+        assert_eq!(
+            recipients,
+            [
+                ("Bo <bo@example.com>".to_string(), RecipientKind::To),
+                ("cy@example.com".to_string(), RecipientKind::To),
+                ("dee@example.com".to_string(), RecipientKind::Cc),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_threads_processor() {
+        let mut document = Document::new();
+        document.pages = vec![
+            message("a@x", None, "Budget", Some(8)),
+            message("z@x", None, "Lunch", Some(9)),
+            message("b@x", Some("a@x"), "Re: Budget", Some(10)),
+        ];
+        Threads.process(&mut document).await.unwrap();
+
+        let sections: Vec<_> = document
+            .structure
+            .sections
+            .iter()
+            .map(|section| {
+                (
+                    section.kind,
+                    section.title.as_str(),
+                    section.start.page,
+                    section.children.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                (SectionKind::Thread, "Budget", 1, 2),
+                (SectionKind::Thread, "Lunch", 3, 1)
+            ]
+        );
+        assert_eq!(document.pages[1].number, 2);
+        assert_eq!(
+            document.pages[1].extract_text().lines().next(),
+            Some("Re: Budget")
+        );
+    }
+}
@@ -0,0 +1,440 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! TNEF (`winmail.dat`) decoding
+//!
+//! Outlook sends the parts of a message that plain MIME cannot carry as a
+//! Transport Neutral Encapsulation Format attachment, usually named
+//! `winmail.dat` with type `application/ms-tnef`. The stream holds the
+//! formatted body as compressed RTF and the message's real attachments;
+//! see [MS-OXTNEF].
+//!
+//! A TNEF stream is a signature followed by attributes, each tagged with
+//! its level (message or attachment), ID, length and a checksum. Message
+//! and attachment properties beyond the fixed attributes are packed as
+//! MAPI property lists in `attMsgProps` and `attAttachment`.
+//!
+//! [MS-OXTNEF]: https://learn.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxtnef
+
+use chrono::{DateTime, NaiveDate, Utc};
+use prism_core::document::Attachment;
+use prism_core::error::{Error, Result};
+
+use super::rtf;
+
+/// First four bytes of every TNEF stream
+const SIGNATURE: u32 = 0x223E_9F78;
+
+/// MIME type of TNEF attachments
+pub const MIME_TYPE: &str = "application/ms-tnef";
+
+// Attribute IDs, with their type in the high word
+const ATT_BODY: u32 = 0x0001_800C;
+const ATT_MSG_PROPS: u32 = 0x0006_9003;
+const ATT_ATTACH_REND_DATA: u32 = 0x0006_9002;
+const ATT_ATTACH_TITLE: u32 = 0x0001_8010;
+const ATT_ATTACH_DATA: u32 = 0x0006_800F;
+const ATT_ATTACH_CREATE_DATE: u32 = 0x0003_8012;
+const ATT_ATTACH_MODIFY_DATE: u32 = 0x0003_8013;
+const ATT_ATTACHMENT: u32 = 0x0006_9005;
+
+// MAPI property IDs
+const PR_BODY: u16 = 0x1000;
+const PR_RTF_COMPRESSED: u16 = 0x1009;
+const PR_ATTACH_DATA: u16 = 0x3701;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+/// Content of a TNEF stream
+#[derive(Debug, Clone, Default)]
+pub struct Tnef {
+    /// Plain text body, if the stream carries one
+    pub body: Option<String>,
+    /// Formatted body, decompressed into RTF
+    pub rtf_body: Option<Vec<u8>>,
+    /// Attachments of the message, with their original names
+    pub attachments: Vec<Attachment>,
+}
+
+impl Tnef {
+    /// Text of the body: the plain text body, or the text of the RTF body
+    #[must_use]
+    pub fn body_text(&self) -> Option<String> {
+        self.body
+            .clone()
+            .filter(|body| !body.trim().is_empty())
+            .or_else(|| self.rtf_body.as_deref().map(rtf::to_text))
+            .filter(|body| !body.trim().is_empty())
+    }
+}
+
+/// Whether `attachment` is a TNEF stream
+#[must_use]
+pub fn is_tnef(attachment: &Attachment) -> bool {
+    attachment
+        .mime_type
+        .as_deref()
+        .is_some_and(|mime| mime.eq_ignore_ascii_case(MIME_TYPE))
+        || attachment.filename.eq_ignore_ascii_case("winmail.dat")
+        || attachment.data.get(..4) == Some(&SIGNATURE.to_le_bytes())
+}
+
+/// Decode a TNEF stream
+///
+/// # Errors
+///
+/// Returns an error if the data does not start with the TNEF signature.
+/// Attributes after a truncated one are ignored.
+pub fn decode(data: &[u8]) -> Result<Tnef> {
+    if data.get(..4) != Some(&SIGNATURE.to_le_bytes()) {
+        return Err(Error::corrupt("TNEF", "Missing TNEF signature"));
+    }
+
+    let mut tnef = Tnef::default();
+    let mut attachment: Option<Attachment> = None;
+    // Signature and legacy key
+    let mut reader = Reader::new(&data[6.min(data.len())..]);
+    while let Some((id, value)) = reader.attribute() {
+        match id {
+            ATT_BODY => tnef.body = Some(text(value)),
+            ATT_MSG_PROPS => {
+                for (property, kind, value) in properties(value) {
+                    match property {
+                        PR_BODY if tnef.body.is_none() => tnef.body = Some(string(kind, value)),
+                        PR_RTF_COMPRESSED => tnef.rtf_body = rtf::decompress(value).ok(),
+                        _ => {}
+                    }
+                }
+            }
+            ATT_ATTACH_REND_DATA => {
+                tnef.attachments.extend(attachment.take());
+                attachment = Some(Attachment {
+                    filename: format!("attachment_{}", tnef.attachments.len()),
+                    mime_type: None,
+                    description: None,
+                    data: Vec::new(),
+                    created: None,
+                    modified: None,
+                    document: None,
+                });
+            }
+            ATT_ATTACH_TITLE => {
+                if let Some(attachment) = &mut attachment {
+                    attachment.filename = text(value);
+                }
+            }
+            ATT_ATTACH_DATA => {
+                if let Some(attachment) = &mut attachment {
+                    attachment.data = value.to_vec();
+                }
+            }
+            ATT_ATTACH_CREATE_DATE | ATT_ATTACH_MODIFY_DATE => {
+                if let Some(attachment) = &mut attachment {
+                    if id == ATT_ATTACH_CREATE_DATE {
+                        attachment.created = date(value);
+                    } else {
+                        attachment.modified = date(value);
+                    }
+                }
+            }
+            ATT_ATTACHMENT => {
+                let Some(attachment) = &mut attachment else {
+                    continue;
+                };
+                for (property, kind, value) in properties(value) {
+                    match property {
+                        PR_ATTACH_LONG_FILENAME => attachment.filename = string(kind, value),
+                        PR_ATTACH_MIME_TAG => attachment.mime_type = Some(string(kind, value)),
+                        PR_ATTACH_DATA if attachment.data.is_empty() => {
+                            attachment.data = value.to_vec();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tnef.attachments.extend(attachment);
+    tnef.attachments
+        .retain(|attachment| !attachment.data.is_empty());
+    Ok(tnef)
+}
+
+/// Replace the TNEF streams among `attachments` by the attachments they
+/// carry, returning the first body text found in them
+///
+/// Streams that cannot be decoded are kept as they are.
+pub fn expand(attachments: &mut Vec<Attachment>) -> Option<String> {
+    if !attachments.iter().any(is_tnef) {
+        return None;
+    }
+    let mut body = None;
+    let mut expanded = Vec::with_capacity(attachments.len());
+    for attachment in attachments.drain(..) {
+        if !is_tnef(&attachment) {
+            expanded.push(attachment);
+            continue;
+        }
+        match decode(&attachment.data) {
+            Ok(tnef) => {
+                if body.is_none() {
+                    body = tnef.body_text();
+                }
+                expanded.extend(tnef.attachments);
+            }
+            Err(_) => expanded.push(attachment),
+        }
+    }
+    *attachments = expanded;
+    body
+}
+
+/// Reader over the attributes of a TNEF stream
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// The next attribute's ID and value, or `None` at the end of the stream
+    fn attribute(&mut self) -> Option<(u32, &'a [u8])> {
+        // Level, ID, length, value, checksum
+        let header = self.data.get(..9)?;
+        let id = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+        let length = u32::from_le_bytes([header[5], header[6], header[7], header[8]]) as usize;
+        let value = self.data.get(9..9_usize.checked_add(length)?)?;
+        self.data = self.data.get(9 + length + 2..).unwrap_or_default();
+        Some((id, value))
+    }
+}
+
+/// Property IDs, types and values of a MAPI property list
+///
+/// Only single-valued string and binary properties are returned; values
+/// of other types are skipped.
+fn properties(data: &[u8]) -> Vec<(u16, u16, &[u8])> {
+    let mut properties = Vec::new();
+    let mut cursor = Cursor { data, position: 0 };
+    let Some(count) = cursor.u32() else {
+        return properties;
+    };
+    for _ in 0..count {
+        let (Some(kind), Some(id)) = (cursor.u16(), cursor.u16()) else {
+            break;
+        };
+        // Named properties carry their GUID and name or number
+        if id >= 0x8000 {
+            cursor.skip(16);
+            match cursor.u32() {
+                Some(0) => cursor.skip(4),
+                Some(_) => {
+                    let length = cursor.u32().unwrap_or(u32::MAX) as usize;
+                    cursor.skip(padded(length));
+                }
+                None => break,
+            }
+        }
+        let multi = kind & 0x1000 != 0;
+        let values = if multi {
+            cursor.u32().unwrap_or(0) as usize
+        } else {
+            1
+        };
+        for _ in 0..values {
+            let size = match kind & !0x1000 {
+                0x0002 | 0x0003 | 0x0004 | 0x000A | 0x000B => Some(4),
+                0x0005..=0x0007 | 0x0014 | 0x0040 => Some(8),
+                0x0048 => Some(16),
+                _ => None,
+            };
+            if let Some(size) = size {
+                cursor.skip(size);
+                continue;
+            }
+            // Variable-size values: a count of 1 when single-valued, then
+            // the length and the padded value
+            if !multi {
+                cursor.skip(4);
+            }
+            let Some(length) = cursor.u32().map(|length| length as usize) else {
+                return properties;
+            };
+            let Some(value) = cursor.take(length) else {
+                return properties;
+            };
+            cursor.skip(padded(length) - length);
+            if !multi {
+                properties.push((id, kind, value));
+            }
+        }
+    }
+    properties
+}
+
+/// `length` rounded up to a multiple of 4
+fn padded(length: usize) -> usize {
+    length.saturating_add(3) & !3
+}
+
+/// Position within a MAPI property list
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let value = self
+            .data
+            .get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        Some(value)
+    }
+
+    fn skip(&mut self, length: usize) {
+        self.position = self.position.saturating_add(length);
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// An 8-bit string value without its terminating null
+///
+/// Attribute strings are in the message's code page; text that is not
+/// UTF-8 is read as Latin-1.
+fn text(value: &[u8]) -> String {
+    String::from_utf8(value.to_vec())
+        .unwrap_or_else(|_| value.iter().map(|&byte| char::from(byte)).collect())
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// A string property value: UTF-16LE for `PT_UNICODE`, otherwise 8-bit
+fn string(kind: u16, value: &[u8]) -> String {
+    if kind != 0x001F {
+        return text(value);
+    }
+    let units: Vec<u16> = value
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// A TNEF date: year, month, day, hour, minute, second and day of week
+fn date(value: &[u8]) -> Option<DateTime<Utc>> {
+    let fields: Vec<u32> = value
+        .get(..12)?
+        .chunks_exact(2)
+        .map(|field| u32::from(u16::from_le_bytes([field[0], field[1]])))
+        .collect();
+    let year = i32::try_from(fields[0]).ok()?;
+    NaiveDate::from_ymd_opt(year, fields[1], fields[2])?
+        .and_hms_opt(fields[3], fields[4], fields[5])
+        .map(|date| date.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(level: u8, id: u32, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![level];
+        out.extend(id.to_le_bytes());
+        out.extend(u32::try_from(value.len()).unwrap().to_le_bytes());
+        out.extend(value);
+        let checksum = value
+            .iter()
+            .fold(0_u16, |sum, &byte| sum.wrapping_add(u16::from(byte)));
+        out.extend(checksum.to_le_bytes());
+        out
+    }
+
+    /// A MAPI property list of Unicode string properties
+    fn strings(properties: &[(u16, &str)]) -> Vec<u8> {
+        let mut out = u32::try_from(properties.len())
+            .unwrap()
+            .to_le_bytes()
+            .to_vec();
+        for (id, value) in properties {
+            let mut encoded: Vec<u8> = value.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            encoded.extend([0, 0]);
+            out.extend(0x001F_u16.to_le_bytes());
+            out.extend(id.to_le_bytes());
+            out.extend(1_u32.to_le_bytes());
+            out.extend(u32::try_from(encoded.len()).unwrap().to_le_bytes());
+            let length = encoded.len();
+            out.extend(encoded);
+            out.extend(vec![0; padded(length) - length]);
+        }
+        out
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut data = SIGNATURE.to_le_bytes().to_vec();
+        data.extend([0x01, 0x00]);
+        data.extend(attribute(1, ATT_BODY, b"Quarterly numbers attached\0"));
+        data.extend(attribute(2, ATT_ATTACH_REND_DATA, &[0; 14]));
+        data.extend(attribute(2, ATT_ATTACH_TITLE, b"REPORT~1.PDF\0"));
+        data.extend(attribute(
+            2,
+            ATT_ATTACH_MODIFY_DATE,
+            &[0xE8, 0x07, 3, 0, 1, 0, 9, 0, 30, 0, 0, 0, 5, 0],
+        ));
+        data.extend(attribute(2, ATT_ATTACH_DATA, b"%PDF-1.7"));
+        data.extend(attribute(
+            2,
+            ATT_ATTACHMENT,
+            &strings(&[
+                (PR_ATTACH_LONG_FILENAME, "Quarterly report.pdf"),
+                (PR_ATTACH_MIME_TAG, "application/pdf"),
+            ]),
+        ));
+        data.extend(attribute(2, ATT_ATTACH_REND_DATA, &[0; 14]));
+        data.extend(attribute(2, ATT_ATTACH_TITLE, b"notes.txt\0"));
+        data.extend(attribute(2, ATT_ATTACH_DATA, b"Draft"));
+
+        let tnef = decode(&data).unwrap();
+        assert_eq!(
+            tnef.body_text().as_deref(),
+            Some("Quarterly numbers attached")
+        );
+        assert_eq!(tnef.attachments.len(), 2);
+        let report = &tnef.attachments[0];
+        assert_eq!(report.filename, "Quarterly report.pdf");
+        assert_eq!(report.mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(report.data, b"%PDF-1.7");
+        assert_eq!(
+            report.modified.map(|date| date.to_rfc3339()),
+            Some("2024-03-01T09:30:00+00:00".to_string())
+        );
+        assert_eq!(tnef.attachments[1].filename, "notes.txt");
+
+        let mut attachments = vec![Attachment {
+            filename: "winmail.dat".to_string(),
+            mime_type: Some(MIME_TYPE.to_string()),
+            description: None,
+            data,
+            created: None,
+            modified: None,
+            document: None,
+        }];
+        assert!(expand(&mut attachments).is_some());
+        assert_eq!(attachments.len(), 2);
+
+        assert!(decode(b"not tnef").is_err());
+    }
+}
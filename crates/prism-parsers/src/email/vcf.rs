@@ -2,27 +2,140 @@
 //! VCF (vCard) parser
 //!
 //! Parses .VCF/.VCARD files (virtual contact cards) into the Unified Document Model.
+//!
+//! Reads vCard 2.1, 3.0 and 4.0: folded lines, quoted-printable values,
+//! parameters with or without a name (`TEL;TYPE=work,voice` and
+//! `TEL;WORK;VOICE`), and grouped properties, whose `X-ABLabel` names the
+//! group's fields. Photos, inline or as a `data:` URI, become image
+//! resources; other photo URIs are kept as links.
+//!
+//! All cards of a file are laid out on one page as a table of contact
+//! cards, two to a row.
 
 use async_trait::async_trait;
+use base64::Engine as _;
 use bytes::Bytes;
-use ical::parser::vcard::component::VcardContact;
-use ical::VcardParser;
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, Rect, ShapeStyle, TextBlock, TextRun, TextStyle,
+        ContentBlock, Dimensions, Document, ImageBlock, Page, Rect, ShapeStyle, TableBlock,
+        TableCell, TableRow, TextBlock, TextDirection, TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
 };
-use std::io::Cursor;
+use std::collections::HashMap;
 use tracing::{debug, info};
 
+use crate::text::linked::LinkedImages;
+
+/// Contact cards per table row
+const CARDS_PER_ROW: usize = 2;
+
 /// VCF vCard parser
 #[derive(Debug, Clone)]
 pub struct VcfParser;
 
+/// A content line of a vCard
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Property {
+    /// Group the property belongs to (`item1` in `item1.EMAIL`)
+    group: Option<String>,
+    /// Upper-case property name
+    name: String,
+    /// Upper-case parameter names and their values; a parameter written
+    /// without a name is a `TYPE`
+    params: Vec<(String, Vec<String>)>,
+    value: String,
+}
+
+impl Property {
+    fn param<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.params
+            .iter()
+            .filter(move |(key, _)| key == name)
+            .flat_map(|(_, values)| values.iter().map(String::as_str))
+    }
+
+    /// Lower-case `TYPE` values, with `pref` for preferred values
+    fn types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self
+            .param("TYPE")
+            .flat_map(|value| value.split(','))
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty() && value != "internet" && value != "x400")
+            .collect();
+        if self.param("PREF").next().is_some() && !types.iter().any(|t| t == "pref") {
+            types.push("pref".to_string());
+        }
+        types
+    }
+
+    /// Components of a structured value (`N`, `ADR`, `ORG`), unescaped
+    fn components(&self) -> Vec<String> {
+        split_unescaped(&self.value, ';')
+            .into_iter()
+            .map(|component| unescape(&component))
+            .collect()
+    }
+
+    fn text(&self) -> String {
+        unescape(&self.value)
+    }
+}
+
+/// A contact: the properties between `BEGIN:VCARD` and `END:VCARD`
+#[derive(Debug, Clone, Default)]
+struct Card {
+    properties: Vec<Property>,
+}
+
+impl Card {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// Display name: `FN`, or else the parts of `N`
+    fn name(&self) -> Option<String> {
+        if let Some(name) = self.get("FN").map(Property::text) {
+            if !name.trim().is_empty() {
+                return Some(name.trim().to_string());
+            }
+        }
+        // N is Family;Given;Additional;Prefixes;Suffixes
+        let parts = self.get("N")?.components();
+        let order = [3, 1, 2, 0, 4];
+        let name = order
+            .iter()
+            .filter_map(|&index| parts.get(index))
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!name.is_empty()).then_some(name)
+    }
+
+    /// Labels of property groups, from Apple's `X-ABLabel`
+    fn group_labels(&self) -> HashMap<&str, String> {
+        self.properties
+            .iter()
+            .filter(|property| property.name == "X-ABLABEL")
+            .filter_map(|property| {
+                let label = property.text();
+                let label = label
+                    .strip_prefix("_$!<")
+                    .and_then(|label| label.strip_suffix(">!$_"))
+                    .unwrap_or(&label)
+                    .to_lowercase();
+                Some((property.group.as_deref()?, label))
+            })
+            .collect()
+    }
+}
+
 impl VcfParser {
     /// Create a new VCF parser
     #[must_use]
@@ -31,88 +144,156 @@ impl VcfParser {
     }
 
     /// Format contact field as text content
-    fn format_field(&self, label: &str, value: &str, bold: bool) -> TextRun {
-        TextRun {
-            text: format!("{}: {}\n", label, value),
+    fn format_field(label: &str, value: &str, bold: bool) -> Vec<TextRun> {
+        let run = |text: String, bold| TextRun {
+            text: text.into(),
             style: TextStyle {
                 bold,
                 ..Default::default()
             },
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
+        };
+        vec![
+            run(format!("{label}: "), true),
+            run(format!("{value}\n"), bold),
+        ]
+    }
+
+    /// Label and display value of a property shown on the card
+    fn field(property: &Property, group_label: Option<&String>) -> Option<(String, String)> {
+        let label = match property.name.as_str() {
+            "NICKNAME" => "Nickname",
+            "ORG" => "Organization",
+            "TITLE" => "Title",
+            "ROLE" => "Role",
+            "EMAIL" => "Email",
+            "TEL" => "Phone",
+            "ADR" => "Address",
+            "URL" => "Website",
+            "IMPP" => "Messaging",
+            "BDAY" => "Birthday",
+            "ANNIVERSARY" => "Anniversary",
+            "GENDER" => "Gender",
+            "LANG" => "Language",
+            "TZ" => "Time zone",
+            "GEO" => "Location",
+            "CATEGORIES" => "Categories",
+            "X-SOCIALPROFILE" => "Profile",
+            "NOTE" => "Note",
+            _ => return None,
+        };
+
+        let value = match property.name.as_str() {
+            "ADR" => {
+                // PO Box;Extended;Street;City;Region;Postal code;Country
+                let parts = property.components();
+                parts
+                    .iter()
+                    .map(|part| part.trim())
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+            "ORG" => property
+                .components()
+                .iter()
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(", "),
+            "CATEGORIES" | "NICKNAME" => split_unescaped(&property.value, ',')
+                .iter()
+                .map(|part| unescape(part).trim().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            "TEL" | "URL" | "IMPP" | "GEO" => {
+                let value = property.text();
+                value
+                    .strip_prefix("tel:")
+                    .or_else(|| value.strip_prefix("geo:"))
+                    .unwrap_or(&value)
+                    .to_string()
+            }
+            _ => property.text(),
+        };
+        if value.trim().is_empty() {
+            return None;
+        }
+
+        let mut types = property.types();
+        if let Some(group_label) = group_label {
+            types.insert(0, group_label.clone());
         }
+        let label = if types.is_empty() {
+            label.to_string()
+        } else {
+            format!("{label} ({})", types.join(", "))
+        };
+        Some((label, value.trim().to_string()))
     }
 
-    /// Parse a single vCard contact
-    fn parse_vcard(&self, contact: &VcardContact) -> Vec<TextRun> {
-        let mut text_runs = Vec::new();
-
-        // Full name (FN property)
-        for prop in &contact.properties {
-            match prop.name.as_str() {
-                "FN" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Name", value, true));
-                    }
-                }
-                "ORG" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Organization", value, false));
-                    }
-                }
-                "TITLE" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Title", value, false));
-                    }
-                }
-                "EMAIL" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Email", value, false));
-                    }
-                }
-                "TEL" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Phone", value, false));
-                    }
-                }
-                "ADR" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        // Address format: PO Box;Extended;Street;City;State;Postal;Country
-                        let parts: Vec<&str> = value.split(';').filter(|s| !s.is_empty()).collect();
-                        if !parts.is_empty() {
-                            text_runs.push(self.format_field("Address", &parts.join(", "), false));
-                        }
-                    }
-                }
-                "URL" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(self.format_field("Website", value, false));
-                    }
-                }
-                "NOTE" => {
-                    if let Some(value) = prop.value.as_ref() {
-                        text_runs.push(TextRun {
-                            text: "\nNote:\n".to_string(),
-                            style: TextStyle {
-                                bold: true,
-                                ..Default::default()
-                            },
-                            bounds: None,
-                            char_positions: None,
-                        });
-                        text_runs.push(TextRun {
-                            text: format!("{}\n", value),
-                            style: Default::default(),
-                            bounds: None,
-                            char_positions: None,
-                        });
-                    }
-                }
-                _ => {} // Ignore other properties
+    /// The card of a contact as table cell content
+    fn parse_vcard(card: &Card, images: &mut LinkedImages) -> Vec<ContentBlock> {
+        let mut content = Vec::new();
+
+        if let Some(photo) = card.get("PHOTO").or_else(|| card.get("LOGO")) {
+            if let Some(resource_id) = photo_resource(photo, images) {
+                content.push(ContentBlock::Image(ImageBlock {
+                    bounds: Rect::default(),
+                    resource_id,
+                    alt_text: card.name(),
+                    format: None,
+                    original_size: None,
+                    style: ShapeStyle::default(),
+                    rotation: 0.0,
+                    crop: None,
+                    flip_horizontal: false,
+                    flip_vertical: false,
+                }));
             }
         }
 
-        text_runs
+        let mut runs = Vec::new();
+        if let Some(name) = card.name() {
+            runs.push(TextRun {
+                text: format!("{name}\n").into(),
+                style: TextStyle {
+                    bold: true,
+                    font_size: Some(14.0),
+                    ..Default::default()
+                },
+                bounds: None,
+                char_positions: None,
+                link: None,
+                revision: None,
+            });
+        }
+        let group_labels = card.group_labels();
+        for property in &card.properties {
+            let group_label = property
+                .group
+                .as_deref()
+                .and_then(|group| group_labels.get(group));
+            if let Some((label, value)) = Self::field(property, group_label) {
+                runs.extend(Self::format_field(&label, &value, false));
+            }
+        }
+        if let Some(last) = runs.last_mut() {
+            last.text = last.text.trim_end_matches('\n').into();
+            content.push(ContentBlock::Text(TextBlock {
+                bounds: Rect::default(),
+                runs,
+                paragraph_style: None,
+                style: ShapeStyle::default(),
+                rotation: 0.0,
+                direction: TextDirection::Auto,
+            }));
+        }
+
+        content
     }
 }
 
@@ -146,78 +327,77 @@ impl Parser for VcfParser {
             context.size, context.filename
         );
 
-        let cursor = Cursor::new(&data[..]);
-        let parser = VcardParser::new(cursor);
-
-        let mut vcards = Vec::new();
-        for contact_result in parser {
-            match contact_result {
-                Ok(contact) => vcards.push(contact),
-                Err(e) => {
-                    debug!("Failed to parse vCard: {:?}", e);
-                    continue;
-                }
-            }
-        }
-
-        if vcards.is_empty() {
+        let text = String::from_utf8_lossy(&data);
+        let cards = parse_cards(&text);
+        if cards.is_empty() {
             return Err(Error::ParseError("No valid vCards found".to_string()));
         }
 
-        let mut pages = Vec::new();
-        let mut contact_name = None;
-
-        for (page_number, vcard) in vcards.iter().enumerate() {
-            let text_runs = self.parse_vcard(vcard);
-
-            // Save first contact name for metadata
-            if page_number == 0 {
-                for prop in &vcard.properties {
-                    if prop.name == "FN" {
-                        contact_name = prop.value.clone();
-                        break;
-                    }
-                }
-            }
+        let mut images = LinkedImages::new(context.options.resource_dir.clone());
+        let cells: Vec<TableCell> = cards
+            .iter()
+            .map(|card| TableCell {
+                content: Self::parse_vcard(card, &mut images),
+                col_span: 1,
+                row_span: 1,
+                background_color: None,
+            })
+            .collect();
+        let column_count = cells.len().min(CARDS_PER_ROW);
+        let mut rows = Vec::new();
+        let mut cells = cells.into_iter().peekable();
+        while cells.peek().is_some() {
+            let mut row: Vec<TableCell> = cells.by_ref().take(column_count).collect();
+            // Pad the last row so that every row has a cell per column
+            row.resize_with(column_count, || TableCell {
+                content: Vec::new(),
+                col_span: 1,
+                row_span: 1,
+                background_color: None,
+            });
+            rows.push(TableRow {
+                cells: row,
+                height: None,
+            });
+        }
 
-            let text_block = TextBlock {
-                bounds: Rect::new(0.0, 0.0, 0.0, 0.0), // No layout info in VCF
-                runs: text_runs,
-                paragraph_style: None,
+        let page = Page {
+            number: 1,
+            dimensions: Dimensions::LETTER,
+            content: vec![ContentBlock::Table(TableBlock {
+                bounds: Rect::default(),
+                rows,
+                column_count,
                 style: ShapeStyle::default(),
                 rotation: 0.0,
-            };
-
-            let page = Page {
-                number: (page_number + 1) as u32,
-                dimensions: Dimensions::LETTER,
-                content: vec![ContentBlock::Text(text_block)],
-                metadata: Default::default(),
-                annotations: Vec::new(),
-            };
-
-            pages.push(page);
-        }
+            })],
+            metadata: Default::default(),
+            annotations: Vec::new(),
+        };
 
         // Create metadata
         let mut metadata = Metadata::default();
-        if let Some(name) = contact_name {
+        if let Some(name) = cards[0].name() {
             metadata.title = Some(name);
         } else if let Some(ref filename) = context.filename {
             metadata.title = Some(filename.clone());
         }
         metadata.add_custom("format", "VCF");
-        metadata.add_custom("contact_count", pages.len() as i64);
+        metadata.add_custom(
+            "contact_count",
+            i64::try_from(cards.len()).unwrap_or(i64::MAX),
+        );
+        if let Some(version) = cards[0].get("VERSION") {
+            metadata.add_custom("vcard_version", version.text());
+        }
 
         // Create document
         let mut document = Document::new();
-        document.pages = pages;
+        document.pages = vec![page];
         document.metadata = metadata;
+        document.resources.images = images.into_resources();
 
-        info!(
-            "Successfully parsed VCF with {} contact(s)",
-            document.pages.len()
-        );
+        info!("Successfully parsed VCF with {} contact(s)", cards.len());
 
         Ok(document)
     }
@@ -228,6 +408,8 @@ impl Parser for VcfParser {
             version: env!("CARGO_PKG_VERSION").to_string(),
             features: vec![
                 ParserFeature::TextExtraction,
+                ParserFeature::TableExtraction,
+                ParserFeature::ImageExtraction,
                 ParserFeature::MetadataExtraction,
             ],
             requires_sandbox: false,
@@ -235,6 +417,220 @@ impl Parser for VcfParser {
     }
 }
 
+/// Resource for a `PHOTO` or `LOGO`: inline base64 data, a `data:` URI or
+/// a link
+fn photo_resource(photo: &Property, images: &mut LinkedImages) -> Option<String> {
+    let value = photo.value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let inline = photo
+        .param("ENCODING")
+        .chain(photo.param("TYPE"))
+        .any(|encoding| {
+            encoding.eq_ignore_ascii_case("b") || encoding.eq_ignore_ascii_case("base64")
+        });
+    if inline {
+        let encoded: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        return match base64::engine::general_purpose::STANDARD.decode(encoded) {
+            Ok(data) => Some(images.embed(data)),
+            Err(e) => {
+                debug!("Skipping undecodable vCard photo: {e}");
+                None
+            }
+        };
+    }
+    Some(images.resolve(value))
+}
+
+/// Read the cards of a vCard file, skipping lines that are not properties
+fn parse_cards(text: &str) -> Vec<Card> {
+    let mut cards = Vec::new();
+    let mut current: Option<Card> = None;
+    // Nested cards (vCard 2.1 AGENT) are skipped
+    let mut depth = 0;
+    for line in unfold(text) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match (
+            property.name.as_str(),
+            property.value.trim().to_ascii_uppercase().as_str(),
+        ) {
+            ("BEGIN", "VCARD") => {
+                depth += 1;
+                if depth == 1 {
+                    current = Some(Card::default());
+                }
+            }
+            ("END", "VCARD") => {
+                depth -= 1;
+                if depth == 0 {
+                    cards.extend(current.take());
+                }
+            }
+            _ if depth == 1 => {
+                if let Some(card) = &mut current {
+                    card.properties.push(property);
+                }
+            }
+            _ => {}
+        }
+    }
+    cards
+}
+
+/// Logical lines: folded lines joined, and quoted-printable soft line
+/// breaks removed
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut soft_break = false;
+    for line in text.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match lines.last_mut() {
+            Some(last) if soft_break => last.push_str(line.trim_start()),
+            Some(last) if line.starts_with([' ', '\t']) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+        let last = lines.last_mut().expect("a line was just added");
+        soft_break = last.ends_with('=') && last.to_ascii_uppercase().contains("QUOTED-PRINTABLE");
+        if soft_break {
+            last.pop();
+        }
+    }
+    lines
+}
+
+/// Parse `group.NAME;PARAM=value,value;BARE:value`
+fn parse_property(line: &str) -> Option<Property> {
+    let colon = find_unquoted(line, ':')?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = split_quoted(head, ';').into_iter();
+    let name = parts.next()?;
+    let (group, name) = match name.rsplit_once('.') {
+        Some((group, name)) => (Some(group.to_string()), name),
+        None => (None, name.as_str()),
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    let params: Vec<(String, Vec<String>)> = parts
+        .map(|param| match param.split_once('=') {
+            Some((key, values)) => (
+                key.trim().to_ascii_uppercase(),
+                split_quoted(values, ',')
+                    .into_iter()
+                    .map(|value| value.trim_matches('"').to_string())
+                    .collect(),
+            ),
+            None => ("TYPE".to_string(), vec![param.trim().to_string()]),
+        })
+        .collect();
+
+    let quoted_printable = params.iter().any(|(key, values)| {
+        (key == "ENCODING" || key == "TYPE")
+            && values
+                .iter()
+                .any(|value| value.eq_ignore_ascii_case("quoted-printable"))
+    });
+    let value = if quoted_printable {
+        decode_quoted_printable(value)
+    } else {
+        value.to_string()
+    };
+
+    Some(Property {
+        group,
+        name: name.to_ascii_uppercase(),
+        params,
+        value,
+    })
+}
+
+fn find_unquoted(text: &str, delimiter: char) -> Option<usize> {
+    let mut quoted = false;
+    text.char_indices().find_map(|(index, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == delimiter && !quoted).then_some(index)
+    })
+}
+
+fn split_quoted(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(index) = find_unquoted(rest, delimiter) {
+        parts.push(rest[..index].to_string());
+        rest = &rest[index + 1..];
+    }
+    parts.push(rest.to_string());
+    parts
+}
+
+/// Split a value on `delimiter`, except where it is escaped with `\`
+fn split_unescaped(value: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let current = parts.last_mut().expect("parts is never empty");
+        match c {
+            '\\' => {
+                current.push('\\');
+                current.extend(chars.next());
+            }
+            c if c == delimiter => parts.push(String::new()),
+            c => current.push(c),
+        }
+    }
+    parts
+}
+
+/// Resolve `\n`, `\,`, `\;` and `\\` escapes
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                chars.next();
+                text.push('\n');
+            }
+            ('\\', Some(escaped @ (',' | ';' | ':' | '\\'))) => {
+                chars.next();
+                text.push(escaped);
+            }
+            (c, _) => text.push(c),
+        }
+    }
+    text
+}
+
+/// Decode a quoted-printable value as UTF-8
+fn decode_quoted_printable(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'=', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,4 +649,69 @@ mod tests {
         assert_eq!(metadata.name, "VCF Parser");
         assert!(!metadata.requires_sandbox);
     }
+
+    #[test]
+    fn test_parse_property() {
+        let property = parse_property("item1.TEL;TYPE=\"work,voice\";PREF=1:+1 555 0100").unwrap();
+        assert_eq!(property.group.as_deref(), Some("item1"));
+        assert_eq!(property.name, "TEL");
+        assert_eq!(property.types(), ["work", "voice", "pref"]);
+
+        let property = parse_property("TEL;HOME;FAX:555").unwrap();
+        assert_eq!(property.types(), ["home", "fax"]);
+
+        let property =
+            parse_property("NOTE;ENCODING=QUOTED-PRINTABLE;CHARSET=UTF-8:Caf=C3=A9").unwrap();
+        assert_eq!(property.text(), "Café");
+
+        let property = parse_property(r"ADR:;;1 Main St\, Apt 2;Springfield;;;").unwrap();
+        assert_eq!(property.components()[2], "1 Main St, Apt 2");
+    }
+
+    #[tokio::test]
+    async fn test_parse_cards() {
+        let vcf = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Ada Lovelace\r\n\
+            EMAIL;TYPE=work:ada@example.com\r\nEMAIL;TYPE=home:ada@home.example\r\n\
+            item1.TEL:+44 20 7946 0000\r\nitem1.X-ABLabel:_$!<Mobile>!$_\r\n\
+            NOTE:Wrote the first\r\n  program\r\n\
+            PHOTO:data:image/png;base64,iVBORw0KGgo=\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:3.0\r\nN:Babbage;Charles;;Mr.;\r\n\
+            ORG:Analytical Engines;R&D\r\nPHOTO;ENCODING=b;TYPE=PNG:iVBORw0KGgo=\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:2.1\r\nFN:Grace Hopper\r\nTEL;WORK;VOICE:555\r\nEND:VCARD\r\n";
+        let parser = VcfParser::new();
+        let data = Bytes::from(vcf);
+        let context = ParseContext {
+            format: parser.format(),
+            filename: Some("contacts.vcf".to_string()),
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let document = parser.parse(data, context).await.unwrap();
+
+        assert_eq!(document.page_count(), 1);
+        assert_eq!(document.metadata.title.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(document.resources.images.len(), 2);
+        assert_eq!(document.resources.images[0].mime_type, "image/png");
+
+        let ContentBlock::Table(table) = &document.pages[0].content[0] else {
+            panic!("expected a table of cards");
+        };
+        assert_eq!(table.column_count, 2);
+        assert_eq!(table.rows.len(), 2);
+        assert!(table.rows[1].cells[1].content.is_empty());
+
+        let ada = table.rows[0].cells[0].extract_text();
+        assert!(ada.contains("Email (work): ada@example.com"));
+        assert!(ada.contains("Email (home): ada@home.example"));
+        assert!(ada.contains("Phone (mobile): +44 20 7946 0000"));
+        assert!(ada.contains("Note: Wrote the first program"));
+
+        let charles = table.rows[0].cells[1].extract_text();
+        assert!(charles.starts_with("Mr. Charles Babbage"));
+        assert!(charles.contains("Organization: Analytical Engines, R&D"));
+
+        let grace = table.rows[1].cells[0].extract_text();
+        assert!(grace.contains("Phone (work, voice): 555"));
+    }
 }
@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Recognising encrypted ZIP archives and OOXML packages
+//!
+//! Neither can be read without a password, and the ZIP reader only reports
+//! that an entry "needs a password" once it tries to decompress it. These
+//! checks look at the headers instead, so parsers can fail early with
+//! [`Error::EncryptedDocument`] naming the encryption method.
+//!
+//! - A ZIP entry is encrypted when bit 0 of its general purpose flags is
+//!   set: `WinZip` AES when the compression method is 99 (the strength is in
+//!   the `0x9901` extra field), PKWARE strong encryption when bit 6 is set
+//!   too, and the traditional `ZipCrypto` otherwise.
+//! - An encrypted OOXML document is not a ZIP file at all but a compound
+//!   file holding the package in an `EncryptedPackage` stream, described by
+//!   an `EncryptionInfo` stream ([MS-OFFCRYPTO]).
+
+use cfb::CompoundFile;
+use prism_core::error::{Error, Result};
+use std::io::{Cursor, Read};
+
+/// Signature of a ZIP central directory file header
+const CENTRAL_HEADER: &[u8] = b"PK\x01\x02";
+
+/// Size of the fixed part of a central directory file header
+const CENTRAL_HEADER_LEN: usize = 46;
+
+/// Signature of the ZIP end of central directory record
+const END_OF_CENTRAL_DIRECTORY: &[u8] = b"PK\x05\x06";
+
+/// General purpose flag: the entry is encrypted
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+/// General purpose flag: PKWARE strong encryption
+const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+
+/// Compression method marking `WinZip` AES encryption
+const METHOD_AES: u16 = 99;
+
+/// Extra field holding the `WinZip` AES parameters
+const AES_EXTRA_FIELD: u16 = 0x9901;
+
+/// Stream of an encrypted OOXML package
+const ENCRYPTED_PACKAGE: &str = "/EncryptedPackage";
+
+/// Stream describing how an OOXML package is encrypted
+const ENCRYPTION_INFO: &str = "/EncryptionInfo";
+
+/// The encrypted entries of a ZIP archive and their encryption methods,
+/// read from the central directory
+///
+/// Returns nothing for data that is not a readable ZIP archive.
+#[must_use]
+pub fn encrypted_zip_entries(data: &[u8]) -> Vec<(String, String)> {
+    let Some(mut offset) = central_directory(data) else {
+        return Vec::new();
+    };
+    let mut encrypted = Vec::new();
+    while data.get(offset..offset + 4) == Some(CENTRAL_HEADER) {
+        let Some(header) = data.get(offset..offset + CENTRAL_HEADER_LEN) else {
+            break;
+        };
+        let flags = le_u16(header, 8);
+        let method = le_u16(header, 10);
+        let name_len = usize::from(le_u16(header, 28));
+        let extra_len = usize::from(le_u16(header, 30));
+        let comment_len = usize::from(le_u16(header, 32));
+
+        let name_start = offset + CENTRAL_HEADER_LEN;
+        let extra_start = name_start + name_len;
+        let (Some(name), Some(extra)) = (
+            data.get(name_start..extra_start),
+            data.get(extra_start..extra_start + extra_len),
+        ) else {
+            break;
+        };
+        if flags & FLAG_ENCRYPTED != 0 {
+            encrypted.push((
+                String::from_utf8_lossy(name).into_owned(),
+                zip_method(flags, method, extra),
+            ));
+        }
+        offset = extra_start + extra_len + comment_len;
+    }
+    encrypted
+}
+
+/// Fail with [`Error::EncryptedDocument`] if any entry of the ZIP archive
+/// is encrypted
+///
+/// # Errors
+///
+/// Returns [`Error::EncryptedDocument`] for `format` with the method of
+/// the first encrypted entry.
+pub fn check_zip(data: &[u8], format: &str) -> Result<()> {
+    match encrypted_zip_entries(data).into_iter().next() {
+        Some((_, method)) => Err(Error::encrypted(format, method)),
+        None => Ok(()),
+    }
+}
+
+/// Encryption method of an encrypted OOXML package, or `None` if `data`
+/// is not one
+#[must_use]
+pub fn ooxml_encryption(data: &[u8]) -> Option<String> {
+    let mut compound = CompoundFile::open(Cursor::new(data)).ok()?;
+    if !compound.is_stream(ENCRYPTED_PACKAGE) {
+        return None;
+    }
+    let mut info = Vec::new();
+    let read = compound
+        .open_stream(ENCRYPTION_INFO)
+        .and_then(|mut stream| stream.read_to_end(&mut info));
+    if read.is_err() {
+        return Some("unknown".to_string());
+    }
+    Some(ooxml_method(&info))
+}
+
+/// Fail with [`Error::EncryptedDocument`] if `data` is an encrypted OOXML
+/// package or a ZIP archive with encrypted entries
+///
+/// # Errors
+///
+/// Returns [`Error::EncryptedDocument`] for `format`.
+pub fn check_package(data: &[u8], format: &str) -> Result<()> {
+    match ooxml_encryption(data) {
+        Some(method) => Err(Error::encrypted(format, method)),
+        None => check_zip(data, format),
+    }
+}
+
+/// Offset of the first central directory header, from the end of central
+/// directory record
+fn central_directory(data: &[u8]) -> Option<usize> {
+    // The record is 22 bytes plus a comment of up to 64 KiB
+    let search_start = data.len().saturating_sub(22 + usize::from(u16::MAX));
+    let end = search_start
+        + data[search_start..]
+            .windows(4)
+            .rposition(|window| window == END_OF_CENTRAL_DIRECTORY)?;
+    let record = data.get(end..end + 22)?;
+    usize::try_from(le_u32(record, 16)).ok()
+}
+
+/// Name of the encryption method of a ZIP entry
+fn zip_method(flags: u16, method: u16, extra: &[u8]) -> String {
+    if method == METHOD_AES {
+        let mut fields = extra;
+        while let (Some(id), Some(len)) = (fields.get(..2), fields.get(2..4)) {
+            let len = usize::from(le_u16(len, 0));
+            let body = fields.get(4..4 + len).unwrap_or_default();
+            if le_u16(id, 0) == AES_EXTRA_FIELD {
+                return match body.get(4) {
+                    Some(1) => "AES-128",
+                    Some(2) => "AES-192",
+                    Some(3) => "AES-256",
+                    _ => "AES",
+                }
+                .to_string();
+            }
+            fields = fields.get(4 + len..).unwrap_or_default();
+        }
+        "AES".to_string()
+    } else if flags & FLAG_STRONG_ENCRYPTION != 0 {
+        "PKWARE strong encryption".to_string()
+    } else {
+        "ZipCrypto".to_string()
+    }
+}
+
+/// Name of the encryption method described by an `EncryptionInfo` stream
+fn ooxml_method(info: &[u8]) -> String {
+    if info.len() < 4 {
+        return "unknown".to_string();
+    }
+    let major = le_u16(info, 0);
+    let minor = le_u16(info, 2);
+    match (major, minor) {
+        (4, 4) => {
+            // An XML descriptor follows the version and reserved field
+            let xml = String::from_utf8_lossy(info.get(8..).unwrap_or_default());
+            let key_data = xml
+                .find("<keyData")
+                .map_or("", |start| &xml[start..])
+                .split('>')
+                .next()
+                .unwrap_or_default();
+            match (
+                attribute(key_data, "cipherAlgorithm"),
+                attribute(key_data, "keyBits"),
+            ) {
+                (Some(cipher), Some(bits)) => format!("Agile ({cipher}-{bits})"),
+                (Some(cipher), None) => format!("Agile ({cipher})"),
+                _ => "Agile".to_string(),
+            }
+        }
+        (2..=4, 2) => {
+            // Version, flags and header size precede the header, whose
+            // algorithm and key size are its third and fifth fields
+            let algorithm = match info.get(20..24).map(|id| le_u32(id, 0)) {
+                Some(0x660E..=0x6610) => "AES",
+                Some(0x6801) => "RC4",
+                _ => return "Standard".to_string(),
+            };
+            match info.get(28..32).map(|bits| le_u32(bits, 0)) {
+                Some(bits) if bits > 0 => format!("Standard ({algorithm}-{bits})"),
+                _ => format!("Standard ({algorithm})"),
+            }
+        }
+        (3 | 4, 3) => "Extensible".to_string(),
+        _ => format!("unknown (version {major}.{minor})"),
+    }
+}
+
+/// Value of the XML attribute `name` in the start tag `tag`
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+fn le_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParserRegistry;
+    use bytes::Bytes;
+    use prism_core::error::ErrorCode;
+    use prism_core::format::Format;
+    use prism_core::parser::{ParseContext, ParseOptions};
+    use std::io::Write;
+
+    /// A ZIP archive with one stored entry, with `flags`, `method` and
+    /// `extra` in its central directory header
+    fn zip_with(flags: u16, method: u16, extra: &[u8]) -> Vec<u8> {
+        let name = b"secret.txt";
+        let mut data = Vec::new();
+        data.extend_from_slice(b"PK\x03\x04");
+        data.extend_from_slice(&[0; 26]);
+        data.extend_from_slice(name);
+
+        let central = data.len();
+        data.extend_from_slice(CENTRAL_HEADER);
+        data.extend_from_slice(&[20, 0, 20, 0]);
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&method.to_le_bytes());
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&u16::try_from(name.len()).unwrap().to_le_bytes());
+        data.extend_from_slice(&u16::try_from(extra.len()).unwrap().to_le_bytes());
+        data.extend_from_slice(&[0; 14]);
+        data.extend_from_slice(name);
+        data.extend_from_slice(extra);
+
+        let size = data.len() - central;
+        data.extend_from_slice(END_OF_CENTRAL_DIRECTORY);
+        data.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        data.extend_from_slice(&u32::try_from(size).unwrap().to_le_bytes());
+        data.extend_from_slice(&u32::try_from(central).unwrap().to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_zip_encryption() {
+        assert!(encrypted_zip_entries(&zip_with(0, 0, &[])).is_empty());
+        assert_eq!(
+            encrypted_zip_entries(&zip_with(FLAG_ENCRYPTED, 8, &[])),
+            [("secret.txt".to_string(), "ZipCrypto".to_string())]
+        );
+
+        let aes = [0x01, 0x99, 7, 0, 2, 0, b'A', b'E', 3, 8, 0];
+        let data = zip_with(FLAG_ENCRYPTED, METHOD_AES, &aes);
+        assert_eq!(encrypted_zip_entries(&data)[0].1, "AES-256");
+        let err = check_zip(&data, "ZIP").unwrap_err();
+        assert_eq!(err.encryption_method(), Some("AES-256"));
+        assert!(encrypted_zip_entries(b"not a zip").is_empty());
+    }
+
+    /// A compound file holding an Agile-encrypted OOXML package
+    fn encrypted_package() -> Vec<u8> {
+        let mut compound = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        let mut info = vec![4, 0, 4, 0, 0x40, 0, 0, 0];
+        info.extend_from_slice(
+            br#"<?xml version="1.0"?><encryption><keyData saltSize="16" blockSize="16" keyBits="256" cipherAlgorithm="AES"/></encryption>"#,
+        );
+        compound
+            .create_stream(ENCRYPTION_INFO)
+            .unwrap()
+            .write_all(&info)
+            .unwrap();
+        compound
+            .create_stream(ENCRYPTED_PACKAGE)
+            .unwrap()
+            .write_all(&[0; 64])
+            .unwrap();
+        compound.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_ooxml_encryption() {
+        let data = encrypted_package();
+        assert_eq!(ooxml_encryption(&data).as_deref(), Some("Agile (AES-256)"));
+        assert!(matches!(
+            check_package(&data, "DOCX"),
+            Err(Error::EncryptedDocument { format, .. }) if format == "DOCX"
+        ));
+
+        let mut standard = vec![
+            3, 0, 2, 0, 0x24, 0, 0, 0, 0xA4, 0, 0, 0, 0x24, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        standard.extend_from_slice(&0x660E_u32.to_le_bytes());
+        standard.extend_from_slice(&0x8004_u32.to_le_bytes());
+        standard.extend_from_slice(&128_u32.to_le_bytes());
+        assert_eq!(ooxml_method(&standard), "Standard (AES-128)");
+    }
+
+    #[tokio::test]
+    async fn test_parse_encrypted_docx() {
+        let data = encrypted_package();
+        let registry = ParserRegistry::with_default_parsers();
+        let detected = registry.detect(&data, Some("report.docx")).unwrap();
+        assert_eq!(detected.format, Format::docx());
+
+        let parser = registry
+            .get_parser_for_data(&detected.format, &data)
+            .unwrap();
+        let context = ParseContext {
+            format: detected.format,
+            filename: Some("report.docx".to_string()),
+            size: data.len(),
+            options: ParseOptions::default(),
+            progress: None,
+        };
+        let err = parser.parse(Bytes::from(data), context).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Encrypted);
+        assert_eq!(err.encryption_method(), Some("Agile (AES-256)"));
+    }
+}
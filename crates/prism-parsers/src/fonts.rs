@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Font program helpers shared by the PDF and Office parsers.
+//!
+//! Parsers find embedded font files in format-specific places; this module
+//! turns the raw bytes and names they find into [`FontResource`]s with the
+//! file format, subset flag and embedding permissions filled in.
+
+use prism_core::document::{EmbeddingRights, FontResource};
+
+/// Length of the tag PDF producers prefix to subset font names (`ABCDEF+`)
+const SUBSET_TAG_LEN: usize = 6;
+
+/// Describe an embedded font file
+///
+/// `name` is the font name as recorded by the container, e.g.
+/// `ABCDEF+Calibri-Bold` in a PDF; a subset tag is recognised and removed.
+#[must_use]
+pub fn embedded_font(name: &str, data: Vec<u8>) -> FontResource {
+    let (subset, family, style) = split_font_name(name);
+    FontResource {
+        subset,
+        ..embedded_face(&family, &style, data)
+    }
+}
+
+/// Describe an embedded font file whose family and style are known
+#[must_use]
+pub fn embedded_face(family: &str, style: &str, data: Vec<u8>) -> FontResource {
+    FontResource {
+        embedded: true,
+        font_type: font_type(&data).map(str::to_string),
+        embedding_rights: embedding_rights(&data),
+        data: Some(data),
+        ..FontResource::new(family, style)
+    }
+}
+
+/// Describe a font that is referenced but not embedded
+#[must_use]
+pub fn referenced_font(name: &str) -> FontResource {
+    let (_, family, style) = split_font_name(name);
+    FontResource::new(family, style)
+}
+
+/// Split a font name into subset flag, family and style
+///
+/// Handles PostScript (`Calibri-Bold`) and PDF (`Arial,BoldItalic`) naming.
+/// Names without a style part are `Regular`.
+#[must_use]
+pub fn split_font_name(name: &str) -> (bool, String, String) {
+    let name = name.trim();
+    let (subset, name) = match name.split_once('+') {
+        Some((tag, rest))
+            if tag.len() == SUBSET_TAG_LEN && tag.bytes().all(|b| b.is_ascii_uppercase()) =>
+        {
+            (true, rest)
+        }
+        _ => (false, name),
+    };
+    match name.rsplit_once([',', '-']) {
+        Some((family, style)) if !family.is_empty() && !style.is_empty() => {
+            (subset, family.to_string(), style.to_string())
+        }
+        _ => (subset, name.to_string(), "Regular".to_string()),
+    }
+}
+
+/// Font file format, sniffed from the data
+#[must_use]
+pub fn font_type(data: &[u8]) -> Option<&'static str> {
+    match data.get(..4)? {
+        [0x00, 0x01, 0x00, 0x00] | b"true" => Some("TrueType"),
+        b"ttcf" => Some("TrueType Collection"),
+        b"OTTO" => Some("OpenType"),
+        b"wOFF" => Some("WOFF"),
+        b"wOF2" => Some("WOFF2"),
+        _ if data.starts_with(b"%!PS-AdobeFont") || data.starts_with(b"%!FontType1") => {
+            Some("Type1")
+        }
+        // Bare CFF as found in PDF FontFile3 streams: major version 1
+        [0x01, 0x00, 0x04, _] => Some("CFF"),
+        // Embedded OpenType keeps its magic number after the size fields
+        _ if data.get(34..36) == Some(&[0x4C, 0x50]) => Some("EOT"),
+        _ => None,
+    }
+}
+
+/// Embedding permissions from the `OS/2` table of a TrueType or OpenType
+/// font; `Unknown` for other formats or damaged data
+#[must_use]
+pub fn embedding_rights(data: &[u8]) -> EmbeddingRights {
+    fs_type(data).map_or(EmbeddingRights::Unknown, EmbeddingRights::from_fs_type)
+}
+
+/// Read `OS/2.fsType` from an sfnt font (the first font of a collection)
+fn fs_type(data: &[u8]) -> Option<u16> {
+    let font = if data.get(..4)? == b"ttcf" {
+        usize::try_from(read_u32(data, 12)?).ok()?
+    } else {
+        0
+    };
+    let tables = usize::from(read_u16(data, font + 4)?);
+    (0..tables)
+        .map(|index| font + 12 + index * 16)
+        .find(|&record| data.get(record..record + 4) == Some(b"OS/2".as_slice()))
+        .and_then(|record| usize::try_from(read_u32(data, record + 8)?).ok())
+        .and_then(|table| read_u16(data, table + 8))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal TrueType file with only an `OS/2` table
+    fn truetype(fs_type: u16) -> Vec<u8> {
+        let mut data = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+        data.resize(12, 0);
+        data.extend_from_slice(b"OS/2");
+        data.extend_from_slice(&[0; 4]); // checksum
+        data.extend_from_slice(&28u32.to_be_bytes()); // offset
+        data.extend_from_slice(&10u32.to_be_bytes()); // length
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&fs_type.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_split_font_name() {
+        assert_eq!(
+            split_font_name("ABCDEF+Calibri-Bold"),
+            (true, "Calibri".to_string(), "Bold".to_string())
+        );
+        assert_eq!(
+            split_font_name("Arial,BoldItalic"),
+            (false, "Arial".to_string(), "BoldItalic".to_string())
+        );
+        assert_eq!(
+            split_font_name("Helvetica"),
+            (false, "Helvetica".to_string(), "Regular".to_string())
+        );
+        assert_eq!(
+            split_font_name("Abc+Sans"),
+            (false, "Abc+Sans".to_string(), "Regular".to_string())
+        );
+    }
+
+    #[test]
+    fn test_embedded_font() {
+        let font = embedded_font("QWERTY+Inter-Italic", truetype(0x0004));
+        assert!(font.embedded && font.subset);
+        assert_eq!(
+            (font.family.as_str(), font.style.as_str()),
+            ("Inter", "Italic")
+        );
+        assert_eq!(font.font_type.as_deref(), Some("TrueType"));
+        assert_eq!(font.embedding_rights, EmbeddingRights::PreviewAndPrint);
+
+        assert_eq!(embedding_rights(&truetype(0)), EmbeddingRights::Installable);
+        assert_eq!(
+            embedding_rights(&truetype(0x000A)),
+            EmbeddingRights::Editable
+        );
+        assert_eq!(embedding_rights(b"OTTO"), EmbeddingRights::Unknown);
+        assert_eq!(font_type(b"%!PS-AdobeFont-1.0"), Some("Type1"));
+    }
+}
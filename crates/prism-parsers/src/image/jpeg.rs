@@ -96,6 +96,9 @@ impl Parser for JpegParser {
             original_size: Some(Dimensions::new(width as f64, height as f64)),
             style: ShapeStyle::default(),
             rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
         };
 
         // Create single page with the image
@@ -102,6 +102,9 @@ impl Parser for PngParser {
             original_size: Some(Dimensions::new(width as f64, height as f64)),
             style: ShapeStyle::default(),
             rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
         };
 
         // Create single page with the image
@@ -194,6 +197,7 @@ mod tests {
             filename: Some("test.png".to_string()),
             size: data_len,
             options: Default::default(),
+            progress: None,
         };
 
         let result = parser.parse(data, context).await;
@@ -225,6 +229,7 @@ mod tests {
             filename: Some("invalid.png".to_string()),
             size: invalid_data.len(),
             options: Default::default(),
+            progress: None,
         };
 
         let result = parser.parse(invalid_data, context).await;
@@ -3,6 +3,7 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::stream;
 use image::{ImageFormat, RgbaImage};
 use prism_core::{
     document::{
@@ -12,8 +13,10 @@ use prism_core::{
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+    progress::ProgressEvent,
+    stream::{DocumentStream, StreamedPage},
 };
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek};
 use tiff::decoder::{Decoder, DecodingResult};
 use tracing::{debug, info, warn};
 
@@ -57,6 +60,19 @@ impl Parser for TiffParser {
     }
 
     async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
+        let document = self.parse_stream(data, context).await?.collect().await?;
+
+        info!(
+            "Successfully parsed TIFF with {} page(s)",
+            document.pages.len()
+        );
+
+        Ok(document)
+    }
+
+    /// Decode one TIFF directory at a time, so only the current page's
+    /// pixels are held in memory
+    async fn parse_stream(&self, data: Bytes, context: ParseContext) -> Result<DocumentStream> {
         debug!(
             "Parsing TIFF image, size: {} bytes, filename: {:?}",
             context.size, context.filename
@@ -67,321 +83,45 @@ impl Parser for TiffParser {
             return Err(Error::ParseError("Invalid TIFF signature".to_string()));
         }
 
-        // Create TIFF decoder
-        let cursor = Cursor::new(&data[..]);
-        let mut decoder = Decoder::new(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to create TIFF decoder: {}", e)))?;
-
-        let mut pages = Vec::new();
-        let mut image_resources = Vec::new();
-        let mut page_number = 1;
-
-        // Iterate through all TIFF pages/directories
-        loop {
-            // Get dimensions for current page
-            let (width, height) = decoder
-                .dimensions()
-                .map_err(|e| Error::ParseError(format!("Failed to get TIFF dimensions: {}", e)))?;
-
-            debug!("TIFF page {} dimensions: {}x{}", page_number, width, height);
-
-            // Decode the image data for this page
-            let decoding_result = decoder.read_image().map_err(|e| {
-                Error::ParseError(format!("Failed to decode TIFF page {}: {}", page_number, e))
-            })?;
-
-            // Convert to RGBA image for consistent handling
-            let rgba_image = match decoding_result {
-                DecodingResult::U8(data) => {
-                    // Check if this is RGB (3 bytes/pixel) or Grayscale (1 byte/pixel)
-                    let pixel_count = (width * height) as usize;
-                    if data.len() == pixel_count * 3 {
-                        // RGB data - convert to RGBA
-                        let mut rgba_data = Vec::with_capacity(pixel_count * 4);
-                        for chunk in data.chunks_exact(3) {
-                            rgba_data.push(chunk[0]); // R
-                            rgba_data.push(chunk[1]); // G
-                            rgba_data.push(chunk[2]); // B
-                            rgba_data.push(255); // A
-                        }
-                        RgbaImage::from_raw(width, height, rgba_data).ok_or_else(|| {
-                            Error::ParseError(format!(
-                                "Failed to create RGBA image from RGB U8 data for page {}",
-                                page_number
-                            ))
-                        })?
-                    } else if data.len() == pixel_count * 4 {
-                        // Already RGBA
-                        RgbaImage::from_raw(width, height, data).ok_or_else(|| {
-                            Error::ParseError(format!(
-                                "Failed to create RGBA image from RGBA U8 data for page {}",
-                                page_number
-                            ))
-                        })?
-                    } else {
-                        // Grayscale - convert to RGBA
-                        RgbaImage::from_raw(
-                            width,
-                            height,
-                            data.into_iter().flat_map(|p| [p, p, p, 255]).collect(),
-                        )
-                        .ok_or_else(|| {
-                            Error::ParseError(format!(
-                                "Failed to create RGBA image from grayscale U8 data for page {}",
-                                page_number
-                            ))
-                        })?
-                    }
-                }
-                DecodingResult::U16(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = (p >> 8) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from U16 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::U32(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = (p >> 24) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from U32 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::U64(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = (p >> 56) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from U64 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::F16(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let float_val = p.to_f32();
-                            let byte = (float_val.clamp(0.0, 1.0) * 255.0) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from F16 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::F32(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = (p.clamp(0.0, 1.0) * 255.0) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from F32 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::F64(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = (p.clamp(0.0, 1.0) * 255.0) as u8;
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from F64 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::I8(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = ((p as i16 + 128) as u8);
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from I8 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::I16(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = ((p >> 8) as i8 as u8);
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from I16 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::I32(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = ((p >> 24) as i8 as u8);
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from I32 data for page {}",
-                        page_number
-                    ))
-                })?,
-                DecodingResult::I64(data) => RgbaImage::from_raw(
-                    width,
-                    height,
-                    data.into_iter()
-                        .flat_map(|p| {
-                            let byte = ((p >> 56) as i8 as u8);
-                            [byte, byte, byte, 255]
-                        })
-                        .collect(),
-                )
-                .ok_or_else(|| {
-                    Error::ParseError(format!(
-                        "Failed to create RGBA image from I64 data for page {}",
-                        page_number
-                    ))
-                })?,
-            };
-
-            // Convert to PNG for web compatibility
-            let dynamic_img = image::DynamicImage::ImageRgba8(rgba_image);
-            let mut png_data = Vec::new();
-            dynamic_img
-                .write_to(&mut Cursor::new(&mut png_data), ImageFormat::Png)
-                .map_err(|e| {
-                    Error::ParseError(format!(
-                        "Failed to encode TIFF page {} as PNG: {}",
-                        page_number, e
-                    ))
-                })?;
-
-            // Create resource ID for the image
-            let resource_id = format!("img_page_{}", page_number);
-
-            // Create image resource
-            let image_resource = ImageResource {
-                id: resource_id.clone(),
-                mime_type: "image/png".to_string(),
-                data: Some(png_data),
-                url: None,
-                width,
-                height,
-            };
-
-            // Create image block
-            let image_block = ImageBlock {
-                bounds: Rect::new(0.0, 0.0, width as f64, height as f64),
-                resource_id: resource_id.clone(),
-                alt_text: None,
-                format: Some("image/tiff".to_string()),
-                original_size: Some(Dimensions::new(width as f64, height as f64)),
-                style: ShapeStyle::default(),
-                rotation: 0.0,
-            };
-
-            // Create page with the image
-            let page = Page {
-                number: page_number,
-                dimensions: Dimensions {
-                    width: width as f64,
-                    height: height as f64,
-                },
-                content: vec![ContentBlock::Image(image_block)],
-                metadata: Default::default(),
-                annotations: Vec::new(),
-            };
-
-            pages.push(page);
-            image_resources.push(image_resource);
-
-            // Try to move to next page/directory
-            if decoder.more_images() {
-                if let Err(e) = decoder.next_image() {
-                    warn!("Failed to move to next TIFF page: {}", e);
-                    return Err(Error::ParseError(format!(
-                        "Failed to move to next TIFF page: {}",
-                        e
-                    )));
-                }
-                page_number += 1;
-            } else {
-                break;
-            }
-        }
-
         // Create basic metadata
         let mut metadata = Metadata::default();
         if let Some(ref filename) = context.filename {
             metadata.title = Some(filename.clone());
         }
         metadata.add_custom("format", "TIFF");
-        metadata.add_custom("page_count", pages.len() as i64);
+        let page_count = count_pages(&data)?;
+        metadata.add_custom("page_count", i64::try_from(page_count).unwrap_or(i64::MAX));
 
-        // Create document
-        let mut document = Document::new();
-        document.pages = pages;
-        document.metadata = metadata;
-        document.resources.images = image_resources;
+        let mut header = Document::new();
+        header.metadata = metadata;
 
-        info!(
-            "Successfully parsed TIFF with {} page(s)",
-            document.pages.len()
-        );
+        // Iterate through all TIFF pages/directories
+        let decoder = decoder(Cursor::new(data))?;
+        let pages = stream::unfold(Some(Ok((decoder, 1))), move |state| {
+            let context = context.clone();
+            async move {
+                let (mut decoder, page_number) = match state? {
+                    Ok(next) => next,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let page = match decode_page(&mut decoder, page_number) {
+                    Ok(page) => page,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                context.report(ProgressEvent::PageParsed {
+                    parsed: page_number as usize,
+                    total: Some(page_count),
+                });
+                let state = match next_page(&mut decoder) {
+                    Ok(true) => Some(Ok((decoder, page_number + 1))),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                };
+                Some((Ok(page), state))
+            }
+        });
 
-        Ok(document)
+        Ok(DocumentStream::new(header, pages))
     }
 
     fn metadata(&self) -> ParserMetadata {
@@ -391,12 +131,329 @@ impl Parser for TiffParser {
             features: vec![
                 ParserFeature::ImageExtraction,
                 ParserFeature::MetadataExtraction,
+                ParserFeature::StreamingSupport,
             ],
             requires_sandbox: false,
         }
     }
 }
 
+/// Create a TIFF decoder
+fn decoder<R: Read + Seek>(reader: R) -> Result<Decoder<R>> {
+    Decoder::new(reader)
+        .map_err(|e| Error::ParseError(format!("Failed to create TIFF decoder: {e}")))
+}
+
+/// Move `decoder` to the next page/directory, returning whether there is one
+fn next_page<R: Read + Seek>(decoder: &mut Decoder<R>) -> Result<bool> {
+    if !decoder.more_images() {
+        return Ok(false);
+    }
+    decoder.next_image().map_err(|e| {
+        warn!("Failed to move to next TIFF page: {e}");
+        Error::ParseError(format!("Failed to move to next TIFF page: {e}"))
+    })?;
+    Ok(true)
+}
+
+/// Number of pages, found by walking the directories without decoding
+/// any pixels
+fn count_pages(data: &[u8]) -> Result<usize> {
+    let mut decoder = decoder(Cursor::new(data))?;
+    let mut count = 1;
+    while next_page(&mut decoder)? {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Convert decoded TIFF samples of any depth to RGBA
+fn rgba_image(
+    result: DecodingResult,
+    width: u32,
+    height: u32,
+    page_number: u32,
+) -> Result<RgbaImage> {
+    let rgba_image = match result {
+        DecodingResult::U8(data) => {
+            // Check if this is RGB (3 bytes/pixel) or Grayscale (1 byte/pixel)
+            let pixel_count = (width * height) as usize;
+            if data.len() == pixel_count * 3 {
+                // RGB data - convert to RGBA
+                let mut rgba_data = Vec::with_capacity(pixel_count * 4);
+                for chunk in data.chunks_exact(3) {
+                    rgba_data.push(chunk[0]); // R
+                    rgba_data.push(chunk[1]); // G
+                    rgba_data.push(chunk[2]); // B
+                    rgba_data.push(255); // A
+                }
+                RgbaImage::from_raw(width, height, rgba_data).ok_or_else(|| {
+                    Error::ParseError(format!(
+                        "Failed to create RGBA image from RGB U8 data for page {}",
+                        page_number
+                    ))
+                })?
+            } else if data.len() == pixel_count * 4 {
+                // Already RGBA
+                RgbaImage::from_raw(width, height, data).ok_or_else(|| {
+                    Error::ParseError(format!(
+                        "Failed to create RGBA image from RGBA U8 data for page {}",
+                        page_number
+                    ))
+                })?
+            } else {
+                // Grayscale - convert to RGBA
+                RgbaImage::from_raw(
+                    width,
+                    height,
+                    data.into_iter().flat_map(|p| [p, p, p, 255]).collect(),
+                )
+                .ok_or_else(|| {
+                    Error::ParseError(format!(
+                        "Failed to create RGBA image from grayscale U8 data for page {}",
+                        page_number
+                    ))
+                })?
+            }
+        }
+        DecodingResult::U16(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = (p >> 8) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from U16 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::U32(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = (p >> 24) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from U32 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::U64(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = (p >> 56) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from U64 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::F16(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let float_val = p.to_f32();
+                    let byte = (float_val.clamp(0.0, 1.0) * 255.0) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from F16 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::F32(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = (p.clamp(0.0, 1.0) * 255.0) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from F32 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::F64(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = (p.clamp(0.0, 1.0) * 255.0) as u8;
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from F64 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::I8(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = ((p as i16 + 128) as u8);
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from I8 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::I16(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = ((p >> 8) as i8 as u8);
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from I16 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::I32(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = ((p >> 24) as i8 as u8);
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from I32 data for page {}",
+                page_number
+            ))
+        })?,
+        DecodingResult::I64(data) => RgbaImage::from_raw(
+            width,
+            height,
+            data.into_iter()
+                .flat_map(|p| {
+                    let byte = ((p >> 56) as i8 as u8);
+                    [byte, byte, byte, 255]
+                })
+                .collect(),
+        )
+        .ok_or_else(|| {
+            Error::ParseError(format!(
+                "Failed to create RGBA image from I64 data for page {}",
+                page_number
+            ))
+        })?,
+    };
+    Ok(rgba_image)
+}
+
+/// Decode the current image of `decoder` into page `page_number`
+fn decode_page<R: Read + Seek>(decoder: &mut Decoder<R>, page_number: u32) -> Result<StreamedPage> {
+    let (width, height) = decoder
+        .dimensions()
+        .map_err(|e| Error::ParseError(format!("Failed to get TIFF dimensions: {}", e)))?;
+
+    debug!("TIFF page {} dimensions: {}x{}", page_number, width, height);
+
+    // Decode the image data for this page
+    let decoding_result = decoder.read_image().map_err(|e| {
+        Error::ParseError(format!("Failed to decode TIFF page {}: {}", page_number, e))
+    })?;
+
+    // Convert to RGBA image for consistent handling
+    let rgba_image = rgba_image(decoding_result, width, height, page_number)?;
+
+    // Convert to PNG for web compatibility
+    let dynamic_img = image::DynamicImage::ImageRgba8(rgba_image);
+    let mut png_data = Vec::new();
+    dynamic_img
+        .write_to(&mut Cursor::new(&mut png_data), ImageFormat::Png)
+        .map_err(|e| {
+            Error::ParseError(format!(
+                "Failed to encode TIFF page {} as PNG: {}",
+                page_number, e
+            ))
+        })?;
+
+    // Create resource ID for the image
+    let resource_id = format!("img_page_{}", page_number);
+
+    // Create image resource
+    let image_resource = ImageResource {
+        id: resource_id.clone(),
+        mime_type: "image/png".to_string(),
+        data: Some(png_data),
+        url: None,
+        width,
+        height,
+    };
+
+    // Create image block
+    let image_block = ImageBlock {
+        bounds: Rect::new(0.0, 0.0, width as f64, height as f64),
+        resource_id: resource_id.clone(),
+        alt_text: None,
+        format: Some("image/tiff".to_string()),
+        original_size: Some(Dimensions::new(width as f64, height as f64)),
+        style: ShapeStyle::default(),
+        rotation: 0.0,
+        crop: None,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+
+    // Create page with the image
+    let page = Page {
+        number: page_number,
+        dimensions: Dimensions {
+            width: width as f64,
+            height: height as f64,
+        },
+        content: vec![ContentBlock::Image(image_block)],
+        metadata: Default::default(),
+        annotations: Vec::new(),
+    };
+
+    Ok(StreamedPage {
+        page,
+        images: vec![image_resource],
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -440,4 +497,46 @@ mod tests {
         assert!(!metadata.requires_sandbox);
         assert!(!metadata.features.is_empty());
     }
+
+    fn two_page_tiff() -> Vec<u8> {
+        use tiff::encoder::{colortype, TiffEncoder};
+
+        let mut data = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut data).unwrap();
+        encoder
+            .write_image::<colortype::Gray8>(2, 2, &[0, 64, 128, 255])
+            .unwrap();
+        encoder
+            .write_image::<colortype::RGB8>(3, 1, &[255, 0, 0, 0, 255, 0, 0, 0, 255])
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream() {
+        use futures::StreamExt;
+
+        let data = Bytes::from(two_page_tiff());
+        let context = ParseContext {
+            format: Format::tiff(),
+            filename: Some("scan.tiff".to_string()),
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        let mut stream = TiffParser::new().parse_stream(data, context).await.unwrap();
+        assert!(matches!(
+            stream.header().metadata.get_custom("page_count"),
+            Some(prism_core::metadata::MetadataValue::Integer(2))
+        ));
+
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.page.number, 1);
+        assert_eq!(first.images[0].id, "img_page_1");
+
+        let document = stream.collect().await.unwrap();
+        assert_eq!(document.pages.len(), 1);
+        assert_eq!(document.pages[0].dimensions.width, 3.0);
+        assert_eq!(document.resources.images[0].id, "img_page_2");
+    }
 }
@@ -14,6 +14,7 @@
 //! - **Email**: MSG, EML, PST (planned)
 //! - **Images**: JPEG, PNG, TIFF, GIF, BMP (planned)
 //! - **Archives**: ZIP, RAR, 7z, TAR (planned)
+//! - **Audio/Video**: MP3, MP4, MOV, MKV, `WebM` (metadata only)
 //! - **CAD**: DWG, DXF (planned)
 //!
 //! ## Usage
@@ -43,6 +44,7 @@
 //!         filename: Some("document.pdf".to_string()),
 //!         size: data_len,
 //!         options: Default::default(),
+//!         progress: None,
 //!     }
 //! ).await?;
 //!
@@ -57,19 +59,25 @@
 #![allow(clippy::module_name_repetitions)]
 
 pub mod email;
+pub mod encryption;
+pub mod fonts;
 pub mod image;
+pub mod media;
 pub mod office;
 pub mod pdf;
 pub mod registry;
+pub mod security;
+pub mod signatures;
 pub mod text;
 
 // Re-export commonly used types
-pub use archive::ArchiveParser;
+pub use archive::{ArchiveEntry, ArchiveParser};
 pub use email::{EmlParser, IcsParser, MboxParser, MsgParser, VcfParser};
 pub use image::{JpegParser, PngParser, TiffParser};
+pub use media::{MediaInfo, MediaParser};
 pub use office::{DocParser, DocxParser, PptParser, PptxParser, XlsParser, XlsxParser};
 pub use pdf::PdfParser;
-pub use registry::ParserRegistry;
+pub use registry::{ParserRegistry, ParserSupport};
 pub use text::{
     CsvParser, HtmlParser, JsonParser, LogParser, MarkdownParser, TextParser, XmlParser,
 };
@@ -0,0 +1,610 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Matroska and `WebM`
+//!
+//! The top-level elements of the segment are read, skipping the clusters
+//! that hold the streams: the segment information gives the duration,
+//! title, creation date and writing application, each track entry a
+//! stream, and the tags and attachments the rest. Tags that target a
+//! single track are only read for the `BPS` statistic muxers such as
+//! mkvmerge write, which is the track's bitrate. An attached image named
+//! `cover` is the cover art, as the Matroska attachment conventions have
+//! it, or else the first attached image.
+
+use chrono::{DateTime, Utc};
+use prism_core::error::{Error, Result};
+
+use super::{CoverArt, MediaInfo, MediaStream, StreamKind};
+
+/// Magic bytes of the EBML header
+const EBML_MAGIC: [u8; 4] = [0x1A, 0x45, 0xDF, 0xA3];
+
+/// Seconds from the Unix epoch to 2001-01-01, the epoch of Matroska dates
+const MATROSKA_EPOCH_OFFSET: i64 = 978_307_200;
+
+// Element IDs, with their length markers
+const EBML: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const DATE_UTC: u32 = 0x4461;
+const TITLE: u32 = 0x7BA9;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const LANGUAGE: u32 = 0x22_B59C;
+const LANGUAGE_BCP47: u32 = 0x22_B59D;
+const DEFAULT_DURATION: u32 = 0x23_E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const OUTPUT_SAMPLING_FREQUENCY: u32 = 0x78B5;
+const CHANNELS: u32 = 0x9F;
+const TAGS: u32 = 0x1254_C367;
+const TAG: u32 = 0x7373;
+const TARGETS: u32 = 0x63C0;
+const TAG_TRACK_UID: u32 = 0x63C5;
+const SIMPLE_TAG: u32 = 0x67C8;
+const TAG_NAME: u32 = 0x45A3;
+const TAG_STRING: u32 = 0x4487;
+const ATTACHMENTS: u32 = 0x1941_A469;
+const ATTACHED_FILE: u32 = 0x61A7;
+const FILE_NAME: u32 = 0x466E;
+const FILE_MEDIA_TYPE: u32 = 0x4660;
+const FILE_DATA: u32 = 0x465C;
+
+/// Whether `data` starts with an EBML header
+pub(crate) fn is_matroska(data: &[u8]) -> bool {
+    data.starts_with(&EBML_MAGIC)
+}
+
+/// Read the segment information, tracks, tags and attachments of a
+/// Matroska or `WebM` file
+pub(crate) fn info(data: &[u8]) -> Result<MediaInfo> {
+    let mut top = elements(data);
+    if top.next().map(|(id, _)| id) != Some(EBML) {
+        return Err(Error::corrupt("Matroska", "no EBML header"));
+    }
+    let segment = top
+        .find(|(id, _)| *id == SEGMENT)
+        .map(|(_, segment)| segment)
+        .ok_or_else(|| Error::corrupt("Matroska", "no segment"))?;
+
+    let mut info = MediaInfo::default();
+    let mut track_uids = Vec::new();
+    let mut tags = Vec::new();
+    for (id, content) in elements(segment) {
+        match id {
+            INFO => segment_info(content, &mut info),
+            TRACKS => {
+                for (_, entry) in elements(content).filter(|(id, _)| *id == TRACK_ENTRY) {
+                    let (stream, uid) = track(entry);
+                    info.streams.push(stream);
+                    track_uids.push(uid);
+                }
+            }
+            // Read once the tracks they may target are known
+            TAGS => tags.push(content),
+            ATTACHMENTS => attachments(content, &mut info),
+            _ => {}
+        }
+    }
+    for content in tags {
+        read_tags(content, &mut info, &track_uids);
+    }
+    Ok(info)
+}
+
+/// The elements in `data`, as their ID and content
+///
+/// An element of unknown size, as live streams write, runs to the end of
+/// `data`.
+fn elements(data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        let (id, after_id) = element_id(data, position)?;
+        let (size, start) = vint(data, after_id)?;
+        let end = size
+            .and_then(|size| usize::try_from(size).ok())
+            .and_then(|size| start.checked_add(size))
+            .map_or(data.len(), |end| end.min(data.len()));
+        position = end;
+        Some((id, data.get(start..end)?))
+    })
+}
+
+/// An element ID, length marker included, and the position after it
+fn element_id(data: &[u8], position: usize) -> Option<(u32, usize)> {
+    let first = *data.get(position)?;
+    let length = usize::try_from(first.leading_zeros()).ok()? + 1;
+    if length > 4 {
+        return None;
+    }
+    let bytes = data.get(position..position + length)?;
+    let id = bytes
+        .iter()
+        .fold(0u32, |id, &byte| (id << 8) | u32::from(byte));
+    Some((id, position + length))
+}
+
+/// A variable-length size, `None` if unknown (all ones), and the position
+/// after it
+fn vint(data: &[u8], position: usize) -> Option<(Option<u64>, usize)> {
+    let first = *data.get(position)?;
+    let length = usize::try_from(first.leading_zeros()).ok()? + 1;
+    if length > 8 {
+        return None;
+    }
+    let bytes = data.get(position + 1..position + length)?;
+    let value = bytes
+        .iter()
+        .fold(u64::from(first) & (0xFF >> length), |value, &byte| {
+            (value << 8) | u64::from(byte)
+        });
+    let unknown = (1u64 << (7 * length)) - 1;
+    Some(((value != unknown).then_some(value), position + length))
+}
+
+/// Read the segment information: duration, title, date and applications
+fn segment_info(content: &[u8], info: &mut MediaInfo) {
+    let mut scale = 1_000_000;
+    let mut duration = None;
+    for (id, value) in elements(content) {
+        match id {
+            TIMESTAMP_SCALE => scale = unsigned(value),
+            DURATION => duration = float(value),
+            DATE_UTC => {
+                let nanoseconds = signed(value);
+                info.created = DateTime::<Utc>::from_timestamp(
+                    MATROSKA_EPOCH_OFFSET + nanoseconds.div_euclid(1_000_000_000),
+                    u32::try_from(nanoseconds.rem_euclid(1_000_000_000)).unwrap_or(0),
+                );
+            }
+            TITLE => info.add_tag("title", &string(value)),
+            WRITING_APP => info.add_tag("encoder", &string(value)),
+            MUXING_APP => info.add_tag("muxer", &string(value)),
+            _ => {}
+        }
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let scale = scale as f64;
+    info.duration = duration
+        .map(|duration| duration * scale / 1e9)
+        .filter(|duration| *duration > 0.0);
+}
+
+/// A stream for a track entry, and the track's UID
+fn track(entry: &[u8]) -> (MediaStream, u64) {
+    let mut stream = MediaStream::new(StreamKind::Other, "Unknown");
+    let mut uid = 0;
+    let mut language = None;
+    let mut language_bcp47 = None;
+    let mut default_duration = None;
+    for (id, value) in elements(entry) {
+        match id {
+            TRACK_UID => uid = unsigned(value),
+            TRACK_TYPE => {
+                stream.kind = match unsigned(value) {
+                    1 => StreamKind::Video,
+                    2 => StreamKind::Audio,
+                    17 => StreamKind::Subtitle,
+                    _ => StreamKind::Other,
+                };
+            }
+            CODEC_ID => stream.codec = codec_name(&string(value)),
+            LANGUAGE => language = Some(string(value)),
+            LANGUAGE_BCP47 => language_bcp47 = Some(string(value)),
+            DEFAULT_DURATION => default_duration = Some(unsigned(value)),
+            VIDEO => {
+                for (id, value) in elements(value) {
+                    match id {
+                        PIXEL_WIDTH => stream.width = u32::try_from(unsigned(value)).ok(),
+                        PIXEL_HEIGHT => stream.height = u32::try_from(unsigned(value)).ok(),
+                        _ => {}
+                    }
+                }
+            }
+            AUDIO => {
+                // Without an element, one channel at 8 kHz
+                let mut sampling = 8000.0;
+                let mut output_sampling = None;
+                let mut channels = 1;
+                for (id, value) in elements(value) {
+                    match id {
+                        SAMPLING_FREQUENCY => sampling = float(value).unwrap_or(sampling),
+                        OUTPUT_SAMPLING_FREQUENCY => output_sampling = float(value),
+                        CHANNELS => channels = unsigned(value),
+                        _ => {}
+                    }
+                }
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let sample_rate = output_sampling.unwrap_or(sampling).round() as u32;
+                stream.sample_rate = Some(sample_rate);
+                stream.channels = u32::try_from(channels).ok();
+            }
+            _ => {}
+        }
+    }
+    // English when the track does not say
+    stream.language = language_bcp47
+        .or(language)
+        .or_else(|| Some("eng".to_string()))
+        .filter(|language| language != "und");
+    if stream.kind == StreamKind::Video {
+        #[allow(clippy::cast_precision_loss)]
+        let frame_rate = default_duration
+            .filter(|&nanoseconds| nanoseconds > 0)
+            .map(|nanoseconds| (1e12 / nanoseconds as f64).round() / 1000.0);
+        stream.frame_rate = frame_rate;
+    }
+    (stream, uid)
+}
+
+/// Read the tags of a `Tags` element
+///
+/// Tags that target tracks only give their bitrate.
+fn read_tags(content: &[u8], info: &mut MediaInfo, track_uids: &[u64]) {
+    for (_, tag) in elements(content).filter(|(id, _)| *id == TAG) {
+        let targets: Vec<u64> = elements(tag)
+            .filter(|(id, _)| *id == TARGETS)
+            .flat_map(|(_, targets)| elements(targets))
+            .filter(|(id, _)| *id == TAG_TRACK_UID)
+            .map(|(_, uid)| unsigned(uid))
+            .filter(|&uid| uid != 0)
+            .collect();
+        for (_, simple) in elements(tag).filter(|(id, _)| *id == SIMPLE_TAG) {
+            let mut name = None;
+            let mut value = None;
+            for (id, content) in elements(simple) {
+                match id {
+                    TAG_NAME => name = Some(string(content)),
+                    TAG_STRING => value = Some(string(content)),
+                    _ => {}
+                }
+            }
+            let (Some(name), Some(value)) = (name, value) else {
+                continue;
+            };
+            if targets.is_empty() {
+                info.add_tag(&tag_name(&name), &value);
+            } else if name == "BPS" {
+                let Ok(bitrate) = value.trim().parse::<u64>() else {
+                    continue;
+                };
+                for uid in &targets {
+                    if let Some(index) = track_uids.iter().position(|track| track == uid) {
+                        info.streams[index].bitrate.get_or_insert(bitrate);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Read the attachments: their names, and the cover art
+fn attachments(content: &[u8], info: &mut MediaInfo) {
+    let mut names = Vec::new();
+    // The best image so far, by rank: `cover`, any other, `small_cover`
+    let mut best: Option<(u8, CoverArt)> = None;
+    for (_, file) in elements(content).filter(|(id, _)| *id == ATTACHED_FILE) {
+        let mut name = String::new();
+        let mut mime_type = String::new();
+        let mut data: &[u8] = &[];
+        for (id, value) in elements(file) {
+            match id {
+                FILE_NAME => name = string(value),
+                FILE_MEDIA_TYPE => mime_type = string(value),
+                FILE_DATA => data = value,
+                _ => {}
+            }
+        }
+        if !name.is_empty() {
+            names.push(name.clone());
+        }
+        if !mime_type.starts_with("image/") || data.is_empty() {
+            continue;
+        }
+        let stem = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+        let rank = match stem.to_lowercase().as_str() {
+            "cover" => 0,
+            "small_cover" | "cover_land" | "small_cover_land" => 2,
+            _ => 1,
+        };
+        if best.as_ref().map_or(true, |(best, _)| rank < *best) {
+            best = Some((
+                rank,
+                CoverArt {
+                    mime_type,
+                    data: data.to_vec(),
+                },
+            ));
+        }
+    }
+    if !names.is_empty() {
+        info.add_tag("attachments", &names.join("; "));
+    }
+    if info.cover.is_none() {
+        info.cover = best.map(|(_, cover)| cover);
+    }
+}
+
+/// The common name of a Matroska tag
+fn tag_name(name: &str) -> String {
+    match name {
+        "TITLE" => "title",
+        "ARTIST" => "artist",
+        "ALBUM" => "album",
+        "DATE_RELEASED" | "DATE_RECORDED" | "DATE" => "date",
+        "GENRE" => "genre",
+        "COMMENT" => "comment",
+        "COMPOSER" => "composer",
+        "COPYRIGHT" => "copyright",
+        "ENCODER" => "encoder",
+        "DESCRIPTION" | "SYNOPSIS" => "description",
+        "PART_NUMBER" => "track",
+        _ => return name.to_lowercase(),
+    }
+    .to_string()
+}
+
+/// The codec of a Matroska codec ID
+fn codec_name(codec_id: &str) -> String {
+    let name = match codec_id {
+        "V_MPEG4/ISO/AVC" => "H.264",
+        "V_MPEGH/ISO/HEVC" => "H.265",
+        "V_AV1" => "AV1",
+        "V_VP8" => "VP8",
+        "V_VP9" => "VP9",
+        "V_MPEG1" => "MPEG-1 Video",
+        "V_MPEG2" => "MPEG-2 Video",
+        "V_THEORA" => "Theora",
+        "V_MJPEG" => "Motion JPEG",
+        "V_PRORES" => "ProRes",
+        "A_MPEG/L3" => "MP3",
+        "A_MPEG/L2" => "MP2",
+        "A_AC3" => "AC-3",
+        "A_EAC3" => "E-AC-3",
+        "A_TRUEHD" => "TrueHD",
+        "A_DTS" => "DTS",
+        "A_OPUS" => "Opus",
+        "A_VORBIS" => "Vorbis",
+        "A_FLAC" => "FLAC",
+        "A_ALAC" => "ALAC",
+        "S_TEXT/UTF8" => "SubRip",
+        "S_TEXT/ASS" | "S_ASS" => "ASS",
+        "S_TEXT/SSA" | "S_SSA" => "SSA",
+        "S_TEXT/WEBVTT" => "WebVTT",
+        "S_HDMV/PGS" => "PGS",
+        "S_VOBSUB" => "VobSub",
+        _ if codec_id.starts_with("V_MPEG4/ISO/") => "MPEG-4 Visual",
+        _ if codec_id.starts_with("A_AAC") => "AAC",
+        _ if codec_id.starts_with("A_PCM/") => "PCM",
+        _ => codec_id,
+    };
+    name.to_string()
+}
+
+fn unsigned(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |value, &byte| (value << 8) | u64::from(byte))
+}
+
+fn signed(data: &[u8]) -> i64 {
+    let Some((&first, rest)) = data.split_first() else {
+        return 0;
+    };
+    rest.iter()
+        .take(7)
+        .fold(i64::from(i8::from_be_bytes([first])), |value, &byte| {
+            (value << 8) | i64::from(byte)
+        })
+}
+
+fn float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f64::from(f32::from_be_bytes(data.try_into().ok()?))),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// A UTF-8 string, which may be padded with NULs
+fn string(data: &[u8]) -> String {
+    let end = data
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An element with its size as an 8-byte variable-length integer
+    fn element(id: u32, content: &[u8]) -> Vec<u8> {
+        let mut element: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&byte| byte == 0)
+            .collect();
+        element.push(0x01);
+        element.extend_from_slice(&u64::try_from(content.len()).unwrap().to_be_bytes()[1..]);
+        element.extend_from_slice(content);
+        element
+    }
+
+    fn uint(id: u32, value: u64) -> Vec<u8> {
+        element(id, &value.to_be_bytes())
+    }
+
+    /// A 1080p H.264 video track and a French Opus audio track
+    fn tracks() -> Vec<u8> {
+        let video = element(
+            TRACK_ENTRY,
+            &[
+                uint(TRACK_UID, 1),
+                uint(TRACK_TYPE, 1),
+                element(CODEC_ID, b"V_MPEG4/ISO/AVC"),
+                uint(DEFAULT_DURATION, 40_000_000),
+                element(
+                    VIDEO,
+                    &[uint(PIXEL_WIDTH, 1920), uint(PIXEL_HEIGHT, 1080)].concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let audio = element(
+            TRACK_ENTRY,
+            &[
+                uint(TRACK_UID, 2),
+                uint(TRACK_TYPE, 2),
+                element(CODEC_ID, b"A_OPUS"),
+                element(LANGUAGE, b"fre"),
+                element(
+                    AUDIO,
+                    &[
+                        element(SAMPLING_FREQUENCY, &48_000.0f32.to_be_bytes()),
+                        uint(CHANNELS, 2),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        );
+        element(TRACKS, &[video, audio].concat())
+    }
+
+    fn file(doc_type: &str) -> Vec<u8> {
+        let header = element(EBML, &element(0x4282, doc_type.as_bytes()));
+        let info = element(
+            INFO,
+            &[
+                uint(TIMESTAMP_SCALE, 1_000_000),
+                element(DURATION, &90_000.0f64.to_be_bytes()),
+                element(TITLE, b"Holiday"),
+                element(WRITING_APP, b"mkvmerge v80"),
+                // 2024-05-17T09:30:00Z
+                uint(
+                    DATE_UTC,
+                    u64::try_from((1_715_938_200 - MATROSKA_EPOCH_OFFSET) * 1_000_000_000).unwrap(),
+                ),
+            ]
+            .concat(),
+        );
+        let tracks = tracks();
+        let cluster = element(0x1F43_B675, &[0xA3; 64]);
+        let simple = |name: &str, value: &str| {
+            element(
+                SIMPLE_TAG,
+                &[
+                    element(TAG_NAME, name.as_bytes()),
+                    element(TAG_STRING, value.as_bytes()),
+                ]
+                .concat(),
+            )
+        };
+        let tags = element(
+            TAGS,
+            &[
+                element(
+                    TAG,
+                    &[element(TARGETS, &[]), simple("ARTIST", "Prism")].concat(),
+                ),
+                element(
+                    TAG,
+                    &[
+                        element(TARGETS, &uint(TAG_TRACK_UID, 2)),
+                        simple("BPS", "96000"),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let attached = |name: &str, mime: &str, data: &[u8]| {
+            element(
+                ATTACHED_FILE,
+                &[
+                    element(FILE_NAME, name.as_bytes()),
+                    element(FILE_MEDIA_TYPE, mime.as_bytes()),
+                    element(FILE_DATA, data),
+                ]
+                .concat(),
+            )
+        };
+        let attachments = element(
+            ATTACHMENTS,
+            &[
+                attached("font.ttf", "font/ttf", b"font"),
+                attached("small_cover.jpg", "image/jpeg", b"small"),
+                attached("cover.png", "image/png", b"large"),
+            ]
+            .concat(),
+        );
+        let segment = element(
+            SEGMENT,
+            &[info, tracks, cluster, tags, attachments].concat(),
+        );
+        [header, segment].concat()
+    }
+
+    #[test]
+    fn test_matroska() {
+        let data = file("matroska");
+        assert!(is_matroska(&data));
+        let info = info(&data).unwrap();
+        assert_eq!(info.duration, Some(90.0));
+        assert_eq!(
+            info.created.unwrap().to_rfc3339(),
+            "2024-05-17T09:30:00+00:00"
+        );
+        assert_eq!(info.tag("title"), Some("Holiday"));
+        assert_eq!(info.tag("artist"), Some("Prism"));
+        assert_eq!(info.tag("encoder"), Some("mkvmerge v80"));
+        assert_eq!(
+            info.tag("attachments"),
+            Some("font.ttf; small_cover.jpg; cover.png")
+        );
+
+        let video = &info.streams[0];
+        assert_eq!(video.codec, "H.264");
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert_eq!(video.frame_rate, Some(25.0));
+        assert_eq!(video.language.as_deref(), Some("eng"));
+
+        let audio = &info.streams[1];
+        assert_eq!(audio.codec, "Opus");
+        assert_eq!(audio.sample_rate, Some(48_000));
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.language.as_deref(), Some("fre"));
+        assert_eq!(audio.bitrate, Some(96_000));
+
+        let cover = info.cover.unwrap();
+        assert_eq!(
+            (cover.mime_type.as_str(), cover.data.as_slice()),
+            ("image/png", &b"large"[..])
+        );
+    }
+
+    #[test]
+    fn test_unknown_size_and_bad_header() {
+        // A segment of unknown size runs to the end of the file
+        let mut data = element(EBML, &element(0x4282, b"webm"));
+        data.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+        data.extend_from_slice(&element(INFO, &element(TITLE, b"Live")));
+        assert_eq!(info(&data).unwrap().tag("title"), Some("Live"));
+
+        assert!(info(b"\x1A\x45\xDF\xA3").is_err());
+        assert!(!is_matroska(b"RIFF"));
+    }
+}
@@ -0,0 +1,635 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Audio and video files: MP3, MP4, M4A, `QuickTime`, Matroska and `WebM`
+//!
+//! Only the container is read, never the streams themselves: the ID3 tags
+//! and first frame of an MP3 file, the movie box of an MP4 or `QuickTime`
+//! file, and the headers, tags and attachments of a Matroska file. That is
+//! enough to catalogue a media file without decoding it.
+//!
+//! A media file parses to a single summary page: a table of its duration,
+//! overall bitrate and tags, a table of its streams with their codecs,
+//! bitrates, sample rates, channels, dimensions and frame rates, and the
+//! embedded cover art. The same values are in the document metadata: the
+//! title, the artist as author and the encoder as creator, the other tags
+//! as custom properties by their names (`album`, `genre`, `date`...), and
+//! `duration` in seconds, `bitrate` in bits per second and the properties
+//! of the first audio and video stream (`audio_codec`, `sample_rate`,
+//! `channels`, `video_codec`, `width`, `height`, `frame_rate`).
+
+pub mod matroska;
+pub mod mp3;
+pub mod mp4;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use image::ImageReader;
+use prism_core::{
+    document::{
+        ContentBlock, Dimensions, Document, ImageBlock, ImageResource, Page, Rect, ShapeStyle,
+        TableBlock, TableCell, TableRow, TextBlock, TextRun,
+    },
+    error::{Error, Result},
+    format::Format,
+    metadata::Metadata,
+    parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+};
+use std::io::Cursor;
+
+/// Media parser supporting MP3, MP4, M4A, `QuickTime`, Matroska and `WebM`
+pub struct MediaParser {
+    format: Format,
+}
+
+/// What the container of a media file says about it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaInfo {
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Overall bitrate in bits per second
+    pub bitrate: Option<u64>,
+    /// Audio, video and subtitle streams, in file order
+    pub streams: Vec<MediaStream>,
+    /// Tags in file order, by their common name where there is one
+    /// (`title`, `artist`, `album`, `album_artist`, `date`, `genre`,
+    /// `track`, `comment`, `composer`, `copyright`, `encoder`)
+    pub tags: Vec<(String, String)>,
+    /// Embedded cover art
+    pub cover: Option<CoverArt>,
+    /// Creation time, if the container records one
+    pub created: Option<DateTime<Utc>>,
+}
+
+/// A stream of a media file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaStream {
+    /// What the stream holds
+    pub kind: StreamKind,
+    /// Codec name, such as "AAC" or "H.264"
+    pub codec: String,
+    /// Language code
+    pub language: Option<String>,
+    /// Bitrate in bits per second
+    pub bitrate: Option<u64>,
+    /// Audio sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Audio channels
+    pub channels: Option<u32>,
+    /// Video width in pixels
+    pub width: Option<u32>,
+    /// Video height in pixels
+    pub height: Option<u32>,
+    /// Video frames per second
+    pub frame_rate: Option<f64>,
+}
+
+/// Kind of a media stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    /// Audio
+    Audio,
+    /// Video
+    Video,
+    /// Subtitles and other timed text
+    Subtitle,
+    /// Anything else (chapters, timecodes, data)
+    Other,
+}
+
+/// An image embedded in a media file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverArt {
+    /// MIME type of the image
+    pub mime_type: String,
+    /// The image data
+    pub data: Vec<u8>,
+}
+
+impl MediaStream {
+    /// A stream of `kind` encoded with `codec`, with nothing else known
+    #[must_use]
+    pub fn new(kind: StreamKind, codec: impl Into<String>) -> Self {
+        Self {
+            kind,
+            codec: codec.into(),
+            language: None,
+            bitrate: None,
+            sample_rate: None,
+            channels: None,
+            width: None,
+            height: None,
+            frame_rate: None,
+        }
+    }
+
+    /// Sample rate and channels, or dimensions and frame rate, and bitrate
+    fn details(&self) -> String {
+        let mut details = Vec::new();
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            details.push(format!("{width}×{height}"));
+        }
+        if let Some(frame_rate) = self.frame_rate {
+            details.push(format!("{} fps", trim_float(frame_rate)));
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            details.push(format!("{sample_rate} Hz"));
+        }
+        match self.channels {
+            Some(1) => details.push("mono".to_string()),
+            Some(2) => details.push("stereo".to_string()),
+            Some(channels) => details.push(format!("{channels} channels")),
+            None => {}
+        }
+        if let Some(bitrate) = self.bitrate {
+            details.push(format_bitrate(bitrate));
+        }
+        details.join(", ")
+    }
+}
+
+impl StreamKind {
+    fn name(self) -> &'static str {
+        match self {
+            StreamKind::Audio => "Audio",
+            StreamKind::Video => "Video",
+            StreamKind::Subtitle => "Subtitle",
+            StreamKind::Other => "Other",
+        }
+    }
+}
+
+impl MediaInfo {
+    /// Add a tag, unless it is empty or a tag of that name is already set
+    pub(crate) fn add_tag(&mut self, name: &str, value: &str) {
+        let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        if !value.is_empty() && self.tag(name).is_none() {
+            self.tags.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    /// The value of the tag called `name`
+    #[must_use]
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The overall bitrate from the file size, if the container does not
+    /// give it
+    fn fill_bitrate(&mut self, size: usize) {
+        if self.bitrate.is_none() {
+            self.bitrate = self
+                .duration
+                .filter(|duration| *duration > 0.0)
+                .map(|duration| bits_per_second(u64::try_from(size).unwrap_or(u64::MAX), duration));
+        }
+    }
+}
+
+impl MediaParser {
+    /// Create a new media parser for the specified format
+    #[must_use]
+    pub fn new(format: Format) -> Self {
+        Self { format }
+    }
+
+    /// Read the container of `data`
+    ///
+    /// # Errors
+    ///
+    /// Returns a parse error if the data is not a file of the parser's
+    /// format or its headers are damaged.
+    pub fn info(&self, data: &[u8]) -> Result<MediaInfo> {
+        let mut info = match self.format.mime_type.as_str() {
+            "audio/mpeg" => mp3::info(data)?,
+            "video/mp4" | "audio/mp4" | "video/quicktime" => mp4::info(data)?,
+            "video/x-matroska" | "video/webm" => matroska::info(data)?,
+            _ => {
+                return Err(Error::UnsupportedFormat(format!(
+                    "Unsupported media format: {}",
+                    self.format.name
+                )))
+            }
+        };
+        info.fill_bitrate(data.len());
+        Ok(info)
+    }
+}
+
+#[async_trait]
+impl Parser for MediaParser {
+    fn format(&self) -> Format {
+        self.format.clone()
+    }
+
+    fn can_parse(&self, data: &[u8]) -> bool {
+        match self.format.mime_type.as_str() {
+            "audio/mpeg" => mp3::is_mp3(data),
+            "video/mp4" | "audio/mp4" | "video/quicktime" => mp4::is_mp4(data),
+            "video/x-matroska" | "video/webm" => matroska::is_matroska(data),
+            _ => false,
+        }
+    }
+
+    async fn parse(&self, data: Bytes, _context: ParseContext) -> Result<Document> {
+        let info = self.info(&data)?;
+        let mut document = summary(&self.format, &info);
+        document.metadata = metadata(&info);
+        Ok(document)
+    }
+
+    fn metadata(&self) -> ParserMetadata {
+        ParserMetadata {
+            name: format!("{} Parser", self.format.name),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: vec![
+                ParserFeature::MetadataExtraction,
+                ParserFeature::TableExtraction,
+                ParserFeature::ImageExtraction,
+            ],
+            requires_sandbox: false,
+        }
+    }
+}
+
+/// Tags that fill a standard metadata field rather than a custom one
+const STANDARD_TAGS: [&str; 3] = ["title", "artist", "encoder"];
+
+/// The document metadata of a media file
+fn metadata(info: &MediaInfo) -> Metadata {
+    let mut metadata = Metadata {
+        title: info.tag("title").map(str::to_string),
+        author: info.tag("artist").map(str::to_string),
+        creator: info.tag("encoder").map(str::to_string),
+        created: info.created,
+        ..Metadata::default()
+    };
+    for (name, value) in &info.tags {
+        if !STANDARD_TAGS.contains(&name.as_str()) {
+            metadata.add_custom(name.as_str(), value.as_str());
+        }
+    }
+    if let Some(duration) = info.duration {
+        metadata.add_custom("duration", duration);
+    }
+    if let Some(bitrate) = info.bitrate {
+        metadata.add_custom("bitrate", i64::try_from(bitrate).unwrap_or(i64::MAX));
+    }
+    let first = |kind| info.streams.iter().find(|stream| stream.kind == kind);
+    if let Some(audio) = first(StreamKind::Audio) {
+        metadata.add_custom("audio_codec", audio.codec.as_str());
+        if let Some(sample_rate) = audio.sample_rate {
+            metadata.add_custom("sample_rate", i64::from(sample_rate));
+        }
+        if let Some(channels) = audio.channels {
+            metadata.add_custom("channels", i64::from(channels));
+        }
+    }
+    if let Some(video) = first(StreamKind::Video) {
+        metadata.add_custom("video_codec", video.codec.as_str());
+        if let Some(width) = video.width {
+            metadata.add_custom("width", i64::from(width));
+        }
+        if let Some(height) = video.height {
+            metadata.add_custom("height", i64::from(height));
+        }
+        if let Some(frame_rate) = video.frame_rate {
+            metadata.add_custom("frame_rate", frame_rate);
+        }
+    }
+    metadata
+}
+
+/// Height of a table row on the summary page
+const ROW_HEIGHT: f64 = 20.0;
+
+/// Widest the cover art is drawn on the summary page
+const COVER_SIZE: f64 = 200.0;
+
+/// The summary page: properties and tags, streams, and cover art
+fn summary(format: &Format, info: &MediaInfo) -> Document {
+    let mut properties = vec![("Format".to_string(), format.name.clone())];
+    if let Some(duration) = info.duration {
+        properties.push(("Duration".to_string(), format_duration(duration)));
+    }
+    if let Some(bitrate) = info.bitrate {
+        properties.push(("Bitrate".to_string(), format_bitrate(bitrate)));
+    }
+    if let Some(created) = info.created {
+        properties.push((
+            "Created".to_string(),
+            created.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ));
+    }
+    properties.extend(
+        info.tags
+            .iter()
+            .map(|(name, value)| (tag_label(name), value.clone())),
+    );
+
+    let mut page = Page::new(1, Dimensions::LETTER);
+    let mut top = 50.0;
+    let rows = std::iter::once(header_row(&["Property", "Value"]))
+        .chain(
+            properties
+                .iter()
+                .map(|(name, value)| text_row(&[name, value])),
+        )
+        .collect();
+    top = add_table(&mut page, rows, 2, top);
+
+    if !info.streams.is_empty() {
+        let rows = std::iter::once(header_row(&[
+            "Stream", "Type", "Codec", "Language", "Details",
+        ]))
+        .chain(info.streams.iter().enumerate().map(|(index, stream)| {
+            text_row(&[
+                &(index + 1).to_string(),
+                stream.kind.name(),
+                &stream.codec,
+                stream.language.as_deref().unwrap_or_default(),
+                &stream.details(),
+            ])
+        }))
+        .collect();
+        top = add_table(&mut page, rows, 5, top + ROW_HEIGHT);
+    }
+
+    let mut document = Document::new();
+    if let Some(cover) = &info.cover {
+        let (width, height) = ImageReader::new(Cursor::new(&cover.data))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok())
+            .unwrap_or((0, 0));
+        let resource_id = format!("img_{}", uuid::Uuid::new_v4());
+        let (drawn_width, drawn_height) = if width == 0 || height == 0 {
+            (COVER_SIZE, COVER_SIZE)
+        } else {
+            let scale = COVER_SIZE / f64::from(width.max(height));
+            (f64::from(width) * scale, f64::from(height) * scale)
+        };
+        page.add_content(ContentBlock::Image(ImageBlock {
+            bounds: Rect::new(50.0, top + ROW_HEIGHT, drawn_width, drawn_height),
+            resource_id: resource_id.clone(),
+            alt_text: Some("Cover art".to_string()),
+            format: Some(cover.mime_type.clone()),
+            original_size: Some(Dimensions::new(f64::from(width), f64::from(height))),
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        document.resources.images.push(ImageResource {
+            id: resource_id,
+            mime_type: cover.mime_type.clone(),
+            data: Some(cover.data.clone()),
+            url: None,
+            width,
+            height,
+        });
+    }
+    document.pages.push(page);
+    document
+}
+
+/// Add a table of `rows` at `top`, returning where it ends
+fn add_table(page: &mut Page, rows: Vec<TableRow>, column_count: usize, top: f64) -> f64 {
+    let height = ROW_HEIGHT * f64::from(u32::try_from(rows.len()).unwrap_or(u32::MAX));
+    let mut table = TableBlock::new(Rect::new(50.0, top, 500.0, height), column_count);
+    table.rows = rows;
+    page.add_content(ContentBlock::Table(table));
+    top + height
+}
+
+fn header_row(cells: &[&str]) -> TableRow {
+    let mut row = text_row(cells);
+    for cell in &mut row.cells {
+        if let Some(ContentBlock::Text(block)) = cell.content.first_mut() {
+            for run in &mut block.runs {
+                run.style.bold = true;
+            }
+        }
+        cell.background_color = Some("#CCCCCC".to_string());
+    }
+    row
+}
+
+fn text_row(cells: &[&str]) -> TableRow {
+    TableRow {
+        cells: cells
+            .iter()
+            .map(|text| {
+                let mut block = TextBlock::new(Rect::default());
+                block.add_run(TextRun::new(*text));
+                TableCell {
+                    content: vec![ContentBlock::Text(block)],
+                    col_span: 1,
+                    row_span: 1,
+                    background_color: None,
+                }
+            })
+            .collect(),
+        height: None,
+    }
+}
+
+/// The label of a tag on the summary page: `album_artist` as "Album artist"
+fn tag_label(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// A duration as `h:mm:ss.mmm`, or `m:ss.mmm` under an hour
+fn format_duration(seconds: f64) -> String {
+    // Saturating, and durations are far below the range of u64
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let millis = (seconds * 1000.0).round() as u64;
+    let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+    let (seconds, millis) = (millis / 1000 % 60, millis % 1000);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}.{millis:03}")
+    } else {
+        format!("{minutes}:{seconds:02}.{millis:03}")
+    }
+}
+
+fn format_bitrate(bitrate: u64) -> String {
+    format!("{} kb/s", (bitrate + 500) / 1000)
+}
+
+/// A float without a fractional part when it is whole, and two decimals
+/// otherwise
+fn trim_float(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// The bitrate of `bytes` played over `seconds`
+pub(crate) fn bits_per_second(bytes: u64, seconds: f64) -> u64 {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let bitrate = (bytes as f64 * 8.0 / seconds).round() as u64;
+    bitrate
+}
+
+/// The MIME type of image data, from its signature
+pub(crate) fn image_mime_type(data: &[u8]) -> Option<String> {
+    image::guess_format(data)
+        .ok()
+        .map(|format| format.to_mime_type().to_string())
+}
+
+/// Genres of `ID3v1`, which `ID3v2` and MP4 refer to by number
+pub(crate) const GENRES: [&str; 80] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "Alternative Rock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native US",
+    "Cabaret",
+    "New Wave",
+    "Psychedelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(3.5), "0:03.500");
+        assert_eq!(format_duration(3725.25), "1:02:05.250");
+    }
+
+    #[test]
+    fn test_tag_label() {
+        assert_eq!(tag_label("album_artist"), "Album artist");
+        assert_eq!(tag_label("title"), "Title");
+    }
+
+    #[test]
+    fn test_summary_and_metadata() {
+        let mut audio = MediaStream::new(StreamKind::Audio, "AAC");
+        audio.sample_rate = Some(48_000);
+        audio.channels = Some(2);
+        let mut video = MediaStream::new(StreamKind::Video, "H.264");
+        video.width = Some(1920);
+        video.height = Some(1080);
+        video.frame_rate = Some(25.0);
+        let mut info = MediaInfo {
+            duration: Some(90.0),
+            streams: vec![video, audio],
+            ..MediaInfo::default()
+        };
+        info.add_tag("title", "Holiday");
+        info.add_tag("title", "Ignored duplicate");
+        info.add_tag("album", " Summer \0");
+        info.fill_bitrate(1_125_000);
+
+        let metadata = metadata(&info);
+        assert_eq!(metadata.title.as_deref(), Some("Holiday"));
+        assert_eq!(info.bitrate, Some(100_000));
+        assert!(matches!(
+            metadata.get_custom("album"),
+            Some(prism_core::metadata::MetadataValue::String(album)) if album == "Summer"
+        ));
+        assert!(matches!(
+            metadata.get_custom("width"),
+            Some(prism_core::metadata::MetadataValue::Integer(1920))
+        ));
+
+        let document = summary(&Format::mp4(), &info);
+        assert_eq!(document.pages.len(), 1);
+        let text = document.extract_text();
+        assert!(text.contains("1:30.000"));
+        assert!(text.contains("1920×1080, 25 fps"));
+        assert!(text.contains("48000 Hz, stereo"));
+        assert!(text.contains("Holiday"));
+    }
+}
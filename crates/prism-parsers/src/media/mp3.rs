@@ -0,0 +1,657 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MP3 audio
+//!
+//! Tags come from the `ID3v2` tag at the start of the file (versions 2.2 to
+//! 2.4), and from the `ID3v1` tag at its end for any the first lacks. The
+//! first MPEG audio frame gives the codec, sample rate and channels. A
+//! variable-bitrate file has a Xing or VBRI header in that frame with the
+//! number of frames, and so its duration; without one the file is taken to
+//! be constant-bitrate, and its duration is its audio size over the
+//! bitrate of the first frame.
+
+use encoding_rs::{UTF_16BE, UTF_16LE};
+use prism_core::error::{Error, Result};
+use std::borrow::Cow;
+
+use super::{
+    bits_per_second, image_mime_type, CoverArt, MediaInfo, MediaStream, StreamKind, GENRES,
+};
+
+/// Furthest past the `ID3v2` tag the first frame is looked for, skipping
+/// padding and junk
+const MAX_FRAME_SEARCH: usize = 64 * 1024;
+
+/// Bitrates in kbit/s by bitrate index: MPEG-1 layers I, II and III, then
+/// MPEG-2 and 2.5 layer I and layers II and III
+const BITRATES: [[u32; 15]; 5] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// Picture type of the front cover in an `ID3v2` picture frame
+const FRONT_COVER: u8 = 3;
+
+/// The header of an MPEG audio frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+    mpeg1: bool,
+    layer: u8,
+    /// Bits per second
+    bitrate: u32,
+    sample_rate: u32,
+    channels: u32,
+    samples: u32,
+    /// Length of the frame, header included
+    length: usize,
+}
+
+/// Whether `data` starts with an `ID3v2` tag or an MPEG audio frame
+pub(crate) fn is_mp3(data: &[u8]) -> bool {
+    data.starts_with(b"ID3") || frame(data, 0).is_some()
+}
+
+/// Read the tags and first frame of an MP3 file
+pub(crate) fn info(data: &[u8]) -> Result<MediaInfo> {
+    let mut info = MediaInfo::default();
+    let mut cover = None;
+    let mut length = None;
+    let tag_end = id3v2(data, &mut info, &mut cover, &mut length);
+    let audio_end = if let Some(tag) = id3v1_tag(data) {
+        id3v1(tag, &mut info);
+        data.len() - 128
+    } else {
+        data.len()
+    };
+    info.cover = cover.map(|(_, cover)| cover);
+
+    let (offset, first) = first_frame(data, tag_end.min(data.len()))
+        .ok_or_else(|| Error::corrupt("MP3", "no MPEG audio frame"))?;
+    let mut stream = MediaStream::new(
+        StreamKind::Audio,
+        match first.layer {
+            1 => "MP1",
+            2 => "MP2",
+            _ => "MP3",
+        },
+    );
+    stream.sample_rate = Some(first.sample_rate);
+    stream.channels = Some(first.channels);
+
+    let audio_size = u64::try_from(audio_end.saturating_sub(offset)).unwrap_or(u64::MAX);
+    if let Some((frames, bytes)) = vbr_header(data, offset, &first) {
+        let duration = f64::from(frames) * f64::from(first.samples) / f64::from(first.sample_rate);
+        info.duration = Some(duration);
+        if duration > 0.0 {
+            stream.bitrate = Some(bits_per_second(
+                bytes.map_or(audio_size, u64::from),
+                duration,
+            ));
+        }
+    } else {
+        #[allow(clippy::cast_precision_loss)]
+        let duration = audio_size as f64 * 8.0 / f64::from(first.bitrate);
+        info.duration = Some(duration);
+        stream.bitrate = Some(u64::from(first.bitrate));
+    }
+    if info.duration.map_or(true, |duration| duration <= 0.0) && length.is_some() {
+        info.duration = length;
+    }
+    info.bitrate = stream.bitrate;
+    info.streams.push(stream);
+    Ok(info)
+}
+
+/// The frame header at `offset`, if there is a valid one
+fn frame(data: &[u8], offset: usize) -> Option<Frame> {
+    let header = data.get(offset..offset + 4)?;
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    // 0 is MPEG 2.5, 1 reserved, 2 MPEG-2 and 3 MPEG-1
+    let version = (header[1] >> 3) & 0x03;
+    let layer = match (header[1] >> 1) & 0x03 {
+        0 => return None,
+        bits => 4 - bits,
+    };
+    let bitrate_index = usize::from(header[2] >> 4);
+    let rate_index = usize::from((header[2] >> 2) & 0x03);
+    if version == 1 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let table = match (mpeg1, layer) {
+        (true, layer) => usize::from(layer - 1),
+        (false, 1) => 3,
+        (false, _) => 4,
+    };
+    let bitrate = BITRATES[table][bitrate_index] * 1000;
+    let sample_rate = [44_100, 48_000, 32_000][rate_index]
+        >> match version {
+            3 => 0,
+            2 => 1,
+            _ => 2,
+        };
+    let samples = match (layer, mpeg1) {
+        (1, _) => 384,
+        (3, false) => 576,
+        _ => 1152,
+    };
+    let padding = u32::from((header[2] >> 1) & 0x01);
+    let length = if layer == 1 {
+        (12 * bitrate / sample_rate + padding) * 4
+    } else {
+        samples / 8 * bitrate / sample_rate + padding
+    };
+    Some(Frame {
+        mpeg1,
+        layer,
+        bitrate,
+        sample_rate,
+        channels: if header[3] >> 6 == 3 { 1 } else { 2 },
+        samples,
+        length: usize::try_from(length).ok()?,
+    })
+}
+
+/// The first frame from `start` that is followed by another frame or the
+/// end of the data, so that a stray sync pattern is not taken for one
+fn first_frame(data: &[u8], start: usize) -> Option<(usize, Frame)> {
+    let end = data.len().min(start.saturating_add(MAX_FRAME_SEARCH));
+    (start..end).find_map(|offset| {
+        let first = frame(data, offset)?;
+        let next = offset + first.length;
+        (next >= data.len() || frame(data, next).is_some()).then_some((offset, first))
+    })
+}
+
+/// The frame count, and audio size if given, of the Xing, Info or VBRI
+/// header in the first frame
+fn vbr_header(data: &[u8], offset: usize, first: &Frame) -> Option<(u32, Option<u32>)> {
+    let side_info = match (first.mpeg1, first.channels) {
+        (true, 1) => 17,
+        (true, _) => 32,
+        (false, 1) => 9,
+        (false, _) => 17,
+    };
+    let xing = offset + 4 + side_info;
+    if matches!(data.get(xing..xing + 4), Some(b"Xing" | b"Info")) {
+        let flags = read_u32(data, xing + 4)?;
+        if flags & 0x01 == 0 {
+            return None;
+        }
+        let frames = read_u32(data, xing + 8)?;
+        let bytes = (flags & 0x02 != 0)
+            .then(|| read_u32(data, xing + 12))
+            .flatten();
+        return Some((frames, bytes));
+    }
+    let vbri = offset + 4 + 32;
+    if data.get(vbri..vbri + 4) == Some(b"VBRI") {
+        return Some((read_u32(data, vbri + 14)?, read_u32(data, vbri + 10)));
+    }
+    None
+}
+
+/// Read the `ID3v2` tag at the start of `data`, returning where it ends
+///
+/// `cover` keeps the front cover over any other picture, and `length` the
+/// duration the tag states, in seconds.
+fn id3v2(
+    data: &[u8],
+    info: &mut MediaInfo,
+    cover: &mut Option<(u8, CoverArt)>,
+    length: &mut Option<f64>,
+) -> usize {
+    if !data.starts_with(b"ID3") || data.len() < 10 {
+        return 0;
+    }
+    let version = data[3];
+    let flags = data[5];
+    let size = syncsafe(&data[6..10]);
+    let end = 10 + size + if flags & 0x10 != 0 { 10 } else { 0 };
+    if !(2..=4).contains(&version) {
+        return end;
+    }
+    let tag = &data[10..data.len().min(10 + size)];
+    // Unsynchronisation applies to the whole tag before 2.4
+    let tag = if flags & 0x80 != 0 && version < 4 {
+        resynchronise(tag)
+    } else {
+        Cow::Borrowed(tag)
+    };
+
+    let mut position = 0;
+    if flags & 0x40 != 0 {
+        position = match version {
+            3 => 4 + read_u32(&tag, 0).map_or(0, |size| usize::try_from(size).unwrap_or(0)),
+            4 => tag.get(..4).map_or(0, syncsafe),
+            _ => 0,
+        };
+    }
+    let header_size = if version == 2 { 6 } else { 10 };
+    while let Some(header) = tag.get(position..position + header_size) {
+        if header[0] == 0 {
+            // Padding
+            break;
+        }
+        let (id, size, frame_flags) = match version {
+            2 => (
+                &header[..3],
+                usize::from(header[3]) << 16 | usize::from(header[4]) << 8 | usize::from(header[5]),
+                0,
+            ),
+            3 => (
+                &header[..4],
+                read_u32(header, 4).map_or(0, |size| usize::try_from(size).unwrap_or(0)),
+                u16::from_be_bytes([header[8], header[9]]),
+            ),
+            _ => (
+                &header[..4],
+                syncsafe(&header[4..8]),
+                u16::from_be_bytes([header[8], header[9]]),
+            ),
+        };
+        let start = position + header_size;
+        position = start.saturating_add(size);
+        let Some(mut body) = tag.get(start..position).map(Cow::Borrowed) else {
+            break;
+        };
+        match version {
+            3 => {
+                // Compressed or encrypted
+                if frame_flags & 0x00C0 != 0 {
+                    continue;
+                }
+                if frame_flags & 0x0020 != 0 {
+                    body = Cow::Owned(body.get(1..).unwrap_or_default().to_vec());
+                }
+            }
+            4 => {
+                if frame_flags & 0x000C != 0 {
+                    continue;
+                }
+                // Group identifier, then data length indicator
+                let skip = usize::from(frame_flags & 0x0040 != 0)
+                    + 4 * usize::from(frame_flags & 0x0001 != 0);
+                let rest = body.get(skip..).unwrap_or_default();
+                body = if frame_flags & 0x0002 != 0 {
+                    Cow::Owned(resynchronise(rest).into_owned())
+                } else {
+                    Cow::Owned(rest.to_vec())
+                };
+            }
+            _ => {}
+        }
+        id3v2_frame(id, &body, version, info, cover, length);
+    }
+    end
+}
+
+/// Read one `ID3v2` frame
+fn id3v2_frame(
+    id: &[u8],
+    body: &[u8],
+    version: u8,
+    info: &mut MediaInfo,
+    cover: &mut Option<(u8, CoverArt)>,
+    length: &mut Option<f64>,
+) {
+    let Some((&encoding, content)) = body.split_first() else {
+        return;
+    };
+    let name = match id {
+        b"TIT2" | b"TT2" => "title",
+        b"TPE1" | b"TP1" => "artist",
+        b"TALB" | b"TAL" => "album",
+        b"TPE2" | b"TP2" => "album_artist",
+        b"TRCK" | b"TRK" => "track",
+        b"TPOS" | b"TPA" => "disc",
+        b"TDRC" | b"TYER" | b"TYE" => "date",
+        b"TCON" | b"TCO" => {
+            let genres: Vec<String> = text_values(encoding, content)
+                .iter()
+                .map(|value| genre(value))
+                .collect();
+            info.add_tag("genre", &genres.join("; "));
+            return;
+        }
+        b"TCOM" | b"TCM" => "composer",
+        b"TCOP" | b"TCR" => "copyright",
+        b"TSSE" | b"TSS" => "encoder",
+        b"TLEN" | b"TLE" => {
+            *length = text(encoding, content)
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .map(|millis| millis / 1000.0);
+            return;
+        }
+        b"TXXX" | b"TXX" => {
+            let (description, value) = split_terminated(encoding, content);
+            let name = text(encoding, description)
+                .trim()
+                .to_lowercase()
+                .replace(' ', "_");
+            if !name.is_empty() {
+                info.add_tag(&name, &text_values(encoding, value).join("; "));
+            }
+            return;
+        }
+        b"COMM" | b"COM" => {
+            // Language, then a short description
+            let (_, value) = split_terminated(encoding, content.get(3..).unwrap_or_default());
+            info.add_tag("comment", &text(encoding, value));
+            return;
+        }
+        b"APIC" | b"PIC" => {
+            picture(encoding, content, version, cover);
+            return;
+        }
+        _ => return,
+    };
+    info.add_tag(name, &text_values(encoding, content).join("; "));
+}
+
+/// Read a picture frame into `cover`, unless it already has the front
+/// cover
+fn picture(encoding: u8, content: &[u8], version: u8, cover: &mut Option<(u8, CoverArt)>) {
+    let (stated_type, rest) = if version == 2 {
+        // A three-letter image format
+        let Some(format) = content.get(..3) else {
+            return;
+        };
+        let mime_type = match format {
+            b"PNG" => "image/png".to_string(),
+            _ => "image/jpeg".to_string(),
+        };
+        (mime_type, content.get(3..).unwrap_or_default())
+    } else {
+        let (mime_type, rest) = split_terminated(0, content);
+        (String::from_utf8_lossy(mime_type).into_owned(), rest)
+    };
+    let Some((&picture_type, rest)) = rest.split_first() else {
+        return;
+    };
+    let (_, data) = split_terminated(encoding, rest);
+    if data.is_empty() || matches!(cover, Some((FRONT_COVER, _))) {
+        return;
+    }
+    if cover.is_some() && picture_type != FRONT_COVER {
+        return;
+    }
+    *cover = Some((
+        picture_type,
+        CoverArt {
+            mime_type: image_mime_type(data).unwrap_or(stated_type),
+            data: data.to_vec(),
+        },
+    ));
+}
+
+/// The last 128 bytes of `data` if they are an `ID3v1` tag
+fn id3v1_tag(data: &[u8]) -> Option<&[u8]> {
+    let tag = data.get(data.len().checked_sub(128)?..)?;
+    tag.starts_with(b"TAG").then_some(tag)
+}
+
+/// Read an `ID3v1` tag, for the tags the `ID3v2` tag does not have
+fn id3v1(tag: &[u8], info: &mut MediaInfo) {
+    let field = |range: std::ops::Range<usize>| latin1(&tag[range]);
+    info.add_tag("title", &field(3..33));
+    info.add_tag("artist", &field(33..63));
+    info.add_tag("album", &field(63..93));
+    info.add_tag("date", &field(93..97));
+    // ID3v1.1 puts the track number at the end of the comment
+    if tag[125] == 0 && tag[126] != 0 {
+        info.add_tag("comment", &field(97..125));
+        info.add_tag("track", &tag[126].to_string());
+    } else {
+        info.add_tag("comment", &field(97..127));
+    }
+    if let Some(genre) = GENRES.get(usize::from(tag[127])) {
+        info.add_tag("genre", genre);
+    }
+}
+
+/// A genre given by number, as `(17)`, `17` or `(17)Rock`, by its name
+fn genre(value: &str) -> String {
+    let value = value.trim();
+    let number = match value
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+    {
+        Some((_, refinement)) if !refinement.is_empty() => return refinement.to_string(),
+        Some((number, _)) => number,
+        None => value,
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| GENRES.get(number))
+        .map_or_else(|| value.to_string(), |genre| (*genre).to_string())
+}
+
+/// The text of an `ID3v2` text field in `encoding`: ISO-8859-1, UTF-16 with
+/// a byte order mark, UTF-16BE or UTF-8
+fn text(encoding: u8, bytes: &[u8]) -> String {
+    match encoding {
+        1 => {
+            let (decoder, bytes) = match bytes {
+                [0xFE, 0xFF, rest @ ..] => (UTF_16BE, rest),
+                [0xFF, 0xFE, rest @ ..] => (UTF_16LE, rest),
+                _ => (UTF_16LE, bytes),
+            };
+            decoder.decode_without_bom_handling(bytes).0.into_owned()
+        }
+        2 => UTF_16BE.decode_without_bom_handling(bytes).0.into_owned(),
+        3 => String::from_utf8_lossy(bytes).into_owned(),
+        _ => latin1(bytes),
+    }
+}
+
+/// The values of a text field, which `ID3v2.4` separates with NULs
+fn text_values(encoding: u8, bytes: &[u8]) -> Vec<String> {
+    text(encoding, bytes)
+        .split('\0')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split `bytes` after the string that starts it, terminated by one NUL,
+/// or two in UTF-16
+fn split_terminated(encoding: u8, bytes: &[u8]) -> (&[u8], &[u8]) {
+    let end = if matches!(encoding, 1 | 2) {
+        (0..bytes.len().saturating_sub(1))
+            .step_by(2)
+            .find(|&index| bytes[index] == 0 && bytes[index + 1] == 0)
+            .map(|index| (index, index + 2))
+    } else {
+        bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .map(|index| (index, index + 1))
+    };
+    match end {
+        Some((end, rest)) => (&bytes[..end], &bytes[rest..]),
+        None => (bytes, &[]),
+    }
+}
+
+/// ISO-8859-1 text, up to its first NUL and without trailing spaces
+fn latin1(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| char::from(byte))
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// Undo unsynchronisation: a zero byte inserted after every 0xFF
+fn resynchronise(data: &[u8]) -> Cow<'_, [u8]> {
+    if !data.windows(2).any(|pair| pair == [0xFF, 0x00]) {
+        return Cow::Borrowed(data);
+    }
+    let mut output = Vec::with_capacity(data.len());
+    let mut previous = 0;
+    for &byte in data {
+        if !(previous == 0xFF && byte == 0) {
+            output.push(byte);
+        }
+        previous = byte;
+    }
+    Cow::Owned(output)
+}
+
+/// A 28-bit size stored 7 bits per byte
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |size, &byte| (size << 7) | usize::from(byte & 0x7F))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An MPEG-1 layer III frame at 128 kbit/s and 44.1 kHz, joint stereo
+    fn frame_bytes() -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x40];
+        frame.resize(417, 0);
+        frame
+    }
+
+    fn id3v2_frame_bytes(id: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend_from_slice(&u32::try_from(body.len()).unwrap().to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn id3v2_tag(frames: &[Vec<u8>]) -> Vec<u8> {
+        let body = frames.concat();
+        let size = body.len() + 16;
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend(
+            (0..4)
+                .rev()
+                .map(|shift| u8::try_from((size >> (7 * shift)) & 0x7F).unwrap()),
+        );
+        tag.extend_from_slice(&body);
+        tag.resize(10 + size, 0);
+        tag
+    }
+
+    #[test]
+    fn test_frame_header() {
+        let frame = frame(&frame_bytes(), 0).unwrap();
+        assert_eq!(frame.bitrate, 128_000);
+        assert_eq!(frame.sample_rate, 44_100);
+        assert_eq!(frame.length, 417);
+        assert_eq!(frame.channels, 2);
+
+        // MPEG-2 layer III at 64 kbit/s and 22.05 kHz, mono
+        let frame = super::frame(&[0xFF, 0xF3, 0x80, 0xC0], 0).unwrap();
+        assert_eq!(
+            (frame.bitrate, frame.sample_rate, frame.samples),
+            (64_000, 22_050, 576)
+        );
+        assert_eq!(frame.channels, 1);
+        assert!(super::frame(b"ID3\x03", 0).is_none());
+    }
+
+    #[test]
+    fn test_id3v2_and_cbr_duration() {
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        png.extend_from_slice(&[0; 8]);
+        let mut picture = b"\x00image/png\x00\x03Cover\x00".to_vec();
+        picture.extend_from_slice(&png);
+        let mut data = id3v2_tag(&[
+            id3v2_frame_bytes(b"TIT2", b"\x03Night Drive"),
+            id3v2_frame_bytes(b"TPE1", b"\x01\xFF\xFEP\x00r\x00i\x00s\x00m\x00"),
+            id3v2_frame_bytes(b"TCON", b"\x00(17)"),
+            id3v2_frame_bytes(b"COMM", b"\x00engShort\x00Recorded live"),
+            id3v2_frame_bytes(b"TXXX", b"\x00Mood\x00Calm"),
+            id3v2_frame_bytes(b"APIC", &picture),
+        ]);
+        for _ in 0..10 {
+            data.extend_from_slice(&frame_bytes());
+        }
+
+        let info = info(&data).unwrap();
+        assert_eq!(info.tag("title"), Some("Night Drive"));
+        assert_eq!(info.tag("artist"), Some("Prism"));
+        assert_eq!(info.tag("genre"), Some("Rock"));
+        assert_eq!(info.tag("comment"), Some("Recorded live"));
+        assert_eq!(info.tag("mood"), Some("Calm"));
+        let cover = info.cover.unwrap();
+        assert_eq!(cover.mime_type, "image/png");
+        assert_eq!(cover.data, png);
+        assert_eq!(info.bitrate, Some(128_000));
+        let duration = info.duration.unwrap();
+        assert!((duration - 4170.0 * 8.0 / 128_000.0).abs() < 1e-9);
+        assert_eq!(info.streams[0].codec, "MP3");
+    }
+
+    #[test]
+    fn test_xing_and_id3v1() {
+        let mut first = frame_bytes();
+        first[36..40].copy_from_slice(b"Xing");
+        first[40..44].copy_from_slice(&3u32.to_be_bytes());
+        first[44..48].copy_from_slice(&100u32.to_be_bytes());
+        first[48..52].copy_from_slice(&41_700u32.to_be_bytes());
+        let mut data = first;
+        data.extend_from_slice(&frame_bytes());
+
+        let mut tag = b"TAG".to_vec();
+        for (field, width) in [
+            (&b"Old Title"[..], 30),
+            (b"Old Artist", 30),
+            (b"", 30),
+            (b"1999", 4),
+        ] {
+            let mut field = field.to_vec();
+            field.resize(width, 0);
+            tag.extend_from_slice(&field);
+        }
+        tag.extend_from_slice(&[0; 28]);
+        tag.extend_from_slice(&[0, 7, 8]);
+        data.extend_from_slice(&tag);
+
+        let info = info(&data).unwrap();
+        let duration = info.duration.unwrap();
+        assert!((duration - 100.0 * 1152.0 / 44_100.0).abs() < 1e-9);
+        assert_eq!(info.bitrate, Some(bits_per_second(41_700, duration)));
+        assert_eq!(info.tag("title"), Some("Old Title"));
+        assert_eq!(info.tag("date"), Some("1999"));
+        assert_eq!(info.tag("track"), Some("7"));
+        assert_eq!(info.tag("genre"), Some("Jazz"));
+    }
+
+    #[test]
+    fn test_not_mp3() {
+        assert!(!is_mp3(b"plain text"));
+        assert!(info(b"ID3\x03\x00\x00\x00\x00\x00\x00").is_err());
+    }
+}
@@ -0,0 +1,740 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MP4, M4A and `QuickTime` movies
+//!
+//! Everything is read from the movie box (`moov`): the duration and
+//! creation time from its header, a stream from each track, and the tags
+//! from its user data. A track's codec comes from its sample description,
+//! refined for MPEG-4 audio by the decoder configuration, and its bitrate
+//! from the decoder configuration or else the total size of its samples
+//! over its duration, which also gives the frame rate of a video track.
+//!
+//! Tags are read from iTunes-style item lists, including `QuickTime`'s
+//! `keys`-based list that cameras and phones write (make, model,
+//! location), and from the older `QuickTime` user data strings.
+
+use chrono::{DateTime, Utc};
+use prism_core::error::{Error, Result};
+
+use super::{
+    bits_per_second, image_mime_type, CoverArt, MediaInfo, MediaStream, StreamKind, GENRES,
+};
+
+/// Seconds from 1904-01-01, the epoch of MP4 times, to the Unix epoch
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Prefix of the names of `QuickTime`'s `keys`-based tags
+const QUICKTIME_KEY_PREFIX: &str = "com.apple.quicktime.";
+
+/// Whether `data` starts with a box an MP4 or `QuickTime` file starts with
+pub(crate) fn is_mp4(data: &[u8]) -> bool {
+    matches!(
+        data.get(4..8),
+        Some(b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip")
+    )
+}
+
+/// Read the movie box of an MP4 or `QuickTime` file
+pub(crate) fn info(data: &[u8]) -> Result<MediaInfo> {
+    let moov = child(data, b"moov").ok_or_else(|| Error::corrupt("MP4", "no movie box"))?;
+    let mut info = MediaInfo::default();
+    let mut track_durations = Vec::new();
+    for (kind, content) in boxes(moov) {
+        match &kind {
+            b"mvhd" => movie_header(content, &mut info),
+            b"trak" => {
+                if let Some((stream, duration)) = track(content) {
+                    info.streams.push(stream);
+                    track_durations.extend(duration);
+                }
+            }
+            b"udta" => user_data(content, &mut info),
+            b"meta" => meta(content, &mut info),
+            _ => {}
+        }
+    }
+    if info.duration.is_none() {
+        info.duration = track_durations.into_iter().reduce(f64::max);
+    }
+    Ok(info)
+}
+
+/// The boxes in `data`, as their type and content
+fn boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        let header = data.get(position..position + 8)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (start, size) = match read_u32(header, 0)? {
+            // To the end of the data
+            0 => (position + 8, data.len() - position),
+            // A 64-bit size follows the type
+            1 => (
+                position + 16,
+                usize::try_from(read_u64(data, position + 8)?).ok()?,
+            ),
+            size => (position + 8, usize::try_from(size).ok()?),
+        };
+        let end = position.checked_add(size)?.min(data.len());
+        if end < start {
+            return None;
+        }
+        position = end;
+        Some((kind, &data[start..end]))
+    })
+}
+
+/// The content of the first box of type `kind` in `data`
+fn child<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    boxes(data)
+        .find(|(found, _)| found == kind)
+        .map(|(_, content)| content)
+}
+
+/// The content of the box at the end of `path`, each a child of the last
+fn descendant<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(data, |data, kind| child(data, *kind))
+}
+
+/// Read the movie header: duration and creation time
+fn movie_header(content: &[u8], info: &mut MediaInfo) {
+    let Some((created, timescale, duration)) = (match content.first() {
+        Some(1) => read_u64(content, 4)
+            .zip(read_u32(content, 20))
+            .zip(read_u64(content, 24))
+            .map(|((created, timescale), duration)| (created, timescale, duration)),
+        Some(_) => read_u32(content, 4)
+            .zip(read_u32(content, 12))
+            .zip(read_u32(content, 16))
+            .map(|((created, timescale), duration)| {
+                (u64::from(created), timescale, u64::from(duration))
+            }),
+        None => None,
+    }) else {
+        return;
+    };
+    info.duration = seconds(duration, timescale);
+    info.created = mp4_time(created);
+}
+
+/// A stream for a track, and the track's duration
+fn track(content: &[u8]) -> Option<(MediaStream, Option<f64>)> {
+    let mdia = child(content, b"mdia")?;
+    let kind = match child(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12)) {
+        Some(b"soun") => StreamKind::Audio,
+        Some(b"vide") => StreamKind::Video,
+        Some(b"sbtl" | b"subt" | b"text" | b"clcp") => StreamKind::Subtitle,
+        _ => StreamKind::Other,
+    };
+    let stbl = descendant(mdia, &[b"minf", b"stbl"]);
+    let entry = stbl
+        .and_then(|stbl| child(stbl, b"stsd"))
+        .and_then(|stsd| boxes(stsd.get(8..)?).next());
+    let mut stream = MediaStream::new(
+        kind,
+        entry.map_or_else(|| "Unknown".to_string(), |(format, _)| codec_name(&format)),
+    );
+
+    let mut duration = None;
+    if let Some(mdhd) = child(mdia, b"mdhd") {
+        let (timescale, length, language) = if mdhd.first() == Some(&1) {
+            (read_u32(mdhd, 20), read_u64(mdhd, 24), read_u16(mdhd, 32))
+        } else {
+            (
+                read_u32(mdhd, 12),
+                read_u32(mdhd, 16).map(u64::from),
+                read_u16(mdhd, 20),
+            )
+        };
+        duration = timescale
+            .zip(length)
+            .and_then(|(timescale, length)| seconds(length, timescale));
+        stream.language = language.and_then(packed_language);
+    }
+
+    if let Some((format, entry)) = entry {
+        match kind {
+            StreamKind::Video => {
+                stream.width = read_u16(entry, 24)
+                    .map(u32::from)
+                    .filter(|&width| width > 0);
+                stream.height = read_u16(entry, 26)
+                    .map(u32::from)
+                    .filter(|&height| height > 0);
+                sample_entry_children(entry.get(78..).unwrap_or_default(), &mut stream);
+            }
+            StreamKind::Audio => audio_sample_entry(&format, entry, &mut stream),
+            _ => {}
+        }
+    }
+    if stream.width.is_none() {
+        // The presentation size, as 16.16 fixed point
+        if let Some(tkhd) = child(content, b"tkhd") {
+            let offset = if tkhd.first() == Some(&1) { 88 } else { 76 };
+            stream.width = read_u32(tkhd, offset)
+                .map(|width| width >> 16)
+                .filter(|&width| width > 0);
+            stream.height = read_u32(tkhd, offset + 4)
+                .map(|height| height >> 16)
+                .filter(|&height| height > 0);
+        }
+    }
+
+    if let (Some((count, bytes)), Some(duration)) = (
+        stbl.and_then(|stbl| child(stbl, b"stsz"))
+            .and_then(sample_sizes),
+        duration.filter(|&duration| duration > 0.0),
+    ) {
+        if stream.bitrate.is_none() && bytes > 0 {
+            stream.bitrate = Some(bits_per_second(bytes, duration));
+        }
+        if kind == StreamKind::Video && count > 0 {
+            let frame_rate = f64::from(count) / duration;
+            stream.frame_rate = Some((frame_rate * 1000.0).round() / 1000.0);
+        }
+    }
+    Some((stream, duration))
+}
+
+/// Read an audio sample entry: channels, sample rate and decoder
+/// configuration
+///
+/// `QuickTime` versions 1 and 2 of the entry are longer; version 2 moves the
+/// sample rate and channels into its extension.
+fn audio_sample_entry(format: &[u8], entry: &[u8], stream: &mut MediaStream) {
+    let version = read_u16(entry, 8).unwrap_or(0);
+    if version == 2 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let sample_rate = read_u64(entry, 32).map(|bits| f64::from_bits(bits).round() as u32);
+        stream.sample_rate = sample_rate;
+        stream.channels = read_u32(entry, 40);
+    } else {
+        stream.channels = read_u16(entry, 16).map(u32::from);
+        stream.sample_rate = read_u32(entry, 24).map(|rate| rate >> 16);
+    }
+    let children = match version {
+        1 => 44,
+        2 => 64,
+        _ => 28,
+    };
+    sample_entry_children(entry.get(children..).unwrap_or_default(), stream);
+    // Version 1 QuickTime entries may nest the decoder configuration in a
+    // `wave` box
+    if format == b"mp4a" && stream.bitrate.is_none() {
+        if let Some(wave) = entry.get(children..).and_then(|data| child(data, b"wave")) {
+            sample_entry_children(wave, stream);
+        }
+    }
+    stream.sample_rate = stream.sample_rate.filter(|&rate| rate > 0);
+    stream.channels = stream.channels.filter(|&channels| channels > 0);
+}
+
+/// Read the bitrate, and for MPEG-4 audio and video the codec, from the
+/// boxes of a sample entry
+fn sample_entry_children(data: &[u8], stream: &mut MediaStream) {
+    for (kind, content) in boxes(data) {
+        match &kind {
+            b"esds" => {
+                if let Some((object_type, bitrate)) = decoder_config(content) {
+                    if let Some(codec) = object_type_codec(object_type) {
+                        stream.codec = codec.to_string();
+                    }
+                    if bitrate > 0 {
+                        stream.bitrate = Some(u64::from(bitrate));
+                    }
+                }
+            }
+            // Buffer size, maximum and average bitrate
+            b"btrt" => {
+                if let Some(bitrate) = read_u32(content, 8).filter(|&bitrate| bitrate > 0) {
+                    stream.bitrate.get_or_insert(u64::from(bitrate));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The object type and average bitrate of the decoder configuration in an
+/// elementary stream descriptor box
+fn decoder_config(esds: &[u8]) -> Option<(u8, u32)> {
+    // Version and flags, then the ES descriptor
+    let es = descriptor(esds.get(4..)?, 0x03)?;
+    let flags = *es.get(2)?;
+    let mut position = 3;
+    if flags & 0x80 != 0 {
+        position += 2;
+    }
+    if flags & 0x40 != 0 {
+        position += 1 + usize::from(*es.get(position)?);
+    }
+    if flags & 0x20 != 0 {
+        position += 2;
+    }
+    let config = descriptor(es.get(position..)?, 0x04)?;
+    Some((*config.first()?, read_u32(config, 9)?))
+}
+
+/// The content of the descriptor at the start of `data` if it has `tag`
+///
+/// Its length takes up to four bytes of seven bits each.
+fn descriptor(data: &[u8], tag: u8) -> Option<&[u8]> {
+    if *data.first()? != tag {
+        return None;
+    }
+    let mut length = 0usize;
+    let mut position = 1;
+    for _ in 0..4 {
+        let byte = *data.get(position)?;
+        position += 1;
+        length = (length << 7) | usize::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    data.get(position..(position + length).min(data.len()))
+}
+
+/// The number of samples of a track and their total size
+fn sample_sizes(stsz: &[u8]) -> Option<(u32, u64)> {
+    let size = read_u32(stsz, 4)?;
+    let count = read_u32(stsz, 8)?;
+    if size != 0 {
+        return Some((count, u64::from(size) * u64::from(count)));
+    }
+    let total = stsz
+        .get(12..)?
+        .chunks_exact(4)
+        .take(usize::try_from(count).unwrap_or(usize::MAX))
+        .map(|size| u64::from(u32::from_be_bytes([size[0], size[1], size[2], size[3]])))
+        .sum();
+    Some((count, total))
+}
+
+/// Read the tags of a user data box
+fn user_data(content: &[u8], info: &mut MediaInfo) {
+    for (kind, item) in boxes(content) {
+        if &kind == b"meta" {
+            meta(item, info);
+        } else if let Some(name) = item_name(&kind) {
+            // A QuickTime string: length and language, then the text
+            if let Some(length) = read_u16(item, 0) {
+                let text = item.get(4..4 + usize::from(length)).unwrap_or_default();
+                info.add_tag(name, &String::from_utf8_lossy(text));
+            }
+        }
+    }
+}
+
+/// Read the item list of a metadata box, naming its items by the `keys`
+/// box if there is one
+fn meta(content: &[u8], info: &mut MediaInfo) {
+    // An ISO full box, except in QuickTime where the handler comes first
+    let content = if content.get(4..8) == Some(b"hdlr") {
+        content
+    } else {
+        content.get(4..).unwrap_or_default()
+    };
+    let keys: Vec<String> = child(content, b"keys")
+        .map(|keys| {
+            boxes(keys.get(8..).unwrap_or_default())
+                .map(|(_, name)| String::from_utf8_lossy(name).into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    let Some(ilst) = child(content, b"ilst") else {
+        return;
+    };
+    for (kind, item) in boxes(ilst) {
+        if !keys.is_empty() {
+            let index = usize::try_from(u32::from_be_bytes(kind)).unwrap_or(0);
+            if let Some(key) = index.checked_sub(1).and_then(|index| keys.get(index)) {
+                if let Some((data_type, value)) = data_value(item) {
+                    info.add_tag(&quicktime_key_name(key), &text_value(data_type, value));
+                }
+            }
+            continue;
+        }
+        item_list_entry(&kind, item, info);
+    }
+}
+
+/// Read an entry of an iTunes-style item list
+fn item_list_entry(kind: &[u8], item: &[u8], info: &mut MediaInfo) {
+    let Some((data_type, value)) = data_value(item) else {
+        return;
+    };
+    match kind {
+        b"covr" => {
+            if info.cover.is_none() && !value.is_empty() {
+                let mime_type = image_mime_type(value).unwrap_or_else(|| {
+                    match data_type {
+                        14 => "image/png",
+                        27 => "image/bmp",
+                        _ => "image/jpeg",
+                    }
+                    .to_string()
+                });
+                info.cover = Some(CoverArt {
+                    mime_type,
+                    data: value.to_vec(),
+                });
+            }
+        }
+        // Number and total, after two reserved bytes
+        b"trkn" | b"disk" => {
+            let name = if kind == b"trkn" { "track" } else { "disc" };
+            match (read_u16(value, 2), read_u16(value, 4)) {
+                (Some(number), Some(total)) if total > 0 => {
+                    info.add_tag(name, &format!("{number}/{total}"));
+                }
+                (Some(number), _) if number > 0 => info.add_tag(name, &number.to_string()),
+                _ => {}
+            }
+        }
+        // An ID3v1 genre, counted from 1
+        b"gnre" => {
+            if let Some(genre) = read_u16(value, 0)
+                .and_then(|genre| usize::from(genre).checked_sub(1))
+                .and_then(|genre| GENRES.get(genre))
+            {
+                info.add_tag("genre", genre);
+            }
+        }
+        // A free-form item, named by its `name` box
+        b"----" => {
+            if let Some(name) = child(item, b"name").and_then(|name| name.get(4..)) {
+                let name = String::from_utf8_lossy(name).to_lowercase();
+                if !name.starts_with("itun") {
+                    info.add_tag(&name.replace(' ', "_"), &text_value(data_type, value));
+                }
+            }
+        }
+        _ => {
+            if let Some(name) = item_name(kind) {
+                info.add_tag(name, &text_value(data_type, value));
+            }
+        }
+    }
+}
+
+/// The well-known type and value of the `data` box of an item
+fn data_value(item: &[u8]) -> Option<(u32, &[u8])> {
+    let data = child(item, b"data")?;
+    // Type, then locale
+    Some((read_u32(data, 0)? & 0x00FF_FFFF, data.get(8..)?))
+}
+
+/// An item value as text: UTF-8, UTF-16 or a big-endian integer
+fn text_value(data_type: u32, value: &[u8]) -> String {
+    match data_type {
+        2 => encoding_rs::UTF_16BE
+            .decode_without_bom_handling(value)
+            .0
+            .into_owned(),
+        21 | 22 => value
+            .iter()
+            .fold(0i64, |number, &byte| (number << 8) | i64::from(byte))
+            .to_string(),
+        _ => String::from_utf8_lossy(value).into_owned(),
+    }
+}
+
+/// The common name of an item list or user data key
+fn item_name(kind: &[u8]) -> Option<&'static str> {
+    Some(match kind {
+        b"\xA9nam" => "title",
+        b"\xA9ART" | b"\xA9aut" => "artist",
+        b"\xA9alb" => "album",
+        b"aART" => "album_artist",
+        b"\xA9day" => "date",
+        b"\xA9gen" => "genre",
+        b"\xA9cmt" => "comment",
+        b"\xA9wrt" | b"\xA9com" => "composer",
+        b"cprt" | b"\xA9cpy" => "copyright",
+        b"\xA9too" | b"\xA9enc" | b"\xA9swr" => "encoder",
+        b"desc" | b"\xA9des" | b"\xA9inf" => "description",
+        b"\xA9xyz" => "location",
+        b"\xA9mak" => "make",
+        b"\xA9mod" => "model",
+        _ => return None,
+    })
+}
+
+/// The common name of a `QuickTime` `keys` tag
+fn quicktime_key_name(key: &str) -> String {
+    let key = key.strip_prefix(QUICKTIME_KEY_PREFIX).unwrap_or(key);
+    match key {
+        "author" => "artist".to_string(),
+        "software" => "encoder".to_string(),
+        "creationdate" => "date".to_string(),
+        "location.ISO6709" => "location".to_string(),
+        _ => key.to_lowercase().replace(['.', ' '], "_"),
+    }
+}
+
+/// The codec of a sample entry format
+fn codec_name(format: &[u8]) -> String {
+    match format {
+        b"avc1" | b"avc3" => "H.264",
+        b"hvc1" | b"hev1" => "H.265",
+        b"av01" => "AV1",
+        b"vp08" => "VP8",
+        b"vp09" => "VP9",
+        b"mp4v" => "MPEG-4 Visual",
+        b"s263" | b"h263" => "H.263",
+        b"jpeg" | b"mjpa" | b"mjpb" => "Motion JPEG",
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" | b"ap4x" => "ProRes",
+        b"mp4a" => "AAC",
+        b".mp3" => "MP3",
+        b"ac-3" => "AC-3",
+        b"ec-3" => "E-AC-3",
+        b"Opus" => "Opus",
+        b"fLaC" => "FLAC",
+        b"alac" => "ALAC",
+        b"samr" => "AMR",
+        b"sowt" | b"twos" | b"lpcm" | b"in24" | b"in32" | b"fl32" | b"fl64" | b"raw " => "PCM",
+        b"ulaw" => "μ-law",
+        b"alaw" => "A-law",
+        b"ima4" => "IMA ADPCM",
+        b"tx3g" => "Timed Text",
+        b"wvtt" => "WebVTT",
+        b"stpp" => "TTML",
+        b"c608" => "CEA-608",
+        b"c708" => "CEA-708",
+        b"text" => "QuickTime Text",
+        _ => return String::from_utf8_lossy(format).trim().to_string(),
+    }
+    .to_string()
+}
+
+/// The codec of an MPEG-4 object type, where it tells more than the
+/// sample entry
+fn object_type_codec(object_type: u8) -> Option<&'static str> {
+    Some(match object_type {
+        0x20 => "MPEG-4 Visual",
+        0x21 => "H.264",
+        0x40 | 0x66..=0x68 => "AAC",
+        0x60..=0x65 => "MPEG-2 Video",
+        0x69 | 0x6B => "MP3",
+        0x6A => "MPEG-1 Video",
+        0xA5 => "AC-3",
+        0xA6 => "E-AC-3",
+        _ => return None,
+    })
+}
+
+/// A language packed as three 5-bit letters, `None` if undetermined
+fn packed_language(packed: u16) -> Option<String> {
+    let language: String = [10, 5, 0]
+        .iter()
+        .map(|shift| char::from(u8::try_from((packed >> shift) & 0x1F).unwrap_or(0) + 0x60))
+        .collect();
+    (language.chars().all(|c| c.is_ascii_lowercase()) && language != "und").then_some(language)
+}
+
+/// `length` in units of `timescale` per second, in seconds
+fn seconds(length: u64, timescale: u32) -> Option<f64> {
+    // All ones for an unknown duration
+    if timescale == 0 || length == u64::MAX || length == u64::from(u32::MAX) {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    Some(length as f64 / f64::from(timescale))
+}
+
+/// A time in seconds since 1904, `None` if unset
+fn mp4_time(seconds: u64) -> Option<DateTime<Utc>> {
+    let seconds = i64::try_from(seconds).ok().filter(|&seconds| seconds > 0)?;
+    DateTime::from_timestamp(seconds - MP4_EPOCH_OFFSET, 0)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(kind: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut atom = u32::try_from(content.len() + 8)
+            .unwrap()
+            .to_be_bytes()
+            .to_vec();
+        atom.extend_from_slice(kind);
+        atom.extend_from_slice(content);
+        atom
+    }
+
+    fn full_atom(kind: &[u8], content: &[u8]) -> Vec<u8> {
+        atom(kind, &[&[0; 4], content].concat())
+    }
+
+    /// A track of `handler` with a timescale, duration, sample entry and
+    /// sample sizes
+    fn trak(
+        handler: &[u8],
+        timescale: u32,
+        duration: u32,
+        entry: &[u8],
+        sample_sizes: &[u8],
+    ) -> Vec<u8> {
+        let mut mdhd = [0u8; 20];
+        mdhd[8..12].copy_from_slice(&timescale.to_be_bytes());
+        mdhd[12..16].copy_from_slice(&duration.to_be_bytes());
+        // "eng"
+        mdhd[16..18].copy_from_slice(&0x15C7u16.to_be_bytes());
+        let hdlr = [&[0; 4], handler, &[0; 12]].concat();
+        let stsd = full_atom(b"stsd", &[1u32.to_be_bytes().as_slice(), entry].concat());
+        let stbl = atom(b"stbl", &[stsd, full_atom(b"stsz", sample_sizes)].concat());
+        let minf = atom(b"minf", &stbl);
+        let mdia = atom(
+            b"mdia",
+            &[full_atom(b"mdhd", &mdhd), full_atom(b"hdlr", &hdlr), minf].concat(),
+        );
+        atom(b"trak", &mdia)
+    }
+
+    fn tag(kind: &[u8], data_type: u32, value: &[u8]) -> Vec<u8> {
+        let data = atom(
+            b"data",
+            &[data_type.to_be_bytes().as_slice(), &[0; 4], value].concat(),
+        );
+        atom(kind, &data)
+    }
+
+    fn movie() -> Vec<u8> {
+        let mut mvhd = [0u8; 96];
+        // 2024-05-17T09:30:00Z
+        let created = u32::try_from(1_715_938_200 + MP4_EPOCH_OFFSET).unwrap();
+        mvhd[0..4].copy_from_slice(&created.to_be_bytes());
+        mvhd[8..12].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[12..16].copy_from_slice(&10_000u32.to_be_bytes());
+
+        let mut avc1 = vec![0u8; 78];
+        avc1[24..26].copy_from_slice(&1280u16.to_be_bytes());
+        avc1[26..28].copy_from_slice(&720u16.to_be_bytes());
+        let video_stsz = [1000u32, 250]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<u8>>();
+        let video = trak(b"vide", 25, 250, &atom(b"avc1", &avc1), &video_stsz);
+
+        let mut mp4a = vec![0u8; 28];
+        mp4a[16..18].copy_from_slice(&2u16.to_be_bytes());
+        mp4a[24..28].copy_from_slice(&(44_100u32 << 16).to_be_bytes());
+        let config = [
+            &[0x40, 0x15, 0, 0, 0][..],
+            &[0; 4],
+            &128_000u32.to_be_bytes(),
+        ]
+        .concat();
+        let es = [
+            &[0, 1, 0, 0x04, u8::try_from(config.len()).unwrap()][..],
+            &config,
+        ]
+        .concat();
+        let esds = [&[0x03, u8::try_from(es.len()).unwrap()][..], &es].concat();
+        mp4a.extend_from_slice(&full_atom(b"esds", &esds));
+        let audio_stsz = [0u32, 0]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<u8>>();
+        let audio = trak(b"soun", 44_100, 441_000, &atom(b"mp4a", &mp4a), &audio_stsz);
+
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+        let ilst = atom(
+            b"ilst",
+            &[
+                tag(b"\xA9nam", 1, "Café".as_bytes()),
+                tag(b"\xA9ART", 1, b"Prism"),
+                tag(b"trkn", 0, &[0, 0, 0, 3, 0, 12, 0, 0]),
+                tag(b"gnre", 0, &[0, 9]),
+                tag(b"covr", 14, &png),
+            ]
+            .concat(),
+        );
+        let hdlr = full_atom(b"hdlr", &[&[0; 4], b"mdir".as_slice(), &[0; 12]].concat());
+        let udta = atom(b"udta", &full_atom(b"meta", &[hdlr, ilst].concat()));
+        let moov = atom(
+            b"moov",
+            &[full_atom(b"mvhd", &mvhd), video, audio, udta].concat(),
+        );
+        [atom(b"ftyp", b"isom\0\0\0\0isom"), moov].concat()
+    }
+
+    #[test]
+    fn test_movie() {
+        let data = movie();
+        assert!(is_mp4(&data));
+        let info = info(&data).unwrap();
+        assert_eq!(info.duration, Some(10.0));
+        assert_eq!(
+            info.created.unwrap().to_rfc3339(),
+            "2024-05-17T09:30:00+00:00"
+        );
+
+        let video = &info.streams[0];
+        assert_eq!(video.kind, StreamKind::Video);
+        assert_eq!(video.codec, "H.264");
+        assert_eq!((video.width, video.height), (Some(1280), Some(720)));
+        assert_eq!(video.frame_rate, Some(25.0));
+        assert_eq!(video.bitrate, Some(200_000));
+        assert_eq!(video.language.as_deref(), Some("eng"));
+
+        let audio = &info.streams[1];
+        assert_eq!(audio.codec, "AAC");
+        assert_eq!(audio.sample_rate, Some(44_100));
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.bitrate, Some(128_000));
+
+        assert_eq!(info.tag("title"), Some("Café"));
+        assert_eq!(info.tag("artist"), Some("Prism"));
+        assert_eq!(info.tag("track"), Some("3/12"));
+        assert_eq!(info.tag("genre"), Some("Jazz"));
+        assert_eq!(info.cover.unwrap().mime_type, "image/png");
+    }
+
+    #[test]
+    fn test_quicktime_keys() {
+        let keys = full_atom(
+            b"keys",
+            &[
+                2u32.to_be_bytes().to_vec(),
+                atom(b"mdta", b"com.apple.quicktime.make"),
+                atom(b"mdta", b"com.apple.quicktime.location.ISO6709"),
+            ]
+            .concat(),
+        );
+        let ilst = atom(
+            b"ilst",
+            &[
+                tag(&1u32.to_be_bytes(), 1, b"Apple"),
+                tag(&2u32.to_be_bytes(), 1, b"+48.8583+002.2945/"),
+            ]
+            .concat(),
+        );
+        let hdlr = full_atom(b"hdlr", &[&[0; 4], b"mdta".as_slice(), &[0; 12]].concat());
+        let meta = atom(b"meta", &[hdlr, keys, ilst].concat());
+        let moov = atom(b"moov", &meta);
+        let info = info(&[atom(b"ftyp", b"qt  \0\0\0\0qt  "), moov].concat()).unwrap();
+        assert_eq!(info.tag("make"), Some("Apple"));
+        assert_eq!(info.tag("location"), Some("+48.8583+002.2945/"));
+    }
+
+    #[test]
+    fn test_no_movie_box() {
+        assert!(info(&atom(b"ftyp", b"isom\0\0\0\0")).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Spreadsheet cells read through calamine
+//!
+//! Shared by the XLSX and XLS parsers: cell values are shown the way a
+//! spreadsheet displays them by default, and a worksheet becomes one
+//! [`TableBlock`] with a row per sheet row.
+
+use calamine::{Data, Range};
+use chrono::Timelike;
+use prism_core::document::{
+    ContentBlock, Rect, ShapeStyle, TableBlock, TableCell, TableRow, TextBlock, TextDirection,
+    TextRun, TextStyle,
+};
+
+/// Approximate width of a column, in points
+const COLUMN_WIDTH: f64 = 72.0;
+
+/// Approximate height of a row, in points
+const ROW_HEIGHT: f64 = 20.0;
+
+/// Display text of a cell value
+///
+/// Whole numbers have no decimal point, booleans are `TRUE`/`FALSE`,
+/// errors use Excel's codes (`#DIV/0!`), and serial dates are written in
+/// ISO 8601: `2024-01-31` for a date, `13:30:00` for a time of day and
+/// both for a date with a time. Durations are hours, minutes and seconds.
+#[must_use]
+pub fn cell_text(data: &Data) -> String {
+    match data {
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::String(s) => s.clone(),
+        Data::Bool(true) => "TRUE".to_string(),
+        Data::Bool(false) => "FALSE".to_string(),
+        Data::DateTime(dt) if dt.is_duration() => dt.as_duration().map_or_else(
+            || dt.as_f64().to_string(),
+            |d| {
+                let seconds = d.num_seconds();
+                format!(
+                    "{}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds % 3600 / 60,
+                    seconds % 60
+                )
+            },
+        ),
+        Data::DateTime(dt) => match dt.as_datetime() {
+            // Serial numbers below one are a time of day without a date
+            Some(value) if dt.as_f64() < 1.0 => value.format("%H:%M:%S").to_string(),
+            Some(value) if value.num_seconds_from_midnight() == 0 => {
+                value.format("%Y-%m-%d").to_string()
+            }
+            Some(value) => value.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => dt.as_f64().to_string(),
+        },
+        Data::DateTimeIso(dt) => dt.clone(),
+        Data::DurationIso(d) => d.clone(),
+        Data::Error(e) => e.to_string(),
+        Data::Empty => String::new(),
+    }
+}
+
+/// The cell grid of a worksheet as a table
+///
+/// Returns `None` for an empty sheet. Empty cells are kept, without
+/// content, so that every row has one cell per column.
+#[must_use]
+pub fn sheet_table(range: &Range<Data>) -> Option<TableBlock> {
+    let (row_count, col_count) = range.get_size();
+    if row_count == 0 || col_count == 0 {
+        return None;
+    }
+
+    let rows = range
+        .rows()
+        .map(|row| TableRow {
+            cells: row.iter().map(cell).collect(),
+            height: None,
+        })
+        .collect();
+
+    #[allow(clippy::cast_precision_loss)]
+    let bounds = Rect::new(
+        0.0,
+        0.0,
+        col_count as f64 * COLUMN_WIDTH,
+        row_count as f64 * ROW_HEIGHT,
+    );
+    Some(TableBlock {
+        bounds,
+        rows,
+        column_count: col_count,
+        style: ShapeStyle::default(),
+        rotation: 0.0,
+    })
+}
+
+fn cell(data: &Data) -> TableCell {
+    let text = cell_text(data);
+    let content = if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![ContentBlock::Text(TextBlock {
+            bounds: Rect::default(),
+            runs: vec![TextRun {
+                text: text.into(),
+                style: TextStyle::default(),
+                bounds: None,
+                char_positions: None,
+                link: None,
+                revision: None,
+            }],
+            paragraph_style: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            direction: TextDirection::Auto,
+        })]
+    };
+    TableCell {
+        content,
+        col_span: 1,
+        row_span: 1,
+        background_color: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{CellErrorType, ExcelDateTime, ExcelDateTimeType};
+
+    fn datetime(value: f64, datetime_type: ExcelDateTimeType) -> Data {
+        Data::DateTime(ExcelDateTime::new(value, datetime_type, false))
+    }
+
+    #[test]
+    fn test_cell_text() {
+        assert_eq!(cell_text(&Data::Float(3.0)), "3");
+        assert_eq!(cell_text(&Data::Float(2.5)), "2.5");
+        assert_eq!(cell_text(&Data::Bool(true)), "TRUE");
+        assert_eq!(cell_text(&Data::Error(CellErrorType::Div0)), "#DIV/0!");
+        assert_eq!(
+            cell_text(&datetime(45322.0, ExcelDateTimeType::DateTime)),
+            "2024-01-31"
+        );
+        assert_eq!(
+            cell_text(&datetime(45322.5625, ExcelDateTimeType::DateTime)),
+            "2024-01-31 13:30:00"
+        );
+        assert_eq!(
+            cell_text(&datetime(0.5625, ExcelDateTimeType::DateTime)),
+            "13:30:00"
+        );
+        assert_eq!(
+            cell_text(&datetime(1.5, ExcelDateTimeType::TimeDelta)),
+            "36:00:00"
+        );
+    }
+
+    #[test]
+    fn test_sheet_table() {
+        let mut range = Range::new((0, 0), (1, 2));
+        range.set_value((0, 0), Data::String("Item".to_string()));
+        range.set_value((0, 2), Data::String("Paid".to_string()));
+        range.set_value((1, 0), Data::String("Tea".to_string()));
+        range.set_value((1, 1), Data::Int(3));
+        range.set_value((1, 2), Data::Bool(false));
+
+        let table = sheet_table(&range).unwrap();
+        assert_eq!(table.column_count, 3);
+        assert_eq!(table.rows.len(), 2);
+        assert!(table.rows[0].cells[1].content.is_empty());
+        let texts: Vec<String> = table.rows[1]
+            .cells
+            .iter()
+            .map(TableCell::extract_text)
+            .collect();
+        assert_eq!(texts, ["Tea", "3", "FALSE"]);
+
+        assert!(sheet_table(&Range::empty()).is_none());
+    }
+}
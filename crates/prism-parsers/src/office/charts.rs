@@ -0,0 +1,1360 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Charts (`c:chartSpace` parts)
+//!
+//! Word documents, workbooks and presentations embed charts the same way: a
+//! graphic frame refers to a chart part through `c:chart r:id`, and the
+//! part holds the plot types, the series and a cache of their data. Charts
+//! are drawn from that cache, so they show the values last saved with the
+//! file.
+//!
+//! Bar, column, line, area, pie, doughnut and scatter plots are drawn,
+//! with gridlines, axis and category labels, the title and a legend; a
+//! chart combining plot types draws them all on the same axes. 3-D charts
+//! are drawn flat and doughnuts as pies. Series colors set in the chart are
+//! kept; theme colors are not resolved, so the other series take the colors
+//! of the default Office theme.
+
+use prism_core::document::{
+    ContainerBlock, ContentBlock, PathCommand, Point, Rect, TextBlock, TextRun, TextStyle,
+    VectorBlock, VectorPath,
+};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::office::relationships::Relationships;
+use crate::office::sheets::read_part;
+use crate::office::{fills, geometry, utils};
+
+/// Relationship type of a chart part
+pub const CHART_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart";
+
+/// Series colors of the default Office theme, `accent1` to `accent6`
+const PALETTE: [&str; 6] = [
+    "#4472C4", "#ED7D31", "#A5A5A5", "#FFC000", "#5B9BD5", "#70AD47",
+];
+
+/// Color of axes and labels
+const AXIS_COLOR: &str = "#868686";
+
+/// Color of gridlines
+const GRID_COLOR: &str = "#D9D9D9";
+
+/// Type of a plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotKind {
+    /// Bars, vertical (columns) or horizontal
+    Bar {
+        /// Whether the bars grow to the right
+        horizontal: bool,
+    },
+    /// Points joined by lines
+    Line,
+    /// Lines filled down to the axis
+    Area,
+    /// Slices of a circle; doughnuts are drawn as pies
+    Pie,
+    /// Points placed by two values
+    Scatter {
+        /// Whether the points are joined by lines
+        lines: bool,
+    },
+}
+
+/// How the series of a plot are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// Side by side, or on top of each other for lines
+    #[default]
+    Standard,
+    /// Stacked on each other
+    Stacked,
+    /// Stacked to 100%
+    PercentStacked,
+}
+
+/// A data series with its cached values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartSeries {
+    /// Series name
+    pub name: Option<String>,
+    /// Category labels, by point
+    pub categories: Vec<Option<String>>,
+    /// Values, by point; `None` for a gap
+    pub values: Vec<Option<f64>>,
+    /// X values of a scatter series, by point
+    pub x_values: Vec<Option<f64>>,
+    /// Color set for the series
+    pub color: Option<String>,
+    /// Colors set for single points, by point
+    pub point_colors: HashMap<usize, String>,
+    /// Whether the series line is hidden (`a:noFill` in its `a:ln`)
+    pub no_line: bool,
+}
+
+/// One plot of a chart, such as a `c:barChart`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    /// Type of the plot
+    pub kind: PlotKind,
+    /// How its series are combined
+    pub grouping: Grouping,
+    /// Its series
+    pub series: Vec<ChartSeries>,
+}
+
+/// A chart read from a `c:chartSpace` part
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chart {
+    /// Chart title
+    pub title: Option<String>,
+    /// Plots, drawn on the same axes
+    pub plots: Vec<Plot>,
+    /// Whether a legend is shown
+    pub legend: bool,
+}
+
+impl Chart {
+    /// Parse a chart part, or `None` if it has no plot that can be drawn
+    #[must_use]
+    pub fn from_xml(xml: &str) -> Option<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+        let mut stack: Vec<Vec<u8>> = Vec::new();
+        let mut chart = Chart::default();
+        let mut title = String::new();
+        let mut title_deleted = false;
+        let mut point = 0;
+        let mut data_point = None;
+
+        loop {
+            let (e, empty) = match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) => (e, false),
+                Ok(Event::Empty(e)) => (e, true),
+                Ok(Event::End(_)) => {
+                    if stack.pop().as_deref() == Some(&b"c:dPt"[..]) {
+                        data_point = None;
+                    }
+                    buf.clear();
+                    continue;
+                }
+                Ok(Event::Text(text)) => {
+                    let text = text.unescape().unwrap_or_default();
+                    match stack.last().map(Vec::as_slice) {
+                        Some(b"a:t") if in_chart_title(&stack) => title.push_str(&text),
+                        Some(b"c:v") => chart.value(&stack, point, &text),
+                        _ => {}
+                    }
+                    buf.clear();
+                    continue;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {
+                    buf.clear();
+                    continue;
+                }
+            };
+            let name = e.name().as_ref().to_vec();
+            match name.as_slice() {
+                b"c:ser" => {
+                    if let Some(plot) = chart.plots.last_mut() {
+                        plot.series.push(ChartSeries::default());
+                    }
+                }
+                b"c:pt" => {
+                    point = utils::attr_value_opt(&e, b"idx")
+                        .and_then(|idx| idx.parse().ok())
+                        .unwrap_or(0);
+                }
+                b"c:idx" if stack.last().map(Vec::as_slice) == Some(&b"c:dPt"[..]) => {
+                    data_point = utils::attr_value_opt(&e, b"val").and_then(|idx| idx.parse().ok());
+                }
+                b"c:barDir" | b"c:grouping" | b"c:scatterStyle" => chart.plot_property(&e),
+                // A missing `val` means true
+                b"c:autoTitleDeleted" => {
+                    title_deleted = !matches!(
+                        utils::attr_value_opt(&e, b"val").as_deref(),
+                        Some("0" | "false")
+                    );
+                }
+                b"c:legend" => chart.legend = true,
+                b"a:noFill"
+                    if in_shape_properties(&stack)
+                        && stack.last().map(Vec::as_slice) == Some(&b"a:ln"[..]) =>
+                {
+                    if let Some(series) = chart.series_mut().filter(|_| data_point.is_none()) {
+                        series.no_line = true;
+                    }
+                }
+                _ if in_shape_properties(&stack) => chart.color(&e, &stack, data_point),
+                name => {
+                    if let Some(kind) = plot_kind(name) {
+                        chart.plots.push(Plot {
+                            kind,
+                            grouping: Grouping::Standard,
+                            series: Vec::new(),
+                        });
+                    }
+                }
+            }
+            if !empty {
+                stack.push(name);
+            }
+            buf.clear();
+        }
+
+        chart.plots.retain(|plot| !plot.series.is_empty());
+        if chart.plots.is_empty() {
+            return None;
+        }
+        chart.title = Some(title).filter(|title| !title.trim().is_empty());
+        // Without a title of its own, a chart of one series is titled
+        // after it
+        if chart.title.is_none() && !title_deleted {
+            if let [plot] = chart.plots.as_slice() {
+                if let [series] = plot.series.as_slice() {
+                    chart.title.clone_from(&series.name);
+                }
+            }
+        }
+        Some(chart)
+    }
+
+    /// Record a property of the plot being read: the direction of bars, the
+    /// grouping of series or the style of a scatter plot
+    fn plot_property(&mut self, e: &BytesStart<'_>) {
+        let Some(plot) = self.plots.last_mut() else {
+            return;
+        };
+        let value = utils::attr_value_opt(e, b"val");
+        match e.name().as_ref() {
+            b"c:barDir" => {
+                plot.kind = PlotKind::Bar {
+                    horizontal: value.as_deref() == Some("bar"),
+                };
+            }
+            b"c:grouping" => {
+                plot.grouping = match value.as_deref() {
+                    Some("stacked") => Grouping::Stacked,
+                    Some("percentStacked") => Grouping::PercentStacked,
+                    _ => Grouping::Standard,
+                };
+            }
+            b"c:scatterStyle" => {
+                plot.kind = PlotKind::Scatter {
+                    lines: value.as_deref() != Some("marker"),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// The series being read
+    fn series_mut(&mut self) -> Option<&mut ChartSeries> {
+        self.plots.last_mut()?.series.last_mut()
+    }
+
+    /// Record a cached value (`c:v`) of point `point`, at the position
+    /// `stack` describes
+    fn value(&mut self, stack: &[Vec<u8>], point: usize, text: &str) {
+        let Some(series) = self.series_mut() else {
+            return;
+        };
+        let within = |name: &[u8]| stack.iter().any(|element| element == name);
+        // Axis titles may be linked to cells too
+        if !within(b"c:ser") {
+            return;
+        }
+        let number = text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite());
+        if within(b"c:tx") {
+            series.name = Some(text.to_string());
+        } else if within(b"c:cat") {
+            set(&mut series.categories, point, Some(text.to_string()));
+        } else if within(b"c:xVal") {
+            // Text x values are placed by their position
+            let x = number.or(Some(position(point)));
+            set(&mut series.x_values, point, x);
+            set(&mut series.categories, point, Some(text.to_string()));
+        } else if within(b"c:val") || within(b"c:yVal") {
+            set(&mut series.values, point, number);
+        }
+    }
+
+    /// Record the color of element `e` of the shape properties of a series
+    /// or of one of its points
+    fn color(&mut self, e: &BytesStart<'_>, stack: &[Vec<u8>], data_point: Option<usize>) {
+        // Only the fill of bars and slices, or the line of lines
+        let in_line = stack.iter().any(|element| element == b"a:ln");
+        let kind = self.plots.last().map(|plot| plot.kind);
+        let wanted = match kind {
+            Some(PlotKind::Line | PlotKind::Scatter { .. }) => in_line,
+            _ => !in_line,
+        };
+        let Some(color) = fills::color(e).filter(|_| wanted) else {
+            return;
+        };
+        let Some(series) = self.series_mut() else {
+            return;
+        };
+        match data_point {
+            Some(index) => {
+                series.point_colors.entry(index).or_insert(color);
+            }
+            None => {
+                series.color.get_or_insert(color);
+            }
+        }
+    }
+
+    /// Draw the chart in a box `bounds` gives the size of
+    ///
+    /// The blocks of the chart are positioned within the box; the caller
+    /// places the box.
+    #[must_use]
+    pub fn render(&self, bounds: Rect) -> ContainerBlock {
+        let (width, height) = (bounds.width, bounds.height);
+        let mut children = Vec::new();
+
+        let title_height = if self.title.is_some() {
+            (height * 0.12).clamp(14.0, 28.0)
+        } else {
+            0.0
+        };
+        if let Some(title) = &self.title {
+            children.push(label(
+                title,
+                Rect::new(0.0, 2.0, width, title_height),
+                (title_height * 0.55).min(14.0),
+                "#404040",
+            ));
+        }
+        let legend = self.legend_entries();
+        let legend_height = if legend.is_empty() {
+            0.0
+        } else {
+            (height * 0.08).clamp(12.0, 20.0)
+        };
+        if !legend.is_empty() {
+            children.push(legend_block(
+                &legend,
+                Rect::new(0.0, height - legend_height, width, legend_height),
+            ));
+        }
+
+        let top = title_height + height * 0.04;
+        let bottom = height - legend_height - height * 0.04;
+        if self.plots[0].kind == PlotKind::Pie {
+            let plot = Rect::new(width * 0.05, top, width * 0.9, bottom - top);
+            children.push(ContentBlock::Vector(pie(&self.plots[0].series[0], plot)));
+        } else {
+            let plot = Rect::new(
+                width * 0.1,
+                top,
+                width * 0.86,
+                bottom - top - (height * 0.08).max(10.0),
+            );
+            children.extend(self.axes_plot(plot));
+        }
+
+        ContainerBlock {
+            bounds,
+            children,
+            container_type: Some("chart".to_string()),
+        }
+    }
+
+    /// Names and colors of the legend: the series, or the slices of a pie
+    fn legend_entries(&self) -> Vec<(String, String)> {
+        if !self.legend {
+            return Vec::new();
+        }
+        match self.plots[0].kind {
+            PlotKind::Pie => {
+                let series = &self.plots[0].series[0];
+                series
+                    .categories
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, category)| {
+                        Some((category.clone()?, point_color(series, index)))
+                    })
+                    .collect()
+            }
+            _ => self
+                .plots
+                .iter()
+                .flat_map(|plot| &plot.series)
+                .enumerate()
+                .filter_map(|(index, series)| {
+                    Some((series.name.clone()?, series_color(series, index)))
+                })
+                .collect(),
+        }
+    }
+
+    /// Gridlines, axis labels and the plots of a chart with axes, drawn in
+    /// `plot`
+    fn axes_plot(&self, plot: Rect) -> Vec<ContentBlock> {
+        let scatter = self
+            .plots
+            .iter()
+            .all(|plot| matches!(plot.kind, PlotKind::Scatter { .. }));
+        let horizontal = self
+            .plots
+            .iter()
+            .any(|plot| plot.kind == PlotKind::Bar { horizontal: true });
+        let (low, high) = self.value_range();
+        let categories = self.categories();
+        let count = categories.len().max(1);
+        let axes = Axes {
+            plot,
+            horizontal,
+            scale: Scale::new(low, high),
+            band: if horizontal { plot.height } else { plot.width } / position(count),
+        };
+
+        // Value axis along the side, or along the bottom for horizontal bars
+        let mut paths = Vec::new();
+        let mut labels = Vec::new();
+        for tick in axes.scale.ticks() {
+            let at = axes.value_at(tick);
+            let (from, to) = if horizontal {
+                (Point::new(at, 0.0), Point::new(at, plot.height))
+            } else {
+                (Point::new(0.0, at), Point::new(plot.width, at))
+            };
+            paths.push(stroke(vec![from, to], GRID_COLOR, 0.75));
+            let text = format_value(tick, self.is_percent());
+            let bounds = if horizontal {
+                Rect::new(plot.x + at - 20.0, plot.y + plot.height + 2.0, 40.0, 10.0)
+            } else {
+                Rect::new(0.0, plot.y + at - 5.0, plot.x - 4.0, 10.0)
+            };
+            labels.push(label(&text, bounds, 8.0, "#595959"));
+        }
+
+        // Category axis at zero
+        let zero = axes.value_at(0.0_f64.clamp(axes.scale.min, axes.scale.max));
+        let baseline = if horizontal {
+            vec![Point::new(zero, 0.0), Point::new(zero, plot.height)]
+        } else {
+            vec![Point::new(0.0, zero), Point::new(plot.width, zero)]
+        };
+        paths.push(stroke(baseline, AXIS_COLOR, 0.75));
+
+        if scatter {
+            self.scatter_series(&axes, &mut paths, &mut labels);
+        } else {
+            for (category, name) in categories.iter().enumerate() {
+                let start = axes.band_start(category);
+                let bounds = if horizontal {
+                    Rect::new(
+                        0.0,
+                        plot.y + start + axes.band / 2.0 - 5.0,
+                        plot.x - 4.0,
+                        10.0,
+                    )
+                } else {
+                    Rect::new(plot.x + start, plot.y + plot.height + 2.0, axes.band, 10.0)
+                };
+                labels.push(label(name, bounds, 8.0, "#595959"));
+            }
+            self.category_series(&axes, &mut paths);
+        }
+
+        let mut blocks = vec![ContentBlock::Vector(VectorBlock {
+            bounds: plot,
+            paths,
+        })];
+        blocks.extend(labels);
+        blocks
+    }
+
+    /// Draw the series of scatter plots, with the labels of their x axis
+    fn scatter_series(
+        &self,
+        axes: &Axes,
+        paths: &mut Vec<VectorPath>,
+        labels: &mut Vec<ContentBlock>,
+    ) {
+        let plot = axes.plot;
+        let (low, high) = self.x_range();
+        let x_scale = Scale::new(low, high);
+        let x_at = |x: f64| (x - x_scale.min) / (x_scale.max - x_scale.min) * plot.width;
+        for tick in x_scale.ticks() {
+            let bounds = Rect::new(
+                plot.x + x_at(tick) - 20.0,
+                plot.y + plot.height + 2.0,
+                40.0,
+                10.0,
+            );
+            labels.push(label(&format_value(tick, false), bounds, 8.0, "#595959"));
+        }
+        let mut index = 0;
+        for plot_data in &self.plots {
+            let lines = matches!(plot_data.kind, PlotKind::Scatter { lines: true });
+            for series in &plot_data.series {
+                let points: Vec<Option<Point>> = series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(point, value)| {
+                        let x = series
+                            .x_values
+                            .get(point)
+                            .copied()
+                            .flatten()
+                            .unwrap_or(position(point + 1));
+                        Some(Point::new(x_at(x), axes.value_at((*value)?)))
+                    })
+                    .collect();
+                paths.extend(line_series(
+                    &points,
+                    &series_color(series, index),
+                    lines && !series.no_line,
+                    true,
+                ));
+                index += 1;
+            }
+        }
+    }
+
+    /// Draw the series of bar, line and area plots, by category
+    fn category_series(&self, axes: &Axes, paths: &mut Vec<VectorPath>) {
+        let bar_plots = self
+            .plots
+            .iter()
+            .filter(|plot| matches!(plot.kind, PlotKind::Bar { .. }))
+            .count()
+            .max(1);
+        let mut index = 0;
+        let mut bar_plot = 0;
+        for plot_data in &self.plots {
+            let values = plot_values(plot_data);
+            match plot_data.kind {
+                PlotKind::Bar { .. } => {
+                    bars(
+                        axes,
+                        plot_data,
+                        &values,
+                        index,
+                        (bar_plot, bar_plots),
+                        paths,
+                    );
+                    bar_plot += 1;
+                }
+                PlotKind::Line | PlotKind::Area => {
+                    for (series_index, series) in plot_data.series.iter().enumerate() {
+                        let color = series_color(series, index + series_index);
+                        let at = |category: usize, value: f64| {
+                            Point::new(
+                                axes.band_start(category) + axes.band / 2.0,
+                                axes.value_at(value),
+                            )
+                        };
+                        let points: Vec<Option<Point>> = values[series_index]
+                            .iter()
+                            .enumerate()
+                            .map(|(category, &(base, value))| Some(at(category, base + value?)))
+                            .collect();
+                        if plot_data.kind == PlotKind::Area {
+                            let floor: Vec<Option<Point>> = values[series_index]
+                                .iter()
+                                .enumerate()
+                                .map(|(category, &(base, value))| value.map(|_| at(category, base)))
+                                .collect();
+                            paths.extend(area(&points, &floor, &color));
+                        } else {
+                            paths.extend(line_series(&points, &color, !series.no_line, false));
+                        }
+                    }
+                }
+                PlotKind::Pie | PlotKind::Scatter { .. } => {}
+            }
+            index += plot_data.series.len();
+        }
+    }
+
+    /// Labels of the categories, from the first series that has them, or
+    /// numbers
+    fn categories(&self) -> Vec<String> {
+        let count = self
+            .plots
+            .iter()
+            .flat_map(|plot| &plot.series)
+            .map(|series| series.values.len())
+            .max()
+            .unwrap_or(0);
+        let named = self
+            .plots
+            .iter()
+            .flat_map(|plot| &plot.series)
+            .find(|series| !series.categories.is_empty());
+        (0..count)
+            .map(|index| {
+                named
+                    .and_then(|series| series.categories.get(index).cloned().flatten())
+                    .unwrap_or_else(|| (index + 1).to_string())
+            })
+            .collect()
+    }
+
+    /// Whether values are shown as percentages
+    fn is_percent(&self) -> bool {
+        self.plots
+            .iter()
+            .any(|plot| plot.grouping == Grouping::PercentStacked)
+    }
+
+    /// Lowest and highest value drawn, stacked values included
+    fn value_range(&self) -> (f64, f64) {
+        self.plots
+            .iter()
+            .flat_map(|plot| plot_values(plot).into_iter().flatten())
+            .filter_map(|(base, value)| Some((base, base + value?)))
+            .fold((0.0, 0.0), |(low, high), (base, top)| {
+                (low.min(base).min(top), high.max(base).max(top))
+            })
+    }
+
+    /// Lowest and highest x value of scatter plots
+    fn x_range(&self) -> (f64, f64) {
+        let values: Vec<f64> = self
+            .plots
+            .iter()
+            .flat_map(|plot| &plot.series)
+            .flat_map(|series| series.x_values.iter().flatten().copied())
+            .collect();
+        let low = values.iter().copied().fold(f64::MAX, f64::min).min(0.0);
+        let high = values.iter().copied().fold(f64::MIN, f64::max).max(1.0);
+        (low, high)
+    }
+}
+
+/// Charts of the relationships of type [`CHART_RELATIONSHIP`] in `rels`, by
+/// relationship ID
+///
+/// `dir` is the directory of the part `rels` belong to. Charts that cannot
+/// be read or drawn are left out.
+pub fn read_charts<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dir: &str,
+    rels: &Relationships,
+) -> HashMap<String, Chart> {
+    rels.find_by_type(CHART_RELATIONSHIP)
+        .filter_map(|rel| {
+            let xml = read_part(archive, &utils::resolve_path(dir, &rel.target))?;
+            Some((rel.id.clone(), Chart::from_xml(&xml)?))
+        })
+        .collect()
+}
+
+/// Relationship ID of the chart a `c:chart` element refers to
+#[must_use]
+pub fn chart_id(e: &BytesStart<'_>) -> Option<String> {
+    (e.name().as_ref() == b"c:chart")
+        .then(|| utils::attr_value_opt(e, b"r:id"))
+        .flatten()
+}
+
+/// Width of a default worksheet column, in points
+const COLUMN_WIDTH: f64 = 48.0;
+
+/// Height of a default worksheet row, in points
+const ROW_HEIGHT: f64 = 15.0;
+
+/// Charts anchored in a worksheet drawing (`xdr:wsDr`), as the relationship
+/// ID of each chart with its width and height in points
+///
+/// A chart anchored between two cells is sized as if the columns and rows
+/// in between had the default width and height.
+#[must_use]
+pub fn drawing_charts(xml: &str) -> Vec<(String, f64, f64)> {
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut charts = Vec::new();
+    let mut id = None;
+    let mut extent = None;
+    // Column, column offset, row and row offset of xdr:from and xdr:to
+    let mut corners = [[0.0; 4]; 2];
+    let mut corner = 0;
+    let mut field = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e) | Event::Empty(e)) => match e.name().as_ref() {
+                b"xdr:twoCellAnchor" | b"xdr:oneCellAnchor" | b"xdr:absoluteAnchor" => {
+                    id = None;
+                    extent = None;
+                    corners = [[0.0; 4]; 2];
+                }
+                b"xdr:from" => corner = 0,
+                b"xdr:to" => corner = 1,
+                b"xdr:col" => field = Some(0),
+                b"xdr:colOff" => field = Some(1),
+                b"xdr:row" => field = Some(2),
+                b"xdr:rowOff" => field = Some(3),
+                b"xdr:ext" => {
+                    let emu = |name| {
+                        utils::attr_value_opt(&e, name)
+                            .and_then(|value| value.parse::<f64>().ok())
+                            .map_or(0.0, |value| value / 12_700.0)
+                    };
+                    extent = Some((emu(b"cx"), emu(b"cy")));
+                }
+                b"c:chart" => id = chart_id(&e),
+                _ => {}
+            },
+            Ok(Event::Text(e)) => {
+                if let Some(field) = field.take() {
+                    let text = e.unescape().unwrap_or_default();
+                    corners[corner][field] = text.trim().parse().unwrap_or(0.0);
+                }
+            }
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"xdr:twoCellAnchor" | b"xdr:oneCellAnchor" | b"xdr:absoluteAnchor" => {
+                    let size = extent.or_else(|| {
+                        let [from, to] = corners;
+                        let width = (to[0] - from[0]) * COLUMN_WIDTH + (to[1] - from[1]) / 12_700.0;
+                        let height = (to[2] - from[2]) * ROW_HEIGHT + (to[3] - from[3]) / 12_700.0;
+                        Some((width, height))
+                    });
+                    if let (Some(id), Some((width, height))) = (id.take(), size) {
+                        if width > 0.0 && height > 0.0 {
+                            charts.push((id, width, height));
+                        }
+                    }
+                }
+                _ => field = None,
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    charts
+}
+
+/// Where values and categories fall in the plot area of a chart
+struct Axes {
+    /// Plot area, within the chart
+    plot: Rect,
+    /// Whether values grow to the right, for horizontal bars
+    horizontal: bool,
+    /// Scale of the value axis
+    scale: Scale,
+    /// Width of the band of a category
+    band: f64,
+}
+
+impl Axes {
+    /// Offset of `value` along the value axis, within the plot area
+    fn value_at(&self, value: f64) -> f64 {
+        let length = if self.horizontal {
+            self.plot.width
+        } else {
+            self.plot.height
+        };
+        let offset = (value - self.scale.min) / (self.scale.max - self.scale.min) * length;
+        if self.horizontal {
+            offset
+        } else {
+            self.plot.height - offset
+        }
+    }
+
+    /// Offset of the band of category `category`, the first at the bottom
+    /// for horizontal bars
+    fn band_start(&self, category: usize) -> f64 {
+        if self.horizontal {
+            self.plot.height - position(category + 1) * self.band
+        } else {
+            position(category) * self.band
+        }
+    }
+}
+
+/// A value axis running through nice round numbers
+struct Scale {
+    min: f64,
+    max: f64,
+    step: f64,
+}
+
+impl Scale {
+    /// Scale taking in `low` to `high` in about four steps
+    fn new(low: f64, high: f64) -> Self {
+        let span = high - low;
+        let step = if span > 0.0 { nice(span / 4.0) } else { 1.0 };
+        let min = (low / step).floor() * step;
+        let mut max = (high / step).ceil() * step;
+        if max <= min {
+            max = min + step;
+        }
+        Self { min, max, step }
+    }
+
+    /// Values of the gridlines
+    fn ticks(&self) -> Vec<f64> {
+        (0..=u16::MAX)
+            .map(|tick| self.min + f64::from(tick) * self.step)
+            .take_while(|tick| *tick <= self.max + self.step / 2.0)
+            .collect()
+    }
+}
+
+/// `value` rounded up to 1, 2 or 5 times a power of ten
+fn nice(value: f64) -> f64 {
+    let power = 10_f64.powf(value.log10().floor());
+    let fraction = value / power;
+    let nice = if fraction <= 1.0 {
+        1.0
+    } else if fraction <= 2.0 {
+        2.0
+    } else if fraction <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * power
+}
+
+/// Base and height of every point of every series of `plot`: stacked
+/// series start on top of the previous ones, and 100% stacked values are
+/// percentages of their category
+fn plot_values(plot: &Plot) -> Vec<Vec<(f64, Option<f64>)>> {
+    let count = plot
+        .series
+        .iter()
+        .map(|series| series.values.len())
+        .max()
+        .unwrap_or(0);
+    let totals: Vec<f64> = (0..count)
+        .map(|point| {
+            plot.series
+                .iter()
+                .filter_map(|series| series.values.get(point).copied().flatten())
+                .map(f64::abs)
+                .sum()
+        })
+        .collect();
+    let mut positive = vec![0.0; count];
+    let mut negative = vec![0.0; count];
+    plot.series
+        .iter()
+        .map(|series| {
+            (0..count)
+                .map(|point| {
+                    let Some(value) = series.values.get(point).copied().flatten() else {
+                        return (0.0, None);
+                    };
+                    match plot.grouping {
+                        Grouping::Standard => (0.0, Some(value)),
+                        Grouping::Stacked | Grouping::PercentStacked => {
+                            let value = if plot.grouping == Grouping::PercentStacked {
+                                if totals[point] > 0.0 {
+                                    value / totals[point] * 100.0
+                                } else {
+                                    0.0
+                                }
+                            } else {
+                                value
+                            };
+                            let stack = if value < 0.0 {
+                                &mut negative[point]
+                            } else {
+                                &mut positive[point]
+                            };
+                            let base = *stack;
+                            *stack += value;
+                            (base, Some(value))
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Kind of a plot element such as `c:barChart`
+fn plot_kind(name: &[u8]) -> Option<PlotKind> {
+    Some(match name {
+        b"c:barChart" | b"c:bar3DChart" => PlotKind::Bar { horizontal: false },
+        b"c:lineChart" | b"c:line3DChart" | b"c:stockChart" => PlotKind::Line,
+        b"c:areaChart" | b"c:area3DChart" => PlotKind::Area,
+        b"c:pieChart" | b"c:pie3DChart" | b"c:doughnutChart" | b"c:ofPieChart" => PlotKind::Pie,
+        b"c:scatterChart" => PlotKind::Scatter { lines: true },
+        _ => return None,
+    })
+}
+
+/// Whether `stack` is within the `c:chart` title, not an axis title
+fn in_chart_title(stack: &[Vec<u8>]) -> bool {
+    stack
+        .windows(2)
+        .any(|pair| pair[0] == b"c:chart" && pair[1] == b"c:title")
+}
+
+/// Whether `stack` is within the shape properties of a series or of one of
+/// its points, rather than of its markers or labels
+fn in_shape_properties(stack: &[Vec<u8>]) -> bool {
+    stack
+        .windows(2)
+        .any(|pair| (pair[0] == b"c:ser" || pair[0] == b"c:dPt") && pair[1] == b"c:spPr")
+}
+
+/// `index` as a coordinate
+fn position(index: usize) -> f64 {
+    f64::from(u32::try_from(index).unwrap_or(u32::MAX))
+}
+
+/// Set `values[index]`, growing `values` as needed
+fn set<T: Clone>(values: &mut Vec<Option<T>>, index: usize, value: Option<T>) {
+    if values.len() <= index {
+        values.resize(index + 1, None);
+    }
+    values[index] = value;
+}
+
+/// Color of series `index`, counted across the plots of a chart
+fn series_color(series: &ChartSeries, index: usize) -> String {
+    series
+        .color
+        .clone()
+        .unwrap_or_else(|| PALETTE[index % PALETTE.len()].to_string())
+}
+
+/// Color of slice `index` of a pie
+fn point_color(series: &ChartSeries, index: usize) -> String {
+    series
+        .point_colors
+        .get(&index)
+        .cloned()
+        .unwrap_or_else(|| PALETTE[index % PALETTE.len()].to_string())
+}
+
+/// `value` as an axis label
+fn format_value(value: f64, percent: bool) -> String {
+    let mut text = format!("{value:.2}");
+    while text.ends_with('0') {
+        text.pop();
+    }
+    if text.ends_with('.') {
+        text.pop();
+    }
+    if text == "-0" {
+        text = "0".to_string();
+    }
+    if percent {
+        text.push('%');
+    }
+    text
+}
+
+/// A line of text in `bounds`
+fn label(text: &str, bounds: Rect, size: f64, color: &str) -> ContentBlock {
+    let mut block = TextBlock::new(bounds);
+    block.add_run(TextRun::with_style(
+        text,
+        TextStyle {
+            font_size: Some(size),
+            color: Some(color.into()),
+            ..TextStyle::default()
+        },
+    ));
+    ContentBlock::Text(block)
+}
+
+/// The legend: a colored square before every name
+fn legend_block(entries: &[(String, String)], bounds: Rect) -> ContentBlock {
+    let size = (bounds.height * 0.6).min(9.0);
+    let mut block = TextBlock::new(bounds);
+    for (index, (name, color)) in entries.iter().enumerate() {
+        let style = |color: &str| TextStyle {
+            font_size: Some(size),
+            color: Some(color.into()),
+            ..TextStyle::default()
+        };
+        let gap = if index == 0 { "" } else { "    " };
+        block.add_run(TextRun::with_style(format!("{gap}\u{25A0} "), style(color)));
+        block.add_run(TextRun::with_style(name.as_str(), style("#595959")));
+    }
+    ContentBlock::Text(block)
+}
+
+/// Draw the bars of a bar plot with `values` as [`plot_values`] gives them
+///
+/// `index` is the index of the plot's first series in the chart, for the
+/// default colors; `slots` is the index of the plot among the bar plots and
+/// their count, which share each category band.
+fn bars(
+    axes: &Axes,
+    plot: &Plot,
+    values: &[Vec<(f64, Option<f64>)>],
+    index: usize,
+    (bar_plot, bar_plots): (usize, usize),
+    paths: &mut Vec<VectorPath>,
+) {
+    let stacked = plot.grouping != Grouping::Standard;
+    let slots = if stacked { 1 } else { plot.series.len() } * bar_plots;
+    let thickness = axes.band * 0.7 / position(slots);
+    for (series_index, series) in plot.series.iter().enumerate() {
+        let color = series_color(series, index + series_index);
+        let slot = bar_plot * slots / bar_plots + if stacked { 0 } else { series_index };
+        for (category, &(base, value)) in values[series_index].iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let start = axes.band_start(category) + axes.band * 0.15 + thickness * position(slot);
+            let (from, to) = (axes.value_at(base), axes.value_at(base + value));
+            let rect = if axes.horizontal {
+                Rect::new(from.min(to), start, (to - from).abs(), thickness)
+            } else {
+                Rect::new(start, from.min(to), thickness, (to - from).abs())
+            };
+            let color = series
+                .point_colors
+                .get(&category)
+                .cloned()
+                .unwrap_or_else(|| color.clone());
+            paths.push(rectangle(rect, &color));
+        }
+    }
+}
+
+/// A filled rectangle
+fn rectangle(rect: Rect, color: &str) -> VectorPath {
+    VectorPath {
+        commands: vec![
+            PathCommand::MoveTo(Point::new(rect.x, rect.y)),
+            PathCommand::LineTo(Point::new(rect.x + rect.width, rect.y)),
+            PathCommand::LineTo(Point::new(rect.x + rect.width, rect.y + rect.height)),
+            PathCommand::LineTo(Point::new(rect.x, rect.y + rect.height)),
+            PathCommand::Close,
+        ],
+        fill: Some(color.to_string()),
+        stroke: None,
+        stroke_width: None,
+        fill_style: None,
+        start_arrow: None,
+        end_arrow: None,
+    }
+}
+
+/// An open line through `points`
+fn stroke(points: Vec<Point>, color: &str, width: f64) -> VectorPath {
+    let commands = points
+        .into_iter()
+        .enumerate()
+        .map(|(index, point)| {
+            if index == 0 {
+                PathCommand::MoveTo(point)
+            } else {
+                PathCommand::LineTo(point)
+            }
+        })
+        .collect();
+    VectorPath {
+        commands,
+        fill: None,
+        stroke: Some(color.to_string()),
+        stroke_width: Some(width),
+        fill_style: None,
+        start_arrow: None,
+        end_arrow: None,
+    }
+}
+
+/// Lines joining `points`, broken at gaps, and optionally a marker on
+/// every point
+fn line_series(
+    points: &[Option<Point>],
+    color: &str,
+    lines: bool,
+    markers: bool,
+) -> Vec<VectorPath> {
+    let mut paths = Vec::new();
+    if lines {
+        for run in points.split(Option::is_none) {
+            let run: Vec<Point> = run.iter().flatten().copied().collect();
+            if run.len() > 1 {
+                paths.push(stroke(run, color, 2.0));
+            }
+        }
+    }
+    if markers || !lines {
+        for point in points.iter().flatten() {
+            paths.push(VectorPath {
+                commands: geometry::ellipse(point.x, point.y, 2.5, 2.5),
+                fill: Some(color.to_string()),
+                stroke: None,
+                stroke_width: None,
+                fill_style: None,
+                start_arrow: None,
+                end_arrow: None,
+            });
+        }
+    }
+    paths
+}
+
+/// Areas between `points` and `floor`, broken at gaps
+fn area(points: &[Option<Point>], floor: &[Option<Point>], color: &str) -> Vec<VectorPath> {
+    let mut paths = Vec::new();
+    let mut run = Vec::new();
+    for (point, base) in points.iter().zip(floor).chain([(&None, &None)]) {
+        if let (Some(point), Some(base)) = (point, base) {
+            run.push((*point, *base));
+            continue;
+        }
+        if run.len() > 1 {
+            let mut commands: Vec<PathCommand> = run
+                .iter()
+                .enumerate()
+                .map(|(index, (point, _))| {
+                    if index == 0 {
+                        PathCommand::MoveTo(*point)
+                    } else {
+                        PathCommand::LineTo(*point)
+                    }
+                })
+                .collect();
+            commands.extend(run.iter().rev().map(|(_, base)| PathCommand::LineTo(*base)));
+            commands.push(PathCommand::Close);
+            paths.push(VectorPath {
+                commands,
+                fill: Some(color.to_string()),
+                stroke: None,
+                stroke_width: None,
+                fill_style: None,
+                start_arrow: None,
+                end_arrow: None,
+            });
+        }
+        run.clear();
+    }
+    paths
+}
+
+/// Slices of the first series of a pie, clockwise from the top, in the
+/// middle of `plot`
+fn pie(series: &ChartSeries, plot: Rect) -> VectorBlock {
+    let radius = plot.width.min(plot.height) / 2.0;
+    let center = Point::new(plot.width / 2.0, plot.height / 2.0);
+    let total: f64 = series
+        .values
+        .iter()
+        .flatten()
+        .filter(|value| **value > 0.0)
+        .sum();
+    let mut paths = Vec::new();
+    let mut angle = -PI / 2.0;
+    for (index, value) in series.values.iter().enumerate() {
+        let Some(value) = value.filter(|value| *value > 0.0 && total > 0.0) else {
+            continue;
+        };
+        let sweep = value / total * 2.0 * PI;
+        let mut commands = vec![
+            PathCommand::MoveTo(center),
+            PathCommand::LineTo(Point::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+            )),
+        ];
+        commands.extend(arc(center, radius, angle, sweep));
+        commands.push(PathCommand::Close);
+        paths.push(VectorPath {
+            commands,
+            fill: Some(point_color(series, index)),
+            stroke: Some("#FFFFFF".to_string()),
+            stroke_width: Some(1.0),
+            fill_style: None,
+            start_arrow: None,
+            end_arrow: None,
+        });
+        angle += sweep;
+    }
+    VectorBlock {
+        bounds: plot,
+        paths,
+    }
+}
+
+/// Cubic Bézier curves along a circular arc of `sweep` radians from `start`
+fn arc(center: Point, radius: f64, start: f64, sweep: f64) -> Vec<PathCommand> {
+    // At most a quarter circle per curve
+    let segments = (1..4)
+        .find(|segments| sweep <= f64::from(*segments) * PI / 2.0 + 1e-9)
+        .unwrap_or(4);
+    let step = sweep / f64::from(segments);
+    let handle = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+    let at = |angle: f64| (angle.cos(), angle.sin());
+    (0..segments)
+        .map(|segment| {
+            let from = start + step * f64::from(segment);
+            let to = from + step;
+            let ((cos1, sin1), (cos2, sin2)) = (at(from), at(to));
+            PathCommand::CurveTo {
+                cp1: Point::new(
+                    center.x + radius * cos1 - handle * sin1,
+                    center.y + radius * sin1 + handle * cos1,
+                ),
+                cp2: Point::new(
+                    center.x + radius * cos2 + handle * sin2,
+                    center.y + radius * sin2 - handle * cos2,
+                ),
+                end: Point::new(center.x + radius * cos2, center.y + radius * sin2),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BAR_CHART: &str = r#"<c:chartSpace xmlns:c="c" xmlns:a="a"><c:chart>
+        <c:title><c:tx><c:rich><a:p><a:r><a:t>Revenue</a:t></a:r></a:p></c:rich></c:tx></c:title>
+        <c:autoTitleDeleted val="0"/><c:plotArea><c:layout/>
+        <c:barChart><c:barDir val="col"/><c:grouping val="clustered"/>
+          <c:ser><c:idx val="0"/><c:tx><c:strRef><c:strCache><c:pt idx="0"><c:v>2023</c:v></c:pt></c:strCache></c:strRef></c:tx>
+            <c:spPr><a:solidFill><a:srgbClr val="1F3864"/></a:solidFill><a:ln><a:solidFill><a:srgbClr val="FFFFFF"/></a:solidFill></a:ln></c:spPr>
+            <c:cat><c:strRef><c:strCache><c:ptCount val="3"/>
+              <c:pt idx="0"><c:v>Q1</c:v></c:pt><c:pt idx="1"><c:v>Q2</c:v></c:pt><c:pt idx="2"><c:v>Q3</c:v></c:pt>
+            </c:strCache></c:strRef></c:cat>
+            <c:val><c:numRef><c:numCache><c:formatCode>General</c:formatCode>
+              <c:pt idx="0"><c:v>4.3</c:v></c:pt><c:pt idx="2"><c:v>3.5</c:v></c:pt>
+            </c:numCache></c:numRef></c:val></c:ser>
+          <c:ser><c:idx val="1"/><c:tx><c:v>2024</c:v></c:tx>
+            <c:val><c:numLit><c:pt idx="0"><c:v>2.4</c:v></c:pt><c:pt idx="1"><c:v>-1</c:v></c:pt><c:pt idx="2"><c:v>1.8</c:v></c:pt></c:numLit></c:val></c:ser>
+        </c:barChart>
+        <c:catAx><c:title><c:tx><c:rich><a:p><a:r><a:t>Quarter</a:t></a:r></a:p></c:rich></c:tx></c:title></c:catAx>
+        </c:plotArea><c:legend><c:legendPos val="b"/></c:legend></c:chart></c:chartSpace>"#;
+
+    #[test]
+    fn test_parse_chart() {
+        let chart = Chart::from_xml(BAR_CHART).unwrap();
+        assert_eq!(chart.title.as_deref(), Some("Revenue"));
+        assert!(chart.legend);
+        let plot = &chart.plots[0];
+        assert_eq!(plot.kind, PlotKind::Bar { horizontal: false });
+        let first = &plot.series[0];
+        assert_eq!(first.name.as_deref(), Some("2023"));
+        assert_eq!(first.color.as_deref(), Some("#1F3864"));
+        assert_eq!(first.values, [Some(4.3), None, Some(3.5)]);
+        assert_eq!(first.categories[1].as_deref(), Some("Q2"));
+        assert_eq!(plot.series[1].name.as_deref(), Some("2024"));
+        assert_eq!(plot.series[1].color, None);
+
+        let pie = Chart::from_xml(
+            r#"<c:chartSpace xmlns:c="c" xmlns:a="a"><c:chart><c:plotArea><c:doughnutChart>
+            <c:ser><c:tx><c:v>Share</c:v></c:tx>
+            <c:dPt><c:idx val="1"/><c:spPr><a:solidFill><a:srgbClr val="C00000"/></a:solidFill></c:spPr></c:dPt>
+            <c:val><c:numLit><c:pt idx="0"><c:v>3</c:v></c:pt><c:pt idx="1"><c:v>1</c:v></c:pt></c:numLit></c:val>
+            </c:ser></c:doughnutChart></c:plotArea></c:chart></c:chartSpace>"#,
+        )
+        .unwrap();
+        // Titled after its only series
+        assert_eq!(pie.title.as_deref(), Some("Share"));
+        assert_eq!(pie.plots[0].kind, PlotKind::Pie);
+        assert_eq!(pie.plots[0].series[0].point_colors[&1], "#C00000");
+
+        assert!(
+            Chart::from_xml("<c:chartSpace><c:chart><c:plotArea/></c:chart></c:chartSpace>")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_render_chart() {
+        let chart = Chart::from_xml(BAR_CHART).unwrap();
+        let container = chart.render(Rect::new(0.0, 0.0, 400.0, 300.0));
+        assert_eq!(container.container_type.as_deref(), Some("chart"));
+        let text: Vec<String> = container
+            .children
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.extract_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text[0], "Revenue");
+        assert!(text[1].contains("2023") && text[1].contains("2024"));
+        // Values from -1 to 4.3, in steps of 2 rounded out to -2 and 6
+        assert!(["-2", "0", "2", "4", "6"]
+            .iter()
+            .all(|tick| text.iter().any(|text| text == tick)));
+        assert!(text.iter().any(|text| text == "Q3"));
+
+        let ContentBlock::Vector(plot) = &container.children[2] else {
+            panic!("expected the plot, got {:?}", container.children);
+        };
+        let bars: Vec<_> = plot
+            .paths
+            .iter()
+            .filter(|path| path.fill.is_some())
+            .collect();
+        // One gap in the first series
+        assert_eq!(bars.len(), 5);
+        assert_eq!(bars[0].fill.as_deref(), Some("#1F3864"));
+        assert_eq!(bars[2].fill.as_deref(), Some(PALETTE[1]));
+
+        let pie = Chart {
+            title: None,
+            plots: vec![Plot {
+                kind: PlotKind::Pie,
+                grouping: Grouping::Standard,
+                series: vec![ChartSeries {
+                    values: vec![Some(1.0), Some(1.0), Some(2.0)],
+                    ..ChartSeries::default()
+                }],
+            }],
+            legend: false,
+        };
+        let container = pie.render(Rect::new(0.0, 0.0, 200.0, 200.0));
+        let ContentBlock::Vector(slices) = &container.children[0] else {
+            panic!("expected the slices, got {:?}", container.children);
+        };
+        assert_eq!(slices.paths.len(), 3);
+        // The last slice is half the pie, drawn in two quarter curves
+        assert_eq!(slices.paths[2].commands.len(), 5);
+    }
+
+    #[test]
+    fn test_nice_scale() {
+        let scale = Scale::new(0.0, 87.0);
+        assert_eq!((scale.min, scale.max, scale.step), (0.0, 100.0, 50.0));
+        assert_eq!(scale.ticks(), [0.0, 50.0, 100.0]);
+        assert_eq!(format_value(0.5, true), "0.5%");
+        assert_eq!(format_value(2.0, false), "2");
+    }
+
+    #[test]
+    fn test_drawing_charts() {
+        let xml = r#"<xdr:wsDr><xdr:twoCellAnchor>
+            <xdr:from><xdr:col>1</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>2</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:from>
+            <xdr:to><xdr:col>7</xdr:col><xdr:colOff>127000</xdr:colOff><xdr:row>16</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:to>
+            <xdr:graphicFrame><a:graphic><a:graphicData><c:chart r:id="rId1"/></a:graphicData></a:graphic></xdr:graphicFrame>
+          </xdr:twoCellAnchor>
+          <xdr:oneCellAnchor><xdr:from><xdr:col>0</xdr:col></xdr:from><xdr:ext cx="2540000" cy="1270000"/>
+            <xdr:graphicFrame><a:graphic><a:graphicData><c:chart r:id="rId2"/></a:graphicData></a:graphic></xdr:graphicFrame>
+          </xdr:oneCellAnchor>
+          <xdr:twoCellAnchor><xdr:from><xdr:col>0</xdr:col></xdr:from><xdr:to><xdr:col>2</xdr:col></xdr:to><xdr:pic/></xdr:twoCellAnchor></xdr:wsDr>"#;
+        assert_eq!(
+            drawing_charts(xml),
+            [
+                ("rId1".to_string(), 298.0, 210.0),
+                ("rId2".to_string(), 200.0, 100.0)
+            ]
+        );
+    }
+}
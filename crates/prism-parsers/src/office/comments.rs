@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Comments of Office Open XML documents
+//!
+//! Each format keeps its comments in parts of its own:
+//!
+//! - DOCX: `word/comments.xml`, one `w:comment` per comment, anchored to a
+//!   range of the body between `w:commentRangeStart` and
+//!   `w:commentRangeEnd`
+//! - PPTX: a comments part per slide, with authors listed in
+//!   `ppt/commentAuthors.xml`, or in `ppt/authors.xml` for the modern
+//!   comments of Microsoft 365
+//! - XLSX: a comments part per sheet, naming the cell of each comment
+//!
+//! Comments become [`Revision`]s of kind [`RevisionKind::Comment`].
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use prism_core::document::{Revision, RevisionKind};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// Comments of `word/comments.xml`, in order
+#[must_use]
+pub fn docx_comments(xml: &str) -> Vec<Revision> {
+    let mut comments = Vec::new();
+    read_comments(
+        xml,
+        b"comment",
+        |e| {
+            let mut comment = Revision::new(
+                attribute(e, b"id").unwrap_or_default(),
+                RevisionKind::Comment,
+                String::new(),
+            );
+            comment.author = attribute(e, b"author");
+            comment.date = attribute(e, b"date").as_deref().and_then(parse_date);
+            comment
+        },
+        &mut comments,
+    );
+    comments
+}
+
+/// Comments of a slide's comments part, with author names looked up in
+/// `authors`
+#[must_use]
+pub fn pptx_comments<S: BuildHasher>(
+    xml: &str,
+    authors: &HashMap<String, String, S>,
+    slide: u32,
+) -> Vec<Revision> {
+    let mut comments = Vec::new();
+    read_comments(
+        xml,
+        b"cm",
+        |e| {
+            let id = attribute(e, b"idx")
+                .or_else(|| attribute(e, b"id"))
+                .unwrap_or_default();
+            let mut comment = Revision::new(
+                format!("{slide}-{id}"),
+                RevisionKind::Comment,
+                String::new(),
+            );
+            comment.author = attribute(e, b"authorId").and_then(|id| authors.get(&id).cloned());
+            comment.date = attribute(e, b"dt")
+                .or_else(|| attribute(e, b"created"))
+                .as_deref()
+                .and_then(parse_date);
+            comment.page = Some(slide);
+            comment
+        },
+        &mut comments,
+    );
+    comments
+}
+
+/// Author names by ID, from `ppt/commentAuthors.xml` or `ppt/authors.xml`
+#[must_use]
+pub fn pptx_authors(xml: &str) -> HashMap<String, String> {
+    let mut authors = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e))
+                if matches!(e.local_name().as_ref(), b"cmAuthor" | b"author") =>
+            {
+                if let (Some(id), Some(name)) = (attribute(&e, b"id"), attribute(&e, b"name")) {
+                    authors.insert(id, name);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    authors
+}
+
+/// Comments of a sheet's comments part, on page `sheet`
+#[must_use]
+pub fn xlsx_comments(xml: &str, sheet: u32) -> Vec<Revision> {
+    // Authors are listed before the comments that refer to them by index
+    let mut authors = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    let mut in_author = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"author" => {
+                in_author = true;
+                authors.push(String::new());
+            }
+            Ok(Event::Text(text)) if in_author => {
+                if let (Some(author), Ok(text)) = (authors.last_mut(), text.unescape()) {
+                    author.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"author" => in_author = false,
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"commentList" => break,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    let mut comments = Vec::new();
+    read_comments(
+        xml,
+        b"comment",
+        |e| {
+            let cell = attribute(e, b"ref").unwrap_or_default();
+            let mut comment = Revision::new(
+                format!("{sheet}-{cell}"),
+                RevisionKind::Comment,
+                String::new(),
+            );
+            comment.author = attribute(e, b"authorId")
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|id| authors.get(id).cloned());
+            comment.page = Some(sheet);
+            comment.cell = Some(cell);
+            comment
+        },
+        &mut comments,
+    );
+    comments
+}
+
+/// Read every `element` of a comments part, starting each comment with
+/// `start` and collecting the text within it, one line per paragraph
+fn read_comments(
+    xml: &str,
+    element: &[u8],
+    mut start: impl FnMut(&BytesStart<'_>) -> Revision,
+    comments: &mut Vec<Revision>,
+) {
+    let mut reader = Reader::from_str(xml);
+    let mut comment: Option<Revision> = None;
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == element => {
+                comment = Some(start(&e));
+            }
+            Ok(Event::Empty(e)) if e.local_name().as_ref() == element => {
+                comments.push(start(&e));
+            }
+            Ok(Event::Start(e)) if comment.is_some() => {
+                // Text is in w:t, a:t and t elements, except for legacy
+                // slide comments, whose p:text holds it directly
+                let name = e.local_name();
+                in_text = matches!(name.as_ref(), b"t" | b"text");
+                if name.as_ref() == b"p" {
+                    if let Some(comment) = comment.as_mut().filter(|c| !c.text.is_empty()) {
+                        comment.text.push('\n');
+                    }
+                }
+            }
+            Ok(Event::Text(text)) if in_text => {
+                if let (Some(comment), Ok(text)) = (comment.as_mut(), text.unescape()) {
+                    comment.text.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"t" | b"text" => in_text = false,
+                name if name == element => {
+                    if let Some(mut comment) = comment.take() {
+                        comment.text = comment.text.trim_end().to_string();
+                        comments.push(comment);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+}
+
+/// Unescaped value of the attribute with local name `name`
+fn attribute(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == name)
+        .map(|attr| {
+            let value = String::from_utf8_lossy(&attr.value);
+            unescape(&value).map_or_else(|_| value.to_string(), Cow::into_owned)
+        })
+}
+
+/// A comment or revision date: RFC 3339, or without a zone, which Office
+/// writes for UTC
+#[must_use]
+pub fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|date| date.and_utc())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pptx_and_xlsx_comments() {
+        let authors = pptx_authors(
+            r#"<p:cmAuthorLst xmlns:p="p"><p:cmAuthor id="1" name="Ana &amp; Co" initials="A"/></p:cmAuthorLst>"#,
+        );
+        let comments = pptx_comments(
+            r#"<p:cmLst xmlns:p="p"><p:cm authorId="1" dt="2024-03-01T09:30:00.123" idx="4"><p:pos x="10" y="10"/><p:text>Check this figure</p:text></p:cm></p:cmLst>"#,
+            &authors,
+            2,
+        );
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, "2-4");
+        assert_eq!(comments[0].author.as_deref(), Some("Ana & Co"));
+        assert_eq!(comments[0].text, "Check this figure");
+        assert_eq!(comments[0].page, Some(2));
+        assert_eq!(
+            comments[0].date.map(|date| date.to_rfc3339()),
+            Some("2024-03-01T09:30:00.123+00:00".to_string())
+        );
+
+        let comments = xlsx_comments(
+            r#"<comments><authors><author>Bo</author></authors><commentList><comment ref="B2" authorId="0"><text><r><t>Too </t></r><r><t>high</t></r></text></comment></commentList></comments>"#,
+            1,
+        );
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author.as_deref(), Some("Bo"));
+        assert_eq!(comments[0].cell.as_deref(), Some("B2"));
+        assert_eq!(comments[0].text, "Too high");
+    }
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! DOCX content controls (`w:sdt`)
+//!
+//! Content controls wrap document content in a structured region. Those
+//! declaring a form type in their properties (`w:sdtPr`) become form fields:
+//! plain text, checkboxes (`w14:checkbox`), dropdowns and combo boxes, and
+//! date pickers. Rich text controls and the building-block wrappers Word
+//! uses for cover pages and tables of contents are left as content.
+
+use prism_core::document::{FormFieldBlock, FormFieldType, Rect};
+use quick_xml::events::BytesStart;
+
+use crate::office::utils;
+
+/// A content control being read
+#[derive(Debug)]
+pub struct ContentControl {
+    /// Nesting depth of the control's `w:sdt`
+    pub depth: usize,
+    field_type: Option<FormFieldType>,
+    name: String,
+    label: Option<String>,
+    checked: bool,
+    options: Vec<String>,
+    read_only: bool,
+    /// Whether the content is the placeholder text, not a value
+    placeholder: bool,
+    text: String,
+}
+
+impl ContentControl {
+    /// Start reading a control opened at `depth`
+    #[must_use]
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            field_type: None,
+            name: String::new(),
+            label: None,
+            checked: false,
+            options: Vec::new(),
+            read_only: false,
+            placeholder: false,
+            text: String::new(),
+        }
+    }
+
+    /// Whether the control is a form field, so its content is the value
+    #[must_use]
+    pub fn is_field(&self) -> bool {
+        self.field_type.is_some()
+    }
+
+    /// Apply an element of `w:sdtPr`
+    pub fn property(&mut self, e: &BytesStart<'_>) {
+        let value = || utils::attr_value_opt(e, b"w:val");
+        match e.name().as_ref() {
+            b"w:alias" => self.label = value().filter(|label| !label.is_empty()),
+            b"w:tag" => self.name = value().unwrap_or_default(),
+            b"w:text" => self.field_type = Some(FormFieldType::Text),
+            b"w14:checkbox" => self.field_type = Some(FormFieldType::Checkbox),
+            b"w14:checked" => {
+                self.checked = matches!(
+                    utils::attr_value_opt(e, b"w14:val").as_deref(),
+                    Some("1" | "true")
+                );
+            }
+            b"w:dropDownList" | b"w:comboBox" => {
+                self.field_type = Some(FormFieldType::Dropdown);
+            }
+            b"w:listItem" => {
+                if let Some(option) = utils::attr_value_opt(e, b"w:displayText")
+                    .or_else(|| utils::attr_value_opt(e, b"w:value"))
+                {
+                    self.options.push(option);
+                }
+            }
+            b"w:date" => self.field_type = Some(FormFieldType::Date),
+            b"w:showingPlcHdr" => self.placeholder = utils::is_on(e),
+            b"w:lock" => {
+                self.read_only = matches!(
+                    value().as_deref(),
+                    Some("contentLocked" | "sdtContentLocked")
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Add text of the control's content
+    pub fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// End a paragraph of the control's content
+    pub fn end_paragraph(&mut self) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+    }
+
+    /// The form field, if the control is one
+    ///
+    /// Content controls are laid out with the text around them, so the
+    /// field has no bounds of its own.
+    #[must_use]
+    pub fn into_field(self) -> Option<FormFieldBlock> {
+        let field_type = self.field_type?;
+        let mut field = FormFieldBlock::new(field_type, self.name, Rect::default());
+        field.label = self.label;
+        field.checked = self.checked;
+        field.options = self.options;
+        field.read_only = self.read_only;
+        // A checkbox's content is just the box glyph
+        let text = self.text.trim_end_matches('\n');
+        if field_type != FormFieldType::Checkbox && !self.placeholder && !text.is_empty() {
+            field.value = Some(text.to_string());
+        }
+        Some(field)
+    }
+}
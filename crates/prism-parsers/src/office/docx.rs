@@ -2,28 +2,776 @@
 //! DOCX (Microsoft Word) parser
 //!
 //! Parses DOCX files into the Unified Document Model with high fidelity.
+//!
+//! Consecutive numbered and bulleted paragraphs become list blocks, with
+//! markers taken from `word/numbering.xml`.
+//! Runs inside `w:hyperlink` link to its external target or bookmark.
+//! Text, checkbox, dropdown and date content controls become form fields.
+//! Paragraphs in the built-in heading styles start chapter sections.
+//! Tracked insertions and deletions (`w:ins`, `w:del` and moves) are
+//! applied as [`RevisionMode`] asks and, like the comments of
+//! `word/comments.xml` with the text they are anchored to, listed in
+//! [`Document::revisions`].
+//! Opening the package and parsing the body are timed into
+//! [`Document::timings`].
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, PageMetadata, Rect, TextBlock, TextRun, TextStyle,
+        ContentBlock, Dimensions, Document, FormFieldBlock, Link, ListBlock, Page, PageMetadata,
+        Rect, Revision, RevisionKind, RevisionMark, Section, TextBlock, TextDirection, TextRun,
+        TextStyle,
     },
-    error::{Error, Result},
+    error::{Error, ErrorLocation, Result},
     format::Format,
     metadata::Metadata,
-    parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+    parser::{ParseContext, Parser, ParserFeature, ParserMetadata, RevisionMode},
+    timing::StageTimer,
 };
-use quick_xml::events::Event;
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io::Cursor;
-use tracing::{debug, warn};
+use std::ops::Range;
+use tracing::debug;
 use zip::ZipArchive;
 
+use crate::encryption;
+use crate::office::charts::{self, Chart};
+use crate::office::comments;
+use crate::office::controls::ContentControl;
+use crate::office::embeddings;
+use crate::office::fonts;
+use crate::office::numbering::{ListCounters, Numbering};
+use crate::office::package;
 use crate::office::relationships::Relationships;
-use crate::office::styles::Styles;
+use crate::office::sheets::read_part;
+use crate::office::styles::{self, Styles};
 use crate::office::tables;
+use crate::office::theme::{parse_theme, Theme};
 use crate::office::utils;
+use crate::security;
+use crate::signatures;
+
+/// Languages a run declares with `w:lang`, one per script class
+#[derive(Debug, Clone, Default)]
+struct RunLanguages {
+    latin: Option<String>,
+    east_asian: Option<String>,
+    complex: Option<String>,
+}
+
+impl RunLanguages {
+    fn from_element(e: &BytesStart<'_>) -> Self {
+        Self {
+            latin: utils::attr_value_opt(e, b"w:val"),
+            east_asian: utils::attr_value_opt(e, b"w:eastAsia"),
+            complex: utils::attr_value_opt(e, b"w:bidi"),
+        }
+    }
+
+    /// Pick the language that applies to `text`, the way Word does: the
+    /// complex-script language for right-to-left runs, the East Asian one
+    /// for CJK text, otherwise the default
+    fn for_text(&self, text: &str, rtl: bool) -> Option<String> {
+        let complex = rtl || text.chars().any(is_rtl_char);
+        let language = if complex && self.complex.is_some() {
+            &self.complex
+        } else if text.chars().any(is_cjk_char) && self.east_asian.is_some() {
+            &self.east_asian
+        } else {
+            &self.latin
+        };
+        language.clone()
+    }
+}
+
+/// Hebrew, Arabic, Syriac, Thaana and their presentation forms
+fn is_rtl_char(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFC}')
+}
+
+/// Han, kana, Hangul and full-width forms
+fn is_cjk_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{11FF}'
+            | '\u{3000}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF00}'..='\u{FFEF}'
+    )
+}
+
+/// Body content in document order, as read from a chunk
+enum BodyItem {
+    /// The start of a paragraph, counted for pagination
+    ParagraphStart,
+    /// A paragraph with text
+    Paragraph(ContentBlock),
+    /// A numbered or bulleted paragraph with text
+    ListParagraph {
+        block: ContentBlock,
+        num_id: String,
+        level: u8,
+    },
+    /// A table
+    Table(ContentBlock),
+    /// A tracked insertion or deletion, once its text is read
+    Revision(Revision),
+    /// Text of the body a comment is anchored to; a range split between
+    /// chunks comes in pieces
+    CommentAnchor { id: String, text: String },
+    /// A recoverable error
+    Error(Error),
+}
+
+/// Body chunks are at least this long, so that small documents are parsed
+/// in one piece
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Relationship type of the theme part, usually `word/theme/theme1.xml`
+const THEME_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme";
+
+/// Byte ranges of `word/document.xml` to parse independently
+///
+/// The body is split between its top-level elements, so every chunk but
+/// the first, which also holds the start of the document, is balanced. A
+/// document that is small or cannot be split is returned as one chunk.
+fn body_chunks(xml: &str) -> Vec<Range<usize>> {
+    let mut reader = Reader::from_str(xml);
+    let mut depth = 0;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let position = reader.buffer_position();
+        match reader.read_event() {
+            Ok(Event::Start(_)) => {
+                if depth == 2 && position - start >= CHUNK_BYTES {
+                    chunks.push(start..position);
+                    start = position;
+                }
+                depth += 1;
+            }
+            // The end of the body closes the last chunk
+            Ok(Event::End(_)) if depth == 2 => {
+                if chunks.is_empty() {
+                    break;
+                }
+                chunks.push(start..position);
+                return chunks;
+            }
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    chunks.clear();
+    chunks.push(0..xml.len());
+    chunks
+}
+
+/// Paragraph and run state while reading a chunk of the body
+#[allow(clippy::struct_excessive_bools)]
+struct ChunkReader<'a> {
+    styles: &'a Styles,
+    relationships: &'a Relationships,
+    /// Charts, by relationship ID
+    charts: &'a HashMap<String, Chart>,
+    revision_mode: RevisionMode,
+    items: Vec<BodyItem>,
+
+    // State for paragraph parsing
+    in_paragraph: bool,
+    paragraph_runs: Vec<TextRun>,
+    paragraph_style: Option<String>,
+    paragraph_direction: TextDirection,
+    in_paragraph_props: bool,
+    /// Numbering instance and level of the paragraph (`w:numPr`)
+    paragraph_num_id: Option<String>,
+    paragraph_level: u8,
+    /// Target of the open `w:hyperlink`
+    hyperlink: Option<Link>,
+    /// Form fields of content controls within the paragraph
+    paragraph_fields: Vec<FormFieldBlock>,
+    /// Charts drawn in the paragraph
+    paragraph_charts: Vec<ContentBlock>,
+    /// Size of the drawing being read (`wp:extent`), in points
+    drawing_extent: (f64, f64),
+
+    // State for content controls
+    sdt_depth: usize,
+    control: Option<ContentControl>,
+    in_sdt_props: bool,
+    /// Whether runs are the value of a form field rather than text
+    in_field_content: bool,
+
+    // State for run parsing
+    in_run: bool,
+    run_text: String,
+    run_style: TextStyle,
+    in_run_props: bool,
+    run_languages: RunLanguages,
+    run_rtl: bool,
+
+    /// The open `w:ins` or `w:del`, collecting its text
+    change: Option<Revision>,
+    /// Comments whose range is open, with the text read so far
+    comment_ranges: Vec<(String, String)>,
+}
+
+impl<'a> ChunkReader<'a> {
+    fn new(
+        styles: &'a Styles,
+        relationships: &'a Relationships,
+        charts: &'a HashMap<String, Chart>,
+        revision_mode: RevisionMode,
+    ) -> Self {
+        Self {
+            styles,
+            relationships,
+            charts,
+            revision_mode,
+            items: Vec::new(),
+            in_paragraph: false,
+            paragraph_runs: Vec::new(),
+            paragraph_style: None,
+            paragraph_direction: TextDirection::Auto,
+            in_paragraph_props: false,
+            paragraph_num_id: None,
+            paragraph_level: 0,
+            hyperlink: None,
+            paragraph_fields: Vec::new(),
+            paragraph_charts: Vec::new(),
+            drawing_extent: (0.0, 0.0),
+            sdt_depth: 0,
+            control: None,
+            in_sdt_props: false,
+            in_field_content: false,
+            in_run: false,
+            run_text: String::new(),
+            run_style: TextStyle::default(),
+            in_run_props: false,
+            run_languages: RunLanguages::default(),
+            run_rtl: false,
+            change: None,
+            comment_ranges: Vec::new(),
+        }
+    }
+
+    /// The items read, with the comment ranges still open at the end of the
+    /// chunk
+    fn finish(mut self) -> Vec<BodyItem> {
+        for (id, text) in self.comment_ranges.drain(..) {
+            self.items.push(BodyItem::CommentAnchor { id, text });
+        }
+        self.items
+    }
+
+    /// Whether a `w:ins` or `w:del` here marks runs rather than a paragraph
+    /// mark or a property change
+    fn is_run_change(&self) -> bool {
+        self.in_paragraph && !self.in_run_props && !self.in_paragraph_props
+    }
+
+    fn start(&mut self, e: &BytesStart<'_>) {
+        match e.name().as_ref() {
+            b"w:p" => {
+                self.in_paragraph = true;
+                self.paragraph_runs.clear();
+                self.paragraph_style = None;
+                self.paragraph_direction = TextDirection::Auto;
+                self.paragraph_num_id = None;
+                self.paragraph_level = 0;
+                self.items.push(BodyItem::ParagraphStart);
+            }
+            b"w:pPr" => {
+                // Paragraph properties (e.g. style)
+                // We need to parse this eagerly to apply to the paragraph
+                self.in_paragraph_props = true;
+            }
+            b"w:r" => {
+                if self.in_paragraph {
+                    self.in_run = true;
+                    self.run_text.clear();
+                    self.run_style = TextStyle::default();
+                    self.run_languages = RunLanguages::default();
+                    self.run_rtl = false;
+                    // TODO: Apply paragraph style defaults here?
+                }
+            }
+            b"w:hyperlink" => self.hyperlink = self.hyperlink_target(e),
+            b"w:ins" | b"w:moveTo" if self.is_run_change() => {
+                self.change = Some(tracked_change(e, RevisionKind::Insertion));
+            }
+            b"w:del" | b"w:moveFrom" if self.is_run_change() => {
+                self.change = Some(tracked_change(e, RevisionKind::Deletion));
+            }
+            b"w:sdt" => {
+                self.sdt_depth += 1;
+                // Fields nested in a field are part of its value
+                if !self.control.as_ref().is_some_and(ContentControl::is_field) {
+                    self.control = Some(ContentControl::new(self.sdt_depth));
+                }
+            }
+            b"w:sdtPr" => {
+                self.in_sdt_props = self
+                    .control
+                    .as_ref()
+                    .is_some_and(|control| control.depth == self.sdt_depth);
+            }
+            b"w:sdtContent" => {
+                self.in_field_content = self.control.as_ref().is_some_and(ContentControl::is_field);
+            }
+            b"w:rPr" => self.in_run_props = true,
+            _ => self.property(e),
+        }
+    }
+
+    /// Target of a `w:hyperlink`: an external relationship, a bookmark, or
+    /// a bookmark within the external target
+    fn hyperlink_target(&self, e: &BytesStart<'_>) -> Option<Link> {
+        let url = utils::attr_value_opt(e, b"r:id")
+            .and_then(|id| self.relationships.get(&id))
+            .map(|rel| unescape(&rel.target).map_or_else(|_| rel.target.clone(), Cow::into_owned));
+        let anchor = utils::attr_value_opt(e, b"w:anchor");
+        match (url, anchor) {
+            (Some(url), Some(anchor)) => Some(Link::Url(format!("{url}#{anchor}"))),
+            (Some(url), None) => Some(Link::Url(url)),
+            (None, anchor) => anchor.map(Link::Anchor),
+        }
+    }
+
+    /// Properties read from both start and empty elements, like `<w:b/>`
+    fn property(&mut self, e: &BytesStart<'_>) {
+        if self.in_sdt_props {
+            if let Some(control) = &mut self.control {
+                control.property(e);
+            }
+            return;
+        }
+        match e.name().as_ref() {
+            b"w:bidi" if self.in_paragraph_props => {
+                self.paragraph_direction = styles::bidi(e);
+            }
+            b"w:numId" if self.in_paragraph_props => {
+                // Numbering ID 0 removes numbering inherited from a style
+                self.paragraph_num_id = e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| attr.key.as_ref() == b"w:val")
+                    .map(|attr| utils::attr_value(&attr.value))
+                    .filter(|id| id != "0");
+            }
+            b"w:ilvl" if self.in_paragraph_props => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"w:val" {
+                        self.paragraph_level = utils::attr_value(&attr.value).parse().unwrap_or(0);
+                    }
+                }
+            }
+            b"w:pStyle" => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"w:val" {
+                        self.paragraph_style = Some(utils::attr_value(&attr.value));
+                    }
+                }
+            }
+            // Run Properties
+            b"w:b" if self.in_run_props => self.run_style.bold = true,
+            b"w:i" if self.in_run_props => self.run_style.italic = true,
+            b"w:u" if self.in_run_props => self.run_style.underline = true,
+            b"w:rtl" if self.in_run_props => self.run_rtl = utils::is_on(e),
+            b"w:color" if self.in_run_props => {
+                self.run_style.color = styles::color(e, self.styles.theme()).map(Into::into);
+            }
+            b"w:sz" if self.in_run_props => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"w:val" {
+                        if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
+                            self.run_style.font_size = Some(val / 2.0);
+                        }
+                    }
+                }
+            }
+            b"w:rFonts" if self.in_run_props => {
+                if let Some(font) = styles::font_family(e, self.styles.theme()) {
+                    self.run_style.font_family = Some(font.into());
+                }
+            }
+            b"w:lang" if self.in_run_props => {
+                self.run_languages = RunLanguages::from_element(e);
+            }
+            b"wp:extent" => {
+                let emu = |name| {
+                    utils::attr_value_opt(e, name)
+                        .and_then(|value| value.parse::<f64>().ok())
+                        .map_or(0.0, |value| value / 12_700.0)
+                };
+                self.drawing_extent = (emu(b"cx"), emu(b"cy"));
+            }
+            b"c:chart" => {
+                if let Some(chart) = charts::chart_id(e).and_then(|id| self.charts.get(&id)) {
+                    let (width, height) = self.drawing_extent;
+                    // Inline charts flow with the text; only their parts are
+                    // positioned, within the chart
+                    let mut block = chart.render(Rect::new(0.0, 0.0, width, height));
+                    block.bounds = Rect::default();
+                    self.paragraph_charts.push(ContentBlock::Container(block));
+                }
+            }
+            b"w:commentRangeStart" => {
+                if let Some(id) = utils::attr_value_opt(e, b"w:id") {
+                    self.comment_ranges.push((id, String::new()));
+                }
+            }
+            b"w:commentRangeEnd" => {
+                let id = utils::attr_value_opt(e, b"w:id");
+                if let Some(index) = self
+                    .comment_ranges
+                    .iter()
+                    .position(|(open, _)| Some(open) == id.as_ref())
+                {
+                    let (id, text) = self.comment_ranges.remove(index);
+                    self.items.push(BodyItem::CommentAnchor { id, text });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &[u8]) {
+        match name {
+            b"w:p" => {
+                if !self.paragraph_runs.is_empty() {
+                    let block = TextBlock {
+                        runs: self.paragraph_runs.clone(),
+                        paragraph_style: self.paragraph_style.clone(),
+                        bounds: Rect::default(),
+                        style: prism_core::document::ShapeStyle::default(),
+                        rotation: 0.0,
+                        direction: match self.paragraph_direction {
+                            TextDirection::Auto => self
+                                .styles
+                                .paragraph_direction(self.paragraph_style.as_deref()),
+                            direction => direction,
+                        },
+                    };
+                    let block = ContentBlock::Text(block);
+                    self.items.push(match self.paragraph_num_id.take() {
+                        Some(num_id) => BodyItem::ListParagraph {
+                            block,
+                            num_id,
+                            level: self.paragraph_level,
+                        },
+                        None => BodyItem::Paragraph(block),
+                    });
+                }
+                for field in self.paragraph_fields.drain(..) {
+                    self.items
+                        .push(BodyItem::Paragraph(ContentBlock::FormField(field)));
+                }
+                for chart in self.paragraph_charts.drain(..) {
+                    self.items.push(BodyItem::Paragraph(chart));
+                }
+                if self.in_field_content {
+                    if let Some(control) = &mut self.control {
+                        control.end_paragraph();
+                    }
+                }
+                self.in_paragraph = false;
+            }
+            b"w:r" if self.in_field_content => {
+                if let Some(control) = &mut self.control {
+                    control.push_text(&self.run_text);
+                }
+                self.in_run = false;
+            }
+            b"w:r" => {
+                let revision = self.change.as_mut().map(|change| {
+                    change.text.push_str(&self.run_text);
+                    RevisionMark {
+                        kind: change.kind,
+                        id: change.id.clone(),
+                    }
+                });
+                let keep = !matches!(
+                    (self.revision_mode, revision.as_ref().map(|mark| mark.kind)),
+                    (RevisionMode::Accept, Some(RevisionKind::Deletion))
+                        | (RevisionMode::Reject, Some(RevisionKind::Insertion))
+                );
+                if keep && !self.run_text.is_empty() {
+                    for (_, anchor) in &mut self.comment_ranges {
+                        anchor.push_str(&self.run_text);
+                    }
+                    self.run_style.language = self
+                        .run_languages
+                        .for_text(&self.run_text, self.run_rtl)
+                        .map(Into::into);
+
+                    // Resolve style against global styles if needed
+                    let effective_style = self
+                        .styles
+                        .resolve_text_style(self.paragraph_style.as_deref(), &self.run_style);
+
+                    self.paragraph_runs.push(TextRun {
+                        text: self.run_text.as_str().into(),
+                        style: effective_style,
+                        bounds: None,
+                        char_positions: None,
+                        link: self.hyperlink.clone(),
+                        revision: revision.filter(|_| self.revision_mode == RevisionMode::Markup),
+                    });
+                }
+                self.in_run = false;
+            }
+            b"w:hyperlink" => self.hyperlink = None,
+            b"w:ins" | b"w:moveTo" | b"w:del" | b"w:moveFrom" if self.is_run_change() => {
+                if let Some(change) = self.change.take().filter(|change| !change.text.is_empty()) {
+                    self.items.push(BodyItem::Revision(change));
+                }
+            }
+            b"w:sdt" => self.end_control(),
+            b"w:sdtPr" => self.in_sdt_props = false,
+            b"w:rPr" => self.in_run_props = false,
+            b"w:pPr" => self.in_paragraph_props = false,
+            _ => {}
+        }
+    }
+
+    /// Close a `w:sdt`, adding the form field of the control it ends
+    fn end_control(&mut self) {
+        if self
+            .control
+            .as_ref()
+            .is_some_and(|control| control.depth == self.sdt_depth)
+        {
+            self.in_field_content = false;
+            if let Some(field) = self.control.take().and_then(ContentControl::into_field) {
+                // Inline controls follow the text of their paragraph
+                if self.in_paragraph {
+                    self.paragraph_fields.push(field);
+                } else {
+                    self.items
+                        .push(BodyItem::Paragraph(ContentBlock::FormField(field)));
+                }
+            }
+        }
+        self.sdt_depth = self.sdt_depth.saturating_sub(1);
+    }
+}
+
+/// A tracked change starting at `e`, before its text is read
+fn tracked_change(e: &BytesStart<'_>, kind: RevisionKind) -> Revision {
+    let mut change = Revision::new(
+        utils::attr_value_opt(e, b"w:id").unwrap_or_default(),
+        kind,
+        String::new(),
+    );
+    change.author = utils::attr_value_opt(e, b"w:author")
+        .map(|author| unescape(&author).map_or_else(|_| author.clone(), Cow::into_owned));
+    change.date = utils::attr_value_opt(e, b"w:date")
+        .as_deref()
+        .and_then(comments::parse_date);
+    change
+}
+
+/// Paragraphs per page, for approximate pagination
+const PARAS_PER_PAGE: usize = 50;
+
+/// Lays body items out on pages in document order, grouping consecutive
+/// list paragraphs into list blocks
+struct PageBuilder<'a> {
+    pages: Vec<Page>,
+    content: Vec<ContentBlock>,
+    list: Option<ListBlock>,
+    counters: ListCounters<'a>,
+    para_count: usize,
+    /// Tracked changes and anchored comments, on the page they were read
+    /// on
+    revisions: Vec<Revision>,
+}
+
+impl<'a> PageBuilder<'a> {
+    fn new(numbering: &'a Numbering) -> Self {
+        Self {
+            pages: Vec::new(),
+            content: Vec::new(),
+            list: None,
+            counters: ListCounters::new(numbering),
+            para_count: 0,
+            revisions: Vec::new(),
+        }
+    }
+
+    /// Number of the page being filled
+    fn page_number(&self) -> u32 {
+        u32::try_from(self.pages.len() + 1).unwrap_or(u32::MAX)
+    }
+
+    fn revision(&mut self, mut revision: Revision) {
+        revision.page = Some(self.page_number());
+        self.revisions.push(revision);
+    }
+
+    /// Record the text a comment is anchored to, where its range ends
+    fn comment_anchor(&mut self, id: String, text: String) {
+        let existing = self
+            .revisions
+            .iter_mut()
+            .find(|revision| revision.kind == RevisionKind::Comment && revision.id == id);
+        if let Some(comment) = existing {
+            comment
+                .anchor
+                .get_or_insert_with(String::new)
+                .push_str(&text);
+        } else {
+            let mut comment = Revision::new(id, RevisionKind::Comment, String::new());
+            comment.anchor = Some(text);
+            self.revision(comment);
+        }
+    }
+    fn paragraph(&mut self, block: ContentBlock) {
+        self.end_list();
+        self.content.push(block);
+        self.paginate();
+    }
+
+    fn list_paragraph(&mut self, block: ContentBlock, num_id: &str, level: u8) {
+        // Undefined numbering leaves a plain paragraph
+        let Some(item) = self.counters.item(num_id, level, vec![block.clone()]) else {
+            return self.paragraph(block);
+        };
+        self.list
+            .get_or_insert_with(|| ListBlock::new(Rect::default()))
+            .add_item(item);
+        self.paginate();
+    }
+
+    fn table(&mut self, block: ContentBlock) {
+        self.end_list();
+        self.content.push(block);
+    }
+
+    fn end_list(&mut self) {
+        if let Some(list) = self.list.take() {
+            self.content.push(ContentBlock::List(list));
+        }
+    }
+
+    fn paginate(&mut self) {
+        if self.para_count >= PARAS_PER_PAGE {
+            self.end_page();
+            self.para_count = 0;
+        }
+    }
+
+    fn end_page(&mut self) {
+        self.end_list();
+        self.pages.push(Page {
+            number: self.page_number(),
+            dimensions: Dimensions::LETTER,
+            content: std::mem::take(&mut self.content),
+            annotations: Vec::new(),
+            metadata: PageMetadata::default(),
+        });
+    }
+
+    /// The pages, with at least one, and the revisions
+    fn finish(mut self) -> (Vec<Page>, Vec<Revision>) {
+        self.end_list();
+        if !self.content.is_empty() || self.pages.is_empty() {
+            self.end_page();
+        }
+        (self.pages, self.revisions)
+    }
+}
+
+/// Parse the body of `word/document.xml`
+///
+/// Top-level body elements are independent, so the body is split into
+/// chunks that are parsed in parallel; pagination runs over the results in
+/// document order.
+fn parse_body(
+    xml: &str,
+    styles: &Styles,
+    rels: &Relationships,
+    charts: &HashMap<String, Chart>,
+    revisions: RevisionMode,
+) -> Vec<(Vec<BodyItem>, Option<Error>)> {
+    body_chunks(xml)
+        .into_par_iter()
+        .map(|range| {
+            parse_chunk(
+                &xml[range.clone()],
+                range.start as u64,
+                styles,
+                rels,
+                charts,
+                revisions,
+            )
+        })
+        .collect()
+}
+
+/// Parse a run of top-level body elements starting at byte `offset` of
+/// `word/document.xml`
+///
+/// Returns the items read and the XML error that ended the chunk early, if
+/// any.
+fn parse_chunk(
+    xml: &str,
+    offset: u64,
+    styles: &Styles,
+    relationships: &Relationships,
+    charts: &HashMap<String, Chart>,
+    revision_mode: RevisionMode,
+) -> (Vec<BodyItem>, Option<Error>) {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut chunk = ChunkReader::new(styles, relationships, charts, revision_mode);
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:tbl" => {
+                // Delegate to table parser
+                // Note: parse_table expects we just consumed <w:tbl>
+                chunk.items.push(match tables::parse_table(&mut reader) {
+                    Ok(table_block) => BodyItem::Table(ContentBlock::Table(table_block)),
+                    Err(e) => BodyItem::Error(e),
+                });
+            }
+            Ok(Event::Start(e)) => chunk.start(&e),
+            // Handle empty tags like <w:b/>
+            Ok(Event::Empty(e)) => chunk.property(&e),
+            Ok(Event::End(e)) => chunk.end(e.name().as_ref()),
+            Ok(Event::Text(e)) if chunk.in_run => {
+                if let Ok(text) = e.unescape() {
+                    chunk.run_text.push_str(&text);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                let position = offset + reader.buffer_position() as u64;
+                let error = Error::corrupt("DOCX", format!("XML error: {e}"))
+                    .at(ErrorLocation::part("word/document.xml").at_offset(position));
+                return (chunk.finish(), Some(error));
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    (chunk.finish(), None)
+}
 
 /// DOCX parser
 #[derive(Debug, Clone)]
@@ -69,259 +817,118 @@ impl Parser for DocxParser {
     }
 
     fn can_parse(&self, data: &[u8]) -> bool {
-        Self::is_docx_zip(data)
+        // Encrypted packages are accepted so parsing can report the encryption
+        Self::is_docx_zip(data) || encryption::ooxml_encryption(data).is_some()
     }
 
     async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
         debug!("Parsing DOCX file: {:?}", context.filename);
 
-        let cursor = Cursor::new(data.as_ref());
+        let timer = StageTimer::new(&context.options);
+        let mut diagnostics = Vec::new();
+        let package = timer.time("zip_open", || {
+            package::package_bytes(&data, "DOCX", &context.options, &mut diagnostics)
+        })?;
+        let active_content = security::check(&package, "DOCX", &context.options)?;
+        let cursor = Cursor::new(package.as_ref());
         let mut archive = ZipArchive::new(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open DOCX ZIP: {}", e)))?;
+            .map_err(|e| Error::corrupt("DOCX", format!("Failed to open ZIP package: {e}")))?;
 
         // 1. Parse Relationships
-        let mut _rels = Relationships::new();
-        if let Ok(mut file) = archive.by_name("word/_rels/document.xml.rels") {
-            use std::io::Read;
-            let mut xml = String::new();
-            file.read_to_string(&mut xml).ok(); // Ignore errors, rels are optional-ish
-            if let Ok(r) = Relationships::from_xml(&xml) {
-                _rels = r;
-            }
-        }
+        // Relationships are optional, so unreadable ones are ignored
+        let rels = read_part(&mut archive, "word/_rels/document.xml.rels")
+            .and_then(|xml| Relationships::from_xml(&xml).ok())
+            .unwrap_or_default();
 
-        // 2. Parse Styles
-        let mut styles = Styles::new();
+        // 2. Parse Theme and Styles, which may refer to theme colors and fonts
+        let theme = read_theme(&mut archive, &rels);
+        let mut styles = Styles::with_theme(theme.clone());
         if let Ok(mut file) = archive.by_name("word/styles.xml") {
             use std::io::Read;
             let mut xml = String::new();
             file.read_to_string(&mut xml).ok();
-            if let Ok(s) = Styles::from_xml(&xml) {
+            if let Ok(s) = Styles::from_xml(&xml, theme) {
                 styles = s;
             }
         }
 
+        let numbering = read_part(&mut archive, "word/numbering.xml")
+            .and_then(|xml| Numbering::from_xml(&xml).ok())
+            .unwrap_or_default();
+
         // 3. Parse Document Content
         let mut document_xml = String::new();
         match archive.by_name("word/document.xml") {
             Ok(mut file) => {
                 use std::io::Read;
                 file.read_to_string(&mut document_xml).map_err(|e| {
-                    Error::ParseError(format!("Failed to read document.xml: {}", e))
+                    Error::corrupt("DOCX", format!("Failed to read part: {e}"))
+                        .at(ErrorLocation::part("word/document.xml"))
                 })?;
             }
-            Err(_) => return Err(Error::ParseError("word/document.xml not found".to_string())),
+            Err(_) => {
+                return Err(Error::corrupt("DOCX", "Missing main document part")
+                    .at(ErrorLocation::part("word/document.xml")))
+            }
         }
 
-        // Streaming Parse of Document XML
-        let mut reader = Reader::from_str(&document_xml);
-        reader.trim_text(false);
-        let mut buf = Vec::new();
-
-        let mut pages = Vec::new();
-        let mut current_page_content = Vec::new();
-
-        // State for paragraph parsing
-        let mut in_paragraph = false;
-        let mut current_paragraph_runs = Vec::new();
-        let mut current_paragraph_style: Option<String> = None;
-
-        // State for run parsing
-        let mut in_run = false;
-        let mut current_run_text = String::new();
-        let mut current_run_style = TextStyle::default();
-        let mut in_run_props = false;
-
-        // Count paragraphs for approximate pagination
-        let mut para_count = 0;
-        const PARAS_PER_PAGE: usize = 50;
-
-        loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(e)) => {
-                    let name = e.name();
-                    match name.as_ref() {
-                        b"w:p" => {
-                            in_paragraph = true;
-                            current_paragraph_runs.clear();
-                            current_paragraph_style = None;
-                            para_count += 1;
-                        }
-                        b"w:pPr" => {
-                            // Paragraph properties (e.g. style)
-                            // We need to parse this eagerly to apply to the paragraph
-                        }
-                        b"w:pStyle" => {
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"w:val" {
-                                    current_paragraph_style = Some(utils::attr_value(&attr.value));
-                                }
-                            }
-                        }
-                        b"w:r" => {
-                            if in_paragraph {
-                                in_run = true;
-                                current_run_text.clear();
-                                current_run_style = TextStyle::default();
-                                // TODO: Apply paragraph style defaults here?
-                            }
-                        }
-                        b"w:rPr" => in_run_props = true,
-                        // Run Properties
-                        b"w:b" if in_run_props => current_run_style.bold = true,
-                        b"w:i" if in_run_props => current_run_style.italic = true,
-                        b"w:u" if in_run_props => current_run_style.underline = true,
-                        b"w:color" if in_run_props => {
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"w:val" {
-                                    let val = utils::attr_value(&attr.value);
-                                    if val != "auto" {
-                                        current_run_style.color = Some(format!("#{}", val));
-                                    }
-                                }
-                            }
-                        }
-                        b"w:sz" if in_run_props => {
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"w:val" {
-                                    if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                        current_run_style.font_size = Some(val / 2.0);
-                                    }
-                                }
-                            }
-                        }
-                        b"w:rFonts" if in_run_props => {
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"w:ascii" {
-                                    current_run_style.font_family =
-                                        Some(utils::attr_value(&attr.value));
-                                }
-                            }
-                        }
-                        b"w:t" => {
-                            // Text content
-                        }
-                        b"w:tbl" => {
-                            // Delegate to table parser
-                            // Note: parse_table expects we just consumed <w:tbl>
-                            match tables::parse_table(&mut reader) {
-                                Ok(table_block) => {
-                                    current_page_content.push(ContentBlock::Table(table_block));
-                                }
-                                Err(e) => warn!("Failed to parse table: {}", e),
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                Ok(Event::Empty(e)) => {
-                    // Handle empty tags like <w:b/>
-                    let name = e.name();
-                    match name.as_ref() {
-                        b"w:b" if in_run_props => current_run_style.bold = true,
-                        b"w:i" if in_run_props => current_run_style.italic = true,
-                        b"w:u" if in_run_props => current_run_style.underline = true,
-                        b"w:pStyle" => {
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"w:val" {
-                                    current_paragraph_style = Some(utils::attr_value(&attr.value));
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                Ok(Event::End(e)) => {
-                    match e.name().as_ref() {
-                        b"w:p" => {
-                            if !current_paragraph_runs.is_empty() {
-                                let block = TextBlock {
-                                    runs: current_paragraph_runs.clone(),
-                                    paragraph_style: current_paragraph_style.clone(),
-                                    bounds: Rect::default(),
-                                    style: prism_core::document::ShapeStyle::default(),
-                                    rotation: 0.0,
-                                };
-                                current_page_content.push(ContentBlock::Text(block));
-
-                                // Pagination logic
-                                if para_count >= PARAS_PER_PAGE {
-                                    pages.push(Page {
-                                        number: (pages.len() + 1) as u32,
-                                        dimensions: Dimensions::LETTER,
-                                        content: current_page_content.clone(),
-                                        annotations: Vec::new(),
-                                        metadata: PageMetadata::default(),
-                                    });
-                                    current_page_content.clear();
-                                    para_count = 0;
-                                }
-                            }
-                            in_paragraph = false;
-                        }
-                        b"w:r" => {
-                            if !current_run_text.is_empty() {
-                                // Resolve style against global styles if needed
-                                let effective_style = styles.resolve_text_style(
-                                    current_paragraph_style.as_deref(),
-                                    &current_run_style,
-                                );
-
-                                current_paragraph_runs.push(TextRun {
-                                    text: current_run_text.clone(),
-                                    style: effective_style,
-                                    bounds: None,
-                                    char_positions: None,
-                                });
-                            }
-                            in_run = false;
-                        }
-                        b"w:rPr" => in_run_props = false,
-                        _ => {}
-                    }
-                }
-                Ok(Event::Text(e)) => {
-                    if in_run {
-                        if let Ok(text) = e.unescape() {
-                            current_run_text.push_str(&text);
-                        }
-                    }
-                }
-                Ok(Event::Eof) => break,
-                Err(e) => {
-                    warn!("XML error: {}", e);
-                    break;
+        let chunks = timer.time("body", || {
+            Ok(parse_body(
+                &document_xml,
+                &styles,
+                &rels,
+                &charts::read_charts(&mut archive, "word", &rels),
+                context.options.revisions,
+            ))
+        })?;
+
+        let mut builder = PageBuilder::new(&numbering);
+        'chunks: for (items, error) in chunks {
+            for item in items {
+                match item {
+                    BodyItem::ParagraphStart => builder.para_count += 1,
+                    BodyItem::Paragraph(block) => builder.paragraph(block),
+                    BodyItem::ListParagraph {
+                        block,
+                        num_id,
+                        level,
+                    } => builder.list_paragraph(block, &num_id, level),
+                    BodyItem::Table(block) => builder.table(block),
+                    BodyItem::Revision(revision) => builder.revision(revision),
+                    BodyItem::CommentAnchor { id, text } => builder.comment_anchor(id, text),
+                    BodyItem::Error(e) => context.options.recover(e, &mut diagnostics)?,
                 }
-                _ => {}
             }
-            buf.clear();
+            // Nothing after malformed XML can be trusted
+            if let Some(error) = error {
+                context.options.recover(error, &mut diagnostics)?;
+                break 'chunks;
+            }
         }
 
-        // Add final page
-        if !current_page_content.is_empty() {
-            pages.push(Page {
-                number: (pages.len() + 1) as u32,
-                dimensions: Dimensions::LETTER,
-                content: current_page_content,
-                annotations: Vec::new(),
-                metadata: PageMetadata::default(),
-            });
-        }
+        let (pages, mut revisions) = builder.finish();
 
-        // Ensure at least one page
-        if pages.is_empty() {
-            pages.push(Page::new(1, Dimensions::LETTER));
-        }
+        merge_comments(&mut archive, &mut revisions);
 
         let mut metadata = Metadata::new();
         if let Some(filename) = context.filename {
             metadata.title = Some(filename);
         }
         metadata.add_custom("format", "DOCX");
+        metadata.signatures = signatures::verify(&package);
+        metadata.active_content = active_content;
 
         let mut document = Document::builder().metadata(metadata).build();
         document.pages = pages;
+        document.resources.fonts = fonts::docx_fonts(&mut archive);
+        let embedded = embeddings::embedded_parts("word", &rels);
+        document.attachments = embeddings::read_embeddings(&mut archive, &embedded);
+        document.diagnostics = diagnostics;
+        document.revisions = revisions;
+        document.timings = timer.into_timings();
         document.structure.headings = Vec::new(); // TODO: Extract headings from structure
+        document.structure.sections = Section::from_headings(&document.pages);
+        embeddings::parse_embedded(&mut document, &context.options).await;
 
         Ok(document)
     }
@@ -338,3 +945,441 @@ impl Parser for DocxParser {
         }
     }
 }
+
+/// Fill the comment anchors of `revisions` with the comments of
+/// `word/comments.xml`
+///
+/// Comments take their anchor and page from the body, in body order; those
+/// without a range follow. Anchors of comments missing from the part have
+/// nothing to show and are dropped.
+fn merge_comments<R: std::io::Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    revisions: &mut Vec<Revision>,
+) {
+    use std::io::Read;
+    let mut xml = String::new();
+    if let Ok(mut file) = archive.by_name("word/comments.xml") {
+        file.read_to_string(&mut xml).ok();
+    }
+    for comment in comments::docx_comments(&xml) {
+        let anchored = revisions
+            .iter_mut()
+            .find(|revision| revision.kind == RevisionKind::Comment && revision.id == comment.id);
+        match anchored {
+            Some(anchored) => {
+                *anchored = Revision {
+                    anchor: anchored.anchor.take(),
+                    page: anchored.page,
+                    ..comment
+                };
+            }
+            None => revisions.push(comment),
+        }
+    }
+    revisions.retain(|revision| {
+        revision.kind != RevisionKind::Comment
+            || revision.author.is_some()
+            || !revision.text.is_empty()
+    });
+}
+
+/// Theme of the document, or the default theme if it has none
+fn read_theme<R: std::io::Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    rels: &Relationships,
+) -> Theme {
+    use std::io::Read;
+    let part = rels.find_by_type(THEME_RELATIONSHIP).next().map_or_else(
+        || "word/theme/theme1.xml".to_string(),
+        |rel| utils::resolve_path("word", &rel.target),
+    );
+    let mut xml = Vec::new();
+    let read = archive
+        .by_name(&part)
+        .is_ok_and(|mut file| file.read_to_end(&mut xml).is_ok());
+    if read {
+        parse_theme(&xml).unwrap_or_default()
+    } else {
+        Theme::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::FormFieldType;
+
+    fn document_xml(paragraphs: usize) -> String {
+        let body = (0..paragraphs)
+            .map(|i| format!("<w:p><w:r><w:t>Paragraph {i}</w:t></w:r></w:p>\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        format!(
+            "<?xml version=\"1.0\"?><w:document><w:body>{body}<w:sectPr/></w:body></w:document>"
+        )
+    }
+
+    #[test]
+    fn test_body_chunks() {
+        let small = document_xml(10);
+        assert_eq!(body_chunks(&small).len(), 1);
+
+        let large = document_xml(5000);
+        let chunks = body_chunks(&large);
+        assert!(chunks.len() > 1);
+        assert!(large[chunks[1].clone()].starts_with("<w:p>"));
+        assert!(large[..chunks.last().unwrap().end].ends_with("<w:sectPr/>"));
+
+        // Chunks parse to the same paragraphs as the whole body
+        let styles = Styles::new();
+        let rels = Relationships::new();
+        let texts = |items: Vec<BodyItem>| {
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    BodyItem::Paragraph(ContentBlock::Text(block)) => Some(block.extract_text()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut chunked = Vec::new();
+        for range in chunks {
+            let (items, error) = parse_chunk(
+                &large[range.clone()],
+                range.start as u64,
+                &styles,
+                &rels,
+                &HashMap::new(),
+                RevisionMode::default(),
+            );
+            assert!(error.is_none());
+            chunked.extend(texts(items));
+        }
+        let (items, _) = parse_chunk(
+            &large,
+            0,
+            &styles,
+            &rels,
+            &HashMap::new(),
+            RevisionMode::default(),
+        );
+        assert_eq!(chunked, texts(items));
+        assert_eq!(chunked.len(), 5000);
+    }
+
+    #[test]
+    fn test_numbered_paragraphs() {
+        let numbering = Numbering::from_xml(
+            r#"<w:numbering><w:abstractNum w:abstractNumId="0">
+            <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>
+            <w:lvl w:ilvl="1"><w:numFmt w:val="bullet"/><w:lvlText w:val="o"/></w:lvl>
+            </w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num></w:numbering>"#,
+        )
+        .unwrap();
+        let item = |text: &str, num_pr: &str| {
+            format!("<w:p><w:pPr>{num_pr}</w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>")
+        };
+        let num_pr = |level: u8| {
+            format!(r#"<w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="1"/></w:numPr>"#)
+        };
+        let xml = format!(
+            "<w:document><w:body>{}{}{}{}{}</w:body></w:document>",
+            item("Intro", ""),
+            item("First", &num_pr(0)),
+            item("Detail", &num_pr(1)),
+            item("Second", &num_pr(0)),
+            item("Unnumbered", r#"<w:numPr><w:numId w:val="0"/></w:numPr>"#),
+        );
+
+        let (items, error) = parse_chunk(
+            &xml,
+            0,
+            &Styles::new(),
+            &Relationships::new(),
+            &HashMap::new(),
+            RevisionMode::default(),
+        );
+        assert!(error.is_none());
+        let mut builder = PageBuilder::new(&numbering);
+        for item in items {
+            match item {
+                BodyItem::Paragraph(block) => builder.paragraph(block),
+                BodyItem::ListParagraph {
+                    block,
+                    num_id,
+                    level,
+                } => builder.list_paragraph(block, &num_id, level),
+                _ => {}
+            }
+        }
+        let (pages, _) = builder.finish();
+        let content = &pages[0].content;
+        assert_eq!(content.len(), 3);
+        let ContentBlock::List(list) = &content[1] else {
+            panic!("expected a list");
+        };
+        assert_eq!(list.extract_text(), "1. First\n◦ Detail\n2. Second");
+        assert_eq!(list.items[1].level, 1);
+    }
+
+    #[test]
+    fn test_inline_chart() {
+        let chart = Chart::from_xml(
+            r#"<c:chartSpace><c:chart><c:plotArea><c:pieChart><c:ser><c:tx><c:v>Share</c:v></c:tx>
+            <c:val><c:numLit><c:pt idx="0"><c:v>3</c:v></c:pt><c:pt idx="1"><c:v>1</c:v></c:pt></c:numLit></c:val>
+            </c:ser></c:pieChart></c:plotArea></c:chart></c:chartSpace>"#,
+        )
+        .unwrap();
+        let charts = HashMap::from([("rId9".to_string(), chart)]);
+        let xml = r#"<w:document><w:body><w:p><w:r><w:drawing><wp:inline>
+            <wp:extent cx="5486400" cy="3200400"/><a:graphic><a:graphicData>
+            <c:chart r:id="rId9"/></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>
+            </w:body></w:document>"#;
+
+        let (items, error) = parse_chunk(
+            xml,
+            0,
+            &Styles::new(),
+            &Relationships::new(),
+            &charts,
+            RevisionMode::default(),
+        );
+        assert!(error.is_none());
+        let chart = items
+            .iter()
+            .find_map(|item| match item {
+                BodyItem::Paragraph(ContentBlock::Container(block)) => Some(block),
+                _ => None,
+            })
+            .expect("expected a chart");
+        assert_eq!(chart.container_type.as_deref(), Some("chart"));
+        assert_eq!(chart.bounds.width, 0.0);
+        // Parts are laid out within the 432 by 252 point drawing
+        let right = chart
+            .children
+            .iter()
+            .map(|child| child.bounds().x + child.bounds().width)
+            .fold(0.0, f64::max);
+        assert!(right > 300.0 && right <= 432.0);
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let rels = Relationships::from_xml(
+            r#"<Relationships><Relationship Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink"
+            Target="https://example.com/?a=1&amp;b=2" TargetMode="External"/></Relationships>"#,
+        )
+        .unwrap();
+        let xml = r#"<w:document><w:body><w:p><w:r><w:t xml:space="preserve">See </w:t></w:r>
+            <w:hyperlink r:id="rId5"><w:r><w:t>the site</w:t></w:r></w:hyperlink>
+            <w:r><w:t xml:space="preserve"> or </w:t></w:r>
+            <w:hyperlink w:anchor="_Toc1"><w:r><w:t>the summary</w:t></w:r></w:hyperlink></w:p>
+            </w:body></w:document>"#;
+
+        let (items, error) = parse_chunk(
+            xml,
+            0,
+            &Styles::new(),
+            &rels,
+            &HashMap::new(),
+            RevisionMode::default(),
+        );
+        assert!(error.is_none());
+        let Some(BodyItem::Paragraph(ContentBlock::Text(block))) = items.get(1) else {
+            panic!("expected a paragraph");
+        };
+        let links: Vec<_> = block.runs.iter().map(|run| run.link.clone()).collect();
+        assert_eq!(
+            links,
+            [
+                None,
+                Some(Link::Url("https://example.com/?a=1&b=2".to_string())),
+                None,
+                Some(Link::Anchor("_Toc1".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_theme_colors_and_fonts() {
+        let theme = crate::office::theme::parse_theme(
+            br#"<a:theme name="Office Theme"><a:themeElements><a:clrScheme name="Office">
+            <a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1>
+            <a:accent1><a:srgbClr val="4472C4"/></a:accent1></a:clrScheme>
+            <a:fontScheme name="Office"><a:majorFont><a:latin typeface="Calibri Light"/></a:majorFont>
+            <a:minorFont><a:latin typeface="Calibri"/></a:minorFont></a:fontScheme>
+            </a:themeElements></a:theme>"#,
+        )
+        .unwrap();
+        let styles = Styles::from_xml(
+            r#"<w:styles><w:style w:type="paragraph" w:styleId="Heading1"><w:rPr>
+            <w:rFonts w:asciiTheme="majorHAnsi" w:hAnsiTheme="majorHAnsi"/>
+            <w:color w:val="2F5496" w:themeColor="accent1" w:themeShade="BF"/><w:sz w:val="32"/>
+            </w:rPr></w:style></w:styles>"#,
+            theme,
+        )
+        .unwrap();
+        let xml = r#"<w:document><w:body><w:p>
+            <w:r><w:rPr><w:rFonts w:asciiTheme="minorHAnsi"/><w:color w:val="FF0000" w:themeColor="accent1"/></w:rPr><w:t>Themed</w:t></w:r>
+            <w:r><w:rPr><w:rFonts w:ascii="Arial"/><w:color w:val="00FF00"/></w:rPr><w:t>Direct</w:t></w:r>
+            <w:r><w:rPr><w:color w:val="auto" w:themeColor="text1" w:themeTint="80"/></w:rPr><w:t>Tinted</w:t></w:r>
+            </w:p></w:body></w:document>"#;
+
+        let (items, error) = parse_chunk(
+            xml,
+            0,
+            &styles,
+            &Relationships::new(),
+            &HashMap::new(),
+            RevisionMode::default(),
+        );
+        assert!(error.is_none());
+        let Some(BodyItem::Paragraph(ContentBlock::Text(block))) = items.get(1) else {
+            panic!("expected a paragraph");
+        };
+        let styles_of = |i: usize| {
+            let style = &block.runs[i].style;
+            (style.color.as_deref(), style.font_family.as_deref())
+        };
+        assert_eq!(styles_of(0), (Some("#4472C4"), Some("Calibri")));
+        assert_eq!(styles_of(1), (Some("#00FF00"), Some("Arial")));
+        assert_eq!(styles_of(2), (Some("#7F7F7F"), None));
+
+        let heading = styles.resolve_text_style(Some("Heading1"), &TextStyle::default());
+        assert_eq!(heading.color.as_deref(), Some("#2F5496"));
+        assert_eq!(heading.font_family.as_deref(), Some("Calibri Light"));
+        assert_eq!(heading.font_size, Some(16.0));
+    }
+
+    #[test]
+    fn test_tracked_changes() {
+        let xml = r#"<w:document><w:body><w:p><w:commentRangeStart w:id="7"/>
+            <w:r><w:t xml:space="preserve">Revenue </w:t></w:r>
+            <w:del w:id="1" w:author="Ana" w:date="2024-05-02T10:00:00Z"><w:r><w:delText>fell</w:delText></w:r></w:del>
+            <w:ins w:id="2" w:author="Bo &amp; Co"><w:r><w:t>grew</w:t></w:r></w:ins>
+            <w:commentRangeEnd w:id="7"/><w:r><w:commentReference w:id="7"/></w:r></w:p>
+            </w:body></w:document>"#;
+        let text = |mode| {
+            let (items, _) = parse_chunk(
+                xml,
+                0,
+                &Styles::new(),
+                &Relationships::new(),
+                &HashMap::new(),
+                mode,
+            );
+            items
+                .iter()
+                .find_map(|item| match item {
+                    BodyItem::Paragraph(ContentBlock::Text(block)) => Some(block.extract_text()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(text(RevisionMode::Accept), "Revenue grew");
+        assert_eq!(text(RevisionMode::Reject), "Revenue fell");
+        assert_eq!(text(RevisionMode::Markup), "Revenue fellgrew");
+
+        let (items, _) = parse_chunk(
+            xml,
+            0,
+            &Styles::new(),
+            &Relationships::new(),
+            &HashMap::new(),
+            RevisionMode::Markup,
+        );
+        let numbering = Numbering::new();
+        let mut builder = PageBuilder::new(&numbering);
+        for item in items {
+            match item {
+                BodyItem::Paragraph(ContentBlock::Text(block)) => {
+                    let marks: Vec<_> = block
+                        .runs
+                        .iter()
+                        .map(|run| run.revision.as_ref().map(|mark| mark.kind))
+                        .collect();
+                    assert_eq!(
+                        marks,
+                        [
+                            None,
+                            Some(RevisionKind::Deletion),
+                            Some(RevisionKind::Insertion)
+                        ]
+                    );
+                }
+                BodyItem::Revision(revision) => builder.revision(revision),
+                BodyItem::CommentAnchor { id, text } => builder.comment_anchor(id, text),
+                _ => {}
+            }
+        }
+        let (_, revisions) = builder.finish();
+        assert_eq!(revisions.len(), 3);
+        assert_eq!(revisions[0].kind, RevisionKind::Deletion);
+        assert_eq!(revisions[0].text, "fell");
+        assert_eq!(revisions[0].author.as_deref(), Some("Ana"));
+        assert!(revisions[0].date.is_some());
+        assert_eq!(revisions[1].author.as_deref(), Some("Bo & Co"));
+        assert_eq!(revisions[2].kind, RevisionKind::Comment);
+        assert_eq!(revisions[2].anchor.as_deref(), Some("Revenue fellgrew"));
+        assert_eq!(revisions[2].page, Some(1));
+
+        let comments = comments::docx_comments(
+            r#"<w:comments><w:comment w:id="7" w:author="Cy" w:date="2024-05-03T08:00:00Z">
+            <w:p><w:r><w:t>Source?</w:t></w:r></w:p><w:p><w:r><w:t>Please cite.</w:t></w:r></w:p>
+            </w:comment></w:comments>"#,
+        );
+        assert_eq!(comments[0].id, "7");
+        assert_eq!(comments[0].text, "Source?\nPlease cite.");
+    }
+
+    #[test]
+    fn test_content_controls() {
+        let xml = r#"<w:document><w:body><w:p><w:r><w:t xml:space="preserve">Name: </w:t></w:r>
+            <w:sdt><w:sdtPr><w:alias w:val="Full name"/><w:tag w:val="name"/><w:text/></w:sdtPr>
+            <w:sdtContent><w:r><w:t>Ada Lovelace</w:t></w:r></w:sdtContent></w:sdt></w:p>
+            <w:sdt><w:sdtPr><w:tag w:val="agree"/><w14:checkbox><w14:checked w14:val="1"/></w14:checkbox></w:sdtPr>
+            <w:sdtContent><w:p><w:r><w:t>☒</w:t></w:r></w:p></w:sdtContent></w:sdt>
+            <w:sdt><w:sdtPr><w:tag w:val="color"/><w:showingPlcHdr/><w:lock w:val="sdtContentLocked"/>
+            <w:dropDownList><w:listItem w:displayText="Red" w:value="r"/><w:listItem w:value="Blue"/></w:dropDownList></w:sdtPr>
+            <w:sdtContent><w:p><w:r><w:t>Choose an item.</w:t></w:r></w:p></w:sdtContent></w:sdt>
+            </w:body></w:document>"#;
+
+        let (items, error) = parse_chunk(
+            xml,
+            0,
+            &Styles::new(),
+            &Relationships::new(),
+            &HashMap::new(),
+            RevisionMode::default(),
+        );
+        assert!(error.is_none());
+        let fields: Vec<_> = items
+            .iter()
+            .filter_map(|item| match item {
+                BodyItem::Paragraph(ContentBlock::FormField(field)) => Some(field),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fields.len(), 3);
+
+        assert_eq!(fields[0].field_type, FormFieldType::Text);
+        assert_eq!(fields[0].name, "name");
+        assert_eq!(fields[0].label.as_deref(), Some("Full name"));
+        assert_eq!(fields[0].value.as_deref(), Some("Ada Lovelace"));
+
+        assert_eq!(fields[1].field_type, FormFieldType::Checkbox);
+        assert!(fields[1].checked);
+        assert_eq!(fields[1].value, None);
+
+        assert_eq!(fields[2].field_type, FormFieldType::Dropdown);
+        assert_eq!(fields[2].options, ["Red", "Blue"]);
+        assert_eq!(fields[2].value, None);
+        assert!(fields[2].read_only);
+
+        // The field's value is not also paragraph text
+        let Some(BodyItem::Paragraph(ContentBlock::Text(block))) = items.get(1) else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(block.extract_text(), "Name: ");
+    }
+}
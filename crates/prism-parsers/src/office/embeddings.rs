@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Embedded objects (`oleObject` and `package` parts)
+//!
+//! Documents, slides and sheets embed other files through relationships of
+//! type `package`, for Office Open XML files stored as they are, and
+//! `oleObject`, for files wrapped in an OLE compound file. The wrapped file
+//! is taken from the `\x01Ole10Native` stream of files packaged by Object
+//! Packager, with their original name, or from the `Package` or `CONTENTS`
+//! stream; a compound file without them, such as an embedded Excel 97-2003
+//! workbook, is the embedded file itself.
+//!
+//! Embedded files become attachments of the document; with
+//! [`ParseOptions::parse_embedded`] those in a supported format are parsed
+//! as documents of their own.
+
+use bytes::Bytes;
+use cfb::CompoundFile;
+use prism_core::{
+    diagnostics::Diagnostic,
+    document::{Attachment, Document},
+    format::detect_format,
+    parser::{ParseContext, ParseOptions},
+};
+use std::io::{Cursor, Read, Seek};
+use tracing::warn;
+use zip::ZipArchive;
+
+use crate::office::fonts::read_bytes;
+use crate::office::relationships::Relationships;
+use crate::office::utils;
+use crate::registry::ParserRegistry;
+
+/// Relationship type of an object wrapped in an OLE compound file
+pub const OLE_OBJECT_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/oleObject";
+
+/// Relationship type of an embedded Office Open XML package
+pub const PACKAGE_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/package";
+
+/// Compound file streams of the legacy Office formats, with the extension
+/// and MIME type of their files
+const LEGACY_FORMATS: [(&str, &str, &str); 5] = [
+    ("WordDocument", "doc", "application/msword"),
+    ("Workbook", "xls", "application/vnd.ms-excel"),
+    ("Book", "xls", "application/vnd.ms-excel"),
+    (
+        "PowerPoint Document",
+        "ppt",
+        "application/vnd.ms-powerpoint",
+    ),
+    ("VisioDocument", "vsd", "application/vnd.visio"),
+];
+
+/// Paths of the parts embedded through `rels`, which belong to a part in
+/// `dir`
+#[must_use]
+pub fn embedded_parts(dir: &str, rels: &Relationships) -> Vec<String> {
+    let mut parts: Vec<String> = rels
+        .find_by_type(OLE_OBJECT_RELATIONSHIP)
+        .chain(rels.find_by_type(PACKAGE_RELATIONSHIP))
+        .map(|rel| utils::resolve_path(dir, &rel.target))
+        .collect();
+    parts.sort();
+    parts
+}
+
+/// Read the embedded `parts` as attachments, each part once and in order
+pub fn read_embeddings<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    parts: &[String],
+) -> Vec<Attachment> {
+    let mut parts = parts.to_vec();
+    parts.sort();
+    parts.dedup();
+    parts
+        .iter()
+        .filter_map(|part| Some(attachment(part, read_bytes(archive, part)?)))
+        .collect()
+}
+
+/// Parse the attachments of `document` in a format the default parsers
+/// support into [`Attachment::document`], if `options` ask for it with
+/// [`ParseOptions::parse_embedded`]
+///
+/// Attachments are parsed with `options`, but their own embedded documents
+/// are not parsed. An attachment that fails to parse is kept as it is, with
+/// a warning in the diagnostics of `document`.
+pub async fn parse_embedded(document: &mut Document, options: &ParseOptions) {
+    if !options.parse_embedded {
+        return;
+    }
+    let registry = ParserRegistry::with_default_parsers();
+    let options = ParseOptions {
+        parse_embedded: false,
+        ..options.clone()
+    };
+    for attachment in &mut document.attachments {
+        let Some(detected) = registry.detect(&attachment.data, Some(&attachment.filename)) else {
+            continue;
+        };
+        let Some(parser) = registry.get_parser_for_data(&detected.format, &attachment.data) else {
+            continue;
+        };
+        let context = ParseContext {
+            format: detected.format,
+            filename: Some(attachment.filename.clone()),
+            size: attachment.data.len(),
+            options: options.clone(),
+            progress: None,
+        };
+        match parser
+            .parse(Bytes::from(attachment.data.clone()), context)
+            .await
+        {
+            Ok(document) => attachment.document = Some(Box::new(document)),
+            Err(error) => {
+                warn!(
+                    "Failed to parse embedded {}: {}",
+                    attachment.filename, error
+                );
+                document.diagnostics.push(Diagnostic::warning(
+                    error.code(),
+                    format!("Embedded {} was not parsed: {error}", attachment.filename),
+                ));
+            }
+        }
+    }
+}
+
+/// The file embedded in part `path`, whose content is `data`
+fn attachment(path: &str, data: Vec<u8>) -> Attachment {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (filename, data, mime_type) = match unwrap_ole(&data) {
+        Some(Unwrapped::Packaged { filename, data }) => {
+            let mime_type = mime_type(&filename, &data);
+            (filename, data, mime_type)
+        }
+        Some(Unwrapped::Stream(data)) => {
+            let mime_type = mime_type(name, &data);
+            (renamed(name, &data), data, mime_type)
+        }
+        Some(Unwrapped::Legacy {
+            extension,
+            mime_type,
+        }) => (
+            with_extension(name, extension),
+            data,
+            Some(mime_type.to_string()),
+        ),
+        None => {
+            let mime_type = mime_type(name, &data);
+            (name.to_string(), data, mime_type)
+        }
+    };
+    Attachment {
+        filename,
+        mime_type,
+        description: None,
+        data,
+        created: None,
+        modified: None,
+        document: None,
+    }
+}
+
+/// What an OLE compound file wraps
+enum Unwrapped {
+    /// A file packaged by Object Packager, with its original name
+    Packaged { filename: String, data: Vec<u8> },
+    /// The content of a `Package` or `CONTENTS` stream
+    Stream(Vec<u8>),
+    /// Nothing: the compound file is a document in a legacy Office format
+    Legacy {
+        extension: &'static str,
+        mime_type: &'static str,
+    },
+}
+
+/// What `data` wraps, or `None` if it is not an OLE compound file
+fn unwrap_ole(data: &[u8]) -> Option<Unwrapped> {
+    let mut comp = CompoundFile::open(Cursor::new(data)).ok()?;
+    let mut read_stream = |name: &str| {
+        let mut stream = comp.open_stream(name).ok()?;
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).ok()?;
+        Some(data)
+    };
+    if let Some((filename, data)) = read_stream("\u{1}Ole10Native").and_then(|s| ole10_native(&s)) {
+        return Some(Unwrapped::Packaged { filename, data });
+    }
+    if let Some(data) = read_stream("Package").or_else(|| read_stream("CONTENTS")) {
+        return Some(Unwrapped::Stream(data));
+    }
+    LEGACY_FORMATS
+        .iter()
+        .find(|(stream, _, _)| comp.exists(stream))
+        .map(|&(_, extension, mime_type)| Unwrapped::Legacy {
+            extension,
+            mime_type,
+        })
+}
+
+/// Name and content of the file in an `\x01Ole10Native` stream
+///
+/// The stream holds its size, two flag bytes, the label and source path as
+/// NUL-terminated strings, four reserved bytes, the length and bytes of a
+/// temporary path, and the length and bytes of the file.
+fn ole10_native(stream: &[u8]) -> Option<(String, Vec<u8>)> {
+    let mut rest = stream.get(6..)?;
+    let label = take_string(&mut rest)?;
+    let source = take_string(&mut rest)?;
+    rest = rest.get(4..)?;
+    let temporary = take_length(&mut rest)?;
+    rest = rest.get(temporary..)?;
+    let size = take_length(&mut rest)?;
+    let data = rest.get(..size)?.to_vec();
+    // The label is the file name; the source path is a fallback
+    let filename = if label.is_empty() {
+        source
+            .rsplit(['\\', '/'])
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        label
+    };
+    Some((filename, data))
+}
+
+/// Take a NUL-terminated string off the front of `rest`
+fn take_string(rest: &mut &[u8]) -> Option<String> {
+    let end = rest.iter().position(|&byte| byte == 0)?;
+    let value = String::from_utf8_lossy(&rest[..end]).into_owned();
+    *rest = &rest[end + 1..];
+    Some(value)
+}
+
+/// Take a little-endian 32-bit length off the front of `rest`
+fn take_length(rest: &mut &[u8]) -> Option<usize> {
+    let bytes: [u8; 4] = rest.get(..4)?.try_into().ok()?;
+    *rest = &rest[4..];
+    usize::try_from(u32::from_le_bytes(bytes)).ok()
+}
+
+/// MIME type of the file `name` holding `data`
+fn mime_type(name: &str, data: &[u8]) -> Option<String> {
+    // Visio drawings are packages format detection takes for plain ZIPs
+    if name.to_ascii_lowercase().ends_with(".vsdx") {
+        return Some("application/vnd.ms-visio.drawing".to_string());
+    }
+    detect_format(data, Some(name)).map(|result| result.format.mime_type)
+}
+
+/// `name`, with the extension of the format of `data` when it has none of
+/// its own, like the `.bin` of OLE object parts
+fn renamed(name: &str, data: &[u8]) -> String {
+    let has_extension = name
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| !extension.eq_ignore_ascii_case("bin"));
+    match detect_format(data, None) {
+        Some(result) if !has_extension && !result.format.extension.is_empty() => {
+            with_extension(name, &result.format.extension)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// `name` with its extension replaced by `extension`
+fn with_extension(name: &str, extension: &str) -> String {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    format!("{stem}.{extension}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ole10_stream(label: &str, data: &[u8]) -> Vec<u8> {
+        let mut stream = vec![0; 4];
+        stream.extend_from_slice(&[2, 0]);
+        stream.extend_from_slice(label.as_bytes());
+        stream.push(0);
+        stream.extend_from_slice(b"C:\\Users\\ada\\report.csv\0");
+        stream.extend_from_slice(&[0, 0, 3, 0]);
+        stream.extend_from_slice(&4u32.to_le_bytes());
+        stream.extend_from_slice(b"tmp\0");
+        stream.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        stream.extend_from_slice(data);
+        stream
+    }
+
+    fn compound_file(streams: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut comp = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in streams {
+            let mut stream = comp.create_stream(name).unwrap();
+            std::io::Write::write_all(&mut stream, data).unwrap();
+        }
+        comp.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_ole10_native() {
+        let stream = ole10_stream("report.csv", b"q1,10\n");
+        assert_eq!(
+            ole10_native(&stream),
+            Some(("report.csv".to_string(), b"q1,10\n".to_vec()))
+        );
+        let stream = ole10_stream("", b"q1,10\n");
+        assert_eq!(ole10_native(&stream).unwrap().0, "report.csv");
+        assert_eq!(ole10_native(&stream[..stream.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_embedded_attachments() {
+        let packaged = compound_file(&[("\u{1}Ole10Native", &ole10_stream("notes.txt", b"Hello"))]);
+        let notes = attachment("word/embeddings/oleObject1.bin", packaged);
+        assert_eq!(notes.filename, "notes.txt");
+        assert_eq!(notes.data, b"Hello");
+
+        let workbook = compound_file(&[("Workbook", b"\x09\x08")]);
+        let legacy = attachment("ppt/embeddings/oleObject2.bin", workbook.clone());
+        assert_eq!(legacy.filename, "oleObject2.xls");
+        assert_eq!(
+            legacy.mime_type.as_deref(),
+            Some("application/vnd.ms-excel")
+        );
+        assert_eq!(legacy.data, workbook);
+
+        let rels = Relationships::from_xml(&format!(
+            r#"<Relationships>
+            <Relationship Id="rId3" Type="{PACKAGE_RELATIONSHIP}" Target="../embeddings/Microsoft_Excel_Worksheet.xlsx"/>
+            <Relationship Id="rId2" Type="{OLE_OBJECT_RELATIONSHIP}" Target="../embeddings/oleObject1.bin"/>
+            <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/image1.png"/>
+            </Relationships>"#
+        ))
+        .unwrap();
+        assert_eq!(
+            embedded_parts("ppt/slides", &rels),
+            [
+                "ppt/embeddings/Microsoft_Excel_Worksheet.xlsx",
+                "ppt/embeddings/oleObject1.bin"
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Fills of shapes and slide backgrounds other than solid colors
+//!
+//! Shapes and slide backgrounds can be filled with a gradient
+//! (`a:gradFill`), a picture (`a:blipFill`) or a two-color pattern
+//! (`a:pattFill`). Colors are read from `a:srgbClr`, `a:sysClr` and
+//! `a:prstClr`; theme colors (`a:schemeClr`) are not resolved, so stops
+//! using them are left out.
+
+use prism_core::document::{Fill, GradientFill, GradientStop};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::office::utils;
+
+/// Whether `name` is an element [`parse_fill`] reads
+#[must_use]
+pub fn is_fill(name: &[u8]) -> bool {
+    matches!(name, b"a:gradFill" | b"a:blipFill" | b"a:pattFill")
+}
+
+/// Read the fill started by `start` up to its end tag
+///
+/// `rels` maps relationship IDs to their targets, which are the resource
+/// IDs of pictures. Returns `None` for a fill without usable colors or
+/// picture.
+pub fn parse_fill<S: BuildHasher>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    start: &BytesStart<'_>,
+    rels: &HashMap<String, String, S>,
+) -> Option<Fill> {
+    let end = start.name().as_ref().to_vec();
+    let mut stops = Vec::new();
+    let mut stop_position = None;
+    let mut angle = 0.0;
+    let mut radial = false;
+    let mut embed_id = None;
+    let mut tile = false;
+    let mut foreground = None;
+    let mut background = None;
+    // Pattern colors are wrapped in a:fgClr and a:bgClr
+    let mut in_background = false;
+
+    loop {
+        match reader.read_event_into(buf) {
+            Ok(Event::Start(e) | Event::Empty(e)) => match e.name().as_ref() {
+                b"a:gs" => {
+                    stop_position = utils::attr_value_opt(&e, b"pos")
+                        .and_then(|pos| pos.parse::<f64>().ok())
+                        .map(|pos| pos / 100_000.0);
+                }
+                b"a:lin" => {
+                    // 60000ths of a degree
+                    angle = utils::attr_value_opt(&e, b"ang")
+                        .and_then(|ang| ang.parse::<f64>().ok())
+                        .map_or(0.0, |ang| ang / 60_000.0);
+                }
+                b"a:path" => radial = true,
+                b"a:blip" => embed_id = utils::attr_value_opt(&e, b"r:embed"),
+                b"a:tile" => tile = true,
+                b"a:fgClr" => in_background = false,
+                b"a:bgClr" => in_background = true,
+                _ => {
+                    if let Some(color) = color(&e) {
+                        if let Some(position) = stop_position.take() {
+                            stops.push(GradientStop { position, color });
+                        } else if in_background {
+                            background = Some(color);
+                        } else {
+                            foreground = Some(color);
+                        }
+                    }
+                }
+            },
+            Ok(Event::End(e)) if e.name().as_ref() == end.as_slice() => break,
+            // A stop whose color could not be read
+            Ok(Event::End(e)) if e.name().as_ref() == b"a:gs" => stop_position = None,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    buf.clear();
+
+    match end.as_slice() {
+        b"a:gradFill" if !stops.is_empty() => {
+            stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+            Some(Fill::Gradient(GradientFill {
+                stops,
+                angle,
+                radial,
+            }))
+        }
+        b"a:blipFill" => embed_id.map(|id| Fill::Picture {
+            resource_id: rels.get(&id).cloned().unwrap_or(id),
+            tile,
+        }),
+        b"a:pattFill" => Some(Fill::Pattern {
+            preset: utils::attr_value_opt(start, b"prst").unwrap_or_else(|| "pct5".to_string()),
+            // The defaults of the schema: black on white
+            foreground: foreground.unwrap_or_else(|| "#000000".to_string()),
+            background: background.unwrap_or_else(|| "#FFFFFF".to_string()),
+        }),
+        _ => None,
+    }
+}
+
+/// Color to show where a fill cannot be drawn: the first gradient stop or
+/// the pattern background
+#[must_use]
+pub fn representative_color(fill: &Fill) -> Option<String> {
+    match fill {
+        Fill::Gradient(gradient) => gradient.stops.first().map(|stop| stop.color.clone()),
+        Fill::Pattern { background, .. } => Some(background.clone()),
+        Fill::Picture { .. } => None,
+    }
+}
+
+/// Color of an `a:srgbClr`, `a:sysClr` or `a:prstClr` element as
+/// `#RRGGBB`
+#[must_use]
+pub fn color(e: &BytesStart<'_>) -> Option<String> {
+    let hex = match e.name().as_ref() {
+        b"a:srgbClr" => utils::attr_value_opt(e, b"val"),
+        b"a:sysClr" => utils::attr_value_opt(e, b"lastClr"),
+        b"a:prstClr" => utils::attr_value_opt(e, b"val").and_then(|name| preset_color(&name)),
+        _ => None,
+    }?;
+    Some(format!("#{hex}"))
+}
+
+/// RGB hex value of the common preset colors
+fn preset_color(name: &str) -> Option<String> {
+    let hex = match name {
+        "black" => "000000",
+        "white" => "FFFFFF",
+        "red" => "FF0000",
+        "green" => "008000",
+        "blue" => "0000FF",
+        "yellow" => "FFFF00",
+        "gray" => "808080",
+        _ => return None,
+    };
+    Some(hex.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(xml: &str) -> Option<Fill> {
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if is_fill(e.name().as_ref()) => {
+                    let start = e.into_owned();
+                    let rels =
+                        HashMap::from([("rId2".to_string(), "../media/image1.png".to_string())]);
+                    return parse_fill(&mut reader, &mut buf, &start, &rels);
+                }
+                Ok(Event::Eof) => return None,
+                _ => buf.clear(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_fill() {
+        let gradient = fill(
+            r#"<p:spPr><a:gradFill rotWithShape="1"><a:gsLst>
+            <a:gs pos="100000"><a:srgbClr val="1F3864"/></a:gs>
+            <a:gs pos="0"><a:srgbClr val="4472C4"><a:lumMod val="60000"/></a:srgbClr></a:gs>
+            <a:gs pos="50000"><a:schemeClr val="accent1"/></a:gs>
+            </a:gsLst><a:lin ang="5400000" scaled="0"/></a:gradFill></p:spPr>"#,
+        )
+        .unwrap();
+        let Fill::Gradient(gradient) = gradient else {
+            panic!("expected a gradient");
+        };
+        assert_eq!(gradient.angle, 90.0);
+        assert!(!gradient.radial);
+        let stops: Vec<_> = gradient
+            .stops
+            .iter()
+            .map(|stop| (stop.position, stop.color.as_str()))
+            .collect();
+        assert_eq!(stops, [(0.0, "#4472C4"), (1.0, "#1F3864")]);
+
+        assert_eq!(
+            fill(r#"<a:blipFill><a:blip r:embed="rId2"/><a:tile/></a:blipFill>"#),
+            Some(Fill::Picture {
+                resource_id: "../media/image1.png".to_string(),
+                tile: true,
+            })
+        );
+
+        let pattern = fill(
+            r#"<a:pattFill prst="dkDnDiag"><a:fgClr><a:prstClr val="red"/></a:fgClr>
+            <a:bgClr><a:sysClr val="window" lastClr="FFFFFF"/></a:bgClr></a:pattFill>"#,
+        )
+        .unwrap();
+        assert_eq!(representative_color(&pattern).as_deref(), Some("#FFFFFF"));
+        assert_eq!(
+            pattern,
+            Fill::Pattern {
+                preset: "dkDnDiag".to_string(),
+                foreground: "#FF0000".to_string(),
+                background: "#FFFFFF".to_string(),
+            }
+        );
+
+        assert_eq!(fill("<a:gradFill><a:gsLst/></a:gradFill>"), None);
+    }
+}
@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Font tables and embedded fonts in OOXML packages
+//!
+//! Word lists every font a document uses in `word/fontTable.xml`; fonts
+//! saved with the document are referenced from there (`w:embedRegular` and
+//! friends) and stored obfuscated as `.odttf` parts. Presentations list only
+//! embedded fonts, in the `p:embeddedFontLst` of `ppt/presentation.xml`.
+
+use prism_core::document::FontResource;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::fonts;
+use crate::office::relationships::Relationships;
+use crate::office::utils;
+
+/// Number of leading bytes of an `.odttf` font that are obfuscated
+const OBFUSCATED_LEN: usize = 32;
+
+/// A font family and the faces of it that are embedded
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontEntry {
+    /// Family name
+    pub family: String,
+    /// Embedded faces
+    pub faces: Vec<EmbeddedFace>,
+}
+
+/// Reference to one embedded face of a family
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedFace {
+    /// Style name, e.g. `Bold Italic`
+    pub style: &'static str,
+    /// Relationship ID of the font part
+    pub rel_id: String,
+    /// Obfuscation key (`w:fontKey`), for Word fonts
+    pub font_key: Option<String>,
+}
+
+/// Fonts of a DOCX package, embedded ones with their data
+pub fn docx_fonts<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Vec<FontResource> {
+    let Some(xml) = read_string(archive, "word/fontTable.xml") else {
+        return Vec::new();
+    };
+    let entries = parse_font_table(&xml);
+    let rels = read_string(archive, "word/_rels/fontTable.xml.rels")
+        .and_then(|xml| Relationships::from_xml(&xml).ok())
+        .unwrap_or_default();
+
+    let mut resources = Vec::new();
+    for entry in entries {
+        let mut embedded = false;
+        for face in &entry.faces {
+            let Some(mut data) = rels
+                .get(&face.rel_id)
+                .and_then(|rel| read_bytes(archive, &format!("word/{}", rel.target)))
+            else {
+                continue;
+            };
+            if let Some(key) = &face.font_key {
+                if !deobfuscate(&mut data, key) {
+                    continue;
+                }
+            }
+            resources.push(fonts::embedded_face(&entry.family, face.style, data));
+            embedded = true;
+        }
+        if !embedded {
+            resources.push(FontResource::new(entry.family, "Regular"));
+        }
+    }
+    resources
+}
+
+/// Embedded fonts of a PPTX package
+///
+/// The `.fntdata` parts are kept as stored; they are usually Embedded
+/// OpenType.
+pub fn pptx_fonts<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Vec<FontResource> {
+    let Some(xml) = read_string(archive, "ppt/presentation.xml") else {
+        return Vec::new();
+    };
+    let entries = parse_embedded_font_list(&xml);
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    let rels = read_string(archive, "ppt/_rels/presentation.xml.rels")
+        .and_then(|xml| Relationships::from_xml(&xml).ok())
+        .unwrap_or_default();
+
+    let mut resources = Vec::new();
+    for entry in entries {
+        for face in &entry.faces {
+            if let Some(data) = rels
+                .get(&face.rel_id)
+                .and_then(|rel| read_bytes(archive, &format!("ppt/{}", rel.target)))
+            {
+                resources.push(fonts::embedded_face(&entry.family, face.style, data));
+            }
+        }
+    }
+    resources
+}
+
+/// Parse `word/fontTable.xml`
+#[must_use]
+pub fn parse_font_table(xml: &str) -> Vec<FontEntry> {
+    parse_entries(xml, b"w:font", b"w:name", |name| match name {
+        b"w:embedRegular" => Some("Regular"),
+        b"w:embedBold" => Some("Bold"),
+        b"w:embedItalic" => Some("Italic"),
+        b"w:embedBoldItalic" => Some("Bold Italic"),
+        _ => None,
+    })
+}
+
+/// Parse the `p:embeddedFontLst` of `ppt/presentation.xml`
+#[must_use]
+pub fn parse_embedded_font_list(xml: &str) -> Vec<FontEntry> {
+    parse_entries(xml, b"p:embeddedFont", b"typeface", |name| match name {
+        b"p:regular" => Some("Regular"),
+        b"p:bold" => Some("Bold"),
+        b"p:italic" => Some("Italic"),
+        b"p:boldItalic" => Some("Bold Italic"),
+        _ => None,
+    })
+}
+
+/// Shared walker for both listings: `entry` elements name the family
+/// either themselves (`w:font w:name`) or through a `p:font` child
+/// (`typeface`), and contain one element per embedded face
+fn parse_entries(
+    xml: &str,
+    entry: &[u8],
+    family_attr: &[u8],
+    face_style: impl Fn(&[u8]) -> Option<&'static str>,
+) -> Vec<FontEntry> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut entries = Vec::new();
+    let mut current: Option<FontEntry> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == entry => {
+                current = Some(FontEntry {
+                    family: utils::attr_value_opt(&e, family_attr).unwrap_or_default(),
+                    faces: Vec::new(),
+                });
+            }
+            // A self-closing entry has no faces
+            Ok(Event::Empty(e)) if e.name().as_ref() == entry => {
+                if let Some(family) = utils::attr_value_opt(&e, family_attr) {
+                    entries.push(FontEntry {
+                        family,
+                        faces: Vec::new(),
+                    });
+                }
+            }
+            Ok(Event::Start(e) | Event::Empty(e)) => {
+                let Some(current) = current.as_mut() else {
+                    buf.clear();
+                    continue;
+                };
+                let name = e.name();
+                if name.as_ref() == b"p:font" {
+                    if let Some(family) = utils::attr_value_opt(&e, family_attr) {
+                        current.family = family;
+                    }
+                } else if let Some(style) = face_style(name.as_ref()) {
+                    if let Some(rel_id) = utils::attr_value_opt(&e, b"r:id") {
+                        current.faces.push(EmbeddedFace {
+                            style,
+                            rel_id,
+                            font_key: utils::attr_value_opt(&e, b"w:fontKey"),
+                        });
+                    }
+                }
+            }
+            Ok(Event::End(e)) if e.name().as_ref() == entry => {
+                if let Some(entry) = current.take().filter(|entry| !entry.family.is_empty()) {
+                    entries.push(entry);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    entries
+}
+
+/// Undo `.odttf` obfuscation in place
+///
+/// The first 32 bytes of the font are combined by XOR with the 16 bytes of
+/// the GUID `key`, taken in reverse order (ECMA-376 Part 1, 17.8.1).
+/// Returns `false` when the key is not a GUID or the data is too short.
+pub fn deobfuscate(data: &mut [u8], key: &str) -> bool {
+    let hex: String = key.chars().filter(char::is_ascii_hexdigit).collect();
+    if hex.len() != 32 || data.len() < OBFUSCATED_LEN {
+        return false;
+    }
+    let mut guid = [0u8; 16];
+    for (index, byte) in guid.iter_mut().enumerate() {
+        let Ok(value) = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16) else {
+            return false;
+        };
+        *byte = value;
+    }
+    for (index, byte) in data[..OBFUSCATED_LEN].iter_mut().enumerate() {
+        *byte ^= guid[15 - index % 16];
+    }
+    true
+}
+
+pub(crate) fn read_bytes<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(&name.replace('\\', "/")).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+fn read_string<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    read_bytes(archive, name).and_then(|data| String::from_utf8(data).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_font_table() {
+        let xml = r#"<w:fonts xmlns:w="w" xmlns:r="r">
+            <w:font w:name="Calibri"><w:panose1 w:val="020F0502020204030204"/></w:font>
+            <w:font w:name="Brand Sans">
+                <w:embedRegular r:id="rId1" w:fontKey="{00112233-4455-6677-8899-AABBCCDDEEFF}"/>
+                <w:embedBold r:id="rId2"/>
+            </w:font>
+            <w:font w:name="Symbol"/>
+        </w:fonts>"#;
+        let entries = parse_font_table(xml);
+        let families: Vec<&str> = entries.iter().map(|e| e.family.as_str()).collect();
+        assert_eq!(families, ["Calibri", "Brand Sans", "Symbol"]);
+        assert_eq!(entries[1].faces.len(), 2);
+        assert_eq!(entries[1].faces[1].style, "Bold");
+        assert_eq!(entries[1].faces[1].font_key, None);
+    }
+
+    #[test]
+    fn test_parse_embedded_font_list() {
+        let xml = r#"<p:presentation xmlns:p="p" xmlns:r="r"><p:embeddedFontLst>
+            <p:embeddedFont><p:font typeface="Lato"/><p:regular r:id="rId7"/><p:boldItalic r:id="rId8"/></p:embeddedFont>
+        </p:embeddedFontLst></p:presentation>"#;
+        let entries = parse_embedded_font_list(xml);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].family, "Lato");
+        let styles: Vec<&str> = entries[0].faces.iter().map(|f| f.style).collect();
+        assert_eq!(styles, ["Regular", "Bold Italic"]);
+    }
+
+    #[test]
+    fn test_deobfuscate() {
+        let key = "{00112233-4455-6677-8899-AABBCCDDEEFF}";
+        let mut data = vec![0u8; 40];
+        assert!(deobfuscate(&mut data, key));
+        assert_eq!(data[0], 0xFF);
+        assert_eq!(data[15], 0x00);
+        assert_eq!(data[16], 0xFF);
+        assert_eq!(data[32], 0);
+        // Obfuscation is its own inverse
+        assert!(deobfuscate(&mut data, key));
+        assert!(data.iter().all(|&b| b == 0));
+
+        assert!(!deobfuscate(&mut data, "not-a-guid"));
+        assert!(!deobfuscate(&mut [0u8; 8], key));
+    }
+}
@@ -0,0 +1,567 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Outlines of preset shapes (`a:prstGeom`)
+//!
+//! Office defines close to two hundred preset shapes by formulas over
+//! the shape size and up to eight adjust values (`a:gd` in `a:avLst`, in
+//! 1/100000ths). The common ones are drawn here: basic shapes, block
+//! arrows, stars, callouts, flowchart symbols and connectors. Where the
+//! formulas only shape details, such as the rounded corners of a callout or
+//! the extra bends of a connector, the outline is simplified.
+
+use prism_core::document::{PathCommand, Point};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::hash::BuildHasher;
+
+/// Control point distance of a cubic Bézier quarter circle, as a share of
+/// the radius
+const KAPPA: f64 = 0.552_284_749_8;
+
+/// Outline of `preset` in a `width` by `height` box, relative to its top
+/// left corner, or `None` if the preset is not supported
+///
+/// `adjust` holds the adjust values by guide name (`adj`, `adj1`, ...);
+/// missing ones take the default of the preset.
+#[must_use]
+pub fn preset_path<S: BuildHasher>(
+    preset: &str,
+    width: f64,
+    height: f64,
+    adjust: &HashMap<String, f64, S>,
+) -> Option<Vec<PathCommand>> {
+    // Adjust value `name` as a share, e.g. 0.5 for 50000
+    let adj = |name: &str, default: f64| adjust.get(name).copied().unwrap_or(default) / 100_000.0;
+    if let Some(flowchart) = preset.strip_prefix("flowChart") {
+        flowchart_path(flowchart, width, height)
+    } else if preset.ends_with("Arrow") || preset.starts_with("star") || preset.contains("Callout")
+    {
+        arrow_path(preset, width, height, &adj)
+    } else {
+        basic_path(preset, width, height, &adj)
+    }
+}
+
+/// Outline of a basic shape, such as a diamond or a hexagon, or of a line
+fn basic_path(
+    preset: &str,
+    w: f64,
+    h: f64,
+    adj: &dyn Fn(&str, f64) -> f64,
+) -> Option<Vec<PathCommand>> {
+    let ss = w.min(h);
+    let path = match preset {
+        "rect" => rectangle(w, h),
+        "roundRect" => rounded_rect(w, h, ss * adj("adj", 16_667.0)),
+        "ellipse" => ellipse(w / 2.0, h / 2.0, w / 2.0, h / 2.0),
+        "triangle" => {
+            let apex = w * adj("adj", 50_000.0);
+            polygon(&[(apex, 0.0), (w, h), (0.0, h)])
+        }
+        "rtTriangle" => polygon(&[(0.0, 0.0), (w, h), (0.0, h)]),
+        "diamond" => diamond(w, h),
+        "parallelogram" => {
+            let x = ss * adj("adj", 25_000.0);
+            polygon(&[(x, 0.0), (w, 0.0), (w - x, h), (0.0, h)])
+        }
+        "trapezoid" => {
+            let x = ss * adj("adj", 25_000.0);
+            polygon(&[(0.0, h), (x, 0.0), (w - x, 0.0), (w, h)])
+        }
+        "pentagon" => polygon(&scaled(
+            w,
+            h,
+            &[
+                (0.5, 0.0),
+                (1.0, 0.382),
+                (0.809, 1.0),
+                (0.191, 1.0),
+                (0.0, 0.382),
+            ],
+        )),
+        "hexagon" => hexagon(w, h, ss * adj("adj", 25_000.0)),
+        "octagon" => {
+            let x = ss * adj("adj", 29_289.0);
+            polygon(&[
+                (x, 0.0),
+                (w - x, 0.0),
+                (w, x),
+                (w, h - x),
+                (w - x, h),
+                (x, h),
+                (0.0, h - x),
+                (0.0, x),
+            ])
+        }
+        "homePlate" => {
+            let x = w - ss * adj("adj", 50_000.0);
+            polygon(&[(0.0, 0.0), (x, 0.0), (w, h / 2.0), (x, h), (0.0, h)])
+        }
+        "chevron" => {
+            let x = ss * adj("adj", 50_000.0);
+            polygon(&[
+                (0.0, 0.0),
+                (w - x, 0.0),
+                (w, h / 2.0),
+                (w - x, h),
+                (0.0, h),
+                (x, h / 2.0),
+            ])
+        }
+        "plus" => cross(w, h, ss * adj("adj", 25_000.0)),
+        "line" | "straightConnector1" => vec![
+            PathCommand::MoveTo(Point::new(0.0, 0.0)),
+            PathCommand::LineTo(Point::new(w, h)),
+        ],
+        "bentConnector2" => vec![
+            PathCommand::MoveTo(Point::new(0.0, 0.0)),
+            PathCommand::LineTo(Point::new(w, 0.0)),
+            PathCommand::LineTo(Point::new(w, h)),
+        ],
+        // Connectors with more bends keep only the middle one
+        "bentConnector3" | "bentConnector4" | "bentConnector5" => {
+            let x = w * adj("adj1", 50_000.0);
+            vec![
+                PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                PathCommand::LineTo(Point::new(x, 0.0)),
+                PathCommand::LineTo(Point::new(x, h)),
+                PathCommand::LineTo(Point::new(w, h)),
+            ]
+        }
+        "curvedConnector2" => vec![
+            PathCommand::MoveTo(Point::new(0.0, 0.0)),
+            PathCommand::CurveTo {
+                cp1: Point::new(w / 2.0, 0.0),
+                cp2: Point::new(w, h / 2.0),
+                end: Point::new(w, h),
+            },
+        ],
+        "curvedConnector3" | "curvedConnector4" | "curvedConnector5" => {
+            let x = w * adj("adj1", 50_000.0);
+            vec![
+                PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                PathCommand::CurveTo {
+                    cp1: Point::new(x, 0.0),
+                    cp2: Point::new(x, h),
+                    end: Point::new(w, h),
+                },
+            ]
+        }
+        _ => return None,
+    };
+    Some(path)
+}
+
+/// Outline of a block arrow, a star or a callout
+fn arrow_path(
+    preset: &str,
+    w: f64,
+    h: f64,
+    adj: &dyn Fn(&str, f64) -> f64,
+) -> Option<Vec<PathCommand>> {
+    let ss = w.min(h);
+    let path = match preset {
+        "rightArrow" | "leftArrow" => {
+            let points = arrow(w, h, h * adj("adj1", 50_000.0), ss * adj("adj2", 50_000.0));
+            if preset == "leftArrow" {
+                polygon(&mirror(&points, w, false))
+            } else {
+                polygon(&points)
+            }
+        }
+        "downArrow" | "upArrow" => {
+            // A right arrow turned on its side
+            let points = arrow(h, w, w * adj("adj1", 50_000.0), ss * adj("adj2", 50_000.0));
+            let points: Vec<_> = points.into_iter().map(|(x, y)| (y, x)).collect();
+            if preset == "upArrow" {
+                polygon(&mirror(&points, h, true))
+            } else {
+                polygon(&points)
+            }
+        }
+        "leftRightArrow" => {
+            let thickness = h * adj("adj1", 50_000.0);
+            let head = ss * adj("adj2", 50_000.0);
+            let (top, bottom) = ((h - thickness) / 2.0, (h + thickness) / 2.0);
+            polygon(&[
+                (0.0, h / 2.0),
+                (head, 0.0),
+                (head, top),
+                (w - head, top),
+                (w - head, 0.0),
+                (w, h / 2.0),
+                (w - head, h),
+                (w - head, bottom),
+                (head, bottom),
+                (head, h),
+            ])
+        }
+        "star4" => star(w, h, 4, adj("adj", 12_500.0) * 2.0),
+        "star5" => star(w, h, 5, adj("adj", 19_098.0) * 2.0),
+        "star6" => star(w, h, 6, adj("adj", 28_868.0) * 2.0),
+        "star7" => star(w, h, 7, adj("adj", 34_601.0) * 2.0),
+        "star8" => star(w, h, 8, adj("adj", 38_250.0) * 2.0),
+        "star10" => star(w, h, 10, adj("adj", 42_533.0) * 2.0),
+        "star12" => star(w, h, 12, adj("adj", 37_500.0) * 2.0),
+        "star16" => star(w, h, 16, adj("adj", 37_500.0) * 2.0),
+        "star24" => star(w, h, 24, adj("adj", 37_500.0) * 2.0),
+        "star32" => star(w, h, 32, adj("adj", 37_500.0) * 2.0),
+        "wedgeRectCallout" | "wedgeRoundRectCallout" => {
+            let tip = (
+                w / 2.0 + w * adj("adj1", -20_833.0),
+                h / 2.0 + h * adj("adj2", 62_500.0),
+            );
+            callout(w, h, tip)
+        }
+        _ => return None,
+    };
+    Some(path)
+}
+
+/// Outline of a flowchart symbol, named without its `flowChart` prefix
+fn flowchart_path(symbol: &str, w: f64, h: f64) -> Option<Vec<PathCommand>> {
+    let ss = w.min(h);
+    let path = match symbol {
+        "Process" => rectangle(w, h),
+        "AlternateProcess" => rounded_rect(w, h, ss / 6.0),
+        "Terminator" => rounded_rect(w, h, ss / 2.0),
+        "Connector" => ellipse(w / 2.0, h / 2.0, w / 2.0, h / 2.0),
+        "Extract" => polygon(&[(w / 2.0, 0.0), (w, h), (0.0, h)]),
+        "Merge" => polygon(&[(0.0, 0.0), (w, 0.0), (w / 2.0, h)]),
+        "Decision" => diamond(w, h),
+        "InputOutput" => polygon(&[(w / 5.0, 0.0), (w, 0.0), (w * 0.8, h), (0.0, h)]),
+        "ManualInput" => polygon(&[(0.0, h / 5.0), (w, 0.0), (w, h), (0.0, h)]),
+        "ManualOperation" => polygon(&[(0.0, 0.0), (w, 0.0), (w * 0.8, h), (w / 5.0, h)]),
+        "Preparation" => hexagon(w, h, w / 5.0),
+        "OffpageConnector" => polygon(&[
+            (0.0, 0.0),
+            (w, 0.0),
+            (w, h * 0.8),
+            (w / 2.0, h),
+            (0.0, h * 0.8),
+        ]),
+        "SummingJunction" => cross(w, h, ss / 4.0),
+        "PredefinedProcess" => {
+            let mut path = rectangle(w, h);
+            for x in [w / 8.0, w * 7.0 / 8.0] {
+                path.push(PathCommand::MoveTo(Point::new(x, 0.0)));
+                path.push(PathCommand::LineTo(Point::new(x, h)));
+            }
+            path
+        }
+        "Delay" => {
+            let (rx, ry) = (w / 2.0, h / 2.0);
+            vec![
+                PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                PathCommand::LineTo(Point::new(rx, 0.0)),
+                PathCommand::CurveTo {
+                    cp1: Point::new(rx + rx * KAPPA, 0.0),
+                    cp2: Point::new(w, ry - ry * KAPPA),
+                    end: Point::new(w, ry),
+                },
+                PathCommand::CurveTo {
+                    cp1: Point::new(w, ry + ry * KAPPA),
+                    cp2: Point::new(rx + rx * KAPPA, h),
+                    end: Point::new(rx, h),
+                },
+                PathCommand::LineTo(Point::new(0.0, h)),
+                PathCommand::Close,
+            ]
+        }
+        _ => return None,
+    };
+    Some(path)
+}
+
+/// Whether the outline of `preset` is open, so it is stroked but never
+/// filled
+#[must_use]
+pub fn is_open(preset: &str) -> bool {
+    matches!(preset, "line" | "straightConnector1")
+        || preset.starts_with("bentConnector")
+        || preset.starts_with("curvedConnector")
+}
+
+/// Closed path through `points`
+fn polygon(points: &[(f64, f64)]) -> Vec<PathCommand> {
+    let mut path: Vec<_> = points
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| {
+            let point = Point::new(x, y);
+            if i == 0 {
+                PathCommand::MoveTo(point)
+            } else {
+                PathCommand::LineTo(point)
+            }
+        })
+        .collect();
+    path.push(PathCommand::Close);
+    path
+}
+
+fn rectangle(w: f64, h: f64) -> Vec<PathCommand> {
+    polygon(&[(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)])
+}
+
+fn diamond(w: f64, h: f64) -> Vec<PathCommand> {
+    polygon(&[(w / 2.0, 0.0), (w, h / 2.0), (w / 2.0, h), (0.0, h / 2.0)])
+}
+
+/// Plus sign with arms `x` from the edges of the box
+fn cross(w: f64, h: f64, x: f64) -> Vec<PathCommand> {
+    polygon(&[
+        (x, 0.0),
+        (w - x, 0.0),
+        (w - x, x),
+        (w, x),
+        (w, h - x),
+        (w - x, h - x),
+        (w - x, h),
+        (x, h),
+        (x, h - x),
+        (0.0, h - x),
+        (0.0, x),
+        (x, x),
+    ])
+}
+
+/// `points` given as shares of a `width` by `height` box
+fn scaled(width: f64, height: f64, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    points
+        .iter()
+        .map(|&(x, y)| (x * width, y * height))
+        .collect()
+}
+
+/// `points` mirrored across the middle of a box `size` wide (or high, if
+/// `vertical`)
+fn mirror(points: &[(f64, f64)], size: f64, vertical: bool) -> Vec<(f64, f64)> {
+    points
+        .iter()
+        .map(|&(x, y)| {
+            if vertical {
+                (x, size - y)
+            } else {
+                (size - x, y)
+            }
+        })
+        .collect()
+}
+
+/// Outline of a right arrow with a shaft `thickness` high and a head
+/// `head` long
+fn arrow(w: f64, h: f64, thickness: f64, head: f64) -> Vec<(f64, f64)> {
+    let (top, bottom) = ((h - thickness) / 2.0, (h + thickness) / 2.0);
+    let x = w - head;
+    vec![
+        (0.0, top),
+        (x, top),
+        (x, 0.0),
+        (w, h / 2.0),
+        (x, h),
+        (x, bottom),
+        (0.0, bottom),
+    ]
+}
+
+fn hexagon(w: f64, h: f64, x: f64) -> Vec<PathCommand> {
+    polygon(&[
+        (x, 0.0),
+        (w - x, 0.0),
+        (w, h / 2.0),
+        (w - x, h),
+        (x, h),
+        (0.0, h / 2.0),
+    ])
+}
+
+/// Rectangle with corners rounded to `radius`
+fn rounded_rect(w: f64, h: f64, radius: f64) -> Vec<PathCommand> {
+    let r = radius.min(w / 2.0).min(h / 2.0).max(0.0);
+    let k = r * (1.0 - KAPPA);
+    vec![
+        PathCommand::MoveTo(Point::new(r, 0.0)),
+        PathCommand::LineTo(Point::new(w - r, 0.0)),
+        PathCommand::CurveTo {
+            cp1: Point::new(w - k, 0.0),
+            cp2: Point::new(w, k),
+            end: Point::new(w, r),
+        },
+        PathCommand::LineTo(Point::new(w, h - r)),
+        PathCommand::CurveTo {
+            cp1: Point::new(w, h - k),
+            cp2: Point::new(w - k, h),
+            end: Point::new(w - r, h),
+        },
+        PathCommand::LineTo(Point::new(r, h)),
+        PathCommand::CurveTo {
+            cp1: Point::new(k, h),
+            cp2: Point::new(0.0, h - k),
+            end: Point::new(0.0, h - r),
+        },
+        PathCommand::LineTo(Point::new(0.0, r)),
+        PathCommand::CurveTo {
+            cp1: Point::new(0.0, k),
+            cp2: Point::new(k, 0.0),
+            end: Point::new(r, 0.0),
+        },
+        PathCommand::Close,
+    ]
+}
+
+/// Ellipse centered on (`cx`, `cy`) with radii `rx` and `ry`
+pub(crate) fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<PathCommand> {
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    vec![
+        PathCommand::MoveTo(Point::new(cx, cy - ry)),
+        PathCommand::CurveTo {
+            cp1: Point::new(cx + kx, cy - ry),
+            cp2: Point::new(cx + rx, cy - ky),
+            end: Point::new(cx + rx, cy),
+        },
+        PathCommand::CurveTo {
+            cp1: Point::new(cx + rx, cy + ky),
+            cp2: Point::new(cx + kx, cy + ry),
+            end: Point::new(cx, cy + ry),
+        },
+        PathCommand::CurveTo {
+            cp1: Point::new(cx - kx, cy + ry),
+            cp2: Point::new(cx - rx, cy + ky),
+            end: Point::new(cx - rx, cy),
+        },
+        PathCommand::CurveTo {
+            cp1: Point::new(cx - rx, cy - ky),
+            cp2: Point::new(cx - kx, cy - ry),
+            end: Point::new(cx, cy - ry),
+        },
+        PathCommand::Close,
+    ]
+}
+
+/// Star with `points` points, the inner vertices at `inner` times the
+/// outer radius, pointing up
+fn star(w: f64, h: f64, points: u32, inner: f64) -> Vec<PathCommand> {
+    let (rx, ry) = (w / 2.0, h / 2.0);
+    let vertices: Vec<_> = (0..points * 2)
+        .map(|i| {
+            let angle = -PI / 2.0 + PI * f64::from(i) / f64::from(points);
+            let scale = if i % 2 == 0 { 1.0 } else { inner };
+            (rx + rx * scale * angle.cos(), ry + ry * scale * angle.sin())
+        })
+        .collect();
+    polygon(&vertices)
+}
+
+/// Rectangle with a wedge pointing at `tip`, based on the side facing it
+fn callout(w: f64, h: f64, tip: (f64, f64)) -> Vec<PathCommand> {
+    let (dx, dy) = (tip.0 - w / 2.0, tip.1 - h / 2.0);
+    let inside = dx.abs() <= w / 2.0 && dy.abs() <= h / 2.0;
+    if inside {
+        return rectangle(w, h);
+    }
+    // The wedge starts between 1/6 and 1/3 of the side, from the end
+    // nearer the tip
+    let base = |length: f64, toward_end: bool| {
+        if toward_end {
+            (length * 2.0 / 3.0, length * 5.0 / 6.0)
+        } else {
+            (length / 6.0, length / 3.0)
+        }
+    };
+    let mut points = Vec::with_capacity(7);
+    if dy.abs() / h >= dx.abs() / w {
+        let (a, b) = base(w, dx > 0.0);
+        if dy > 0.0 {
+            // Bottom side, drawn right to left
+            points.extend([(0.0, 0.0), (w, 0.0), (w, h), (b, h), tip, (a, h), (0.0, h)]);
+        } else {
+            points.extend([
+                (0.0, 0.0),
+                (a, 0.0),
+                tip,
+                (b, 0.0),
+                (w, 0.0),
+                (w, h),
+                (0.0, h),
+            ]);
+        }
+    } else {
+        let (a, b) = base(h, dy > 0.0);
+        if dx > 0.0 {
+            points.extend([(0.0, 0.0), (w, 0.0), (w, a), tip, (w, b), (w, h), (0.0, h)]);
+        } else {
+            // Left side, drawn bottom to top
+            points.extend([
+                (0.0, 0.0),
+                (w, 0.0),
+                (w, h),
+                (0.0, h),
+                (0.0, b),
+                tip,
+                (0.0, a),
+            ]);
+        }
+    }
+    polygon(&points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(path: &[PathCommand]) -> Vec<(f64, f64)> {
+        path.iter()
+            .filter_map(|command| match command {
+                PathCommand::MoveTo(p) | PathCommand::LineTo(p) => Some((p.x, p.y)),
+                PathCommand::CurveTo { end, .. } | PathCommand::QuadTo { end, .. } => {
+                    Some((end.x, end.y))
+                }
+                PathCommand::Close => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_preset_path() {
+        let defaults: HashMap<String, f64> = HashMap::new();
+        let diamond = preset_path("flowChartDecision", 100.0, 50.0, &defaults).unwrap();
+        assert_eq!(
+            points(&diamond),
+            [(50.0, 0.0), (100.0, 25.0), (50.0, 50.0), (0.0, 25.0)]
+        );
+
+        // Shaft half the height, head as long as half the shorter side
+        let arrow = preset_path("rightArrow", 200.0, 40.0, &defaults).unwrap();
+        assert_eq!(
+            points(&arrow)[..4],
+            [(0.0, 10.0), (180.0, 10.0), (180.0, 0.0), (200.0, 20.0)]
+        );
+        let thin = HashMap::from([("adj1".to_string(), 20_000.0)]);
+        let arrow = preset_path("leftArrow", 200.0, 40.0, &thin).unwrap();
+        assert_eq!(points(&arrow)[..2], [(200.0, 16.0), (20.0, 16.0)]);
+
+        let star = preset_path("star5", 100.0, 100.0, &defaults).unwrap();
+        let star = points(&star);
+        assert_eq!(star.len(), 10);
+        assert!((star[0].0 - 50.0).abs() < 1e-9 && star[0].1.abs() < 1e-9);
+
+        let rounded = preset_path("roundRect", 60.0, 60.0, &defaults).unwrap();
+        assert!(matches!(rounded[0], PathCommand::MoveTo(p) if (p.x - 10.0).abs() < 0.01));
+
+        let callout = preset_path("wedgeRectCallout", 120.0, 60.0, &defaults).unwrap();
+        // Tail from the left part of the bottom side, below and left of
+        // the center by default
+        let callout = points(&callout);
+        assert_eq!(callout[3], (40.0, 60.0));
+        assert!((callout[4].0 - 35.0).abs() < 0.01 && (callout[4].1 - 67.5).abs() < 0.01);
+        assert_eq!(callout[5], (20.0, 60.0));
+
+        assert!(preset_path("cloud", 10.0, 10.0, &defaults).is_none());
+        assert!(is_open("line") && is_open("bentConnector3"));
+        let elbow = preset_path("bentConnector3", 100.0, 40.0, &defaults).unwrap();
+        assert_eq!(
+            points(&elbow),
+            [(0.0, 0.0), (50.0, 0.0), (50.0, 40.0), (100.0, 40.0)]
+        );
+    }
+}
@@ -9,8 +9,8 @@ use calamine::Reader;
 use cfb::CompoundFile;
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, PageMetadata, ShapeStyle, TextBlock, TextRun,
-        TextStyle,
+        ContentBlock, Dimensions, Document, Page, PageMetadata, ShapeStyle, TextBlock,
+        TextDirection, TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
@@ -20,6 +20,9 @@ use prism_core::{
 use std::io::Cursor;
 use tracing::{debug, info, warn};
 
+use crate::office::cells;
+use crate::security;
+
 /// Legacy DOC parser (Word 97-2003)
 #[derive(Debug, Clone)]
 pub struct DocParser;
@@ -37,7 +40,7 @@ impl DocParser {
     fn extract_text_from_doc(data: &[u8]) -> Result<Vec<String>> {
         let cursor = Cursor::new(data);
         let mut comp = CompoundFile::open(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open OLE2 file: {}", e)))?;
+            .map_err(|e| Error::corrupt("DOC", format!("Failed to open OLE2 container: {e}")))?;
 
         // Try to find WordDocument stream
         let mut text_parts = Vec::new();
@@ -119,6 +122,7 @@ impl Parser for DocParser {
             "Parsing DOC file, size: {} bytes, filename: {:?}",
             context.size, context.filename
         );
+        let active_content = security::check(&data, "DOC", &context.options)?;
 
         let text_parts = Self::extract_text_from_doc(&data)?;
 
@@ -130,10 +134,12 @@ impl Parser for DocParser {
             }
 
             let text_run = TextRun {
-                text: text.clone(),
+                text: text.as_str().into(),
                 style: TextStyle::default(),
                 bounds: None,
                 char_positions: None,
+                link: None,
+                revision: None,
             };
 
             let text_block = TextBlock {
@@ -142,6 +148,7 @@ impl Parser for DocParser {
                 bounds: prism_core::document::Rect::default(),
                 style: ShapeStyle::default(),
                 rotation: 0.0,
+                direction: TextDirection::Auto,
             };
 
             content_blocks.push(ContentBlock::Text(text_block));
@@ -155,6 +162,9 @@ impl Parser for DocParser {
             metadata: PageMetadata {
                 label: None,
                 rotation: 0,
+                notes: None,
+                message: None,
+                sheet: None,
             },
         };
 
@@ -163,6 +173,7 @@ impl Parser for DocParser {
             metadata.title = Some(filename);
         }
         metadata.add_custom("format", "DOC");
+        metadata.active_content = active_content;
         metadata.add_custom("legacy_format", true);
 
         let mut document = Document::builder().metadata(metadata).build();
@@ -238,97 +249,84 @@ impl Parser for XlsParser {
             "Parsing XLS file, size: {} bytes, filename: {:?}",
             context.size, context.filename
         );
+        let active_content = security::check(&data, "XLS", &context.options)?;
 
-        // Try using calamine which supports XLS format
         let cursor = Cursor::new(data.as_ref());
-        match calamine::open_workbook_auto_from_rs(cursor) {
-            Ok(mut workbook) => {
-                // Use the same logic as XLSX parser
-                let sheet_names = workbook.sheet_names().to_vec();
-                let mut pages = Vec::new();
-
-                for (idx, name) in sheet_names.iter().enumerate() {
-                    if let Ok(range) = workbook.worksheet_range(name) {
-                        // Create table-like content from cells
-                        let mut content_blocks = Vec::new();
-
-                        for row in range.rows() {
-                            let mut row_text = String::new();
-                            for cell in row {
-                                let cell_text = format!("{}\t", cell);
-                                row_text.push_str(&cell_text);
-                            }
-
-                            if !row_text.trim().is_empty() {
-                                let text_run = TextRun {
-                                    text: row_text,
-                                    style: TextStyle::default(),
-                                    bounds: None,
-                                    char_positions: None,
-                                };
-
-                                let text_block = TextBlock {
-                                    runs: vec![text_run],
-                                    paragraph_style: None,
-                                    bounds: prism_core::document::Rect::default(),
-                                    style: ShapeStyle::default(),
-                                    rotation: 0.0,
-                                };
-
-                                content_blocks.push(ContentBlock::Text(text_block));
-                            }
-                        }
-
-                        let page = Page {
-                            number: (idx + 1) as u32,
-                            dimensions: Dimensions::LETTER,
-                            content: content_blocks,
-                            annotations: vec![],
-                            metadata: PageMetadata {
-                                label: Some(name.clone()),
-                                rotation: 0,
-                            },
-                        };
-
-                        pages.push(page);
-                    }
+        let mut workbook = calamine::open_workbook_auto_from_rs(cursor).map_err(|e| {
+            warn!("Failed to parse XLS with calamine: {}", e);
+            Error::ParseError(format!("Failed to parse XLS: {e}"))
+        })?;
+
+        // Each sheet becomes a page holding its cell grid, as for XLSX
+        let sheet_names = workbook.sheet_names().to_vec();
+        let mut diagnostics = Vec::new();
+        let mut pages = Vec::new();
+
+        for (idx, name) in sheet_names.iter().enumerate() {
+            let range = match workbook.worksheet_range(name) {
+                Ok(range) => range,
+                Err(e) => {
+                    context.options.recover(
+                        Error::corrupt("XLS", format!("Failed to read sheet '{name}': {e}")),
+                        &mut diagnostics,
+                    )?;
+                    continue;
                 }
+            };
 
-                if pages.is_empty() {
-                    pages.push(Page {
-                        number: 1,
-                        dimensions: Dimensions::LETTER,
-                        content: vec![],
-                        annotations: vec![],
-                        metadata: PageMetadata {
-                            label: None,
-                            rotation: 0,
-                        },
-                    });
-                }
+            pages.push(Page {
+                number: (idx + 1) as u32,
+                dimensions: Dimensions::LETTER,
+                content: cells::sheet_table(&range)
+                    .map(ContentBlock::Table)
+                    .into_iter()
+                    .collect(),
+                annotations: vec![],
+                metadata: PageMetadata {
+                    label: Some(name.clone()),
+                    rotation: 0,
+                    notes: None,
+                    message: None,
+                    sheet: None,
+                },
+            });
+        }
 
-                let mut metadata = Metadata::new();
-                if let Some(filename) = context.filename {
-                    metadata.title = Some(filename);
-                }
-                metadata.add_custom("format", "XLS");
-                metadata.add_custom("legacy_format", true);
+        if pages.is_empty() {
+            pages.push(Page {
+                number: 1,
+                dimensions: Dimensions::LETTER,
+                content: vec![],
+                annotations: vec![],
+                metadata: PageMetadata {
+                    label: None,
+                    rotation: 0,
+                    notes: None,
+                    message: None,
+                    sheet: None,
+                },
+            });
+        }
+
+        let mut metadata = Metadata::new();
+        if let Some(filename) = context.filename {
+            metadata.title = Some(filename);
+        }
+        metadata.add_custom("format", "XLS");
+        metadata.active_content = active_content;
+        metadata.add_custom("legacy_format", true);
 
-                let page_count = pages.len();
-                metadata.add_custom("sheet_count", page_count as i64);
+        let page_count = pages.len();
+        metadata.add_custom("sheet_count", page_count as i64);
+        metadata.add_custom("excel_sheet_names", sheet_names.join(", "));
 
-                let mut document = Document::builder().metadata(metadata).build();
-                document.pages = pages;
+        let mut document = Document::builder().metadata(metadata).build();
+        document.pages = pages;
+        document.diagnostics = diagnostics;
 
-                info!("Successfully parsed XLS with {} sheets", page_count);
+        info!("Successfully parsed XLS with {} sheets", page_count);
 
-                Ok(document)
-            }
-            Err(e) => {
-                warn!("Failed to parse XLS with calamine: {}", e);
-                Err(Error::ParseError(format!("Failed to parse XLS: {}", e)))
-            }
-        }
+        Ok(document)
     }
 
     fn metadata(&self) -> ParserMetadata {
@@ -397,10 +395,11 @@ impl Parser for PptParser {
             "Parsing PPT file, size: {} bytes, filename: {:?}",
             context.size, context.filename
         );
+        let active_content = security::check(&data, "PPT", &context.options)?;
 
         let cursor = Cursor::new(data.as_ref());
         let mut comp = CompoundFile::open(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open PPT file: {}", e)))?;
+            .map_err(|e| Error::corrupt("PPT", format!("Failed to open OLE2 container: {e}")))?;
 
         // Extract basic text - PPT format is very complex
         let mut text_parts = Vec::new();
@@ -430,10 +429,12 @@ impl Parser for PptParser {
             }
 
             let text_run = TextRun {
-                text: text.clone(),
+                text: text.as_str().into(),
                 style: TextStyle::default(),
                 bounds: None,
                 char_positions: None,
+                link: None,
+                revision: None,
             };
 
             let text_block = TextBlock {
@@ -442,6 +443,7 @@ impl Parser for PptParser {
                 bounds: prism_core::document::Rect::default(),
                 style: ShapeStyle::default(),
                 rotation: 0.0,
+                direction: TextDirection::Auto,
             };
 
             content_blocks.push(ContentBlock::Text(text_block));
@@ -455,6 +457,9 @@ impl Parser for PptParser {
             metadata: PageMetadata {
                 label: Some("Slide 1".to_string()),
                 rotation: 0,
+                notes: None,
+                message: None,
+                sheet: None,
             },
         };
 
@@ -463,6 +468,7 @@ impl Parser for PptParser {
             metadata.title = Some(filename);
         }
         metadata.add_custom("format", "PPT");
+        metadata.active_content = active_content;
         metadata.add_custom("legacy_format", true);
 
         let mut document = Document::builder().metadata(metadata).build();
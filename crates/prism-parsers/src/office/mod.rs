@@ -4,12 +4,23 @@
 //! Parsers for Microsoft Office Open XML formats (DOCX, XLSX, PPTX)
 //! and legacy Office binary formats.
 
+pub mod cells;
+pub mod charts;
+pub mod comments;
+pub mod controls;
 pub mod docx;
+pub mod embeddings;
 pub mod excel_styles;
+pub mod fills;
+pub mod fonts;
+pub mod geometry;
 pub mod legacy;
+pub mod numbering;
+pub mod package;
 pub mod pptx;
 pub mod relationships;
 pub mod shapes;
+pub mod sheets;
 pub mod slides;
 pub mod styles;
 pub mod tables;
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! DOCX list numbering (`word/numbering.xml`)
+//!
+//! A numbered paragraph refers to a numbering instance (`w:num`) and a
+//! level. The instance points to an abstract definition holding, per level,
+//! the number format and the marker text, e.g. `%1.` or `%1.%2)`. Instances
+//! of the same definition share their counters unless they restart them
+//! with `w:startOverride`, as Word does.
+
+use prism_core::document::{ContentBlock, ListItem, ListMarker};
+use prism_core::error::{Error, Result};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+
+use crate::office::utils;
+
+/// One level of an abstract numbering definition
+#[derive(Debug, Clone)]
+struct Level {
+    start: u32,
+    format: ListMarker,
+    /// Marker text with `%1`-`%9` standing for the level counters
+    text: String,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            start: 1,
+            format: ListMarker::Decimal,
+            text: String::new(),
+        }
+    }
+}
+
+/// A numbering instance
+#[derive(Debug, Clone, Default)]
+struct Instance {
+    abstract_id: String,
+    /// Levels whose counter restarts at the given value
+    start_overrides: HashMap<u8, u32>,
+}
+
+/// Numbering definitions of a document
+#[derive(Debug, Clone, Default)]
+pub struct Numbering {
+    abstracts: HashMap<String, HashMap<u8, Level>>,
+    instances: HashMap<String, Instance>,
+}
+
+impl Numbering {
+    /// Create an empty set of definitions, for documents without numbering
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `word/numbering.xml`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParseError`] if the XML is malformed.
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut numbering = Self::new();
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+
+        // Abstract definition, or instance, being read
+        let mut abstract_id: Option<String> = None;
+        let mut instance: Option<(String, Instance)> = None;
+        let mut level: Option<(u8, Level)> = None;
+        let mut override_level: Option<u8> = None;
+
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) => match e.name().as_ref() {
+                    b"w:abstractNum" => abstract_id = attr(&e, b"w:abstractNumId"),
+                    b"w:num" => {
+                        instance = attr(&e, b"w:numId").map(|id| (id, Instance::default()));
+                    }
+                    b"w:lvl" if instance.is_none() => {
+                        level = ilvl(&e).map(|ilvl| (ilvl, Level::default()));
+                    }
+                    b"w:lvlOverride" => override_level = ilvl(&e),
+                    _ => {}
+                },
+                Ok(Event::Empty(e)) => {
+                    let value = attr(&e, b"w:val");
+                    match (e.name().as_ref(), &mut level, &mut instance) {
+                        (b"w:start", Some((_, level)), _) => {
+                            level.start = value.and_then(|v| v.parse().ok()).unwrap_or(1);
+                        }
+                        (b"w:numFmt", Some((_, level)), _) => {
+                            level.format = marker(value.as_deref().unwrap_or_default());
+                        }
+                        (b"w:lvlText", Some((_, level)), _) => {
+                            level.text = value.unwrap_or_default();
+                        }
+                        (b"w:abstractNumId", _, Some((_, instance))) => {
+                            instance.abstract_id = value.unwrap_or_default();
+                        }
+                        (b"w:startOverride", _, Some((_, instance))) => {
+                            if let (Some(ilvl), Some(start)) =
+                                (override_level, value.and_then(|v| v.parse().ok()))
+                            {
+                                instance.start_overrides.insert(ilvl, start);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Event::End(e)) => match e.name().as_ref() {
+                    b"w:lvl" => {
+                        if let (Some(id), Some((ilvl, level))) = (&abstract_id, level.take()) {
+                            numbering
+                                .abstracts
+                                .entry(id.clone())
+                                .or_default()
+                                .insert(ilvl, level);
+                        }
+                    }
+                    b"w:abstractNum" => abstract_id = None,
+                    b"w:num" => {
+                        if let Some((id, instance)) = instance.take() {
+                            numbering.instances.insert(id, instance);
+                        }
+                    }
+                    b"w:lvlOverride" => override_level = None,
+                    _ => {}
+                },
+                Ok(Event::Eof) => break,
+                Err(e) => {
+                    return Err(Error::ParseError(format!(
+                        "Failed to parse numbering.xml: {e}"
+                    )))
+                }
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(numbering)
+    }
+
+    fn level(&self, num_id: &str, ilvl: u8) -> Option<(&Instance, &Level)> {
+        let instance = self.instances.get(num_id)?;
+        let level = self.abstracts.get(&instance.abstract_id)?.get(&ilvl)?;
+        Some((instance, level))
+    }
+}
+
+/// Running counters while numbering the paragraphs of a document in order
+#[derive(Debug)]
+pub struct ListCounters<'a> {
+    numbering: &'a Numbering,
+    /// Current value per abstract definition and level
+    counters: HashMap<String, HashMap<u8, u32>>,
+    /// Instances whose start overrides have been applied
+    started: HashSet<String>,
+}
+
+impl<'a> ListCounters<'a> {
+    /// Start counting with every counter unset
+    #[must_use]
+    pub fn new(numbering: &'a Numbering) -> Self {
+        Self {
+            numbering,
+            counters: HashMap::new(),
+            started: HashSet::new(),
+        }
+    }
+
+    /// The list item for the next paragraph numbered with `num_id` at `ilvl`
+    ///
+    /// Returns `None` if the numbering is not defined.
+    pub fn item(&mut self, num_id: &str, ilvl: u8, content: Vec<ContentBlock>) -> Option<ListItem> {
+        let (instance, level) = self.numbering.level(num_id, ilvl)?;
+        let levels = self
+            .counters
+            .entry(instance.abstract_id.clone())
+            .or_default();
+
+        if self.started.insert(num_id.to_string()) {
+            for (&ilvl, &start) in &instance.start_overrides {
+                levels.insert(ilvl, start.saturating_sub(1));
+            }
+        }
+        let value = levels
+            .get(&ilvl)
+            .map_or(level.start, |value| value.saturating_add(1));
+        levels.insert(ilvl, value);
+        // Deeper levels start over under a new item
+        levels.retain(|&other, _| other <= ilvl);
+
+        if !level.format.is_ordered() {
+            let mut item = ListItem::bullet(content, ilvl);
+            item.marker_style = level.format;
+            item.marker = match level.format {
+                ListMarker::None => String::new(),
+                _ => bullet(&level.text),
+            };
+            return Some(item);
+        }
+
+        let mut marker = level.text.clone();
+        for other in 0..=ilvl {
+            let Some((_, outer)) = self.numbering.level(num_id, other) else {
+                continue;
+            };
+            let value = levels.get(&other).copied().unwrap_or(outer.start);
+            marker = marker.replace(&format!("%{}", other + 1), &outer.format.label(value));
+        }
+        let mut item = ListItem::numbered(content, ilvl, level.format, value);
+        if !marker.is_empty() {
+            item.marker = marker;
+        }
+        Some(item)
+    }
+}
+
+/// Attribute value with entities resolved; bullet glyphs are often
+/// written as character references
+fn attr(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    let attr = e
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == name)?;
+    let value = utils::attr_value(&attr.value);
+    Some(unescape(&value).map_or_else(|_| value.clone(), std::borrow::Cow::into_owned))
+}
+
+fn ilvl(e: &BytesStart<'_>) -> Option<u8> {
+    attr(e, b"w:ilvl").and_then(|value| value.parse().ok())
+}
+
+/// Marker style for a `w:numFmt` value
+fn marker(format: &str) -> ListMarker {
+    match format {
+        "bullet" => ListMarker::Bullet,
+        "none" => ListMarker::None,
+        "lowerLetter" => ListMarker::LowerAlpha,
+        "upperLetter" => ListMarker::UpperAlpha,
+        "lowerRoman" => ListMarker::LowerRoman,
+        "upperRoman" => ListMarker::UpperRoman,
+        _ => ListMarker::Decimal,
+    }
+}
+
+/// Displayable bullet for a bullet level's text
+///
+/// Bullets are usually symbol-font glyphs: private-use code points from
+/// Symbol or Wingdings, or letters such as `o` set in Courier New.
+fn bullet(text: &str) -> String {
+    match text.chars().next() {
+        Some('o') => "◦".to_string(),
+        Some('§') => "▪".to_string(),
+        Some(c) if !('\u{E000}'..='\u{F8FF}').contains(&c) && !c.is_alphanumeric() => {
+            text.to_string()
+        }
+        _ => "•".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUMBERING: &str = r#"<?xml version="1.0"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:abstractNum w:abstractNumId="0">
+    <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>
+    <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/><w:lvlText w:val="%1.%2)"/></w:lvl>
+  </w:abstractNum>
+  <w:abstractNum w:abstractNumId="1">
+    <w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val="&#xF0B7;"/></w:lvl>
+  </w:abstractNum>
+  <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+  <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+  <w:num w:numId="3"><w:abstractNumId w:val="0"/>
+    <w:lvlOverride w:ilvl="0"><w:startOverride w:val="1"/></w:lvlOverride>
+  </w:num>
+</w:numbering>"#;
+
+    #[test]
+    fn test_list_counters() {
+        let numbering = Numbering::from_xml(NUMBERING).unwrap();
+        let mut counters = ListCounters::new(&numbering);
+        let mut marker = |num_id: &str, ilvl: u8| {
+            counters
+                .item(num_id, ilvl, Vec::new())
+                .map(|item| (item.ordered, item.marker))
+        };
+
+        assert_eq!(marker("1", 0), Some((true, "1.".to_string())));
+        assert_eq!(marker("1", 1), Some((true, "1.a)".to_string())));
+        assert_eq!(marker("1", 1), Some((true, "1.b)".to_string())));
+        assert_eq!(marker("1", 0), Some((true, "2.".to_string())));
+        assert_eq!(marker("1", 1), Some((true, "2.a)".to_string())));
+        assert_eq!(marker("2", 0), Some((false, "•".to_string())));
+        // A restarted instance of the same definition
+        assert_eq!(marker("3", 0), Some((true, "1.".to_string())));
+        assert_eq!(marker("9", 0), None);
+    }
+}
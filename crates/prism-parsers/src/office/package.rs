@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Opening OOXML packages, including damaged ones
+//!
+//! A ZIP file is read through its central directory at the end of the
+//! file, so a truncated download cannot be opened at all even though most
+//! of its entries are intact. In lenient mode the package is rebuilt from
+//! the local file headers that precede each entry instead.
+
+use flate2::read::DeflateDecoder;
+use prism_core::diagnostics::Diagnostic;
+use prism_core::error::{Error, ErrorCode, Result};
+use prism_core::parser::ParseOptions;
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::encryption;
+
+/// Signature of a ZIP local file header
+const LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+
+/// Size of the fixed part of a local file header
+const LOCAL_HEADER_LEN: usize = 30;
+
+/// General purpose flag: sizes follow the data in a data descriptor
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// Bytes of a package that [`ZipArchive`] can open
+///
+/// Returns `data` unchanged when it opens. Otherwise, in lenient mode, the
+/// entries that can be recovered are repacked and a diagnostic is added;
+/// in strict mode, or when nothing can be recovered, a corrupt-file error
+/// is returned.
+///
+/// # Errors
+///
+/// Returns [`Error::EncryptedDocument`] when the package is encrypted and
+/// [`Error::CorruptFile`] when it cannot be opened.
+pub fn package_bytes<'a>(
+    data: &'a [u8],
+    format: &str,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Cow<'a, [u8]>> {
+    encryption::check_package(data, format)?;
+    let error = match ZipArchive::new(Cursor::new(data)) {
+        Ok(_) => return Ok(Cow::Borrowed(data)),
+        Err(e) => Error::corrupt(format, format!("Failed to open ZIP package: {e}")),
+    };
+    if !options.lenient {
+        return Err(error);
+    }
+
+    let (repacked, recovered) = salvage(data).ok_or(error)?;
+    diagnostics.push(Diagnostic::warning(
+        ErrorCode::CorruptFile,
+        format!(
+            "Damaged ZIP central directory; rebuilt the package from {recovered} readable entries"
+        ),
+    ));
+    Ok(Cow::Owned(repacked))
+}
+
+/// Rebuild a ZIP file from its local file headers
+///
+/// Returns the new archive and the number of entries it holds, or `None`
+/// if no entry could be read. Entries cut off by truncation are dropped.
+#[must_use]
+pub fn salvage(data: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut recovered = 0;
+    let mut offset = 0;
+
+    while let Some(found) = find(&data[offset..], LOCAL_HEADER) {
+        let start = offset + found;
+        match read_entry(data, start) {
+            Some((name, content, end)) => {
+                if !name.ends_with('/') && writer.start_file(name, options).is_ok() {
+                    writer.write_all(&content).ok()?;
+                    recovered += 1;
+                }
+                offset = end;
+            }
+            None => offset = start + LOCAL_HEADER.len(),
+        }
+    }
+
+    if recovered == 0 {
+        return None;
+    }
+    let repacked = writer.finish().ok()?.into_inner();
+    Some((repacked, recovered))
+}
+
+/// Read the entry whose local header starts at `start`
+///
+/// Returns the entry name, its uncompressed content and the offset just
+/// past its data.
+fn read_entry(data: &[u8], start: usize) -> Option<(String, Vec<u8>, usize)> {
+    let header = data.get(start..start + LOCAL_HEADER_LEN)?;
+    let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+    let u32_at =
+        |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+
+    let flags = u16_at(6);
+    let method = u16_at(8);
+    let compressed_size = usize::try_from(u32_at(18)).ok()?;
+    let name_len = usize::from(u16_at(26));
+    let extra_len = usize::from(u16_at(28));
+
+    let name_start = start + LOCAL_HEADER_LEN;
+    let name = String::from_utf8(data.get(name_start..name_start + name_len)?.to_vec()).ok()?;
+    let data_start = name_start + name_len + extra_len;
+    let rest = data.get(data_start..)?;
+
+    // Sizes are unknown until the data descriptor; a deflate stream ends
+    // by itself, stored data cannot be delimited
+    if flags & FLAG_DATA_DESCRIPTOR != 0 && compressed_size == 0 {
+        return match method {
+            8 => {
+                let mut decoder = DeflateDecoder::new(rest);
+                let mut content = Vec::new();
+                decoder.read_to_end(&mut content).ok()?;
+                let consumed = usize::try_from(decoder.total_in()).ok()?;
+                Some((name, content, data_start + consumed))
+            }
+            _ => None,
+        };
+    }
+
+    let compressed = rest.get(..compressed_size)?;
+    let content = match method {
+        0 => compressed.to_vec(),
+        8 => {
+            let mut content = Vec::new();
+            DeflateDecoder::new(compressed)
+                .read_to_end(&mut content)
+                .ok()?;
+            content
+        }
+        _ => return None,
+    };
+    Some((name, content, data_start + compressed_size))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn entry(data: &[u8], name: &str) -> String {
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_truncated_package() {
+        let data = package(&[
+            ("[Content_Types].xml", "<Types/>"),
+            ("word/document.xml", &"<w:p/>".repeat(200)),
+            ("word/styles.xml", "<w:styles/>"),
+        ]);
+        // Cut off the central directory and part of the last entry
+        let styles = find(&data, b"word/styles.xml").unwrap();
+        let truncated = &data[..styles + 20];
+
+        let mut diagnostics = Vec::new();
+        let strict = package_bytes(
+            truncated,
+            "DOCX",
+            &ParseOptions::default(),
+            &mut diagnostics,
+        );
+        assert!(matches!(strict, Err(Error::CorruptFile { .. })));
+
+        let lenient = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let repaired = package_bytes(truncated, "DOCX", &lenient, &mut diagnostics).unwrap();
+        assert_eq!(entry(&repaired, "word/document.xml"), "<w:p/>".repeat(200));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("2 readable entries"));
+
+        // Intact packages are passed through
+        assert!(matches!(
+            package_bytes(&data, "DOCX", &lenient, &mut diagnostics),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert!(salvage(b"not a zip").is_none());
+    }
+}
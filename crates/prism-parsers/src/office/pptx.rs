@@ -2,30 +2,64 @@
 //! PPTX (Microsoft PowerPoint) parser
 //!
 //! Parses PPTX files into the Unified Document Model.
+//!
+//! Comments on slides, both legacy and the modern comments of Microsoft
+//! 365, are listed in [`Document::revisions`].
+//!
+//! Opening the package, probing each image and parsing each slide are
+//! timed into [`Document::timings`].
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use prism_core::{
-    document::{Dimensions, Document},
-    error::{Error, Result},
+    diagnostics::Diagnostic,
+    document::{Dimensions, Document, Page, Revision},
+    error::{Error, ErrorLocation, Result},
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+    progress::ProgressEvent,
+    timing::StageTimer,
 };
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, info};
 use zip::ZipArchive;
 
+use crate::encryption;
+use crate::office::charts::{self, Chart};
+use crate::office::comments;
+use crate::office::embeddings;
+use crate::office::fonts;
+use crate::office::package;
 use crate::office::relationships::Relationships;
 use crate::office::slides::SlideParser;
 use crate::office::utils;
+use crate::security;
+use crate::signatures;
 use image::ImageReader;
 use prism_core::document::ImageResource;
 use std::collections::HashSet;
 
+/// The parts of a slide, read from the package before parsing
+struct SlidePart {
+    /// 1-indexed slide number
+    number: u32,
+    /// ZIP entry name of the slide
+    name: String,
+    xml: String,
+    /// Relationship ID to target
+    rels: HashMap<String, String>,
+    /// Charts, by relationship ID
+    charts: HashMap<String, Chart>,
+    /// Speaker notes slide
+    notes_xml: Option<String>,
+}
+
 /// PPTX parser
 ///
 /// Parses Microsoft PowerPoint PPTX files into the Unified Document Model.
@@ -63,6 +97,59 @@ impl PptxParser {
         false
     }
 
+    /// Parse slides in parallel, keeping them in presentation order
+    ///
+    /// A slide cut short by malformed XML keeps the shapes read before the
+    /// error, which is recovered through the context's options. Every
+    /// finished slide is reported to the context's progress sink.
+    fn parse_slides(
+        parts: Vec<SlidePart>,
+        dimensions: Dimensions,
+        context: &ParseContext,
+        timer: &StageTimer,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Vec<Page>> {
+        let total = parts.len();
+        let parsed = AtomicUsize::new(0);
+        let slides: Vec<Result<(Page, Option<Error>)>> = parts
+            .into_par_iter()
+            .map(|part| {
+                let slide = timer.time(format!("slide:{}", part.number), || {
+                    let (mut page, error) = SlideParser::parse_partial(
+                        &part.xml,
+                        part.number,
+                        &part.rels,
+                        &part.charts,
+                        dimensions,
+                    );
+                    page.metadata.notes =
+                        part.notes_xml.as_deref().and_then(SlideParser::parse_notes);
+                    Ok((page, error.map(|error| error.in_part(part.name.as_str()))))
+                });
+                context.report(ProgressEvent::PageParsed {
+                    parsed: parsed.fetch_add(1, Ordering::Relaxed) + 1,
+                    total: Some(total),
+                });
+                slide
+            })
+            .collect();
+
+        let mut pages = Vec::new();
+        for slide in slides {
+            match slide {
+                Ok((page, error)) => {
+                    if let Some(error) = error {
+                        context.options.recover(error, diagnostics)?;
+                    }
+                    pages.push(page);
+                }
+                // A slide over its time budget is left out in lenient mode
+                Err(error) => context.options.recover(error, diagnostics)?,
+            }
+        }
+        Ok(pages)
+    }
+
     /// Parse presentation.xml to get slide IDs and dimensions
     fn parse_presentation_xml(xml: &str) -> Result<(Vec<String>, Dimensions)> {
         let mut reader = Reader::from_str(xml);
@@ -113,10 +200,9 @@ impl PptxParser {
                 }
                 Ok(Event::Eof) => break,
                 Err(e) => {
-                    return Err(Error::ParseError(format!(
-                        "XML error in presentation.xml: {}",
-                        e
-                    )))
+                    return Err(Error::corrupt("PPTX", format!("XML error: {e}"))
+                        .at(ErrorLocation::part("ppt/presentation.xml")
+                            .at_offset(reader.buffer_position() as u64)))
                 }
                 _ => {}
             }
@@ -140,7 +226,8 @@ impl Parser for PptxParser {
     }
 
     fn can_parse(&self, data: &[u8]) -> bool {
-        Self::is_pptx_zip(data)
+        // Encrypted packages are accepted so parsing can report the encryption
+        Self::is_pptx_zip(data) || encryption::ooxml_encryption(data).is_some()
     }
 
     async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
@@ -150,9 +237,15 @@ impl Parser for PptxParser {
         );
 
         // Open PPTX as ZIP archive
-        let cursor = Cursor::new(data.as_ref());
+        let timer = StageTimer::new(&context.options);
+        let mut diagnostics = Vec::new();
+        let package = timer.time("zip_open", || {
+            package::package_bytes(&data, "PPTX", &context.options, &mut diagnostics)
+        })?;
+        let active_content = security::check(&package, "PPTX", &context.options)?;
+        let cursor = Cursor::new(package.as_ref());
         let mut archive = ZipArchive::new(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open PPTX as ZIP: {}", e)))?;
+            .map_err(|e| Error::corrupt("PPTX", format!("Failed to open ZIP package: {e}")))?;
 
         // 1. Read relationships to find slide filenames
         let mut rels_map: HashMap<String, String> = HashMap::new();
@@ -192,13 +285,13 @@ impl Parser for PptxParser {
                 let mut xml = String::new();
                 use std::io::Read;
                 presentation_file.read_to_string(&mut xml).map_err(|e| {
-                    Error::ParseError(format!("Failed to read presentation.xml: {}", e))
+                    Error::corrupt("PPTX", format!("Failed to read part: {e}"))
+                        .at(ErrorLocation::part("ppt/presentation.xml"))
                 })?;
                 Self::parse_presentation_xml(&xml)?
             } else {
-                return Err(Error::ParseError(
-                    "Missing ppt/presentation.xml".to_string(),
-                ));
+                return Err(Error::corrupt("PPTX", "Missing presentation part")
+                    .at(ErrorLocation::part("ppt/presentation.xml")));
             };
 
         // 3. Resolve rIds to filenames
@@ -256,10 +349,25 @@ impl Parser for PptxParser {
             }
         }
 
-        // 5. Parse slides in order
-        let mut pages = Vec::new();
+        // 5. Read the parts of every slide in order; the ZIP archive needs
+        // `&mut` access, so this is sequential
+        let mut parts = Vec::new();
         let mut images = Vec::new();
         let mut loaded_images: HashSet<String> = HashSet::new();
+        let mut revisions = Vec::new();
+        let mut embedded = Vec::new();
+        let comment_authors = comment_authors(&mut archive);
+
+        // Slide number of each slide part, so that links between slides
+        // become page links
+        let slide_numbers: HashMap<String, u32> = slide_rids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rid)| {
+                let name = format!("ppt/{}", rid_to_target.get(rid)?).replace('\\', "/");
+                Some((name, u32::try_from(i + 1).unwrap_or(u32::MAX)))
+            })
+            .collect();
 
         for (i, rid) in slide_rids.iter().enumerate() {
             if let Some(target) = rid_to_target.get(rid) {
@@ -277,7 +385,8 @@ impl Parser for PptxParser {
                 if let Ok(mut file) = archive.by_name(&clean_name) {
                     use std::io::Read;
                     file.read_to_string(&mut slide_xml).map_err(|e| {
-                        Error::ParseError(format!("Failed to read slide XML {}: {}", clean_name, e))
+                        Error::corrupt("PPTX", format!("Failed to read slide: {e}"))
+                            .at(ErrorLocation::part(clean_name.as_str()))
                     })?;
                 } else {
                     debug!("Could not find slide file: {}", clean_name);
@@ -288,23 +397,69 @@ impl Parser for PptxParser {
                     // Load slide relationships to resolve images
                     // Path format: ppt/slides/slide1.xml -> ppt/slides/_rels/slide1.xml.rels
                     let mut slide_rels = HashMap::new();
+                    let mut notes_xml = None;
+                    let mut charts = HashMap::new();
                     if let Some((dir, filename)) = clean_name.rsplit_once('/') {
                         let rels_path = format!("{}/_rels/{}.rels", dir, filename);
                         use std::io::Read; // Ensure Read is imported for ZipFile
 
-                        // ... existing code ...
-
+                        let mut notes = None;
+                        let mut comment_parts = Vec::new();
+                        let mut typed_rels = Relationships::new();
                         if let Ok(mut rels_file) = archive.by_name(&rels_path) {
                             let mut xml = String::new();
                             if rels_file.read_to_string(&mut xml).is_ok() {
                                 if let Ok(rels) = Relationships::from_xml(&xml) {
                                     for rel in rels.map.values() {
-                                        slide_rels.insert(rel.id.clone(), rel.target.clone());
+                                        let slide = rel
+                                            .rel_type
+                                            .ends_with("/slide")
+                                            .then(|| {
+                                                slide_numbers
+                                                    .get(&utils::resolve_path(dir, &rel.target))
+                                            })
+                                            .flatten();
+                                        let target = slide.map_or_else(
+                                            || rel.target.clone(),
+                                            |number| format!("#page-{number}"),
+                                        );
+                                        slide_rels.insert(rel.id.clone(), target);
                                     }
+                                    notes = rels
+                                        .map
+                                        .values()
+                                        .find(|rel| rel.rel_type.ends_with("/notesSlide"))
+                                        .map(|rel| rel.target.clone());
+                                    comment_parts = rels
+                                        .map
+                                        .values()
+                                        .filter(|rel| rel.rel_type.ends_with("/comments"))
+                                        .map(|rel| utils::resolve_path(dir, &rel.target))
+                                        .collect();
+                                    typed_rels = rels;
                                 }
                             }
                         }
 
+                        charts = charts::read_charts(&mut archive, dir, &typed_rels);
+                        embedded.extend(embeddings::embedded_parts(dir, &typed_rels));
+
+                        // Speaker notes live in a separate notes slide part
+                        notes_xml = notes.and_then(|target| {
+                            let path = utils::resolve_path(dir, &target);
+                            let mut xml = String::new();
+                            archive.by_name(&path).ok()?.read_to_string(&mut xml).ok()?;
+                            Some(xml)
+                        });
+
+                        let number = u32::try_from(i + 1).unwrap_or(u32::MAX);
+                        revisions.extend(slide_comments(
+                            &mut archive,
+                            comment_parts,
+                            &comment_authors,
+                            number,
+                        ));
+
                         // Extract images referenced by this slide
                         for target in slide_rels.values() {
                             // Target is usually relative like "../media/image1.png"
@@ -312,22 +467,7 @@ impl Parser for PptxParser {
                             // We need to resolve it relative to the slide directory (dir)
                             // dir is "ppt/slides" usually.
 
-                            // Simple path resolution:
-                            // Split base dir and target by '/'
-                            let base_parts: Vec<&str> = dir.split('/').collect();
-                            let target_parts: Vec<&str> = target.split('/').collect();
-
-                            let mut resolved_parts = base_parts.clone();
-
-                            for part in target_parts {
-                                if part == ".." {
-                                    resolved_parts.pop();
-                                } else if part != "." {
-                                    resolved_parts.push(part);
-                                }
-                            }
-
-                            let resolved_path = resolved_parts.join("/");
+                            let resolved_path = utils::resolve_path(dir, target);
 
                             // Check if already loaded to avoid duplicates
                             // Use the raw target as the ID, because proper parsing uses the target string from relationships
@@ -363,14 +503,15 @@ impl Parser for PptxParser {
                                         let (width, height) = if mime_type == "image/svg+xml" {
                                             (0, 0)
                                         } else {
-                                            match ImageReader::new(std::io::Cursor::new(&img_data))
-                                                .with_guessed_format()
-                                            {
-                                                Ok(reader) => {
-                                                    reader.into_dimensions().unwrap_or((0, 0))
-                                                }
-                                                Err(_) => (0, 0),
-                                            }
+                                            timer.time(format!("image:{clean_path}"), || {
+                                                Ok(ImageReader::new(Cursor::new(&img_data))
+                                                    .with_guessed_format()
+                                                    .ok()
+                                                    .and_then(|reader| {
+                                                        reader.into_dimensions().ok()
+                                                    })
+                                                    .unwrap_or((0, 0)))
+                                            })?
                                         };
 
                                         images.push(ImageResource {
@@ -388,13 +529,21 @@ impl Parser for PptxParser {
                         }
                     }
 
-                    let page =
-                        SlideParser::parse(&slide_xml, (i + 1) as u32, &slide_rels, dimensions);
-                    pages.push(page);
+                    parts.push(SlidePart {
+                        number: u32::try_from(i + 1).unwrap_or(u32::MAX),
+                        name: clean_name,
+                        xml: slide_xml,
+                        rels: slide_rels,
+                        charts,
+                        notes_xml,
+                    });
                 }
             }
         }
 
+        // 6. Slides are independent, so parse them in parallel
+        let pages = Self::parse_slides(parts, dimensions, &context, &timer, &mut diagnostics)?;
+
         // Create document metadata
         let mut metadata = Metadata::new();
         if let Some(filename) = context.filename {
@@ -402,6 +551,8 @@ impl Parser for PptxParser {
         }
         metadata.add_custom("format", "PPTX");
         metadata.add_custom("slide_count", pages.len() as i64);
+        metadata.signatures = signatures::verify(&package);
+        metadata.active_content = active_content;
         if let Some(name) = theme_name {
             metadata.add_custom("theme_name", name);
         }
@@ -416,6 +567,12 @@ impl Parser for PptxParser {
         let mut document = Document::builder().metadata(metadata).build();
         document.pages = pages;
         document.resources.images = images;
+        document.resources.fonts = fonts::pptx_fonts(&mut archive);
+        document.attachments = embeddings::read_embeddings(&mut archive, &embedded);
+        document.diagnostics = diagnostics;
+        document.revisions = revisions;
+        document.timings = timer.into_timings();
+        embeddings::parse_embedded(&mut document, &context.options).await;
 
         info!(
             "Successfully parsed PPTX with {} slides",
@@ -437,3 +594,41 @@ impl Parser for PptxParser {
         }
     }
 }
+
+/// Comment author names by ID, from the legacy and the modern authors parts
+fn comment_authors<R: std::io::Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+) -> HashMap<String, String> {
+    use std::io::Read;
+    let mut authors = HashMap::new();
+    for name in ["ppt/commentAuthors.xml", "ppt/authors.xml"] {
+        let mut xml = String::new();
+        if let Ok(mut file) = archive.by_name(name) {
+            if file.read_to_string(&mut xml).is_ok() {
+                authors.extend(comments::pptx_authors(&xml));
+            }
+        }
+    }
+    authors
+}
+
+/// Comments of slide `slide`, read from its comments `parts` in name order
+fn slide_comments<R: std::io::Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    mut parts: Vec<String>,
+    authors: &HashMap<String, String>,
+    slide: u32,
+) -> Vec<Revision> {
+    use std::io::Read;
+    parts.sort();
+    let mut revisions = Vec::new();
+    for path in parts {
+        let mut xml = String::new();
+        if let Ok(mut file) = archive.by_name(&path) {
+            if file.read_to_string(&mut xml).is_ok() {
+                revisions.extend(comments::pptx_comments(&xml, authors, slide));
+            }
+        }
+    }
+    revisions
+}
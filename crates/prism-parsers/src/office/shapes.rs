@@ -1,107 +1,85 @@
 // SPDX-License-Identifier: AGPL-3.0-only
-use crate::office::utils;
+use crate::office::charts::{self, Chart};
+use crate::office::{fills, geometry, utils};
 use prism_core::document::{
-    ContentBlock, Dimensions, ImageBlock, Rect, ShapeStyle, TextBlock, TextRun, TextStyle,
+    Arrowhead, ArrowheadKind, ContentBlock, Dimensions, Fill, ImageBlock, ImageCrop, Link,
+    ListBlock, ListItem, ListMarker, PathCommand, Point, Rect, ShapeStyle, TextBlock,
+    TextDirection, TextRun, TextStyle, VectorBlock, VectorPath,
 };
-use quick_xml::events::Event;
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use std::borrow::Cow;
 
-/// Parse a shape element (p:sp) into a ContentBlock
-/// Parse a shape element (p:sp) into a ContentBlock
-pub fn parse_shape(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Option<ContentBlock> {
+/// Parse a shape element (p:sp) or connector (p:cxnSp) into content
+/// blocks: the outline of its preset geometry as a vector, if it is visible
+/// and more than a text box, and its text on top
+///
+/// Connectors are lines with optional arrowheads, black unless their line
+/// color is set, as theme colors are not resolved. `rels` maps the slide's
+/// relationship IDs to their targets, for links.
+pub fn parse_shape(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    rels: &HashMap<String, String>,
+) -> Vec<ContentBlock> {
     let mut bounds = Rect::default();
     let mut style = ShapeStyle::default();
-    let mut text_runs = Vec::new();
+    let mut text = TextBody::default();
     let mut rotation = 0.0;
+    let mut flip = (false, false);
+    let mut placeholder = None;
+    let mut geometry = None;
+    let mut adjust = HashMap::new();
+    let mut arrows = (None, None);
+    let mut connector = false;
     // Auxiliary buffer for nested parsing to avoid borrow issues with `buf` which is borrowed by `e`
     let mut inner_buf = Vec::new();
 
     let mut in_ln = false;
+    let mut no_fill = false;
+    let mut no_line = false;
 
     loop {
         match reader.read_event_into(buf) {
             Ok(Event::Start(e)) => match e.name().as_ref() {
                 b"a:xfrm" | b"p:xfrm" | b"xfrm" => {
                     bounds = parse_transform_2d(reader, &mut inner_buf);
-                    // Rotation? a:xfrm has rot attribute (60000ths of a degree)
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"rot" {
-                            if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                rotation = val / 60000.0;
-                            }
-                        }
-                    }
-                }
-                b"a:solidFill" => {
-                    // Try to find srgbClr
-                    // Since solidFill is a container, we need to iterate its children or check next event
-                    // Actually, let's just wait for srgbClr event to appear?
-                    // But srgbClr might appear in other contexts (text runs).
-                    // To be safe, we should really track context.
-                    // For now, let's try a simple heuristic: if we see srgbClr and we haven't parsed text yet, it's likely shape fill.
-                    if text_runs.is_empty() {
-                        // We need to peek or read inside.
-                        // Let's implement a quick helper or just use a flag?
-                        // Simpler: iterate inside solidFill
-                        // But we can't easily iterate inside without consuming.
-                    }
-                }
-                b"a:srgbClr" => {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"val" {
-                            let color = utils::attr_value(&attr.value);
-                            if in_ln {
-                                style.stroke_color = Some(color);
-                            } else {
-                                style.fill_color = Some(color);
-                            }
-                        }
-                    }
+                    (rotation, flip) = rotation_and_flip(&e);
                 }
+                b"a:srgbClr" => shape_color(&e, in_ln, &mut style),
                 b"a:ln" => {
                     in_ln = true;
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"w" {
-                            if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                // EMUs to points
-                                style.stroke_width = Some(val / 12700.0);
-                            }
-                        }
-                    }
+                    line_width(&e, &mut style);
+                }
+                name if fills::is_fill(name) && !in_ln => {
+                    style.fill = fills::parse_fill(reader, &mut inner_buf, &e, rels);
+                    style.fill_color = style.fill.as_ref().and_then(fills::representative_color);
                 }
+                b"a:prstGeom" => geometry = utils::attr_value_opt(&e, b"prst"),
                 b"p:txBody" => {
-                    text_runs = parse_text_body(reader, &mut inner_buf, b"p:txBody");
+                    text = parse_text_body(reader, &mut inner_buf, b"p:txBody", rels);
                 }
+                b"p:ph" => placeholder = placeholder_style(&e),
                 _ => {}
             },
             Ok(Event::Empty(e)) => match e.name().as_ref() {
-                b"a:ln" => {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"w" {
-                            if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                style.stroke_width = Some(val / 12700.0);
-                            }
-                        }
-                    }
-                }
-                b"a:srgbClr" => {
-                    // Handle self-closing color tags
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"val" {
-                            let color = utils::attr_value(&attr.value);
-                            if in_ln {
-                                style.stroke_color = Some(color);
-                            } else {
-                                style.fill_color = Some(color);
-                            }
-                        }
-                    }
-                }
+                b"p:ph" => placeholder = placeholder_style(&e),
+                b"a:ln" => line_width(&e, &mut style),
+                // Handle self-closing color tags
+                b"a:srgbClr" => shape_color(&e, in_ln, &mut style),
+                b"a:noFill" if in_ln => no_line = true,
+                b"a:noFill" => no_fill = true,
+                b"a:prstGeom" => geometry = utils::attr_value_opt(&e, b"prst"),
+                b"a:gd" => adjust.extend(adjust_value(&e)),
+                b"a:headEnd" => arrows.0 = arrowhead(&e),
+                b"a:tailEnd" => arrows.1 = arrowhead(&e),
                 _ => {}
             },
             Ok(Event::End(e)) => {
                 let name = e.name();
-                if name.as_ref() == b"p:sp" {
+                if matches!(name.as_ref(), b"p:sp" | b"p:cxnSp") {
+                    connector = name.as_ref() == b"p:cxnSp";
                     break;
                 } else if name.as_ref() == b"a:ln" {
                     in_ln = false;
@@ -113,20 +91,215 @@ pub fn parse_shape(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Option<Cont
         buf.clear();
     }
 
-    if !text_runs.is_empty() {
+    // Explicitly unfilled or unlined, whatever the shape style says
+    if no_fill {
+        style.fill_color = None;
+        style.fill = None;
+    }
+    if no_line {
+        style.stroke_color = None;
+    } else if connector && style.stroke_color.is_none() {
+        style.stroke_color = Some("#000000".to_string());
+    }
+
+    let mut blocks = Vec::new();
+    let has_text = !text.runs.is_empty();
+    // A rectangle with text is drawn as the text box itself
+    let outline = geometry
+        .filter(|preset| preset != "rect" || !has_text)
+        .and_then(|preset| outline(&preset, bounds, &adjust, &style));
+    if let Some(mut path) = outline {
+        let bounds = place(&mut path.commands, bounds, flip, rotation);
+        (path.start_arrow, path.end_arrow) = arrows;
+        blocks.push(ContentBlock::Vector(VectorBlock {
+            bounds,
+            paths: vec![path],
+        }));
+        style = ShapeStyle::default();
+    }
+
+    if let Some(list) = text.list(bounds) {
+        blocks.push(ContentBlock::List(list));
+    } else if has_text {
         let mut block = TextBlock::new(bounds);
-        for run in text_runs {
-            block.add_run(run);
-        }
+        block.runs = text.runs;
+        block.direction = text.direction;
+        block.paragraph_style = placeholder.map(str::to_string);
         block.style = style;
         block.rotation = rotation;
-        return Some(ContentBlock::Text(block));
+        blocks.push(ContentBlock::Text(block));
+    }
+
+    blocks
+}
+
+/// Rotation, in degrees clockwise, and horizontal and vertical flips of a
+/// transform (`a:xfrm`)
+fn rotation_and_flip(e: &BytesStart) -> (f64, (bool, bool)) {
+    // 60000ths of a degree
+    let rotation = utils::attr_value_opt(e, b"rot")
+        .and_then(|rot| rot.parse::<f64>().ok())
+        .map_or(0.0, |rot| rot / 60000.0);
+    let flip =
+        |name: &[u8]| utils::attr_value_opt(e, name).is_some_and(|v| v == "1" || v == "true");
+    (rotation, (flip(b"flipH"), flip(b"flipV")))
+}
+
+/// Set the fill or, inside `a:ln`, the line color of a shape
+fn shape_color(e: &BytesStart, in_ln: bool, style: &mut ShapeStyle) {
+    let color = fills::color(e);
+    if in_ln {
+        style.stroke_color = color;
+    } else {
+        style.fill_color = color;
+    }
+}
+
+/// Name and value of an adjust value, e.g. `<a:gd name="adj" fmla="val 50000"/>`
+fn adjust_value(e: &BytesStart) -> Option<(String, f64)> {
+    let value = utils::attr_value_opt(e, b"fmla")?
+        .strip_prefix("val ")?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    Some((utils::attr_value_opt(e, b"name")?, value))
+}
+
+/// Outline of a preset shape with its fill and line, or `None` if it is
+/// unknown or would not show
+fn outline(
+    preset: &str,
+    bounds: Rect,
+    adjust: &HashMap<String, f64>,
+    style: &ShapeStyle,
+) -> Option<VectorPath> {
+    let open = geometry::is_open(preset);
+    let filled = !open && (style.fill_color.is_some() || style.fill.is_some());
+    if !filled && style.stroke_color.is_none() || bounds.width <= 0.0 && bounds.height <= 0.0 {
+        return None;
+    }
+    Some(VectorPath {
+        commands: geometry::preset_path(preset, bounds.width, bounds.height, adjust)?,
+        fill: style.fill_color.clone().filter(|_| filled),
+        stroke: style.stroke_color.clone(),
+        // Lines are 0.75pt unless set
+        stroke_width: style
+            .stroke_color
+            .as_ref()
+            .map(|_| style.stroke_width.unwrap_or(0.75)),
+        fill_style: style.fill.clone().filter(|_| filled),
+        start_arrow: None,
+        end_arrow: None,
+    })
+}
+
+/// Flip and then rotate `commands`, drawn in `bounds`, as the shape is,
+/// and return the bounds of the result, which the commands are moved into
+fn place(
+    commands: &mut [PathCommand],
+    bounds: Rect,
+    (flip_h, flip_v): (bool, bool),
+    rotation: f64,
+) -> Rect {
+    let (cx, cy) = (bounds.width / 2.0, bounds.height / 2.0);
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    // Clockwise about the center, as y grows downward
+    let turn = |x: f64, y: f64| {
+        let (dx, dy) = (x - cx, y - cy);
+        (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+    };
+    let corners = [
+        turn(0.0, 0.0),
+        turn(bounds.width, 0.0),
+        turn(bounds.width, bounds.height),
+        turn(0.0, bounds.height),
+    ];
+    let (left, top) = corners.iter().fold((f64::MAX, f64::MAX), |(x, y), corner| {
+        (x.min(corner.0), y.min(corner.1))
+    });
+    let (right, bottom) = corners.iter().fold((f64::MIN, f64::MIN), |(x, y), corner| {
+        (x.max(corner.0), y.max(corner.1))
+    });
+
+    let move_point = |point: &mut Point| {
+        let x = if flip_h {
+            bounds.width - point.x
+        } else {
+            point.x
+        };
+        let y = if flip_v {
+            bounds.height - point.y
+        } else {
+            point.y
+        };
+        let (x, y) = turn(x, y);
+        *point = Point::new(x - left, y - top);
+    };
+    for command in commands {
+        match command {
+            PathCommand::MoveTo(point) | PathCommand::LineTo(point) => move_point(point),
+            PathCommand::CurveTo { cp1, cp2, end } => {
+                move_point(cp1);
+                move_point(cp2);
+                move_point(end);
+            }
+            PathCommand::QuadTo { cp, end } => {
+                move_point(cp);
+                move_point(end);
+            }
+            PathCommand::Close => {}
+        }
+    }
+    Rect::new(bounds.x + left, bounds.y + top, right - left, bottom - top)
+}
+
+/// Arrowhead of an `a:headEnd` or `a:tailEnd` element, sized `sm`, `med`
+/// or `lg` across (`w`) and along (`len`) the line
+fn arrowhead(e: &BytesStart) -> Option<Arrowhead> {
+    let kind = match utils::attr_value_opt(e, b"type")?.as_str() {
+        "triangle" => ArrowheadKind::Triangle,
+        "stealth" => ArrowheadKind::Stealth,
+        "arrow" => ArrowheadKind::Open,
+        "diamond" => ArrowheadKind::Diamond,
+        "oval" => ArrowheadKind::Oval,
+        _ => return None,
+    };
+    let size = |name: &[u8]| match utils::attr_value_opt(e, name).as_deref() {
+        Some("sm") => 2.0,
+        Some("lg") => 5.0,
+        _ => 3.0,
+    };
+    Some(Arrowhead {
+        kind,
+        width: size(b"w"),
+        length: size(b"len"),
+    })
+}
+
+/// Apply the width of an `a:ln` element, in EMUs, to a shape style
+fn line_width(e: &BytesStart, style: &mut ShapeStyle) {
+    for attr in e.attributes().flatten() {
+        if attr.key.as_ref() == b"w" {
+            if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
+                // EMUs to points
+                style.stroke_width = Some(val / 12700.0);
+            }
+        }
     }
+}
 
-    None
+/// Paragraph style for a title placeholder (`p:ph type`), so slide titles
+/// are treated like Word title and subtitle paragraphs
+fn placeholder_style(e: &BytesStart) -> Option<&'static str> {
+    match utils::attr_value_opt(e, b"type").as_deref() {
+        Some("title" | "ctrTitle") => Some("Title"),
+        Some("subTitle") => Some("Subtitle"),
+        _ => None,
+    }
 }
 
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 
 /// Parse a picture element (p:pic) into a ContentBlock
 pub fn parse_picture(
@@ -138,11 +311,15 @@ pub fn parse_picture(
     let mut embed_id = String::new();
     let mut alt_text = None;
     let mut image_format = None;
+    let mut rotation = 0.0;
+    let mut flip = (false, false);
+    let mut crop = None;
 
     loop {
         match reader.read_event_into(buf) {
             Ok(Event::Start(e)) => match e.name().as_ref() {
                 b"a:xfrm" | b"p:xfrm" | b"xfrm" => {
+                    (rotation, flip) = rotation_and_flip(&e);
                     bounds = parse_transform_2d(reader, buf);
                 }
                 b"a:blip" => {
@@ -159,6 +336,14 @@ pub fn parse_picture(
                         }
                     }
                 }
+                b"a:srcRect" => crop = source_rect(&e),
+                _ => {}
+            },
+            // Usually self-closing
+            Ok(Event::Empty(e)) => match e.name().as_ref() {
+                b"p:cNvPr" => alt_text = utils::attr_value_opt(&e, b"descr"),
+                b"a:blip" => embed_id = utils::attr_value_opt(&e, b"r:embed").unwrap_or_default(),
+                b"a:srcRect" => crop = source_rect(&e),
                 _ => {}
             },
             Ok(Event::End(e)) => {
@@ -204,14 +389,41 @@ pub fn parse_picture(
         format: image_format,
         original_size: None, // TODO: Get intrinsic size from headers?
         style: ShapeStyle::default(),
-        rotation: 0.0,
+        rotation,
+        crop,
+        flip_horizontal: flip.0,
+        flip_vertical: flip.1,
     }))
 }
 
-/// Parse a graphic frame element (p:graphicFrame) into a ContentBlock
-pub fn parse_graphic_frame(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Option<ContentBlock> {
+/// Crop of a picture from its source rectangle (`a:srcRect`), whose `l`,
+/// `t`, `r` and `b` are in thousandths of a percent; `None` if nothing is
+/// cropped
+fn source_rect(e: &BytesStart<'_>) -> Option<ImageCrop> {
+    let edge = |name: &[u8]| {
+        utils::attr_value_opt(e, name)
+            .and_then(|value| value.parse::<f64>().ok())
+            .map_or(0.0, |value| value / 100_000.0)
+    };
+    let crop = ImageCrop {
+        left: edge(b"l"),
+        top: edge(b"t"),
+        right: edge(b"r"),
+        bottom: edge(b"b"),
+    };
+    (!crop.is_empty()).then_some(crop)
+}
+
+/// Parse a graphic frame element (`p:graphicFrame`) into a table, or into
+/// one of `charts`, which are by relationship ID
+pub fn parse_graphic_frame<S: BuildHasher>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    charts: &HashMap<String, Chart, S>,
+) -> Option<ContentBlock> {
     let mut bounds = Rect::default();
     let mut table_block = None;
+    let mut chart = None;
 
     loop {
         match reader.read_event_into(buf) {
@@ -226,8 +438,14 @@ pub fn parse_graphic_frame(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Opt
                         table_block = Some(block);
                     }
                 }
+                b"c:chart" => chart = charts::chart_id(&e).and_then(|id| charts.get(&id)),
                 _ => {}
             },
+            Ok(Event::Empty(e)) => {
+                if let Some(id) = charts::chart_id(&e) {
+                    chart = charts.get(&id);
+                }
+            }
             Ok(Event::End(e)) => {
                 if e.name().as_ref() == b"p:graphicFrame" {
                     break;
@@ -243,47 +461,36 @@ pub fn parse_graphic_frame(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Opt
         block.bounds = bounds;
         Some(ContentBlock::Table(block))
     } else {
-        None
+        chart.map(|chart| ContentBlock::Container(chart.render(bounds)))
     }
 }
 
-/// Parse a background element (p:bg) into a ContentBlock (Image)
+/// Parse a background element (`p:bg`) into a content block: an image for
+/// a stretched picture, otherwise a rectangle filled like the background
+///
+/// Backgrounds referring to the theme (`p:bgRef`) are not resolved.
 pub fn parse_background(
     reader: &mut Reader<&[u8]>,
     buf: &mut Vec<u8>,
     rels: &HashMap<String, String>,
     dimensions: Dimensions,
 ) -> Option<ContentBlock> {
-    let mut embed_id = String::new();
-
-    // Iterate through p:bg children to find p:bgPr -> a:blipFill -> a:blip
-    // Since p:bg is a container, we just loop until we find a:blip or end of p:bg
-
-    // Actually, we can just look for a:blip anywhere inside p:bg.
-    // Assuming simple structure.
+    let mut style = ShapeStyle::default();
+    let mut inner_buf = Vec::new();
 
     loop {
         match reader.read_event_into(buf) {
             Ok(Event::Start(e)) => match e.name().as_ref() {
-                b"a:blip" => {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"r:embed" {
-                            embed_id = utils::attr_value(&attr.value);
-                        }
-                    }
-                }
-                _ => {}
-            },
-            Ok(Event::Empty(e)) => match e.name().as_ref() {
-                b"a:blip" => {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"r:embed" {
-                            embed_id = utils::attr_value(&attr.value);
-                        }
-                    }
+                name if fills::is_fill(name) => {
+                    style.fill = fills::parse_fill(reader, &mut inner_buf, &e, rels);
+                    style.fill_color = style.fill.as_ref().and_then(fills::representative_color);
                 }
+                b"a:srgbClr" => style.fill_color = utils::attr_value_opt(&e, b"val"),
                 _ => {}
             },
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"a:srgbClr" => {
+                style.fill_color = utils::attr_value_opt(&e, b"val");
+            }
             Ok(Event::End(e)) => {
                 if e.name().as_ref() == b"p:bg" {
                     break;
@@ -295,40 +502,58 @@ pub fn parse_background(
         buf.clear();
     }
 
-    if embed_id.is_empty() {
-        return None;
-    }
-
-    let image_path = if let Some(target) = rels.get(&embed_id) {
-        target.clone()
-    } else {
-        embed_id.clone()
-    };
-
-    // Determine format from path
-    let image_format = if let Some(ext) = std::path::Path::new(&image_path)
-        .extension()
-        .and_then(|s| s.to_str())
+    let bounds = Rect::new(0.0, 0.0, dimensions.width, dimensions.height);
+    if let Some(Fill::Picture {
+        resource_id,
+        tile: false,
+    }) = &style.fill
     {
-        Some(match ext.to_lowercase().as_str() {
-            "png" => "image/png".to_string(),
-            "jpg" | "jpeg" => "image/jpeg".to_string(),
-            "gif" => "image/gif".to_string(),
-            "svg" => "image/svg+xml".to_string(),
-            _ => format!("image/{}", ext),
-        })
-    } else {
-        None
-    };
+        // Determine format from path
+        let image_format = std::path::Path::new(resource_id)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| match ext.to_lowercase().as_str() {
+                "png" => "image/png".to_string(),
+                "jpg" | "jpeg" => "image/jpeg".to_string(),
+                "gif" => "image/gif".to_string(),
+                "svg" => "image/svg+xml".to_string(),
+                _ => format!("image/{}", ext),
+            });
+        return Some(ContentBlock::Image(ImageBlock {
+            bounds,
+            resource_id: resource_id.clone(),
+            alt_text: Some("Background Image".to_string()),
+            format: image_format,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+    }
 
-    Some(ContentBlock::Image(ImageBlock {
-        bounds: Rect::new(0.0, 0.0, dimensions.width, dimensions.height),
-        resource_id: image_path,
-        alt_text: Some("Background Image".to_string()),
-        format: image_format,
-        original_size: None,
-        style: ShapeStyle::default(),
-        rotation: 0.0,
+    if style.fill.is_none() && style.fill_color.is_none() {
+        return None;
+    }
+    let (width, height) = (dimensions.width, dimensions.height);
+    Some(ContentBlock::Vector(VectorBlock {
+        bounds,
+        paths: vec![VectorPath {
+            commands: vec![
+                PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                PathCommand::LineTo(Point::new(width, 0.0)),
+                PathCommand::LineTo(Point::new(width, height)),
+                PathCommand::LineTo(Point::new(0.0, height)),
+                PathCommand::Close,
+            ],
+            fill: style.fill_color,
+            stroke: None,
+            stroke_width: None,
+            fill_style: style.fill,
+            start_arrow: None,
+            end_arrow: None,
+        }],
     }))
 }
 
@@ -339,43 +564,12 @@ pub fn parse_transform_2d(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Rect
 
     loop {
         match reader.read_event_into(buf) {
-            Ok(Event::Start(e)) => match e.name().as_ref() {
-                b"a:off" | b"off" => {
-                    for attr in e.attributes().flatten() {
-                        match attr.key.as_ref() {
-                            b"x" => {
-                                if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                    bounds.x = val / 12700.0;
-                                }
-                            }
-                            b"y" => {
-                                if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                    bounds.y = val / 12700.0;
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                b"a:ext" | b"ext" => {
-                    for attr in e.attributes().flatten() {
-                        match attr.key.as_ref() {
-                            b"cx" => {
-                                if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                    bounds.width = val / 12700.0;
-                                }
-                            }
-                            b"cy" => {
-                                if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                    bounds.height = val / 12700.0;
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                _ => depth += 1,
-            },
+            Ok(Event::Start(e)) => {
+                transform_part(&e, &mut bounds);
+                depth += 1;
+            }
+            // a:off and a:ext are usually self-closing
+            Ok(Event::Empty(e)) => transform_part(&e, &mut bounds),
             Ok(Event::End(_)) => {
                 if depth > 0 {
                     depth -= 1;
@@ -392,62 +586,171 @@ pub fn parse_transform_2d(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Rect
     bounds
 }
 
+/// Apply an offset (`a:off`) or extent (`a:ext`), in EMUs, to `bounds`
+fn transform_part(e: &BytesStart<'_>, bounds: &mut Rect) {
+    let offset = match e.name().as_ref() {
+        b"a:off" | b"off" => true,
+        b"a:ext" | b"ext" => false,
+        _ => return,
+    };
+    for attr in e.attributes().flatten() {
+        let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() else {
+            continue;
+        };
+        match (offset, attr.key.as_ref()) {
+            (true, b"x") => bounds.x = val / 12700.0,
+            (true, b"y") => bounds.y = val / 12700.0,
+            (false, b"cx") => bounds.width = val / 12700.0,
+            (false, b"cy") => bounds.height = val / 12700.0,
+            _ => {}
+        }
+    }
+}
+
 /// Parse a text body element (p:txBody) into a list of TextRuns
 use std::io::BufRead;
 
-/// Parse a text body element (p:txBody or a:txBody) into a list of TextRuns
+/// Text runs of a text body and the direction it is written in
+#[derive(Debug, Default)]
+pub struct TextBody {
+    /// Runs, with a newline run closing every paragraph
+    pub runs: Vec<TextRun>,
+    /// Direction of the first paragraph that declares one (`a:pPr rtl`)
+    pub direction: TextDirection,
+    /// Bullet of each paragraph, in order
+    pub bullets: Vec<Bullet>,
+}
+
+/// Bullet formatting of a paragraph (`a:pPr`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bullet {
+    /// Indentation level (`lvl`), 0 for the outermost
+    pub level: u8,
+    /// The bullet, if the paragraph sets one
+    pub kind: BulletKind,
+}
+
+/// Kind of paragraph bullet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BulletKind {
+    /// No bullet (`a:buNone`, or none declared)
+    #[default]
+    None,
+    /// A bullet character (`a:buChar`)
+    Char(String),
+    /// Automatic numbering (`a:buAutoNum`)
+    AutoNum {
+        /// Number style
+        style: ListMarker,
+        /// Text around the number, e.g. `("", ")")` for `1)`
+        affixes: (&'static str, &'static str),
+        /// Number of the first paragraph (`startAt`)
+        start: u32,
+    },
+}
+
+impl TextBody {
+    /// The paragraphs as a list, when any of them has a bullet
+    ///
+    /// Paragraphs without a bullet become unmarked items, so no text is
+    /// dropped. Numbered paragraphs are counted per level; a paragraph at
+    /// a shallower level, or an unnumbered one, restarts the count.
+    #[must_use]
+    pub fn list(&self, bounds: Rect) -> Option<ListBlock> {
+        if self.bullets.iter().all(|b| b.kind == BulletKind::None) {
+            return None;
+        }
+
+        let mut list = ListBlock::new(bounds);
+        let mut counters: HashMap<u8, u32> = HashMap::new();
+        let paragraphs = self.runs.split(|run| &*run.text == "\n");
+        for (runs, bullet) in paragraphs.zip(&self.bullets) {
+            counters.retain(|&level, _| level <= bullet.level);
+            if runs.is_empty() {
+                continue;
+            }
+            let mut block = TextBlock::new(Rect::default());
+            block.runs = runs.to_vec();
+            block.direction = self.direction;
+            let content = vec![ContentBlock::Text(block)];
+
+            let item = match &bullet.kind {
+                BulletKind::AutoNum {
+                    style,
+                    affixes: (prefix, suffix),
+                    start,
+                } => {
+                    let number = counters
+                        .get(&bullet.level)
+                        .map_or(*start, |n| n.saturating_add(1));
+                    counters.insert(bullet.level, number);
+                    let mut item = ListItem::numbered(content, bullet.level, *style, number);
+                    item.marker = format!("{prefix}{}{suffix}", style.label(number));
+                    item
+                }
+                BulletKind::Char(marker) => {
+                    counters.remove(&bullet.level);
+                    let mut item = ListItem::bullet(content, bullet.level);
+                    item.marker.clone_from(marker);
+                    item
+                }
+                BulletKind::None => {
+                    counters.remove(&bullet.level);
+                    let mut item = ListItem::bullet(content, bullet.level);
+                    item.marker_style = ListMarker::None;
+                    item.marker.clear();
+                    item
+                }
+            };
+            list.add_item(item);
+        }
+        Some(list)
+    }
+}
+
+/// Parse a text body element (p:txBody or a:txBody) into its runs
+///
+/// Runs with an `a:hlinkClick` link to the target of its relationship in
+/// `rels`.
 pub fn parse_text_body<R: BufRead>(
     reader: &mut Reader<R>,
     buf: &mut Vec<u8>,
     end_tag: &[u8],
-) -> Vec<TextRun> {
+    rels: &HashMap<String, String>,
+) -> TextBody {
     let mut runs = Vec::new();
+    let mut direction = TextDirection::Auto;
     let mut current_run_style = TextStyle::default();
     let mut current_run_text = String::new();
     let mut in_run = false;
+    let mut in_text = false;
+    let mut bullets = Vec::new();
+    let mut bullet = Bullet::default();
+    let mut in_ppr = false;
+    let mut run_link = None;
 
     loop {
         match reader.read_event_into(buf) {
             Ok(Event::Start(e)) => match e.name().as_ref() {
-                b"a:p" => {
-                    // Paragraph start
-                }
+                b"a:p" => bullet = Bullet::default(),
                 b"a:r" => {
                     in_run = true;
                     current_run_style = TextStyle::default(); // Reset style for new run
+                    run_link = None;
                     current_run_text.clear();
                 }
-                b"a:rPr" => {
-                    if in_run {
-                        for attr in e.attributes().flatten() {
-                            match attr.key.as_ref() {
-                                b"sz" => {
-                                    if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
-                                        current_run_style.font_size = Some(val / 100.0);
-                                    }
-                                }
-                                b"b" => {
-                                    current_run_style.bold = utils::attr_value(&attr.value) == "1";
-                                }
-                                b"i" => {
-                                    current_run_style.italic =
-                                        utils::attr_value(&attr.value) == "1";
-                                }
-                                b"u" => {
-                                    current_run_style.underline =
-                                        utils::attr_value(&attr.value) == "sng";
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
+                b"a:pPr" => {
+                    in_ppr = true;
+                    paragraph_properties(&e, &mut direction, &mut bullet);
                 }
+                b"a:rPr" if in_run => run_properties(&e, &mut current_run_style),
+                b"a:hlinkClick" if in_run => run_link = hyperlink(&e, rels),
                 b"a:latin" => {
                     if in_run {
                         for attr in e.attributes().flatten() {
                             if attr.key.as_ref() == b"typeface" {
                                 current_run_style.font_family =
-                                    Some(utils::attr_value(&attr.value));
+                                    Some(utils::attr_value(&attr.value).into());
                             }
                         }
                     }
@@ -456,41 +759,48 @@ pub fn parse_text_body<R: BufRead>(
                     if in_run {
                         for attr in e.attributes().flatten() {
                             if attr.key.as_ref() == b"val" {
-                                current_run_style.color = Some(utils::attr_value(&attr.value));
+                                current_run_style.color =
+                                    Some(utils::attr_value(&attr.value).into());
                             }
                         }
                     }
                 }
-                b"a:t" => {
-                    // Text content is usually in a text event inside a:t, but sometimes directly?
-                    // actually a:t usually contains text.
+                b"a:t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match e.name().as_ref() {
+                b"a:pPr" => paragraph_properties(&e, &mut direction, &mut bullet),
+                b"a:rPr" if in_run => run_properties(&e, &mut current_run_style),
+                b"a:hlinkClick" if in_run => run_link = hyperlink(&e, rels),
+                b"a:buNone" | b"a:buChar" | b"a:buAutoNum" if in_ppr => {
+                    bullet.kind = bullet_kind(&e);
                 }
                 _ => {}
             },
-            Ok(Event::Text(e)) => {
-                if in_run {
-                    if let Ok(text) = e.unescape() {
-                        current_run_text.push_str(&text);
-                    }
+            Ok(Event::Text(e)) if in_run && in_text => {
+                if let Ok(text) = e.unescape() {
+                    current_run_text.push_str(&text);
                 }
             }
             Ok(Event::End(e)) => {
                 if e.name().as_ref() == b"a:p" {
                     // End of paragraph, add newline
-                    runs.push(TextRun {
-                        text: "\n".to_string(),
-                        style: TextStyle::default(),
-                        bounds: None,
-                        char_positions: None,
-                    });
+                    bullets.push(std::mem::take(&mut bullet));
+                    runs.push(TextRun::new("\n"));
+                } else if e.name().as_ref() == b"a:pPr" {
+                    in_ppr = false;
+                } else if e.name().as_ref() == b"a:t" {
+                    in_text = false;
                 } else if e.name().as_ref() == b"a:r" {
                     in_run = false;
                     if !current_run_text.is_empty() {
                         runs.push(TextRun {
-                            text: current_run_text.clone(),
+                            text: current_run_text.as_str().into(),
                             style: current_run_style.clone(),
                             bounds: None,
                             char_positions: None,
+                            link: run_link.take(),
+                            revision: None,
                         });
                         current_run_text.clear();
                     }
@@ -504,5 +814,108 @@ pub fn parse_text_body<R: BufRead>(
         buf.clear();
     }
 
-    runs
+    TextBody {
+        runs,
+        direction,
+        bullets,
+    }
+}
+
+/// Target of an `a:hlinkClick`
+///
+/// Actions without a relationship, such as "next slide", have no target.
+/// Links to other slides resolve to page anchors if the slide's
+/// relationships were mapped to them.
+fn hyperlink(e: &BytesStart, rels: &HashMap<String, String>) -> Option<Link> {
+    let target = rels.get(&utils::attr_value_opt(e, b"r:id")?)?;
+    let target = unescape(target).map_or_else(|_| target.clone(), Cow::into_owned);
+    Some(Link::from_href(&target))
+}
+
+/// Apply the direction and indentation level of an `a:pPr` element
+fn paragraph_properties(e: &BytesStart, direction: &mut TextDirection, bullet: &mut Bullet) {
+    paragraph_direction(e, direction);
+    bullet.level = utils::attr_value_opt(e, b"lvl")
+        .and_then(|lvl| lvl.parse().ok())
+        .unwrap_or(0);
+}
+
+/// Bullet declared by an `a:buNone`, `a:buChar` or `a:buAutoNum` element
+fn bullet_kind(e: &BytesStart) -> BulletKind {
+    match e.name().as_ref() {
+        b"a:buChar" => BulletKind::Char(
+            utils::attr_value_opt(e, b"char")
+                .filter(|c| !c.is_empty())
+                .unwrap_or_else(|| "•".to_string()),
+        ),
+        b"a:buAutoNum" => {
+            let scheme = utils::attr_value_opt(e, b"type").unwrap_or_default();
+            let style = match scheme.get(..7).unwrap_or_default() {
+                "alphaLc" => ListMarker::LowerAlpha,
+                "alphaUc" => ListMarker::UpperAlpha,
+                "romanLc" => ListMarker::LowerRoman,
+                "romanUc" => ListMarker::UpperRoman,
+                _ => ListMarker::Decimal,
+            };
+            let affixes = if scheme.ends_with("ParenBoth") {
+                ("(", ")")
+            } else if scheme.ends_with("ParenR") {
+                ("", ")")
+            } else if scheme.ends_with("Plain") {
+                ("", "")
+            } else {
+                ("", ".")
+            };
+            let start = utils::attr_value_opt(e, b"startAt")
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(1);
+            BulletKind::AutoNum {
+                style,
+                affixes,
+                start,
+            }
+        }
+        _ => BulletKind::None,
+    }
+}
+
+/// Record the direction of the first paragraph that sets `rtl`
+fn paragraph_direction(e: &BytesStart, direction: &mut TextDirection) {
+    if *direction != TextDirection::Auto {
+        return;
+    }
+    for attr in e.attributes().flatten() {
+        if attr.key.as_ref() == b"rtl" {
+            *direction = match utils::attr_value(&attr.value).as_str() {
+                "1" | "true" => TextDirection::Rtl,
+                _ => TextDirection::Ltr,
+            };
+        }
+    }
+}
+
+/// Apply the attributes of an `a:rPr` element to a run style
+fn run_properties(e: &BytesStart, style: &mut TextStyle) {
+    for attr in e.attributes().flatten() {
+        match attr.key.as_ref() {
+            b"sz" => {
+                if let Ok(val) = utils::attr_value(&attr.value).parse::<f64>() {
+                    style.font_size = Some(val / 100.0);
+                }
+            }
+            b"b" => {
+                style.bold = utils::attr_value(&attr.value) == "1";
+            }
+            b"i" => {
+                style.italic = utils::attr_value(&attr.value) == "1";
+            }
+            b"u" => {
+                style.underline = utils::attr_value(&attr.value) == "sng";
+            }
+            b"lang" => {
+                style.language = Some(utils::attr_value(&attr.value).into());
+            }
+            _ => {}
+        }
+    }
 }
@@ -0,0 +1,481 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Sheets of XLSX workbooks
+//!
+//! `xl/workbook.xml` lists the sheets in order, with their visibility, and
+//! defines the print area of a sheet as a `_xlnm.Print_Area` name local to
+//! it. Each sheet part holds its own view: the frozen panes of its
+//! `sheetView`, and the rows and columns its `row` and `col` elements hide.
+//!
+//! These are read in sheet coordinates, from `A1`, and turned into a
+//! [`SheetLayout`] in the coordinates of the table holding the sheet's
+//! cells by [`layout`].
+
+use prism_core::document::{CellRange, ContentBlock, FrozenPanes, Page, SheetLayout};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::office::relationships::Relationships;
+use crate::office::utils;
+
+/// Last row of a worksheet, 0-based
+const LAST_ROW: usize = 1_048_575;
+
+/// Last column of a worksheet, 0-based
+const LAST_COLUMN: usize = 16_383;
+
+/// A sheet as listed in `xl/workbook.xml`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkbookSheet {
+    /// Path of the sheet's part in the package
+    pub path: Option<String>,
+
+    /// Whether the sheet is hidden or very hidden
+    pub hidden: bool,
+
+    /// Print areas of the sheet
+    pub print_areas: Vec<CellRange>,
+}
+
+/// How a sheet part shows the sheet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SheetView {
+    /// Hidden rows, 0-based
+    pub hidden_rows: Vec<usize>,
+
+    /// Hidden columns, as 0-based first and last columns of each run
+    pub hidden_columns: Vec<(usize, usize)>,
+
+    /// Frozen rows and columns
+    pub frozen: Option<FrozenPanes>,
+}
+
+/// Sheets of the workbook, in order
+pub fn workbook_sheets<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Vec<WorkbookSheet> {
+    let Some(workbook) = read_part(archive, "xl/workbook.xml") else {
+        return Vec::new();
+    };
+    let rels = read_part(archive, "xl/_rels/workbook.xml.rels")
+        .and_then(|xml| Relationships::from_xml(&xml).ok())
+        .unwrap_or_default();
+
+    let mut sheets: Vec<WorkbookSheet> = Vec::new();
+    // Sheet and reference of the print area being read
+    let mut print_area: Option<(usize, String)> = None;
+    let mut reader = Reader::from_str(&workbook);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) if e.local_name().as_ref() == b"sheet" => {
+                let path = utils::attr_value_opt(&e, b"r:id")
+                    .and_then(|id| rels.get(&id))
+                    .map(|rel| {
+                        rel.target
+                            .strip_prefix('/')
+                            .map_or_else(|| utils::resolve_path("xl", &rel.target), str::to_string)
+                    });
+                let state = utils::attr_value_opt(&e, b"state");
+                sheets.push(WorkbookSheet {
+                    path,
+                    hidden: matches!(state.as_deref(), Some("hidden" | "veryHidden")),
+                    print_areas: Vec::new(),
+                });
+            }
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"definedName" => {
+                let name = utils::attr_value_opt(&e, b"name");
+                let sheet = utils::attr_value_opt(&e, b"localSheetId")
+                    .and_then(|id| id.parse::<usize>().ok());
+                if let (Some("_xlnm.Print_Area"), Some(sheet)) = (name.as_deref(), sheet) {
+                    print_area = Some((sheet, String::new()));
+                }
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some((_, reference)), Ok(text)) = (print_area.as_mut(), text.unescape()) {
+                    reference.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"definedName" => {
+                if let Some((sheet, reference)) = print_area.take() {
+                    if let Some(sheet) = sheets.get_mut(sheet) {
+                        sheet.print_areas = print_areas(&reference);
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    sheets
+}
+
+/// Hidden rows and columns and frozen panes of a sheet part
+#[must_use]
+pub fn sheet_view(xml: &str) -> SheetView {
+    let mut view = SheetView::default();
+    let mut reader = Reader::from_str(xml);
+    // Rows without an `r` attribute follow the row before them
+    let mut next_row = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) => match e.local_name().as_ref() {
+                b"pane" => {
+                    let state = utils::attr_value_opt(&e, b"state");
+                    if matches!(state.as_deref(), Some("frozen" | "frozenSplit")) {
+                        let split = |name: &[u8]| {
+                            utils::attr_value_opt(&e, name)
+                                .and_then(|value| value.parse::<usize>().ok())
+                                .unwrap_or(0)
+                        };
+                        view.frozen = Some(FrozenPanes {
+                            rows: split(b"ySplit"),
+                            columns: split(b"xSplit"),
+                        });
+                    }
+                }
+                b"col" if is_hidden(&e) => {
+                    let column = |name: &[u8]| {
+                        utils::attr_value_opt(&e, name)
+                            .and_then(|value| value.parse::<usize>().ok())
+                            .filter(|&column| column > 0)
+                    };
+                    if let (Some(first), Some(last)) = (column(b"min"), column(b"max")) {
+                        view.hidden_columns.push((first - 1, last - 1));
+                    }
+                }
+                b"row" => {
+                    let row = utils::attr_value_opt(&e, b"r")
+                        .and_then(|r| r.parse::<usize>().ok())
+                        .filter(|&r| r > 0)
+                        .map_or(next_row, |r| r - 1);
+                    if is_hidden(&e) {
+                        view.hidden_rows.push(row);
+                    }
+                    next_row = row + 1;
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    view.hidden_rows.sort_unstable();
+    view.hidden_rows.dedup();
+    view
+}
+
+/// Ranges of a print area reference, such as `Sheet1!$A$1:$D$20` or
+/// `'Q1 Sales'!$A:$C,'Q1 Sales'!$1:$2`
+#[must_use]
+pub fn print_areas(reference: &str) -> Vec<CellRange> {
+    // Quoted sheet names may hold commas
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in reference.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(&reference[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&reference[start..]);
+
+    parts
+        .into_iter()
+        .filter_map(|part| {
+            let cells = part.rsplit_once('!').map_or(part, |(_, cells)| cells);
+            let cells = cells.replace('$', "").to_ascii_uppercase();
+            let (first, last) = cells.split_once(':').unwrap_or((&cells, &cells));
+            let (first_row, first_column) = endpoint(first, 0)?;
+            let (last_row, last_column) = endpoint(last, LAST_ROW.max(LAST_COLUMN))?;
+            Some(CellRange {
+                first_row,
+                first_column,
+                last_row: last_row.min(LAST_ROW),
+                last_column: last_column.min(LAST_COLUMN),
+            })
+        })
+        .filter(|range| {
+            range.first_row <= range.last_row && range.first_column <= range.last_column
+        })
+        .collect()
+}
+
+/// Row and column of one end of a range, such as `B5`, `B` or `5`, with
+/// `open` for the missing half of a whole row or column
+fn endpoint(reference: &str, open: usize) -> Option<(usize, usize)> {
+    let split = reference
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(reference.len());
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() && digits.is_empty() {
+        return None;
+    }
+    let column = if letters.is_empty() {
+        open
+    } else {
+        utils::excel_column_to_index(letters).ok()?
+    };
+    let row = if digits.is_empty() {
+        open
+    } else {
+        digits.parse::<usize>().ok()?.checked_sub(1)?
+    };
+    Some((row, column))
+}
+
+/// Layout of a sheet whose table of `size` rows and columns starts at
+/// sheet row and column `origin`
+#[must_use]
+pub fn layout(
+    sheet: &WorkbookSheet,
+    view: &SheetView,
+    origin: (usize, usize),
+    size: (usize, usize),
+) -> SheetLayout {
+    let (first_row, first_column) = origin;
+    let (rows, columns) = size;
+    let in_table = |index: usize, first: usize, len: usize| {
+        index.checked_sub(first).filter(|&index| index < len)
+    };
+
+    let mut hidden_columns: Vec<usize> = view
+        .hidden_columns
+        .iter()
+        .flat_map(|&(first, last)| first..=last.min(first_column + columns))
+        .filter_map(|column| in_table(column, first_column, columns))
+        .collect();
+    hidden_columns.sort_unstable();
+    hidden_columns.dedup();
+
+    SheetLayout {
+        hidden: sheet.hidden,
+        hidden_rows: view
+            .hidden_rows
+            .iter()
+            .filter_map(|&row| in_table(row, first_row, rows))
+            .collect(),
+        hidden_columns,
+        frozen: view.frozen.map(|frozen| FrozenPanes {
+            rows: frozen.rows.saturating_sub(first_row).min(rows),
+            columns: frozen.columns.saturating_sub(first_column).min(columns),
+        }),
+        print_areas: sheet
+            .print_areas
+            .iter()
+            .filter_map(|area| {
+                let clipped = CellRange {
+                    first_row: area.first_row.max(first_row) - first_row,
+                    first_column: area.first_column.max(first_column) - first_column,
+                    last_row: area
+                        .last_row
+                        .checked_sub(first_row)?
+                        .min(rows.checked_sub(1)?),
+                    last_column: area
+                        .last_column
+                        .checked_sub(first_column)?
+                        .min(columns.checked_sub(1)?),
+                };
+                (clipped.first_row <= clipped.last_row
+                    && clipped.first_column <= clipped.last_column)
+                    .then_some(clipped)
+            })
+            .collect(),
+    }
+}
+
+/// Remove the hidden rows and columns of a sheet's page from its table,
+/// moving the frozen panes and print areas of its layout along
+pub fn skip_hidden(page: &mut Page) {
+    let Some(layout) = page.metadata.sheet.as_mut() else {
+        return;
+    };
+    let rows = std::mem::take(&mut layout.hidden_rows);
+    let columns = std::mem::take(&mut layout.hidden_columns);
+    if rows.is_empty() && columns.is_empty() {
+        return;
+    }
+
+    for block in &mut page.content {
+        let ContentBlock::Table(table) = block else {
+            continue;
+        };
+        let mut index = 0;
+        table.rows.retain(|_| {
+            index += 1;
+            rows.binary_search(&(index - 1)).is_err()
+        });
+        for row in &mut table.rows {
+            let mut index = 0;
+            row.cells.retain(|_| {
+                index += 1;
+                columns.binary_search(&(index - 1)).is_err()
+            });
+        }
+        table.column_count = table.column_count.saturating_sub(columns.len());
+    }
+
+    // Everything after a removed row or column moves up or left
+    let shift = |index: usize, removed: &[usize]| {
+        index - removed.partition_point(|&removed| removed < index)
+    };
+    if let Some(frozen) = layout.frozen.as_mut() {
+        frozen.rows = shift(frozen.rows, &rows);
+        frozen.columns = shift(frozen.columns, &columns);
+    }
+    layout.print_areas.retain_mut(|area| {
+        let (first_row, end_row) = (
+            shift(area.first_row, &rows),
+            shift(area.last_row + 1, &rows),
+        );
+        let (first_column, end_column) = (
+            shift(area.first_column, &columns),
+            shift(area.last_column + 1, &columns),
+        );
+        *area = CellRange {
+            first_row,
+            first_column,
+            last_row: end_row.saturating_sub(1),
+            last_column: end_column.saturating_sub(1),
+        };
+        end_row > first_row && end_column > first_column
+    });
+}
+
+/// Whether a `row` or `col` element is hidden
+fn is_hidden(e: &quick_xml::events::BytesStart<'_>) -> bool {
+    matches!(
+        utils::attr_value_opt(e, b"hidden").as_deref(),
+        Some("1" | "true")
+    )
+}
+
+/// A part of the package as text, or `None` if it is missing or unreadable
+pub(crate) fn read_part<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut xml = String::new();
+    archive.by_name(name).ok()?.read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{PageMetadata, Rect, TableBlock, TableCell, TableRow};
+
+    #[test]
+    fn test_sheet_view_and_print_areas() {
+        let view = sheet_view(
+            r#"<worksheet><sheetViews><sheetView><pane xSplit="1" ySplit="2" topLeftCell="B3" state="frozen"/></sheetView></sheetViews>
+            <cols><col min="3" max="4" width="0" hidden="1"/></cols>
+            <sheetData><row r="1"/><row r="3" hidden="1"/><row hidden="1"/></sheetData></worksheet>"#,
+        );
+        assert_eq!(view.hidden_rows, [2, 3]);
+        assert_eq!(view.hidden_columns, [(2, 3)]);
+        assert_eq!(
+            view.frozen,
+            Some(FrozenPanes {
+                rows: 2,
+                columns: 1
+            })
+        );
+
+        assert_eq!(
+            print_areas("'Q1, Sales'!$B$2:$D$9,'Q1, Sales'!$1:$2"),
+            [
+                CellRange {
+                    first_row: 1,
+                    first_column: 1,
+                    last_row: 8,
+                    last_column: 3,
+                },
+                CellRange {
+                    first_row: 0,
+                    first_column: 0,
+                    last_row: 1,
+                    last_column: LAST_COLUMN,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_and_skip_hidden() {
+        let sheet = WorkbookSheet {
+            path: None,
+            hidden: false,
+            print_areas: print_areas("Sheet1!$B$2:$E$3"),
+        };
+        let view = SheetView {
+            hidden_rows: vec![2],
+            hidden_columns: vec![(2, 2)],
+            frozen: Some(FrozenPanes {
+                rows: 3,
+                columns: 1,
+            }),
+        };
+        // A table of 4 rows and 3 columns starting at B2
+        let layout = layout(&sheet, &view, (1, 1), (4, 3));
+        assert_eq!(layout.hidden_rows, [1]);
+        assert_eq!(layout.hidden_columns, [1]);
+        assert_eq!(
+            layout.frozen,
+            Some(FrozenPanes {
+                rows: 2,
+                columns: 0,
+            })
+        );
+        assert_eq!(
+            layout.print_areas,
+            [CellRange {
+                first_row: 0,
+                first_column: 0,
+                last_row: 1,
+                last_column: 2,
+            }]
+        );
+
+        let mut table = TableBlock::new(Rect::default(), 3);
+        for _ in 0..4 {
+            table.add_row(TableRow {
+                cells: (0..3)
+                    .map(|_| TableCell {
+                        content: Vec::new(),
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    })
+                    .collect(),
+                height: None,
+            });
+        }
+        let mut page = Page::new(1, prism_core::document::Dimensions::LETTER);
+        page.add_content(ContentBlock::Table(table));
+        page.metadata = PageMetadata {
+            sheet: Some(layout),
+            ..PageMetadata::default()
+        };
+        skip_hidden(&mut page);
+
+        let ContentBlock::Table(table) = &page.content[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.column_count, 2);
+        assert!(table.rows.iter().all(|row| row.cells.len() == 2));
+        let layout = page.metadata.sheet.unwrap();
+        assert!(layout.hidden_rows.is_empty());
+        assert_eq!(layout.frozen.map(|frozen| frozen.rows), Some(1));
+        assert_eq!(
+            layout.print_areas,
+            [CellRange {
+                first_row: 0,
+                first_column: 0,
+                last_row: 0,
+                last_column: 1,
+            }]
+        );
+    }
+}
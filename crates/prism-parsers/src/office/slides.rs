@@ -1,22 +1,51 @@
 // SPDX-License-Identifier: AGPL-3.0-only
-use crate::office::shapes;
+use crate::office::charts::Chart;
+use crate::office::{shapes, utils};
 use prism_core::document::{ContentBlock, Dimensions, Page, PageMetadata};
+use prism_core::error::{Error, ErrorLocation, Result};
 use quick_xml::events::Event;
 use quick_xml::Reader;
 
 pub struct SlideParser;
 
 impl SlideParser {
+    /// Parse a slide, failing on malformed XML
+    ///
+    /// `charts` holds the charts of the slide by relationship ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CorruptFile`] if the slide XML is malformed.
     pub fn parse(
         xml: &str,
         slide_num: u32,
         rels: &std::collections::HashMap<String, String>,
+        charts: &std::collections::HashMap<String, Chart>,
         dimensions: Dimensions,
-    ) -> Page {
+    ) -> Result<Page> {
+        match Self::parse_partial(xml, slide_num, rels, charts, dimensions) {
+            (page, None) => Ok(page),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    /// Parse a slide, keeping the shapes read before any XML error
+    ///
+    /// The error, if any, is returned alongside the partial page; its
+    /// location holds the byte offset within the slide part.
+    #[must_use]
+    pub fn parse_partial(
+        xml: &str,
+        slide_num: u32,
+        rels: &std::collections::HashMap<String, String>,
+        charts: &std::collections::HashMap<String, Chart>,
+        dimensions: Dimensions,
+    ) -> (Page, Option<Error>) {
         let mut reader = Reader::from_str(xml);
         reader.trim_text(true);
         let mut buf = Vec::new();
         let mut content = Vec::new();
+        let mut error = None;
 
         loop {
             match reader.read_event_into(&mut buf) {
@@ -33,10 +62,8 @@ impl SlideParser {
                             content.insert(0, block);
                         }
                     }
-                    b"p:sp" => {
-                        if let Some(block) = shapes::parse_shape(&mut reader, &mut Vec::new()) {
-                            content.push(block);
-                        }
+                    b"p:sp" | b"p:cxnSp" => {
+                        content.extend(shapes::parse_shape(&mut reader, &mut Vec::new(), rels));
                     }
                     b"p:pic" => {
                         if let Some(mut block) =
@@ -52,7 +79,7 @@ impl SlideParser {
                     }
                     b"p:graphicFrame" => {
                         if let Some(block) =
-                            shapes::parse_graphic_frame(&mut reader, &mut Vec::new())
+                            shapes::parse_graphic_frame(&mut reader, &mut Vec::new(), charts)
                         {
                             content.push(block);
                         }
@@ -60,12 +87,20 @@ impl SlideParser {
                     _ => {}
                 },
                 Ok(Event::Eof) => break,
+                Err(e) => {
+                    let position = reader.buffer_position() as u64;
+                    error = Some(
+                        Error::corrupt("PPTX", format!("XML error: {e}"))
+                            .at(ErrorLocation::offset(position)),
+                    );
+                    break;
+                }
                 _ => {}
             }
             buf.clear();
         }
 
-        Page {
+        let page = Page {
             number: slide_num,
             dimensions,
             content,
@@ -73,7 +108,409 @@ impl SlideParser {
             metadata: PageMetadata {
                 label: Some(format!("Slide {}", slide_num)),
                 rotation: 0,
+                notes: None,
+                message: None,
+                sheet: None,
             },
+        };
+        (page, error)
+    }
+
+    /// Speaker notes of a notes slide (`ppt/notesSlides/notesSlideN.xml`)
+    ///
+    /// A notes slide also holds the slide thumbnail and header, footer and
+    /// slide number placeholders; only the body placeholder is kept.
+    /// Returns `None` when the notes are empty.
+    #[must_use]
+    pub fn parse_notes(xml: &str) -> Option<String> {
+        // Untrimmed, so spaces at run boundaries survive
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        let mut in_body = false;
+        let mut notes = String::new();
+
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e) | Event::Empty(e)) if e.name().as_ref() == b"p:ph" => {
+                    in_body = utils::attr_value_opt(&e, b"type").as_deref() == Some("body");
+                }
+                Ok(Event::Start(e)) if e.name().as_ref() == b"p:txBody" && in_body => {
+                    let body = shapes::parse_text_body(
+                        &mut reader,
+                        &mut Vec::new(),
+                        b"p:txBody",
+                        &std::collections::HashMap::new(),
+                    );
+                    notes.extend(body.runs.iter().map(|run| &*run.text));
+                    notes.push('\n');
+                }
+                Ok(Event::End(e)) if e.name().as_ref() == b"p:sp" => in_body = false,
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+            buf.clear();
         }
+
+        let notes = notes.trim();
+        (!notes.is_empty()).then(|| notes.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Arrowhead, ArrowheadKind, Fill, ImageCrop, Link, PathCommand};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_slide_titles_and_alt_text() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a" xmlns:r="r"><p:cSld><p:spTree>
+            <p:sp><p:nvSpPr><p:cNvPr id="2" name="Title 1"/><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr>
+                <p:txBody><a:p><a:r><a:t>Quarterly results</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:sp><p:nvSpPr><p:cNvPr id="3" name="Content 2"/><p:nvPr><p:ph idx="1"/></p:nvPr></p:nvSpPr>
+                <p:txBody><a:p><a:r><a:t>Revenue grew</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:pic><p:nvPicPr><p:cNvPr id="4" name="Picture 3" descr="Revenue chart"/></p:nvPicPr>
+                <p:blipFill><a:blip r:embed="rId2"></a:blip></p:blipFill></p:pic>
+        </p:spTree></p:cSld></p:sld>"#;
+        let rels = HashMap::from([("rId2".to_string(), "../media/image1.png".to_string())]);
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &rels,
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+
+        let styles: Vec<_> = page
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.paragraph_style.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(styles, [Some("Title"), None]);
+        assert!(matches!(
+            &page.content[2],
+            ContentBlock::Image(image) if image.alt_text.as_deref() == Some("Revenue chart")
+        ));
+    }
+
+    #[test]
+    fn test_parse_picture_transform() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a" xmlns:r="r"><p:cSld><p:spTree>
+            <p:pic><p:nvPicPr><p:cNvPr id="4" name="Picture 3"/></p:nvPicPr>
+                <p:blipFill><a:blip r:embed="rId2"/><a:srcRect l="10000" t="5000" r="25000"/>
+                <a:stretch><a:fillRect/></a:stretch></p:blipFill>
+                <p:spPr><a:xfrm rot="5400000" flipH="1"><a:off x="127000" y="254000"/>
+                <a:ext cx="1270000" cy="635000"/></a:xfrm></p:spPr></p:pic>
+        </p:spTree></p:cSld></p:sld>"#;
+        let rels = HashMap::from([("rId2".to_string(), "../media/image1.png".to_string())]);
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &rels,
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+
+        let ContentBlock::Image(image) = &page.content[0] else {
+            panic!("expected a picture, got {:?}", page.content);
+        };
+        assert_eq!(image.resource_id, "../media/image1.png");
+        assert_eq!(
+            (image.bounds.x, image.bounds.y, image.bounds.width),
+            (10.0, 20.0, 100.0)
+        );
+        assert_eq!(image.rotation, 90.0);
+        assert!(image.flip_horizontal && !image.flip_vertical);
+        assert_eq!(
+            image.crop,
+            Some(ImageCrop {
+                left: 0.1,
+                top: 0.05,
+                right: 0.25,
+                bottom: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_fills() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld>
+            <p:bg><p:bgPr><a:pattFill prst="smGrid"><a:fgClr><a:srgbClr val="D9D9D9"/></a:fgClr>
+                <a:bgClr><a:srgbClr val="FFFFFF"/></a:bgClr></a:pattFill></p:bgPr></p:bg>
+            <p:spTree><p:sp><p:spPr><a:gradFill><a:gsLst>
+                <a:gs pos="0"><a:srgbClr val="4472C4"/></a:gs><a:gs pos="100000"><a:srgbClr val="1F3864"/></a:gs>
+                </a:gsLst><a:path path="circle"/></a:gradFill>
+                <a:ln w="12700"><a:solidFill><a:srgbClr val="000000"/></a:solidFill></a:ln></p:spPr>
+                <p:txBody><a:p><a:r><a:rPr><a:solidFill><a:srgbClr val="FFFFFF"/></a:solidFill></a:rPr>
+                <a:t>Agenda</a:t></a:r></a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:sld>"#;
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &HashMap::new(),
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+
+        let ContentBlock::Vector(background) = &page.content[0] else {
+            panic!("expected a background, got {:?}", page.content);
+        };
+        assert_eq!(background.bounds.width, 960.0);
+        assert!(matches!(
+            &background.paths[0].fill_style,
+            Some(Fill::Pattern { preset, .. }) if preset == "smGrid"
+        ));
+        assert_eq!(background.paths[0].fill.as_deref(), Some("#FFFFFF"));
+
+        let ContentBlock::Text(shape) = &page.content[1] else {
+            panic!("expected a text box, got {:?}", page.content);
+        };
+        let Some(Fill::Gradient(gradient)) = &shape.style.fill else {
+            panic!("expected a gradient, got {:?}", shape.style);
+        };
+        assert!(gradient.radial);
+        assert_eq!(shape.style.fill_color.as_deref(), Some("#4472C4"));
+        assert_eq!(shape.style.stroke_color.as_deref(), Some("#000000"));
+        // The background adds no text
+        assert_eq!(page.extract_text(), shape.extract_text());
+    }
+
+    #[test]
+    fn test_parse_preset_geometry() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+            <p:sp><p:spPr><a:xfrm flipH="1"><a:off x="127000" y="254000"/><a:ext cx="2540000" cy="508000"/></a:xfrm>
+                <a:prstGeom prst="rightArrow"><a:avLst/></a:prstGeom>
+                <a:solidFill><a:srgbClr val="4472C4"/></a:solidFill><a:ln><a:noFill/></a:ln></p:spPr>
+                <p:txBody><a:p><a:r><a:t>Next</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:sp><p:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="127000" cy="127000"/></a:xfrm>
+                <a:prstGeom prst="ellipse"/><a:noFill/>
+                <a:ln w="25400"><a:solidFill><a:srgbClr val="FF0000"/></a:solidFill></a:ln></p:spPr></p:sp>
+            <p:sp><p:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="127000" cy="127000"/></a:xfrm>
+                <a:prstGeom prst="rect"/><a:noFill/></p:spPr>
+                <p:txBody><a:p><a:r><a:t>Caption</a:t></a:r></a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:sld>"#;
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &HashMap::new(),
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+        assert_eq!(page.content.len(), 4, "{:?}", page.content);
+
+        // The arrow, pointing left, under its text
+        let ContentBlock::Vector(arrow) = &page.content[0] else {
+            panic!("expected an arrow, got {:?}", page.content);
+        };
+        let bounds = arrow.bounds;
+        assert_eq!(
+            (bounds.x, bounds.y, bounds.width, bounds.height),
+            (10.0, 20.0, 200.0, 40.0)
+        );
+        let path = &arrow.paths[0];
+        assert_eq!(path.fill.as_deref(), Some("#4472C4"));
+        assert_eq!(path.stroke, None);
+        let PathCommand::LineTo(tip) = path.commands[3] else {
+            panic!("expected a line, got {:?}", path.commands);
+        };
+        assert_eq!((tip.x, tip.y), (0.0, 20.0));
+        let ContentBlock::Text(text) = &page.content[1] else {
+            panic!("expected a text box, got {:?}", page.content);
+        };
+        assert!(text.style.fill_color.is_none() && text.style.stroke_color.is_none());
+
+        // An outlined circle
+        let ContentBlock::Vector(circle) = &page.content[2] else {
+            panic!("expected a circle, got {:?}", page.content);
+        };
+        assert_eq!(circle.paths[0].fill, None);
+        assert_eq!(circle.paths[0].stroke.as_deref(), Some("#FF0000"));
+        assert_eq!(circle.paths[0].stroke_width, Some(2.0));
+
+        // A plain text box stays one
+        let ContentBlock::Text(caption) = &page.content[3] else {
+            panic!("expected a text box, got {:?}", page.content);
+        };
+        assert_eq!(caption.bounds.width, 10.0);
+    }
+
+    #[test]
+    fn test_parse_connectors() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+            <p:cxnSp><p:nvCxnSpPr><p:cNvPr id="4" name="Elbow Connector 3"/>
+                <p:cNvCxnSpPr><a:stCxn id="2" idx="3"/><a:endCxn id="3" idx="1"/></p:cNvCxnSpPr><p:nvPr/></p:nvCxnSpPr>
+                <p:spPr><a:xfrm rot="5400000"><a:off x="127000" y="0"/><a:ext cx="254000" cy="127000"/></a:xfrm>
+                <a:prstGeom prst="bentConnector3"><a:avLst/></a:prstGeom>
+                <a:ln w="19050"><a:solidFill><a:srgbClr val="4472C4"/></a:solidFill>
+                <a:tailEnd type="triangle" w="lg" len="sm"/></a:ln></p:spPr>
+                <p:style><a:lnRef idx="1"><a:schemeClr val="accent1"/></a:lnRef></p:style></p:cxnSp>
+            <p:cxnSp><p:spPr><a:xfrm flipV="1"><a:off x="0" y="0"/><a:ext cx="127000" cy="0"/></a:xfrm>
+                <a:prstGeom prst="straightConnector1"/><a:ln><a:headEnd type="oval"/></a:ln></p:spPr></p:cxnSp>
+        </p:spTree></p:cSld></p:sld>"#;
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &HashMap::new(),
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+        assert_eq!(page.content.len(), 2, "{:?}", page.content);
+
+        // Turned a quarter clockwise about its center, so 10pt wide
+        let ContentBlock::Vector(elbow) = &page.content[0] else {
+            panic!("expected a connector, got {:?}", page.content);
+        };
+        let bounds = elbow.bounds;
+        let rounded = |value: f64| (value * 1000.0).round() / 1000.0;
+        assert_eq!(
+            (
+                rounded(bounds.x),
+                rounded(bounds.y),
+                rounded(bounds.width),
+                rounded(bounds.height)
+            ),
+            (15.0, -5.0, 10.0, 20.0)
+        );
+        let path = &elbow.paths[0];
+        assert_eq!(path.fill, None);
+        assert_eq!(path.stroke.as_deref(), Some("#4472C4"));
+        assert_eq!(path.stroke_width, Some(1.5));
+        assert_eq!(path.start_arrow, None);
+        assert_eq!(
+            path.end_arrow,
+            Some(Arrowhead {
+                kind: ArrowheadKind::Triangle,
+                width: 5.0,
+                length: 2.0,
+            })
+        );
+        // The first point, top left before turning, ends up top right
+        let PathCommand::MoveTo(start) = path.commands[0] else {
+            panic!("expected a move, got {:?}", path.commands);
+        };
+        assert_eq!((rounded(start.x), rounded(start.y)), (10.0, 0.0));
+
+        // Black by default
+        let ContentBlock::Vector(line) = &page.content[1] else {
+            panic!("expected a connector, got {:?}", page.content);
+        };
+        assert_eq!(line.paths[0].stroke.as_deref(), Some("#000000"));
+        assert_eq!(line.paths[0].stroke_width, Some(0.75));
+        assert!(matches!(
+            line.paths[0].start_arrow,
+            Some(Arrowhead {
+                kind: ArrowheadKind::Oval,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_bulleted_shape() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+            <p:sp><p:txBody>
+                <a:p><a:pPr><a:buAutoNum type="arabicParenR"/></a:pPr><a:r><a:t>Plan</a:t></a:r></a:p>
+                <a:p><a:pPr lvl="1"><a:buChar char="–"/></a:pPr><a:r><a:t>Budget</a:t></a:r></a:p>
+                <a:p><a:pPr><a:buAutoNum type="arabicParenR"/></a:pPr><a:r><a:t>Build</a:t></a:r></a:p>
+                <a:p><a:pPr><a:buNone/></a:pPr><a:r><a:t>Questions?</a:t></a:r></a:p>
+            </p:txBody></p:sp>
+        </p:spTree></p:cSld></p:sld>"#;
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &HashMap::new(),
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+
+        let ContentBlock::List(list) = &page.content[0] else {
+            panic!("expected a list, got {:?}", page.content);
+        };
+        let items: Vec<_> = list
+            .items
+            .iter()
+            .map(|item| (item.level, item.ordered, item.marker.as_str()))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                (0, true, "1)"),
+                (1, false, "–"),
+                (0, true, "2)"),
+                (0, false, "")
+            ]
+        );
+        assert_eq!(
+            list.extract_text(),
+            "1) Plan\n– Budget\n2) Build\nQuestions?"
+        );
+    }
+
+    #[test]
+    fn test_parse_hyperlinks() {
+        let xml = r#"<p:sld xmlns:p="p" xmlns:a="a" xmlns:r="r"><p:cSld><p:spTree>
+            <p:sp><p:txBody><a:p>
+                <a:r><a:rPr lang="en-US"><a:hlinkClick r:id="rId2"/></a:rPr><a:t>Docs</a:t></a:r>
+                <a:r><a:t> and </a:t></a:r>
+                <a:r><a:rPr><a:hlinkClick r:id="rId3" action="ppaction://hlinksldjump"/></a:rPr><a:t>appendix</a:t></a:r>
+                <a:r><a:rPr><a:hlinkClick r:id="" action="ppaction://hlinkshowjump?jump=nextslide"/></a:rPr><a:t> next</a:t></a:r>
+            </a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:sld>"#;
+        let rels = HashMap::from([
+            (
+                "rId2".to_string(),
+                "https://example.com/?a=1&amp;b=2".to_string(),
+            ),
+            ("rId3".to_string(), "#page-4".to_string()),
+        ]);
+        let page = SlideParser::parse(
+            xml,
+            1,
+            &rels,
+            &HashMap::new(),
+            Dimensions::new(960.0, 540.0),
+        )
+        .unwrap();
+
+        let ContentBlock::Text(text) = &page.content[0] else {
+            panic!("expected text");
+        };
+        let links: Vec<_> = text.runs.iter().map(|run| run.link.clone()).collect();
+        assert_eq!(
+            links,
+            [
+                Some(Link::Url("https://example.com/?a=1&b=2".to_string())),
+                None,
+                Some(Link::Page(4)),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let xml = r#"<p:notes xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+            <p:sp><p:nvSpPr><p:nvPr><p:ph type="sldImg"/></p:nvPr></p:nvSpPr></p:sp>
+            <p:sp><p:nvSpPr><p:nvPr><p:ph type="body" idx="1"/></p:nvPr></p:nvSpPr>
+                <p:txBody><a:p><a:r><a:t>Mention the </a:t></a:r><a:r><a:t>new region</a:t></a:r></a:p>
+                <a:p><a:r><a:t>Pause for questions</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:sp><p:nvSpPr><p:nvPr><p:ph type="sldNum" idx="5"/></p:nvPr></p:nvSpPr>
+                <p:txBody><a:p><a:r><a:t>3</a:t></a:r></a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:notes>"#;
+        assert_eq!(
+            SlideParser::parse_notes(xml).as_deref(),
+            Some("Mention the new region\nPause for questions")
+        );
+        assert_eq!(SlideParser::parse_notes("<p:notes/>"), None);
     }
 }
@@ -1,8 +1,9 @@
 // SPDX-License-Identifier: AGPL-3.0-only
+use crate::office::theme::Theme;
 use crate::office::utils;
-use prism_core::document::{ParagraphStyle, TextAlignment, TextStyle};
+use prism_core::document::{ParagraphStyle, TextAlignment, TextDirection, TextStyle};
 use prism_core::error::{Error, Result};
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
 use std::collections::HashMap;
 
@@ -22,6 +23,7 @@ pub struct Styles {
     styles: HashMap<String, Style>,
     default_paragraph_style: ParagraphStyle,
     default_text_style: TextStyle,
+    theme: Theme,
 }
 
 impl Styles {
@@ -29,6 +31,21 @@ impl Styles {
         Self::default()
     }
 
+    /// No styles, with theme colors and fonts resolved from `theme`
+    #[must_use]
+    pub fn with_theme(theme: Theme) -> Self {
+        Self {
+            theme,
+            ..Self::default()
+        }
+    }
+
+    /// Theme that run colors and fonts refer to
+    #[must_use]
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
     /// Resolve effective text style for a paragraph/run
     /// TODO: Implement full inheritance (Style -> BasedOn -> Defaults)
     pub fn resolve_text_style(
@@ -58,6 +75,9 @@ impl Styles {
                 if let Some(ref font) = style.text_style.font_family {
                     resolved.font_family = Some(font.clone());
                 }
+                if let Some(ref language) = style.text_style.language {
+                    resolved.language = Some(language.clone());
+                }
             }
         }
 
@@ -80,11 +100,37 @@ impl Styles {
         if let Some(ref font) = direct_formatting.font_family {
             resolved.font_family = Some(font.clone());
         }
+        if let Some(ref language) = direct_formatting.language {
+            resolved.language = Some(language.clone());
+        }
 
         resolved
     }
 
-    pub fn from_xml(xml: &str) -> Result<Self> {
+    /// Paragraph direction set by a style or one it is based on
+    #[must_use]
+    pub fn paragraph_direction(&self, style_id: Option<&str>) -> TextDirection {
+        let mut next = style_id;
+        // Bounded walk, so a cyclic basedOn chain cannot loop forever
+        for _ in 0..16 {
+            let Some(style) = next.and_then(|id| self.styles.get(id)) else {
+                break;
+            };
+            if style.para_style.direction != TextDirection::Auto {
+                return style.para_style.direction;
+            }
+            next = style.based_on.as_deref();
+        }
+        self.default_paragraph_style.direction
+    }
+
+    /// Parse `word/styles.xml`, resolving theme colors and fonts from
+    /// `theme`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML is malformed.
+    pub fn from_xml(xml: &str, theme: Theme) -> Result<Self> {
         let mut styles = HashMap::new();
         let mut reader = Reader::from_str(xml);
         reader.trim_text(true);
@@ -136,14 +182,13 @@ impl Styles {
                             b"w:b" => style.text_style.bold = true,
                             b"w:i" => style.text_style.italic = true,
                             b"w:u" => style.text_style.underline = true,
+                            b"w:bidi" => style.para_style.direction = bidi(&e),
                             b"w:color" => {
-                                for attr in e.attributes().flatten() {
-                                    if attr.key.as_ref() == b"w:val" {
-                                        let val = utils::attr_value(&attr.value);
-                                        if val != "auto" {
-                                            style.text_style.color = Some(format!("#{}", val));
-                                        }
-                                    }
+                                style.text_style.color = color(&e, &theme).map(Into::into);
+                            }
+                            b"w:rFonts" => {
+                                if let Some(font) = font_family(&e, &theme) {
+                                    style.text_style.font_family = Some(font.into());
                                 }
                             }
                             b"w:sz" => {
@@ -181,6 +226,24 @@ impl Styles {
                             b"w:b" => style.text_style.bold = true,
                             b"w:i" => style.text_style.italic = true,
                             b"w:u" => style.text_style.underline = true,
+                            b"w:bidi" => style.para_style.direction = bidi(&e),
+                            b"w:lang" => {
+                                style.text_style.language =
+                                    utils::attr_value_opt(&e, b"w:val").map(Into::into);
+                            }
+                            b"w:color" => {
+                                style.text_style.color = color(&e, &theme).map(Into::into);
+                            }
+                            b"w:sz" => {
+                                style.text_style.font_size = utils::attr_value_opt(&e, b"w:val")
+                                    .and_then(|val| val.parse::<f64>().ok())
+                                    .map(|val| val / 2.0);
+                            }
+                            b"w:rFonts" => {
+                                if let Some(font) = font_family(&e, &theme) {
+                                    style.text_style.font_family = Some(font.into());
+                                }
+                            }
                             // TODO: Handle more empty tags
                             _ => {}
                         }
@@ -204,6 +267,47 @@ impl Styles {
             styles,
             default_paragraph_style: ParagraphStyle::default(),
             default_text_style: TextStyle::default(),
+            theme,
         })
     }
 }
+
+/// Direction given by a `w:bidi` paragraph property
+#[must_use]
+pub fn bidi(e: &BytesStart<'_>) -> TextDirection {
+    if utils::is_on(e) {
+        TextDirection::Rtl
+    } else {
+        TextDirection::Ltr
+    }
+}
+
+/// Color given by a `w:color` run property as `#RRGGBB`, taken from the
+/// theme when it names a theme color, or `None` if it is automatic
+#[must_use]
+pub fn color(e: &BytesStart<'_>, theme: &Theme) -> Option<String> {
+    let byte = |key: &[u8]| {
+        utils::attr_value_opt(e, key).and_then(|value| u8::from_str_radix(&value, 16).ok())
+    };
+    utils::attr_value_opt(e, b"w:themeColor")
+        .and_then(|name| {
+            theme.resolve_word_color(&name, byte(b"w:themeTint"), byte(b"w:themeShade"))
+        })
+        .or_else(|| utils::attr_value_opt(e, b"w:val").filter(|val| val != "auto"))
+        .map(|hex| format!("#{hex}"))
+}
+
+/// Font given by a `w:rFonts` run property, taken from the theme when it
+/// names a theme font such as `minorHAnsi`
+#[must_use]
+pub fn font_family(e: &BytesStart<'_>, theme: &Theme) -> Option<String> {
+    let themed = |key: &[u8]| {
+        utils::attr_value_opt(e, key)
+            .and_then(|font_ref| theme.resolve_font(&font_ref).map(str::to_string))
+    };
+    // A theme font overrides the explicit one
+    themed(b"w:asciiTheme")
+        .or_else(|| utils::attr_value_opt(e, b"w:ascii"))
+        .or_else(|| themed(b"w:hAnsiTheme"))
+        .or_else(|| utils::attr_value_opt(e, b"w:hAnsi"))
+}
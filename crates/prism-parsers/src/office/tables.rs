@@ -178,13 +178,18 @@ pub fn parse_drawingml_table<R: BufRead>(reader: &mut Reader<R>) -> Result<Table
                         cell_content.clear();
                     }
                     b"a:txBody" => {
-                        let text_runs =
-                            crate::office::shapes::parse_text_body(reader, &mut buf, b"a:txBody");
-                        if !text_runs.is_empty() {
+                        let text = crate::office::shapes::parse_text_body(
+                            reader,
+                            &mut buf,
+                            b"a:txBody",
+                            &std::collections::HashMap::new(),
+                        );
+                        if !text.runs.is_empty() {
                             let mut block = TextBlock::new(Rect::default());
-                            for run in text_runs {
+                            for run in text.runs {
                                 block.add_run(run);
                             }
+                            block.direction = text.direction;
                             cell_content.push(ContentBlock::Text(block));
                         }
                     }
@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: AGPL-3.0-only
-use crate::office::utils::attr_value;
+use crate::office::utils::{self, attr_value};
 use prism_core::error::{Error, Result};
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
@@ -18,6 +18,105 @@ impl Theme {
     pub fn resolve_color(&self, color_ref: &str) -> Option<String> {
         self.color_scheme.get(color_ref).cloned()
     }
+
+    /// Resolve a `w:themeColor` of a DOCX run (e.g., "accent1", "text1") to
+    /// a hex string, lightened by a `w:themeTint` or darkened by a
+    /// `w:themeShade` byte
+    #[must_use]
+    pub fn resolve_word_color(
+        &self,
+        theme_color: &str,
+        tint: Option<u8>,
+        shade: Option<u8>,
+    ) -> Option<String> {
+        // Word names the scheme slots after their default role
+        let slot = match theme_color {
+            "dark1" | "text1" => "dk1",
+            "light1" | "background1" => "lt1",
+            "dark2" | "text2" => "dk2",
+            "light2" | "background2" => "lt2",
+            "hyperlink" => "hlink",
+            "followedHyperlink" => "folHlink",
+            other => other,
+        };
+        let hex = self.resolve_color(slot)?;
+        if tint.is_none() && shade.is_none() {
+            return Some(hex);
+        }
+        let (hue, saturation, mut luminance) = rgb_to_hsl(parse_rgb(&hex)?);
+        if let Some(tint) = tint {
+            let tint = f64::from(tint) / 255.0;
+            luminance = luminance * tint + (1.0 - tint);
+        }
+        if let Some(shade) = shade {
+            luminance *= f64::from(shade) / 255.0;
+        }
+        let (r, g, b) = hsl_to_rgb(hue, saturation, luminance);
+        Some(format!("{r:02X}{g:02X}{b:02X}"))
+    }
+
+    /// Resolve a theme font reference (e.g., "minorHAnsi", "majorBidi") to
+    /// a typeface
+    #[must_use]
+    pub fn resolve_font(&self, font_ref: &str) -> Option<&str> {
+        if font_ref.starts_with("major") {
+            self.major_font.as_deref()
+        } else if font_ref.starts_with("minor") {
+            self.minor_font.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_rgb(hex: &str) -> Option<(u8, u8, u8)> {
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    if hex.len() != 6 {
+        return None;
+    }
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn rgb_to_hsl((r, g, b): (u8, u8, u8)) -> (f64, f64, f64) {
+    let (r, g, b) = (
+        f64::from(r) / 255.0,
+        f64::from(g) / 255.0,
+        f64::from(b) / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let luminance = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, luminance);
+    }
+    let saturation = delta / (1.0 - (2.0 * luminance - 1.0).abs());
+    let hue = if r >= g && r >= b {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if g >= b {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    (hue * 60.0, saturation, luminance)
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, luminance: f64) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * luminance - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let second = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (red, green, blue) = match sector {
+        s if s < 1.0 => (chroma, second, 0.0),
+        s if s < 2.0 => (second, chroma, 0.0),
+        s if s < 3.0 => (0.0, chroma, second),
+        s if s < 4.0 => (0.0, second, chroma),
+        s if s < 5.0 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let lightness = luminance - chroma / 2.0;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let channel = |value: f64| ((value + lightness).clamp(0.0, 1.0) * 255.0).round() as u8;
+    (channel(red), channel(green), channel(blue))
 }
 
 pub fn parse_theme(content: &[u8]) -> Result<Theme> {
@@ -37,7 +136,9 @@ pub fn parse_theme(content: &[u8]) -> Result<Theme> {
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
+            // Colors and typefaces are usually empty elements, e.g.
+            // <a:srgbClr val="4472C4"/>
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) => {
                 let name = e.name();
                 match name.as_ref() {
                     b"a:theme" => {
@@ -84,24 +185,17 @@ pub fn parse_theme(content: &[u8]) -> Result<Theme> {
 
                             // Check for color definitions inside a color slot
                             if let Some(ref slot) = current_clr_tag {
-                                if tag_name == "a:srgbClr" || tag_name == "a:sysClr" {
-                                    for attr in e.attributes().flatten() {
-                                        if attr.key.as_ref() == b"val" {
-                                            let val = attr_value(&attr.value);
-                                            // Extract actual name from "a:dk1" -> "dk1"
-                                            let slot_key = slot.replace("a:", "");
-                                            theme.color_scheme.insert(slot_key, val);
-                                        }
-                                        if attr.key.as_ref() == b"lastClr"
-                                            && !theme
-                                                .color_scheme
-                                                .contains_key(&slot.replace("a:", ""))
-                                        {
-                                            let val = attr_value(&attr.value);
-                                            let slot_key = slot.replace("a:", "");
-                                            theme.color_scheme.insert(slot_key, val);
-                                        }
-                                    }
+                                // A system color names its value, e.g.
+                                // "windowText", with the RGB in lastClr
+                                let key: &[u8] = match tag_name.as_str() {
+                                    "a:srgbClr" => b"val",
+                                    "a:sysClr" => b"lastClr",
+                                    _ => b"",
+                                };
+                                if let Some(val) = utils::attr_value_opt(e, key) {
+                                    // Extract actual name from "a:dk1" -> "dk1"
+                                    let slot_key = slot.replace("a:", "");
+                                    theme.color_scheme.insert(slot_key, val);
                                 }
                             } else if tag_name.starts_with("a:") {
                                 // Assume this is a color slot like a:dk1, a:lt1, a:accent1
@@ -139,3 +233,51 @@ pub fn parse_theme(content: &[u8]) -> Result<Theme> {
 
     Ok(theme)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str = r#"<a:theme name="Office Theme"><a:themeElements>
+        <a:clrScheme name="Office">
+        <a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1>
+        <a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1>
+        <a:accent1><a:srgbClr val="4472C4"/></a:accent1>
+        </a:clrScheme>
+        <a:fontScheme name="Office">
+        <a:majorFont><a:latin typeface="Calibri Light"/><a:ea typeface=""/></a:majorFont>
+        <a:minorFont><a:latin typeface="Calibri"/><a:ea typeface=""/></a:minorFont>
+        </a:fontScheme></a:themeElements></a:theme>"#;
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = parse_theme(THEME.as_bytes()).unwrap();
+        assert_eq!(theme.name, "Office Theme");
+        assert_eq!(theme.resolve_color("dk1").as_deref(), Some("000000"));
+        assert_eq!(theme.resolve_color("accent1").as_deref(), Some("4472C4"));
+        assert_eq!(theme.major_font.as_deref(), Some("Calibri Light"));
+        assert_eq!(theme.minor_font.as_deref(), Some("Calibri"));
+    }
+
+    #[test]
+    fn test_word_colors_and_fonts() {
+        let theme = parse_theme(THEME.as_bytes()).unwrap();
+        let color = |name, tint, shade| theme.resolve_word_color(name, tint, shade);
+        assert_eq!(color("text1", None, None).as_deref(), Some("000000"));
+        assert_eq!(color("background1", None, None).as_deref(), Some("FFFFFF"));
+        // Word's "Darker 25%" and "Lighter 40%", give or take rounding
+        assert_eq!(
+            color("accent1", None, Some(0xBF)).as_deref(),
+            Some("2F5496")
+        );
+        assert_eq!(
+            color("accent1", Some(0x99), None).as_deref(),
+            Some("8FAADC")
+        );
+        assert_eq!(color("accent6", None, None), None);
+
+        assert_eq!(theme.resolve_font("majorHAnsi"), Some("Calibri Light"));
+        assert_eq!(theme.resolve_font("minorBidi"), Some("Calibri"));
+        assert_eq!(theme.resolve_font("Arial"), None);
+    }
+}
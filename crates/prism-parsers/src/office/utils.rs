@@ -90,6 +90,36 @@ pub fn attr_value_opt(event: &quick_xml::events::BytesStart<'_>, key: &[u8]) ->
     None
 }
 
+/// Resolve a relationship target against the directory of its source part
+///
+/// `resolve_path("ppt/slides", "../notesSlides/notesSlide1.xml")` is
+/// `ppt/notesSlides/notesSlide1.xml`.
+#[must_use]
+pub fn resolve_path(dir: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = dir.split('/').collect();
+    for part in target.split(['/', '\\']) {
+        match part {
+            ".." => {
+                parts.pop();
+            }
+            "." => {}
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Value of an OOXML on/off toggle such as `<w:bidi/>` or `<w:rtl w:val="0"/>`
+///
+/// A missing `w:val` means on.
+#[must_use]
+pub fn is_on(event: &quick_xml::events::BytesStart<'_>) -> bool {
+    !matches!(
+        attr_value_opt(event, b"w:val").as_deref(),
+        Some("0" | "false" | "off")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,4 +176,31 @@ mod tests {
             assert_eq!(index, i);
         }
     }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(
+            resolve_path("ppt/slides", "../media/image1.png"),
+            "ppt/media/image1.png"
+        );
+        assert_eq!(
+            resolve_path("ppt/slides", "./notes\\notesSlide1.xml"),
+            "ppt/slides/notes/notesSlide1.xml"
+        );
+    }
+
+    #[test]
+    fn test_is_on() {
+        let toggle = |xml: &str| {
+            let mut reader = quick_xml::Reader::from_str(xml);
+            match reader.read_event() {
+                Ok(quick_xml::events::Event::Empty(e)) => is_on(&e),
+                other => panic!("unexpected event {other:?}"),
+            }
+        };
+        assert!(toggle("<w:bidi/>"));
+        assert!(toggle(r#"<w:bidi w:val="1"/>"#));
+        assert!(!toggle(r#"<w:bidi w:val="0"/>"#));
+        assert!(!toggle(r#"<w:rtl w:val="false"/>"#));
+    }
 }
@@ -2,26 +2,55 @@
 //! XLSX (Excel) parser
 //!
 //! Parses XLSX (Office Open XML Spreadsheet) files into the Unified Document Model.
-//! Each worksheet becomes a Page containing a TableBlock with the cell grid.
+//! Each worksheet becomes a Page containing a TableBlock with the cell grid,
+//! listed as a sheet section. Cell comments are listed in
+//! [`Document::revisions`].
+//!
+//! Charts drawn on a sheet follow its cells as vector drawings.
+//!
+//! Hidden sheets, rows and columns, frozen panes and print areas are
+//! recorded in [`PageMetadata::sheet`]; hidden sheets, rows and columns
+//! are left out instead when
+//! [`ParseOptions::hidden`](prism_core::parser::ParseOptions::hidden) is
+//! [`HiddenContent::Skip`].
+//!
+//! Opening the package, reading the shared strings and parsing each sheet
+//! are timed into [`Document::timings`].
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use calamine::{open_workbook_auto_from_rs, Data, Reader, Sheets};
+use calamine::{open_workbook_auto_from_rs, Data, Range, Reader, Sheets};
 use prism_core::{
     document::{
-        ContentBlock, Dimensions, Document, Page, PageMetadata, TableBlock, TableCell, TableRow,
-        TextBlock, TextRun, TextStyle,
+        Attachment, ContentBlock, Dimensions, Document, Page, PageMetadata, Rect, Revision, Section,
+        SectionKind, TableBlock, TableCell, TableRow, TextBlock, TextRun, TextStyle,
     },
-    error::{Error, Result},
+    error::{Error, ErrorLocation, Result},
     format::Format,
+    intern::Interner,
     metadata::Metadata,
-    parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+    parser::{HiddenContent, ParseContext, Parser, ParserFeature, ParserMetadata},
+    progress::ProgressEvent,
+    timing::StageTimer,
 };
-use std::io::{Cursor, Read};
+use rayon::prelude::*;
+use std::io::{Cursor, Read, Seek};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, info, warn};
 use zip::ZipArchive;
 
+use crate::encryption;
+use crate::office::cells;
+use crate::office::charts;
+use crate::office::comments;
+use crate::office::embeddings;
 use crate::office::excel_styles::ExcelStyles;
+use crate::office::package;
+use crate::office::relationships::Relationships;
+use crate::office::sheets::{self, read_part, SheetView, WorkbookSheet};
+use crate::office::utils;
+use crate::security;
+use crate::signatures;
 
 /// XLSX (Excel) parser
 ///
@@ -41,17 +70,13 @@ impl XlsxParser {
     }
 
     /// Convert a calamine Data to a TextRun with fallback style
-    fn data_to_text_run(&self, data: &Data) -> TextRun {
+    ///
+    /// String cells are interned so that values repeated down a column share
+    /// one allocation.
+    fn data_to_text_run(&self, data: &Data, interner: &mut Interner) -> TextRun {
         let text = match data {
-            Data::Int(i) => i.to_string(),
-            Data::Float(f) => f.to_string(),
-            Data::String(s) => s.clone(),
-            Data::Bool(b) => b.to_string(),
-            Data::DateTime(dt) => format!("{}", dt),
-            Data::DateTimeIso(dt) => dt.clone(),
-            Data::DurationIso(d) => d.clone(),
-            Data::Error(e) => format!("#{:?}", e),
-            Data::Empty => String::new(),
+            Data::String(value) => interner.intern(value),
+            _ => cells::cell_text(data).into(),
         };
 
         TextRun {
@@ -59,6 +84,8 @@ impl XlsxParser {
             style: TextStyle::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         }
     }
 
@@ -84,12 +111,135 @@ impl XlsxParser {
         //
         // Re-reading sheet XML is the only way to get true fidelity of `s` (style) attribute if calamine hides it.
         // Let's implement basic loading first.
-        _styles: &Option<ExcelStyles>,
+        _styles: Option<&ExcelStyles>,
     ) -> (TextStyle, Option<String>) {
         (TextStyle::default(), None)
     }
 
+    /// Build the page of a worksheet, or `None` if the sheet is empty
+    fn sheet_page(
+        &self,
+        sheet_index: usize,
+        sheet_name: &str,
+        range: &Range<Data>,
+        styles: Option<&ExcelStyles>,
+    ) -> Option<Page> {
+        // Get dimensions
+        let (row_count, col_count) = range.get_size();
+        debug!(
+            "Sheet '{}' size: {}x{} (rows x cols)",
+            sheet_name, row_count, col_count
+        );
+
+        if row_count == 0 || col_count == 0 {
+            debug!("Sheet '{}' is empty, skipping", sheet_name);
+            return None;
+        }
+
+        // Build table rows
+        let mut table_rows = Vec::new();
+        let mut interner = Interner::new();
+
+        for row_idx in 0..row_count {
+            let mut cells = Vec::new();
+
+            for col_idx in 0..col_count {
+                let cell_data = range.get((row_idx, col_idx));
+
+                // In the future, match (row_idx, col_idx) with parsed sheet XML to get style ID
+                let (_style, _bg_color) = self.apply_style(row_idx, col_idx, styles);
+
+                let content = if let Some(data) = cell_data.filter(|data| !matches!(data, Data::Empty)) {
+                    // Create text block from cell data
+                    let text_run = self.data_to_text_run(data, &mut interner);
+                    // Convert Excel styles to UDM styles if we had the mapping
+                    // text_run.style = style;
+
+                    vec![ContentBlock::Text(TextBlock {
+                        bounds: prism_core::document::Rect {
+                            x: 0.0,
+                            y: 0.0,
+                            width: 0.0,
+                            height: 0.0,
+                        },
+                        runs: vec![text_run],
+                        paragraph_style: None,
+                        style: prism_core::document::ShapeStyle::default(),
+                        rotation: 0.0,
+                        direction: prism_core::document::TextDirection::Auto,
+                    })]
+                } else {
+                    // Empty cell
+                    vec![]
+                };
+
+                cells.push(TableCell {
+                    content,
+                    col_span: 1,
+                    row_span: 1,
+                    background_color: None, // bg_color
+                });
+            }
+
+            table_rows.push(TableRow {
+                cells,
+                height: None,
+            });
+        }
+
+        // Create table block
+        let table_block = TableBlock {
+            bounds: prism_core::document::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: col_count as f64 * 72.0, // Approximate column width
+                height: row_count as f64 * 20.0, // Approximate row height
+            },
+            rows: table_rows,
+            column_count: col_count as usize,
+            style: prism_core::document::ShapeStyle::default(),
+            rotation: 0.0,
+        };
+
+        // Create page for this sheet
+        let page_metadata = PageMetadata {
+            label: Some(sheet_name.to_string()),
+            ..PageMetadata::default()
+        };
+
+        Some(Page {
+            number: (sheet_index + 1) as u32,
+            dimensions: Dimensions::LETTER, // Standard paper size
+            content: vec![ContentBlock::Table(table_block)],
+            metadata: page_metadata,
+            annotations: Vec::new(),
+        })
+    }
+
     /// Check if data is an XLSX file by checking ZIP signature
+    /// Read and lay out one sheet, `None` if it is empty
+    fn read_sheet<RS: Read + Seek>(
+        &self,
+        workbook: &mut Sheets<RS>,
+        sheet_index: usize,
+        sheet_name: &str,
+        parts: &WorkbookParts,
+        skip_hidden: bool,
+    ) -> Result<Option<Page>> {
+        let range = workbook.worksheet_range(sheet_name).map_err(|e| {
+            Error::corrupt("XLSX", format!("Failed to read sheet '{sheet_name}': {e}"))
+        })?;
+        Ok(self
+            .sheet_page(sheet_index, sheet_name, &range, parts.styles.as_ref())
+            .map(|mut page| {
+                parts.lay_out(&mut page, sheet_index, &range, skip_hidden);
+                if let Some(charts) = parts.charts.get(sheet_index) {
+                    page.content.extend(charts.iter().cloned());
+                }
+                page
+            }))
+    }
+
     fn is_xlsx_zip(data: &[u8]) -> bool {
         // Check ZIP signature: PK (0x504B)
         if data.len() < 4 {
@@ -118,7 +268,8 @@ impl Parser for XlsxParser {
     fn can_parse(&self, data: &[u8]) -> bool {
         // XLSX is a ZIP file, so check ZIP signature
         if !Self::is_xlsx_zip(data) {
-            return false;
+            // Encrypted packages are accepted so parsing can report the encryption
+            return encryption::ooxml_encryption(data).is_some();
         }
 
         // Additional check: try to verify it's an XLSX by looking for xl/ directory
@@ -134,36 +285,26 @@ impl Parser for XlsxParser {
 
         // Validate ZIP signature
         if !Self::is_xlsx_zip(&data) {
-            return Err(Error::ParseError(
-                "Invalid XLSX signature (not a ZIP file)".to_string(),
-            ));
-        }
-
-        // 1. Parse Styles
-        // We open the zip separately to read styles.xml
-        let mut styles: Option<ExcelStyles> = None;
-        let cursor_zip = Cursor::new(data.as_ref());
-        if let Ok(mut archive) = ZipArchive::new(cursor_zip) {
-            if let Ok(mut styles_file) = archive.by_name("xl/styles.xml") {
-                let mut xml = String::new();
-                if styles_file.read_to_string(&mut xml).is_ok() {
-                    if let Ok(parsed_styles) = ExcelStyles::from_xml(&xml) {
-                        debug!(
-                            "Parsed {} fonts, {} fills, {} cellXfs",
-                            parsed_styles.fonts.len(),
-                            parsed_styles.fills.len(),
-                            parsed_styles.cell_xfs.len()
-                        );
-                        styles = Some(parsed_styles);
-                    }
-                }
-            }
+            return Err(Error::corrupt("XLSX", "Invalid signature (not a ZIP file)")
+                .at(ErrorLocation::offset(0)));
         }
 
-        // 2. Open workbook using calamine for Data
-        let cursor = Cursor::new(data.as_ref());
-        let mut workbook: Sheets<_> = open_workbook_auto_from_rs(cursor)
-            .map_err(|e| Error::ParseError(format!("Failed to open XLSX workbook: {}", e)))?;
+        // 1. Parse styles, sheet views and comments
+        // We open the zip separately to read them
+        let timer = StageTimer::new(&context.options);
+        let mut diagnostics = Vec::new();
+        let package = timer.time("zip_open", || {
+            package::package_bytes(&data, "XLSX", &context.options, &mut diagnostics)
+        })?;
+        let active_content = security::check(&package, "XLSX", &context.options)?;
+        let mut parts = timer.time("workbook_parts", || Ok(workbook_parts(&package)))?;
+
+        // 2. Open workbook using calamine for Data; calamine reads the
+        // shared strings when opening
+        let workbook: Sheets<_> = timer.time("shared_strings", || {
+            open_workbook_auto_from_rs(Cursor::new(package.as_ref()))
+                .map_err(|e| Error::corrupt("XLSX", format!("Failed to open workbook: {e}")))
+        })?;
 
         let sheet_names = workbook.sheet_names().to_vec();
         let sheet_count = sheet_names.len();
@@ -175,113 +316,55 @@ impl Parser for XlsxParser {
 
         if sheet_count == 0 {
             warn!("XLSX workbook has no sheets");
-            return Ok(Document::builder()
+            let mut document = Document::builder()
                 .metadata(Metadata::builder().title("Empty Workbook").build())
-                .build());
+                .build();
+            document.diagnostics = diagnostics;
+            document.timings = timer.into_timings();
+            return Ok(document);
         }
 
-        let mut pages = Vec::new();
-
-        // Process each worksheet
-        for (sheet_index, sheet_name) in sheet_names.iter().enumerate() {
-            debug!("Processing sheet {}: {}", sheet_index + 1, sheet_name);
-
-            let range = match workbook.worksheet_range(sheet_name) {
-                Ok(range) => range,
-                Err(e) => {
-                    warn!("Failed to read sheet '{}': {}", sheet_name, e);
-                    continue;
-                }
-            };
-
-            // Get dimensions
-            let (row_count, col_count) = range.get_size();
-            debug!(
-                "Sheet '{}' size: {}x{} (rows x cols)",
-                sheet_name, row_count, col_count
-            );
-
-            if row_count == 0 || col_count == 0 {
-                debug!("Sheet '{}' is empty, skipping", sheet_name);
-                continue;
-            }
-
-            // Build table rows
-            let mut table_rows = Vec::new();
-
-            for row_idx in 0..row_count {
-                let mut cells = Vec::new();
-
-                for col_idx in 0..col_count {
-                    let cell_data = range.get((row_idx, col_idx));
-
-                    // In the future, match (row_idx, col_idx) with parsed sheet XML to get style ID
-                    let (_style, _bg_color) = self.apply_style(row_idx, col_idx, &styles);
-
-                    let content = if let Some(data) = cell_data {
-                        // Create text block from cell data
-                        let text_run = self.data_to_text_run(data);
-                        // Convert Excel styles to UDM styles if we had the mapping
-                        // text_run.style = style;
-
-                        vec![ContentBlock::Text(TextBlock {
-                            bounds: prism_core::document::Rect {
-                                x: 0.0,
-                                y: 0.0,
-                                width: 0.0,
-                                height: 0.0,
-                            },
-                            runs: vec![text_run],
-                            paragraph_style: None,
-                            style: prism_core::document::ShapeStyle::default(),
-                            rotation: 0.0,
-                        })]
-                    } else {
-                        // Empty cell
-                        vec![]
-                    };
-
-                    cells.push(TableCell {
-                        content,
-                        col_span: 1,
-                        row_span: 1,
-                        background_color: None, // bg_color
+        // Hidden sheets are left out when asked to, along with their comments
+        let skip_hidden = context.options.hidden == HiddenContent::Skip;
+        if skip_hidden {
+            parts.skip_hidden_comments();
+        }
+        let is_skipped = |index: usize| skip_hidden && parts.is_hidden(index);
+        let total = (0..sheet_count).filter(|&index| !is_skipped(index)).count();
+
+        // Sheets are independent, so they are read in parallel. calamine
+        // needs `&mut` access to read a sheet, so every rayon job opens its
+        // own reader; results are collected in sheet order.
+        let parsed = AtomicUsize::new(0);
+        let sheets: Vec<Result<Option<Page>>> = sheet_names
+            .par_iter()
+            .enumerate()
+            .filter(|(sheet_index, _)| !is_skipped(*sheet_index))
+            .map_init(
+                || open_workbook_auto_from_rs(Cursor::new(package.as_ref())),
+                |workbook, (sheet_index, sheet_name)| {
+                    debug!("Processing sheet {}: {}", sheet_index + 1, sheet_name);
+                    let page = timer.time(format!("sheet:{sheet_name}"), || {
+                        let workbook = workbook.as_mut().map_err(|e| {
+                            Error::corrupt("XLSX", format!("Failed to open workbook: {e}"))
+                        })?;
+                        self.read_sheet(workbook, sheet_index, sheet_name, &parts, skip_hidden)
                     });
-                }
-
-                table_rows.push(TableRow {
-                    cells,
-                    height: None,
-                });
-            }
-
-            // Create table block
-            let table_block = TableBlock {
-                bounds: prism_core::document::Rect {
-                    x: 0.0,
-                    y: 0.0,
-                    width: col_count as f64 * 72.0, // Approximate column width
-                    height: row_count as f64 * 20.0, // Approximate row height
+                    context.report(ProgressEvent::PageParsed {
+                        parsed: parsed.fetch_add(1, Ordering::Relaxed) + 1,
+                        total: Some(total),
+                    });
+                    page
                 },
-                rows: table_rows,
-                column_count: col_count as usize,
-                style: prism_core::document::ShapeStyle::default(),
-                rotation: 0.0,
-            };
-
-            // Create page for this sheet
-            let mut page_metadata = PageMetadata::default();
-            page_metadata.label = Some(sheet_name.clone());
-
-            let page = Page {
-                number: (sheet_index + 1) as u32,
-                dimensions: Dimensions::LETTER, // Standard paper size
-                content: vec![ContentBlock::Table(table_block)],
-                metadata: page_metadata,
-                annotations: Vec::new(),
-            };
+            )
+            .collect();
 
-            pages.push(page);
+        let mut pages = Vec::new();
+        for sheet in sheets {
+            match sheet {
+                Ok(page) => pages.extend(page),
+                Err(e) => context.options.recover(e, &mut diagnostics)?,
+            }
         }
 
         // Create document metadata
@@ -293,12 +376,26 @@ impl Parser for XlsxParser {
         // Add custom metadata for Excel-specific info
         metadata.add_custom("excel_sheet_count", sheet_count as i64);
         metadata.add_custom("excel_sheet_names", sheet_names.join(", "));
+        metadata.signatures = signatures::verify(&package);
+        metadata.active_content = active_content;
 
         // Build document
         let mut document = Document::builder().metadata(metadata).build();
 
-        // Add pages to the document
+        // Add pages to the document, with a section per sheet
+        document.structure.sections = pages
+            .iter()
+            .map(|page| {
+                let name = page.metadata.label.clone().unwrap_or_default();
+                Section::page(SectionKind::Sheet, name, page)
+            })
+            .collect();
         document.pages = pages;
+        document.diagnostics = diagnostics;
+        document.revisions = parts.revisions;
+        document.attachments = parts.attachments;
+        document.timings = timer.into_timings();
+        embeddings::parse_embedded(&mut document, &context.options).await;
 
         info!("Successfully parsed XLSX with {} sheets", sheet_count);
 
@@ -319,9 +416,193 @@ impl Parser for XlsxParser {
     }
 }
 
+/// Parts of the package read besides the cells
+#[derive(Default)]
+struct WorkbookParts {
+    /// Styles of `xl/styles.xml`
+    styles: Option<ExcelStyles>,
+    /// Sheets listed in the workbook
+    sheets: Vec<WorkbookSheet>,
+    /// Views of the sheets, in the same order
+    views: Vec<SheetView>,
+    /// Comments of every sheet
+    revisions: Vec<Revision>,
+    /// Charts drawn on each sheet, in the same order as the sheets
+    charts: Vec<Vec<ContentBlock>>,
+    /// Files embedded in the sheets
+    attachments: Vec<Attachment>,
+}
+
+impl WorkbookParts {
+    /// Whether sheet `index` is hidden
+    fn is_hidden(&self, index: usize) -> bool {
+        self.sheets.get(index).is_some_and(|sheet| sheet.hidden)
+    }
+
+    /// Drop the comments of hidden sheets
+    fn skip_hidden_comments(&mut self) {
+        let sheets = &self.sheets;
+        self.revisions.retain(|revision| {
+            let index = revision.page.and_then(|page| page.checked_sub(1));
+            !index.is_some_and(|index| sheets.get(index as usize).is_some_and(|sheet| sheet.hidden))
+        });
+    }
+
+    /// Record the layout of sheet `index`, read into `range`, in its page,
+    /// removing hidden rows and columns with `skip_hidden`
+    fn lay_out(&self, page: &mut Page, index: usize, range: &Range<Data>, skip_hidden: bool) {
+        let (row, column) = range.start().unwrap_or_default();
+        page.metadata.sheet = Some(sheets::layout(
+            self.sheets.get(index).unwrap_or(&WorkbookSheet::default()),
+            self.views.get(index).unwrap_or(&SheetView::default()),
+            (row as usize, column as usize),
+            range.get_size(),
+        ));
+        if skip_hidden {
+            sheets::skip_hidden(page);
+        }
+    }
+}
+
+/// Read the styles, sheets, sheet views and comments of a package
+fn workbook_parts(package: &[u8]) -> WorkbookParts {
+    let Ok(mut archive) = ZipArchive::new(Cursor::new(package)) else {
+        return WorkbookParts::default();
+    };
+    let sheets = sheets::workbook_sheets(&mut archive);
+    let views = sheets
+        .iter()
+        .map(|sheet| {
+            sheet
+                .path
+                .as_deref()
+                .and_then(|path| read_part(&mut archive, path))
+                .map(|xml| sheets::sheet_view(&xml))
+                .unwrap_or_default()
+        })
+        .collect();
+    let revisions = sheet_comments(&mut archive, &sheets);
+    let charts = sheet_charts(&mut archive, &sheets);
+    let embedded: Vec<String> = sheets
+        .iter()
+        .filter_map(|sheet| sheet_rels(&mut archive, sheet))
+        .flat_map(|(dir, rels)| embeddings::embedded_parts(dir, &rels))
+        .collect();
+    let attachments = embeddings::read_embeddings(&mut archive, &embedded);
+    let styles =
+        read_part(&mut archive, "xl/styles.xml").and_then(|xml| ExcelStyles::from_xml(&xml).ok());
+    if let Some(styles) = &styles {
+        debug!(
+            "Parsed {} fonts, {} fills, {} cellXfs",
+            styles.fonts.len(),
+            styles.fills.len(),
+            styles.cell_xfs.len()
+        );
+    }
+    WorkbookParts {
+        styles,
+        sheets,
+        views,
+        revisions,
+        charts,
+        attachments,
+    }
+}
+
+/// Directory of the part of `sheet` and the sheet's relationships, which
+/// are empty if it has none
+fn sheet_rels<'a, R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    sheet: &'a WorkbookSheet,
+) -> Option<(&'a str, Relationships)> {
+    let (dir, name) = sheet.path.as_deref()?.rsplit_once('/')?;
+    let rels = read_part(archive, &format!("{dir}/_rels/{name}.rels"))
+        .and_then(|xml| Relationships::from_xml(&xml).ok())
+        .unwrap_or_default();
+    Some((dir, rels))
+}
+
+/// Comments of every sheet, on the page of their sheet
+///
+/// The comments part of a sheet is found through the sheet's
+/// relationships.
+fn sheet_comments<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    sheets: &[WorkbookSheet],
+) -> Vec<Revision> {
+    let mut revisions = Vec::new();
+    for (index, sheet) in sheets.iter().enumerate() {
+        let Some((dir, sheet_rels)) = sheet_rels(archive, sheet) else {
+            continue;
+        };
+        let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        let mut parts: Vec<String> = sheet_rels
+            .map
+            .values()
+            .filter(|rel| rel.rel_type.ends_with("/comments"))
+            .map(|rel| utils::resolve_path(dir, &rel.target))
+            .collect();
+        parts.sort();
+        for part in parts {
+            if let Some(xml) = read_part(archive, &part) {
+                revisions.extend(comments::xlsx_comments(&xml, number));
+            }
+        }
+    }
+    revisions
+}
+
+/// Charts drawn on every sheet, as blocks following the cells
+///
+/// Charts are found through the sheet's drawing part and sized after their
+/// anchors.
+fn sheet_charts<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    sheets: &[WorkbookSheet],
+) -> Vec<Vec<ContentBlock>> {
+    sheets
+        .iter()
+        .map(|sheet| {
+            let Some((dir, sheet_rels)) = sheet_rels(archive, sheet) else {
+                return Vec::new();
+            };
+            let mut drawings: Vec<String> = sheet_rels
+                .map
+                .values()
+                .filter(|rel| rel.rel_type.ends_with("/drawing"))
+                .map(|rel| utils::resolve_path(dir, &rel.target))
+                .collect();
+            drawings.sort();
+            let mut blocks = Vec::new();
+            for drawing in drawings {
+                let Some(xml) = read_part(archive, &drawing) else {
+                    continue;
+                };
+                let Some((drawing_dir, drawing_name)) = drawing.rsplit_once('/') else {
+                    continue;
+                };
+                let drawing_rels =
+                    read_part(archive, &format!("{drawing_dir}/_rels/{drawing_name}.rels"))
+                        .and_then(|xml| Relationships::from_xml(&xml).ok())
+                        .unwrap_or_default();
+                let charts = charts::read_charts(archive, drawing_dir, &drawing_rels);
+                for (id, width, height) in charts::drawing_charts(&xml) {
+                    if let Some(chart) = charts.get(&id) {
+                        let mut block = chart.render(Rect::new(0.0, 0.0, width, height));
+                        block.bounds = Rect::default();
+                        blocks.push(ContentBlock::Container(block));
+                    }
+                }
+            }
+            blocks
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_is_xlsx_zip() {
@@ -341,4 +622,28 @@ mod tests {
         let too_short = [0x50, 0x4B];
         assert!(!XlsxParser::is_xlsx_zip(&too_short));
     }
+
+    #[test]
+    fn test_sheet_page_shares_strings() {
+        let mut range = Range::new((0, 0), (2, 1));
+        range.set_value((0, 0), Data::String("Status".to_string()));
+        range.set_value((1, 0), Data::String("Paid".to_string()));
+        range.set_value((2, 0), Data::String("Paid".to_string()));
+        range.set_value((2, 1), Data::Int(7));
+
+        let page = XlsxParser::new()
+            .sheet_page(0, "Sheet1", &range, None)
+            .unwrap();
+        let ContentBlock::Table(table) = &page.content[0] else {
+            panic!("expected a table");
+        };
+        let run = |row: usize, col: usize| match &table.rows[row].cells[col].content[..] {
+            [ContentBlock::Text(text)] => text.runs[0].text.clone(),
+            _ => panic!("expected text in ({row}, {col})"),
+        };
+
+        assert!(Arc::ptr_eq(&run(1, 0), &run(2, 0)));
+        assert_eq!(&*run(2, 1), "7");
+        assert!(table.rows[0].cells[1].content.is_empty());
+    }
 }
@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! PDF page annotations
+//!
+//! Markup a reviewer left on the pages (`/Annots`) maps to UDM annotations:
+//!
+//! - `Highlight`, `Underline`, `Squiggly` and `StrikeOut` to text markup
+//! - `Text` notes and `FreeText` boxes to comments
+//! - `Link` to links, with the URI of their action or `#page-N` for a
+//!   destination in the document
+//! - `Redact`, `Stamp` and `Ink` to their namesakes
+//!
+//! Widgets belong to form fields, which [`super::forms`] reads, and popups
+//! only show the text of their parent, so both are skipped. So are
+//! annotation types the model has no equivalent for, such as file
+//! attachments or sounds.
+//!
+//! Bounds are in points from the top-left corner of the annotation's page.
+
+use chrono::{DateTime, Local, Utc};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use prism_core::document::{Annotation, AnnotationType};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::forms::{dictionary, rect, text};
+
+/// Annotations of every page, in page order
+#[must_use]
+pub fn annotations(pdf: &Document) -> Vec<Annotation> {
+    let pages = pdf.get_pages();
+    let numbers: HashMap<ObjectId, u32> = pages.iter().map(|(&number, &id)| (id, number)).collect();
+
+    let mut annotations = Vec::new();
+    for &id in pages.values() {
+        let Ok(page) = pdf.get_dictionary(id) else {
+            continue;
+        };
+        let Ok(annots) = page.get_deref(b"Annots", pdf).and_then(Object::as_array) else {
+            continue;
+        };
+        for annot in annots {
+            if let Some(annotation) =
+                dictionary(pdf, annot).and_then(|annot| annotation(pdf, annot, page, &numbers))
+            {
+                annotations.push(annotation);
+            }
+        }
+    }
+    annotations
+}
+
+fn annotation(
+    pdf: &Document,
+    annot: &Dictionary,
+    page: &Dictionary,
+    numbers: &HashMap<ObjectId, u32>,
+) -> Option<Annotation> {
+    let subtype = annot.get(b"Subtype").and_then(Object::as_name).ok()?;
+    let annotation_type = match subtype {
+        b"Highlight" => AnnotationType::Highlight,
+        b"Underline" | b"Squiggly" => AnnotationType::Underline,
+        b"StrikeOut" => AnnotationType::Strikeout,
+        b"Text" | b"FreeText" => AnnotationType::Comment,
+        b"Link" => AnnotationType::Link {
+            url: link_target(pdf, annot, numbers)?,
+        },
+        b"Redact" => AnnotationType::Redaction,
+        b"Stamp" => AnnotationType::Stamp,
+        b"Ink" => AnnotationType::Ink,
+        _ => return None,
+    };
+
+    // Stamps without text show their icon name, e.g. `Approved`
+    let content = annot.get(b"Contents").ok().and_then(text).or_else(|| {
+        matches!(annotation_type, AnnotationType::Stamp)
+            .then(|| annot.get(b"Name").and_then(Object::as_name).ok())
+            .flatten()
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+    });
+    let created = [b"CreationDate".as_slice(), b"M"]
+        .into_iter()
+        .find_map(|key| date(annot.get(key).ok()?));
+
+    Some(Annotation {
+        id: Uuid::new_v4(),
+        annotation_type,
+        bounds: rect(pdf, annot, Some(page)).unwrap_or_default(),
+        content,
+        author: annot.get(b"T").ok().and_then(text),
+        created,
+        color: annot.get_deref(b"C", pdf).ok().and_then(color),
+    })
+}
+
+/// Where a link goes: the URI of its action, or the page its destination
+/// is on
+fn link_target(
+    pdf: &Document,
+    annot: &Dictionary,
+    numbers: &HashMap<ObjectId, u32>,
+) -> Option<String> {
+    let action = annot.get_deref(b"A", pdf).and_then(Object::as_dict).ok();
+    let destination = match action {
+        Some(action) => match action.get(b"S").and_then(Object::as_name).ok()? {
+            b"URI" => {
+                let uri = action
+                    .get_deref(b"URI", pdf)
+                    .and_then(Object::as_str)
+                    .ok()?;
+                return Some(String::from_utf8_lossy(uri).into_owned());
+            }
+            b"GoTo" => action.get_deref(b"D", pdf).ok()?,
+            _ => return None,
+        },
+        None => annot.get_deref(b"Dest", pdf).ok()?,
+    };
+    match destination {
+        // `[page /XYZ left top zoom]` and the like
+        Object::Array(destination) => {
+            let page = destination.first()?.as_reference().ok()?;
+            numbers.get(&page).map(|number| format!("#page-{number}"))
+        }
+        // A named destination
+        destination => text(destination).map(|name| format!("#{name}")),
+    }
+}
+
+/// A PDF date such as `D:20240301100000Z`
+fn date(object: &Object) -> Option<DateTime<Utc>> {
+    let date = object.as_datetime()?;
+    DateTime::<Local>::try_from(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// A `/C` color array as `#rrggbb`: one component is gray, three RGB and
+/// four CMYK; an empty array means transparent
+fn color(object: &Object) -> Option<String> {
+    let components = object
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|component| {
+            component
+                .as_float()
+                .ok()
+                .map(|c| f64::from(c).clamp(0.0, 1.0))
+        })
+        .collect::<Option<Vec<f64>>>()?;
+    let [r, g, b] = match components[..] {
+        [gray] => [gray; 3],
+        [r, g, b] => [r, g, b],
+        [c, m, y, k] => [
+            (1.0 - c) * (1.0 - k),
+            (1.0 - m) * (1.0 - k),
+            (1.0 - y) * (1.0 - k),
+        ],
+        _ => return None,
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let channel = |value: f64| (value * 255.0).round() as u8;
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(r),
+        channel(g),
+        channel(b)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, StringFormat};
+
+    fn string(text: &str) -> Object {
+        Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut pdf = Document::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let first = pdf.new_object_id();
+        let second = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        let annots = vec![
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Highlight",
+                "Rect" => vec![72.into(), 692.into(), 272.into(), 712.into()],
+                "C" => vec![1.into(), 1.into(), 0.into()],
+                "T" => string("Dana"),
+                "M" => string("D:20240301100000Z"),
+            }),
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Text",
+                "Rect" => vec![10.into(), 10.into(), 30.into(), 30.into()],
+                "Contents" => string("Check this"),
+            }),
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Link",
+                "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+                "A" => dictionary! {
+                    "S" => "URI",
+                    "URI" => string("https://example.com"),
+                },
+            }),
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Link",
+                "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+                "Dest" => vec![second.into(), "Fit".into()],
+            }),
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Stamp",
+                "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+                "Name" => "Approved",
+            }),
+            Object::Dictionary(dictionary! {
+                "Subtype" => "Widget",
+                "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            }),
+        ];
+        pdf.objects.insert(
+            first,
+            Object::Dictionary(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Annots" => annots,
+            }),
+        );
+        pdf.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![first.into(), second.into()],
+                "Count" => 2,
+            }),
+        );
+        let catalog = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        pdf.trailer.set("Root", catalog);
+
+        let annotations = annotations(&pdf);
+        assert_eq!(annotations.len(), 5);
+        assert!(matches!(
+            annotations[0].annotation_type,
+            AnnotationType::Highlight
+        ));
+        assert!(matches!(
+            annotations[1].annotation_type,
+            AnnotationType::Comment
+        ));
+        assert!(
+            matches!(&annotations[2].annotation_type, AnnotationType::Link { url } if url == "https://example.com")
+        );
+        assert!(
+            matches!(&annotations[3].annotation_type, AnnotationType::Link { url } if url == "#page-2")
+        );
+        assert!(matches!(
+            annotations[4].annotation_type,
+            AnnotationType::Stamp
+        ));
+
+        let highlight = &annotations[0];
+        let bounds = highlight.bounds;
+        assert_eq!(
+            (bounds.x, bounds.y, bounds.width, bounds.height),
+            (72.0, 80.0, 200.0, 20.0)
+        );
+        assert_eq!(highlight.color.as_deref(), Some("#ffff00"));
+        assert_eq!(highlight.author.as_deref(), Some("Dana"));
+        assert!(highlight.created.is_some());
+        assert_eq!(annotations[1].content.as_deref(), Some("Check this"));
+        assert_eq!(annotations[4].content.as_deref(), Some("Approved"));
+    }
+}
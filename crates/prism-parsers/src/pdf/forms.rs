@@ -0,0 +1,320 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! PDF interactive forms (`AcroForm`)
+//!
+//! Fields form a tree under the catalog's `/AcroForm /Fields`. A field's
+//! full name joins the partial names (`/T`) of its ancestors with dots, and
+//! its type (`/FT`), flags (`/Ff`) and value (`/V`) may be inherited from
+//! them. The leaves are widget annotations, which place the field on a page;
+//! a field with a single widget is usually merged with it.
+//!
+//! Bounds are on the page holding the field's first widget, in points from
+//! its top-left corner.
+
+use lopdf::{Dictionary, Document, Object};
+use prism_core::document::{FormFieldBlock, FormFieldType, Rect};
+
+/// Field flag: the user may not change the value
+const READ_ONLY: i64 = 1;
+/// Button field flag: radio button rather than checkbox
+const RADIO: i64 = 1 << 15;
+/// Button field flag: push button, which has no value
+const PUSH_BUTTON: i64 = 1 << 16;
+/// Choice field flag: combo box rather than list box
+const COMBO: i64 = 1 << 17;
+
+/// Deepest field tree read, guarding against reference cycles
+const MAX_DEPTH: usize = 32;
+
+/// Height of a page without a usable `/MediaBox` (US Letter)
+const DEFAULT_PAGE_HEIGHT: f64 = 792.0;
+
+/// Attributes a field inherits from its ancestors
+#[derive(Debug, Clone, Default)]
+struct Inherited<'a> {
+    name: String,
+    field_type: Option<&'a [u8]>,
+    flags: i64,
+    value: Option<&'a Object>,
+}
+
+/// Every terminal field of the document's interactive form
+#[must_use]
+pub fn form_fields(pdf: &Document) -> Vec<FormFieldBlock> {
+    let fields = pdf
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", pdf))
+        .and_then(Object::as_dict)
+        .and_then(|form| form.get_deref(b"Fields", pdf))
+        .and_then(Object::as_array);
+    let mut blocks = Vec::new();
+    if let Ok(fields) = fields {
+        for field in fields {
+            if let Some(field) = dictionary(pdf, field) {
+                collect(pdf, field, &Inherited::default(), 0, &mut blocks);
+            }
+        }
+    }
+    blocks
+}
+
+fn collect<'a>(
+    pdf: &'a Document,
+    field: &'a Dictionary,
+    parent: &Inherited<'a>,
+    depth: usize,
+    blocks: &mut Vec<FormFieldBlock>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let mut inherited = parent.clone();
+    if let Some(partial) = field.get(b"T").ok().and_then(text) {
+        if !inherited.name.is_empty() {
+            inherited.name.push('.');
+        }
+        inherited.name.push_str(&partial);
+    }
+    if let Ok(field_type) = field.get(b"FT").and_then(Object::as_name) {
+        inherited.field_type = Some(field_type);
+    }
+    if let Ok(flags) = field.get(b"Ff").and_then(Object::as_i64) {
+        inherited.flags = flags;
+    }
+    if let Ok(value) = field.get_deref(b"V", pdf) {
+        inherited.value = Some(value);
+    }
+
+    // Kids with names are fields of their own; the others are widgets
+    let kids: Vec<&Dictionary> = field
+        .get_deref(b"Kids", pdf)
+        .and_then(Object::as_array)
+        .map(|kids| kids.iter().filter_map(|kid| dictionary(pdf, kid)).collect())
+        .unwrap_or_default();
+    let (fields, widgets): (Vec<_>, Vec<_>) = kids.into_iter().partition(|kid| kid.has(b"T"));
+    if !fields.is_empty() {
+        for kid in fields {
+            collect(pdf, kid, &inherited, depth + 1, blocks);
+        }
+        return;
+    }
+
+    let widget = widgets.first().copied().unwrap_or(field);
+    if let Some(block) = field_block(pdf, field, widget, &inherited) {
+        blocks.push(block);
+    }
+}
+
+/// The block for a terminal field, placed by `widget`
+fn field_block(
+    pdf: &Document,
+    field: &Dictionary,
+    widget: &Dictionary,
+    inherited: &Inherited<'_>,
+) -> Option<FormFieldBlock> {
+    let flags = inherited.flags;
+    let field_type = match inherited.field_type? {
+        b"Tx" => FormFieldType::Text,
+        b"Btn" if flags & PUSH_BUTTON != 0 => FormFieldType::Button,
+        b"Btn" if flags & RADIO != 0 => FormFieldType::Radio,
+        b"Btn" => FormFieldType::Checkbox,
+        b"Ch" if flags & COMBO != 0 => FormFieldType::Dropdown,
+        b"Ch" => FormFieldType::ListBox,
+        b"Sig" => FormFieldType::Signature,
+        _ => return None,
+    };
+
+    let mut block = FormFieldBlock::new(
+        field_type,
+        inherited.name.clone(),
+        bounds(pdf, widget).unwrap_or_default(),
+    );
+    block.label = field.get(b"TU").ok().and_then(text);
+    block.read_only = flags & READ_ONLY != 0;
+    block.options = field
+        .get_deref(b"Opt", pdf)
+        .and_then(Object::as_array)
+        .map(|options| {
+            options
+                .iter()
+                .filter_map(|option| option_label(pdf, option))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let value = inherited.value;
+    match field_type {
+        FormFieldType::Checkbox | FormFieldType::Radio => {
+            let state = value.and_then(|value| value.as_name().ok());
+            block.checked = state.is_some_and(|state| state != b"Off");
+            if field_type == FormFieldType::Radio && block.checked {
+                block.value = state.map(|state| String::from_utf8_lossy(state).into_owned());
+            }
+        }
+        // The signer's name, if the field is signed
+        FormFieldType::Signature => {
+            block.value = value
+                .and_then(|value| value.as_dict().ok())
+                .and_then(|signature| signature.get(b"Name").ok())
+                .and_then(text);
+        }
+        FormFieldType::Button => {}
+        _ => {
+            block.value = value.and_then(|value| match value {
+                // Multiple selections of a list box
+                Object::Array(values) => {
+                    let values: Vec<String> = values.iter().filter_map(text).collect();
+                    (!values.is_empty()).then(|| values.join(", "))
+                }
+                value => text(value),
+            });
+        }
+    }
+    Some(block)
+}
+
+/// Widget rectangle in top-left page coordinates
+fn bounds(pdf: &Document, widget: &Dictionary) -> Option<Rect> {
+    let page = widget
+        .get(b"P")
+        .and_then(Object::as_reference)
+        .and_then(|page| pdf.get_dictionary(page))
+        .ok();
+    rect(pdf, widget, page)
+}
+
+/// The `/Rect` of an annotation in top-left coordinates of `page`
+pub(super) fn rect(
+    pdf: &Document,
+    annotation: &Dictionary,
+    page: Option<&Dictionary>,
+) -> Option<Rect> {
+    let [x1, y1, x2, y2] = numbers(annotation.get(b"Rect").ok()?)?;
+    let page_top = page
+        .and_then(|page| page.get_deref(b"MediaBox", pdf).ok())
+        .and_then(numbers)
+        .map_or(DEFAULT_PAGE_HEIGHT, |media_box| media_box[3]);
+    Some(Rect::new(
+        x1.min(x2),
+        page_top - y1.max(y2),
+        (x2 - x1).abs(),
+        (y2 - y1).abs(),
+    ))
+}
+
+/// A four-number array such as a rectangle
+fn numbers(object: &Object) -> Option<[f64; 4]> {
+    let array = object.as_array().ok()?;
+    let mut numbers = [0.0; 4];
+    for (number, object) in numbers.iter_mut().zip(array) {
+        *number = f64::from(object.as_float().ok()?);
+    }
+    (array.len() == 4).then_some(numbers)
+}
+
+/// Display text of a choice option, either a string or an
+/// `[export display]` pair
+fn option_label(pdf: &Document, option: &Object) -> Option<String> {
+    match pdf.dereference(option).ok()?.1 {
+        Object::Array(pair) => pair.get(1).or_else(|| pair.first()).and_then(text),
+        option => text(option),
+    }
+}
+
+pub(super) fn dictionary<'a>(pdf: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    pdf.dereference(object).ok()?.1.as_dict().ok()
+}
+
+/// A PDF text string, in `PDFDocEncoding` or UTF-16
+pub(super) fn text(object: &Object) -> Option<String> {
+    lopdf::decode_text_string(object)
+        .ok()
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object, StringFormat};
+
+    fn string(text: &str) -> Object {
+        Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    #[test]
+    fn test_form_fields() {
+        let mut pdf = Document::with_version("1.7");
+        let page = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        let name = pdf.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => string("name"),
+            "TU" => string("Full name"),
+            "V" => string("Ada"),
+            "Rect" => vec![72.into(), 692.into(), 272.into(), 712.into()],
+            "P" => page,
+        });
+        let agree = pdf.add_object(dictionary! {
+            "FT" => "Btn",
+            "T" => string("agree"),
+            "V" => "Yes",
+            "Ff" => READ_ONLY,
+        });
+        let color = pdf.add_object(dictionary! {
+            "FT" => "Ch",
+            "T" => string("color"),
+            "Ff" => COMBO,
+            "Opt" => vec![
+                Object::Array(vec![string("r"), string("Red")]),
+                string("Blue"),
+            ],
+            "V" => string("Blue"),
+        });
+        let city = pdf.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => string("city"),
+        });
+        let address = pdf.add_object(dictionary! {
+            "T" => string("address"),
+            "Kids" => vec![city.into()],
+        });
+        let form = dictionary! {
+            "Fields" => vec![name.into(), agree.into(), color.into(), address.into()],
+        };
+        let catalog = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "AcroForm" => form,
+        });
+        pdf.trailer.set("Root", catalog);
+
+        let fields = form_fields(&pdf);
+        let summary: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                (
+                    field.field_type,
+                    field.name.as_str(),
+                    field.value.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (FormFieldType::Text, "name", Some("Ada")),
+                (FormFieldType::Checkbox, "agree", None),
+                (FormFieldType::Dropdown, "color", Some("Blue")),
+                (FormFieldType::Text, "address.city", None),
+            ]
+        );
+        assert_eq!(fields[0].label.as_deref(), Some("Full name"));
+        let bounds = fields[0].bounds;
+        assert_eq!(
+            (bounds.x, bounds.y, bounds.width, bounds.height),
+            (72.0, 80.0, 200.0, 20.0)
+        );
+        assert!(fields[1].checked && fields[1].read_only);
+        assert_eq!(fields[2].options, ["Red", "Blue"]);
+    }
+}
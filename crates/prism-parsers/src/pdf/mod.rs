@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! PDF format parser
 
+pub mod annotations;
+pub mod forms;
 pub mod pdf_parser;
 
 pub use pdf_parser::PdfParser;
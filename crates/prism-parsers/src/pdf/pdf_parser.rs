@@ -1,20 +1,32 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! PDF document parser
 //!
-//! Parses PDF files by embedding raw PDF data for client-side rendering with PDF.js
+//! Parses PDF files by embedding raw PDF data for client-side rendering with PDF.js.
+//! Fields of the interactive form follow the embedded data as form field blocks,
+//! and the annotations of every page are kept as page annotations.
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use lopdf::Document as LopdfDocument;
 use prism_core::{
-    document::{ContentBlock, Dimensions, Document, Page, Rect, TextBlock, TextRun, TextStyle},
-    error::{Error, Result},
+    document::{
+        Annotation, ContentBlock, Dimensions, Document, FontResource, Page, Rect, TextBlock,
+        TextRun, TextStyle,
+    },
+    error::{Error, ErrorLocation, Result},
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+    progress::ProgressEvent,
 };
+use std::collections::HashSet;
 use tracing::{debug, info};
 
+use crate::fonts;
+use crate::pdf::{annotations, forms};
+use crate::security;
+use crate::signatures;
+
 /// PDF document parser
 #[derive(Debug, Clone)]
 pub struct PdfParser;
@@ -54,6 +66,91 @@ impl PdfParser {
         metadata
     }
 
+    /// Collect the fonts a PDF uses, with the data of embedded ones
+    ///
+    /// Embedded programs are found through font descriptors (`FontFile`,
+    /// `FontFile2`, `FontFile3`); fonts without one, such as the standard 14,
+    /// are listed as referenced only.
+    fn extract_fonts(data: &[u8]) -> Vec<FontResource> {
+        let cursor = std::io::Cursor::new(data);
+        let Ok(pdf_doc) = LopdfDocument::load_from(cursor) else {
+            return Vec::new();
+        };
+
+        let mut names = HashSet::new();
+        let mut fonts = Vec::new();
+        let name_of = |dict: &lopdf::Dictionary, key: &[u8]| {
+            dict.get(key)
+                .and_then(lopdf::Object::as_name)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .ok()
+        };
+
+        // Descriptors first, so an embedded font wins over its font dictionary
+        let dicts: Vec<&lopdf::Dictionary> = pdf_doc
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .collect();
+        for dict in &dicts {
+            if !dict.has_type(b"FontDescriptor") {
+                continue;
+            }
+            let Some(name) = name_of(dict, b"FontName") else {
+                continue;
+            };
+            let program = [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"]
+                .iter()
+                .find_map(|key| {
+                    let id = dict.get(key).and_then(lopdf::Object::as_reference).ok()?;
+                    let stream = pdf_doc
+                        .get_object(id)
+                        .and_then(lopdf::Object::as_stream)
+                        .ok()?;
+                    stream.get_plain_content().ok()
+                });
+            if names.insert(name.clone()) {
+                fonts.push(match program {
+                    Some(program) => fonts::embedded_font(&name, program),
+                    None => fonts::referenced_font(&name),
+                });
+            }
+        }
+        for dict in &dicts {
+            if !dict.has_type(b"Font") || dict.has(b"FontDescriptor") {
+                continue;
+            }
+            if let Some(name) = name_of(dict, b"BaseFont") {
+                if names.insert(name.clone()) {
+                    fonts.push(fonts::referenced_font(&name));
+                }
+            }
+        }
+        fonts
+    }
+
+    /// Extract the fields of the PDF's interactive form
+    fn extract_form_fields(data: &[u8]) -> Vec<ContentBlock> {
+        let cursor = std::io::Cursor::new(data);
+        LopdfDocument::load_from(cursor).map_or_else(
+            |_| Vec::new(),
+            |pdf_doc| {
+                forms::form_fields(&pdf_doc)
+                    .into_iter()
+                    .map(ContentBlock::FormField)
+                    .collect()
+            },
+        )
+    }
+
+    /// Extract the annotations of every page
+    fn extract_annotations(data: &[u8]) -> Vec<Annotation> {
+        let cursor = std::io::Cursor::new(data);
+        LopdfDocument::load_from(cursor)
+            .map(|pdf_doc| annotations::annotations(&pdf_doc))
+            .unwrap_or_default()
+    }
+
     fn get_page_count(data: &[u8]) -> usize {
         let cursor = std::io::Cursor::new(data);
         if let Ok(pdf_doc) = LopdfDocument::load_from(cursor) {
@@ -87,25 +184,28 @@ impl Parser for PdfParser {
         debug!("Parsing PDF, size: {} bytes", context.size);
 
         if !self.can_parse(&data) {
-            return Err(Error::ParseError("Invalid PDF signature".to_string()));
+            return Err(Error::corrupt("PDF", "Invalid signature").at(ErrorLocation::offset(0)));
         }
 
+        let active_content = security::check(&data, "PDF", &context.options)?;
         let page_count = Self::get_page_count(&data);
         if page_count == 0 {
-            return Err(Error::ParseError("PDF has no pages".to_string()));
+            return Err(Error::corrupt("PDF", "Document has no pages"));
         }
 
         // Embed PDF as base64
         let pdf_base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &data);
 
         let text_run = TextRun {
-            text: format!("__PDF_DATA__:{}", pdf_base64),
+            text: format!("__PDF_DATA__:{pdf_base64}").into(),
             style: TextStyle::default(),
             bounds: Some(Rect::default()),
             char_positions: Some(Vec::new()),
+            link: None,
+            revision: None,
         };
 
-        let page = Page {
+        let mut page = Page {
             number: 1,
             dimensions: Dimensions {
                 width: 612.0,
@@ -117,10 +217,12 @@ impl Parser for PdfParser {
                 bounds: prism_core::document::Rect::default(),
                 style: prism_core::document::ShapeStyle::default(),
                 rotation: 0.0,
+                direction: prism_core::document::TextDirection::Auto,
             })],
             metadata: Default::default(),
-            annotations: Vec::new(),
+            annotations: Self::extract_annotations(&data),
         };
+        page.content.extend(Self::extract_form_fields(&data));
 
         let mut metadata = Self::extract_metadata(&data);
         if let Some(ref filename) = context.filename {
@@ -129,15 +231,24 @@ impl Parser for PdfParser {
             }
         }
         metadata.add_custom("page_count", page_count as i64);
+        metadata.signatures = signatures::verify(&data);
+        metadata.active_content = active_content;
 
         let mut document = Document::new();
         document.pages = vec![page];
         document.metadata = metadata;
+        document.resources.fonts = Self::extract_fonts(&data);
 
         info!(
             "Prepared PDF with {} pages for client rendering",
             page_count
         );
+        // Pages are handed to the viewer as a whole, so they are all done
+        // at once
+        context.report(ProgressEvent::PageParsed {
+            parsed: page_count,
+            total: Some(page_count),
+        });
         Ok(document)
     }
 
@@ -1,18 +1,33 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Parser registry for managing and discovering format parsers.
 
-use prism_core::format::Format;
-use prism_core::parser::Parser;
+use prism_core::format::{DetectionResult, Format, FormatRegistry};
+use prism_core::parser::{Parser, ParserMetadata};
+use prism_core::pipeline::ParserProvider;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// A row of the support matrix: what a registered parser reads and how
+#[derive(Debug, Clone, Serialize)]
+pub struct ParserSupport {
+    /// Format the parser is registered for
+    pub format: Format,
+    /// Name, version, features and sandbox requirement of the parser
+    #[serde(flatten)]
+    pub metadata: ParserMetadata,
+}
+
 /// Registry for managing format parsers
 ///
 /// The registry maintains a collection of available parsers and provides
-/// methods to find the appropriate parser for a given format.
+/// methods to find the appropriate parser for a given format. It also owns
+/// the [`FormatRegistry`] used for detection, which registered parsers
+/// extend with their own formats.
 #[derive(Clone, Default)]
 pub struct ParserRegistry {
     parsers: HashMap<String, Arc<dyn Parser>>,
+    formats: FormatRegistry,
 }
 
 impl ParserRegistry {
@@ -27,10 +42,63 @@ impl ParserRegistry {
     pub fn with_default_parsers() -> Self {
         let mut registry = Self::new();
 
+        // Register PDF parser
+        registry.register(Arc::new(crate::pdf::PdfParser::new()));
+
+        // Register image parsers
+        registry.register(Arc::new(crate::image::PngParser::new()));
+        registry.register(Arc::new(crate::image::JpegParser::new()));
+        registry.register(Arc::new(crate::image::TiffParser::new()));
+
+        // Register Office parsers (modern)
+        registry.register(Arc::new(crate::office::DocxParser::new()));
+        registry.register(Arc::new(crate::office::PptxParser::new()));
+        registry.register(Arc::new(crate::office::XlsxParser::new()));
+
+        // Register Office parsers (legacy)
+        registry.register(Arc::new(crate::office::DocParser::new()));
+        registry.register(Arc::new(crate::office::PptParser::new()));
+        registry.register(Arc::new(crate::office::XlsParser::new()));
+
+        // Register text-based parsers
+        registry.register(Arc::new(crate::text::TextParser::new()));
+        registry.register(Arc::new(crate::text::HtmlParser::new()));
+        registry.register(Arc::new(crate::text::JsonParser::new()));
+        registry.register(Arc::new(crate::text::XmlParser::new()));
+        registry.register(Arc::new(crate::text::CsvParser::new()));
+        registry.register(Arc::new(crate::text::MarkdownParser::new()));
+        registry.register(Arc::new(crate::text::LogParser::new()));
+
+        // Register email parsers
+        registry.register(Arc::new(crate::email::EmlParser::new()));
+        registry.register(Arc::new(crate::email::MsgParser::new()));
+        registry.register(Arc::new(crate::email::MboxParser::new()));
+        registry.register(Arc::new(crate::email::VcfParser::new()));
+        registry.register(Arc::new(crate::email::IcsParser::new()));
+
         // Register archive parsers
         registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::zip())));
         registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::tar())));
+        registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::iso())));
+        registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::msi())));
         registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::gzip())));
+        registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::xz())));
+        registry.register(Arc::new(
+            crate::archive::ArchiveParser::new(Format::bzip2()),
+        ));
+        registry.register(Arc::new(crate::archive::ArchiveParser::new(Format::zstd())));
+
+        // Register audio and video parsers
+        for format in [
+            Format::mp3(),
+            Format::mp4(),
+            Format::m4a(),
+            Format::mov(),
+            Format::mkv(),
+            Format::webm(),
+        ] {
+            registry.register(Arc::new(crate::media::MediaParser::new(format)));
+        }
 
         registry
     }
@@ -41,6 +109,7 @@ impl ParserRegistry {
     ///
     /// * `parser` - The parser implementation to register
     pub fn register(&mut self, parser: Arc<dyn Parser>) {
+        parser.register_formats(&mut self.formats);
         let format = parser.format();
         self.parsers.insert(format.mime_type.clone(), parser);
     }
@@ -89,6 +158,22 @@ impl ParserRegistry {
         self.parsers.values().cloned().collect()
     }
 
+    /// The format, features, sandbox requirement and version of every
+    /// registered parser, ordered by MIME type
+    #[must_use]
+    pub fn support_matrix(&self) -> Vec<ParserSupport> {
+        let mut matrix: Vec<ParserSupport> = self
+            .parsers
+            .values()
+            .map(|parser| ParserSupport {
+                format: parser.format(),
+                metadata: parser.metadata(),
+            })
+            .collect();
+        matrix.sort_by(|a, b| a.format.mime_type.cmp(&b.format.mime_type));
+        matrix
+    }
+
     /// Check if a parser is registered for the given format
     #[must_use]
     pub fn has_parser(&self, format: &Format) -> bool {
@@ -100,6 +185,37 @@ impl ParserRegistry {
     pub fn count(&self) -> usize {
         self.parsers.len()
     }
+
+    /// The formats known to detection
+    #[must_use]
+    pub fn formats(&self) -> &FormatRegistry {
+        &self.formats
+    }
+
+    /// The formats known to detection, for registering more
+    pub fn formats_mut(&mut self) -> &mut FormatRegistry {
+        &mut self.formats
+    }
+
+    /// Detect the format of `data`, including formats added by parsers
+    #[must_use]
+    pub fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        self.formats.detect(data, filename)
+    }
+}
+
+impl ParserProvider for ParserRegistry {
+    fn parser_for(&self, format: &Format, data: &[u8]) -> Option<Arc<dyn Parser>> {
+        self.get_parser_for_data(format, data)
+    }
+
+    fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        ParserRegistry::detect(self, data, filename)
+    }
+
+    fn detect_all(&self, data: &[u8], filename: Option<&str>) -> Vec<DetectionResult> {
+        self.formats.detect_all(data, filename)
+    }
 }
 
 #[cfg(test)]
@@ -112,6 +228,97 @@ mod tests {
         assert_eq!(registry.count(), 0);
     }
 
+    /// Parser for a format the built-in tables do not know
+    struct NotesParser;
+
+    fn notes_format() -> Format {
+        Format {
+            mime_type: "application/x-notes".to_string(),
+            extension: "notes".to_string(),
+            family: prism_core::format::FormatFamily::Text,
+            name: "Notes".to_string(),
+            is_container: false,
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Parser for NotesParser {
+        fn format(&self) -> Format {
+            notes_format()
+        }
+
+        fn can_parse(&self, data: &[u8]) -> bool {
+            data.starts_with(b"NOTES")
+        }
+
+        async fn parse(
+            &self,
+            _data: bytes::Bytes,
+            _context: prism_core::parser::ParseContext,
+        ) -> prism_core::Result<prism_core::Document> {
+            Ok(prism_core::Document::new())
+        }
+
+        fn register_formats(&self, formats: &mut FormatRegistry) {
+            formats.register_extension("notes", notes_format());
+            formats.register_signature(prism_core::format::FormatSignature {
+                bytes: b"NOTES",
+                offset: 0,
+                format: notes_format,
+            });
+        }
+    }
+
+    #[test]
+    fn test_parsers_extend_detection() {
+        let mut registry = ParserRegistry::with_default_parsers();
+        assert!(registry.detect(b"NOTES v1", None).is_none());
+
+        registry.register(Arc::new(NotesParser));
+        let detected = registry.detect(b"NOTES v1", None).unwrap();
+        assert_eq!(detected.format, notes_format());
+        let detected = registry.detect(b"plain words", Some("todo.notes")).unwrap();
+        assert_eq!(detected.format, notes_format());
+        assert!(registry.parser_for(&detected.format, b"NOTES").is_some());
+
+        // Built-in formats still work, and custom detectors run first
+        assert_eq!(
+            registry.detect(b"%PDF-1.7", None).unwrap().format,
+            Format::pdf()
+        );
+        registry.formats_mut().register_detector(|data, _| {
+            data.starts_with(b"%PDF-1.7").then(|| DetectionResult {
+                format: notes_format(),
+                confidence: 1.0,
+                method: prism_core::format::DetectionMethod::ContentAnalysis,
+            })
+        });
+        assert_eq!(
+            registry.detect(b"%PDF-1.7", None).unwrap().format,
+            notes_format()
+        );
+    }
+
+    #[test]
+    fn test_support_matrix() {
+        let registry = ParserRegistry::with_default_parsers();
+        let matrix = registry.support_matrix();
+        assert_eq!(matrix.len(), registry.count());
+        assert!(matrix
+            .windows(2)
+            .all(|pair| pair[0].format.mime_type <= pair[1].format.mime_type));
+
+        let pdf = matrix
+            .iter()
+            .find(|support| support.format == Format::pdf())
+            .unwrap();
+        assert!(!pdf.metadata.name.is_empty());
+        let json = serde_json::to_value(pdf).unwrap();
+        assert_eq!(json["format"]["mime_type"], "application/pdf");
+        assert!(json["features"].is_array());
+        assert!(json["requires_sandbox"].is_boolean());
+    }
+
     #[test]
     fn test_has_parser() {
         let registry = ParserRegistry::new();
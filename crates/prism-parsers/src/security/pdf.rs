@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! PDF: JavaScript, actions run on open, launch actions, embedded programs
+//! and actions that reach other files
+
+use lopdf::{decode_text_string, Dictionary, Document as LopdfDocument, Object};
+use prism_core::metadata::{ActiveContent, ActiveContentKind};
+
+use super::is_executable;
+
+/// Actions that send data to or load content from elsewhere
+const EXTERNAL_ACTIONS: &[&[u8]] = &[b"GoToR", b"GoToE", b"SubmitForm", b"ImportData"];
+
+/// Active content of a PDF, in object order after the catalog's own
+pub fn scan(data: &[u8]) -> Vec<ActiveContent> {
+    let Ok(document) = LopdfDocument::load_mem(data) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+
+    if let Ok(catalog) = document.catalog() {
+        let open_action = catalog
+            .get(b"OpenAction")
+            .ok()
+            .and_then(|action| resolve(&document, action).as_dict().ok())
+            .and_then(|action| action.get(b"S").and_then(Object::as_name).ok())
+            .filter(|kind| *kind != b"GoTo");
+        if let Some(kind) = open_action {
+            found.push(
+                ActiveContent::new(ActiveContentKind::AutoAction, "catalog /OpenAction")
+                    .with_detail(String::from_utf8_lossy(kind)),
+            );
+        }
+        if catalog.has(b"AA") {
+            found.push(ActiveContent::new(
+                ActiveContentKind::AutoAction,
+                "catalog /AA",
+            ));
+        }
+    }
+
+    for (id, object) in &document.objects {
+        let location = format!("object {} {}", id.0, id.1);
+        walk(&document, object, &location, &mut found);
+    }
+    found
+}
+
+/// Inspect the dictionaries of an object, including direct ones nested
+/// in it such as an annotation's action
+fn walk(document: &LopdfDocument, object: &Object, location: &str, found: &mut Vec<ActiveContent>) {
+    match object {
+        Object::Dictionary(dict) | Object::Stream(lopdf::Stream { dict, .. }) => {
+            found.extend(inspect(document, dict, location));
+            for (_, value) in dict {
+                walk(document, value, location, found);
+            }
+        }
+        Object::Array(items) => {
+            for item in items {
+                walk(document, item, location, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Active content of one dictionary
+fn inspect(document: &LopdfDocument, dict: &Dictionary, location: &str) -> Vec<ActiveContent> {
+    let mut found = Vec::new();
+    let name = |key: &[u8]| dict.get(key).and_then(Object::as_name).ok();
+    let kind = name(b"S");
+
+    if dict.has(b"JS") || kind == Some(b"JavaScript") {
+        found.push(ActiveContent::new(ActiveContentKind::Script, location));
+    }
+    match kind {
+        Some(b"Launch") => {
+            let target = dict
+                .get(b"F")
+                .ok()
+                .or_else(|| {
+                    dict.get(b"Win")
+                        .and_then(Object::as_dict)
+                        .and_then(|win| win.get(b"F"))
+                        .ok()
+                })
+                .and_then(|file| file_name(document, file));
+            let mut item = ActiveContent::new(ActiveContentKind::Launch, location);
+            item.detail = target;
+            found.push(item);
+        }
+        Some(kind) if EXTERNAL_ACTIONS.contains(&kind) => {
+            let target = dict
+                .get(b"F")
+                .ok()
+                .and_then(|file| file_name(document, file))
+                .unwrap_or_else(|| String::from_utf8_lossy(kind).into_owned());
+            found.push(
+                ActiveContent::new(ActiveContentKind::ExternalReference, location)
+                    .with_detail(target),
+            );
+        }
+        _ => {}
+    }
+
+    if name(b"Type") == Some(b"Page") && dict.has(b"AA") {
+        found.push(ActiveContent::new(ActiveContentKind::AutoAction, location));
+    }
+
+    // File specifications with an embedded file stream
+    let embedded = dict
+        .get(b"EF")
+        .and_then(Object::as_dict)
+        .and_then(|files| files.get(b"UF").or_else(|_| files.get(b"F")))
+        .ok()
+        .and_then(|stream| resolve(document, stream).as_stream().ok());
+    if let Some(stream) = embedded {
+        let file = dict
+            .get(b"UF")
+            .or_else(|_| dict.get(b"F"))
+            .ok()
+            .and_then(|name| decode_text_string(name).ok())
+            .unwrap_or_default();
+        let content = stream
+            .decompressed_content()
+            .unwrap_or_else(|_| stream.content.clone());
+        if is_executable(&file, &content) {
+            found.push(
+                ActiveContent::new(ActiveContentKind::EmbeddedExecutable, location)
+                    .with_detail(file),
+            );
+        }
+    }
+    found
+}
+
+/// The object a reference points to, or the object itself
+fn resolve<'a>(document: &'a LopdfDocument, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => document.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+/// File name or URL of a file specification, given as a string or a
+/// dictionary
+fn file_name(document: &LopdfDocument, file: &Object) -> Option<String> {
+    match resolve(document, file) {
+        Object::Dictionary(spec) => spec
+            .get(b"UF")
+            .or_else(|_| spec.get(b"F"))
+            .ok()
+            .and_then(|name| decode_text_string(name).ok()),
+        other => decode_text_string(other).ok(),
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream, StringFormat};
+
+    fn string(text: &str) -> Object {
+        Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    /// A PDF running JavaScript on open, with a launch action and an
+    /// embedded program
+    pub fn pdf_with_javascript() -> Vec<u8> {
+        let mut pdf = LopdfDocument::with_version("1.7");
+        let script = pdf.add_object(dictionary! {
+            "S" => "JavaScript",
+            "JS" => string("app.alert('hi')"),
+        });
+        let launch = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "A" => dictionary! { "S" => "Launch", "F" => string("cmd.exe") },
+        });
+        let program = pdf.add_object(Stream::new(dictionary! {}, b"MZ\x90\x00".to_vec()));
+        let attachment = pdf.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => string("update.exe"),
+            "EF" => dictionary! { "F" => program },
+        });
+        let page = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Annots" => vec![launch.into()],
+        });
+        let pages = pdf.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page.into()],
+            "Count" => 1,
+        });
+        let catalog = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages,
+            "OpenAction" => script,
+            "Names" => dictionary! {
+                "EmbeddedFiles" => dictionary! {
+                    "Names" => vec![string("update.exe"), attachment.into()],
+                },
+            },
+        });
+        pdf.trailer.set("Root", catalog);
+        let mut data = Vec::new();
+        pdf.save_to(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_pdf_scan() {
+        let found = scan(&pdf_with_javascript());
+        let summary: Vec<_> = found.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "automatic action in catalog /OpenAction (JavaScript)",
+                "script in object 1 0",
+                "launch action in object 2 0 (cmd.exe)",
+                "embedded executable in object 4 0 (update.exe)",
+            ]
+        );
+    }
+}
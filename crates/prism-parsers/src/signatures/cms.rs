@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! The parts of CMS `SignedData` (RFC 5652) and X.509 certificates that
+//! identify a signer and the digest they signed
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use super::der::{
+    Tlv, CONTEXT_0, GENERALIZED_TIME, INTEGER, OCTET_STRING, OID, SEQUENCE, SET, UTC_TIME,
+};
+use super::DigestAlgorithm;
+
+/// id-signedData, 1.2.840.113549.1.7.2
+const SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+/// id-messageDigest, 1.2.840.113549.1.9.4
+const MESSAGE_DIGEST: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x04];
+/// id-signingTime, 1.2.840.113549.1.9.5
+const SIGNING_TIME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x05];
+/// id-at-commonName, 2.5.4.3
+const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// What a `SignedData` structure says about its (first) signer
+#[derive(Debug, Default)]
+pub struct SignerInfo {
+    /// Algorithm of the message digest
+    pub digest_algorithm: Option<DigestAlgorithm>,
+    /// Digest of the signed content, from the signed attributes
+    pub message_digest: Option<Vec<u8>>,
+    /// Signing time from the signed attributes
+    pub signing_time: Option<DateTime<Utc>>,
+    /// Common name of the signing certificate's subject
+    pub signer: Option<String>,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm an object identifier names
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            [0x2B, 0x0E, 0x03, 0x02, 0x1A] => Some(Self::Sha1),
+            [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, n] => match n {
+                1 => Some(Self::Sha256),
+                2 => Some(Self::Sha384),
+                3 => Some(Self::Sha512),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Read the signer of a DER-encoded CMS `ContentInfo` holding `SignedData`
+///
+/// Trailing bytes, such as the zero padding of a PDF `/Contents` string,
+/// are ignored.
+pub fn signer_info(data: &[u8]) -> Option<SignerInfo> {
+    let (content_info, _) = Tlv::read(data)?;
+    let mut parts = content_info.children();
+    if parts.next().filter(|oid| oid.tag == OID)?.content != SIGNED_DATA {
+        return None;
+    }
+    let explicit = parts.next().filter(|part| part.tag == CONTEXT_0)?;
+    let signed_data = explicit.children().next()?;
+
+    // version, digestAlgorithms and encapContentInfo come first
+    let mut certificates = None;
+    let mut signer_infos = None;
+    for field in signed_data.children().skip(3) {
+        match field.tag {
+            CONTEXT_0 => certificates = Some(field),
+            SET => signer_infos = Some(field),
+            _ => {}
+        }
+    }
+    let signer = signer_infos?.children().next()?;
+
+    let mut info = SignerInfo::default();
+    let mut fields = signer.children().skip(1);
+    let serial = fields
+        .next()
+        .filter(|sid| sid.tag == SEQUENCE)
+        .and_then(|sid| sid.children().nth(1))
+        .filter(|serial| serial.tag == INTEGER)
+        .map(|serial| serial.content);
+    info.digest_algorithm = fields
+        .next()
+        .and_then(|algorithm| algorithm.children().next())
+        .and_then(|oid| DigestAlgorithm::from_oid(oid.content));
+    if let Some(attributes) = fields.next().filter(|field| field.tag == CONTEXT_0) {
+        for attribute in attributes.children() {
+            let mut parts = attribute.children();
+            let (Some(oid), Some(values)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let value = values.children().next();
+            match oid.content {
+                MESSAGE_DIGEST => {
+                    info.message_digest = value
+                        .filter(|value| value.tag == OCTET_STRING)
+                        .map(|value| value.content.to_vec());
+                }
+                SIGNING_TIME => info.signing_time = value.and_then(|value| time(&value)),
+                _ => {}
+            }
+        }
+    }
+
+    // The signing certificate is the one whose serial the signer names,
+    // or failing that the first one
+    if let Some(certificates) = certificates {
+        let subjects: Vec<_> = certificates.children().filter_map(subject).collect();
+        info.signer = subjects
+            .iter()
+            .find(|(number, _)| Some(*number) == serial)
+            .or(subjects.first())
+            .and_then(|(_, name)| name.clone());
+    }
+    Some(info)
+}
+
+/// Common name of a DER-encoded X.509 certificate's subject
+pub fn certificate_subject(data: &[u8]) -> Option<String> {
+    subject(Tlv::read(data)?.0)?.1
+}
+
+/// Serial number and subject common name of a certificate
+fn subject(certificate: Tlv<'_>) -> Option<(&[u8], Option<String>)> {
+    let tbs = certificate.children().next()?;
+    let mut fields = tbs.children().skip_while(|field| field.tag == CONTEXT_0);
+    let serial = fields.next().filter(|serial| serial.tag == INTEGER)?;
+    // signature algorithm, issuer and validity precede the subject
+    let subject = fields.nth(3)?;
+    let name = subject
+        .children()
+        .flat_map(|rdn| rdn.children())
+        .find_map(|attribute| {
+            let mut parts = attribute.children();
+            (parts.next()?.content == COMMON_NAME)
+                .then(|| parts.next()?.text())
+                .flatten()
+        });
+    Some((serial.content, name))
+}
+
+/// A `UTCTime` or `GeneralizedTime`
+fn time(value: &Tlv<'_>) -> Option<DateTime<Utc>> {
+    let text = std::str::from_utf8(value.content).ok()?;
+    let formats: &[&str] = match value.tag {
+        UTC_TIME => &["%y%m%d%H%M%SZ", "%y%m%d%H%MZ"],
+        GENERALIZED_TIME => &["%Y%m%d%H%M%SZ", "%Y%m%d%H%M%S%.fZ"],
+        _ => return None,
+    };
+    formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| time.and_utc())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Encode an element with a definite length
+    pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            out.push(u8::try_from(len).unwrap());
+        } else {
+            let bytes: Vec<u8> = len
+                .to_be_bytes()
+                .into_iter()
+                .skip_while(|&b| b == 0)
+                .collect();
+            out.push(0x80 | u8::try_from(bytes.len()).unwrap());
+            out.extend(bytes);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    /// A self-describing certificate with just the fields that are read
+    pub fn certificate(serial: u8, name: &str) -> Vec<u8> {
+        let cn = tlv(
+            SET,
+            &tlv(
+                SEQUENCE,
+                &[tlv(OID, COMMON_NAME), tlv(0x0C, name.as_bytes())].concat(),
+            ),
+        );
+        let tbs = [
+            tlv(CONTEXT_0, &tlv(INTEGER, &[2])),
+            tlv(INTEGER, &[serial]),
+            tlv(SEQUENCE, &[]),
+            tlv(SEQUENCE, &[]),
+            tlv(SEQUENCE, &[]),
+            tlv(SEQUENCE, &cn),
+        ]
+        .concat();
+        tlv(SEQUENCE, &tlv(SEQUENCE, &tbs))
+    }
+
+    /// `SignedData` by `signer` over content with the given SHA-256 digest
+    pub fn signed_data(digest: &[u8], signer: &str) -> Vec<u8> {
+        let sha256 = [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+        let attributes = [
+            tlv(
+                SEQUENCE,
+                &[
+                    tlv(OID, MESSAGE_DIGEST),
+                    tlv(SET, &tlv(OCTET_STRING, digest)),
+                ]
+                .concat(),
+            ),
+            tlv(
+                SEQUENCE,
+                &[
+                    tlv(OID, SIGNING_TIME),
+                    tlv(SET, &tlv(UTC_TIME, b"240501102030Z")),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat();
+        let signer_info = tlv(
+            SEQUENCE,
+            &[
+                tlv(INTEGER, &[1]),
+                tlv(SEQUENCE, &[tlv(SEQUENCE, &[]), tlv(INTEGER, &[2])].concat()),
+                tlv(SEQUENCE, &tlv(OID, &sha256)),
+                tlv(CONTEXT_0, &attributes),
+                tlv(OCTET_STRING, &[0; 8]),
+            ]
+            .concat(),
+        );
+        let certificates = [certificate(1, "Someone Else"), certificate(2, signer)].concat();
+        let signed_data = tlv(
+            SEQUENCE,
+            &[
+                tlv(INTEGER, &[1]),
+                tlv(SET, &[]),
+                tlv(SEQUENCE, &[]),
+                tlv(CONTEXT_0, &certificates),
+                tlv(SET, &signer_info),
+            ]
+            .concat(),
+        );
+        tlv(
+            SEQUENCE,
+            &[tlv(OID, SIGNED_DATA), tlv(CONTEXT_0, &signed_data)].concat(),
+        )
+    }
+
+    #[test]
+    fn test_signer_info() {
+        let mut data = signed_data(&[0xAB; 32], "Jane Signer");
+        data.extend([0; 64]);
+        let info = signer_info(&data).unwrap();
+        assert_eq!(info.digest_algorithm, Some(DigestAlgorithm::Sha256));
+        assert_eq!(info.message_digest, Some(vec![0xAB; 32]));
+        assert_eq!(info.signer.as_deref(), Some("Jane Signer"));
+        assert_eq!(
+            info.signing_time.unwrap().to_rfc3339(),
+            "2024-05-01T10:20:30+00:00"
+        );
+        assert!(signer_info(&certificate(1, "Not CMS")).is_none());
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Reading ASN.1 DER, as used by CMS signatures and X.509 certificates
+//!
+//! Only what signature inspection needs: single-byte tags, and the
+//! indefinite lengths of BER, which some PDF signers still emit.
+
+/// Tag of an INTEGER
+pub const INTEGER: u8 = 0x02;
+/// Tag of an OCTET STRING
+pub const OCTET_STRING: u8 = 0x04;
+/// Tag of an OBJECT IDENTIFIER
+pub const OID: u8 = 0x06;
+/// Tag of a `UTF8String`
+const UTF8_STRING: u8 = 0x0C;
+/// Tag of a `UTCTime`
+pub const UTC_TIME: u8 = 0x17;
+/// Tag of a `GeneralizedTime`
+pub const GENERALIZED_TIME: u8 = 0x18;
+/// Tag of a `BMPString` (UTF-16BE)
+const BMP_STRING: u8 = 0x1E;
+/// Tag of a SEQUENCE
+pub const SEQUENCE: u8 = 0x30;
+/// Tag of a SET
+pub const SET: u8 = 0x31;
+/// Tag of the first context-specific constructed element, `[0]`
+pub const CONTEXT_0: u8 = 0xA0;
+
+/// Nesting of indefinite-length elements followed before giving up
+const MAX_DEPTH: usize = 32;
+
+/// An element: its tag and the bytes of its content
+#[derive(Debug, Clone, Copy)]
+pub struct Tlv<'a> {
+    /// Identifier octet
+    pub tag: u8,
+    /// Content octets; for indefinite lengths, the children without the
+    /// end-of-contents marker
+    pub content: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// The element at the start of `data` and the bytes following it
+    pub fn read(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        Self::read_nested(data, 0)
+    }
+
+    fn read_nested(data: &'a [u8], depth: usize) -> Option<(Self, &'a [u8])> {
+        let (&tag, rest) = data.split_first()?;
+        if tag & 0x1F == 0x1F || depth > MAX_DEPTH {
+            return None;
+        }
+        let (&first, rest) = rest.split_first()?;
+
+        if first == 0x80 {
+            // Indefinite length: children up to an end-of-contents marker
+            let mut cursor = rest;
+            while !cursor.starts_with(&[0, 0]) {
+                cursor = Self::read_nested(cursor, depth + 1)?.1;
+            }
+            let content = &rest[..rest.len() - cursor.len()];
+            return Some((Self { tag, content }, &cursor[2..]));
+        }
+
+        let (len, rest) = if first & 0x80 == 0 {
+            (usize::from(first), rest)
+        } else {
+            let count = usize::from(first & 0x7F);
+            if count > std::mem::size_of::<usize>() || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | usize::from(byte));
+            (len, &rest[count..])
+        };
+        (rest.len() >= len).then(|| {
+            (
+                Self {
+                    tag,
+                    content: &rest[..len],
+                },
+                &rest[len..],
+            )
+        })
+    }
+
+    /// The elements inside a constructed element
+    pub fn children(&self) -> Children<'a> {
+        Children(self.content)
+    }
+
+    /// The text of a string element
+    ///
+    /// `BMPString` is decoded as UTF-16; the other string types are read as
+    /// UTF-8, which covers their ASCII subsets as well.
+    pub fn text(&self) -> Option<String> {
+        match self.tag {
+            BMP_STRING => {
+                let units: Vec<u16> = self
+                    .content
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).ok()
+            }
+            UTF8_STRING | 0x12..=0x16 | 0x1A => {
+                Some(String::from_utf8_lossy(self.content).into_owned())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the children of a constructed element
+pub struct Children<'a>(&'a [u8]);
+
+impl<'a> Iterator for Children<'a> {
+    type Item = Tlv<'a>;
+
+    fn next(&mut self) -> Option<Tlv<'a>> {
+        let (tlv, rest) = Tlv::read(self.0)?;
+        self.0 = rest;
+        Some(tlv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_lengths() {
+        // Long-form length, followed by a second element
+        let mut data = vec![OCTET_STRING, 0x81, 200];
+        data.extend([7; 200]);
+        data.extend([INTEGER, 1, 5]);
+        let (first, rest) = Tlv::read(&data).unwrap();
+        assert_eq!(first.content.len(), 200);
+        assert_eq!(Tlv::read(rest).unwrap().0.content, &[5]);
+
+        // Indefinite length with two children
+        let data = [SEQUENCE, 0x80, INTEGER, 1, 1, INTEGER, 1, 2, 0, 0, 0x05, 0];
+        let (sequence, rest) = Tlv::read(&data).unwrap();
+        assert_eq!(sequence.children().count(), 2);
+        assert_eq!(rest, &[0x05, 0]);
+
+        assert!(Tlv::read(&[OCTET_STRING, 5, 1, 2]).is_none());
+        assert!(Tlv::read(&[SEQUENCE, 0x80, INTEGER, 1, 1]).is_none());
+    }
+}
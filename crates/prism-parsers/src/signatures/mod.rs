@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Digital signature detection and validation
+//!
+//! Finds the signatures embedded in PDF files (signature dictionaries with
+//! a `/ByteRange`) and OOXML packages (`_xmlsignatures` parts), and checks
+//! the signed content against the digest each signer committed to. The
+//! result reports who signed, when, and whether the file changed since.
+//!
+//! The signature value is not verified against the signer's public key
+//! and certificate chains are not validated. A
+//! [`SignatureStatus::DigestMatches`] signature only shows that the content
+//! agrees with the digest stored alongside it: whoever edits the file can
+//! store a new digest too, so it proves neither who signed nor that the
+//! content is unchanged.
+//!
+//! ```rust,no_run
+//! use prism_parsers::signatures;
+//!
+//! let data = std::fs::read("contract.pdf")?;
+//! for signature in signatures::verify(&data) {
+//!     println!("{:?} signed by {:?}", signature.status, signature.signer);
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+mod cms;
+mod der;
+mod ooxml;
+mod pdf;
+
+use sha2::Digest;
+
+pub use prism_core::metadata::{DigitalSignature, SignatureKind, SignatureStatus};
+
+/// Find and check the digital signatures in a file
+///
+/// PDF files are recognized by their header and OOXML packages by the ZIP
+/// signature. Other files, and files that cannot be read, have none.
+#[must_use]
+pub fn verify(data: &[u8]) -> Vec<DigitalSignature> {
+    if data.starts_with(b"%PDF-") {
+        pdf::signatures(data)
+    } else if data.starts_with(b"PK\x03\x04") {
+        ooxml::signatures(data)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Hash function a signer used to digest the signed content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Digest of the concatenation of `parts`
+    fn digest(self, parts: &[&[u8]]) -> Vec<u8> {
+        fn run<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+
+        match self {
+            Self::Sha1 => run::<sha1::Sha1>(parts),
+            Self::Sha256 => run::<sha2::Sha256>(parts),
+            Self::Sha384 => run::<sha2::Sha384>(parts),
+            Self::Sha512 => run::<sha2::Sha512>(parts),
+        }
+    }
+}
+
+/// Position of `needle` in `haystack`
+fn memmem(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! OOXML signatures: XML-DSig parts under `_xmlsignatures/` whose package
+//! object lists a digest for every signed part
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use prism_core::metadata::{DigitalSignature, SignatureKind, SignatureStatus};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+use super::{cms, DigestAlgorithm};
+use crate::office::utils::attr_value_opt;
+
+/// Folder of the signature parts
+const SIGNATURES_FOLDER: &str = "_xmlsignatures/";
+
+/// A part the signature covers
+#[derive(Debug, Default)]
+struct Reference {
+    /// Part name, without the leading slash or query
+    part: String,
+    /// Whether the part is transformed before digesting; relationship
+    /// parts are, and cannot be checked byte for byte
+    transformed: bool,
+    algorithm: Option<DigestAlgorithm>,
+    digest: Vec<u8>,
+}
+
+/// What a signature part says
+#[derive(Debug, Default)]
+struct SignaturePart {
+    references: Vec<Reference>,
+    certificate: Option<Vec<u8>>,
+    subject_name: Option<String>,
+    signed_at: Option<DateTime<Utc>>,
+    comments: Option<String>,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm an XML-DSig `DigestMethod` names
+    fn from_uri(uri: &str) -> Option<Self> {
+        match uri.rsplit_once('#')?.1 {
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            "sha384" => Some(Self::Sha384),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+}
+
+/// The signatures of an OOXML package, in part name order
+pub fn signatures(data: &[u8]) -> Vec<DigitalSignature> {
+    let Ok(mut archive) = ZipArchive::new(Cursor::new(data)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            name.starts_with(SIGNATURES_FOLDER)
+                && !name.contains("/_rels/")
+                && std::path::Path::new(name)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+        })
+        .map(str::to_string)
+        .collect();
+    names.sort();
+
+    names
+        .iter()
+        .filter_map(|name| {
+            let xml = read(&mut archive, name).and_then(|data| String::from_utf8(data).ok())?;
+            let part = parse(&xml);
+            (!part.references.is_empty()).then(|| check(&mut archive, part))
+        })
+        .collect()
+}
+
+/// Check the digests of a signature's parts against the package
+fn check(archive: &mut ZipArchive<Cursor<&[u8]>>, part: SignaturePart) -> DigitalSignature {
+    let mut checked = 0;
+    let mut mismatched = false;
+    for reference in &part.references {
+        let Some(algorithm) = reference.algorithm.filter(|_| !reference.transformed) else {
+            continue;
+        };
+        checked += 1;
+        let matches = read(archive, &reference.part)
+            .is_some_and(|content| algorithm.digest(&[&content]) == reference.digest);
+        mismatched |= !matches;
+    }
+    let status = if mismatched {
+        SignatureStatus::Tampered
+    } else if checked == 0 {
+        SignatureStatus::Unverified
+    } else {
+        SignatureStatus::DigestMatches
+    };
+
+    let signer = part
+        .certificate
+        .as_deref()
+        .and_then(cms::certificate_subject)
+        .or_else(|| part.subject_name.as_deref().and_then(common_name));
+    DigitalSignature {
+        kind: SignatureKind::Ooxml,
+        signer,
+        signed_at: part.signed_at,
+        reason: part.comments,
+        status,
+        modified_after_signing: mismatched,
+    }
+}
+
+fn read(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// Element whose text is being collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Capture {
+    DigestValue,
+    Certificate,
+    SubjectName,
+    SignatureTime,
+    Comments,
+}
+
+/// Read the package references and signer details of a signature part
+fn parse(xml: &str) -> SignaturePart {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut part = SignaturePart::default();
+    let mut reference: Option<Reference> = None;
+    let mut capture = None;
+    let mut text = String::new();
+    let mut in_signature_time = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                match e.local_name().as_ref() {
+                    b"Reference" => reference = package_reference(&e),
+                    b"SignatureTime" => in_signature_time = true,
+                    b"Value" if in_signature_time => capture = Some(Capture::SignatureTime),
+                    b"DigestValue" => capture = Some(Capture::DigestValue),
+                    b"X509Certificate" => capture = Some(Capture::Certificate),
+                    b"X509SubjectName" => capture = Some(Capture::SubjectName),
+                    b"SignatureComments" => capture = Some(Capture::Comments),
+                    name => reference_child(reference.as_mut(), name, &e),
+                }
+                text.clear();
+            }
+            Ok(Event::Empty(e)) => {
+                reference_child(reference.as_mut(), e.local_name().as_ref(), &e);
+            }
+            Ok(Event::Text(e)) if capture.is_some() => {
+                if let Ok(unescaped) = e.unescape() {
+                    text.push_str(&unescaped);
+                }
+            }
+            Ok(Event::End(e)) => {
+                match e.local_name().as_ref() {
+                    b"Reference" => part.references.extend(reference.take()),
+                    b"SignatureTime" => in_signature_time = false,
+                    _ => {}
+                }
+                match capture.take() {
+                    Some(Capture::DigestValue) => {
+                        if let Some(reference) = reference.as_mut() {
+                            reference.digest = decode(&text).unwrap_or_default();
+                        }
+                    }
+                    Some(Capture::Certificate) => {
+                        part.certificate = part.certificate.take().or_else(|| decode(&text));
+                    }
+                    Some(Capture::SubjectName) => part.subject_name = Some(text.clone()),
+                    Some(Capture::SignatureTime) => {
+                        part.signed_at = DateTime::parse_from_rfc3339(text.trim())
+                            .ok()
+                            .map(|time| time.with_timezone(&Utc));
+                    }
+                    Some(Capture::Comments) => {
+                        part.comments = Some(text.trim().to_string()).filter(|c| !c.is_empty());
+                    }
+                    None => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    part
+}
+
+/// A reference to a package part; references to elements of the
+/// signature itself (`#idPackageObject`) are skipped
+fn package_reference(e: &BytesStart<'_>) -> Option<Reference> {
+    let uri = attr_value_opt(e, b"URI")?;
+    let path = uri.strip_prefix('/')?;
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    Some(Reference {
+        part: percent_decode(path),
+        ..Reference::default()
+    })
+}
+
+/// Record the transforms and digest method of a reference
+fn reference_child(reference: Option<&mut Reference>, name: &[u8], e: &BytesStart<'_>) {
+    let Some(reference) = reference else {
+        return;
+    };
+    match name {
+        b"Transform" => reference.transformed = true,
+        b"DigestMethod" => {
+            reference.algorithm =
+                attr_value_opt(e, b"Algorithm").and_then(|uri| DigestAlgorithm::from_uri(&uri));
+        }
+        _ => {}
+    }
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let compact: String = text.split_whitespace().collect();
+    general_purpose::STANDARD.decode(compact).ok()
+}
+
+/// The `CN` of a distinguished name such as `CN=Jane Signer, O=Example`
+fn common_name(name: &str) -> Option<String> {
+    name.split(',')
+        .find_map(|rdn| rdn.trim().strip_prefix("CN="))
+        .map(str::to_string)
+}
+
+/// Decode `%XX` escapes in a part name
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escaped {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cms::tests::certificate;
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    const DOCUMENT: &str = "<w:document>Signed text</w:document>";
+
+    fn package(document: &str) -> Vec<u8> {
+        let digest = general_purpose::STANDARD
+            .encode(DigestAlgorithm::Sha256.digest(&[DOCUMENT.as_bytes()]));
+        let certificate = general_purpose::STANDARD.encode(certificate(7, "Jane Signer"));
+        let signature = format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<Signature xmlns="http://www.w3.org/2000/09/xmldsig#" Id="idPackageSignature">
+  <SignedInfo>
+    <Reference Type="http://www.w3.org/2000/09/xmldsig#Object" URI="#idPackageObject">
+      <DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+      <DigestValue>AAAA</DigestValue>
+    </Reference>
+  </SignedInfo>
+  <KeyInfo><X509Data><X509Certificate>{certificate}</X509Certificate></X509Data></KeyInfo>
+  <Object Id="idPackageObject">
+    <Manifest>
+      <Reference URI="/_rels/.rels?ContentType=application/vnd.openxmlformats-package.relationships+xml">
+        <Transforms><Transform Algorithm="http://schemas.openxmlformats.org/package/2006/RelationshipTransform"/></Transforms>
+        <DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+        <DigestValue>AAAA</DigestValue>
+      </Reference>
+      <Reference URI="/word/document.xml?ContentType=application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml">
+        <DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+        <DigestValue>{digest}</DigestValue>
+      </Reference>
+    </Manifest>
+    <SignatureProperties>
+      <SignatureProperty Id="idSignatureTime" Target="#idPackageSignature">
+        <mdssi:SignatureTime xmlns:mdssi="http://schemas.openxmlformats.org/package/2006/digital-signature">
+          <mdssi:Format>YYYY-MM-DDThh:mm:ssTZD</mdssi:Format>
+          <mdssi:Value>2024-05-01T10:20:30Z</mdssi:Value>
+        </mdssi:SignatureTime>
+      </SignatureProperty>
+    </SignatureProperties>
+  </Object>
+  <Object><SignatureInfoV1 xmlns="http://schemas.microsoft.com/office/2006/digsig"><SignatureComments>Final version</SignatureComments></SignatureInfoV1></Object>
+</Signature>"##
+        );
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in [
+            ("word/document.xml", document),
+            ("_xmlsignatures/origin.sigs", ""),
+            ("_xmlsignatures/sig1.xml", signature.as_str()),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_ooxml_signatures() {
+        let signatures = signatures(&package(DOCUMENT));
+        assert_eq!(signatures.len(), 1);
+        let signature = &signatures[0];
+        assert_eq!(signature.kind, SignatureKind::Ooxml);
+        assert_eq!(signature.status, SignatureStatus::DigestMatches);
+        assert!(!signature.modified_after_signing);
+        assert_eq!(signature.signer.as_deref(), Some("Jane Signer"));
+        assert_eq!(signature.reason.as_deref(), Some("Final version"));
+        assert_eq!(
+            signature.signed_at.unwrap().to_rfc3339(),
+            "2024-05-01T10:20:30+00:00"
+        );
+
+        let signature = &super::signatures(&package("<w:document>Edited</w:document>"))[0];
+        assert_eq!(signature.status, SignatureStatus::Tampered);
+        assert!(signature.modified_after_signing);
+    }
+
+    #[test]
+    fn test_part_names() {
+        assert_eq!(
+            percent_decode("word/media/my%20image.png"),
+            "word/media/my image.png"
+        );
+        assert_eq!(percent_decode("bad%zz"), "bad%zz");
+        assert_eq!(
+            common_name("O=Example, CN=Jane Signer").as_deref(),
+            Some("Jane Signer")
+        );
+    }
+}
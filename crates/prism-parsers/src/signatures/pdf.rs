@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! PDF signatures: signature dictionaries whose `/ByteRange` names the
+//! signed bytes of the file, with everything but the `/Contents` hole
+//! that holds the CMS signature itself
+
+use chrono::{DateTime, Local, Utc};
+use lopdf::{decode_text_string, Dictionary, Document as LopdfDocument};
+use prism_core::metadata::{DigitalSignature, SignatureKind, SignatureStatus};
+
+use super::{cms, memmem};
+
+/// Sub-filters whose CMS signature is detached from the signed bytes, so
+/// that its message digest is the digest of the byte range
+const DETACHED: &[&[u8]] = &[b"adbe.pkcs7.detached", b"ETSI.CAdES.detached"];
+
+/// The signatures of a PDF, in signing order
+pub fn signatures(data: &[u8]) -> Vec<DigitalSignature> {
+    if memmem(data, b"/ByteRange").is_none() {
+        return Vec::new();
+    }
+    let Ok(document) = LopdfDocument::load_mem(data) else {
+        return Vec::new();
+    };
+    let mut signatures: Vec<_> = document
+        .objects
+        .values()
+        .filter_map(|object| object.as_dict().ok())
+        .filter_map(|dict| signature(data, dict))
+        .collect();
+    signatures.sort_by_key(|(end, _)| *end);
+    signatures
+        .into_iter()
+        .map(|(_, signature)| signature)
+        .collect()
+}
+
+/// A signature dictionary checked against `data`, with the end of its
+/// signed range
+fn signature(data: &[u8], dict: &Dictionary) -> Option<(usize, DigitalSignature)> {
+    let range = dict
+        .get(b"ByteRange")
+        .and_then(lopdf::Object::as_array)
+        .ok()?
+        .iter()
+        .map(|value| value.as_i64().ok().and_then(|n| usize::try_from(n).ok()))
+        .collect::<Option<Vec<_>>>()?;
+    let [start, first_len, second_start, second_len] = range[..] else {
+        return None;
+    };
+    let first = data.get(start..start.checked_add(first_len)?)?;
+    let end = second_start.checked_add(second_len)?;
+    let second = data.get(second_start..end)?;
+
+    let contents = dict.get(b"Contents").and_then(lopdf::Object::as_str).ok()?;
+    let detached = dict
+        .get(b"SubFilter")
+        .and_then(lopdf::Object::as_name)
+        .is_ok_and(|sub_filter| DETACHED.contains(&sub_filter));
+    let info = cms::signer_info(contents).unwrap_or_default();
+
+    // The range must cover the whole file up to its end but for the hole
+    // holding this signature, or it leaves bytes unsigned
+    let covers = start == 0
+        && data
+            .get(first_len..second_start)
+            .is_some_and(|hole| is_hole_of(hole, contents));
+    let status = match (&info.message_digest, info.digest_algorithm) {
+        (Some(expected), Some(algorithm)) if detached && covers => {
+            if algorithm.digest(&[first, second]) == *expected {
+                SignatureStatus::DigestMatches
+            } else {
+                SignatureStatus::Tampered
+            }
+        }
+        _ => SignatureStatus::Unverified,
+    };
+    // An incremental update appends to the file after the signed range
+    let appended = data[end..].iter().any(|byte| !byte.is_ascii_whitespace());
+
+    let text = |key: &[u8]| {
+        dict.get(key)
+            .ok()
+            .and_then(|value| decode_text_string(value).ok())
+            .filter(|text| !text.is_empty())
+    };
+    let signed_at = dict
+        .get(b"M")
+        .ok()
+        .and_then(lopdf::Object::as_datetime)
+        .and_then(|date| DateTime::<Local>::try_from(date).ok())
+        .map(|date| date.with_timezone(&Utc));
+
+    Some((
+        end,
+        DigitalSignature {
+            kind: SignatureKind::Pdf,
+            signer: text(b"Name").or(info.signer),
+            signed_at: signed_at.or(info.signing_time),
+            reason: text(b"Reason"),
+            status,
+            modified_after_signing: !covers || appended || status == SignatureStatus::Tampered,
+        },
+    ))
+}
+
+/// Whether `hole` is exactly the hex string `<...>` encoding `contents`
+fn is_hole_of(hole: &[u8], contents: &[u8]) -> bool {
+    let Some(hex) = hole
+        .strip_prefix(b"<")
+        .and_then(|hex| hex.strip_suffix(b">"))
+    else {
+        return false;
+    };
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return false;
+    }
+    // An odd final digit is followed by an implied 0
+    let decoded = hex.chunks(2).map(|pair| {
+        let digit = |index: usize| {
+            pair.get(index)
+                .and_then(|&byte| char::from(byte).to_digit(16))
+                .unwrap_or(0)
+        };
+        digit(0) * 16 + digit(1)
+    });
+    decoded.eq(contents.iter().map(|&byte| u32::from(byte)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cms::tests::signed_data;
+    use super::super::DigestAlgorithm;
+    use super::*;
+    use lopdf::{dictionary, Object, StringFormat};
+    use std::fmt::Write as _;
+
+    /// Size of the `/Contents` hole, in bytes of signature
+    const HOLE: usize = 2048;
+
+    /// A one-page PDF signed by "Jane Signer"
+    fn signed_pdf() -> Vec<u8> {
+        let mut pdf = LopdfDocument::with_version("1.7");
+        let placeholder = || Object::Integer(9_999_999_999);
+        let signature = pdf.add_object(dictionary! {
+            "Type" => "Sig",
+            "Filter" => "Adobe.PPKLite",
+            "SubFilter" => "adbe.pkcs7.detached",
+            "Reason" => Object::String(b"Approved".to_vec(), StringFormat::Literal),
+            "M" => Object::String(b"D:20240501102030Z".to_vec(), StringFormat::Literal),
+            "ByteRange" => vec![placeholder(), placeholder(), placeholder(), placeholder()],
+            "Contents" => Object::String(vec![0; HOLE], StringFormat::Hexadecimal),
+        });
+        let page = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        let pages = pdf.add_object(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page.into()],
+            "Count" => 1,
+        });
+        let catalog = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages,
+            "Perms" => dictionary! { "DocMDP" => signature },
+        });
+        pdf.trailer.set("Root", catalog);
+        let mut data = Vec::new();
+        pdf.save_to(&mut data).unwrap();
+
+        // Point the byte range around the hole, keeping offsets unchanged
+        let hole = format!("<{}>", "0".repeat(HOLE * 2));
+        let hole_start = memmem(&data, hole.as_bytes()).unwrap();
+        let hole_end = hole_start + hole.len();
+        let range = format!("[0 {hole_start} {hole_end} {}", data.len() - hole_end);
+        let placeholder = b"[9999999999 9999999999 9999999999 9999999999";
+        let at = memmem(&data, placeholder).unwrap();
+        let padded = format!("{range:<width$}", width = placeholder.len());
+        data[at..at + placeholder.len()].copy_from_slice(padded.as_bytes());
+
+        let digest = DigestAlgorithm::Sha256.digest(&[&data[..hole_start], &data[hole_end..]]);
+        let mut cms = String::new();
+        for byte in signed_data(&digest, "Jane Signer") {
+            let _ = write!(cms, "{byte:02x}");
+        }
+        data[hole_start + 1..hole_start + 1 + cms.len()].copy_from_slice(cms.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_pdf_signatures() {
+        let data = signed_pdf();
+        let signatures = signatures(&data);
+        assert_eq!(signatures.len(), 1);
+        let signature = &signatures[0];
+        assert_eq!(signature.status, SignatureStatus::DigestMatches);
+        assert!(!signature.modified_after_signing);
+        assert_eq!(signature.signer.as_deref(), Some("Jane Signer"));
+        assert_eq!(signature.reason.as_deref(), Some("Approved"));
+        assert_eq!(
+            signature.signed_at.unwrap().to_rfc3339(),
+            "2024-05-01T10:20:30+00:00"
+        );
+
+        // An incremental update after signing
+        let mut updated = data.clone();
+        updated.extend_from_slice(b"% appended revision\n");
+        let signature = &super::signatures(&updated)[0];
+        assert_eq!(signature.status, SignatureStatus::DigestMatches);
+        assert!(signature.modified_after_signing);
+
+        // A byte range that leaves the first byte unsigned
+        let at = memmem(&data, b"/ByteRange").unwrap();
+        let at = at + memmem(&data[at..], b"[0 ").unwrap() + 1;
+        let mut shifted = data.clone();
+        shifted[at] = b'1';
+        let signature = &super::signatures(&shifted)[0];
+        assert_eq!(signature.status, SignatureStatus::Unverified);
+        assert!(signature.modified_after_signing);
+
+        // A gap in the range wider than the signature
+        assert!(is_hole_of(b"<0a1>", &[0x0a, 0x10]));
+        assert!(!is_hole_of(b"<0a10> /Evil", &[0x0a, 0x10]));
+        assert!(!is_hole_of(b"<0a10>", &[0x0a]));
+
+        // Signed bytes changed in place
+        let mut tampered = data;
+        let at = memmem(&tampered, b"/MediaBox").unwrap();
+        tampered[at + 1] = b'm';
+        let signature = &super::signatures(&tampered)[0];
+        assert_eq!(signature.status, SignatureStatus::Tampered);
+        assert!(signature.modified_after_signing);
+    }
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Text encoding detection
+//!
+//! Text files do not declare their encoding, so [`detect`] guesses it from
+//! the bytes:
+//!
+//! 1. A byte order mark decides between UTF-8, UTF-16LE and UTF-16BE.
+//! 2. Valid UTF-8, which includes plain ASCII, is UTF-8.
+//! 3. UTF-16 without a byte order mark shows as zero bytes in every other
+//!    position, the high bytes of ASCII characters.
+//! 4. Anything else is taken for Windows-1252, the superset of Latin-1
+//!    legacy Western text is written in, as long as none of the bytes it
+//!    leaves undefined occur and most bytes are ASCII.
+//!
+//! Whatever the encoding, content with more than 10% control characters
+//! other than line breaks and tabs is binary rather than text.
+//!
+//! [`decode`] transcodes to UTF-8 and normalizes to NFC, so that an `é`
+//! written as `e` and a combining accent reads the same as a precomposed
+//! one.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// Bytes Windows-1252 assigns no character to
+const UNDEFINED_1252: [u8; 5] = [0x81, 0x8D, 0x8F, 0x90, 0x9D];
+
+/// Text decoded to UTF-8
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// The text, normalized to NFC
+    pub text: String,
+
+    /// Encoding the text was detected in
+    pub encoding: &'static Encoding,
+}
+
+/// Detect the encoding of `data`, or `None` if it is not text
+#[must_use]
+pub fn detect(data: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, bom)) = Encoding::for_bom(data) {
+        let body = &data[bom..];
+        let is_text = if encoding == UTF_8 {
+            is_utf8_text(body)
+        } else {
+            is_utf16_text(body, encoding == UTF_16BE)
+        };
+        return is_text.then_some(encoding);
+    }
+    if is_utf8_text(data) {
+        return Some(UTF_8);
+    }
+    if let Some(big_endian) = utf16_byte_order(data) {
+        if is_utf16_text(data, big_endian) {
+            return Some(if big_endian { UTF_16BE } else { UTF_16LE });
+        }
+    }
+    is_1252_text(data).then_some(WINDOWS_1252)
+}
+
+/// Decode `data` to NFC-normalized UTF-8, or `None` if it is not text
+#[must_use]
+pub fn decode(data: &[u8]) -> Option<Decoded> {
+    let encoding = detect(data)?;
+    let (text, _) = encoding.decode_with_bom_removal(data);
+    let text = if is_nfc(&text) {
+        text.into_owned()
+    } else {
+        text.nfc().collect()
+    };
+    Some(Decoded { text, encoding })
+}
+
+fn is_utf8_text(data: &[u8]) -> bool {
+    // Bytes of multi-byte sequences are never control characters
+    std::str::from_utf8(data).is_ok() && mostly_text(data.iter().copied().map(u32::from))
+}
+
+/// Whether `data` is UTF-16 in the given byte order, without unpaired
+/// surrogates
+fn is_utf16_text(data: &[u8], big_endian: bool) -> bool {
+    if data.len() % 2 != 0 {
+        return false;
+    }
+    let units = || {
+        data.chunks_exact(2).map(move |pair| {
+            let pair = [pair[0], pair[1]];
+            if big_endian {
+                u16::from_be_bytes(pair)
+            } else {
+                u16::from_le_bytes(pair)
+            }
+        })
+    };
+    char::decode_utf16(units()).all(|c| c.is_ok()) && mostly_text(units().map(u32::from))
+}
+
+/// Byte order of UTF-16 without a byte order mark, told by which half of
+/// its code units is mostly zero: `Some(true)` for big endian
+fn utf16_byte_order(data: &[u8]) -> Option<bool> {
+    if data.len() < 2 || data.len() % 2 != 0 {
+        return None;
+    }
+    let units = data.len() / 2;
+    let zeros = |offset: usize| {
+        data.iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    // At least 40% of the high bytes and under 10% of the low ones
+    let order = |high: usize, low: usize| high * 5 >= units * 2 && low * 10 < units;
+    if order(odd, even) {
+        Some(false)
+    } else if order(even, odd) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn is_1252_text(data: &[u8]) -> bool {
+    if data.iter().any(|b| UNDEFINED_1252.contains(b)) {
+        return false;
+    }
+    // Latin text is mostly ASCII, with at most 30% accented letters
+    let high = data.iter().filter(|&&b| b >= 0x80).count();
+    high * 10 <= data.len() * 3 && mostly_text(data.iter().copied().map(u32::from))
+}
+
+/// Whether fewer than 10% of the code units are control characters other
+/// than line breaks and tabs
+fn mostly_text(units: impl Iterator<Item = u32>) -> bool {
+    let (mut total, mut control) = (0, 0);
+    for unit in units {
+        total += 1;
+        if unit < 0x20 && !matches!(unit, 0x09 | 0x0A | 0x0D) {
+            control += 1;
+        }
+    }
+    control * 10 < total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoded = decode("naïve café".as_bytes()).unwrap();
+        assert_eq!(decoded.encoding, UTF_8);
+        assert_eq!(decoded.text, "naïve café");
+
+        // Latin-1 / Windows-1252, with a curly quote from the 0x80 block
+        let decoded = decode(b"Caf\xe9 \x93cr\xe8me\x94 br\xfbl\xe9e\n").unwrap();
+        assert_eq!(decoded.encoding, WINDOWS_1252);
+        assert_eq!(decoded.text, "Café “crème” brûlée\n");
+
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(utf16("Grüße\r\n", false));
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.encoding, UTF_16LE);
+        assert_eq!(decoded.text, "Grüße\r\n");
+
+        let decoded = decode(&utf16("Hello, world", true)).unwrap();
+        assert_eq!(decoded.encoding, UTF_16BE);
+        assert_eq!(decoded.text, "Hello, world");
+
+        // A combining accent is composed
+        assert_eq!(
+            decode("e\u{0301}te\u{0301}".as_bytes()).unwrap().text,
+            "été"
+        );
+
+        // Binary data, and a byte order mark followed by half a code unit
+        assert!(decode(&[0x00, 0x01, 0x02, 0x03, 0xFF]).is_none());
+        assert!(decode(&[0xFF, 0xFE, 0xFD]).is_none());
+        assert!(decode(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00]).is_none());
+    }
+}
@@ -1,24 +1,93 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! HTML file parser
 //!
-//! Parses HTML files into the Unified Document Model.
-//! For HTML files, we preserve the raw HTML content and render it directly.
+//! Parses HTML files into the Unified Document Model as one flowing page:
+//!
+//! - Headings are text blocks styled `Heading 1` to `Heading 6` and are
+//!   listed in the document structure, each starting a chapter section.
+//!   Lists become list blocks as in the Markdown parser, and list items,
+//!   block quotes and preformatted text use its paragraph styles. The `type` and `start` attributes of `<ol>`
+//!   set the numbering.
+//! - Tables become table blocks, keeping `colspan` and `rowspan`.
+//! - Images become image blocks, with `src` resolved as for Markdown.
+//! - Links are underlined, set as the link of their runs and recorded as
+//!   link annotations.
+//!
+//! Character formatting comes from the tags (`<b>`, `<em>`, `<code>`, ...),
+//! from `style` attributes and from `<style>` sheets. The properties used
+//! are `font-weight`, `font-style`, `text-decoration`, `color`,
+//! `background-color`, `font-size`, `font-family` and `display: none`.
+//! Style sheet rules apply in source order, followed by the `style`
+//! attribute; selector specificity, `!important` and at-rules such as
+//! media queries are ignored.
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use ego_tree::NodeId;
 use prism_core::{
-    document::{ContentBlock, Dimensions, Document, Page, Rect, TextBlock, TextRun, TextStyle},
+    document::{
+        Annotation, AnnotationType, ContentBlock, Dimensions, Document, Heading, ImageBlock, Link,
+        ListBlock, ListItem, ListMarker, Page, PageMetadata, Rect, Section, ShapeStyle, TableBlock,
+        TableCell, TableRow, TextBlock, TextDirection, TextRun, TextStyle,
+    },
     error::{Error, Result},
     format::Format,
     metadata::Metadata,
     parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
 };
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
 use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::text::encoding;
+use crate::text::linked::LinkedImages;
+
+/// Font size that relative CSS sizes are based on, in points
+const BASE_FONT_SIZE: f64 = 12.0;
+
+/// Points per CSS pixel
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// Elements whose content is not part of the document text
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "title", "meta", "link", "iframe", "object",
+    "svg", "select",
+];
+
+/// Elements that start and end a paragraph
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "main",
+    "nav",
+    "aside",
+    "figure",
+    "figcaption",
+    "address",
+    "dl",
+    "dt",
+    "dd",
+    "form",
+    "fieldset",
+    "legend",
+    "details",
+    "summary",
+    "center",
+    "caption",
+];
+
+/// A CSS property and its value
+type Declaration = (String, String);
 
 /// HTML file parser
 ///
-/// Parses HTML files into the Unified Document Model.
-/// The HTML content is preserved and can be rendered directly.
+/// Parses HTML files into the Unified Document Model, converting the
+/// element tree into text, table and image blocks.
 #[derive(Debug, Clone)]
 pub struct HtmlParser;
 
@@ -30,16 +99,16 @@ impl HtmlParser {
     }
 
     /// Extract title from HTML if present
+    #[cfg(test)]
     fn extract_title(html: &str) -> Option<String> {
-        // Simple title extraction - look for <title>...</title>
-        if let Some(start_idx) = html.find("<title>") {
-            let after_start = &html[start_idx + 7..];
-            if let Some(end_idx) = after_start.find("</title>") {
-                let title = &after_start[..end_idx];
-                return Some(title.trim().to_string());
-            }
-        }
-        None
+        Self::title(&Html::parse_document(html))
+    }
+
+    fn title(html: &Html) -> Option<String> {
+        let selector = Selector::parse("title").ok()?;
+        let title = html.select(&selector).next()?.text().collect::<String>();
+        let title = title.trim();
+        (!title.is_empty()).then(|| title.to_string())
     }
 
     /// Check if data starts with common HTML markers
@@ -55,6 +124,34 @@ impl HtmlParser {
             || text_lower.starts_with("<html")
             || text_lower.starts_with("<!doctype")
     }
+
+    /// Author, description, keywords and language from the document head
+    fn head_metadata(html: &Html, metadata: &mut Metadata) {
+        if let Some(language) = html.root_element().attr("lang") {
+            metadata.language = Some(language.to_string());
+        }
+        let Ok(selector) = Selector::parse("meta[name][content]") else {
+            return;
+        };
+        for meta in html.select(&selector) {
+            let (Some(name), Some(content)) = (meta.attr("name"), meta.attr("content")) else {
+                continue;
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "author" => metadata.author = Some(content.to_string()),
+                "description" => metadata.subject = Some(content.to_string()),
+                "keywords" => metadata.keywords.extend(
+                    content
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|keyword| !keyword.is_empty())
+                        .map(str::to_string),
+                ),
+                "generator" => metadata.creator = Some(content.to_string()),
+                _ => {}
+            }
+        }
+    }
 }
 
 impl Default for HtmlParser {
@@ -95,45 +192,38 @@ impl Parser for HtmlParser {
         );
 
         // Convert to string
-        let html_content = String::from_utf8(data.to_vec())
-            .map_err(|e| Error::ParseError(format!("Invalid UTF-8 in HTML file: {}", e)))?;
-
-        // Extract title from HTML if present
-        let title = Self::extract_title(&html_content);
-
-        // For HTML, we'll store the raw HTML as a text block
-        // The HTML renderer will handle displaying it properly
-        let text_run = TextRun {
-            text: html_content.clone(),
-            style: TextStyle::default(),
-            bounds: Some(Rect::default()),
-            char_positions: Some(Vec::new()),
-        };
+        let html_content = encoding::decode(&data)
+            .ok_or_else(|| Error::ParseError("HTML file is not text in a known encoding".into()))?
+            .text;
+        let html = Html::parse_document(&html_content);
 
-        let text_block = TextBlock {
-            runs: vec![text_run],
-            paragraph_style: None,
-            bounds: Rect::default(),
-            style: prism_core::document::ShapeStyle::default(),
-            rotation: 0.0,
-        };
+        let mut converter = Converter::new(&html, LinkedImages::new(context.options.resource_dir));
+        converter.element(html.root_element(), &TextStyle::default());
+        converter.flush();
 
-        // Create a single page with the HTML content
-        // Use a wide page to accommodate HTML content
         let page = Page {
             number: 1,
-            dimensions: Dimensions {
-                width: 850.0, // Wider than standard to fit HTML content
-                height: 1100.0,
-            },
-            content: vec![ContentBlock::Text(text_block)],
-            metadata: Default::default(),
-            annotations: Vec::new(),
+            dimensions: Dimensions::LETTER,
+            content: converter.targets.pop().unwrap_or_default(),
+            metadata: PageMetadata::default(),
+            annotations: converter.annotations,
         };
 
         // Create metadata
-        let mut metadata = Metadata::default();
-        metadata.title = title.or_else(|| context.filename.clone());
+        let title = Self::title(&html)
+            .or_else(|| {
+                converter
+                    .headings
+                    .iter()
+                    .find(|heading| heading.level == 1)
+                    .map(|heading| heading.text.clone())
+            })
+            .or_else(|| context.filename.clone());
+        let mut metadata = Metadata {
+            title,
+            ..Metadata::default()
+        };
+        Self::head_metadata(&html, &mut metadata);
         metadata.add_custom("format", "HTML");
         metadata.add_custom("content_type", "text/html");
 
@@ -141,6 +231,9 @@ impl Parser for HtmlParser {
         let mut document = Document::new();
         document.pages = vec![page];
         document.metadata = metadata;
+        document.structure.headings = converter.headings;
+        document.structure.sections = Section::from_headings(&document.pages);
+        document.resources.images = converter.images.into_resources();
 
         info!("Successfully parsed HTML file");
 
@@ -153,6 +246,8 @@ impl Parser for HtmlParser {
             version: env!("CARGO_PKG_VERSION").to_string(),
             features: vec![
                 ParserFeature::TextExtraction,
+                ParserFeature::TableExtraction,
+                ParserFeature::ImageExtraction,
                 ParserFeature::MetadataExtraction,
             ],
             requires_sandbox: false,
@@ -160,6 +255,705 @@ impl Parser for HtmlParser {
     }
 }
 
+/// Paragraph being collected
+#[derive(Debug, Default)]
+struct Paragraph {
+    /// Text, style and link of each run; text is appended until the style
+    /// or link changes
+    runs: Vec<(String, TextStyle, Option<Link>)>,
+    style: Option<String>,
+}
+
+impl Paragraph {
+    fn new(style: Option<String>) -> Self {
+        Self {
+            style,
+            ..Self::default()
+        }
+    }
+
+    fn has_text(&self) -> bool {
+        self.runs.iter().any(|(text, _, _)| !text.trim().is_empty())
+    }
+
+    fn ends_with_space(&self) -> bool {
+        self.runs
+            .last()
+            .map_or(true, |(text, _, _)| text.ends_with(char::is_whitespace))
+    }
+
+    fn push(&mut self, text: &str, style: &TextStyle, link: Option<Link>) {
+        match self.runs.last_mut() {
+            Some((last, last_style, last_link)) if last_style == style && *last_link == link => {
+                last.push_str(text);
+            }
+            _ => self.runs.push((text.to_string(), style.clone(), link)),
+        }
+    }
+
+    fn into_block(mut self) -> Option<TextBlock> {
+        if !self.has_text() {
+            return None;
+        }
+        if self.style.as_deref() != Some("Code") {
+            if let Some((last, _, _)) = self.runs.last_mut() {
+                let trimmed = last.trim_end().len();
+                last.truncate(trimmed);
+            }
+        }
+        Some(TextBlock {
+            bounds: Rect::default(),
+            runs: self
+                .runs
+                .into_iter()
+                .map(|(text, style, link)| TextRun {
+                    link,
+                    ..TextRun::with_style(text, style)
+                })
+                .collect(),
+            paragraph_style: self.style,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            direction: TextDirection::Auto,
+        })
+    }
+}
+
+/// Walks the element tree and builds the page content
+struct Converter {
+    /// Style sheet declarations matching each element
+    rules: HashMap<NodeId, Vec<Declaration>>,
+    images: LinkedImages,
+    annotations: Vec<Annotation>,
+    headings: Vec<Heading>,
+    /// Content being filled: the page, then the innermost table cell or
+    /// list item
+    targets: Vec<Vec<ContentBlock>>,
+    paragraph: Option<Paragraph>,
+    /// Open lists, with their marker style and the next number of ordered
+    /// ones
+    lists: Vec<(ListMarker, u64)>,
+    /// The outermost open list, with nested lists' items flattened into it
+    list: Option<ListBlock>,
+    quote_depth: usize,
+    pre_depth: usize,
+    /// Destination and text of the open link
+    link: Option<(String, String)>,
+}
+
+impl Converter {
+    fn new(html: &Html, images: LinkedImages) -> Self {
+        let mut rules: HashMap<NodeId, Vec<Declaration>> = HashMap::new();
+        if let Ok(style) = Selector::parse("style") {
+            for sheet in html.select(&style) {
+                let css = sheet.text().collect::<String>();
+                for (selector, declarations) in parse_stylesheet(&css) {
+                    for element in html.select(&selector) {
+                        rules
+                            .entry(element.id())
+                            .or_default()
+                            .extend(declarations.iter().cloned());
+                    }
+                }
+            }
+        }
+
+        Self {
+            rules,
+            images,
+            annotations: Vec::new(),
+            headings: Vec::new(),
+            targets: vec![Vec::new()],
+            paragraph: None,
+            lists: Vec::new(),
+            list: None,
+            quote_depth: 0,
+            pre_depth: 0,
+            link: None,
+        }
+    }
+
+    fn children(&mut self, element: ElementRef<'_>, style: &TextStyle) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(&text.text, style),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child, style);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef<'_>, parent: &TextStyle) {
+        let name = element.value().name();
+        if SKIPPED.contains(&name) {
+            return;
+        }
+
+        let mut declarations = self.rules.get(&element.id()).cloned().unwrap_or_default();
+        if let Some(inline) = element.attr("style") {
+            declarations.extend(parse_declarations(inline));
+        }
+        if is_hidden(&declarations) {
+            return;
+        }
+        let mut style = parent.clone();
+        tag_style(name, &mut style);
+        apply_declarations(&declarations, &mut style);
+
+        match name {
+            "br" => self.push("\n", &style),
+            "hr" => self.flush(),
+            "img" => self.image(element),
+            "table" => self.table(element, &style),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.heading(element, name[1..].parse().unwrap_or(1), &style);
+            }
+            "ul" | "ol" | "menu" => {
+                self.flush();
+                let marker = if name == "ol" {
+                    match element.attr("type").map(str::trim) {
+                        Some("a") => ListMarker::LowerAlpha,
+                        Some("A") => ListMarker::UpperAlpha,
+                        Some("i") => ListMarker::LowerRoman,
+                        Some("I") => ListMarker::UpperRoman,
+                        _ => ListMarker::Decimal,
+                    }
+                } else {
+                    ListMarker::Bullet
+                };
+                let start = element
+                    .attr("start")
+                    .and_then(|start| start.trim().parse().ok())
+                    .unwrap_or(1);
+                let outermost = self.list.is_none();
+                if outermost {
+                    self.list = Some(ListBlock::new(Rect::default()));
+                }
+                self.lists.push((marker, start));
+                self.children(element, &style);
+                self.flush();
+                self.lists.pop();
+                if outermost {
+                    self.emit_list();
+                }
+            }
+            "li" => self.list_item(element, &style),
+            "blockquote" => {
+                self.flush();
+                self.quote_depth += 1;
+                self.children(element, &style);
+                self.flush();
+                self.quote_depth -= 1;
+            }
+            "pre" => {
+                self.flush();
+                self.pre_depth += 1;
+                self.begin(Paragraph::new(Some("Code".to_string())));
+                self.children(element, &style);
+                if let Some(last) = self
+                    .paragraph
+                    .as_mut()
+                    .and_then(|paragraph| paragraph.runs.last_mut())
+                {
+                    let trimmed = last.0.trim_end_matches('\n').len();
+                    last.0.truncate(trimmed);
+                }
+                self.flush();
+                self.pre_depth -= 1;
+            }
+            "a" => match element.attr("href") {
+                Some(href) => {
+                    style.underline = true;
+                    self.link(element, href, &style);
+                }
+                None => self.children(element, &style),
+            },
+            _ if BLOCKS.contains(&name) => {
+                self.break_paragraph();
+                self.children(element, &style);
+                self.break_paragraph();
+            }
+            _ => self.children(element, &style),
+        }
+    }
+
+    fn heading(&mut self, element: ElementRef<'_>, level: u8, style: &TextStyle) {
+        self.begin(Paragraph::new(Some(format!("Heading {level}"))));
+        self.children(element, style);
+        if let Some(paragraph) = &self.paragraph {
+            let text = paragraph
+                .runs
+                .iter()
+                .map(|(text, _, _)| text.as_str())
+                .collect::<String>();
+            self.headings.push(Heading {
+                text: text.trim().to_string(),
+                level,
+                page: 1,
+                bounds: None,
+            });
+        }
+        self.flush();
+    }
+
+    /// Add an item to the open list, or to a list of its own when the
+    /// `<li>` is not inside one
+    fn list_item(&mut self, element: ElementRef<'_>, style: &TextStyle) {
+        self.flush();
+        let level = self.lists.len().max(1);
+        let depth = u8::try_from(level - 1).unwrap_or(u8::MAX);
+        let (kind, item) = match self.lists.last_mut() {
+            Some((marker, number)) if marker.is_ordered() => {
+                let label = u32::try_from(*number).unwrap_or(u32::MAX);
+                *number += 1;
+                (
+                    "Number",
+                    ListItem::numbered(Vec::new(), depth, *marker, label),
+                )
+            }
+            _ => ("Bullet", ListItem::bullet(Vec::new(), depth)),
+        };
+        let list_style = if level > 1 {
+            format!("List {kind} {level}")
+        } else {
+            format!("List {kind}")
+        };
+
+        let standalone = self.list.is_none();
+        let list = self
+            .list
+            .get_or_insert_with(|| ListBlock::new(Rect::default()));
+        // Added before its content so that nested items follow it
+        let index = list.items.len();
+        list.add_item(item);
+
+        self.targets.push(Vec::new());
+        self.begin(Paragraph::new(Some(list_style)));
+        self.children(element, style);
+        self.flush();
+        let content = self.targets.pop().unwrap_or_default();
+        if let Some(item) = self
+            .list
+            .as_mut()
+            .and_then(|list| list.items.get_mut(index))
+        {
+            item.content = content;
+        }
+        if standalone {
+            self.emit_list();
+        }
+    }
+
+    /// Emit the open list, if it has any items
+    fn emit_list(&mut self) {
+        if let Some(list) = self.list.take().filter(|list| !list.items.is_empty()) {
+            self.emit(ContentBlock::List(list));
+        }
+    }
+
+    fn link(&mut self, element: ElementRef<'_>, href: &str, style: &TextStyle) {
+        self.link = Some((href.to_string(), String::new()));
+        self.children(element, style);
+        if let Some((url, text)) = self.link.take() {
+            self.annotations.push(Annotation {
+                id: Uuid::new_v4(),
+                annotation_type: AnnotationType::Link { url },
+                bounds: Rect::default(),
+                content: Some(text.trim().to_string()),
+                author: None,
+                created: None,
+                color: None,
+            });
+        }
+    }
+
+    /// Add text, collapsing white space outside preformatted text
+    fn text(&mut self, text: &str, style: &TextStyle) {
+        if self.pre_depth > 0 {
+            self.push(text, style);
+            return;
+        }
+
+        let mut collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            if text.is_empty() {
+                return;
+            }
+            collapsed = " ".to_string();
+        }
+        if text.starts_with(char::is_whitespace) && collapsed != " " {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) && collapsed != " " {
+            collapsed.push(' ');
+        }
+
+        let at_start = self
+            .paragraph
+            .as_ref()
+            .map_or(true, Paragraph::ends_with_space);
+        let collapsed = if at_start {
+            collapsed.trim_start()
+        } else {
+            &collapsed
+        };
+        if !collapsed.is_empty() {
+            self.push(collapsed, style);
+        }
+    }
+
+    fn push(&mut self, text: &str, style: &TextStyle) {
+        let link = self.link.as_mut().map(|(url, link_text)| {
+            link_text.push_str(text);
+            Link::from_href(url)
+        });
+        let context_style = self.context_style();
+        self.paragraph
+            .get_or_insert_with(|| Paragraph::new(context_style))
+            .push(text, style, link);
+    }
+
+    /// Style of a paragraph that is not a heading or list item
+    fn context_style(&self) -> Option<String> {
+        if self.pre_depth > 0 {
+            Some("Code".to_string())
+        } else if self.quote_depth > 0 {
+            Some("Quote".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn begin(&mut self, paragraph: Paragraph) {
+        self.flush();
+        self.paragraph = Some(paragraph);
+    }
+
+    /// End the open paragraph if it has text; an empty one, such as a
+    /// list item before its first `<p>`, stays open
+    fn break_paragraph(&mut self) {
+        if self.paragraph.as_ref().is_some_and(Paragraph::has_text) {
+            self.flush();
+        }
+    }
+
+    /// Emit the open paragraph, if it has any text
+    fn flush(&mut self) {
+        if let Some(block) = self.paragraph.take().and_then(Paragraph::into_block) {
+            self.emit(ContentBlock::Text(block));
+        }
+    }
+
+    fn emit(&mut self, block: ContentBlock) {
+        if let Some(target) = self.targets.last_mut() {
+            target.push(block);
+        }
+    }
+
+    /// Place an image between the text before and after it
+    fn image(&mut self, element: ElementRef<'_>) {
+        let Some(src) = element.attr("src").filter(|src| !src.trim().is_empty()) else {
+            return;
+        };
+
+        let style = self
+            .paragraph
+            .as_ref()
+            .map(|paragraph| paragraph.style.clone());
+        self.flush();
+        let resource_id = self.images.resolve(src.trim());
+        let length = |name| {
+            element
+                .attr(name)
+                .and_then(|value| value.trim().trim_end_matches("px").parse::<f64>().ok())
+                .map(|pixels| pixels * POINTS_PER_PIXEL)
+        };
+        let original_size = length("width")
+            .zip(length("height"))
+            .map(|(width, height)| Dimensions { width, height });
+        self.emit(ContentBlock::Image(ImageBlock {
+            bounds: Rect::default(),
+            resource_id,
+            alt_text: element
+                .attr("alt")
+                .filter(|alt| !alt.is_empty())
+                .map(str::to_string),
+            format: None,
+            original_size,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        if let Some(style) = style {
+            self.paragraph = Some(Paragraph::new(style));
+        }
+    }
+
+    fn table(&mut self, table: ElementRef<'_>, style: &TextStyle) {
+        self.flush();
+        let mut rows = Vec::new();
+        for child in table.child_elements() {
+            match child.value().name() {
+                "caption" => {
+                    self.begin(Paragraph::new(Some("Caption".to_string())));
+                    self.children(child, style);
+                    self.flush();
+                }
+                "tr" => rows.extend(self.table_row(child, style)),
+                "thead" | "tbody" | "tfoot" => {
+                    for row in child.child_elements() {
+                        if row.value().name() == "tr" {
+                            rows.extend(self.table_row(row, style));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if rows.is_empty() {
+            return;
+        }
+
+        self.emit(ContentBlock::Table(TableBlock {
+            bounds: Rect::default(),
+            column_count: column_count(&rows),
+            rows,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+        }));
+    }
+
+    fn table_row(&mut self, row: ElementRef<'_>, style: &TextStyle) -> Option<TableRow> {
+        let mut cells = Vec::new();
+        for cell in row.child_elements() {
+            let name = cell.value().name();
+            if name != "td" && name != "th" {
+                continue;
+            }
+
+            let mut declarations = self.rules.get(&cell.id()).cloned().unwrap_or_default();
+            if let Some(inline) = cell.attr("style") {
+                declarations.extend(parse_declarations(inline));
+            }
+            let mut cell_style = style.clone();
+            tag_style(name, &mut cell_style);
+            apply_declarations(&declarations, &mut cell_style);
+            // The background belongs to the cell rather than its text
+            let background_color = cell_style
+                .background_color
+                .take()
+                .map(|color| color.to_string())
+                .or_else(|| cell.attr("bgcolor").map(str::to_string));
+
+            let paragraph = self.paragraph.take();
+            let lists = std::mem::take(&mut self.lists);
+            let list = self.list.take();
+            self.targets.push(Vec::new());
+            self.children(cell, &cell_style);
+            self.flush();
+            let content = self.targets.pop().unwrap_or_default();
+            self.lists = lists;
+            self.list = list;
+            self.paragraph = paragraph;
+
+            let span = |name| {
+                cell.attr(name)
+                    .and_then(|span| span.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .max(1)
+            };
+            cells.push(TableCell {
+                content,
+                col_span: span("colspan"),
+                row_span: span("rowspan"),
+                background_color,
+            });
+        }
+        (!cells.is_empty()).then_some(TableRow {
+            cells,
+            height: None,
+        })
+    }
+}
+
+/// Number of grid columns of a table, counting cells that span rows into
+/// the rows below them
+fn column_count(rows: &[TableRow]) -> usize {
+    // Rows each column is still covered for by a cell from a row above
+    let mut covered: Vec<usize> = Vec::new();
+    for row in rows {
+        let mut column = 0;
+        for cell in &row.cells {
+            while covered.get(column).is_some_and(|&rows| rows > 0) {
+                column += 1;
+            }
+            let end = column + cell.col_span;
+            if covered.len() < end {
+                covered.resize(end, 0);
+            }
+            covered[column..end].fill(cell.row_span);
+            column = end;
+        }
+        for rows in &mut covered {
+            *rows = rows.saturating_sub(1);
+        }
+    }
+    covered.len()
+}
+
+/// Formatting implied by an element
+fn tag_style(name: &str, style: &mut TextStyle) {
+    match name {
+        "b" | "strong" | "th" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => style.bold = true,
+        "i" | "em" | "cite" | "var" | "dfn" | "address" => style.italic = true,
+        "u" | "ins" => style.underline = true,
+        "s" | "strike" | "del" => style.strikethrough = true,
+        "code" | "kbd" | "samp" | "tt" | "pre" => {
+            style.font_family = Some("monospace".into());
+        }
+        "mark" => style.background_color = Some("yellow".into()),
+        _ => {}
+    }
+}
+
+fn is_hidden(declarations: &[Declaration]) -> bool {
+    declarations.iter().any(|(property, value)| {
+        (property == "display" && value.eq_ignore_ascii_case("none"))
+            || (property == "visibility" && value.eq_ignore_ascii_case("hidden"))
+    })
+}
+
+/// Apply CSS declarations, later ones taking precedence
+fn apply_declarations(declarations: &[Declaration], style: &mut TextStyle) {
+    for (property, value) in declarations {
+        let lower = value.to_ascii_lowercase();
+        match property.as_str() {
+            "font-weight" => {
+                style.bold = match lower.as_str() {
+                    "bold" | "bolder" => true,
+                    "normal" | "lighter" => false,
+                    weight => weight.parse::<u16>().map_or(style.bold, |w| w >= 600),
+                };
+            }
+            "font-style" => style.italic = lower == "italic" || lower.starts_with("oblique"),
+            "text-decoration" | "text-decoration-line" => {
+                style.underline = lower.contains("underline");
+                style.strikethrough = lower.contains("line-through");
+            }
+            "color" => style.color = Some(value.as_str().into()),
+            "background-color" | "background" if !lower.contains("url(") => {
+                style.background_color = Some(value.as_str().into());
+            }
+            "font-size" => {
+                let parent = style.font_size.unwrap_or(BASE_FONT_SIZE);
+                if let Some(size) = font_size(&lower, parent) {
+                    style.font_size = Some(size);
+                }
+            }
+            "font-family" => {
+                style.font_family = value
+                    .split(',')
+                    .next()
+                    .map(|family| family.trim().trim_matches(['"', '\'']))
+                    .filter(|family| !family.is_empty())
+                    .map(Into::into);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A CSS font size in points, relative sizes being based on `parent`
+fn font_size(value: &str, parent: f64) -> Option<f64> {
+    let keyword = match value {
+        "xx-small" => Some(7.0),
+        "x-small" => Some(7.5),
+        "small" => Some(10.0),
+        "medium" => Some(BASE_FONT_SIZE),
+        "large" => Some(13.5),
+        "x-large" => Some(18.0),
+        "xx-large" => Some(24.0),
+        "smaller" => Some(parent / 1.2),
+        "larger" => Some(parent * 1.2),
+        _ => None,
+    };
+    if keyword.is_some() {
+        return keyword;
+    }
+
+    let number = |suffix| value.strip_suffix(suffix)?.trim().parse::<f64>().ok();
+    number("pt")
+        .or_else(|| number("px").map(|px| px * POINTS_PER_PIXEL))
+        .or_else(|| number("rem").map(|rem| rem * BASE_FONT_SIZE))
+        .or_else(|| number("em").map(|em| em * parent))
+        .or_else(|| number("%").map(|percent| percent / 100.0 * parent))
+        .filter(|size| *size > 0.0)
+}
+
+/// Declarations of a `style` attribute or rule body
+fn parse_declarations(css: &str) -> Vec<Declaration> {
+    css.split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim().to_ascii_lowercase();
+            let value = value.trim().trim_end_matches("!important").trim();
+            (!property.is_empty() && !value.is_empty()).then(|| (property, value.to_string()))
+        })
+        .collect()
+}
+
+/// Style rules of a style sheet, skipping at-rules and selectors that do
+/// not parse
+fn parse_stylesheet(css: &str) -> Vec<(Selector, Vec<Declaration>)> {
+    let css = strip_comments(css);
+    let mut rules = Vec::new();
+    let mut rest = css.as_str();
+    while let Some(open) = rest.find('{') {
+        // Statements such as `@import ...;` end at a semicolon
+        let prelude = rest[..open].rsplit(';').next().unwrap_or_default().trim();
+        let body = &rest[open + 1..];
+
+        let mut depth = 1;
+        let Some(close) = body.find(|c| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        }) else {
+            break;
+        };
+
+        if !prelude.starts_with('@') {
+            if let Ok(selector) = Selector::parse(prelude) {
+                rules.push((selector, parse_declarations(&body[..close])));
+            }
+        }
+        rest = &body[close + 1..];
+    }
+    rules
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    result.push_str(rest);
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,4 +1023,199 @@ mod tests {
         assert!(!metadata.requires_sandbox);
         assert!(!metadata.features.is_empty());
     }
+
+    async fn parse(html: &str) -> Document {
+        let parser = HtmlParser::new();
+        let data = Bytes::from(html.to_string());
+        let context = ParseContext {
+            format: parser.format(),
+            filename: Some("page.html".to_string()),
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        parser.parse(data, context).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_structure_and_styles() {
+        let html = r#"<!DOCTYPE html>
+<html lang="en"><head>
+  <meta name="author" content="Ada">
+  <style>
+    /* Brand colours */
+    @media print { p { color: black; } }
+    .note { color: #c00; font-weight: bold }
+    p.small { font-size: 8pt; }
+    .hidden { display: none }
+  </style>
+</head><body>
+  <h1>Report</h1>
+  <p>Plain <em>and   emphasised</em> text.</p>
+  <p class="note">Careful</p>
+  <p class="small">Fine <span style="font-size: 200%">print</span></p>
+  <p class="hidden">Not shown</p>
+  <ul><li>One</li><li><p>Two</p><ol><li>Nested</li></ol></li></ul>
+  <pre>let x = 1;
+let y = 2;
+</pre>
+  <p>See <a href="https://example.com">the site</a>.</p>
+</body></html>"#;
+        let document = parse(html).await;
+
+        assert_eq!(document.metadata.title.as_deref(), Some("Report"));
+        assert_eq!(document.metadata.author.as_deref(), Some("Ada"));
+        assert_eq!(document.metadata.language.as_deref(), Some("en"));
+        assert_eq!(document.structure.headings.len(), 1);
+
+        let blocks: Vec<_> = document.pages[0]
+            .content
+            .iter()
+            .flat_map(|block| match block {
+                ContentBlock::List(list) => list
+                    .items
+                    .iter()
+                    .flat_map(|item| item.content.iter())
+                    .collect(),
+                block => vec![block],
+            })
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        let texts: Vec<_> = blocks
+            .iter()
+            .map(|block| (block.paragraph_style.as_deref(), block.extract_text()))
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (Some("Heading 1"), "Report".to_string()),
+                (None, "Plain and emphasised text.".to_string()),
+                (None, "Careful".to_string()),
+                (None, "Fine print".to_string()),
+                (Some("List Bullet"), "One".to_string()),
+                (Some("List Bullet"), "Two".to_string()),
+                (Some("List Number 2"), "Nested".to_string()),
+                (Some("Code"), "let x = 1;\nlet y = 2;".to_string()),
+                (None, "See the site.".to_string()),
+            ]
+        );
+
+        assert!(blocks[1].runs[1].style.italic);
+        let note = &blocks[2].runs[0].style;
+        assert!(note.bold);
+        assert_eq!(note.color.as_deref(), Some("#c00"));
+        assert_eq!(blocks[3].runs[0].style.font_size, Some(8.0));
+        assert_eq!(blocks[3].runs[1].style.font_size, Some(16.0));
+        assert_eq!(
+            blocks[7].runs[0].style.font_family.as_deref(),
+            Some("monospace")
+        );
+
+        let annotations = &document.pages[0].annotations;
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].content.as_deref(), Some("the site"));
+        assert_eq!(blocks[8].runs[0].link, None);
+        assert_eq!(
+            blocks[8].runs[1].link,
+            Some(Link::Url("https://example.com".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_lists() {
+        let html = r#"<ol type="i" start="3"><li>Third<ul><li>Bullet</li></ul></li><li>Fourth</li></ol>
+            <li>Stray</li>"#;
+        let document = parse(html).await;
+        let lists: Vec<_> = document.pages[0]
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::List(list) => Some(list),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lists.len(), 2);
+
+        let items: Vec<_> = lists[0]
+            .items
+            .iter()
+            .map(|item| (item.level, item.marker_style, item.marker.as_str()))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                (0, ListMarker::LowerRoman, "iii."),
+                (1, ListMarker::Bullet, "•"),
+                (0, ListMarker::LowerRoman, "iv."),
+            ]
+        );
+        assert_eq!(lists[0].items[0].extract_text(), "Third");
+        assert_eq!(lists[1].extract_text(), "• Stray");
+    }
+
+    #[tokio::test]
+    async fn test_parse_tables_and_images() {
+        let html = r##"<html><body>
+<table>
+  <thead><tr><th colspan="2">Name</th><th>Qty</th></tr></thead>
+  <tbody>
+    <tr><td rowspan="2">Tea</td><td>Green</td><td bgcolor="#eee">3</td></tr>
+    <tr><td>Black</td><td><img src="data:image/png;base64,iVBORw==" alt="chart"></td></tr>
+  </tbody>
+</table>
+<p>Logo: <img src="https://example.com/logo.png" alt="Logo" width="100" height="50"></p>
+</body></html>"##;
+        let document = parse(html).await;
+        let content = &document.pages[0].content;
+
+        let ContentBlock::Table(table) = &content[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.column_count, 3);
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[0].cells[0].col_span, 2);
+        assert_eq!(table.rows[1].cells[0].row_span, 2);
+        assert_eq!(
+            table.rows[1].cells[2].background_color.as_deref(),
+            Some("#eee")
+        );
+        let ContentBlock::Text(header) = &table.rows[0].cells[0].content[0] else {
+            panic!("expected header text");
+        };
+        assert!(header.runs[0].style.bold);
+        assert!(matches!(
+            table.rows[2].cells[1].content[0],
+            ContentBlock::Image(_)
+        ));
+
+        let ContentBlock::Image(logo) = &content[2] else {
+            panic!("expected an image");
+        };
+        assert_eq!(logo.alt_text.as_deref(), Some("Logo"));
+        assert_eq!(logo.original_size.map(|size| size.width), Some(75.0));
+
+        let images = &document.resources.images;
+        assert_eq!(images.len(), 2);
+        assert!(images[0].data.is_some());
+        assert_eq!(
+            images[1].url.as_deref(),
+            Some("https://example.com/logo.png")
+        );
+    }
+
+    #[test]
+    fn test_parse_stylesheet() {
+        let rules = parse_stylesheet(
+            "@import url(a.css); h1, h2 { color: red; margin: 0 } @font-face { src: x } \
+             p > b { font-weight: 700 !important }",
+        );
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].1, [("font-weight".to_string(), "700".to_string())]);
+        assert_eq!(font_size("12px", 12.0), Some(9.0));
+        assert_eq!(font_size("1.5em", 10.0), Some(15.0));
+        assert_eq!(font_size("large", 12.0), Some(13.5));
+    }
 }
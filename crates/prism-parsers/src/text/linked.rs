@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Images referenced from text documents
+//!
+//! Markdown, HTML and vCard refer to images by URL. `data:` URIs are decoded,
+//! relative paths are read from [`ParseOptions::resource_dir`] when it is
+//! set, and any other reference is kept as a URL without fetching it.
+//!
+//! [`ParseOptions::resource_dir`]: prism_core::parser::ParseOptions::resource_dir
+
+use base64::Engine as _;
+use image::ImageReader;
+use prism_core::document::ImageResource;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Image resources of a document, one per distinct URL
+#[derive(Debug, Default)]
+pub(crate) struct LinkedImages {
+    resource_dir: Option<PathBuf>,
+    images: Vec<ImageResource>,
+    ids: HashMap<String, String>,
+}
+
+impl LinkedImages {
+    pub(crate) fn new(resource_dir: Option<PathBuf>) -> Self {
+        Self {
+            resource_dir,
+            ..Self::default()
+        }
+    }
+
+    /// ID of the resource for the image at `url`, added on first use
+    pub(crate) fn resolve(&mut self, url: &str) -> String {
+        if let Some(id) = self.ids.get(url) {
+            return id.clone();
+        }
+
+        let data = decode_data_uri(url).or_else(|| {
+            self.resource_dir
+                .as_deref()
+                .filter(|_| is_relative_reference(url))
+                .and_then(|dir| read_local(dir, url))
+        });
+        let id = self.push(data, url);
+        self.ids.insert(url.to_string(), id.clone());
+        id
+    }
+
+    /// ID of a new resource for image data embedded in the document
+    pub(crate) fn embed(&mut self, data: Vec<u8>) -> String {
+        self.push(Some(data), "")
+    }
+
+    /// Add a resource for `data`, or for a reference to `url` when the
+    /// data could not be read
+    fn push(&mut self, data: Option<Vec<u8>>, url: &str) -> String {
+        let id = format!("image{}", self.images.len() + 1);
+        let resource = match data {
+            Some(data) => {
+                let mime_type = image::guess_format(&data).map_or_else(
+                    |_| {
+                        if Path::new(url)
+                            .extension()
+                            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+                            || url.starts_with("data:image/svg+xml")
+                        {
+                            "image/svg+xml".to_string()
+                        } else {
+                            "application/octet-stream".to_string()
+                        }
+                    },
+                    |format| format.to_mime_type().to_string(),
+                );
+                let (width, height) = ImageReader::new(Cursor::new(&data))
+                    .with_guessed_format()
+                    .ok()
+                    .and_then(|reader| reader.into_dimensions().ok())
+                    .unwrap_or((0, 0));
+                ImageResource {
+                    id: id.clone(),
+                    mime_type,
+                    data: Some(data),
+                    url: None,
+                    width,
+                    height,
+                }
+            }
+            None => ImageResource {
+                id: id.clone(),
+                mime_type: "application/octet-stream".to_string(),
+                data: None,
+                url: Some(url.to_string()),
+                width: 0,
+                height: 0,
+            },
+        };
+        self.images.push(resource);
+        id
+    }
+
+    pub(crate) fn into_resources(self) -> Vec<ImageResource> {
+        self.images
+    }
+}
+
+/// Content of a base64 `data:` URI
+fn decode_data_uri(url: &str) -> Option<Vec<u8>> {
+    let (header, payload) = url.strip_prefix("data:")?.split_once(',')?;
+    header.ends_with(";base64").then_some(())?;
+    base64::engine::general_purpose::STANDARD
+        .decode(payload.trim())
+        .ok()
+}
+
+/// Whether `url` refers to a file next to the document
+fn is_relative_reference(url: &str) -> bool {
+    !url.contains("://") && !url.starts_with("data:") && !url.starts_with("mailto:")
+}
+
+/// Read `reference` relative to `dir`, refusing paths that leave it
+fn read_local(dir: &Path, reference: &str) -> Option<Vec<u8>> {
+    let reference = reference.split(['?', '#']).next()?.trim_start_matches('/');
+    let dir = dir.canonicalize().ok()?;
+    let path = dir.join(reference).canonicalize().ok()?;
+    if !path.starts_with(&dir) {
+        debug!(
+            "Not reading {} outside the resource directory",
+            path.display()
+        );
+        return None;
+    }
+    std::fs::read(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_data_uri() {
+        assert_eq!(
+            decode_data_uri("data:image/png;base64,iVBORw=="),
+            Some(vec![0x89, 0x50, 0x4E, 0x47])
+        );
+        assert_eq!(decode_data_uri("data:text/plain,hello"), None);
+        assert_eq!(decode_data_uri("image.png"), None);
+    }
+}
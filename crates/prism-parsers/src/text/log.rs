@@ -0,0 +1,589 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Log file parser
+//!
+//! Splits a log into entries and reads the timestamp and severity of each.
+//! Recognized line formats:
+//!
+//! - JSON lines, with the usual `time`/`timestamp`, `level`/`severity` and
+//!   `msg`/`message` fields
+//! - logfmt (`time=... level=warn msg="..."`)
+//! - syslog (RFC 5424 and RFC 3164), the severity coming from the priority
+//! - any other line starting with an ISO 8601, `YYYY/MM/DD` or syslog-style
+//!   timestamp or holding an Apache-style `[31/Jan/2024:13:30:00 +0000]`,
+//!   with an upper-case or bracketed level name (`ERROR`, `[warn]`)
+//!
+//! Timestamps without an offset are taken as UTC, and syslog timestamps
+//! without a year as the current year. A line with neither a timestamp nor
+//! a level, such as a stack trace frame, continues the previous entry.
+//!
+//! Entries are filtered with [`ParseOptions::log_filter`] and shown one
+//! block per entry, in a monospace font colored by severity. The counts per
+//! level are stored in the metadata, taken before filtering.
+//!
+//! [`ParseOptions::log_filter`]: prism_core::parser::ParseOptions::log_filter
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use prism_core::{
+    document::{
+        ContentBlock, Document, Rect, ShapeStyle, TextBlock, TextDirection, TextRun, TextStyle,
+    },
+    error::Result,
+    format::Format,
+    parser::{LogLevel, ParseContext, Parser, ParserFeature, ParserMetadata},
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::text::encoding;
+use crate::text::plain::TextParser;
+
+/// Timestamp formats with an offset
+const ZONED_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%#z",
+    "%Y-%m-%d %H:%M:%S%.f%#z",
+    "%d/%b/%Y:%H:%M:%S %z",
+];
+
+/// Timestamp formats without an offset
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+];
+
+/// Field names of the timestamp in JSON and logfmt lines
+const TIME_KEYS: &[&str] = &["timestamp", "time", "ts", "@timestamp", "datetime", "date"];
+
+/// Field names of the severity in JSON and logfmt lines
+const LEVEL_KEYS: &[&str] = &["level", "lvl", "severity", "loglevel", "log.level"];
+
+/// Log file parser
+#[derive(Debug, Clone)]
+pub struct LogParser;
+
+impl LogParser {
+    /// Create a new log parser
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for LogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for LogParser {
+    fn format(&self) -> Format {
+        Format::log()
+    }
+
+    fn can_parse(&self, data: &[u8]) -> bool {
+        TextParser::is_likely_text(data)
+    }
+
+    async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
+        let filter = context.options.log_filter.clone();
+        let text = encoding::decode(&data).map_or_else(
+            || String::from_utf8_lossy(&data).into_owned(),
+            |decoded| decoded.text,
+        );
+        let mut document = TextParser::new().parse(data, context).await?;
+
+        let entries = entries(&text);
+        let mut counts: HashMap<LogLevel, i64> = HashMap::new();
+        let mut formats: HashMap<LineFormat, usize> = HashMap::new();
+        for entry in &entries {
+            if let Some(level) = entry.level {
+                *counts.entry(level).or_default() += 1;
+            }
+            *formats.entry(entry.format).or_default() += 1;
+        }
+        let first = entries.iter().filter_map(|entry| entry.timestamp).min();
+        let last = entries.iter().filter_map(|entry| entry.timestamp).max();
+
+        let kept: Vec<&Entry> = entries
+            .iter()
+            .filter(|entry| filter.accepts(entry.timestamp, entry.level))
+            .collect();
+        debug!("Kept {} of {} log entries", kept.len(), entries.len());
+        if let Some(page) = document.pages.first_mut() {
+            page.content = kept.iter().map(|entry| entry.block()).collect();
+        }
+
+        let metadata = &mut document.metadata;
+        metadata.add_custom(
+            "log_entry_count",
+            i64::try_from(entries.len()).unwrap_or(i64::MAX),
+        );
+        metadata.add_custom(
+            "log_entries_shown",
+            i64::try_from(kept.len()).unwrap_or(i64::MAX),
+        );
+        for level in LogLevel::ALL {
+            if let Some(&count) = counts.get(&level) {
+                metadata.add_custom(format!("log_{level}_count"), count);
+            }
+        }
+        if let Some((format, _)) = formats
+            .into_iter()
+            .max_by_key(|(format, count)| (*count, *format))
+        {
+            metadata.add_custom("log_format", format.as_str());
+        }
+        if let Some(first) = first {
+            metadata.add_custom("log_first_timestamp", first);
+        }
+        if let Some(last) = last {
+            metadata.add_custom("log_last_timestamp", last);
+        }
+        Ok(document)
+    }
+
+    fn metadata(&self) -> ParserMetadata {
+        ParserMetadata {
+            name: "Log Parser".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: vec![
+                ParserFeature::TextExtraction,
+                ParserFeature::MetadataExtraction,
+            ],
+            requires_sandbox: false,
+        }
+    }
+}
+
+/// Layout of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum LineFormat {
+    Text,
+    Logfmt,
+    Syslog,
+    Json,
+}
+
+impl LineFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Logfmt => "logfmt",
+            Self::Syslog => "syslog",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Format, timestamp and level read from a line
+type LineInfo = (LineFormat, Option<DateTime<Utc>>, Option<LogLevel>);
+
+/// A log entry: its first line and any continuation lines
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    format: LineFormat,
+    timestamp: Option<DateTime<Utc>>,
+    level: Option<LogLevel>,
+    text: String,
+}
+
+impl Entry {
+    fn block(&self) -> ContentBlock {
+        let style = TextStyle {
+            font_family: Some("monospace".into()),
+            color: self.level.and_then(level_color).map(Into::into),
+            bold: self.level == Some(LogLevel::Fatal),
+            ..TextStyle::default()
+        };
+        ContentBlock::Text(TextBlock {
+            bounds: Rect::default(),
+            runs: vec![TextRun {
+                text: self.text.as_str().into(),
+                style,
+                bounds: None,
+                char_positions: None,
+                link: None,
+                revision: None,
+            }],
+            paragraph_style: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            direction: TextDirection::Auto,
+        })
+    }
+}
+
+/// Color of entries of a level; informational entries keep the default
+fn level_color(level: LogLevel) -> Option<&'static str> {
+    match level {
+        LogLevel::Trace => Some("#9e9e9e"),
+        LogLevel::Debug => Some("#616161"),
+        LogLevel::Info => None,
+        LogLevel::Warn => Some("#e65100"),
+        LogLevel::Error => Some("#c62828"),
+        LogLevel::Fatal => Some("#7f0000"),
+    }
+}
+
+/// Split a log into entries
+fn entries(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some((format, timestamp, level)) => entries.push(Entry {
+                format,
+                timestamp,
+                level,
+                text: line.to_string(),
+            }),
+            None => match entries.last_mut() {
+                Some(entry) => {
+                    entry.text.push('\n');
+                    entry.text.push_str(line);
+                }
+                None => entries.push(Entry {
+                    format: LineFormat::Text,
+                    timestamp: None,
+                    level: None,
+                    text: line.to_string(),
+                }),
+            },
+        }
+    }
+    entries
+}
+
+/// Format, timestamp and level of a line that starts an entry
+fn parse_line(line: &str) -> Option<LineInfo> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('{') {
+        if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(trimmed) {
+            let (timestamp, level) = json_fields(&fields);
+            return Some((LineFormat::Json, timestamp, level));
+        }
+    }
+    if let Some((timestamp, level)) = syslog(line) {
+        return Some((LineFormat::Syslog, timestamp, level));
+    }
+    if let Some((timestamp, level)) = logfmt(line) {
+        return Some((LineFormat::Logfmt, timestamp, level));
+    }
+
+    // Indented lines continue the entry above
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let timestamp = leading_timestamp(line)
+        .map(|(timestamp, _)| timestamp)
+        .or_else(|| bracketed_timestamp(line));
+    let level = text_level(line);
+    (timestamp.is_some() || level.is_some()).then_some((LineFormat::Text, timestamp, level))
+}
+
+fn json_fields(fields: &Map<String, Value>) -> (Option<DateTime<Utc>>, Option<LogLevel>) {
+    let timestamp =
+        TIME_KEYS
+            .iter()
+            .find_map(|key| fields.get(*key))
+            .and_then(|value| match value {
+                Value::String(text) => full_timestamp(text),
+                Value::Number(number) => number.as_f64().and_then(epoch_timestamp),
+                _ => None,
+            });
+    let level = LEVEL_KEYS
+        .iter()
+        .find_map(|key| fields.get(*key))
+        .and_then(|value| match value {
+            Value::String(text) => text.parse().ok(),
+            // Numeric levels as written by pino and bunyan
+            Value::Number(number) => number.as_u64().and_then(|n| match n {
+                0..=10 => Some(LogLevel::Trace),
+                11..=20 => Some(LogLevel::Debug),
+                21..=30 => Some(LogLevel::Info),
+                31..=40 => Some(LogLevel::Warn),
+                41..=50 => Some(LogLevel::Error),
+                51..=60 => Some(LogLevel::Fatal),
+                _ => None,
+            }),
+            _ => None,
+        });
+    (timestamp, level)
+}
+
+/// A syslog line, starting with `<priority>`
+fn syslog(line: &str) -> Option<(Option<DateTime<Utc>>, Option<LogLevel>)> {
+    let (priority, rest) = line.strip_prefix('<')?.split_once('>')?;
+    let priority: u16 = priority.parse().ok()?;
+    let level = match priority % 8 {
+        0..=2 => LogLevel::Fatal,
+        3 => LogLevel::Error,
+        4 => LogLevel::Warn,
+        5 | 6 => LogLevel::Info,
+        _ => LogLevel::Debug,
+    };
+    // RFC 5424 lines carry a version and an RFC 3339 timestamp
+    let timestamp = match rest.strip_prefix("1 ") {
+        Some(rest) => rest.split_whitespace().next().and_then(full_timestamp),
+        None => leading_timestamp(rest).map(|(timestamp, _)| timestamp),
+    };
+    Some((timestamp, Some(level)))
+}
+
+/// A logfmt line: at least two `key=value` pairs, one of them naming the
+/// time, level or message
+fn logfmt(line: &str) -> Option<(Option<DateTime<Utc>>, Option<LogLevel>)> {
+    let pairs = logfmt_pairs(line);
+    let known = |key: &str| {
+        TIME_KEYS.contains(&key) || LEVEL_KEYS.contains(&key) || key == "msg" || key == "message"
+    };
+    if pairs.len() < 2 || !pairs.iter().any(|(key, _)| known(key)) {
+        return None;
+    }
+    let value = |keys: &[&str]| {
+        pairs
+            .iter()
+            .find(|(key, _)| keys.contains(&key.as_str()))
+            .map(|(_, value)| value.as_str())
+    };
+    let timestamp = value(TIME_KEYS).and_then(full_timestamp);
+    let level = value(LEVEL_KEYS).and_then(|level| level.parse().ok());
+    Some((timestamp, level))
+}
+
+fn logfmt_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            key.push(c);
+        }
+        // Every token of a logfmt line is a pair
+        if key.is_empty() || chars.next_if_eq(&'=').is_none() {
+            return Vec::new();
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        pairs.push((key, value));
+    }
+    pairs
+}
+
+/// A timestamp that makes up all of `text`
+fn full_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    leading_timestamp(text)
+        .filter(|(_, rest)| rest.trim().is_empty())
+        .map(|(timestamp, _)| timestamp)
+        .or_else(|| text.trim().parse::<f64>().ok().and_then(epoch_timestamp))
+}
+
+/// Seconds, or milliseconds for large values, since the Unix epoch
+fn epoch_timestamp(value: f64) -> Option<DateTime<Utc>> {
+    let millis = if value.abs() > 1e11 {
+        value
+    } else {
+        value * 1000.0
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    Utc.timestamp_millis_opt(millis as i64).single()
+}
+
+/// A timestamp at the start of `text` and the text after it
+fn leading_timestamp(text: &str) -> Option<(DateTime<Utc>, &str)> {
+    let text = text.trim_start().trim_start_matches('[');
+    for format in ZONED_FORMATS {
+        if let Ok((timestamp, rest)) = DateTime::parse_and_remainder(text, format) {
+            return Some((timestamp.with_timezone(&Utc), rest));
+        }
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok((timestamp, rest)) = NaiveDateTime::parse_and_remainder(text, format) {
+            return Some((timestamp.and_utc(), rest));
+        }
+    }
+
+    // Syslog timestamps (`Jan 31 13:30:00`) have no year
+    let year = Utc::now().year();
+    let dated = format!("{year} {text}");
+    let (timestamp, rest) = NaiveDateTime::parse_and_remainder(&dated, "%Y %b %e %H:%M:%S").ok()?;
+    Some((timestamp.and_utc(), &text[text.len() - rest.len()..]))
+}
+
+/// An Apache-style `[31/Jan/2024:13:30:00 +0000]` timestamp anywhere in
+/// the line
+fn bracketed_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    line.match_indices('[').find_map(|(start, _)| {
+        DateTime::parse_and_remainder(&line[start + 1..], "%d/%b/%Y:%H:%M:%S %z")
+            .ok()
+            .filter(|(_, rest)| rest.starts_with(']'))
+            .map(|(timestamp, _)| timestamp.with_timezone(&Utc))
+    })
+}
+
+/// The first level name written in capitals or in brackets
+fn text_level(line: &str) -> Option<LogLevel> {
+    line.split(|c: char| !c.is_ascii_alphabetic() && c != '[' && c != ']')
+        .find_map(|word| {
+            let bracketed = word
+                .strip_prefix('[')
+                .and_then(|word| word.strip_suffix(']'));
+            match bracketed {
+                Some(name) => name.parse().ok(),
+                None if word.len() > 2 && word.chars().all(|c| c.is_ascii_uppercase()) => {
+                    word.parse().ok()
+                }
+                None => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::metadata::MetadataValue;
+    use prism_core::parser::{LogFilter, ParseOptions};
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let cases = [
+            (
+                r#"{"time":"2024-01-31T13:30:00Z","level":"warn","msg":"slow"}"#,
+                LineFormat::Json,
+                Some(at("2024-01-31T13:30:00Z")),
+                Some(LogLevel::Warn),
+            ),
+            (
+                r#"ts=2024-01-31T13:30:00+01:00 level=error msg="disk full""#,
+                LineFormat::Logfmt,
+                Some(at("2024-01-31T12:30:00Z")),
+                Some(LogLevel::Error),
+            ),
+            (
+                "<11>1 2024-01-31T13:30:00.5Z host app 42 - - failed",
+                LineFormat::Syslog,
+                Some(at("2024-01-31T13:30:00.5Z")),
+                Some(LogLevel::Error),
+            ),
+            (
+                "2024-01-31 13:30:00,123 INFO [main] Started",
+                LineFormat::Text,
+                Some(at("2024-01-31T13:30:00Z")),
+                Some(LogLevel::Info),
+            ),
+            (
+                r#"10.0.0.1 - - [31/Jan/2024:13:30:00 +0000] "GET / HTTP/1.1" 200"#,
+                LineFormat::Text,
+                Some(at("2024-01-31T13:30:00Z")),
+                None,
+            ),
+            (
+                "[warn] cache miss",
+                LineFormat::Text,
+                None,
+                Some(LogLevel::Warn),
+            ),
+        ];
+        for (line, format, timestamp, level) in cases {
+            assert_eq!(parse_line(line), Some((format, timestamp, level)), "{line}");
+        }
+        assert_eq!(
+            parse_line("    at com.example.Main.run(Main.java:10)"),
+            None
+        );
+        assert_eq!(parse_line("an error happened"), None);
+    }
+
+    #[tokio::test]
+    async fn test_parse_and_filter() {
+        let log = "2024-01-31T09:00:00Z INFO starting\n\
+            2024-01-31T10:00:00Z ERROR request failed\n\
+            java.lang.IllegalStateException: closed\n\
+            \tat Main.run(Main.java:10)\n\
+            2024-01-31T11:00:00Z WARN retrying\n\
+            2024-01-31T12:00:00Z ERROR gave up\n";
+        let parser = LogParser::new();
+        let parse = |log_filter| {
+            let data = Bytes::from(log);
+            let context = ParseContext {
+                format: parser.format(),
+                filename: Some("app.log".to_string()),
+                size: data.len(),
+                options: ParseOptions {
+                    log_filter,
+                    ..ParseOptions::default()
+                },
+                progress: None,
+            };
+            parser.parse(data, context)
+        };
+
+        let document = parse(LogFilter::default()).await.unwrap();
+        assert_eq!(document.pages[0].content.len(), 4);
+        let ContentBlock::Text(failure) = &document.pages[0].content[1] else {
+            panic!("expected text");
+        };
+        assert_eq!(failure.runs[0].text.lines().count(), 3);
+        assert_eq!(failure.runs[0].style.color.as_deref(), Some("#c62828"));
+        let custom = |key: &str| document.metadata.custom.get(key).cloned();
+        assert!(matches!(
+            custom("log_error_count"),
+            Some(MetadataValue::Integer(2))
+        ));
+        assert!(matches!(
+            custom("log_warn_count"),
+            Some(MetadataValue::Integer(1))
+        ));
+        assert!(custom("log_debug_count").is_none());
+        assert!(matches!(custom("log_format"), Some(MetadataValue::String(f)) if f == "text"));
+
+        let document = parse(LogFilter {
+            since: Some(at("2024-01-31T10:30:00Z")),
+            until: None,
+            min_level: Some(LogLevel::Warn),
+        })
+        .await
+        .unwrap();
+        let shown: Vec<String> = document.pages[0]
+            .content
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text(text) => text.extract_text(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                "2024-01-31T11:00:00Z WARN retrying",
+                "2024-01-31T12:00:00Z ERROR gave up"
+            ]
+        );
+    }
+}
@@ -0,0 +1,755 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Markdown parser
+//!
+//! Parses `CommonMark`, with the GitHub extensions for tables, strikethrough
+//! and task lists, into a single flowing page:
+//!
+//! - Headings are text blocks styled `Heading 1` to `Heading 6` and are
+//!   listed in [`DocumentStructure::headings`](prism_core::document::DocumentStructure);
+//!   the first level-one heading is the document title. Each heading
+//!   starts a chapter section.
+//! - Lists become list blocks, with nested lists flattened into deeper
+//!   items. Item paragraphs are styled `List Bullet` or `List Number`, with
+//!   the level appended in nested lists (`List Bullet 2`); task list items
+//!   show a ballot box.
+//! - Code blocks are styled `Code`; they and inline code are set in a
+//!   monospace font. Block quotes are styled `Quote`.
+//! - Tables become table blocks with the header row in bold.
+//! - Links are underlined, set as the link of their runs and recorded as
+//!   link annotations.
+//! - Images become image blocks. `data:` URIs are decoded, relative paths
+//!   are read from [`ParseOptions::resource_dir`] when it is set, and any
+//!   other reference is kept as a URL.
+//!
+//! Raw HTML and front matter are left out.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::{
+    document::{
+        Annotation, AnnotationType, ContentBlock, Dimensions, Document, Heading, ImageBlock, Link,
+        ListBlock, ListItem, ListMarker, Page, PageMetadata, Rect, Section, ShapeStyle, TableBlock,
+        TableCell, TableRow, TextBlock, TextDirection, TextRun, TextStyle,
+    },
+    error::{Error, Result},
+    format::Format,
+    metadata::Metadata,
+    parser::{ParseContext, ParseOptions, Parser, ParserFeature, ParserMetadata},
+};
+use pulldown_cmark::{Event, HeadingLevel, Options, Tag, TagEnd};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::text::encoding;
+use crate::text::linked::LinkedImages;
+use crate::text::plain::TextParser;
+
+/// Font family of code
+const MONOSPACE: &str = "monospace";
+
+/// Markdown file parser
+#[derive(Debug, Clone)]
+pub struct MarkdownParser;
+
+impl MarkdownParser {
+    /// Create a new Markdown parser
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MarkdownParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for MarkdownParser {
+    fn format(&self) -> Format {
+        Format::markdown()
+    }
+
+    fn can_parse(&self, data: &[u8]) -> bool {
+        TextParser::is_likely_text(data)
+    }
+
+    async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
+        debug!(
+            "Parsing Markdown file, size: {} bytes, filename: {:?}",
+            context.size, context.filename
+        );
+
+        let text = encoding::decode(&data)
+            .ok_or_else(|| Error::ParseError("Not text in a known encoding".to_string()))?
+            .text;
+        let mut converter = Converter::new(&context.options);
+        converter.convert(&text);
+
+        let metadata = Metadata {
+            title: converter.title.take().or(context.filename),
+            ..Metadata::default()
+        };
+
+        let mut document = Document::builder().metadata(metadata).build();
+        document.pages = vec![Page {
+            number: 1,
+            dimensions: Dimensions::LETTER,
+            content: converter.content,
+            annotations: converter.annotations,
+            metadata: PageMetadata::default(),
+        }];
+        document.structure.headings = converter.headings;
+        document.structure.sections = Section::from_headings(&document.pages);
+        document.resources.images = converter.images.into_resources();
+        Ok(document)
+    }
+
+    fn metadata(&self) -> ParserMetadata {
+        ParserMetadata {
+            name: "Markdown Parser".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: vec![
+                ParserFeature::TextExtraction,
+                ParserFeature::TableExtraction,
+                ParserFeature::ImageExtraction,
+                ParserFeature::MetadataExtraction,
+            ],
+            requires_sandbox: false,
+        }
+    }
+}
+
+/// Inline formatting in effect for a piece of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+struct Inline {
+    bold: bool,
+    italic: bool,
+    strikethrough: bool,
+    underline: bool,
+    code: bool,
+}
+
+impl Inline {
+    fn text_style(self) -> TextStyle {
+        TextStyle {
+            bold: self.bold,
+            italic: self.italic,
+            strikethrough: self.strikethrough,
+            underline: self.underline,
+            font_family: self.code.then(|| MONOSPACE.into()),
+            ..TextStyle::default()
+        }
+    }
+}
+
+/// Text of a paragraph or table cell being collected
+#[derive(Debug, Default)]
+struct Runs(Vec<(Inline, Option<Link>, String)>);
+
+impl Runs {
+    fn push(&mut self, inline: Inline, link: Option<Link>, text: &str) {
+        match self.0.last_mut() {
+            Some((last, last_link, existing)) if *last == inline && *last_link == link => {
+                existing.push_str(text);
+            }
+            _ => self.0.push((inline, link, text.to_string())),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.0.iter().map(|(_, _, text)| text.as_str()).collect()
+    }
+
+    fn into_block(self, paragraph_style: Option<String>) -> Option<TextBlock> {
+        if self.0.iter().all(|(_, _, text)| text.trim().is_empty()) {
+            return None;
+        }
+        Some(TextBlock {
+            bounds: Rect::default(),
+            runs: self
+                .0
+                .into_iter()
+                .map(|(inline, link, text)| TextRun {
+                    text: text.into(),
+                    style: inline.text_style(),
+                    bounds: None,
+                    char_positions: None,
+                    link,
+                    revision: None,
+                })
+                .collect(),
+            paragraph_style,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            direction: TextDirection::Auto,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct TableState {
+    column_count: usize,
+    rows: Vec<TableRow>,
+    cells: Vec<TableCell>,
+    runs: Runs,
+    in_head: bool,
+}
+
+/// Walks the Markdown events and builds the page content
+struct Converter {
+    content: Vec<ContentBlock>,
+    annotations: Vec<Annotation>,
+    headings: Vec<Heading>,
+    images: LinkedImages,
+    title: Option<String>,
+
+    /// Paragraph being collected and its style
+    block: Option<(Runs, Option<String>)>,
+    inline: Inline,
+    in_code_block: bool,
+    in_metadata: bool,
+    quote_depth: usize,
+    /// Open lists, with the next number of ordered ones
+    lists: Vec<Option<u64>>,
+    /// The outermost open list, with nested lists' items flattened into it
+    list: Option<ListBlock>,
+    /// Indices in `list` of the open items, innermost last
+    items: Vec<usize>,
+    /// Destination and text of the open link
+    link: Option<(String, String)>,
+    /// Destination and alternative text of the open image
+    image: Option<(String, String)>,
+    table: Option<TableState>,
+}
+
+impl Converter {
+    fn new(options: &ParseOptions) -> Self {
+        Self {
+            content: Vec::new(),
+            annotations: Vec::new(),
+            headings: Vec::new(),
+            images: LinkedImages::new(options.resource_dir.clone()),
+            title: None,
+            block: None,
+            inline: Inline::default(),
+            in_code_block: false,
+            in_metadata: false,
+            quote_depth: 0,
+            lists: Vec::new(),
+            list: None,
+            items: Vec::new(),
+            link: None,
+            image: None,
+            table: None,
+        }
+    }
+
+    fn convert(&mut self, text: &str) {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+        for event in pulldown_cmark::Parser::new_ext(text, options) {
+            match event {
+                Event::Start(tag) => self.start(tag),
+                Event::End(tag) => self.end(tag),
+                Event::Text(text) if self.in_metadata => drop(text),
+                Event::Text(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                    self.text(&text);
+                }
+                Event::Code(code) => {
+                    let inline = self.inline;
+                    self.inline.code = true;
+                    self.text(&code);
+                    self.inline = inline;
+                }
+                Event::FootnoteReference(label) => self.text(&format!("[^{label}]")),
+                Event::SoftBreak => self.text(" "),
+                Event::HardBreak => self.text("\n"),
+                Event::TaskListMarker(checked) => self.text(if checked { "☑ " } else { "☐ " }),
+                Event::Rule => self.flush(),
+                Event::Html(_) | Event::InlineHtml(_) => {}
+            }
+        }
+        self.flush();
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph if self.block.is_none() && self.table.is_none() => {
+                self.begin(self.context_style());
+            }
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.begin(Some(format!("Heading {}", heading_level(level))));
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush();
+                self.in_code_block = true;
+                self.inline.code = true;
+                self.begin(Some("Code".to_string()));
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+                self.list
+                    .get_or_insert_with(|| ListBlock::new(Rect::default()));
+            }
+            Tag::Item => {
+                self.flush();
+                let level = self.lists.len();
+                let depth = u8::try_from(level - 1).unwrap_or(u8::MAX);
+                let (kind, item) = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let label = u32::try_from(*number).unwrap_or(u32::MAX);
+                        *number += 1;
+                        let item =
+                            ListItem::numbered(Vec::new(), depth, ListMarker::Decimal, label);
+                        ("Number", item)
+                    }
+                    _ => ("Bullet", ListItem::bullet(Vec::new(), depth)),
+                };
+                if let Some(list) = &mut self.list {
+                    self.items.push(list.items.len());
+                    list.add_item(item);
+                }
+                let style = if level > 1 {
+                    format!("List {kind} {level}")
+                } else {
+                    format!("List {kind}")
+                };
+                self.begin(Some(style));
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.table = Some(TableState {
+                    column_count: alignments.len(),
+                    ..TableState::default()
+                });
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.in_head = true;
+                }
+                self.inline.bold = true;
+            }
+            Tag::Emphasis => self.inline.italic = true,
+            Tag::Strong => self.inline.bold = true,
+            Tag::Strikethrough => self.inline.strikethrough = true,
+            Tag::Link { dest_url, .. } => {
+                self.link = Some((dest_url.to_string(), String::new()));
+                self.inline.underline = true;
+            }
+            Tag::Image { dest_url, .. } => self.image = Some((dest_url.to_string(), String::new())),
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush(),
+            TagEnd::Item => {
+                self.flush();
+                self.items.pop();
+            }
+            TagEnd::Heading(level) => {
+                if let Some((runs, _)) = &self.block {
+                    let text = runs.text().trim().to_string();
+                    if level == HeadingLevel::H1 && self.title.is_none() {
+                        self.title = Some(text.clone());
+                    }
+                    self.headings.push(Heading {
+                        text,
+                        level: heading_level(level),
+                        page: 1,
+                        bounds: None,
+                    });
+                }
+                self.flush();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                if let Some((runs, _)) = &mut self.block {
+                    if let Some((_, _, text)) = runs.0.last_mut() {
+                        let trimmed = text.trim_end_matches('\n').len();
+                        text.truncate(trimmed);
+                    }
+                }
+                self.flush();
+                self.in_code_block = false;
+                self.inline.code = false;
+            }
+            TagEnd::List(_) => self.end_list(),
+            TagEnd::TableHead => {
+                self.end_table_row();
+                if let Some(table) = &mut self.table {
+                    table.in_head = false;
+                }
+                self.inline.bold = false;
+            }
+            TagEnd::TableRow => self.end_table_row(),
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table {
+                    let runs = std::mem::take(&mut table.runs);
+                    table.cells.push(TableCell {
+                        content: runs
+                            .into_block(None)
+                            .map(ContentBlock::Text)
+                            .into_iter()
+                            .collect(),
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    });
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push(ContentBlock::Table(TableBlock {
+                        bounds: Rect::default(),
+                        rows: table.rows,
+                        column_count: table.column_count,
+                        style: ShapeStyle::default(),
+                        rotation: 0.0,
+                    }));
+                }
+            }
+            TagEnd::Emphasis => self.inline.italic = false,
+            // Table headers are bold as a whole
+            TagEnd::Strong => {
+                self.inline.bold = self.table.as_ref().is_some_and(|table| table.in_head);
+            }
+            TagEnd::Strikethrough => self.inline.strikethrough = false,
+            TagEnd::Link => {
+                self.inline.underline = false;
+                if let Some((url, text)) = self.link.take() {
+                    self.annotations.push(Annotation {
+                        id: Uuid::new_v4(),
+                        annotation_type: AnnotationType::Link { url },
+                        bounds: Rect::default(),
+                        content: Some(text),
+                        author: None,
+                        created: None,
+                        color: None,
+                    });
+                }
+            }
+            TagEnd::Image => {
+                if let Some((url, alt_text)) = self.image.take() {
+                    self.end_image(&url, alt_text);
+                }
+            }
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+            _ => {}
+        }
+    }
+
+    /// Add text to the open image, table cell or paragraph
+    fn text(&mut self, text: &str) {
+        if let Some((_, alt_text)) = &mut self.image {
+            alt_text.push_str(text);
+            return;
+        }
+        let link = self.link.as_mut().map(|(url, link_text)| {
+            link_text.push_str(text);
+            Link::from_href(url)
+        });
+        if let Some(table) = &mut self.table {
+            table.runs.push(self.inline, link, text);
+            return;
+        }
+        if self.block.is_none() {
+            self.begin(self.context_style());
+        }
+        if let Some((runs, _)) = &mut self.block {
+            runs.push(self.inline, link, text);
+        }
+    }
+
+    /// Style of a paragraph that is not a heading or list item
+    fn context_style(&self) -> Option<String> {
+        if self.in_code_block {
+            Some("Code".to_string())
+        } else if self.quote_depth > 0 {
+            Some("Quote".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn begin(&mut self, style: Option<String>) {
+        self.flush();
+        self.block = Some((Runs::default(), style));
+    }
+
+    /// Emit the open paragraph, if it has any text
+    fn flush(&mut self) {
+        if let Some((runs, style)) = self.block.take() {
+            if let Some(block) = runs.into_block(style) {
+                self.push(ContentBlock::Text(block));
+            }
+        }
+    }
+
+    /// Add a block to the open list item, or to the page
+    fn push(&mut self, block: ContentBlock) {
+        let item = self
+            .list
+            .as_mut()
+            .zip(self.items.last())
+            .and_then(|(list, &index)| list.items.get_mut(index));
+        match item {
+            Some(item) => item.content.push(block),
+            None => self.content.push(block),
+        }
+    }
+
+    /// Close a list; the block is emitted once the outermost list closes
+    fn end_list(&mut self) {
+        self.flush();
+        self.lists.pop();
+        if self.lists.is_empty() {
+            if let Some(list) = self.list.take() {
+                self.content.push(ContentBlock::List(list));
+            }
+        }
+    }
+
+    fn end_table_row(&mut self) {
+        if let Some(table) = &mut self.table {
+            let cells = std::mem::take(&mut table.cells);
+            if !cells.is_empty() {
+                table.rows.push(TableRow {
+                    cells,
+                    height: None,
+                });
+            }
+        }
+    }
+
+    /// Place an image between the text before and after it
+    fn end_image(&mut self, url: &str, alt_text: String) {
+        // Images in table cells are represented by their description
+        if self.table.is_some() {
+            self.text(&alt_text);
+            return;
+        }
+
+        let style = self.block.as_ref().map(|(_, style)| style.clone());
+        self.flush();
+        let resource_id = self.images.resolve(url);
+        self.push(ContentBlock::Image(ImageBlock {
+            bounds: Rect::default(),
+            resource_id,
+            alt_text: (!alt_text.is_empty()).then_some(alt_text),
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        if let Some(style) = style {
+            self.block = Some((Runs::default(), style));
+        }
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(markdown: &str, options: ParseOptions) -> Document {
+        let parser = MarkdownParser::new();
+        let data = Bytes::from(markdown.to_string());
+        let context = ParseContext {
+            format: parser.format(),
+            filename: Some("readme.md".to_string()),
+            size: data.len(),
+            options,
+            progress: None,
+        };
+        parser.parse(data, context).await.unwrap()
+    }
+
+    fn texts(document: &Document) -> Vec<(Option<String>, String)> {
+        document.pages[0]
+            .content
+            .iter()
+            .flat_map(|block| match block {
+                ContentBlock::List(list) => list
+                    .items
+                    .iter()
+                    .flat_map(|item| item.content.iter())
+                    .collect(),
+                block => vec![block],
+            })
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => {
+                    Some((text.paragraph_style.clone(), text.extract_text()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_parse_structure() {
+        let markdown = "# Guide\n\nSome **bold** and `code`.\n\n## Steps\n\n\
+            1. First\n2. Second\n   - nested\n\n- [x] done\n- [ ] todo\n\n\
+            > Quoted\n\n```rust\nfn main() {}\n```\n";
+        let document = parse(markdown, ParseOptions::default()).await;
+
+        assert_eq!(document.metadata.title.as_deref(), Some("Guide"));
+        let headings: Vec<_> = document
+            .structure
+            .headings
+            .iter()
+            .map(|h| (h.text.as_str(), h.level))
+            .collect();
+        assert_eq!(headings, [("Guide", 1), ("Steps", 2)]);
+        let sections = &document.structure.sections;
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].title, "Guide");
+        assert_eq!(sections[0].children[0].title, "Steps");
+        assert_eq!(sections[0].children[0].start.block, 2);
+
+        let style = |s: &str| Some(s.to_string());
+        assert_eq!(
+            texts(&document),
+            [
+                (style("Heading 1"), "Guide".to_string()),
+                (None, "Some bold and code.".to_string()),
+                (style("Heading 2"), "Steps".to_string()),
+                (style("List Number"), "First".to_string()),
+                (style("List Number"), "Second".to_string()),
+                (style("List Bullet 2"), "nested".to_string()),
+                (style("List Bullet"), "☑ done".to_string()),
+                (style("List Bullet"), "☐ todo".to_string()),
+                (style("Quote"), "Quoted".to_string()),
+                (style("Code"), "fn main() {}".to_string()),
+            ]
+        );
+
+        let markers: Vec<_> = document.pages[0]
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::List(list) => Some(
+                    list.items
+                        .iter()
+                        .map(|item| (item.level, item.ordered, item.marker.as_str()))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            markers,
+            [
+                vec![(0, true, "1."), (0, true, "2."), (1, false, "•")],
+                vec![(0, false, "•"), (0, false, "•")],
+            ]
+        );
+
+        let ContentBlock::Text(paragraph) = &document.pages[0].content[1] else {
+            panic!("expected a paragraph");
+        };
+        assert!(paragraph.runs[1].style.bold);
+        assert_eq!(
+            paragraph.runs[3].style.font_family.as_deref(),
+            Some(MONOSPACE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_tables_links_and_images() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("logo.svg"), "<svg/>").unwrap();
+        let options = ParseOptions {
+            resource_dir: Some(dir.path().to_path_buf()),
+            ..ParseOptions::default()
+        };
+        let markdown = "| Name | Qty |\n|------|----:|\n| Tea  | 3   |\n\n\
+            See [the docs](https://example.com/docs).\n\n\
+            ![Logo](logo.svg) ![Remote](https://example.com/a.png) ![Secret](../secret.png)\n";
+        let document = parse(markdown, options).await;
+        let page = &document.pages[0];
+
+        let ContentBlock::Table(table) = &page.content[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.column_count, 2);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[1].cells[0].extract_text(), "Tea");
+        let ContentBlock::Text(header) = &table.rows[0].cells[0].content[0] else {
+            panic!("expected header text");
+        };
+        assert!(header.runs[0].style.bold);
+
+        assert_eq!(page.annotations.len(), 1);
+        assert!(matches!(
+            &page.annotations[0].annotation_type,
+            AnnotationType::Link { url } if url == "https://example.com/docs"
+        ));
+        assert_eq!(page.annotations[0].content.as_deref(), Some("the docs"));
+        let ContentBlock::Text(paragraph) = &page.content[1] else {
+            panic!("expected the link paragraph");
+        };
+        let links: Vec<_> = paragraph
+            .runs
+            .iter()
+            .map(|run| (&*run.text, run.link.clone()))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                ("See ", None),
+                (
+                    "the docs",
+                    Some(Link::Url("https://example.com/docs".to_string()))
+                ),
+                (".", None),
+            ]
+        );
+
+        let images = &document.resources.images;
+        assert_eq!(images.len(), 3);
+        assert_eq!(images[0].data.as_deref(), Some(&b"<svg/>"[..]));
+        assert_eq!(images[0].mime_type, "image/svg+xml");
+        assert_eq!(images[1].url.as_deref(), Some("https://example.com/a.png"));
+        assert!(images[2].data.is_none());
+        let alt: Vec<_> = page
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Image(image) => image.alt_text.as_deref(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alt, ["Logo", "Remote", "Secret"]);
+    }
+}
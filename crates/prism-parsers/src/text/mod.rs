@@ -3,11 +3,17 @@
 //!
 //! Parsers for plain text files (.txt, .log, .json, .xml, .csv, .md, .html, etc.)
 
+pub mod encoding;
 pub mod html;
+pub(crate) mod linked;
+pub mod log;
+pub mod markdown;
 pub mod plain;
+pub mod structured;
 
 // Re-export parsers
 pub use html::HtmlParser;
-pub use plain::{
-    CsvParser, JsonParser, LogParser, MarkdownParser, TextParser, XmlParser,
-};
+pub use markdown::MarkdownParser;
+pub use log::LogParser;
+pub use plain::{CsvParser, TextParser};
+pub use structured::{JsonParser, XmlParser};
@@ -3,13 +3,17 @@
 //!
 //! Parses plain text files (.txt, .log, .json, .xml, .csv, .md, etc.) into the Unified Document Model.
 //! Creates a single-page document with text content that wraps properly.
+//!
+//! Input need not be UTF-8: the encoding is detected as described in
+//! [`super::encoding`], recorded as the `encoding` custom property, and the
+//! text transcoded.
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use prism_core::{
     document::{
         ContentBlock, Dimensions, Document, Page, PageMetadata, Rect, ShapeStyle, TextBlock,
-        TextRun, TextStyle,
+        TextDirection, TextRun, TextStyle,
     },
     error::{Error, Result},
     format::Format,
@@ -18,6 +22,8 @@ use prism_core::{
 };
 use tracing::{debug, info};
 
+use crate::text::encoding;
+
 /// Plain text parser
 ///
 /// Parses plain text files into the Unified Document Model.
@@ -25,26 +31,10 @@ use tracing::{debug, info};
 #[derive(Debug, Clone)]
 pub struct TextParser;
 
-/// JSON file parser
-#[derive(Debug, Clone)]
-pub struct JsonParser;
-
-/// XML file parser
-#[derive(Debug, Clone)]
-pub struct XmlParser;
-
 /// CSV file parser
 #[derive(Debug, Clone)]
 pub struct CsvParser;
 
-/// Markdown file parser
-#[derive(Debug, Clone)]
-pub struct MarkdownParser;
-
-/// Log file parser
-#[derive(Debug, Clone)]
-pub struct LogParser;
-
 impl TextParser {
     /// Create a new text parser
     #[must_use]
@@ -52,23 +42,9 @@ impl TextParser {
         Self
     }
 
-    /// Detect if content is likely UTF-8 text
-    fn is_likely_text(data: &[u8]) -> bool {
-        // Check if it's valid UTF-8
-        if std::str::from_utf8(data).is_err() {
-            return false;
-        }
-
-        // Additional heuristics: check for common text patterns
-        // Reject if too many null bytes or control characters (except newlines, tabs)
-        let control_char_count = data
-            .iter()
-            .filter(|&&b| b < 32 && b != b'\n' && b != b'\r' && b != b'\t')
-            .count();
-        let ratio = control_char_count as f64 / data.len() as f64;
-
-        // If more than 10% are non-text control characters, probably binary
-        ratio < 0.1
+    /// Detect if content is likely text in an encoding we can decode
+    pub(crate) fn is_likely_text(data: &[u8]) -> bool {
+        encoding::detect(data).is_some()
     }
 
     /// Get the appropriate format based on file extension
@@ -146,7 +122,7 @@ impl Parser for TextParser {
     }
 
     fn can_parse(&self, data: &[u8]) -> bool {
-        // Accept if it's valid UTF-8 text
+        // Accept if it's text in a known encoding
         Self::is_likely_text(data)
     }
 
@@ -156,20 +132,27 @@ impl Parser for TextParser {
             context.size, context.filename
         );
 
-        // Convert bytes to UTF-8 string
-        let text = std::str::from_utf8(&data)
-            .map_err(|e| Error::ParseError(format!("Invalid UTF-8: {}", e)))?
-            .to_string();
+        // Transcode to UTF-8
+        let decoded = encoding::decode(&data)
+            .ok_or_else(|| Error::ParseError("Not text in a known encoding".to_string()))?;
+        let encoding = decoded.encoding.name();
+        let text = decoded.text;
 
         let char_count = text.len();
-        debug!("Successfully decoded {} characters of text", char_count);
+        let line_count = text.matches('\n').count();
+        debug!(
+            "Successfully decoded {} characters of {} text",
+            char_count, encoding
+        );
 
         // Create a single text run with all the content
         let text_run = TextRun {
-            text,
+            text: text.into(),
             style: TextStyle::default(),
             bounds: None,
             char_positions: None,
+            link: None,
+            revision: None,
         };
 
         // Create text block with wrapping enabled (no specific bounds means it will wrap)
@@ -184,6 +167,7 @@ impl Parser for TextParser {
             paragraph_style: None,
             style: ShapeStyle::default(),
             rotation: 0.0,
+            direction: TextDirection::Auto,
         };
 
         // Create single page
@@ -207,10 +191,8 @@ impl Parser for TextParser {
         }
 
         metadata.add_custom("character_count", char_count as i64);
-        metadata.add_custom(
-            "line_count",
-            data.iter().filter(|&&b| b == b'\n').count() as i64,
-        );
+        metadata.add_custom("line_count", i64::try_from(line_count).unwrap_or(i64::MAX));
+        metadata.add_custom("encoding", encoding);
 
         // Build document
         let mut document = Document::builder().metadata(metadata).build();
@@ -284,11 +266,7 @@ macro_rules! impl_text_parser {
     };
 }
 
-impl_text_parser!(JsonParser, Format::json, "JSON Parser");
-impl_text_parser!(XmlParser, Format::xml, "XML Parser");
 impl_text_parser!(CsvParser, Format::csv, "CSV Parser");
-impl_text_parser!(MarkdownParser, Format::markdown, "Markdown Parser");
-impl_text_parser!(LogParser, Format::log, "Log Parser");
 
 #[cfg(test)]
 mod tests {
@@ -308,6 +286,9 @@ mod tests {
         // Invalid UTF-8
         let invalid_utf8 = [0xFF, 0xFE, 0xFD];
         assert!(!TextParser::is_likely_text(&invalid_utf8));
+
+        // Latin-1
+        assert!(TextParser::is_likely_text(b"Fran\xe7ais"));
     }
 
     #[test]
@@ -330,6 +311,7 @@ mod tests {
             filename: Some("test.txt".to_string()),
             size: data.len(),
             options: Default::default(),
+            progress: None,
         };
 
         let result = parser.parse(data, context).await;
@@ -343,7 +325,7 @@ mod tests {
         match &document.pages[0].content[0] {
             ContentBlock::Text(text_block) => {
                 assert_eq!(text_block.runs.len(), 1);
-                assert_eq!(text_block.runs[0].text, content);
+                assert_eq!(&*text_block.runs[0].text, content);
             }
             _ => panic!("Expected text block"),
         }
@@ -360,6 +342,7 @@ mod tests {
             filename: Some("data.json".to_string()),
             size: data.len(),
             options: Default::default(),
+            progress: None,
         };
 
         let result = parser.parse(data, context).await;
@@ -380,6 +363,7 @@ mod tests {
             filename: Some("test.log".to_string()),
             size: data.len(),
             options: Default::default(),
+            progress: None,
         };
 
         let result = parser.parse(data, context).await;
@@ -392,6 +376,29 @@ mod tests {
         assert!(line_count.is_some());
     }
 
+    #[tokio::test]
+    async fn test_parse_utf16() {
+        let parser = TextParser::new();
+        let mut data = vec![0xFF, 0xFE];
+        data.extend("Größe\nHöhe".encode_utf16().flat_map(u16::to_le_bytes));
+        let data = Bytes::from(data);
+
+        let context = ParseContext {
+            format: parser.format(),
+            filename: Some("sizes.txt".to_string()),
+            size: data.len(),
+            options: Default::default(),
+            progress: None,
+        };
+
+        let document = parser.parse(data, context).await.unwrap();
+        assert_eq!(document.extract_text(), "Größe\nHöhe");
+        assert!(matches!(
+            document.metadata.get_custom("encoding"),
+            Some(prism_core::metadata::MetadataValue::String(encoding)) if encoding == "UTF-16LE"
+        ));
+    }
+
     #[test]
     fn test_parser_metadata() {
         let parser = TextParser::new();
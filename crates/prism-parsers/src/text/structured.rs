@@ -0,0 +1,703 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! JSON and XML parsers
+//!
+//! Both formats are pretty-printed in the `Code` paragraph style and split
+//! into sections, each listed in the document outline, so that large
+//! payloads can be navigated:
+//!
+//! - JSON: a section per member of a top-level object, headed by its key.
+//! - XML: a section per run of same-named children of the root element,
+//!   headed by the name and the number of elements.
+//!
+//! Arrays of flat JSON objects and runs of flat XML elements become tables
+//! instead, with a column per key, attribute or child element.
+//!
+//! Input that does not parse is kept as plain text, with a warning in the
+//! document's diagnostics.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::{
+    diagnostics::Diagnostic,
+    document::{
+        ContentBlock, Document, Heading, OutlineItem, Rect, ShapeStyle, TableBlock, TableCell,
+        TableRow, TextBlock, TextDirection, TextRun, TextStyle,
+    },
+    error::{ErrorCode, Result},
+    format::Format,
+    parser::{ParseContext, Parser, ParserFeature, ParserMetadata},
+};
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::Value;
+use std::fmt::Write as _;
+use tracing::debug;
+
+use crate::text::encoding;
+use crate::text::plain::TextParser;
+
+/// Tables with more columns than this are shown as text
+const MAX_TABLE_COLUMNS: usize = 32;
+
+/// Indentation of pretty-printed XML
+const XML_INDENT: &str = "  ";
+
+/// JSON file parser
+#[derive(Debug, Clone)]
+pub struct JsonParser;
+
+/// XML file parser
+#[derive(Debug, Clone)]
+pub struct XmlParser;
+
+impl JsonParser {
+    /// Create a new JSON parser
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for JsonParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XmlParser {
+    /// Create a new XML parser
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for XmlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for JsonParser {
+    fn format(&self) -> Format {
+        Format::json()
+    }
+
+    fn can_parse(&self, data: &[u8]) -> bool {
+        TextParser::is_likely_text(data)
+    }
+
+    async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
+        let value = match encoding::decode(&data) {
+            Some(decoded) => serde_json::from_str::<Value>(&decoded.text),
+            None => serde_json::from_slice::<Value>(&data),
+        };
+        let mut document = TextParser::new().parse(data, context).await?;
+        match value {
+            Ok(value) => Sections::json(&value).apply(&mut document),
+            Err(e) => {
+                debug!("Not valid JSON, keeping the text: {e}");
+                document.diagnostics.push(Diagnostic::warning(
+                    ErrorCode::ParseError,
+                    format!("Invalid JSON, shown as plain text: {e}"),
+                ));
+            }
+        }
+        Ok(document)
+    }
+
+    fn metadata(&self) -> ParserMetadata {
+        structured_metadata("JSON Parser")
+    }
+}
+
+#[async_trait]
+impl Parser for XmlParser {
+    fn format(&self) -> Format {
+        Format::xml()
+    }
+
+    fn can_parse(&self, data: &[u8]) -> bool {
+        TextParser::is_likely_text(data)
+    }
+
+    async fn parse(&self, data: Bytes, context: ParseContext) -> Result<Document> {
+        let root = encoding::decode(&data)
+            .ok_or_else(|| "not text in a known encoding".to_string())
+            .and_then(|decoded| parse_xml(&decoded.text));
+        let mut document = TextParser::new().parse(data, context).await?;
+        match root {
+            Ok(root) => Sections::xml(&root).apply(&mut document),
+            Err(e) => {
+                debug!("Not well-formed XML, keeping the text: {e}");
+                document.diagnostics.push(Diagnostic::warning(
+                    ErrorCode::ParseError,
+                    format!("Invalid XML, shown as plain text: {e}"),
+                ));
+            }
+        }
+        Ok(document)
+    }
+
+    fn metadata(&self) -> ParserMetadata {
+        structured_metadata("XML Parser")
+    }
+}
+
+fn structured_metadata(name: &str) -> ParserMetadata {
+    ParserMetadata {
+        name: name.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: vec![
+            ParserFeature::TextExtraction,
+            ParserFeature::TableExtraction,
+            ParserFeature::MetadataExtraction,
+        ],
+        requires_sandbox: false,
+    }
+}
+
+/// Content blocks and outline of a structured document
+#[derive(Debug, Default)]
+struct Sections {
+    content: Vec<ContentBlock>,
+    headings: Vec<Heading>,
+}
+
+impl Sections {
+    fn json(value: &Value) -> Self {
+        let mut sections = Self::default();
+        match value {
+            Value::Object(members) if !members.is_empty() => {
+                for (key, value) in members {
+                    sections.heading(key.clone());
+                    sections.json_value(value);
+                }
+            }
+            value => sections.json_value(value),
+        }
+        sections
+    }
+
+    fn json_value(&mut self, value: &Value) {
+        if let Some(table) = value.as_array().and_then(|items| json_table(items)) {
+            self.content.push(ContentBlock::Table(table));
+        } else {
+            let text = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+            self.code(&text);
+        }
+    }
+
+    fn xml(root: &XmlElement) -> Self {
+        let mut sections = Self::default();
+        if !root.children.iter().any(XmlNode::is_element) {
+            sections.code(&root.to_pretty_string());
+            return sections;
+        }
+
+        if !root.attributes.is_empty() {
+            sections.code(&root.start_tag());
+        }
+        let mut children = root.children.as_slice();
+        while let Some(first) = children.first() {
+            let XmlNode::Element(element) = first else {
+                let mut text = String::new();
+                first.write(&mut text, 0);
+                sections.code(&text);
+                children = &children[1..];
+                continue;
+            };
+
+            let run: Vec<&XmlElement> = children
+                .iter()
+                .map_while(|node| match node {
+                    XmlNode::Element(next) if next.name == element.name => Some(next),
+                    _ => None,
+                })
+                .collect();
+            children = &children[run.len()..];
+
+            if run.len() > 1 {
+                sections.heading(format!("{} ({})", element.name, run.len()));
+            } else {
+                sections.heading(element.name.clone());
+            }
+            match xml_table(&run) {
+                Some(table) if run.len() > 1 => sections.content.push(ContentBlock::Table(table)),
+                _ => {
+                    let mut text = String::new();
+                    for element in &run {
+                        element.write(&mut text, 0);
+                    }
+                    sections.code(&text);
+                }
+            }
+        }
+        sections
+    }
+
+    fn heading(&mut self, text: String) {
+        self.content.push(ContentBlock::Text(text_block(
+            text.clone(),
+            TextStyle::default(),
+            Some("Heading 1"),
+        )));
+        self.headings.push(Heading {
+            text,
+            level: 1,
+            page: 1,
+            bounds: None,
+        });
+    }
+
+    fn code(&mut self, text: &str) {
+        let style = TextStyle {
+            font_family: Some("monospace".into()),
+            ..TextStyle::default()
+        };
+        let text = text.trim_end_matches('\n').to_string();
+        self.content
+            .push(ContentBlock::Text(text_block(text, style, Some("Code"))));
+    }
+
+    /// Replace the plain text of `document`
+    fn apply(self, document: &mut Document) {
+        if let Some(page) = document.pages.first_mut() {
+            page.content = self.content;
+        }
+        document.structure.outline = self
+            .headings
+            .iter()
+            .map(|heading| OutlineItem {
+                title: heading.text.clone(),
+                page: 1,
+                y_position: None,
+                children: Vec::new(),
+            })
+            .collect();
+        document.structure.headings = self.headings;
+    }
+}
+
+fn text_block(text: String, style: TextStyle, paragraph_style: Option<&str>) -> TextBlock {
+    TextBlock {
+        bounds: Rect::default(),
+        runs: vec![TextRun {
+            text: text.into(),
+            style,
+            bounds: None,
+            char_positions: None,
+            link: None,
+            revision: None,
+        }],
+        paragraph_style: paragraph_style.map(str::to_string),
+        style: ShapeStyle::default(),
+        rotation: 0.0,
+        direction: TextDirection::Auto,
+    }
+}
+
+/// A table with a bold header row, or `None` if there are too many columns
+fn table(columns: &[String], rows: Vec<Vec<String>>) -> Option<TableBlock> {
+    if columns.is_empty() || columns.len() > MAX_TABLE_COLUMNS {
+        return None;
+    }
+
+    let cell = |text: String, bold: bool| TableCell {
+        content: if text.is_empty() {
+            Vec::new()
+        } else {
+            let style = TextStyle {
+                bold,
+                ..TextStyle::default()
+            };
+            vec![ContentBlock::Text(text_block(text, style, None))]
+        },
+        col_span: 1,
+        row_span: 1,
+        background_color: None,
+    };
+    let header = TableRow {
+        cells: columns
+            .iter()
+            .map(|name| cell(name.clone(), true))
+            .collect(),
+        height: None,
+    };
+    let body = rows.into_iter().map(|values| TableRow {
+        cells: values.into_iter().map(|text| cell(text, false)).collect(),
+        height: None,
+    });
+    Some(TableBlock {
+        bounds: Rect::default(),
+        rows: std::iter::once(header).chain(body).collect(),
+        column_count: columns.len(),
+        style: ShapeStyle::default(),
+        rotation: 0.0,
+    })
+}
+
+/// A table of an array of objects whose values are all scalars
+fn json_table(items: &[Value]) -> Option<TableBlock> {
+    let mut columns: Vec<String> = Vec::new();
+    for item in items {
+        for (key, value) in item.as_object()? {
+            if value.is_object() || value.is_array() {
+                return None;
+            }
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rows = items
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|object| {
+            columns
+                .iter()
+                .map(|column| match object.get(column) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(text)) => text.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    table(&columns, rows)
+}
+
+/// A table of elements whose children are all text-only elements, with a
+/// column per attribute and child element name
+fn xml_table(elements: &[&XmlElement]) -> Option<TableBlock> {
+    let mut columns: Vec<String> = Vec::new();
+    for element in elements {
+        for (name, _) in &element.attributes {
+            let column = format!("@{name}");
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        for child in &element.children {
+            let XmlNode::Element(child) = child else {
+                continue;
+            };
+            if child.children.iter().any(XmlNode::is_element) || !child.attributes.is_empty() {
+                return None;
+            }
+            if !columns.contains(&child.name) {
+                columns.push(child.name.clone());
+            }
+        }
+        // Text mixed with elements does not fit a cell
+        if element.children.iter().any(|child| !child.is_element()) {
+            return None;
+        }
+    }
+
+    let rows = elements
+        .iter()
+        .map(|element| {
+            columns
+                .iter()
+                .map(|column| match column.strip_prefix('@') {
+                    Some(attribute) => element
+                        .attributes
+                        .iter()
+                        .find(|(name, _)| name == attribute)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default(),
+                    None => element
+                        .children
+                        .iter()
+                        .filter_map(|child| match child {
+                            XmlNode::Element(child) if child.name == *column => Some(child.text()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                })
+                .collect()
+        })
+        .collect();
+    table(&columns, rows)
+}
+
+/// A node of an XML document
+#[derive(Debug, Clone, PartialEq)]
+enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+    CData(String),
+    Comment(String),
+}
+
+impl XmlNode {
+    fn is_element(&self) -> bool {
+        matches!(self, Self::Element(_))
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = XML_INDENT.repeat(depth);
+        match self {
+            Self::Element(element) => element.write(out, depth),
+            Self::Text(text) => {
+                out.push_str(&indent);
+                out.push_str(&escape(text.as_str()));
+                out.push('\n');
+            }
+            Self::CData(text) => {
+                let _ = writeln!(out, "{indent}<![CDATA[{text}]]>");
+            }
+            Self::Comment(text) => {
+                let _ = writeln!(out, "{indent}<!--{text}-->");
+            }
+        }
+    }
+}
+
+/// An XML element with its attributes and children
+#[derive(Debug, Clone, Default, PartialEq)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    /// Text of the element and its descendants
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .map(|child| match child {
+                XmlNode::Element(element) => element.text(),
+                XmlNode::Text(text) | XmlNode::CData(text) => text.clone(),
+                XmlNode::Comment(_) => String::new(),
+            })
+            .collect()
+    }
+
+    fn start_tag(&self) -> String {
+        let mut tag = format!("<{}", self.name);
+        for (name, value) in &self.attributes {
+            let _ = write!(tag, " {name}=\"{}\"", escape(value.as_str()));
+        }
+        tag.push('>');
+        tag
+    }
+
+    fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    /// Write the element indented by `depth` levels, keeping elements
+    /// that only hold text on one line
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = XML_INDENT.repeat(depth);
+        let start = self.start_tag();
+        out.push_str(&indent);
+        match self.children.as_slice() {
+            [] => {
+                out.push_str(&start[..start.len() - 1]);
+                out.push_str("/>\n");
+            }
+            [XmlNode::Text(text)] => {
+                let _ = writeln!(out, "{start}{}</{}>", escape(text.as_str()), self.name);
+            }
+            children => {
+                out.push_str(&start);
+                out.push('\n');
+                for child in children {
+                    child.write(out, depth + 1);
+                }
+                let _ = writeln!(out, "{indent}</{}>", self.name);
+            }
+        }
+    }
+}
+
+/// Read the root element of an XML document
+fn parse_xml(xml: &str) -> std::result::Result<XmlElement, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root = None;
+    let mut buf = Vec::new();
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("at byte {}: {e}", reader.buffer_position()))?;
+        let node = match event {
+            Event::Start(start) => {
+                stack.push(element(&start, &reader));
+                None
+            }
+            Event::Empty(start) => Some(XmlNode::Element(element(&start, &reader))),
+            Event::End(_) => stack.pop().map(XmlNode::Element),
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| e.to_string())?;
+                Some(XmlNode::Text(text.into_owned()))
+            }
+            Event::CData(text) => Some(XmlNode::CData(
+                String::from_utf8_lossy(&text.into_inner()).into_owned(),
+            )),
+            Event::Comment(text) => Some(XmlNode::Comment(
+                String::from_utf8_lossy(&text.into_inner()).into_owned(),
+            )),
+            Event::Decl(_) | Event::PI(_) | Event::DocType(_) => None,
+            Event::Eof => break,
+        };
+
+        match (node, stack.last_mut()) {
+            (Some(node), Some(parent)) => parent.children.push(node),
+            (Some(XmlNode::Element(_)), None) if root.is_some() => {
+                return Err("more than one root element".to_string());
+            }
+            (Some(XmlNode::Element(element)), None) => root = Some(element),
+            (Some(XmlNode::Text(_)), None) => return Err("text outside the root element".into()),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(format!("element <{}> is not closed", open.name));
+    }
+    root.ok_or_else(|| "no root element".to_string())
+}
+
+fn element(start: &quick_xml::events::BytesStart<'_>, reader: &Reader<&[u8]>) -> XmlElement {
+    XmlElement {
+        name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+        attributes: start
+            .attributes()
+            .flatten()
+            .map(|attribute| {
+                let value = attribute.decode_and_unescape_value(reader).map_or_else(
+                    |_| String::from_utf8_lossy(&attribute.value).into_owned(),
+                    std::borrow::Cow::into_owned,
+                );
+                (
+                    String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                    value,
+                )
+            })
+            .collect(),
+        children: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(parser: &dyn Parser, text: &str) -> Document {
+        let data = Bytes::from(text.to_string());
+        let context = ParseContext {
+            format: parser.format(),
+            filename: Some("payload".to_string()),
+            size: data.len(),
+            options: prism_core::parser::ParseOptions::default(),
+            progress: None,
+        };
+        parser.parse(data, context).await.unwrap()
+    }
+
+    fn outline(document: &Document) -> Vec<&str> {
+        document
+            .structure
+            .outline
+            .iter()
+            .map(|item| item.title.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_parse_json() {
+        let json = r#"{"name": "orders", "meta": {"count": 2},
+            "items": [{"id": 1, "sku": "A"}, {"id": 2, "sku": "B", "note": null}]}"#;
+        let document = parse(&JsonParser::new(), json).await;
+
+        assert_eq!(outline(&document), ["name", "meta", "items"]);
+        let content = &document.pages[0].content;
+        assert_eq!(content.len(), 6);
+        let ContentBlock::Text(meta) = &content[3] else {
+            panic!("expected text");
+        };
+        assert_eq!(meta.paragraph_style.as_deref(), Some("Code"));
+        assert_eq!(meta.extract_text(), "{\n  \"count\": 2\n}");
+
+        let ContentBlock::Table(items) = &content[5] else {
+            panic!("expected a table");
+        };
+        assert_eq!(items.column_count, 3);
+        assert_eq!(items.rows.len(), 3);
+        assert_eq!(items.rows[0].cells[2].extract_text(), "note");
+        assert_eq!(items.rows[2].cells[1].extract_text(), "B");
+    }
+
+    #[tokio::test]
+    async fn test_parse_invalid_json() {
+        let document = parse(&JsonParser::new(), "{not json").await;
+        assert_eq!(document.extract_text().trim(), "{not json");
+        assert_eq!(document.diagnostics.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_parse_xml() {
+        let xml = r#"<?xml version="1.0"?>
+<catalog version="2">
+  <!-- Stock -->
+  <book id="b1"><title>Dune</title><year>1965</year></book>
+  <book id="b2"><title>Emma &amp; Co</title></book>
+  <shelf><book id="b3"/><label>Sci-fi</label></shelf>
+</catalog>"#;
+        let document = parse(&XmlParser::new(), xml).await;
+
+        assert_eq!(outline(&document), ["book (2)", "shelf"]);
+        let texts: Vec<String> = document.pages[0]
+            .content
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text(text) => text.extract_text(),
+                ContentBlock::Table(_) => "<table>".to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "<catalog version=\"2\">",
+                "<!-- Stock -->",
+                "book (2)",
+                "<table>",
+                "shelf",
+                "<shelf>\n  <book id=\"b3\"/>\n  <label>Sci-fi</label>\n</shelf>",
+            ]
+        );
+
+        let ContentBlock::Table(books) = &document.pages[0].content[3] else {
+            panic!("expected a table");
+        };
+        let header: Vec<String> = books.rows[0]
+            .cells
+            .iter()
+            .map(TableCell::extract_text)
+            .collect();
+        assert_eq!(header, ["@id", "title", "year"]);
+        assert_eq!(books.rows[2].cells[1].extract_text(), "Emma & Co");
+    }
+
+    #[test]
+    fn test_parse_xml_errors() {
+        assert!(parse_xml("<a><b></a>").is_err());
+        assert!(parse_xml("<a>").is_err());
+        assert!(parse_xml("<a/><b/>").is_err());
+    }
+}
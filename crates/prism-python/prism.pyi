@@ -0,0 +1,62 @@
+# SPDX-License-Identifier: AGPL-3.0-only
+"""Prism document processing."""
+
+import os
+from typing import Any, Dict, Literal, Optional, Union
+
+__version__: str
+
+Source = Union[bytes, str, "os.PathLike[str]"]
+
+class PrismError(Exception):
+    """Raised when a document cannot be detected, parsed or rendered."""
+
+    code: str
+    """Prism error code, such as ``E1200``."""
+
+class ParseOptions:
+    """Options for parsing documents."""
+
+    extract_images: bool
+    preserve_formatting: bool
+    extract_structure: bool
+    max_memory: Optional[int]
+    timeout: Optional[int]
+    password: Optional[str]
+    lenient: bool
+    content_ids: bool
+    resource_dir: Optional[str]
+    reject_active_content: bool
+
+    def __init__(
+        self,
+        *,
+        extract_images: bool = False,
+        preserve_formatting: bool = False,
+        extract_structure: bool = False,
+        max_memory: Optional[int] = None,
+        timeout: Optional[int] = None,
+        password: Optional[str] = None,
+        lenient: bool = False,
+        content_ids: bool = False,
+        resource_dir: Optional[Union[str, "os.PathLike[str]"]] = None,
+        reject_active_content: bool = False,
+    ) -> None: ...
+
+def detect(source: Source, filename: Optional[str] = None) -> Optional[Dict[str, Any]]:
+    """Detect the format of a document."""
+
+def parse(
+    source: Source,
+    filename: Optional[str] = None,
+    options: Optional[ParseOptions] = None,
+) -> Dict[str, Any]:
+    """Parse a document into the Unified Document Model."""
+
+def convert(
+    source: Source,
+    to: Literal["html", "text", "markdown"] = "html",
+    filename: Optional[str] = None,
+    options: Optional[ParseOptions] = None,
+) -> str:
+    """Convert a document to HTML, plain text or Markdown."""
@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Prism for Python
+//!
+//! Python bindings for Prism document processing, built with pyo3 and
+//! packaged with maturin as the `prism` module:
+//!
+//! ```python
+//! import prism
+//!
+//! prism.detect("report.docx")
+//! # {'mime_type': 'application/vnd.openxmlformats-...', 'name': 'DOCX', ...}
+//!
+//! document = prism.parse("report.docx", options=prism.ParseOptions(lenient=True))
+//! print(document["metadata"]["title"], len(document["pages"]))
+//!
+//! markdown = prism.convert("report.docx", to="markdown")
+//! ```
+//!
+//! Every function takes either a path (`str` or `os.PathLike`) or the
+//! document's bytes. Parsing releases the GIL, so documents can be parsed
+//! from several Python threads at once. Failures raise `prism.PrismError`,
+//! whose `code` attribute holds the Prism error code (such as `E1200`);
+//! files that cannot be read raise the usual `OSError`.
+//!
+//! Build the wheel with `maturin build --release` (or `maturin develop`)
+//! from `crates/prism-python`.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+
+mod options;
+
+use bytes::Bytes;
+use prism::render::MarkdownRenderer;
+use prism::{Document, Prism};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+pub use options::ParseOptions;
+
+create_exception!(
+    prism,
+    PrismError,
+    PyException,
+    "Raised when a document cannot be detected, parsed or rendered"
+);
+
+/// Prism Python bindings version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Formats `convert` can produce
+const CONVERT_FORMATS: &[&str] = &["html", "text", "markdown"];
+
+/// Runtime the async pipeline is driven on, shared by every call
+fn runtime() -> PyResult<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to start runtime: {e}")))?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+/// The bytes and filename of a document given as bytes or a path
+fn read_source(
+    source: &Bound<'_, PyAny>,
+    filename: Option<String>,
+) -> PyResult<(Bytes, Option<String>)> {
+    if let Ok(data) = source.downcast::<PyBytes>() {
+        return Ok((Bytes::copy_from_slice(data.as_bytes()), filename));
+    }
+    let path: PathBuf = source.extract()?;
+    let data = std::fs::read(&path)?;
+    let filename = filename.or_else(|| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_string)
+    });
+    Ok((Bytes::from(data), filename))
+}
+
+/// `error` as a `PrismError` carrying its error code
+fn prism_error(py: Python<'_>, error: &prism::Error) -> PyErr {
+    let err = PrismError::new_err(error.to_string());
+    // Setting an attribute on a fresh exception cannot fail
+    let _ = err.value(py).setattr("code", error.code().to_string());
+    err
+}
+
+/// Detect and parse a document without holding the GIL
+fn parse_document(
+    py: Python<'_>,
+    source: &Bound<'_, PyAny>,
+    filename: Option<String>,
+    options: Option<ParseOptions>,
+) -> PyResult<Document> {
+    let (data, filename) = read_source(source, filename)?;
+    let prism = Prism::new().with_parse_options(options.map(Into::into).unwrap_or_default());
+    let runtime = runtime()?;
+    py.allow_threads(|| runtime.block_on(prism.parse(data, filename.as_deref())))
+        .map_err(|e| prism_error(py, &e))
+}
+
+/// Detect the format of a document
+///
+/// Returns a dict with the `mime_type`, `extension`, `name`, `family`,
+/// `confidence` and detection `method`, or `None` if the format is not
+/// recognised. `filename` is a hint for ambiguous content given as bytes.
+#[pyfunction]
+#[pyo3(signature = (source, filename = None))]
+fn detect<'py>(
+    source: &Bound<'py, PyAny>,
+    filename: Option<String>,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let py = source.py();
+    let (data, filename) = read_source(source, filename)?;
+    let Some(detection) = Prism::new().detect(&data, filename.as_deref()) else {
+        return Ok(None);
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("mime_type", &detection.format.mime_type)?;
+    dict.set_item("extension", &detection.format.extension)?;
+    dict.set_item("name", &detection.format.name)?;
+    dict.set_item("family", format!("{:?}", detection.format.family))?;
+    dict.set_item("confidence", detection.confidence)?;
+    dict.set_item("method", format!("{:?}", detection.method))?;
+    Ok(Some(dict))
+}
+
+/// Parse a document into the Unified Document Model
+///
+/// Returns the document as a dict with the same layout as Prism's JSON
+/// output: `id`, `metadata`, `pages` with their content blocks,
+/// `resources`, `diagnostics` and so on.
+#[pyfunction]
+#[pyo3(signature = (source, filename = None, options = None))]
+fn parse<'py>(
+    source: &Bound<'py, PyAny>,
+    filename: Option<String>,
+    options: Option<ParseOptions>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = source.py();
+    let document = parse_document(py, source, filename, options)?;
+    let json = serde_json::to_string(&document)
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to serialize document: {e}")))?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+/// Convert a document to `html`, `text` or `markdown`
+#[pyfunction]
+#[pyo3(signature = (source, to = "html", filename = None, options = None))]
+fn convert(
+    source: &Bound<'_, PyAny>,
+    to: &str,
+    filename: Option<String>,
+    options: Option<ParseOptions>,
+) -> PyResult<String> {
+    if !CONVERT_FORMATS.contains(&to) {
+        return Err(PyValueError::new_err(format!(
+            "Unsupported output format '{to}', expected one of: {}",
+            CONVERT_FORMATS.join(", ")
+        )));
+    }
+
+    let py = source.py();
+    let document = parse_document(py, source, filename, options)?;
+    match to {
+        "text" => Ok(document.extract_text()),
+        "markdown" => Ok(MarkdownRenderer::new().render_markdown(&document)),
+        _ => {
+            let runtime = runtime()?;
+            let html = py
+                .allow_threads(|| runtime.block_on(Prism::new().render_html(&document)))
+                .map_err(|e| prism_error(py, &e))?;
+            Ok(String::from_utf8_lossy(&html).into_owned())
+        }
+    }
+}
+
+/// The `prism` Python module
+#[pymodule]
+#[pyo3(name = "prism")]
+fn prism_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", VERSION)?;
+    m.add("PrismError", m.py().get_type::<PrismError>())?;
+    m.add_class::<ParseOptions>()?;
+    m.add_function(wrap_pyfunction!(detect, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyModule;
+    use std::io::Write;
+
+    fn module(py: Python<'_>) -> Bound<'_, PyModule> {
+        let module = PyModule::new(py, "prism").unwrap();
+        prism_module(&module).unwrap();
+        module
+    }
+
+    #[test]
+    fn test_detect_and_convert() {
+        Python::with_gil(|py| {
+            let prism = module(py);
+            let data = PyBytes::new(py, b"# Notes\n\nShip *it*.\n");
+
+            let detected = prism
+                .getattr("detect")
+                .unwrap()
+                .call1((&data, "notes.md"))
+                .unwrap();
+            assert_eq!(
+                detected.get_item("mime_type").unwrap().to_string(),
+                "text/markdown"
+            );
+
+            let convert = prism.getattr("convert").unwrap();
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("to", "text").unwrap();
+            kwargs.set_item("filename", "notes.md").unwrap();
+            let text: String = convert
+                .call((&data,), Some(&kwargs))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert!(text.contains("Ship it"));
+
+            kwargs.set_item("to", "pdf").unwrap();
+            let err = convert.call((&data,), Some(&kwargs)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn test_parse() {
+        Python::with_gil(|py| {
+            let prism = module(py);
+            let mut file = tempfile::Builder::new().suffix(".txt").tempfile().unwrap();
+            file.write_all(b"Hello from Python").unwrap();
+            let path = file.path().to_path_buf();
+            let options = Bound::new(
+                py,
+                ParseOptions {
+                    content_ids: true,
+                    ..ParseOptions::default()
+                },
+            )
+            .unwrap();
+
+            let parse = prism.getattr("parse").unwrap();
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("options", &options).unwrap();
+            let first = parse.call((&path,), Some(&kwargs)).unwrap();
+            let second = parse.call((&path,), Some(&kwargs)).unwrap();
+            assert!(first.get_item("pages").unwrap().len().unwrap() > 0);
+            assert!(first
+                .get_item("id")
+                .unwrap()
+                .eq(second.get_item("id").unwrap())
+                .unwrap());
+
+            let err = parse.call1((PyBytes::new(py, b"\x00\x01"),)).unwrap_err();
+            assert!(err.is_instance_of::<PrismError>(py));
+            let code: String = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, "E1000");
+        });
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! `prism.ParseOptions`
+
+use prism::parse::IdStrategy;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+/// Options for parsing documents, mirroring [`prism::ParseOptions`]
+///
+/// Every argument is keyword-only and every attribute can be changed after
+/// construction:
+///
+/// ```python
+/// options = prism.ParseOptions(lenient=True, password="secret")
+/// options.extract_images = True
+/// ```
+#[pyclass(name = "ParseOptions", module = "prism")]
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParseOptions {
+    /// Whether to extract images
+    #[pyo3(get, set)]
+    pub extract_images: bool,
+    /// Whether to preserve formatting
+    #[pyo3(get, set)]
+    pub preserve_formatting: bool,
+    /// Whether to extract structure (headings, TOC)
+    #[pyo3(get, set)]
+    pub extract_structure: bool,
+    /// Maximum memory to use, in bytes
+    #[pyo3(get, set)]
+    pub max_memory: Option<usize>,
+    /// Timeout for parsing, in seconds
+    #[pyo3(get, set)]
+    pub timeout: Option<u64>,
+    /// Password for encrypted documents
+    #[pyo3(get, set)]
+    pub password: Option<String>,
+    /// Recover what can be read from damaged files instead of failing
+    #[pyo3(get, set)]
+    pub lenient: bool,
+    /// Derive the document ID from the content, so parsing the same file
+    /// twice yields the same ID
+    #[pyo3(get, set)]
+    pub content_ids: bool,
+    /// Directory that relative references to other files are read from
+    #[pyo3(get, set)]
+    pub resource_dir: Option<PathBuf>,
+    /// Refuse files with macros, scripts, embedded executables or external
+    /// references
+    #[pyo3(get, set)]
+    pub reject_active_content: bool,
+}
+
+#[pymethods]
+impl ParseOptions {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        extract_images = false,
+        preserve_formatting = false,
+        extract_structure = false,
+        max_memory = None,
+        timeout = None,
+        password = None,
+        lenient = false,
+        content_ids = false,
+        resource_dir = None,
+        reject_active_content = false,
+    ))]
+    #[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+    fn new(
+        extract_images: bool,
+        preserve_formatting: bool,
+        extract_structure: bool,
+        max_memory: Option<usize>,
+        timeout: Option<u64>,
+        password: Option<String>,
+        lenient: bool,
+        content_ids: bool,
+        resource_dir: Option<PathBuf>,
+        reject_active_content: bool,
+    ) -> Self {
+        Self {
+            extract_images,
+            preserve_formatting,
+            extract_structure,
+            max_memory,
+            timeout,
+            password,
+            lenient,
+            content_ids,
+            resource_dir,
+            reject_active_content,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        // The password is never echoed
+        format!(
+            "ParseOptions(extract_images={}, preserve_formatting={}, extract_structure={}, \
+             max_memory={}, timeout={}, password={}, lenient={}, content_ids={}, \
+             resource_dir={}, reject_active_content={})",
+            py_bool(self.extract_images),
+            py_bool(self.preserve_formatting),
+            py_bool(self.extract_structure),
+            py_option(self.max_memory),
+            py_option(self.timeout),
+            if self.password.is_some() {
+                "'***'"
+            } else {
+                "None"
+            },
+            py_bool(self.lenient),
+            py_bool(self.content_ids),
+            self.resource_dir.as_ref().map_or_else(
+                || "None".to_string(),
+                |dir| format!("{:?}", dir.display().to_string())
+            ),
+            py_bool(self.reject_active_content),
+        )
+    }
+}
+
+impl From<ParseOptions> for prism::ParseOptions {
+    fn from(options: ParseOptions) -> Self {
+        Self {
+            extract_images: options.extract_images,
+            preserve_formatting: options.preserve_formatting,
+            extract_structure: options.extract_structure,
+            max_memory: options.max_memory,
+            timeout: options.timeout,
+            password: options.password,
+            id_strategy: if options.content_ids {
+                IdStrategy::ContentHash
+            } else {
+                IdStrategy::Random
+            },
+            lenient: options.lenient,
+            resource_dir: options.resource_dir,
+            reject_active_content: options.reject_active_content,
+            ..Self::default()
+        }
+    }
+}
+
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+fn py_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "None".to_string(), |value| value.to_string())
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! HTML render throughput.
+//!
+//! Documents are built in memory in three sizes, each page holding styled
+//! paragraphs, a table and an image, and rendered in the main output modes.
+//! Run with `cargo bench -p prism-render`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use prism_core::document::{
+    ContentBlock, Dimensions, Document, ImageBlock, ImageResource, Page, Rect, ShapeStyle,
+    TableBlock, TableCell, TableRow, TextBlock, TextRun, TextStyle,
+};
+use prism_core::render::{Pagination, RenderContext, RenderOptions, Renderer};
+use prism_render::html::{HtmlConfig, HtmlLayout, HtmlRenderer};
+
+/// Document sizes, as the number of pages
+const SIZES: &[(&str, u32)] = &[("small", 1), ("medium", 20), ("large", 200)];
+
+/// Paragraphs per page
+const PARAGRAPHS: u32 = 30;
+
+const SENTENCE: &str = "The quick brown fox jumps over the lazy dog & keeps running.";
+
+/// A 1x1 PNG
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xde, 0x00, 0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xff, 0xff, 0x3f,
+    0x00, 0x05, 0xfe, 0x02, 0xfe, 0x0d, 0xef, 0x46, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+    0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+fn paragraph(y: f64, index: u32) -> ContentBlock {
+    let bold = TextStyle {
+        bold: true,
+        font_family: Some("Arial".into()),
+        ..TextStyle::default()
+    };
+    let mut block = TextBlock::new(Rect::new(72.0, y, 468.0, 14.0));
+    block.add_run(TextRun::with_style(format!("Paragraph {index}: "), bold));
+    block.add_run(TextRun::new(SENTENCE));
+    ContentBlock::Text(block)
+}
+
+fn table(y: f64) -> ContentBlock {
+    let mut table = TableBlock::new(Rect::new(72.0, y, 468.0, 100.0), 4);
+    for row in 0..5 {
+        table.add_row(TableRow {
+            cells: (0..4)
+                .map(|col| {
+                    let mut text = TextBlock::new(Rect::default());
+                    text.add_run(TextRun::new(format!("R{row}C{col}")));
+                    TableCell {
+                        content: vec![ContentBlock::Text(text)],
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    }
+                })
+                .collect(),
+            height: None,
+        });
+    }
+    ContentBlock::Table(table)
+}
+
+fn document(pages: u32) -> Document {
+    let mut document = Document::new();
+    document.metadata.title = Some("Benchmark".to_string());
+    document.resources.images.push(ImageResource {
+        id: "logo".to_string(),
+        mime_type: "image/png".to_string(),
+        data: Some(PNG.to_vec()),
+        url: None,
+        width: 1,
+        height: 1,
+    });
+    for number in 1..=pages {
+        let mut page = Page::new(number, Dimensions::LETTER);
+        page.content = (0..PARAGRAPHS)
+            .map(|i| paragraph(72.0 + f64::from(i) * 16.0, i))
+            .collect();
+        page.content.push(table(580.0));
+        page.content.push(ContentBlock::Image(ImageBlock {
+            bounds: Rect::new(500.0, 20.0, 40.0, 40.0),
+            resource_id: "logo".to_string(),
+            alt_text: Some("Logo".to_string()),
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        document.pages.push(page);
+    }
+    document
+}
+
+fn bench_render(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let modes = [
+        ("positioned", HtmlRenderer::new(), Pagination::Continuous),
+        (
+            "semantic",
+            HtmlRenderer::with_config(HtmlConfig {
+                layout: HtmlLayout::Semantic,
+                ..HtmlConfig::default()
+            }),
+            Pagination::Continuous,
+        ),
+        ("split", HtmlRenderer::new(), Pagination::Split),
+    ];
+
+    for (mode, renderer, pagination) in modes {
+        let mut group = c.benchmark_group(format!("html/{mode}"));
+        for &(size, pages) in SIZES {
+            let document = document(pages);
+            let context = RenderContext {
+                options: RenderOptions {
+                    pagination,
+                    ..RenderOptions::default()
+                },
+                filename: None,
+                progress: None,
+            };
+            group.throughput(Throughput::Elements(u64::from(pages)));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &document, |b, document| {
+                b.iter(|| {
+                    runtime
+                        .block_on(renderer.render(document, context.clone()))
+                        .unwrap()
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
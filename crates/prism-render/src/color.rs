@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Color pipeline
+//!
+//! Every color a renderer emits goes through [`convert`], which applies the
+//! requested [`ColorMode`]. Colors are understood in the forms parsers
+//! produce: `#RGB`, `#RRGGBB`, `#RRGGBBAA`, bare `RRGGBB` (OOXML),
+//! `rgb()`/`rgba()` and a handful of CSS names. Anything else is passed
+//! through unchanged.
+
+use prism_core::render::ColorMode;
+
+/// Share of white mixed into full-page backgrounds in
+/// [`ColorMode::InkSaving`]
+pub const BACKDROP_LIGHTEN: f64 = 0.85;
+
+/// What a color is used for, which decides how ink-saving treats it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    /// Text and strokes
+    Ink,
+    /// Shape and highlight fills
+    Fill,
+    /// Fill covering the whole page
+    Backdrop,
+}
+
+/// An sRGB color with alpha
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    /// Red
+    pub r: u8,
+    /// Green
+    pub g: u8,
+    /// Blue
+    pub b: u8,
+    /// Opacity, 0.0 to 1.0
+    pub a: f64,
+}
+
+impl Rgba {
+    /// Opaque color
+    #[must_use]
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// Parse a color as written by parsers
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+
+        if let Some(args) = lower
+            .strip_prefix("rgba(")
+            .or_else(|| lower.strip_prefix("rgb("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+            let channel = |i: usize| parts.get(i).and_then(|p| p.parse::<u8>().ok());
+            let alpha = match parts.get(3) {
+                Some(a) => a.parse::<f64>().ok()?.clamp(0.0, 1.0),
+                None => 1.0,
+            };
+            if parts.len() > 4 {
+                return None;
+            }
+            return Some(Self {
+                r: channel(0)?,
+                g: channel(1)?,
+                b: channel(2)?,
+                a: alpha,
+            });
+        }
+
+        let named = match lower.as_str() {
+            "black" => Some(Self::rgb(0, 0, 0)),
+            "white" => Some(Self::rgb(255, 255, 255)),
+            "red" => Some(Self::rgb(255, 0, 0)),
+            "green" => Some(Self::rgb(0, 128, 0)),
+            "blue" => Some(Self::rgb(0, 0, 255)),
+            "yellow" => Some(Self::rgb(255, 255, 0)),
+            "gray" | "grey" => Some(Self::rgb(128, 128, 128)),
+            _ => None,
+        };
+        if named.is_some() {
+            return named;
+        }
+
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let nibble = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok().map(|n| n * 17);
+        match hex.len() {
+            3 if value.starts_with('#') => Some(Self::rgb(nibble(0)?, nibble(1)?, nibble(2)?)),
+            6 => Some(Self::rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Self {
+                a: f64::from(byte(6)?) / 255.0,
+                ..Self::rgb(byte(0)?, byte(2)?, byte(4)?)
+            }),
+            _ => None,
+        }
+    }
+
+    /// Relative luminance (Rec. 601 luma), 0.0 to 1.0
+    #[must_use]
+    pub fn luma(self) -> f64 {
+        (0.299 * f64::from(self.r) + 0.587 * f64::from(self.g) + 0.114 * f64::from(self.b)) / 255.0
+    }
+
+    /// The gray with the same luminance
+    #[must_use]
+    pub fn grayscale(self) -> Self {
+        let level = channel(self.luma());
+        Self {
+            r: level,
+            g: level,
+            b: level,
+            a: self.a,
+        }
+    }
+
+    /// Mix with white; `amount` 0.0 keeps the color, 1.0 gives white
+    #[must_use]
+    pub fn lighten(self, amount: f64) -> Self {
+        let mix = |c: u8| channel((f64::from(c) / 255.0) * (1.0 - amount) + amount);
+        Self {
+            r: mix(self.r),
+            g: mix(self.g),
+            b: mix(self.b),
+            a: self.a,
+        }
+    }
+
+    /// The color with its luminance inverted, keeping it gray
+    #[must_use]
+    fn inverted_gray(self) -> Self {
+        let level = channel(1.0 - self.luma());
+        Self {
+            r: level,
+            g: level,
+            b: level,
+            a: self.a,
+        }
+    }
+
+    /// CSS notation: `#rrggbb`, or `rgba()` when translucent
+    #[must_use]
+    pub fn to_css(self) -> String {
+        if self.a >= 1.0 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+        }
+    }
+}
+
+/// Convert a 0.0-1.0 intensity to a channel value
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Apply `mode` to a color used as `paint`
+///
+/// In [`ColorMode::InkSaving`] backdrops are lightened by
+/// [`BACKDROP_LIGHTEN`], and light text, which was presumably set on a
+/// dark background, is inverted so it stays readable on the lightened one.
+#[must_use]
+pub fn convert(color: &str, mode: ColorMode, paint: Paint) -> String {
+    if mode == ColorMode::Color {
+        return color.to_string();
+    }
+    let Some(rgba) = Rgba::parse(color) else {
+        return color.to_string();
+    };
+
+    let gray = rgba.grayscale();
+    let converted = match (mode, paint) {
+        (ColorMode::InkSaving, Paint::Backdrop) => gray.lighten(BACKDROP_LIGHTEN),
+        (ColorMode::InkSaving, Paint::Ink) if gray.luma() > 0.6 => gray.inverted_gray(),
+        _ => gray,
+    };
+    converted.to_css()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Rgba::parse("#FF0000"), Some(Rgba::rgb(255, 0, 0)));
+        assert_eq!(Rgba::parse("4472C4"), Some(Rgba::rgb(0x44, 0x72, 0xc4)));
+        assert_eq!(Rgba::parse("#fff"), Some(Rgba::rgb(255, 255, 255)));
+        assert_eq!(Rgba::parse("rgb(1, 2, 3)"), Some(Rgba::rgb(1, 2, 3)));
+        assert_eq!(Rgba::parse("rgba(0, 0, 0, 0.5)").map(|c| c.a), Some(0.5));
+        assert_eq!(Rgba::parse("White"), Some(Rgba::rgb(255, 255, 255)));
+        assert_eq!(Rgba::parse("transparent"), None);
+        assert_eq!(Rgba::parse("#12345"), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert("#FF0000", ColorMode::Color, Paint::Ink), "#FF0000");
+        assert_eq!(
+            convert("#FF0000", ColorMode::Grayscale, Paint::Ink),
+            "#4c4c4c"
+        );
+        assert_eq!(
+            convert("inherit", ColorMode::Grayscale, Paint::Fill),
+            "inherit"
+        );
+
+        // Dark slide background becomes a pale gray, white text turns black
+        assert_eq!(
+            convert("#1F3864", ColorMode::InkSaving, Paint::Backdrop),
+            "#e1e1e1"
+        );
+        assert_eq!(
+            convert("#FFFFFF", ColorMode::InkSaving, Paint::Ink),
+            "#000000"
+        );
+        assert_eq!(
+            convert("#FFFFFF", ColorMode::Grayscale, Paint::Ink),
+            "#ffffff"
+        );
+    }
+}
@@ -0,0 +1,900 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # DOCX renderer
+//!
+//! Writes a document as a Word file. Content flows instead of keeping its
+//! page positions: text blocks become paragraphs, lists become numbered or
+//! bulleted paragraphs, tables become Word tables with their merged cells,
+//! and images become inline pictures at their original size, shrunk to fit
+//! the page. Each page of the source starts on a new page unless
+//! [`DocxConfig::page_breaks`] is off.
+//!
+//! Paragraph styles naming a heading (`Heading 2`, `Title`, ...) and text
+//! listed in [`DocumentStructure::headings`](prism_core::document::DocumentStructure)
+//! map to Word's built-in heading styles, so the navigation pane and tables
+//! of contents work. Run formatting and hyperlinks are kept; links to a page
+//! point at a bookmark placed where that page starts. Form fields are
+//! written as their label and value, and vector graphics are dropped.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::document::{
+    ContentBlock, Dimensions, Document, FormFieldBlock, FormFieldType, ImageBlock, Link, ListBlock,
+    ListMarker, TableBlock, TextBlock, TextDirection, TextRun, TextStyle,
+};
+use prism_core::error::Result;
+use prism_core::format::Format;
+use prism_core::render::{RenderContext, RenderFeature, Renderer, RendererMetadata};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+use crate::color::Rgba;
+use crate::filter::filter_document;
+use crate::html::pdf_payload;
+use crate::html::semantic::heading_level;
+use crate::ooxml::{self, escape, RELATIONSHIPS_NS, XML_DECLARATION};
+use crate::zip_writer::DeterministicZipWriter;
+
+/// `WordprocessingML` namespace
+const WORDML_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Page margin on every side, in points
+const MARGIN: f64 = 72.0;
+
+/// EMUs (drawing units) per point
+const EMU_PER_POINT: f64 = 12_700.0;
+
+/// Points per pixel of an image without a placed size (96 dpi)
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// Deepest list level Word numbers
+const MAX_LIST_LEVEL: u8 = 8;
+
+/// Paragraph holding a page break
+const PAGE_BREAK: &str = r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p>"#;
+
+/// Styles referenced by the writer: the heading and title styles, list
+/// paragraphs, bordered tables and hyperlinks
+const STYLES: &str = r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/><w:sz w:val="22"/><w:szCs w:val="22"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="259" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style><w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="0"/></w:pPr><w:rPr><w:sz w:val="56"/><w:szCs w:val="56"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720"/><w:contextualSpacing/></w:pPr></w:style><w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style><w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/></w:pPr><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#;
+
+/// Font sizes of heading levels 1 to 6, in half-points
+const HEADING_SIZES: [u32; 6] = [32, 26, 24, 22, 22, 22];
+
+/// DOCX renderer configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocxConfig {
+    /// Start each page of the document on a new page. Turn this off for
+    /// documents whose pages are an artifact of the source, such as
+    /// sheets or slides converted for reading.
+    pub page_breaks: bool,
+}
+
+impl Default for DocxConfig {
+    fn default() -> Self {
+        Self { page_breaks: true }
+    }
+}
+
+/// Renders documents as DOCX files
+#[derive(Debug, Clone, Default)]
+pub struct DocxRenderer {
+    config: DocxConfig,
+}
+
+impl DocxRenderer {
+    /// Create a renderer with the default configuration
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a renderer with custom configuration
+    #[must_use]
+    pub fn with_config(config: DocxConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write `document` as a Word file
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::RenderError` if the package cannot be written.
+    pub fn render_docx(&self, document: &Document) -> Result<Vec<u8>> {
+        let dimensions = document
+            .pages
+            .first()
+            .map_or(Dimensions::LETTER, |page| page.dimensions);
+        let mut writer = Writer::new(document, dimensions.width - 2.0 * MARGIN);
+        for (index, page) in document.pages.iter().enumerate() {
+            if index > 0 && self.config.page_breaks {
+                writer.body.push_str(PAGE_BREAK);
+            }
+            let _ = write!(
+                writer.body,
+                r#"<w:bookmarkStart w:id="{0}" w:name="page-{0}"/><w:bookmarkEnd w:id="{0}"/>"#,
+                page.number
+            );
+            writer.blocks(&page.content, page.number);
+        }
+        writer.finish(&dimensions)
+    }
+}
+
+/// A relationship of the main document part
+#[derive(Debug)]
+struct Relationship {
+    /// Type, relative to [`RELATIONSHIPS_NS`]
+    kind: &'static str,
+    target: String,
+    external: bool,
+}
+
+/// Builds the parts of a Word file
+struct Writer<'a> {
+    document: &'a Document,
+    /// Content of `w:body`
+    body: String,
+    /// Relationships after the styles (`rId1`) and numbering (`rId2`)
+    relationships: Vec<Relationship>,
+    /// Image files by part name
+    media: Vec<(String, &'a [u8])>,
+    /// Relationship IDs of the image resources written so far
+    images: HashMap<&'a str, String>,
+    /// Number format of each list, one numbering definition per list so
+    /// that every list counts from 1
+    lists: Vec<ListMarker>,
+    /// Last drawing object ID used
+    drawings: usize,
+    /// Widest an image may be, in points
+    content_width: f64,
+}
+
+impl<'a> Writer<'a> {
+    fn new(document: &'a Document, content_width: f64) -> Self {
+        Self {
+            document,
+            body: String::new(),
+            relationships: Vec::new(),
+            media: Vec::new(),
+            images: HashMap::new(),
+            lists: Vec::new(),
+            drawings: 0,
+            content_width,
+        }
+    }
+
+    /// Add a relationship of the document part, returning its ID
+    fn relationship(&mut self, kind: &'static str, target: String, external: bool) -> String {
+        self.relationships.push(Relationship {
+            kind,
+            target,
+            external,
+        });
+        format!("rId{}", self.relationships.len() + 2)
+    }
+
+    fn blocks(&mut self, blocks: &'a [ContentBlock], page: u32) {
+        for block in blocks {
+            match block {
+                ContentBlock::Text(text) => {
+                    let plain: String = text.runs.iter().map(|run| &*run.text).collect();
+                    if pdf_payload(&plain).is_none() {
+                        let style = paragraph_style(self.document, text, &plain, page);
+                        self.paragraph(text, style.as_deref(), None);
+                    }
+                }
+                ContentBlock::Image(image) => self.image(image),
+                ContentBlock::Table(table) => self.table(table, page),
+                ContentBlock::List(list) => self.list(list, page),
+                ContentBlock::FormField(field) => {
+                    let _ = write!(self.body, "<w:p>{}</w:p>", form_field_run(field));
+                }
+                ContentBlock::Container(container) => self.blocks(&container.children, page),
+                ContentBlock::Vector(_) => {}
+            }
+        }
+    }
+
+    /// Write a paragraph, optionally numbered as `(list, level)`
+    fn paragraph(
+        &mut self,
+        block: &TextBlock,
+        style: Option<&str>,
+        numbering: Option<(usize, u8)>,
+    ) {
+        self.body.push_str("<w:p>");
+        let rtl = block.direction == TextDirection::Rtl;
+        if style.is_some() || numbering.is_some() || rtl {
+            self.body.push_str("<w:pPr>");
+            if let Some(style) = style {
+                let _ = write!(self.body, r#"<w:pStyle w:val="{style}"/>"#);
+            }
+            if let Some((list, level)) = numbering {
+                let _ = write!(
+                    self.body,
+                    r#"<w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{list}"/></w:numPr>"#
+                );
+            }
+            if rtl {
+                self.body.push_str("<w:bidi/>");
+            }
+            self.body.push_str("</w:pPr>");
+        }
+        for run in &block.runs {
+            self.run(run);
+        }
+        self.body.push_str("</w:p>");
+    }
+
+    fn run(&mut self, run: &TextRun) {
+        let open = match &run.link {
+            None => {
+                self.body.push_str(&run_xml(&run.text, &run.style, false));
+                return;
+            }
+            Some(Link::Url(url)) => {
+                let id = self.relationship("hyperlink", url.clone(), true);
+                format!(r#"<w:hyperlink r:id="{id}">"#)
+            }
+            Some(Link::Anchor(anchor)) => {
+                format!(r#"<w:hyperlink w:anchor="{}">"#, escape(anchor))
+            }
+            Some(Link::Page(page)) => format!(r#"<w:hyperlink w:anchor="page-{page}">"#),
+        };
+        self.body.push_str(&open);
+        self.body.push_str(&run_xml(&run.text, &run.style, true));
+        self.body.push_str("</w:hyperlink>");
+    }
+
+    /// Write an image as an inline picture in a paragraph of its own
+    ///
+    /// Images that are not embedded, or in a format Word cannot show, are
+    /// replaced by their alt text.
+    fn image(&mut self, image: &ImageBlock) {
+        let document = self.document;
+        let resource = document
+            .resources
+            .images
+            .iter()
+            .find(|resource| resource.id == image.resource_id);
+        let embeddable = resource.and_then(|resource| {
+            let extension = image_extension(&resource.mime_type)?;
+            Some((resource, extension, resource.data.as_deref()?))
+        });
+        let Some((resource, extension, data)) = embeddable else {
+            if let Some(alt) = image.alt_text.as_deref().filter(|alt| !alt.is_empty()) {
+                let _ = write!(
+                    self.body,
+                    "<w:p>{}</w:p>",
+                    run_xml(alt, &TextStyle::default(), false)
+                );
+            }
+            return;
+        };
+
+        let id = if let Some(id) = self.images.get(resource.id.as_str()) {
+            id.clone()
+        } else {
+            let name = format!("media/image{}.{extension}", self.media.len() + 1);
+            self.media.push((name.clone(), data));
+            let id = self.relationship("image", name, false);
+            self.images.insert(&resource.id, id.clone());
+            id
+        };
+
+        let (mut width, mut height) = if image.bounds.width > 0.0 && image.bounds.height > 0.0 {
+            (image.bounds.width, image.bounds.height)
+        } else if let Some(size) = image.original_size {
+            (size.width, size.height)
+        } else {
+            (
+                f64::from(resource.width) * POINTS_PER_PIXEL,
+                f64::from(resource.height) * POINTS_PER_PIXEL,
+            )
+        };
+        if width > self.content_width && self.content_width > 0.0 {
+            height *= self.content_width / width;
+            width = self.content_width;
+        }
+        self.drawings += 1;
+        let (cx, cy) = (emu(width), emu(height));
+        let number = self.drawings;
+        let alt = escape(image.alt_text.as_deref().unwrap_or_default());
+        let _ = write!(
+            self.body,
+            r#"<w:p><w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{number}" name="Picture {number}" descr="{alt}"/><wp:cNvGraphicFramePr><a:graphicFrameLocks noChangeAspect="1"/></wp:cNvGraphicFramePr><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{number}" name="Picture {number}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#
+        );
+    }
+
+    /// Write a table, turning row spans into vertically merged cells
+    fn table(&mut self, table: &'a TableBlock, page: u32) {
+        let columns = table.column_count.max(1);
+        let width = if table.bounds.width > 0.0 {
+            table.bounds.width
+        } else {
+            self.content_width
+        };
+        let column_width = width / f64::from(u32::try_from(columns).unwrap_or(u32::MAX));
+        let _ = write!(
+            self.body,
+            r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="{}" w:type="dxa"/><w:tblLook w:val="04A0"/></w:tblPr><w:tblGrid>"#,
+            twips(width)
+        );
+        for _ in 0..columns {
+            let _ = write!(self.body, r#"<w:gridCol w:w="{}"/>"#, twips(column_width));
+        }
+        self.body.push_str("</w:tblGrid>");
+
+        // Cells continuing a row span, by grid column: rows left and width
+        let mut spans: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+        for row in &table.rows {
+            self.body.push_str("<w:tr>");
+            if let Some(height) = row.height.filter(|height| *height > 0.0) {
+                let _ = write!(
+                    self.body,
+                    r#"<w:trPr><w:trHeight w:val="{}"/></w:trPr>"#,
+                    twips(height)
+                );
+            }
+            let mut column = 0;
+            for cell in &row.cells {
+                merged_cells(&mut self.body, &mut spans, &mut column, false);
+                let rows = cell.row_span.max(1);
+                let columns = cell.col_span.max(1);
+                self.body.push_str("<w:tc><w:tcPr>");
+                if columns > 1 {
+                    let _ = write!(self.body, r#"<w:gridSpan w:val="{columns}"/>"#);
+                }
+                if rows > 1 {
+                    self.body.push_str(r#"<w:vMerge w:val="restart"/>"#);
+                    spans.insert(column, (rows - 1, columns));
+                }
+                if let Some(fill) = cell.background_color.as_deref().and_then(hex) {
+                    let _ = write!(
+                        self.body,
+                        r#"<w:shd w:val="clear" w:color="auto" w:fill="{fill}"/>"#
+                    );
+                }
+                self.body.push_str("</w:tcPr>");
+                self.blocks(&cell.content, page);
+                // A cell must end with a paragraph
+                if !self.body.ends_with("</w:p>") {
+                    self.body.push_str("<w:p/>");
+                }
+                self.body.push_str("</w:tc>");
+                column += columns;
+            }
+            merged_cells(&mut self.body, &mut spans, &mut column, true);
+            if self.body.ends_with("<w:tr>") || self.body.ends_with("</w:trPr>") {
+                self.body.push_str("<w:tc><w:p/></w:tc>");
+            }
+            self.body.push_str("</w:tr>");
+        }
+        self.body.push_str("</w:tbl>");
+    }
+
+    /// Write a list as numbered paragraphs
+    ///
+    /// The first text block of an item carries the number; any other
+    /// blocks follow it unnumbered.
+    fn list(&mut self, list: &'a ListBlock, page: u32) {
+        let mut ids: Vec<(ListMarker, usize)> = Vec::new();
+        for item in &list.items {
+            let list_id = if let Some((_, id)) =
+                ids.iter().find(|(marker, _)| *marker == item.marker_style)
+            {
+                *id
+            } else {
+                self.lists.push(item.marker_style);
+                ids.push((item.marker_style, self.lists.len()));
+                self.lists.len()
+            };
+            let level = item.level.min(MAX_LIST_LEVEL);
+
+            let mut numbered = false;
+            for block in &item.content {
+                match block {
+                    ContentBlock::Text(text) if !numbered => {
+                        self.paragraph(text, Some("ListParagraph"), Some((list_id, level)));
+                        numbered = true;
+                    }
+                    block => self.blocks(std::slice::from_ref(block), page),
+                }
+            }
+            if !numbered {
+                let _ = write!(
+                    self.body,
+                    r#"<w:p><w:pPr><w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{list_id}"/></w:numPr></w:pPr></w:p>"#
+                );
+            }
+        }
+    }
+
+    /// Package the parts
+    fn finish(self, dimensions: &Dimensions) -> Result<Vec<u8>> {
+        let mut zip = DeterministicZipWriter::new().with_leading(["[Content_Types].xml"]);
+        zip.add("[Content_Types].xml", self.content_types());
+        zip.add(
+            "_rels/.rels",
+            ooxml::package_relationships("word/document.xml"),
+        );
+        zip.add(
+            ooxml::CORE_PROPERTIES_PART,
+            ooxml::core_properties(&self.document.metadata),
+        );
+        zip.add(
+            "word/document.xml",
+            format!(
+                r#"{XML_DECLARATION}<w:document xmlns:w="{WORDML_NS}" xmlns:r="{RELATIONSHIPS_NS}" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><w:body>{}{}</w:body></w:document>"#,
+                self.body,
+                section_properties(dimensions)
+            ),
+        );
+        zip.add("word/styles.xml", styles());
+        if !self.lists.is_empty() {
+            zip.add("word/numbering.xml", numbering(&self.lists));
+        }
+        zip.add(
+            "word/_rels/document.xml.rels",
+            self.document_relationships(),
+        );
+        for (name, data) in &self.media {
+            zip.add(format!("word/{name}"), *data);
+        }
+        zip.finish()
+    }
+
+    fn content_types(&self) -> String {
+        let mut xml = format!(
+            r#"{XML_DECLARATION}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/>"#
+        );
+        let mut extensions: Vec<&str> = self
+            .media
+            .iter()
+            .filter_map(|(name, _)| name.rsplit_once('.').map(|(_, extension)| extension))
+            .collect();
+        extensions.sort_unstable();
+        extensions.dedup();
+        for extension in extensions {
+            let _ = write!(
+                xml,
+                r#"<Default Extension="{extension}" ContentType="image/{extension}"/>"#
+            );
+        }
+        let _ = write!(
+            xml,
+            r#"<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/{}" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>"#,
+            ooxml::CORE_PROPERTIES_PART
+        );
+        if !self.lists.is_empty() {
+            xml.push_str(r#"<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>"#);
+        }
+        xml.push_str("</Types>");
+        xml
+    }
+
+    fn document_relationships(&self) -> String {
+        let mut xml = format!(
+            r#"{XML_DECLARATION}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="{RELATIONSHIPS_NS}/styles" Target="styles.xml"/>"#
+        );
+        if !self.lists.is_empty() {
+            let _ = write!(
+                xml,
+                r#"<Relationship Id="rId2" Type="{RELATIONSHIPS_NS}/numbering" Target="numbering.xml"/>"#
+            );
+        }
+        for (index, relationship) in self.relationships.iter().enumerate() {
+            let _ = write!(
+                xml,
+                r#"<Relationship Id="rId{}" Type="{RELATIONSHIPS_NS}/{}" Target="{}"{}/>"#,
+                index + 3,
+                relationship.kind,
+                escape(&relationship.target),
+                if relationship.external {
+                    r#" TargetMode="External""#
+                } else {
+                    ""
+                }
+            );
+        }
+        xml.push_str("</Relationships>");
+        xml
+    }
+}
+
+/// Word style for a paragraph: `Title`, `Heading1`..`Heading6` or none
+fn paragraph_style(
+    document: &Document,
+    block: &TextBlock,
+    text: &str,
+    page: u32,
+) -> Option<String> {
+    let style = block.paragraph_style.as_deref().unwrap_or_default();
+    if style.trim().eq_ignore_ascii_case("title") {
+        return Some("Title".to_string());
+    }
+    let text = text.trim();
+    heading_level(style)
+        .or_else(|| {
+            document
+                .structure
+                .headings
+                .iter()
+                .find(|heading| heading.page == page && heading.text.trim() == text)
+                .map(|heading| heading.level.clamp(1, 6))
+        })
+        .map(|level| format!("Heading{level}"))
+}
+
+/// A run, with line breaks and tabs as Word elements
+fn run_xml(text: &str, style: &TextStyle, linked: bool) -> String {
+    let mut xml = String::from("<w:r>");
+    let properties = run_properties(style, linked);
+    if !properties.is_empty() {
+        let _ = write!(xml, "<w:rPr>{properties}</w:rPr>");
+    }
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            xml.push_str("<w:br/>");
+        }
+        for (index, part) in line.split('\t').enumerate() {
+            if index > 0 {
+                xml.push_str("<w:tab/>");
+            }
+            if !part.is_empty() {
+                let _ = write!(xml, r#"<w:t xml:space="preserve">{}</w:t>"#, escape(part));
+            }
+        }
+    }
+    xml.push_str("</w:r>");
+    xml
+}
+
+/// Content of `w:rPr`, in schema order
+fn run_properties(style: &TextStyle, linked: bool) -> String {
+    let mut xml = String::new();
+    if linked {
+        xml.push_str(r#"<w:rStyle w:val="Hyperlink"/>"#);
+    }
+    if let Some(family) = style.font_family.as_deref() {
+        let family = escape(family);
+        let _ = write!(
+            xml,
+            r#"<w:rFonts w:ascii="{family}" w:hAnsi="{family}" w:cs="{family}"/>"#
+        );
+    }
+    for (on, tag) in [
+        (style.bold, "<w:b/><w:bCs/>"),
+        (style.italic, "<w:i/><w:iCs/>"),
+        (style.strikethrough, "<w:strike/>"),
+    ] {
+        if on {
+            xml.push_str(tag);
+        }
+    }
+    if let Some(color) = style.color.as_deref().and_then(hex) {
+        let _ = write!(xml, r#"<w:color w:val="{color}"/>"#);
+    }
+    if let Some(size) = style.font_size.filter(|size| *size > 0.0) {
+        let half_points = format!("{:.0}", size * 2.0);
+        let _ = write!(
+            xml,
+            r#"<w:sz w:val="{half_points}"/><w:szCs w:val="{half_points}"/>"#
+        );
+    }
+    if style.underline {
+        xml.push_str(r#"<w:u w:val="single"/>"#);
+    }
+    if let Some(fill) = style.background_color.as_deref().and_then(hex) {
+        let _ = write!(
+            xml,
+            r#"<w:shd w:val="clear" w:color="auto" w:fill="{fill}"/>"#
+        );
+    }
+    if let Some(language) = style.language.as_deref() {
+        let _ = write!(xml, r#"<w:lang w:val="{}"/>"#, escape(language));
+    }
+    xml
+}
+
+/// A form field as a run of text: its label and value, or a ballot box
+/// for checkboxes and radio buttons
+fn form_field_run(field: &FormFieldBlock) -> String {
+    let label = field.label.as_deref().unwrap_or(&field.name);
+    let text = match field.field_type {
+        FormFieldType::Checkbox | FormFieldType::Radio => {
+            format!("{} {label}", if field.checked { '☒' } else { '☐' })
+        }
+        _ => match field.value.as_deref() {
+            Some(value) => format!("{label}: {value}"),
+            None => format!("{label}:"),
+        },
+    };
+    run_xml(&text, &TextStyle::default(), false)
+}
+
+/// Write the cells continuing row spans at `column`, or with `rest` all
+/// remaining ones, padding any gap before them with an empty cell
+fn merged_cells(
+    xml: &mut String,
+    spans: &mut BTreeMap<usize, (usize, usize)>,
+    column: &mut usize,
+    rest: bool,
+) {
+    loop {
+        let next = if rest {
+            spans.range(*column..).next()
+        } else {
+            spans.get_key_value(column)
+        };
+        let Some((&start, &(rows, width))) = next else {
+            return;
+        };
+        if start > *column {
+            let _ = write!(
+                xml,
+                r#"<w:tc><w:tcPr><w:gridSpan w:val="{}"/></w:tcPr><w:p/></w:tc>"#,
+                start - *column
+            );
+        }
+        xml.push_str("<w:tc><w:tcPr>");
+        if width > 1 {
+            let _ = write!(xml, r#"<w:gridSpan w:val="{width}"/>"#);
+        }
+        xml.push_str("<w:vMerge/></w:tcPr><w:p/></w:tc>");
+        if rows > 1 {
+            spans.insert(start, (rows - 1, width));
+        } else {
+            spans.remove(&start);
+        }
+        *column = start + width;
+    }
+}
+
+/// `w:sectPr` for a page size with one-inch margins
+fn section_properties(dimensions: &Dimensions) -> String {
+    let orientation = if dimensions.width > dimensions.height {
+        r#" w:orient="landscape""#
+    } else {
+        ""
+    };
+    let margin = twips(MARGIN);
+    format!(
+        r#"<w:sectPr><w:pgSz w:w="{}" w:h="{}"{orientation}/><w:pgMar w:top="{margin}" w:right="{margin}" w:bottom="{margin}" w:left="{margin}" w:header="720" w:footer="720" w:gutter="0"/></w:sectPr>"#,
+        twips(dimensions.width),
+        twips(dimensions.height)
+    )
+}
+
+fn styles() -> String {
+    let mut xml = format!(r#"{XML_DECLARATION}<w:styles xmlns:w="{WORDML_NS}">{STYLES}"#);
+    for (index, size) in HEADING_SIZES.iter().enumerate() {
+        let level = index + 1;
+        let _ = write!(
+            xml,
+            r#"<w:style w:type="paragraph" w:styleId="Heading{level}"><w:name w:val="heading {level}"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="{index}"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="{size}"/><w:szCs w:val="{size}"/></w:rPr></w:style>"#
+        );
+    }
+    xml.push_str("</w:styles>");
+    xml
+}
+
+/// `word/numbering.xml` with one definition per list
+fn numbering(lists: &[ListMarker]) -> String {
+    let mut xml = format!(r#"{XML_DECLARATION}<w:numbering xmlns:w="{WORDML_NS}">"#);
+    for (index, marker) in lists.iter().enumerate() {
+        let format = match marker {
+            ListMarker::Bullet => "bullet",
+            ListMarker::Decimal => "decimal",
+            ListMarker::LowerAlpha => "lowerLetter",
+            ListMarker::UpperAlpha => "upperLetter",
+            ListMarker::LowerRoman => "lowerRoman",
+            ListMarker::UpperRoman => "upperRoman",
+            ListMarker::None => "none",
+        };
+        let _ = write!(
+            xml,
+            r#"<w:abstractNum w:abstractNumId="{index}"><w:multiLevelType w:val="hybridMultilevel"/>"#
+        );
+        for level in 0..=MAX_LIST_LEVEL {
+            let text = match marker {
+                ListMarker::Bullet => "•".to_string(),
+                ListMarker::None => String::new(),
+                _ => format!("%{}.", level + 1),
+            };
+            let _ = write!(
+                xml,
+                r#"<w:lvl w:ilvl="{level}"><w:start w:val="1"/><w:numFmt w:val="{format}"/><w:lvlText w:val="{text}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{}" w:hanging="360"/></w:pPr></w:lvl>"#,
+                720 * (u32::from(level) + 1)
+            );
+        }
+        xml.push_str("</w:abstractNum>");
+    }
+    for index in 0..lists.len() {
+        let _ = write!(
+            xml,
+            r#"<w:num w:numId="{}"><w:abstractNumId w:val="{index}"/></w:num>"#,
+            index + 1
+        );
+    }
+    xml.push_str("</w:numbering>");
+    xml
+}
+
+/// File extension of an image format Word displays
+fn image_extension(mime_type: &str) -> Option<&'static str> {
+    match mime_type {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpeg"),
+        "image/gif" => Some("gif"),
+        "image/bmp" => Some("bmp"),
+        "image/tiff" => Some("tiff"),
+        _ => None,
+    }
+}
+
+/// A color as `RRGGBB` hex
+fn hex(color: &str) -> Option<String> {
+    let color = Rgba::parse(color)?;
+    Some(format!("{:02X}{:02X}{:02X}", color.r, color.g, color.b))
+}
+
+/// Points as twentieths of a point
+fn twips(points: f64) -> String {
+    format!("{:.0}", points * 20.0)
+}
+
+/// Points as EMUs
+fn emu(points: f64) -> String {
+    format!("{:.0}", points * EMU_PER_POINT)
+}
+
+#[async_trait]
+impl Renderer for DocxRenderer {
+    fn output_format(&self) -> Format {
+        Format::docx()
+    }
+
+    async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes> {
+        let docx = if context.options.content.is_all() {
+            self.render_docx(document)?
+        } else {
+            self.render_docx(&filter_document(document, &context.options.content))?
+        };
+        Ok(Bytes::from(docx))
+    }
+
+    /// Vector graphics have no Word equivalent
+    fn supports(&self, block: &ContentBlock) -> bool {
+        !matches!(block, ContentBlock::Vector(_))
+    }
+
+    fn metadata(&self) -> RendererMetadata {
+        RendererMetadata {
+            name: "DOCX Renderer".to_string(),
+            version: crate::VERSION.to_string(),
+            features: vec![
+                RenderFeature::TextRendering,
+                RenderFeature::ImageRendering,
+                RenderFeature::TableRendering,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{
+        ImageResource, ListItem, Page, Rect, ShapeStyle, TableCell, TableRow,
+    };
+    use std::io::{Cursor, Read};
+
+    fn text(text: &str) -> TextBlock {
+        let mut block = TextBlock::new(Rect::default());
+        block.add_run(TextRun::new(text));
+        block
+    }
+
+    fn cell(content: &str, col_span: usize, row_span: usize) -> TableCell {
+        TableCell {
+            content: vec![ContentBlock::Text(text(content))],
+            col_span,
+            row_span,
+            background_color: None,
+        }
+    }
+
+    fn part(docx: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+        let mut xml = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        xml
+    }
+
+    #[test]
+    fn test_render_docx() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        let mut heading = text("Results");
+        heading.paragraph_style = Some("Heading 2".to_string());
+        page.add_content(ContentBlock::Text(heading));
+
+        let mut paragraph = text("Bold & ");
+        paragraph.runs[0].style.bold = true;
+        let mut link = TextRun::new("linked");
+        link.link = Some(Link::Url("https://example.com/?a=1&b=2".to_string()));
+        paragraph.add_run(link);
+        page.add_content(ContentBlock::Text(paragraph));
+
+        let mut list = ListBlock::new(Rect::default());
+        list.add_item(ListItem::bullet(vec![ContentBlock::Text(text("One"))], 0));
+        list.add_item(ListItem::bullet(vec![ContentBlock::Text(text("Two"))], 1));
+        page.add_content(ContentBlock::List(list));
+
+        let mut table = TableBlock::new(Rect::default(), 2);
+        table.add_row(TableRow {
+            cells: vec![cell("Tall", 1, 2), cell("Top", 1, 1)],
+            height: None,
+        });
+        table.add_row(TableRow {
+            cells: vec![cell("Bottom", 1, 1)],
+            height: None,
+        });
+        page.add_content(ContentBlock::Table(table));
+
+        page.add_content(ContentBlock::Image(ImageBlock {
+            bounds: Rect::new(0.0, 0.0, 144.0, 72.0),
+            resource_id: "logo".to_string(),
+            alt_text: Some("Logo".to_string()),
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }));
+        let document = Document::builder()
+            .page(page)
+            .resource(ImageResource {
+                id: "logo".to_string(),
+                mime_type: "image/png".to_string(),
+                data: Some(vec![0x89, b'P', b'N', b'G']),
+                url: None,
+                width: 192,
+                height: 96,
+            })
+            .add_text_page("Next", "Second page.")
+            .build();
+
+        let docx = DocxRenderer::new().render_docx(&document).unwrap();
+        let body = part(&docx, "word/document.xml");
+        assert!(body.contains(r#"<w:pStyle w:val="Heading2"/>"#));
+        assert!(body.contains(
+            r#"<w:rPr><w:b/><w:bCs/></w:rPr><w:t xml:space="preserve">Bold &amp; </w:t>"#
+        ));
+        assert!(
+            body.contains(r#"<w:hyperlink r:id="rId3"><w:r><w:rPr><w:rStyle w:val="Hyperlink"/>"#)
+        );
+        assert!(body.contains(r#"<w:numPr><w:ilvl w:val="1"/><w:numId w:val="1"/></w:numPr>"#));
+        assert!(body.contains(r#"<w:vMerge w:val="restart"/>"#));
+        // The continuation of "Tall" comes before "Bottom"
+        let continuation = body.find("<w:vMerge/>").unwrap();
+        assert!(continuation < body.find("Bottom").unwrap());
+        assert!(body.contains(r#"<wp:extent cx="1828800" cy="914400"/>"#));
+        assert!(body.contains(r#"<a:blip r:embed="rId4"/>"#));
+        assert_eq!(body.matches(PAGE_BREAK).count(), 1);
+
+        let relationships = part(&docx, "word/_rels/document.xml.rels");
+        assert!(relationships
+            .contains(r#"Target="https://example.com/?a=1&amp;b=2" TargetMode="External""#));
+        assert!(relationships.contains(r#"Target="media/image1.png""#));
+        let mut archive = zip::ZipArchive::new(Cursor::new(&docx)).unwrap();
+        assert_eq!(archive.by_name("word/media/image1.png").unwrap().size(), 4);
+        assert!(part(&docx, "word/numbering.xml").contains(r#"<w:numFmt w:val="bullet"/>"#));
+        assert!(part(&docx, "[Content_Types].xml").contains(r#"<Default Extension="png""#));
+    }
+}
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Content filtering
+//!
+//! Strips categories of content ([`ContentKind`]) from a document before it
+//! is rendered, so the same UDM can produce a full rendition, a text-only
+//! proof or an image-only exhibit set. Renderers apply [`filter_document`]
+//! up front and never see the excluded content.
+//!
+//! Tables and containers are descended into. A table keeps its rows and
+//! cells even when some cells end up empty, so the grid stays intact; it is
+//! dropped only once no cell has any content left. List items and
+//! containers are dropped once they have no content left, and lists once
+//! they have no items.
+//!
+//! [`select_pages`] likewise drops the pages outside a requested
+//! [`PageRange`], and [`crop_sheet`] the cells of a worksheet outside the
+//! areas to render.
+
+use prism_core::document::{CellRange, ContentBlock, Document, Page, TableBlock};
+use prism_core::render::{ContentFilter, ContentKind, PageRange};
+
+/// Return `document` with the content excluded by `filter` removed
+#[must_use]
+pub fn filter_document(document: &Document, filter: &ContentFilter) -> Document {
+    let mut document = document.clone();
+    for page in &mut document.pages {
+        filter_page(page, filter);
+    }
+    document
+}
+
+/// Return `document` with only the pages in `range`
+#[must_use]
+pub fn select_pages(document: &Document, range: &PageRange) -> Document {
+    let mut document = document.clone();
+    document.pages.retain(|page| range.includes(page.number));
+    document
+}
+
+/// Return the page of a worksheet with its table cropped to `areas`, one
+/// table per area, stacked in order
+///
+/// Cells are taken by position, as worksheet tables have no spans.
+#[must_use]
+pub fn crop_sheet(page: &Page, areas: &[CellRange]) -> Page {
+    let mut page = page.clone();
+    let content = std::mem::take(&mut page.content);
+    for block in content {
+        let ContentBlock::Table(table) = block else {
+            page.content.push(block);
+            continue;
+        };
+        let mut y = table.bounds.y;
+        for area in areas {
+            let mut cropped = TableBlock::new(table.bounds, 0);
+            cropped.style = table.style.clone();
+            cropped.rotation = table.rotation;
+            for row in table
+                .rows
+                .iter()
+                .skip(area.first_row)
+                .take(area.last_row + 1 - area.first_row)
+            {
+                let mut row = row.clone();
+                row.cells = row
+                    .cells
+                    .into_iter()
+                    .skip(area.first_column)
+                    .take(area.last_column + 1 - area.first_column)
+                    .collect();
+                cropped.column_count = cropped.column_count.max(row.cells.len());
+                cropped.rows.push(row);
+            }
+            if cropped.rows.is_empty() || cropped.column_count == 0 {
+                continue;
+            }
+            // Keep the size of a cell as it was
+            cropped.bounds.y = y;
+            cropped.bounds.width *= ratio(cropped.column_count, table.column_count);
+            cropped.bounds.height *= ratio(cropped.rows.len(), table.rows.len());
+            y += cropped.bounds.height;
+            page.content.push(ContentBlock::Table(cropped));
+        }
+    }
+    page
+}
+
+/// The smallest range holding every cell of `table` with content, or
+/// `None` if it has none
+#[must_use]
+pub fn used_range(table: &TableBlock) -> Option<CellRange> {
+    let mut used: Option<CellRange> = None;
+    for (row_index, row) in table.rows.iter().enumerate() {
+        for (column, cell) in row.cells.iter().enumerate() {
+            if cell.content.is_empty() {
+                continue;
+            }
+            let range = used.get_or_insert(CellRange {
+                first_row: row_index,
+                first_column: column,
+                last_row: row_index,
+                last_column: column,
+            });
+            range.first_column = range.first_column.min(column);
+            range.last_column = range.last_column.max(column);
+            range.last_row = row_index;
+        }
+    }
+    used
+}
+
+/// `part` as a fraction of `whole`
+fn ratio(part: usize, whole: usize) -> f64 {
+    let count = |n: usize| f64::from(u32::try_from(n).unwrap_or(u32::MAX));
+    if whole == 0 {
+        1.0
+    } else {
+        count(part) / count(whole)
+    }
+}
+
+/// Remove excluded content from a single page in place
+pub fn filter_page(page: &mut Page, filter: &ContentFilter) {
+    filter_blocks(&mut page.content, filter);
+    if !filter.includes(ContentKind::Annotations) {
+        page.annotations.clear();
+    }
+}
+
+fn filter_blocks(blocks: &mut Vec<ContentBlock>, filter: &ContentFilter) {
+    blocks.retain_mut(|block| match block {
+        ContentBlock::Text(_) => filter.includes(ContentKind::Text),
+        ContentBlock::Image(_) => filter.includes(ContentKind::Images),
+        ContentBlock::Vector(_) => filter.includes(ContentKind::Vectors),
+        // Interactive widgets, like links and comments
+        ContentBlock::FormField(_) => filter.includes(ContentKind::Annotations),
+        ContentBlock::Table(table) => {
+            let mut has_content = false;
+            for cell in table.rows.iter_mut().flat_map(|row| &mut row.cells) {
+                filter_blocks(&mut cell.content, filter);
+                has_content |= !cell.content.is_empty();
+            }
+            has_content
+        }
+        ContentBlock::List(list) => {
+            list.items.retain_mut(|item| {
+                filter_blocks(&mut item.content, filter);
+                !item.content.is_empty()
+            });
+            !list.items.is_empty()
+        }
+        ContentBlock::Container(container) => {
+            let had_children = !container.children.is_empty();
+            filter_blocks(&mut container.children, filter);
+            !had_children || !container.children.is_empty()
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{
+        Dimensions, ImageBlock, Rect, ShapeStyle, TableBlock, TableCell, TableRow, TextBlock,
+        TextRun,
+    };
+
+    fn text(value: &str) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::default());
+        block.add_run(TextRun::new(value));
+        ContentBlock::Text(block)
+    }
+
+    fn image() -> ContentBlock {
+        ContentBlock::Image(ImageBlock {
+            bounds: Rect::default(),
+            resource_id: "rId1".to_string(),
+            alt_text: None,
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        })
+    }
+
+    fn table(cells: Vec<Vec<ContentBlock>>) -> ContentBlock {
+        ContentBlock::Table(TableBlock {
+            bounds: Rect::default(),
+            rows: vec![TableRow {
+                cells: cells
+                    .into_iter()
+                    .map(|content| TableCell {
+                        content,
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    })
+                    .collect(),
+                height: None,
+            }],
+            column_count: 2,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+        })
+    }
+
+    fn page() -> Page {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(text("caption"));
+        page.add_content(image());
+        page.add_content(table(vec![vec![text("cell")], vec![image()]]));
+        page.add_content(table(vec![vec![text("a")], vec![text("b")]]));
+        page
+    }
+
+    fn kinds(blocks: &[ContentBlock]) -> Vec<&'static str> {
+        blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text(_) => "text",
+                ContentBlock::Image(_) => "image",
+                ContentBlock::Table(_) => "table",
+                ContentBlock::List(_) => "list",
+                ContentBlock::FormField(_) => "form field",
+                ContentBlock::Vector(_) => "vector",
+                ContentBlock::Container(_) => "container",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_only() {
+        let mut page = page();
+        filter_page(&mut page, &ContentFilter::text_only());
+        assert_eq!(kinds(&page.content), ["text", "table", "table"]);
+        let ContentBlock::Table(table) = &page.content[1] else {
+            unreachable!()
+        };
+        assert!(table.rows[0].cells[1].content.is_empty());
+    }
+
+    #[test]
+    fn test_images_only() {
+        let mut page = page();
+        filter_page(&mut page, &ContentFilter::images_only());
+        // The all-text table is gone, the mixed one keeps its image
+        assert_eq!(kinds(&page.content), ["image", "table"]);
+    }
+
+    #[test]
+    fn test_crop_sheet() {
+        let mut page = Page::new(1, Dimensions::LETTER);
+        let mut grid = TableBlock::new(Rect::new(0.0, 0.0, 300.0, 60.0), 3);
+        for values in [["", "", ""], ["", "a", "b"], ["", "c", ""]] {
+            grid.add_row(TableRow {
+                cells: values
+                    .into_iter()
+                    .map(|value| TableCell {
+                        content: if value.is_empty() {
+                            Vec::new()
+                        } else {
+                            vec![text(value)]
+                        },
+                        col_span: 1,
+                        row_span: 1,
+                        background_color: None,
+                    })
+                    .collect(),
+                height: None,
+            });
+        }
+        let used = used_range(&grid).unwrap();
+        assert_eq!(
+            (
+                used.first_row,
+                used.first_column,
+                used.last_row,
+                used.last_column
+            ),
+            (1, 1, 2, 2)
+        );
+        page.add_content(ContentBlock::Table(grid));
+
+        let cropped = crop_sheet(&page, &[used]);
+        let ContentBlock::Table(table) = &cropped.content[0] else {
+            unreachable!()
+        };
+        assert_eq!(table.column_count, 2);
+        assert_eq!(table.rows[0].cells[0].extract_text(), "a");
+        assert_eq!(table.rows[1].cells[0].extract_text(), "c");
+        assert!((table.bounds.width - 200.0).abs() < 1e-9);
+        assert!((table.bounds.height - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_select_pages() {
+        let document = Document::builder()
+            .add_text_page("One", "1")
+            .add_text_page("Two", "2")
+            .add_text_page("Three", "3")
+            .build();
+        let selected = select_pages(&document, &"1,3".parse().unwrap());
+        let numbers: Vec<u32> = selected.pages.iter().map(|page| page.number).collect();
+        assert_eq!(numbers, [1, 3]);
+    }
+}
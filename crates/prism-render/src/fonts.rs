@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Fonts
+//!
+//! Resolves the font families a document references to something a
+//! renderer can actually use:
+//!
+//! 1. Fonts whose data travels with the document ([`FontResource::data`])
+//!    are embedded, e.g. as `@font-face` rules in HTML.
+//! 2. Common Office and PDF base fonts are mapped to freely available
+//!    metric-compatible substitutes (Calibri → Carlito, Arial → Liberation
+//!    Sans, ...), so text keeps its line breaks.
+//! 3. Everything else falls back to a generic family.
+//!
+//! Cases 2 and 3 are reported as [`FontSubstitution`]s.
+
+use prism_core::document::{ContentBlock, Document, FontResource};
+use prism_core::render::FontSubstitution;
+use std::collections::BTreeSet;
+
+/// Metric-compatible substitutes for widely used proprietary fonts
+const METRIC_COMPATIBLE: &[(&str, &str)] = &[
+    ("arial", "Liberation Sans"),
+    ("arial narrow", "Liberation Sans Narrow"),
+    ("calibri", "Carlito"),
+    ("cambria", "Caladea"),
+    ("courier", "Liberation Mono"),
+    ("courier new", "Liberation Mono"),
+    ("georgia", "Gelasio"),
+    ("helvetica", "Liberation Sans"),
+    ("segoe ui", "Selawik"),
+    ("times", "Liberation Serif"),
+    ("times new roman", "Liberation Serif"),
+];
+
+/// Families that fall back to `serif` or `monospace` rather than
+/// `sans-serif`
+const SERIF: &[&str] = &[
+    "book antiqua",
+    "cambria",
+    "garamond",
+    "georgia",
+    "palatino",
+    "palatino linotype",
+    "times",
+    "times new roman",
+];
+const MONOSPACE: &[&str] = &[
+    "consolas",
+    "courier",
+    "courier new",
+    "lucida console",
+    "menlo",
+];
+
+/// Leading bytes of the font formats that can be embedded in web output
+const WEB_FONT_MAGIC: &[&[u8]] = &[b"\x00\x01\x00\x00", b"true", b"OTTO", b"wOFF", b"wOF2"];
+
+/// A font whose data is available for embedding
+#[derive(Debug, Clone, Copy)]
+pub struct FontFace<'a> {
+    /// Position in [`ResourceStore::fonts`](prism_core::document::ResourceStore)
+    pub index: usize,
+    /// Font family name
+    pub family: &'a str,
+    /// CSS font weight (100-900)
+    pub weight: u16,
+    /// Whether this is an italic or oblique face
+    pub italic: bool,
+    /// Raw font file
+    pub data: &'a [u8],
+}
+
+impl FontFace<'_> {
+    /// MIME type and file extension, sniffed from the data
+    #[must_use]
+    pub fn file_type(&self) -> (&'static str, &'static str) {
+        match self.data.get(..4) {
+            Some(b"wOFF") => ("font/woff", "woff"),
+            Some(b"wOF2") => ("font/woff2", "woff2"),
+            Some(b"OTTO") => ("font/otf", "otf"),
+            _ => ("font/ttf", "ttf"),
+        }
+    }
+
+    /// CSS `format()` hint for the data
+    #[must_use]
+    pub fn css_format(&self) -> &'static str {
+        match self.file_type().1 {
+            "woff" => "woff",
+            "woff2" => "woff2",
+            "otf" => "opentype",
+            _ => "truetype",
+        }
+    }
+}
+
+/// Font resolution for one document
+#[derive(Debug)]
+pub struct FontManager<'a> {
+    faces: Vec<FontFace<'a>>,
+    families: BTreeSet<String>,
+}
+
+impl<'a> FontManager<'a> {
+    /// Collect embeddable fonts and referenced families from a document
+    #[must_use]
+    pub fn new(document: &'a Document) -> Self {
+        let faces = document
+            .resources
+            .fonts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, font)| face(index, font))
+            .collect();
+
+        let mut families = BTreeSet::new();
+        for page in &document.pages {
+            collect_families(&page.content, &mut families);
+        }
+
+        Self { faces, families }
+    }
+
+    /// Fonts that can be embedded, in resource order
+    #[must_use]
+    pub fn embedded(&self) -> &[FontFace<'a>] {
+        &self.faces
+    }
+
+    /// Whether data for `family` is embedded
+    #[must_use]
+    pub fn is_embedded(&self, family: &str) -> bool {
+        self.faces
+            .iter()
+            .any(|face| face.family.eq_ignore_ascii_case(family))
+    }
+
+    /// Every referenced family that is not embedded, with its replacement
+    #[must_use]
+    pub fn substitutions(&self) -> Vec<FontSubstitution> {
+        self.families
+            .iter()
+            .filter(|family| !self.is_embedded(family))
+            .map(|family| match metric_compatible(family) {
+                Some(substitute) => FontSubstitution {
+                    requested: family.clone(),
+                    substitute: substitute.to_string(),
+                    metric_compatible: true,
+                },
+                None => FontSubstitution {
+                    requested: family.clone(),
+                    substitute: generic_family(family).to_string(),
+                    metric_compatible: false,
+                },
+            })
+            .collect()
+    }
+}
+
+/// Metric-compatible substitute for a family, if one is known
+#[must_use]
+pub fn metric_compatible(family: &str) -> Option<&'static str> {
+    let family = family.trim().to_ascii_lowercase();
+    METRIC_COMPATIBLE
+        .iter()
+        .find(|(name, _)| *name == family)
+        .map(|(_, substitute)| *substitute)
+}
+
+/// Generic CSS family closest to `family`
+#[must_use]
+pub fn generic_family(family: &str) -> &'static str {
+    let family = family.trim().to_ascii_lowercase();
+    if MONOSPACE.contains(&family.as_str()) || family.ends_with(" mono") {
+        "monospace"
+    } else if SERIF.contains(&family.as_str()) || family.ends_with(" serif") {
+        "serif"
+    } else {
+        "sans-serif"
+    }
+}
+
+/// CSS weight and italic flag from a style name such as `Bold Italic`
+#[must_use]
+pub fn parse_style(style: &str) -> (u16, bool) {
+    let style = style.to_ascii_lowercase();
+    let weight = [
+        ("thin", 100),
+        ("extralight", 200),
+        ("light", 300),
+        ("medium", 500),
+        ("semibold", 600),
+        ("demibold", 600),
+        ("extrabold", 800),
+        ("bold", 700),
+        ("black", 900),
+        ("heavy", 900),
+    ]
+    .iter()
+    .find(|(name, _)| style.replace([' ', '-'], "").contains(name))
+    .map_or(400, |(_, weight)| *weight);
+    let italic = style.contains("italic") || style.contains("oblique");
+    (weight, italic)
+}
+
+fn face(index: usize, font: &FontResource) -> Option<FontFace<'_>> {
+    // Only formats browsers load; Type 1, bare CFF and EOT data is skipped
+    let data = font
+        .data
+        .as_deref()
+        .filter(|data| WEB_FONT_MAGIC.iter().any(|magic| data.starts_with(magic)))?;
+    let (weight, italic) = parse_style(&font.style);
+    Some(FontFace {
+        index,
+        family: &font.family,
+        weight,
+        italic,
+        data,
+    })
+}
+
+fn collect_families(blocks: &[ContentBlock], families: &mut BTreeSet<String>) {
+    for block in blocks {
+        match block {
+            ContentBlock::Text(text) => families.extend(
+                text.runs
+                    .iter()
+                    .filter_map(|run| run.style.font_family.as_deref())
+                    .map(str::trim)
+                    .filter(|family| !family.is_empty())
+                    .map(str::to_string),
+            ),
+            ContentBlock::Table(table) => {
+                for cell in table.rows.iter().flat_map(|row| &row.cells) {
+                    collect_families(&cell.content, families);
+                }
+            }
+            ContentBlock::List(list) => {
+                for item in &list.items {
+                    collect_families(&item.content, families);
+                }
+            }
+            ContentBlock::Container(container) => collect_families(&container.children, families),
+            ContentBlock::Image(_) | ContentBlock::FormField(_) | ContentBlock::Vector(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Dimensions, Page, Rect, TextBlock, TextRun};
+
+    fn document_with_fonts(families: &[&str]) -> Document {
+        let mut block = TextBlock::new(Rect::default());
+        for family in families {
+            let mut run = TextRun::new("text");
+            run.style.font_family = Some((*family).into());
+            block.add_run(run);
+        }
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(ContentBlock::Text(block));
+
+        let mut document = Document::new();
+        document.pages.push(page);
+        document
+    }
+
+    #[test]
+    fn test_substitutions() {
+        let mut document = document_with_fonts(&["Calibri", "Brand Sans", "Garamond", "Inter"]);
+        document.resources.fonts.push(FontResource {
+            embedded: true,
+            data: Some(b"wOF2....".to_vec()),
+            ..FontResource::new("Inter", "Bold Italic")
+        });
+
+        let fonts = FontManager::new(&document);
+        assert_eq!(fonts.embedded().len(), 1);
+        assert_eq!(fonts.embedded()[0].file_type(), ("font/woff2", "woff2"));
+        assert_eq!(
+            (fonts.embedded()[0].weight, fonts.embedded()[0].italic),
+            (700, true)
+        );
+
+        let substitutions = fonts.substitutions();
+        let summary: Vec<(&str, &str, bool)> = substitutions
+            .iter()
+            .map(|s| {
+                (
+                    s.requested.as_str(),
+                    s.substitute.as_str(),
+                    s.metric_compatible,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Brand Sans", "sans-serif", false),
+                ("Calibri", "Carlito", true),
+                ("Garamond", "serif", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(parse_style("Regular"), (400, false));
+        assert_eq!(parse_style("SemiBold"), (600, false));
+        assert_eq!(parse_style("Light Oblique"), (300, true));
+        assert_eq!(parse_style("Extra-Bold"), (800, false));
+    }
+}
@@ -4,18 +4,93 @@
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
 use bytes::Bytes;
-use prism_core::document::{ContentBlock, Document};
+use futures::stream::{self, StreamExt};
+use prism_core::document::{
+    CellRange, ContentBlock, Dimensions, Document, Fill, FormFieldBlock, FormFieldType, Link,
+    ListBlock, ListItem, ListMarker, Page, Rect, RevisionKind, TextDirection,
+};
 use prism_core::error::Result;
 use prism_core::format::Format;
-use prism_core::render::{RenderContext, RenderFeature, Renderer, RendererMetadata};
+use prism_core::progress::{ProgressEvent, ProgressSink};
+use prism_core::render::{
+    AnnotationOptions, BatesNumbering, ColorMode, Imposition, PageRange, Pagination, RenderContext,
+    RenderDiagnostics, RenderFeature, RenderOptions, RenderResult, Renderer, RendererMetadata,
+    Watermark,
+};
+use prism_core::stream::{ByteStream, DocumentStream, StreamedPage};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::color::{convert, Paint, BACKDROP_LIGHTEN};
+use crate::filter::{crop_sheet, filter_document, filter_page, select_pages, used_range};
+use crate::fonts::{generic_family, metric_compatible, FontFace, FontManager};
+use crate::imposition::impose;
+use crate::normalize::{normalize_document, normalize_page};
+use crate::zip_writer::DeterministicZipWriter;
+
+mod annotation;
+mod arrow;
+mod fill;
+pub(crate) mod semantic;
+mod stamp;
+mod transform;
 
 /// HTML5 renderer
 ///
 /// Renders documents as responsive, accessible HTML5 with embedded CSS.
-#[derive(Debug, Default)]
+///
+/// With [`HtmlConfig::embed_resources`] disabled, images are written as
+/// separate files under [`HtmlConfig::asset_dir`] and referenced by relative
+/// URL; [`Renderer::render`] then returns a ZIP archive holding `index.html`
+/// and the assets.
+#[derive(Debug, Clone, Default)]
 pub struct HtmlRenderer {
     /// Renderer configuration
     config: HtmlConfig,
+
+    /// Mark images for lazy loading (paginated layouts)
+    lazy_images: bool,
+
+    /// Prefix for asset URLs, for pages nested below the output root
+    asset_prefix: &'static str,
+
+    /// Color handling for the current render
+    color_mode: ColorMode,
+
+    /// Watermark stamped over the pages of the current render
+    watermark: Option<Watermark>,
+
+    /// Bates numbers stamped on the pages of the current render
+    bates: Option<BatesNumbering>,
+
+    /// Annotations drawn over the pages of the current render
+    annotations: AnnotationOptions,
+
+    /// Where the pages of the current render are reported
+    progress: Option<PageProgress>,
+}
+
+/// Count of rendered pages, reported to the progress sink of a render
+#[derive(Debug, Clone)]
+struct PageProgress {
+    sink: Arc<dyn ProgressSink>,
+    rendered: Arc<AtomicUsize>,
+}
+
+impl PageProgress {
+    /// Report one more page of the `total` being rendered
+    fn page_done(&self, total: usize) {
+        let rendered = self.rendered.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sink.report(ProgressEvent::PageRendered {
+            rendered,
+            // Streamed documents are rendered before their length is known
+            total: Some(total).filter(|total| *total >= rendered),
+        });
+    }
 }
 
 /// Configuration for HTML rendering
@@ -32,6 +107,78 @@ pub struct HtmlConfig {
 
     /// Custom CSS to inject
     pub custom_css: Option<String>,
+
+    /// Directory, relative to the HTML, that linked resources are written to
+    pub asset_dir: String,
+
+    /// How embedded PDFs are displayed
+    pub pdf_viewer: PdfViewer,
+
+    /// How page content is laid out
+    pub layout: HtmlLayout,
+
+    /// How form fields are shown
+    pub forms: FormRendering,
+
+    /// Which cells of worksheets are rendered
+    pub sheet_area: SheetArea,
+}
+
+/// Which cells of a worksheet are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SheetArea {
+    /// The whole grid read from the workbook
+    #[default]
+    Grid,
+
+    /// The cells in use, without the empty rows and columns around them
+    Used,
+
+    /// The print areas of the sheet, one table each, or the cells in use
+    /// when the sheet has none
+    Print,
+}
+
+/// How form fields are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FormRendering {
+    /// As disabled HTML form controls holding the field values
+    #[default]
+    Disabled,
+
+    /// As plain text: the value, or a ballot box for checkboxes
+    Flattened,
+}
+
+/// How page content is laid out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HtmlLayout {
+    /// Every block absolutely positioned as on the source page
+    #[default]
+    Positioned,
+
+    /// Headings, paragraphs, lists and tables in reading order, without
+    /// fixed positioning, for accessibility and reflow on small screens
+    Semantic,
+}
+
+/// How embedded PDFs are displayed
+///
+/// Output never references third-party hosts, so it works air-gapped and
+/// under a strict Content-Security-Policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PdfViewer {
+    /// No viewer script; offer the PDF as a download instead
+    #[default]
+    Omit,
+
+    /// Interactive viewer using a self-hosted copy of pdf.js
+    Bundled {
+        /// URL of `pdf.min.js`, same-origin or relative to the output
+        script_url: String,
+        /// URL of `pdf.worker.min.js`, same-origin or relative to the output
+        worker_url: String,
+    },
 }
 
 impl Default for HtmlConfig {
@@ -41,6 +188,11 @@ impl Default for HtmlConfig {
             include_styles: true,
             responsive: true,
             custom_css: None,
+            asset_dir: "assets".to_string(),
+            pdf_viewer: PdfViewer::Omit,
+            layout: HtmlLayout::Positioned,
+            forms: FormRendering::Disabled,
+            sheet_area: SheetArea::Grid,
         }
     }
 }
@@ -55,7 +207,16 @@ impl HtmlRenderer {
     /// Create a new HTML renderer with custom configuration
     #[must_use]
     pub fn with_config(config: HtmlConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            lazy_images: false,
+            asset_prefix: "",
+            color_mode: ColorMode::Color,
+            watermark: None,
+            bates: None,
+            annotations: AnnotationOptions::all(),
+            progress: None,
+        }
     }
 
     /// Render a text run with its formatting
@@ -67,7 +228,7 @@ impl HtmlRenderer {
         let mut styles = Vec::new();
 
         if let Some(ref font_family) = style.font_family {
-            styles.push(format!("font-family: {}", html_escape(font_family)));
+            styles.push(format!("font-family: {}", font_stack(font_family)));
         }
 
         if let Some(font_size) = style.font_size {
@@ -75,11 +236,14 @@ impl HtmlRenderer {
         }
 
         if let Some(ref color) = style.color {
-            styles.push(format!("color: {}", html_escape(color)));
+            styles.push(format!("color: {}", self.paint(color, Paint::Ink)));
         }
 
         if let Some(ref bg_color) = style.background_color {
-            styles.push(format!("background-color: {}", html_escape(bg_color)));
+            styles.push(format!(
+                "background-color: {}",
+                self.paint(bg_color, Paint::Fill)
+            ));
         }
 
         // Apply font weight/style/decoration
@@ -99,9 +263,36 @@ impl HtmlRenderer {
             html = format!("<s>{}</s>", html);
         }
 
-        // Wrap in span with inline styles if needed
+        // Wrap in span with inline styles and language if needed
+        let lang = style
+            .language
+            .as_deref()
+            .map(|lang| format!(r#" lang="{}""#, html_escape(lang)))
+            .unwrap_or_default();
         if !styles.is_empty() {
-            html = format!(r#"<span style="{}">{}</span>"#, styles.join("; "), html);
+            html = format!(
+                r#"<span{lang} style="{}">{}</span>"#,
+                styles.join("; "),
+                html
+            );
+        } else if !lang.is_empty() {
+            html = format!("<span{lang}>{html}</span>");
+        }
+
+        if let Some(link) = &run.link {
+            html = format!(r#"<a href="{}">{html}</a>"#, html_escape(&link_href(link)));
+        }
+
+        // Tracked changes kept as markup
+        if let Some(revision) = &run.revision {
+            let tag = match revision.kind {
+                RevisionKind::Deletion => "del",
+                RevisionKind::Insertion | RevisionKind::Comment => "ins",
+            };
+            html = format!(
+                r#"<{tag} class="revision" data-revision="{}">{html}</{tag}>"#,
+                html_escape(&revision.id)
+            );
         }
 
         html
@@ -109,54 +300,59 @@ impl HtmlRenderer {
 
     /// Check if content contains embedded special viewers (PDF, single images)
     fn has_embedded_viewer(&self, page: &prism_core::document::Page) -> bool {
-        // Check if this is a single-block page with PDF data or single image
-        if page.content.len() == 1 {
-            match &page.content[0] {
-                ContentBlock::Text(text_block) => {
-                    // Check for PDF embed marker
-                    if text_block.runs.len() == 1 {
-                        return text_block.runs[0].text.starts_with("__PDF_DATA__:");
-                    }
-                }
-                ContentBlock::Image(_) => {
-                    // Single image doesn't need page wrapper
-                    return true;
-                }
-                _ => {}
+        match page.content.as_slice() {
+            // Single image doesn't need page wrapper
+            [ContentBlock::Image(_)] => true,
+            // PDF embed marker, followed by the PDF's form fields if it has any
+            [ContentBlock::Text(text_block), rest @ ..] => {
+                text_block.runs.len() == 1
+                    && pdf_payload(&text_block.runs[0].text).is_some()
+                    && rest
+                        .iter()
+                        .all(|block| matches!(block, ContentBlock::FormField(_)))
             }
+            _ => false,
         }
-        false
     }
 
     /// Render all pages in the document
     fn render_pages(&self, document: &Document) -> String {
-        // Check if this is an email or contact format (no page concept)
-        let is_email_format = document
-            .metadata
-            .custom
-            .get("format")
-            .and_then(|v| {
-                if let prism_core::metadata::MetadataValue::String(s) = v {
-                    Some(s.as_str())
-                } else {
-                    None
-                }
-            })
-            .map(|f| matches!(f, "EML" | "MSG" | "MBOX" | "VCF" | "ICS"))
-            .unwrap_or(false);
-
-        // Check if this is a single-page document with embedded viewer
-        if is_email_format
-            || (document.pages.len() == 1 && self.has_embedded_viewer(&document.pages[0]))
-        {
-            // Render content directly without page wrapper
-            document
-                .pages
-                .iter()
-                .flat_map(|page| &page.content)
-                .map(|block| self.render_content_block(document, block))
-                .collect::<Vec<_>>()
-                .join("\n")
+        if self.is_unpaged(document) {
+            let content = if self.config.layout == HtmlLayout::Semantic {
+                let blocks: Vec<ContentBlock> = document
+                    .pages
+                    .iter()
+                    .flat_map(|page| page.content.iter().cloned())
+                    .collect();
+                self.render_semantic_blocks(document, &blocks, 1)
+            } else {
+                // Render content directly without page wrapper; without a
+                // page box to position them in, form fields flow after the
+                // content
+                document
+                    .pages
+                    .iter()
+                    .flat_map(|page| &page.content)
+                    .map(|block| match block {
+                        ContentBlock::FormField(field) => format!(
+                            r#"<div class="form-field">{}</div>"#,
+                            self.form_field_markup(field)
+                        ),
+                        block => self.render_content_block(document, block),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            // Unpaged output is stamped as a single page
+            let stamps = self.stamps(0);
+            if stamps.is_empty() {
+                content
+            } else {
+                format!(
+                    r#"<div class="stamped" style="position: relative;">{content}{stamps}</div>"#
+                )
+            }
         } else {
             // Render with page wrappers for multi-page or regular content
             document
@@ -169,13 +365,32 @@ impl HtmlRenderer {
         }
     }
 
-    /// Render a single page
+    /// Render a single page, counting it as rendered
     fn render_page(
         &self,
         document: &Document,
         page: &prism_core::document::Page,
         page_num: usize,
     ) -> String {
+        let page = sheet_area(page, self.config.sheet_area);
+        let html = self.page_markup(document, &page, page_num);
+        if let Some(progress) = &self.progress {
+            progress.page_done(document.page_count());
+        }
+        html
+    }
+
+    /// Markup of a single page
+    fn page_markup(
+        &self,
+        document: &Document,
+        page: &prism_core::document::Page,
+        page_num: usize,
+    ) -> String {
+        if self.config.layout == HtmlLayout::Semantic {
+            return self.render_semantic_page(document, page, page_num);
+        }
+
         // Use page dimensions for the container
         let width = page.dimensions.width;
         let height = page.dimensions.height;
@@ -191,21 +406,20 @@ impl HtmlRenderer {
                 && img_block.bounds.x.abs() < 0.1
                 && img_block.bounds.y.abs() < 0.1
             {
-                if let Some(img_resource) = document
-                    .resources
-                    .images
-                    .iter()
-                    .find(|img| img.id == img_block.resource_id)
-                {
-                    if let Some(ref data) = img_resource.data {
-                        let base64_data = general_purpose::STANDARD.encode(data);
-                        // Assumes mime_type is available on ImageResource
-                        background_style = format!(
-                            "background-image: url('data:{};base64,{}'); background-size: cover; background-position: center;",
-                            img_resource.mime_type, base64_data
-                        );
-                        skip_first_block = true;
-                    }
+                if let Some(src) = self.image_src(document, &img_block.resource_id) {
+                    // Ink saving washes the picture out under a white overlay
+                    let overlay = if self.color_mode == ColorMode::InkSaving {
+                        format!(
+                            "linear-gradient(rgba(255, 255, 255, {BACKDROP_LIGHTEN}), rgba(255, 255, 255, {BACKDROP_LIGHTEN})), "
+                        )
+                    } else {
+                        String::new()
+                    };
+                    background_style = format!(
+                        "background-image: {overlay}url('{}'); background-size: cover; background-position: center;",
+                        html_escape(&src.replace('\'', "%27"))
+                    );
+                    skip_first_block = true;
                 }
             }
         }
@@ -215,16 +429,29 @@ impl HtmlRenderer {
             .iter()
             .enumerate()
             .filter(|(i, _)| !skip_first_block || *i > 0)
-            .map(|(_, block)| self.render_content_block(document, block))
+            .map(|(_, block)| {
+                if self.color_mode == ColorMode::InkSaving && covers_page(block, page.dimensions) {
+                    self.render_content_block(document, &backdrop(block))
+                } else {
+                    self.render_content_block(document, block)
+                }
+            })
             .collect::<Vec<_>>()
             .join("\n");
 
         format!(
-            r#"<div class="page" style="width: {}pt; height: {}pt; position: relative; overflow: hidden; {}">
+            r#"<div class="page" id="page-{}" style="width: {}pt; height: {}pt; position: relative; overflow: hidden; {}">
         <div class="page-number" style="display: none;">Page {}</div>
-        {}
+        {}{}{}
     </div>"#,
-            width, height, background_style, page_num, content
+            page_num,
+            width,
+            height,
+            background_style,
+            page_num,
+            content,
+            self.annotations(page),
+            self.stamps(page_num - 1)
         )
     }
 
@@ -233,6 +460,25 @@ impl HtmlRenderer {
         &self,
         document: &Document,
         table: &prism_core::document::TableBlock,
+    ) -> String {
+        let html = self.table_markup(document, table);
+
+        // Wrap table in absolute div if it has bounds
+        if table.bounds.width > 0.0 && table.bounds.height > 0.0 {
+            format!(
+                r#"<div style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;">{}</div>"#,
+                table.bounds.x, table.bounds.y, table.bounds.width, table.bounds.height, html
+            )
+        } else {
+            html
+        }
+    }
+
+    /// The `<table>` element for a table block
+    fn table_markup(
+        &self,
+        document: &Document,
+        table: &prism_core::document::TableBlock,
     ) -> String {
         let mut html = String::from(r#"<table class="data-table">"#);
 
@@ -278,32 +524,165 @@ impl HtmlRenderer {
         }
 
         html.push_str("</table>");
+        html
+    }
 
-        // Wrap table in absolute div if it has bounds
-        if table.bounds.width > 0.0 && table.bounds.height > 0.0 {
+    /// Render a list block
+    fn render_list(&self, document: &Document, list: &ListBlock) -> String {
+        let html = self.list_markup(document, list);
+        if list.bounds.width > 0.0 && list.bounds.height > 0.0 {
             format!(
                 r#"<div style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;">{}</div>"#,
-                table.bounds.x, table.bounds.y, table.bounds.width, table.bounds.height, html
+                list.bounds.x, list.bounds.y, list.bounds.width, list.bounds.height, html
             )
         } else {
             html
         }
     }
 
+    /// Render a form field, positioned if it has bounds
+    fn render_form_field(&self, field: &FormFieldBlock) -> String {
+        let html = self.form_field_markup(field);
+        let bounds = &field.bounds;
+        if bounds.width > 0.0 && bounds.height > 0.0 {
+            format!(
+                r#"<div class="form-field" style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;">{}</div>"#,
+                bounds.x, bounds.y, bounds.width, bounds.height, html
+            )
+        } else {
+            format!(r#"<div class="form-field">{html}</div>"#)
+        }
+    }
+
+    /// Markup for a form field: a disabled control, or its value as text
+    fn form_field_markup(&self, field: &FormFieldBlock) -> String {
+        if self.config.forms == FormRendering::Flattened {
+            return format!(
+                r#"<span class="form-value">{}</span>"#,
+                html_escape(&field.extract_text())
+            );
+        }
+
+        let label = field.label.as_deref().unwrap_or(&field.name);
+        let attrs = format!(
+            r#" name="{}" aria-label="{}" disabled"#,
+            html_escape(&field.name),
+            html_escape(label)
+        );
+        let value = html_escape(field.value.as_deref().unwrap_or_default());
+        match field.field_type {
+            FormFieldType::Text | FormFieldType::Date => {
+                format!(r#"<input type="text"{attrs} value="{value}">"#)
+            }
+            FormFieldType::Checkbox | FormFieldType::Radio => {
+                let kind = if field.field_type == FormFieldType::Radio {
+                    "radio"
+                } else {
+                    "checkbox"
+                };
+                let checked = if field.checked { " checked" } else { "" };
+                format!(r#"<input type="{kind}"{attrs}{checked}>"#)
+            }
+            FormFieldType::Dropdown | FormFieldType::ListBox => {
+                let mut options = field.options.clone();
+                if let Some(value) = &field.value {
+                    if !options.contains(value) {
+                        options.insert(0, value.clone());
+                    }
+                }
+                let options = options.iter().fold(String::new(), |mut html, option| {
+                    let selected = if field.value.as_ref() == Some(option) {
+                        " selected"
+                    } else {
+                        ""
+                    };
+                    let _ = write!(html, "<option{selected}>{}</option>", html_escape(option));
+                    html
+                });
+                let size = if field.field_type == FormFieldType::ListBox {
+                    format!(r#" size="{}""#, field.options.len().max(2))
+                } else {
+                    String::new()
+                };
+                format!("<select{attrs}{size}>{options}</select>")
+            }
+            FormFieldType::Signature => format!(
+                r#"<span class="form-signature" role="img" aria-label="{}">{}</span>"#,
+                html_escape(label),
+                if value.is_empty() {
+                    "Signature"
+                } else {
+                    &value
+                }
+            ),
+            FormFieldType::Button => {
+                format!(
+                    r#"<button type="button"{attrs}>{}</button>"#,
+                    html_escape(label)
+                )
+            }
+        }
+    }
+
+    /// Nested `<ul>`/`<ol>` elements for a list block
+    ///
+    /// An item more than one level deeper than the one before it is nested
+    /// only one level, since HTML lists cannot skip levels.
+    fn list_markup(&self, document: &Document, list: &ListBlock) -> String {
+        let mut html = String::new();
+        // Whether each open list is ordered, outermost first
+        let mut open: Vec<bool> = Vec::new();
+        let close = |ordered: bool| if ordered { "</li></ol>" } else { "</li></ul>" };
+
+        for item in &list.items {
+            let depth = (usize::from(item.level) + 1).min(open.len() + 1);
+            while open.len() > depth {
+                html.push_str(close(open.pop().unwrap_or_default()));
+            }
+            if open.len() == depth {
+                if open.last() == Some(&item.ordered) {
+                    html.push_str("</li>");
+                } else {
+                    html.push_str(close(open.pop().unwrap_or_default()));
+                }
+            }
+            if open.len() < depth {
+                html.push_str(&list_open_tag(item));
+                open.push(item.ordered);
+            }
+
+            html.push_str("<li>");
+            for block in &item.content {
+                match block {
+                    ContentBlock::Text(text) => {
+                        html.extend(text.runs.iter().map(|run| self.render_text_run(run)));
+                    }
+                    _ => html.push_str(&self.render_content_block(document, block)),
+                }
+            }
+        }
+        while let Some(ordered) = open.pop() {
+            html.push_str(close(ordered));
+        }
+        html
+    }
+
     /// Render a content block
     fn render_content_block(&self, document: &Document, block: &ContentBlock) -> String {
         match block {
             ContentBlock::Text(text_block) => {
                 // Check if this is embedded PDF data
-                if text_block.runs.len() == 1
-                    && text_block.runs[0].text.starts_with("__PDF_DATA__:")
-                {
-                    return self.render_pdf_viewer(&text_block.runs[0].text);
+                if text_block.runs.len() == 1 {
+                    if let Some(pdf_data) = pdf_payload(&text_block.runs[0].text) {
+                        return self.render_pdf_viewer(pdf_data);
+                    }
                 }
-                self.render_text_block(text_block)
+                self.render_text_block(document, text_block)
             }
             ContentBlock::Image(image_block) => self.render_image_block(document, image_block),
             ContentBlock::Table(table_block) => self.render_table(document, table_block),
+            ContentBlock::List(list) => self.render_list(document, list),
+            ContentBlock::FormField(field) => self.render_form_field(field),
             ContentBlock::Vector(vector_block) => self.render_vector(document, vector_block),
             ContentBlock::Container(container_block) => {
                 self.render_container(document, container_block)
@@ -312,69 +691,40 @@ impl HtmlRenderer {
     }
 
     /// Render embedded PDF viewer
-    fn render_pdf_viewer(&self, text: &str) -> String {
-        let pdf_data = &text[13..]; // Skip "__PDF_DATA__:" prefix
-        format!(
-            r#"<div class="pdf-viewer-container">
+    fn render_pdf_viewer(&self, pdf_data: &str) -> String {
+        match &self.config.pdf_viewer {
+            PdfViewer::Omit => format!(
+                r#"<div class="pdf-viewer-container">
+                <p>This PDF cannot be previewed here.</p>
+                <a download="document.pdf" href="data:application/pdf;base64,{pdf_data}">Download PDF</a>
+            </div>"#
+            ),
+            PdfViewer::Bundled {
+                script_url,
+                worker_url,
+            } => format!(
+                r#"<div class="pdf-viewer-container" data-pdf="{pdf_data}" data-worker="{}">
                 <canvas id="pdf-canvas" style="width: 100%; border: 1px solid #ccc;"></canvas>
                 <div class="pdf-controls" style="margin-top: 10px; text-align: center;">
-                    <button onclick="prevPage()" style="margin: 0 5px;">Previous</button>
+                    <button id="pdf-prev" type="button" style="margin: 0 5px;">Previous</button>
                     <span id="page-info">Page <span id="current-page">1</span> of <span id="total-pages">1</span></span>
-                    <button onclick="nextPage()" style="margin: 0 5px;">Next</button>
+                    <button id="pdf-next" type="button" style="margin: 0 5px;">Next</button>
                 </div>
-                <script src="https://cdnjs.cloudflare.com/ajax/libs/pdf.js/3.11.174/pdf.min.js"></script>
-                <script>
-                    pdfjsLib.GlobalWorkerOptions.workerSrc = 'https://cdnjs.cloudflare.com/ajax/libs/pdf.js/3.11.174/pdf.worker.min.js';
-                    const pdfData = atob('{pdf_data}');
-                    const loadingTask = pdfjsLib.getDocument({{data: Uint8Array.from(pdfData, c => c.charCodeAt(0))}});
-                    let pdfDoc = null;
-                    let pageNum = 1;
-                    let rendering = false;
-
-                    loadingTask.promise.then(pdf => {{
-                        pdfDoc = pdf;
-                        document.getElementById('total-pages').textContent = pdf.numPages;
-                        renderPage(pageNum);
-                    }});
-
-                    function renderPage(num) {{
-                        rendering = true;
-                        pdfDoc.getPage(num).then(page => {{
-                            const canvas = document.getElementById('pdf-canvas');
-                            const ctx = canvas.getContext('2d');
-                            const viewport = page.getViewport({{scale: 1.5}});
-
-                            canvas.height = viewport.height;
-                            canvas.width = viewport.width;
-
-                            page.render({{
-                                canvasContext: ctx,
-                                viewport: viewport
-                            }}).promise.then(() => {{
-                                rendering = false;
-                                document.getElementById('current-page').textContent = num;
-                            }});
-                        }});
-                    }}
-
-                    function nextPage() {{
-                        if (pageNum >= pdfDoc.numPages || rendering) return;
-                        pageNum++;
-                        renderPage(pageNum);
-                    }}
-
-                    function prevPage() {{
-                        if (pageNum <= 1 || rendering) return;
-                        pageNum--;
-                        renderPage(pageNum);
-                    }}
-                </script>
-            </div>"#
-        )
+                <script src="{}"></script>
+{PDF_VIEWER_SCRIPT}
+            </div>"#,
+                html_escape(worker_url),
+                html_escape(script_url)
+            ),
+        }
     }
 
     /// Render a text block
-    fn render_text_block(&self, text_block: &prism_core::document::TextBlock) -> String {
+    fn render_text_block(
+        &self,
+        document: &Document,
+        text_block: &prism_core::document::TextBlock,
+    ) -> String {
         // Render each text run with its formatting
         let formatted_text = text_block
             .runs
@@ -409,19 +759,26 @@ impl HtmlRenderer {
         // Apply styles (background, border) from the shape
         let mut shape_styles = Vec::new();
         if let Some(ref bg) = text_block.style.fill_color {
-            shape_styles.push(format!("background-color: {};", html_escape(bg)));
+            shape_styles.push(format!(
+                "background-color: {};",
+                self.paint(bg, Paint::Fill)
+            ));
+        }
+        if let Some(ref fill) = text_block.style.fill {
+            shape_styles.push(self.css_fill(document, fill, Paint::Fill));
         }
 
         if let Some(ref stroke) = text_block.style.stroke_color {
             shape_styles.push(format!(
                 "border: {}pt solid {};",
                 text_block.style.stroke_width.unwrap_or(1.0),
-                html_escape(stroke)
+                self.paint(stroke, Paint::Ink)
             ));
         }
 
         format!(
-            r#"<div class="text-content" style="{pos_style} {transform_style} {}">{formatted_text}</div>"#,
+            r#"<div class="text-content" dir="{}" style="{pos_style} {transform_style} {}">{formatted_text}</div>"#,
+            dir_attribute(text_block.direction),
             shape_styles.join(" ")
         )
     }
@@ -432,53 +789,72 @@ impl HtmlRenderer {
         document: &Document,
         image_block: &prism_core::document::ImageBlock,
     ) -> String {
-        let img_tag = {
-            // Find the image resource by ID
-            if let Some(img_resource) = document
-                .resources
-                .images
-                .iter()
-                .find(|img| img.id == image_block.resource_id)
-            {
-                // Base64 encode the image data if available
-                if let Some(ref data) = img_resource.data {
-                    let base64_data = general_purpose::STANDARD.encode(data);
-                    let alt_text = image_block.alt_text.as_deref().unwrap_or("Image");
-
-                    format!(
-                        r#"<img src="data:{};base64,{base64_data}" alt="{}" style="width: 100%; height: 100%;" />"#,
-                        html_escape(&img_resource.mime_type),
-                        html_escape(alt_text)
-                    )
-                } else {
-                    String::from("<p><em>[Image data missing]</em></p>")
-                }
-            } else {
-                // Fallback if resource not found
-                String::from("<p><em>[Image not found]</em></p>")
-            }
-        };
+        let img_tag = self.image_tag(document, image_block, &transform::image_style(image_block));
 
-        // Position wrapper
+        // Position wrapper, which crops, flips and rotates the image
+        let box_style = transform::box_style(image_block);
         if image_block.bounds.width > 0.0 && image_block.bounds.height > 0.0 {
             format!(
-                r#"<div class="image-container" style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;">{img_tag}</div>"#,
+                r#"<div class="image-container" style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;{box_style}">{img_tag}</div>"#,
                 image_block.bounds.x,
                 image_block.bounds.y,
                 image_block.bounds.width,
                 image_block.bounds.height
             )
-        } else {
+        } else if box_style.is_empty() {
             format!(r#"<div class="image-container">{img_tag}</div>"#)
+        } else {
+            format!(
+                r#"<div class="image-container" style="position: relative;{box_style}">{img_tag}</div>"#
+            )
+        }
+    }
+
+    /// The `<img>` element for an image block with the CSS declarations
+    /// `style`, or a placeholder
+    fn image_tag(
+        &self,
+        document: &Document,
+        image_block: &prism_core::document::ImageBlock,
+        style: &str,
+    ) -> String {
+        // Find the image resource by ID
+        if let Some(img_resource) = document
+            .resources
+            .images
+            .iter()
+            .find(|img| img.id == image_block.resource_id)
+        {
+            // Embed or link the image data if available
+            if let Some(src) = self.image_src(document, &img_resource.id) {
+                let alt_text = image_block.alt_text.as_deref().unwrap_or("Image");
+
+                format!(
+                    r#"<img src="{}" alt="{}"{} style="{style}" />"#,
+                    html_escape(&src),
+                    html_escape(alt_text),
+                    if self.lazy_images {
+                        r#" loading="lazy""#
+                    } else {
+                        ""
+                    }
+                )
+            } else {
+                String::from("<p><em>[Image data missing]</em></p>")
+            }
+        } else {
+            // Fallback if resource not found
+            String::from("<p><em>[Image not found]</em></p>")
         }
     }
 
     /// Render a vector block
     fn render_vector(
         &self,
-        _document: &Document,
+        document: &Document,
         vector: &prism_core::document::VectorBlock,
     ) -> String {
+        let mut defs = String::new();
         let mut paths_svg = String::new();
         for path in &vector.paths {
             let mut d = String::new();
@@ -498,30 +874,67 @@ impl HtmlRenderer {
                 }
             }
 
-            let fill = path.fill.as_deref().unwrap_or("none");
-            let stroke = path.stroke.as_deref().unwrap_or("none");
+            let mut fill =
+                html_escape(&self.paint(path.fill.as_deref().unwrap_or("none"), Paint::Fill));
+            if let Some((definition, url)) = path
+                .fill_style
+                .as_ref()
+                .and_then(|fill_style| self.svg_fill(document, fill_style, Paint::Fill))
+            {
+                if !defs.contains(&definition) {
+                    defs.push_str(&definition);
+                }
+                fill = url;
+            }
+            let stroke = self.paint(path.stroke.as_deref().unwrap_or("none"), Paint::Ink);
             let stroke_width = path.stroke_width.unwrap_or(0.0);
 
+            let mut markers = String::new();
+            for (attribute, arrow) in [
+                ("marker-start", &path.start_arrow),
+                ("marker-end", &path.end_arrow),
+            ] {
+                if let Some(arrow) = arrow.as_ref().filter(|_| path.stroke.is_some()) {
+                    let (definition, url) = arrow::svg_marker(arrow, &stroke);
+                    if !defs.contains(&definition) {
+                        defs.push_str(&definition);
+                    }
+                    let _ = write!(markers, r#" {attribute}="{url}""#);
+                }
+            }
+
             paths_svg.push_str(&format!(
-                r#"<path d="{}" fill="{}" stroke="{}" stroke-width="{}" />"#,
+                r#"<path d="{}" fill="{}" stroke="{}" stroke-width="{}"{markers} />"#,
                 d.trim(),
-                html_escape(fill),
-                html_escape(stroke),
+                fill,
+                html_escape(&stroke),
                 stroke_width
             ));
         }
+        if !defs.is_empty() {
+            paths_svg.insert_str(0, &format!("<defs>{defs}</defs>"));
+        }
 
-        // Wrap in SVG
+        // Wrap in SVG, widened by the room arrowheads and flat lines need
+        let pad = arrow::padding(vector);
+        let bounds = Rect::new(
+            vector.bounds.x - pad,
+            vector.bounds.y - pad,
+            vector.bounds.width + 2.0 * pad,
+            vector.bounds.height + 2.0 * pad,
+        );
+        // Not `-pad`, which prints as "-0" without padding
+        let origin = 0.0 - pad;
         let svg = format!(
-            r#"<svg viewBox="0 0 {} {}" width="100%" height="100%" preserveAspectRatio="none">{}</svg>"#,
-            vector.bounds.width, vector.bounds.height, paths_svg
+            r#"<svg viewBox="{} {} {} {}" width="100%" height="100%" preserveAspectRatio="none">{}</svg>"#,
+            origin, origin, bounds.width, bounds.height, paths_svg
         );
 
         // Position wrapper
-        if vector.bounds.width > 0.0 && vector.bounds.height > 0.0 {
+        if bounds.width > 0.0 && bounds.height > 0.0 {
             format!(
                 r#"<div class="vector-block" style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;">{}</div>"#,
-                vector.bounds.x, vector.bounds.y, vector.bounds.width, vector.bounds.height, svg
+                bounds.x, bounds.y, bounds.width, bounds.height, svg
             )
         } else {
             format!(r#"<div class="vector-block">{}</div>"#, svg)
@@ -551,82 +964,855 @@ impl HtmlRenderer {
                 content
             )
         } else {
-            format!(r#"<div class="container-block">{}</div>"#, content)
+            // A flowing container of positioned parts, such as a chart in a
+            // paragraph, takes up the room its parts cover
+            let (width, height) = container.children.iter().map(ContentBlock::bounds).fold(
+                (0.0_f64, 0.0_f64),
+                |(width, height), bounds| {
+                    (
+                        width.max(bounds.x + bounds.width),
+                        height.max(bounds.y + bounds.height),
+                    )
+                },
+            );
+            if width > 0.0 && height > 0.0 {
+                format!(
+                    r#"<div class="container-block" style="position: relative; width: {width}pt; height: {height}pt;">{content}</div>"#
+                )
+            } else {
+                format!(r#"<div class="container-block">{}</div>"#, content)
+            }
         }
     }
-}
 
-/// Escape HTML special characters to prevent XSS
-fn html_escape(text: &str) -> String {
-    text.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#x27;")
-}
+    /// Whether the document has no page concept (emails, contacts) or is a
+    /// single embedded viewer, so it is always rendered continuously
+    fn is_unpaged(&self, document: &Document) -> bool {
+        let is_email_format = document
+            .metadata
+            .custom
+            .get("format")
+            .and_then(|v| {
+                if let prism_core::metadata::MetadataValue::String(s) = v {
+                    Some(s.as_str())
+                } else {
+                    None
+                }
+            })
+            .is_some_and(|f| matches!(f, "EML" | "MSG" | "MBOX" | "VCF" | "ICS"));
 
-#[async_trait]
-impl Renderer for HtmlRenderer {
-    fn output_format(&self) -> Format {
-        Format {
-            mime_type: "text/html".to_string(),
-            extension: "html".to_string(),
-            family: prism_core::format::FormatFamily::Text,
-            name: "HTML5".to_string(),
-            is_container: false,
-        }
+        is_email_format
+            || (document.pages.len() == 1 && self.has_embedded_viewer(&document.pages[0]))
     }
 
-    async fn render(&self, document: &Document, _context: RenderContext) -> Result<Bytes> {
-        let title = document
-            .metadata
-            .title
-            .as_deref()
-            .unwrap_or("Untitled Document");
+    /// Full stylesheet: base styles, `extra` for the output mode, layout
+    /// styles and the document's fonts
+    fn stylesheet(&self, document: &Document, extra: &str) -> String {
+        let layout = match self.config.layout {
+            HtmlLayout::Positioned => "",
+            HtmlLayout::Semantic => SEMANTIC_CSS,
+        };
+        let color = match self.color_mode {
+            ColorMode::Color => "",
+            ColorMode::Grayscale | ColorMode::InkSaving => GRAYSCALE_CSS,
+        };
+        format!(
+            "{BASE_CSS}{extra}{layout}{color}{}",
+            self.font_face_css(document)
+        )
+    }
+
+    /// `@font-face` rules for every font embedded in the document
+    ///
+    /// URLs of linked fonts are relative to the output root, which is where
+    /// both inline styles and `styles.css` resolve from.
+    fn font_face_css(&self, document: &Document) -> String {
+        FontManager::new(document)
+            .embedded()
+            .iter()
+            .fold(String::new(), |mut css, face| {
+                let src = if self.config.embed_resources {
+                    format!(
+                        "data:{};base64,{}",
+                        face.file_type().0,
+                        general_purpose::STANDARD.encode(face.data)
+                    )
+                } else {
+                    self.font_asset_path(face)
+                };
+                let _ = write!(
+                    css,
+                    "        @font-face {{
+            font-family: '{}';
+            src: url('{src}') format('{}');
+            font-weight: {};
+            font-style: {};
+        }}
+",
+                    css_value(face.family),
+                    face.css_format(),
+                    face.weight,
+                    if face.italic { "italic" } else { "normal" }
+                );
+                css
+            })
+    }
 
-        // Check if this is a single-page document with embedded viewer
-        let has_embedded =
-            document.pages.len() == 1 && self.has_embedded_viewer(&document.pages[0]);
+    /// Path of an embedded font inside the output
+    fn font_asset_path(&self, face: &FontFace<'_>) -> String {
+        format!(
+            "{}/font-{:04}.{}",
+            self.config.asset_dir.trim_end_matches('/'),
+            face.index + 1,
+            face.file_type().1
+        )
+    }
 
-        let html = format!(
+    /// Wrap rendered content in the standard HTML document
+    fn html_shell(&self, title: &str, head: &str, body: &str) -> String {
+        format!(
             r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
+    {}
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{}</title>
-    <style>
-        body {{
-            font-family: Arial, sans-serif;
-            margin: 0;
-            padding: 2rem;
-            background-color: #f5f5f5;
-        }}
-        .container {{
-            max-width: 1200px;
-            margin: 0 auto;
-            background-color: white;
-            padding: 2rem;
-            box-shadow: 0 2px 8px rgba(0,0,0,0.1);
-        }}
-        h1 {{
-            color: #333;
+    {head}
+</head>
+<body>
+    <div class="container">
+        {body}
+    </div>
+</body>
+</html>"#,
+            self.csp_meta(&[head, body]),
+            html_escape(title)
+        )
+    }
+
+    /// Content-Security-Policy `<meta>` tag for a document made of `parts`
+    ///
+    /// Inline scripts are allowed by hash only, and nothing may be loaded
+    /// from other origins.
+    fn csp_meta(&self, parts: &[&str]) -> String {
+        let bundled_viewer = matches!(self.config.pdf_viewer, PdfViewer::Bundled { .. });
+
+        let mut script_src: Vec<String> = parts
+            .iter()
+            .flat_map(|part| inline_scripts(part))
+            .map(|script| {
+                format!(
+                    "'sha256-{}'",
+                    general_purpose::STANDARD.encode(Sha256::digest(script.as_bytes()))
+                )
+            })
+            .collect();
+        script_src.dedup();
+        if bundled_viewer {
+            script_src.insert(0, "'self'".to_string());
+        }
+        let script_src = if script_src.is_empty() {
+            "'none'".to_string()
+        } else {
+            script_src.join(" ")
+        };
+
+        let mut policy = format!(
+            "default-src 'none'; script-src {script_src}; style-src 'self' 'unsafe-inline'; \
+             img-src 'self' data:; font-src 'self' data:; frame-src 'self'; \
+             base-uri 'none'; form-action 'none'"
+        );
+        if bundled_viewer {
+            policy.push_str("; worker-src 'self'");
+        }
+
+        format!(
+            r#"<meta http-equiv="Content-Security-Policy" content="{}">"#,
+            html_escape(&policy)
+        )
+    }
+
+    /// Copy of this renderer used for the paginated layouts
+    fn lazy(&self, asset_prefix: &'static str) -> Self {
+        Self {
+            config: self.config.clone(),
+            lazy_images: true,
+            asset_prefix,
+            color_mode: self.color_mode,
+            watermark: self.watermark.clone(),
+            bates: self.bates.clone(),
+            annotations: self.annotations,
+            progress: self.progress.clone(),
+        }
+    }
+
+    /// A copy of this renderer that reproduces colors, stamps pages and
+    /// draws annotations according to `options`
+    fn for_options(&self, options: &RenderOptions) -> Self {
+        Self {
+            config: self.config.clone(),
+            lazy_images: self.lazy_images,
+            asset_prefix: self.asset_prefix,
+            color_mode: options.color_mode,
+            watermark: options.watermark.clone(),
+            bates: options.bates.clone(),
+            annotations: options.annotations,
+            progress: self.progress.clone(),
+        }
+    }
+
+    /// A copy of this renderer for a render with `context`, which also
+    /// reports rendered pages to the context's progress sink
+    fn for_context(&self, context: &RenderContext) -> Self {
+        Self {
+            progress: context.progress.clone().map(|sink| PageProgress {
+                sink,
+                rendered: Arc::default(),
+            }),
+            ..self.for_options(&context.options)
+        }
+    }
+
+    /// A color run through the color pipeline, safe to embed in CSS
+    fn paint(&self, color: &str, paint: Paint) -> String {
+        css_value(&convert(color, self.color_mode, paint))
+    }
+
+    /// URL for an image resource: a data URI when embedding, otherwise a
+    /// relative path into the asset directory
+    fn image_src(&self, document: &Document, resource_id: &str) -> Option<String> {
+        let (index, image) = document
+            .resources
+            .images
+            .iter()
+            .enumerate()
+            .find(|(_, img)| img.id == resource_id)?;
+        let data = image.data.as_ref()?;
+
+        if self.config.embed_resources {
+            Some(format!(
+                "data:{};base64,{}",
+                safe_mime(&image.mime_type),
+                general_purpose::STANDARD.encode(data)
+            ))
+        } else {
+            Some(format!(
+                "{}{}",
+                self.asset_prefix,
+                self.image_asset_path(index, &image.mime_type)
+            ))
+        }
+    }
+
+    /// Path of the `index`th image resource inside the output
+    fn image_asset_path(&self, index: usize, mime_type: &str) -> String {
+        let extension = match mime_type {
+            "image/jpeg" | "image/jpg" => "jpg",
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/tiff" => "tiff",
+            "image/bmp" => "bmp",
+            "image/webp" => "webp",
+            "image/svg+xml" => "svg",
+            "image/x-emf" | "image/emf" => "emf",
+            "image/x-wmf" | "image/wmf" => "wmf",
+            _ => "bin",
+        };
+        format!(
+            "{}/image-{:04}.{extension}",
+            self.config.asset_dir.trim_end_matches('/'),
+            index + 1
+        )
+    }
+
+    /// Pass every linked resource to `sink` as `(path, bytes)`
+    ///
+    /// Does nothing when resources are embedded.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by `sink`.
+    pub fn write_assets<F>(&self, document: &Document, mut sink: F) -> Result<()>
+    where
+        F: FnMut(&str, &[u8]) -> Result<()>,
+    {
+        if self.config.embed_resources {
+            return Ok(());
+        }
+        for (index, image) in document.resources.images.iter().enumerate() {
+            if let Some(ref data) = image.data {
+                sink(&self.image_asset_path(index, &image.mime_type), data)?;
+            }
+        }
+        for face in FontManager::new(document).embedded() {
+            sink(&self.font_asset_path(face), face.data)?;
+        }
+        Ok(())
+    }
+
+    /// Linked resources as a map of path to bytes (empty when embedding)
+    #[must_use]
+    pub fn assets(&self, document: &Document) -> BTreeMap<String, Vec<u8>> {
+        let mut assets = BTreeMap::new();
+        let _ = self.write_assets(document, |path, data| {
+            assets.insert(path.to_string(), data.to_vec());
+            Ok(())
+        });
+        assets
+    }
+
+    /// Render a single HTML document along with any linked resources
+    ///
+    /// [`Pagination::Split`] is rendered continuously here; use
+    /// [`HtmlRenderer::render_split`] for per-page output. An imposed
+    /// layout is always a single document, whatever the pagination.
+    #[must_use]
+    pub fn render_with_assets(&self, document: &Document, options: &RenderOptions) -> HtmlOutput {
+        if options.color_mode != self.color_mode
+            || options.watermark != self.watermark
+            || options.bates != self.bates
+            || options.annotations != self.annotations
+        {
+            return self
+                .for_options(options)
+                .render_with_assets(document, options);
+        }
+        let document = &*prepared(document, options);
+
+        let html = match options.pagination {
+            _ if options.imposition != Imposition::None => {
+                self.render_imposed(document, options.imposition)
+            }
+            Pagination::Deferred => self.render_deferred(document),
+            Pagination::Continuous | Pagination::Split => self.html_shell(
+                document_title(document),
+                &format!("<style>\n{}    </style>", self.stylesheet(document, "")),
+                &self.render_pages(document),
+            ),
+        };
+        HtmlOutput {
+            html,
+            assets: self.assets(document),
+            diagnostics: diagnostics(document),
+        }
+    }
+
+    /// Whether output for `options` can be written before the last page
+    /// is known
+    ///
+    /// Only a continuous document of every page with embedded resources
+    /// qualifies, and only without the PDF viewer, whose inline scripts the
+    /// Content-Security-Policy in the head has to list up front.
+    fn can_stream(&self, options: &RenderOptions) -> bool {
+        options.pagination == Pagination::Continuous
+            && options.imposition == Imposition::None
+            && options
+                .page_range
+                .as_ref()
+                .map_or(true, |range| *range == PageRange::All)
+            && self.config.embed_resources
+            && self.config.pdf_viewer == PdfViewer::Omit
+    }
+
+    /// The HTML shell of a streamed document, split where its pages go
+    fn shell_parts(&self, document: &Document) -> (String, String) {
+        const PAGES: &str = "<!--pages-->";
+        let shell = self.html_shell(
+            document_title(document),
+            &format!("<style>\n{}    </style>", self.stylesheet(document, "")),
+            PAGES,
+        );
+        let (open, close) = shell.rsplit_once(PAGES).unwrap_or((&shell, ""));
+        (open.to_string(), close.to_string())
+    }
+
+    /// Render print sheets with pages arranged by `imposition`
+    fn render_imposed(&self, document: &Document, imposition: Imposition) -> String {
+        let title = document_title(document);
+        let head = format!(
+            "<style>\n{}    </style>",
+            self.stylesheet(document, IMPOSITION_CSS)
+        );
+        if self.is_unpaged(document) {
+            return self.html_shell(title, &head, &self.render_pages(document));
+        }
+
+        // Blank cells take the size of the first page
+        let blank = document.pages.first().map_or_else(String::new, |page| {
+            format!(
+                r#"<div class="page blank-page" style="width: {}pt; height: {}pt;"></div>"#,
+                page.dimensions.width, page.dimensions.height
+            )
+        });
+
+        let sheets = impose(document.pages.len(), imposition)
+            .iter()
+            .enumerate()
+            .map(|(i, side)| {
+                let cells = side
+                    .cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Some(index) => {
+                            self.render_page(document, &document.pages[*index], index + 1)
+                        }
+                        None => blank.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let zoom = if matches!(imposition, Imposition::NUp { .. }) {
+                    format!(" zoom: {};", side.scale())
+                } else {
+                    String::new()
+                };
+                format!(
+                    r#"<div class="sheet" data-sheet="{}" style="grid-template-columns: repeat({}, max-content);{zoom}">
+{cells}
+    </div>"#,
+                    i + 1,
+                    side.columns
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.html_shell(title, &head, &sheets)
+    }
+
+    /// Render a single document whose pages are materialized on scroll
+    fn render_deferred(&self, document: &Document) -> String {
+        let title = document_title(document);
+        let head = format!(
+            "<style>\n{}    </style>",
+            self.stylesheet(document, PAGINATION_CSS)
+        );
+        if self.is_unpaged(document) {
+            return self.html_shell(title, &head, &self.render_pages(document));
+        }
+
+        let renderer = self.lazy("");
+        let slots = document
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                format!(
+                    r#"<div class="page-slot" data-page="{}" style="width: {}pt; height: {}pt;"><template>{}</template></div>"#,
+                    i + 1,
+                    page.dimensions.width,
+                    page.dimensions.height,
+                    renderer.render_page(document, page, i + 1)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.html_shell(title, &head, &format!("{slots}\n{DEFERRED_SCRIPT}"))
+    }
+
+    /// Render one fragment per page plus an index shell that navigates them
+    #[must_use]
+    pub fn render_split(&self, document: &Document) -> PaginatedHtml {
+        let title = document_title(document);
+        let stylesheet = self.stylesheet(document, PAGINATION_CSS);
+
+        // Pages live one directory below the assets
+        let renderer = self.lazy("../");
+        let bodies = if self.is_unpaged(document) {
+            vec![renderer.render_pages(document)]
+        } else {
+            document
+                .pages
+                .iter()
+                .enumerate()
+                .map(|(i, page)| renderer.render_page(document, page, i + 1))
+                .collect()
+        };
+        let total = bodies.len();
+
+        let pages = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, body)| {
+                format!(
+                    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    {}
+    <title>{} - Page {}</title>
+    <link rel="stylesheet" href="../styles.css">
+</head>
+<body class="page-fragment">
+{body}
+</body>
+</html>"#,
+                    renderer.csp_meta(&[body]),
+                    html_escape(title),
+                    i + 1
+                )
+            })
+            .collect();
+
+        let links = (1..=total).fold(String::new(), |mut links, n| {
+            let _ = write!(links, r#"<li><a href="{}">Page {n}</a></li>"#, page_path(n));
+            links
+        });
+
+        let body = format!(
+            r#"<nav class="page-nav">
+            <button id="prev-page" type="button">Previous</button>
+            <span>Page <span id="current-page">1</span> of {total}</span>
+            <button id="next-page" type="button">Next</button>
+        </nav>
+        <iframe id="page-frame" class="page-frame" src="{first}" title="Document page"></iframe>
+        <noscript><ol class="page-list">{links}</ol></noscript>
+    <script>
+        (function () {{
+            var total = {total};
+            var current = 1;
+            var frame = document.getElementById('page-frame');
+            function pagePath(n) {{
+                return 'pages/page-' + String(n).padStart(4, '0') + '.html';
+            }}
+            function show(n) {{
+                if (n < 1 || n > total) return;
+                current = n;
+                frame.src = pagePath(n);
+                document.getElementById('current-page').textContent = n;
+                history.replaceState(null, '', '#page=' + n);
+            }}
+            document.getElementById('prev-page').onclick = function () {{ show(current - 1); }};
+            document.getElementById('next-page').onclick = function () {{ show(current + 1); }};
+            document.addEventListener('keydown', function (e) {{
+                if (e.key === 'ArrowLeft') show(current - 1);
+                if (e.key === 'ArrowRight') show(current + 1);
+            }});
+            var match = /page=(\d+)/.exec(location.hash);
+            if (match) show(parseInt(match[1], 10));
+        }})();
+    </script>"#,
+            first = page_path(1)
+        );
+
+        PaginatedHtml {
+            index: self.html_shell(title, r#"<link rel="stylesheet" href="styles.css">"#, &body),
+            stylesheet,
+            pages,
+            assets: self.assets(document),
+            diagnostics: diagnostics(document),
+        }
+    }
+}
+
+/// Fidelity notes for `document`, with the fonts the stylesheet replaces
+fn diagnostics(document: &Document) -> RenderDiagnostics {
+    RenderDiagnostics {
+        font_substitutions: FontManager::new(document).substitutions(),
+        ..RenderDiagnostics::check(document, |_| true)
+    }
+}
+
+/// The document with pages selected, content filtered and pages resized
+/// as requested by `options`
+fn prepared<'a>(document: &'a Document, options: &RenderOptions) -> Cow<'a, Document> {
+    let mut document = Cow::Borrowed(document);
+    if let Some(range) = options.page_range.as_ref() {
+        if *range != PageRange::All {
+            document = Cow::Owned(select_pages(&document, range));
+        }
+    }
+    if !options.content.is_all() {
+        document = Cow::Owned(filter_document(&document, &options.content));
+    }
+    if let Some(normalization) = &options.page_size {
+        document = Cow::Owned(normalize_document(&document, normalization));
+    }
+    document
+}
+
+/// `page` cropped to `area` if it holds a worksheet
+fn sheet_area(page: &Page, area: SheetArea) -> Cow<'_, Page> {
+    let Some(layout) = page.metadata.sheet.as_ref() else {
+        return Cow::Borrowed(page);
+    };
+    let areas: Vec<CellRange> = match area {
+        SheetArea::Grid => return Cow::Borrowed(page),
+        SheetArea::Print if !layout.print_areas.is_empty() => layout.print_areas.clone(),
+        SheetArea::Used | SheetArea::Print => page
+            .content
+            .iter()
+            .find_map(|block| match block {
+                ContentBlock::Table(table) => used_range(table),
+                _ => None,
+            })
+            .into_iter()
+            .collect(),
+    };
+    Cow::Owned(crop_sheet(page, &areas))
+}
+
+/// Opening tag of the list that `item` starts
+fn list_open_tag(item: &ListItem) -> String {
+    if !item.ordered {
+        return match item.marker_style {
+            ListMarker::None => r#"<ul class="list-block" style="list-style: none;">"#.to_string(),
+            _ => r#"<ul class="list-block">"#.to_string(),
+        };
+    }
+    let kind = match item.marker_style {
+        ListMarker::LowerAlpha => r#" type="a""#,
+        ListMarker::UpperAlpha => r#" type="A""#,
+        ListMarker::LowerRoman => r#" type="i""#,
+        ListMarker::UpperRoman => r#" type="I""#,
+        _ => "",
+    };
+    let start = item
+        .marker
+        .trim_end_matches(['.', ')'])
+        .parse::<u32>()
+        .ok()
+        .filter(|&start| start != 1)
+        .map_or_else(String::new, |start| format!(r#" start="{start}""#));
+    format!(r#"<ol class="list-block"{kind}{start}>"#)
+}
+
+/// Whether a block fills (nearly) the whole page, i.e. is a background
+fn covers_page(block: &ContentBlock, page: Dimensions) -> bool {
+    let bounds = match block {
+        ContentBlock::Text(text) => text.bounds,
+        ContentBlock::Vector(vector) => vector.bounds,
+        _ => return false,
+    };
+    bounds.width >= page.width * 0.9 && bounds.height >= page.height * 0.9
+}
+
+/// A copy of a background block with its fills lightened for ink saving
+fn backdrop(block: &ContentBlock) -> ContentBlock {
+    let lighten = |color: &mut Option<String>| {
+        if let Some(value) = color {
+            *value = convert(value, ColorMode::InkSaving, Paint::Backdrop);
+        }
+    };
+    let lighten_fill = |fill: &mut Option<Fill>| match fill {
+        Some(Fill::Gradient(gradient)) => {
+            for stop in &mut gradient.stops {
+                stop.color = convert(&stop.color, ColorMode::InkSaving, Paint::Backdrop);
+            }
+        }
+        Some(Fill::Pattern {
+            foreground,
+            background,
+            ..
+        }) => {
+            *foreground = convert(foreground, ColorMode::InkSaving, Paint::Backdrop);
+            *background = convert(background, ColorMode::InkSaving, Paint::Backdrop);
+        }
+        Some(Fill::Picture { .. }) | None => {}
+    };
+    let mut block = block.clone();
+    match &mut block {
+        ContentBlock::Text(text) => {
+            lighten(&mut text.style.fill_color);
+            lighten_fill(&mut text.style.fill);
+        }
+        ContentBlock::Vector(vector) => {
+            for path in &mut vector.paths {
+                lighten(&mut path.fill);
+                lighten_fill(&mut path.fill_style);
+            }
+        }
+        _ => {}
+    }
+    block
+}
+
+/// CSS font stack: the requested family, its metric-compatible substitute
+/// and a generic fallback. Single-quoted as it ends up in `style` attributes.
+fn font_stack(family: &str) -> String {
+    let family = css_value(family);
+    let family = family.trim();
+    let mut stack = format!("'{family}'");
+    if let Some(substitute) = metric_compatible(family) {
+        let _ = write!(stack, ", '{substitute}'");
+    }
+    let _ = write!(stack, ", {}", generic_family(family));
+    stack
+}
+
+/// Title shown in the browser tab
+fn document_title(document: &Document) -> &str {
+    document
+        .metadata
+        .title
+        .as_deref()
+        .unwrap_or("Untitled Document")
+}
+
+/// Drives the bundled pdf.js viewer. The PDF and worker URL are read from
+/// data attributes so this script is identical for every document and can
+/// be allowed by hash in the Content-Security-Policy.
+const PDF_VIEWER_SCRIPT: &str = r"                <script>
+                    (function () {
+                        var container = document.querySelector('.pdf-viewer-container');
+                        pdfjsLib.GlobalWorkerOptions.workerSrc = container.dataset.worker;
+                        var pdfData = atob(container.dataset.pdf);
+                        var loadingTask = pdfjsLib.getDocument({data: Uint8Array.from(pdfData, function (c) { return c.charCodeAt(0); })});
+                        var pdfDoc = null;
+                        var pageNum = 1;
+                        var rendering = false;
+
+                        loadingTask.promise.then(function (pdf) {
+                            pdfDoc = pdf;
+                            document.getElementById('total-pages').textContent = pdf.numPages;
+                            renderPage(pageNum);
+                        });
+
+                        function renderPage(num) {
+                            rendering = true;
+                            pdfDoc.getPage(num).then(function (page) {
+                                var canvas = document.getElementById('pdf-canvas');
+                                var ctx = canvas.getContext('2d');
+                                var viewport = page.getViewport({scale: 1.5});
+
+                                canvas.height = viewport.height;
+                                canvas.width = viewport.width;
+
+                                page.render({
+                                    canvasContext: ctx,
+                                    viewport: viewport
+                                }).promise.then(function () {
+                                    rendering = false;
+                                    document.getElementById('current-page').textContent = num;
+                                });
+                            });
+                        }
+
+                        document.getElementById('pdf-next').addEventListener('click', function () {
+                            if (!pdfDoc || pageNum >= pdfDoc.numPages || rendering) return;
+                            pageNum++;
+                            renderPage(pageNum);
+                        });
+
+                        document.getElementById('pdf-prev').addEventListener('click', function () {
+                            if (!pdfDoc || pageNum <= 1 || rendering) return;
+                            pageNum--;
+                            renderPage(pageNum);
+                        });
+                    })();
+                </script>";
+
+/// Base64 PDF data carried by a `__PDF_DATA__:` marker run
+///
+/// Anything that is not strictly base64 is rejected, since the payload ends
+/// up inside attribute values.
+pub(crate) fn pdf_payload(text: &str) -> Option<&str> {
+    let data = text.strip_prefix("__PDF_DATA__:")?;
+    data.bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='))
+        .then_some(data)
+}
+
+/// Value of the `dir` attribute for a text direction
+fn dir_attribute(direction: TextDirection) -> &'static str {
+    match direction {
+        TextDirection::Auto => "auto",
+        TextDirection::Ltr => "ltr",
+        TextDirection::Rtl => "rtl",
+    }
+}
+
+/// Reduce a CSS value (color, font family) to characters that cannot end
+/// the declaration or pull in external resources
+fn css_value(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| {
+            c.is_alphanumeric() || matches!(c, ' ' | '#' | ',' | '.' | '%' | '-' | '_' | '(' | ')')
+        })
+        .collect();
+    let lower = cleaned.to_ascii_lowercase();
+    if lower.contains("url(") || lower.contains("expression(") {
+        String::new()
+    } else {
+        cleaned
+    }
+}
+
+/// MIME type safe to place in a data URI, or a generic fallback
+fn safe_mime(mime_type: &str) -> &str {
+    let valid = !mime_type.is_empty()
+        && mime_type
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'/' | b'+' | b'.' | b'-'));
+    if valid {
+        mime_type
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Escape HTML special characters to prevent XSS
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+/// The `href` for a link, with script and data URLs neutralised
+///
+/// Browsers ignore whitespace and control characters inside the scheme, so
+/// they are dropped before the check.
+fn link_href(link: &Link) -> String {
+    let href = link.href();
+    let scheme: String = href
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .take_while(|&c| c != ':')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if href.contains(':') && matches!(scheme.as_str(), "javascript" | "vbscript" | "data") {
+        "#".to_string()
+    } else {
+        href
+    }
+}
+
+/// Stylesheet shared by every HTML layout
+const BASE_CSS: &str = "        body {
+            font-family: Arial, sans-serif;
+            margin: 0;
+            padding: 2rem;
+            background-color: #f5f5f5;
+        }
+        .container {
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 2rem;
+            box-shadow: 0 2px 8px rgba(0,0,0,0.1);
+        }
+        h1 {
+            color: #333;
             margin-top: 0;
-        }}
-        .page {{
+        }
+        .page {
             margin-bottom: 2rem;
             padding: 1rem;
             text-align: center;
-        }}
-        .page img {{
+        }
+        .page img {
             max-width: 100%;
             height: auto;
             border: 1px solid #ddd;
             border-radius: 4px;
             box-shadow: 0 1px 3px rgba(0,0,0,0.1);
-        }}
-        .text-content {{
-            text-align: left;
+        }
+        .text-content {
+            text-align: start;
             white-space: pre-wrap;
             word-wrap: break-word;
             overflow-wrap: break-word;
@@ -636,42 +1822,375 @@ impl Renderer for HtmlRenderer {
             border-radius: 4px;
             line-height: 1.5;
             max-width: 100%;
-        }}
-        .data-table {{
+        }
+        .data-table {
             width: 100%;
             border-collapse: collapse;
             margin: 1rem 0;
             font-size: 0.9rem;
             box-shadow: 0 2px 5px rgba(0,0,0,0.1);
-        }}
-        .data-table td {{
+        }
+        .data-table td {
             border: 1px solid #ddd;
             padding: 8px 12px;
             text-align: left;
             vertical-align: top;
-        }}
-        .data-table tr:nth-child(even) {{
+        }
+        .data-table tr:nth-child(even) {
             background-color: #f9f9f9;
-        }}
-        .data-table tr:hover {{
+        }
+        .data-table tr:hover {
             background-color: #f5f5f5;
-        }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        {}
-        {}
-    </div>
-</body>
-</html>"#,
-            html_escape(title),
-            // No header - removed filename and page count
-            String::new(),
-            self.render_pages(document)
+        }
+        .form-field input[type=text], .form-field select, .form-field button {
+            box-sizing: border-box;
+            width: 100%;
+            height: 100%;
+            font: inherit;
+        }
+        .form-signature {
+            display: block;
+            height: 100%;
+            border-bottom: 1px solid #333;
+            font-style: italic;
+        }
+        ins.revision {
+            color: #1a7f37;
+            text-decoration: underline;
+        }
+        del.revision {
+            color: #cf222e;
+            text-decoration: line-through;
+        }
+";
+
+/// Extra styles for the paginated layouts
+const PAGINATION_CSS: &str = "        .page-slot {
+            margin: 0 auto 2rem;
+            background-color: white;
+        }
+        .page-nav {
+            display: flex;
+            gap: 1rem;
+            align-items: center;
+            justify-content: center;
+            margin-bottom: 1rem;
+        }
+        .page-frame {
+            width: 100%;
+            height: 85vh;
+            border: 1px solid #ddd;
+            background-color: white;
+        }
+        .page-fragment {
+            padding: 0;
+            background-color: white;
+        }
+";
+
+/// Grays out raster images and page backgrounds, which the color pipeline
+/// cannot reach
+const GRAYSCALE_CSS: &str = "        .page, img {
+            filter: grayscale(100%);
+        }
+";
+
+/// Extra styles for the semantic layout
+const SEMANTIC_CSS: &str = "        .semantic-page {
+            position: relative;
+            max-width: 48rem;
+            margin: 0 auto 2rem;
+            line-height: 1.6;
+            overflow-wrap: break-word;
+        }
+        .semantic-page + .semantic-page {
+            border-top: 1px solid #ddd;
+            padding-top: 2rem;
+        }
+        .semantic-figure {
+            margin: 1rem 0;
+        }
+        .semantic-figure img {
+            max-width: 100%;
+            height: auto;
+        }
+        .semantic-page .data-table {
+            display: block;
+            overflow-x: auto;
+        }
+";
+
+/// Extra styles for imposed print sheets
+const IMPOSITION_CSS: &str = "        .sheet {
+            display: grid;
+            justify-content: center;
+            margin: 0 auto 2rem;
+        }
+        .sheet .page {
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }
+        @media print {
+            body, .container {
+                padding: 0;
+                background: none;
+                box-shadow: none;
+            }
+            .sheet {
+                margin: 0;
+                break-after: page;
+            }
+        }
+";
+
+/// Renders pages as they approach the viewport. Each page waits in an inert
+/// `<template>` (so its images are not decoded) inside a placeholder sized
+/// to the page.
+const DEFERRED_SCRIPT: &str = r"    <script>
+        (function () {
+            var slots = document.querySelectorAll('.page-slot');
+            function show(slot) {
+                var template = slot.querySelector('template');
+                if (template) {
+                    slot.replaceWith(template.content.cloneNode(true));
+                }
+            }
+            if (!('IntersectionObserver' in window)) {
+                slots.forEach(show);
+                return;
+            }
+            var observer = new IntersectionObserver(function (entries) {
+                entries.forEach(function (entry) {
+                    if (entry.isIntersecting) {
+                        observer.unobserve(entry.target);
+                        show(entry.target);
+                    }
+                });
+            }, { rootMargin: '200% 0px' });
+            slots.forEach(function (slot) { observer.observe(slot); });
+        })();
+    </script>";
+
+/// Bodies of inline `<script>` elements (scripts without attributes)
+fn inline_scripts(html: &str) -> impl Iterator<Item = &str> {
+    html.split("<script>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</script>").map(|(script, _)| script))
+}
+
+/// Path of a page fragment inside a split render
+#[must_use]
+pub fn page_path(page_num: usize) -> String {
+    format!("pages/page-{page_num:04}.html")
+}
+
+/// Output of a split (per-page) render
+#[derive(Debug, Clone)]
+pub struct PaginatedHtml {
+    /// Index shell with navigation (`index.html`)
+    pub index: String,
+    /// Shared stylesheet (`styles.css`)
+    pub stylesheet: String,
+    /// Page fragments, in order; page `n` lives at [`page_path`]`(n)`
+    pub pages: Vec<String>,
+    /// Linked resources by path (empty when resources are embedded)
+    pub assets: BTreeMap<String, Vec<u8>>,
+    /// Fidelity notes such as font substitutions
+    pub diagnostics: RenderDiagnostics,
+}
+
+impl PaginatedHtml {
+    /// All files as `(path, contents)` pairs
+    #[must_use]
+    pub fn files(&self) -> Vec<(String, Vec<u8>)> {
+        let mut files = vec![
+            ("index.html".to_string(), self.index.clone().into_bytes()),
+            (
+                "styles.css".to_string(),
+                self.stylesheet.clone().into_bytes(),
+            ),
+        ];
+        files.extend(
+            self.pages
+                .iter()
+                .enumerate()
+                .map(|(i, page)| (page_path(i + 1), page.clone().into_bytes())),
         );
+        files.extend(self.assets.clone());
+        files
+    }
 
-        Ok(Bytes::from(html))
+    /// Package every file into a deterministic ZIP archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be written.
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        let mut zip = DeterministicZipWriter::new().with_leading(["index.html"]);
+        for (path, data) in self.files() {
+            zip.add(path, data);
+        }
+        zip.finish()
+    }
+}
+
+/// A single HTML document plus the resources it links to
+#[derive(Debug, Clone)]
+pub struct HtmlOutput {
+    /// The HTML document (`index.html`)
+    pub html: String,
+    /// Linked resources by path (empty when resources are embedded)
+    pub assets: BTreeMap<String, Vec<u8>>,
+    /// Fidelity notes such as font substitutions
+    pub diagnostics: RenderDiagnostics,
+}
+
+impl HtmlOutput {
+    /// Package the document and its resources into a deterministic ZIP archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be written.
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        let mut zip = DeterministicZipWriter::new().with_leading(["index.html"]);
+        zip.add("index.html", self.html.as_bytes());
+        for (path, data) in &self.assets {
+            zip.add(path.as_str(), data.as_slice());
+        }
+        zip.finish()
+    }
+}
+
+/// Renders the pages of a [`DocumentStream`] one at a time
+///
+/// Holds the document header for resource lookups; images that arrive with
+/// a page are dropped again once that page is rendered.
+struct PageWriter {
+    renderer: HtmlRenderer,
+    document: Document,
+    options: RenderOptions,
+    page_num: usize,
+}
+
+impl PageWriter {
+    /// Markup of the next page, preceded by the separator from the page
+    /// before it
+    fn page(&mut self, streamed: StreamedPage) -> String {
+        let StreamedPage { mut page, images } = streamed;
+        if !self.options.content.is_all() {
+            filter_page(&mut page, &self.options.content);
+        }
+        if let Some(normalization) = &self.options.page_size {
+            normalize_page(&mut page, normalization);
+        }
+
+        let shared = self.document.resources.images.len();
+        self.document.resources.images.extend(images);
+        self.page_num += 1;
+        let html = self
+            .renderer
+            .render_page(&self.document, &page, self.page_num);
+        self.document.resources.images.truncate(shared);
+
+        if self.page_num == 1 {
+            html
+        } else {
+            format!("\n{html}")
+        }
+    }
+}
+
+#[async_trait]
+impl Renderer for HtmlRenderer {
+    fn output_format(&self) -> Format {
+        Format {
+            mime_type: "text/html".to_string(),
+            extension: "html".to_string(),
+            family: prism_core::format::FormatFamily::Text,
+            name: "HTML5".to_string(),
+            is_container: false,
+        }
+    }
+
+    async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes> {
+        if context.options.pagination == Pagination::Split
+            && context.options.imposition == Imposition::None
+        {
+            return self
+                .for_context(&context)
+                .render_split(&prepared(document, &context.options))
+                .to_zip()
+                .map(Bytes::from);
+        }
+
+        let output = self
+            .for_context(&context)
+            .render_with_assets(document, &context.options);
+        if self.config.embed_resources {
+            Ok(Bytes::from(output.html))
+        } else {
+            // Linked resources have to travel with the HTML
+            output.to_zip().map(Bytes::from)
+        }
+    }
+
+    async fn render_with_diagnostics(
+        &self,
+        document: &Document,
+        context: RenderContext,
+    ) -> Result<RenderResult> {
+        let output = self.render(document, context).await?;
+        Ok(RenderResult {
+            output,
+            diagnostics: diagnostics(document),
+        })
+    }
+
+    /// Write the head as soon as the document header is known and then one
+    /// chunk per page
+    ///
+    /// Output that needs every page up front (see
+    /// [`HtmlRenderer::render_with_assets`]) is rendered once the stream
+    /// has been read, as is a lone page, which may be an embedded viewer
+    /// rendered without a page wrapper.
+    async fn render_stream(
+        &self,
+        document: DocumentStream,
+        context: RenderContext,
+    ) -> Result<ByteStream> {
+        let (header, mut pages) = document.into_parts();
+        let mut first = Vec::new();
+        while first.len() < 2 {
+            match pages.next().await {
+                Some(page) => first.push(page),
+                None => break,
+            }
+        }
+        let lone_page = first.len() < 2;
+        let pages = stream::iter(first).chain(pages);
+        if lone_page || !self.can_stream(&context.options) || self.is_unpaged(&header) {
+            let document = DocumentStream::new(header, pages).collect().await?;
+            let output = self.render(&document, context).await?;
+            return Ok(stream::once(async move { Ok(output) }).boxed());
+        }
+
+        let renderer = self.for_context(&context);
+        let (open, close) = renderer.shell_parts(&header);
+        let writer = PageWriter {
+            renderer,
+            document: header,
+            options: context.options,
+            page_num: 0,
+        };
+        let body = pages.scan(writer, |writer, page| {
+            let chunk = page.map(|page| Bytes::from(writer.page(page)));
+            async move { Some(chunk) }
+        });
+        Ok(stream::once(async move { Ok(Bytes::from(open)) })
+            .chain(body)
+            .chain(stream::once(async move { Ok(Bytes::from(close)) }))
+            .boxed())
     }
 
     fn metadata(&self) -> RendererMetadata {
@@ -682,6 +2201,9 @@ impl Renderer for HtmlRenderer {
                 RenderFeature::TextRendering,
                 RenderFeature::ImageRendering,
                 RenderFeature::TableRendering,
+                RenderFeature::PageRangeSupport,
+                RenderFeature::StreamingSupport,
+                RenderFeature::Imposition,
             ],
         }
     }
@@ -709,6 +2231,7 @@ mod tests {
         let context = RenderContext {
             options: prism_core::render::RenderOptions::default(),
             filename: None,
+            progress: None,
         };
 
         let result = renderer.render(&document, context).await;
@@ -748,6 +2271,7 @@ mod tests {
         let context = RenderContext {
             options: prism_core::render::RenderOptions::default(),
             filename: None,
+            progress: None,
         };
 
         let result = renderer.render(&document, context).await;
@@ -757,4 +2281,791 @@ mod tests {
         assert!(html.contains("Page 1"));
         assert!(html.contains("Page 2"));
     }
+
+    fn two_page_document() -> Document {
+        let page = |number| Page {
+            number,
+            dimensions: Dimensions::LETTER,
+            content: vec![],
+            metadata: Default::default(),
+            annotations: vec![],
+        };
+        Document::builder()
+            .metadata(Metadata::builder().title("Deck").build())
+            .page(page(1))
+            .page(page(2))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_render_deferred() {
+        let renderer = HtmlRenderer::new();
+        let context = RenderContext {
+            options: prism_core::render::RenderOptions {
+                pagination: Pagination::Deferred,
+                ..Default::default()
+            },
+            filename: None,
+            progress: None,
+        };
+
+        let html = renderer
+            .render(&two_page_document(), context)
+            .await
+            .unwrap();
+        let html = String::from_utf8(html.to_vec()).unwrap();
+        assert_eq!(html.matches(r#"<div class="page-slot""#).count(), 2);
+        assert!(html.contains("<template>"));
+        assert!(html.contains("IntersectionObserver"));
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(std::sync::Mutex<Vec<ProgressEvent>>);
+
+    impl ProgressSink for Recorder {
+        fn report(&self, event: ProgressEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_progress() {
+        let recorder = Arc::new(Recorder::default());
+        let context = RenderContext {
+            options: prism_core::render::RenderOptions::default(),
+            filename: None,
+            progress: Some(recorder.clone()),
+        };
+
+        HtmlRenderer::new()
+            .render(&two_page_document(), context)
+            .await
+            .unwrap();
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                ProgressEvent::PageRendered {
+                    rendered: 1,
+                    total: Some(2)
+                },
+                ProgressEvent::PageRendered {
+                    rendered: 2,
+                    total: Some(2)
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_split() {
+        let split = HtmlRenderer::new().render_split(&two_page_document());
+        assert_eq!(split.pages.len(), 2);
+        assert!(split.index.contains("of 2"));
+        assert!(split.index.contains(&page_path(1)));
+        assert!(split.pages[1].contains("Page 2"));
+
+        let paths: Vec<_> = split.files().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "index.html",
+                "styles.css",
+                "pages/page-0001.html",
+                "pages/page-0002.html"
+            ]
+        );
+
+        let zip = split.to_zip().unwrap();
+        assert_eq!(zip, split.to_zip().unwrap());
+        assert!(zip.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_render_booklet() {
+        let options = prism_core::render::RenderOptions {
+            imposition: Imposition::Booklet,
+            ..Default::default()
+        };
+        let html = HtmlRenderer::new()
+            .render_with_assets(&two_page_document(), &options)
+            .html;
+
+        assert_eq!(html.matches(r#"<div class="sheet""#).count(), 2);
+        assert_eq!(html.matches("blank-page").count(), 2);
+        assert!(html.contains("break-after: page"));
+    }
+
+    #[test]
+    fn test_semantic_layout() {
+        let renderer = HtmlRenderer::with_config(HtmlConfig {
+            layout: HtmlLayout::Semantic,
+            ..HtmlConfig::default()
+        });
+        let html = renderer
+            .render_with_assets(
+                &two_page_document(),
+                &prism_core::render::RenderOptions::default(),
+            )
+            .html;
+
+        assert_eq!(html.matches(r#"<section class="semantic-page""#).count(), 2);
+        assert!(html.contains(".semantic-page {"));
+        assert!(!html.contains("position: absolute"));
+    }
+
+    #[test]
+    fn test_page_size_normalization() {
+        use prism_core::render::{PageFit, PageNormalization};
+
+        let options = prism_core::render::RenderOptions {
+            page_size: Some(PageNormalization::new(Dimensions::A4, PageFit::Fit)),
+            ..Default::default()
+        };
+        let html = HtmlRenderer::new()
+            .render_with_assets(&two_page_document(), &options)
+            .html;
+        assert!(html.contains("width: 595.28pt"));
+        assert!(!html.contains("width: 612pt"));
+    }
+
+    #[test]
+    fn test_fonts() {
+        use prism_core::document::{FontResource, Rect, TextBlock, TextRun};
+
+        let mut document = two_page_document();
+        let mut block = TextBlock::new(Rect::default());
+        for family in ["Calibri", "Inter"] {
+            let mut run = TextRun::new(family);
+            run.style.font_family = Some(family.into());
+            block.add_run(run);
+        }
+        document.pages[0].content.push(ContentBlock::Text(block));
+        document.resources.fonts.push(FontResource {
+            embedded: true,
+            data: Some(b"OTTO font".to_vec()),
+            ..FontResource::new("Inter", "Regular")
+        });
+        let options = prism_core::render::RenderOptions::default();
+
+        let output = HtmlRenderer::new().render_with_assets(&document, &options);
+        assert!(output
+            .html
+            .contains("font-family: 'Calibri', 'Carlito', sans-serif"));
+        assert!(output.html.contains("url('data:font/otf;base64,"));
+        assert_eq!(output.diagnostics.font_substitutions.len(), 1);
+        assert_eq!(
+            output.diagnostics.font_substitutions[0].substitute,
+            "Carlito"
+        );
+
+        let linked = HtmlRenderer::with_config(HtmlConfig {
+            embed_resources: false,
+            ..HtmlConfig::default()
+        })
+        .render_with_assets(&document, &options);
+        assert!(linked
+            .html
+            .contains("url('assets/font-0001.otf') format('opentype')"));
+        assert!(linked.assets.contains_key("assets/font-0001.otf"));
+    }
+
+    #[test]
+    fn test_ink_saving() {
+        use prism_core::document::{Rect, TextBlock, TextRun};
+        use prism_core::render::ColorMode;
+
+        let mut document = two_page_document();
+        let mut slide = TextBlock::new(Rect::new(0.0, 0.0, 612.0, 792.0));
+        slide.style.fill_color = Some("#1F3864".to_string());
+        let mut run = TextRun::new("Title");
+        run.style.color = Some("#FFFFFF".into());
+        slide.add_run(run);
+        document.pages[0].content.push(ContentBlock::Text(slide));
+
+        let render = |color_mode| {
+            let options = prism_core::render::RenderOptions {
+                color_mode,
+                ..Default::default()
+            };
+            HtmlRenderer::new()
+                .render_with_assets(&document, &options)
+                .html
+        };
+
+        let color = render(ColorMode::Color);
+        assert!(color.contains("background-color: #1F3864"));
+        assert!(!color.contains("grayscale(100%)"));
+
+        let grayscale = render(ColorMode::Grayscale);
+        assert!(grayscale.contains("background-color: #363636"));
+        assert!(grayscale.contains("color: #ffffff"));
+        assert!(grayscale.contains("filter: grayscale(100%)"));
+
+        let ink_saving = render(ColorMode::InkSaving);
+        assert!(ink_saving.contains("background-color: #e1e1e1"));
+        assert!(ink_saving.contains("color: #000000"));
+    }
+
+    #[test]
+    fn test_fills() {
+        use prism_core::document::{
+            GradientFill, GradientStop, PathCommand, Point, Rect, TextBlock, TextRun, VectorBlock,
+            VectorPath,
+        };
+
+        let gradient = Fill::Gradient(GradientFill {
+            stops: vec![
+                GradientStop {
+                    position: 0.0,
+                    color: "#4472C4".to_string(),
+                },
+                GradientStop {
+                    position: 1.0,
+                    color: "#1F3864".to_string(),
+                },
+            ],
+            angle: 90.0,
+            radial: false,
+        });
+        let mut document = two_page_document();
+        let page = &mut document.pages[0].content;
+        page.push(ContentBlock::Vector(VectorBlock {
+            bounds: Rect::new(0.0, 0.0, 612.0, 792.0),
+            paths: vec![VectorPath {
+                commands: vec![
+                    PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                    PathCommand::LineTo(Point::new(612.0, 792.0)),
+                    PathCommand::Close,
+                ],
+                fill: Some("#4472C4".to_string()),
+                stroke: None,
+                stroke_width: None,
+                fill_style: Some(gradient.clone()),
+                start_arrow: None,
+                end_arrow: None,
+            }],
+        }));
+        let mut banner = TextBlock::new(Rect::new(36.0, 36.0, 540.0, 72.0));
+        banner.add_run(TextRun::new("Quarterly results"));
+        banner.style.fill = Some(gradient);
+        page.push(ContentBlock::Text(banner.clone()));
+        banner.style.fill = Some(Fill::Pattern {
+            preset: "dkHorz".to_string(),
+            foreground: "#000000".to_string(),
+            background: "#FFFFFF".to_string(),
+        });
+        page.push(ContentBlock::Text(banner));
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &prism_core::render::RenderOptions::default())
+            .html;
+        assert!(
+            html.contains("background-image: linear-gradient(180deg, #4472C4 0%, #1F3864 100%);")
+        );
+        assert!(html.contains("background-image: url('data:image/svg+xml;base64,"));
+        let (_, definition) = html.split_once("<defs><linearGradient id=\"").unwrap();
+        let id = &definition[..definition.find('"').unwrap()];
+        assert!(html.contains(&format!("fill=\"url(#{id})\"")));
+        assert!(html.contains(r##"<stop offset="1" stop-color="#1F3864"/>"##));
+    }
+
+    #[test]
+    fn test_arrowheads() {
+        use prism_core::document::{
+            Arrowhead, ArrowheadKind, PathCommand, Point, VectorBlock, VectorPath,
+        };
+
+        let mut document = two_page_document();
+        // A horizontal connector, with no height
+        document.pages[0]
+            .content
+            .push(ContentBlock::Vector(VectorBlock {
+                bounds: Rect::new(100.0, 50.0, 200.0, 0.0),
+                paths: vec![VectorPath {
+                    commands: vec![
+                        PathCommand::MoveTo(Point::new(0.0, 0.0)),
+                        PathCommand::LineTo(Point::new(200.0, 0.0)),
+                    ],
+                    fill: None,
+                    stroke: Some("#FF0000".to_string()),
+                    stroke_width: Some(2.0),
+                    fill_style: None,
+                    start_arrow: Some(Arrowhead {
+                        kind: ArrowheadKind::Oval,
+                        width: 2.0,
+                        length: 2.0,
+                    }),
+                    end_arrow: Some(Arrowhead {
+                        kind: ArrowheadKind::Triangle,
+                        width: 3.0,
+                        length: 5.0,
+                    }),
+                }],
+            }));
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &prism_core::render::RenderOptions::default())
+            .html;
+        // Room for the larger arrowhead, 5 line widths long, all around
+        assert!(html.contains("left: 95pt; top: 45pt; width: 210pt; height: 10pt;"));
+        assert!(html.contains(r#"<svg viewBox="-5 -5 210 10""#));
+        assert_eq!(html.matches("<marker id=").count(), 2);
+        assert!(html.contains(r#"markerWidth="5" markerHeight="3" orient="auto-start-reverse""#));
+        assert!(html.contains(r##"<path d="M0 0L10 5L0 10Z" fill="#FF0000"/>"##));
+        let (_, end) = html.split_once(r#"marker-end="url(#"#).unwrap();
+        let (id, _) = end.split_once(')').unwrap();
+        assert!(html.contains(&format!(r#"<marker id="{id}""#)));
+    }
+
+    #[test]
+    fn test_flowing_chart_container() {
+        use prism_core::document::{ContainerBlock, VectorBlock};
+
+        let mut document = two_page_document();
+        document.pages[0]
+            .content
+            .push(ContentBlock::Container(ContainerBlock {
+                bounds: Rect::default(),
+                children: vec![ContentBlock::Vector(VectorBlock {
+                    bounds: Rect::new(40.0, 20.0, 360.0, 200.0),
+                    paths: Vec::new(),
+                })],
+                container_type: Some("chart".to_string()),
+            }));
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &prism_core::render::RenderOptions::default())
+            .html;
+        assert!(html.contains(
+            r#"<div class="container-block" style="position: relative; width: 400pt; height: 220pt;">"#
+        ));
+    }
+
+    #[test]
+    fn test_watermark_and_bates_stamps() {
+        use prism_core::render::{BatesNumbering, StampPosition, Watermark};
+
+        let document = two_page_document();
+        let options = RenderOptions {
+            watermark: Some(
+                Watermark::text("CONFIDENTIAL <draft>")
+                    .with_position(StampPosition::Top)
+                    .first_page_only(),
+            ),
+            bates: Some(BatesNumbering::new("ACME").with_start(41)),
+            ..Default::default()
+        };
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &options)
+            .html;
+
+        assert_eq!(html.matches(r#"class="watermark""#).count(), 1);
+        assert!(html.contains("CONFIDENTIAL &lt;draft&gt;"));
+        assert!(html.contains("rotate(-45deg); opacity: 0.3;"));
+        let first = html.find("ACME000041").unwrap();
+        let second = html.find("ACME000042").unwrap();
+        assert!(html.find(r#"id="page-2""#).unwrap() > first);
+        assert!(html.find(r#"id="page-2""#).unwrap() < second);
+
+        let plain = HtmlRenderer::new()
+            .render_with_assets(&document, &RenderOptions::default())
+            .html;
+        assert!(!plain.contains("bates-number"));
+    }
+
+    #[test]
+    fn test_text_direction_and_language() {
+        use prism_core::document::{Rect, TextBlock, TextRun};
+
+        let mut document = two_page_document();
+        let mut block = TextBlock::new(Rect::default());
+        block.direction = TextDirection::Rtl;
+        let mut run = TextRun::new("مرحبا");
+        run.style.language = Some("ar-SA".into());
+        block.add_run(run);
+        let mut run = TextRun::new("你好");
+        run.style.language = Some("zh-CN".into());
+        run.style.bold = true;
+        block.add_run(run);
+        document.pages[0].content.push(ContentBlock::Text(block));
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &RenderOptions::default())
+            .html;
+        assert!(html.contains(r#"class="text-content" dir="rtl""#));
+        assert!(html.contains(r#"<span lang="ar-SA">مرحبا</span>"#));
+        assert!(html.contains(r#"<span lang="zh-CN"><strong>你好</strong></span>"#));
+        assert!(html.contains("text-align: start"));
+
+        let semantic = HtmlRenderer::with_config(HtmlConfig {
+            layout: HtmlLayout::Semantic,
+            ..Default::default()
+        })
+        .render_with_assets(&document, &RenderOptions::default())
+        .html;
+        assert!(semantic.contains(r#"<p dir="rtl"><span lang="ar-SA">"#));
+    }
+
+    #[test]
+    fn test_sheet_area() {
+        use prism_core::document::{
+            PageMetadata, Rect, SheetLayout, TableBlock, TableCell, TableRow, TextBlock, TextRun,
+        };
+
+        let cell = |value: &str| {
+            let mut block = TextBlock::new(Rect::default());
+            block.add_run(TextRun::new(value));
+            TableCell {
+                content: vec![ContentBlock::Text(block)],
+                col_span: 1,
+                row_span: 1,
+                background_color: None,
+            }
+        };
+        let mut table = TableBlock::new(Rect::default(), 2);
+        for values in [["Region", "Sales"], ["North", "12"], ["Notes", "draft"]] {
+            table.add_row(TableRow {
+                cells: values.into_iter().map(cell).collect(),
+                height: None,
+            });
+        }
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.add_content(ContentBlock::Table(table));
+        page.metadata = PageMetadata {
+            sheet: Some(SheetLayout {
+                print_areas: vec![CellRange {
+                    first_row: 0,
+                    first_column: 0,
+                    last_row: 1,
+                    last_column: 1,
+                }],
+                ..SheetLayout::default()
+            }),
+            ..PageMetadata::default()
+        };
+        let mut document = Document::new();
+        document.pages.push(page);
+
+        let render = |sheet_area| {
+            HtmlRenderer::with_config(HtmlConfig {
+                sheet_area,
+                ..Default::default()
+            })
+            .render_with_assets(&document, &RenderOptions::default())
+            .html
+        };
+        assert!(render(SheetArea::Grid).contains("draft"));
+        assert!(render(SheetArea::Used).contains("draft"));
+        let print = render(SheetArea::Print);
+        assert!(print.contains("North"));
+        assert!(!print.contains("draft"));
+    }
+
+    #[test]
+    fn test_links() {
+        use prism_core::document::{Rect, TextBlock, TextRun};
+
+        let mut document = two_page_document();
+        let mut block = TextBlock::new(Rect::default());
+        block.add_run(TextRun::new("Docs").with_link(Link::Url("https://example.com/?a&b".into())));
+        block.add_run(TextRun::new("Back").with_link(Link::Page(1)));
+        block.add_run(TextRun::new("Bad").with_link(Link::Url(" Java\tScript:alert(1)".into())));
+        document.pages[1].content.push(ContentBlock::Text(block));
+
+        for layout in [HtmlLayout::Positioned, HtmlLayout::Semantic] {
+            let html = HtmlRenderer::with_config(HtmlConfig {
+                layout,
+                ..Default::default()
+            })
+            .render_with_assets(&document, &RenderOptions::default())
+            .html;
+            assert!(html.contains(r#"id="page-1""#));
+            assert!(html.contains(r#"<a href="https://example.com/?a&amp;b">Docs</a>"#));
+            assert!(html.contains(r##"<a href="#page-1">Back</a>"##));
+            assert!(html.contains(r##"<a href="#">Bad</a>"##));
+        }
+    }
+
+    #[test]
+    fn test_form_fields() {
+        use prism_core::document::{FormFieldBlock, FormFieldType, Rect};
+
+        let mut document = two_page_document();
+        let mut name = FormFieldBlock::new(
+            FormFieldType::Text,
+            "name".to_string(),
+            Rect::new(72.0, 80.0, 200.0, 20.0),
+        );
+        name.value = Some("Ada <3".to_string());
+        let mut color = FormFieldBlock::new(
+            FormFieldType::Dropdown,
+            "color".to_string(),
+            Rect::default(),
+        );
+        color.options = vec!["Red".to_string(), "Blue".to_string()];
+        color.value = Some("Blue".to_string());
+        let mut agree = FormFieldBlock::new(
+            FormFieldType::Checkbox,
+            "agree".to_string(),
+            Rect::default(),
+        );
+        agree.checked = true;
+        for field in [name, color, agree] {
+            document.pages[1]
+                .content
+                .push(ContentBlock::FormField(field));
+        }
+
+        let render = |forms| {
+            HtmlRenderer::with_config(HtmlConfig {
+                forms,
+                ..Default::default()
+            })
+            .render_with_assets(&document, &RenderOptions::default())
+            .html
+        };
+        let html = render(FormRendering::Disabled);
+        assert!(html.contains("left: 72pt; top: 80pt;"));
+        assert!(html.contains(
+            r#"<input type="text" name="name" aria-label="name" disabled value="Ada &lt;3">"#
+        ));
+        assert!(html.contains("<option>Red</option><option selected>Blue</option>"));
+        assert!(html.contains(
+            r#"<input type="checkbox" name="agree" aria-label="agree" disabled checked>"#
+        ));
+
+        let html = render(FormRendering::Flattened);
+        assert!(!html.contains("<input"));
+        assert!(html.contains(r#"<span class="form-value">Ada &lt;3</span>"#));
+        assert!(html.contains(r#"<span class="form-value">☒</span>"#));
+    }
+
+    #[test]
+    fn test_image_crop_and_flip() {
+        use prism_core::document::ImageCrop;
+
+        let mut document = image_document();
+        let Some(ContentBlock::Image(image)) = document.pages[1].content.last_mut() else {
+            panic!("expected an image");
+        };
+        image.crop = Some(ImageCrop {
+            left: 0.25,
+            top: 0.0,
+            right: 0.25,
+            bottom: 0.5,
+        });
+        image.rotation = 90.0;
+        image.flip_vertical = true;
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &prism_core::render::RenderOptions::default())
+            .html;
+        assert!(html.contains(
+            "width: 50pt; height: 50pt; overflow: hidden; transform: rotate(90deg) scale(1, -1); transform-origin: center;"
+        ));
+        assert!(html.contains("left: -50%; top: 0%; width: 200%; height: 200%; max-width: none;"));
+    }
+
+    fn image_document() -> Document {
+        use prism_core::document::{ImageBlock, ImageResource, Rect, ShapeStyle};
+
+        let mut document = two_page_document();
+        document.resources.images.push(ImageResource {
+            id: "rId1".to_string(),
+            mime_type: "image/png".to_string(),
+            data: Some(vec![0x89, b'P', b'N', b'G']),
+            url: None,
+            width: 1,
+            height: 1,
+        });
+        document.pages[1]
+            .content
+            .push(ContentBlock::Image(ImageBlock {
+                bounds: Rect::new(10.0, 10.0, 50.0, 50.0),
+                resource_id: "rId1".to_string(),
+                alt_text: None,
+                format: None,
+                original_size: None,
+                style: ShapeStyle::default(),
+                rotation: 0.0,
+                crop: None,
+                flip_horizontal: false,
+                flip_vertical: false,
+            }));
+        document
+    }
+
+    #[tokio::test]
+    async fn test_render_stream() {
+        // The image resource travels with the second page
+        let streamed = || {
+            let mut header = image_document();
+            let images = std::mem::take(&mut header.resources.images);
+            let second = header.pages.pop().unwrap();
+            let pages = stream::iter([Ok(StreamedPage {
+                page: second,
+                images,
+            })]);
+            DocumentStream::new(header, pages)
+        };
+        let context = || RenderContext {
+            options: RenderOptions::default(),
+            filename: None,
+            progress: None,
+        };
+        let renderer = HtmlRenderer::new();
+
+        let chunks: Vec<Bytes> = renderer
+            .render_stream(streamed(), context())
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 4);
+        assert!(std::str::from_utf8(&chunks[2])
+            .unwrap()
+            .contains("data:image/png"));
+        let expected = renderer
+            .render(&streamed().collect().await.unwrap(), context())
+            .await
+            .unwrap();
+        assert_eq!(chunks.concat(), expected.to_vec());
+
+        // Split output needs every page before it can start
+        let split = RenderContext {
+            options: RenderOptions {
+                pagination: Pagination::Split,
+                ..RenderOptions::default()
+            },
+            filename: None,
+            progress: None,
+        };
+        let chunks: Vec<_> = renderer
+            .render_stream(streamed(), split)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_external_assets() {
+        let renderer = HtmlRenderer::with_config(HtmlConfig {
+            embed_resources: false,
+            ..HtmlConfig::default()
+        });
+        let document = image_document();
+
+        let output =
+            renderer.render_with_assets(&document, &prism_core::render::RenderOptions::default());
+        assert!(output.html.contains(r#"src="assets/image-0001.png""#));
+        assert!(!output.html.contains("base64"));
+        assert_eq!(output.assets.len(), 1);
+        assert_eq!(
+            output.assets["assets/image-0001.png"],
+            vec![0x89, b'P', b'N', b'G']
+        );
+
+        let mut written = Vec::new();
+        renderer
+            .write_assets(&document, |path, _| {
+                written.push(path.to_string());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(written, vec!["assets/image-0001.png"]);
+
+        let split = renderer.render_split(&document);
+        assert!(split.pages[1].contains(r#"src="../assets/image-0001.png""#));
+        assert!(split
+            .files()
+            .iter()
+            .any(|(path, _)| path == "assets/image-0001.png"));
+
+        let context = RenderContext {
+            options: prism_core::render::RenderOptions::default(),
+            filename: None,
+            progress: None,
+        };
+        let zip = renderer.render(&document, context).await.unwrap();
+        assert!(zip.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_embedded_assets_by_default() {
+        let renderer = HtmlRenderer::new();
+        let document = image_document();
+
+        let output =
+            renderer.render_with_assets(&document, &prism_core::render::RenderOptions::default());
+        assert!(output.html.contains("data:image/png;base64,"));
+        assert!(output.assets.is_empty());
+    }
+
+    fn pdf_document(payload: &str) -> Document {
+        use prism_core::document::{Rect, TextBlock, TextRun};
+
+        let mut block = TextBlock::new(Rect::default());
+        block.add_run(TextRun::new(format!("__PDF_DATA__:{payload}")));
+        let mut document = two_page_document();
+        document.pages.truncate(1);
+        document.pages[0].content.push(ContentBlock::Text(block));
+        document
+    }
+
+    #[test]
+    fn test_pdf_viewer_modes() {
+        let options = prism_core::render::RenderOptions::default();
+        let document = pdf_document("JVBERi0xLjQ=");
+
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &options)
+            .html;
+        assert!(html.contains("Download PDF"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("cdnjs"));
+        assert!(html.contains("script-src &#x27;none&#x27;"));
+
+        let bundled = HtmlRenderer::with_config(HtmlConfig {
+            pdf_viewer: PdfViewer::Bundled {
+                script_url: "vendor/pdf.min.js".to_string(),
+                worker_url: "vendor/pdf.worker.min.js".to_string(),
+            },
+            ..HtmlConfig::default()
+        });
+        let html = bundled.render_with_assets(&document, &options).html;
+        assert!(html.contains(r#"<script src="vendor/pdf.min.js"></script>"#));
+        assert!(html.contains(r#"data-pdf="JVBERi0xLjQ=""#));
+        assert!(!html.contains("onclick"));
+
+        let script = inline_scripts(&html).next().unwrap();
+        let hash = general_purpose::STANDARD.encode(Sha256::digest(script.as_bytes()));
+        assert!(html.contains(&format!("sha256-{hash}")));
+    }
+
+    #[test]
+    fn test_pdf_marker_injection_is_escaped() {
+        let document = pdf_document("'); alert(1); //");
+        let html = HtmlRenderer::new()
+            .render_with_assets(&document, &prism_core::render::RenderOptions::default())
+            .html;
+        assert!(!html.contains("Download PDF"));
+        assert!(!html.contains("alert(1); //\""));
+        assert!(html.contains("&#x27;); alert(1); //"));
+    }
+
+    #[test]
+    fn test_css_value() {
+        assert_eq!(css_value("#FF0000"), "#FF0000");
+        assert_eq!(css_value("rgb(1, 2, 3)"), "rgb(1, 2, 3)");
+        assert_eq!(css_value("Arial; background: red"), "Arial background red");
+        assert_eq!(css_value("red\"><script>"), "redscript");
+        assert_eq!(css_value("url(http://evil)"), "");
+        assert_eq!(
+            safe_mime("image/png\" onerror=\"x"),
+            "application/octet-stream"
+        );
+    }
 }
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Page annotations drawn over rendered pages.
+//!
+//! Each annotation is an absolutely positioned element over its bounds in
+//! the page box, drawn above the content and below watermarks and Bates
+//! numbers:
+//!
+//! - highlights are translucent fills, underlines and strikeouts lines
+//!   through their box
+//! - comments are small note markers whose tooltip gives the author, date
+//!   and text, which is also their accessible label
+//! - links are clickable areas
+//! - redactions are opaque black boxes
+//! - stamps are bordered labels
+//!
+//! Redaction boxes only hide what is underneath: the covered text stays in
+//! the markup. Remove it from the document before rendering when it must
+//! not be disclosed.
+
+use prism_core::document::{Annotation, AnnotationType, Link, Page, Rect};
+use std::fmt::Write as _;
+
+use super::{html_escape, link_href, HtmlRenderer};
+use crate::color::Paint;
+
+/// Color of highlights without one of their own
+const HIGHLIGHT_COLOR: &str = "#ffeb3b";
+
+/// Color of underlines, strikeouts and stamps without one of their own
+const MARKUP_COLOR: &str = "#d32f2f";
+
+/// Color of comment markers without one of their own
+const COMMENT_COLOR: &str = "#ffc107";
+
+/// Side of a comment marker, in points
+const COMMENT_MARKER: f64 = 12.0;
+
+impl HtmlRenderer {
+    /// Overlays for the annotations of `page` that the current render
+    /// draws, empty if there are none
+    pub(super) fn annotations(&self, page: &Page) -> String {
+        let mut html = String::new();
+        for annotation in page
+            .annotations
+            .iter()
+            .filter(|annotation| self.annotations.includes(&annotation.annotation_type))
+        {
+            html.push_str(&self.annotation(annotation));
+        }
+        html
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> String {
+        let bounds = annotation.bounds;
+        let color = |default: &str, paint: Paint| {
+            self.paint(annotation.color.as_deref().unwrap_or(default), paint)
+        };
+        let title = html_escape(&tooltip(annotation));
+        match &annotation.annotation_type {
+            AnnotationType::Highlight => format!(
+                r#"<div class="annotation highlight" title="{title}" style="{} background-color: {}; opacity: 0.4; mix-blend-mode: multiply; z-index: 5;"></div>"#,
+                place(bounds),
+                color(HIGHLIGHT_COLOR, Paint::Fill)
+            ),
+            AnnotationType::Underline => format!(
+                r#"<div class="annotation underline" title="{title}" style="{} border-bottom: 1pt solid {}; box-sizing: border-box; z-index: 5;"></div>"#,
+                place(bounds),
+                color(MARKUP_COLOR, Paint::Ink)
+            ),
+            AnnotationType::Strikeout => format!(
+                r#"<div class="annotation strikeout" title="{title}" style="position: absolute; left: {}pt; top: {}pt; width: {}pt; height: 0; border-top: 1pt solid {}; z-index: 5;"></div>"#,
+                bounds.x,
+                bounds.y + bounds.height / 2.0,
+                bounds.width,
+                color(MARKUP_COLOR, Paint::Ink)
+            ),
+            AnnotationType::Comment => format!(
+                r#"<div class="annotation comment" role="note" aria-label="{title}" title="{title}" style="position: absolute; left: {}pt; top: {}pt; width: {COMMENT_MARKER}pt; height: {COMMENT_MARKER}pt; background-color: {}; border-radius: 2pt; z-index: 6;"></div>"#,
+                bounds.x,
+                bounds.y,
+                color(COMMENT_COLOR, Paint::Fill)
+            ),
+            AnnotationType::Link { url } => format!(
+                r#"<a class="annotation link" href="{}" title="{title}" style="{} display: block; z-index: 6;"></a>"#,
+                html_escape(&link_href(&Link::Url(url.clone()))),
+                place(bounds)
+            ),
+            // Stays black in every color mode, or it would no longer hide
+            // anything
+            AnnotationType::Redaction => format!(
+                r#"<div class="annotation redaction" aria-label="Redacted" style="{} background-color: #000000; z-index: 7;"></div>"#,
+                place(bounds)
+            ),
+            AnnotationType::Stamp => {
+                let ink = color(MARKUP_COLOR, Paint::Ink);
+                format!(
+                    r#"<div class="annotation stamp" title="{title}" style="{} border: 2pt solid {ink}; color: {ink}; box-sizing: border-box; display: flex; align-items: center; justify-content: center; font-weight: bold; text-transform: uppercase; overflow: hidden; z-index: 5;">{}</div>"#,
+                    place(bounds),
+                    html_escape(annotation.content.as_deref().unwrap_or_default())
+                )
+            }
+            AnnotationType::Ink => String::new(),
+        }
+    }
+}
+
+/// CSS placing an element over `bounds`
+fn place(bounds: Rect) -> String {
+    format!(
+        "position: absolute; left: {}pt; top: {}pt; width: {}pt; height: {}pt;",
+        bounds.x, bounds.y, bounds.width, bounds.height
+    )
+}
+
+/// Author, date and text of an annotation, as shown in its tooltip
+fn tooltip(annotation: &Annotation) -> String {
+    let mut byline = annotation.author.clone().unwrap_or_default();
+    if let Some(created) = annotation.created {
+        if !byline.is_empty() {
+            byline.push_str(", ");
+        }
+        let _ = write!(byline, "{}", created.format("%Y-%m-%d %H:%M"));
+    }
+    let text = match &annotation.annotation_type {
+        AnnotationType::Link { url } => Some(url.as_str()),
+        _ => annotation.content.as_deref(),
+    };
+    match (byline.is_empty(), text) {
+        (true, Some(text)) => text.to_string(),
+        (false, Some(text)) => format!("{byline}: {text}"),
+        (_, None) => byline,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::render::AnnotationOptions;
+
+    fn annotation(annotation_type: AnnotationType, content: Option<&str>) -> Annotation {
+        Annotation {
+            id: uuid::Uuid::nil(),
+            annotation_type,
+            bounds: Rect {
+                x: 10.0,
+                y: 20.0,
+                width: 100.0,
+                height: 12.0,
+            },
+            content: content.map(str::to_string),
+            author: None,
+            created: None,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn test_annotation_markup() {
+        let mut page = Page::new(1, prism_core::document::Dimensions::LETTER);
+        let mut comment = annotation(AnnotationType::Comment, Some("Check <this>"));
+        comment.author = Some("Dana".to_string());
+        comment.created = "2024-03-01T10:00:00Z".parse().ok();
+        page.annotations = vec![
+            annotation(AnnotationType::Highlight, None),
+            comment,
+            annotation(
+                AnnotationType::Link {
+                    url: "javascript:alert(1)".to_string(),
+                },
+                None,
+            ),
+            annotation(AnnotationType::Redaction, None),
+            annotation(AnnotationType::Stamp, Some("Approved")),
+        ];
+
+        let renderer = HtmlRenderer::new();
+        let html = renderer.annotations(&page);
+        assert!(html.contains(r#"class="annotation highlight""#));
+        assert!(html.contains("background-color: #ffeb3b"));
+        assert!(html.contains(r#"title="Dana, 2024-03-01 10:00: Check &lt;this&gt;""#));
+        assert!(html.contains(r##"<a class="annotation link" href="#""##));
+        assert!(html.contains("background-color: #000000; z-index: 7;"));
+        assert!(html.contains(">Approved</div>"));
+
+        let renderer = HtmlRenderer {
+            annotations: AnnotationOptions::none(),
+            ..HtmlRenderer::new()
+        };
+        assert!(renderer.annotations(&page).is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Arrowheads at the ends of lines.
+//!
+//! Arrowheads are SVG markers sized in multiples of the line width and
+//! turned along the line, the one at the start facing backward. Like paint
+//! servers, markers are named after a digest of their shape and color.
+
+use prism_core::document::{Arrowhead, ArrowheadKind, VectorBlock};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+
+use super::html_escape;
+
+/// SVG marker drawing `arrow` in `color` and the `url(#id)` referring
+/// to it
+pub(super) fn svg_marker(arrow: &Arrowhead, color: &str) -> (String, String) {
+    let id = marker_id(arrow, color);
+    let color = html_escape(color);
+    // Drawn in a 10 by 10 box pointing right, the tip on the line end
+    // unless the arrowhead is centered on it
+    let (shape, ref_x) = match arrow.kind {
+        ArrowheadKind::Triangle => (format!(r#"<path d="M0 0L10 5L0 10Z" fill="{color}"/>"#), 10),
+        ArrowheadKind::Stealth => (
+            format!(r#"<path d="M0 0L10 5L0 10L3 5Z" fill="{color}"/>"#),
+            10,
+        ),
+        ArrowheadKind::Open => (
+            format!(r#"<path d="M1 1L9 5L1 9" fill="none" stroke="{color}" stroke-width="2"/>"#),
+            9,
+        ),
+        ArrowheadKind::Diamond => (
+            format!(r#"<path d="M0 5L5 0L10 5L5 10Z" fill="{color}"/>"#),
+            5,
+        ),
+        ArrowheadKind::Oval => (
+            format!(r#"<ellipse cx="5" cy="5" rx="5" ry="5" fill="{color}"/>"#),
+            5,
+        ),
+    };
+    let definition = format!(
+        r#"<marker id="{id}" viewBox="0 0 10 10" refX="{ref_x}" refY="5" markerWidth="{}" markerHeight="{}" orient="auto-start-reverse" preserveAspectRatio="none">{shape}</marker>"#,
+        arrow.length, arrow.width
+    );
+    (definition, format!("url(#{id})"))
+}
+
+/// Room to leave around `vector` so arrowheads and lines along its edges
+/// are not cut off, in points
+///
+/// Only lines with arrowheads and vectors without width or height, such as
+/// a horizontal line, get any.
+pub(super) fn padding(vector: &VectorBlock) -> f64 {
+    let flat = vector.bounds.width <= 0.0 || vector.bounds.height <= 0.0;
+    vector
+        .paths
+        .iter()
+        .map(|path| {
+            let stroke_width = path.stroke_width.unwrap_or(0.0);
+            let arrow = [path.start_arrow, path.end_arrow]
+                .iter()
+                .flatten()
+                .map(|arrow| arrow.width.max(arrow.length))
+                .fold(0.0, f64::max);
+            if arrow > 0.0 {
+                stroke_width * arrow / 2.0
+            } else if flat {
+                stroke_width / 2.0
+            } else {
+                0.0
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+/// ID of the marker drawing `arrow` in `color`
+fn marker_id(arrow: &Arrowhead, color: &str) -> String {
+    let digest = Sha256::digest(format!("{arrow:?}{color}").as_bytes());
+    digest[..8]
+        .iter()
+        .fold(String::from("arrow-"), |mut id, byte| {
+            let _ = write!(id, "{byte:02x}");
+            id
+        })
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Gradient, picture and pattern fills.
+//!
+//! Text boxes are filled with CSS backgrounds: `linear-gradient()` or
+//! `radial-gradient()` for gradients, the image for pictures, and a small
+//! SVG tile for patterns. Vector paths refer to an SVG paint server
+//! (`<linearGradient>`, `<radialGradient>` or `<pattern>`) defined next to
+//! them. Paint servers are named after a digest of the fill, so identical
+//! fills share a definition and the output stays deterministic.
+
+use base64::{engine::general_purpose, Engine as _};
+use prism_core::document::{Document, Fill, GradientFill};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+
+use super::{html_escape, HtmlRenderer};
+use crate::color::Paint;
+
+/// Size of a pattern tile, in points
+const TILE: f64 = 8.0;
+
+impl HtmlRenderer {
+    /// CSS declarations drawing `fill` as the background of a box
+    pub(super) fn css_fill(&self, document: &Document, fill: &Fill, paint: Paint) -> String {
+        match fill {
+            Fill::Gradient(gradient) => {
+                let stops = self.css_stops(gradient, paint);
+                if gradient.radial {
+                    format!("background-image: radial-gradient(circle, {stops});")
+                } else {
+                    // CSS angles run clockwise from bottom to top
+                    format!(
+                        "background-image: linear-gradient({}deg, {stops});",
+                        gradient.angle + 90.0
+                    )
+                }
+            }
+            Fill::Picture { resource_id, tile } => {
+                let Some(src) = self.image_src(document, resource_id) else {
+                    return String::new();
+                };
+                let size = if *tile {
+                    "background-repeat: repeat;"
+                } else {
+                    "background-size: 100% 100%; background-repeat: no-repeat;"
+                };
+                format!("background-image: url('{}'); {size}", css_url(&src))
+            }
+            Fill::Pattern {
+                preset,
+                foreground,
+                background,
+            } => {
+                let tile = self.pattern_tile(preset, foreground, background, paint);
+                format!(
+                    "background-image: url('data:image/svg+xml;base64,{}'); background-size: {TILE}pt {TILE}pt;",
+                    general_purpose::STANDARD.encode(format!(
+                        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{TILE}" height="{TILE}">{tile}</svg>"#
+                    ))
+                )
+            }
+        }
+    }
+
+    /// SVG paint server for `fill` and the `url(#id)` referring to it, or
+    /// `None` if it cannot be drawn
+    pub(super) fn svg_fill(
+        &self,
+        document: &Document,
+        fill: &Fill,
+        paint: Paint,
+    ) -> Option<(String, String)> {
+        let id = paint_server_id(fill);
+        let definition = match fill {
+            Fill::Gradient(gradient) => {
+                let mut stops = String::new();
+                for stop in &gradient.stops {
+                    let _ = write!(
+                        stops,
+                        r#"<stop offset="{}" stop-color="{}"/>"#,
+                        stop.position,
+                        html_escape(&self.paint(&stop.color, paint))
+                    );
+                }
+                if gradient.radial {
+                    format!(r#"<radialGradient id="{id}">{stops}</radialGradient>"#)
+                } else {
+                    let (sin, cos) = gradient.angle.to_radians().sin_cos();
+                    let (dx, dy) = (round(cos / 2.0), round(sin / 2.0));
+                    format!(
+                        r#"<linearGradient id="{id}" x1="{}" y1="{}" x2="{}" y2="{}">{stops}</linearGradient>"#,
+                        0.5 - dx,
+                        0.5 - dy,
+                        0.5 + dx,
+                        0.5 + dy
+                    )
+                }
+            }
+            Fill::Picture { resource_id, tile } => {
+                let src = html_escape(&self.image_src(document, resource_id)?);
+                let image = document
+                    .resources
+                    .images
+                    .iter()
+                    .find(|image| &image.id == resource_id)?;
+                if *tile && image.width > 0 && image.height > 0 {
+                    // Pixels at 96 DPI to points
+                    let width = f64::from(image.width) * 0.75;
+                    let height = f64::from(image.height) * 0.75;
+                    format!(
+                        r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{width}" height="{height}"><image href="{src}" width="{width}" height="{height}"/></pattern>"#
+                    )
+                } else {
+                    format!(
+                        r#"<pattern id="{id}" patternContentUnits="objectBoundingBox" width="1" height="1"><image href="{src}" width="1" height="1" preserveAspectRatio="none"/></pattern>"#
+                    )
+                }
+            }
+            Fill::Pattern {
+                preset,
+                foreground,
+                background,
+            } => format!(
+                r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{TILE}" height="{TILE}">{}</pattern>"#,
+                self.pattern_tile(preset, foreground, background, paint)
+            ),
+        };
+        Some((definition, format!("url(#{id})")))
+    }
+
+    /// Color stops of a CSS gradient
+    fn css_stops(&self, gradient: &GradientFill, paint: Paint) -> String {
+        gradient
+            .stops
+            .iter()
+            .map(|stop| {
+                format!(
+                    "{} {}%",
+                    self.paint(&stop.color, paint),
+                    stop.position * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// SVG content of one tile of a pattern fill
+    fn pattern_tile(
+        &self,
+        preset: &str,
+        foreground: &str,
+        background: &str,
+        paint: Paint,
+    ) -> String {
+        let foreground = html_escape(&self.paint(foreground, Paint::Ink));
+        let background = html_escape(&self.paint(background, paint));
+        format!(
+            r#"<rect width="{TILE}" height="{TILE}" fill="{background}"/><path d="{}" stroke="{foreground}" fill="none"/>"#,
+            pattern_path(preset)
+        )
+    }
+}
+
+/// Path drawn on an 8 by 8 tile for a pattern preset such as `dkHorz`
+///
+/// Presets are grouped by their lines; percentages, dots and the
+/// decorative presets (bricks, waves, ...) are drawn as sparse dots.
+fn pattern_path(preset: &str) -> &'static str {
+    let preset = preset.to_ascii_lowercase();
+    if preset.contains("diagcross") {
+        "M0 0L8 8M0 8L8 0"
+    } else if preset.contains("cross") || preset.contains("grid") {
+        "M0 4H8M4 0V8"
+    } else if preset.contains("horz") {
+        "M0 4H8"
+    } else if preset.contains("vert") {
+        "M4 0V8"
+    } else if preset.contains("dndiag") {
+        "M0 0L8 8"
+    } else if preset.contains("updiag") {
+        "M0 8L8 0"
+    } else {
+        "M2 2h1M6 6h1"
+    }
+}
+
+/// Gradient vector coordinate, rounded to keep the markup short
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// ID of the paint server drawing `fill`
+fn paint_server_id(fill: &Fill) -> String {
+    let digest = Sha256::digest(format!("{fill:?}").as_bytes());
+    digest[..8]
+        .iter()
+        .fold(String::from("fill-"), |mut id, byte| {
+            let _ = write!(id, "{byte:02x}");
+            id
+        })
+}
+
+/// A URL made safe to quote in a CSS `url()`
+fn css_url(url: &str) -> String {
+    url.replace('\'', "%27")
+        .replace('"', "%22")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('\\', "%5C")
+}
@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Semantic (reflowable) HTML layout.
+//!
+//! Instead of absolutely positioned boxes, text blocks become `h1`-`h6`,
+//! `p` and list markup in reading order so the output reflows on small
+//! screens and is navigable with assistive technology. Headings come from
+//! the paragraph style name (`Heading 2`, `Title`, ...) or, failing that,
+//! from [`DocumentStructure::headings`](prism_core::document::DocumentStructure).
+
+use prism_core::document::{ContentBlock, Document, Page, TextAlignment, TextBlock, TextDirection};
+use std::fmt::Write as _;
+
+use super::{dir_attribute, pdf_payload, transform, HtmlRenderer};
+
+/// Bullet characters recognised at the start of a paragraph
+const BULLETS: &[char] = &['•', '◦', '▪', '‣', '–', '-', '*'];
+
+/// How a text block is emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Heading(u8),
+    Paragraph,
+    ListItem { ordered: bool },
+}
+
+impl HtmlRenderer {
+    /// Render a page as a flowing section
+    pub(super) fn render_semantic_page(
+        &self,
+        document: &Document,
+        page: &Page,
+        page_num: usize,
+    ) -> String {
+        format!(
+            r#"<section class="semantic-page" id="page-{page_num}" data-page="{page_num}" aria-label="Page {page_num}">
+{}{}
+    </section>"#,
+            self.render_semantic_blocks(document, &page.content, page.number),
+            self.stamps(page_num - 1)
+        )
+    }
+
+    /// Render blocks in reading order, grouping consecutive list items
+    pub(super) fn render_semantic_blocks(
+        &self,
+        document: &Document,
+        blocks: &[ContentBlock],
+        page_number: u32,
+    ) -> String {
+        let mut html = String::new();
+        // `Some(ordered)` while a list is open
+        let mut open_list: Option<bool> = None;
+
+        for block in reading_order(blocks) {
+            let element = match block {
+                ContentBlock::Text(text) if pdf_payload(&plain_text(text)).is_none() => {
+                    Some((text, classify(document, text, page_number)))
+                }
+                _ => None,
+            };
+
+            let list = match element {
+                Some((_, Element::ListItem { ordered })) => Some(ordered),
+                _ => None,
+            };
+            if open_list != list {
+                if let Some(ordered) = open_list {
+                    html.push_str(if ordered { "</ol>\n" } else { "</ul>\n" });
+                }
+                if let Some(ordered) = list {
+                    html.push_str(if ordered { "<ol>\n" } else { "<ul>\n" });
+                }
+                open_list = list;
+            }
+
+            match element {
+                Some((text, element)) => {
+                    let _ = writeln!(html, "{}", self.semantic_text(document, text, element));
+                }
+                None => {
+                    let _ = writeln!(
+                        html,
+                        "{}",
+                        self.semantic_block(document, block, page_number)
+                    );
+                }
+            }
+        }
+
+        if let Some(ordered) = open_list {
+            html.push_str(if ordered { "</ol>\n" } else { "</ul>\n" });
+        }
+        html
+    }
+
+    /// Render a text block as its element, with inline formatting kept
+    fn semantic_text(&self, document: &Document, block: &TextBlock, element: Element) -> String {
+        let mut runs = block.runs.iter().map(|run| self.render_text_run(run));
+        let mut content = String::new();
+        if let (Element::ListItem { .. }, Some(first)) = (element, block.runs.first()) {
+            // Drop the typed bullet or number; the list supplies its own
+            let rest = list_marker(&first.text)
+                .map_or(&*first.text, |(_, len)| first.text[len..].trim_start());
+            let mut run = first.clone();
+            run.text = rest.into();
+            content.push_str(&self.render_text_run(&run));
+            runs.next();
+        }
+        content.extend(runs);
+
+        let align = block
+            .paragraph_style
+            .as_deref()
+            .and_then(|name| {
+                document
+                    .styles
+                    .paragraph_styles
+                    .iter()
+                    .find(|style| style.name == name)
+            })
+            .and_then(|style| match style.style.alignment {
+                TextAlignment::Left => None,
+                TextAlignment::Center => Some("center"),
+                TextAlignment::Right => Some("right"),
+                TextAlignment::Justify => Some("justify"),
+            })
+            .map_or_else(String::new, |align| {
+                format!(r#" style="text-align: {align};""#)
+            });
+
+        // Flowing text inherits the page direction unless it declares one
+        let attrs = match block.direction {
+            TextDirection::Auto => align,
+            direction => format!(r#" dir="{}"{align}"#, dir_attribute(direction)),
+        };
+
+        match element {
+            Element::Heading(level) => format!("<h{level}{attrs}>{content}</h{level}>"),
+            Element::Paragraph => format!("<p{attrs}>{content}</p>"),
+            Element::ListItem { .. } => format!("<li{attrs}>{content}</li>"),
+        }
+    }
+
+    /// Render a non-text block without fixed positioning
+    fn semantic_block(
+        &self,
+        document: &Document,
+        block: &ContentBlock,
+        page_number: u32,
+    ) -> String {
+        match block {
+            ContentBlock::Text(text) => pdf_payload(&plain_text(text))
+                .map(|pdf_data| self.render_pdf_viewer(pdf_data))
+                .unwrap_or_default(),
+            // Figures flow at their natural size, so crops are left out
+            ContentBlock::Image(image) => {
+                let transform = transform::transform(image);
+                let style = if transform.is_empty() {
+                    String::from("width: 100%; height: 100%;")
+                } else {
+                    format!("width: 100%; height: 100%; transform: {transform};")
+                };
+                format!(
+                    r#"<figure class="semantic-figure">{}</figure>"#,
+                    self.image_tag(document, image, &style)
+                )
+            }
+            ContentBlock::Table(table) => self.table_markup(document, table),
+            ContentBlock::List(list) => self.list_markup(document, list),
+            ContentBlock::FormField(field) => {
+                format!(
+                    r#"<p class="form-field">{}</p>"#,
+                    self.form_field_markup(field)
+                )
+            }
+            ContentBlock::Container(container) => {
+                self.render_semantic_blocks(document, &container.children, page_number)
+            }
+            // Shapes and rules only make sense at fixed positions
+            ContentBlock::Vector(_) => String::new(),
+        }
+    }
+}
+
+/// Visual reading order
+///
+/// Flow documents (no bounds) are already in order. Positioned blocks are
+/// read top to bottom, then left to right.
+fn reading_order(blocks: &[ContentBlock]) -> Vec<&ContentBlock> {
+    let mut ordered: Vec<&ContentBlock> = blocks.iter().collect();
+    if blocks.iter().all(|block| position(block).is_some()) {
+        ordered.sort_by(|a, b| {
+            let (a, b) = (
+                position(a).unwrap_or_default(),
+                position(b).unwrap_or_default(),
+            );
+            a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+    }
+    ordered
+}
+
+/// Top-left corner of a positioned block
+fn position(block: &ContentBlock) -> Option<(f64, f64)> {
+    let bounds = match block {
+        ContentBlock::Text(b) => &b.bounds,
+        ContentBlock::Image(b) => &b.bounds,
+        ContentBlock::Table(b) => &b.bounds,
+        ContentBlock::List(b) => &b.bounds,
+        ContentBlock::FormField(b) => &b.bounds,
+        ContentBlock::Vector(b) => &b.bounds,
+        ContentBlock::Container(b) => &b.bounds,
+    };
+    (bounds.width > 0.0 && bounds.height > 0.0).then_some((bounds.y, bounds.x))
+}
+
+/// Decide whether a text block is a heading, list item or paragraph
+fn classify(document: &Document, block: &TextBlock, page_number: u32) -> Element {
+    let style = block.paragraph_style.as_deref().unwrap_or_default();
+    if let Some(level) = heading_level(style) {
+        return Element::Heading(level);
+    }
+
+    let text = plain_text(block);
+    let text = text.trim();
+    if let Some(heading) = document
+        .structure
+        .headings
+        .iter()
+        .find(|heading| heading.page == page_number && heading.text.trim() == text)
+    {
+        return Element::Heading(heading.level.clamp(1, 6));
+    }
+
+    let style = style.to_ascii_lowercase();
+    if style.contains("list") {
+        return Element::ListItem {
+            ordered: style.contains("number"),
+        };
+    }
+    match list_marker(text) {
+        Some((ordered, _)) => Element::ListItem { ordered },
+        None => Element::Paragraph,
+    }
+}
+
+/// Heading level implied by a paragraph style name or ID
+pub(crate) fn heading_level(style: &str) -> Option<u8> {
+    let normalized: String = style
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match normalized.as_str() {
+        "title" => Some(1),
+        "subtitle" => Some(2),
+        _ => normalized
+            .strip_prefix("heading")
+            .and_then(|level| level.parse::<u8>().ok())
+            .map(|level| level.clamp(1, 6)),
+    }
+}
+
+/// A leading bullet (`• `) or number (`1.`, `2)`) and its length in bytes
+fn list_marker(text: &str) -> Option<(bool, usize)> {
+    let mut chars = text.char_indices();
+    let (_, first) = chars.next()?;
+    if BULLETS.contains(&first) {
+        let len = first.len_utf8();
+        return text[len..]
+            .starts_with(char::is_whitespace)
+            .then_some((false, len));
+    }
+
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let rest = &text[digits..];
+    (rest.starts_with(['.', ')']) && rest[1..].starts_with(char::is_whitespace))
+        .then_some((true, digits + 1))
+}
+
+/// Concatenated text of a block's runs
+fn plain_text(block: &TextBlock) -> String {
+    block.runs.iter().map(|run| &*run.text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Heading, Rect, TextRun};
+
+    fn paragraph(text: &str, style: Option<&str>, bounds: Rect) -> ContentBlock {
+        let mut block = TextBlock::new(bounds);
+        block.add_run(TextRun::new(text));
+        block.paragraph_style = style.map(str::to_string);
+        ContentBlock::Text(block)
+    }
+
+    #[test]
+    fn test_heading_level() {
+        assert_eq!(heading_level("Heading1"), Some(1));
+        assert_eq!(heading_level("heading 3"), Some(3));
+        assert_eq!(heading_level("Heading 9"), Some(6));
+        assert_eq!(heading_level("Title"), Some(1));
+        assert_eq!(heading_level("Normal"), None);
+    }
+
+    #[test]
+    fn test_list_marker() {
+        assert_eq!(list_marker("• item"), Some((false, 3)));
+        assert_eq!(list_marker("12. item"), Some((true, 3)));
+        assert_eq!(list_marker("1) item"), Some((true, 2)));
+        assert_eq!(list_marker("-5 degrees"), None);
+        assert_eq!(list_marker("2024 was a year"), None);
+    }
+
+    #[test]
+    fn test_semantic_blocks() {
+        let mut document = Document::new();
+        document.structure.headings.push(Heading {
+            text: "Overview".to_string(),
+            level: 2,
+            page: 1,
+            bounds: None,
+        });
+        let flow = Rect::default();
+        let blocks = vec![
+            paragraph("Report", Some("Title"), flow),
+            paragraph("Overview", None, flow),
+            paragraph("• first", None, flow),
+            paragraph("• second", None, flow),
+            paragraph("Closing <remarks>", None, flow),
+        ];
+
+        let html = HtmlRenderer::new().render_semantic_blocks(&document, &blocks, 1);
+        assert!(html.contains("<h1>Report</h1>"));
+        assert!(html.contains("<h2>Overview</h2>"));
+        assert!(html.contains("<ul>\n<li>first</li>\n<li>second</li>\n</ul>"));
+        assert!(html.contains("<p>Closing &lt;remarks&gt;</p>"));
+        assert!(!html.contains("position: absolute"));
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let blocks = vec![
+            paragraph("bottom", None, Rect::new(0.0, 500.0, 100.0, 20.0)),
+            paragraph("right", None, Rect::new(300.0, 50.0, 100.0, 20.0)),
+            paragraph("left", None, Rect::new(0.0, 50.0, 100.0, 20.0)),
+        ];
+        let order: Vec<String> = reading_order(&blocks)
+            .into_iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(plain_text(text)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(order, ["left", "right", "bottom"]);
+    }
+}
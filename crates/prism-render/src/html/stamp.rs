@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Watermarks and Bates numbers stamped over rendered pages.
+//!
+//! Stamps are absolutely positioned over the page box (or the section, in
+//! the semantic layout) and anchored with CSS alone, so no estimate of
+//! their rendered size is needed. They ignore pointer events, leaving the
+//! content underneath selectable.
+
+use base64::{engine::general_purpose, Engine as _};
+use prism_core::render::{StampPosition, WatermarkContent};
+use std::fmt::Write as _;
+
+use super::{html_escape, HtmlRenderer};
+use crate::color::Paint;
+
+/// Distance of edge and corner stamps from the page edge, in points
+const STAMP_MARGIN: f64 = 18.0;
+
+impl HtmlRenderer {
+    /// Overlays for the page at `index` (0-based), empty without stamps
+    pub(super) fn stamps(&self, index: usize) -> String {
+        let mut html = String::new();
+        if let Some(watermark) = self
+            .watermark
+            .as_ref()
+            .filter(|watermark| watermark.pages.includes(index))
+        {
+            let (anchor, translate) = anchor(watermark.position);
+            let style = format!(
+                "position: absolute; {anchor} transform: {translate} rotate({}deg); opacity: {}; pointer-events: none; z-index: 10;",
+                -watermark.rotation, watermark.opacity
+            );
+            match &watermark.content {
+                WatermarkContent::Text {
+                    text,
+                    font_size,
+                    color,
+                } => {
+                    let _ = write!(
+                        html,
+                        r#"<div class="watermark" aria-hidden="true" style="{style} font-size: {font_size}pt; font-weight: bold; color: {}; white-space: nowrap;">{}</div>"#,
+                        self.paint(color, Paint::Ink),
+                        html_escape(text)
+                    );
+                }
+                WatermarkContent::Image {
+                    data,
+                    mime_type,
+                    size,
+                } => {
+                    let _ = write!(
+                        html,
+                        r#"<img class="watermark" alt="" aria-hidden="true" src="data:{};base64,{}" style="{style} width: {}pt; height: {}pt; max-width: none; border: none; box-shadow: none;">"#,
+                        html_escape(mime_type),
+                        general_purpose::STANDARD.encode(data),
+                        size.width,
+                        size.height
+                    );
+                }
+            }
+        }
+        if let Some(bates) = &self.bates {
+            let (anchor, translate) = anchor(bates.position);
+            let _ = write!(
+                html,
+                r#"<div class="bates-number" style="position: absolute; {anchor} transform: {translate}; font-family: monospace; font-size: {}pt; color: {}; background-color: white; padding: 0 2pt; white-space: nowrap; pointer-events: none; z-index: 11;">{}</div>"#,
+                bates.font_size,
+                self.paint("#000000", Paint::Ink),
+                html_escape(&bates.label(index))
+            );
+        }
+        html
+    }
+}
+
+/// CSS placing a stamp's box at `position`, and the translation that
+/// centers the box on the anchor where needed
+fn anchor(position: StampPosition) -> (String, &'static str) {
+    let margin = STAMP_MARGIN;
+    match position {
+        StampPosition::Center => ("left: 50%; top: 50%;".to_string(), "translate(-50%, -50%)"),
+        StampPosition::Top => (format!("left: 50%; top: {margin}pt;"), "translateX(-50%)"),
+        StampPosition::Bottom => (
+            format!("left: 50%; bottom: {margin}pt;"),
+            "translateX(-50%)",
+        ),
+        StampPosition::TopLeft => (
+            format!("left: {margin}pt; top: {margin}pt;"),
+            "translate(0, 0)",
+        ),
+        StampPosition::TopRight => (
+            format!("right: {margin}pt; top: {margin}pt;"),
+            "translate(0, 0)",
+        ),
+        StampPosition::BottomLeft => (
+            format!("left: {margin}pt; bottom: {margin}pt;"),
+            "translate(0, 0)",
+        ),
+        StampPosition::BottomRight => (
+            format!("right: {margin}pt; bottom: {margin}pt;"),
+            "translate(0, 0)",
+        ),
+    }
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Crops, flips and rotations of images.
+//!
+//! A cropped image is drawn larger than its box and shifted so only the
+//! kept part shows through the box, which hides the rest. Flips and
+//! rotations transform the box about its center, flipping first as
+//! Office does.
+
+use prism_core::document::ImageBlock;
+use std::fmt::Write as _;
+
+/// CSS declarations sizing the `<img>` of `image` within its box
+pub(super) fn image_style(image: &ImageBlock) -> String {
+    let Some(crop) = image.crop.filter(|crop| !crop.is_empty()) else {
+        return String::from("width: 100%; height: 100%;");
+    };
+    let width = 1.0 - crop.left - crop.right;
+    let height = 1.0 - crop.top - crop.bottom;
+    if width <= 0.0 || height <= 0.0 {
+        return String::from("width: 100%; height: 100%;");
+    }
+    format!(
+        "position: absolute; left: {}%; top: {}%; width: {}%; height: {}%; max-width: none;",
+        // Subtracted from zero, as -0 is written with its sign
+        percent((0.0 - crop.left) / width),
+        percent((0.0 - crop.top) / height),
+        percent(1.0 / width),
+        percent(1.0 / height)
+    )
+}
+
+/// CSS declarations for the box of `image`: hiding what a crop cuts off,
+/// and the flips and rotation
+pub(super) fn box_style(image: &ImageBlock) -> String {
+    let mut style = String::new();
+    if image.crop.is_some_and(|crop| !crop.is_empty()) {
+        style.push_str(" overflow: hidden;");
+    }
+    let transform = transform(image);
+    if !transform.is_empty() {
+        let _ = write!(style, " transform: {transform}; transform-origin: center;");
+    }
+    style
+}
+
+/// CSS transform functions for the flips and rotation of `image`, empty if
+/// it has none
+pub(super) fn transform(image: &ImageBlock) -> String {
+    let mut functions = Vec::new();
+    if image.rotation != 0.0 {
+        functions.push(format!("rotate({}deg)", image.rotation));
+    }
+    if image.flip_horizontal || image.flip_vertical {
+        let sign = |flip: bool| if flip { -1 } else { 1 };
+        functions.push(format!(
+            "scale({}, {})",
+            sign(image.flip_horizontal),
+            sign(image.flip_vertical)
+        ));
+    }
+    functions.join(" ")
+}
+
+/// `fraction` as a percentage, rounded to keep the markup short
+fn percent(fraction: f64) -> f64 {
+    (fraction * 100_000.0).round() / 1000.0
+}
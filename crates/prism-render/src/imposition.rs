@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Imposition
+//!
+//! Maps document pages onto printed sheet sides for the layouts in
+//! [`Imposition`]. The layout is independent of the output format; each
+//! renderer only has to draw the returned sides in order.
+//!
+//! For a booklet the page count is padded with blanks to a multiple of four
+//! and every physical sheet yields two sides (front, then back), so an
+//! 8-page document becomes:
+//!
+//! | Side      | Left | Right |
+//! |-----------|------|-------|
+//! | 1 (front) | 8    | 1     |
+//! | 1 (back)  | 2    | 7     |
+//! | 2 (front) | 6    | 3     |
+//! | 2 (back)  | 4    | 5     |
+
+use prism_core::render::Imposition;
+
+/// One printed sheet side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetSide {
+    /// Number of page cells across
+    pub columns: usize,
+    /// Number of page cells down
+    pub rows: usize,
+    /// Zero-based page index for every cell in row-major order, `None` for
+    /// a blank cell
+    pub cells: Vec<Option<usize>>,
+}
+
+impl SheetSide {
+    fn new(columns: usize, rows: usize, cells: Vec<Option<usize>>) -> Self {
+        debug_assert_eq!(cells.len(), columns * rows);
+        Self {
+            columns,
+            rows,
+            cells,
+        }
+    }
+
+    /// Scale factor that fits every cell into the footprint of one page
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn scale(&self) -> f64 {
+        1.0 / self.columns.max(self.rows) as f64
+    }
+}
+
+/// Lay out `page_count` pages according to `imposition`
+///
+/// Returns one entry per sheet side in print order. Grids of zero columns
+/// or rows are treated as one.
+#[must_use]
+pub fn impose(page_count: usize, imposition: Imposition) -> Vec<SheetSide> {
+    match imposition {
+        Imposition::None => grid(page_count, 1, 1),
+        Imposition::TwoUp => grid(page_count, 2, 1),
+        Imposition::NUp { columns, rows } => grid(
+            page_count,
+            usize::try_from(columns).unwrap_or(usize::MAX).max(1),
+            usize::try_from(rows).unwrap_or(usize::MAX).max(1),
+        ),
+        Imposition::Booklet => booklet(page_count),
+    }
+}
+
+/// Fill sheets with consecutive pages
+fn grid(page_count: usize, columns: usize, rows: usize) -> Vec<SheetSide> {
+    let per_sheet = columns.saturating_mul(rows);
+    (0..page_count)
+        .step_by(per_sheet)
+        .map(|first| {
+            let cells = (first..first.saturating_add(per_sheet))
+                .map(|page| (page < page_count).then_some(page))
+                .collect();
+            SheetSide::new(columns, rows, cells)
+        })
+        .collect()
+}
+
+/// Saddle-stitch order: outermost sheet first, two sides per sheet
+fn booklet(page_count: usize) -> Vec<SheetSide> {
+    let padded = page_count.div_ceil(4) * 4;
+    let page = |index: usize| (index < page_count).then_some(index);
+
+    (0..padded / 4)
+        .flat_map(|sheet| {
+            let (outer, inner) = (2 * sheet, padded - 1 - 2 * sheet);
+            [
+                SheetSide::new(2, 1, vec![page(inner), page(outer)]),
+                SheetSide::new(2, 1, vec![page(outer + 1), page(inner - 1)]),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(sides: &[SheetSide]) -> Vec<Vec<Option<usize>>> {
+        sides.iter().map(|side| side.cells.clone()).collect()
+    }
+
+    #[test]
+    fn test_two_up_and_n_up() {
+        let sides = impose(3, Imposition::TwoUp);
+        assert_eq!(
+            pages(&sides),
+            vec![vec![Some(0), Some(1)], vec![Some(2), None]]
+        );
+
+        let contact = Imposition::NUp {
+            columns: 2,
+            rows: 2,
+        };
+        let sides = impose(5, contact);
+        assert_eq!(sides.len(), 2);
+        assert_eq!(sides[1].cells, vec![Some(4), None, None, None]);
+        assert!((sides[0].scale() - 0.5).abs() < f64::EPSILON);
+
+        let degenerate = Imposition::NUp {
+            columns: 0,
+            rows: 0,
+        };
+        assert_eq!(impose(2, degenerate).len(), 2);
+        assert!(impose(0, Imposition::None).is_empty());
+    }
+
+    #[test]
+    fn test_booklet_order() {
+        let sides = impose(8, Imposition::Booklet);
+        assert_eq!(
+            pages(&sides),
+            vec![
+                vec![Some(7), Some(0)],
+                vec![Some(1), Some(6)],
+                vec![Some(5), Some(2)],
+                vec![Some(3), Some(4)],
+            ]
+        );
+
+        // Padded to a multiple of four with blanks at the back
+        let sides = impose(5, Imposition::Booklet);
+        assert_eq!(sides.len(), 4);
+        assert_eq!(sides[0].cells, vec![None, Some(0)]);
+        assert_eq!(sides[3].cells, vec![Some(3), Some(4)]);
+    }
+}
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # JSONL renderer
+//!
+//! Writes a document as JSON Lines for vector database ingestion: one
+//! record per chunk (see [`prism_core::chunk`]) or per top-level block,
+//! each on its own line. A record carries the text to embed together with
+//! everything needed to filter on and cite it:
+//!
+//! ```json
+//! {"id":"<document id>:0","document_id":"…","index":0,"text":"…",
+//!  "pages":[1],"regions":[{"page":1,"bounds":{…}}],"heading_path":["Intro"],
+//!  "style":{"block_types":["text"],"paragraph_styles":["Heading 1"]},
+//!  "metadata":{"title":"…","format":"DOCX"}}
+//! ```
+//!
+//! Regions are left out for flowing content, and empty style hints and
+//! unknown metadata are omitted.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::chunk::{self, Chunk, ChunkOptions, ChunkRegion};
+use prism_core::document::{ContentBlock, ContentPosition, Document};
+use prism_core::error::{Error, Result};
+use prism_core::format::{Format, FormatFamily};
+use prism_core::render::{PageRange, RenderContext, RenderFeature, Renderer, RendererMetadata};
+use serde::Serialize;
+use std::borrow::Cow;
+
+use crate::filter::{filter_document, select_pages};
+
+/// MIME type of the output
+pub const JSONL_MIME_TYPE: &str = "application/jsonl";
+
+/// What each line of the output holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonlUnit {
+    /// A chunk sized by [`JsonlConfig::chunking`]
+    #[default]
+    Chunks,
+    /// A top-level block
+    Blocks,
+}
+
+/// JSONL renderer configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonlConfig {
+    /// Record granularity
+    pub unit: JsonlUnit,
+    /// Chunk sizes, for [`JsonlUnit::Chunks`]
+    pub chunking: ChunkOptions,
+}
+
+/// Renders documents as JSON Lines
+#[derive(Debug, Clone, Default)]
+pub struct JsonlRenderer {
+    config: JsonlConfig,
+}
+
+/// One line of output
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    id: String,
+    document_id: String,
+    index: usize,
+    text: &'a str,
+    pages: &'a [u32],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    regions: &'a [ChunkRegion],
+    heading_path: &'a [String],
+    #[serde(skip_serializing_if = "StyleHints::is_empty")]
+    style: StyleHints<'a>,
+    metadata: &'a RecordMetadata<'a>,
+}
+
+/// What the blocks of a record look like
+#[derive(Debug, Default, Serialize)]
+struct StyleHints<'a> {
+    /// Kinds of the blocks, in order of first appearance
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    block_types: Vec<&'static str>,
+    /// Paragraph styles of the text blocks
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    paragraph_styles: Vec<&'a str>,
+    /// Largest font size of the text, in points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_font_size: Option<f64>,
+}
+
+impl StyleHints<'_> {
+    fn is_empty(&self) -> bool {
+        self.block_types.is_empty()
+            && self.paragraph_styles.is_empty()
+            && self.max_font_size.is_none()
+    }
+}
+
+/// Document properties repeated on every record
+#[derive(Debug, Serialize)]
+struct RecordMetadata<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a str>,
+    page_count: usize,
+}
+
+impl JsonlRenderer {
+    /// Create a renderer writing one record per chunk of the default size
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a renderer with custom configuration
+    #[must_use]
+    pub fn with_config(config: JsonlConfig) -> Self {
+        Self { config }
+    }
+
+    /// Render `document` as JSON Lines
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a record cannot be serialized.
+    pub fn render_jsonl(&self, document: &Document) -> Result<String> {
+        let chunks = match self.config.unit {
+            JsonlUnit::Chunks => chunk::chunk(document, &self.config.chunking),
+            JsonlUnit::Blocks => chunk::block_chunks(document),
+        };
+        let metadata = RecordMetadata {
+            title: document.metadata.title.as_deref(),
+            author: document.metadata.author.as_deref(),
+            language: document.metadata.language.as_deref(),
+            filename: document.source.filename.as_deref(),
+            format: document
+                .source
+                .format
+                .as_ref()
+                .map(|format| format.name.as_str()),
+            page_count: document.page_count(),
+        };
+
+        let mut out = String::new();
+        for chunk in &chunks {
+            let record = Record {
+                id: format!("{}:{}", document.id, chunk.index),
+                document_id: document.id.to_string(),
+                index: chunk.index,
+                text: &chunk.text,
+                pages: &chunk.pages,
+                regions: &chunk.regions,
+                heading_path: &chunk.heading_path,
+                style: style_hints(document, chunk),
+                metadata: &metadata,
+            };
+            let line = serde_json::to_string(&record)
+                .map_err(|e| Error::RenderError(format!("Failed to serialize record: {e}")))?;
+            out.push_str(&line);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+/// Style hints for the top-level blocks of `chunk`
+fn style_hints<'a>(document: &'a Document, chunk: &Chunk) -> StyleHints<'a> {
+    let mut hints = StyleHints::default();
+    for page in &document.pages {
+        if page.number < chunk.start.page || page.number > chunk.end.page {
+            continue;
+        }
+        for (index, block) in page.content.iter().enumerate() {
+            let position = ContentPosition::new(page.number, index);
+            if position < chunk.start || position >= chunk.end {
+                continue;
+            }
+            let kind = block.kind();
+            if !hints.block_types.contains(&kind) {
+                hints.block_types.push(kind);
+            }
+            if let ContentBlock::Text(text) = block {
+                if let Some(style) = text.paragraph_style.as_deref() {
+                    if !hints.paragraph_styles.contains(&style) {
+                        hints.paragraph_styles.push(style);
+                    }
+                }
+                for size in text.runs.iter().filter_map(|run| run.style.font_size) {
+                    hints.max_font_size =
+                        Some(hints.max_font_size.map_or(size, |max| max.max(size)));
+                }
+            }
+        }
+    }
+    hints
+}
+
+#[async_trait]
+impl Renderer for JsonlRenderer {
+    fn output_format(&self) -> Format {
+        Format {
+            mime_type: JSONL_MIME_TYPE.to_string(),
+            extension: "jsonl".to_string(),
+            family: FormatFamily::Text,
+            name: "JSONL".to_string(),
+            is_container: false,
+        }
+    }
+
+    async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes> {
+        let document = match &context.options.page_range {
+            Some(range) if *range != PageRange::All => Cow::Owned(select_pages(document, range)),
+            _ => Cow::Borrowed(document),
+        };
+        let jsonl = if context.options.content.is_all() {
+            self.render_jsonl(&document)?
+        } else {
+            self.render_jsonl(&filter_document(&document, &context.options.content))?
+        };
+        Ok(Bytes::from(jsonl))
+    }
+
+    /// Vector graphics have no text equivalent
+    fn supports(&self, block: &ContentBlock) -> bool {
+        !matches!(block, ContentBlock::Vector(_))
+    }
+
+    fn metadata(&self) -> RendererMetadata {
+        RendererMetadata {
+            name: "JSONL Renderer".to_string(),
+            version: crate::VERSION.to_string(),
+            features: vec![
+                RenderFeature::TextRendering,
+                RenderFeature::TableRendering,
+                RenderFeature::PageRangeSupport,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Rect, TextBlock, TextRun};
+
+    #[test]
+    fn test_render_jsonl() {
+        let mut document = Document::builder()
+            .add_text_page("Intro", "Hello world.")
+            .add_text_page("Usage", "Run it.")
+            .build();
+        document.metadata.title = Some("Guide".to_string());
+        let mut positioned = TextBlock::new(Rect::new(72.0, 72.0, 200.0, 14.0));
+        let mut run = TextRun::new("Big");
+        run.style.font_size = Some(18.0);
+        positioned.add_run(run);
+        document.pages[1].add_content(ContentBlock::Text(positioned));
+
+        let jsonl = JsonlRenderer::new().render_jsonl(&document).unwrap();
+        let records: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["text"], "Intro\n\nHello world.");
+        assert_eq!(records[0]["id"], format!("{}:0", document.id));
+        assert_eq!(records[0]["heading_path"][0], "Intro");
+        assert_eq!(records[0]["metadata"]["title"], "Guide");
+        assert_eq!(records[0]["style"]["paragraph_styles"][0], "Heading 1");
+        assert!(records[0].get("regions").is_none());
+        assert_eq!(records[1]["pages"][0], 2);
+        assert_eq!(records[1]["regions"][0]["bounds"]["x"], 72.0);
+        assert_eq!(records[1]["style"]["max_font_size"], 18.0);
+
+        let blocks = JsonlRenderer::with_config(JsonlConfig {
+            unit: JsonlUnit::Blocks,
+            ..JsonlConfig::default()
+        })
+        .render_jsonl(&document)
+        .unwrap();
+        assert_eq!(blocks.lines().count(), 5);
+    }
+}
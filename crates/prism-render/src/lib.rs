@@ -8,10 +8,14 @@
 //!
 //! ## Supported Output Formats
 //!
+//! - **DOCX**: Word document with flowing paragraphs, tables and images
 //! - **HTML5**: Responsive, accessible HTML with CSS
+//! - **JSONL**: Chunked text with provenance, for vector database ingestion
+//! - **Markdown**: Headings, paragraphs, lists and tables as GitHub-flavoured Markdown
 //! - **PDF**: PDF output (planned)
 //! - **PNG/JPEG**: Raster image output (planned)
 //! - **SVG**: Vector graphics output (planned)
+//! - **XLSX**: Spreadsheet of the document's tables
 //! - **Text**: Plain text output (planned)
 //!
 //! ## Usage
@@ -32,6 +36,7 @@
 //!         ..Default::default()
 //!     },
 //!     filename: Some("output.html".to_string()),
+//!     progress: None,
 //! };
 //!
 //! let html_bytes = renderer.render(&document, context).await?;
@@ -45,7 +50,19 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod color;
+pub mod docx;
+pub mod filter;
+pub mod fonts;
 pub mod html;
+pub mod imposition;
+pub mod jsonl;
+pub mod markdown;
+pub mod normalize;
+mod ooxml;
+pub mod slides;
+pub mod xlsx;
+pub mod zip_writer;
 // pub mod pdf;
 // pub mod image;
 // pub mod svg;
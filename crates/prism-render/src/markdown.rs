@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Markdown renderer
+//!
+//! Writes the content of a document as GitHub-flavoured Markdown: headings
+//! from heading paragraph styles, paragraphs with bold, italic and link
+//! markup, nested lists, pipe tables and image references; text deleted by
+//! a tracked change is struck through. Layout is dropped; blocks follow
+//! each other in reading order, separated by blank lines, so the columns
+//! of positioned pages such as slides are read one after the other.
+//!
+//! Pipe tables have no spans, so a spanning cell is written once and the
+//! cells it covers are left empty.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::document::{ContentBlock, Document, RevisionKind, TableBlock, TextBlock, TextRun};
+use prism_core::error::Result;
+use prism_core::format::Format;
+use prism_core::render::{PageRange, RenderContext, RenderFeature, Renderer, RendererMetadata};
+use std::borrow::Cow;
+
+use crate::filter::{filter_document, select_pages};
+use crate::html::semantic::heading_level;
+use crate::slides::markdown_marker;
+
+/// Renders documents as Markdown
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    /// Create a new Markdown renderer
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Render `document` as Markdown
+    #[must_use]
+    pub fn render_markdown(&self, document: &Document) -> String {
+        let mut blocks = Vec::new();
+        for page in &document.pages {
+            for block in page.blocks_in_reading_order() {
+                push_block(&mut blocks, block);
+            }
+        }
+        let mut out = blocks.join("\n\n");
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Append the Markdown of `block` to `blocks`, one entry per paragraph
+fn push_block(blocks: &mut Vec<String>, block: &ContentBlock) {
+    match block {
+        ContentBlock::Text(text) => {
+            let heading = text.paragraph_style.as_deref().and_then(heading_level);
+            if let Some(level) = heading {
+                let title = collapse(&escape(&text.extract_text()));
+                if !title.is_empty() {
+                    blocks.push(format!("{} {title}", "#".repeat(usize::from(level))));
+                }
+            } else {
+                let paragraph = inline(text);
+                if !paragraph.trim().is_empty() {
+                    blocks.push(paragraph.trim().to_string());
+                }
+            }
+        }
+        ContentBlock::List(list) => {
+            let items: Vec<String> = list
+                .items
+                .iter()
+                .filter_map(|item| {
+                    let text = item
+                        .content
+                        .iter()
+                        .filter_map(|block| match block {
+                            ContentBlock::Text(text) => Some(inline(text)),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let text = collapse(&text);
+                    (!text.is_empty()).then(|| {
+                        let indent = "  ".repeat(usize::from(item.level));
+                        format!("{indent}{} {text}", markdown_marker(item))
+                    })
+                })
+                .collect();
+            if !items.is_empty() {
+                blocks.push(items.join("\n"));
+            }
+        }
+        ContentBlock::Table(table) => {
+            if let Some(table) = pipe_table(table) {
+                blocks.push(table);
+            }
+        }
+        ContentBlock::Image(image) => {
+            let alt = image.alt_text.as_deref().map(escape).unwrap_or_default();
+            blocks.push(format!("![{}]({})", collapse(&alt), image.resource_id));
+        }
+        ContentBlock::FormField(field) => {
+            let value = field.extract_text();
+            if !value.is_empty() {
+                let label = field.label.as_deref().unwrap_or(&field.name);
+                blocks.push(format!("{}: {}", escape(label), escape(&value)));
+            }
+        }
+        ContentBlock::Container(container) => {
+            for child in &container.children {
+                push_block(blocks, child);
+            }
+        }
+        ContentBlock::Vector(_) => {}
+    }
+}
+
+/// Text of a paragraph with emphasis and links; line breaks inside the
+/// paragraph become hard breaks
+fn inline(text: &TextBlock) -> String {
+    let mut out = String::new();
+    for run in &text.runs {
+        out.push_str(&inline_run(run));
+    }
+    out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("  \n")
+}
+
+fn inline_run(run: &TextRun) -> String {
+    // Emphasis markers must hug the text, so surrounding spaces go outside
+    let text = escape(&run.text);
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text;
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + trimmed.len();
+
+    let mut marked = trimmed.to_string();
+    if run.style.italic {
+        marked = format!("*{marked}*");
+    }
+    if run.style.bold {
+        marked = format!("**{marked}**");
+    }
+    let deleted = run
+        .revision
+        .as_ref()
+        .is_some_and(|revision| revision.kind == RevisionKind::Deletion);
+    if run.style.strikethrough || deleted {
+        marked = format!("~~{marked}~~");
+    }
+    if let Some(link) = &run.link {
+        marked = format!("[{marked}]({})", link.href());
+    }
+    format!("{}{marked}{}", &text[..start], &text[end..])
+}
+
+/// A pipe table with the first row as header, or `None` for an empty table
+fn pipe_table(table: &TableBlock) -> Option<String> {
+    let rows: Vec<Vec<String>> = table
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .flat_map(|cell| {
+                    let text = cell
+                        .extract_text()
+                        .lines()
+                        .map(|line| escape(line.trim()).replace('|', "\\|"))
+                        .filter(|line| !line.is_empty())
+                        .collect::<Vec<_>>()
+                        .join("<br>");
+                    std::iter::once(text)
+                        .chain(std::iter::repeat(String::new()).take(cell.col_span.max(1) - 1))
+                })
+                .collect()
+        })
+        .collect();
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(table.column_count);
+    if columns == 0 || rows.iter().flatten().all(String::is_empty) {
+        return None;
+    }
+
+    let line = |cells: &[String]| {
+        let mut line = String::from("|");
+        for column in 0..columns {
+            line.push(' ');
+            line.push_str(cells.get(column).map_or("", String::as_str));
+            line.push_str(" |");
+        }
+        line
+    };
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    Some(lines.join("\n"))
+}
+
+/// Escape the characters Markdown would read as markup
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `text` on one line, with runs of whitespace collapsed
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[async_trait]
+impl Renderer for MarkdownRenderer {
+    fn output_format(&self) -> Format {
+        Format::markdown()
+    }
+
+    async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes> {
+        let document = match &context.options.page_range {
+            Some(range) if *range != PageRange::All => Cow::Owned(select_pages(document, range)),
+            _ => Cow::Borrowed(document),
+        };
+        let markdown = if context.options.content.is_all() {
+            self.render_markdown(&document)
+        } else {
+            self.render_markdown(&filter_document(&document, &context.options.content))
+        };
+        Ok(Bytes::from(markdown))
+    }
+
+    /// Vector graphics have no Markdown equivalent
+    fn supports(&self, block: &ContentBlock) -> bool {
+        !matches!(block, ContentBlock::Vector(_))
+    }
+
+    fn metadata(&self) -> RendererMetadata {
+        RendererMetadata {
+            name: "Markdown Renderer".to_string(),
+            version: crate::VERSION.to_string(),
+            features: vec![
+                RenderFeature::TextRendering,
+                RenderFeature::TableRendering,
+                RenderFeature::PageRangeSupport,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Link, ListBlock, ListItem, Rect, TableCell, TableRow, TextStyle};
+
+    fn paragraph(runs: Vec<TextRun>) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::default());
+        for run in runs {
+            block.add_run(run);
+        }
+        ContentBlock::Text(block)
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let mut document = Document::builder()
+            .add_text_page("Intro", "Costs fell by 5*2%.")
+            .build();
+
+        let mut bold = TextRun::new("Revenue ");
+        bold.style = TextStyle {
+            bold: true,
+            ..TextStyle::default()
+        };
+        let mut link = TextRun::new("report");
+        link.link = Some(Link::Url("https://example.com".to_string()));
+        let page = &mut document.pages[0];
+        page.add_content(paragraph(vec![bold, TextRun::new("grew, see "), link]));
+        let mut list = ListBlock::new(Rect::default());
+        list.add_item(ListItem::bullet(
+            vec![paragraph(vec![TextRun::new("Europe")])],
+            0,
+        ));
+        list.add_item(ListItem::bullet(
+            vec![paragraph(vec![TextRun::new("Asia")])],
+            1,
+        ));
+        page.add_content(ContentBlock::List(list));
+        let cell = |text: &str| TableCell {
+            content: vec![paragraph(vec![TextRun::new(text)])],
+            col_span: 1,
+            row_span: 1,
+            background_color: None,
+        };
+        let mut table = TableBlock::new(Rect::default(), 2);
+        for cells in [["Region", "Sales"], ["EU | UK", "12"]] {
+            table.add_row(TableRow {
+                cells: cells.into_iter().map(cell).collect(),
+                height: None,
+            });
+        }
+        page.add_content(ContentBlock::Table(table));
+
+        let markdown = MarkdownRenderer::new().render_markdown(&document);
+        assert_eq!(
+            markdown,
+            "# Intro\n\n\
+             Costs fell by 5\\*2%.\n\n\
+             **Revenue** grew, see [report](https://example.com)\n\n\
+             - Europe\n  - Asia\n\n\
+             | Region | Sales |\n| --- | --- |\n| EU \\| UK | 12 |\n"
+        );
+    }
+}
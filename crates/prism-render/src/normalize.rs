@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Page normalization
+//!
+//! Rescales pages to a common size before rendering, e.g. to turn a set of
+//! mixed-size scans into uniform Letter pages. Content is scaled uniformly
+//! so nothing is distorted; [`PageFit`] decides what happens when the
+//! aspect ratios differ.
+//!
+//! Top-level blocks, text run positions and annotations are in page
+//! coordinates and are scaled and offset. Children of tables and
+//! containers and vector path points are relative to their parent, so they
+//! are only scaled. Blocks without bounds (flowing content) are left as
+//! they are, apart from font sizes.
+
+use prism_core::document::{
+    ContentBlock, Dimensions, Document, Page, PathCommand, Point, Rect, TextBlock,
+};
+use prism_core::render::{PageFit, PageNormalization};
+
+/// Uniform scale followed by a translation
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+    scale: f64,
+    dx: f64,
+    dy: f64,
+}
+
+impl Transform {
+    /// Transform for a page of `source` size, and the resulting page size
+    fn for_page(source: Dimensions, target: Dimensions, fit: PageFit) -> (Self, Dimensions) {
+        let sx = target.width / source.width;
+        let sy = target.height / source.height;
+        let scale = match fit {
+            PageFit::Fit | PageFit::Letterbox => sx.min(sy),
+            PageFit::Fill => sx.max(sy),
+        };
+
+        if fit == PageFit::Fit {
+            let size = Dimensions::new(source.width * scale, source.height * scale);
+            return (
+                Self {
+                    scale,
+                    dx: 0.0,
+                    dy: 0.0,
+                },
+                size,
+            );
+        }
+
+        let transform = Self {
+            scale,
+            dx: (target.width - source.width * scale) / 2.0,
+            dy: (target.height - source.height * scale) / 2.0,
+        };
+        (transform, target)
+    }
+
+    /// The same scale without translation, for parent-relative coordinates
+    fn relative(self) -> Self {
+        Self {
+            dx: 0.0,
+            dy: 0.0,
+            ..self
+        }
+    }
+
+    fn point(self, point: &mut Point) {
+        point.x = point.x * self.scale + self.dx;
+        point.y = point.y * self.scale + self.dy;
+    }
+
+    fn rect(self, rect: &mut Rect) {
+        // Zero-sized bounds mean "not positioned" and must stay that way
+        if rect.width <= 0.0 || rect.height <= 0.0 {
+            return;
+        }
+        rect.x = rect.x * self.scale + self.dx;
+        rect.y = rect.y * self.scale + self.dy;
+        rect.width *= self.scale;
+        rect.height *= self.scale;
+    }
+
+    fn length(self, value: &mut Option<f64>) {
+        if let Some(value) = value {
+            *value *= self.scale;
+        }
+    }
+}
+
+/// Return `document` with every page resized according to `normalization`
+///
+/// Pages without a usable size are left untouched.
+#[must_use]
+pub fn normalize_document(document: &Document, normalization: &PageNormalization) -> Document {
+    let mut document = document.clone();
+    for page in &mut document.pages {
+        normalize_page(page, normalization);
+    }
+    document
+}
+
+/// Resize a single page in place
+pub fn normalize_page(page: &mut Page, normalization: &PageNormalization) {
+    let source = page.dimensions;
+    let target = normalization.size;
+    if !(source.width > 0.0 && source.height > 0.0 && target.width > 0.0 && target.height > 0.0) {
+        return;
+    }
+
+    let (transform, size) = Transform::for_page(source, target, normalization.fit);
+    page.dimensions = size;
+    for block in &mut page.content {
+        scale_block(block, transform);
+    }
+    for annotation in &mut page.annotations {
+        transform.rect(&mut annotation.bounds);
+    }
+}
+
+fn scale_block(block: &mut ContentBlock, transform: Transform) {
+    match block {
+        ContentBlock::Text(text) => scale_text(text, transform),
+        ContentBlock::Image(image) => {
+            transform.rect(&mut image.bounds);
+            transform.length(&mut image.style.stroke_width);
+        }
+        ContentBlock::Table(table) => {
+            transform.rect(&mut table.bounds);
+            transform.length(&mut table.style.stroke_width);
+            for row in &mut table.rows {
+                transform.length(&mut row.height);
+                for cell in &mut row.cells {
+                    for child in &mut cell.content {
+                        scale_block(child, transform.relative());
+                    }
+                }
+            }
+        }
+        ContentBlock::List(list) => {
+            transform.rect(&mut list.bounds);
+            for child in list.items.iter_mut().flat_map(|item| &mut item.content) {
+                scale_block(child, transform.relative());
+            }
+        }
+        ContentBlock::FormField(field) => transform.rect(&mut field.bounds),
+        ContentBlock::Vector(vector) => {
+            transform.rect(&mut vector.bounds);
+            let relative = transform.relative();
+            for path in &mut vector.paths {
+                relative.length(&mut path.stroke_width);
+                for command in &mut path.commands {
+                    match command {
+                        PathCommand::MoveTo(point) | PathCommand::LineTo(point) => {
+                            relative.point(point);
+                        }
+                        PathCommand::CurveTo { cp1, cp2, end } => {
+                            relative.point(cp1);
+                            relative.point(cp2);
+                            relative.point(end);
+                        }
+                        PathCommand::QuadTo { cp, end } => {
+                            relative.point(cp);
+                            relative.point(end);
+                        }
+                        PathCommand::Close => {}
+                    }
+                }
+            }
+        }
+        ContentBlock::Container(container) => {
+            transform.rect(&mut container.bounds);
+            for child in &mut container.children {
+                scale_block(child, transform.relative());
+            }
+        }
+    }
+}
+
+fn scale_text(text: &mut TextBlock, transform: Transform) {
+    transform.rect(&mut text.bounds);
+    transform.length(&mut text.style.stroke_width);
+    for run in &mut text.runs {
+        transform.length(&mut run.style.font_size);
+        if let Some(bounds) = &mut run.bounds {
+            transform.rect(bounds);
+        }
+        for point in run.char_positions.iter_mut().flatten() {
+            transform.point(point);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::TextRun;
+
+    fn scan(width: f64, height: f64) -> Page {
+        let mut block = TextBlock::new(Rect::new(0.0, 0.0, width, height / 2.0));
+        let mut run = TextRun::new("scan");
+        run.style.font_size = Some(10.0);
+        block.add_run(run);
+
+        let mut page = Page::new(1, Dimensions::new(width, height));
+        page.add_content(ContentBlock::Text(block));
+        page
+    }
+
+    fn text_bounds(page: &Page) -> (Rect, Option<f64>) {
+        match &page.content[0] {
+            ContentBlock::Text(text) => (
+                text.bounds,
+                text.runs.first().and_then(|run| run.style.font_size),
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_letterbox() {
+        // Half-width landscape page onto Letter: scaled by 2 to 612 wide,
+        // centered vertically
+        let mut page = scan(306.0, 198.0);
+        normalize_page(
+            &mut page,
+            &PageNormalization::new(Dimensions::LETTER, PageFit::Letterbox),
+        );
+
+        assert!((page.dimensions.width - 612.0).abs() < 1e-9);
+        assert!((page.dimensions.height - 792.0).abs() < 1e-9);
+        let (bounds, font_size) = text_bounds(&page);
+        assert!((bounds.y - 198.0).abs() < 1e-9);
+        assert!((bounds.height - 198.0).abs() < 1e-9);
+        assert_eq!(font_size, Some(20.0));
+    }
+
+    #[test]
+    fn test_fit_and_fill() {
+        let mut fit = scan(306.0, 198.0);
+        normalize_page(
+            &mut fit,
+            &PageNormalization::new(Dimensions::LETTER, PageFit::Fit),
+        );
+        assert!((fit.dimensions.height - 396.0).abs() < 1e-9);
+        assert!(text_bounds(&fit).0.y.abs() < 1e-9);
+
+        // Fill scales by 4 to cover the height and crops the sides
+        let mut fill = scan(306.0, 198.0);
+        normalize_page(
+            &mut fill,
+            &PageNormalization::new(Dimensions::LETTER, PageFit::Fill),
+        );
+        let (bounds, _) = text_bounds(&fill);
+        assert!((bounds.width - 1224.0).abs() < 1e-9);
+        assert!((bounds.x + 306.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unpositioned_blocks_stay_unpositioned() {
+        let mut page = Page::new(1, Dimensions::A4);
+        page.add_content(ContentBlock::Text(TextBlock::new(Rect::default())));
+        normalize_page(
+            &mut page,
+            &PageNormalization::new(Dimensions::LETTER, PageFit::Letterbox),
+        );
+
+        let (bounds, _) = text_bounds(&page);
+        assert!(bounds.width == 0.0 && bounds.x == 0.0);
+    }
+}
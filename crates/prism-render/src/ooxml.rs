@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Package parts shared by the Office Open XML writers.
+
+use prism_core::metadata::Metadata;
+use std::fmt::Write as _;
+
+/// Declaration starting every XML part
+pub const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+
+/// Namespace of relationship IDs in a part, and base of the relationship
+/// types between parts
+pub const RELATIONSHIPS_NS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Relationship type of a package's main part
+pub const OFFICE_DOCUMENT_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+
+/// Name of the core properties part
+pub const CORE_PROPERTIES_PART: &str = "docProps/core.xml";
+
+/// Escape text for element content and attribute values
+///
+/// Characters XML 1.0 cannot represent at all, such as most C0 controls,
+/// are dropped.
+#[must_use]
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `_rels/.rels`, pointing at the main part and the core properties
+#[must_use]
+pub fn package_relationships(main_part: &str) -> String {
+    format!(
+        r#"{XML_DECLARATION}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="{OFFICE_DOCUMENT_RELATIONSHIP}" Target="{main_part}"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="{CORE_PROPERTIES_PART}"/></Relationships>"#
+    )
+}
+
+/// `docProps/core.xml` with the document's title, author, subject and
+/// keywords
+///
+/// Dates are left out so that output depends only on content.
+#[must_use]
+pub fn core_properties(metadata: &Metadata) -> String {
+    let mut xml = format!(
+        r#"{XML_DECLARATION}<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">"#
+    );
+    let mut element = |name: &str, value: Option<&str>| {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            let _ = write!(xml, "<{name}>{}</{name}>", escape(value));
+        }
+    };
+    element("dc:title", metadata.title.as_deref());
+    element("dc:subject", metadata.subject.as_deref());
+    element("dc:creator", metadata.author.as_deref());
+    let keywords = metadata.keywords.join(", ");
+    element("cp:keywords", Some(&keywords));
+    element("dc:language", metadata.language.as_deref());
+    xml.push_str("</cp:coreProperties>");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("a < b & \"c\"\u{1}\n"),
+            "a &lt; b &amp; &quot;c&quot;\n"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Slide Content Export
+//!
+//! Per-slide text of a presentation: title, body text, speaker notes and
+//! image alt text. Rendering a deck as pages keeps its layout but drops the
+//! notes and scatters the text across positioned boxes; this export keeps
+//! only the words, slide by slide, as JSON or Markdown, for turning decks
+//! into documentation or feeding them to search and language models.
+
+use prism_core::document::{ContentBlock, Document, ListItem, ListMarker, Page};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Text content of a presentation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlideExport {
+    /// Document title
+    pub title: Option<String>,
+    /// Slides in order
+    pub slides: Vec<SlideContent>,
+}
+
+/// Text content of one slide
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlideContent {
+    /// Slide number (1-indexed)
+    pub number: u32,
+    /// Text of the title placeholder
+    pub title: Option<String>,
+    /// Paragraphs of the other text, in reading order; table rows are
+    /// joined with ` | ` and list items start with a Markdown marker
+    pub body: Vec<String>,
+    /// Speaker notes
+    pub notes: Option<String>,
+    /// Pictures on the slide
+    pub images: Vec<SlideImage>,
+}
+
+/// A picture on a slide
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlideImage {
+    /// Image resource ID
+    pub resource_id: String,
+    /// Alternative text, if the author provided any
+    pub alt_text: Option<String>,
+}
+
+impl SlideExport {
+    /// Collect the slide content of a parsed presentation
+    ///
+    /// Any document can be exported; pages without a title placeholder
+    /// simply have no title.
+    #[must_use]
+    pub fn from_document(document: &Document) -> Self {
+        Self {
+            title: document.metadata.title.clone(),
+            slides: document.pages.iter().map(SlideContent::from_page).collect(),
+        }
+    }
+
+    /// Render the export as Markdown, one section per slide
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "# {title}\n");
+        }
+
+        for slide in &self.slides {
+            let _ = write!(out, "## Slide {}", slide.number);
+            if let Some(title) = &slide.title {
+                let _ = write!(out, ": {title}");
+            }
+            let _ = writeln!(out, "\n");
+
+            for paragraph in &slide.body {
+                let _ = writeln!(out, "{paragraph}\n");
+            }
+            if !slide.images.is_empty() {
+                let _ = writeln!(out, "### Images\n");
+                for image in &slide.images {
+                    let _ = writeln!(
+                        out,
+                        "- {}",
+                        image.alt_text.as_deref().unwrap_or("(no alt text)")
+                    );
+                }
+                let _ = writeln!(out);
+            }
+            if let Some(notes) = &slide.notes {
+                let _ = writeln!(out, "### Notes\n\n{notes}\n");
+            }
+        }
+
+        let trimmed = out.trim_end().len();
+        out.truncate(trimmed);
+        out.push('\n');
+        out
+    }
+}
+
+impl SlideContent {
+    /// Collect the content of one page
+    #[must_use]
+    pub fn from_page(page: &Page) -> Self {
+        let mut slide = Self {
+            number: page.number,
+            notes: page.metadata.notes.clone(),
+            ..Self::default()
+        };
+        for block in &page.content {
+            slide.add_block(block, page);
+        }
+        slide
+    }
+
+    fn add_block(&mut self, block: &ContentBlock, page: &Page) {
+        match block {
+            ContentBlock::Text(text) => {
+                let content = text.extract_text();
+                let is_title = text
+                    .paragraph_style
+                    .as_deref()
+                    .is_some_and(|style| style.eq_ignore_ascii_case("title"));
+                if is_title && self.title.is_none() {
+                    let title = content.split_whitespace().collect::<Vec<_>>().join(" ");
+                    self.title = (!title.is_empty()).then_some(title);
+                } else {
+                    self.body.extend(
+                        content
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .map(str::to_string),
+                    );
+                }
+            }
+            ContentBlock::Table(table) => {
+                self.body.extend(table.rows.iter().filter_map(|row| {
+                    let cells: Vec<String> = row
+                        .cells
+                        .iter()
+                        .map(|cell| cell.extract_text().trim().to_string())
+                        .collect();
+                    cells
+                        .iter()
+                        .any(|cell| !cell.is_empty())
+                        .then(|| cells.join(" | "))
+                }));
+            }
+            ContentBlock::List(list) => {
+                self.body.extend(list.items.iter().filter_map(|item| {
+                    let text = item.extract_text();
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    (!text.is_empty()).then(|| {
+                        let indent = "  ".repeat(usize::from(item.level));
+                        format!("{indent}{} {text}", markdown_marker(item))
+                    })
+                }));
+            }
+            ContentBlock::Image(image) => {
+                // Slide backgrounds are pictures too, but not content
+                let bounds = &image.bounds;
+                let is_background = bounds.x <= 0.0
+                    && bounds.y <= 0.0
+                    && bounds.width >= page.dimensions.width
+                    && bounds.height >= page.dimensions.height;
+                if !is_background {
+                    self.images.push(SlideImage {
+                        resource_id: image.resource_id.clone(),
+                        alt_text: image
+                            .alt_text
+                            .as_deref()
+                            .map(str::trim)
+                            .filter(|alt| !alt.is_empty())
+                            .map(str::to_string),
+                    });
+                }
+            }
+            ContentBlock::FormField(field) => {
+                let value = field.extract_text();
+                if !value.is_empty() {
+                    let label = field.label.as_deref().unwrap_or(&field.name);
+                    self.body.push(format!("{label}: {value}"));
+                }
+            }
+            ContentBlock::Container(container) => {
+                for child in &container.children {
+                    self.add_block(child, page);
+                }
+            }
+            ContentBlock::Vector(_) => {}
+        }
+    }
+}
+
+/// Markdown marker for a list item; Markdown only numbers in decimal
+pub(crate) fn markdown_marker(item: &ListItem) -> String {
+    if !item.ordered {
+        "-".to_string()
+    } else if item.marker_style == ListMarker::Decimal && !item.marker.is_empty() {
+        item.marker.clone()
+    } else {
+        "1.".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{
+        Dimensions, ImageBlock, ListBlock, Rect, ShapeStyle, TextBlock, TextRun, TextStyle,
+    };
+
+    fn text(content: &str, style: Option<&str>) -> ContentBlock {
+        let mut block = TextBlock::new(Rect::new(10.0, 10.0, 100.0, 20.0));
+        block.add_run(TextRun {
+            text: content.into(),
+            style: TextStyle::default(),
+            bounds: None,
+            char_positions: None,
+            link: None,
+            revision: None,
+        });
+        block.paragraph_style = style.map(str::to_string);
+        ContentBlock::Text(block)
+    }
+
+    fn image(bounds: Rect, alt_text: Option<&str>) -> ContentBlock {
+        ContentBlock::Image(ImageBlock {
+            bounds,
+            resource_id: "../media/image1.png".to_string(),
+            alt_text: alt_text.map(str::to_string),
+            format: None,
+            original_size: None,
+            style: ShapeStyle::default(),
+            rotation: 0.0,
+            crop: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+        })
+    }
+
+    fn bullets(items: &[&str]) -> ContentBlock {
+        let mut list = ListBlock::new(Rect::new(10.0, 40.0, 100.0, 40.0));
+        for (level, item) in (0..).zip(items) {
+            list.add_item(ListItem::bullet(vec![text(item, None)], level));
+        }
+        ContentBlock::List(list)
+    }
+
+    fn deck() -> Document {
+        let mut page = Page::new(1, Dimensions::new(960.0, 540.0));
+        page.content = vec![
+            image(Rect::new(0.0, 0.0, 960.0, 540.0), Some("Background Image")),
+            text("Quarterly\nresults\n", Some("Title")),
+            text("Revenue grew\n\nCosts fell\n", None),
+            bullets(&["Europe", "Asia"]),
+            image(Rect::new(100.0, 100.0, 200.0, 100.0), Some("Revenue chart")),
+            image(Rect::new(300.0, 100.0, 200.0, 100.0), None),
+        ];
+        page.metadata.notes = Some("Mention the new region".to_string());
+
+        let mut document = Document::new();
+        document.metadata.title = Some("Review".to_string());
+        document.pages = vec![page, Page::new(2, Dimensions::new(960.0, 540.0))];
+        document
+    }
+
+    #[test]
+    fn test_from_document() {
+        let export = SlideExport::from_document(&deck());
+        assert_eq!(export.slides.len(), 2);
+
+        let slide = &export.slides[0];
+        assert_eq!(slide.title.as_deref(), Some("Quarterly results"));
+        assert_eq!(
+            slide.body,
+            ["Revenue grew", "Costs fell", "- Europe", "  - Asia"]
+        );
+        assert_eq!(slide.notes.as_deref(), Some("Mention the new region"));
+        let alt: Vec<_> = slide.images.iter().map(|i| i.alt_text.as_deref()).collect();
+        assert_eq!(alt, [Some("Revenue chart"), None]);
+
+        assert_eq!(
+            export.slides[1],
+            SlideContent {
+                number: 2,
+                ..SlideContent::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = SlideExport::from_document(&deck()).to_markdown();
+        assert!(markdown.starts_with("# Review\n\n## Slide 1: Quarterly results\n\n"));
+        assert!(markdown.contains("Costs fell\n\n- Europe\n\n  - Asia\n\n### Images\n\n"));
+        assert!(markdown.contains("- Revenue chart\n- (no alt text)\n"));
+        assert!(markdown.contains("### Notes\n\nMention the new region\n\n## Slide 2\n"));
+        assert!(markdown.ends_with("## Slide 2\n"));
+    }
+}
@@ -0,0 +1,760 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # XLSX renderer
+//!
+//! Writes the tables of a document as an Excel workbook, one worksheet per
+//! page holding a table. This is the way back to a spreadsheet for documents
+//! read from CSV, XLS or XLSX, and for tables pulled out of any other format.
+//!
+//! Tables on the same page are stacked with a blank row between them. Cell
+//! spans become merged ranges and row heights are kept. Column widths come
+//! from the width of the cells' text boxes or, for flowing content, from the
+//! length of their text. A cell's font (bold, italic, underline,
+//! strikethrough, family, size and color) is taken from its first run, and
+//! its background becomes a solid fill.
+//!
+//! Sheets are named after the page label, made valid and unique. Text is
+//! written as inline strings, and values that read as plain decimal numbers
+//! as numbers.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prism_core::document::{ContentBlock, Document, Page, TableBlock, TableCell, TextStyle};
+use prism_core::error::{Error, Result};
+use prism_core::format::Format;
+use prism_core::render::{RenderContext, RenderFeature, Renderer, RendererMetadata};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+
+use crate::color::Rgba;
+use crate::filter::filter_document;
+use crate::ooxml::{self, escape, RELATIONSHIPS_NS, XML_DECLARATION};
+use crate::zip_writer::DeterministicZipWriter;
+
+/// `SpreadsheetML` namespace
+const SPREADSHEET_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+
+/// Longest sheet name Excel accepts
+const MAX_SHEET_NAME: usize = 31;
+
+/// Most characters a cell holds
+const MAX_CELL_TEXT: usize = 32_767;
+
+/// Rows of a worksheet
+const MAX_ROWS: usize = 1_048_576;
+
+/// Columns of a worksheet
+const MAX_COLUMNS: usize = 16_384;
+
+/// Excel's default column width, in characters
+const MIN_COLUMN_WIDTH: f64 = 8.43;
+
+/// Widest column written, in characters
+const MAX_COLUMN_WIDTH: f64 = 80.0;
+
+/// Points per character of column width (7 pixels at 96 dpi)
+const POINTS_PER_CHARACTER: f64 = 5.25;
+
+/// Most significant digits a number cell keeps
+const MAX_NUMBER_DIGITS: usize = 15;
+
+/// XLSX renderer configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XlsxConfig {
+    /// Write text that reads as a plain decimal number as a number cell.
+    /// Values with leading zeros, such as codes and IDs, always stay text.
+    pub detect_numbers: bool,
+}
+
+impl Default for XlsxConfig {
+    fn default() -> Self {
+        Self {
+            detect_numbers: true,
+        }
+    }
+}
+
+/// Renders the tables of documents as XLSX workbooks
+#[derive(Debug, Clone, Default)]
+pub struct XlsxRenderer {
+    config: XlsxConfig,
+}
+
+impl XlsxRenderer {
+    /// Create a renderer with the default configuration
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a renderer with custom configuration
+    #[must_use]
+    pub fn with_config(config: XlsxConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write the tables of `document` as a workbook
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::RenderError` if the document has no tables or the
+    /// package cannot be written.
+    pub fn render_xlsx(&self, document: &Document) -> Result<Vec<u8>> {
+        let mut styles = Styles::default();
+        let mut names = Vec::new();
+        let mut sheets = Vec::new();
+        for page in &document.pages {
+            let mut tables = Vec::new();
+            collect_tables(&page.content, &mut tables);
+            if tables.is_empty() {
+                continue;
+            }
+            let mut sheet = Sheet::default();
+            for table in tables {
+                sheet.add_table(table, &mut styles, self.config.detect_numbers);
+            }
+            names.push(sheet_name(page, &names));
+            sheets.push(sheet);
+        }
+        if sheets.is_empty() {
+            return Err(Error::RenderError(
+                "Document has no tables to write as a spreadsheet".to_string(),
+            ));
+        }
+
+        let mut zip = DeterministicZipWriter::new().with_leading(["[Content_Types].xml"]);
+        zip.add("[Content_Types].xml", content_types(sheets.len()));
+        zip.add(
+            "_rels/.rels",
+            ooxml::package_relationships("xl/workbook.xml"),
+        );
+        zip.add(
+            ooxml::CORE_PROPERTIES_PART,
+            ooxml::core_properties(&document.metadata),
+        );
+        zip.add("xl/workbook.xml", workbook(&names));
+        zip.add(
+            "xl/_rels/workbook.xml.rels",
+            workbook_relationships(sheets.len()),
+        );
+        zip.add("xl/styles.xml", styles.to_xml());
+        for (index, sheet) in sheets.iter().enumerate() {
+            zip.add(
+                format!("xl/worksheets/sheet{}.xml", index + 1),
+                sheet.to_xml(),
+            );
+        }
+        zip.finish()
+    }
+}
+
+/// Tables among `blocks`, including those inside containers
+fn collect_tables<'a>(blocks: &'a [ContentBlock], tables: &mut Vec<&'a TableBlock>) {
+    for block in blocks {
+        match block {
+            ContentBlock::Table(table) => tables.push(table),
+            ContentBlock::Container(container) => collect_tables(&container.children, tables),
+            _ => {}
+        }
+    }
+}
+
+/// Value of a worksheet cell
+#[derive(Debug, Clone, PartialEq)]
+enum CellValue {
+    Empty,
+    Text(String),
+    Number(f64),
+}
+
+/// A written cell
+#[derive(Debug, Clone, PartialEq)]
+struct Cell {
+    value: CellValue,
+    /// Index into the cell formats of [`Styles`]
+    style: usize,
+}
+
+/// A worksheet row
+#[derive(Debug, Default)]
+struct Row {
+    /// Height in points
+    height: Option<f64>,
+    /// Cells by zero-based column
+    cells: BTreeMap<usize, Cell>,
+}
+
+/// A worksheet being filled
+#[derive(Debug, Default)]
+struct Sheet {
+    /// Rows by zero-based index
+    rows: BTreeMap<usize, Row>,
+    /// Merged ranges as first row, first column, last row, last column
+    merges: Vec<[usize; 4]>,
+    /// Column widths in characters; zero keeps the default
+    widths: Vec<f64>,
+    /// First row below everything written so far
+    next_row: usize,
+}
+
+impl Sheet {
+    /// Write `table` below the content already on the sheet
+    fn add_table(&mut self, table: &TableBlock, styles: &mut Styles, detect_numbers: bool) {
+        if self.next_row > 0 {
+            self.next_row += 1;
+        }
+        let top = self.next_row;
+        // Grid positions covered by cells, relative to the table
+        let mut taken = HashSet::new();
+        for (table_row, row) in table.rows.iter().enumerate() {
+            let row_index = top + table_row;
+            if row_index >= MAX_ROWS {
+                break;
+            }
+            let sheet_row = self.rows.entry(row_index).or_default();
+            sheet_row.height = row.height.filter(|height| *height > 0.0);
+
+            let mut column = 0;
+            for cell in &row.cells {
+                while taken.contains(&(table_row, column)) {
+                    column += 1;
+                }
+                let rows = cell.row_span.max(1);
+                let columns = cell.col_span.max(1);
+                for covered_row in table_row..table_row + rows {
+                    for covered_column in column..column + columns {
+                        taken.insert((covered_row, covered_column));
+                    }
+                }
+                if column >= MAX_COLUMNS {
+                    continue;
+                }
+
+                let style = styles.index(cell);
+                let value = cell_value(cell, detect_numbers);
+                if value != CellValue::Empty || style != 0 {
+                    sheet_row.cells.insert(column, Cell { value, style });
+                }
+                if rows > 1 || columns > 1 {
+                    self.merges.push([
+                        row_index,
+                        column,
+                        (row_index + rows - 1).min(MAX_ROWS - 1),
+                        (column + columns - 1).min(MAX_COLUMNS - 1),
+                    ]);
+                } else {
+                    if self.widths.len() <= column {
+                        self.widths.resize(column + 1, 0.0);
+                    }
+                    self.widths[column] = self.widths[column].max(cell_width(cell));
+                }
+                column += columns;
+            }
+        }
+        // Row spans may reach past the last row
+        let height = taken.iter().map(|(row, _)| row + 1).max().unwrap_or(0);
+        self.next_row = (top + height).max(top + table.rows.len());
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = format!(
+            r#"{XML_DECLARATION}<worksheet xmlns="{SPREADSHEET_NS}" xmlns:r="{RELATIONSHIPS_NS}">"#
+        );
+        if self.widths.iter().any(|width| *width > 0.0) {
+            xml.push_str("<cols>");
+            for (column, width) in self.widths.iter().enumerate() {
+                if *width > 0.0 {
+                    let width = width.clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH);
+                    let _ = write!(
+                        xml,
+                        r#"<col min="{0}" max="{0}" width="{width:.2}" customWidth="1"/>"#,
+                        column + 1
+                    );
+                }
+            }
+            xml.push_str("</cols>");
+        }
+
+        xml.push_str("<sheetData>");
+        for (index, row) in &self.rows {
+            let _ = write!(xml, r#"<row r="{}""#, index + 1);
+            if let Some(height) = row.height {
+                let _ = write!(xml, r#" ht="{height:.2}" customHeight="1""#);
+            }
+            xml.push('>');
+            for (column, cell) in &row.cells {
+                let _ = write!(xml, r#"<c r="{}{}""#, column_name(*column), index + 1);
+                if cell.style != 0 {
+                    let _ = write!(xml, r#" s="{}""#, cell.style);
+                }
+                match &cell.value {
+                    CellValue::Empty => xml.push_str("/>"),
+                    CellValue::Number(number) => {
+                        let _ = write!(xml, "><v>{number}</v></c>");
+                    }
+                    CellValue::Text(text) => {
+                        let _ = write!(
+                            xml,
+                            r#" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                            escape(text)
+                        );
+                    }
+                }
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData>");
+
+        if !self.merges.is_empty() {
+            let _ = write!(xml, r#"<mergeCells count="{}">"#, self.merges.len());
+            for [first_row, first_column, last_row, last_column] in &self.merges {
+                let _ = write!(
+                    xml,
+                    r#"<mergeCell ref="{}{}:{}{}"/>"#,
+                    column_name(*first_column),
+                    first_row + 1,
+                    column_name(*last_column),
+                    last_row + 1
+                );
+            }
+            xml.push_str("</mergeCells>");
+        }
+        xml.push_str("</worksheet>");
+        xml
+    }
+}
+
+/// The value to write for `cell`
+fn cell_value(cell: &TableCell, detect_numbers: bool) -> CellValue {
+    let text = cell.extract_text();
+    if text.is_empty() {
+        return CellValue::Empty;
+    }
+    if detect_numbers {
+        if let Some(number) = number(text.trim()) {
+            return CellValue::Number(number);
+        }
+    }
+    if text.chars().count() > MAX_CELL_TEXT {
+        return CellValue::Text(text.chars().take(MAX_CELL_TEXT).collect());
+    }
+    CellValue::Text(text)
+}
+
+/// `text` as a number, if it is a plain decimal such as `-12` or `3.50`
+///
+/// Leading zeros, exponents, thousands separators and numbers with more
+/// digits than a cell keeps are left as text.
+fn number(text: &str) -> Option<f64> {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let plain = !whole.is_empty()
+        && whole.bytes().all(|b| b.is_ascii_digit())
+        && fraction.bytes().all(|b| b.is_ascii_digit())
+        && !(whole.len() > 1 && whole.starts_with('0'))
+        && !digits.ends_with('.')
+        && whole.len() + fraction.len() <= MAX_NUMBER_DIGITS;
+    plain.then(|| text.parse().ok()).flatten()
+}
+
+/// Width `cell` needs, in characters
+fn cell_width(cell: &TableCell) -> f64 {
+    let positioned = cell
+        .content
+        .iter()
+        .map(|block| block.bounds().width)
+        .fold(0.0, f64::max);
+    if positioned > 0.0 {
+        return positioned / POINTS_PER_CHARACTER;
+    }
+    let longest = cell
+        .extract_text()
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    // Padding for the gridlines and cell margins
+    u32::try_from(longest).map_or(MAX_COLUMN_WIDTH, |chars| f64::from(chars) + 1.0)
+}
+
+/// Spreadsheet column name: A, B, ..., Z, AA, AB, ...
+fn column_name(mut index: usize) -> String {
+    const LETTERS: &[u8; 26] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut name = Vec::new();
+    loop {
+        name.push(LETTERS[index % 26]);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// A valid sheet name for `page`, distinct from the names in `taken`
+///
+/// Excel rejects names longer than 31 characters, names containing any of
+/// `[]:*?/\` and names starting or ending with an apostrophe, and compares
+/// them ignoring case.
+fn sheet_name(page: &Page, taken: &[String]) -> String {
+    let label: String = page
+        .metadata
+        .label
+        .as_deref()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') && !c.is_control())
+        .collect();
+    let label = label.trim().trim_matches('\'');
+    let base = if label.is_empty() {
+        format!("Sheet{}", taken.len() + 1)
+    } else {
+        label.to_string()
+    };
+
+    let is_taken = |name: &str| {
+        let name = name.to_lowercase();
+        taken.iter().any(|other| other.to_lowercase() == name)
+    };
+    let mut name: String = base.chars().take(MAX_SHEET_NAME).collect();
+    let mut suffix = 2;
+    while is_taken(&name) {
+        let tag = format!(" ({suffix})");
+        name = base
+            .chars()
+            .take(MAX_SHEET_NAME - tag.len())
+            .chain(tag.chars())
+            .collect();
+        suffix += 1;
+    }
+    name
+}
+
+/// A cell font; the default is the workbook font
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Font {
+    family: Option<String>,
+    size: Option<f64>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    /// ARGB hex
+    color: Option<String>,
+}
+
+impl Font {
+    fn from_style(style: &TextStyle) -> Self {
+        Self {
+            family: style.font_family.as_deref().map(str::to_string),
+            size: style.font_size.filter(|size| *size > 0.0),
+            bold: style.bold,
+            italic: style.italic,
+            underline: style.underline,
+            strikethrough: style.strikethrough,
+            color: style.color.as_deref().and_then(argb),
+        }
+    }
+}
+
+/// A color as `SpreadsheetML` ARGB hex, dropping transparency
+fn argb(color: &str) -> Option<String> {
+    let color = Rgba::parse(color)?;
+    Some(format!("FF{:02X}{:02X}{:02X}", color.r, color.g, color.b))
+}
+
+/// Fonts, fills and the cell formats combining them
+#[derive(Debug, Default)]
+struct Styles {
+    /// Fonts after the default
+    fonts: Vec<Font>,
+    /// Solid fill colors after the two reserved fills
+    fills: Vec<String>,
+    /// Cell formats after the default, as font and fill indexes
+    formats: Vec<(usize, usize)>,
+}
+
+impl Styles {
+    /// The cell format for `cell`, 0 being the default
+    fn index(&mut self, cell: &TableCell) -> usize {
+        let font = cell
+            .content
+            .iter()
+            .find_map(|block| match block {
+                ContentBlock::Text(text) => text.runs.first(),
+                _ => None,
+            })
+            .map(|run| Font::from_style(&run.style))
+            .unwrap_or_default();
+        let fill = cell.background_color.as_deref().and_then(argb);
+        if font == Font::default() && fill.is_none() {
+            return 0;
+        }
+
+        let font = if font == Font::default() {
+            0
+        } else {
+            position_or_push(&mut self.fonts, font) + 1
+        };
+        // Fills 0 and 1 are reserved for "none" and "gray125"
+        let fill = fill.map_or(0, |fill| position_or_push(&mut self.fills, fill) + 2);
+        position_or_push(&mut self.formats, (font, fill)) + 1
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = format!(r#"{XML_DECLARATION}<styleSheet xmlns="{SPREADSHEET_NS}">"#);
+
+        let _ = write!(
+            xml,
+            r#"<fonts count="{}"><font><sz val="11"/><name val="Calibri"/></font>"#,
+            self.fonts.len() + 1
+        );
+        for font in &self.fonts {
+            xml.push_str("<font>");
+            for (on, tag) in [
+                (font.bold, "<b/>"),
+                (font.italic, "<i/>"),
+                (font.strikethrough, "<strike/>"),
+                (font.underline, "<u/>"),
+            ] {
+                if on {
+                    xml.push_str(tag);
+                }
+            }
+            let _ = write!(xml, r#"<sz val="{}"/>"#, font.size.unwrap_or(11.0));
+            if let Some(color) = &font.color {
+                let _ = write!(xml, r#"<color rgb="{color}"/>"#);
+            }
+            let _ = write!(
+                xml,
+                r#"<name val="{}"/></font>"#,
+                escape(font.family.as_deref().unwrap_or("Calibri"))
+            );
+        }
+        xml.push_str("</fonts>");
+
+        let _ = write!(
+            xml,
+            r#"<fills count="{}"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill>"#,
+            self.fills.len() + 2
+        );
+        for color in &self.fills {
+            let _ = write!(
+                xml,
+                r#"<fill><patternFill patternType="solid"><fgColor rgb="{color}"/><bgColor indexed="64"/></patternFill></fill>"#
+            );
+        }
+        xml.push_str("</fills>");
+
+        xml.push_str(
+            r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+        );
+        let _ = write!(
+            xml,
+            r#"<cellXfs count="{}"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>"#,
+            self.formats.len() + 1
+        );
+        for (font, fill) in &self.formats {
+            let _ = write!(
+                xml,
+                r#"<xf numFmtId="0" fontId="{font}" fillId="{fill}" borderId="0" xfId="0" applyFont="1" applyFill="1"/>"#
+            );
+        }
+        xml.push_str(
+            r#"</cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#,
+        );
+        xml
+    }
+}
+
+/// Index of `item` in `items`, appending it if missing
+fn position_or_push<T: PartialEq>(items: &mut Vec<T>, item: T) -> usize {
+    if let Some(index) = items.iter().position(|existing| *existing == item) {
+        return index;
+    }
+    items.push(item);
+    items.len() - 1
+}
+
+fn content_types(sheet_count: usize) -> String {
+    let mut xml = format!(
+        r#"{XML_DECLARATION}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/><Override PartName="/{}" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>"#,
+        ooxml::CORE_PROPERTIES_PART
+    );
+    for sheet in 1..=sheet_count {
+        let _ = write!(
+            xml,
+            r#"<Override PartName="/xl/worksheets/sheet{sheet}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        );
+    }
+    xml.push_str("</Types>");
+    xml
+}
+
+fn workbook(names: &[String]) -> String {
+    let mut xml = format!(
+        r#"{XML_DECLARATION}<workbook xmlns="{SPREADSHEET_NS}" xmlns:r="{RELATIONSHIPS_NS}"><sheets>"#
+    );
+    for (index, name) in names.iter().enumerate() {
+        let _ = write!(
+            xml,
+            r#"<sheet name="{}" sheetId="{1}" r:id="rId{1}"/>"#,
+            escape(name),
+            index + 1
+        );
+    }
+    xml.push_str("</sheets></workbook>");
+    xml
+}
+
+/// Relationships of the workbook: the sheets as `rId1`..`rIdN`, then the
+/// styles
+fn workbook_relationships(sheet_count: usize) -> String {
+    let mut xml = format!(
+        r#"{XML_DECLARATION}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#
+    );
+    for sheet in 1..=sheet_count {
+        let _ = write!(
+            xml,
+            r#"<Relationship Id="rId{sheet}" Type="{RELATIONSHIPS_NS}/worksheet" Target="worksheets/sheet{sheet}.xml"/>"#
+        );
+    }
+    let _ = write!(
+        xml,
+        r#"<Relationship Id="rId{}" Type="{RELATIONSHIPS_NS}/styles" Target="styles.xml"/></Relationships>"#,
+        sheet_count + 1
+    );
+    xml
+}
+
+#[async_trait]
+impl Renderer for XlsxRenderer {
+    fn output_format(&self) -> Format {
+        Format::xlsx()
+    }
+
+    async fn render(&self, document: &Document, context: RenderContext) -> Result<Bytes> {
+        let xlsx = if context.options.content.is_all() {
+            self.render_xlsx(document)?
+        } else {
+            self.render_xlsx(&filter_document(document, &context.options.content))?
+        };
+        Ok(Bytes::from(xlsx))
+    }
+
+    /// Only tables become worksheets; containers are searched for them
+    fn supports(&self, block: &ContentBlock) -> bool {
+        matches!(block, ContentBlock::Table(_) | ContentBlock::Container(_))
+    }
+
+    fn metadata(&self) -> RendererMetadata {
+        RendererMetadata {
+            name: "XLSX Renderer".to_string(),
+            version: crate::VERSION.to_string(),
+            features: vec![RenderFeature::TableRendering],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_core::document::{Dimensions, Rect, TableRow, TextBlock, TextRun};
+    use std::io::{Cursor, Read};
+
+    fn cell(text: &str, col_span: usize, row_span: usize) -> TableCell {
+        let mut block = TextBlock::new(Rect::default());
+        if !text.is_empty() {
+            block.add_run(TextRun::new(text));
+        }
+        TableCell {
+            content: vec![ContentBlock::Text(block)],
+            col_span,
+            row_span,
+            background_color: None,
+        }
+    }
+
+    fn part(xlsx: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(xlsx)).unwrap();
+        let mut xml = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        xml
+    }
+
+    #[test]
+    fn test_render_xlsx() {
+        let mut table = TableBlock::new(Rect::default(), 3);
+        let mut heading = cell("Region", 1, 1);
+        heading.background_color = Some("#FFFF00".to_string());
+        if let ContentBlock::Text(text) = &mut heading.content[0] {
+            text.runs[0].style.bold = true;
+        }
+        table.add_row(TableRow {
+            cells: vec![heading, cell("Q1 & Q2", 2, 1)],
+            height: Some(24.0),
+        });
+        table.add_row(TableRow {
+            cells: vec![cell("North", 1, 2), cell("42", 1, 1), cell("007", 1, 1)],
+            height: None,
+        });
+        table.add_row(TableRow {
+            cells: vec![cell("-3.5", 1, 1), cell("", 1, 1)],
+            height: None,
+        });
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.metadata.label = Some("Sales: 2024".to_string());
+        page.add_content(ContentBlock::Table(table));
+        let document = Document::builder()
+            .page(page)
+            .add_text_page("Notes", "No tables here.")
+            .build();
+
+        let xlsx = XlsxRenderer::new().render_xlsx(&document).unwrap();
+        assert!(part(&xlsx, "xl/workbook.xml").contains(r#"<sheet name="Sales 2024" sheetId="1""#));
+        let sheet = part(&xlsx, "xl/worksheets/sheet1.xml");
+        assert!(sheet
+            .contains(r#"<c r="A1" s="1" t="inlineStr"><is><t xml:space="preserve">Region</t>"#));
+        assert!(sheet.contains("Q1 &amp; Q2"));
+        assert!(sheet.contains(r#"<row r="1" ht="24.00" customHeight="1">"#));
+        assert!(sheet.contains(r#"<c r="B2"><v>42</v></c>"#));
+        assert!(sheet.contains(r#"<c r="C2" t="inlineStr"><is><t xml:space="preserve">007</t>"#));
+        // The row span pushes the third row's cells past column A
+        assert!(sheet.contains(r#"<c r="B3"><v>-3.5</v></c>"#));
+        assert!(sheet.contains(r#"<mergeCell ref="B1:C1"/><mergeCell ref="A2:A3"/>"#));
+        let styles = part(&xlsx, "xl/styles.xml");
+        assert!(styles.contains("<font><b/>"));
+        assert!(styles.contains(r#"<fgColor rgb="FFFFFF00"/>"#));
+        assert!(zip::ZipArchive::new(Cursor::new(&xlsx))
+            .unwrap()
+            .by_name("xl/worksheets/sheet2.xml")
+            .is_err());
+
+        let error = XlsxRenderer::new()
+            .render_xlsx(&Document::builder().add_text_page("Notes", "Text").build())
+            .unwrap_err();
+        assert!(matches!(error, Error::RenderError(_)));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(16_383), "XFD");
+
+        let mut page = Page::new(1, Dimensions::LETTER);
+        page.metadata.label = Some("Quarterly figures for the northern region".to_string());
+        let first = sheet_name(&page, &[]);
+        assert_eq!(first, "Quarterly figures for the north");
+        let second = sheet_name(&page, std::slice::from_ref(&first));
+        assert_eq!(second, "Quarterly figures for the n (2)");
+        page.metadata.label = None;
+        assert_eq!(sheet_name(&page, &[first, second]), "Sheet3");
+    }
+}
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Deterministic ZIP writer for container outputs.
+//!
+//! Container formats produced by Prism (DOCX, XLSX, EPUB, batch archives)
+//! must hash identically across runs and machines. [`DeterministicZipWriter`]
+//! guarantees that by:
+//!
+//! - ordering entries by name, after an optional list of leading entries
+//!   (e.g. `mimetype` for EPUB, `[Content_Types].xml` for OOXML)
+//! - stamping every entry with the same fixed timestamp
+//! - using a fixed compression method and level, and fixed permissions
+
+use prism_core::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+/// Deflate level used for compressed entries
+pub const COMPRESSION_LEVEL: i32 = 6;
+
+/// Unix permissions recorded for every entry
+const FILE_PERMISSIONS: u32 = 0o644;
+
+/// How an entry is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryCompression {
+    /// Deflate at [`COMPRESSION_LEVEL`]
+    Deflated,
+    /// No compression (required for e.g. the EPUB `mimetype` entry)
+    Stored,
+}
+
+/// Fixed timestamp written to every entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZipTimestamp {
+    /// Year (1980-2107)
+    pub year: u16,
+    /// Month (1-12)
+    pub month: u8,
+    /// Day (1-31)
+    pub day: u8,
+}
+
+impl Default for ZipTimestamp {
+    /// The DOS epoch, 1980-01-01 00:00:00
+    fn default() -> Self {
+        Self {
+            year: 1980,
+            month: 1,
+            day: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    data: Vec<u8>,
+    compression: EntryCompression,
+}
+
+/// Builds ZIP archives whose bytes depend only on their contents
+#[derive(Debug, Clone, Default)]
+pub struct DeterministicZipWriter {
+    entries: BTreeMap<String, Entry>,
+    leading: Vec<String>,
+    timestamp: ZipTimestamp,
+}
+
+impl DeterministicZipWriter {
+    /// Create an empty writer
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entries that must come first, in the given order (if present)
+    #[must_use]
+    pub fn with_leading<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.leading = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Use a different fixed timestamp
+    #[must_use]
+    pub fn with_timestamp(mut self, timestamp: ZipTimestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Add a deflated entry, replacing any entry with the same name
+    pub fn add(&mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) {
+        self.add_with(name, data, EntryCompression::Deflated);
+    }
+
+    /// Add an entry with explicit compression
+    pub fn add_with(
+        &mut self,
+        name: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+        compression: EntryCompression,
+    ) {
+        self.entries.insert(
+            name.into(),
+            Entry {
+                data: data.into(),
+                compression,
+            },
+        );
+    }
+
+    /// Number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no entries have been added
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry names in the order they will be written
+    #[must_use]
+    pub fn entry_order(&self) -> Vec<&str> {
+        let leading = self
+            .leading
+            .iter()
+            .filter(|name| self.entries.contains_key(name.as_str()))
+            .map(String::as_str);
+        let rest = self
+            .entries
+            .keys()
+            .filter(|name| !self.leading.contains(name))
+            .map(String::as_str);
+        leading.chain(rest).collect()
+    }
+
+    /// Write the archive
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::RenderError` if the timestamp is invalid or the
+    /// archive cannot be written.
+    pub fn finish(&self) -> Result<Vec<u8>> {
+        let ts = self.timestamp;
+        let modified = DateTime::from_date_and_time(ts.year, ts.month, ts.day, 0, 0, 0)
+            .map_err(|()| {
+                Error::RenderError(format!(
+                    "Invalid ZIP timestamp {}-{:02}-{:02}",
+                    ts.year, ts.month, ts.day
+                ))
+            })?;
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in self.entry_order() {
+            let entry = &self.entries[name];
+            let options = FileOptions::default()
+                .last_modified_time(modified)
+                .unix_permissions(FILE_PERMISSIONS);
+            let options = match entry.compression {
+                EntryCompression::Deflated => options
+                    .compression_method(CompressionMethod::Deflated)
+                    .compression_level(Some(COMPRESSION_LEVEL)),
+                EntryCompression::Stored => options.compression_method(CompressionMethod::Stored),
+            };
+
+            zip.start_file(name, options)
+                .and_then(|()| zip.write_all(&entry.data).map_err(Into::into))
+                .map_err(|e| Error::RenderError(format!("Failed to write {name}: {e}")))?;
+        }
+
+        zip.finish()
+            .map(Cursor::into_inner)
+            .map_err(|e| Error::RenderError(format!("Failed to finish ZIP: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_insertion_order_does_not_matter() {
+        let mut a = DeterministicZipWriter::new();
+        a.add("b.xml", "<b/>");
+        a.add("a.xml", "<a/>");
+
+        let mut b = DeterministicZipWriter::new();
+        b.add("a.xml", "<a/>");
+        b.add("b.xml", "<b/>");
+
+        assert_eq!(a.finish().unwrap(), b.finish().unwrap());
+    }
+
+    #[test]
+    fn test_leading_entries_first() {
+        let mut writer =
+            DeterministicZipWriter::new().with_leading(["mimetype", "missing", "META-INF/x"]);
+        writer.add("OEBPS/content.opf", "<package/>");
+        writer.add("META-INF/x", "x");
+        writer.add_with("mimetype", "application/epub+zip", EntryCompression::Stored);
+
+        assert_eq!(
+            writer.entry_order(),
+            vec!["mimetype", "META-INF/x", "OEBPS/content.opf"]
+        );
+
+        let bytes = writer.finish().unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut first = archive.by_index(0).unwrap();
+        assert_eq!(first.name(), "mimetype");
+        assert_eq!(first.compression(), CompressionMethod::Stored);
+        let mut content = String::new();
+        first.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "application/epub+zip");
+    }
+
+    #[test]
+    fn test_fixed_timestamp() {
+        let mut writer = DeterministicZipWriter::new().with_timestamp(ZipTimestamp {
+            year: 2020,
+            month: 2,
+            day: 29,
+        });
+        writer.add("a.txt", "a");
+
+        let bytes = writer.finish().unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let entry = archive.by_index(0).unwrap();
+        let modified = entry.last_modified();
+        assert_eq!(
+            (modified.year(), modified.month(), modified.day()),
+            (2020, 2, 29)
+        );
+        assert_eq!(entry.unix_mode(), Some(0o100_644));
+    }
+
+    #[test]
+    fn test_invalid_timestamp() {
+        let writer = DeterministicZipWriter::new().with_timestamp(ZipTimestamp {
+            year: 1970,
+            month: 1,
+            day: 1,
+        });
+        assert!(writer.finish().is_err());
+    }
+}
@@ -109,6 +109,15 @@ impl SandboxManager {
     pub fn config(&self) -> &SandboxConfig {
         &self.config
     }
+
+    /// Whether a WASM runtime is compiled into this build.
+    ///
+    /// No runtime backend is linked yet, so parsers currently execute
+    /// in-process and callers should treat sandboxing as unavailable.
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        false
+    }
 }
 
 /// Prism sandbox version
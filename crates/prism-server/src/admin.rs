@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Admin endpoints
+//!
+//! Every admin request must carry `Authorization: Bearer <admin_token>`.
+//! When no token is configured the admin API is disabled entirely.
+
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::config::{ServerConfig, TenantConfig};
+use crate::jobs::{ErrorRecord, JobInfo};
+use crate::reload::ReloadReport;
+use crate::tenants::TenantUsage;
+use crate::{ApiError, AppState};
+
+/// Active conversions
+#[derive(Debug, Serialize)]
+pub struct JobsResponse {
+    /// Number of running conversions
+    pub count: usize,
+    /// Running conversions, oldest first
+    pub jobs: Vec<JobInfo>,
+}
+
+/// Recent conversion failures
+#[derive(Debug, Serialize)]
+pub struct ErrorsResponse {
+    /// Failures, newest first
+    pub errors: Vec<ErrorRecord>,
+}
+
+/// Effective runtime configuration
+#[derive(Debug, Serialize)]
+pub struct ConfigResponse {
+    /// Active runtime generation
+    pub generation: u64,
+    /// When the runtime was loaded
+    pub loaded_at: DateTime<Utc>,
+    /// MIME types with a registered parser
+    pub formats: Vec<String>,
+    /// Server configuration (secrets omitted)
+    pub config: ServerConfig,
+}
+
+/// Sandbox status
+#[derive(Debug, Serialize)]
+pub struct SandboxResponse {
+    /// Whether a sandbox runtime is compiled in
+    pub available: bool,
+    /// Sandbox instances currently alive
+    pub instances: usize,
+    /// Memory limit per instance in bytes
+    pub max_memory: usize,
+    /// Execution time limit in seconds
+    pub max_execution_time_secs: u64,
+    /// Instruction limit per execution
+    pub max_instructions: Option<u64>,
+}
+
+/// Conversion cache status
+#[derive(Debug, Serialize)]
+pub struct CacheResponse {
+    /// Whether a cache is configured
+    pub enabled: bool,
+    /// Number of cached entries
+    pub entries: usize,
+    /// Total size of cached entries in bytes
+    pub size_bytes: u64,
+}
+
+/// A tenant with its usage this month
+#[derive(Debug, Serialize)]
+pub struct TenantResponse {
+    /// Tenant configuration (API keys omitted)
+    #[serde(flatten)]
+    pub tenant: TenantConfig,
+    /// Storage directory of the tenant
+    pub storage_dir: PathBuf,
+    /// Usage this month
+    pub usage: TenantUsage,
+}
+
+/// Result of purging the conversion cache
+#[derive(Debug, Serialize)]
+pub struct PurgeResponse {
+    /// Number of entries removed
+    pub purged: usize,
+}
+
+/// Reject the request unless it carries the configured admin token
+pub fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let runtime = state.runtime.current();
+    let Some(expected) = runtime.config.admin_token.as_deref() else {
+        return Err(ApiError::NotFound("Admin API is disabled".to_string()));
+    };
+
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err(ApiError::Unauthorized("Invalid admin token".to_string())),
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Reload parsers and configuration
+pub async fn reload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadReport>, ApiError> {
+    authorize(&state, &headers)?;
+    info!("Reload requested via admin API");
+
+    state
+        .runtime
+        .reload()
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::InternalServerError(format!("{e:#}")))
+}
+
+/// List running conversions
+pub async fn jobs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<JobsResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    let jobs = state.jobs.active();
+    Ok(Json(JobsResponse {
+        count: jobs.len(),
+        jobs,
+    }))
+}
+
+/// List recent failures with the hashes of their inputs
+pub async fn errors(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ErrorsResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(ErrorsResponse {
+        errors: state.jobs.recent_errors(),
+    }))
+}
+
+/// Show the effective configuration
+pub async fn config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ConfigResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    let runtime = state.runtime.current();
+    Ok(Json(ConfigResponse {
+        generation: runtime.generation,
+        loaded_at: runtime.loaded_at,
+        formats: runtime.formats.clone(),
+        config: runtime.config.clone(),
+    }))
+}
+
+/// Show sandbox status
+pub async fn sandbox(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SandboxResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    let config = state.sandbox.config();
+    Ok(Json(SandboxResponse {
+        available: state.sandbox.is_available(),
+        // Parsers run in-process until a sandbox runtime is linked
+        instances: 0,
+        max_memory: config.max_memory,
+        max_execution_time_secs: config.max_execution_time.as_secs(),
+        max_instructions: config.max_instructions,
+    }))
+}
+
+/// Show conversion cache status
+pub async fn cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<CacheResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    let runtime = state.runtime.current();
+    let stats = runtime
+        .cache
+        .as_ref()
+        .map(|cache| cache.stats())
+        .unwrap_or_default();
+    Ok(Json(CacheResponse {
+        enabled: runtime.cache.is_some(),
+        entries: stats.entries,
+        size_bytes: stats.size_bytes,
+    }))
+}
+
+/// List tenants with their usage this month
+pub async fn tenants(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TenantResponse>>, ApiError> {
+    authorize(&state, &headers)?;
+    let runtime = state.runtime.current();
+    let tenants = runtime
+        .config
+        .tenants
+        .iter()
+        .map(|tenant| {
+            let storage_dir = runtime.config.tenant_dir(tenant);
+            TenantResponse {
+                usage: state.usage.usage(tenant, &storage_dir),
+                tenant: tenant.clone(),
+                storage_dir,
+            }
+        })
+        .collect();
+    Ok(Json(tenants))
+}
+
+/// Empty the conversion cache
+pub async fn purge_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<PurgeResponse>, ApiError> {
+    authorize(&state, &headers)?;
+    let runtime = state.runtime.current();
+    let Some(cache) = &runtime.cache else {
+        return Err(ApiError::NotFound(
+            "No conversion cache is configured".to_string(),
+        ));
+    };
+    let purged = cache.purge();
+    info!("Purged {} conversion cache entries via admin API", purged);
+    Ok(Json(PurgeResponse { purged }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Analyze endpoint
+//!
+//! Parses a document without rendering it and returns its
+//! [`DocumentStatistics`], so that ingestion pipelines can triage files
+//! cheaply. `GET /api/analyze?url=...` analyzes a remote document under the
+//! URL fetch policy; `POST /api/analyze` takes a multipart upload like
+//! `/api/convert`.
+
+use axum::{
+    extract::{rejection::QueryRejection, Multipart, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use bytes::Bytes;
+use prism_core::statistics::DocumentStatistics;
+use prism_core::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use crate::config::TenantConfig;
+use crate::convert::{check_tenant_limits, extract_file, fetch_url};
+use crate::preflight::Preflight;
+use crate::reload::Runtime;
+use crate::{tenants, ApiError, AppState};
+
+/// Query of `GET /api/analyze`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnalyzeQuery {
+    /// HTTP(S) URL of the document
+    pub url: String,
+}
+
+/// Statistics of an analyzed document
+#[derive(Debug, Serialize)]
+pub struct AnalyzeResponse {
+    /// Original filename, if known
+    pub filename: Option<String>,
+    /// MIME type of the detected format
+    pub format: String,
+    /// Source size in bytes
+    pub size: usize,
+    /// SHA-256 of the source
+    pub hash: Option<String>,
+    /// Content counts
+    #[serde(flatten)]
+    pub statistics: DocumentStatistics,
+}
+
+/// Analyze the document at the `url` query parameter
+pub async fn analyze_url(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Result<Query<AnalyzeQuery>, QueryRejection>,
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    let runtime = state.runtime.current();
+    let Query(query) = query.map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+    let tenant = tenants::authenticate(&runtime.config, &headers)?;
+
+    // A tenant out of quota is turned away before anything is fetched
+    if let Some(tenant) = tenant {
+        state
+            .usage
+            .check_quota(tenant, &runtime.config.tenant_dir(tenant))?;
+    }
+    let preflight = Preflight::new(&runtime.config, tenant);
+    let (filename, data) = fetch_url(&runtime, preflight, &query.url).await?;
+    preflight.check_magic_bytes(&data, filename.as_deref())?;
+    analyze_counted(&state, &runtime, tenant, filename, data)
+        .await
+        .map(Json)
+}
+
+/// Analyze the document in the `file` field of a multipart upload
+pub async fn analyze_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    let runtime = state.runtime.current();
+    let tenant = tenants::authenticate(&runtime.config, &headers)?;
+    let preflight = Preflight::new(&runtime.config, tenant);
+    preflight.check_content_length(&headers)?;
+
+    let (filename, data, _) = extract_file(&mut multipart, preflight).await?;
+    analyze_counted(&state, &runtime, tenant, filename, data)
+        .await
+        .map(Json)
+}
+
+/// Analyze `data` for `tenant`, checking its limits and counting the
+/// analysis against its quota like a conversion
+async fn analyze_counted(
+    state: &AppState,
+    runtime: &Runtime,
+    tenant: Option<&TenantConfig>,
+    filename: Option<String>,
+    data: Vec<u8>,
+) -> Result<AnalyzeResponse, ApiError> {
+    if let Some(tenant) = tenant {
+        check_tenant_limits(runtime, tenant, filename.as_deref(), &data, None)?;
+        state
+            .usage
+            .admit(tenant, &runtime.config.tenant_dir(tenant), data.len())?;
+    }
+    let result = analyze_data(runtime, filename, data).await;
+    if let (Err(_), Some(tenant)) = (&result, tenant) {
+        state
+            .usage
+            .record_failure(tenant, &runtime.config.tenant_dir(tenant));
+    }
+    result
+}
+
+/// Parse `data` with the runtime's parsers and processors, skipping the
+/// renderer
+async fn analyze_data(
+    runtime: &Runtime,
+    filename: Option<String>,
+    data: Vec<u8>,
+) -> Result<AnalyzeResponse, ApiError> {
+    let size = data.len();
+    info!("Analyzing file: {:?}, size: {} bytes", filename, size);
+
+    let pipeline = runtime.pipeline.clone().without_renderer();
+    let output = match pipeline.run(Bytes::from(data), filename.as_deref()).await {
+        Ok(output) => output,
+        Err(Error::DetectionFailed(_)) => {
+            return Err(ApiError::UnsupportedMediaType(
+                "Unable to detect file format".to_string(),
+            ));
+        }
+        Err(Error::UnsupportedFormat(name)) => {
+            return Err(ApiError::NotImplemented(format!(
+                "No parser available for format: {name}"
+            )));
+        }
+        Err(e) => {
+            error!("Analysis error ({}): {}", e.code(), e);
+            return Err(ApiError::Document(e));
+        }
+    };
+    debug!(
+        "Analyzed {} with {} page(s)",
+        output.detection.format.mime_type,
+        output.document.page_count()
+    );
+
+    Ok(AnalyzeResponse {
+        filename,
+        format: output.detection.format.mime_type,
+        size,
+        hash: output.document.source.hash.clone(),
+        statistics: output.document.statistics(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use prism_core::license::LicenseManager;
+
+    #[tokio::test]
+    async fn test_analyze_data() {
+        let runtime = Runtime::build(ServerConfig::default(), 0);
+        let response = analyze_data(
+            &runtime,
+            Some("notes.txt".to_string()),
+            b"Three short words\nand four more here".to_vec(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.format, "text/plain");
+        assert_eq!(response.statistics.pages, 1);
+        assert_eq!(response.statistics.words, 7);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["filename"], "notes.txt");
+        assert_eq!(json["words"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_analyses_count_against_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: ServerConfig = serde_json::from_value(serde_json::json!({
+            "temp_dir": dir.path(),
+            "tenants": [{ "id": "legal", "api_keys": ["secret"], "monthly_quota": 1 }]
+        }))
+        .unwrap();
+        let state = AppState::new(LicenseManager::default(), config);
+        let runtime = state.runtime.current();
+        let tenant = &runtime.config.tenants[0];
+        let dir = runtime.config.tenant_dir(tenant);
+        let analyze = || {
+            let name = Some("notes.txt".to_string());
+            analyze_counted(
+                &state,
+                &runtime,
+                Some(tenant),
+                name,
+                b"Three words".to_vec(),
+            )
+        };
+
+        analyze().await.unwrap();
+        assert!(matches!(analyze().await, Err(ApiError::TooManyRequests(_))));
+        assert_eq!(state.usage.usage(tenant, &dir).conversions, 1);
+        assert!(state.usage.check_quota(tenant, &dir).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Downloads of converted outputs through signed URLs
+//!
+//! With `artifacts` configured, every conversion keeps a copy of its
+//! output under the job ID and returns a download URL for it in the
+//! `x-prism-artifact-url` header:
+//!
+//! ```text
+//! GET /api/artifacts/:id?exp=<unix seconds>&sig=<hex HMAC-SHA256>[&tenant=<id>]
+//! ```
+//!
+//! The signature covers the job ID, the expiry time and the tenant, so the
+//! URL can be handed to a browser or another system instead of an API
+//! key: it grants access to that one output until `url_ttl_seconds` have
+//! passed, and to nothing else. Outputs are kept for `retention_seconds`
+//! in the configured directory, or in memory, which starts out empty after
+//! every reload. Each tenant has a store of its own, under its storage
+//! directory and with its own size budget, so one tenant's outputs neither
+//! sit beside another's nor evict them. Changing the signing key
+//! invalidates every URL handed out.
+
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::{header, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use prism_core::cache::{CacheKey, CachedOutput, ConversionCache};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config::{ArtifactConfig, ServerConfig};
+use crate::{ApiError, AppState};
+
+/// Response header carrying the signed download URL of the output
+pub const ARTIFACT_URL_HEADER: &str = "x-prism-artifact-url";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Query of a download URL
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    /// When the URL expires, in seconds since the Unix epoch
+    pub exp: i64,
+    /// HMAC-SHA256 of the job ID, expiry and tenant, in hex
+    pub sig: String,
+    /// Tenant whose store holds the output
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// Why a download URL is refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    /// The signature does not match the job ID and expiry
+    #[error("Invalid download URL signature")]
+    Invalid,
+    /// The URL is past its expiry time
+    #[error("Download URL has expired")]
+    Expired,
+}
+
+/// Signs and verifies download URLs
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for UrlSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlSigner").finish_non_exhaustive()
+    }
+}
+
+impl UrlSigner {
+    /// Signer using `key` as the HMAC secret
+    #[must_use]
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    fn mac(&self, id: &Uuid, tenant: Option<&str>, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(format!("{id}\n{expires}").as_bytes());
+        if let Some(tenant) = tenant {
+            mac.update(format!("\n{tenant}").as_bytes());
+        }
+        mac
+    }
+
+    /// Signature of job `id` of `tenant` expiring at `expires`, in hex
+    #[must_use]
+    pub fn sign(&self, id: &Uuid, tenant: Option<&str>, expires: i64) -> String {
+        let tag = self.mac(id, tenant, expires).finalize().into_bytes();
+        tag.iter().fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+    }
+
+    /// Check `signature` for job `id` of `tenant` expiring at `expires`,
+    /// at `now`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SignatureError::Invalid`] if the signature does not match,
+    /// compared in constant time, and [`SignatureError::Expired`] if it
+    /// does but `expires` has passed.
+    pub fn verify(
+        &self,
+        id: &Uuid,
+        tenant: Option<&str>,
+        expires: i64,
+        signature: &str,
+        now: i64,
+    ) -> Result<(), SignatureError> {
+        let tag = decode_hex(signature).ok_or(SignatureError::Invalid)?;
+        self.mac(id, tenant, expires)
+            .verify_slice(&tag)
+            .map_err(|_| SignatureError::Invalid)?;
+        if now >= expires {
+            return Err(SignatureError::Expired);
+        }
+        Ok(())
+    }
+}
+
+/// Kept outputs and the signer of their download URLs
+#[derive(Debug)]
+pub struct Artifacts {
+    /// Outputs of conversions made without a tenant
+    store: Arc<dyn ConversionCache>,
+    /// Outputs of each tenant, by tenant ID
+    tenant_stores: HashMap<String, Arc<dyn ConversionCache>>,
+    signer: UrlSigner,
+    url_ttl: i64,
+    public_url: String,
+}
+
+impl Artifacts {
+    /// Open the stores configured by `config`, one for each tenant of
+    /// `server` and one for conversions without a tenant
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no signing key is set or a store cannot be
+    /// created.
+    pub fn from_config(config: &ArtifactConfig, server: &ServerConfig) -> anyhow::Result<Self> {
+        if config.signing_key.is_empty() {
+            anyhow::bail!("no signing key configured");
+        }
+        let mut tenant_stores = HashMap::new();
+        for tenant in &server.tenants {
+            let store = config.build_store(server.tenant_artifacts_dir(tenant))?;
+            tenant_stores.insert(tenant.id.clone(), store);
+        }
+        Ok(Self {
+            store: config.build_store(config.dir.clone())?,
+            tenant_stores,
+            signer: UrlSigner::new(config.signing_key.as_bytes()),
+            url_ttl: i64::try_from(config.url_ttl_seconds).unwrap_or(i64::MAX),
+            public_url: config
+                .public_url
+                .as_deref()
+                .unwrap_or_default()
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+
+    /// Download URL of job `id` of `tenant`, valid from `now` for the
+    /// configured time
+    #[must_use]
+    pub fn url(&self, id: &Uuid, tenant: Option<&str>, now: i64) -> String {
+        let expires = now.saturating_add(self.url_ttl);
+        let mut url = format!(
+            "{}/api/artifacts/{id}?exp={expires}&sig={}",
+            self.public_url,
+            self.signer.sign(id, tenant, expires)
+        );
+        if let Some(tenant) = tenant {
+            url.push_str("&tenant=");
+            url.push_str(&encode_query(tenant));
+        }
+        url
+    }
+
+    /// The store of `tenant`, or of conversions without one
+    fn store(&self, tenant: Option<&str>) -> Option<&Arc<dyn ConversionCache>> {
+        match tenant {
+            Some(tenant) => self.tenant_stores.get(tenant),
+            None => Some(&self.store),
+        }
+    }
+
+    /// Keep `output` as the artifact of job `id` of `tenant`
+    pub fn put(&self, id: &Uuid, tenant: Option<&str>, output: CachedOutput) {
+        if let Some(store) = self.store(tenant) {
+            store.put(&artifact_key(id), output);
+        }
+    }
+
+    /// The artifact of job `id` of `tenant`, unless it is missing or past
+    /// retention
+    #[must_use]
+    pub fn get(&self, id: &Uuid, tenant: Option<&str>) -> Option<CachedOutput> {
+        self.store(tenant)?.get(&artifact_key(id))
+    }
+
+    /// Keep the body of a conversion `response` as the artifact of job
+    /// `id` of `tenant` and add its download URL to the headers
+    ///
+    /// # Errors
+    ///
+    /// Returns an internal error if the body cannot be read.
+    pub async fn keep(
+        &self,
+        id: &Uuid,
+        tenant: Option<&str>,
+        response: Response,
+    ) -> Result<Response, ApiError> {
+        let (mut parts, body) = response.into_parts();
+        let data = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        let mut output = CachedOutput::new(data.clone());
+        if let Some(content_type) = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        {
+            output = output.with_attribute(header::CONTENT_TYPE.as_str(), content_type);
+        }
+        self.put(id, tenant, output);
+        debug!("Kept output of job {} for download", id);
+
+        let url = HeaderValue::try_from(self.url(id, tenant, Utc::now().timestamp()))
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        parts
+            .headers
+            .insert(HeaderName::from_static(ARTIFACT_URL_HEADER), url);
+        Ok(Response::from_parts(parts, data.into()))
+    }
+}
+
+/// Download the artifact of job `id` through a signed URL
+///
+/// # Errors
+///
+/// Returns forbidden if the URL is not signed by this server or has
+/// expired, and not found if the artifact is no longer kept.
+pub async fn download(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    query: Result<Query<DownloadQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let runtime = state.runtime.current();
+    let artifacts = runtime
+        .artifacts
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Artifact downloads are disabled".to_string()))?;
+    let Query(query) = query.map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+    let now = Utc::now().timestamp();
+    let tenant = query.tenant.as_deref();
+    artifacts
+        .signer
+        .verify(&id, tenant, query.exp, &query.sig, now)
+        .map_err(|e| ApiError::Forbidden(e.to_string()))?;
+    let output = artifacts
+        .get(&id, tenant)
+        .ok_or_else(|| ApiError::NotFound(format!("No artifact for job {id}")))?;
+
+    let content_type = output
+        .attributes
+        .get(header::CONTENT_TYPE.as_str())
+        .cloned()
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    // Shared caches must not serve one client's URL to another
+    let cache_control = format!("private, max-age={}", query.exp - now);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        output.data,
+    )
+        .into_response())
+}
+
+/// Store key of the artifact of job `id`
+fn artifact_key(id: &Uuid) -> CacheKey {
+    CacheKey::new(&id.to_string(), "artifact", &())
+}
+
+/// `value` percent-encoded for a URL query, leaving unreserved characters
+fn encode_query(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut encoded, byte| {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+        encoded
+    })
+}
+
+/// Bytes of a hex string, or `None` if it is not one
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_signer() {
+        let signer = UrlSigner::new("secret");
+        let id = Uuid::new_v4();
+        let signature = signer.sign(&id, None, 1_000);
+        assert_eq!(signature.len(), 64);
+        assert_eq!(signer.verify(&id, None, 1_000, &signature, 999), Ok(()));
+        assert_eq!(
+            signer.verify(&id, None, 1_000, &signature, 1_000),
+            Err(SignatureError::Expired)
+        );
+
+        // A later expiry, another job, another key or a malformed signature
+        let invalid = Err(SignatureError::Invalid);
+        assert_eq!(signer.verify(&id, None, 2_000, &signature, 999), invalid);
+        assert_eq!(
+            signer.verify(&Uuid::new_v4(), None, 1_000, &signature, 999),
+            invalid
+        );
+        assert_eq!(
+            UrlSigner::new("other").verify(&id, None, 1_000, &signature, 999),
+            invalid
+        );
+        assert_eq!(signer.verify(&id, None, 1_000, "zz", 999), invalid);
+        assert!(!format!("{signer:?}").contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_artifacts() {
+        let server = ServerConfig::default();
+        assert!(Artifacts::from_config(&ArtifactConfig::default(), &server).is_err());
+        let artifacts = Artifacts::from_config(
+            &ArtifactConfig {
+                signing_key: "secret".to_string(),
+                public_url: Some("https://prism.example.com/".to_string()),
+                ..ArtifactConfig::default()
+            },
+            &server,
+        )
+        .unwrap();
+
+        let id = Uuid::new_v4();
+        let response = ([(header::CONTENT_TYPE, "text/html")], "<p>Hi</p>").into_response();
+        let response = artifacts.keep(&id, None, response).await.unwrap();
+        let url = response.headers()[ARTIFACT_URL_HEADER].to_str().unwrap();
+        assert!(url.starts_with(&format!(
+            "https://prism.example.com/api/artifacts/{id}?exp="
+        )));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"<p>Hi</p>");
+
+        let kept = artifacts.get(&id, None).unwrap();
+        assert_eq!(&kept.data[..], b"<p>Hi</p>");
+        assert_eq!(kept.attributes["content-type"], "text/html");
+        assert!(artifacts.get(&Uuid::new_v4(), None).is_none());
+    }
+
+    #[test]
+    fn test_tenant_stores() {
+        let dir = tempfile::tempdir().unwrap();
+        let server: ServerConfig = serde_json::from_value(serde_json::json!({
+            "temp_dir": dir.path(),
+            "tenants": [
+                {"id": "legal", "api_keys": ["k1"]},
+                {"id": "hr & co", "api_keys": ["k2"]}
+            ],
+            "artifacts": {"signing_key": "secret", "dir": dir.path().join("shared"), "max_bytes": 10}
+        }))
+        .unwrap();
+        let artifacts =
+            Artifacts::from_config(server.artifacts.as_ref().unwrap(), &server).unwrap();
+
+        // The same job ID in two tenants is two artifacts
+        let id = Uuid::new_v4();
+        artifacts.put(&id, Some("legal"), CachedOutput::new("legal-1"));
+        artifacts.put(&id, Some("hr & co"), CachedOutput::new("hr-1"));
+        assert_eq!(
+            &artifacts.get(&id, Some("legal")).unwrap().data[..],
+            b"legal-1"
+        );
+        assert_eq!(
+            &artifacts.get(&id, Some("hr & co")).unwrap().data[..],
+            b"hr-1"
+        );
+        assert!(artifacts.get(&id, None).is_none());
+        assert!(artifacts.get(&id, Some("other")).is_none());
+        assert!(dir.path().join("legal").join("artifacts").is_dir());
+
+        // Filling one tenant's budget leaves the other's outputs alone
+        artifacts.put(&Uuid::new_v4(), Some("legal"), CachedOutput::new("legal-2"));
+        assert!(artifacts.get(&id, Some("legal")).is_none());
+        assert!(artifacts.get(&id, Some("hr & co")).is_some());
+
+        // A URL signed for one tenant opens nothing in another
+        let url = artifacts.url(&id, Some("hr & co"), 0);
+        assert!(url.ends_with("&tenant=hr%20%26%20co"));
+        let signature = artifacts.signer.sign(&id, Some("hr & co"), 10);
+        let signer = &artifacts.signer;
+        assert_eq!(
+            signer.verify(&id, Some("hr & co"), 10, &signature, 0),
+            Ok(())
+        );
+        assert_eq!(
+            signer.verify(&id, Some("legal"), 10, &signature, 0),
+            Err(SignatureError::Invalid)
+        );
+        assert_eq!(
+            signer.verify(&id, None, 10, &signature, 0),
+            Err(SignatureError::Invalid)
+        );
+    }
+}
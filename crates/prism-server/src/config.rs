@@ -1,10 +1,24 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Server configuration
 
+use anyhow::Context;
+use prism_core::cache::{CacheLimits, ConversionCache, DiskCache, MemoryCache};
+use prism_core::fetch::FetchPolicy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Environment variable pointing at a JSON configuration file
+pub const CONFIG_FILE_ENV: &str = "PRISM_CONFIG";
 
 /// Server configuration
+///
+/// Loaded from the JSON file named by `PRISM_CONFIG`; missing fields fall
+/// back to their defaults. The file is re-read on reload.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ServerConfig {
     /// Maximum file size in bytes (default: 5GB)
     pub max_file_size: usize,
@@ -14,6 +28,289 @@ pub struct ServerConfig {
 
     /// Whether to enable fallback mode for unsupported formats
     pub enable_fallback: bool,
+
+    /// Formats that must not be parsed, by MIME type or extension
+    pub disabled_formats: Vec<String>,
+
+    /// Bearer token for the admin endpoints (admin API disabled if unset)
+    #[serde(skip_serializing)]
+    pub admin_token: Option<String>,
+
+    /// Derive document IDs from the content hash instead of generating
+    /// random ones, so re-uploads of the same file get the same ID
+    pub deterministic_ids: bool,
+
+    /// Convert damaged files as far as possible instead of rejecting them;
+    /// the problems are reported in the diagnostics response header
+    pub lenient_parsing: bool,
+
+    /// Reject files with macros, scripts, embedded executables or external
+    /// references instead of converting them
+    pub reject_active_content: bool,
+
+    /// Strip macros, scripts, external links and embedded executables
+    /// while converting; the removed items are reported in the disarmed
+    /// response header
+    pub disarm: bool,
+
+    /// Serve repeated conversions of the same file from a cache (no cache
+    /// if unset)
+    pub cache: Option<CacheConfig>,
+
+    /// Teams sharing this server; once any are configured, conversions
+    /// require one of a tenant's API keys
+    pub tenants: Vec<TenantConfig>,
+
+    /// Directory holding a subdirectory per tenant (default: `prism` in
+    /// the system temp directory)
+    pub temp_dir: Option<PathBuf>,
+
+    /// Let clients convert documents by URL (disabled if unset)
+    pub url_fetch: Option<FetchConfig>,
+
+    /// Seconds in-flight conversions have to finish after SIGTERM before
+    /// the server exits anyway (default: 30)
+    pub shutdown_timeout_seconds: u64,
+
+    /// Checks made on uploads before their body is read
+    pub preflight: PreflightConfig,
+
+    /// Keep converted outputs for download through signed URLs (disabled
+    /// if unset)
+    pub artifacts: Option<ArtifactConfig>,
+}
+
+/// Checks made on uploads before their body is read, each enabled by
+/// default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreflightConfig {
+    /// Reject requests whose `Content-Length` exceeds the size limit, and
+    /// stop reading uploads as soon as they do
+    pub content_length: bool,
+
+    /// Reject file parts whose `Content-Type` is not an allowed format
+    pub declared_type: bool,
+
+    /// Reject files whose first bytes are detected as a format that is
+    /// not allowed
+    pub magic_bytes: bool,
+
+    /// Formats accepted, by MIME type or extension (every format if empty)
+    pub allowed_formats: Vec<String>,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            content_length: true,
+            declared_type: true,
+            magic_bytes: true,
+            allowed_formats: Vec::new(),
+        }
+    }
+}
+
+/// Storage of converted outputs, downloaded through signed, expiring URLs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtifactConfig {
+    /// Secret the download URLs are signed with; outputs are not kept
+    /// while it is empty
+    #[serde(skip_serializing)]
+    pub signing_key: String,
+
+    /// Directory of the outputs, shared across restarts; outputs are kept
+    /// in memory if unset. Each tenant keeps its outputs in the `artifacts`
+    /// subdirectory of its own storage directory instead.
+    pub dir: Option<PathBuf>,
+
+    /// Total size of the kept outputs in bytes, for each tenant on its own
+    /// when tenants are configured (default: 1GB)
+    pub max_bytes: u64,
+
+    /// Seconds an output is kept after its conversion (default: 86400)
+    pub retention_seconds: u64,
+
+    /// Seconds a download URL stays valid (default: 3600)
+    pub url_ttl_seconds: u64,
+
+    /// Base the download URLs start with, e.g. `https://prism.example.com`
+    /// (relative URLs if unset)
+    pub public_url: Option<String>,
+}
+
+impl Default for ArtifactConfig {
+    fn default() -> Self {
+        Self {
+            signing_key: String::new(),
+            dir: None,
+            max_bytes: 1024 * 1024 * 1024,
+            retention_seconds: 24 * 60 * 60,
+            url_ttl_seconds: 60 * 60,
+            public_url: None,
+        }
+    }
+}
+
+/// Subdirectory of a tenant's storage directory holding its outputs
+pub const TENANT_ARTIFACTS_DIR: &str = "artifacts";
+
+impl ArtifactConfig {
+    /// Open the store the outputs are kept in, in `dir` or in memory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn build_store(&self, dir: Option<PathBuf>) -> anyhow::Result<Arc<dyn ConversionCache>> {
+        CacheConfig {
+            dir,
+            max_bytes: self.max_bytes,
+            ttl_seconds: Some(self.retention_seconds),
+        }
+        .build()
+        .context("Failed to create artifact store")
+    }
+}
+
+/// Limits on documents the server downloads for its clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FetchConfig {
+    /// Hosts documents may be fetched from, including their subdomains
+    /// (every public host if empty)
+    pub allowed_hosts: Vec<String>,
+
+    /// Largest document downloaded, in bytes, within the size limit of
+    /// the request (default: that limit)
+    pub max_size: Option<usize>,
+
+    /// Redirects followed before giving up (default: 5)
+    pub max_redirects: usize,
+
+    /// Seconds a download may take, redirects included (default: 30)
+    pub timeout_seconds: u64,
+
+    /// Allow hosts on loopback, private and link-local addresses
+    pub allow_private_hosts: bool,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        let policy = FetchPolicy::default();
+        Self {
+            allowed_hosts: Vec::new(),
+            max_size: None,
+            max_redirects: policy.max_redirects,
+            timeout_seconds: policy.timeout.as_secs(),
+            allow_private_hosts: false,
+        }
+    }
+}
+
+impl FetchConfig {
+    /// Download policy for a request accepting files of up to
+    /// `max_file_size` bytes
+    #[must_use]
+    pub fn policy(&self, max_file_size: usize) -> FetchPolicy {
+        FetchPolicy {
+            allowed_hosts: self.allowed_hosts.clone(),
+            max_size: self
+                .max_size
+                .map_or(max_file_size, |max| max.min(max_file_size)),
+            max_redirects: self.max_redirects,
+            timeout: Duration::from_secs(self.timeout_seconds),
+            allow_private_hosts: self.allow_private_hosts,
+        }
+    }
+}
+
+/// A team using the server, with its own keys, limits and storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Tenant name, used in logs, job listings and usage reports
+    pub id: String,
+
+    /// Keys identifying the tenant, sent as `Authorization: Bearer <key>`
+    /// or in the `x-api-key` header
+    #[serde(default, skip_serializing)]
+    pub api_keys: Vec<String>,
+
+    /// Maximum file size in bytes, within the server's own limit
+    #[serde(default)]
+    pub max_file_size: Option<usize>,
+
+    /// Formats the tenant may convert, by MIME type or extension (every
+    /// format if empty)
+    #[serde(default)]
+    pub allowed_formats: Vec<String>,
+
+    /// Conversions allowed per calendar month (UTC), unlimited if unset
+    #[serde(default)]
+    pub monthly_quota: Option<u64>,
+
+    /// Storage directory of the tenant (default: a subdirectory named
+    /// after the tenant in the server's `temp_dir`)
+    #[serde(default)]
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl TenantConfig {
+    /// Whether the tenant may convert a format
+    pub fn allows_format(&self, mime_type: &str, extension: &str) -> bool {
+        self.allowed_formats.is_empty()
+            || self.allowed_formats.iter().any(|entry| {
+                let entry = entry.trim_start_matches('.');
+                entry.eq_ignore_ascii_case(mime_type) || entry.eq_ignore_ascii_case(extension)
+            })
+    }
+}
+
+/// Conversion cache settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Directory of a cache shared across restarts; entries are kept in
+    /// memory if unset
+    pub dir: Option<PathBuf>,
+
+    /// Total size of the cached outputs in bytes (default: 256MB)
+    pub max_bytes: u64,
+
+    /// Seconds an entry is served after it was stored (kept until evicted
+    /// if unset)
+    pub ttl_seconds: Option<u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_bytes: CacheLimits::default().max_bytes,
+            ttl_seconds: None,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Open the configured cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be created.
+    pub fn build(&self) -> anyhow::Result<Arc<dyn ConversionCache>> {
+        let limits = CacheLimits {
+            max_bytes: self.max_bytes,
+            ttl: self.ttl_seconds.map(Duration::from_secs),
+        };
+        Ok(match &self.dir {
+            Some(dir) => Arc::new(
+                DiskCache::new(dir, limits)
+                    .with_context(|| format!("Failed to create cache {}", dir.display()))?,
+            ),
+            None => Arc::new(MemoryCache::new(limits)),
+        })
+    }
 }
 
 impl Default for ServerConfig {
@@ -22,10 +319,117 @@ impl Default for ServerConfig {
             max_file_size: 5 * 1024 * 1024 * 1024, // 5GB
             timeout_seconds: 300, // 5 minutes for large files
             enable_fallback: true,
+            disabled_formats: Vec::new(),
+            admin_token: None,
+            deterministic_ids: false,
+            lenient_parsing: false,
+            reject_active_content: false,
+            disarm: false,
+            cache: None,
+            tenants: Vec::new(),
+            temp_dir: None,
+            url_fetch: None,
+            shutdown_timeout_seconds: 30,
+            preflight: PreflightConfig::default(),
+            artifacts: None,
         }
     }
 }
 
+impl ServerConfig {
+    /// Load the configuration named by `PRISM_CONFIG`, or the defaults
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not valid JSON.
+    pub fn load() -> anyhow::Result<Self> {
+        match std::env::var_os(CONFIG_FILE_ENV) {
+            Some(path) => Self::from_file(Path::new(&path)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from a JSON file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not valid JSON or
+    /// names a tenant that cannot be a directory name.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config: Self = serde_json::from_slice(&data)
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Check what the types alone cannot
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tenant ID would not stay inside `temp_dir`
+    /// when joined to it, if two tenants share an ID, or if an API key is
+    /// given more than once.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut ids = HashSet::new();
+        let mut keys = HashSet::new();
+        for tenant in &self.tenants {
+            let id = tenant.id.as_str();
+            if id.is_empty()
+                || id == "."
+                || id.contains("..")
+                || id.contains(['/', '\\', '\0'])
+                || Path::new(id).is_absolute()
+            {
+                anyhow::bail!("Invalid tenant ID {id:?}: it must be a plain directory name");
+            }
+            if !ids.insert(id) {
+                anyhow::bail!("Tenant ID {id:?} is used more than once");
+            }
+            // Keys are not echoed, as they are secrets
+            if !tenant.api_keys.iter().all(|key| keys.insert(key.as_str())) {
+                anyhow::bail!("Tenant {id:?} has an API key already given to a tenant");
+            }
+        }
+        Ok(())
+    }
+
+    /// Directory holding the tenant subdirectories
+    pub fn temp_root(&self) -> PathBuf {
+        self.temp_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("prism"))
+    }
+
+    /// Storage directory of `tenant`
+    pub fn tenant_dir(&self, tenant: &TenantConfig) -> PathBuf {
+        tenant
+            .temp_dir
+            .clone()
+            .unwrap_or_else(|| self.temp_root().join(&tenant.id))
+    }
+
+    /// Directory of the kept outputs of `tenant`, if they are kept on disk
+    pub fn tenant_artifacts_dir(&self, tenant: &TenantConfig) -> Option<PathBuf> {
+        let artifacts = self.artifacts.as_ref()?;
+        artifacts
+            .dir
+            .as_ref()
+            .map(|_| self.tenant_dir(tenant).join(TENANT_ARTIFACTS_DIR))
+    }
+
+    /// Whether parsing of a format is disabled by policy
+    pub fn is_format_disabled(&self, mime_type: &str, extension: &str) -> bool {
+        self.disabled_formats.iter().any(|entry| {
+            let entry = entry.trim_start_matches('.');
+            entry.eq_ignore_ascii_case(mime_type) || entry.eq_ignore_ascii_case(extension)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,8 +437,142 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = ServerConfig::default();
-        assert_eq!(config.max_file_size, 50 * 1024 * 1024);
-        assert_eq!(config.timeout_seconds, 30);
+        assert_eq!(config.max_file_size, 5 * 1024 * 1024 * 1024);
+        assert_eq!(config.timeout_seconds, 300);
         assert!(config.enable_fallback);
+        assert!(config.admin_token.is_none());
+        assert!(!config.deterministic_ids);
+        assert_eq!(config.shutdown_timeout_seconds, 30);
+    }
+
+    #[test]
+    fn test_partial_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prism.json");
+        std::fs::write(&path, r#"{"enable_fallback": false, "disabled_formats": [".doc"]}"#)
+            .unwrap();
+
+        let config = ServerConfig::from_file(&path).unwrap();
+        assert!(!config.enable_fallback);
+        assert_eq!(config.timeout_seconds, 300);
+        assert!(config.is_format_disabled("application/msword", "doc"));
+        assert!(!config.is_format_disabled("application/pdf", "pdf"));
+        assert!(config.cache.is_none());
+        assert!(config.tenants.is_empty());
+        assert!(config.url_fetch.is_none());
+        assert!(config.preflight.magic_bytes);
+        assert!(config.artifacts.is_none());
+    }
+
+    #[test]
+    fn test_tenant_ids_stay_in_temp_dir() {
+        let tenant = |id: &str| -> ServerConfig {
+            serde_json::from_value(serde_json::json!({
+                "tenants": [{"id": id, "api_keys": ["k1"]}]
+            }))
+            .unwrap()
+        };
+        assert!(tenant("legal-eu_2").validate().is_ok());
+        for id in ["", ".", "..", "../x", "a/b", "a\\b", "/etc", "x..y"] {
+            assert!(tenant(id).validate().is_err(), "{id}");
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prism.json");
+        std::fs::write(
+            &path,
+            r#"{"tenants": [{"id": "../x", "api_keys": ["k1"]}]}"#,
+        )
+        .unwrap();
+        assert!(ServerConfig::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_tenant_ids() {
+        let config: ServerConfig = serde_json::from_value(serde_json::json!({
+            "tenants": [
+                {"id": "legal", "api_keys": ["k1"]},
+                {"id": "legal", "api_keys": ["k2"]}
+            ]
+        }))
+        .unwrap();
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("used more than once"), "{error}");
+    }
+
+    #[test]
+    fn test_shared_api_keys() {
+        let config = |keys: [&[&str]; 2]| -> ServerConfig {
+            serde_json::from_value(serde_json::json!({
+                "tenants": [
+                    {"id": "legal", "api_keys": keys[0]},
+                    {"id": "hr", "api_keys": keys[1]}
+                ]
+            }))
+            .unwrap()
+        };
+        assert!(config([&["k1"], &["k2", "k3"]]).validate().is_ok());
+        let error = config([&["k1"], &["k2", "k1"]]).validate().unwrap_err();
+        assert!(!error.to_string().contains("k1"), "{error}");
+        // A key repeated within one tenant is a mistake too
+        assert!(config([&["k1", "k1"], &["k2"]]).validate().is_err());
+    }
+
+    #[test]
+    fn test_tenant_config() {
+        let config: ServerConfig = serde_json::from_str(
+            r#"{
+                "temp_dir": "/var/lib/prism",
+                "tenants": [
+                    {"id": "legal", "api_keys": ["k1"], "allowed_formats": ["pdf", "text/plain"]},
+                    {"id": "hr", "api_keys": ["k2"], "temp_dir": "/srv/hr"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let legal = &config.tenants[0];
+        assert!(legal.allows_format("application/pdf", "pdf"));
+        assert!(legal.allows_format("text/plain", "txt"));
+        assert!(!legal.allows_format("application/msword", "doc"));
+        assert!(config.tenants[1].allows_format("application/msword", "doc"));
+        assert_eq!(
+            config.tenant_dir(legal),
+            PathBuf::from("/var/lib/prism/legal")
+        );
+        assert_eq!(
+            config.tenant_dir(&config.tenants[1]),
+            PathBuf::from("/srv/hr")
+        );
+        assert!(!serde_json::to_string(legal).unwrap().contains("k1"));
+    }
+
+    #[test]
+    fn test_cache_config() {
+        let config: ServerConfig =
+            serde_json::from_str(r#"{"cache": {"ttl_seconds": 60}}"#).unwrap();
+        let cache = config.cache.unwrap();
+        assert_eq!(cache.max_bytes, CacheLimits::default().max_bytes);
+        assert_eq!(cache.build().unwrap().stats().entries, 0);
+    }
+
+    #[test]
+    fn test_fetch_config() {
+        let config: ServerConfig = serde_json::from_str(
+            r#"{"max_file_size": 1000, "url_fetch": {"allowed_hosts": ["example.com"]}}"#,
+        )
+        .unwrap();
+        let policy = config.url_fetch.unwrap().policy(config.max_file_size);
+        assert_eq!(policy.allowed_hosts, ["example.com"]);
+        assert_eq!(policy.max_size, 1000);
+        assert_eq!(policy.max_redirects, FetchPolicy::default().max_redirects);
+        assert!(!policy.allow_private_hosts);
+
+        // A configured download limit only ever lowers the request's
+        let fetch = FetchConfig {
+            max_size: Some(500),
+            ..FetchConfig::default()
+        };
+        assert_eq!(fetch.policy(1000).max_size, 500);
+        assert_eq!(fetch.policy(100).max_size, 100);
     }
 }
@@ -2,21 +2,115 @@
 //! Convert endpoint for document format conversion
 
 use axum::{
-    extract::{Multipart, State},
-    http::{header, StatusCode},
+    extract::{rejection::QueryRejection, FromRequest, Multipart, Query, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use bytes::Bytes;
-use prism_core::{
-    format::detect_format,
-    parser::ParseContext,
-    render::{RenderContext, Renderer},
-};
-use serde::Serialize;
-use tracing::{debug, error, info, warn};
+use prism_core::cache::{CacheKey, CachedOutput};
+use prism_core::document::SourceInfo;
+use prism_core::fetch::{fetch, FetchError};
+use prism_core::format::{format_by_mime, Format};
+use prism_core::progress::ProgressSink;
+use prism_core::render::{Pagination, Renderer};
+use prism_core::Error;
+use prism_render::html::HtmlRenderer;
+use prism_render::jsonl::{JsonlRenderer, JSONL_MIME_TYPE};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+use uuid::Uuid;
+
+use crate::config::TenantConfig;
+use crate::options::ConvertOptions;
+use crate::preflight::{Preflight, SNIFF_LEN};
+use crate::reload::Runtime;
+use crate::{tenants, ApiError, AppState};
+
+/// Response header carrying the SHA-256 of the uploaded file
+pub const CONTENT_HASH_HEADER: &str = "x-prism-content-hash";
+
+/// Response header carrying the ID of the converted document
+pub const DOCUMENT_ID_HEADER: &str = "x-prism-document-id";
+
+/// Response header carrying the problems recovered from during lenient
+/// parsing, as a JSON array; omitted when there were none
+pub const DIAGNOSTICS_HEADER: &str = "x-prism-diagnostics";
+
+/// Response header carrying what the renderer could not reproduce
+/// faithfully (substituted fonts, missing images, skipped blocks, content
+/// outside the page), as a JSON object; omitted when there was nothing
+pub const RENDER_DIAGNOSTICS_HEADER: &str = "x-prism-render-diagnostics";
+
+/// Response header carrying the items removed by content disarm, as a
+/// JSON array; omitted when nothing was removed
+pub const DISARMED_HEADER: &str = "x-prism-disarmed";
+
+/// Response header telling whether the output was served from the
+/// conversion cache (`hit`) or converted and stored (`miss`); omitted when
+/// no cache is configured
+pub const CACHE_HEADER: &str = "x-prism-cache";
+
+/// Request and response header carrying the job ID of the conversion,
+/// under which its progress is streamed from `/api/jobs/:id/events`
+///
+/// Clients may choose the ID (a UUID) to follow the conversion while it
+/// runs; otherwise one is generated.
+pub const JOB_ID_HEADER: &str = "x-prism-job-id";
+
+/// Media types requesting JSON Lines output
+const JSONL_MEDIA_TYPES: [&str; 3] = [
+    JSONL_MIME_TYPE,
+    "application/x-ndjson",
+    "application/jsonlines",
+];
+
+/// Output negotiated from the request's `Accept` header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputKind {
+    /// HTML page, the default
+    #[default]
+    Html,
+    /// One JSON record per chunk, for embedding pipelines
+    Jsonl,
+}
+
+impl OutputKind {
+    /// The output requested by `accept`, ignoring parameters and quality
+    /// values; anything not asking for JSON Lines gets HTML
+    #[must_use]
+    pub fn from_accept(accept: &str) -> Self {
+        let jsonl = accept.split(',').any(|range| {
+            let media_type = range.split(';').next().unwrap_or_default().trim();
+            JSONL_MEDIA_TYPES
+                .iter()
+                .any(|jsonl| media_type.eq_ignore_ascii_case(jsonl))
+        });
+        if jsonl {
+            Self::Jsonl
+        } else {
+            Self::Html
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Html => "text/html; charset=utf-8",
+            Self::Jsonl => JSONL_MIME_TYPE,
+        }
+    }
+}
 
-use crate::{ApiError, AppState};
+/// Formats the convert endpoint can render to
+#[must_use]
+pub fn output_formats() -> Vec<Format> {
+    vec![
+        HtmlRenderer::new().output_format(),
+        JsonlRenderer::new().output_format(),
+    ]
+}
 
 /// Format detection response (fallback mode)
 #[derive(Debug, Serialize)]
@@ -48,16 +142,205 @@ pub struct FormatInfo {
 
 /// Convert endpoint handler
 ///
-/// Accepts a file upload and attempts to convert it to the output format.
+/// Accepts a file upload, or a JSON [`UrlInput`] naming a document to
+/// download, and runs it through the conversion pipeline, tuned by the
+/// request's [`ConvertOptions`].
+/// Renders HTML unless the `Accept` header asks for JSON Lines.
 /// If no parser is available and fallback mode is enabled, returns format detection info.
 pub async fn convert(
     State(state): State<AppState>,
-    mut multipart: Multipart,
+    headers: HeaderMap,
+    query: Result<Query<ConvertOptions>, QueryRejection>,
+    request: Request,
 ) -> Result<Response, ApiError> {
     debug!("Received convert request");
 
-    // Extract file from multipart
-    let (filename, file_data) = extract_file(&mut multipart).await?;
+    // Requests keep the runtime they started with across reloads
+    let runtime = state.runtime.current();
+
+    let output = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map_or(OutputKind::Html, OutputKind::from_accept);
+    let Query(query) = query.map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+    let job_id = headers
+        .get(JOB_ID_HEADER)
+        .map(|id| {
+            id.to_str()
+                .ok()
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .ok_or_else(|| ApiError::BadRequest(format!("{JOB_ID_HEADER} must be a UUID")))
+        })
+        .transpose()?;
+
+    // With tenants configured, every upload belongs to one
+    let tenant = tenants::authenticate(&runtime.config, &headers)?.cloned();
+    let span = match &tenant {
+        Some(tenant) => info_span!("tenant", tenant = %tenant.id),
+        None => Span::none(),
+    };
+
+    async {
+        // Policy violations are rejected before the body is buffered
+        let preflight = Preflight::new(&runtime.config, tenant.as_ref());
+        preflight.check_content_length(&headers)?;
+        let is_json = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("application/json"));
+        let (filename, file_data, form_options) = if is_json {
+            let input = fetch_input(&runtime, preflight, request).await?;
+            preflight.check_magic_bytes(&input.1, input.0.as_deref())?;
+            input
+        } else {
+            // Extract file from multipart
+            let mut multipart = Multipart::from_request(request, &())
+                .await
+                .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+            extract_file(&mut multipart, preflight).await?
+        };
+        let options = query.merge(form_options.unwrap_or_default());
+        let declared = options
+            .format
+            .as_deref()
+            .map(|mime_type| declared_format(&runtime, mime_type))
+            .transpose()?;
+        if let Some(tenant) = &tenant {
+            check_tenant_limits(
+                &runtime,
+                tenant,
+                filename.as_deref(),
+                &file_data,
+                declared.as_ref(),
+            )?;
+            state
+                .usage
+                .admit(tenant, &runtime.config.tenant_dir(tenant), file_data.len())?;
+        }
+
+        let tenant_id = tenant.as_ref().map(|tenant| tenant.id.clone());
+        let job = match job_id {
+            Some(id) => state
+                .jobs
+                .start_as(id, filename.clone(), tenant_id, &file_data)
+                .ok_or_else(|| ApiError::BadRequest(format!("Job {id} is already running")))?,
+            None => state.jobs.start(filename.clone(), tenant_id, &file_data),
+        };
+        debug!(
+            "Job {} started, sha256: {}",
+            job.info().id,
+            job.info().sha256
+        );
+        let result = convert_file(
+            &runtime,
+            filename,
+            file_data,
+            output,
+            &options,
+            declared,
+            job.progress(),
+        )
+        .await;
+        match result {
+            Ok(response) => {
+                // Format detection answers of fallback mode are not kept
+                let mut response = match &runtime.artifacts {
+                    Some(artifacts) if response.headers().contains_key(DOCUMENT_ID_HEADER) => {
+                        artifacts
+                            .keep(
+                                &job.info().id,
+                                tenant.as_ref().map(|tenant| tenant.id.as_str()),
+                                response,
+                            )
+                            .await?
+                    }
+                    _ => response,
+                };
+                if let Ok(id) = HeaderValue::try_from(job.info().id.to_string()) {
+                    response
+                        .headers_mut()
+                        .insert(HeaderName::from_static(JOB_ID_HEADER), id);
+                }
+                Ok(response)
+            }
+            Err(e) => {
+                job.fail(e.to_string());
+                if let Some(tenant) = &tenant {
+                    state
+                        .usage
+                        .record_failure(tenant, &runtime.config.tenant_dir(tenant));
+                }
+                Err(e)
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// The format named by the `format` option, which must have a parser
+fn declared_format(runtime: &Runtime, mime_type: &str) -> Result<Format, ApiError> {
+    let format = format_by_mime(mime_type)
+        .ok_or_else(|| ApiError::BadRequest(format!("Unknown format '{mime_type}'")))?;
+    if runtime.formats.contains(&format.mime_type) {
+        Ok(format)
+    } else {
+        Err(ApiError::UnsupportedMediaType(format!(
+            "No parser is registered for {}",
+            format.mime_type
+        )))
+    }
+}
+
+/// Hold an upload to the size and format limits of its tenant, checking
+/// the declared format if there is one
+pub(crate) fn check_tenant_limits(
+    runtime: &Runtime,
+    tenant: &TenantConfig,
+    filename: Option<&str>,
+    data: &[u8],
+    declared: Option<&Format>,
+) -> Result<(), ApiError> {
+    if let Some(max_file_size) = tenant.max_file_size {
+        if data.len() > max_file_size {
+            return Err(ApiError::PayloadTooLarge(format!(
+                "File size {} exceeds maximum allowed size {}",
+                data.len(),
+                max_file_size
+            )));
+        }
+    }
+    if tenant.allowed_formats.is_empty() {
+        return Ok(());
+    }
+    let format = declared.cloned().or_else(|| {
+        runtime
+            .pipeline
+            .detect(data, filename)
+            .map(|detection| detection.format)
+    });
+    match format {
+        Some(format) if tenant.allows_format(&format.mime_type, &format.extension) => Ok(()),
+        Some(format) => Err(ApiError::Forbidden(format!(
+            "Format {} is not enabled for this tenant",
+            format.mime_type
+        ))),
+        None => Err(ApiError::Forbidden(
+            "Unrecognized formats are not enabled for this tenant".to_string(),
+        )),
+    }
+}
+
+/// Convert an uploaded file with the given runtime
+async fn convert_file(
+    runtime: &Runtime,
+    filename: Option<String>,
+    file_data: Vec<u8>,
+    kind: OutputKind,
+    options: &ConvertOptions,
+    declared: Option<Format>,
+    progress: Arc<dyn ProgressSink>,
+) -> Result<Response, ApiError> {
     let file_size = file_data.len();
 
     info!(
@@ -66,129 +349,317 @@ pub async fn convert(
     );
 
     // Validate file size
-    if file_size > state.config.max_file_size {
+    if file_size > runtime.config.max_file_size {
         return Err(ApiError::BadRequest(format!(
             "File size {} exceeds maximum allowed size {}",
-            file_size, state.config.max_file_size
+            file_size, runtime.config.max_file_size
         )));
     }
 
-    // Detect format
-    let format_result = detect_format(&file_data, filename.as_deref()).ok_or_else(|| {
-        ApiError::UnsupportedMediaType("Unable to detect file format".to_string())
-    })?;
+    let mut config = runtime.pipeline.config().clone();
+    options.apply(&mut config)?;
+    config.format.clone_from(&declared);
+    // Split pages travel as a ZIP of one file per page
+    let content_type = if kind == OutputKind::Html && config.render.pagination == Pagination::Split
+    {
+        "application/zip"
+    } else {
+        kind.content_type()
+    };
+
+    // A file converted before with the same settings is served from the
+    // cache, headers included. The same bytes can parse as another format
+    // under another name, so the detected format is part of the key; a
+    // declared one is already in the config.
+    let cached = runtime.cache.as_ref().map(|cache| {
+        let detected = declared.is_none().then(|| {
+            runtime
+                .pipeline
+                .detect(&file_data, filename.as_deref())
+                .map(|detection| detection.format.mime_type)
+        });
+        let settings = (kind, &config, runtime.config.disarm, detected);
+        let key = CacheKey::new(
+            &SourceInfo::content_hash(&file_data),
+            &cache_version(),
+            &settings,
+        );
+        (cache, key)
+    });
+    if let Some((cache, key)) = &cached {
+        if let Some(hit) = cache.get(key) {
+            info!("Serving {:?} from the conversion cache", filename);
+            return respond(hit, Some("hit"));
+        }
+    }
+
+    let data = Bytes::from(file_data);
+    let pipeline = match kind {
+        OutputKind::Html => runtime.pipeline.clone(),
+        OutputKind::Jsonl => runtime
+            .pipeline
+            .clone()
+            .with_renderer(Arc::new(JsonlRenderer::new())),
+    }
+    .with_config(config)
+    .with_progress(progress);
+    let result = pipeline.run(data.clone(), filename.as_deref()).await;
+    let output = match result {
+        Ok(output) => output,
+        Err(Error::DetectionFailed(_)) => {
+            return Err(ApiError::UnsupportedMediaType(
+                "Unable to detect file format".to_string(),
+            ));
+        }
+        Err(Error::UnsupportedFormat(name)) if declared.is_some() => {
+            return Err(ApiError::UnsupportedMediaType(format!(
+                "The file is not a valid {name} file"
+            )));
+        }
+        Err(Error::UnsupportedFormat(_)) => {
+            return no_parser(runtime, &data, filename.as_deref());
+        }
+        Err(e) => {
+            error!("Conversion error ({}): {}", e.code(), e);
+            return Err(ApiError::Document(e));
+        }
+    };
 
     debug!(
-        "Detected format: {} (confidence: {:.2}%), MIME: {}",
-        format_result.format.name,
-        format_result.confidence * 100.0,
-        format_result.format.mime_type
+        "Document parsed successfully, format: {}, pages: {}",
+        output.detection.format.mime_type,
+        output.document.page_count()
     );
+    info!("Document rendered successfully to {:?}", kind);
 
-    // Check if parser exists and can handle this specific file
-    let has_parser = state.parser_registry.has_parser(&format_result.format);
-    debug!("Parser available for {}: {}", format_result.format.mime_type, has_parser);
+    // Return the rendered document, with the hash and ID for deduplication
+    let source = &output.document.source;
+    let diagnostics = &output.document.diagnostics;
+    if !diagnostics.is_empty() {
+        warn!(
+            "Converted with {} recovered problem(s): {}",
+            diagnostics.len(),
+            diagnostics[0]
+        );
+    }
+    let mut converted = CachedOutput::new(output.rendered.unwrap_or_default())
+        .with_attribute(header::CONTENT_TYPE.as_str(), content_type)
+        .with_attribute(CONTENT_HASH_HEADER, source.hash.clone().unwrap_or_default())
+        .with_attribute(DOCUMENT_ID_HEADER, output.document.id.to_string());
+    if !diagnostics.is_empty() {
+        converted
+            .attributes
+            .insert(DIAGNOSTICS_HEADER.to_string(), header_json(diagnostics)?);
+    }
+    let render_diagnostics = &output.render_diagnostics;
+    if !render_diagnostics.is_empty() {
+        debug!(
+            "Rendered with {} fidelity note(s)",
+            render_diagnostics.lines().len()
+        );
+        converted.attributes.insert(
+            RENDER_DIAGNOSTICS_HEADER.to_string(),
+            header_json(render_diagnostics)?,
+        );
+    }
+    let disarmed = &output.document.metadata.disarmed;
+    if !disarmed.is_empty() {
+        converted
+            .attributes
+            .insert(DISARMED_HEADER.to_string(), header_json(disarmed)?);
+    }
 
-    match state.parser_registry.get_parser_for_data(&format_result.format, &file_data) {
-        Some(parser) => {
-            // Parser available - perform conversion
-            info!(
-                "Parser found for format: {}",
-                format_result.format.mime_type
-            );
+    let cache_status = cached.map(|(cache, key)| {
+        cache.put(&key, converted.clone());
+        "miss"
+    });
+    respond(converted, cache_status)
+}
 
-            // Parse document
-            let parse_context = ParseContext {
-                format: format_result.format.clone(),
-                filename: filename.clone(),
-                size: file_size,
-                options: Default::default(),
-            };
+/// Version of the conversion code, part of every cache key
+fn cache_version() -> String {
+    format!(
+        "core {} parsers {} render {}",
+        prism_core::VERSION,
+        prism_parsers::VERSION,
+        prism_render::VERSION
+    )
+}
 
-            let document = parser
-                .parse(Bytes::from(file_data.clone()), parse_context)
-                .await
-                .map_err(|e| {
-                    error!("Parse error: {}", e);
-                    ApiError::InternalServerError(format!("Failed to parse document: {}", e))
-                })?;
-
-            debug!("Document parsed successfully, pages: {}", document.page_count());
-
-            // Render to HTML
-            let render_context = RenderContext {
-                options: Default::default(),
-                filename: filename.clone(),
-            };
-
-            let html_bytes = state
-                .html_renderer
-                .render(&document, render_context)
-                .await
-                .map_err(|e| {
-                    error!("Render error: {}", e);
-                    ApiError::InternalServerError(format!("Failed to render document: {}", e))
-                })?;
-
-            info!("Document rendered successfully to HTML");
-
-            // Return HTML response
-            Ok((
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
-                html_bytes,
-            )
-                .into_response())
-        }
-        None => {
-            // No parser available
-            if state.config.enable_fallback {
-                // Fallback mode - return format detection info
-                warn!(
-                    "No parser available for format: {}, returning detection info",
-                    format_result.format.mime_type
-                );
-
-                let response = FormatDetectionResponse {
-                    format: FormatInfo {
-                        mime_type: format_result.format.mime_type.clone(),
-                        extension: format_result.format.extension.clone(),
-                        family: format!("{:?}", format_result.format.family),
-                        name: format_result.format.name.clone(),
-                        is_container: format_result.format.is_container,
-                    },
-                    confidence: format_result.confidence as f32,
-                    method: format!("{:?}", format_result.method),
-                    message: format!(
-                        "Format detected as {} but no parser is available. Returning format detection information.",
-                        format_result.format.name
-                    ),
-                };
+/// Response carrying a converted document, with its attributes as headers
+fn respond(output: CachedOutput, cache_status: Option<&'static str>) -> Result<Response, ApiError> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &output.attributes {
+        let name = HeaderName::try_from(name.as_str())
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        let value = HeaderValue::try_from(value.as_str())
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        headers.insert(name, value);
+    }
+    if let Some(status) = cache_status {
+        headers.insert(
+            HeaderName::from_static(CACHE_HEADER),
+            HeaderValue::from_static(status),
+        );
+    }
+    Ok((StatusCode::OK, headers, output.data).into_response())
+}
+
+/// `value` as JSON that can be sent as a header value
+fn header_json(value: &impl Serialize) -> Result<String, ApiError> {
+    serde_json::to_string(value)
+        .map(|json| ascii_json(&json))
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))
+}
 
-                Ok(Json(response).into_response())
-            } else {
-                // Fallback disabled - return error
-                Err(ApiError::NotImplemented(format!(
-                    "No parser available for format: {}",
-                    format_result.format.name
-                )))
+/// Escape every non-ASCII character of a JSON document as `\uXXXX`, so it
+/// can be sent as a header value
+fn ascii_json(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                let _ = write!(escaped, "\\u{unit:04x}");
             }
         }
     }
+    escaped
+}
+
+/// Respond to a file whose format was detected but has no parser
+///
+/// If fallback mode is enabled, returns format detection info.
+fn no_parser(runtime: &Runtime, data: &[u8], filename: Option<&str>) -> Result<Response, ApiError> {
+    let format_result = runtime.pipeline.detect(data, filename).ok_or_else(|| {
+        ApiError::UnsupportedMediaType("Unable to detect file format".to_string())
+    })?;
+
+    if runtime.config.enable_fallback {
+        // Fallback mode - return format detection info
+        warn!(
+            "No parser available for format: {}, returning detection info",
+            format_result.format.mime_type
+        );
+
+        let response = FormatDetectionResponse {
+            format: FormatInfo {
+                mime_type: format_result.format.mime_type.clone(),
+                extension: format_result.format.extension.clone(),
+                family: format!("{:?}", format_result.format.family),
+                name: format_result.format.name.clone(),
+                is_container: format_result.format.is_container,
+            },
+            confidence: format_result.confidence as f32,
+            method: format!("{:?}", format_result.method),
+            message: format!(
+                "Format detected as {} but no parser is available. Returning format detection information.",
+                format_result.format.name
+            ),
+        };
+
+        Ok(Json(response).into_response())
+    } else {
+        // Fallback disabled - return error
+        Err(ApiError::NotImplemented(format!(
+            "No parser available for format: {}",
+            format_result.format.name
+        )))
+    }
+}
+
+/// Request body converting a remote document
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UrlInput {
+    /// HTTP(S) URL of the document
+    pub url: String,
+    /// Conversion settings, as in the `options` form field
+    #[serde(default)]
+    pub options: Option<ConvertOptions>,
+}
+
+/// Download the document named by a JSON [`UrlInput`] body
+async fn fetch_input(
+    runtime: &Runtime,
+    preflight: Preflight<'_>,
+    request: Request,
+) -> Result<(Option<String>, Vec<u8>, Option<ConvertOptions>), ApiError> {
+    let Json(input) = Json::<UrlInput>::from_request(request, &())
+        .await
+        .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+    let (filename, data) = fetch_url(runtime, preflight, &input.url).await?;
+    Ok((filename, data, input.options))
+}
+
+/// Download `url` under the configured fetch policy
+///
+/// The download stops at the size limit of `preflight`, so a tenant's
+/// own limit holds before anything past it is buffered.
+pub(crate) async fn fetch_url(
+    runtime: &Runtime,
+    preflight: Preflight<'_>,
+    url: &str,
+) -> Result<(Option<String>, Vec<u8>), ApiError> {
+    let policy = runtime
+        .config
+        .url_fetch
+        .as_ref()
+        .ok_or_else(|| ApiError::Forbidden("Converting documents by URL is disabled".to_string()))?
+        .policy(preflight.max_size());
+
+    info!("Fetching {}", url);
+    let document = fetch(url, &policy).await.map_err(|e| match e {
+        FetchError::HostNotAllowed(_) => ApiError::Forbidden(e.to_string()),
+        FetchError::TooManyRedirects(_)
+        | FetchError::Timeout(_)
+        | FetchError::Status(_)
+        | FetchError::Request(_) => ApiError::BadGateway(format!("Failed to fetch {url}: {e}")),
+        FetchError::InvalidUrl(_)
+        | FetchError::UnsupportedScheme(_)
+        | FetchError::TooLarge { .. } => ApiError::BadRequest(e.to_string()),
+    })?;
+    Ok((document.filename, document.data.to_vec()))
 }
 
-/// Extract file from multipart form data
-async fn extract_file(multipart: &mut Multipart) -> Result<(Option<String>, Vec<u8>), ApiError> {
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
+/// Extract the file, and the options if the form has any, from multipart
+/// form data
+///
+/// The file is streamed in, and rejected under `preflight` as soon as its
+/// declared type, its first bytes or its size break the policy.
+pub(crate) async fn extract_file(
+    multipart: &mut Multipart,
+    preflight: Preflight<'_>,
+) -> Result<(Option<String>, Vec<u8>, Option<ConvertOptions>), ApiError> {
+    let mut file = None;
+    let mut options = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
         ApiError::BadRequest(format!("Failed to read multipart field: {}", e))
     })? {
         let name = field.name().unwrap_or("").to_string();
 
         if name == "file" {
             let filename = field.file_name().map(|s| s.to_string());
-            let data = field.bytes().await.map_err(|e| {
+            if let Some(content_type) = field.content_type() {
+                preflight.check_declared_type(content_type)?;
+            }
+            let mut data = Vec::new();
+            let mut sniffed = false;
+            while let Some(chunk) = field.chunk().await.map_err(|e| {
                 ApiError::BadRequest(format!("Failed to read file data: {}", e))
-            })?;
+            })? {
+                data.extend_from_slice(&chunk);
+                preflight.check_size(data.len())?;
+                if !sniffed && data.len() >= SNIFF_LEN {
+                    preflight.check_magic_bytes(&data, filename.as_deref())?;
+                    sniffed = true;
+                }
+            }
+            if !sniffed {
+                preflight.check_magic_bytes(&data, filename.as_deref())?;
+            }
 
             debug!(
                 "Extracted file: {:?}, size: {} bytes",
@@ -196,11 +667,161 @@ async fn extract_file(multipart: &mut Multipart) -> Result<(Option<String>, Vec<
                 data.len()
             );
 
-            return Ok((filename, data.to_vec()));
+            file = Some((filename, data));
+        } else if name == "options" {
+            let json = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read options: {e}")))?;
+            options = Some(
+                serde_json::from_slice(&json)
+                    .map_err(|e| ApiError::BadRequest(format!("Invalid options: {e}")))?,
+            );
         }
     }
 
-    Err(ApiError::BadRequest(
-        "No file field found in multipart form".to_string(),
-    ))
+    let (filename, data) = file
+        .ok_or_else(|| ApiError::BadRequest("No file field found in multipart form".to_string()))?;
+    Ok((filename, data, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_json() {
+        let json = r#"[{"message":"Feuille « Données » illisible 😀"}]"#;
+        let escaped = ascii_json(json);
+        assert!(escaped.is_ascii());
+        assert!(escaped.contains(r"Donn\u00e9es"));
+        assert!(escaped.contains(r"\ud83d\ude00"));
+        let value: serde_json::Value = serde_json::from_str(&escaped).unwrap();
+        assert_eq!(value[0]["message"], "Feuille « Données » illisible 😀");
+    }
+
+    #[test]
+    fn test_declared_format() {
+        let config = crate::config::ServerConfig {
+            disabled_formats: vec!["pdf".to_string()],
+            ..Default::default()
+        };
+        let runtime = Runtime::build(config, 0);
+
+        let format = declared_format(&runtime, "application/vnd.ms-outlook").unwrap();
+        assert_eq!(format, Format::msg());
+        assert!(matches!(
+            declared_format(&runtime, "application/x-unknown"),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            declared_format(&runtime, "application/pdf"),
+            Err(ApiError::UnsupportedMediaType(_))
+        ));
+    }
+
+    #[test]
+    fn test_tenant_file_size() {
+        let runtime = Runtime::build(crate::config::ServerConfig::default(), 0);
+        let tenant: TenantConfig = serde_json::from_value(serde_json::json!({
+            "id": "legal", "api_keys": ["k1"], "max_file_size": 4
+        }))
+        .unwrap();
+        assert!(check_tenant_limits(&runtime, &tenant, None, b"four", None).is_ok());
+        assert!(matches!(
+            check_tenant_limits(&runtime, &tenant, None, b"fives", None),
+            Err(ApiError::PayloadTooLarge(_))
+        ));
+    }
+
+    #[derive(Debug)]
+    struct NoProgress;
+
+    impl ProgressSink for NoProgress {
+        fn report(&self, _event: prism_core::progress::ProgressEvent) {}
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_covers_detected_format() {
+        let config = crate::config::ServerConfig {
+            cache: Some(crate::config::CacheConfig::default()),
+            ..Default::default()
+        };
+        let runtime = Runtime::build(config, 0);
+        let data = b"name,total\nwidgets,3\n".to_vec();
+        let options = ConvertOptions::default();
+        let convert = |name: &str| {
+            convert_file(
+                &runtime,
+                Some(name.to_string()),
+                data.clone(),
+                OutputKind::Jsonl,
+                &options,
+                None,
+                Arc::new(NoProgress),
+            )
+        };
+        let status = |response: &Response| {
+            response.headers()[CACHE_HEADER]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(status(&convert("x.csv").await.unwrap()), "miss");
+        assert_eq!(status(&convert("x.txt").await.unwrap()), "miss");
+        assert_eq!(status(&convert("y.csv").await.unwrap()), "hit");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_url_stops_at_tenant_limit() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let body = "x".repeat(2000);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+
+        let config: crate::config::ServerConfig = serde_json::from_value(serde_json::json!({
+            "url_fetch": {"allow_private_hosts": true},
+            "tenants": [{"id": "legal", "api_keys": ["k1"], "max_file_size": 1000}]
+        }))
+        .unwrap();
+        let runtime = Runtime::build(config, 0);
+        let preflight = Preflight::new(&runtime.config, runtime.config.tenants.first());
+        let result = fetch_url(&runtime, preflight, &format!("http://{address}/big.txt")).await;
+        assert!(
+            matches!(&result, Err(ApiError::BadRequest(message)) if message.contains("1000")),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let formats = output_formats();
+        assert_eq!(formats.len(), 2);
+        assert_eq!(formats[1].mime_type, JSONL_MIME_TYPE);
+    }
+
+    #[test]
+    fn test_output_from_accept() {
+        assert_eq!(OutputKind::from_accept("text/html"), OutputKind::Html);
+        assert_eq!(OutputKind::from_accept("*/*"), OutputKind::Html);
+        assert_eq!(
+            OutputKind::from_accept("application/jsonl"),
+            OutputKind::Jsonl
+        );
+        assert_eq!(
+            OutputKind::from_accept("text/html;q=0.5, Application/X-NDJSON; q=0.9"),
+            OutputKind::Jsonl
+        );
+    }
 }
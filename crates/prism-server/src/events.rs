@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Progress streaming for running conversions
+//!
+//! `GET /api/jobs/:id/events` streams the progress of a conversion as
+//! server-sent events. Each event is named after its kind (`stage_changed`,
+//! `format_detected`, `page_parsed`, `render_started`, `page_rendered`,
+//! `render_finished`, `warning`) and carries the event as JSON; a final
+//! `finished` event reports whether the conversion succeeded.
+//!
+//! To follow its own upload a client picks the job ID up front, sends it
+//! in the `x-prism-job-id` header of the convert request and opens the
+//! stream alongside. A stream opened before the upload has been received
+//! waits for the job to start. With tenants configured, the stream takes
+//! the same API key as the upload and only shows the tenant's own jobs.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::{self, Stream, StreamExt};
+use prism_core::progress::ProgressEvent;
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::jobs::{JobProgress, JobTracker, JobUpdate};
+use crate::{tenants, ApiError, AppState};
+
+/// How long a stream waits for its job to start
+const JOB_WAIT: Duration = Duration::from_secs(30);
+
+/// How often a waiting stream looks for its job
+const JOB_POLL: Duration = Duration::from_millis(100);
+
+/// Stream the progress of job `id`
+///
+/// # Errors
+///
+/// Returns not found if no job with this ID starts within [`JOB_WAIT`].
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let tenant = {
+        let runtime = state.runtime.current();
+        tenants::authenticate(&runtime.config, &headers)?.map(|tenant| tenant.id.clone())
+    };
+    let progress = wait_for_job(&state.jobs, &id, tenant.as_deref())
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("No running job {id}")))?;
+    let (events, updates) = progress.subscribe();
+
+    let live = stream::unfold(Some(updates), move |updates| async move {
+        let mut updates = updates?;
+        loop {
+            match updates.recv().await {
+                Ok(JobUpdate::Progress(event)) => {
+                    return Some((progress_event(&event), Some(updates)))
+                }
+                Ok(JobUpdate::Finished { error }) => return Some((finished_event(error), None)),
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Job {} stream skipped {} event(s)", id, skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = stream::iter(events.iter().map(progress_event).collect::<Vec<_>>())
+        .chain(live)
+        .map(Ok);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Progress of job `id` of `tenant`, waiting up to [`JOB_WAIT`] for it to
+/// start
+async fn wait_for_job(
+    jobs: &JobTracker,
+    id: &Uuid,
+    tenant: Option<&str>,
+) -> Option<Arc<JobProgress>> {
+    let mut waited = Duration::ZERO;
+    loop {
+        if let Some(progress) = jobs.progress(id, tenant) {
+            return Some(progress);
+        }
+        if waited >= JOB_WAIT {
+            return None;
+        }
+        tokio::time::sleep(JOB_POLL).await;
+        waited += JOB_POLL;
+    }
+}
+
+fn progress_event(event: &ProgressEvent) -> Event {
+    Event::default()
+        .event(event.name())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
+fn finished_event(error: Option<String>) -> Event {
+    let data = match error {
+        Some(message) => json!({ "status": "failed", "message": message }),
+        None => json!({ "status": "completed" }),
+    };
+    Event::default().event("finished").data(data.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_job() {
+        let jobs = Arc::new(JobTracker::new());
+        let job = jobs.start(None, None, b"abc");
+        assert!(wait_for_job(&jobs, &job.info().id, None).await.is_some());
+
+        let id = Uuid::new_v4();
+        let waiting = tokio::spawn({
+            let jobs = Arc::clone(&jobs);
+            async move { wait_for_job(&jobs, &id, None).await.is_some() }
+        });
+        tokio::time::sleep(JOB_POLL * 2).await;
+        let _job = jobs.start_as(id, None, None, b"abc").unwrap();
+        assert!(waiting.await.unwrap());
+    }
+}
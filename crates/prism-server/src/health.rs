@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Liveness and readiness probes
+//!
+//! `/healthz` answers as long as the process serves requests at all; an
+//! orchestrator restarts the server when it stops answering. `/health` is
+//! kept as an alias for existing clients.
+//!
+//! `/readyz` tells whether the server should be sent conversions. It fails
+//! with `503 Service Unavailable` while the server shuts down, or when one
+//! of its checks fails:
+//!
+//! - `parsers`: the parser registry of the active runtime is not empty
+//! - `temp_dir`: the directory holding tenant storage is writable
+//! - `workers`: the blocking worker pool runs a task within a second
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::reload::Runtime;
+use crate::shutdown::Shutdown;
+use crate::AppState;
+
+/// Time the worker pool has to pick up a no-op task
+const WORKER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Liveness response
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// Always `ok`
+    pub status: String,
+    /// Server version
+    pub version: String,
+}
+
+/// Outcome of one readiness check
+#[derive(Debug, Serialize)]
+pub struct ReadinessCheck {
+    /// Check name, e.g. `temp_dir`
+    pub name: &'static str,
+    /// Whether the check passed
+    pub ok: bool,
+    /// Why it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ReadinessCheck {
+    fn new(name: &'static str, result: Result<(), String>) -> Self {
+        Self {
+            name,
+            ok: result.is_ok(),
+            message: result.err(),
+        }
+    }
+}
+
+/// Readiness response
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// `ready`, `not_ready` or `shutting_down`
+    pub status: &'static str,
+    /// Every check, passed or not
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessResponse {
+    /// Whether conversions may be sent to the server
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
+
+/// Liveness probe
+pub async fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+}
+
+/// Readiness probe
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let report = readiness(&state.runtime.current(), &state.shutdown).await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Run the readiness checks against `runtime`
+pub async fn readiness(runtime: &Runtime, shutdown: &Shutdown) -> ReadinessResponse {
+    let parsers = if runtime.parser_count == 0 {
+        Err("no parsers registered".to_string())
+    } else {
+        Ok(())
+    };
+    let dir = runtime.config.temp_root();
+    let temp_dir = match tokio::time::timeout(
+        WORKER_TIMEOUT,
+        tokio::task::spawn_blocking(move || check_writable(&dir)),
+    )
+    .await
+    {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("timed out".to_string()),
+    };
+    let workers =
+        match tokio::time::timeout(WORKER_TIMEOUT, tokio::task::spawn_blocking(|| ())).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("no worker free within {WORKER_TIMEOUT:?}")),
+        };
+
+    let checks = vec![
+        ReadinessCheck::new("parsers", parsers),
+        ReadinessCheck::new("temp_dir", temp_dir),
+        ReadinessCheck::new("workers", workers),
+    ];
+    let status = if shutdown.is_draining() {
+        "shutting_down"
+    } else if checks.iter().all(|check| check.ok) {
+        "ready"
+    } else {
+        "not_ready"
+    };
+    ReadinessResponse { status, checks }
+}
+
+/// Create `dir` if needed and write and remove a probe file in it
+fn check_writable(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(format!(".readyz-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b"ok"))
+        .and_then(|()| std::fs::remove_file(&probe))
+        .map_err(|e| format!("{} is not writable: {e}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ServerConfig {
+            temp_dir: Some(dir.path().join("prism")),
+            ..ServerConfig::default()
+        };
+        let runtime = Runtime::build(config, 0);
+        let shutdown = Shutdown::default();
+
+        let report = readiness(&runtime, &shutdown).await;
+        assert!(report.is_ready(), "{report:?}");
+        assert!(dir.path().join("prism").is_dir());
+
+        // A file where the directory should be
+        let config = ServerConfig {
+            temp_dir: Some(dir.path().join("prism").join("file")),
+            ..ServerConfig::default()
+        };
+        std::fs::write(dir.path().join("prism").join("file"), b"").unwrap();
+        let report = readiness(&Runtime::build(config, 0), &shutdown).await;
+        assert_eq!(report.status, "not_ready");
+        assert!(!report.checks[1].ok);
+
+        shutdown.begin();
+        let report = readiness(&runtime, &shutdown).await;
+        assert_eq!(report.status, "shutting_down");
+    }
+}
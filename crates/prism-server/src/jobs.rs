@@ -0,0 +1,351 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Tracking of in-flight conversions and recent failures
+//!
+//! Every conversion registers a job for as long as it runs; failures are
+//! kept in a bounded ring buffer together with the SHA-256 of the input so
+//! the offending document can be identified without storing it.
+//!
+//! Each job also collects the progress events of its conversion, which
+//! subscribers receive from the start of the job onwards.
+
+use chrono::{DateTime, Utc};
+use prism_core::document::SourceInfo;
+use prism_core::progress::{ProgressEvent, ProgressSink};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Number of failures kept for inspection
+pub const RECENT_ERRORS: usize = 100;
+
+/// Number of progress events kept per job for late subscribers
+pub const PROGRESS_HISTORY: usize = 256;
+
+/// A conversion currently running
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    /// Job identifier, chosen by the client or generated
+    pub id: Uuid,
+    /// Uploaded filename
+    pub filename: Option<String>,
+    /// Tenant the conversion runs for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Input size in bytes
+    pub size: usize,
+    /// SHA-256 of the input
+    pub sha256: String,
+    /// When the conversion started
+    pub started_at: DateTime<Utc>,
+}
+
+/// A failed conversion
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    /// The job that failed
+    #[serde(flatten)]
+    pub job: JobInfo,
+    /// When it failed
+    pub failed_at: DateTime<Utc>,
+    /// Error message returned to the client
+    pub message: String,
+}
+
+/// Something that happened to a job
+#[derive(Debug, Clone)]
+pub enum JobUpdate {
+    /// The conversion made progress
+    Progress(ProgressEvent),
+    /// The job ended, with the error message if it failed
+    Finished {
+        /// Error message returned to the client
+        error: Option<String>,
+    },
+}
+
+/// Progress of a running job
+///
+/// The most recent events are kept so that subscribers joining late still
+/// see how far the conversion got.
+#[derive(Debug)]
+pub struct JobProgress {
+    events: Mutex<VecDeque<ProgressEvent>>,
+    sender: broadcast::Sender<JobUpdate>,
+}
+
+impl JobProgress {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(PROGRESS_HISTORY);
+        Self {
+            events: Mutex::default(),
+            sender,
+        }
+    }
+
+    /// The events so far, and a receiver for every later update
+    #[must_use]
+    pub fn subscribe(&self) -> (Vec<ProgressEvent>, broadcast::Receiver<JobUpdate>) {
+        // Holding the lock keeps events from slipping in between the two
+        let events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        (events.iter().cloned().collect(), self.sender.subscribe())
+    }
+
+    fn finish(&self, error: Option<String>) {
+        let _events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = self.sender.send(JobUpdate::Finished { error });
+    }
+}
+
+impl ProgressSink for JobProgress {
+    fn report(&self, event: ProgressEvent) {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.len() == PROGRESS_HISTORY {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+        // Nobody may be listening
+        let _ = self.sender.send(JobUpdate::Progress(event));
+    }
+}
+
+#[derive(Debug)]
+struct ActiveJob {
+    info: JobInfo,
+    progress: Arc<JobProgress>,
+}
+
+/// Registry of active jobs and recent errors
+#[derive(Debug, Default)]
+pub struct JobTracker {
+    active: Mutex<BTreeMap<Uuid, ActiveJob>>,
+    errors: Mutex<VecDeque<ErrorRecord>>,
+}
+
+impl JobTracker {
+    /// Create an empty tracker
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a conversion; it stays active until the guard is dropped
+    pub fn start(
+        self: &Arc<Self>,
+        filename: Option<String>,
+        tenant: Option<String>,
+        data: &[u8],
+    ) -> JobGuard {
+        loop {
+            if let Some(job) = self.start_as(Uuid::new_v4(), filename.clone(), tenant.clone(), data)
+            {
+                return job;
+            }
+        }
+    }
+
+    /// Register a conversion under an identifier chosen by the client,
+    /// or `None` if a running job already has it
+    pub fn start_as(
+        self: &Arc<Self>,
+        id: Uuid,
+        filename: Option<String>,
+        tenant: Option<String>,
+        data: &[u8],
+    ) -> Option<JobGuard> {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        if active.contains_key(&id) {
+            return None;
+        }
+        let info = JobInfo {
+            id,
+            filename,
+            tenant,
+            size: data.len(),
+            sha256: SourceInfo::content_hash(data),
+            started_at: Utc::now(),
+        };
+        let progress = Arc::new(JobProgress::new());
+        active.insert(
+            id,
+            ActiveJob {
+                info: info.clone(),
+                progress: Arc::clone(&progress),
+            },
+        );
+
+        Some(JobGuard {
+            tracker: Arc::clone(self),
+            info,
+            progress,
+            error: Mutex::default(),
+        })
+    }
+
+    /// Snapshot of running jobs, oldest first
+    #[must_use]
+    pub fn active(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self
+            .active
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(|job| job.info.clone())
+            .collect();
+        jobs.sort_by_key(|job| job.started_at);
+        jobs
+    }
+
+    /// Progress of the running job `id`, if it belongs to `tenant`
+    #[must_use]
+    pub fn progress(&self, id: &Uuid, tenant: Option<&str>) -> Option<Arc<JobProgress>> {
+        self.active
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .filter(|job| job.info.tenant.as_deref() == tenant)
+            .map(|job| Arc::clone(&job.progress))
+    }
+
+    /// Recent failures, newest first
+    #[must_use]
+    pub fn recent_errors(&self) -> Vec<ErrorRecord> {
+        self.errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    fn record_error(&self, job: &JobInfo, message: String) {
+        let mut errors = self.errors.lock().unwrap_or_else(PoisonError::into_inner);
+        if errors.len() == RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(ErrorRecord {
+            job: job.clone(),
+            failed_at: Utc::now(),
+            message,
+        });
+    }
+}
+
+/// Keeps a job registered while a conversion runs
+#[derive(Debug)]
+pub struct JobGuard {
+    tracker: Arc<JobTracker>,
+    info: JobInfo,
+    progress: Arc<JobProgress>,
+    error: Mutex<Option<String>>,
+}
+
+impl JobGuard {
+    /// Details of this job
+    #[must_use]
+    pub fn info(&self) -> &JobInfo {
+        &self.info
+    }
+
+    /// Sink receiving the progress of this job's conversion
+    #[must_use]
+    pub fn progress(&self) -> Arc<dyn ProgressSink> {
+        self.progress.clone()
+    }
+
+    /// Record that this job failed
+    pub fn fail(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.tracker.record_error(&self.info, message.clone());
+        *self.error.lock().unwrap_or_else(PoisonError::into_inner) = Some(message);
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        self.tracker
+            .active
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.info.id);
+        let error = self
+            .error
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        self.progress.finish(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let tracker = Arc::new(JobTracker::new());
+        let job = tracker.start(Some("a.pdf".to_string()), None, b"abc");
+        assert_eq!(tracker.active().len(), 1);
+        assert_eq!(
+            job.info().sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        job.fail("Failed to parse document");
+        drop(job);
+
+        assert!(tracker.active().is_empty());
+        let errors = tracker.recent_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].job.filename.as_deref(), Some("a.pdf"));
+    }
+
+    #[test]
+    fn test_job_progress() {
+        let tracker = Arc::new(JobTracker::new());
+        let id = Uuid::new_v4();
+        let job = tracker
+            .start_as(id, None, Some("legal".to_string()), b"abc")
+            .unwrap();
+        assert!(tracker
+            .start_as(id, None, Some("legal".to_string()), b"abc")
+            .is_none());
+
+        job.progress()
+            .report(ProgressEvent::RenderStarted { pages: 1 });
+        assert!(tracker.progress(&id, None).is_none());
+        assert!(tracker.progress(&id, Some("sales")).is_none());
+        let (events, mut updates) = tracker.progress(&id, Some("legal")).unwrap().subscribe();
+        assert_eq!(events, [ProgressEvent::RenderStarted { pages: 1 }]);
+
+        job.progress()
+            .report(ProgressEvent::RenderFinished { bytes: 10 });
+        job.fail("Render failed");
+        drop(job);
+        assert!(matches!(
+            updates.try_recv(),
+            Ok(JobUpdate::Progress(ProgressEvent::RenderFinished {
+                bytes: 10
+            }))
+        ));
+        assert!(matches!(
+            updates.try_recv(),
+            Ok(JobUpdate::Finished { error: Some(message) }) if message == "Render failed"
+        ));
+        assert!(tracker.progress(&id, Some("legal")).is_none());
+    }
+
+    #[test]
+    fn test_error_buffer_is_bounded() {
+        let tracker = Arc::new(JobTracker::new());
+        for i in 0..=RECENT_ERRORS {
+            tracker.start(None, None, &[]).fail(format!("error {i}"));
+        }
+
+        let errors = tracker.recent_errors();
+        assert_eq!(errors.len(), RECENT_ERRORS);
+        assert_eq!(errors[0].message, format!("error {RECENT_ERRORS}"));
+    }
+}
@@ -5,107 +5,94 @@
 //!
 //! This is the main entry point for the Prism HTTP server.
 
+mod admin;
+mod analyze;
+mod artifacts;
 mod config;
 mod convert;
+mod events;
+mod health;
+mod jobs;
+mod options;
+mod preflight;
+mod reload;
+mod shutdown;
+mod tenants;
 
 use axum::{
-    extract::{DefaultBodyLimit, Json},
+    extract::{DefaultBodyLimit, Json, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
-use prism_parsers::ParserRegistry;
-use prism_render::html::HtmlRenderer;
+use prism_core::error::ErrorLocation;
+use prism_core::license::{LicenseFeature, LicenseManager, LicenseStatus};
+use prism_sandbox::SandboxManager;
 use serde::Serialize;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 
 use config::ServerConfig;
+use jobs::JobTracker;
+use reload::{Runtime, RuntimeHandle};
+use shutdown::Shutdown;
+use tenants::UsageTracker;
 
 /// Application state
 #[derive(Clone)]
 struct AppState {
-    /// Parser registry for format support
-    parser_registry: Arc<ParserRegistry>,
-    /// HTML renderer
-    html_renderer: Arc<HtmlRenderer>,
-    /// Server configuration
-    config: Arc<ServerConfig>,
+    /// Pipeline and configuration, swapped on reload
+    runtime: Arc<RuntimeHandle>,
+    /// Active license
+    license: Arc<LicenseManager>,
+    /// In-flight conversions and recent failures
+    jobs: Arc<JobTracker>,
+    /// Sandbox used for untrusted parsers
+    sandbox: Arc<SandboxManager>,
+    /// Conversions of each tenant this month
+    usage: Arc<UsageTracker>,
+    /// Set once the server starts shutting down
+    shutdown: Arc<Shutdown>,
 }
 
 impl AppState {
-    /// Create a new AppState with default configuration
-    fn new() -> Self {
-        let mut registry = ParserRegistry::new();
-
-        // Register PDF parser
-        registry.register(Arc::new(prism_parsers::PdfParser::new()));
-
-        // Register image parsers
-        registry.register(Arc::new(prism_parsers::PngParser::new()));
-        registry.register(Arc::new(prism_parsers::JpegParser::new()));
-        registry.register(Arc::new(prism_parsers::TiffParser::new()));
-
-        // Register Office parsers (modern)
-        registry.register(Arc::new(prism_parsers::DocxParser::new()));
-        registry.register(Arc::new(prism_parsers::PptxParser::new()));
-        registry.register(Arc::new(prism_parsers::XlsxParser::new()));
-
-        // Register Office parsers (legacy)
-        registry.register(Arc::new(prism_parsers::DocParser::new()));
-        registry.register(Arc::new(prism_parsers::PptParser::new()));
-        registry.register(Arc::new(prism_parsers::XlsParser::new()));
-
-        // Register text-based parsers
-        registry.register(Arc::new(prism_parsers::TextParser::new()));
-        registry.register(Arc::new(prism_parsers::HtmlParser::new()));
-        registry.register(Arc::new(prism_parsers::JsonParser::new()));
-        registry.register(Arc::new(prism_parsers::XmlParser::new()));
-        registry.register(Arc::new(prism_parsers::CsvParser::new()));
-        registry.register(Arc::new(prism_parsers::MarkdownParser::new()));
-        registry.register(Arc::new(prism_parsers::LogParser::new()));
-
-        // Register email parsers
-        registry.register(Arc::new(prism_parsers::EmlParser::new()));
-        registry.register(Arc::new(prism_parsers::MsgParser::new()));
-        registry.register(Arc::new(prism_parsers::MboxParser::new()));
-        registry.register(Arc::new(prism_parsers::VcfParser::new()));
-        registry.register(Arc::new(prism_parsers::IcsParser::new()));
-
-        info!("Registered {} parsers", registry.count());
-
-        // Log registered MIME types for debugging
-        for parser in registry.all_parsers() {
-            info!("  - {}: {}", parser.metadata().name, parser.format().mime_type);
-        }
+    /// Create a new AppState from the loaded configuration
+    fn new(license: LicenseManager, config: ServerConfig) -> Self {
+        let runtime = Runtime::build(config, 0);
 
-        let renderer = HtmlRenderer::new();
-        let config = ServerConfig::default();
+        info!("Registered {} parsers", runtime.parser_count);
 
         Self {
-            parser_registry: Arc::new(registry),
-            html_renderer: Arc::new(renderer),
-            config: Arc::new(config),
+            runtime: Arc::new(RuntimeHandle::new(runtime)),
+            license: Arc::new(license),
+            jobs: Arc::new(JobTracker::new()),
+            sandbox: Arc::new(SandboxManager::default_config()),
+            usage: Arc::new(UsageTracker::new()),
+            shutdown: Arc::new(Shutdown::default()),
         }
     }
 }
 
-/// Health check response
-#[derive(Debug, Serialize)]
-struct HealthResponse {
-    status: String,
-    version: String,
-}
-
 /// Error response
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
+    /// Stable error code for document processing failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+    /// Where in the file the problem was found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<ErrorLocation>,
+    /// Encryption method of an encrypted document; clients can prompt for
+    /// a password when it is present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<String>,
 }
 
 /// API error type
@@ -113,51 +100,140 @@ pub struct ErrorResponse {
 pub enum ApiError {
     /// Bad request (400)
     BadRequest(String),
+    /// Unauthorized (401)
+    Unauthorized(String),
+    /// Forbidden (403)
+    Forbidden(String),
+    /// Not found (404)
+    NotFound(String),
+    /// Payload too large (413)
+    PayloadTooLarge(String),
     /// Unsupported media type (415)
     UnsupportedMediaType(String),
     /// Not implemented (501)
     NotImplemented(String),
+    /// Too many requests (429)
+    TooManyRequests(String),
+    /// Bad gateway (502)
+    BadGateway(String),
     /// Internal server error (500)
     InternalServerError(String),
+    /// Document processing failed; the status follows the error code
+    Document(prism_core::Error),
+}
+
+impl From<prism_core::Error> for ApiError {
+    fn from(error: prism_core::Error) -> Self {
+        ApiError::Document(error)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::NotFound(msg)
+            | ApiError::PayloadTooLarge(msg)
+            | ApiError::UnsupportedMediaType(msg)
+            | ApiError::NotImplemented(msg)
+            | ApiError::TooManyRequests(msg)
+            | ApiError::BadGateway(msg)
+            | ApiError::InternalServerError(msg) => f.write_str(msg),
+            ApiError::Document(error) => error.fmt(f),
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            ApiError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
             ApiError::UnsupportedMediaType(msg) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg),
             ApiError::NotImplemented(msg) => (StatusCode::NOT_IMPLEMENTED, msg),
+            ApiError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+            ApiError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
             ApiError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            ApiError::Document(error) => {
+                let code = error.code();
+                let status = StatusCode::from_u16(code.http_status())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                let body = Json(ErrorResponse {
+                    error: status.to_string(),
+                    message: error.to_string(),
+                    code: Some(code.as_u16()),
+                    location: error.location().cloned(),
+                    encryption: error.encryption_method().map(str::to_string),
+                });
+                return (status, body).into_response();
+            }
         };
 
         let body = Json(ErrorResponse {
             error: status.to_string(),
             message,
+            code: None,
+            location: None,
+            encryption: None,
         });
 
         (status, body).into_response()
     }
 }
 
-/// Health check endpoint
-async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "ok".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    })
-}
-
 /// Version endpoint
-async fn version() -> Json<serde_json::Value> {
+async fn version(State(state): State<AppState>) -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "server": env!("CARGO_PKG_VERSION"),
         "core": prism_core::VERSION,
         "parsers": prism_parsers::VERSION,
         "render": prism_render::VERSION,
         "sandbox": prism_sandbox::VERSION,
+        "license": {
+            "type": state.license.license_type(),
+            "status": state.license.status(),
+            "features": state.license.enabled_features(),
+        },
     }))
 }
 
+/// Support matrix endpoint: the parsers of the current runtime, with
+/// their features and sandbox requirement, and the output formats
+async fn formats(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let runtime = state.runtime.current();
+    Json(serde_json::json!({
+        "parsers": runtime.support,
+        "outputs": convert::output_formats(),
+    }))
+}
+
+/// Load the license and make sure it allows running the server
+fn load_license() -> anyhow::Result<LicenseManager> {
+    let license = LicenseManager::from_env()?;
+
+    info!("License: {}", license.license_type());
+    match license.status() {
+        LicenseStatus::Valid => {}
+        LicenseStatus::GracePeriod { days_remaining } => {
+            warn!(
+                "License has expired; features will be disabled in {} day(s)",
+                days_remaining
+            );
+        }
+        LicenseStatus::Expired => {
+            warn!("License has expired; falling back to community features");
+        }
+    }
+
+    license.require(LicenseFeature::Server)?;
+    Ok(license)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -168,14 +244,41 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting Prism Server v{}", env!("CARGO_PKG_VERSION"));
 
+    // Check license before doing any work
+    let license = load_license()?;
+
     // Initialize app state
-    let state = AppState::new();
+    let config = ServerConfig::load()?;
+    let state = AppState::new(license, config);
+
+    #[cfg(unix)]
+    reload::spawn_sighup_listener(state.runtime.clone())?;
+    shutdown::spawn_signal_listener(state.shutdown.clone())?;
+    let shutdown = state.shutdown.clone();
+    let jobs = state.jobs.clone();
+    let runtime = state.runtime.clone();
 
     // Build router with API routes
     let api_router = Router::new()
-        .route("/health", get(health))
+        .route("/health", get(health::healthz))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
         .route("/version", get(version))
+        .route("/formats", get(formats))
         .route("/convert", post(convert::convert))
+        .route(
+            "/analyze",
+            get(analyze::analyze_url).post(analyze::analyze_upload),
+        )
+        .route("/jobs/:id/events", get(events::job_events))
+        .route("/artifacts/:id", get(artifacts::download))
+        .route("/admin/reload", post(admin::reload))
+        .route("/admin/jobs", get(admin::jobs))
+        .route("/admin/errors", get(admin::errors))
+        .route("/admin/config", get(admin::config))
+        .route("/admin/sandbox", get(admin::sandbox))
+        .route("/admin/cache", get(admin::cache).delete(admin::purge_cache))
+        .route("/admin/tenants", get(admin::tenants))
         .layer(DefaultBodyLimit::max(5 * 1024 * 1024 * 1024)) // 5GB limit
         .with_state(state);
 
@@ -190,7 +293,21 @@ async fn main() -> anyhow::Result<()> {
     info!("Server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let server = axum::serve(listener, app).with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move { shutdown.wait().await }
+    });
+    // The timeout in effect when the signal arrives applies
+    let deadline = async {
+        shutdown.wait().await;
+        let timeout = Duration::from_secs(runtime.current().config.shutdown_timeout_seconds);
+        shutdown.deadline(timeout, &jobs).await;
+    };
+    tokio::select! {
+        result = server => result?,
+        () = deadline => {}
+    }
+    info!("Server stopped");
 
     Ok(())
 }
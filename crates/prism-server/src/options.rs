@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Per-request conversion options.
+//!
+//! Clients tune a conversion with query parameters, such as
+//! `POST /convert?pages=1-3&lenient=true&watermark=DRAFT`, or with an
+//! `options` form field holding the same settings as a JSON object.
+//! `?format=application/vnd.ms-outlook` parses the file as the given MIME
+//! type instead of detecting its format, for files with a wrong or missing
+//! extension.
+//! Settings in the form field win over the query; anything left unset
+//! keeps the server's configuration.
+
+use prism_core::pipeline::PipelineConfig;
+use prism_core::render::{ContentKind, Watermark};
+use serde::Deserialize;
+
+use crate::ApiError;
+
+/// Conversion settings a request may override
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConvertOptions {
+    /// Recognize text in scanned pages
+    pub ocr: Option<bool>,
+    /// Pages to render: `all`, a range such as `2-5`, or a list such as
+    /// `1,4,7-9`
+    pub pages: Option<String>,
+    /// Recover what can be read from damaged files instead of failing
+    pub lenient: Option<bool>,
+    /// Render images; `false` leaves them out
+    pub include_images: Option<bool>,
+    /// `continuous`, `deferred` or `split` (a ZIP of one page per file)
+    pub pagination: Option<String>,
+    /// Text stamped across every page
+    pub watermark: Option<String>,
+    /// MIME type to parse the file as, such as
+    /// `application/vnd.ms-outlook`, instead of detecting its format
+    pub format: Option<String>,
+    /// Tracked changes: `accept`, `reject` or `markup` to show them
+    pub revisions: Option<String>,
+    /// Annotations to draw: `all`, `none` or a list such as
+    /// `highlights,comments,links,redactions,stamps`
+    pub annotations: Option<String>,
+    /// Most pages to render; the output ends with a truncation marker
+    pub max_pages: Option<usize>,
+    /// Most bytes of output, cut at a page boundary
+    pub max_bytes: Option<usize>,
+}
+
+impl ConvertOptions {
+    /// These options with every setting made in `overrides` replaced
+    #[must_use]
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            ocr: overrides.ocr.or(self.ocr),
+            pages: overrides.pages.or(self.pages),
+            lenient: overrides.lenient.or(self.lenient),
+            include_images: overrides.include_images.or(self.include_images),
+            pagination: overrides.pagination.or(self.pagination),
+            watermark: overrides.watermark.or(self.watermark),
+            format: overrides.format.or(self.format),
+            revisions: overrides.revisions.or(self.revisions),
+            annotations: overrides.annotations.or(self.annotations),
+            max_pages: overrides.max_pages.or(self.max_pages),
+            max_bytes: overrides.max_bytes.or(self.max_bytes),
+        }
+    }
+
+    /// Apply the settings made to the server's pipeline configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns a bad request for malformed page ranges, pagination,
+    /// revision modes or annotation types, and not implemented when OCR is asked for, since no OCR
+    /// engine is available.
+    pub fn apply(&self, config: &mut PipelineConfig) -> Result<(), ApiError> {
+        if self.ocr == Some(true) {
+            return Err(ApiError::NotImplemented(
+                "OCR is not available on this server".to_string(),
+            ));
+        }
+        if let Some(lenient) = self.lenient {
+            config.parse.lenient = lenient;
+        }
+        if let Some(pages) = &self.pages {
+            config.render.page_range = Some(pages.parse()?);
+        }
+        if let Some(include_images) = self.include_images {
+            config.render.include_images = include_images;
+            if !include_images {
+                config.render.content = config.render.content.clone().without(ContentKind::Images);
+            }
+        }
+        if let Some(pagination) = &self.pagination {
+            config.render.pagination = pagination.parse()?;
+        }
+        if let Some(revisions) = &self.revisions {
+            config.parse.revisions = revisions.parse()?;
+        }
+        if let Some(annotations) = &self.annotations {
+            config.render.annotations = annotations.parse()?;
+        }
+        if let Some(max_pages) = self.max_pages {
+            config.render.limits.max_pages = Some(max_pages);
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            config.render.limits.max_bytes = Some(max_bytes);
+        }
+        if let Some(text) = self
+            .watermark
+            .as_deref()
+            .filter(|text| !text.trim().is_empty())
+        {
+            config.render.watermark = Some(Watermark::text(text));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use prism_core::parser::RevisionMode;
+    use prism_core::render::{AnnotationOptions, PageRange, Pagination};
+
+    #[test]
+    fn test_apply_options() {
+        let uri = "/convert?pages=2-3&lenient=true&include_images=false&revisions=markup&annotations=links,redactions&max_pages=50"
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<ConvertOptions>::try_from_uri(&uri).unwrap();
+        let body: ConvertOptions =
+            serde_json::from_str(r#"{"pagination": "split", "lenient": false}"#).unwrap();
+        let options = query.merge(body);
+
+        let mut config = PipelineConfig::default();
+        options.apply(&mut config).unwrap();
+        assert!(!config.parse.lenient);
+        assert_eq!(
+            config.render.page_range,
+            Some(PageRange::Range { start: 2, end: 3 })
+        );
+        assert!(!config.render.content.includes(ContentKind::Images));
+        assert_eq!(config.render.pagination, Pagination::Split);
+        assert!(config.render.watermark.is_none());
+        assert_eq!(config.parse.revisions, RevisionMode::Markup);
+        assert_eq!(config.render.limits.max_pages, Some(50));
+        assert_eq!(
+            config.render.annotations,
+            AnnotationOptions {
+                links: true,
+                redactions: true,
+                ..AnnotationOptions::none()
+            }
+        );
+
+        let invalid = ConvertOptions {
+            pages: Some("3-1".to_string()),
+            ..ConvertOptions::default()
+        };
+        assert!(invalid.apply(&mut config).is_err());
+        let ocr = ConvertOptions {
+            ocr: Some(true),
+            ..ConvertOptions::default()
+        };
+        assert!(matches!(
+            ocr.apply(&mut config),
+            Err(ApiError::NotImplemented(_))
+        ));
+        assert!(serde_json::from_str::<ConvertOptions>(r#"{"page": "1"}"#).is_err());
+    }
+}
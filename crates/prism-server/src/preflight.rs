@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Upload policy enforced before the body is read
+//!
+//! A request that is going to be rejected anyway should not be buffered
+//! first, least of all a multi-gigabyte one. Before reading the body, and
+//! while streaming the file part of a multipart upload, the server checks:
+//!
+//! - the declared `Content-Length` against the size limit of the server,
+//!   or of the tenant if it is lower; uploads without one are cut off as
+//!   soon as the file part passes the limit
+//! - the `Content-Type` of the file part against the allowed formats
+//! - the format its first bytes are detected as, once enough has arrived
+//!
+//! A format is allowed when the server does not disable it, it is in the
+//! server's [`PreflightConfig::allowed_formats`] (if any are listed) and
+//! the tenant allows it. Generic types such as `application/octet-stream`
+//! and containers such as ZIP, which OOXML, ODF and EPUB files also start
+//! as, are inconclusive and left to the checks made once the file is read.
+//! Each check can be turned off in [`PreflightConfig`].
+
+use axum::http::{header, HeaderMap};
+use prism_core::format::{detect_format, format_by_mime};
+
+use crate::config::{PreflightConfig, ServerConfig, TenantConfig};
+use crate::ApiError;
+
+/// Bytes of the file part the format is detected from
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Room for multipart boundaries, part headers and the options field on
+/// top of the file itself
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+/// Content types that say nothing about the format
+const GENERIC_TYPES: [&str; 3] = [
+    "application/octet-stream",
+    "application/x-www-form-urlencoded",
+    "binary/octet-stream",
+];
+
+/// Upload policy of one request
+#[derive(Debug, Clone, Copy)]
+pub struct Preflight<'a> {
+    config: &'a ServerConfig,
+    tenant: Option<&'a TenantConfig>,
+}
+
+impl<'a> Preflight<'a> {
+    /// Policy of the server, narrowed by `tenant`
+    #[must_use]
+    pub fn new(config: &'a ServerConfig, tenant: Option<&'a TenantConfig>) -> Self {
+        Self { config, tenant }
+    }
+
+    fn checks(&self) -> &PreflightConfig {
+        &self.config.preflight
+    }
+
+    /// Largest file accepted, in bytes
+    #[must_use]
+    pub fn max_size(&self) -> usize {
+        self.tenant
+            .and_then(|tenant| tenant.max_file_size)
+            .map_or(self.config.max_file_size, |max| {
+                max.min(self.config.max_file_size)
+            })
+    }
+
+    /// Reject a request whose declared length cannot fit under the size
+    /// limit
+    ///
+    /// # Errors
+    ///
+    /// Returns payload too large if it cannot.
+    pub fn check_content_length(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        if !self.checks().content_length {
+            return Ok(());
+        }
+        let length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<usize>().ok());
+        let limit = self.max_size().saturating_add(MULTIPART_OVERHEAD);
+        match length {
+            Some(length) if length > limit => Err(self.too_large(length)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject a file that has grown past the size limit while streaming
+    ///
+    /// # Errors
+    ///
+    /// Returns payload too large if it has.
+    pub fn check_size(&self, size: usize) -> Result<(), ApiError> {
+        if self.checks().content_length && size > self.max_size() {
+            Err(self.too_large(size))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reject a file part declared as a format that is not allowed
+    ///
+    /// # Errors
+    ///
+    /// Returns unsupported media type or forbidden if it is not.
+    pub fn check_declared_type(&self, content_type: &str) -> Result<(), ApiError> {
+        if !self.checks().declared_type {
+            return Ok(());
+        }
+        let mime_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        if mime_type.is_empty() || GENERIC_TYPES.contains(&mime_type.as_str()) {
+            return Ok(());
+        }
+        let extension = format_by_mime(&mime_type)
+            .map(|format| format.extension)
+            .unwrap_or_default();
+        self.check_format(&mime_type, &extension)
+    }
+
+    /// Reject a file whose first bytes are a format that is not allowed
+    ///
+    /// # Errors
+    ///
+    /// Returns unsupported media type or forbidden if they are.
+    pub fn check_magic_bytes(&self, head: &[u8], filename: Option<&str>) -> Result<(), ApiError> {
+        if !self.checks().magic_bytes {
+            return Ok(());
+        }
+        match detect_format(head, filename) {
+            Some(detection) if !detection.format.is_container => {
+                let format = detection.format;
+                self.check_format(&format.mime_type, &format.extension)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_format(&self, mime_type: &str, extension: &str) -> Result<(), ApiError> {
+        let allowed = &self.checks().allowed_formats;
+        let listed = allowed.is_empty()
+            || allowed.iter().any(|entry| {
+                let entry = entry.trim_start_matches('.');
+                entry.eq_ignore_ascii_case(mime_type) || entry.eq_ignore_ascii_case(extension)
+            });
+        if !listed || self.config.is_format_disabled(mime_type, extension) {
+            return Err(ApiError::UnsupportedMediaType(format!(
+                "Format {mime_type} is not accepted by this server"
+            )));
+        }
+        match self.tenant {
+            Some(tenant) if !tenant.allows_format(mime_type, extension) => Err(
+                ApiError::Forbidden(format!("Format {mime_type} is not enabled for this tenant")),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn too_large(&self, size: usize) -> ApiError {
+        ApiError::PayloadTooLarge(format!(
+            "Upload of {size} bytes exceeds maximum allowed size {}",
+            self.max_size()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_preflight() {
+        let config = ServerConfig {
+            max_file_size: 1_000_000,
+            disabled_formats: vec!["doc".to_string()],
+            preflight: PreflightConfig {
+                allowed_formats: vec!["pdf".to_string(), "doc".to_string(), "docx".to_string()],
+                ..PreflightConfig::default()
+            },
+            ..ServerConfig::default()
+        };
+        let tenant = TenantConfig {
+            id: "legal".to_string(),
+            api_keys: Vec::new(),
+            max_file_size: Some(1000),
+            allowed_formats: vec!["docx".to_string()],
+            monthly_quota: None,
+            temp_dir: None,
+        };
+
+        let server = Preflight::new(&config, None);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("900000"));
+        assert!(server.check_content_length(&headers).is_ok());
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("2000000"));
+        assert!(matches!(
+            server.check_content_length(&headers),
+            Err(ApiError::PayloadTooLarge(_))
+        ));
+
+        assert!(server.check_declared_type("application/pdf").is_ok());
+        assert!(server
+            .check_declared_type("application/octet-stream")
+            .is_ok());
+        assert!(matches!(
+            server.check_declared_type("image/png"),
+            Err(ApiError::UnsupportedMediaType(_))
+        ));
+        // Listed, but disabled
+        assert!(server.check_declared_type("application/msword").is_err());
+        assert!(server.check_magic_bytes(b"%PDF-1.7\n", None).is_ok());
+        assert!(server
+            .check_magic_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", None)
+            .is_err());
+        // A ZIP may still turn out to be a DOCX
+        assert!(server.check_magic_bytes(b"PK\x03\x04", None).is_ok());
+
+        let tenant = Preflight::new(&config, Some(&tenant));
+        assert_eq!(tenant.max_size(), 1000);
+        assert!(tenant.check_size(1000).is_ok());
+        assert!(tenant.check_size(1001).is_err());
+        assert!(matches!(
+            tenant.check_declared_type("application/pdf; name=a.pdf"),
+            Err(ApiError::Forbidden(_))
+        ));
+
+        let lax = ServerConfig {
+            preflight: PreflightConfig {
+                content_length: false,
+                declared_type: false,
+                magic_bytes: false,
+                ..config.preflight.clone()
+            },
+            ..config.clone()
+        };
+        let lax = Preflight::new(&lax, None);
+        assert!(lax.check_content_length(&headers).is_ok());
+        assert!(lax.check_declared_type("image/png").is_ok());
+    }
+}
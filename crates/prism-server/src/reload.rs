@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Hot-reload of the parser registry and pipeline configuration.
+//!
+//! Handlers take a snapshot of the current [`Runtime`] when a request
+//! starts, so swapping in a new one never affects conversions already in
+//! flight. A reload re-reads the configuration, rebuilds the registry and
+//! pipeline, and only swaps them in once a smoke conversion succeeds; if it
+//! fails the previous runtime stays active.
+
+use anyhow::{anyhow, bail, Context};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use prism_core::cache::ConversionCache;
+use prism_core::parser::IdStrategy;
+use prism_core::pipeline::{Pipeline, PipelineConfig};
+use prism_parsers::security::Disarm;
+use prism_parsers::{ParserRegistry, ParserSupport};
+use prism_render::html::HtmlRenderer;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use crate::artifacts::Artifacts;
+use crate::config::ServerConfig;
+
+/// Sample converted before a new runtime is accepted
+const SMOKE_SAMPLE: (&str, &[u8]) = ("health-check.txt", b"Prism health check");
+
+/// Everything a request needs that can change on reload
+#[derive(Debug)]
+pub struct Runtime {
+    /// Conversion pipeline
+    pub pipeline: Pipeline,
+    /// Effective configuration
+    pub config: ServerConfig,
+    /// Number of registered parsers
+    pub parser_count: usize,
+    /// MIME types with a registered parser, sorted
+    pub formats: Vec<String>,
+    /// Support matrix of the registered parsers
+    pub support: Vec<ParserSupport>,
+    /// Conversion cache, if configured; a memory cache starts out empty
+    /// after every reload
+    pub cache: Option<Arc<dyn ConversionCache>>,
+    /// Kept outputs and their download URLs, if configured
+    pub artifacts: Option<Artifacts>,
+    /// Incremented on every successful reload
+    pub generation: u64,
+    /// When this runtime was built
+    pub loaded_at: DateTime<Utc>,
+}
+
+impl Runtime {
+    /// Build a runtime from a configuration
+    #[must_use]
+    pub fn build(config: ServerConfig, generation: u64) -> Self {
+        let mut registry = ParserRegistry::new();
+        for parser in ParserRegistry::with_default_parsers().all_parsers() {
+            let format = parser.format();
+            if config.is_format_disabled(&format.mime_type, &format.extension) {
+                info!("Format disabled by policy: {}", format.mime_type);
+            } else {
+                info!("  - {}: {}", parser.metadata().name, format.mime_type);
+                registry.register(parser);
+            }
+        }
+
+        let parser_count = registry.count();
+        let mut formats: Vec<String> = registry
+            .all_parsers()
+            .iter()
+            .map(|parser| parser.format().mime_type)
+            .collect();
+        formats.sort();
+        let support = registry.support_matrix();
+        let mut pipeline_config = PipelineConfig::default();
+        if config.deterministic_ids {
+            pipeline_config.parse.id_strategy = IdStrategy::ContentHash;
+        }
+        pipeline_config.parse.lenient = config.lenient_parsing;
+        pipeline_config.parse.reject_active_content = config.reject_active_content;
+        let mut pipeline = Pipeline::new(Arc::new(registry))
+            .with_renderer(Arc::new(HtmlRenderer::new()))
+            .with_config(pipeline_config);
+        if config.disarm {
+            pipeline = pipeline.with_processor(Arc::new(Disarm));
+        }
+        for tenant in &config.tenants {
+            let dir = config.tenant_dir(tenant);
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                warn!(
+                    "Storage of tenant {} unavailable at {}: {}",
+                    tenant.id,
+                    dir.display(),
+                    e
+                );
+            }
+        }
+        let cache = config.cache.as_ref().and_then(|cache| match cache.build() {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!("Conversion cache disabled: {:#}", e);
+                None
+            }
+        });
+        let artifacts =
+            config.artifacts.as_ref().and_then(|artifacts| {
+                match Artifacts::from_config(artifacts, &config) {
+                    Ok(artifacts) => Some(artifacts),
+                    Err(e) => {
+                        warn!("Artifact downloads disabled: {:#}", e);
+                        None
+                    }
+                }
+            });
+
+        Self {
+            pipeline,
+            config,
+            parser_count,
+            formats,
+            support,
+            cache,
+            artifacts,
+            generation,
+            loaded_at: Utc::now(),
+        }
+    }
+
+    /// Verify the runtime can serve requests
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no parsers are registered or the smoke
+    /// conversion fails.
+    pub async fn check_health(&self) -> anyhow::Result<()> {
+        if self.parser_count == 0 {
+            bail!("no parsers registered");
+        }
+
+        let (filename, data) = SMOKE_SAMPLE;
+        match self
+            .pipeline
+            .run(Bytes::from_static(data), Some(filename))
+            .await
+        {
+            Ok(output)
+                if output
+                    .rendered
+                    .as_ref()
+                    .is_some_and(|html| !html.is_empty()) =>
+            {
+                Ok(())
+            }
+            Ok(_) => Err(anyhow!("smoke conversion produced no output")),
+            // Plain text may itself be disabled by policy
+            Err(prism_core::Error::UnsupportedFormat(_)) => Ok(()),
+            Err(e) => Err(anyhow!(e).context("smoke conversion failed")),
+        }
+    }
+}
+
+/// Summary of a reload
+#[derive(Debug, Serialize)]
+pub struct ReloadReport {
+    /// Generation now active
+    pub generation: u64,
+    /// Number of registered parsers
+    pub parsers: usize,
+    /// When the runtime was built
+    pub loaded_at: DateTime<Utc>,
+}
+
+/// Shared, swappable handle to the active [`Runtime`]
+#[derive(Debug)]
+pub struct RuntimeHandle {
+    current: RwLock<Arc<Runtime>>,
+    // Serializes reloads so two triggers can't race each other
+    reloading: tokio::sync::Mutex<()>,
+}
+
+impl RuntimeHandle {
+    /// Wrap an initial runtime
+    #[must_use]
+    pub fn new(runtime: Runtime) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(runtime)),
+            reloading: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Snapshot of the active runtime
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panicking writer.
+    #[must_use]
+    pub fn current(&self) -> Arc<Runtime> {
+        self.current.read().expect("runtime lock poisoned").clone()
+    }
+
+    /// Reload configuration and parsers, keeping the old runtime on failure
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration cannot be loaded or the new
+    /// runtime fails its health check.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panicking writer.
+    pub async fn reload(&self) -> anyhow::Result<ReloadReport> {
+        let _guard = self.reloading.lock().await;
+
+        let config = ServerConfig::load().context("Reload aborted")?;
+        let generation = self.current().generation + 1;
+        let runtime = Runtime::build(config, generation);
+
+        if let Err(e) = runtime.check_health().await {
+            warn!(
+                "Reload rejected, keeping generation {}: {:#}",
+                generation - 1,
+                e
+            );
+            return Err(e.context("Reload rejected by health check"));
+        }
+
+        let report = ReloadReport {
+            generation,
+            parsers: runtime.parser_count,
+            loaded_at: runtime.loaded_at,
+        };
+        *self.current.write().expect("runtime lock poisoned") = Arc::new(runtime);
+
+        info!(
+            "Reloaded runtime generation {} ({} parsers)",
+            report.generation, report.parsers
+        );
+        Ok(report)
+    }
+}
+
+/// Reload whenever the process receives SIGHUP
+#[cfg(unix)]
+pub fn spawn_sighup_listener(handle: Arc<RuntimeHandle>) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading");
+            // Failures are already logged and the old runtime stays active
+            let _ = handle.reload().await;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disabled_formats_are_not_registered() {
+        let config = ServerConfig {
+            disabled_formats: vec!["pdf".to_string(), "text/plain".to_string()],
+            ..ServerConfig::default()
+        };
+        let runtime = Runtime::build(config, 0);
+
+        let all = ParserRegistry::with_default_parsers().count();
+        assert!(runtime.parser_count < all);
+        assert!(runtime.check_health().await.is_ok());
+
+        let result = runtime
+            .pipeline
+            .run(Bytes::from_static(b"%PDF-1.4\n%%EOF"), Some("a.pdf"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(prism_core::Error::UnsupportedFormat(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_survives_reload() {
+        let handle = RuntimeHandle::new(Runtime::build(ServerConfig::default(), 0));
+        let in_flight = handle.current();
+
+        let report = handle.reload().await.unwrap();
+        assert_eq!(report.generation, 1);
+        assert_eq!(handle.current().generation, 1);
+        assert_eq!(in_flight.generation, 0);
+    }
+}
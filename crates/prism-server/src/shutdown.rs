@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Graceful shutdown
+//!
+//! On SIGTERM or Ctrl-C the server stops accepting connections, reports
+//! itself as shutting down on `/readyz`, and lets the conversions in flight
+//! finish. Conversions still running once `shutdown_timeout_seconds` have
+//! passed are abandoned and the process exits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::jobs::JobTracker;
+
+/// Whether the server is shutting down, shared by the signal listener,
+/// the server and the readiness probe
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    draining: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    /// Start shutting down
+    pub fn begin(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Whether shutdown has begun
+    #[must_use]
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Wait until shutdown begins
+    pub async fn wait(&self) {
+        // Registered before the check, so a `begin` in between is not lost
+        let notified = self.notify.notified();
+        if !self.is_draining() {
+            notified.await;
+        }
+    }
+
+    /// Wait until shutdown began `timeout` ago, logging the conversions
+    /// that will be abandoned
+    pub async fn deadline(&self, timeout: Duration, jobs: &JobTracker) {
+        self.wait().await;
+        let active = jobs.active().len();
+        info!(
+            "Shutting down, waiting up to {:?} for {} conversion(s)",
+            timeout, active
+        );
+        tokio::time::sleep(timeout).await;
+        warn!(
+            "Shutdown timeout reached, abandoning {} conversion(s)",
+            jobs.active().len()
+        );
+    }
+}
+
+/// Begin shutdown when the process receives SIGTERM or Ctrl-C
+pub fn spawn_signal_listener(shutdown: Arc<Shutdown>) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    let mut terminate = {
+        use tokio::signal::unix::{signal, SignalKind};
+        signal(SignalKind::terminate())?
+    };
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        let terminated = terminate.recv();
+        #[cfg(not(unix))]
+        let terminated = std::future::pending::<Option<()>>();
+        tokio::select! {
+            _ = terminated => info!("SIGTERM received"),
+            _ = tokio::signal::ctrl_c() => info!("Interrupted"),
+        }
+        shutdown.begin();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let shutdown = Arc::new(Shutdown::default());
+        let waiter = tokio::spawn({
+            let shutdown = Arc::clone(&shutdown);
+            async move { shutdown.wait().await }
+        });
+        assert!(!shutdown.is_draining());
+
+        shutdown.begin();
+        waiter.await.unwrap();
+        assert!(shutdown.is_draining());
+        // Waiting after the fact returns at once
+        shutdown.wait().await;
+
+        let jobs = JobTracker::new();
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            shutdown.deadline(Duration::from_millis(1), &jobs),
+        )
+        .await
+        .unwrap();
+    }
+}
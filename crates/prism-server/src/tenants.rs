@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Tenants sharing one server
+//!
+//! With tenants configured, every conversion has to present one of a
+//! tenant's API keys and is held to that tenant's limits: a maximum file
+//! size, the formats it may convert and a monthly quota of conversions.
+//! Usage is kept per tenant and month in `usage.json` in the tenant's own
+//! storage directory, so it survives restarts. Conversions run in memory;
+//! what a tenant leaves on disk goes in that directory, kept artifacts
+//! included, apart from the conversion cache, which is shared. Jobs,
+//! errors and log lines carry the tenant.
+
+use axum::http::{header, HeaderMap};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tracing::{error, warn};
+
+use crate::admin::constant_time_eq;
+use crate::config::{ServerConfig, TenantConfig};
+use crate::ApiError;
+
+/// Request header carrying a tenant's API key, as an alternative to
+/// `Authorization: Bearer <key>`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Name of the usage file in a tenant's storage directory
+pub const USAGE_FILE: &str = "usage.json";
+
+/// The tenant a request belongs to, or `None` when no tenants are
+/// configured
+///
+/// # Errors
+///
+/// Returns unauthorized if tenants are configured and the request carries
+/// no key or one that belongs to no tenant.
+pub fn authenticate<'a>(
+    config: &'a ServerConfig,
+    headers: &HeaderMap,
+) -> Result<Option<&'a TenantConfig>, ApiError> {
+    if config.tenants.is_empty() {
+        return Ok(None);
+    }
+    let key = headers
+        .get(API_KEY_HEADER)
+        .or_else(|| headers.get(header::AUTHORIZATION))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.strip_prefix("Bearer ").unwrap_or(value))
+        .ok_or_else(|| ApiError::Unauthorized("An API key is required".to_string()))?;
+
+    config
+        .tenants
+        .iter()
+        .find(|tenant| {
+            tenant
+                .api_keys
+                .iter()
+                .any(|candidate| constant_time_eq(candidate.as_bytes(), key.as_bytes()))
+        })
+        .map(Some)
+        .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))
+}
+
+/// Conversions of a tenant in one month
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantUsage {
+    /// Month counted, as `YYYY-MM` (UTC)
+    pub month: String,
+    /// Conversions started
+    pub conversions: u64,
+    /// Conversions that failed
+    pub failures: u64,
+    /// Bytes uploaded
+    pub bytes: u64,
+}
+
+/// Usage of every tenant, written through to the tenants' directories
+///
+/// Callers pass the tenant's directory from the current configuration on
+/// every call; when a reload moves it, usage is read afresh from the new
+/// directory.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    usage: Mutex<HashMap<String, (PathBuf, TenantUsage)>>,
+}
+
+impl UsageTracker {
+    /// Create an empty tracker
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a conversion of `bytes` against the tenant's quota
+    ///
+    /// # Errors
+    ///
+    /// Returns too many requests once the tenant has used up this month's
+    /// quota, and an internal error if the tenant has a quota and its usage
+    /// cannot be saved, so that a quota is never enforced from memory alone.
+    pub fn admit(&self, tenant: &TenantConfig, dir: &Path, bytes: usize) -> Result<(), ApiError> {
+        let must_save = tenant.monthly_quota.is_some();
+        self.update(&tenant.id, dir, must_save, |usage| {
+            if let Some(quota) = tenant.monthly_quota {
+                if usage.conversions >= quota {
+                    return Err(ApiError::TooManyRequests(format!(
+                        "Monthly quota of {quota} conversions used up"
+                    )));
+                }
+            }
+            usage.conversions += 1;
+            usage.bytes += u64::try_from(bytes).unwrap_or(u64::MAX);
+            Ok(())
+        })
+    }
+
+    /// Fail if the tenant has used up this month's quota, without counting
+    /// anything
+    ///
+    /// # Errors
+    ///
+    /// Returns too many requests once the quota is used up.
+    pub fn check_quota(&self, tenant: &TenantConfig, dir: &Path) -> Result<(), ApiError> {
+        match tenant.monthly_quota {
+            Some(quota) if self.usage(tenant, dir).conversions >= quota => Err(
+                ApiError::TooManyRequests(format!("Monthly quota of {quota} conversions used up")),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a failed conversion of the tenant
+    pub fn record_failure(&self, tenant: &TenantConfig, dir: &Path) {
+        let _ = self.update(&tenant.id, dir, false, |usage| {
+            usage.failures += 1;
+            Ok(())
+        });
+    }
+
+    /// This month's usage of `tenant`
+    #[must_use]
+    pub fn usage(&self, tenant: &TenantConfig, dir: &Path) -> TenantUsage {
+        let month = current_month();
+        let all = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let path = dir.join(USAGE_FILE);
+        let usage = match all.get(&tenant.id) {
+            Some((cached, usage)) if *cached == path => usage.clone(),
+            _ => load(&path),
+        };
+        if usage.month == month {
+            usage
+        } else {
+            TenantUsage {
+                month,
+                ..TenantUsage::default()
+            }
+        }
+    }
+
+    /// Apply `change` to this month's usage of tenant `id` and save it
+    ///
+    /// If saving fails, the change is undone and an error returned when
+    /// `must_save` is set, and only logged otherwise.
+    fn update(
+        &self,
+        id: &str,
+        dir: &Path,
+        must_save: bool,
+        change: impl FnOnce(&mut TenantUsage) -> Result<(), ApiError>,
+    ) -> Result<(), ApiError> {
+        let month = current_month();
+        let path = dir.join(USAGE_FILE);
+        let mut all = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let (cached, usage) = all
+            .entry(id.to_string())
+            .or_insert_with(|| (path.clone(), load(&path)));
+        if *cached != path {
+            *cached = path.clone();
+            *usage = load(&path);
+        }
+        if usage.month != month {
+            *usage = TenantUsage {
+                month,
+                ..TenantUsage::default()
+            };
+        }
+        let previous = usage.clone();
+        change(usage)?;
+
+        let saved = serde_json::to_vec_pretty(usage)
+            .map_err(std::io::Error::from)
+            .and_then(|data| std::fs::write(&path, data));
+        match saved {
+            Ok(()) => Ok(()),
+            Err(e) if must_save => {
+                *usage = previous;
+                error!("Failed to save usage of tenant {}: {}", id, e);
+                Err(ApiError::InternalServerError(
+                    "Failed to record usage".to_string(),
+                ))
+            }
+            Err(e) => {
+                warn!("Failed to save usage of tenant {}: {}", id, e);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Usage saved at `path`, empty if there is none
+fn load(path: &Path) -> TenantUsage {
+    std::fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn tenant(quota: Option<u64>) -> TenantConfig {
+        TenantConfig {
+            id: "legal".to_string(),
+            api_keys: vec!["secret".to_string()],
+            max_file_size: None,
+            allowed_formats: Vec::new(),
+            monthly_quota: quota,
+            temp_dir: None,
+        }
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let mut config = ServerConfig::default();
+        let mut headers = HeaderMap::new();
+        assert!(authenticate(&config, &headers).unwrap().is_none());
+
+        config.tenants.push(tenant(None));
+        assert!(matches!(
+            authenticate(&config, &headers),
+            Err(ApiError::Unauthorized(_))
+        ));
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("wrong"));
+        assert!(authenticate(&config, &headers).is_err());
+
+        headers.remove(API_KEY_HEADER);
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        let found = authenticate(&config, &headers).unwrap().unwrap();
+        assert_eq!(found.id, "legal");
+    }
+
+    #[test]
+    fn test_monthly_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let tenant = tenant(Some(2));
+        let usage = UsageTracker::new();
+        usage.admit(&tenant, dir.path(), 10).unwrap();
+        usage.record_failure(&tenant, dir.path());
+        usage.check_quota(&tenant, dir.path()).unwrap();
+        usage.admit(&tenant, dir.path(), 5).unwrap();
+        assert!(matches!(
+            usage.check_quota(&tenant, dir.path()),
+            Err(ApiError::TooManyRequests(_))
+        ));
+        assert!(matches!(
+            usage.admit(&tenant, dir.path(), 1),
+            Err(ApiError::TooManyRequests(_))
+        ));
+
+        // Usage survives a restart
+        let saved = load(&dir.path().join(USAGE_FILE));
+        assert_eq!(saved.conversions, 2);
+        assert_eq!(saved.failures, 1);
+        assert_eq!(saved.bytes, 15);
+        assert_eq!(saved.month, current_month());
+        assert_eq!(UsageTracker::new().usage(&tenant, dir.path()), saved);
+        assert!(UsageTracker::new().admit(&tenant, dir.path(), 1).is_err());
+    }
+
+    #[test]
+    fn test_usage_follows_moved_dir() {
+        let old = tempfile::tempdir().unwrap();
+        let new = tempfile::tempdir().unwrap();
+        let tenant = tenant(Some(1));
+        let usage = UsageTracker::new();
+        usage.admit(&tenant, old.path(), 10).unwrap();
+        assert!(usage.admit(&tenant, old.path(), 10).is_err());
+
+        // After a reload moves the tenant, its usage is that of the new
+        // directory, and is saved there
+        assert_eq!(usage.usage(&tenant, new.path()).conversions, 0);
+        usage.admit(&tenant, new.path(), 5).unwrap();
+        assert_eq!(load(&new.path().join(USAGE_FILE)).bytes, 5);
+        assert_eq!(load(&old.path().join(USAGE_FILE)).bytes, 10);
+    }
+
+    #[test]
+    fn test_unsaved_usage_fails_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let usage = UsageTracker::new();
+
+        assert!(matches!(
+            usage.admit(&tenant(Some(5)), &missing, 1),
+            Err(ApiError::InternalServerError(_))
+        ));
+        assert_eq!(usage.usage(&tenant(Some(5)), &missing).conversions, 0);
+
+        // Without a quota there is nothing to enforce
+        usage.admit(&tenant(None), &missing, 1).unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Conversion fidelity scoring.
+//!
+//! A fidelity fixture is an input document next to an `<input>.expected.json`
+//! file describing what parsing it should produce: its text, the cells of
+//! its tables, the number of images and the bounds of positioned text
+//! blocks. Parsing the input and comparing the result against the
+//! expectation yields a score between 0 and 1 per metric:
+//!
+//! - **text**: F1 overlap of the words of the whole document
+//! - **tables**: share of expected cells found with the same text at the
+//!   same row and column of the same table
+//! - **images**: how close the number of images is to the expected count
+//! - **layout**: mean intersection over union of the bounds of text blocks,
+//!   matched by page and text
+//!
+//! Metrics that do not apply (no tables or positioned blocks expected, no
+//! images on either side) are left out, and the overall score is the mean
+//! of the rest.
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use prism_core::document::{ContentBlock, Document, Rect, TableBlock};
+use prism_core::parser::{ParseContext, ParseOptions};
+use prism_parsers::registry::ParserRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the expectation file next to each input
+pub const EXPECTED_SUFFIX: &str = ".expected.json";
+
+/// Overall score a fixture must reach unless its expectation sets one
+pub const DEFAULT_MIN_SCORE: f64 = 0.95;
+
+/// What parsing a fixture should produce
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Expectation {
+    /// Plain text of the document
+    pub text: String,
+    /// Cell texts of every table, in document order
+    #[serde(default)]
+    pub tables: Vec<Vec<Vec<String>>>,
+    /// Number of placed images
+    #[serde(default)]
+    pub images: usize,
+    /// Positioned text blocks
+    #[serde(default)]
+    pub blocks: Vec<ExpectedBlock>,
+    /// Overall score below which the fixture fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+}
+
+/// A text block with its position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedBlock {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Text of the block
+    pub text: String,
+    /// Bounds on the page, in points
+    pub bounds: Rect,
+}
+
+/// Scores of one fixture, each between 0 and 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Scores {
+    /// Word overlap of the document text
+    pub text: f64,
+    /// Share of table cells reproduced
+    pub tables: Option<f64>,
+    /// Closeness of the image count
+    pub images: Option<f64>,
+    /// Mean overlap of text block bounds
+    pub layout: Option<f64>,
+}
+
+impl Scores {
+    /// Mean of the metrics that apply
+    #[must_use]
+    pub fn overall(&self) -> f64 {
+        let scores: Vec<f64> = [Some(self.text), self.tables, self.images, self.layout]
+            .into_iter()
+            .flatten()
+            .collect();
+        mean(&scores)
+    }
+}
+
+impl Expectation {
+    /// The expectation that `document` meets exactly, for recording a new
+    /// fixture or accepting a deliberate change in output
+    #[must_use]
+    pub fn from_document(document: &Document) -> Self {
+        let mut expectation = Self {
+            text: document.extract_text(),
+            ..Self::default()
+        };
+        for page in &document.pages {
+            visit(&page.content, &mut |block| match block {
+                ContentBlock::Table(table) => expectation.tables.push(cell_texts(table)),
+                ContentBlock::Image(_) => expectation.images += 1,
+                ContentBlock::Text(text) if has_area(text.bounds) => {
+                    expectation.blocks.push(ExpectedBlock {
+                        page: page.number,
+                        text: text.extract_text(),
+                        bounds: text.bounds,
+                    });
+                }
+                _ => {}
+            });
+        }
+        expectation
+    }
+
+    /// Score `document` against this expectation
+    #[must_use]
+    pub fn score(&self, document: &Document) -> Scores {
+        let actual = Self::from_document(document);
+        Scores {
+            text: text_similarity(&self.text, &actual.text),
+            tables: (!self.tables.is_empty()).then(|| table_accuracy(&self.tables, &actual.tables)),
+            images: (self.images > 0 || actual.images > 0)
+                .then(|| count_similarity(self.images, actual.images)),
+            layout: (!self.blocks.is_empty()).then(|| layout_iou(&self.blocks, &actual.blocks)),
+        }
+    }
+}
+
+/// F1 score of the words of `actual` against those of `expected`, ignoring
+/// case, punctuation and whitespace
+#[must_use]
+pub fn text_similarity(expected: &str, actual: &str) -> f64 {
+    let expected = word_counts(expected);
+    let actual = word_counts(actual);
+    let expected_total: usize = expected.values().sum();
+    let actual_total: usize = actual.values().sum();
+    if expected_total == 0 && actual_total == 0 {
+        return 1.0;
+    }
+    let common: usize = expected
+        .iter()
+        .map(|(word, count)| (*count).min(actual.get(word).copied().unwrap_or(0)))
+        .sum();
+    ratio(2 * common, expected_total + actual_total)
+}
+
+/// Share of expected cells whose text is found at the same position of the
+/// table with the same index
+#[must_use]
+pub fn table_accuracy(expected: &[Vec<Vec<String>>], actual: &[Vec<Vec<String>>]) -> f64 {
+    let mut total = 0;
+    let mut matched = 0;
+    for (index, table) in expected.iter().enumerate() {
+        for (row_index, row) in table.iter().enumerate() {
+            for (col_index, cell) in row.iter().enumerate() {
+                total += 1;
+                let found = actual
+                    .get(index)
+                    .and_then(|table| table.get(row_index))
+                    .and_then(|row| row.get(col_index));
+                if found.is_some_and(|found| normalize(found) == normalize(cell)) {
+                    matched += 1;
+                }
+            }
+        }
+    }
+    if total == 0 {
+        1.0
+    } else {
+        ratio(matched, total)
+    }
+}
+
+/// Mean intersection over union of each expected block with the unused
+/// actual block of the same page and text; unmatched blocks score 0
+#[must_use]
+pub fn layout_iou(expected: &[ExpectedBlock], actual: &[ExpectedBlock]) -> f64 {
+    let mut used = vec![false; actual.len()];
+    let scores: Vec<f64> = expected
+        .iter()
+        .map(|block| {
+            let text = normalize(&block.text);
+            let found = actual.iter().enumerate().position(|(index, candidate)| {
+                !used[index] && candidate.page == block.page && normalize(&candidate.text) == text
+            });
+            found.map_or(0.0, |index| {
+                used[index] = true;
+                iou(block.bounds, actual[index].bounds)
+            })
+        })
+        .collect();
+    mean(&scores)
+}
+
+/// Intersection over union of two rectangles
+#[must_use]
+pub fn iou(a: Rect, b: Rect) -> f64 {
+    let width = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+    let height = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+    let intersection = width.max(0.0) * height.max(0.0);
+    let union = a.width * a.height + b.width * b.height - intersection;
+    if union > 0.0 {
+        intersection / union
+    } else {
+        0.0
+    }
+}
+
+fn count_similarity(expected: usize, actual: usize) -> f64 {
+    ratio(expected.min(actual), expected.max(actual))
+}
+
+/// Score of one fixture
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureScore {
+    /// Input file name
+    pub fixture: String,
+    /// Name of the parser that handled it
+    pub parser: String,
+    /// Metric scores
+    pub scores: Scores,
+    /// Overall score
+    pub overall: f64,
+    /// Overall score required to pass
+    pub min_score: f64,
+}
+
+impl FixtureScore {
+    /// Whether the fixture reached its minimum score
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.overall >= self.min_score
+    }
+}
+
+/// Scores of a directory of fixtures
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FidelityReport {
+    /// Every scored fixture, by file name
+    pub fixtures: Vec<FixtureScore>,
+    /// Inputs without an expectation, which were not scored
+    pub missing_expectations: Vec<String>,
+}
+
+impl FidelityReport {
+    /// Fixtures below their minimum score
+    pub fn failures(&self) -> impl Iterator<Item = &FixtureScore> {
+        self.fixtures.iter().filter(|fixture| !fixture.passed())
+    }
+
+    /// Mean overall score per parser
+    #[must_use]
+    pub fn by_parser(&self) -> BTreeMap<&str, f64> {
+        let mut groups: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+        for fixture in &self.fixtures {
+            groups
+                .entry(fixture.parser.as_str())
+                .or_default()
+                .push(fixture.overall);
+        }
+        groups
+            .into_iter()
+            .map(|(parser, scores)| (parser, mean(&scores)))
+            .collect()
+    }
+
+    /// Render the report as a plain-text table
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let metric = |score: Option<f64>| score.map_or("-".to_string(), |s| format!("{s:.3}"));
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<32} {:<20} {:>6} {:>6} {:>6} {:>6} {:>7}",
+            "fixture", "parser", "text", "tables", "images", "layout", "overall"
+        );
+        for fixture in &self.fixtures {
+            let scores = &fixture.scores;
+            let _ = writeln!(
+                out,
+                "{:<32} {:<20} {:>6} {:>6} {:>6} {:>6} {:>7.3}{}",
+                fixture.fixture,
+                fixture.parser,
+                metric(Some(scores.text)),
+                metric(scores.tables),
+                metric(scores.images),
+                metric(scores.layout),
+                fixture.overall,
+                if fixture.passed() { "" } else { "  FAIL" }
+            );
+        }
+        let _ = writeln!(out, "Per parser");
+        for (parser, score) in self.by_parser() {
+            let _ = writeln!(out, "  {parser}: {score:.3}");
+        }
+        for fixture in &self.missing_expectations {
+            let _ = writeln!(out, "No expectation for {fixture}");
+        }
+        out
+    }
+}
+
+/// Parse every fixture in `dir` and score it against its expectation
+///
+/// With `bless` set, expectations are (re)written from the current output
+/// instead, keeping any `min_score` already recorded.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read, or a fixture cannot be
+/// detected, parsed or have its expectation read or written.
+pub async fn score_dir(
+    registry: &ParserRegistry,
+    dir: &Path,
+    bless: bool,
+) -> Result<FidelityReport> {
+    let mut inputs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && !path.to_string_lossy().ends_with(EXPECTED_SUFFIX))
+        .collect();
+    inputs.sort();
+
+    let mut report = FidelityReport::default();
+    for input in inputs {
+        let name = input
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let expected_path = dir.join(format!("{name}{EXPECTED_SUFFIX}"));
+        let (parser, document) = parse(registry, &input, &name).await?;
+
+        let previous: Option<Expectation> = match std::fs::read(&expected_path) {
+            Ok(data) => Some(
+                serde_json::from_slice(&data)
+                    .with_context(|| format!("Invalid {}", expected_path.display()))?,
+            ),
+            Err(_) => None,
+        };
+        if bless {
+            let expectation = Expectation {
+                min_score: previous.and_then(|previous| previous.min_score),
+                ..Expectation::from_document(&document)
+            };
+            std::fs::write(
+                &expected_path,
+                serde_json::to_string_pretty(&expectation)? + "\n",
+            )
+            .with_context(|| format!("Failed to write {}", expected_path.display()))?;
+            continue;
+        }
+        let Some(expectation) = previous else {
+            report.missing_expectations.push(name);
+            continue;
+        };
+
+        let scores = expectation.score(&document);
+        report.fixtures.push(FixtureScore {
+            fixture: name,
+            parser,
+            scores,
+            overall: scores.overall(),
+            min_score: expectation.min_score.unwrap_or(DEFAULT_MIN_SCORE),
+        });
+    }
+    Ok(report)
+}
+
+/// Detect and parse a fixture, returning the parser name and the document
+async fn parse(registry: &ParserRegistry, path: &Path, name: &str) -> Result<(String, Document)> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let detection = registry
+        .detect(&data, Some(name))
+        .with_context(|| format!("Unknown format: {name}"))?;
+    let parser = registry
+        .get_parser_for_data(&detection.format, &data)
+        .with_context(|| format!("No parser for {name} ({})", detection.format.name))?;
+    let context = ParseContext {
+        format: detection.format,
+        filename: Some(name.to_string()),
+        size: data.len(),
+        options: ParseOptions::default(),
+        progress: None,
+    };
+    let document = parser
+        .parse(Bytes::from(data), context)
+        .await
+        .with_context(|| format!("Failed to parse {name}"))?;
+    Ok((parser.metadata().name, document))
+}
+
+/// Call `f` for every block, including blocks nested in tables and
+/// containers (tables are visited before their cells)
+fn visit<'a>(blocks: &'a [ContentBlock], f: &mut impl FnMut(&'a ContentBlock)) {
+    for block in blocks {
+        f(block);
+        match block {
+            ContentBlock::Table(table) => {
+                for cell in table.rows.iter().flat_map(|row| &row.cells) {
+                    visit(&cell.content, f);
+                }
+            }
+            ContentBlock::List(list) => {
+                for item in &list.items {
+                    visit(&item.content, f);
+                }
+            }
+            ContentBlock::Container(container) => visit(&container.children, f),
+            _ => {}
+        }
+    }
+}
+
+fn cell_texts(table: &TableBlock) -> Vec<Vec<String>> {
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .map(|cell| normalize(&cell.extract_text()))
+                .collect()
+        })
+        .collect()
+}
+
+fn has_area(bounds: Rect) -> bool {
+    bounds.width > 0.0 && bounds.height > 0.0
+}
+
+fn word_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(scores: &[f64]) -> f64 {
+    if scores.is_empty() {
+        1.0
+    } else {
+        scores.iter().sum::<f64>() / scores.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| (*cell).to_string()).collect())
+            .collect()
+    }
+
+    fn block(page: u32, text: &str, x: f64) -> ExpectedBlock {
+        ExpectedBlock {
+            page,
+            text: text.to_string(),
+            bounds: Rect::new(x, 0.0, 10.0, 10.0),
+        }
+    }
+
+    #[test]
+    fn test_text_similarity() {
+        assert!((text_similarity("The quick fox", "the  quick, fox!") - 1.0).abs() < 1e-9);
+        assert!((text_similarity("a b c d", "a b") - 2.0 / 3.0).abs() < 1e-9);
+        assert!(text_similarity("a b", "").abs() < 1e-9);
+        assert!((text_similarity("", " ") - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_table_accuracy() {
+        let expected = vec![cells(&[&["A", "B"], &["1", "2"]])];
+        let actual = vec![cells(&[&["A", "B "], &["1", "3"]])];
+        assert!((table_accuracy(&expected, &actual) - 0.75).abs() < 1e-9);
+        assert!(table_accuracy(&expected, &[]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_layout_iou() {
+        assert!(
+            (iou(
+                Rect::new(0.0, 0.0, 10.0, 10.0),
+                Rect::new(5.0, 0.0, 10.0, 10.0)
+            ) - 1.0 / 3.0)
+                .abs()
+                < 1e-9
+        );
+        assert!(iou(Rect::new(0.0, 0.0, 1.0, 1.0), Rect::new(5.0, 5.0, 1.0, 1.0)).abs() < 1e-9);
+
+        let expected = [block(1, "Title", 0.0), block(1, "Body", 0.0)];
+        let actual = [block(1, "Body", 0.0), block(2, "Title", 0.0)];
+        assert!((layout_iou(&expected, &actual) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_score() {
+        let expectation = Expectation {
+            text: "Hello world".to_string(),
+            images: 2,
+            ..Expectation::default()
+        };
+        let mut document = Document::new();
+        let mut page = prism_core::document::Page::new(1, prism_core::document::Dimensions::LETTER);
+        let mut text = prism_core::document::TextBlock::new(Rect::default());
+        text.add_run(prism_core::document::TextRun::new("Hello world"));
+        page.content.push(ContentBlock::Text(text));
+        document.pages.push(page);
+
+        let scores = expectation.score(&document);
+        assert!((scores.text - 1.0).abs() < 1e-9);
+        assert_eq!(scores.images, Some(0.0));
+        assert_eq!(scores.tables, None);
+        assert_eq!(scores.layout, None);
+        assert!((scores.overall() - 0.5).abs() < 1e-9);
+    }
+}
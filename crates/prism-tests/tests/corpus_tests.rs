@@ -55,6 +55,7 @@ async fn test_corpus_smoke() {
                             filename: Some(filename.clone()),
                             size: data.len(),
                             options: ParseOptions::default(),
+                            progress: None,
                         };
 
                         match parser.parse(bytes::Bytes::from(data), context).await {
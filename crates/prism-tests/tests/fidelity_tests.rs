@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Fidelity scores of the fixtures in `fidelity/`.
+//!
+//! Ignored by default since it is a report rather than a unit test; run it
+//! with `cargo test -p prism-tests -- --ignored fidelity --nocapture`.
+//! Set `PRISM_FIDELITY_DIR` to score another directory of fixtures and
+//! `PRISM_FIDELITY_BLESS=1` to record the current output as the expectation.
+
+use prism_parsers::registry::ParserRegistry;
+use prism_tests::fidelity;
+use std::path::PathBuf;
+
+#[tokio::test]
+#[ignore = "fidelity report; run with --ignored"]
+async fn fidelity() {
+    let dir = std::env::var_os("PRISM_FIDELITY_DIR").map_or_else(
+        || PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fidelity"),
+        PathBuf::from,
+    );
+    let bless = std::env::var_os("PRISM_FIDELITY_BLESS").is_some();
+
+    let registry = ParserRegistry::with_default_parsers();
+    let report = fidelity::score_dir(&registry, &dir, bless).await.unwrap();
+    println!("{}", report.render_text());
+
+    let failures: Vec<_> = report.failures().map(|f| f.fixture.as_str()).collect();
+    assert!(failures.is_empty(), "below minimum score: {failures:?}");
+    assert!(
+        report.missing_expectations.is_empty(),
+        "no expectation (run with PRISM_FIDELITY_BLESS=1): {:?}",
+        report.missing_expectations
+    );
+}
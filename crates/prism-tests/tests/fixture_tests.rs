@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Parse generated fixtures and check that their features survive.
+
+use prism_cli::fixtures::{self, FixtureKind, FixtureSpec, UNICODE_SAMPLE};
+use prism_core::metadata::MetadataValue;
+use prism_core::parser::{ParseContext, ParseOptions};
+use prism_core::Document;
+use prism_parsers::registry::ParserRegistry;
+use prism_render::docx::DocxRenderer;
+use prism_render::xlsx::XlsxRenderer;
+
+async fn parse_fixture(kind: FixtureKind, spec: &FixtureSpec) -> Document {
+    let data = fixtures::generate(kind, spec).unwrap();
+    parse(data, format!("fixture.{}", kind.extension())).await
+}
+
+async fn parse(data: Vec<u8>, filename: String) -> Document {
+    let detection = prism_core::format::detect_format(&data, Some(&filename)).unwrap();
+    let registry = ParserRegistry::with_default_parsers();
+    let parser = registry
+        .get_parser_for_data(&detection.format, &data)
+        .unwrap_or_else(|| panic!("no parser for {filename}"));
+
+    let context = ParseContext {
+        format: detection.format,
+        filename: Some(filename),
+        size: data.len(),
+        options: ParseOptions::default(),
+        progress: None,
+    };
+    parser
+        .parse(bytes::Bytes::from(data), context)
+        .await
+        .unwrap()
+}
+
+fn full_spec(pages: usize) -> FixtureSpec {
+    FixtureSpec {
+        pages,
+        merged_cells: true,
+        images: 2,
+        unicode: true,
+    }
+}
+
+#[tokio::test]
+async fn test_docx_fixture() {
+    let doc = parse_fixture(FixtureKind::Docx, &full_spec(3)).await;
+    let text = doc.extract_text();
+    assert!(text.contains("Page 3: The quick brown fox"));
+    assert!(text.contains(UNICODE_SAMPLE));
+    assert!(text.contains("Merged"));
+}
+
+#[tokio::test]
+async fn test_docx_round_trip() {
+    let doc = parse_fixture(FixtureKind::Docx, &full_spec(3)).await;
+    let docx = DocxRenderer::new().render_docx(&doc).unwrap();
+    let round_trip = parse(docx, "round_trip.docx".to_string()).await;
+    let text = round_trip.extract_text();
+    assert!(text.contains("Page 3: The quick brown fox"));
+    assert!(text.contains(UNICODE_SAMPLE));
+    assert!(text.contains("Merged"));
+    assert_eq!(
+        round_trip.resources.images.len(),
+        doc.resources.images.len()
+    );
+}
+
+#[tokio::test]
+async fn test_xlsx_fixture() {
+    let doc = parse_fixture(FixtureKind::Xlsx, &full_spec(4)).await;
+    assert_eq!(doc.page_count(), 4);
+    assert!(doc.extract_text().contains(UNICODE_SAMPLE));
+    assert_eq!(doc.structure.sections.len(), 4);
+    assert!(doc
+        .structure
+        .sections
+        .iter()
+        .all(|section| section.kind == prism_core::document::SectionKind::Sheet));
+}
+
+#[tokio::test]
+async fn test_xlsx_round_trip() {
+    let doc = parse_fixture(FixtureKind::Xlsx, &full_spec(3)).await;
+    let xlsx = XlsxRenderer::new().render_xlsx(&doc).unwrap();
+    let round_trip = parse(xlsx, "round_trip.xlsx".to_string()).await;
+    assert_eq!(round_trip.page_count(), 3);
+    assert_eq!(round_trip.extract_text(), doc.extract_text());
+    let labels = |doc: &Document| -> Vec<Option<String>> {
+        doc.pages
+            .iter()
+            .map(|page| page.metadata.label.clone())
+            .collect()
+    };
+    assert_eq!(labels(&round_trip), labels(&doc));
+}
+
+#[tokio::test]
+async fn test_pptx_fixture() {
+    let doc = parse_fixture(FixtureKind::Pptx, &full_spec(5)).await;
+    assert_eq!(doc.page_count(), 5);
+    assert_eq!(doc.resources.images.len(), 2);
+    assert!(doc.extract_text().contains(UNICODE_SAMPLE));
+}
+
+#[tokio::test]
+async fn test_pdf_fixture() {
+    let doc = parse_fixture(FixtureKind::Pdf, &full_spec(6)).await;
+    assert!(matches!(
+        doc.metadata.get_custom("page_count"),
+        Some(MetadataValue::Integer(6))
+    ));
+}
+
+#[tokio::test]
+async fn test_eml_fixture() {
+    let doc = parse_fixture(FixtureKind::Eml, &full_spec(1)).await;
+    assert_eq!(doc.metadata.title.as_deref(), Some(UNICODE_SAMPLE));
+    assert!(doc.extract_text().contains("Page 1: The quick brown fox"));
+}
+
+#[tokio::test]
+async fn test_truncated_fixture_error() {
+    use prism_core::error::{Error, ErrorCode};
+    use prism_core::parser::Parser;
+
+    let data = fixtures::generate(FixtureKind::Docx, &full_spec(1)).unwrap();
+    let truncated = data[..data.len() / 2].to_vec();
+    let context = ParseContext {
+        format: prism_core::format::Format::docx(),
+        filename: Some("truncated.docx".to_string()),
+        size: truncated.len(),
+        options: ParseOptions::default(),
+        progress: None,
+    };
+    let error = prism_parsers::DocxParser::new()
+        .parse(bytes::Bytes::from(truncated), context)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(&error, Error::CorruptFile { format: Some(f), .. } if f == "DOCX"));
+    assert_eq!(error.code(), ErrorCode::CorruptFile);
+    assert_eq!(error.code().exit_code(), 3);
+}
+
+#[tokio::test]
+async fn test_lenient_truncated_fixture() {
+    use prism_core::diagnostics::Severity;
+    use prism_core::parser::Parser;
+
+    let data = fixtures::generate(FixtureKind::Pptx, &full_spec(3)).unwrap();
+    // Drop the central directory; every entry is still intact
+    let end = data
+        .windows(4)
+        .position(|window| window == b"PK\x01\x02")
+        .unwrap();
+    let truncated = data[..end].to_vec();
+    let context = ParseContext {
+        format: prism_core::format::Format::pptx(),
+        filename: Some("truncated.pptx".to_string()),
+        size: truncated.len(),
+        options: ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        },
+        progress: None,
+    };
+    let doc = prism_parsers::PptxParser::new()
+        .parse(bytes::Bytes::from(truncated), context)
+        .await
+        .unwrap();
+
+    assert_eq!(doc.page_count(), 3);
+    assert!(doc.extract_text().contains(UNICODE_SAMPLE));
+    assert_eq!(doc.diagnostics.len(), 1);
+    assert_eq!(doc.diagnostics[0].severity, Severity::Warning);
+}
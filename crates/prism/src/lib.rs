@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+//! # Prism
+//!
+//! Document processing SDK: detect the format of a file, parse it into the
+//! Unified Document Model (UDM) and render it to HTML.
+//!
+//! This crate is the supported entry point for Rust applications. It
+//! re-exports a curated subset of the `prism-core`, `prism-parsers` and
+//! `prism-render` crates under stable paths; the layout of those crates
+//! may change between releases, the paths here only with a major version.
+//!
+//! ## Usage
+//!
+//! ```rust,no_run
+//! use prism::{ParseOptions, Prism};
+//!
+//! # async fn example() -> prism::Result<()> {
+//! let prism = Prism::new().with_parse_options(ParseOptions {
+//!     lenient: true,
+//!     ..ParseOptions::default()
+//! });
+//!
+//! let document = prism.parse_file("report.docx").await?;
+//! println!("{} pages", document.page_count());
+//!
+//! let html = prism.render_html(&document).await?;
+//! std::fs::write("report.html", html)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+
+use bytes::Bytes;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use prism_core::pipeline::PipelineConfig;
+use prism_core::render::RenderContext;
+
+pub use prism_core::document::Document;
+pub use prism_core::error::{Error, ErrorCode, Result};
+pub use prism_core::format::{DetectionResult, Format};
+pub use prism_core::parser::ParseOptions;
+pub use prism_core::render::RenderOptions;
+
+/// The document model
+pub mod document {
+    pub use prism_core::diagnostics::{Diagnostic, Severity};
+    pub use prism_core::document::*;
+    pub use prism_core::metadata::Metadata;
+    pub use prism_core::statistics::DocumentStatistics;
+}
+
+/// Format detection
+pub mod detect {
+    pub use prism_core::format::{
+        detect_format, detect_format_all, DetectionMethod, DetectionResult, Detector, Format,
+        FormatFamily, FormatRegistry, FormatSignature,
+    };
+}
+
+/// Parsing and custom parsers
+pub mod parse {
+    pub use prism_core::parser::{
+        HiddenContent, IdStrategy, LogFilter, LogLevel, ParseContext, ParseOptions, Parser,
+        ParserFeature, ParserMetadata, RevisionMode,
+    };
+    pub use prism_parsers::email::thread::{self, thread_documents, Threads};
+    pub use prism_parsers::security::{self, ActiveContent, ActiveContentKind, Disarm};
+    pub use prism_parsers::signatures::{self, DigitalSignature, SignatureKind, SignatureStatus};
+    pub use prism_parsers::{ArchiveEntry, ArchiveParser, ParserRegistry, ParserSupport};
+}
+
+/// Rendering and custom renderers
+pub mod render {
+    pub use prism_core::render::{
+        BatesNumbering, ColorMode, ContentFilter, ContentKind, Imposition, PageFit,
+        PageNormalization, PageRange, Pagination, RenderContext, RenderOptions, Renderer,
+        StampPages, StampPosition, Watermark, WatermarkContent,
+    };
+    pub use prism_render::docx::{DocxConfig, DocxRenderer};
+    pub use prism_render::html::{FormRendering, HtmlConfig, HtmlLayout, HtmlRenderer, SheetArea};
+    pub use prism_render::jsonl::{JsonlConfig, JsonlRenderer, JsonlUnit};
+    pub use prism_render::markdown::MarkdownRenderer;
+    pub use prism_render::xlsx::{XlsxConfig, XlsxRenderer};
+}
+
+/// Detect, parse, process and render in one call
+pub mod pipeline {
+    pub use prism_core::pipeline::{
+        ErrorPolicy, Pipeline, PipelineConfig, PipelineHook, PipelineOutput, Stage, StageError,
+    };
+    pub use prism_core::processor::Processor;
+}
+
+/// Splitting documents into chunks for retrieval pipelines
+pub mod chunk {
+    pub use prism_core::chunk::{chunk, Chunk, ChunkOptions, ChunkRegion, ChunkSize};
+}
+
+/// Path queries over parsed documents
+pub mod query {
+    pub use prism_core::query::{query, Query};
+}
+
+/// Prism SDK version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Entry point to detection, parsing and rendering
+///
+/// Holds the parsers and the options to apply; cheap to clone and share
+/// across tasks.
+#[derive(Clone)]
+pub struct Prism {
+    parsers: parse::ParserRegistry,
+    config: PipelineConfig,
+}
+
+impl fmt::Debug for Prism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prism")
+            .field("parsers", &self.parsers.count())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl Default for Prism {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Prism {
+    /// Prism with every built-in parser and default options
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_parsers(parse::ParserRegistry::with_default_parsers())
+    }
+
+    /// Prism with a custom set of parsers
+    #[must_use]
+    pub fn with_parsers(parsers: parse::ParserRegistry) -> Self {
+        Self {
+            parsers,
+            config: PipelineConfig::default(),
+        }
+    }
+
+    /// Add a parser; it takes precedence over the built-in parser for its
+    /// format
+    #[must_use]
+    pub fn with_parser(mut self, parser: Arc<dyn parse::Parser>) -> Self {
+        self.parsers.register(parser);
+        self
+    }
+
+    /// Set the parse options
+    #[must_use]
+    pub fn with_parse_options(mut self, options: ParseOptions) -> Self {
+        self.config.parse = options;
+        self
+    }
+
+    /// Set the render options
+    #[must_use]
+    pub fn with_render_options(mut self, options: RenderOptions) -> Self {
+        self.config.render = options;
+        self
+    }
+
+    /// Detect the format of `data`
+    ///
+    /// `filename` is used as a hint when the content is ambiguous.
+    #[must_use]
+    pub fn detect(&self, data: &[u8], filename: Option<&str>) -> Option<DetectionResult> {
+        self.parsers.detect(data, filename)
+    }
+
+    /// Detect and parse a document
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DetectionFailed`] if the format cannot be detected,
+    /// [`Error::UnsupportedFormat`] if no parser accepts it, and otherwise
+    /// the parser's error.
+    pub async fn parse(&self, data: impl Into<Bytes>, filename: Option<&str>) -> Result<Document> {
+        let output = self.pipeline().run(data.into(), filename).await?;
+        Ok(output.document)
+    }
+
+    /// Read, detect and parse the file at `path`
+    ///
+    /// # Errors
+    ///
+    /// As [`parse`](Self::parse), and [`Error::Io`] if the file cannot be
+    /// read.
+    pub async fn parse_file(&self, path: impl AsRef<Path>) -> Result<Document> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let filename = path.file_name().and_then(|name| name.to_str());
+        self.parse(data, filename).await
+    }
+
+    /// Render a document as HTML
+    ///
+    /// # Errors
+    ///
+    /// Returns the renderer's error.
+    pub async fn render_html(&self, document: &Document) -> Result<Bytes> {
+        let context = RenderContext {
+            options: self.config.render.clone(),
+            filename: None,
+            progress: None,
+        };
+        render::Renderer::render(&render::HtmlRenderer::new(), document, context).await
+    }
+
+    /// A pipeline with these parsers and options, for adding processors,
+    /// a renderer or instrumentation hooks
+    #[must_use]
+    pub fn pipeline(&self) -> pipeline::Pipeline {
+        pipeline::Pipeline::new(Arc::new(self.parsers.clone())).with_config(self.config.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_and_render() {
+        let prism = Prism::new();
+        let detected = prism.detect(b"%PDF-1.7", None).unwrap();
+        assert_eq!(detected.format, Format::pdf());
+
+        let document = prism
+            .parse(&b"Hello from Prism"[..], Some("hello.txt"))
+            .await
+            .unwrap();
+        assert!(document.extract_text().contains("Hello from Prism"));
+
+        let html = prism.render_html(&document).await.unwrap();
+        assert!(String::from_utf8_lossy(&html).contains("Hello from Prism"));
+
+        let result = prism.parse(&b"\x00\x01"[..], None).await;
+        assert!(matches!(result, Err(Error::DetectionFailed(_))));
+    }
+}